@@ -1,16 +1,31 @@
 use anyhow::Result;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
 use std::time::Instant;
 
 use crate::commands::qemu_system::NetworkCapabilities;
 use crate::config::Config;
-use crate::hardware::{MultiGpuPassthroughStatus, PciDevice, SingleGpuConfig, UsbDevice};
-use crate::metadata::{AsciiArtStore, HierarchyConfig, MetadataStore, OsInfo, QemuProfileStore, SettingsHelpStore, SharedFoldersHelpStore};
+use crate::hardware::{HostResources, MultiGpuPassthroughStatus, PassthroughCheck, PciDevice, SingleGpuConfig, UsbDevice};
+use crate::metadata::iso_checksums::IsoChecksums;
+use crate::metadata::{AsciiArtStore, EventStore, HierarchyConfig, MetadataStore, OnThisDay, OsInfo, QemuProfile, QemuProfileStore, SettingsHelpStore, SharedFoldersHelpStore};
 use crate::ui::widgets::build_visual_order;
-use crate::vm::{discover_vms, BootMode, DiscoveredVm, LaunchOptions, QemuProcess, SharedFolder, Snapshot};
+use crate::vm::{discover_vms, BootMode, DiscoveredVm, LaunchOptions, LaunchOverrides, QemuProcess, SharedFolder, SharedFolderMethod, Snapshot, UsbPassthrough};
 use crate::vm::qemu_config::{PortForward, PortProtocol};
+use crate::vm::cloud_init::CloudInitConfig;
+use crate::vm::unattended::UnattendedConfig;
+use crate::vm::disk_ops::{find_interrupted_operations, InterruptedDiskOp};
+use crate::vm::migration::{check_library, PendingMigration};
+use crate::vm::guest_agent::GuestInfo;
+use crate::vm::snapshot_meta::{SnapshotContext, SnapshotMeta};
+use crate::vm::qmp::AttachedUsb;
+use crate::vm::sound_blaster::Sb16Settings;
+use crate::vm::display_preset::DisplayPreset;
+use crate::vm::batch::{BatchOperation, BatchReport};
+use crate::vm::notes::{self, Notes};
+use crate::vm::launch_history::{self, LaunchHistory};
 
 /// Application screens/views
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -24,19 +39,96 @@ pub enum Screen {
     Configuration,
     /// Raw launch script view
     RawScript,
-    /// Detailed info (history, blurbs) - planned feature
-    #[allow(dead_code)]
+    /// Detailed info (history, blurbs, notes and journal)
     DetailedInfo,
+    /// Notes editor for the selected VM
+    Notes,
+    /// Post-install checklist of the selected VM
+    Checklist,
+    /// Problems found in the selected VM and their fixes
+    Doctor,
+    /// Files in VM folders nothing refers to
+    OrphanFiles,
+    /// Disk usage of each VM and the whole library
+    Storage,
+    /// Output of the selected VM's launches
+    LogViewer,
+    /// CPU, memory, disk and network use of a running VM
+    Monitor,
+    /// The selected VM's boot screenshots
+    Screenshots,
+    /// VMs ranked by how much they are used
+    Statistics,
     /// Snapshot management
     Snapshots,
+    /// Name, description and context of a new snapshot
+    CreateSnapshot,
+    /// Size, format and bus of a blank extra disk
+    AddDataDisk,
+    /// Install images in the ISO library, identified by checksum
+    IsoLibrary,
     /// Boot options
     BootOptions,
+    /// Memory, CPUs, display and QEMU arguments for one launch
+    LaunchOptions,
+    /// Environment variables and wrapper command for the VM's launches
+    LaunchEnvironment,
+    /// Which QEMU build runs the VM
+    QemuBinary,
+    /// Host keyboards, mice and controllers passed through evdev
+    InputPassthrough,
+    /// Gamepad passthrough, or the emulated game port joystick
+    GameControllers,
     /// Display options
     DisplayOptions,
+    /// CPU era preset selection
+    CpuEra,
+    /// CPU model and flag selection
+    CpuModel,
+    /// Machine type selection
+    MachineType,
+    /// Per-disk cache, AIO, discard and throttling editor
+    DiskIo,
+    /// UUID and MAC address view
+    Identity,
+    /// Files on the mounted guest disk, with copy to and from the host
+    GuestFiles,
+    /// Guided conversion of a VM's disk to another image format
+    ConvertDisk,
+    /// The images a VM's disk is layered on, with rebase/commit/flatten
+    BackingChain,
+    /// Host hard drives and USB sticks attached to the VM
+    PhysicalDisks,
+    /// Mac ROM and OpenBIOS settings
+    MacFirmware,
+    /// Moving the VM to another library host
+    HostMigration,
+    /// systemd user service for the VM
+    SystemdUnit,
+    /// Full-screen exhibit cycling through the VMs
+    Slideshow,
+    /// Multiple-choice trivia about the library
+    Quiz,
+    /// Editing the OS info shown for the VM
+    MetadataEditor,
+    /// Creating, editing and deleting the user's QEMU profiles
+    ProfileEditor,
+    /// Host audio backend selection
+    AudioBackend,
+    /// Serial MIDI synth selection
+    MidiOutput,
+    /// Sound Blaster 16 port/IRQ/DMA editor
+    SoundBlaster,
+    /// Resolution, scaling and full-screen editor
+    DisplayPreset,
+    /// virtio-gpu 3D mode selection
+    GpuAccel,
     /// USB device selection
     UsbDevices,
     /// PCI device selection for passthrough
     PciPassthrough,
+    /// Host setup check for the selected PCI devices
+    PciHostCheck,
     /// Shared folder management (virtio-9p)
     SharedFolders,
     /// Single GPU passthrough setup
@@ -45,6 +137,8 @@ pub enum Screen {
     SingleGpuInstructions,
     /// Multi-GPU passthrough setup (Looking Glass)
     MultiGpuSetup,
+    /// Looking Glass host/guest/client setup steps
+    LookingGlassInstructions,
     /// Confirmation dialog
     Confirm(ConfirmAction),
     /// Help screen
@@ -61,26 +155,34 @@ pub enum Screen {
     CreateWizard,
     /// Custom OS metadata entry (secondary form during wizard)
     CreateWizardCustomOs,
-    /// ISO download progress screen (planned feature)
-    #[allow(dead_code)]
+    /// ISO download progress (wizard's ISO step)
     CreateWizardDownload,
     /// Network settings (backend + port forwarding)
     NetworkSettings,
+    /// Bridge or TAP setup consent (privileged commands)
+    BridgeSetup,
     /// Application settings
     Settings,
+    /// Outcome of the last batch operation
+    BatchReport,
 }
 
 /// Context for text input dialogs
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TextInputContext {
-    SnapshotName,
     RenameVm,
+    ArchivalDate,
+    JournalEntry,
 }
 
 /// Actions that need confirmation
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ConfirmAction {
     LaunchVm,
+    /// The VM is already running: offer its display instead of a second copy
+    ConnectToInstance,
+    /// Another machine sharing the library holds the VM: break its lock
+    TakeOverVm,
     ResetVm,
     DeleteVm,
     DeleteSnapshot(String),
@@ -88,6 +190,17 @@ pub enum ConfirmAction {
     DiscardScriptChanges,
     StopVm,
     ForceStopVm,
+    /// Save the running VM's state to disk and stop it
+    SuspendVm,
+    /// Boot fresh, throwing a suspended VM's saved state away
+    DiscardSavedState,
+    CompactDisk,
+    RecoverDiskOperations,
+    /// A Create VM wizard was left unfinished: resume it
+    ResumeWizardDraft,
+    MigrateLibrary,
+    LeaveArchivalMode,
+    BatchSnapshot,
 }
 
 /// Input mode for text entry
@@ -112,6 +225,8 @@ pub enum DiskAction {
     #[default]
     Copy,
     Move,
+    /// Write a qcow2 copy of a vmdk/vhd/raw image (the original is kept)
+    Convert,
 }
 
 /// Steps in the VM creation wizard
@@ -181,6 +296,8 @@ impl WizardStep {
 pub struct WizardQemuConfig {
     /// QEMU emulator command
     pub emulator: String,
+    /// Emulator the VM runs under (QEMU unless the profile names another)
+    pub backend: crate::vm::backend::Backend,
     /// RAM in megabytes
     pub memory_mb: u32,
     /// CPU cores
@@ -191,8 +308,16 @@ pub struct WizardQemuConfig {
     pub machine: Option<String>,
     /// Graphics adapter
     pub vga: String,
+    /// Preferred guest resolution (e.g., "800x600"), passed via EDID for std/qxl
+    pub resolution: Option<String>,
     /// Audio devices
     pub audio: Vec<String>,
+    /// Host audio backend for `-audiodev` (pipewire, pa, jack, alsa, none)
+    pub audio_backend: String,
+    /// Host synth for serial MIDI output (none, fluidsynth, mt32)
+    pub midi_synth: String,
+    /// Sound Blaster 16 port/IRQ/DMA (used when audio includes sb16)
+    pub sb16: Sb16Settings,
     /// Network adapter model
     pub network_model: String,
     /// Disk interface
@@ -203,12 +328,28 @@ pub struct WizardQemuConfig {
     pub gl_acceleration: bool,
     /// UEFI boot mode
     pub uefi: bool,
+    /// Secure Boot firmware (UEFI only)
+    pub secure_boot: bool,
+    /// Non-UEFI firmware for ARM/RISC-V boards ("u-boot")
+    pub firmware: Option<String>,
+    /// Direct kernel boot: kernel, initrd and device tree in the VM folder
+    pub kernel: Option<String>,
+    pub initrd: Option<String>,
+    pub dtb: Option<String>,
+    /// Kernel command line for direct kernel boot
+    pub kernel_append: Option<String>,
+    /// OpenBIOS `-prom-env` variables (PowerPC Macs)
+    pub prom_env: Vec<String>,
     /// TPM emulation
     pub tpm: bool,
     /// RTC uses local time (for Windows)
     pub rtc_localtime: bool,
     /// USB tablet for mouse
     pub usb_tablet: bool,
+    /// USB controller (none, uhci, ehci, xhci)
+    pub usb_controller: String,
+    /// QEMU guest agent channel (virtio-serial)
+    pub guest_agent: bool,
     /// Display output
     pub display: String,
     /// Network backend
@@ -217,6 +358,8 @@ pub struct WizardQemuConfig {
     pub port_forwards: Vec<PortForward>,
     /// Bridge name when backend is "bridge"
     pub bridge_name: Option<String>,
+    /// Tap device when backend is "tap"
+    pub tap_name: Option<String>,
     /// Additional QEMU arguments
     pub extra_args: Vec<String>,
 }
@@ -225,24 +368,39 @@ impl Default for WizardQemuConfig {
     fn default() -> Self {
         Self {
             emulator: "qemu-system-x86_64".to_string(),
+            backend: Default::default(),
             memory_mb: 2048,
             cpu_cores: 2,
             cpu_model: Some("host".to_string()),
             machine: Some("q35".to_string()),
             vga: "std".to_string(),
+            resolution: None,
             audio: vec!["intel-hda".to_string(), "hda-duplex".to_string()],
+            audio_backend: "pa".to_string(),
+            midi_synth: "none".to_string(),
+            sb16: Sb16Settings::default(),
             network_model: "e1000".to_string(),
             disk_interface: "ide".to_string(),
             enable_kvm: true,
             gl_acceleration: false,
             uefi: false,
+            secure_boot: false,
+            firmware: None,
+            kernel: None,
+            initrd: None,
+            dtb: None,
+            kernel_append: None,
+            prom_env: Vec::new(),
             tpm: false,
             rtc_localtime: false,
             usb_tablet: true,
+            usb_controller: "xhci".to_string(),
+            guest_agent: false,
             display: "gtk".to_string(),
             network_backend: "user".to_string(),
             port_forwards: Vec::new(),
             bridge_name: None,
+            tap_name: None,
             extra_args: Vec::new(),
         }
     }
@@ -256,29 +414,57 @@ impl WizardQemuConfig {
             arg.contains("virtio-vga-gl") || arg.contains("gl=on")
         );
 
+        // Guests with virtio drivers can run qemu-guest-agent
+        let guest_agent = profile.disk_interface == "virtio" || profile.network_model.contains("virtio");
+
         Self {
             emulator: profile.emulator.clone(),
+            backend: profile
+                .backend
+                .as_deref()
+                .and_then(crate::vm::backend::Backend::from_id)
+                .unwrap_or_default(),
             memory_mb: profile.memory_mb,
             cpu_cores: profile.cpu_cores,
             cpu_model: profile.cpu_model.clone(),
             machine: profile.machine.clone(),
             vga: profile.vga.clone(),
+            resolution: profile.resolution.clone(),
             audio: profile.audio.clone(),
+            audio_backend: "pa".to_string(),
+            midi_synth: "none".to_string(),
+            sb16: Sb16Settings::default(),
             network_model: profile.network_model.clone(),
             disk_interface: profile.disk_interface.clone(),
             enable_kvm: profile.enable_kvm,
             gl_acceleration,
             uefi: profile.uefi,
+            secure_boot: profile.wants_secure_boot(),
+            firmware: profile.firmware.clone(),
+            kernel: profile.kernel.clone(),
+            initrd: profile.initrd.clone(),
+            dtb: profile.dtb.clone(),
+            kernel_append: profile.kernel_append.clone(),
+            prom_env: profile.prom_env.clone(),
             tpm: profile.tpm,
             rtc_localtime: profile.rtc_localtime,
             usb_tablet: profile.usb_tablet,
+            usb_controller: profile.usb_controller(),
+            guest_agent,
             display: profile.display.clone(),
             network_backend: "user".to_string(),
             port_forwards: Vec::new(),
             bridge_name: None,
+            tap_name: None,
             extra_args: profile.extra_args.clone(),
         }
     }
+
+    /// Scale memory and CPU suggestions down to what the host can spare
+    pub fn fit_to_host(&mut self, host: &HostResources) {
+        self.memory_mb = host.suggest_memory_mb(self.memory_mb);
+        self.cpu_cores = host.suggest_cpu_cores(self.cpu_cores);
+    }
 }
 
 /// Custom OS entry for when user selects "Other"
@@ -328,16 +514,32 @@ pub struct CreateWizardState {
     pub iso_path: Option<PathBuf>,
     /// Whether an ISO download is in progress
     pub iso_downloading: bool,
-    /// ISO download progress (0.0 - 1.0)
+    /// ISO download progress (0.0 - 1.0; 0 while the size is unknown)
     pub iso_download_progress: f32,
+    /// Where the download is coming from and how far it has got
+    pub iso_download_status: String,
+    /// Set to stop the running download
+    pub iso_download_cancel: Option<Arc<AtomicBool>>,
     /// Disk size in gigabytes (for new disk creation)
     pub disk_size_gb: u32,
+    /// qcow2 options for the new disk
+    pub disk_tuning: crate::commands::qemu_img::Qcow2Options,
     /// Whether to use an existing disk instead of creating a new one
     pub use_existing_disk: bool,
     /// Path to an existing disk to use
     pub existing_disk_path: Option<PathBuf>,
+    /// NBD/iSCSI URL of an existing remote disk (instead of a local path)
+    pub remote_disk_url: Option<String>,
     /// Action to take with existing disk (copy or move)
     pub existing_disk_action: DiskAction,
+    /// OS found on the existing disk by virt-inspector
+    pub detected_os: Option<crate::vm::os_fingerprint::InspectedOs>,
+    /// Whether the existing disk is being inspected in the background
+    pub inspecting_disk: bool,
+    /// cloud-init seed settings (cloud images only; None = no seed)
+    pub cloud_init: Option<CloudInitConfig>,
+    /// Kickstart/preseed settings (new disk + supported Linux ISO; None = manual install)
+    pub unattended: Option<UnattendedConfig>,
     /// QEMU configuration
     pub qemu_config: WizardQemuConfig,
     /// Auto-launch VM after creation
@@ -378,6 +580,14 @@ pub enum WizardField {
     CustomOsPublisher,
     CustomOsReleaseDate,
     CustomOsShortBlurb,
+    CloudInitHostname,
+    CloudInitUsername,
+    CloudInitPassword,
+    CloudInitSshKey,
+    UnattendedHostname,
+    UnattendedUsername,
+    UnattendedPassword,
+    RemoteDiskUrl,
 }
 
 impl Default for CreateWizardState {
@@ -391,10 +601,18 @@ impl Default for CreateWizardState {
             iso_path: None,
             iso_downloading: false,
             iso_download_progress: 0.0,
+            iso_download_status: String::new(),
+            iso_download_cancel: None,
             disk_size_gb: 32,
+            disk_tuning: Default::default(),
             use_existing_disk: false,
             existing_disk_path: None,
+            remote_disk_url: None,
             existing_disk_action: DiskAction::Copy,
+            detected_os: None,
+            inspecting_disk: false,
+            cloud_init: None,
+            unattended: None,
             qemu_config: WizardQemuConfig::default(),
             auto_launch: true,
             field_focus: 0,
@@ -499,15 +717,21 @@ impl CreateWizardState {
             }
             WizardStep::ConfigureDisk => {
                 if self.use_existing_disk {
-                    // Validate existing disk path
-                    match &self.existing_disk_path {
-                        None => return Err("Please select an existing disk".to_string()),
-                        Some(path) => {
+                    // Validate existing disk path (or remote URL)
+                    match (&self.existing_disk_path, &self.remote_disk_url) {
+                        (_, Some(url)) => {
+                            crate::vm::remote_disk::validate_remote_url(url).map_err(|e| e.to_string())?;
+                        }
+                        (None, None) => return Err("Please select an existing disk".to_string()),
+                        (Some(path), None) => {
                             if !path.exists() {
                                 return Err(format!("Disk file not found: {}", path.display()));
                             }
                         }
                     }
+                    if let Some(ref cloud_init) = self.cloud_init {
+                        cloud_init.validate()?;
+                    }
                 } else {
                     // Validate new disk size
                     if self.disk_size_gb == 0 {
@@ -516,6 +740,13 @@ impl CreateWizardState {
                     if self.disk_size_gb > 10000 {
                         return Err("Disk size cannot exceed 10TB".to_string());
                     }
+                    self.disk_tuning.validate(false).map_err(|e| e.to_string())?;
+                    if let Some(ref unattended) = self.unattended {
+                        if self.iso_path.is_none() {
+                            return Err("Unattended install needs an installation ISO".to_string());
+                        }
+                        unattended.validate()?;
+                    }
                 }
                 Ok(())
             }
@@ -547,13 +778,554 @@ impl CreateWizardState {
     }
 }
 
+/// State for the CPU model picker
+#[derive(Debug, Clone)]
+pub struct CpuModelState {
+    /// (model, description) choices; the first entry is QEMU's default
+    pub models: Vec<(String, String)>,
+    pub model_selected: usize,
+    /// Flags appended to the model (e.g. "-hypervisor", "kvm=off")
+    pub flags: Vec<String>,
+    pub flag_selected: usize,
+    /// Focus is on the flag toggles rather than the model list
+    pub editing_flags: bool,
+}
+
+impl CpuModelState {
+    /// Build the picker for a VM: default, host passthrough, then the emulator's models
+    pub fn new(current: Option<&str>, supported: Option<Vec<(String, String)>>) -> Self {
+        let current = current.map(crate::vm::cpu_model::CpuSpec::parse);
+
+        let mut models = vec![
+            ("".to_string(), "QEMU default for the machine type".to_string()),
+            ("host".to_string(), "Host passthrough: every host CPU feature (KVM)".to_string()),
+        ];
+        for (name, desc) in supported.unwrap_or_default() {
+            if name != "host" {
+                models.push((name, desc));
+            }
+        }
+        if let Some(ref spec) = current {
+            if !models.iter().any(|(name, _)| *name == spec.model) {
+                models.push((spec.model.clone(), "Current model".to_string()));
+            }
+        }
+
+        let model_selected = current
+            .as_ref()
+            .and_then(|spec| models.iter().position(|(name, _)| *name == spec.model))
+            .unwrap_or(0);
+
+        Self {
+            models,
+            model_selected,
+            flags: current.map(|spec| spec.flags).unwrap_or_default(),
+            flag_selected: 0,
+            editing_flags: false,
+        }
+    }
+
+    pub fn has_flag(&self, token: &str) -> bool {
+        self.flags.iter().any(|f| f == token)
+    }
+
+    /// Add or remove a flag, keeping the others in order
+    pub fn toggle_flag(&mut self, token: &str) {
+        if self.has_flag(token) {
+            self.flags.retain(|f| f != token);
+        } else {
+            self.flags.push(token.to_string());
+        }
+    }
+
+    /// The `-cpu` setting to write (None for QEMU's default)
+    pub fn spec(&self) -> Option<crate::vm::cpu_model::CpuSpec> {
+        let (model, _) = self.models.get(self.model_selected)?;
+        (!model.is_empty()).then(|| crate::vm::cpu_model::CpuSpec {
+            model: model.clone(),
+            flags: self.flags.clone(),
+        })
+    }
+}
+
+/// State for the disk I/O tuning editor
+#[derive(Debug, Clone)]
+pub struct DiskIoState {
+    /// The VM's disk drives, with pending tuning
+    pub drives: Vec<crate::vm::disk_io::DiskDrive>,
+    pub drive_selected: usize,
+}
+
+/// State for the disk format conversion wizard
+#[derive(Debug, Clone)]
+pub struct ConvertDiskState {
+    /// Image being converted
+    pub source: PathBuf,
+    /// Format and sizes of the source, if qemu-img could read it
+    pub info: Option<crate::commands::qemu_img::ImageInfo>,
+    /// Images the source is an overlay on, nearest first
+    pub backing: Vec<crate::commands::qemu_img::ImageInfo>,
+    /// Index into the offered output formats
+    pub format_index: usize,
+    /// Write compressed qcow2 clusters
+    pub compress: bool,
+    /// qcow2 options for the converted image
+    pub tuning: crate::commands::qemu_img::Qcow2Options,
+    pub keep_original: bool,
+    /// Focused option (format, compression, original)
+    pub field: usize,
+    /// Free bytes where the converted image will be written
+    pub free_space: Option<u64>,
+    /// Internal snapshots of the source, which conversion can't carry over
+    pub snapshots: usize,
+    /// Progress (0.0 - 1.0) once the conversion is running
+    pub progress: Option<f32>,
+}
+
+/// State for the backing chain screen
+#[derive(Debug, Clone)]
+pub struct BackingChainState {
+    /// The VM's disk, first link of the chain
+    pub disk: PathBuf,
+    pub links: Vec<crate::vm::backing_chain::ChainLink>,
+    pub selected: usize,
+    /// Change shown with its consequences, waiting for `y`
+    pub pending: Option<crate::vm::backing_chain::ChainAction>,
+    /// A change is being applied
+    pub busy: bool,
+}
+
+/// State for the physical disk passthrough screen
+#[derive(Debug, Clone)]
+pub struct PhysicalDisksState {
+    /// Whole disks currently on the host
+    pub devices: Vec<crate::hardware::block::BlockDevice>,
+    /// Pending attachments (may include disks that are not plugged in)
+    pub attached: Vec<crate::vm::physical_disk::PhysicalDisk>,
+    pub selected: usize,
+}
+
+/// State for the classic Mac firmware screen
+#[derive(Debug, Clone)]
+pub struct MacFirmwareState {
+    /// Dumps found in the ROM folder
+    pub roms: Vec<crate::vm::mac_firmware::MacRom>,
+    /// Pending settings
+    pub firmware: crate::vm::mac_firmware::MacFirmware,
+    pub selected: usize,
+}
+
+/// State for the migrate-to-host dialog
+#[derive(Debug, Clone)]
+pub struct HostMigrationState {
+    /// Hosts the VM can move to (None is this computer)
+    pub destinations: Vec<Option<crate::config::RemoteHost>>,
+    pub selected: usize,
+    /// The VM is running, so it is live-migrated rather than copied
+    pub live: bool,
+    /// Keep the source folder after a copy
+    pub keep_source: bool,
+    /// Progress (0.0 - 1.0) once the migration is running
+    pub progress: Option<f32>,
+}
+
+/// State for the systemd autostart dialog
+#[derive(Debug, Clone)]
+pub struct SystemdUnitState {
+    /// What is installed now
+    pub status: crate::vm::systemd::UnitStatus,
+    /// When the service should start
+    pub start_at: crate::vm::systemd::StartAt,
+    /// Add a QMP socket to launch.sh on install (None when not offered)
+    pub add_qmp: Option<bool>,
+    pub selected: usize,
+}
+
+/// State for the slideshow
+#[derive(Debug, Clone)]
+pub struct SlideshowState {
+    /// Indices into `vms`, in list order
+    pub slides: Vec<usize>,
+    pub current: usize,
+    pub shown_at: Instant,
+    /// VM launched from its demo snapshot for the current slide
+    pub demo_vm: Option<String>,
+    /// Set when the slide moves on, so a demo still being started stops itself
+    pub demo_cancel: Option<Arc<AtomicBool>>,
+    /// Slides shown so far (picks each slide's fun fact)
+    pub shown: usize,
+}
+
+/// State for the trivia quiz
+#[derive(Debug, Clone)]
+pub struct QuizState {
+    pub questions: Vec<crate::metadata::quiz::Question>,
+    /// Question on screen (`questions.len()` once the quiz is over)
+    pub current: usize,
+    /// Highlighted choice
+    pub selected: usize,
+    /// Choice given for the current question, once answered
+    pub picked: Option<usize>,
+    pub score: usize,
+}
+
+/// State for the usage statistics screen
+#[derive(Debug, Clone)]
+pub struct StatisticsState {
+    /// VM IDs, most used first
+    pub ranking: Vec<String>,
+    pub selected: usize,
+}
+
+/// State for the post-install checklist
+#[derive(Debug, Clone)]
+pub struct ChecklistState {
+    pub vm_dir: PathBuf,
+    pub vm_name: String,
+    /// The OS profile's tips, shown above the steps
+    pub notes: Option<String>,
+    pub checklist: crate::vm::checklist::Checklist,
+    pub selected: usize,
+}
+
+/// State for the VM doctor
+#[derive(Debug, Clone)]
+pub struct DoctorState {
+    pub vm_id: String,
+    pub vm_name: String,
+    pub findings: Vec<crate::vm::doctor::Finding>,
+    pub selected: usize,
+}
+
+/// State for the unused files report
+#[derive(Debug, Clone)]
+pub struct OrphanFilesState {
+    pub files: Vec<crate::vm::orphans::OrphanFile>,
+    /// Which files are selected for removal
+    pub marked: Vec<bool>,
+    pub selected: usize,
+    /// Waiting for a second `D` before deleting
+    pub confirm_delete: bool,
+}
+
+/// State for the storage usage dashboard
+#[derive(Debug, Clone)]
+pub struct StorageState {
+    /// Empty while the VMs are being measured
+    pub rows: Vec<crate::vm::storage::VmStorage>,
+    pub sort: crate::vm::storage::StorageSort,
+    pub selected: usize,
+}
+
+/// State for the launch log viewer
+#[derive(Debug, Clone)]
+pub struct LogViewerState {
+    pub vm_dir: PathBuf,
+    pub vm_name: String,
+    /// Showing the log of the launch before the latest
+    pub previous: bool,
+    pub lines: Vec<String>,
+    /// Size of the log when it was last read
+    pub log_len: u64,
+    /// Lines between the end of the log and the bottom of the view
+    pub from_bottom: usize,
+    /// Keep the view at the end as output arrives
+    pub follow: bool,
+}
+
+/// State for the resource monitor of a running VM
+#[derive(Debug, Clone)]
+pub struct MonitorState {
+    pub vm_name: String,
+    pub vm_dir: PathBuf,
+    pub pid: u32,
+    /// Guest RAM in the VM's configuration
+    pub memory_mb: u32,
+    /// Disk counters come through QMP
+    pub qmp: bool,
+    /// Tap devices carrying the VM's network traffic
+    pub taps: Vec<String>,
+    pub last: Option<crate::vm::monitor::Sample>,
+    pub history: crate::vm::monitor::History,
+    /// The VM's process has exited
+    pub stopped: bool,
+}
+
+/// State for the Create Snapshot dialog
+#[derive(Debug, Clone, Default)]
+pub struct CreateSnapshotState {
+    pub name: String,
+    pub description: String,
+    pub context: SnapshotContext,
+    pub selected: usize,
+    pub editing: bool,
+}
+
+/// State for the Add Data Disk dialog
+#[derive(Debug, Clone)]
+pub struct DataDiskState {
+    /// Size in GB as typed
+    pub size: String,
+    /// Index into `data_disk::FORMATS`
+    pub format_index: usize,
+    pub interface: crate::vm::data_disk::DataInterface,
+    pub filesystem: crate::vm::data_disk::DataFilesystem,
+    pub tuning: crate::commands::qemu_img::Qcow2Options,
+    pub selected: usize,
+    pub editing: bool,
+}
+
+/// State for the ISO library screen
+#[derive(Debug, Clone)]
+pub struct IsoLibraryState {
+    pub dir: PathBuf,
+    pub isos: Vec<crate::vm::iso_library::LibraryIso>,
+    /// Images are being checksummed in the background
+    pub scanning: bool,
+    pub selected: usize,
+}
+
+/// State for the boot screenshot gallery
+#[derive(Debug, Clone)]
+pub struct ScreenshotGalleryState {
+    pub vm_name: String,
+    /// Newest first
+    pub shots: Vec<crate::vm::screenshots::Screenshot>,
+    pub selected: usize,
+    /// The selected screenshot, if it could be read
+    pub image: Option<crate::vm::screenshots::Image>,
+}
+
+/// Newest boot screenshot of the selected VM, for the info panel
+#[derive(Debug, Clone)]
+pub struct LatestScreenshot {
+    pub vm_id: String,
+    /// None when the VM has no screenshot
+    pub image: Option<crate::vm::screenshots::Image>,
+}
+
+/// State for the Launch with options dialog, values as typed
+#[derive(Debug, Clone, Default)]
+pub struct LaunchOptionsState {
+    pub vm_name: String,
+    pub memory: String,
+    pub cpu_cores: String,
+    /// None keeps the script's display
+    pub display: Option<String>,
+    pub extra_args: String,
+    pub selected: usize,
+    pub editing: bool,
+}
+
+/// State for the launch environment editor
+#[derive(Debug, Clone)]
+pub struct LaunchEnvState {
+    pub vm_dir: PathBuf,
+    pub vm_id: String,
+    pub vm_name: String,
+    pub environment: crate::vm::launch_env::LaunchEnvironment,
+    pub selected: usize,
+    pub editing: bool,
+    pub dirty: bool,
+    /// Esc was pressed once with unsaved changes
+    pub confirm_discard: bool,
+}
+
+/// State for the QEMU build selection
+#[derive(Debug, Clone)]
+pub struct QemuBinaryState {
+    /// Builds of the VM's emulator, with their versions
+    pub binaries: Vec<(crate::commands::qemu_system::QemuBinary, Option<String>)>,
+    /// Version of the one on PATH
+    pub default_version: Option<String>,
+    /// The VM's current choice (None is PATH)
+    pub current: Option<PathBuf>,
+    pub selected: usize,
+}
+
+/// State for the evdev input passthrough editor
+#[derive(Debug, Clone)]
+pub struct InputPassthroughState {
+    /// Input devices on the host
+    pub devices: Vec<crate::hardware::input::InputDevice>,
+    pub settings: crate::vm::input_passthrough::InputPassthrough,
+    pub selected: usize,
+    pub dirty: bool,
+    /// Esc was pressed once with unsaved changes
+    pub confirm_discard: bool,
+}
+
+/// State for the game controller screen
+#[derive(Debug, Clone)]
+pub struct GameControllerState {
+    pub backend: crate::vm::backend::Backend,
+    /// Host gamepads and joysticks, in kernel order
+    pub controllers: Vec<crate::hardware::input::InputDevice>,
+    /// QEMU VMs: how each controller reaches the guest
+    pub routes: Vec<crate::vm::game_controller::ControllerRoute>,
+    /// 86Box and DOSBox-X VMs: the emulated joystick
+    pub gameport: crate::vm::game_controller::Gameport,
+    pub selected: usize,
+    pub dirty: bool,
+    /// Esc was pressed once with unsaved changes
+    pub confirm_discard: bool,
+}
+
+/// State for the OS info editor
+#[derive(Debug, Clone)]
+pub struct MetadataEditorState {
+    /// Metadata ID the user override is saved under
+    pub os_id: String,
+    pub info: OsInfo,
+    /// The long blurb, one entry per paragraph
+    pub paragraphs: Vec<String>,
+    pub selected: usize,
+    pub editing: bool,
+    pub dirty: bool,
+    /// Esc was pressed once with unsaved changes
+    pub confirm_discard: bool,
+}
+
+/// State for the QEMU profile editor
+#[derive(Debug, Clone)]
+pub struct ProfileEditorState {
+    /// Every profile ID, in wizard category order then by name
+    pub ids: Vec<String>,
+    /// IDs of the built-in and plugin profiles
+    pub builtin: HashSet<String>,
+    /// The user's profiles file
+    pub user: BTreeMap<String, QemuProfile>,
+    pub selected: usize,
+    /// d was pressed once on a user profile
+    pub confirm_delete: bool,
+    /// The profile being edited
+    pub form: Option<ProfileForm>,
+}
+
+/// A profile being edited in the profile editor
+#[derive(Debug, Clone)]
+pub struct ProfileForm {
+    /// ID the profile is saved under
+    pub id: String,
+    /// The ID can still be changed (new and duplicated profiles)
+    pub new_id: bool,
+    /// The profile being edited; fields the form doesn't show are kept as they are
+    pub profile: QemuProfile,
+    /// Text of each form field
+    pub values: Vec<String>,
+    pub selected: usize,
+    pub editing: bool,
+    pub dirty: bool,
+    /// Esc was pressed once with unsaved changes
+    pub confirm_discard: bool,
+}
+
+/// The lock on the VM whose management menu is open
+#[derive(Debug)]
+pub struct EditLock {
+    pub vm_dir: PathBuf,
+    /// Held by this instance (None when the VM needs no lock or can't be locked)
+    pub lock: Option<crate::vm::instance_lock::VmLock>,
+    /// Who is using the VM instead
+    pub held_by: Option<crate::vm::instance_lock::LockOwner>,
+}
+
+/// One side of the guest file browser
+#[derive(Debug, Clone)]
+pub struct FilePane {
+    pub dir: PathBuf,
+    /// Navigation stops here (the mountpoint, for the guest side)
+    pub root: Option<PathBuf>,
+    pub entries: Vec<FileBrowserEntry>,
+    pub selected: usize,
+}
+
+impl FilePane {
+    pub fn new(dir: PathBuf, root: Option<PathBuf>) -> Self {
+        let mut pane = Self {
+            dir,
+            root,
+            entries: Vec::new(),
+            selected: 0,
+        };
+        pane.load();
+        pane
+    }
+
+    /// Re-read the current directory, directories first
+    pub fn load(&mut self) {
+        self.entries.clear();
+
+        let at_root = self.root.as_ref().is_some_and(|root| *root == self.dir);
+        if let Some(parent) = self.dir.parent().filter(|_| !at_root) {
+            self.entries.push(FileBrowserEntry {
+                name: "..".to_string(),
+                path: parent.to_path_buf(),
+                is_dir: true,
+            });
+        }
+
+        let mut dirs = Vec::new();
+        let mut files = Vec::new();
+        if let Ok(entries) = std::fs::read_dir(&self.dir) {
+            for entry in entries.flatten() {
+                let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+                let entry = FileBrowserEntry {
+                    name: entry.file_name().to_string_lossy().to_string(),
+                    path: entry.path(),
+                    is_dir,
+                };
+                if is_dir {
+                    dirs.push(entry);
+                } else {
+                    files.push(entry);
+                }
+            }
+        }
+        dirs.sort_by_key(|e| e.name.to_lowercase());
+        files.sort_by_key(|e| e.name.to_lowercase());
+        self.entries.extend(dirs);
+        self.entries.extend(files);
+
+        self.selected = self.selected.min(self.entries.len().saturating_sub(1));
+    }
+
+    /// Open the selected directory
+    pub fn enter(&mut self) {
+        if let Some(entry) = self.entries.get(self.selected).filter(|e| e.is_dir) {
+            self.dir = entry.path.clone();
+            self.selected = 0;
+            self.load();
+        }
+    }
+
+    /// The selected entry, excluding the ".." link
+    pub fn selected_entry(&self) -> Option<&FileBrowserEntry> {
+        self.entries.get(self.selected).filter(|e| e.name != "..")
+    }
+}
+
+/// State for browsing and copying files on a mounted guest disk
+#[derive(Debug, Clone)]
+pub struct GuestFilesState {
+    pub mount: crate::commands::guest_mount::GuestMount,
+    /// Guest pane first, host pane second
+    pub panes: [FilePane; 2],
+    pub active: usize,
+}
+
 /// State for network settings editing screen
 #[derive(Debug, Clone)]
 pub struct NetworkSettingsState {
     pub model: String,
     pub backend: String,
     pub bridge_name: Option<String>,
+    /// Tap device and how it reaches the network (tap backend)
+    pub tap_name: Option<String>,
+    pub tap_uplink: crate::commands::tap_setup::TapUplink,
     pub port_forwards: Vec<PortForward>,
+    /// Export the VM's share folder via SLIRP's SMB server (user backend)
+    pub smb_share: bool,
     pub selected_field: usize,
     pub editing_port_forwards: bool,
     pub pf_selected: usize,
@@ -587,6 +1359,8 @@ pub struct App {
     pub config: Config,
     /// Discovered VMs
     pub vms: Vec<DiscoveredVm>,
+    /// VMs in remote host libraries, read at startup and kept in `vms`
+    pub remote_vms: Vec<DiscoveredVm>,
     /// Currently selected VM index
     pub selected_vm: usize,
     /// OS metadata store
@@ -597,12 +1371,18 @@ pub struct App {
     pub hierarchy: HierarchyConfig,
     /// Snapshots for current VM (cached)
     pub snapshots: Vec<Snapshot>,
+    /// Sidecar metadata of those snapshots, by name
+    pub snapshot_meta: BTreeMap<String, SnapshotMeta>,
     /// Selected snapshot index
     pub selected_snapshot: usize,
     /// USB devices (cached)
     pub usb_devices: Vec<UsbDevice>,
     /// Selected USB devices for passthrough
     pub selected_usb_devices: Vec<usize>,
+    /// USB passthrough rules of the selected VM, as edited on the USB screen
+    pub usb_rules: Vec<UsbPassthrough>,
+    /// Map of vm_id -> USB devices hot-plugged into that running VM
+    pub usb_attachments: HashMap<String, Vec<AttachedUsb>>,
     /// PCI devices (cached)
     pub pci_devices: Vec<PciDevice>,
     /// Selected PCI devices for passthrough
@@ -611,12 +1391,30 @@ pub struct App {
     pub shared_folders: Vec<SharedFolder>,
     /// Selected shared folder index
     pub shared_folder_selected: usize,
+    /// How the current VM's shared folders are exported
+    pub shared_folder_method: SharedFolderMethod,
     /// Multi-GPU passthrough status (prerequisites)
     pub multi_gpu_status: Option<MultiGpuPassthroughStatus>,
+    /// Host setup check for the selected PCI devices
+    pub pci_host_check: Option<PassthroughCheck>,
+    /// Scroll offset of the PCI host check screen
+    pub pci_host_check_scroll: u16,
     /// Selected management menu item
     pub selected_menu_item: usize,
     /// Current boot mode
     pub boot_mode: BootMode,
+    /// Settings changed for the next launch only
+    pub launch_overrides: LaunchOverrides,
+    /// Launch with options dialog state
+    pub launch_options_state: Option<LaunchOptionsState>,
+    /// Launch environment editor state
+    pub launch_env_state: Option<LaunchEnvState>,
+    /// QEMU build selection state
+    pub qemu_binary_state: Option<QemuBinaryState>,
+    /// Input passthrough editor state
+    pub input_passthrough_state: Option<InputPassthroughState>,
+    /// Game controller screen state
+    pub game_controller_state: Option<GameControllerState>,
     /// Search query
     pub search_query: String,
     /// Input mode
@@ -665,12 +1463,16 @@ pub struct App {
     pub script_editor_h_scroll: usize,
     /// QEMU profiles for VM creation
     pub qemu_profiles: QemuProfileStore,
+    /// Checksums of known install images
+    pub iso_checksums: IsoChecksums,
     /// Settings help text store
     pub settings_help: SettingsHelpStore,
     /// Shared folders help text store
     pub shared_folders_help: SharedFoldersHelpStore,
     /// VM creation wizard state
     pub wizard_state: Option<CreateWizardState>,
+    /// Wizard input saved by an earlier session, offered for resuming
+    pub wizard_draft: Option<crate::recovery::WizardDraft>,
     /// Settings screen selected item
     pub settings_selected: usize,
     /// Settings screen editing mode
@@ -681,6 +1483,26 @@ pub struct App {
     pub settings_gpu_validation: Option<crate::ui::screens::settings::GpuValidationResult>,
     /// Cached display capabilities per emulator (populated at startup)
     pub display_capabilities: HashMap<String, Vec<String>>,
+    /// Cached `-audiodev` drivers per emulator (populated at startup)
+    pub audio_capabilities: HashMap<String, Vec<String>>,
+    /// Sound servers detected on the host, most preferred first
+    pub host_audio: Vec<&'static str>,
+    /// Host render nodes and virglrenderer (for the GPU acceleration screen)
+    pub host_gl: crate::hardware::gl::HostGl,
+    /// Whether the selected VM's emulator has virtio-vga-gl (None if unknown)
+    pub qemu_has_virgl: Option<bool>,
+    /// Machine types the selected VM's emulator supports (None if unknown)
+    pub qemu_machines: Option<Vec<String>>,
+    /// Host RAM and CPU totals (for wizard suggestions and limits)
+    pub host_resources: HostResources,
+    /// Disk operations left unfinished by a previous session
+    pub interrupted_disk_ops: Vec<InterruptedDiskOp>,
+    /// Library layout upgrade offered at startup
+    pub pending_migration: Option<PendingMigration>,
+    /// Sound Blaster settings being edited for the selected VM
+    pub sb16_edit: Option<Sb16Settings>,
+    /// Display preset being edited for the selected VM
+    pub display_preset_edit: Option<DisplayPreset>,
 
     // === VM Process Monitoring ===
     /// Receives QEMU process info from background detection thread
@@ -689,6 +1511,21 @@ pub struct App {
     pub running_vms: HashMap<String, u32>,
     /// Map of vm_id -> when SIGTERM was sent (for force-stop timeout)
     pub stopping_vms: HashMap<String, Instant>,
+    /// Map of vm_id -> latest guest agent report for running VMs
+    pub guest_info: HashMap<String, GuestInfo>,
+    /// VMs with a guest agent query in flight
+    pub guest_queries_pending: HashSet<String>,
+    /// Map of vm_id -> PID of the QEMU whose QMP events are being followed
+    pub qmp_watchers: HashMap<String, u32>,
+    /// Map of vm_id -> PID of VMs that reported shutting down while their
+    /// process is still exiting
+    pub shut_down_vms: HashMap<String, u32>,
+
+    // === Batch Operations ===
+    /// Report from the last batch operation
+    pub batch_report: Option<BatchReport>,
+    /// Selected item in the batch report
+    pub batch_report_selected: usize,
 
     // === Single GPU Passthrough ===
     /// Single GPU passthrough configuration
@@ -703,6 +1540,72 @@ pub struct App {
     pub network_caps: NetworkCapabilities,
     /// Network settings editing state
     pub network_settings_state: Option<NetworkSettingsState>,
+    /// Bridge or tap device and the privileged steps awaiting consent
+    pub bridge_setup_plan: Option<(String, Vec<crate::commands::bridge_setup::SetupStep>)>,
+    /// Consent given: the main loop leaves the TUI and runs the plan
+    pub bridge_setup_confirmed: bool,
+    /// CPU model picker state
+    pub cpu_model_state: Option<CpuModelState>,
+    /// Disk I/O editor state
+    pub disk_io_state: Option<DiskIoState>,
+    /// Mounted guest disk and file panes
+    pub guest_files_state: Option<GuestFilesState>,
+    /// Disk conversion wizard state
+    pub convert_disk_state: Option<ConvertDiskState>,
+    /// Backing chain screen state
+    pub backing_chain_state: Option<BackingChainState>,
+    /// Physical disk passthrough state
+    pub physical_disks_state: Option<PhysicalDisksState>,
+    /// Mac firmware screen state
+    pub mac_firmware_state: Option<MacFirmwareState>,
+    /// Migrate-to-host dialog state
+    pub host_migration_state: Option<HostMigrationState>,
+    /// systemd autostart dialog state
+    pub systemd_unit_state: Option<SystemdUnitState>,
+    /// Lock on the VM being managed, for libraries shared between machines
+    pub edit_lock: Option<EditLock>,
+    /// Slideshow state
+    pub slideshow_state: Option<SlideshowState>,
+    /// Trivia quiz state
+    pub quiz_state: Option<QuizState>,
+    /// OS info editor state
+    pub metadata_editor_state: Option<MetadataEditorState>,
+    /// Usage statistics state
+    pub statistics_state: Option<StatisticsState>,
+    /// Post-install checklist state
+    pub checklist_state: Option<ChecklistState>,
+    /// VM doctor state
+    pub doctor_state: Option<DoctorState>,
+    /// Unused files report state
+    pub orphan_files_state: Option<OrphanFilesState>,
+    /// Storage usage dashboard state
+    pub storage_state: Option<StorageState>,
+    /// Launch log viewer state
+    pub log_viewer_state: Option<LogViewerState>,
+    /// Resource monitor state
+    pub monitor_state: Option<MonitorState>,
+    pub screenshot_gallery_state: Option<ScreenshotGalleryState>,
+    pub create_snapshot_state: Option<CreateSnapshotState>,
+    /// Add Data Disk dialog state
+    pub data_disk_state: Option<DataDiskState>,
+    /// ISO library screen state
+    pub iso_library_state: Option<IsoLibraryState>,
+    /// Cached for the info panel (only on truecolor terminals)
+    pub latest_screenshot: Option<LatestScreenshot>,
+    /// QEMU profile editor state
+    pub profile_editor_state: Option<ProfileEditorState>,
+    /// Launch history of each local VM, by VM ID
+    pub launch_history: HashMap<String, LaunchHistory>,
+    /// Last key press or mouse event (the slideshow starts after a while without one)
+    pub last_input: Instant,
+    /// Anniversaries today related to VMs in the library, for the main menu banner
+    pub on_this_day: Vec<OnThisDay>,
+    /// Anniversary shown in the banner
+    pub on_this_day_index: usize,
+    /// Notes and journal of the VM in the details screen
+    pub notes: Option<Notes>,
+    /// Scroll position in the details screen
+    pub details_scroll: u16,
     /// Whether the wizard port forward editor is active
     pub wizard_editing_port_forwards: bool,
     /// Wizard port forward editor selection index
@@ -727,6 +1630,34 @@ pub enum BackgroundResult {
     /// Reserved for async snapshot loading
     #[allow(dead_code)]
     SnapshotsLoaded { snapshots: Vec<Snapshot>, error: Option<String> },
+    DiskCompacted { vm_name: String, success: bool, error: Option<String> },
+    StorageMeasured { rows: Vec<crate::vm::storage::VmStorage> },
+    DebugBundleCreated { result: Result<PathBuf, String> },
+    DiskOperationsRecovered { resumed: usize, error: Option<String> },
+    GuestInfoQueried { vm_id: String, info: Option<GuestInfo> },
+    DiskInspected { path: PathBuf, result: Result<crate::vm::os_fingerprint::InspectedOs, String> },
+    QmpEvent { vm_id: String, event: crate::vm::qmp::QmpEvent },
+    /// Boot screenshot taken (failures are only logged)
+    ScreenshotCaptured { vm_id: String, success: bool },
+    /// Snapshot taken after a VM shut down (its name, or the error)
+    SessionSnapshotTaken { vm_id: String, vm_name: String, result: Result<String, String> },
+    DiskConvertProgress { progress: f32 },
+    DiskConverted { vm_name: String, result: Result<PathBuf, String> },
+    /// A backing chain change finished (what was done, or the error)
+    BackingChainChanged { result: Result<String, String> },
+    DataDiskAdded { vm_name: String, result: Result<PathBuf, String> },
+    IsoLibraryScanned { isos: Vec<crate::vm::iso_library::LibraryIso> },
+    HostMigrationProgress { progress: f32 },
+    HostMigrated { vm_name: String, dest: String, live: bool, result: Result<(), String> },
+    SuspendProgress { vm_name: String, progress: f32 },
+    /// The size of the saved state on success
+    VmSuspended { vm_name: String, result: Result<u64, String> },
+    /// `retry` is set when only the failed items of the previous report were re-run
+    BatchFinished { report: BatchReport, retry: bool },
+    PluginActionFinished { result: Result<String, String> },
+    SlideshowDemoFailed { vm_name: String, error: String },
+    IsoDownloadProgress { from: String, done: u64, total: Option<u64> },
+    IsoDownloaded { result: Result<PathBuf, String> },
 }
 
 impl App {
@@ -739,12 +1670,20 @@ impl App {
 
         // Step 1: Discover VMs
         progress(1, TOTAL_STEPS, "Discovering VMs...");
-        let vms = discover_vms(&config.vm_library_path)?;
+        let mut vms = discover_vms(&config.vm_library_path)?;
+        if !config.remote_hosts.is_empty() {
+            progress(1, TOTAL_STEPS, "Connecting to remote hosts...");
+        }
+        let (remote_vms, remote_errors) = crate::vm::remote_host::discover_all(&config.remote_hosts);
+        vms.extend(remote_vms.iter().cloned());
+        let interrupted_disk_ops = find_interrupted_operations(&config.vm_library_path);
+        let pending_migration = check_library(&config.vm_library_path);
         progress(1, TOTAL_STEPS, &format!("Found {} VMs", vms.len()));
 
         // Step 2: Load metadata
         progress(2, TOTAL_STEPS, "Loading OS metadata...");
         let mut metadata = MetadataStore::load_embedded();
+        crate::plugins::merge_metadata(&mut metadata);
         if let Ok(user_metadata) = MetadataStore::load_from_dir(&config.metadata_path) {
             metadata.merge(user_metadata);
         }
@@ -752,6 +1691,7 @@ impl App {
         // Step 3: Load ASCII art
         progress(3, TOTAL_STEPS, "Loading ASCII art...");
         let mut ascii_art = AsciiArtStore::load_embedded();
+        crate::plugins::merge_ascii_art(&mut ascii_art);
         let user_art = AsciiArtStore::load_from_dir(&config.ascii_art_path);
         ascii_art.merge(user_art);
 
@@ -762,12 +1702,13 @@ impl App {
         // Step 5: Load QEMU profiles
         progress(5, TOTAL_STEPS, "Loading QEMU profiles...");
         let mut qemu_profiles = QemuProfileStore::load_embedded();
+        crate::plugins::merge_profiles(&mut qemu_profiles);
         let config_dir = Config::config_file_path()
             .parent()
             .map(|p| p.to_path_buf())
             .unwrap_or_else(|| PathBuf::from("."));
-        let user_profiles_path = config_dir.join("qemu_profiles.toml");
-        qemu_profiles.load_user_overrides(&user_profiles_path);
+        qemu_profiles.load_user_overrides(&Config::qemu_profiles_path());
+        let iso_checksums = IsoChecksums::load(&Config::iso_checksums_path(), &qemu_profiles);
 
         // Load settings help text
         let mut settings_help = SettingsHelpStore::load_embedded();
@@ -782,6 +1723,7 @@ impl App {
         progress(6, TOTAL_STEPS, "Building VM list...");
         let filtered_indices: Vec<usize> = (0..vms.len()).collect();
         let visual_order = build_visual_order(&vms, &filtered_indices, &hierarchy, &metadata);
+        let on_this_day = EventStore::load_embedded().on_this_day(chrono::Local::now().date_naive(), &metadata, &vms);
         let (background_tx, background_rx) = mpsc::channel();
 
         // Detect network capabilities
@@ -796,38 +1738,66 @@ impl App {
             }
         }
 
+        // Detect host sound servers and the audio drivers each emulator has
+        let host_audio = crate::hardware::audio::detect_host_audio();
+        let mut audio_capabilities = HashMap::new();
+        for emulator in crate::commands::qemu_system::list_available_emulators() {
+            if let Some(drivers) = crate::commands::qemu_system::get_supported_audiodevs(&emulator) {
+                audio_capabilities.insert(emulator, drivers);
+            }
+        }
+
         // Spawn background VM status detection thread
         let (vm_status_tx, vm_status_rx) = mpsc::channel();
+        let remote_hosts = config.remote_hosts.clone();
         std::thread::spawn(move || {
             loop {
                 std::thread::sleep(std::time::Duration::from_secs(3));
-                let processes = crate::vm::detect_qemu_processes();
+                let mut processes = crate::vm::detect_qemu_processes();
+                for host in &remote_hosts {
+                    if let Ok(remote) = crate::vm::remote_host::running_processes(host) {
+                        processes.extend(remote);
+                    }
+                }
                 if vm_status_tx.send(processes).is_err() {
                     break; // Receiver dropped (app exited)
                 }
             }
         });
 
-        Ok(Self {
+        let mut app = Self {
             screen: Screen::MainMenu,
             screen_stack: Vec::new(),
             config,
             vms,
+            remote_vms,
             selected_vm: 0,
             metadata,
             ascii_art,
             hierarchy,
             snapshots: Vec::new(),
+            snapshot_meta: Default::default(),
             selected_snapshot: 0,
             usb_devices: Vec::new(),
             selected_usb_devices: Vec::new(),
+            usb_rules: Vec::new(),
+            usb_attachments: HashMap::new(),
             pci_devices: Vec::new(),
             selected_pci_devices: Vec::new(),
             shared_folders: Vec::new(),
             shared_folder_selected: 0,
+            shared_folder_method: SharedFolderMethod::default(),
             multi_gpu_status: None,
+            pci_host_check: None,
+            pci_host_check_scroll: 0,
             selected_menu_item: 0,
             boot_mode: BootMode::Normal,
+            launch_overrides: LaunchOverrides::default(),
+            launch_options_state: None,
+            launch_env_state: None,
+            qemu_binary_state: None,
+            input_passthrough_state: None,
+            game_controller_state: None,
             search_query: String::new(),
             input_mode: InputMode::Normal,
             filtered_indices,
@@ -852,19 +1822,39 @@ impl App {
             script_editor_modified: false,
             script_editor_h_scroll: 0,
             qemu_profiles,
+            iso_checksums,
             settings_help,
             shared_folders_help,
             wizard_state: None,
+            wizard_draft: crate::recovery::load_draft(),
             settings_selected: 0,
             settings_editing: false,
             settings_edit_buffer: String::new(),
             settings_gpu_validation: None,
             display_capabilities,
+            audio_capabilities,
+            host_audio,
+            host_gl: crate::hardware::gl::detect_host_gl(),
+            qemu_has_virgl: None,
+            qemu_machines: None,
+            host_resources: HostResources::detect(),
+            interrupted_disk_ops,
+            pending_migration,
+            sb16_edit: None,
+            display_preset_edit: None,
 
             // VM Process Monitoring
             vm_status_rx,
             running_vms: HashMap::new(),
             stopping_vms: HashMap::new(),
+            guest_info: HashMap::new(),
+            guest_queries_pending: HashSet::new(),
+            qmp_watchers: HashMap::new(),
+            shut_down_vms: HashMap::new(),
+
+            // Batch Operations
+            batch_report: None,
+            batch_report_selected: 0,
 
             // Single GPU Passthrough
             single_gpu_config: None,
@@ -874,10 +1864,71 @@ impl App {
             // Networking
             network_caps,
             network_settings_state: None,
+            bridge_setup_plan: None,
+            bridge_setup_confirmed: false,
+            cpu_model_state: None,
+            disk_io_state: None,
+            guest_files_state: None,
+            convert_disk_state: None,
+            backing_chain_state: None,
+            physical_disks_state: None,
+            mac_firmware_state: None,
+            host_migration_state: None,
+            systemd_unit_state: None,
+            edit_lock: None,
+            slideshow_state: None,
+            quiz_state: None,
+            metadata_editor_state: None,
+            statistics_state: None,
+            checklist_state: None,
+            doctor_state: None,
+            orphan_files_state: None,
+            storage_state: None,
+            log_viewer_state: None,
+            monitor_state: None,
+            screenshot_gallery_state: None,
+            create_snapshot_state: None,
+            data_disk_state: None,
+            iso_library_state: None,
+            latest_screenshot: None,
+            profile_editor_state: None,
+            launch_history: HashMap::new(),
+            last_input: Instant::now(),
+            on_this_day,
+            on_this_day_index: 0,
+            notes: None,
+            details_scroll: 0,
             wizard_editing_port_forwards: false,
             wizard_pf_selected: 0,
             wizard_adding_pf: None,
-        })
+        };
+
+        app.load_launch_history();
+
+        // Offer to pick up a VM creation that was left unfinished
+        if app.wizard_draft.is_some() && !app.config.kiosk_mode {
+            app.push_screen(Screen::Confirm(ConfirmAction::ResumeWizardDraft));
+        }
+
+        // Offer to resume or clean up disk operations that were cut short
+        // (left for an administrator in kiosk mode)
+        if !app.interrupted_disk_ops.is_empty() && !app.config.kiosk_mode {
+            app.push_screen(Screen::Confirm(ConfirmAction::RecoverDiskOperations));
+        }
+
+        // An old library layout is upgraded first (pushed last, so shown first)
+        if app.pending_migration.is_some() && !app.config.kiosk_mode {
+            app.push_screen(Screen::Confirm(ConfirmAction::MigrateLibrary));
+        }
+
+        if !remote_errors.is_empty() {
+            app.set_status(format!("Remote host unavailable: {}", remote_errors.join("; ")));
+        }
+        if !crate::plugins::load_errors().is_empty() {
+            app.set_status(crate::plugins::load_errors().join("; "));
+        }
+
+        Ok(app)
     }
 
     /// Get display options for an emulator, filtered and ordered.
@@ -911,6 +1962,34 @@ impl App {
         preferred_order.iter().map(|s| s.to_string()).collect()
     }
 
+    /// Get host audio backend options for an emulator, most preferred first.
+    ///
+    /// Only sound servers running on this host (and built into the emulator)
+    /// are offered; "none" is always last.
+    pub fn get_audio_backend_options(&self, emulator: &str) -> Vec<String> {
+        let supported = self.audio_capabilities.get(emulator).map(Vec::as_slice);
+        crate::hardware::audio::backend_options(&self.host_audio, supported)
+    }
+
+    /// Audio backend choices for the selected VM (its current backend is always listed)
+    pub fn vm_audio_backend_options(&self) -> Vec<String> {
+        let Some(vm) = self.selected_vm() else {
+            return Vec::new();
+        };
+        let mut options = self.get_audio_backend_options(vm.config.emulator.command());
+        if let Some(current) = &vm.config.audio_backend {
+            if !options.contains(current) {
+                options.push(current.clone());
+            }
+        }
+        options
+    }
+
+    /// Best host audio backend for new VMs on an emulator
+    pub fn preferred_audio_backend(&self, emulator: &str) -> String {
+        self.get_audio_backend_options(emulator).swap_remove(0)
+    }
+
     /// Get available network backend options based on detected capabilities
     pub fn get_network_backend_options(&self) -> Vec<(&str, &str)> {
         let mut options = vec![
@@ -926,6 +2005,11 @@ impl App {
                 options.push(("bridge", "Bridge - Requires one-time setup"));
             }
         }
+        if self.network_caps.tap_devices.iter().any(|d| d.owner.is_some() && d.owner == crate::fs::current_uid()) {
+            options.push(("tap", "TAP - Persistent tap device, own IP"));
+        } else if self.network_caps.tap_manager.is_some() {
+            options.push(("tap", "TAP - Requires one-time setup"));
+        }
         options.push(("none", "None - No networking"));
         options
     }
@@ -943,6 +2027,48 @@ impl App {
         self.vms.get(*actual_idx)
     }
 
+    /// Select the VM the banner's anniversary relates to; once it is
+    /// selected, move on to the next anniversary
+    pub fn show_on_this_day(&mut self) {
+        if self.on_this_day.is_empty() {
+            self.set_status("Nothing in the library has an anniversary today");
+            return;
+        }
+        let current = &self.on_this_day[self.on_this_day_index].vm_id;
+        if self.selected_vm().is_some_and(|vm| &vm.id == current) {
+            self.on_this_day_index = (self.on_this_day_index + 1) % self.on_this_day.len();
+        }
+
+        let vm_id = self.on_this_day[self.on_this_day_index].vm_id.clone();
+        if !self.select_vm_by_id(&vm_id) {
+            self.set_status("That VM is hidden by the current filter");
+        }
+    }
+
+    /// Reload the QEMU profiles after the user's profiles file changed
+    pub fn reload_qemu_profiles(&mut self) {
+        let mut qemu_profiles = QemuProfileStore::load_embedded();
+        crate::plugins::merge_profiles(&mut qemu_profiles);
+        qemu_profiles.load_user_overrides(&Config::qemu_profiles_path());
+        self.iso_checksums = IsoChecksums::load(&Config::iso_checksums_path(), &qemu_profiles);
+        self.qemu_profiles = qemu_profiles;
+    }
+
+    /// Select a VM in the list by ID; false if it isn't listed
+    pub fn select_vm_by_id(&mut self, vm_id: &str) -> bool {
+        let position = self.visual_order.iter().position(|filtered_idx| {
+            self.filtered_indices
+                .get(*filtered_idx)
+                .and_then(|idx| self.vms.get(*idx))
+                .is_some_and(|vm| vm.id == vm_id)
+        });
+        if let Some(pos) = position {
+            self.selected_vm = pos;
+            self.info_scroll = 0;
+        }
+        position.is_some()
+    }
+
     /// Get OS info for the selected VM
     pub fn selected_vm_info(&self) -> Option<OsInfo> {
         let vm = self.selected_vm()?;
@@ -955,10 +2081,26 @@ impl App {
     /// Get ASCII art for the selected VM
     pub fn selected_vm_ascii(&self) -> &str {
         self.selected_vm()
-            .map(|vm| self.ascii_art.get_or_fallback(&vm.id))
+            .map(|vm| self.vm_ascii(vm))
             .unwrap_or("")
     }
 
+    /// ASCII art for a VM: its own, its profile's, or its OS family's
+    pub fn vm_ascii(&self, vm: &DiscoveredVm) -> &str {
+        if let Some(art) = self.ascii_art.get(&vm.id) {
+            return art;
+        }
+        let profile_id = vm.os_profile.as_deref().unwrap_or(&vm.id);
+        let (family, subcategory) = self.hierarchy.categorize(profile_id);
+        let category = self
+            .qemu_profiles
+            .get(profile_id)
+            .or_else(|| crate::metadata::os_info::strip_numeric_suffix(profile_id).and_then(|id| self.qemu_profiles.get(id)))
+            .map(|p| p.category.as_str())
+            .unwrap_or_default();
+        self.ascii_art.get_or_fallback(profile_id, &[&subcategory, category, &family])
+    }
+
     /// Navigate to a new screen
     pub fn push_screen(&mut self, screen: Screen) {
         self.screen_stack.push(self.screen.clone());
@@ -1030,22 +2172,47 @@ impl App {
         }
     }
 
+    /// Re-read the remote host libraries, then the whole VM list
+    pub fn refresh_remote_vms(&mut self) {
+        let (remote_vms, errors) = crate::vm::remote_host::discover_all(&self.config.remote_hosts);
+        self.remote_vms = remote_vms;
+        let _ = self.refresh_vms();
+        if !errors.is_empty() {
+            self.set_status(format!("Remote host unavailable: {}", errors.join("; ")));
+        }
+    }
+
     /// Refresh VM list
     pub fn refresh_vms(&mut self) -> Result<()> {
         self.vms = discover_vms(&self.config.vm_library_path)?;
+        self.vms.extend(self.remote_vms.iter().cloned());
         self.update_filter();
+        self.load_launch_history();
         Ok(())
     }
 
+    /// Reload the launch history of the local VMs
+    pub fn load_launch_history(&mut self) {
+        self.launch_history = self
+            .vms
+            .iter()
+            .filter(|vm| vm.remote.is_none())
+            .map(|vm| (vm.id.clone(), launch_history::load(&vm.path)))
+            .collect();
+    }
+
     /// Load snapshots for the current VM
     pub fn load_snapshots(&mut self) -> Result<()> {
         self.snapshots.clear();
+        self.snapshot_meta.clear();
         self.selected_snapshot = 0;
 
-        if let Some(vm) = self.selected_vm() {
+        if let Some(vm) = self.selected_vm().filter(|vm| vm.remote.is_none()) {
             if let Some(disk) = vm.config.primary_disk() {
                 if disk.format.supports_snapshots() {
-                    self.snapshots = crate::vm::list_snapshots(&disk.path)?;
+                    let path = disk.path.clone();
+                    self.snapshots = crate::vm::list_snapshots(&path)?;
+                    self.snapshot_meta = crate::vm::snapshot_meta::load(&path);
                 }
             }
         }
@@ -1060,35 +2227,219 @@ impl App {
         Ok(())
     }
 
-    /// Toggle USB device selection
+    /// Load the selected VM's USB rules and select the connected devices they match
+    pub fn load_usb_rules(&mut self) {
+        self.usb_rules = self
+            .selected_vm()
+            .map(crate::vm::load_usb_passthrough)
+            .unwrap_or_default();
+        self.selected_usb_devices = self
+            .usb_devices
+            .iter()
+            .enumerate()
+            .filter(|(_, d)| self.usb_rules.iter().any(|r| r.matches(d)))
+            .map(|(i, _)| i)
+            .collect();
+    }
+
+    /// Rules whose device isn't connected, listed after the connected devices
+    pub fn usb_absent_rules(&self) -> Vec<usize> {
+        (0..self.usb_rules.len())
+            .filter(|&r| !self.usb_devices.iter().any(|d| self.usb_rules[r].matches(d)))
+            .collect()
+    }
+
+    /// Number of rows on the USB screen
+    pub fn usb_list_len(&self) -> usize {
+        self.usb_devices.len() + self.usb_absent_rules().len()
+    }
+
+    /// Index into `usb_rules` of the rule shown on a USB screen row
+    pub fn usb_rule_at(&self, index: usize) -> Option<usize> {
+        match self.usb_devices.get(index) {
+            Some(device) => self.usb_rules.iter().position(|r| r.matches(device)),
+            None => self.usb_absent_rules().get(index - self.usb_devices.len()).copied(),
+        }
+    }
+
+    /// Toggle USB device selection, adding or removing its rule
     pub fn toggle_usb_device(&mut self, index: usize) {
+        let Some(device) = self.usb_devices.get(index) else {
+            // Unchecking a disconnected device drops its rule
+            if let Some(rule) = self.usb_rule_at(index) {
+                self.usb_rules.remove(rule);
+            }
+            return;
+        };
+
         if let Some(pos) = self.selected_usb_devices.iter().position(|&i| i == index) {
             self.selected_usb_devices.remove(pos);
+            self.usb_rules.retain(|r| !r.matches(device));
         } else {
             self.selected_usb_devices.push(index);
+            if !self.usb_rules.iter().any(|r| r.matches(device)) {
+                self.usb_rules.push(UsbPassthrough::from(device));
+            }
         }
     }
 
-    /// Load PCI devices
-    pub fn load_pci_devices(&mut self) -> Result<()> {
-        self.pci_devices = crate::hardware::enumerate_pci_devices()?;
-        self.selected_pci_devices.clear();
-        self.multi_gpu_status = Some(crate::hardware::check_multi_gpu_passthrough_status());
+    /// Pin a row's rule to the port its device is plugged into, or unpin it
+    pub fn toggle_usb_pin(&mut self, index: usize) -> Result<()> {
+        let rule = self
+            .usb_rule_at(index)
+            .ok_or_else(|| anyhow::anyhow!("Select the device first"))?;
+        if self.usb_rules[rule].is_pinned() {
+            self.usb_rules[rule].set_pinned(None);
+        } else {
+            let device = self
+                .usb_devices
+                .get(index)
+                .ok_or_else(|| anyhow::anyhow!("Connect the device to pin it to a port"))?;
+            self.usb_rules[rule].set_pinned(Some(device));
+        }
         Ok(())
     }
 
-    /// Load shared folders for the current VM
-    pub fn load_shared_folders(&mut self) {
-        self.shared_folders.clear();
-        self.shared_folder_selected = 0;
-
-        if let Some(vm) = self.selected_vm() {
-            self.shared_folders = crate::vm::load_shared_folders(vm);
-        }
+    /// Switch a row's rule between attaching whenever plugged in and at launch only
+    pub fn toggle_usb_auto_attach(&mut self, index: usize) -> Result<()> {
+        let rule = self
+            .usb_rule_at(index)
+            .ok_or_else(|| anyhow::anyhow!("Select the device first"))?;
+        self.usb_rules[rule].auto_attach = !self.usb_rules[rule].auto_attach;
+        Ok(())
     }
 
-    /// Add a shared folder, generating a unique mount tag
-    pub fn add_shared_folder(&mut self, host_path: String) {
+    /// Ask every running VM which USB devices it holds (via QMP)
+    ///
+    /// VMs without a QMP socket (launched before it was added) are skipped.
+    pub fn refresh_usb_attachments(&mut self) {
+        self.usb_attachments = self
+            .vms
+            .iter()
+            .filter(|vm| self.running_vms.contains_key(&vm.id))
+            .filter_map(|vm| {
+                let attached = crate::vm::qmp::attached_usb(&vm.path).ok()?;
+                Some((vm.id.clone(), attached))
+            })
+            .collect();
+    }
+
+    /// The running VM a host USB device is attached to, with its attachment
+    pub fn usb_attachment(&self, device: &UsbDevice) -> Option<(&DiscoveredVm, &AttachedUsb)> {
+        self.usb_attachments.iter().find_map(|(vm_id, attached)| {
+            let usb = attached.iter().find(|a| {
+                a.matches(device.vendor_id, device.product_id, device.bus_num, device.dev_num)
+            })?;
+            let vm = self.vms.iter().find(|vm| &vm.id == vm_id)?;
+            Some((vm, usb))
+        })
+    }
+
+    /// USB devices the hot-plug keys act on: the checked ones, or the one
+    /// under the cursor when nothing is checked
+    fn usb_hotplug_targets(&self) -> Vec<UsbDevice> {
+        if self.selected_usb_devices.is_empty() {
+            self.usb_devices.get(self.selected_menu_item).cloned().into_iter().collect()
+        } else {
+            self.selected_usb_devices
+                .iter()
+                .filter_map(|&i| self.usb_devices.get(i).cloned())
+                .collect()
+        }
+    }
+
+    /// Hot-plug the targeted USB devices into the selected running VM
+    ///
+    /// Returns a status message describing what happened.
+    pub fn attach_usb_to_selected_vm(&mut self) -> Result<String> {
+        let vm = self
+            .selected_vm()
+            .filter(|vm| self.running_vms.contains_key(&vm.id))
+            .ok_or_else(|| anyhow::anyhow!("Start the VM to attach USB devices live"))?;
+        let (vm_name, vm_path) = (vm.display_name(), vm.path.clone());
+
+        let mut attached = 0;
+        let mut skipped = Vec::new();
+        for device in self.usb_hotplug_targets() {
+            if let Some((holder, _)) = self.usb_attachment(&device) {
+                skipped.push(format!("{} (in use by {})", device.display_name(), holder.display_name()));
+                continue;
+            }
+            crate::vm::qmp::attach_usb(&vm_path, device.bus_num, device.dev_num)
+                .map_err(|e| anyhow::anyhow!("{}: {:#}", device.display_name(), e))?;
+            attached += 1;
+        }
+
+        self.refresh_usb_attachments();
+        let mut msg = format!("Attached {} USB device(s) to {}", attached, vm_name);
+        if !skipped.is_empty() {
+            msg.push_str(&format!("; skipped {}", skipped.join(", ")));
+        }
+        Ok(msg)
+    }
+
+    /// Unplug the targeted USB devices from whichever running VM holds them
+    pub fn detach_usb_from_vms(&mut self) -> Result<String> {
+        let mut detached = 0;
+        for device in self.usb_hotplug_targets() {
+            let Some((vm, usb)) = self.usb_attachment(&device) else {
+                continue;
+            };
+            crate::vm::qmp::detach_usb(&vm.path, usb)
+                .map_err(|e| anyhow::anyhow!("{}: {:#}", device.display_name(), e))?;
+            detached += 1;
+        }
+
+        if detached == 0 {
+            anyhow::bail!("None of the selected USB devices are attached to a running VM");
+        }
+
+        // device_del completes once the guest releases the device
+        self.refresh_usb_attachments();
+        Ok(format!("Requested detach of {} USB device(s)", detached))
+    }
+
+    /// Load PCI devices
+    pub fn load_pci_devices(&mut self) -> Result<()> {
+        self.pci_devices = crate::hardware::enumerate_pci_devices()?;
+        self.selected_pci_devices.clear();
+        self.multi_gpu_status = Some(crate::hardware::check_multi_gpu_passthrough_status());
+        Ok(())
+    }
+
+    /// Re-read PCI devices (e.g. after rebinding drivers), keeping the selection
+    pub fn load_pci_devices_keep_selection(&mut self) {
+        let selected: Vec<String> = self
+            .selected_pci_devices
+            .iter()
+            .filter_map(|&i| self.pci_devices.get(i).map(|d| d.address.clone()))
+            .collect();
+
+        if let Ok(devices) = crate::hardware::enumerate_pci_devices() {
+            self.pci_devices = devices;
+        }
+        self.selected_pci_devices = self
+            .pci_devices
+            .iter()
+            .enumerate()
+            .filter(|(_, d)| selected.contains(&d.address))
+            .map(|(i, _)| i)
+            .collect();
+        self.multi_gpu_status = Some(crate::hardware::check_multi_gpu_passthrough_status());
+    }
+
+    /// Load shared folders for the current VM
+    pub fn load_shared_folders(&mut self) {
+        self.shared_folders.clear();
+        self.shared_folder_selected = 0;
+
+        if let Some(vm) = self.selected_vm() {
+            (self.shared_folders, self.shared_folder_method) = crate::vm::load_shared_folders(vm);
+        }
+    }
+
+    /// Add a shared folder, generating a unique mount tag
+    pub fn add_shared_folder(&mut self, host_path: String) {
         // Reject duplicate paths
         if self.shared_folders.iter().any(|f| f.host_path == host_path) {
             return;
@@ -1123,6 +2474,18 @@ impl App {
         }
     }
 
+    /// Check the host setup for the selected PCI devices
+    pub fn check_pci_host_setup(&mut self) -> &PassthroughCheck {
+        let selected: Vec<&PciDevice> = self
+            .selected_pci_devices
+            .iter()
+            .filter_map(|&i| self.pci_devices.get(i))
+            .collect();
+        let check = crate::hardware::check_passthrough_setup(&selected, &self.pci_devices);
+        self.pci_host_check_scroll = 0;
+        self.pci_host_check.insert(check)
+    }
+
     /// Toggle PCI device selection
     pub fn toggle_pci_device(&mut self, index: usize) {
         // Don't allow selecting boot VGA
@@ -1177,26 +2540,59 @@ impl App {
         }
     }
 
-    /// Get launch options based on current state
-    pub fn get_launch_options(&self) -> LaunchOptions {
-        let usb_devices = self
-            .selected_usb_devices
+    /// A VM's launch checked against the host's free memory and the VMs
+    /// already running here, with the next launch's overrides (None when
+    /// checking is off or the VM is remote)
+    pub fn launch_check(&self, vm: &DiscoveredVm) -> Option<crate::hardware::LaunchCheck> {
+        if self.config.launch_resource_check == crate::hardware::ResourceCheck::Off || vm.remote.is_some() {
+            return None;
+        }
+        let running: Vec<(u32, u32)> = self
+            .vms
             .iter()
-            .filter_map(|&i| self.usb_devices.get(i))
-            .map(|d| crate::vm::UsbPassthrough {
-                vendor_id: d.vendor_id,
-                product_id: d.product_id,
-                usb_version: d.usb_version,
-            })
+            .filter(|other| other.remote.is_none() && other.id != vm.id && self.running_vms.contains_key(&other.id))
+            .map(|other| (other.config.memory_mb, other.config.cpu_cores))
             .collect();
+        Some(crate::hardware::LaunchCheck::new(
+            &self.host_resources,
+            crate::hardware::host::available_memory_mb(),
+            &running,
+            self.launch_overrides.memory_mb.unwrap_or(vm.config.memory_mb),
+            self.launch_overrides.cpu_cores.unwrap_or(vm.config.cpu_cores),
+        ))
+    }
+
+    /// Ask to launch the selected VM with `boot_mode`; a suspended VM's
+    /// saved state goes with any boot but resuming it, so that is asked first
+    pub fn confirm_boot(&mut self) {
+        let suspended = self
+            .selected_vm()
+            .is_some_and(|vm| vm.remote.is_none() && crate::vm::suspend::has_saved_state(&vm.path));
+        let action = match self.boot_mode {
+            BootMode::Resume => ConfirmAction::LaunchVm,
+            _ if suspended => ConfirmAction::DiscardSavedState,
+            _ => ConfirmAction::LaunchVm,
+        };
+        self.push_screen(Screen::Confirm(action));
+    }
 
+    /// Get launch options based on current state
+    pub fn get_launch_options(&self) -> LaunchOptions {
         LaunchOptions {
             boot_mode: self.boot_mode.clone(),
             extra_args: Vec::new(),
-            usb_devices,
+            overrides: self.launch_overrides.clone(),
         }
     }
 
+    /// In kiosk mode, say that changes are disabled and return true
+    pub fn kiosk_blocks(&mut self) -> bool {
+        if self.config.kiosk_mode {
+            self.set_status("Not available in kiosk mode");
+        }
+        self.config.kiosk_mode
+    }
+
     /// Set a status message (auto-clears after 5 seconds)
     pub fn set_status(&mut self, msg: impl Into<String>) {
         self.status_message = Some(msg.into());
@@ -1205,7 +2601,9 @@ impl App {
 
     /// Show a detailed error in a scrollable dialog
     pub fn show_error(&mut self, error: impl Into<String>) {
-        self.error_detail = Some(error.into());
+        let error = error.into();
+        tracing::warn!("{}", error);
+        self.error_detail = Some(error);
         self.error_scroll = 0;
         self.push_screen(Screen::ErrorDialog);
     }
@@ -1229,8 +2627,168 @@ impl App {
     pub fn check_background_results(&mut self) {
         // Non-blocking check for results
         while let Ok(result) = self.background_rx.try_recv() {
-            self.loading = false;
+            // Guest agent polling runs alongside other work; don't touch `loading`
+            if !matches!(
+                result,
+                BackgroundResult::GuestInfoQueried { .. }
+                    | BackgroundResult::QmpEvent { .. }
+                    | BackgroundResult::SessionSnapshotTaken { .. }
+                    | BackgroundResult::ScreenshotCaptured { .. }
+                    | BackgroundResult::DiskInspected { .. }
+                    | BackgroundResult::DiskConvertProgress { .. }
+                    | BackgroundResult::HostMigrationProgress { .. }
+                    | BackgroundResult::SuspendProgress { .. }
+                    | BackgroundResult::IsoDownloadProgress { .. }
+                    | BackgroundResult::IsoDownloaded { .. }
+            ) {
+                self.loading = false;
+            }
             match result {
+                BackgroundResult::QmpEvent { vm_id, event } => self.handle_qmp_event(vm_id, event),
+                BackgroundResult::ScreenshotCaptured { vm_id, success } => {
+                    // Reloaded for the info panel if it is showing this VM
+                    if success && self.latest_screenshot.as_ref().is_some_and(|latest| latest.vm_id == vm_id) {
+                        self.latest_screenshot = None;
+                    }
+                }
+                BackgroundResult::SessionSnapshotTaken { vm_id, vm_name, result } => match result {
+                    Ok(name) => {
+                        self.set_status(format!("{} shut down; saved snapshot {}", vm_name, name));
+                        if self.selected_vm().is_some_and(|vm| vm.id == vm_id) {
+                            let _ = self.load_snapshots();
+                        }
+                    }
+                    Err(e) => self.set_status(format!("Failed to snapshot {} after shutdown: {}", vm_name, e)),
+                },
+                BackgroundResult::GuestInfoQueried { vm_id, info } => {
+                    self.guest_queries_pending.remove(&vm_id);
+                    match info {
+                        Some(info) if self.running_vms.contains_key(&vm_id) => {
+                            self.guest_info.insert(vm_id, info);
+                        }
+                        _ => {
+                            self.guest_info.remove(&vm_id);
+                        }
+                    }
+                }
+                BackgroundResult::DiskInspected { path, result } => {
+                    self.apply_disk_inspection(&path, result);
+                }
+                BackgroundResult::DiskConvertProgress { progress } => {
+                    if let Some(ref mut state) = self.convert_disk_state {
+                        state.progress = Some(progress);
+                    }
+                }
+                BackgroundResult::DiskConverted { vm_name, result } => {
+                    self.convert_disk_state = None;
+                    if self.screen == Screen::ConvertDisk {
+                        self.pop_screen();
+                    }
+                    match result {
+                        Ok(path) => {
+                            let _ = self.refresh_vms();
+                            self.set_status(format!(
+                                "Converted disk of {} to {}",
+                                vm_name,
+                                path.file_name().unwrap_or_default().to_string_lossy()
+                            ));
+                        }
+                        Err(e) => self.set_status(format!("Error converting disk: {}", e)),
+                    }
+                }
+                BackgroundResult::DataDiskAdded { vm_name, result } => match result {
+                    Ok(path) => {
+                        let _ = self.refresh_vms();
+                        self.set_status(format!(
+                            "Added {} to {}; the guest sees it from the next start",
+                            path.file_name().unwrap_or_default().to_string_lossy(),
+                            vm_name
+                        ));
+                    }
+                    Err(e) => self.set_status(format!("Error adding data disk: {}", e)),
+                },
+                BackgroundResult::IsoLibraryScanned { isos } => {
+                    let known = isos.iter().filter(|iso| iso.known.is_some()).count();
+                    let total = isos.len();
+                    if let Some(ref mut state) = self.iso_library_state {
+                        state.isos = isos;
+                        state.scanning = false;
+                        state.selected = state.selected.min(total.saturating_sub(1));
+                        self.set_status(format!("{} of {} images identified", known, total));
+                    }
+                }
+                BackgroundResult::BackingChainChanged { result } => {
+                    match result {
+                        Ok(done) => self.set_status(done),
+                        Err(e) => self.set_status(format!("Error: {}", e)),
+                    }
+                    if let Some(ref mut state) = self.backing_chain_state {
+                        state.busy = false;
+                        state.links = crate::vm::backing_chain::load(&state.disk, &self.config.vm_library_path);
+                        state.selected = state.selected.min(state.links.len().saturating_sub(1));
+                    }
+                }
+                BackgroundResult::IsoDownloadProgress { from, done, total } => {
+                    if let Some(ref mut state) = self.wizard_state.as_mut().filter(|s| s.iso_downloading) {
+                        let mb = |bytes: u64| bytes as f64 / 1_048_576.0;
+                        state.iso_download_progress = total.map(|t| done as f32 / t.max(1) as f32).unwrap_or(0.0);
+                        state.iso_download_status = match total {
+                            Some(total) => format!("{:.0} of {:.0} MB from {}", mb(done), mb(total), from),
+                            None => format!("{:.0} MB from {}", mb(done), from),
+                        };
+                    }
+                }
+                BackgroundResult::IsoDownloaded { result } => {
+                    // A cancelled download was already closed
+                    let Some(state) = self.wizard_state.as_mut().filter(|s| s.iso_downloading) else {
+                        continue;
+                    };
+                    state.iso_downloading = false;
+                    state.iso_download_cancel = None;
+                    if self.screen == Screen::CreateWizardDownload {
+                        self.pop_screen();
+                    }
+                    match result {
+                        Ok(path) => {
+                            self.set_status(format!("Downloaded {}", path.display()));
+                            if let Some(ref mut state) = self.wizard_state {
+                                state.iso_path = Some(path);
+                            }
+                            let _ = self.wizard_next_step();
+                        }
+                        Err(e) => self.set_status(format!("Download failed: {}", e)),
+                    }
+                }
+                BackgroundResult::HostMigrationProgress { progress } => {
+                    if let Some(ref mut state) = self.host_migration_state {
+                        state.progress = Some(progress);
+                    }
+                }
+                BackgroundResult::HostMigrated { vm_name, dest, live, result } => {
+                    self.host_migration_state = None;
+                    if self.screen == Screen::HostMigration {
+                        self.pop_screen();
+                    }
+                    match result {
+                        Ok(()) => {
+                            self.refresh_remote_vms();
+                            let verb = if live { "Live-migrated" } else { "Moved" };
+                            self.set_status(format!("{} {} to {}", verb, vm_name, dest));
+                        }
+                        Err(e) => self.set_status(format!("Error migrating {}: {}", vm_name, e)),
+                    }
+                }
+                BackgroundResult::SuspendProgress { vm_name, progress } => {
+                    self.set_status(format!("Suspending {}... {:.0}%", vm_name, progress * 100.0));
+                }
+                BackgroundResult::VmSuspended { vm_name, result } => match result {
+                    Ok(size) => self.set_status(format!(
+                        "Suspended {} ({} saved); launch it to resume",
+                        vm_name,
+                        crate::format::format_size(size)
+                    )),
+                    Err(e) => self.set_status(format!("Failed to suspend {}: {}", vm_name, e)),
+                },
                 BackgroundResult::SnapshotCreated { name, success, error } => {
                     if success {
                         self.set_status(format!("Created snapshot: {}", name));
@@ -1263,10 +2821,159 @@ impl App {
                         self.selected_snapshot = 0;
                     }
                 }
+                BackgroundResult::DiskCompacted { vm_name, success, error } => {
+                    if success {
+                        self.set_status(format!("Compacted disk: {}", vm_name));
+                        // Show the reclaimed space on the dashboard
+                        if let Some(vm) = self.selected_vm().cloned() {
+                            if let Some(ref mut state) = self.storage_state {
+                                if let Some(row) = state.rows.iter_mut().find(|r| r.vm_id == vm.id) {
+                                    *row = crate::vm::storage::measure(&vm);
+                                }
+                            }
+                        }
+                    } else if let Some(e) = error {
+                        self.set_status(format!("Error compacting disk: {}", e));
+                    }
+                }
+                BackgroundResult::StorageMeasured { mut rows } => {
+                    if let Some(ref mut state) = self.storage_state {
+                        crate::vm::storage::sort(&mut rows, state.sort);
+                        let bloated: Vec<u64> = rows.iter().filter_map(|r| r.reclaimable).collect();
+                        state.rows = rows;
+                        if bloated.is_empty() {
+                            self.clear_status();
+                        } else {
+                            self.set_status(format!(
+                                "Compacting {} VM(s) would reclaim ~{} (r on a VM compacts it)",
+                                bloated.len(),
+                                crate::format::format_size(bloated.iter().sum())
+                            ));
+                        }
+                    }
+                }
+                BackgroundResult::DebugBundleCreated { result } => match result {
+                    Ok(path) => self.set_status(format!("Debug bundle written to {}", path.display())),
+                    Err(e) => self.set_status(format!("Error creating debug bundle: {}", e)),
+                },
+                BackgroundResult::SlideshowDemoFailed { vm_name, error } => {
+                    self.set_status(format!("Demo of {} failed: {}", vm_name, error));
+                }
+                BackgroundResult::PluginActionFinished { result } => {
+                    match result {
+                        Ok(message) => self.set_status(message),
+                        Err(e) => self.set_status(format!("Error: {}", e)),
+                    }
+                    // The action may have changed the VM's files
+                    let _ = self.refresh_vms();
+                }
+                BackgroundResult::BatchFinished { report, retry } => {
+                    let report = match (retry, self.batch_report.take()) {
+                        (true, Some(mut previous)) => {
+                            previous.merge_retry(report);
+                            previous
+                        }
+                        _ => report,
+                    };
+                    self.set_status(format!(
+                        "{}: {} succeeded, {} failed",
+                        report.operation.label(),
+                        report.success_count(),
+                        report.failure_count()
+                    ));
+                    self.batch_report = Some(report);
+                    self.batch_report_selected = 0;
+                    self.push_screen(Screen::BatchReport);
+                }
+                BackgroundResult::DiskOperationsRecovered { resumed, error } => {
+                    self.interrupted_disk_ops =
+                        find_interrupted_operations(&self.config.vm_library_path);
+                    if let Some(e) = error {
+                        self.set_status(format!("Error recovering disk operations: {}", e));
+                    } else {
+                        self.set_status(format!(
+                            "Recovered interrupted disk operations ({} resumed)",
+                            resumed
+                        ));
+                    }
+                }
             }
         }
     }
 
+    /// Record the OS found on the wizard's existing disk and switch to its
+    /// profile, unless the user has since picked a different disk
+    fn apply_disk_inspection(&mut self, path: &std::path::Path, result: Result<crate::vm::os_fingerprint::InspectedOs, String>) {
+        let Some(ref mut state) = self.wizard_state else {
+            return;
+        };
+        if state.existing_disk_path.as_deref() != Some(path) {
+            return;
+        }
+        state.inspecting_disk = false;
+
+        let os = match result {
+            Ok(os) => os,
+            Err(e) => {
+                self.set_status(format!("Could not detect the OS on the disk: {}", e));
+                return;
+            }
+        };
+        let label = os.label();
+        let profile_id = crate::vm::os_fingerprint::match_profile(&os);
+        state.detected_os = Some(os);
+
+        let Some(profile) = self.qemu_profiles.get(profile_id).cloned() else {
+            self.set_status(format!("Detected {}", label));
+            return;
+        };
+        if state.selected_os.as_deref() == Some(profile_id) {
+            self.set_status(format!("Detected {}", label));
+            return;
+        }
+        // Show the chosen profile in the OS list
+        if !state.expanded_categories.contains(&profile.category) {
+            state.expanded_categories.push(profile.category.clone());
+        }
+        self.wizard_select_os(profile_id);
+        self.set_status(format!("Detected {}: using the {} profile", label, profile.display_name));
+    }
+
+    /// Load the selected VM's newest boot screenshot for the info panel
+    /// when the selection changes
+    pub fn sync_latest_screenshot(&mut self) {
+        if !crate::ui::widgets::thumbnail::truecolor() {
+            return;
+        }
+        let Some(vm) = self.selected_vm().filter(|vm| vm.remote.is_none()) else {
+            return;
+        };
+        if self.latest_screenshot.as_ref().is_some_and(|latest| latest.vm_id == vm.id) {
+            return;
+        }
+        let image = crate::vm::screenshots::latest(&vm.path)
+            .and_then(|shot| crate::vm::screenshots::load(&shot.path).ok());
+        self.latest_screenshot = Some(LatestScreenshot { vm_id: vm.id.clone(), image });
+    }
+
+    /// Hold the lock on the selected VM while its management menu is open
+    pub fn sync_edit_lock(&mut self) {
+        let managing = self.screen == Screen::Management || self.screen_stack.contains(&Screen::Management);
+        let wanted = self
+            .selected_vm()
+            .filter(|vm| managing && vm.remote.is_none())
+            .map(|vm| vm.path.clone());
+        if self.edit_lock.as_ref().map(|l| &l.vm_dir) == wanted.as_ref() {
+            return;
+        }
+        // Release the previous VM before locking the next
+        self.edit_lock = None;
+        self.edit_lock = wanted.map(|vm_dir| match crate::vm::instance_lock::acquire_for_editing(&vm_dir) {
+            Ok(lock) => EditLock { vm_dir, lock, held_by: None },
+            Err(owner) => EditLock { vm_dir, lock: None, held_by: Some(owner) },
+        });
+    }
+
     /// Non-blocking check for VM status updates from background thread.
     /// Consumes all pending messages, keeping only the latest result.
     pub fn check_vm_status(&mut self) {
@@ -1275,41 +2982,381 @@ impl App {
             latest = Some(processes);
         }
         if let Some(processes) = latest {
-            self.running_vms = self.match_running_vms(&processes);
+            // A VM that reported shutting down stays stopped while QEMU exits
+            let mut exited = Vec::new();
+            self.shut_down_vms.retain(|id, pid| {
+                let alive = processes.iter().any(|p| p.host.is_none() && p.pid == *pid);
+                if !alive {
+                    exited.push(id.clone());
+                }
+                alive
+            });
+            let mut running = crate::vm::lifecycle::match_running_vms(&self.vms, &processes);
+            running.retain(|id, pid| self.shut_down_vms.get(id) != Some(pid));
+            let was_running = std::mem::replace(&mut self.running_vms, running);
+            self.record_sessions(&was_running);
+            exited.extend(
+                was_running
+                    .keys()
+                    .filter(|id| !self.running_vms.contains_key(*id) && !self.shut_down_vms.contains_key(*id))
+                    .cloned(),
+            );
+            for vm_id in exited {
+                self.clean_up_after_exit(&vm_id);
+            }
+            self.schedule_boot_screenshots(&was_running);
             // Clean up stopping_vms for VMs that have actually stopped
             self.stopping_vms.retain(|id, _| self.running_vms.contains_key(id));
+            self.guest_info.retain(|id, _| self.running_vms.contains_key(id));
+            self.query_guest_agents();
+            self.watch_qmp_events();
+
+            // Show other machines sharing the library that our locks are live
+            for vm in self.vms.iter().filter(|vm| vm.remote.is_none() && self.running_vms.contains_key(&vm.id)) {
+                crate::vm::instance_lock::refresh(&vm.path);
+            }
+            if let Some(EditLock { ref vm_dir, lock: Some(_), .. }) = self.edit_lock {
+                crate::vm::instance_lock::refresh(vm_dir);
+            }
+        }
+    }
+
+    /// Record VMs that started or stopped since the last status check
+    fn record_sessions(&mut self, was_running: &HashMap<String, u32>) {
+        let now = chrono::Utc::now().timestamp();
+        let mut installed = Vec::new();
+        for vm in self.vms.iter().filter(|vm| vm.remote.is_none()) {
+            let result = match (was_running.contains_key(&vm.id), self.running_vms.contains_key(&vm.id)) {
+                (false, true) => launch_history::record_running(&vm.path, now),
+                (true, false) => {
+                    // An install session that left a bootable disk ends install mode
+                    if crate::vm::first_boot::complete_if_bootable(vm).unwrap_or(false) {
+                        installed.push(vm.id.clone());
+                    }
+                    launch_history::record_stop(&vm.path, now)
+                }
+                _ => continue,
+            };
+            if result.is_ok() {
+                self.launch_history.insert(vm.id.clone(), launch_history::load(&vm.path));
+            }
+        }
+        for vm_id in installed {
+            self.finish_install(&vm_id);
+        }
+    }
+
+    /// Capture the screen of local VMs that have just started, once they
+    /// have had time to boot
+    fn schedule_boot_screenshots(&self, was_running: &HashMap<String, u32>) {
+        use crate::vm::screenshots::CAPTURE_DELAY;
+
+        for vm in &self.vms {
+            let Some(&pid) = self.running_vms.get(&vm.id) else {
+                continue;
+            };
+            if was_running.contains_key(&vm.id)
+                || vm.remote.is_some()
+                || !crate::vm::qmp::is_configured(&vm.config.raw_script)
+            {
+                continue;
+            }
+            // VMs already running when vm-curator started aren't booting
+            let Some(age) = crate::vm::monitor::process_age_secs(pid) else {
+                continue;
+            };
+            let Some(wait) = CAPTURE_DELAY.checked_sub(std::time::Duration::from_secs_f64(age.max(0.0))) else {
+                continue;
+            };
+
+            let tx = self.background_tx.clone();
+            let vm_id = vm.id.clone();
+            let vm_dir = vm.path.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(wait);
+                // The QMP socket takes one client at a time
+                let mut result = crate::vm::screenshots::capture(&vm_dir);
+                for _ in 0..2 {
+                    if result.is_ok() || crate::vm::monitor::process_stats(pid).is_none() {
+                        break;
+                    }
+                    std::thread::sleep(std::time::Duration::from_secs(2));
+                    result = crate::vm::screenshots::capture(&vm_dir);
+                }
+                if let Err(ref e) = result {
+                    tracing::debug!(dir = %vm_dir.display(), "boot screenshot: {:#}", e);
+                }
+                let _ = tx.send(BackgroundResult::ScreenshotCaptured { vm_id, success: result.is_ok() });
+            });
+        }
+    }
+
+    /// Tidy up after a local VM's QEMU process has gone
+    ///
+    /// Removes the lock and QMP sockets a crashed QEMU leaves behind and,
+    /// when session snapshots are on, snapshots the disk in the background.
+    fn clean_up_after_exit(&mut self, vm_id: &str) {
+        self.qmp_watchers.remove(vm_id);
+        let Some(vm) = self.vms.iter().find(|vm| vm.id == vm_id && vm.remote.is_none()) else {
+            return;
+        };
+        crate::vm::instance_lock::owner(&vm.path);
+        crate::vm::qmp::remove_stale_sockets(&vm.path);
+
+        let keep = self.config.session_snapshots as usize;
+        // Demo runs go back to their snapshot, and a suspended disk must
+        // stay as the saved state left it
+        if keep == 0
+            || self.slideshow_state.is_some()
+            || !vm.config.supports_snapshots()
+            || crate::vm::suspend::has_saved_state(&vm.path)
+        {
+            return;
+        }
+        let Some(disk) = vm.config.primary_disk().map(|d| d.path.clone()) else {
+            return;
+        };
+        let meta = SnapshotMeta::for_vm(vm, "", SnapshotContext::Session);
+        let vm_id = vm.id.clone();
+        let vm_name = vm.display_name();
+        let tx = self.background_tx.clone();
+        std::thread::spawn(move || {
+            let result = crate::vm::snapshot::take_session_snapshot(&disk, keep, meta).map_err(|e| format!("{:#}", e));
+            let _ = tx.send(BackgroundResult::SessionSnapshotTaken { vm_id, vm_name, result });
+        });
+    }
+
+    /// Update a VM whose launch script was switched to normal boot
+    pub fn finish_install(&mut self, vm_id: &str) {
+        let Some(vm) = self.vms.iter_mut().find(|vm| vm.id == vm_id) else {
+            return;
+        };
+        vm.config.install_pending = false;
+        if let Ok(content) = std::fs::read_to_string(&vm.launch_script) {
+            vm.config.raw_script = content;
+        }
+        let name = vm.display_name();
+        self.set_status(format!("{} is installed and now boots from its disk", name));
+    }
+
+    /// VMs a batch snapshot would apply to (those with snapshot-capable disks)
+    pub fn batch_snapshot_targets(&self) -> Vec<&DiscoveredVm> {
+        self.vms
+            .iter()
+            .filter(|vm| vm.remote.is_none() && vm.config.supports_snapshots())
+            .collect()
+    }
+
+    /// Compact the selected VM's disk in the background
+    pub fn start_compaction(&mut self) {
+        let Some(vm) = self.selected_vm().cloned() else {
+            return;
+        };
+        if self.running_vms.contains_key(&vm.id) {
+            self.set_status("Error: Cannot compact disk while VM is running. Please shut down the VM first.");
+            return;
+        }
+        if let Err(e) = crate::vm::suspend::ensure_not_suspended(&vm.path) {
+            self.set_status(format!("Error: {}", e));
+            return;
         }
+        let Some(disk) = vm.config.primary_disk() else {
+            return;
+        };
+        let disk_path = disk.path.clone();
+        let vm_name = vm.display_name();
+        let tx = self.background_tx.clone();
+        self.loading = true;
+        self.set_status(format!("Compacting disk for {}...", vm_name));
+
+        std::thread::spawn(move || {
+            let result = crate::vm::disk_ops::compact_disk(&disk_path);
+            let _ = tx.send(BackgroundResult::DiskCompacted {
+                vm_name,
+                success: result.is_ok(),
+                error: result.err().map(|e| e.to_string()),
+            });
+        });
+    }
+
+    /// Save the selected VM's state to disk and stop it, in the background
+    pub fn start_suspend(&mut self) {
+        let Some(vm) = self.selected_vm().cloned() else {
+            return;
+        };
+        if !self.running_vms.contains_key(&vm.id) {
+            self.set_status(format!("{} is not running", vm.display_name()));
+            return;
+        }
+        let vm_name = vm.display_name();
+        let tx = self.background_tx.clone();
+        self.loading = true;
+        self.set_status(format!("Suspending {}...", vm_name));
+
+        std::thread::spawn(move || {
+            let progress_tx = tx.clone();
+            let progress_name = vm_name.clone();
+            let result = crate::vm::suspend::suspend(&vm, |progress| {
+                let _ = progress_tx.send(BackgroundResult::SuspendProgress { vm_name: progress_name.clone(), progress });
+            });
+            let _ = tx.send(BackgroundResult::VmSuspended {
+                vm_name,
+                result: result.map_err(|e| format!("{:#}", e)),
+            });
+        });
+    }
+
+    /// Collect a debug bundle for the selected VM in the background
+    pub fn start_debug_bundle(&mut self) {
+        let vm = self.selected_vm().filter(|vm| vm.remote.is_none()).cloned();
+        let tx = self.background_tx.clone();
+        self.loading = true;
+        self.set_status("Collecting debug bundle...");
+
+        std::thread::spawn(move || {
+            let result = crate::debug_bundle::create(vm.as_ref()).map_err(|e| format!("{:#}", e));
+            let _ = tx.send(BackgroundResult::DebugBundleCreated { result });
+        });
     }
 
-    /// Match QEMU processes against known VMs using the process working directory.
+    /// Run a batch operation in the background
     ///
-    /// Launch scripts run QEMU from the VM's directory, so /proc/<pid>/cwd
-    /// reliably identifies which VM a process belongs to — unlike disk filenames
-    /// which are often generic (e.g., "disk.qcow2").
-    fn match_running_vms(&self, processes: &[QemuProcess]) -> HashMap<String, u32> {
-        let mut result = HashMap::new();
+    /// With `only` set, just those VM IDs are processed and the result is
+    /// merged into the current report (used to retry failed items).
+    pub fn start_batch(&mut self, operation: BatchOperation, only: Option<Vec<String>>) {
+        let retry = only.is_some();
+        let targets: Vec<DiscoveredVm> = self
+            .batch_snapshot_targets()
+            .into_iter()
+            .filter(|vm| only.as_ref().is_none_or(|ids| ids.contains(&vm.id)))
+            .cloned()
+            .collect();
+        if targets.is_empty() {
+            self.set_status("No VMs to process");
+            return;
+        }
+
+        // Running VMs hold a write lock on their disks
+        let running: HashSet<String> = self.running_vms.keys().cloned().collect();
+
+        self.loading = true;
+        self.set_status(format!("{} on {} VMs...", operation.label(), targets.len()));
+
+        let tx = self.background_tx.clone();
+        std::thread::spawn(move || {
+            let report = crate::vm::batch::run_batch(&operation, &targets, |vm| {
+                running.contains(&vm.id).then(|| "VM is running".to_string())
+            });
+            let _ = tx.send(BackgroundResult::BatchFinished { report, retry });
+        });
+    }
+
+    /// Poll the guest agent of each running VM that has one, in the background
+    fn query_guest_agents(&mut self) {
         for vm in &self.vms {
-            for proc in processes {
-                if let Some(ref cwd) = proc.cwd {
-                    // cwd is available — use it as the authoritative match
-                    if cwd == &vm.path {
-                        result.insert(vm.id.clone(), proc.pid);
+            if !vm.config.guest_agent
+                || vm.remote.is_some()
+                || !self.running_vms.contains_key(&vm.id)
+                || self.guest_queries_pending.contains(&vm.id)
+            {
+                continue;
+            }
+            self.guest_queries_pending.insert(vm.id.clone());
+
+            let tx = self.background_tx.clone();
+            let vm_id = vm.id.clone();
+            let vm_dir = vm.path.clone();
+            std::thread::spawn(move || {
+                let info = crate::vm::guest_agent::query_guest_info(&vm_dir).ok();
+                let _ = tx.send(BackgroundResult::GuestInfoQueried { vm_id, info });
+            });
+        }
+    }
+
+    /// Follow the QMP events of local VMs that started since the last check
+    fn watch_qmp_events(&mut self) {
+        self.qmp_watchers.retain(|id, pid| self.running_vms.get(id) == Some(pid));
+        for vm in &self.vms {
+            let Some(&pid) = self.running_vms.get(&vm.id) else {
+                continue;
+            };
+            if vm.remote.is_some()
+                || !crate::vm::qmp::has_event_socket(&vm.config.raw_script)
+                || self.qmp_watchers.contains_key(&vm.id)
+            {
+                continue;
+            }
+            self.qmp_watchers.insert(vm.id.clone(), pid);
+
+            let tx = self.background_tx.clone();
+            let vm_id = vm.id.clone();
+            let vm_dir = vm.path.clone();
+            std::thread::spawn(move || {
+                // QEMU opens the socket a moment after starting
+                let socket = crate::vm::qmp::events_socket_path(&vm_dir);
+                for _ in 0..50 {
+                    if socket.exists() {
                         break;
                     }
+                    std::thread::sleep(std::time::Duration::from_millis(100));
+                }
+                // VMs started before the socket was added have none
+                let _ = crate::vm::qmp::watch_events(&vm_dir, |event| {
+                    let _ = tx.send(BackgroundResult::QmpEvent { vm_id: vm_id.clone(), event });
+                });
+            });
+        }
+    }
+
+    /// Tell the user about a VM's QMP event and update its running state
+    fn handle_qmp_event(&mut self, vm_id: String, event: crate::vm::qmp::QmpEvent) {
+        use crate::vm::qmp::QmpEvent;
+
+        let name = self
+            .vms
+            .iter()
+            .find(|vm| vm.id == vm_id)
+            .map_or_else(|| vm_id.clone(), |vm| vm.display_name());
+        tracing::info!(vm = %vm_id, ?event, "QMP event");
+        match event {
+            QmpEvent::Shutdown { guest, reason } => {
+                // Shown as stopped now rather than at the next process check
+                if let Some(&pid) = self.running_vms.get(&vm_id) {
+                    let was_running = self.running_vms.clone();
+                    self.running_vms.remove(&vm_id);
+                    self.shut_down_vms.insert(vm_id.clone(), pid);
+                    self.record_sessions(&was_running);
+                    self.stopping_vms.remove(&vm_id);
+                    self.guest_info.remove(&vm_id);
+                }
+                if guest {
+                    self.set_status(format!("{} powered off", name));
+                } else if reason != "host-qmp-quit" {
+                    // Quits through QMP (suspend, migration) report their own outcome
+                    self.set_status(format!("{} stopped", name));
+                }
+            }
+            QmpEvent::Reset { guest } => {
+                self.guest_info.remove(&vm_id);
+                if guest {
+                    self.set_status(format!("{} restarted", name));
                 } else {
-                    // No cwd available (permissions?) — fall back to full disk path in cmdline
-                    if let Some(disk) = vm.config.primary_disk() {
-                        if let Some(disk_path_str) = disk.path.to_str() {
-                            if !disk_path_str.is_empty() && proc.cmdline.contains(disk_path_str) {
-                                result.insert(vm.id.clone(), proc.pid);
-                                break;
-                            }
-                        }
-                    }
+                    self.set_status(format!("{} was reset", name));
                 }
             }
+            QmpEvent::BlockIoError { device, operation, nospace } => {
+                let cause = if nospace { " (host disk full)" } else { "" };
+                tracing::warn!(vm = %vm_id, %device, %operation, nospace, "disk I/O error");
+                self.set_status(format!("Disk error in {}: {} failed on {}{}", name, operation, device, cause));
+            }
+            QmpEvent::GuestPanicked => {
+                self.show_error(format!(
+                    "{}'s guest OS crashed (QEMU received a guest panic).\n\n\
+                     QEMU leaves the VM paused; its launch log (l) may have details before you stop it.",
+                    name
+                ));
+            }
         }
-        result
     }
 
     /// Get PID of the currently selected VM if it's running.
@@ -1327,7 +3374,10 @@ impl App {
         // Determine file extensions to filter by based on mode
         let extensions: &[&str] = match mode {
             FileBrowserMode::Iso => &[".iso", ".ISO"],
-            FileBrowserMode::Disk => &[".qcow2", ".QCOW2", ".qcow", ".QCOW"],
+            FileBrowserMode::Disk => &[
+                ".qcow2", ".QCOW2", ".qcow", ".QCOW", ".vmdk", ".VMDK", ".vhd", ".VHD",
+                ".vhdx", ".VHDX", ".vdi", ".VDI", ".img", ".IMG", ".raw", ".RAW",
+            ],
             FileBrowserMode::Directory => &[],
         };
 
@@ -1432,6 +3482,42 @@ impl App {
         }
     }
 
+    /// Open the details screen for the selected VM
+    pub fn open_details(&mut self) {
+        let Some(vm) = self.selected_vm() else {
+            return;
+        };
+        // Remote VMs keep their folder on the other machine
+        self.notes = vm.remote.is_none().then(|| notes::load(&vm.path));
+        self.details_scroll = 0;
+        self.push_screen(Screen::DetailedInfo);
+    }
+
+    /// Load the selected VM's notes into the editor
+    pub fn load_notes_into_editor(&mut self) {
+        if let Some(vm) = self.selected_vm() {
+            self.script_editor_lines = notes::read(&vm.path).lines().map(String::from).collect();
+            if self.script_editor_lines.is_empty() {
+                self.script_editor_lines.push(String::new());
+            }
+            self.script_editor_cursor = (0, 0);
+            self.script_editor_modified = false;
+            self.script_editor_h_scroll = 0;
+            self.raw_script_scroll = 0;
+        }
+    }
+
+    /// Save the editor content to the selected VM's notes
+    pub fn save_notes_from_editor(&mut self) -> Result<()> {
+        let vm_path = self.selected_vm()
+            .map(|vm| vm.path.clone())
+            .ok_or_else(|| anyhow::anyhow!("No VM selected"))?;
+        notes::write(&vm_path, &self.script_editor_lines.join("\n"))?;
+        self.notes = Some(notes::load(&vm_path));
+        self.script_editor_modified = false;
+        Ok(())
+    }
+
     /// Save the editor content back to the launch.sh file
     pub fn save_script_from_editor(&mut self) -> Result<()> {
         // Get the launch script path before we need mutable access
@@ -1489,13 +3575,121 @@ impl App {
         state.qemu_config.cpu_cores = self.config.default_cpu_cores;
         state.qemu_config.enable_kvm = self.config.default_enable_kvm;
         state.qemu_config.display = self.config.default_display.clone();
+        state.qemu_config.fit_to_host(&self.host_resources);
+        state.qemu_config.audio_backend = self.preferred_audio_backend(&state.qemu_config.emulator);
 
         self.wizard_state = Some(state);
         self.push_screen(Screen::CreateWizard);
     }
 
-    /// Cancel the wizard and return to main menu
+    /// Start the wizard for an existing disk image and open the picker
+    ///
+    /// The VM name comes from the file name and the profile from OS
+    /// fingerprinting once an image is chosen.
+    pub fn start_import_disk(&mut self) {
+        self.start_create_wizard();
+        if let Some(ref mut state) = self.wizard_state {
+            state.use_existing_disk = true;
+        }
+        self.load_file_browser(FileBrowserMode::Disk);
+        self.push_screen(Screen::FileBrowser);
+    }
+
+    /// Start the wizard with an install image, and the OS profile it was
+    /// identified as (if any) already chosen
+    pub fn start_wizard_with_iso(&mut self, iso: PathBuf, profile: Option<&str>) {
+        self.start_create_wizard();
+        let profile = profile.filter(|id| self.qemu_profiles.get(id).is_some());
+        if let Some(id) = profile {
+            self.wizard_select_os(id);
+        }
+        if let Some(ref mut state) = self.wizard_state {
+            state.iso_path = Some(iso);
+        }
+        // On to the ISO step, where the image is shown picked
+        if profile.is_some() {
+            let _ = self.wizard_next_step();
+        }
+    }
+
+    /// Download one of the selected profile's ISO sources into the
+    /// downloads folder, showing the download screen meanwhile
+    pub fn start_iso_download(&mut self, source: crate::metadata::qemu_profiles::IsoSource) {
+        let dir = dirs::download_dir().unwrap_or_else(|| self.config.iso_library());
+        let cancel = Arc::new(AtomicBool::new(false));
+        let Some(ref mut state) = self.wizard_state else {
+            return;
+        };
+        state.iso_downloading = true;
+        state.iso_download_progress = 0.0;
+        state.iso_download_status = format!("Connecting for {}...", source.name);
+        state.iso_download_cancel = Some(cancel.clone());
+        self.push_screen(Screen::CreateWizardDownload);
+
+        let tx = self.background_tx.clone();
+        std::thread::spawn(move || {
+            let result = crate::vm::iso_download::download(&source, &dir, &cancel, |from, done, total| {
+                let _ = tx.send(BackgroundResult::IsoDownloadProgress { from: from.to_string(), done, total });
+            });
+            let _ = tx.send(BackgroundResult::IsoDownloaded { result: result.map_err(|e| format!("{:#}", e)) });
+        });
+    }
+
+    /// Stop the running ISO download
+    pub fn cancel_iso_download(&mut self) {
+        if let Some(ref mut state) = self.wizard_state {
+            if let Some(cancel) = state.iso_download_cancel.take() {
+                cancel.store(true, Ordering::Relaxed);
+            }
+            state.iso_downloading = false;
+            state.iso_download_progress = 0.0;
+        }
+        if self.screen == Screen::CreateWizardDownload {
+            self.pop_screen();
+        }
+    }
+
+    /// Open the wizard filled in with the saved draft
+    pub fn resume_wizard_draft(&mut self) {
+        let Some(draft) = self.wizard_draft.take() else {
+            return;
+        };
+        crate::recovery::discard_draft();
+        self.start_create_wizard();
+        if let Some(ref os_id) = draft.selected_os {
+            if self.qemu_profiles.get(os_id).is_some() {
+                self.wizard_select_os(os_id);
+            }
+        }
+        let library_path = self.config.vm_library_path.clone();
+        if let Some(ref mut state) = self.wizard_state {
+            draft.apply_to(state);
+            state.update_folder_name(&library_path);
+        }
+        self.set_status(format!("Resumed the draft of {}", draft.summary()));
+    }
+
+    /// Throw the saved draft away
+    pub fn discard_wizard_draft(&mut self) {
+        self.wizard_draft = None;
+        crate::recovery::discard_draft();
+    }
+
+    /// Cancel the wizard, keeping its input as a draft, and return to main menu
     pub fn cancel_wizard(&mut self) {
+        if let Some(ref state) = self.wizard_state {
+            match crate::recovery::save_wizard_draft(state) {
+                Ok(true) => self.set_status("Draft saved; vm-curator offers to resume it at the next start"),
+                Ok(false) => {}
+                Err(e) => self.set_status(format!("Could not save the draft: {}", e)),
+            }
+        }
+        self.close_wizard();
+    }
+
+    /// Close the wizard and return to main menu
+    pub fn close_wizard(&mut self) {
+        self.cancel_iso_download();
         self.wizard_state = None;
         // Pop all wizard-related screens
         while matches!(
@@ -1557,13 +3751,23 @@ impl App {
                     .or_else(|| self.qemu_profiles.get(prev_id).map(|p| p.display_name.clone()))
             });
 
+        // Host audio backend for the profile's emulator
+        let audio_backend = self.qemu_profiles.get(os_id)
+            .map(|p| self.preferred_audio_backend(&p.emulator));
+
         if let Some(ref mut state) = self.wizard_state {
             state.selected_os = Some(os_id.to_string());
             state.custom_os = None;
+            // Answer-file flavour depends on the OS
+            state.unattended = None;
 
             // Apply profile settings
             if let Some(profile) = self.qemu_profiles.get(os_id) {
                 state.apply_profile(profile);
+                state.qemu_config.fit_to_host(&self.host_resources);
+                if let Some(backend) = audio_backend {
+                    state.qemu_config.audio_backend = backend;
+                }
 
                 // Only update VM name if:
                 // 1. Name is empty, OR
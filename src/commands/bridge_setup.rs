@@ -0,0 +1,129 @@
+//! Bridge networking setup
+//!
+//! The bridge backend needs three things only root can provide: a bridge
+//! interface, a qemu-bridge-helper allowed to attach tap devices to it
+//! (cap_net_admin or setuid), and the bridge listed in the helper's ACL.
+//! This plans whichever steps are missing and runs them in a single pkexec
+//! (or sudo) call, after the user has seen the exact commands.
+
+use anyhow::{bail, Result};
+use std::process::{Command, Stdio};
+
+use super::qemu_system::NetworkCapabilities;
+
+/// qemu-bridge-helper's access control list
+pub const BRIDGE_ACL_PATH: &str = "/etc/qemu/bridge.conf";
+
+/// One privileged setup step
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetupStep {
+    /// What the step does, for the consent screen
+    pub description: String,
+    /// Shell command run as root
+    pub command: String,
+}
+
+/// Work out the steps still needed to use `bridge` (empty when done)
+pub fn plan_bridge_setup(caps: &NetworkCapabilities, bridge: &str) -> Result<Vec<SetupStep>> {
+    let Some(ref helper) = caps.bridge_helper_path else {
+        bail!("qemu-bridge-helper not found; install your distribution's QEMU networking package");
+    };
+    if !is_valid_interface_name(bridge) {
+        bail!("Invalid bridge name: {}", bridge);
+    }
+
+    let mut steps = Vec::new();
+
+    if !caps.system_bridges.iter().any(|b| b == bridge) {
+        if is_command_available("nmcli") {
+            // NetworkManager's shared mode adds NAT and DHCP, and survives reboots
+            steps.push(SetupStep {
+                description: format!("Create bridge {} with NAT and DHCP (NetworkManager, persistent)", bridge),
+                command: format!(
+                    "nmcli connection add type bridge ifname {0} con-name {0} ipv4.method shared ipv6.method ignore && nmcli connection up {0}",
+                    bridge
+                ),
+            });
+        } else {
+            steps.push(SetupStep {
+                description: format!("Create bridge {} (until reboot; attach an uplink or DHCP server yourself)", bridge),
+                command: format!("ip link add {0} type bridge && ip link set {0} up", bridge),
+            });
+        }
+    }
+
+    if !caps.bridge_helper_configured {
+        steps.push(SetupStep {
+            description: "Let qemu-bridge-helper create tap devices".to_string(),
+            command: format!("setcap cap_net_admin+ep {}", helper.display()),
+        });
+    }
+
+    let allowed = caps
+        .bridge_acl
+        .as_ref()
+        .is_some_and(|acl| acl.iter().any(|b| b == bridge || b == "all"));
+    if !allowed {
+        steps.push(SetupStep {
+            description: format!("Allow {} in {}", bridge, BRIDGE_ACL_PATH),
+            command: format!(
+                "mkdir -p /etc/qemu && (grep -qsx 'allow {0}' {1} || echo 'allow {0}' >> {1})",
+                bridge, BRIDGE_ACL_PATH
+            ),
+        });
+    }
+
+    Ok(steps)
+}
+
+/// Run the steps as root with one authentication prompt
+///
+/// Prefers pkexec (polkit agent), falling back to sudo on the terminal.
+/// The caller must have left the TUI so prompts are visible.
+pub fn run_bridge_setup(steps: &[SetupStep]) -> Result<()> {
+    if steps.is_empty() {
+        return Ok(());
+    }
+
+    let script = std::iter::once("set -e".to_string())
+        .chain(steps.iter().map(|s| s.command.clone()))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let tool = if is_command_available("pkexec") {
+        "pkexec"
+    } else if is_command_available("sudo") {
+        "sudo"
+    } else {
+        bail!("Neither pkexec nor sudo is available");
+    };
+
+    let status = Command::new(tool)
+        .args(["sh", "-c", &script])
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()?;
+
+    if !status.success() {
+        bail!("Setup failed or authentication was cancelled ({})", status);
+    }
+    Ok(())
+}
+
+/// Linux interface names: at most 15 characters, nothing a shell would expand
+pub(super) fn is_valid_interface_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 15
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+pub(super) fn is_command_available(name: &str) -> bool {
+    Command::new("which")
+        .arg(name)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
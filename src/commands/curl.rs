@@ -0,0 +1,100 @@
+//! HTTP downloads
+//!
+//! Provides a wrapper around curl for the few online features, so
+//! vm-curator needs no HTTP or TLS stack of its own.
+
+use anyhow::{bail, Context, Result};
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Seconds a download may take before curl gives up
+const MAX_TIME_SECS: u32 = 30;
+
+/// Seconds a file download may stall (under 1 KB/s) before it is given up
+const STALL_SECS: u32 = 30;
+
+fn user_agent() -> String {
+    format!("vm-curator/{} ({})", env!("CARGO_PKG_VERSION"), env!("CARGO_PKG_REPOSITORY"))
+}
+
+/// Fetch a URL, following redirects, and return the body
+pub fn get(url: &str) -> Result<Vec<u8>> {
+    let user_agent = user_agent();
+    let output = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location"])
+        .args(["--max-time", &MAX_TIME_SECS.to_string()])
+        .args(["--user-agent", &user_agent])
+        .arg(url)
+        .output()
+        .context("Failed to run curl (is it installed?)")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("Failed to download {}: {}", url, stderr.trim());
+    }
+
+    Ok(output.stdout)
+}
+
+/// Size of a download from a HEAD request, if the server gives one
+fn content_length(url: &str) -> Option<u64> {
+    let output = Command::new("curl")
+        .args(["--fail", "--silent", "--head", "--location", "--max-time", "15"])
+        .args(["--user-agent", &user_agent()])
+        .arg(url)
+        .output()
+        .ok()?;
+    // After redirects the last response's header counts
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .filter_map(|(_, value)| value.trim().parse().ok())
+        .next_back()
+}
+
+/// Download a URL to a file, calling `progress` with the bytes written so
+/// far (and the total, if known) a few times a second
+///
+/// Setting `cancel` stops the download. A server that doesn't answer or
+/// stalls fails the download, so the caller can try a mirror; the partial
+/// file is removed either way.
+pub fn download(url: &str, dest: &Path, cancel: &AtomicBool, mut progress: impl FnMut(u64, Option<u64>)) -> Result<()> {
+    let total = content_length(url);
+    let mut child = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location"])
+        .args(["--connect-timeout", "15", "--speed-limit", "1024", "--speed-time", &STALL_SECS.to_string()])
+        .args(["--user-agent", &user_agent()])
+        .arg("--output")
+        .arg(dest)
+        .arg(url)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run curl (is it installed?)")?;
+
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            let _ = child.kill();
+            let _ = child.wait();
+            let _ = std::fs::remove_file(dest);
+            bail!("Download cancelled");
+        }
+        if let Some(status) = child.try_wait()? {
+            if status.success() {
+                return Ok(());
+            }
+            let mut stderr = String::new();
+            if let Some(mut pipe) = child.stderr.take() {
+                let _ = pipe.read_to_string(&mut stderr);
+            }
+            let _ = std::fs::remove_file(dest);
+            bail!("Failed to download {}: {}", url, stderr.trim());
+        }
+        progress(std::fs::metadata(dest).map(|m| m.len()).unwrap_or(0), total);
+        std::thread::sleep(Duration::from_millis(250));
+    }
+}
@@ -0,0 +1,153 @@
+//! Guest disk mounting
+//!
+//! Mounts a VM's disk image on the host with libguestfs' guestmount, which
+//! runs the filesystem code in a small appliance and exposes it over FUSE,
+//! so no root or loop devices are needed. This is how files get in and out
+//! of retro guests that have no network. Mounts are read-only unless the
+//! VM is stopped: two writers on one filesystem would corrupt it.
+
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use super::bridge_setup::is_command_available;
+use crate::vm::qemu_config::DiskConfig;
+
+/// A disk image mounted on the host
+#[derive(Debug, Clone)]
+pub struct GuestMount {
+    /// Directory the guest filesystem appears under
+    pub mountpoint: PathBuf,
+    pub read_only: bool,
+}
+
+/// Mount `disk` under a per-VM directory in the runtime dir
+///
+/// Tries libguestfs OS inspection first, then the first partition, then
+/// the whole device, which covers partitioned DOS/Windows disks and
+/// unpartitioned floppy-style images that inspection does not recognise.
+pub fn mount_disk(disk: &DiskConfig, vm_id: &str, read_only: bool) -> Result<GuestMount> {
+    if disk.remote.is_some() {
+        bail!("Remote disks cannot be mounted on the host");
+    }
+    if !is_command_available("guestmount") {
+        bail!("guestmount not found; install libguestfs (guestfs-tools or libguestfs-tools)");
+    }
+    if !disk.path.exists() {
+        bail!("Disk image not found: {}", disk.path.display());
+    }
+
+    let mountpoint = dirs::runtime_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("vm-curator")
+        .join("mnt")
+        .join(vm_id);
+    std::fs::create_dir_all(&mountpoint)
+        .with_context(|| format!("Failed to create {}", mountpoint.display()))?;
+
+    let format = disk.format.qemu_name();
+
+    let mut last_error = String::new();
+    for target in [["-i", ""], ["-m", "/dev/sda1"], ["-m", "/dev/sda"]] {
+        let mut cmd = Command::new("guestmount");
+        cmd.arg("-a").arg(&disk.path).arg(format!("--format={}", format));
+        if read_only {
+            cmd.arg("--ro");
+        }
+        cmd.arg(target[0]);
+        if !target[1].is_empty() {
+            cmd.arg(target[1]);
+        }
+        let output = cmd
+            .arg(&mountpoint)
+            .stdin(Stdio::null())
+            .output()
+            .context("Failed to run guestmount")?;
+        if output.status.success() {
+            return Ok(GuestMount { mountpoint, read_only });
+        }
+        last_error = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    }
+
+    let _ = std::fs::remove_dir(&mountpoint);
+    bail!("Failed to mount disk: {}", last_error.lines().last().unwrap_or("unknown error"))
+}
+
+/// Unmount and remove the mountpoint
+///
+/// guestunmount waits for the appliance to flush writes back to the
+/// image; plain fusermount is the fallback on systems without it.
+pub fn unmount(mount: &GuestMount) -> Result<()> {
+    let status = if is_command_available("guestunmount") {
+        Command::new("guestunmount").arg(&mount.mountpoint).status()
+    } else {
+        Command::new("fusermount").arg("-u").arg(&mount.mountpoint).status()
+    }
+    .context("Failed to run guestunmount")?;
+
+    if !status.success() {
+        bail!("Failed to unmount {} (is a file still open?)", mount.mountpoint.display());
+    }
+    let _ = std::fs::remove_dir(&mount.mountpoint);
+    Ok(())
+}
+
+/// Copy a file or directory tree into `dest_dir`, keeping its name.
+/// Symbolic links are copied as links (skipped where the host has none),
+/// never followed, so a link out of the guest or in a loop is harmless.
+pub fn copy_into(source: &Path, dest_dir: &Path) -> Result<PathBuf> {
+    let Some(name) = source.file_name() else {
+        bail!("Cannot copy {}", source.display());
+    };
+    let dest = dest_dir.join(name);
+    if dest.symlink_metadata().is_ok() {
+        bail!("{} already exists", dest.display());
+    }
+    copy_recursive(source, &dest)?;
+    Ok(dest)
+}
+
+fn copy_recursive(source: &Path, dest: &Path) -> Result<()> {
+    let file_type = std::fs::symlink_metadata(source)
+        .with_context(|| format!("Failed to read {}", source.display()))?
+        .file_type();
+    if file_type.is_symlink() {
+        #[cfg(unix)]
+        {
+            let target = std::fs::read_link(source)?;
+            std::os::unix::fs::symlink(&target, dest).with_context(|| format!("Failed to create {}", dest.display()))?;
+        }
+    } else if file_type.is_dir() {
+        std::fs::create_dir(dest).with_context(|| format!("Failed to create {}", dest.display()))?;
+        for entry in std::fs::read_dir(source)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &dest.join(entry.file_name()))?;
+        }
+    } else {
+        std::fs::copy(source, dest).with_context(|| format!("Failed to copy {}", source.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_into_keeps_symlinks() {
+        let root = std::env::temp_dir().join(format!("vm-curator-guest-copy-{}", std::process::id()));
+        let (tree, dest) = (root.join("tree"), root.join("dest"));
+        std::fs::create_dir_all(tree.join("sub")).unwrap();
+        std::fs::create_dir_all(&dest).unwrap();
+        std::fs::write(tree.join("sub").join("file.txt"), "hello").unwrap();
+        std::os::unix::fs::symlink("..", tree.join("sub").join("loop")).unwrap();
+        std::os::unix::fs::symlink("/etc/passwd", tree.join("outside")).unwrap();
+
+        let copied = copy_into(&tree, &dest).unwrap();
+        assert_eq!(std::fs::read_to_string(copied.join("sub").join("file.txt")).unwrap(), "hello");
+        assert_eq!(std::fs::read_link(copied.join("sub").join("loop")).unwrap(), Path::new(".."));
+        assert_eq!(std::fs::read_link(copied.join("outside")).unwrap(), Path::new("/etc/passwd"));
+        assert!(copy_into(&tree, &dest).is_err());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
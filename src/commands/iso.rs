@@ -0,0 +1,123 @@
+//! ISO image helpers
+//!
+//! Wraps whichever of genisoimage/mkisofs/xorriso is installed to build
+//! small data discs (cloud-init seeds, kickstart media), and bsdtar/
+//! xorriso/isoinfo to pull individual files out of installation ISOs, and
+//! sha256sum to identify install media.
+
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+use std::process::Command;
+
+/// Tools that can build an ISO from a list of files, in order of preference
+const BUILD_TOOLS: &[&str] = &["genisoimage", "mkisofs", "xorriso"];
+
+/// Tools that can extract a single file from an ISO, in order of preference
+const EXTRACT_TOOLS: &[&str] = &["bsdtar", "xorriso", "isoinfo"];
+
+fn is_installed(tool: &str) -> bool {
+    Command::new("which")
+        .arg(tool)
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+/// Find an installed tool that can build data ISOs
+pub fn find_builder() -> Option<&'static str> {
+    BUILD_TOOLS.iter().copied().find(|tool| is_installed(tool))
+}
+
+/// Find an installed tool that can extract files from ISOs
+pub fn find_extractor() -> Option<&'static str> {
+    EXTRACT_TOOLS.iter().copied().find(|tool| is_installed(tool))
+}
+
+/// Build a Joliet/Rock Ridge ISO at `dest` containing `files` at its root
+pub fn build_data_iso(dest: &Path, volume_label: &str, files: &[&Path]) -> Result<()> {
+    let Some(tool) = find_builder() else {
+        bail!("No ISO tool found (install genisoimage or xorriso)");
+    };
+
+    let mut cmd = Command::new(tool);
+    if tool == "xorriso" {
+        cmd.args(["-as", "mkisofs"]);
+    }
+    cmd.arg("-output")
+        .arg(dest)
+        .args(["-volid", volume_label, "-joliet", "-rock"])
+        .args(files);
+
+    let output = cmd
+        .output()
+        .with_context(|| format!("Failed to run {}", tool))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("{} failed: {}", tool, stderr.trim());
+    }
+
+    Ok(())
+}
+
+/// Extract a single file (absolute path inside the ISO) to `dest`
+pub fn extract_file(iso: &Path, inner_path: &str, dest: &Path) -> Result<()> {
+    let Some(tool) = find_extractor() else {
+        bail!("No ISO extraction tool found (install libarchive/bsdtar or xorriso)");
+    };
+
+    let output = match tool {
+        "bsdtar" => Command::new(tool)
+            .arg("-xOf")
+            .arg(iso)
+            .arg(inner_path.trim_start_matches('/'))
+            .output(),
+        "xorriso" => Command::new(tool)
+            .args(["-osirrox", "on", "-indev"])
+            .arg(iso)
+            .args(["-extract", inner_path])
+            .arg(dest)
+            .output(),
+        _ => Command::new(tool)
+            .arg("-R")
+            .arg("-i")
+            .arg(iso)
+            .args(["-x", inner_path])
+            .output(),
+    }
+    .with_context(|| format!("Failed to run {}", tool))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("{} could not extract {}: {}", tool, inner_path, stderr.trim());
+    }
+
+    // bsdtar and isoinfo write the file to stdout
+    if tool != "xorriso" {
+        if output.stdout.is_empty() {
+            bail!("{} not found in {}", inner_path, iso.display());
+        }
+        std::fs::write(dest, &output.stdout)
+            .with_context(|| format!("Failed to write {}", dest.display()))?;
+    } else if !dest.exists() {
+        bail!("{} not found in {}", inner_path, iso.display());
+    }
+
+    Ok(())
+}
+
+/// SHA-256 of an image, as lowercase hex (via coreutils `sha256sum`)
+pub fn sha256(path: &Path) -> Result<String> {
+    let output = Command::new("sha256sum")
+        .arg(path)
+        .output()
+        .context("Failed to run sha256sum")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("sha256sum failed: {}", stderr.trim());
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    match stdout.split_whitespace().next() {
+        Some(sum) if sum.len() == 64 => Ok(sum.to_string()),
+        _ => bail!("Unexpected sha256sum output: {}", stdout.trim()),
+    }
+}
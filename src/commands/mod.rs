@@ -1,2 +1,8 @@
+pub mod bridge_setup;
+pub mod curl;
+pub mod guest_mount;
+pub mod iso;
 pub mod qemu_img;
 pub mod qemu_system;
+pub mod ssh;
+pub mod tap_setup;
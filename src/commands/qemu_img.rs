@@ -1,8 +1,9 @@
 //! QEMU disk image operations
 //!
-//! Provides wrappers around qemu-img for disk creation and format detection.
+//! Provides wrappers around qemu-img for disk creation, conversion, and format detection.
 
 use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 use std::process::Command;
 
@@ -13,10 +14,21 @@ fn path_to_str(path: &Path) -> Result<&str> {
 }
 
 /// Create a new qcow2 disk image
-pub fn create_disk(path: &Path, size: &str) -> Result<()> {
+pub fn create_disk(path: &Path, size: &str, tuning: &Qcow2Options) -> Result<()> {
+    create_image(path, "qcow2", size, tuning)
+}
+
+/// Create a new, blank disk image in the given format
+pub fn create_image(path: &Path, format: &str, size: &str, tuning: &Qcow2Options) -> Result<()> {
+    tuning.validate(false)?;
     let path_str = path_to_str(path)?;
-    let output = Command::new("qemu-img")
-        .args(["create", "-f", "qcow2", path_str, size])
+    let mut cmd = Command::new("qemu-img");
+    cmd.args(["create", "-f", format]);
+    if let Some(options) = tuning.option_string(format) {
+        cmd.args(["-o", &options]);
+    }
+    let output = cmd
+        .args([path_str, size])
         .output()
         .context("Failed to run qemu-img create")?;
 
@@ -28,11 +40,450 @@ pub fn create_disk(path: &Path, size: &str) -> Result<()> {
     Ok(())
 }
 
+/// Convert (or rewrite) a disk image into `dest` with the given output format.
+/// With `compress`, qcow2 output is written with compressed clusters.
+pub fn convert_image(source: &Path, dest: &Path, format: &str, compress: bool, tuning: &Qcow2Options) -> Result<()> {
+    let compress = compress && format == "qcow2";
+    tuning.validate(compress)?;
+    let source_str = path_to_str(source)?;
+    let dest_str = path_to_str(dest)?;
+    let mut cmd = Command::new("qemu-img");
+    cmd.args(["convert", "-O", format]);
+    if compress {
+        cmd.arg("-c");
+    }
+    if let Some(options) = tuning.option_string(format) {
+        cmd.args(["-o", &options]);
+    }
+    let output = cmd
+        .args([source_str, dest_str])
+        .output()
+        .context("Failed to run qemu-img convert")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("Failed to convert disk: {}", stderr);
+    }
+
+    Ok(())
+}
+
+/// Cluster sizes offered for new qcow2 images, in KiB
+pub const CLUSTER_SIZES_KIB: &[u32] = &[4, 16, 32, 64, 128, 256, 512, 1024, 2048];
+
+/// qcow2 creation options, passed to qemu-img with `-o`
+///
+/// Large clusters mean less metadata to look up on big modern guests;
+/// small ones waste less space on tiny retro disks. Preallocation saves
+/// allocating on first write, zstd compresses faster than zlib, and compat
+/// 0.10 images still open in QEMU older than 1.1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Qcow2Options {
+    pub cluster_kib: u32,
+    pub preallocation: Preallocation,
+    pub compression: Compression,
+    pub compat: Compat,
+}
+
+impl Default for Qcow2Options {
+    /// qemu-img's own defaults
+    fn default() -> Self {
+        Self {
+            cluster_kib: 64,
+            preallocation: Preallocation::Off,
+            compression: Compression::Zlib,
+            compat: Compat::V1_1,
+        }
+    }
+}
+
+/// How much of the image is allocated up front
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preallocation {
+    /// Grow as the guest writes
+    #[default]
+    Off,
+    /// Write the cluster tables, still sparse (qcow2 only)
+    Metadata,
+    /// Reserve the whole size with fallocate, without writing it
+    Falloc,
+    /// Write out the whole size
+    Full,
+}
+
+impl Preallocation {
+    pub const ALL: &'static [Preallocation] = &[Self::Off, Self::Metadata, Self::Falloc, Self::Full];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Metadata => "metadata",
+            Self::Falloc => "falloc",
+            Self::Full => "full",
+        }
+    }
+}
+
+/// Algorithm for compressed qcow2 clusters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    /// Readable by any QEMU
+    #[default]
+    Zlib,
+    /// Faster and smaller; needs QEMU 5.1 or later
+    Zstd,
+}
+
+impl Compression {
+    pub const ALL: &'static [Compression] = &[Self::Zlib, Self::Zstd];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Zlib => "zlib",
+            Self::Zstd => "zstd",
+        }
+    }
+}
+
+/// qcow2 version written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Compat {
+    /// qcow2 v2, for QEMU before 1.1
+    #[serde(rename = "0.10")]
+    V0_10,
+    /// qcow2 v3
+    #[default]
+    #[serde(rename = "1.1")]
+    V1_1,
+}
+
+impl Compat {
+    pub const ALL: &'static [Compat] = &[Self::V1_1, Self::V0_10];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::V0_10 => "0.10",
+            Self::V1_1 => "1.1",
+        }
+    }
+}
+
+impl Qcow2Options {
+    /// The `-o` value for an image of `format`, None when everything is at
+    /// qemu-img's defaults. Only preallocation applies to raw images, which
+    /// are sparse anyway, so metadata preallocation leaves them as they are.
+    pub fn option_string(&self, format: &str) -> Option<String> {
+        let defaults = Self::default();
+        let mut options = Vec::new();
+        match format {
+            "qcow2" => {
+                if self.compat != defaults.compat {
+                    options.push(format!("compat={}", self.compat.name()));
+                }
+                if self.cluster_kib != defaults.cluster_kib {
+                    options.push(format!("cluster_size={}K", self.cluster_kib));
+                }
+                if self.compression != defaults.compression {
+                    options.push(format!("compression_type={}", self.compression.name()));
+                }
+                if self.preallocation != Preallocation::Off {
+                    options.push(format!("preallocation={}", self.preallocation.name()));
+                }
+            }
+            "raw" if matches!(self.preallocation, Preallocation::Falloc | Preallocation::Full) => {
+                options.push(format!("preallocation={}", self.preallocation.name()));
+            }
+            _ => {}
+        }
+        (!options.is_empty()).then(|| options.join(","))
+    }
+
+    /// Refuse combinations qemu-img would reject, with a clearer message
+    pub fn validate(&self, compress: bool) -> Result<()> {
+        if !CLUSTER_SIZES_KIB.contains(&self.cluster_kib) {
+            bail!("Unsupported cluster size: {} KiB", self.cluster_kib);
+        }
+        if self.compat == Compat::V0_10 && self.compression == Compression::Zstd {
+            bail!("zstd compression needs compat 1.1");
+        }
+        if compress && self.preallocation != Preallocation::Off {
+            bail!("Compressed images can't be preallocated");
+        }
+        Ok(())
+    }
+
+    /// One line for dialogs, e.g. "64 KiB clusters, no preallocation, zlib, compat 1.1"
+    pub fn summary(&self) -> String {
+        let clusters = if self.cluster_kib >= 1024 {
+            format!("{} MiB", self.cluster_kib / 1024)
+        } else {
+            format!("{} KiB", self.cluster_kib)
+        };
+        let preallocation = match self.preallocation {
+            Preallocation::Off => "no preallocation".to_string(),
+            other => format!("{} preallocation", other.name()),
+        };
+        format!(
+            "{} clusters, {}, {}, compat {}",
+            clusters,
+            preallocation,
+            self.compression.name(),
+            self.compat.name()
+        )
+    }
+}
+
+/// Format, sizes and layering reported by `qemu-img info`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImageInfo {
+    pub filename: String,
+    /// Format string like "qcow2", "vmdk", "vpc", "raw"
+    pub format: String,
+    /// Size of the disk as the guest sees it
+    pub virtual_size: u64,
+    /// Bytes actually allocated on the host
+    pub actual_size: u64,
+    /// Image this one is an overlay on, as named in its header
+    pub backing_file: Option<String>,
+    /// Persistent dirty bitmaps (qcow2), as used for incremental backups
+    pub bitmaps: Vec<Bitmap>,
+    /// Cluster size, compression type and compat level of a qcow2 image
+    /// (preallocation isn't recorded in the image and reads as off)
+    pub qcow2: Option<Qcow2Options>,
+}
+
+/// A persistent dirty bitmap in a qcow2 image
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bitmap {
+    pub name: String,
+    /// Bytes of disk per bit
+    #[serde(default)]
+    pub granularity: u64,
+    /// e.g. "auto", "in-use"
+    #[serde(default)]
+    pub flags: Vec<String>,
+}
+
+/// One image in `qemu-img info --output=json`
+#[derive(Debug, Deserialize)]
+struct ImageJson {
+    #[serde(default)]
+    filename: String,
+    format: String,
+    #[serde(rename = "virtual-size", default)]
+    virtual_size: u64,
+    #[serde(rename = "actual-size", default)]
+    actual_size: u64,
+    #[serde(rename = "cluster-size")]
+    cluster_size: Option<u64>,
+    #[serde(rename = "backing-filename")]
+    backing_filename: Option<String>,
+    #[serde(rename = "format-specific")]
+    format_specific: Option<FormatSpecific>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct FormatSpecific {
+    #[serde(default)]
+    data: FormatData,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct FormatData {
+    #[serde(default)]
+    bitmaps: Vec<Bitmap>,
+    compat: Option<String>,
+    #[serde(rename = "compression-type")]
+    compression_type: Option<String>,
+}
+
+impl From<ImageJson> for ImageInfo {
+    fn from(json: ImageJson) -> Self {
+        let data = json.format_specific.unwrap_or_default().data;
+        let qcow2 = (json.format == "qcow2").then(|| Qcow2Options {
+            cluster_kib: json.cluster_size.map_or(64, |bytes| (bytes / 1024) as u32),
+            preallocation: Preallocation::Off,
+            compression: match data.compression_type.as_deref() {
+                Some("zstd") => Compression::Zstd,
+                _ => Compression::Zlib,
+            },
+            compat: match data.compat.as_deref() {
+                Some("0.10") => Compat::V0_10,
+                _ => Compat::V1_1,
+            },
+        });
+        ImageInfo {
+            filename: json.filename,
+            format: json.format,
+            virtual_size: json.virtual_size,
+            actual_size: json.actual_size,
+            backing_file: json.backing_filename,
+            bitmaps: data.bitmaps,
+            qcow2,
+        }
+    }
+}
+
+/// `qemu-img info --output=json`, read with `-U` so a running VM's lock
+/// doesn't get in the way
+fn info_json(path: &Path, backing_chain: bool) -> Option<Vec<u8>> {
+    let path_str = path_to_str(path).ok()?;
+    let mut cmd = Command::new("qemu-img");
+    cmd.args(["info", "-U", "--output=json"]);
+    if backing_chain {
+        cmd.arg("--backing-chain");
+    }
+    let output = cmd.arg(path_str).output().ok()?;
+    output.status.success().then_some(output.stdout)
+}
+
+/// Read an image's format and sizes (also while a running VM holds its lock)
+pub fn image_info(path: &Path) -> Option<ImageInfo> {
+    let json: ImageJson = serde_json::from_slice(&info_json(path, false)?).ok()?;
+    Some(json.into())
+}
+
+/// An image and the images it is layered on, the given one first and its
+/// base last
+pub fn image_chain(path: &Path) -> Option<Vec<ImageInfo>> {
+    parse_image_chain(&info_json(path, true)?)
+}
+
+/// `qemu-img info --backing-chain --output=json`, which prints an array
+/// even for a single image
+fn parse_image_chain(json: &[u8]) -> Option<Vec<ImageInfo>> {
+    let images: Vec<ImageJson> = serde_json::from_slice(json).ok()?;
+    (!images.is_empty()).then(|| images.into_iter().map(ImageInfo::from).collect())
+}
+
+/// Bytes allocated on the host by a whole chain
+pub fn chain_actual_size(chain: &[ImageInfo]) -> u64 {
+    chain.iter().map(|image| image.actual_size).sum()
+}
+
+/// Like [`convert_image`], reporting progress (0.0 - 1.0) as qemu-img runs
+pub fn convert_image_with_progress<F>(
+    source: &Path,
+    dest: &Path,
+    format: &str,
+    compress: bool,
+    tuning: &Qcow2Options,
+    mut on_progress: F,
+) -> Result<()>
+where
+    F: FnMut(f32),
+{
+    use std::io::Read;
+    use std::process::Stdio;
+
+    let compress = compress && format == "qcow2";
+    tuning.validate(compress)?;
+    let source_str = path_to_str(source)?;
+    let dest_str = path_to_str(dest)?;
+    let mut cmd = Command::new("qemu-img");
+    cmd.args(["convert", "-p", "-O", format]);
+    if compress {
+        cmd.arg("-c");
+    }
+    if let Some(options) = tuning.option_string(format) {
+        cmd.args(["-o", &options]);
+    }
+    let mut child = cmd
+        .args([source_str, dest_str])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run qemu-img convert")?;
+
+    // -p redraws "    (42.00/100%)" with carriage returns
+    if let Some(mut stdout) = child.stdout.take() {
+        let mut line = Vec::new();
+        let mut byte = [0u8; 1];
+        while stdout.read(&mut byte).unwrap_or(0) == 1 {
+            if byte[0] == b'\r' || byte[0] == b'\n' {
+                let text = String::from_utf8_lossy(&line);
+                if let Some(percent) = text
+                    .trim()
+                    .strip_prefix('(')
+                    .and_then(|t| t.split('/').next())
+                    .and_then(|p| p.parse::<f32>().ok())
+                {
+                    on_progress(percent / 100.0);
+                }
+                line.clear();
+            } else {
+                line.push(byte[0]);
+            }
+        }
+    }
+
+    let output = child.wait_with_output().context("Failed to run qemu-img convert")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("Failed to convert disk: {}", stderr);
+    }
+
+    Ok(())
+}
+
+/// Change the backing image of an overlay
+///
+/// With `backing` None the overlay is made standalone. A safe rebase first
+/// copies whatever differs between the old and new backing into the
+/// overlay, so the guest sees the same data; `unsafe_only` just rewrites
+/// the name in the header, for a backing file that was moved or renamed.
+pub fn rebase(image: &Path, backing: Option<(&str, &str)>, unsafe_only: bool) -> Result<()> {
+    let image_str = path_to_str(image)?;
+    let mut cmd = Command::new("qemu-img");
+    cmd.arg("rebase");
+    if unsafe_only {
+        cmd.arg("-u");
+    }
+    match backing {
+        Some((file, format)) => cmd.args(["-b", file, "-F", format]),
+        None => cmd.args(["-b", ""]),
+    };
+    let output = cmd.arg(image_str).output().context("Failed to run qemu-img rebase")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("Failed to rebase disk: {}", stderr);
+    }
+
+    Ok(())
+}
+
+/// Write an overlay's changes into its backing image, emptying the overlay
+pub fn commit(image: &Path) -> Result<()> {
+    let image_str = path_to_str(image)?;
+    let output = Command::new("qemu-img")
+        .args(["commit", image_str])
+        .output()
+        .context("Failed to run qemu-img commit")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("Failed to commit disk: {}", stderr);
+    }
+
+    Ok(())
+}
+
 /// Detect the format of a disk image (returns format string like "qcow2", "raw", etc.)
 pub fn detect_disk_format(path: &Path) -> Option<String> {
+    image_info(path).map(|info| info.format)
+}
+
+/// Bytes a qcow2 copy of an image would need, from `qemu-img measure`.
+/// Unused, leaked and snapshot-only clusters are left out, so this is
+/// what compacting the image would leave (before compression).
+pub fn measure_required(path: &Path) -> Option<u64> {
     let path_str = path_to_str(path).ok()?;
     let output = Command::new("qemu-img")
-        .args(["info", "--output=json", path_str])
+        .args(["measure", "--output=json", "-O", "qcow2", path_str])
         .output()
         .ok()?;
 
@@ -40,8 +491,91 @@ pub fn detect_disk_format(path: &Path) -> Option<String> {
         return None;
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let json: serde_json::Value = serde_json::from_str(&stdout).ok()?;
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).ok()?;
+    json["required"].as_u64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_image_chain() {
+        let json = br#"[
+            {
+                "virtual-size": 8589934592,
+                "filename": "win98.qcow2",
+                "cluster-size": 65536,
+                "format": "qcow2",
+                "actual-size": 209715200,
+                "format-specific": {
+                    "type": "qcow2",
+                    "data": {
+                        "compat": "1.1",
+                        "bitmaps": [{"flags": ["auto"], "name": "backup-0", "granularity": 65536}],
+                        "refcount-bits": 16,
+                        "corrupt": false
+                    }
+                },
+                "full-backing-filename": "/vms/bases/win98-base.qcow2",
+                "backing-filename": "../bases/win98-base.qcow2",
+                "backing-filename-format": "qcow2",
+                "dirty-flag": false
+            },
+            {
+                "virtual-size": 8589934592,
+                "filename": "/vms/bases/win98-base.qcow2",
+                "format": "qcow2",
+                "actual-size": 1288490188,
+                "format-specific": {"type": "qcow2", "data": {"compat": "1.1"}},
+                "dirty-flag": false
+            }
+        ]"#;
+        let chain = parse_image_chain(json).unwrap();
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain[0].backing_file.as_deref(), Some("../bases/win98-base.qcow2"));
+        assert_eq!(chain[0].bitmaps[0].name, "backup-0");
+        assert_eq!(chain[0].bitmaps[0].flags, vec!["auto"]);
+        assert_eq!(chain[0].qcow2, Some(Qcow2Options::default()));
+        assert_eq!(chain[1].backing_file, None);
+        assert!(chain[1].bitmaps.is_empty());
+        assert_eq!(chain_actual_size(&chain), 209715200 + 1288490188);
+
+        // A raw image has no format-specific data
+        let raw = parse_image_chain(br#"[{"virtual-size": 1474560, "filename": "boot.img", "format": "raw", "actual-size": 1474560}]"#).unwrap();
+        assert_eq!((raw[0].format.as_str(), raw[0].actual_size), ("raw", 1474560));
+
+        assert_eq!(parse_image_chain(b"[]"), None);
+        assert_eq!(parse_image_chain(b"qemu-img: Could not open"), None);
+    }
+
+    #[test]
+    fn test_qcow2_option_string() {
+        let defaults = Qcow2Options::default();
+        assert_eq!(defaults.option_string("qcow2"), None);
+        assert!(defaults.validate(true).is_ok());
+
+        let modern = Qcow2Options {
+            cluster_kib: 2048,
+            preallocation: Preallocation::Metadata,
+            compression: Compression::Zstd,
+            compat: Compat::V1_1,
+        };
+        assert_eq!(
+            modern.option_string("qcow2").as_deref(),
+            Some("cluster_size=2048K,compression_type=zstd,preallocation=metadata")
+        );
+        assert_eq!(modern.summary(), "2 MiB clusters, metadata preallocation, zstd, compat 1.1");
+        // Raw images are sparse already; only full allocation means anything
+        assert_eq!(modern.option_string("raw"), None);
+        let full = Qcow2Options { preallocation: Preallocation::Full, ..defaults };
+        assert_eq!(full.option_string("raw").as_deref(), Some("preallocation=full"));
+        assert_eq!(full.option_string("vmdk"), None);
+        assert!(full.validate(true).is_err());
 
-    json["format"].as_str().map(|s| s.to_string())
+        let old = Qcow2Options { compat: Compat::V0_10, cluster_kib: 4, ..defaults };
+        assert_eq!(old.option_string("qcow2").as_deref(), Some("compat=0.10,cluster_size=4K"));
+        assert!(Qcow2Options { compression: Compression::Zstd, ..old }.validate(false).is_err());
+        assert!(Qcow2Options { cluster_kib: 3, ..defaults }.validate(false).is_err());
+    }
 }
@@ -3,8 +3,13 @@
 //! Provides utilities for checking QEMU availability and capabilities.
 
 use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::sync::Mutex;
+
+use super::tap_setup::{detect_tap_manager, list_tap_devices, TapDevice, TapManager};
 
 /// Get QEMU version information
 pub fn get_qemu_version(emulator: &str) -> Result<String> {
@@ -19,11 +24,22 @@ pub fn get_qemu_version(emulator: &str) -> Result<String> {
 
 /// Check if QEMU emulator is available
 pub fn is_emulator_available(emulator: &str) -> bool {
-    Command::new("which")
-        .arg(emulator)
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
+    find_in_path(emulator).is_some()
+}
+
+/// Where a program is on PATH (as `name.exe` on Windows)
+pub fn find_in_path(program: &str) -> Option<PathBuf> {
+    let file = format!("{}{}", program, std::env::consts::EXE_SUFFIX);
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path).map(|dir| dir.join(&file)).find(|path| path.is_file())
+}
+
+/// The command name of a qemu-system-* file ("qemu-system-i386.exe" is
+/// qemu-system-i386 on Windows)
+fn emulator_name(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_str()?;
+    let name = name.strip_suffix(std::env::consts::EXE_SUFFIX).unwrap_or(name);
+    (name.starts_with("qemu-system-") && !name.contains('.')).then(|| name.to_string())
 }
 
 /// List available QEMU emulators on the system
@@ -35,6 +51,7 @@ pub fn list_available_emulators() -> Vec<String> {
         "qemu-system-m68k",
         "qemu-system-arm",
         "qemu-system-aarch64",
+        "qemu-system-riscv64",
     ];
 
     emulators
@@ -44,11 +61,225 @@ pub fn list_available_emulators() -> Vec<String> {
         .collect()
 }
 
+/// Where a QEMU build was found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QemuSource {
+    /// A directory on PATH
+    Path,
+    /// One of the configured `qemu_prefixes`
+    Prefix,
+    /// Inside a Flatpak app, run through `flatpak run`
+    Flatpak(String),
+}
+
+/// A qemu-system-* binary
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QemuBinary {
+    /// Command name (e.g. "qemu-system-i386")
+    pub emulator: String,
+    pub path: PathBuf,
+    pub source: QemuSource,
+}
+
+impl QemuBinary {
+    /// Where it came from, for lists
+    pub fn source_label(&self) -> String {
+        match self.source {
+            QemuSource::Path => "PATH".to_string(),
+            QemuSource::Prefix => "custom prefix".to_string(),
+            QemuSource::Flatpak(ref app) => format!("Flatpak {}", app),
+        }
+    }
+}
+
+/// Flatpak installations, system-wide and per user
+fn flatpak_app_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![PathBuf::from("/var/lib/flatpak/app")];
+    if let Some(data) = dirs::data_dir() {
+        dirs.push(data.join("flatpak/app"));
+    }
+    dirs
+}
+
+/// The Flatpak app a binary belongs to (`.../flatpak/app/<id>/...`)
+fn flatpak_app(path: &Path) -> Option<String> {
+    let mut components = path.components().map(|c| c.as_os_str().to_string_lossy());
+    components.find(|c| c == "flatpak")?;
+    (components.next()? == "app").then_some(())?;
+    components.next().map(|app| app.to_string())
+}
+
+/// Every qemu-system-* on PATH, under `prefixes` (or their `bin`) and in
+/// Flatpak apps
+pub fn find_qemu_binaries(prefixes: &[PathBuf]) -> Vec<QemuBinary> {
+    let mut dirs: Vec<(PathBuf, QemuSource)> = std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).map(|dir| (dir, QemuSource::Path)).collect())
+        .unwrap_or_default();
+    for prefix in prefixes {
+        dirs.push((prefix.join("bin"), QemuSource::Prefix));
+        dirs.push((prefix.clone(), QemuSource::Prefix));
+    }
+    for apps in flatpak_app_dirs() {
+        let Ok(entries) = std::fs::read_dir(&apps) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let app = entry.file_name().to_string_lossy().to_string();
+            dirs.push((entry.path().join("current/active/files/bin"), QemuSource::Flatpak(app)));
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let mut binaries = Vec::new();
+    for (dir, source) in dirs {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut found: Vec<(PathBuf, String)> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file())
+            .filter_map(|path| emulator_name(&path).map(|emulator| (path, emulator)))
+            .collect();
+        found.sort();
+        for (path, emulator) in found {
+            // /bin and /usr/bin are often the same directory
+            if !seen.insert(std::fs::canonicalize(&path).unwrap_or_else(|_| path.clone())) {
+                continue;
+            }
+            binaries.push(QemuBinary { emulator, path, source: source.clone() });
+        }
+    }
+    binaries
+}
+
+/// Versions already asked for, by binary
+static VERSIONS: Lazy<Mutex<HashMap<PathBuf, Option<String>>>> = Lazy::new(Default::default);
+
+/// Version number of a QEMU binary (a path, or a command name on PATH),
+/// asked once and remembered
+pub fn binary_version(binary: &Path) -> Option<String> {
+    if let Some(version) = VERSIONS.lock().ok().and_then(|versions| versions.get(binary).cloned()) {
+        return version;
+    }
+    let output = match flatpak_app(binary) {
+        Some(app) => {
+            let emulator = binary.file_name()?.to_string_lossy().to_string();
+            Command::new("flatpak")
+                .args(["run", &format!("--command={}", emulator), &app, "--version"])
+                .stderr(Stdio::null())
+                .output()
+        }
+        None => Command::new(binary).arg("--version").stderr(Stdio::null()).output(),
+    };
+    let version = output
+        .ok()
+        .and_then(|output| parse_qemu_version(&String::from_utf8_lossy(&output.stdout)));
+    if let Ok(mut versions) = VERSIONS.lock() {
+        versions.insert(binary.to_path_buf(), version.clone());
+    }
+    version
+}
+
+/// The version number in `--version` output ("QEMU emulator version 8.2.2 (...)")
+fn parse_qemu_version(output: &str) -> Option<String> {
+    let rest = output.lines().next()?.split("version ").nth(1)?;
+    rest.split_whitespace().next().map(String::from)
+}
+
+/// Directory to put first on PATH so launch scripts run `binary`
+///
+/// Flatpak builds can't run outside their sandbox, so they get a small
+/// script in the cache directory that goes through `flatpak run`.
+pub fn binary_dir(binary: &Path) -> Result<PathBuf> {
+    let Some(app) = flatpak_app(binary) else {
+        return binary.parent().map(Path::to_path_buf).context("QEMU binary has no directory");
+    };
+    let emulator = binary.file_name().context("QEMU binary has no name")?.to_string_lossy().to_string();
+    let dir = dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("vm-curator/qemu-shims")
+        .join(&app);
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let shim = dir.join(&emulator);
+    std::fs::write(
+        &shim,
+        format!(
+            "#!/bin/sh\n# Runs {emulator} from the {app} Flatpak (written by vm-curator)\n\
+             exec flatpak run --filesystem=host --device=all --share=network --command={emulator} {app} \"$@\"\n"
+        ),
+    )
+    .with_context(|| format!("Failed to write {}", shim.display()))?;
+    crate::fs::set_mode(&shim, 0o755)?;
+    Ok(dir)
+}
+
 /// Check KVM availability
 pub fn is_kvm_available() -> bool {
     Path::new("/dev/kvm").exists()
 }
 
+/// Hardware acceleration for QEMU, by host OS
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Accelerator {
+    /// KVM (Linux)
+    Kvm,
+    /// Windows Hypervisor Platform
+    Whpx,
+}
+
+impl Accelerator {
+    /// The accelerator for this host's OS
+    pub fn host() -> Self {
+        if cfg!(windows) {
+            Accelerator::Whpx
+        } else {
+            Accelerator::Kvm
+        }
+    }
+
+    /// Name for `-accel` and `accel=`
+    pub fn name(self) -> &'static str {
+        match self {
+            Accelerator::Kvm => "kvm",
+            Accelerator::Whpx => "whpx",
+        }
+    }
+
+    /// Name for display
+    pub fn label(self) -> &'static str {
+        match self {
+            Accelerator::Kvm => "KVM",
+            Accelerator::Whpx => "WHPX",
+        }
+    }
+}
+
+/// Whether the host's accelerator can be used
+pub fn is_accel_available() -> bool {
+    match Accelerator::host() {
+        Accelerator::Kvm => is_kvm_available(),
+        Accelerator::Whpx => *WHPX_AVAILABLE,
+    }
+}
+
+/// Whether QEMU was built with WHPX and a hypervisor is running (the
+/// Windows Hypervisor Platform feature turned on)
+static WHPX_AVAILABLE: Lazy<bool> = Lazy::new(|| {
+    let built_in = help_output("qemu-system-x86_64", &["-accel", "help"]).is_some_and(|help| accel_listed(&help, "whpx"));
+    built_in
+        && Command::new("powershell")
+            .args(["-NoProfile", "-Command", "(Get-CimInstance Win32_ComputerSystem).HypervisorPresent"])
+            .stdin(Stdio::null())
+            .output()
+            .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).trim() == "True")
+});
+
+/// Whether `-accel help` output lists an accelerator
+fn accel_listed(help: &str, name: &str) -> bool {
+    help.lines().skip(1).any(|line| line.trim() == name)
+}
+
 /// Get supported display backends for a QEMU emulator
 ///
 /// Runs `<emulator> -display help` and parses the output to get
@@ -86,6 +317,104 @@ pub fn get_supported_displays(emulator: &str) -> Vec<String> {
     displays
 }
 
+/// Get the device names (and aliases) a QEMU emulator supports
+///
+/// Parses `<emulator> -device help`, whose entries look like
+/// `name "e1000", bus PCI, alias "e1000-82540em", desc "..."`.
+pub fn get_supported_devices(emulator: &str) -> Option<Vec<String>> {
+    let text = help_output(emulator, &["-device", "help"])?;
+
+    let quoted = |line: &str, key: &str| -> Option<String> {
+        let rest = &line[line.find(key)? + key.len()..];
+        rest.split('"').next().map(str::to_string)
+    };
+
+    let mut devices = Vec::new();
+    for line in text.lines() {
+        if let Some(name) = quoted(line, "name \"") {
+            devices.push(name);
+        }
+        if let Some(alias) = quoted(line, "alias \"") {
+            devices.push(alias);
+        }
+    }
+    Some(devices)
+}
+
+/// Get the machine types (and aliases like `q35`) a QEMU emulator supports
+///
+/// Parses `<emulator> -machine help`: one machine per line after the
+/// "Supported machines are:" header.
+pub fn get_supported_machines(emulator: &str) -> Option<Vec<String>> {
+    let text = help_output(emulator, &["-machine", "help"])?;
+
+    Some(
+        text.lines()
+            .filter(|line| !line.starts_with("Supported machines"))
+            .filter_map(|line| line.split_whitespace().next())
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+/// Get the host audio drivers (`-audiodev` backends) a QEMU emulator supports
+///
+/// Parses `<emulator> -audiodev help`: one driver per line after the
+/// "Available audio drivers:" header.
+pub fn get_supported_audiodevs(emulator: &str) -> Option<Vec<String>> {
+    let text = help_output(emulator, &["-audiodev", "help"])?;
+
+    Some(
+        text.lines()
+            .filter(|line| !line.starts_with("Available"))
+            .filter_map(|line| line.split_whitespace().next())
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+/// Get the CPU models a QEMU emulator supports, with their descriptions
+///
+/// Parses `<emulator> -cpu help`. x86 entries look like
+/// `x86 Skylake-Client    Intel Core Processor (Skylake)`, other targets
+/// list one model per line; the "Recognized CPUID flags" block is skipped.
+pub fn get_supported_cpus(emulator: &str) -> Option<Vec<(String, String)>> {
+    let text = help_output(emulator, &["-cpu", "help"])?;
+
+    let mut cpus = Vec::new();
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("Recognized") {
+            break;
+        }
+        if trimmed.is_empty() || trimmed.ends_with(':') {
+            continue;
+        }
+        let entry = trimmed
+            .strip_prefix("x86 ")
+            .or_else(|| trimmed.strip_prefix("PowerPC "))
+            .unwrap_or(trimmed)
+            .trim_start();
+        let (name, description) = entry.split_once(char::is_whitespace).unwrap_or((entry, ""));
+        cpus.push((name.to_string(), description.trim().to_string()));
+    }
+    Some(cpus)
+}
+
+/// Run an emulator's help query, or None if the emulator can't be run
+fn help_output(emulator: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(emulator)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
 /// Check if a SPICE viewer application is available in PATH
 ///
 /// Checks for `remote-viewer` (from virt-viewer package) or `virt-viewer`.
@@ -103,6 +432,40 @@ pub fn is_spice_viewer_available() -> bool {
     false
 }
 
+/// Find a viewer that can connect to a SPICE URI
+///
+/// Prefers `remote-viewer` (virt-viewer), falling back to `spicy` (spice-gtk).
+pub fn find_spice_viewer() -> Option<&'static str> {
+    ["remote-viewer", "spicy"].into_iter().find(|viewer| {
+        Command::new("which")
+            .arg(viewer)
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+    })
+}
+
+/// Check if virtiofsd is available (in PATH or the usual libexec location)
+pub fn is_virtiofsd_available() -> bool {
+    std::path::Path::new("/usr/libexec/virtiofsd").exists()
+        || std::path::Path::new("/usr/lib/qemu/virtiofsd").exists()
+        || Command::new("which")
+            .arg("virtiofsd")
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+}
+
+/// Check if Samba's smbd is available (needed for SLIRP SMB shares)
+pub fn is_smbd_available() -> bool {
+    std::path::Path::new("/usr/sbin/smbd").exists()
+        || Command::new("which")
+            .arg("smbd")
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+}
+
 /// Get KVM module info
 pub fn get_kvm_info() -> Option<String> {
     if !is_kvm_available() {
@@ -128,6 +491,14 @@ pub struct NetworkCapabilities {
     pub bridge_helper_path: Option<PathBuf>,
     pub bridge_helper_configured: bool,
     pub system_bridges: Vec<String>,
+    /// Bridges allowed in qemu-bridge-helper's ACL (None if it can't be read)
+    pub bridge_acl: Option<Vec<String>>,
+    /// Network manager that can define persistent tap devices
+    pub tap_manager: Option<TapManager>,
+    /// Tap devices present on the host
+    pub tap_devices: Vec<TapDevice>,
+    /// nft binary, for NAT on routed taps
+    pub nft_path: Option<String>,
 }
 
 /// Detect all available networking capabilities
@@ -139,12 +510,20 @@ pub fn detect_network_capabilities() -> NetworkCapabilities {
         .map(|p| is_bridge_helper_configured(p))
         .unwrap_or(false);
     let system_bridges = list_system_bridges();
+    let bridge_acl = read_bridge_acl();
+    let tap_manager = detect_tap_manager();
+    let tap_devices = list_tap_devices();
+    let nft_path = find_nft();
 
     NetworkCapabilities {
         passt_available,
         bridge_helper_path,
         bridge_helper_configured,
         system_bridges,
+        bridge_acl,
+        tap_manager,
+        tap_devices,
+        nft_path,
     }
 }
 
@@ -159,6 +538,13 @@ fn is_passt_available() -> bool {
         .unwrap_or(false)
 }
 
+/// Find the nft binary (systemd units need its absolute path)
+fn find_nft() -> Option<String> {
+    let output = Command::new("which").arg("nft").output().ok()?;
+    let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !path.is_empty()).then_some(path)
+}
+
 /// Find qemu-bridge-helper binary
 fn find_bridge_helper() -> Option<PathBuf> {
     let paths = [
@@ -179,6 +565,7 @@ fn find_bridge_helper() -> Option<PathBuf> {
 /// Check if bridge helper has setuid or CAP_NET_ADMIN
 fn is_bridge_helper_configured(path: &Path) -> bool {
     // Check setuid bit
+    #[cfg(unix)]
     if let Ok(metadata) = std::fs::metadata(path) {
         use std::os::unix::fs::PermissionsExt;
         let mode = metadata.permissions().mode();
@@ -201,6 +588,26 @@ fn is_bridge_helper_configured(path: &Path) -> bool {
     false
 }
 
+/// Read the `allow` entries of qemu-bridge-helper's ACL
+///
+/// A missing file allows nothing; an unreadable one (often root:qemu 0640)
+/// is reported as unknown.
+fn read_bridge_acl() -> Option<Vec<String>> {
+    let content = match std::fs::read_to_string(super::bridge_setup::BRIDGE_ACL_PATH) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Some(Vec::new()),
+        Err(_) => return None,
+    };
+
+    Some(
+        content
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("allow "))
+            .map(|bridge| bridge.trim().to_string())
+            .collect(),
+    )
+}
+
 /// List bridges currently on the system
 fn list_system_bridges() -> Vec<String> {
     let output = match Command::new("ip")
@@ -224,3 +631,31 @@ fn list_system_bridges() -> Vec<String> {
     }
     bridges
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_qemu_version() {
+        let output = "QEMU emulator version 8.2.2 (Debian 1:8.2.2+ds-0ubuntu1)\nCopyright (c) 2003-2023 Fabrice Bellard\n";
+        assert_eq!(parse_qemu_version(output).as_deref(), Some("8.2.2"));
+        assert_eq!(parse_qemu_version("QEMU emulator version 2.12.1\n").as_deref(), Some("2.12.1"));
+        assert_eq!(parse_qemu_version(""), None);
+    }
+
+    #[test]
+    fn test_accel_listed() {
+        let help = "Accelerators supported in QEMU binary:\ntcg\nwhpx\n";
+        assert!(accel_listed(help, "whpx"));
+        assert!(!accel_listed(help, "kvm"));
+        assert_eq!(Accelerator::host() == Accelerator::Whpx, cfg!(windows));
+    }
+
+    #[test]
+    fn test_flatpak_app() {
+        let path = Path::new("/var/lib/flatpak/app/org.gnome.Boxes/current/active/files/bin/qemu-system-x86_64");
+        assert_eq!(flatpak_app(path).as_deref(), Some("org.gnome.Boxes"));
+        assert_eq!(flatpak_app(Path::new("/opt/qemu-2.12/bin/qemu-system-i386")), None);
+    }
+}
@@ -0,0 +1,146 @@
+//! SSH transport for remote VM hosts
+//!
+//! Commands to a host share one multiplexed connection (ControlMaster), so
+//! polling it for running VMs every few seconds costs a round trip rather
+//! than a new login. BatchMode keeps ssh from prompting inside the TUI:
+//! key-based login (or an agent) must already work for the host.
+
+use anyhow::{bail, Context, Result};
+use std::io::{Read, Write};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::config::RemoteHost;
+
+/// How long a new forward waits for its first client, in seconds
+const FORWARD_IDLE_SECS: u32 = 30;
+
+/// How long to wait for a forward to come up
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Options every call uses, optionally sharing the host's connection
+fn options(multiplex: bool) -> Vec<String> {
+    let mut options = vec!["-o".to_string(), "BatchMode=yes".to_string(), "-o".to_string(), "ConnectTimeout=5".to_string()];
+    let sharing = if multiplex {
+        let control_path = dirs::runtime_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("vm-curator-ssh-%C");
+        vec![
+            "ControlMaster=auto".to_string(),
+            "ControlPersist=120".to_string(),
+            format!("ControlPath={}", control_path.display()),
+        ]
+    } else {
+        vec!["ControlPath=none".to_string()]
+    };
+    for option in sharing {
+        options.push("-o".to_string());
+        options.push(option);
+    }
+    options
+}
+
+fn base_command(multiplex: bool) -> Command {
+    let mut cmd = Command::new("ssh");
+    cmd.args(options(multiplex));
+    cmd
+}
+
+/// The remote shell for `rsync -e`, sharing the host's connection
+pub fn rsync_shell() -> String {
+    let mut shell = vec!["ssh".to_string()];
+    shell.extend(options(true));
+    shell.join(" ")
+}
+
+/// Run a bash script on the host and return its standard output
+///
+/// The script is sent on stdin, so it needs no quoting for the remote shell.
+pub fn run_script(host: &RemoteHost, script: &str) -> Result<String> {
+    let mut child = base_command(true)
+        .arg(&host.ssh)
+        .args(["bash", "-s"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run ssh")?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(script.as_bytes())?;
+    }
+    let output = child.wait_with_output().context("Failed to run ssh")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("ssh failed");
+        bail!("{}: {}", host.name, reason.trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Replace a file on the host
+pub fn write_file(host: &RemoteHost, path: &str, content: &str) -> Result<()> {
+    let mut content = content.to_string();
+    if !content.ends_with('\n') {
+        content.push('\n');
+    }
+    let target = crate::vm::create::shell_escape(path);
+    let script = format!(
+        "cat > {target}.tmp <<'VM_CURATOR_EOF'\n{content}VM_CURATOR_EOF\nmv {target}.tmp {target}\n"
+    );
+    run_script(host, &script)?;
+    Ok(())
+}
+
+/// Forward a local socket or port to the host (`spec` is an `ssh -L` argument)
+///
+/// Uses a connection of its own, which stays up for FORWARD_IDLE_SECS and
+/// then for as long as a client is connected. Returns once `ready` reports
+/// the local end usable.
+pub fn forward(host: &RemoteHost, spec: &str, ready: impl Fn() -> bool) -> Result<Child> {
+    spawn_forward(host, "-L", spec, ready)
+}
+
+/// Forward a port on the host back to this machine (`spec` is an `ssh -R` argument)
+///
+/// The remote end cannot be probed from here, so this returns once ssh
+/// has had a moment to report a refused forward.
+pub fn reverse_forward(host: &RemoteHost, spec: &str) -> Result<Child> {
+    let start = Instant::now();
+    spawn_forward(host, "-R", spec, || start.elapsed() > Duration::from_millis(1500))
+}
+
+fn spawn_forward(host: &RemoteHost, flag: &str, spec: &str, ready: impl Fn() -> bool) -> Result<Child> {
+    let mut child = base_command(false)
+        .args(["-o", "ExitOnForwardFailure=yes", "-o", "StreamLocalBindUnlink=yes"])
+        .arg(flag)
+        .arg(spec)
+        .arg(&host.ssh)
+        .arg(format!("sleep {}", FORWARD_IDLE_SECS))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run ssh")?;
+
+    let start = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            let mut stderr = String::new();
+            if let Some(mut pipe) = child.stderr.take() {
+                let _ = pipe.read_to_string(&mut stderr);
+            }
+            let reason = stderr.lines().rev().find(|l| !l.trim().is_empty()).map(str::trim);
+            bail!("{}: tunnel failed ({})", host.name, reason.unwrap_or(&status.to_string()));
+        }
+        if ready() {
+            return Ok(child);
+        }
+        if start.elapsed() > FORWARD_TIMEOUT {
+            let _ = child.kill();
+            bail!("{}: timed out setting up the tunnel", host.name);
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,411 @@
+//! TAP device networking setup
+//!
+//! A persistent tap device owned by the user lets QEMU open it directly
+//! (`-netdev tap,ifname=...,script=no`), so VMs start without a helper or
+//! root and avoid SLIRP's limits (no ping, no inbound connections). The
+//! device is defined for systemd-networkd or NetworkManager so it survives
+//! reboots, then either joined to a host bridge (the VM sits on the LAN)
+//! or routed on its own /24 with the host as gateway, optionally NATed to
+//! the uplink by an nftables table loaded from a small systemd unit.
+
+use anyhow::{bail, Result};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use super::bridge_setup::{is_command_available, is_valid_interface_name, SetupStep};
+use super::qemu_system::NetworkCapabilities;
+use crate::fs::current_uid;
+
+/// Tap device suggested when none exists yet
+pub const DEFAULT_TAP_NAME: &str = "vmtap0";
+
+/// Where generated systemd-networkd definitions go
+const NETWORKD_DIR: &str = "/etc/systemd/network";
+
+/// Where generated nftables rule files go
+const NAT_RULES_DIR: &str = "/etc/vm-curator";
+
+/// Enables forwarding for routed taps
+const FORWARD_SYSCTL_PATH: &str = "/etc/sysctl.d/50-vm-curator.conf";
+
+/// tun_flags bit for tap (as opposed to tun) devices
+const IFF_TAP: u32 = 0x0002;
+
+/// The network manager that will own the tap definition
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TapManager {
+    Networkd,
+    NetworkManager,
+}
+
+impl TapManager {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Networkd => "systemd-networkd",
+            Self::NetworkManager => "NetworkManager",
+        }
+    }
+}
+
+/// How a tap device reaches the outside
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TapUplink {
+    /// Joined to a host bridge: the VM gets an address on that network
+    Bridge(String),
+    /// Own subnet with the host as gateway, optionally NATed
+    Routed { nat: bool },
+}
+
+impl std::fmt::Display for TapUplink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bridge(bridge) => write!(f, "bridge {}", bridge),
+            Self::Routed { nat: false } => write!(f, "routed"),
+            Self::Routed { nat: true } => write!(f, "routed + NAT"),
+        }
+    }
+}
+
+/// A tap device present on the host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TapDevice {
+    pub name: String,
+    /// UID allowed to open it (None if any process with CAP_NET_ADMIN)
+    pub owner: Option<u32>,
+    /// Bridge it is attached to
+    pub master: Option<String>,
+}
+
+/// Uplinks to offer: each host bridge, then routed with and without NAT
+pub fn uplink_options(bridges: &[String]) -> Vec<TapUplink> {
+    bridges
+        .iter()
+        .map(|b| TapUplink::Bridge(b.clone()))
+        .chain([TapUplink::Routed { nat: true }, TapUplink::Routed { nat: false }])
+        .collect()
+}
+
+/// Tap names to offer: existing devices, then the first free `vmtapN`
+pub fn tap_name_options(devices: &[TapDevice]) -> Vec<String> {
+    let mut names: Vec<String> = devices.iter().map(|d| d.name.clone()).collect();
+    if let Some(free) = (0..).map(|n| format!("vmtap{}", n)).find(|n| !names.contains(n)) {
+        names.push(free);
+    }
+    names
+}
+
+/// Which network manager is running (NetworkManager wins if both are)
+pub fn detect_tap_manager() -> Option<TapManager> {
+    if is_service_active("NetworkManager") && is_command_available("nmcli") {
+        Some(TapManager::NetworkManager)
+    } else if is_service_active("systemd-networkd") {
+        Some(TapManager::Networkd)
+    } else {
+        None
+    }
+}
+
+/// List tap devices from sysfs
+pub fn list_tap_devices() -> Vec<TapDevice> {
+    let Ok(entries) = std::fs::read_dir("/sys/class/net") else {
+        return Vec::new();
+    };
+
+    let mut devices: Vec<TapDevice> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let dir = entry.path();
+            let flags = std::fs::read_to_string(dir.join("tun_flags")).ok()?;
+            let flags = u32::from_str_radix(flags.trim().trim_start_matches("0x"), 16).ok()?;
+            if flags & IFF_TAP == 0 {
+                return None;
+            }
+            let owner = std::fs::read_to_string(dir.join("owner"))
+                .ok()
+                .and_then(|o| o.trim().parse::<u32>().ok());
+            let master = std::fs::read_link(dir.join("master"))
+                .ok()
+                .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()));
+            Some(TapDevice {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                owner,
+                master,
+            })
+        })
+        .collect();
+    devices.sort_by(|a, b| a.name.cmp(&b.name));
+    devices
+}
+
+/// Host address (gateway) and network of a routed tap
+///
+/// Each tap gets its own 10.0.N.0/24, N = 100 + the name's trailing number
+/// (mod 100). Taps whose names map to the same subnet can't both be routed;
+/// `plan_tap_setup` refuses the second.
+pub fn routed_subnet(tap: &str) -> (String, String) {
+    let octet = subnet_octet(tap);
+    (format!("10.0.{}.1/24", octet), format!("10.0.{}.0/24", octet))
+}
+
+/// How the guest should configure its NIC, when it can't just use DHCP
+///
+/// systemd-networkd runs a DHCP server on routed taps; NetworkManager's
+/// manual addressing doesn't.
+pub fn guest_hint(manager: TapManager, tap: &str, uplink: &TapUplink) -> Option<String> {
+    match (manager, uplink) {
+        (TapManager::NetworkManager, TapUplink::Routed { .. }) => {
+            let octet = subnet_octet(tap);
+            Some(format!(
+                "Guest needs a static address: 10.0.{0}.2/24, gateway and DNS 10.0.{0}.1",
+                octet
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Whether `tap` exists, is usable by this user and has the right uplink
+pub fn is_tap_ready(caps: &NetworkCapabilities, tap: &str, uplink: &TapUplink) -> bool {
+    let Some(device) = caps.tap_devices.iter().find(|d| d.name == tap) else {
+        return false;
+    };
+    let attached = match uplink {
+        TapUplink::Bridge(bridge) => device.master.as_deref() == Some(bridge.as_str()),
+        TapUplink::Routed { .. } => device.master.is_none(),
+    };
+    device.owner.is_some() && device.owner == current_uid() && attached
+}
+
+/// Work out the steps still needed to use `tap` via `uplink` (empty when done)
+pub fn plan_tap_setup(caps: &NetworkCapabilities, tap: &str, uplink: &TapUplink) -> Result<Vec<SetupStep>> {
+    if !is_valid_interface_name(tap) {
+        bail!("Invalid tap device name: {}", tap);
+    }
+    let Some(manager) = caps.tap_manager else {
+        bail!("Neither NetworkManager nor systemd-networkd is running; create the tap device yourself");
+    };
+    if let TapUplink::Bridge(ref bridge) = uplink {
+        if !caps.system_bridges.iter().any(|b| b == bridge) {
+            bail!("Bridge {} does not exist; set it up from the bridge backend first", bridge);
+        }
+    }
+    let Some(uid) = current_uid() else {
+        bail!("Cannot determine the current user");
+    };
+
+    if let TapUplink::Routed { .. } = uplink {
+        if let Some(other) = taps_using_subnet(tap, Path::new(NETWORKD_DIR), Path::new(NAT_RULES_DIR)).first() {
+            bail!(
+                "{} is already routed on {}, which {} would use too; pick a tap name with another number",
+                other,
+                routed_subnet(tap).1,
+                tap
+            );
+        }
+    }
+
+    let mut steps = Vec::new();
+
+    if !is_tap_ready(caps, tap, uplink) {
+        steps.push(match manager {
+            TapManager::Networkd => networkd_step(tap, uplink),
+            TapManager::NetworkManager => nmcli_step(tap, uplink, uid),
+        });
+    }
+
+    let nat_unit_exists = Path::new(&nat_unit_path(tap)).exists();
+    match uplink {
+        TapUplink::Routed { nat: true } => {
+            let Some(ref nft) = caps.nft_path else {
+                bail!("nft not found; install nftables for NAT, or choose routed without NAT");
+            };
+            if !is_ip_forward_enabled() {
+                steps.push(SetupStep {
+                    description: "Enable IPv4 forwarding (persistent)".to_string(),
+                    command: format!(
+                        "echo 'net.ipv4.ip_forward = 1' > {}\nsysctl -q -w net.ipv4.ip_forward=1",
+                        FORWARD_SYSCTL_PATH
+                    ),
+                });
+            }
+            if !nat_unit_exists {
+                steps.push(nat_step(tap, nft));
+            }
+        }
+        _ if nat_unit_exists => steps.push(SetupStep {
+            description: format!("Remove the NAT rules for {}", tap),
+            command: format!(
+                "systemctl disable --now {0} || true\nrm -f {1} {2}/nat-{3}.nft\nsystemctl daemon-reload",
+                nat_unit_name(tap),
+                nat_unit_path(tap),
+                NAT_RULES_DIR,
+                tap
+            ),
+        }),
+        _ => {}
+    }
+
+    Ok(steps)
+}
+
+/// .netdev + .network for systemd-networkd, recreating the device
+fn networkd_step(tap: &str, uplink: &TapUplink) -> SetupStep {
+    let user = std::env::var("USER").ok().filter(|u| !u.is_empty()).unwrap_or_else(|| {
+        current_uid().map(|uid| uid.to_string()).unwrap_or_default()
+    });
+    let network = match uplink {
+        TapUplink::Bridge(bridge) => format!("Bridge={}\nConfigureWithoutCarrier=yes", bridge),
+        TapUplink::Routed { .. } => format!(
+            "Address={}\nDHCPServer=yes\nConfigureWithoutCarrier=yes",
+            routed_subnet(tap).0
+        ),
+    };
+    let base = format!("{}/50-vm-curator-{}", NETWORKD_DIR, tap);
+
+    SetupStep {
+        description: format!("Define tap {} for {} ({}, owned by {})", tap, TapManager::Networkd.label(), uplink, user),
+        command: format!(
+            "cat > {base}.netdev <<'EOF'\n[NetDev]\nName={tap}\nKind=tap\n\n[Tap]\nUser={user}\nEOF\n\
+             cat > {base}.network <<'EOF'\n[Match]\nName={tap}\n\n[Network]\n{network}\nEOF\n\
+             ip link delete {tap} 2>/dev/null || true\nnetworkctl reload",
+        ),
+    }
+}
+
+/// A NetworkManager tun connection in tap mode, replacing any old one
+fn nmcli_step(tap: &str, uplink: &TapUplink, uid: u32) -> SetupStep {
+    let addressing = match uplink {
+        TapUplink::Bridge(bridge) => format!("master {} slave-type bridge", bridge),
+        TapUplink::Routed { .. } => format!(
+            "ipv4.method manual ipv4.addresses {} ipv6.method ignore",
+            routed_subnet(tap).0
+        ),
+    };
+
+    SetupStep {
+        description: format!("Define tap {} for {} ({}, owned by UID {})", tap, TapManager::NetworkManager.label(), uplink, uid),
+        command: format!(
+            "nmcli connection delete {tap} >/dev/null 2>&1 || true\n\
+             nmcli connection add type tun ifname {tap} con-name {tap} mode tap owner {uid} {addressing}\n\
+             nmcli connection up {tap}",
+        ),
+    }
+}
+
+/// An nftables masquerade table for the tap's subnet, loaded at boot
+fn nat_step(tap: &str, nft: &str) -> SetupStep {
+    let table = nft_table_name(tap);
+    let (_, subnet) = routed_subnet(tap);
+    let rules = format!(
+        "table ip {table}\ndelete table ip {table}\ntable ip {table} {{\n    \
+         chain postrouting {{\n        type nat hook postrouting priority srcnat; policy accept;\n        \
+         ip saddr {subnet} oifname != \"{tap}\" masquerade\n    }}\n    \
+         chain forward {{\n        type filter hook forward priority filter; policy accept;\n        \
+         iifname \"{tap}\" accept\n        oifname \"{tap}\" ct state established,related accept\n    }}\n}}"
+    );
+    let unit = format!(
+        "[Unit]\nDescription=vm-curator NAT for {tap}\nAfter=network.target nftables.service\n\n\
+         [Service]\nType=oneshot\nRemainAfterExit=yes\nExecStart={nft} -f {NAT_RULES_DIR}/nat-{tap}.nft\n\
+         ExecStop={nft} delete table ip {table}\n\n[Install]\nWantedBy=multi-user.target"
+    );
+
+    SetupStep {
+        description: format!("NAT {} to the host's uplink with nftables (table {}, loaded at boot)", subnet, table),
+        command: format!(
+            "mkdir -p {NAT_RULES_DIR}\ncat > {NAT_RULES_DIR}/nat-{tap}.nft <<'EOF'\n{rules}\nEOF\n\
+             cat > {unit_path} <<'EOF'\n{unit}\nEOF\n\
+             systemctl daemon-reload\nsystemctl enable {unit_name}\nsystemctl restart {unit_name}",
+            unit_path = nat_unit_path(tap),
+            unit_name = nat_unit_name(tap),
+        ),
+    }
+}
+
+fn subnet_octet(tap: &str) -> u32 {
+    let digits = &tap[tap.trim_end_matches(|c: char| c.is_ascii_digit()).len()..];
+    100 + digits.parse::<u32>().unwrap_or(0) % 100
+}
+
+/// Other taps routed on `tap`'s subnet by the networkd files or NAT rules
+/// generated here
+fn taps_using_subnet(tap: &str, networkd_dir: &Path, nat_dir: &Path) -> Vec<String> {
+    let (address, subnet) = routed_subnet(tap);
+    let generated = [
+        (networkd_dir, "50-vm-curator-", ".network", format!("Address={}", address)),
+        (nat_dir, "nat-", ".nft", format!("ip saddr {} ", subnet)),
+    ];
+    let mut taps: Vec<String> = generated
+        .iter()
+        .flat_map(|(dir, prefix, suffix, needle)| {
+            std::fs::read_dir(dir)
+                .into_iter()
+                .flatten()
+                .flatten()
+                .filter_map(move |entry| {
+                    let name = entry.file_name().to_string_lossy().into_owned();
+                    let other = name.strip_prefix(prefix)?.strip_suffix(suffix)?.to_string();
+                    let content = std::fs::read_to_string(entry.path()).ok()?;
+                    (other != tap && content.contains(needle.as_str())).then_some(other)
+                })
+        })
+        .collect();
+    taps.sort();
+    taps.dedup();
+    taps
+}
+
+fn nft_table_name(tap: &str) -> String {
+    let name: String = tap.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    format!("vm_curator_{}", name)
+}
+
+fn nat_unit_name(tap: &str) -> String {
+    format!("vm-curator-nat-{}.service", tap)
+}
+
+fn nat_unit_path(tap: &str) -> String {
+    format!("/etc/systemd/system/{}", nat_unit_name(tap))
+}
+
+fn is_ip_forward_enabled() -> bool {
+    std::fs::read_to_string("/proc/sys/net/ipv4/ip_forward")
+        .map(|v| v.trim() == "1")
+        .unwrap_or(false)
+}
+
+fn is_service_active(unit: &str) -> bool {
+    Command::new("systemctl")
+        .args(["is-active", "--quiet", unit])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_taps_using_subnet() {
+        let dir = std::env::temp_dir().join(format!("vm-curator-tap-subnets-{}", std::process::id()));
+        let (networkd, nat) = (dir.join("network"), dir.join("nat"));
+        std::fs::create_dir_all(&networkd).unwrap();
+        std::fs::create_dir_all(&nat).unwrap();
+
+        // vmtap0 routed without NAT, vmtap1 with
+        std::fs::write(networkd.join("50-vm-curator-vmtap0.network"), "[Network]\nAddress=10.0.100.1/24\n").unwrap();
+        std::fs::write(networkd.join("50-vm-curator-vmtap0.netdev"), "[NetDev]\nName=vmtap0\n").unwrap();
+        std::fs::write(nat.join("nat-vmtap1.nft"), "ip saddr 10.0.101.0/24 oifname != \"vmtap1\" masquerade\n").unwrap();
+
+        assert_eq!(taps_using_subnet("vmtap100", &networkd, &nat), ["vmtap0"]);
+        assert_eq!(taps_using_subnet("vmtap", &networkd, &nat), ["vmtap0"]);
+        assert_eq!(taps_using_subnet("vmtap101", &networkd, &nat), ["vmtap1"]);
+        // A tap doesn't collide with itself, and other numbers are free
+        assert!(taps_using_subnet("vmtap0", &networkd, &nat).is_empty());
+        assert!(taps_using_subnet("vmtap2", &networkd, &nat).is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
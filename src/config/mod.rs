@@ -2,6 +2,9 @@ use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+use crate::format::DateStyle;
+use crate::hardware::ResourceCheck;
+
 /// Application configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -26,10 +29,29 @@ pub struct Config {
     pub default_display: String,
     /// Enable KVM acceleration by default
     pub default_enable_kvm: bool,
+    /// Folder of install images, identified by checksum (None = `isos` in the VM library)
+    pub iso_library_path: Option<PathBuf>,
 
     // === Behavior ===
     /// Show confirmation dialog before launching VMs
     pub confirm_before_launch: bool,
+    /// What to do when a VM doesn't fit in the host's free memory and CPUs
+    pub launch_resource_check: ResourceCheck,
+    /// How dates are shown (iso, locale, relative)
+    pub date_format: DateStyle,
+    /// VNC client command (`{addr}` is replaced with host:port); None = auto-detect
+    pub vnc_client: Option<String>,
+    /// Snapshot a VM's disk each time it shuts down, keeping this many
+    /// session snapshots per VM (0 = off)
+    pub session_snapshots: u32,
+    /// Kiosk mode: VMs can be browsed, launched and stopped but not changed
+    /// (config file only, so visitors can't turn it off)
+    pub kiosk_mode: bool,
+
+    // === QEMU ===
+    /// Directories holding other QEMU builds (`<prefix>/bin/qemu-system-*`),
+    /// offered per VM alongside PATH and Flatpak ones
+    pub qemu_prefixes: Vec<PathBuf>,
 
     // === Multi-GPU Passthrough ===
     /// Enable multi-GPU passthrough features in the UI
@@ -50,6 +72,35 @@ pub struct Config {
     pub looking_glass_client_path: Option<PathBuf>,
     /// Auto-launch Looking Glass client when VM starts
     pub looking_glass_auto_launch: bool,
+
+    // === Slideshow ===
+    /// Seconds each VM is shown
+    pub slideshow_interval_secs: u64,
+    /// Start the slideshow after this many idle seconds on the VM list (None = only with `p`)
+    pub slideshow_idle_secs: Option<u64>,
+    /// Snapshot a VM is reverted to and launched from while it is shown (None = no demos)
+    pub slideshow_demo_snapshot: Option<String>,
+
+    // === Daemon ===
+    /// Address `vm-curator daemon` listens on
+    pub daemon_listen: String,
+    /// Bearer token the daemon's API requires (mandatory off loopback)
+    pub daemon_token: Option<String>,
+
+    // === Remote Hosts ===
+    /// Hosts whose VM libraries are listed alongside the local one
+    pub remote_hosts: Vec<RemoteHost>,
+}
+
+/// A machine that runs VMs from its own library, reached over SSH
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoteHost {
+    /// Short name shown next to its VMs
+    pub name: String,
+    /// SSH destination (user@host or a Host alias from ~/.ssh/config)
+    pub ssh: String,
+    /// VM library on the remote machine (relative paths start at the remote home)
+    pub library_path: String,
 }
 
 impl Default for Config {
@@ -71,9 +122,18 @@ impl Default for Config {
             default_disk_size_gb: 64,
             default_display: "gtk".to_string(),
             default_enable_kvm: true,
+            iso_library_path: None,
 
             // Behavior
             confirm_before_launch: true,
+            launch_resource_check: ResourceCheck::default(),
+            date_format: DateStyle::default(),
+            vnc_client: None,
+            session_snapshots: 0,
+            kiosk_mode: false,
+
+            // QEMU
+            qemu_prefixes: Vec::new(),
 
             // Multi-GPU Passthrough
             enable_multi_gpu_passthrough: false,
@@ -86,6 +146,18 @@ impl Default for Config {
             single_gpu_dm_override: None,
             looking_glass_client_path: None,
             looking_glass_auto_launch: true,
+
+            // Slideshow
+            slideshow_interval_secs: 20,
+            slideshow_idle_secs: None,
+            slideshow_demo_snapshot: None,
+
+            // Daemon
+            daemon_listen: "127.0.0.1:7340".to_string(),
+            daemon_token: None,
+
+            // Remote Hosts
+            remote_hosts: Vec::new(),
         }
     }
 }
@@ -118,6 +190,11 @@ impl Config {
             .context("Failed to serialize config")?;
         std::fs::write(&config_path, content)
             .with_context(|| format!("Failed to write config to {:?}", config_path))?;
+        // The daemon's API token is a secret
+        if self.daemon_token.is_some() {
+            crate::fs::set_mode(&config_path, 0o600)
+                .with_context(|| format!("Failed to set permissions on {:?}", config_path))?;
+        }
 
         Ok(())
     }
@@ -129,4 +206,19 @@ impl Config {
             .join("vm-curator")
             .join("config.toml")
     }
+
+    /// Get the user QEMU profiles file (additions to and overrides of the built-in profiles)
+    pub fn qemu_profiles_path() -> PathBuf {
+        Self::config_file_path().with_file_name("qemu_profiles.toml")
+    }
+
+    /// Get the user's additions to the known install image checksums
+    pub fn iso_checksums_path() -> PathBuf {
+        Self::config_file_path().with_file_name("iso_checksums.toml")
+    }
+
+    /// Folder of install images
+    pub fn iso_library(&self) -> PathBuf {
+        self.iso_library_path.clone().unwrap_or_else(|| self.vm_library_path.join("isos"))
+    }
 }
@@ -0,0 +1,230 @@
+//! Minimal HTTP/1.1 for the daemon's JSON API
+//!
+//! One request per connection (`Connection: close`), bodies sized by
+//! Content-Length. Enough for curl, scripts and a browser front end; put a
+//! reverse proxy in front of the daemon for TLS.
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+
+/// Largest request body accepted
+const MAX_BODY: usize = 1024 * 1024;
+
+/// Most header lines accepted
+const MAX_HEADERS: usize = 100;
+
+/// A parsed request
+#[derive(Debug)]
+pub struct Request {
+    pub method: String,
+    /// Percent-decoded path segments, without the query string
+    pub segments: Vec<String>,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    /// Value of a header (case-insensitive name)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Whether the request has a body (blank counts as none)
+    pub fn has_body(&self) -> bool {
+        !self.body.iter().all(u8::is_ascii_whitespace)
+    }
+
+    /// The body as JSON (an empty body is an empty object)
+    pub fn json(&self) -> Result<Value> {
+        if !self.has_body() {
+            return Ok(json!({}));
+        }
+        serde_json::from_slice(&self.body).context("Request body is not valid JSON")
+    }
+}
+
+/// A response body
+#[derive(Debug, PartialEq)]
+pub enum Body {
+    Empty,
+    Json(Value),
+    /// Plain text with its content type
+    Text(&'static str, String),
+}
+
+/// A response, JSON unless built with `text`
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    pub body: Body,
+}
+
+impl Response {
+    pub fn ok(body: Value) -> Self {
+        Self { status: 200, body: Body::Json(body) }
+    }
+
+    pub fn text(content_type: &'static str, body: String) -> Self {
+        Self { status: 200, body: Body::Text(content_type, body) }
+    }
+
+    pub fn no_content() -> Self {
+        Self { status: 204, body: Body::Empty }
+    }
+
+    /// An error response: `{"error": message}`
+    pub fn error(status: u16, message: impl std::fmt::Display) -> Self {
+        Self {
+            status,
+            body: Body::Json(json!({"error": message.to_string()})),
+        }
+    }
+}
+
+/// Whether a Content-Type is JSON (parameters such as charset aside)
+pub fn is_json(content_type: &str) -> bool {
+    content_type.split(';').next().is_some_and(|media| media.trim().eq_ignore_ascii_case("application/json"))
+}
+
+/// Read one request from a connection
+pub fn read_request(reader: &mut impl BufRead) -> Result<Request> {
+    let mut line = String::new();
+    reader.read_line(&mut line).context("Failed to read the request")?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        bail!("Malformed request line");
+    };
+    let method = method.to_string();
+    let path = target.split(['?', '#']).next().unwrap_or("/");
+    let segments = path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(percent_decode)
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut headers = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            bail!("Connection closed in the headers");
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if headers.len() == MAX_HEADERS {
+            bail!("Too many headers");
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    let mut request = Request { method, segments, headers, body: Vec::new() };
+    let length: usize = match request.header("Content-Length") {
+        Some(value) => value.parse().context("Invalid Content-Length")?,
+        None => 0,
+    };
+    if length > MAX_BODY {
+        bail!("Request body too large");
+    }
+    request.body.resize(length, 0);
+    reader.read_exact(&mut request.body).context("Request body cut short")?;
+    Ok(request)
+}
+
+/// Write a response and mark the connection for closing
+///
+/// Any origin may call the API: it is guarded by the token, which a page
+/// can only send once it has it, not by cookies the browser adds itself.
+/// So a web front end can be served from elsewhere.
+pub fn write_response(writer: &mut impl Write, response: &Response) -> std::io::Result<()> {
+    let (content_type, body) = match response.body {
+        Body::Empty => ("application/json", String::new()),
+        Body::Json(ref value) => ("application/json", value.to_string()),
+        Body::Text(content_type, ref text) => (content_type, text.clone()),
+    };
+    write!(
+        writer,
+        "HTTP/1.1 {} {}\r\n\
+         Content-Type: {}\r\n\
+         Content-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\n\
+         Access-Control-Allow-Methods: GET, POST, DELETE, OPTIONS\r\n\
+         Access-Control-Allow-Headers: Authorization, Content-Type\r\n\
+         Connection: close\r\n\r\n{}",
+        response.status,
+        reason(response.status),
+        content_type,
+        body.len(),
+        body
+    )?;
+    writer.flush()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        409 => "Conflict",
+        415 => "Unsupported Media Type",
+        _ => "Internal Server Error",
+    }
+}
+
+fn percent_decode(segment: &str) -> Result<String> {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = segment.get(i + 1..i + 3).context("Invalid percent-encoding in path")?;
+            decoded.push(u8::from_str_radix(hex, 16).context("Invalid percent-encoding in path")?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).context("Path is not valid UTF-8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_request() {
+        let raw = "POST /api/vms/windows-98/snapshots/before%20patch?x=1 HTTP/1.1\r\n\
+                   Host: localhost\r\n\
+                   content-length: 16\r\n\
+                   \r\n\
+                   {\"force\": true}\n";
+        let request = read_request(&mut raw.as_bytes()).unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.segments, ["api", "vms", "windows-98", "snapshots", "before patch"]);
+        assert_eq!(request.header("Content-Length"), Some("16"));
+        assert_eq!(request.json().unwrap()["force"], true);
+
+        let raw = "GET /api/vms HTTP/1.1\r\n\r\n";
+        let request = read_request(&mut raw.as_bytes()).unwrap();
+        assert_eq!(request.json().unwrap(), json!({}));
+        assert!(read_request(&mut "GET /%zz HTTP/1.1\r\n\r\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_write_response() {
+        let mut out = Vec::new();
+        write_response(&mut out, &Response::error(404, "No VM named dos")).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(out.ends_with("\r\n\r\n{\"error\":\"No VM named dos\"}"));
+    }
+}
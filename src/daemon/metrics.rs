@@ -0,0 +1,110 @@
+//! Prometheus metrics for `GET /metrics`
+//!
+//! Per-VM figures come from the QEMU process in /proc (CPU time, resident
+//! memory) and from the VM's folder (space allocated on disk, snapshots in
+//! its qcow2 images), so scraping costs a directory walk and one
+//! `qemu-img info` per snapshot-capable disk.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use super::Library;
+use crate::vm::monitor::{process_stats, ProcessStats};
+use crate::vm::{self, DiscoveredVm};
+
+/// Content type of the text exposition format
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// One metric family being written
+struct Family<'a> {
+    out: &'a mut String,
+    name: &'static str,
+}
+
+impl Family<'_> {
+    fn sample(&mut self, labels: &str, value: f64) {
+        let _ = writeln!(self.out, "{}{} {}", self.name, labels, value);
+    }
+}
+
+fn family<'a>(out: &'a mut String, name: &'static str, kind: &str, help: &str) -> Family<'a> {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    Family { out, name }
+}
+
+/// `{vm="...",name="..."}` for a VM
+fn vm_labels(vm: &DiscoveredVm) -> String {
+    format!("{{vm=\"{}\",name=\"{}\"}}", escape_label(&vm.id), escape_label(&vm.display_name()))
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Render every metric for the library
+pub fn render(library: &Library) -> String {
+    let labels: HashMap<&str, String> = library.vms.iter().map(|vm| (vm.id.as_str(), vm_labels(vm))).collect();
+    let running: Vec<(&DiscoveredVm, u32)> = library
+        .vms
+        .iter()
+        .filter_map(|vm| library.running.get(&vm.id).map(|pid| (vm, *pid)))
+        .collect();
+    let disk_usage: Vec<(&DiscoveredVm, u64)> = library.vms.iter().map(|vm| (vm, vm::storage::allocated_bytes(&vm.path))).collect();
+
+    let mut out = String::new();
+    family(&mut out, "vm_curator_vms", "gauge", "VMs in the library").sample("", library.vms.len() as f64);
+    family(&mut out, "vm_curator_vms_running", "gauge", "VMs running").sample("", running.len() as f64);
+
+    let mut up = family(&mut out, "vm_curator_vm_running", "gauge", "Whether the VM is running");
+    for vm in &library.vms {
+        up.sample(&labels[vm.id.as_str()], f64::from(u8::from(library.running.contains_key(&vm.id))));
+    }
+
+    let mut memory = family(&mut out, "vm_curator_vm_memory_configured_bytes", "gauge", "Guest RAM in the VM's configuration");
+    for vm in &library.vms {
+        memory.sample(&labels[vm.id.as_str()], f64::from(vm.config.memory_mb) * 1024.0 * 1024.0);
+    }
+
+    let stats: Vec<(&DiscoveredVm, ProcessStats)> =
+        running.iter().filter_map(|(vm, pid)| process_stats(*pid).map(|stats| (*vm, stats))).collect();
+    let mut cpu = family(&mut out, "vm_curator_vm_cpu_seconds_total", "counter", "CPU time used by the running VM's process");
+    for (vm, stats) in &stats {
+        cpu.sample(&labels[vm.id.as_str()], stats.cpu_seconds);
+    }
+    let mut rss = family(&mut out, "vm_curator_vm_memory_rss_bytes", "gauge", "Resident memory of the running VM's process");
+    for (vm, stats) in &stats {
+        rss.sample(&labels[vm.id.as_str()], stats.rss_bytes as f64);
+    }
+
+    let mut disk = family(&mut out, "vm_curator_vm_disk_bytes", "gauge", "Space allocated on disk by the VM's folder");
+    for (vm, bytes) in &disk_usage {
+        disk.sample(&labels[vm.id.as_str()], *bytes as f64);
+    }
+    let total: u64 = disk_usage.iter().map(|(_, bytes)| bytes).sum();
+    family(&mut out, "vm_curator_library_disk_bytes", "gauge", "Space allocated on disk by all VMs").sample("", total as f64);
+
+    let mut snapshots = family(&mut out, "vm_curator_vm_snapshots", "gauge", "Snapshots in the VM's primary disk");
+    for vm in library.vms.iter().filter(|vm| vm.config.supports_snapshots()) {
+        let count = vm.config.primary_disk().and_then(|disk| vm::list_snapshots(&disk.path).ok()).map(|s| s.len());
+        if let Some(count) = count {
+            snapshots.sample(&labels[vm.id.as_str()], count as f64);
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let library = Library { vms: Vec::new(), running: HashMap::new() };
+        let text = render(&library);
+        assert!(text.contains("# TYPE vm_curator_vms gauge\nvm_curator_vms 0\n"));
+        assert!(text.contains("vm_curator_library_disk_bytes 0\n"));
+        assert_eq!(escape_label("say \"hi\"\\"), "say \\\"hi\\\"\\\\");
+    }
+}
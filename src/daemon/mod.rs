@@ -0,0 +1,359 @@
+//! Headless daemon with a REST/JSON API
+//!
+//! `vm-curator daemon` serves the local VM library over HTTP so it can be
+//! scripted or fronted by a web UI on a home server:
+//!
+//! - `GET /api/status` - version, library path and running VMs
+//! - `GET /api/vms` - every VM in the library
+//! - `GET /api/vms/{id}` - one VM
+//! - `POST /api/vms/{id}/launch` - body `{"mode": "normal|install|netboot|resume", "cdrom": "/path.iso"}`
+//! - `POST /api/vms/{id}/stop` - body `{"force": true}` to kill instead of powering down
+//! - `GET /api/vms/{id}/snapshots`, `POST` with `{"name": "..."}` to create one
+//! - `POST /api/vms/{id}/snapshots/{name}/restore`, `DELETE /api/vms/{id}/snapshots/{name}`
+//! - `GET /metrics` - Prometheus metrics (running VMs, CPU, memory, disk usage, snapshots)
+//!
+//! Every request needs `Authorization: Bearer <daemon_token>`. Without a
+//! token in the config the daemon makes one up and saves it there first,
+//! as a web page the user visits could otherwise reach a loopback daemon
+//! and launch VMs or delete snapshots. Request bodies must be sent as
+//! `application/json`.
+
+mod http;
+mod metrics;
+
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::io::{BufReader, Read};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::vm::{self, DiscoveredVm};
+use http::{Request, Response};
+
+/// How long a client may take to send its request
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Serve the API until killed
+pub fn run(mut config: Config, listen: Option<String>) -> Result<()> {
+    if config.daemon_token.is_none() {
+        // Saved to the config as it is on disk, without command line overrides
+        let token = generate_token();
+        let mut saved = Config::load()?;
+        saved.daemon_token = Some(token.clone());
+        saved.save().context("Failed to save the new daemon token")?;
+        eprintln!("Generated an API token, saved as daemon_token in {}", Config::config_file_path().display());
+        config.daemon_token = Some(token);
+    }
+
+    let address = listen.unwrap_or_else(|| config.daemon_listen.clone());
+    let listener = TcpListener::bind(&address).with_context(|| format!("Cannot listen on {}", address))?;
+    let local = listener.local_addr()?;
+    eprintln!("vm-curator daemon listening on http://{}", local);
+
+    let config = std::sync::Arc::new(config);
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let config = config.clone();
+        thread::spawn(move || serve(stream, &config));
+    }
+    Ok(())
+}
+
+fn serve(stream: TcpStream, config: &Config) {
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+    let response = match http::read_request(&mut BufReader::new(stream)) {
+        Ok(request) => {
+            let response = handle(&request, config);
+            tracing::debug!(method = %request.method, path = %request.segments.join("/"), status = response.status, "request");
+            response
+        }
+        Err(e) => Response::error(400, format!("{:#}", e)),
+    };
+    let _ = http::write_response(&mut writer, &response);
+}
+
+/// A new API token: 32 random bytes in hex
+fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    let from_os = std::fs::File::open("/dev/urandom").and_then(|mut f| f.read_exact(&mut bytes));
+    if from_os.is_err() {
+        // std seeds its hash keys from the OS's random number generator
+        use std::hash::{BuildHasher, RandomState};
+        for (i, chunk) in bytes.chunks_mut(8).enumerate() {
+            chunk.copy_from_slice(&RandomState::new().hash_one(i).to_le_bytes());
+        }
+    }
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Whether the request carries the token, compared in constant time
+fn authorized(request: &Request, token: &str) -> bool {
+    let Some(given) = request.header("Authorization").and_then(|v| v.strip_prefix("Bearer ")) else {
+        return false;
+    };
+    given.len() == token.len() && given.bytes().zip(token.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Answer one request
+fn handle(request: &Request, config: &Config) -> Response {
+    // CORS preflight carries no credentials; the request after it does
+    if request.method == "OPTIONS" {
+        return Response::no_content();
+    }
+    if !config.daemon_token.as_deref().is_some_and(|token| authorized(request, token)) {
+        return Response::error(401, "Missing or wrong bearer token");
+    }
+    // Forms can be posted from any page without a preflight
+    if request.has_body() && !request.header("Content-Type").is_some_and(http::is_json) {
+        return Response::error(415, "Send the request body as application/json");
+    }
+
+    match route(request, config) {
+        Ok(response) => response,
+        Err(e) => Response::error(500, format!("{:#}", e)),
+    }
+}
+
+fn route(request: &Request, config: &Config) -> Result<Response> {
+    let segments: Vec<&str> = request.segments.iter().map(String::as_str).collect();
+    let method = request.method.as_str();
+    if method == "GET" && segments == ["metrics"] {
+        let library = Library::load(config)?;
+        return Ok(Response::text(metrics::CONTENT_TYPE, metrics::render(&library)));
+    }
+    let Some(rest) = segments.strip_prefix(&["api"]) else {
+        return Ok(Response::error(404, "Not found"));
+    };
+
+    match (method, rest) {
+        ("GET", ["status"]) => status(config),
+        ("GET", ["vms"]) => {
+            let library = Library::load(config)?;
+            let vms: Vec<Value> = library.vms.iter().map(|vm| library.vm_json(vm)).collect();
+            Ok(Response::ok(json!(vms)))
+        }
+        (_, ["vms", id, action @ ..]) => {
+            let library = Library::load(config)?;
+            let Some(vm) = library.vms.iter().find(|vm| vm.id == *id) else {
+                return Ok(Response::error(404, format!("No VM with id {}", id)));
+            };
+            match (method, action) {
+                ("GET", []) => Ok(Response::ok(library.vm_json(vm))),
+                ("POST", ["launch"]) => launch(&library, vm, &request.json()?),
+                ("POST", ["stop"]) => stop(&library, vm, &request.json()?),
+                ("GET", ["snapshots"]) => list_snapshots(vm),
+                ("POST", ["snapshots"]) => {
+                    let body = request.json()?;
+                    let Some(name) = body.get("name").and_then(Value::as_str) else {
+                        return Ok(Response::error(400, "A snapshot needs a name"));
+                    };
+                    change_snapshot(&library, vm, |disk| vm::create_snapshot(disk, name))
+                }
+                ("POST", ["snapshots", name, "restore"]) => {
+                    if vm::suspend::has_saved_state(&vm.path) {
+                        return Ok(Response::error(409, format!("{} is suspended; resume it or discard its saved state first", vm.display_name())));
+                    }
+                    change_snapshot(&library, vm, |disk| vm::restore_snapshot(disk, name))
+                }
+                ("DELETE", ["snapshots", name]) => {
+                    change_snapshot(&library, vm, |disk| vm::delete_snapshot(disk, name))
+                }
+                _ => Ok(Response::error(404, "Not found")),
+            }
+        }
+        _ => Ok(Response::error(404, "Not found")),
+    }
+}
+
+/// The library as it is right now, with the PIDs of running VMs
+struct Library {
+    vms: Vec<DiscoveredVm>,
+    running: std::collections::HashMap<String, u32>,
+}
+
+impl Library {
+    fn load(config: &Config) -> Result<Self> {
+        let vms = if config.vm_library_path.exists() {
+            vm::discover_vms(&config.vm_library_path)?
+        } else {
+            Vec::new()
+        };
+        let running = vm::lifecycle::match_running_vms(&vms, &vm::detect_qemu_processes());
+        Ok(Self { vms, running })
+    }
+
+    fn vm_json(&self, vm: &DiscoveredVm) -> Value {
+        let pid = self.running.get(&vm.id);
+        json!({
+            "id": vm.id,
+            "name": vm.display_name(),
+            "os_profile": vm.os_profile,
+            "backend": vm.backend.id(),
+            "emulator": vm.config.emulator.command(),
+            "architecture": vm.config.emulator.architecture(),
+            "memory_mb": vm.config.memory_mb,
+            "cpu_cores": vm.config.cpu_cores,
+            "running": pid.is_some(),
+            "pid": pid,
+            "snapshots_supported": vm.config.supports_snapshots(),
+            "vnc": vm.config.vnc_display.map(vm::vnc::address),
+            "path": vm.path,
+        })
+    }
+}
+
+fn status(config: &Config) -> Result<Response> {
+    let library = Library::load(config)?;
+    let mut running: Vec<&String> = library.running.keys().collect();
+    running.sort();
+    Ok(Response::ok(json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "library": config.vm_library_path,
+        "vms": library.vms.len(),
+        "running": running,
+    })))
+}
+
+fn launch(library: &Library, vm: &DiscoveredVm, body: &Value) -> Result<Response> {
+    if library.running.contains_key(&vm.id) || vm::instance_lock::find_instance(&vm.path).is_some() {
+        return Ok(Response::error(409, format!("{} is already running", vm.display_name())));
+    }
+    let boot_mode = match (body.get("cdrom").and_then(Value::as_str), body.get("mode").and_then(Value::as_str)) {
+        (Some(iso), _) => vm::BootMode::Cdrom(PathBuf::from(iso)),
+        (None, None | Some("normal")) => vm::BootMode::Normal,
+        (None, Some("install")) => vm::BootMode::Install,
+        (None, Some("netboot")) => vm::BootMode::Network,
+        (None, Some("resume")) => vm::BootMode::Resume,
+        (None, Some(other)) => return Ok(Response::error(400, format!("Unknown boot mode {}", other))),
+    };
+    // A normal launch resumes a suspended VM; any other boot would lose its saved state
+    if !matches!(boot_mode, vm::BootMode::Normal | vm::BootMode::Resume) && vm::suspend::has_saved_state(&vm.path) {
+        return Ok(Response::error(409, format!("{} is suspended; resume it or discard its saved state first", vm.display_name())));
+    }
+
+    let result = vm::launch_vm_with_error_check(vm, &vm::LaunchOptions { boot_mode, ..Default::default() });
+    if !result.success {
+        let error = result.error.unwrap_or_else(|| "QEMU exited during startup".to_string());
+        return Ok(Response::error(500, error));
+    }
+    let usb_attached = vm::lifecycle::attach_launch_usb(vm).unwrap_or(0);
+    Ok(Response::ok(json!({"launched": vm.id, "usb_attached": usb_attached})))
+}
+
+fn stop(library: &Library, vm: &DiscoveredVm, body: &Value) -> Result<Response> {
+    let Some(&pid) = library.running.get(&vm.id) else {
+        return Ok(Response::error(409, format!("{} is not running", vm.display_name())));
+    };
+    let method = if body.get("force").and_then(Value::as_bool).unwrap_or(false) {
+        vm::force_stop_vm(pid)?;
+        "killed"
+    } else if vm.config.guest_agent && vm::guest_agent::shutdown_guest(&vm.path).is_ok() {
+        "guest_agent"
+    } else {
+        vm::stop_vm_by_pid(pid)?;
+        "signal"
+    };
+    Ok(Response::ok(json!({"stopping": vm.id, "method": method})))
+}
+
+fn snapshot_disk(vm: &DiscoveredVm) -> Result<PathBuf, Response> {
+    if !vm.config.supports_snapshots() {
+        return Err(Response::error(400, format!("{} does not support snapshots", vm.display_name())));
+    }
+    vm.config
+        .primary_disk()
+        .map(|disk| disk.path.clone())
+        .ok_or_else(|| Response::error(400, format!("{} has no disk", vm.display_name())))
+}
+
+fn list_snapshots(vm: &DiscoveredVm) -> Result<Response> {
+    let disk = match snapshot_disk(vm) {
+        Ok(disk) => disk,
+        Err(response) => return Ok(response),
+    };
+    let snapshots: Vec<Value> = vm::list_snapshots(&disk)?
+        .into_iter()
+        .map(|s| json!({"name": s.name, "size_bytes": s.size_bytes, "timestamp": s.timestamp}))
+        .collect();
+    Ok(Response::ok(json!(snapshots)))
+}
+
+/// Create, restore or delete a snapshot of a stopped VM
+fn change_snapshot(library: &Library, vm: &DiscoveredVm, change: impl FnOnce(&std::path::Path) -> Result<()>) -> Result<Response> {
+    if library.running.contains_key(&vm.id) {
+        return Ok(Response::error(409, format!("Stop {} before changing its snapshots", vm.display_name())));
+    }
+    let disk = match snapshot_disk(vm) {
+        Ok(disk) => disk,
+        Err(response) => return Ok(response),
+    };
+    // Held until the change is done
+    let _lock = match vm::instance_lock::acquire_for_editing(&vm.path) {
+        Ok(lock) => lock,
+        Err(owner) => return Ok(Response::error(409, format!("{} is {}", vm.display_name(), owner.describe()))),
+    };
+    change(&disk)?;
+    list_snapshots(vm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(raw: &str) -> Request {
+        http::read_request(&mut raw.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_routes() {
+        let config = Config {
+            vm_library_path: std::env::temp_dir().join(format!("vm-curator-daemon-{}", std::process::id())),
+            daemon_token: Some("s3cret".to_string()),
+            ..Config::default()
+        };
+        let auth = "Authorization: Bearer s3cret\r\n";
+
+        let response = handle(&request(&format!("GET /api/vms HTTP/1.1\r\n{}\r\n", auth)), &config);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, http::Body::Json(json!([])));
+
+        let response = handle(&request(&format!("POST /api/vms/dos-622/launch HTTP/1.1\r\n{}\r\n", auth)), &config);
+        assert_eq!(response.status, 404);
+        assert_eq!(handle(&request(&format!("GET /index.html HTTP/1.1\r\n{}\r\n", auth)), &config).status, 404);
+
+        // A body only as JSON, so a cross-site form post can't pass for one
+        let form = format!("POST /api/vms/dos-622/stop HTTP/1.1\r\n{}Content-Type: text/plain\r\nContent-Length: 2\r\n\r\n{{}}", auth);
+        assert_eq!(handle(&request(&form), &config).status, 415);
+        let json = format!("POST /api/vms/dos-622/stop HTTP/1.1\r\n{}Content-Type: application/json; charset=utf-8\r\nContent-Length: 2\r\n\r\n{{}}", auth);
+        assert_eq!(handle(&request(&json), &config).status, 404);
+    }
+
+    #[test]
+    fn test_token() {
+        let config = Config {
+            vm_library_path: std::env::temp_dir().join(format!("vm-curator-daemon-token-{}", std::process::id())),
+            daemon_token: Some("s3cret".to_string()),
+            ..Config::default()
+        };
+
+        assert_eq!(handle(&request("GET /api/vms HTTP/1.1\r\n\r\n"), &config).status, 401);
+        assert_eq!(handle(&request("GET /api/vms HTTP/1.1\r\nAuthorization: Bearer s3cre\r\n\r\n"), &config).status, 401);
+        let authorized = "GET /api/vms HTTP/1.1\r\nAuthorization: Bearer s3cret\r\n\r\n";
+        assert_eq!(handle(&request(authorized), &config).status, 200);
+        assert_eq!(handle(&request("OPTIONS /api/vms HTTP/1.1\r\n\r\n"), &config).status, 204);
+
+        // Without a token nothing is allowed
+        let open = Config { daemon_token: None, ..config };
+        assert_eq!(handle(&request("POST /api/vms/dos-622/launch HTTP/1.1\r\n\r\n"), &open).status, 401);
+        assert_eq!(generate_token().len(), 64);
+        assert_ne!(generate_token(), generate_token());
+    }
+}
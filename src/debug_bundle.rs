@@ -0,0 +1,183 @@
+//! Debug bundles for bug reports
+//!
+//! One archive with what a bug report needs: the application log, what the
+//! host offers (QEMU versions, KVM, 3D, networking), the config with its
+//! secrets removed and, for the VM in question, its launch script, manifest,
+//! launch logs and what the Doctor finds. Written to the home directory as
+//! `vm-curator-debug-<time>.tar.gz`, or left as a folder when tar isn't
+//! installed.
+
+use anyhow::{Context, Result};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::commands::qemu_system;
+use crate::config::Config;
+use crate::hardware::{gl, HostResources};
+use crate::vm::{doctor, qemu_log, DiscoveredVm};
+
+/// Config keys whose values are never put in a bundle
+const SECRET_KEYS: &[&str] = &["daemon_token"];
+
+/// Files copied from the VM folder
+const VM_FILES: &[&str] = &[
+    "launch.sh",
+    "vm-curator.toml",
+    qemu_log::LOG_FILE,
+    qemu_log::PREVIOUS_LOG_FILE,
+];
+
+/// Collect a bundle, with the files of `vm` if given, and return its path
+pub fn create(vm: Option<&DiscoveredVm>) -> Result<PathBuf> {
+    let name = format!("vm-curator-debug-{}", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+    let staging = std::env::temp_dir().join(&name);
+    std::fs::create_dir_all(&staging)
+        .with_context(|| format!("Failed to create {}", staging.display()))?;
+
+    std::fs::write(staging.join("host.txt"), host_report())?;
+    if let Ok(config) = std::fs::read_to_string(Config::config_file_path()) {
+        std::fs::write(staging.join("config.toml"), redact(&config))?;
+    }
+    copy_files(&crate::logging::log_dir(), &staging.join("logs"), |_| true)?;
+    if let Some(vm) = vm {
+        let vm_dir = staging.join("vm");
+        copy_files(&vm.path, &vm_dir, |name| VM_FILES.contains(&name))?;
+        if vm.backend.capabilities().qemu_args {
+            let findings: String = doctor::diagnose(vm)
+                .iter()
+                .map(|f| format!("- {}\n", f.problem))
+                .collect();
+            std::fs::write(vm_dir.join("doctor.txt"), if findings.is_empty() { "No problems found\n".to_string() } else { findings })?;
+        }
+    }
+
+    let home = dirs::home_dir().unwrap_or_else(std::env::temp_dir);
+    let archive = home.join(format!("{}.tar.gz", name));
+    let tarred = Command::new("tar")
+        .arg("-czf")
+        .arg(&archive)
+        .arg("-C")
+        .arg(std::env::temp_dir())
+        .arg(&name)
+        .status()
+        .is_ok_and(|status| status.success());
+    if tarred {
+        let _ = std::fs::remove_dir_all(&staging);
+        return Ok(archive);
+    }
+
+    // No tar: hand over the folder itself
+    let folder = home.join(&name);
+    if std::fs::rename(&staging, &folder).is_err() {
+        return Ok(staging);
+    }
+    Ok(folder)
+}
+
+/// Copy the files directly in `from` that `keep` accepts into `to`
+fn copy_files(from: &Path, to: &Path, keep: impl Fn(&str) -> bool) -> Result<()> {
+    let Ok(entries) = std::fs::read_dir(from) else {
+        return Ok(());
+    };
+    std::fs::create_dir_all(to)?;
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if entry.path().is_file() && keep(&name) {
+            std::fs::copy(entry.path(), to.join(&name))
+                .with_context(|| format!("Failed to copy {}", entry.path().display()))?;
+        }
+    }
+    Ok(())
+}
+
+/// A config file with the values of secret keys replaced
+fn redact(config: &str) -> String {
+    config
+        .lines()
+        .map(|line| {
+            let key = line.split('=').next().unwrap_or_default().trim();
+            if line.contains('=') && SECRET_KEYS.contains(&key) {
+                format!("{} = \"<redacted>\"", key)
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// What the host offers, as text
+fn host_report() -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "vm-curator {}", env!("CARGO_PKG_VERSION"));
+    let os = std::fs::read_to_string("/etc/os-release")
+        .ok()
+        .and_then(|content| {
+            content
+                .lines()
+                .find_map(|line| line.strip_prefix("PRETTY_NAME="))
+                .map(|name| name.trim_matches('"').to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+    let _ = writeln!(out, "OS: {}", os);
+    let kernel = std::fs::read_to_string("/proc/sys/kernel/osrelease").unwrap_or_default();
+    let _ = writeln!(out, "Kernel: {}", kernel.trim());
+    let cpu = std::fs::read_to_string("/proc/cpuinfo")
+        .ok()
+        .and_then(|content| {
+            content
+                .lines()
+                .find(|line| line.starts_with("model name"))
+                .and_then(|line| line.split_once(':'))
+                .map(|(_, model)| model.trim().to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+    let resources = HostResources::detect();
+    let _ = writeln!(out, "CPU: {} ({} threads)", cpu, resources.cpu_cores);
+    let _ = writeln!(out, "Memory: {} MB", resources.total_memory_mb);
+    let _ = writeln!(out, "KVM: {}", qemu_system::get_kvm_info().unwrap_or_else(|| "not available".to_string()));
+
+    let _ = writeln!(out, "\nEmulators:");
+    let emulators = qemu_system::list_available_emulators();
+    if emulators.is_empty() {
+        let _ = writeln!(out, "  none found");
+    }
+    for emulator in emulators {
+        let version = qemu_system::get_qemu_version(&emulator).unwrap_or_else(|_| "version unknown".to_string());
+        let _ = writeln!(out, "  {}: {}", emulator, version);
+    }
+
+    let gl = gl::detect_host_gl();
+    let _ = writeln!(out, "\n3D: virglrenderer {}", if gl.virglrenderer { "installed" } else { "missing" });
+    for node in &gl.render_nodes {
+        let _ = writeln!(
+            out,
+            "  {} ({}, {})",
+            node.path.display(),
+            node.driver.as_deref().unwrap_or("unknown driver"),
+            if node.accessible { "accessible" } else { "no access" }
+        );
+    }
+
+    let _ = writeln!(out, "\nNetworking: {:#?}", qemu_system::detect_network_capabilities());
+    let _ = writeln!(out, "\nTools:");
+    let _ = writeln!(out, "  SPICE viewer: {}", qemu_system::find_spice_viewer().unwrap_or("none"));
+    let _ = writeln!(out, "  virtiofsd: {}", qemu_system::is_virtiofsd_available());
+    let _ = writeln!(out, "  smbd: {}", qemu_system::is_smbd_available());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact() {
+        let config = "vm_library_path = \"/home/ada/vm-space\"\ndaemon_token = \"s3cret\"\n[[remote_hosts]]\nname = \"attic\"";
+        let redacted = redact(config);
+        assert!(!redacted.contains("s3cret"));
+        assert!(redacted.contains("daemon_token = \"<redacted>\""));
+        assert!(redacted.contains("name = \"attic\""));
+    }
+}
@@ -0,0 +1,167 @@
+//! Display formatting for sizes and dates
+//!
+//! qemu-img reports byte counts and Unix timestamps; everything the UI and
+//! CLI show goes through here so sizes always use binary units (KiB, MiB,
+//! GiB) and dates follow the user's chosen style.
+
+use chrono::{DateTime, Local, TimeZone};
+use serde::{Deserialize, Serialize};
+
+/// How dates are shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DateStyle {
+    /// 2024-01-15 09:30
+    #[default]
+    Iso,
+    /// Date order and clock of the user's locale (LC_TIME / LANG)
+    Locale,
+    /// "3 days ago"
+    Relative,
+}
+
+impl DateStyle {
+    pub const ALL: &'static [&'static str] = &["iso", "locale", "relative"];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Iso => "iso",
+            Self::Locale => "locale",
+            Self::Relative => "relative",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "iso" => Some(Self::Iso),
+            "locale" => Some(Self::Locale),
+            "relative" => Some(Self::Relative),
+            _ => None,
+        }
+    }
+}
+
+/// Format a byte count with binary units ("1.5 GiB", "512 B")
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Format a Unix timestamp in the given style
+pub fn format_timestamp(secs: i64, style: DateStyle) -> String {
+    match Local.timestamp_opt(secs, 0).single() {
+        Some(dt) => format_date(&dt, style),
+        None => "unknown".to_string(),
+    }
+}
+
+/// Format a local date/time in the given style
+pub fn format_date(dt: &DateTime<Local>, style: DateStyle) -> String {
+    match style {
+        DateStyle::Iso => dt.format("%Y-%m-%d %H:%M").to_string(),
+        DateStyle::Locale => dt.format(locale_pattern(&locale_name())).to_string(),
+        DateStyle::Relative => format_relative(dt.timestamp(), Local::now().timestamp()),
+    }
+}
+
+/// Describe how long before `now` a timestamp was ("3 days ago")
+pub fn format_relative(secs: i64, now: i64) -> String {
+    let elapsed = now - secs;
+    if elapsed < 0 {
+        return "in the future".to_string();
+    }
+    if elapsed < 60 {
+        return "just now".to_string();
+    }
+
+    let (count, unit) = match elapsed {
+        e if e < 3_600 => (e / 60, "minute"),
+        e if e < 86_400 => (e / 3_600, "hour"),
+        e if e < 86_400 * 30 => (e / 86_400, "day"),
+        e if e < 86_400 * 365 => (e / (86_400 * 30), "month"),
+        e => (e / (86_400 * 365), "year"),
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    format!("{} {}{} ago", count, unit, plural)
+}
+
+/// Locale used for dates, following the usual POSIX precedence
+fn locale_name() -> String {
+    ["LC_ALL", "LC_TIME", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|v| !v.is_empty())
+        .unwrap_or_default()
+}
+
+/// strftime pattern for a locale name like "de_DE.UTF-8"
+fn locale_pattern(locale: &str) -> &'static str {
+    let lang = locale.split(['.', '@']).next().unwrap_or("");
+    match lang {
+        "en_US" | "en_PH" => "%m/%d/%Y %I:%M %p",
+        "en_CA" | "sv_SE" | "lt_LT" => "%Y-%m-%d %H:%M",
+        l if l.starts_with("ja") || l.starts_with("zh") || l.starts_with("ko") || l.starts_with("hu") => {
+            "%Y/%m/%d %H:%M"
+        }
+        l if l.starts_with("de")
+            || l.starts_with("ru")
+            || l.starts_with("pl")
+            || l.starts_with("cs")
+            || l.starts_with("fi")
+            || l.starts_with("nb")
+            || l.starts_with("da")
+            || l.starts_with("tr") =>
+        {
+            "%d.%m.%Y %H:%M"
+        }
+        l if l.starts_with("nl") => "%d-%m-%Y %H:%M",
+        // C/POSIX has no preferred order; fall back to ISO
+        "" | "C" | "POSIX" => "%Y-%m-%d %H:%M",
+        // Most other locales (en_GB, fr, es, it, pt, ...) use day/month/year
+        _ => "%d/%m/%Y %H:%M",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1024), "1.0 KiB");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(1048576), "1.0 MiB");
+        assert_eq!(format_size(1073741824), "1.0 GiB");
+        assert_eq!(format_size(5 * 1024 * 1024 * 1024 * 1024), "5.0 TiB");
+    }
+
+    #[test]
+    fn test_format_relative() {
+        let now = 1_700_000_000;
+        assert_eq!(format_relative(now - 10, now), "just now");
+        assert_eq!(format_relative(now - 60, now), "1 minute ago");
+        assert_eq!(format_relative(now - 3 * 86_400, now), "3 days ago");
+        assert_eq!(format_relative(now - 400 * 86_400, now), "1 year ago");
+        assert_eq!(format_relative(now + 100, now), "in the future");
+    }
+
+    #[test]
+    fn test_locale_pattern() {
+        assert_eq!(locale_pattern("en_US.UTF-8"), "%m/%d/%Y %I:%M %p");
+        assert_eq!(locale_pattern("de_DE.UTF-8"), "%d.%m.%Y %H:%M");
+        assert_eq!(locale_pattern("en_GB.UTF-8"), "%d/%m/%Y %H:%M");
+        assert_eq!(locale_pattern("C"), "%Y-%m-%d %H:%M");
+    }
+}
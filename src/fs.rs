@@ -81,6 +81,83 @@ fn is_btrfs_from_mounts(path: &Path) -> bool {
     false
 }
 
+/// Free space available to the user on the filesystem holding `path`
+pub fn available_space(path: &Path) -> Option<u64> {
+    let output = Command::new("df")
+        .args(["-B1", "--output=avail", path.to_str()?])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    // Header line, then the value
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .nth(1)?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Replace a file's content by renaming a new file over it
+///
+/// A process reading the file as it runs (bash reads scripts a piece at a
+/// time) keeps the old content instead of resuming at its offset in the
+/// new one. The file's permissions carry over.
+pub fn replace_file(path: &Path, content: impl AsRef<[u8]>) -> Result<()> {
+    let name = path.file_name().context("Not a file path")?.to_string_lossy();
+    let temp = path.with_file_name(format!(".{}.tmp", name));
+    std::fs::write(&temp, content).with_context(|| format!("Failed to write {}", temp.display()))?;
+    let result = std::fs::metadata(path)
+        .and_then(|meta| std::fs::set_permissions(&temp, meta.permissions()))
+        .and_then(|()| std::fs::rename(&temp, path));
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&temp);
+        return Err(e).with_context(|| format!("Failed to replace {}", path.display()));
+    }
+    Ok(())
+}
+
+/// Set a file's permission bits (ignored on hosts without them)
+pub fn set_mode(path: &Path, mode: u32) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (path, mode);
+        Ok(())
+    }
+}
+
+/// Space a file takes on disk (sparse files count what they use)
+pub fn allocated_size(meta: &std::fs::Metadata) -> u64 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        meta.blocks() * 512
+    }
+    #[cfg(not(unix))]
+    {
+        meta.len()
+    }
+}
+
+/// The UID this process runs as (None on hosts without UIDs)
+pub fn current_uid() -> Option<u32> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        std::fs::metadata("/proc/self").ok().map(|m| m.uid())
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
 /// Disable copy-on-write on a directory using chattr +C
 ///
 /// This should be called on newly created directories BEFORE any files
@@ -144,6 +221,35 @@ mod tests {
         assert!(result == true || result == false);
     }
 
+    #[test]
+    fn test_replace_file_keeps_open_readers() {
+        use std::io::Read;
+
+        let dir = std::env::temp_dir().join(format!("vm-curator-fs-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("launch.sh");
+        std::fs::write(&script, "echo one\necho two\n").unwrap();
+        set_mode(&script, 0o755).unwrap();
+
+        // Like bash, half way through the script
+        let mut reader = std::fs::File::open(&script).unwrap();
+        let mut head = [0u8; 9];
+        reader.read_exact(&mut head).unwrap();
+        replace_file(&script, "INCOMING_ARGS=\"\"\necho one\necho two\n").unwrap();
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "echo two\n");
+
+        assert!(std::fs::read_to_string(&script).unwrap().starts_with("INCOMING_ARGS"));
+        assert!(!dir.join(".launch.sh.tmp").exists());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&script).unwrap().permissions().mode() & 0o777, 0o755);
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn test_is_btrfs_root() {
         // Should work on root
@@ -0,0 +1,116 @@
+//! Host Audio Detection
+//!
+//! Finds the sound servers running on the host so VMs get an `-audiodev`
+//! backend that actually reaches the speakers, instead of QEMU's legacy
+//! default (which silently falls back to no audio on many desktops).
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Host audio backends in order of preference: (QEMU driver, label)
+pub const AUDIO_BACKENDS: &[(&str, &str)] = &[
+    ("pipewire", "PipeWire"),
+    ("pa", "PulseAudio"),
+    ("jack", "JACK"),
+    ("alsa", "ALSA"),
+    ("none", "None (silent)"),
+];
+
+/// Human-readable name for a QEMU audio driver
+pub fn backend_label(driver: &str) -> &str {
+    AUDIO_BACKENDS
+        .iter()
+        .find(|(id, _)| *id == driver)
+        .map(|(_, label)| *label)
+        .unwrap_or(driver)
+}
+
+/// Detect the host sound servers, most preferred first (QEMU driver names)
+///
+/// PipeWire hosts usually also run the PulseAudio compatibility server, so
+/// both are reported and PipeWire wins.
+pub fn detect_host_audio() -> Vec<&'static str> {
+    let runtime_dir = runtime_dir();
+    let mut found = Vec::new();
+
+    if let Some(ref dir) = runtime_dir {
+        if dir.join("pipewire-0").exists() {
+            found.push("pipewire");
+        }
+        if dir.join("pulse/native").exists() || std::env::var_os("PULSE_SERVER").is_some() {
+            found.push("pa");
+        }
+    }
+
+    let jack_running = fs::read_dir("/dev/shm")
+        .map(|entries| {
+            entries
+                .flatten()
+                .any(|e| e.file_name().to_string_lossy().starts_with("jack_"))
+        })
+        .unwrap_or(false);
+    if jack_running {
+        found.push("jack");
+    }
+
+    let has_alsa = fs::read_dir("/dev/snd")
+        .map(|entries| {
+            entries
+                .flatten()
+                .any(|e| e.file_name().to_string_lossy().starts_with("controlC"))
+        })
+        .unwrap_or(false);
+    if has_alsa {
+        found.push("alsa");
+    }
+
+    found
+}
+
+/// Backends to offer: detected host servers the emulator supports, then "none"
+///
+/// `supported` is the emulator's `-audiodev help` list; when it is unknown
+/// every detected server is offered.
+pub fn backend_options(detected: &[&str], supported: Option<&[String]>) -> Vec<String> {
+    let mut options: Vec<String> = AUDIO_BACKENDS
+        .iter()
+        .map(|(id, _)| *id)
+        .filter(|id| *id != "none" && detected.contains(id))
+        .filter(|id| supported.is_none_or(|s| s.iter().any(|d| d == id)))
+        .map(str::to_string)
+        .collect();
+    options.push("none".to_string());
+    options
+}
+
+/// The user's runtime directory, where sound server sockets live
+fn runtime_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("XDG_RUNTIME_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    let uid = crate::fs::current_uid()?;
+    let dir = Path::new("/run/user").join(uid.to_string());
+    dir.is_dir().then_some(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend_options_order_and_support() {
+        let detected = ["alsa", "pa", "pipewire"];
+        assert_eq!(backend_options(&detected, None), vec!["pipewire", "pa", "alsa", "none"]);
+
+        // QEMU built before PipeWire support (8.1)
+        let supported: Vec<String> = ["none", "alsa", "oss", "pa", "sdl", "wav"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(backend_options(&detected, Some(&supported)), vec!["pa", "alsa", "none"]);
+
+        assert_eq!(backend_options(&[], None), vec!["none"]);
+        assert_eq!(backend_label("pa"), "PulseAudio");
+        assert_eq!(backend_label("sdl"), "sdl");
+    }
+}
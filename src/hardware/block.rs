@@ -0,0 +1,182 @@
+//! Host block device enumeration
+//!
+//! Lists whole disks from sysfs so a physical drive (an old IDE disk on a
+//! USB adapter, a CF card, a USB stick) can be handed to a VM. Disks the
+//! host is using - mounted, swap, or held by LVM/LUKS/RAID - are flagged so
+//! the UI can refuse to give a guest write access to them.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A whole disk on the host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockDevice {
+    /// Kernel name (e.g. "sdb")
+    pub name: String,
+    /// Stable /dev/disk/by-id path, or /dev/<name> if there is none
+    pub path: PathBuf,
+    /// Vendor and model as reported by the drive
+    pub model: String,
+    pub size_bytes: u64,
+    pub removable: bool,
+    /// Attached over USB
+    pub usb: bool,
+    /// Where the disk or its partitions are mounted (including "[swap]")
+    pub mountpoints: Vec<String>,
+    /// A partition is claimed by device-mapper or md
+    pub held: bool,
+}
+
+impl BlockDevice {
+    /// Whether the host has the disk in use, so a guest must not write to it
+    pub fn in_use(&self) -> bool {
+        !self.mountpoints.is_empty() || self.held
+    }
+
+    /// Whether the disk holds the running system
+    pub fn is_system_disk(&self) -> bool {
+        self.mountpoints.iter().any(|m| m == "/" || m == "/boot" || m == "/usr")
+    }
+
+    /// Whether the current user can open the device for reading
+    pub fn is_readable(&self) -> bool {
+        fs::File::open(&self.path).is_ok()
+    }
+
+    /// Whether the current user can open the device for writing
+    pub fn is_writable(&self) -> bool {
+        fs::OpenOptions::new().write(true).open(&self.path).is_ok()
+    }
+}
+
+/// Enumerate whole disks (no loop, RAM, optical or device-mapper devices)
+pub fn enumerate_block_devices() -> Vec<BlockDevice> {
+    let mounts = fs::read_to_string("/proc/mounts").unwrap_or_default();
+    let swaps = fs::read_to_string("/proc/swaps").unwrap_or_default();
+
+    let Ok(entries) = fs::read_dir("/sys/block") else {
+        return Vec::new();
+    };
+
+    let mut devices: Vec<BlockDevice> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if ["loop", "ram", "zram", "sr", "dm-", "md", "nbd", "fd"]
+                .iter()
+                .any(|prefix| name.starts_with(prefix))
+            {
+                return None;
+            }
+            let sys = entry.path();
+            let read = |attr: &str| fs::read_to_string(sys.join(attr)).map(|s| s.trim().to_string()).unwrap_or_default();
+
+            let sectors: u64 = read("size").parse().unwrap_or(0);
+            if sectors == 0 {
+                return None;
+            }
+            let model = format!("{} {}", read("device/vendor"), read("device/model"))
+                .trim()
+                .to_string();
+            let usb = fs::canonicalize(&sys)
+                .map(|p| p.to_string_lossy().contains("/usb"))
+                .unwrap_or(false);
+
+            let mut mountpoints = mountpoints_of(&mounts, &name);
+            if device_lines(&swaps, &name).next().is_some() {
+                mountpoints.push("[swap]".to_string());
+            }
+
+            Some(BlockDevice {
+                path: stable_path(&name),
+                model: if model.is_empty() { name.clone() } else { model },
+                size_bytes: sectors * 512,
+                removable: read("removable") == "1",
+                usb,
+                mountpoints,
+                held: is_held(&sys, &name),
+                name,
+            })
+        })
+        .collect();
+
+    devices.sort_by(|a, b| a.name.cmp(&b.name));
+    devices
+}
+
+/// Lines of /proc/mounts or /proc/swaps whose device is `disk` or one of its partitions
+fn device_lines<'a>(table: &'a str, disk: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+    table.lines().filter(move |line| {
+        let device = line.split_whitespace().next().unwrap_or_default();
+        let Some(rest) = device.strip_prefix("/dev/").and_then(|d| d.strip_prefix(disk)) else {
+            return false;
+        };
+        // sda1 and nvme0n1p1 are partitions of sda and nvme0n1; sdaa is not
+        rest.is_empty()
+            || rest.chars().all(|c| c.is_ascii_digit())
+            || rest.strip_prefix('p').is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+    })
+}
+
+/// Mount points of a disk and its partitions, from /proc/mounts content
+fn mountpoints_of(mounts: &str, disk: &str) -> Vec<String> {
+    device_lines(mounts, disk)
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .map(|m| m.replace("\\040", " "))
+        .collect()
+}
+
+/// Whether device-mapper or md holds the disk or one of its partitions
+fn is_held(sys: &Path, disk: &str) -> bool {
+    let has_holders = |dir: &Path| {
+        fs::read_dir(dir.join("holders"))
+            .map(|mut h| h.next().is_some())
+            .unwrap_or(false)
+    };
+    if has_holders(sys) {
+        return true;
+    }
+    fs::read_dir(sys)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.file_name().to_string_lossy().starts_with(disk))
+                .any(|e| has_holders(&e.path()))
+        })
+        .unwrap_or(false)
+}
+
+/// A /dev/disk/by-id link for the disk, which survives replugging in a
+/// different order; ata-/usb-/nvme- names are preferred over wwn- ones
+fn stable_path(name: &str) -> PathBuf {
+    let dev = PathBuf::from(format!("/dev/{}", name));
+    let Ok(entries) = fs::read_dir("/dev/disk/by-id") else {
+        return dev;
+    };
+    let mut links: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| fs::canonicalize(p).is_ok_and(|target| target == dev))
+        .collect();
+    links.sort_by_key(|p| p.to_string_lossy().contains("/wwn-"));
+    links.into_iter().next().unwrap_or(dev)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mountpoints_of() {
+        let mounts = "/dev/sda2 / ext4 rw 0 0\n\
+                      /dev/sda1 /boot vfat rw 0 0\n\
+                      /dev/sdaa1 /data ext4 rw 0 0\n\
+                      /dev/sdb1 /run/media/me/OLD\\040DISK vfat rw 0 0\n\
+                      /dev/nvme0n1p3 /home ext4 rw 0 0\n\
+                      tmpfs /tmp tmpfs rw 0 0\n";
+        assert_eq!(mountpoints_of(mounts, "sda"), vec!["/", "/boot"]);
+        assert_eq!(mountpoints_of(mounts, "sdb"), vec!["/run/media/me/OLD DISK"]);
+        assert_eq!(mountpoints_of(mounts, "nvme0n1"), vec!["/home"]);
+        assert!(mountpoints_of(mounts, "sdc").is_empty());
+    }
+}
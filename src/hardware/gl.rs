@@ -0,0 +1,85 @@
+//! Host OpenGL Detection
+//!
+//! virtio-gpu 3D (virgl) renders guest OpenGL on the host GPU through a DRM
+//! render node and the virglrenderer library. This checks both are present
+//! and usable before a VM is switched to an accelerated display.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Library directories searched for virglrenderer
+const LIBRARY_DIRS: &[&str] = &[
+    "/usr/lib",
+    "/usr/lib64",
+    "/usr/lib/x86_64-linux-gnu",
+    "/usr/lib/aarch64-linux-gnu",
+    "/usr/local/lib",
+];
+
+/// A DRM render node (/dev/dri/renderD*)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderNode {
+    pub path: PathBuf,
+    /// Kernel driver bound to the GPU (amdgpu, i915, nouveau, ...)
+    pub driver: Option<String>,
+    /// Whether this user may open the node (usually the `render` group)
+    pub accessible: bool,
+}
+
+/// What the host offers for guest 3D
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HostGl {
+    pub render_nodes: Vec<RenderNode>,
+    /// libvirglrenderer is installed
+    pub virglrenderer: bool,
+}
+
+impl HostGl {
+    /// Render nodes this user can actually open
+    pub fn usable_nodes(&self) -> impl Iterator<Item = &RenderNode> {
+        self.render_nodes.iter().filter(|n| n.accessible)
+    }
+}
+
+/// Detect render nodes and virglrenderer
+pub fn detect_host_gl() -> HostGl {
+    let mut render_nodes: Vec<RenderNode> = fs::read_dir("/dev/dri")
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.file_name().to_string_lossy().starts_with("renderD"))
+                .map(|e| render_node(&e.path()))
+                .collect()
+        })
+        .unwrap_or_default();
+    render_nodes.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let virglrenderer = LIBRARY_DIRS.iter().any(|dir| {
+        fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .any(|e| e.file_name().to_string_lossy().starts_with("libvirglrenderer.so"))
+            })
+            .unwrap_or(false)
+    });
+
+    HostGl {
+        render_nodes,
+        virglrenderer,
+    }
+}
+
+fn render_node(path: &Path) -> RenderNode {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let driver = fs::read_link(Path::new("/sys/class/drm").join(&name).join("device/driver"))
+        .ok()
+        .and_then(|link| link.file_name().map(|n| n.to_string_lossy().to_string()));
+    let accessible = fs::OpenOptions::new().read(true).write(true).open(path).is_ok();
+
+    RenderNode {
+        path: path.to_path_buf(),
+        driver,
+        accessible,
+    }
+}
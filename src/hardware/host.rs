@@ -0,0 +1,282 @@
+//! Host Resource Detection
+//!
+//! Reads total RAM and CPU count from the host so the creation wizard can
+//! suggest VM sizes that actually fit on this machine, and checks a launch
+//! against the memory still free and the VMs already running, so starting
+//! one VM too many gets a warning rather than a visit from the OOM killer.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+/// Memory left for the host when capping VM memory (MB)
+const HOST_RESERVED_MB: u32 = 1024;
+
+/// Smallest memory value the wizard will offer (MB)
+const MIN_MEMORY_MB: u32 = 128;
+
+/// Host memory and CPU totals
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HostResources {
+    /// Total physical RAM in megabytes (0 if unknown)
+    pub total_memory_mb: u32,
+    /// Logical CPU count (0 if unknown)
+    pub cpu_cores: u32,
+}
+
+impl HostResources {
+    /// Detect host resources from /proc/meminfo and the scheduler
+    pub fn detect() -> Self {
+        let total_memory_mb = fs::read_to_string("/proc/meminfo")
+            .ok()
+            .and_then(|content| parse_meminfo_total_mb(&content))
+            .unwrap_or(0);
+
+        let cpu_cores = std::thread::available_parallelism()
+            .map(|n| n.get() as u32)
+            .unwrap_or(0);
+
+        Self {
+            total_memory_mb,
+            cpu_cores,
+        }
+    }
+
+    /// Upper limit for VM memory: host RAM minus a reserve for the host itself
+    pub fn max_memory_mb(&self) -> u32 {
+        if self.total_memory_mb == 0 {
+            return 1048576;
+        }
+        self.total_memory_mb
+            .saturating_sub(HOST_RESERVED_MB)
+            .max(MIN_MEMORY_MB)
+    }
+
+    /// Upper limit for VM CPU cores
+    pub fn max_cpu_cores(&self) -> u32 {
+        if self.cpu_cores == 0 {
+            256
+        } else {
+            self.cpu_cores
+        }
+    }
+
+    /// Scale a profile's suggested memory down so it stays within half of host RAM.
+    ///
+    /// The result is rounded down to a 256MB step, never below the minimum,
+    /// and never raised above the profile's own value.
+    pub fn suggest_memory_mb(&self, profile_mb: u32) -> u32 {
+        if self.total_memory_mb == 0 {
+            return profile_mb;
+        }
+        let half = self.total_memory_mb / 2;
+        if profile_mb <= half {
+            return profile_mb;
+        }
+        let rounded = (half / 256) * 256;
+        rounded.max(MIN_MEMORY_MB).min(profile_mb)
+    }
+
+    /// Cap a profile's suggested core count at the host's core count
+    pub fn suggest_cpu_cores(&self, profile_cores: u32) -> u32 {
+        if self.cpu_cores == 0 {
+            profile_cores
+        } else {
+            profile_cores.min(self.cpu_cores).max(1)
+        }
+    }
+
+    /// Warning text when the chosen memory exceeds 50% of host RAM
+    pub fn memory_warning(&self, memory_mb: u32) -> Option<String> {
+        if self.total_memory_mb == 0 || memory_mb <= self.total_memory_mb / 2 {
+            return None;
+        }
+        let percent = (memory_mb as u64 * 100) / self.total_memory_mb as u64;
+        Some(format!(
+            "{} MB is {}% of host RAM ({} MB)",
+            memory_mb, percent, self.total_memory_mb
+        ))
+    }
+}
+
+/// What to do when a launch doesn't fit on the host
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResourceCheck {
+    /// Launch without checking
+    Off,
+    /// Ask before launching
+    #[default]
+    Warn,
+    /// Don't launch a VM that needs more memory than is free
+    Refuse,
+}
+
+impl ResourceCheck {
+    pub const ALL: &'static [&'static str] = &["off", "warn", "refuse"];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Warn => "warn",
+            Self::Refuse => "refuse",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "off" => Some(Self::Off),
+            "warn" => Some(Self::Warn),
+            "refuse" => Some(Self::Refuse),
+            _ => None,
+        }
+    }
+}
+
+/// Problems with launching a VM on this host right now
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LaunchCheck {
+    /// Not enough memory; launching may get a VM killed
+    pub memory: Option<String>,
+    /// More virtual CPUs than the host has; everything slows down
+    pub cpu: Option<String>,
+}
+
+impl LaunchCheck {
+    /// Check a VM needing `memory_mb` and `cpu_cores` against the host, with
+    /// `available_mb` free (MemAvailable) and `running` (memory, cores) for
+    /// each VM already running
+    ///
+    /// Running VMs count at their full size: QEMU only takes guest memory
+    /// as the guest touches it, so a VM that has been up for a minute can
+    /// still grow into what looks free.
+    pub fn new(host: &HostResources, available_mb: Option<u32>, running: &[(u32, u32)], memory_mb: u32, cpu_cores: u32) -> Self {
+        let mut check = Self::default();
+        let running_memory: u32 = running.iter().map(|(memory, _)| memory).sum();
+        let running_cores: u32 = running.iter().map(|(_, cores)| cores).sum();
+
+        if let Some(available) = available_mb.filter(|available| memory_mb > available.saturating_sub(HOST_RESERVED_MB / 2)) {
+            check.memory = Some(format!("needs {} MB of RAM but only {} MB is free", memory_mb, available));
+        } else if host.total_memory_mb > 0 && running_memory + memory_mb > host.max_memory_mb() {
+            check.memory = Some(format!(
+                "would bring VM memory to {} of the host's {} MB ({} running VM(s) use {} MB)",
+                running_memory + memory_mb,
+                host.total_memory_mb,
+                running.len(),
+                running_memory
+            ));
+        }
+
+        if host.cpu_cores > 0 && running_cores + cpu_cores > host.cpu_cores {
+            check.cpu = Some(format!(
+                "would bring VM CPUs to {} on a host with {}",
+                running_cores + cpu_cores,
+                host.cpu_cores
+            ));
+        }
+        check
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.memory.is_none() && self.cpu.is_none()
+    }
+
+    /// The problems as one sentence
+    pub fn summary(&self) -> String {
+        [self.memory.as_deref(), self.cpu.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    /// Whether `policy` stops the launch
+    pub fn refused_by(&self, policy: ResourceCheck) -> bool {
+        policy == ResourceCheck::Refuse && self.memory.is_some()
+    }
+}
+
+/// Memory the kernel thinks can be handed out without swapping (MB)
+pub fn available_memory_mb() -> Option<u32> {
+    let content = fs::read_to_string("/proc/meminfo").ok()?;
+    parse_meminfo_mb(&content, "MemAvailable:")
+}
+
+/// Parse the MemTotal line of /proc/meminfo, returning megabytes
+fn parse_meminfo_total_mb(content: &str) -> Option<u32> {
+    parse_meminfo_mb(content, "MemTotal:")
+}
+
+/// Parse one line of /proc/meminfo, returning megabytes
+fn parse_meminfo_mb(content: &str, key: &str) -> Option<u32> {
+    content
+        .lines()
+        .find(|line| line.starts_with(key))
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|kb| kb.parse::<u64>().ok())
+        .map(|kb| (kb / 1024) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_meminfo_total() {
+        let content = "MemTotal:       16314460 kB\nMemFree:         1234567 kB\n";
+        assert_eq!(parse_meminfo_total_mb(content), Some(15932));
+        assert_eq!(parse_meminfo_total_mb("MemFree: 100 kB\n"), None);
+        assert_eq!(parse_meminfo_mb(content, "MemFree:"), Some(1205));
+    }
+
+    #[test]
+    fn test_launch_check() {
+        let host = HostResources {
+            total_memory_mb: 16384,
+            cpu_cores: 8,
+        };
+
+        // Plenty free, nothing running
+        let check = LaunchCheck::new(&host, Some(12000), &[], 4096, 2);
+        assert!(check.is_ok());
+
+        // Not enough free right now
+        let check = LaunchCheck::new(&host, Some(3000), &[], 4096, 2);
+        assert!(check.memory.as_deref().unwrap().contains("only 3000 MB is free"));
+        assert!(check.refused_by(ResourceCheck::Refuse));
+        assert!(!check.refused_by(ResourceCheck::Warn));
+
+        // Free now, but the running VMs can still grow into it
+        let check = LaunchCheck::new(&host, Some(12000), &[(8192, 4), (4096, 4)], 4096, 2);
+        assert!(check.memory.as_deref().unwrap().starts_with("would bring VM memory to 16384 of"));
+        assert_eq!(check.cpu.as_deref(), Some("would bring VM CPUs to 10 on a host with 8"));
+        assert_eq!(check.summary().matches("; ").count(), 1);
+
+        // CPU overcommit alone only warns
+        let check = LaunchCheck::new(&host, Some(12000), &[(1024, 8)], 1024, 1);
+        assert!(check.memory.is_none());
+        assert!(!check.refused_by(ResourceCheck::Refuse));
+    }
+
+    #[test]
+    fn test_suggestions_on_small_host() {
+        let host = HostResources {
+            total_memory_mb: 4096,
+            cpu_cores: 2,
+        };
+        assert_eq!(host.suggest_memory_mb(4096), 2048);
+        assert_eq!(host.suggest_memory_mb(512), 512);
+        assert_eq!(host.suggest_cpu_cores(4), 2);
+        assert_eq!(host.max_memory_mb(), 3072);
+        assert!(host.memory_warning(3072).is_some());
+        assert!(host.memory_warning(2048).is_none());
+
+        // Unknown host leaves profile values untouched
+        let unknown = HostResources {
+            total_memory_mb: 0,
+            cpu_cores: 0,
+        };
+        assert_eq!(unknown.suggest_memory_mb(8192), 8192);
+        assert_eq!(unknown.suggest_cpu_cores(8), 8);
+        assert!(unknown.memory_warning(8192).is_none());
+    }
+}
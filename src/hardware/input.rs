@@ -0,0 +1,146 @@
+//! Host Input Devices
+//!
+//! Lists the keyboards, mice and game controllers under
+//! `/dev/input/by-id`. Those links keep their names across reboots and
+//! replugging, unlike `/dev/input/eventN`, so they are what launch.sh
+//! hands to QEMU for evdev passthrough.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory of stable evdev links
+pub const BY_ID_DIR: &str = "/dev/input/by-id";
+
+/// What an input device is, from the udev link name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputKind {
+    Keyboard,
+    Mouse,
+    Joystick,
+    Other,
+}
+
+impl InputKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Keyboard => "Keyboard",
+            Self::Mouse => "Mouse",
+            Self::Joystick => "Joystick",
+            Self::Other => "Input",
+        }
+    }
+}
+
+/// An evdev device on the host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputDevice {
+    /// `/dev/input/by-id/...-event-...` link
+    pub path: PathBuf,
+    /// Product name from the link
+    pub name: String,
+    pub kind: InputKind,
+    /// Whether this user can open it (usually needs the `input` group)
+    pub readable: bool,
+    /// USB vendor and product, for devices on the USB bus
+    pub usb_id: Option<(u16, u16)>,
+    /// Kernel event node number (`/dev/input/eventN`)
+    pub event: Option<u32>,
+}
+
+/// List the host's evdev devices, keyboards first
+pub fn enumerate_input_devices() -> Result<Vec<InputDevice>> {
+    let dir = Path::new(BY_ID_DIR);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut devices: Vec<InputDevice> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", BY_ID_DIR))?
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let (name, kind) = parse_link_name(&file_name)?;
+            let path = entry.path();
+            let readable = fs::File::open(&path).is_ok();
+            // The link points at the kernel's eventN node
+            let node = fs::canonicalize(&path).ok()?;
+            let node = node.file_name()?.to_string_lossy().to_string();
+            let event = node.strip_prefix("event").and_then(|n| n.parse().ok());
+            let usb_id = usb_id(&Path::new("/sys/class/input").join(&node).join("device/id"));
+            Some(InputDevice { path, name, kind, readable, usb_id, event })
+        })
+        .collect();
+    devices.sort_by(|a, b| (a.kind as u8, &a.name, &a.path).cmp(&(b.kind as u8, &b.name, &b.path)));
+    Ok(devices)
+}
+
+/// Game controllers, in the order the kernel (and so SDL) numbers them
+pub fn enumerate_game_controllers() -> Result<Vec<InputDevice>> {
+    let mut controllers: Vec<InputDevice> = enumerate_input_devices()?
+        .into_iter()
+        .filter(|d| d.kind == InputKind::Joystick)
+        .collect();
+    controllers.sort_by_key(|d| d.event);
+    Ok(controllers)
+}
+
+/// Vendor and product from an input device's sysfs `id` directory, if it
+/// is on the USB bus
+fn usb_id(id_dir: &Path) -> Option<(u16, u16)> {
+    let read = |file: &str| {
+        fs::read_to_string(id_dir.join(file))
+            .ok()
+            .and_then(|v| u16::from_str_radix(v.trim(), 16).ok())
+    };
+    // BUS_USB in linux/input.h
+    if read("bustype")? != 0x0003 {
+        return None;
+    }
+    Some((read("vendor")?, read("product")?))
+}
+
+/// Name and kind from a by-id link such as
+/// `usb-Logitech_USB_Receiver-if02-event-mouse`; None for links that
+/// aren't event devices (the legacy `-mouse` and `-joystick` nodes)
+pub fn parse_link_name(file_name: &str) -> Option<(String, InputKind)> {
+    let (device, suffix) = file_name.rsplit_once("-event-")?;
+    let kind = match suffix {
+        "kbd" => InputKind::Keyboard,
+        "mouse" => InputKind::Mouse,
+        "joystick" => InputKind::Joystick,
+        _ => InputKind::Other,
+    };
+    // Drop the bus prefix and the USB interface number
+    let device = device.split_once('-').map_or(device, |(_, rest)| rest);
+    let device = match device.rsplit_once("-if") {
+        Some((product, interface)) if interface.chars().all(|c| c.is_ascii_digit()) => product,
+        _ => device,
+    };
+    Some((device.replace('_', " "), kind))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_link_name() {
+        assert_eq!(
+            parse_link_name("usb-Logitech_USB_Receiver-if02-event-mouse"),
+            Some(("Logitech USB Receiver".to_string(), InputKind::Mouse))
+        );
+        assert_eq!(
+            parse_link_name("usb-Corsair_K70_RGB-event-kbd"),
+            Some(("Corsair K70 RGB".to_string(), InputKind::Keyboard))
+        );
+        assert_eq!(
+            parse_link_name("usb-Microsoft_Controller_3039363431313739383635393433-event-joystick"),
+            Some(("Microsoft Controller 3039363431313739383635393433".to_string(), InputKind::Joystick))
+        );
+        assert_eq!(
+            parse_link_name("usb-Logitech_USB_Receiver-if01-event-if01"),
+            Some(("Logitech USB Receiver".to_string(), InputKind::Other))
+        );
+        assert_eq!(parse_link_name("usb-Logitech_USB_Receiver-if02-mouse"), None);
+    }
+}
@@ -1,12 +1,19 @@
+pub mod audio;
+pub mod block;
+pub mod gl;
+pub mod host;
+pub mod input;
 pub mod multi_gpu;
 pub mod pci;
 pub mod single_gpu;
 pub mod usb;
 
+pub use host::{HostResources, LaunchCheck, ResourceCheck};
 pub use multi_gpu::LookingGlassConfig;
 pub use pci::{
-    check_multi_gpu_passthrough_status, enumerate_pci_devices, find_gpu_audio_pair,
-    generate_passthrough_args, MultiGpuPassthroughStatus, PciDevice,
+    check_multi_gpu_passthrough_status, check_passthrough_setup, enumerate_pci_devices,
+    find_gpu_audio_pair, generate_passthrough_args, vfio_binding_guidance,
+    MultiGpuPassthroughStatus, PassthroughCheck, PciDevice,
 };
 pub use single_gpu::{
     check_single_gpu_support, load_config, save_config, scripts_exist, SingleGpuConfig,
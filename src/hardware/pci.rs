@@ -56,8 +56,7 @@ pub struct PciDevice {
     pub address: String,
     /// Vendor ID (e.g., 0x10de for NVIDIA)
     pub vendor_id: u16,
-    /// Device ID (reserved for future use with PCI ID database lookup)
-    #[allow(dead_code)]
+    /// Device ID (used with the vendor ID for `vfio-pci.ids=`)
     pub device_id: u16,
     /// PCI class code (e.g., 0x030000 for VGA)
     pub class_code: u32,
@@ -691,6 +690,129 @@ pub fn find_gpu_audio_pair(gpu: &PciDevice, all_devices: &[PciDevice]) -> Option
     None
 }
 
+/// Host readiness for passing through a set of selected devices
+#[derive(Debug, Clone, Default)]
+pub struct PassthroughCheck {
+    pub iommu_enabled: bool,
+    pub vfio_loaded: bool,
+    /// Problems that will stop QEMU from claiming the devices
+    pub issues: Vec<String>,
+    /// Selected devices not yet bound to vfio-pci
+    pub unbound: Vec<PciDevice>,
+}
+
+impl PassthroughCheck {
+    /// Check if the VM can start with these devices as things stand
+    pub fn is_ready(&self) -> bool {
+        self.iommu_enabled && self.vfio_loaded && self.issues.is_empty() && self.unbound.is_empty()
+    }
+
+    /// Number of problems found (each unbound device counts once)
+    pub fn problem_count(&self) -> usize {
+        usize::from(!self.iommu_enabled)
+            + usize::from(!self.vfio_loaded)
+            + self.issues.len()
+            + self.unbound.len()
+    }
+}
+
+/// Validate the host setup for passing through the selected devices
+pub fn check_passthrough_setup(selected: &[&PciDevice], all_devices: &[PciDevice]) -> PassthroughCheck {
+    PassthroughCheck {
+        iommu_enabled: check_iommu_enabled(),
+        vfio_loaded: check_vfio_modules(),
+        issues: check_iommu_groups(selected, all_devices),
+        unbound: selected
+            .iter()
+            .filter(|d| !d.is_vfio_bound())
+            .map(|d| (*d).clone())
+            .collect(),
+    }
+}
+
+/// Check that each selected device's IOMMU group can be handed over whole
+///
+/// VFIO only accepts a group when every endpoint in it is bound to vfio-pci
+/// (or has no driver). Bridges are exempt.
+fn check_iommu_groups(selected: &[&PciDevice], all_devices: &[PciDevice]) -> Vec<String> {
+    let mut issues = Vec::new();
+
+    for device in selected {
+        if device.iommu_group.is_none() {
+            issues.push(format!("{} has no IOMMU group", device.address));
+        }
+    }
+
+    let mut groups: Vec<u32> = selected.iter().filter_map(|d| d.iommu_group).collect();
+    groups.sort_unstable();
+    groups.dedup();
+
+    for group in groups {
+        for peer in all_devices.iter().filter(|d| d.iommu_group == Some(group)) {
+            let is_selected = selected.iter().any(|d| d.address == peer.address);
+            let is_bridge = (peer.class_code & 0xFF0000) == 0x060000;
+            if is_selected || is_bridge || peer.is_vfio_bound() || peer.driver.is_none() {
+                continue;
+            }
+            issues.push(format!(
+                "IOMMU group {} also contains {} ({}) using {}; select it too or bind it to vfio-pci",
+                group,
+                peer.address,
+                peer.display_name(),
+                peer.driver.as_deref().unwrap_or("no driver"),
+            ));
+        }
+    }
+
+    issues
+}
+
+/// Step-by-step instructions for binding devices to vfio-pci
+pub fn vfio_binding_guidance(devices: &[PciDevice]) -> Vec<String> {
+    if devices.is_empty() {
+        return Vec::new();
+    }
+
+    let mut ids: Vec<String> = devices
+        .iter()
+        .map(|d| format!("{:04x}:{:04x}", d.vendor_id, d.device_id))
+        .collect();
+    ids.dedup();
+    let ids = ids.join(",");
+
+    let mut drivers: Vec<&str> = devices
+        .iter()
+        .filter_map(|d| d.driver.as_deref())
+        .filter(|d| *d != "vfio-pci")
+        .collect();
+    drivers.sort_unstable();
+    drivers.dedup();
+
+    let mut lines = vec![
+        "Bind at boot (persistent):".to_string(),
+        format!("  Kernel parameter: vfio-pci.ids={}", ids),
+        "  or in /etc/modprobe.d/vfio.conf:".to_string(),
+        format!("    options vfio-pci ids={}", ids),
+    ];
+    for driver in drivers {
+        lines.push(format!("    softdep {} pre: vfio-pci", driver));
+    }
+    lines.push("  then regenerate the initramfs and reboot".to_string());
+
+    lines.push("Bind now (until reboot):".to_string());
+    lines.push("  sudo modprobe vfio-pci".to_string());
+    for device in devices {
+        let sysfs = format!("/sys/bus/pci/devices/{}", device.address);
+        lines.push(format!("  echo vfio-pci | sudo tee {}/driver_override", sysfs));
+        if device.driver.is_some() {
+            lines.push(format!("  echo {} | sudo tee {}/driver/unbind", device.address, sysfs));
+        }
+        lines.push(format!("  echo {} | sudo tee /sys/bus/pci/drivers_probe", device.address));
+    }
+
+    lines
+}
+
 /// Generate QEMU arguments for PCI passthrough
 pub fn generate_passthrough_args(devices: &[PciDevice]) -> Vec<String> {
     let mut args = Vec::new();
@@ -752,6 +874,40 @@ mod tests {
         assert!(!device.can_passthrough());
     }
 
+    #[test]
+    fn test_iommu_group_must_be_passed_whole() {
+        let device = |address: &str, class_code: u32, driver: &str| PciDevice {
+            address: address.to_string(),
+            vendor_id: 0x10de,
+            device_id: 0x2684,
+            class_code,
+            vendor_name: "NVIDIA".to_string(),
+            device_name: String::new(),
+            driver: Some(driver.to_string()),
+            iommu_group: Some(14),
+            is_boot_vga: false,
+            subsystem_vendor_id: 0,
+            subsystem_device_id: 0,
+        };
+        let all = vec![
+            device("0000:00:01.0", 0x060400, "pcieport"),
+            device("0000:01:00.0", 0x030000, "vfio-pci"),
+            device("0000:01:00.1", 0x040300, "snd_hda_intel"),
+        ];
+
+        // The audio function shares the GPU's group and still has a host driver
+        let issues = check_iommu_groups(&[&all[1]], &all);
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].contains("0000:01:00.1"));
+
+        // Selecting both functions satisfies the group; the bridge is exempt
+        assert!(check_iommu_groups(&[&all[1], &all[2]], &all).is_empty());
+
+        let guidance = vfio_binding_guidance(&[all[2].clone()]);
+        assert!(guidance.iter().any(|l| l.contains("vfio-pci.ids=10de:2684")));
+        assert!(guidance.iter().any(|l| l.contains("softdep snd_hda_intel pre: vfio-pci")));
+    }
+
     #[test]
     fn test_generate_passthrough_args() {
         let devices = vec![
@@ -1,4 +1,6 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Result};
+#[cfg(any(all(target_os = "linux", feature = "udev"), feature = "rusb", windows))]
+use anyhow::Context;
 
 /// USB version/speed classification
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -14,6 +16,7 @@ pub enum UsbVersion {
 
 impl UsbVersion {
     /// Parse USB version from sysfs speed attribute value (in Mbps)
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
     pub fn from_speed(speed: &str) -> Self {
         match speed.trim() {
             "1.5" | "12" => UsbVersion::Usb1,
@@ -24,6 +27,7 @@ impl UsbVersion {
     }
 
     /// Parse USB version from bcdUSB attribute (e.g., "0300" for USB 3.0)
+    #[cfg_attr(not(any(target_os = "linux", feature = "rusb")), allow(dead_code))]
     pub fn from_bcd_usb(bcd: u16) -> Self {
         if bcd >= 0x0300 {
             UsbVersion::Usb3
@@ -47,12 +51,12 @@ pub struct UsbDevice {
     pub product_id: u16,
     pub vendor_name: String,
     pub product_name: String,
-    /// Bus number - reserved for future bus-specific passthrough
-    #[allow(dead_code)]
+    /// Bus number, used to hot-plug this exact device
     pub bus_num: u8,
-    /// Device number - reserved for future bus-specific passthrough
-    #[allow(dead_code)]
+    /// Device number on the bus, used to hot-plug this exact device
     pub dev_num: u8,
+    /// Physical port path on the bus (e.g. "1.2"), stable across replugs
+    pub port_path: String,
     pub device_class: u8,
     /// USB version/speed classification
     pub usb_version: UsbVersion,
@@ -91,25 +95,97 @@ impl UsbDevice {
     }
 }
 
-/// Enumerate USB devices using libudev
-pub fn enumerate_usb_devices() -> Result<Vec<UsbDevice>> {
-    // Try using libudev, fall back to sysfs
-    let mut devices = match enumerate_via_udev() {
-        Ok(devs) => devs,
-        Err(e) => {
-            // Log the fallback for debugging purposes
-            eprintln!("vm-curator: libudev enumeration failed ({}), falling back to sysfs", e);
-            enumerate_via_sysfs().unwrap_or_default()
+/// A way of listing the host's USB devices
+///
+/// Which ones a build has depends on the host OS and the `udev` and `rusb`
+/// features; they are tried in the order of [`UsbBackend::available`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsbBackend {
+    /// libudev (Linux, `udev` feature)
+    #[cfg(all(target_os = "linux", feature = "udev"))]
+    Udev,
+    /// /sys/bus/usb/devices, read directly (Linux)
+    #[cfg(target_os = "linux")]
+    Sysfs,
+    /// libusb (`rusb` feature), which also gives QEMU's bus and port
+    /// numbers on Windows and macOS
+    #[cfg(feature = "rusb")]
+    Libusb,
+    /// The PnP device list through PowerShell (Windows)
+    #[cfg(windows)]
+    Pnp,
+}
+
+impl UsbBackend {
+    /// The backends in this build, best first
+    pub fn available() -> Vec<UsbBackend> {
+        vec![
+            #[cfg(all(target_os = "linux", feature = "udev"))]
+            UsbBackend::Udev,
+            #[cfg(all(feature = "rusb", not(target_os = "linux")))]
+            UsbBackend::Libusb,
+            #[cfg(target_os = "linux")]
+            UsbBackend::Sysfs,
+            #[cfg(all(feature = "rusb", target_os = "linux"))]
+            UsbBackend::Libusb,
+            #[cfg(windows)]
+            UsbBackend::Pnp,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            #[cfg(all(target_os = "linux", feature = "udev"))]
+            UsbBackend::Udev => "libudev",
+            #[cfg(target_os = "linux")]
+            UsbBackend::Sysfs => "sysfs",
+            #[cfg(feature = "rusb")]
+            UsbBackend::Libusb => "libusb",
+            #[cfg(windows)]
+            UsbBackend::Pnp => "PnP device list",
         }
-    };
+    }
 
-    // Filter out hubs and root hubs
-    devices.retain(|d| !d.is_hub());
+    /// Every device the backend sees, hubs included
+    pub fn enumerate(&self) -> Result<Vec<UsbDevice>> {
+        match *self {
+            #[cfg(all(target_os = "linux", feature = "udev"))]
+            UsbBackend::Udev => enumerate_via_udev(),
+            #[cfg(target_os = "linux")]
+            UsbBackend::Sysfs => enumerate_via_sysfs(),
+            #[cfg(feature = "rusb")]
+            UsbBackend::Libusb => enumerate_via_libusb(),
+            #[cfg(windows)]
+            UsbBackend::Pnp => enumerate_via_pnp(),
+        }
+    }
+}
 
-    Ok(devices)
+/// Enumerate USB devices with the first backend that works
+pub fn enumerate_usb_devices() -> Result<Vec<UsbDevice>> {
+    let mut failures = Vec::new();
+    for backend in UsbBackend::available() {
+        match backend.enumerate() {
+            Ok(mut devices) => {
+                // Filter out hubs and root hubs
+                devices.retain(|d| !d.is_hub());
+                return Ok(devices);
+            }
+            Err(e) => {
+                // Log the fallback for debugging purposes
+                tracing::warn!(backend = backend.name(), "USB enumeration failed: {:#}", e);
+                failures.push(format!("{}: {:#}", backend.name(), e));
+            }
+        }
+    }
+    if failures.is_empty() {
+        bail!("This build has no way to list USB devices (build with the rusb feature)");
+    }
+    bail!("Failed to list USB devices ({})", failures.join("; "))
 }
 
 /// Enumerate using libudev
+#[cfg(all(target_os = "linux", feature = "udev"))]
 fn enumerate_via_udev() -> Result<Vec<UsbDevice>> {
     use libudev::Context;
 
@@ -187,6 +263,11 @@ fn enumerate_via_udev() -> Result<Vec<UsbDevice>> {
                         .unwrap_or_default()
                 });
 
+            let port_path = device
+                .sysname()
+                .map(|name| port_from_sysname(&name.to_string_lossy()))
+                .unwrap_or_default();
+
             devices.push(UsbDevice {
                 vendor_id,
                 product_id,
@@ -194,6 +275,7 @@ fn enumerate_via_udev() -> Result<Vec<UsbDevice>> {
                 product_name,
                 bus_num,
                 dev_num,
+                port_path,
                 device_class,
                 usb_version,
             });
@@ -204,12 +286,14 @@ fn enumerate_via_udev() -> Result<Vec<UsbDevice>> {
 }
 
 /// Fallback enumeration via /sys/bus/usb/devices
+#[cfg(target_os = "linux")]
 fn enumerate_via_sysfs() -> Result<Vec<UsbDevice>> {
     let mut devices = Vec::new();
     let sysfs_path = std::path::Path::new("/sys/bus/usb/devices");
 
+    // Containers often have no USB view of the host
     if !sysfs_path.exists() {
-        return Ok(devices);
+        bail!("{} not found", sysfs_path.display());
     }
 
     for entry in std::fs::read_dir(sysfs_path)? {
@@ -260,6 +344,7 @@ fn enumerate_via_sysfs() -> Result<Vec<UsbDevice>> {
             product_name,
             bus_num,
             dev_num,
+            port_path: port_from_sysname(&name_str),
             device_class,
             usb_version,
         });
@@ -268,16 +353,154 @@ fn enumerate_via_sysfs() -> Result<Vec<UsbDevice>> {
     Ok(devices)
 }
 
+/// Enumerate through libusb
+#[cfg(feature = "rusb")]
+fn enumerate_via_libusb() -> Result<Vec<UsbDevice>> {
+    use rusb::UsbContext;
+
+    let context = rusb::Context::new().context("Failed to start libusb")?;
+    let mut devices = Vec::new();
+
+    for device in context.devices().context("Failed to list USB devices")?.iter() {
+        let Ok(descriptor) = device.device_descriptor() else {
+            continue;
+        };
+
+        // Skip root hubs (usually vendor 0x1d6b)
+        if descriptor.vendor_id() == 0x1d6b {
+            continue;
+        }
+
+        // Names need the device opened, which the OS may not allow
+        let (vendor_name, product_name) = match device.open() {
+            Ok(handle) => (
+                handle.read_manufacturer_string_ascii(&descriptor).unwrap_or_default(),
+                handle.read_product_string_ascii(&descriptor).unwrap_or_default(),
+            ),
+            Err(_) => (String::new(), String::new()),
+        };
+
+        // Detect USB version from the link speed first, fall back to bcdUSB
+        let usb_version = match device.speed() {
+            rusb::Speed::Low | rusb::Speed::Full => UsbVersion::Usb1,
+            rusb::Speed::High => UsbVersion::Usb2,
+            rusb::Speed::Super | rusb::Speed::SuperPlus => UsbVersion::Usb3,
+            _ => {
+                let version = descriptor.usb_version();
+                let bcd = (u16::from(version.major()) << 8) | (u16::from(version.minor()) << 4) | u16::from(version.sub_minor());
+                UsbVersion::from_bcd_usb(bcd)
+            }
+        };
+
+        let port_path = device
+            .port_numbers()
+            .map(|ports| ports.iter().map(u8::to_string).collect::<Vec<_>>().join("."))
+            .unwrap_or_default();
+
+        devices.push(UsbDevice {
+            vendor_id: descriptor.vendor_id(),
+            product_id: descriptor.product_id(),
+            vendor_name,
+            product_name,
+            bus_num: device.bus_number(),
+            dev_num: device.address(),
+            port_path,
+            device_class: descriptor.class_code(),
+            usb_version,
+        });
+    }
+
+    Ok(devices)
+}
+
+/// Enumerate through PowerShell's view of the PnP devices (Windows has no
+/// libudev or sysfs)
+#[cfg(windows)]
+fn enumerate_via_pnp() -> Result<Vec<UsbDevice>> {
+    let script = "Get-CimInstance Win32_PnPEntity | Where-Object { $_.DeviceID -like 'USB\\VID_*' } | \
+                  ForEach-Object { \"$($_.DeviceID)`t$($_.Manufacturer)`t$($_.Name)`t$($_.CompatibleID -join ',')\" }";
+    let output = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-Command", script])
+        .output()
+        .context("Failed to run PowerShell to list USB devices")?;
+    Ok(parse_pnp_devices(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Devices from `DeviceID<TAB>Manufacturer<TAB>Name<TAB>CompatibleIDs` lines
+///
+/// Device IDs look like `USB\VID_046D&PID_C077\5&2A7B3C&0&2`; composite
+/// devices' interfaces (`&MI_00`) are skipped, and the class comes from the
+/// `USB\Class_XX` compatible ID.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn parse_pnp_devices(output: &str) -> Vec<UsbDevice> {
+    let mut devices = Vec::new();
+    for line in output.lines() {
+        let mut fields = line.split('\t');
+        let (Some(id), manufacturer, name, compatible) = (fields.next(), fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        let Some(ids) = id.split('\\').nth(1) else {
+            continue;
+        };
+        if ids.contains("&MI_") {
+            continue;
+        }
+        let hex = |key: &str| {
+            ids.split('&')
+                .find_map(|part| part.strip_prefix(key))
+                .and_then(|value| u16::from_str_radix(value, 16).ok())
+        };
+        let (Some(vendor_id), Some(product_id)) = (hex("VID_"), hex("PID_")) else {
+            continue;
+        };
+        let device_class = compatible
+            .unwrap_or_default()
+            .split(',')
+            .find_map(|id| id.trim().strip_prefix("USB\\Class_"))
+            .and_then(|class| u8::from_str_radix(class.get(..2)?, 16).ok())
+            .unwrap_or(0);
+        // Windows puts generic driver names where the vendor would be
+        let vendor_name = manufacturer
+            .unwrap_or_default()
+            .trim()
+            .trim_start_matches('(')
+            .trim_end_matches(')');
+        devices.push(UsbDevice {
+            vendor_id,
+            product_id,
+            vendor_name: if vendor_name.starts_with("Standard") { String::new() } else { vendor_name.to_string() },
+            product_name: name.unwrap_or_default().trim().to_string(),
+            // Not the bus and port QEMU's hostbus/hostport take, so
+            // devices are matched by vendor and product ID only
+            bus_num: 0,
+            dev_num: 0,
+            port_path: String::new(),
+            device_class,
+            usb_version: UsbVersion::default(),
+        });
+    }
+    devices
+}
+
+/// Port path from a sysfs device name ("1-1.2" is port 1.2 on bus 1)
+#[cfg(target_os = "linux")]
+fn port_from_sysname(name: &str) -> String {
+    name.split_once('-').map(|(_, port)| port.to_string()).unwrap_or_default()
+}
+
+#[cfg(target_os = "linux")]
 fn read_sysfs_hex(path: &std::path::Path, attr: &str) -> Option<u16> {
     let value = std::fs::read_to_string(path.join(attr)).ok()?;
     u16::from_str_radix(value.trim(), 16).ok()
 }
 
+#[cfg(target_os = "linux")]
 fn read_sysfs_decimal(path: &std::path::Path, attr: &str) -> Option<u32> {
     let value = std::fs::read_to_string(path.join(attr)).ok()?;
     value.trim().parse().ok()
 }
 
+#[cfg(target_os = "linux")]
 fn read_sysfs_string(path: &std::path::Path, attr: &str) -> Option<String> {
     std::fs::read_to_string(path.join(attr))
         .ok()
@@ -325,8 +548,6 @@ pub fn generate_udev_rules(devices: &[UsbDevice]) -> String {
 /// Install udev rules for USB passthrough
 /// Uses pkexec (graphical sudo) if available, falls back to sudo
 pub fn install_udev_rules(devices: &[UsbDevice]) -> UdevInstallResult {
-    use std::os::unix::fs::PermissionsExt;
-
     if devices.is_empty() {
         return UdevInstallResult::Error("No devices selected".to_string());
     }
@@ -346,12 +567,7 @@ pub fn install_udev_rules(devices: &[UsbDevice]) -> UdevInstallResult {
 
     // Create file with restrictive permissions (0600) before writing content
     let file_result = std::fs::File::create(&temp_path)
-        .and_then(|file| {
-            let mut perms = file.metadata()?.permissions();
-            perms.set_mode(0o600);
-            std::fs::set_permissions(&temp_path, perms)?;
-            Ok(())
-        })
+        .and_then(|_| crate::fs::set_mode(std::path::Path::new(&temp_path), 0o600))
         .and_then(|_| std::fs::write(&temp_path, &rules_content));
 
     if let Err(e) = file_result {
@@ -459,6 +675,7 @@ mod tests {
             product_name: "M105 Mouse".to_string(),
             bus_num: 1,
             dev_num: 3,
+            port_path: "2".to_string(),
             device_class: 0,
             usb_version: UsbVersion::Usb2,
         };
@@ -476,6 +693,7 @@ mod tests {
             product_name: "M105 Mouse".to_string(),
             bus_num: 1,
             dev_num: 3,
+            port_path: "2".to_string(),
             device_class: 0,
             usb_version: UsbVersion::Usb2,
         };
@@ -515,4 +733,29 @@ mod tests {
         assert!(!UsbVersion::Usb2.is_usb3());
         assert!(UsbVersion::Usb3.is_usb3());
     }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_backend_order() {
+        let backends = UsbBackend::available();
+        // sysfs is always there to fall back on, after libudev
+        assert!(backends.contains(&UsbBackend::Sysfs));
+        assert_eq!(backends.first() == Some(&UsbBackend::Sysfs), cfg!(not(feature = "udev")));
+        assert!(backends.iter().all(|backend| !backend.name().is_empty()));
+    }
+
+    #[test]
+    fn test_parse_pnp_devices() {
+        let output = "USB\\VID_046D&PID_C077\\5&2A7B3C&0&2\tLogitech\tUSB Optical Mouse\tUSB\\Class_03&SubClass_01,USB\\Class_03\n\
+                      USB\\VID_046D&PID_C077&MI_00\\6&1F&0&0000\t(Standard system devices)\tUSB Input Device\t\n\
+                      USB\\VID_05E3&PID_0610\\5&3B&0&9\t(Standard USB HUBs)\tGeneric USB Hub\tUSB\\Class_09&SubClass_00,USB\\Class_09\n\
+                      USB\\ROOT_HUB30\\4&1C&0&0\t(Standard USB HUBs)\tUSB Root Hub (USB 3.0)\t\n";
+        let devices = parse_pnp_devices(output);
+        assert_eq!(devices.len(), 2);
+        assert_eq!((devices[0].vendor_id, devices[0].product_id), (0x046d, 0xc077));
+        assert_eq!(devices[0].display_name(), "Logitech USB Optical Mouse");
+        assert_eq!(devices[0].device_class, 0x03);
+        assert!(devices[1].is_hub());
+        assert_eq!(devices[1].display_name(), "Generic USB Hub");
+    }
 }
@@ -0,0 +1,55 @@
+//! Application log
+//!
+//! What vm-curator does (launches, stops, disk and snapshot operations,
+//! errors shown to the user) is logged to `logs/vm-curator.<date>.log` in
+//! the config directory, one file per day, keeping a week of them. The
+//! level is `info`, `debug` with `--verbose`, or whatever `VM_CURATOR_LOG`
+//! says (e.g. `VM_CURATOR_LOG=vm_curator::vm=trace`).
+
+use std::path::PathBuf;
+
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{Builder, Rotation};
+use tracing_subscriber::EnvFilter;
+
+use crate::config::Config;
+
+/// Environment variable overriding the log level
+const LOG_ENV: &str = "VM_CURATOR_LOG";
+
+/// Daily log files kept
+const MAX_LOG_FILES: usize = 7;
+
+/// Directory holding the log files
+pub fn log_dir() -> PathBuf {
+    Config::config_file_path().with_file_name("logs")
+}
+
+/// Start logging to the log directory
+///
+/// The returned guard flushes the log when dropped, so it must live until
+/// the program exits. Logging is skipped (returning None) if the directory
+/// can't be written.
+pub fn init(verbose: bool) -> Option<WorkerGuard> {
+    std::fs::create_dir_all(log_dir()).ok()?;
+    let appender = Builder::new()
+        .rotation(Rotation::DAILY)
+        .filename_prefix("vm-curator")
+        .filename_suffix("log")
+        .max_log_files(MAX_LOG_FILES)
+        .build(log_dir())
+        .ok()?;
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+
+    let default_level = if verbose { "debug" } else { "info" };
+    let filter = EnvFilter::try_from_env(LOG_ENV).unwrap_or_else(|_| EnvFilter::new(default_level));
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(writer)
+        .with_ansi(false)
+        .try_init()
+        .ok()?;
+
+    tracing::info!(version = env!("CARGO_PKG_VERSION"), "vm-curator started");
+    Some(guard)
+}
@@ -1,18 +1,25 @@
 mod app;
 mod commands;
 mod config;
+mod daemon;
+mod debug_bundle;
+mod format;
 mod fs;
 mod hardware;
+mod logging;
 mod metadata;
+mod plugins;
+mod recovery;
 mod ui;
 mod vm;
 
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
 use crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture},
+    event::EnableMouseCapture,
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{enable_raw_mode, EnterAlternateScreen},
 };
 use ratatui::prelude::*;
 use std::io::{self, Write};
@@ -31,6 +38,14 @@ struct Cli {
     #[arg(short, long)]
     library: Option<PathBuf>,
 
+    /// Print machine-readable JSON instead of text
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Log debug detail to the application log
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
     /// Subcommand to run
     #[command(subcommand)]
     command: Option<Commands>,
@@ -49,16 +64,44 @@ enum Commands {
         #[arg(short, long)]
         install: bool,
         /// Boot with custom ISO
-        #[arg(short, long)]
+        #[arg(short, long, visible_alias = "iso")]
         cdrom: Option<PathBuf>,
     },
 
+    /// Create a VM from an OS profile with its suggested settings
+    Create {
+        /// OS profile ID (e.g. windows-98, linux-debian)
+        #[arg(short, long)]
+        profile: String,
+        /// VM display name (defaults to the profile's name)
+        #[arg(short, long)]
+        name: Option<String>,
+        /// Installation ISO to attach
+        #[arg(long)]
+        iso: Option<PathBuf>,
+        /// Disk size in GB (overrides the profile)
+        #[arg(long)]
+        disk_size: Option<u32>,
+        /// Memory in MB (overrides the profile)
+        #[arg(long)]
+        memory: Option<u32>,
+        /// CPU cores (overrides the profile)
+        #[arg(long)]
+        cpus: Option<u32>,
+    },
+
     /// Show VM configuration
     Info {
         /// VM name or ID
         name: String,
     },
 
+    /// Print everything known about a VM as JSON (configuration, disks, snapshots)
+    Inspect {
+        /// VM name or ID
+        name: String,
+    },
+
     /// Manage snapshots
     Snapshot {
         /// VM name or ID
@@ -69,6 +112,131 @@ enum Commands {
 
     /// List available QEMU emulators
     Emulators,
+
+    /// Identify the images in the ISO library by checksum
+    Isos {
+        #[command(subcommand)]
+        action: Option<IsoAction>,
+    },
+
+    /// Upgrade the VM library to the current layout (config files are backed up first)
+    Migrate,
+
+    /// Fetch summaries, release dates and logos from Wikipedia and Wikidata into the user metadata
+    Enrich {
+        /// OS ids to look up (defaults to every OS in the library)
+        ids: Vec<String>,
+        /// Wikipedia article to use instead of the OS name (with one OS id)
+        #[arg(long)]
+        title: Option<String>,
+        /// Use only what earlier lookups downloaded
+        #[arg(long)]
+        offline: bool,
+        /// Save every change without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Share user metadata, ASCII art and VM notes as one JSON bundle
+    Bundle {
+        #[command(subcommand)]
+        action: BundleAction,
+    },
+
+    /// Share a tuned OS profile, with its metadata and art, as a .vmprofile file
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
+
+    /// Collect logs, host information and a VM's launch script into an archive for bug reports
+    DebugBundle {
+        /// VM name or ID whose files to include
+        name: Option<String>,
+    },
+
+    /// Serve the VM library over a REST/JSON API (no TUI)
+    Daemon {
+        /// Address to listen on, overriding daemon_listen (e.g. 0.0.0.0:7340)
+        #[arg(long)]
+        listen: Option<String>,
+    },
+
+    /// Print a shell completion script to stdout
+    Completions {
+        /// Shell to generate completions for
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+
+    /// Print the man page (roff) to stdout, or write all pages to a directory
+    Man {
+        /// Write vm-curator.1 and one page per subcommand into this directory
+        #[arg(short, long)]
+        out_dir: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum BundleAction {
+    /// Write the user metadata, art and notes to a bundle
+    Export {
+        /// Bundle file to write (stdout if omitted or -)
+        file: Option<PathBuf>,
+    },
+    /// Merge a bundle into the user metadata, art and notes, asking about each conflict
+    Import {
+        /// Bundle file to read
+        file: PathBuf,
+        /// Use the bundle's version of every conflicting item without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProfileAction {
+    /// Write an OS profile with its metadata and ASCII art to a .vmprofile file
+    Export {
+        /// OS profile id (e.g. os2-warp4)
+        id: String,
+        /// File to write (defaults to <id>.vmprofile)
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+        /// Install image the profile was tested with, referenced by checksum (repeatable)
+        #[arg(long)]
+        iso: Vec<PathBuf>,
+    },
+    /// Add a .vmprofile's profile, metadata and art to yours, asking about each conflict
+    Import {
+        /// .vmprofile file to read
+        file: PathBuf,
+        /// Use the imported version of every conflicting item without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Check an install image against the images a .vmprofile was tested with
+    Verify {
+        /// .vmprofile file to read
+        file: PathBuf,
+        /// Install image to check
+        iso: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum IsoAction {
+    /// Record an image you have checked as a known image, in your iso_checksums.toml
+    Add {
+        /// Image file (read in full to checksum it)
+        iso: PathBuf,
+        /// OS profile id the image installs
+        #[arg(long)]
+        profile: String,
+        /// What the image is (defaults to the profile name and file name)
+        #[arg(long)]
+        name: Option<String>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -79,6 +247,9 @@ enum SnapshotAction {
     Create {
         /// Snapshot name
         snapshot_name: String,
+        /// What the snapshot holds, shown in the Snapshots screen
+        #[arg(short, long)]
+        description: Option<String>,
     },
     /// Restore a snapshot
     Restore {
@@ -95,6 +266,16 @@ enum SnapshotAction {
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
+    // Completions and man page don't need a config or VM library
+    match cli.command {
+        Some(Commands::Completions { shell }) => return cmd_completions(shell),
+        Some(Commands::Man { out_dir }) => return cmd_man(out_dir),
+        _ => {}
+    }
+
+    // Flushed when dropped at exit
+    let _log_guard = logging::init(cli.verbose);
+
     // Load configuration
     let mut config = Config::load()?;
 
@@ -103,18 +284,34 @@ fn main() -> Result<()> {
         config.vm_library_path = library.clone();
     }
 
-    // Check if VM library exists, prompt for setup if not
-    if !config.vm_library_path.exists() {
+    // Check if VM library exists, prompt for setup if not (a daemon has no one to ask)
+    if !config.vm_library_path.exists() && !matches!(cli.command, Some(Commands::Daemon { .. })) {
         config = prompt_vm_library_setup(config)?;
     }
 
     // Handle subcommands
     match cli.command {
-        Some(Commands::List) => cmd_list(&config),
-        Some(Commands::Launch { name, install, cdrom }) => cmd_launch(&config, &name, install, cdrom),
-        Some(Commands::Info { name }) => cmd_info(&config, &name),
-        Some(Commands::Snapshot { name, action }) => cmd_snapshot(&config, &name, action),
-        Some(Commands::Emulators) => cmd_emulators(),
+        Some(Commands::List) => cmd_list(&config, cli.json),
+        Some(Commands::Launch { name, install, cdrom }) => cmd_launch(&config, &name, install, cdrom, cli.json),
+        Some(Commands::Create { profile, name, iso, disk_size, memory, cpus }) => {
+            let overrides = CreateOverrides { disk_size, memory, cpus };
+            cmd_create(&config, &profile, name, iso, overrides, cli.json)
+        }
+        Some(Commands::Info { name }) => cmd_info(&config, &name, cli.json),
+        Some(Commands::Inspect { name }) => cmd_inspect(&config, &name),
+        Some(Commands::Snapshot { name, action }) => cmd_snapshot(&config, &name, action, cli.json),
+        Some(Commands::Emulators) => cmd_emulators(cli.json),
+        Some(Commands::Isos { action: None }) => cmd_isos(&config, cli.json),
+        Some(Commands::Isos { action: Some(IsoAction::Add { iso, profile, name }) }) => {
+            cmd_isos_add(iso, &profile, name, cli.json)
+        }
+        Some(Commands::Migrate) => cmd_migrate(&config, cli.json),
+        Some(Commands::Enrich { ids, title, offline, yes }) => cmd_enrich(&config, ids, title, offline, yes, cli.json),
+        Some(Commands::Bundle { action }) => cmd_bundle(&config, action, cli.json),
+        Some(Commands::Profile { action }) => cmd_profile(&config, action, cli.json),
+        Some(Commands::DebugBundle { name }) => cmd_debug_bundle(&config, name.as_deref(), cli.json),
+        Some(Commands::Daemon { listen }) => daemon::run(config, listen),
+        Some(Commands::Completions { .. }) | Some(Commands::Man { .. }) => unreachable!(),
         None => run_tui(config),
     }
 }
@@ -197,13 +394,7 @@ struct TerminalGuard;
 impl Drop for TerminalGuard {
     fn drop(&mut self) {
         // Best effort restoration - ignore errors since we may be panicking
-        let _ = disable_raw_mode();
-        let _ = execute!(
-            io::stdout(),
-            LeaveAlternateScreen,
-            DisableMouseCapture
-        );
-        let _ = crossterm::cursor::Show;
+        recovery::restore_terminal();
     }
 }
 
@@ -221,6 +412,10 @@ fn run_tui(config: Config) -> Result<()> {
     println!("\x1b[32m✓\x1b[0m Ready! Starting TUI...");
     std::thread::sleep(std::time::Duration::from_millis(150));
 
+    // Put the terminal back on panics and termination signals
+    recovery::install_panic_hook();
+    recovery::watch_signals()?;
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -228,13 +423,24 @@ fn run_tui(config: Config) -> Result<()> {
 
     // Create guard AFTER setup so it only cleans up if setup succeeded
     let _guard = TerminalGuard;
+    recovery::tui_started();
 
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
     // Run the app - guard will restore terminal even if this panics
     let mut app = app;
-    ui::run(&mut terminal, &mut app)
+    let result = ui::run(&mut terminal, &mut app);
+    if recovery::terminate_requested() {
+        tracing::info!("terminated by signal");
+    }
+    // Leaving with the wizard open keeps its input for the next start
+    if let Some(ref state) = app.wizard_state {
+        if let Err(e) = recovery::save_wizard_draft(state) {
+            tracing::warn!("could not save the wizard draft: {:#}", e);
+        }
+    }
+    result
 }
 
 fn print_loading_header() {
@@ -260,9 +466,51 @@ fn print_loading_progress(step: usize, total: usize, message: &str) {
     let _ = io::stdout().flush();
 }
 
-fn cmd_list(config: &Config) -> Result<()> {
+fn cmd_completions(shell: Shell) -> Result<()> {
+    let mut cmd = Cli::command();
+    let name = cmd.get_name().to_string();
+    clap_complete::generate(shell, &mut cmd, name, &mut io::stdout());
+    Ok(())
+}
+
+fn cmd_man(out_dir: Option<PathBuf>) -> Result<()> {
+    match out_dir {
+        Some(dir) => {
+            std::fs::create_dir_all(&dir)
+                .with_context(|| format!("Failed to create {:?}", dir))?;
+            clap_mangen::generate_to(Cli::command(), &dir)
+                .with_context(|| format!("Failed to write man pages to {:?}", dir))?;
+            println!("Man pages written to {:?}", dir);
+        }
+        None => {
+            let man = clap_mangen::Man::new(Cli::command());
+            man.render(&mut io::stdout())?;
+        }
+    }
+    Ok(())
+}
+
+/// Print a value as pretty JSON on stdout
+fn print_json(value: &impl serde::Serialize) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// A VM as JSON: its discovered fields plus the display name
+fn vm_json(vm: &vm::DiscoveredVm) -> Result<serde_json::Value> {
+    let mut value = serde_json::to_value(vm)?;
+    value["name"] = vm.display_name().into();
+    Ok(value)
+}
+
+fn cmd_list(config: &Config, json: bool) -> Result<()> {
     let vms = vm::discover_vms(&config.vm_library_path)?;
 
+    if json {
+        let vms = vms.iter().map(vm_json).collect::<Result<Vec<_>>>()?;
+        return print_json(&vms);
+    }
+
     if vms.is_empty() {
         println!("No VMs found in {:?}", config.vm_library_path);
         return Ok(());
@@ -297,7 +545,7 @@ fn cmd_list(config: &Config) -> Result<()> {
     Ok(())
 }
 
-fn cmd_launch(config: &Config, name: &str, install: bool, cdrom: Option<PathBuf>) -> Result<()> {
+fn cmd_launch(config: &Config, name: &str, install: bool, cdrom: Option<PathBuf>, json: bool) -> Result<()> {
     let vms = vm::discover_vms(&config.vm_library_path)?;
 
     let vm = vms
@@ -315,18 +563,122 @@ fn cmd_launch(config: &Config, name: &str, install: bool, cdrom: Option<PathBuf>
 
     let options = vm::LaunchOptions {
         boot_mode,
-        extra_args: Vec::new(),
-        usb_devices: Vec::new(),
+        ..Default::default()
     };
 
-    println!("Launching {}...", vm.display_name());
+    // Check the VM fits next to the ones already running here
+    if config.launch_resource_check != hardware::ResourceCheck::Off && vm.remote.is_none() {
+        let running = vm::lifecycle::match_running_vms(&vms, &vm::detect_qemu_processes());
+        let others: Vec<(u32, u32)> = vms
+            .iter()
+            .filter(|other| other.id != vm.id && running.contains_key(&other.id))
+            .map(|other| (other.config.memory_mb, other.config.cpu_cores))
+            .collect();
+        let host = hardware::HostResources::detect();
+        let check = hardware::LaunchCheck::new(&host, hardware::host::available_memory_mb(), &others, vm.config.memory_mb, vm.config.cpu_cores);
+        if check.refused_by(config.launch_resource_check) {
+            anyhow::bail!("{} wasn't launched: it {}", vm.display_name(), check.summary());
+        }
+        if !check.is_ok() {
+            eprintln!("Warning: {} {}", vm.display_name(), check.summary());
+        }
+    }
+
+    if !json {
+        println!("Launching {}...", vm.display_name());
+    }
     vm::launch_vm_sync(vm, &options)?;
-    println!("VM started.");
+    if !json {
+        println!("VM started.");
+    }
+
+    let usb_attached = match vm::lifecycle::attach_launch_usb(vm) {
+        Ok(n) => n,
+        Err(e) => {
+            eprintln!("Warning: failed to attach USB devices: {:#}", e);
+            0
+        }
+    };
+    if json {
+        return print_json(&serde_json::json!({"launched": vm.id, "usb_attached": usb_attached}));
+    }
+    if usb_attached > 0 {
+        println!("Attached {} USB device(s).", usb_attached);
+    }
+
+    Ok(())
+}
+
+/// Settings given on the command line for `create`, over the profile's
+struct CreateOverrides {
+    disk_size: Option<u32>,
+    memory: Option<u32>,
+    cpus: Option<u32>,
+}
+
+fn cmd_create(
+    config: &Config,
+    profile_id: &str,
+    name: Option<String>,
+    iso: Option<PathBuf>,
+    overrides: CreateOverrides,
+    json: bool,
+) -> Result<()> {
+    let profiles = load_profiles();
+    let profile = profiles.get(profile_id).ok_or_else(|| {
+        let similar: Vec<&str> = profiles.search(profile_id).iter().take(5).map(|(id, _)| id.as_str()).collect();
+        if similar.is_empty() {
+            anyhow::anyhow!("Unknown OS profile '{}'", profile_id)
+        } else {
+            anyhow::anyhow!("Unknown OS profile '{}' (did you mean: {})", profile_id, similar.join(", "))
+        }
+    })?;
+
+    if let Some(ref iso) = iso {
+        if !iso.exists() {
+            anyhow::bail!("ISO not found: {}", iso.display());
+        }
+    }
 
+    let mut state = app::CreateWizardState {
+        vm_name: name.unwrap_or_else(|| profile.display_name.clone()),
+        selected_os: Some(profile_id.to_string()),
+        iso_path: iso,
+        ..Default::default()
+    };
+    state.apply_profile(profile);
+    let host = hardware::HostResources::detect();
+    state.qemu_config.fit_to_host(&host);
+    state.qemu_config.audio_backend =
+        hardware::audio::backend_options(&hardware::audio::detect_host_audio(), None).swap_remove(0);
+    if let Some(size) = overrides.disk_size {
+        state.disk_size_gb = size;
+    }
+    // The same limits as typing the values into the wizard
+    if let Some(memory) = overrides.memory {
+        state.qemu_config.memory_mb = ui::screens::create_wizard::memory_limit(&host, Some(profile))
+            .check(memory)
+            .map_err(|e| anyhow::anyhow!("--memory {}: {}", memory, e))?;
+    }
+    if let Some(cpus) = overrides.cpus {
+        state.qemu_config.cpu_cores = ui::screens::create_wizard::cpu_limit(&host, Some(profile))
+            .check(cpus)
+            .map_err(|e| anyhow::anyhow!("--cpus {}: {}", cpus, e))?;
+    }
+    state.update_folder_name(&config.vm_library_path);
+
+    if json {
+        let created = vm::create_vm(&config.vm_library_path, &state)?;
+        return print_json(&serde_json::json!({"id": state.folder_name, "path": created.path}));
+    }
+    println!("Creating {} ({})...", state.vm_name, profile.display_name);
+    let created = vm::create_vm(&config.vm_library_path, &state)?;
+    println!("VM created in {}", created.path.display());
+    println!("Start the installation with: vm-curator launch {} --install", state.folder_name);
     Ok(())
 }
 
-fn cmd_info(config: &Config, name: &str) -> Result<()> {
+fn cmd_info(config: &Config, name: &str, json: bool) -> Result<()> {
     let vms = vm::discover_vms(&config.vm_library_path)?;
 
     let vm = vms
@@ -334,6 +686,10 @@ fn cmd_info(config: &Config, name: &str) -> Result<()> {
         .find(|v| v.id == name || v.display_name().to_lowercase() == name.to_lowercase())
         .ok_or_else(|| anyhow::anyhow!("VM '{}' not found", name))?;
 
+    if json {
+        return print_json(&vm_json(vm)?);
+    }
+
     println!("VM: {}", vm.display_name());
     println!("ID: {}", vm.id);
     println!("Path: {:?}", vm.path);
@@ -347,22 +703,68 @@ fn cmd_info(config: &Config, name: &str) -> Result<()> {
     if let Some(ref model) = vm.config.cpu_model {
         println!("  CPU Model: {}", model);
     }
+    if let Some(preset) = vm.config.cpu_era.as_deref().and_then(vm::cpu_era::find_preset) {
+        println!("  CPU Era: {} (-cpu {})", preset.name, preset.cpu);
+    }
     if let Some(ref machine) = vm.config.machine {
         println!("  Machine: {}", machine);
     }
+    if let Some(ref controller) = vm.config.usb_controller {
+        println!("  USB Controller: {}", controller);
+    }
 
     println!("  VGA: {:?}", vm.config.vga);
+    if let Some(mode) = vm.config.gpu_accel.as_deref().and_then(vm::gpu_accel::find_mode) {
+        println!("  3D Acceleration: {}", mode.name);
+    }
+    if let Some(ref resolution) = vm.config.resolution {
+        println!("  Resolution: {}", resolution);
+    }
+    if let Some(ref preset) = vm.config.display_preset {
+        println!("  Display Scaling: {}", preset.summary());
+    }
+    if let Some(ref backend) = vm.config.audio_backend {
+        println!("  Audio Output: {}", hardware::audio::backend_label(backend));
+    }
+    if let Some(ref sb16) = vm.config.sb16 {
+        println!("  Sound Blaster: BLASTER={}", sb16.blaster());
+    }
+    if let Some(synth) = vm.config.midi_synth.as_deref().and_then(vm::midi::find_synth) {
+        println!("  MIDI: COM1 -> {}", synth.name);
+    }
     println!("  KVM: {}", vm.config.enable_kvm);
     println!("  UEFI: {}", vm.config.uefi);
+    println!("  Secure Boot: {}", vm.config.secure_boot);
     println!("  TPM: {}", vm.config.tpm);
+    if let Some(ref lg) = vm.config.looking_glass {
+        println!("  Looking Glass: {}MB IVSHMEM at {}", lg.size_mb, lg.mem_path());
+    }
+    if let Some(display) = vm.config.vnc_display {
+        println!("  VNC: {}", vm::vnc::address(display));
+    }
+    if let Some(ref identity) = vm.config.identity {
+        println!("  UUID: {}", identity.uuid);
+        println!("  MAC: {}", identity.mac);
+    }
 
     println!();
     println!("Disks:");
     for disk in &vm.config.disks {
-        println!(
-            "  {:?} ({:?}, {})",
-            disk.path, disk.format, disk.interface
-        );
+        match disk.remote {
+            Some(ref remote) => println!(
+                "  {} {} ({:?}, {})",
+                remote.protocol.label(), remote.display_url(), disk.format, disk.interface
+            ),
+            None => println!(
+                "  {:?} ({:?}, {})",
+                disk.path, disk.format, disk.interface
+            ),
+        }
+    }
+    for drive in vm::disk_io::parse_disk_drives(&vm.config.raw_script) {
+        if drive.tuning != vm::disk_io::DiskTuning::default() {
+            println!("  I/O for {}: {}", drive.file, drive.tuning.summary());
+        }
     }
 
     println!();
@@ -375,7 +777,12 @@ fn cmd_info(config: &Config, name: &str) -> Result<()> {
                 println!();
                 println!("Snapshots:");
                 for snap in snapshots {
-                    println!("  {} ({}, {})", snap.name, snap.date, snap.size);
+                    println!(
+                        "  {} ({}, {})",
+                        snap.name,
+                        format::format_timestamp(snap.timestamp, config.date_format),
+                        format::format_size(snap.size_bytes)
+                    );
                 }
             }
         }
@@ -384,7 +791,64 @@ fn cmd_info(config: &Config, name: &str) -> Result<()> {
     Ok(())
 }
 
-fn cmd_snapshot(config: &Config, name: &str, action: SnapshotAction) -> Result<()> {
+fn cmd_inspect(config: &Config, name: &str) -> Result<()> {
+    let vms = vm::discover_vms(&config.vm_library_path)?;
+
+    let vm = vms
+        .iter()
+        .find(|v| v.id == name || v.display_name().to_lowercase() == name.to_lowercase())
+        .ok_or_else(|| anyhow::anyhow!("VM '{}' not found", name))?;
+
+    let running = vm::lifecycle::match_running_vms(&vms, &vm::detect_qemu_processes());
+    // Image details for local disks; snapshots live in qcow2 images only
+    let disks: Vec<serde_json::Value> = vm
+        .config
+        .disks
+        .iter()
+        .filter(|disk| disk.remote.is_none())
+        .map(|disk| {
+            let info = vm::snapshot::get_disk_info(&disk.path);
+            let snapshots = match info {
+                Ok(ref info) if info.format == "qcow2" => vm::list_snapshots(&disk.path).ok(),
+                _ => None,
+            };
+            // The image and each one it is layered on, with their bitmaps
+            let chain = commands::qemu_img::image_chain(&disk.path);
+            serde_json::json!({
+                "path": disk.path,
+                "info": info.as_ref().ok(),
+                "error": info.as_ref().err().map(|e| format!("{:#}", e)),
+                "snapshots": snapshots,
+                "backing_chain": chain,
+            })
+        })
+        .collect();
+
+    let mut value = vm_json(vm)?;
+    value["pid"] = running.get(&vm.id).copied().into();
+    value["lock"] = serde_json::to_value(vm::instance_lock::owner(&vm.path))?;
+    value["disk_images"] = disks.into();
+    print_json(&value)
+}
+
+/// Take a snapshot and record it as a manual one, with its description and
+/// the current launch script, in the disk's snapshot sidecar
+fn create_described_snapshot(
+    vm: &vm::DiscoveredVm,
+    disk: &std::path::Path,
+    snapshot_name: &str,
+    description: Option<&str>,
+) -> Result<()> {
+    vm::create_snapshot(disk, snapshot_name)?;
+    let meta = vm::snapshot_meta::SnapshotMeta::for_vm(
+        vm,
+        description.unwrap_or_default(),
+        vm::snapshot_meta::SnapshotContext::Manual,
+    );
+    vm::snapshot_meta::record(disk, snapshot_name, meta)
+}
+
+fn cmd_snapshot(config: &Config, name: &str, action: SnapshotAction, json: bool) -> Result<()> {
     let vms = vm::discover_vms(&config.vm_library_path)?;
 
     let vm = vms
@@ -393,7 +857,12 @@ fn cmd_snapshot(config: &Config, name: &str, action: SnapshotAction) -> Result<(
         .ok_or_else(|| anyhow::anyhow!("VM '{}' not found", name))?;
 
     if !vm.config.supports_snapshots() {
-        anyhow::bail!("VM '{}' does not support snapshots (raw disk format)", name);
+        let reason = vm.config.primary_disk().and_then(|disk| disk.format.limitation());
+        anyhow::bail!(
+            "VM '{}' does not support snapshots ({})",
+            name,
+            reason.unwrap_or_else(|| "no local disk".to_string())
+        );
     }
 
     let disk = vm
@@ -401,44 +870,520 @@ fn cmd_snapshot(config: &Config, name: &str, action: SnapshotAction) -> Result<(
         .primary_disk()
         .ok_or_else(|| anyhow::anyhow!("VM has no disk configured"))?;
 
-    match action {
-        SnapshotAction::List => {
+    // A suspended VM resumes onto the disk as it left it
+    if matches!(action, SnapshotAction::Restore { .. }) {
+        vm::suspend::ensure_not_suspended(&vm.path)?;
+    }
+
+    // The action runs the same either way; --json only changes the output
+    let progress = |message: String| {
+        if !json {
+            println!("{}", message);
+        }
+    };
+    let done = match action {
+        SnapshotAction::List => None,
+        SnapshotAction::Create { snapshot_name, description } => {
+            progress(format!("Creating snapshot '{}'...", snapshot_name));
+            create_described_snapshot(vm, &disk.path, &snapshot_name, description.as_deref())?;
+            Some("Snapshot created.")
+        }
+        SnapshotAction::Restore { snapshot_name } => {
+            progress(format!("Restoring snapshot '{}'...", snapshot_name));
+            vm::restore_snapshot(&disk.path, &snapshot_name)?;
+            Some("Snapshot restored.")
+        }
+        SnapshotAction::Delete { snapshot_name } => {
+            progress(format!("Deleting snapshot '{}'...", snapshot_name));
+            vm::delete_snapshot(&disk.path, &snapshot_name)?;
+            Some("Snapshot deleted.")
+        }
+    };
+
+    if json {
+        // The disk's snapshots after any change
+        return print_json(&vm::list_snapshots(&disk.path)?);
+    }
+    match done {
+        Some(message) => println!("{}", message),
+        None => {
             let snapshots = vm::list_snapshots(&disk.path)?;
             if snapshots.is_empty() {
                 println!("No snapshots for {}", vm.display_name());
             } else {
                 println!("Snapshots for {}:", vm.display_name());
                 for snap in snapshots {
-                    println!("  {} ({}, {})", snap.name, snap.date, snap.size);
+                    println!(
+                        "  {} ({}, {})",
+                        snap.name,
+                        format::format_timestamp(snap.timestamp, config.date_format),
+                        format::format_size(snap.size_bytes)
+                    );
                 }
             }
         }
-        SnapshotAction::Create { snapshot_name } => {
-            println!("Creating snapshot '{}'...", snapshot_name);
-            vm::create_snapshot(&disk.path, &snapshot_name)?;
-            println!("Snapshot created.");
+    }
+
+    Ok(())
+}
+
+fn cmd_migrate(config: &Config, json: bool) -> Result<()> {
+    let library = &config.vm_library_path;
+    let Some(pending) = vm::migration::check_library(library) else {
+        if json {
+            return print_json(&serde_json::json!({"schema": vm::migration::schema_version(library), "migrated": false}));
         }
-        SnapshotAction::Restore { snapshot_name } => {
-            println!("Restoring snapshot '{}'...", snapshot_name);
-            vm::restore_snapshot(&disk.path, &snapshot_name)?;
-            println!("Snapshot restored.");
+        println!(
+            "Library is up to date (schema {})",
+            vm::migration::schema_version(library)
+        );
+        return Ok(());
+    };
+
+    if json {
+        let report = vm::migration::migrate_library(library)?;
+        return print_json(&serde_json::json!({
+            "schema": pending.to,
+            "migrated": true,
+            "from": pending.from,
+            "steps": pending.steps,
+            "backup_dir": report.backup_dir,
+        }));
+    }
+
+    println!("Upgrading library from schema {} to {}:", pending.from, pending.to);
+    for step in &pending.steps {
+        println!("  - {}", step);
+    }
+
+    let report = vm::migration::migrate_library(library)?;
+    println!();
+    println!("Done. Previous configuration files saved to {}", report.backup_dir.display());
+    Ok(())
+}
+
+fn cmd_enrich(config: &Config, mut ids: Vec<String>, title: Option<String>, offline: bool, yes: bool, json: bool) -> Result<()> {
+    use metadata::enrich;
+
+    if title.is_some() && ids.len() != 1 {
+        anyhow::bail!("--title needs exactly one OS id");
+    }
+    let mut metadata = metadata::MetadataStore::load_embedded();
+    plugins::merge_metadata(&mut metadata);
+    if let Ok(user_metadata) = metadata::MetadataStore::load_from_dir(&config.metadata_path) {
+        metadata.merge(user_metadata);
+    }
+    if ids.is_empty() {
+        for vm in vm::discover_vms(&config.vm_library_path)? {
+            let id = metadata.key_for(&vm.id);
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
         }
-        SnapshotAction::Delete { snapshot_name } => {
-            println!("Deleting snapshot '{}'...", snapshot_name);
-            vm::delete_snapshot(&disk.path, &snapshot_name)?;
-            println!("Snapshot deleted.");
+    }
+
+    let source = enrich::Source::new(offline);
+    let mut results = Vec::new();
+    for id in &ids {
+        let info = metadata.get(id).cloned().unwrap_or_else(|| metadata::default_os_info(id));
+        let article = title.clone().unwrap_or_else(|| info.name.clone());
+        let enrichment = match enrich::lookup(&source, &article) {
+            Ok(enrichment) => enrichment,
+            Err(e) => {
+                if json {
+                    results.push(serde_json::json!({"id": id, "error": format!("{:#}", e)}));
+                } else {
+                    eprintln!("{}: {:#}", id, e);
+                }
+                continue;
+            }
+        };
+        let changes = enrichment.changes(&info, enrich::logo_path(&config.metadata_path, id).as_deref());
+
+        // JSON output is for scripts: nothing is saved without --yes
+        if json {
+            let written = if yes {
+                enrich::save(&source, &config.metadata_path, id, &info, &enrichment, &changes)?
+            } else {
+                Vec::new()
+            };
+            let changes: Vec<serde_json::Value> = changes
+                .iter()
+                .map(|c| serde_json::json!({"field": c.field.label(), "current": c.current, "proposed": c.proposed}))
+                .collect();
+            results.push(serde_json::json!({
+                "id": id,
+                "article": enrichment.title,
+                "url": enrichment.page_url,
+                "changes": changes,
+                "saved": written,
+            }));
+            continue;
+        }
+
+        println!("\x1b[1m{}\x1b[0m: {} ({})", id, enrichment.title, enrichment.page_url);
+        if changes.is_empty() {
+            println!("  Up to date");
+            println!();
+            continue;
+        }
+        let mut accepted = Vec::new();
+        for change in changes {
+            println!("  {}:", change.field.label());
+            if !change.current.is_empty() {
+                println!("    \x1b[31m- {}\x1b[0m", change.current);
+            }
+            println!("    \x1b[32m+ {}\x1b[0m", change.proposed);
+            if yes || confirm("  Use this?")? {
+                accepted.push(change);
+            }
+        }
+        for path in enrich::save(&source, &config.metadata_path, id, &info, &enrichment, &accepted)? {
+            println!("  Saved {}", path.display());
         }
+        println!();
     }
 
+    if json {
+        return print_json(&results);
+    }
     Ok(())
 }
 
-fn cmd_emulators() -> Result<()> {
-    println!("Available QEMU emulators:");
+fn cmd_bundle(config: &Config, action: BundleAction, json: bool) -> Result<()> {
+    use metadata::bundle::Bundle;
+
+    let vms = vm::discover_vms(&config.vm_library_path)?;
+    let (file, yes) = match action {
+        BundleAction::Export { file } => {
+            let bundle = Bundle::export(&config.metadata_path, &config.ascii_art_path, &vms)?;
+            let content = serde_json::to_string_pretty(&bundle)?;
+            match file.filter(|file| file.as_os_str() != "-") {
+                None => println!("{}", content),
+                Some(file) => {
+                    std::fs::write(&file, format!("{}\n", content))
+                        .with_context(|| format!("Failed to write {}", file.display()))?;
+                    let summary = serde_json::json!({
+                        "file": file,
+                        "metadata": bundle.metadata.len(),
+                        "ascii_art": bundle.ascii_art.len(),
+                        "notes": bundle.notes.len(),
+                    });
+                    if json {
+                        return print_json(&summary);
+                    }
+                    println!(
+                        "Wrote {}: {} metadata entries, {} art files, notes of {} VMs",
+                        file.display(),
+                        bundle.metadata.len(),
+                        bundle.ascii_art.len(),
+                        bundle.notes.len()
+                    );
+                }
+            }
+            return Ok(());
+        }
+        BundleAction::Import { file, yes } => (file, yes),
+    };
+
+    let bundle = Bundle::load(&file)?;
+    let items = bundle.plan(&config.metadata_path, &config.ascii_art_path, &vms)?;
+
+    // JSON output is for scripts: nothing is written without --yes
+    if json {
+        return print_json(&merge_items_json(&items, yes)?);
+    }
+    if !bundle.generator.is_empty() {
+        println!("{} (from {}, {})", file.display(), bundle.generator, bundle.exported);
+    }
+    merge_items(&items, yes)
+}
+
+/// Same profiles as the wizard, including plugins and the user's overrides
+fn load_profiles() -> metadata::QemuProfileStore {
+    let mut profiles = metadata::QemuProfileStore::load_embedded();
+    plugins::merge_profiles(&mut profiles);
+    profiles.load_user_overrides(&Config::qemu_profiles_path());
+    profiles
+}
+
+fn cmd_profile(config: &Config, action: ProfileAction, json: bool) -> Result<()> {
+    use metadata::iso_checksums::IsoChecksums;
+    use metadata::vmprofile::{IsoRef, VmProfile, EXTENSION};
+
+    match action {
+        ProfileAction::Export { id, out, iso } => {
+            let profile = load_profiles()
+                .get(&id)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("Unknown OS profile '{}'", id))?;
+            let mut metadata = metadata::MetadataStore::load_embedded();
+            plugins::merge_metadata(&mut metadata);
+            if let Ok(user_metadata) = metadata::MetadataStore::load_from_dir(&config.metadata_path) {
+                metadata.merge(user_metadata);
+            }
+            let mut ascii_art = metadata::AsciiArtStore::load_embedded();
+            plugins::merge_ascii_art(&mut ascii_art);
+            ascii_art.merge(metadata::AsciiArtStore::load_from_dir(&config.ascii_art_path));
+
+            let mut shared = VmProfile::new(
+                &id,
+                profile,
+                metadata.entries.get(&id).cloned(),
+                ascii_art.get(&id).map(str::to_string),
+            );
+            for path in &iso {
+                shared.isos.push(IsoRef::from_file(path)?);
+            }
+            let out = out.unwrap_or_else(|| PathBuf::from(format!("{}.{}", id, EXTENSION)));
+            std::fs::write(&out, shared.to_toml()?).with_context(|| format!("Failed to write {}", out.display()))?;
+            if json {
+                return print_json(&serde_json::json!({"file": out, "id": id, "isos": shared.isos.len()}));
+            }
+            println!("Wrote {}", out.display());
+            Ok(())
+        }
+        ProfileAction::Import { file, yes } => {
+            let shared = VmProfile::load(&file)?;
+            let items = shared.plan(&Config::qemu_profiles_path(), &config.metadata_path, &config.ascii_art_path)?;
+            // The tested media become known images, so the ISO library recognises them
+            let checksums_file = Config::iso_checksums_path();
+            if json {
+                let items = merge_items_json(&items, yes)?;
+                let recorded = if yes { IsoChecksums::add_user_images(&checksums_file, &shared.known_images())? } else { 0 };
+                return print_json(&serde_json::json!({
+                    "id": shared.id,
+                    "items": items,
+                    "isos": shared.isos.iter().map(|iso| serde_json::json!({
+                        "file": iso.file, "sha256": iso.sha256, "size": iso.size,
+                    })).collect::<Vec<_>>(),
+                    "isos_recorded": recorded,
+                }));
+            }
+            println!("\x1b[1m{}\x1b[0m: {}", shared.id, shared.profile.display_name);
+            merge_items(&items, yes)?;
+            if !shared.isos.is_empty() {
+                println!();
+                println!("Tested with (check yours with `vm-curator profile verify {} IMAGE`):", file.display());
+                for iso in &shared.isos {
+                    println!("  {}  {}", iso.sha256, iso.file);
+                }
+                let recorded = IsoChecksums::add_user_images(&checksums_file, &shared.known_images())?;
+                if recorded > 0 {
+                    println!("Added {} known image(s) to {}", recorded, checksums_file.display());
+                }
+            }
+            Ok(())
+        }
+        ProfileAction::Verify { file, iso } => {
+            let shared = VmProfile::load(&file)?;
+            let sha256 = IsoRef::from_file(&iso)?.sha256;
+            let matched = shared.find_iso(&sha256);
+            if json {
+                return print_json(&serde_json::json!({"sha256": sha256, "matches": matched.map(|iso| &iso.file)}));
+            }
+            match matched {
+                Some(reference) => println!("{} matches {} from {}", iso.display(), reference.file, file.display()),
+                None => anyhow::bail!("{} (sha256 {}) is not an image {} was tested with", iso.display(), sha256, file.display()),
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Write the new and (with `yes`) conflicting items of an import, for --json
+fn merge_items_json(items: &[metadata::bundle::Item], yes: bool) -> Result<Vec<serde_json::Value>> {
+    use metadata::bundle::Status;
+
+    let mut results = Vec::new();
+    for item in items {
+        let apply = yes && matches!(item.status, Status::New | Status::Conflict);
+        if apply {
+            item.apply()?;
+        }
+        results.push(serde_json::json!({
+            "kind": item.kind.label(),
+            "name": item.name,
+            "status": item.status.label(),
+            "path": item.path,
+            "written": apply,
+        }));
+    }
+    Ok(results)
+}
+
+/// Write the new items of an import, showing each conflict and asking
+/// whether to take the imported version (always, with `yes`)
+fn merge_items(items: &[metadata::bundle::Item], yes: bool) -> Result<()> {
+    use metadata::bundle::Status;
+
+    let (mut written, mut unchanged, mut skipped) = (0, 0, 0);
+    for item in items {
+        match item.status {
+            Status::Unchanged => unchanged += 1,
+            Status::NoSuchVm => {
+                println!("  Skipped notes for {}: no such VM in the library", item.name);
+                skipped += 1;
+            }
+            Status::New => {
+                item.apply()?;
+                println!("  \x1b[32mAdded\x1b[0m {} {}", item.kind.label(), item.name);
+                written += 1;
+            }
+            Status::Conflict => {
+                println!("  \x1b[1m{} {}\x1b[0m differs from {}:", item.kind.label(), item.name, item.path.display());
+                let diff = item.diff();
+                for line in diff.iter().take(20) {
+                    let color = if line.starts_with('-') { 31 } else { 32 };
+                    println!("    \x1b[{}m{}\x1b[0m", color, line);
+                }
+                if diff.len() > 20 {
+                    println!("    ... {} more lines", diff.len() - 20);
+                }
+                if yes || confirm("  Use the imported version?")? {
+                    item.apply()?;
+                    written += 1;
+                } else {
+                    skipped += 1;
+                }
+            }
+        }
+    }
+    println!("{} written, {} unchanged, {} skipped", written, unchanged, skipped);
+    Ok(())
+}
+
+/// Ask a yes/no question on the terminal (no by default)
+fn confirm(question: &str) -> Result<bool> {
+    print!("{} [y/N] ", question);
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(matches!(input.trim(), "y" | "Y" | "yes"))
+}
+
+fn cmd_debug_bundle(config: &Config, name: Option<&str>, json: bool) -> Result<()> {
+    let vms = match name {
+        Some(_) => vm::discover_vms(&config.vm_library_path)?,
+        None => Vec::new(),
+    };
+    let vm = match name {
+        Some(name) => Some(
+            vms.iter()
+                .find(|v| v.id == name || v.display_name().to_lowercase() == name.to_lowercase())
+                .ok_or_else(|| anyhow::anyhow!("VM '{}' not found", name))?,
+        ),
+        None => None,
+    };
+
+    let path = debug_bundle::create(vm)?;
+    if json {
+        return print_json(&serde_json::json!({"bundle": path}));
+    }
+    println!("Debug bundle written to {}", path.display());
+    println!("Check it for anything private before attaching it to a bug report.");
+    Ok(())
+}
+
+fn cmd_isos(config: &Config, json: bool) -> Result<()> {
+    let dir = config.iso_library();
+    let known = metadata::iso_checksums::IsoChecksums::load(&Config::iso_checksums_path(), &load_profiles());
+    let isos = vm::iso_library::scan(&dir, &known);
+
+    if json {
+        let isos: Vec<serde_json::Value> = isos
+            .iter()
+            .map(|iso| {
+                serde_json::json!({
+                    "path": iso.path,
+                    "size": iso.size,
+                    "sha256": iso.sha256,
+                    "known": iso.known,
+                })
+            })
+            .collect();
+        return print_json(&serde_json::json!({"library": dir, "isos": isos}));
+    }
+
+    println!("ISO library: {}", dir.display());
+    println!();
+
+    if isos.is_empty() {
+        println!("  No ISO images found.");
+        return Ok(());
+    }
+
+    for iso in &isos {
+        let identified = match (&iso.known, &iso.sha256) {
+            (Some(known), _) => format!("{} ({})", known.name, known.profile),
+            (None, Some(_)) => "unknown image".to_string(),
+            (None, None) => "not checked".to_string(),
+        };
+        println!("  {:<40} {:>10}  {}", iso.file_name(), format::format_size(iso.size), identified);
+    }
+
+    println!();
+    println!("{} of {} images identified", isos.iter().filter(|iso| iso.known.is_some()).count(), isos.len());
+    Ok(())
+}
+
+fn cmd_isos_add(iso: PathBuf, profile: &str, name: Option<String>, json: bool) -> Result<()> {
+    use metadata::iso_checksums::{IsoChecksums, KnownImage};
+    use metadata::vmprofile::IsoRef;
+
+    let profiles = load_profiles();
+    let Some(os) = profiles.get(profile) else {
+        anyhow::bail!("Unknown OS profile: {}", profile);
+    };
+    let reference = IsoRef::from_file(&iso)?;
+    let image = KnownImage {
+        name: name.unwrap_or_else(|| format!("{} ({})", os.display_name, reference.file)),
+        sha256: reference.sha256,
+        profile: profile.to_string(),
+        file: Some(reference.file),
+        size: reference.size,
+    };
+    let checksums_file = Config::iso_checksums_path();
+    let added = IsoChecksums::add_user_images(&checksums_file, std::slice::from_ref(&image))? > 0;
+
+    if json {
+        return print_json(&serde_json::json!({"image": image, "added": added, "file": checksums_file}));
+    }
+    print!("{}", IsoChecksums::to_toml(std::slice::from_ref(&image))?);
     println!();
+    if added {
+        println!("Added to {}", checksums_file.display());
+    } else {
+        println!("Already in {}", checksums_file.display());
+    }
+    println!("If the image is freely distributable and matches its publisher's checksum, consider");
+    println!("contributing the entry above to assets/metadata/iso_checksums.toml.");
+    Ok(())
+}
 
+fn cmd_emulators(json: bool) -> Result<()> {
     let emulators = commands::qemu_system::list_available_emulators();
 
+    if json {
+        let emulators: Vec<serde_json::Value> = emulators
+            .iter()
+            .map(|emulator| {
+                let version = commands::qemu_system::get_qemu_version(emulator).ok();
+                serde_json::json!({"command": emulator, "version": version})
+            })
+            .collect();
+        return print_json(&serde_json::json!({
+            "emulators": emulators,
+            "kvm": commands::qemu_system::is_kvm_available(),
+            "kvm_module": commands::qemu_system::get_kvm_info(),
+            "accelerator": commands::qemu_system::Accelerator::host().name(),
+            "accelerator_available": commands::qemu_system::is_accel_available(),
+        }));
+    }
+
+    println!("Available QEMU emulators:");
+    println!();
+
     if emulators.is_empty() {
         println!("  No QEMU emulators found. Please install QEMU.");
         return Ok(());
@@ -454,14 +1399,13 @@ fn cmd_emulators() -> Result<()> {
 
     println!();
 
-    if commands::qemu_system::is_kvm_available() {
-        if let Some(module) = commands::qemu_system::get_kvm_info() {
-            println!("KVM: available ({})", module);
-        } else {
-            println!("KVM: available");
-        }
+    let accel = commands::qemu_system::Accelerator::host();
+    if !commands::qemu_system::is_accel_available() {
+        println!("{}: not available", accel.label());
+    } else if let Some(module) = commands::qemu_system::get_kvm_info() {
+        println!("KVM: available ({})", module);
     } else {
-        println!("KVM: not available");
+        println!("{}: available", accel.label());
     }
 
     Ok(())
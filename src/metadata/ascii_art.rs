@@ -1,10 +1,30 @@
+use serde::Deserialize;
 use std::collections::HashMap;
 use std::path::Path;
 
+use super::os_info::strip_numeric_suffix;
+
 /// ASCII art storage and loading
 #[derive(Debug, Clone, Default)]
 pub struct AsciiArtStore {
     pub art: HashMap<String, String>,
+    /// Art ID shown for VMs in a hierarchy subcategory or family, or a profile category
+    pub fallbacks: HashMap<String, String>,
+}
+
+/// An art pack file (see `assets/ascii/pack.toml`)
+#[derive(Debug, Default, Deserialize)]
+struct ArtPack {
+    #[serde(default)]
+    fallbacks: HashMap<String, String>,
+    #[serde(default)]
+    art: Vec<PackArt>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PackArt {
+    ids: Vec<String>,
+    art: String,
 }
 
 impl AsciiArtStore {
@@ -19,7 +39,11 @@ impl AsciiArtStore {
         if let Ok(entries) = std::fs::read_dir(dir) {
             for entry in entries.flatten() {
                 let path = entry.path();
-                if path.extension().map(|e| e == "txt" || e == "ascii").unwrap_or(false) {
+                if path.extension().is_some_and(|e| e == "toml") {
+                    if let Ok(content) = std::fs::read_to_string(&path) {
+                        store.load_pack(&content);
+                    }
+                } else if path.extension().map(|e| e == "txt" || e == "ascii").unwrap_or(false) {
                     if let Ok(content) = std::fs::read_to_string(&path) {
                         let id = path.file_stem()
                             .and_then(|s| s.to_str())
@@ -96,35 +120,53 @@ impl AsciiArtStore {
         // Research OS logos
         store.art.insert("plan9".to_string(), include_str!("../../assets/ascii/plan9.txt").to_string());
 
+        // Family logos, for VMs without art of their own
+        store.art.insert("_windows".to_string(), WINDOWS_FALLBACK.to_string());
+        store.art.insert("_mac".to_string(), MAC_FALLBACK.to_string());
+        store.art.insert("_linux".to_string(), LINUX_FALLBACK.to_string());
+        store.art.insert("_dos".to_string(), DOS_FALLBACK.to_string());
+        store.art.insert("_ibm".to_string(), IBM_FALLBACK.to_string());
+
+        // Everything else comes from the art pack
+        store.load_pack(include_str!("../../assets/ascii/pack.toml"));
+
         store
     }
 
+    /// Add the art and fallbacks of an art pack
+    fn load_pack(&mut self, content: &str) {
+        let Ok(pack) = toml::from_str::<ArtPack>(content) else {
+            return;
+        };
+        for entry in pack.art {
+            for id in entry.ids {
+                self.art.insert(id, entry.art.clone());
+            }
+        }
+        self.fallbacks.extend(pack.fallbacks);
+    }
+
     /// Get ASCII art for a VM ID
     pub fn get(&self, id: &str) -> Option<&str> {
         self.art.get(id).map(|s| s.as_str())
     }
 
     /// Get ASCII art or a fallback based on OS family
-    pub fn get_or_fallback(&self, id: &str) -> &str {
-        if let Some(art) = self.get(id) {
+    ///
+    /// `groups` are the groupings the OS belongs to, most specific first
+    /// (hierarchy subcategory, profile category, hierarchy family); the
+    /// first with a fallback decides the art.
+    pub fn get_or_fallback(&self, id: &str, groups: &[&str]) -> &str {
+        // Duplicates share their original's art ("windows-98-2" -> "windows-98")
+        if let Some(art) = self.get(id).or_else(|| strip_numeric_suffix(id).and_then(|base| self.get(base))) {
             return art;
         }
 
-        // Try to find a fallback based on the name
-        let id_lower = id.to_lowercase();
-        if id_lower.contains("windows") {
-            return WINDOWS_FALLBACK;
-        } else if id_lower.contains("mac") || id_lower.contains("osx") {
-            return MAC_FALLBACK;
-        } else if id_lower.contains("linux") {
-            return LINUX_FALLBACK;
-        } else if id_lower.contains("dos") {
-            return DOS_FALLBACK;
-        } else if id_lower.contains("os2") || id_lower.contains("os-2") {
-            return IBM_FALLBACK;
-        }
-
-        DEFAULT_FALLBACK
+        groups
+            .iter()
+            .filter_map(|group| self.fallbacks.get(*group))
+            .find_map(|art_id| self.get(art_id))
+            .unwrap_or(DEFAULT_FALLBACK)
     }
 
     /// Merge user overrides
@@ -132,6 +174,7 @@ impl AsciiArtStore {
         for (id, art) in overrides.art {
             self.art.insert(id, art);
         }
+        self.fallbacks.extend(overrides.fallbacks);
     }
 }
 
@@ -142,3 +185,30 @@ const LINUX_FALLBACK: &str = include_str!("../../assets/ascii/_linux.txt");
 const DOS_FALLBACK: &str = include_str!("../../assets/ascii/_dos.txt");
 const IBM_FALLBACK: &str = include_str!("../../assets/ascii/_ibm.txt");
 const DEFAULT_FALLBACK: &str = include_str!("../../assets/ascii/_default.txt");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::HierarchyConfig;
+
+    #[test]
+    fn test_fallbacks() {
+        let store = AsciiArtStore::load_embedded();
+        let hierarchy = HierarchyConfig::load_embedded();
+        let art_for = |id: &str, category: &str| {
+            let (family, subcategory) = hierarchy.categorize(id);
+            store.get_or_fallback(id, &[&subcategory, category, &family])
+        };
+
+        // From the pack, and duplicates of it
+        assert!(art_for("openbsd", "bsd").contains("| .__/"));
+        assert_eq!(art_for("windows-nt-4", "windows"), art_for("windows-nt-351-2", "windows"));
+        // By subcategory, profile category and family
+        assert_eq!(art_for("openindiana", "unix"), store.get("solaris").unwrap());
+        assert_eq!(art_for("freedos-13", "retro"), DOS_FALLBACK);
+        assert_eq!(art_for("linux-kali", "linux"), LINUX_FALLBACK);
+        assert_eq!(art_for("my-custom-build", "windows"), WINDOWS_FALLBACK);
+        assert_eq!(art_for("morphos", "alternative"), store.get("amigaos").unwrap());
+        assert_eq!(art_for("my-custom-build", ""), DEFAULT_FALLBACK);
+    }
+}
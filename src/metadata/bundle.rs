@@ -0,0 +1,283 @@
+//! Curation bundles
+//!
+//! A bundle is one JSON file holding a user's OS metadata overrides, ASCII
+//! art and per-VM notes, so a curated collection can be shared with others.
+//! Importing one compares each item with what is already on disk: new items
+//! are added, identical ones skipped, and differing ones are conflicts the
+//! caller decides on.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use super::os_info::OsInfo;
+use crate::vm::{notes, DiscoveredVm};
+
+/// Bundle format written by this version
+const FORMAT: u32 = 1;
+
+/// A shareable set of user metadata, art and notes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Bundle {
+    pub format: u32,
+    /// Program that wrote the bundle
+    #[serde(default)]
+    pub generator: String,
+    /// When the bundle was written (RFC 3339)
+    #[serde(default)]
+    pub exported: String,
+    /// OS metadata by OS id
+    #[serde(default)]
+    pub metadata: BTreeMap<String, OsInfo>,
+    /// ASCII art and art packs by file name
+    #[serde(default)]
+    pub ascii_art: BTreeMap<String, String>,
+    /// Notes documents by VM id
+    #[serde(default)]
+    pub notes: BTreeMap<String, String>,
+}
+
+impl Bundle {
+    /// Collect the user's metadata, art and the notes of the local VMs
+    pub fn export(metadata_dir: &Path, ascii_dir: &Path, vms: &[DiscoveredVm]) -> Result<Self> {
+        let metadata = super::MetadataStore::load_from_dir(metadata_dir)?.entries.into_iter().collect();
+
+        let mut ascii_art = BTreeMap::new();
+        if ascii_dir.exists() {
+            for entry in std::fs::read_dir(ascii_dir)?.flatten() {
+                let path = entry.path();
+                let is_art = path.extension().is_some_and(|e| e == "txt" || e == "ascii" || e == "toml");
+                if let (true, Some(name)) = (is_art, path.file_name().and_then(|n| n.to_str())) {
+                    let content = std::fs::read_to_string(&path)
+                        .with_context(|| format!("Failed to read {}", path.display()))?;
+                    ascii_art.insert(name.to_string(), content);
+                }
+            }
+        }
+
+        let notes = vms
+            .iter()
+            .filter(|vm| vm.remote.is_none())
+            .map(|vm| (vm.id.clone(), notes::read(&vm.path)))
+            .filter(|(_, text)| !text.trim().is_empty())
+            .collect();
+
+        Ok(Self {
+            format: FORMAT,
+            generator: format!("vm-curator {}", env!("CARGO_PKG_VERSION")),
+            exported: chrono::Local::now().to_rfc3339(),
+            metadata,
+            ascii_art,
+            notes,
+        })
+    }
+
+    /// Read a bundle file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let bundle: Self = serde_json::from_str(&content).with_context(|| format!("{} is not a vm-curator bundle", path.display()))?;
+        if bundle.format > FORMAT {
+            bail!("{} needs a newer vm-curator (bundle format {})", path.display(), bundle.format);
+        }
+        Ok(bundle)
+    }
+
+    /// What importing the bundle would do, item by item
+    pub fn plan(&self, metadata_dir: &Path, ascii_dir: &Path, vms: &[DiscoveredVm]) -> Result<Vec<Item>> {
+        let mut items = Vec::new();
+        for (id, info) in &self.metadata {
+            let path = metadata_dir.join(format!("{}.toml", safe_name(id)?));
+            // Compared as parsed metadata, so formatting and comments don't conflict
+            let current = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| toml::from_str::<OsInfo>(&content).ok())
+                .map(|info| toml::to_string(&info))
+                .transpose()?;
+            items.push(Item::new(Kind::Metadata, id, path, current, toml::to_string(info)?));
+        }
+        for (name, art) in &self.ascii_art {
+            let path = ascii_dir.join(safe_name(name)?);
+            let current = std::fs::read_to_string(&path).ok();
+            items.push(Item::new(Kind::AsciiArt, name, path, current, art.clone()));
+        }
+        for (vm_id, text) in &self.notes {
+            // Notes only go to a VM of the same id in this library
+            let Some(vm) = vms.iter().find(|vm| vm.id == *vm_id && vm.remote.is_none()) else {
+                items.push(Item {
+                    kind: Kind::Notes,
+                    name: vm_id.clone(),
+                    path: PathBuf::new(),
+                    current: None,
+                    proposed: text.clone(),
+                    status: Status::NoSuchVm,
+                });
+                continue;
+            };
+            let current = Some(notes::read(&vm.path)).filter(|text| !text.trim().is_empty());
+            items.push(Item::new(Kind::Notes, vm_id, notes::notes_path(&vm.path), current, text.clone()));
+        }
+        Ok(items)
+    }
+}
+
+/// A file name from a bundle, refused if it could point outside its folder
+fn safe_name(name: &str) -> Result<&str> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        bail!("Unsafe name in bundle: {:?}", name);
+    }
+    Ok(name)
+}
+
+/// What a bundle item is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// A QEMU profile, an entry of the user's `qemu_profiles.toml`
+    Profile,
+    Metadata,
+    AsciiArt,
+    Notes,
+}
+
+impl Kind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Kind::Profile => "QEMU profile",
+            Kind::Metadata => "metadata",
+            Kind::AsciiArt => "ASCII art",
+            Kind::Notes => "notes",
+        }
+    }
+}
+
+/// How a bundle item compares with what is on disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    New,
+    Unchanged,
+    Conflict,
+    /// Notes for a VM this library doesn't have
+    NoSuchVm,
+}
+
+impl Status {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Status::New => "new",
+            Status::Unchanged => "unchanged",
+            Status::Conflict => "conflict",
+            Status::NoSuchVm => "no such VM",
+        }
+    }
+}
+
+/// One item of a bundle and where it would be written
+#[derive(Debug, Clone)]
+pub struct Item {
+    pub kind: Kind,
+    /// OS id, art file name or VM id
+    pub name: String,
+    /// File the item is written to (for a profile, the profiles file it is an entry of)
+    pub path: PathBuf,
+    pub current: Option<String>,
+    pub proposed: String,
+    pub status: Status,
+}
+
+impl Item {
+    pub(crate) fn new(kind: Kind, name: &str, path: PathBuf, current: Option<String>, proposed: String) -> Self {
+        let status = match current {
+            None => Status::New,
+            Some(ref current) if current.trim_end() == proposed.trim_end() => Status::Unchanged,
+            Some(_) => Status::Conflict,
+        };
+        Self { kind, name: name.to_string(), path, current, proposed, status }
+    }
+
+    /// Lines only in the current version (-) and only in the bundle's (+)
+    pub fn diff(&self) -> Vec<String> {
+        let current: Vec<&str> = self.current.as_deref().unwrap_or("").lines().collect();
+        let proposed: Vec<&str> = self.proposed.lines().collect();
+        let removed = current.iter().filter(|l| !proposed.contains(l)).map(|l| format!("- {}", l));
+        let added = proposed.iter().filter(|l| !current.contains(l)).map(|l| format!("+ {}", l));
+        removed.chain(added).collect()
+    }
+
+    /// Write the bundle's version
+    pub fn apply(&self) -> Result<()> {
+        if self.status == Status::NoSuchVm {
+            bail!("No VM named {} in the library", self.name);
+        }
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let content = match self.kind {
+            Kind::Profile => {
+                let mut profiles: toml::Table = match std::fs::read_to_string(&self.path) {
+                    Ok(content) => toml::from_str(&content).with_context(|| format!("Failed to parse {}", self.path.display()))?,
+                    Err(_) => toml::Table::new(),
+                };
+                profiles.insert(self.name.clone(), toml::Value::Table(toml::from_str(&self.proposed)?));
+                toml::to_string(&profiles)?
+            }
+            _ => self.proposed.clone(),
+        };
+        std::fs::write(&self.path, content).with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_and_plan() {
+        let root = std::env::temp_dir().join(format!("vm-curator-bundle-{}", std::process::id()));
+        let (metadata_dir, ascii_dir, vm_dir) = (root.join("metadata"), root.join("ascii"), root.join("win98"));
+        for dir in [&metadata_dir, &ascii_dir, &vm_dir] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        let vm = DiscoveredVm {
+            id: "win98".to_string(),
+            path: vm_dir.clone(),
+            launch_script: Default::default(),
+            config: Default::default(),
+            backend: Default::default(),
+            custom_name: None,
+            os_profile: None,
+            remote: None,
+        };
+        let info = crate::metadata::default_os_info("my-os");
+        std::fs::write(metadata_dir.join("my-os.toml"), toml::to_string(&info).unwrap()).unwrap();
+        std::fs::write(ascii_dir.join("my-os.txt"), "  /\\\n /  \\\n").unwrap();
+        notes::write(&vm_dir, "Use the SB16 driver").unwrap();
+
+        let bundle = Bundle::export(&metadata_dir, &ascii_dir, std::slice::from_ref(&vm)).unwrap();
+        assert_eq!(bundle.metadata.len(), 1);
+        assert_eq!(bundle.ascii_art["my-os.txt"], "  /\\\n /  \\\n");
+        assert_eq!(bundle.notes["win98"].trim(), "Use the SB16 driver");
+        let json = serde_json::to_string(&bundle).unwrap();
+        let mut bundle: Bundle = serde_json::from_str(&json).unwrap();
+
+        // Against the same files everything is unchanged
+        let items = bundle.plan(&metadata_dir, &ascii_dir, std::slice::from_ref(&vm)).unwrap();
+        assert!(items.iter().all(|item| item.status == Status::Unchanged));
+
+        bundle.notes.insert("win98".to_string(), "Use the SB16 driver\nIRQ 5".to_string());
+        bundle.notes.insert("os2".to_string(), "Warp".to_string());
+        bundle.ascii_art.insert("beos.txt".to_string(), "Be".to_string());
+        let items = bundle.plan(&metadata_dir, &ascii_dir, std::slice::from_ref(&vm)).unwrap();
+        let status = |name: &str| items.iter().find(|item| item.name == name).unwrap().status;
+        assert_eq!(status("win98"), Status::Conflict);
+        assert_eq!(status("os2"), Status::NoSuchVm);
+        assert_eq!(status("beos.txt"), Status::New);
+        let conflict = items.iter().find(|item| item.name == "win98").unwrap();
+        assert_eq!(conflict.diff(), vec!["+ IRQ 5"]);
+        conflict.apply().unwrap();
+        assert!(notes::read(&vm_dir).contains("IRQ 5"));
+
+        bundle.ascii_art.insert("../escape.txt".to_string(), String::new());
+        assert!(bundle.plan(&metadata_dir, &ascii_dir, &[]).is_err());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,338 @@
+//! Online Metadata Enrichment
+//!
+//! Looks an OS up on Wikipedia (summary and Wikidata item) and Wikidata
+//! (release date and logo), and proposes the results as changes to its
+//! metadata. Accepted changes are written to the user metadata folder as
+//! `<id>.toml`, with the logo saved in `logos/`. Every download is cached,
+//! so a lookup can be repeated, or reviewed again, without a network.
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+use super::os_info::OsInfo;
+use crate::commands::curl;
+
+const WIKIPEDIA_SUMMARY_URL: &str = "https://en.wikipedia.org/api/rest_v1/page/summary/";
+const WIKIDATA_ENTITY_URL: &str = "https://www.wikidata.org/wiki/Special:EntityData/";
+const COMMONS_FILE_URL: &str = "https://commons.wikimedia.org/wiki/Special:FilePath/";
+
+/// Wikidata properties: publication date, inception, logo image
+const PUBLICATION_DATE: &str = "P577";
+const INCEPTION: &str = "P571";
+const LOGO_IMAGE: &str = "P154";
+
+/// Where downloads come from
+pub struct Source {
+    /// Only use what is already cached
+    pub offline: bool,
+    cache_dir: PathBuf,
+}
+
+impl Source {
+    pub fn new(offline: bool) -> Self {
+        let cache_dir = dirs::cache_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("vm-curator")
+            .join("enrich");
+        Self { offline, cache_dir }
+    }
+
+    /// Download a URL, or read it from the cache when offline or the
+    /// download fails
+    fn fetch(&self, url: &str) -> Result<Vec<u8>> {
+        let cached = self.cache_dir.join(cache_name(url));
+        if self.offline {
+            return std::fs::read(&cached).with_context(|| format!("{} is not cached", url));
+        }
+        match curl::get(url) {
+            Ok(body) => {
+                // The cache is a convenience; a failed write only costs a download
+                if std::fs::create_dir_all(&self.cache_dir).is_ok() {
+                    let _ = std::fs::write(&cached, &body);
+                }
+                Ok(body)
+            }
+            Err(e) => std::fs::read(&cached).map_err(|_| e),
+        }
+    }
+
+    fn fetch_json(&self, url: &str) -> Result<Value> {
+        serde_json::from_slice(&self.fetch(url)?).with_context(|| format!("Invalid JSON from {}", url))
+    }
+}
+
+/// File name for a cached URL
+fn cache_name(url: &str) -> String {
+    let name: String = url
+        .trim_start_matches("https://")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+        .collect();
+    // Distinct URLs may sanitize to the same name
+    format!("{}-{:016x}", name, fnv1a(url))
+}
+
+fn fnv1a(text: &str) -> u64 {
+    text.bytes()
+        .fold(0xcbf29ce484222325, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3))
+}
+
+/// Percent-encode an article or file name for a URL path
+fn encode_title(title: &str) -> String {
+    title
+        .trim()
+        .replace(' ', "_")
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'_' | b'-' | b'.' | b'(' | b')' | b',' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// What was found online for an OS
+#[derive(Debug, Clone, Default)]
+pub struct Enrichment {
+    /// Wikipedia article used
+    pub title: String,
+    pub page_url: String,
+    pub summary: String,
+    /// Release date as YYYY-MM-DD (January 1st when only the year is known)
+    pub release_date: Option<String>,
+    /// Logo on Wikimedia Commons
+    pub logo_url: Option<String>,
+}
+
+/// Look an article up on Wikipedia and its item on Wikidata
+pub fn lookup(source: &Source, title: &str) -> Result<Enrichment> {
+    let summary = source.fetch_json(&format!("{}{}", WIKIPEDIA_SUMMARY_URL, encode_title(title)))?;
+    let (mut enrichment, item) = parse_summary(&summary)?;
+    if let Some(item) = item {
+        let entity = source.fetch_json(&format!("{}{}.json", WIKIDATA_ENTITY_URL, item))?;
+        let claims = &entity["entities"][item.as_str()]["claims"];
+        enrichment.release_date = earliest_date(&claims[PUBLICATION_DATE]).or_else(|| earliest_date(&claims[INCEPTION]));
+        enrichment.logo_url = claims[LOGO_IMAGE][0]["mainsnak"]["datavalue"]["value"]
+            .as_str()
+            .map(|file| format!("{}{}", COMMONS_FILE_URL, encode_title(file)));
+    }
+    Ok(enrichment)
+}
+
+/// Article details and Wikidata item from a page summary
+fn parse_summary(summary: &Value) -> Result<(Enrichment, Option<String>)> {
+    let title = summary["title"].as_str().unwrap_or_default().to_string();
+    if summary["type"] == "disambiguation" {
+        bail!("\"{}\" is a disambiguation page; pass the exact article with --title", title);
+    }
+    let Some(extract) = summary["extract"].as_str().filter(|e| !e.is_empty()) else {
+        bail!("No summary for \"{}\"", title);
+    };
+    let enrichment = Enrichment {
+        page_url: summary["content_urls"]["desktop"]["page"].as_str().unwrap_or_default().to_string(),
+        title,
+        summary: extract.trim().to_string(),
+        ..Enrichment::default()
+    };
+    Ok((enrichment, summary["wikibase_item"].as_str().map(str::to_string)))
+}
+
+/// Earliest date among a property's claims
+fn earliest_date(claims: &Value) -> Option<String> {
+    claims
+        .as_array()?
+        .iter()
+        .filter_map(|claim| {
+            let value = &claim["mainsnak"]["datavalue"]["value"];
+            // "+1995-08-24T00:00:00Z"; precision 9 is a year, 10 a month, 11 a day
+            let time = value["time"].as_str()?.strip_prefix('+')?;
+            let (year, rest) = time.split_once('-')?;
+            let (month, rest) = rest.split_once('-')?;
+            let day = rest.get(..2)?;
+            match value["precision"].as_u64()? {
+                11.. => Some(format!("{}-{}-{}", year, month, day)),
+                10 => Some(format!("{}-{}-01", year, month)),
+                9 => Some(format!("{}-01-01", year)),
+                _ => None,
+            }
+        })
+        .min()
+}
+
+/// A part of the metadata the enrichment would change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Summary,
+    ReleaseDate,
+    Logo,
+}
+
+impl Field {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Field::Summary => "Summary",
+            Field::ReleaseDate => "Release date",
+            Field::Logo => "Logo",
+        }
+    }
+}
+
+/// A proposed change, for review before it is saved
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub field: Field,
+    pub current: String,
+    pub proposed: String,
+}
+
+/// Saved logo for an OS id, if any
+pub fn logo_path(metadata_dir: &Path, id: &str) -> Option<PathBuf> {
+    let dir = metadata_dir.join("logos");
+    std::fs::read_dir(&dir)
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .find(|p| p.file_stem().is_some_and(|stem| stem == id))
+}
+
+impl Enrichment {
+    /// What would change in an OS's metadata
+    pub fn changes(&self, info: &OsInfo, logo: Option<&Path>) -> Vec<Change> {
+        let mut changes = Vec::new();
+        if self.summary != info.blurb.long {
+            changes.push(Change {
+                field: Field::Summary,
+                current: info.blurb.long.clone(),
+                proposed: self.summary.clone(),
+            });
+        }
+        if let Some(ref date) = self.release_date {
+            if *date != info.release_date {
+                changes.push(Change {
+                    field: Field::ReleaseDate,
+                    current: info.release_date.clone(),
+                    proposed: date.clone(),
+                });
+            }
+        }
+        if let (Some(ref url), None) = (&self.logo_url, logo) {
+            changes.push(Change {
+                field: Field::Logo,
+                current: String::new(),
+                proposed: url.clone(),
+            });
+        }
+        changes
+    }
+}
+
+/// Write accepted changes to the user metadata folder; returns the files written
+pub fn save(source: &Source, metadata_dir: &Path, id: &str, info: &OsInfo, enrichment: &Enrichment, accepted: &[Change]) -> Result<Vec<PathBuf>> {
+    let mut written = Vec::new();
+    let mut info = info.clone();
+    for change in accepted {
+        match change.field {
+            Field::Summary => {
+                info.blurb.long = change.proposed.clone();
+                if info.blurb.short.is_empty() {
+                    info.blurb.short = first_sentence(&change.proposed);
+                }
+            }
+            Field::ReleaseDate => info.release_date = change.proposed.clone(),
+            Field::Logo => {
+                let bytes = source.fetch(&change.proposed)?;
+                let extension = Path::new(&change.proposed)
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .map(str::to_lowercase)
+                    .unwrap_or_else(|| "png".to_string());
+                let path = metadata_dir.join("logos").join(format!("{}.{}", id, extension));
+                std::fs::create_dir_all(metadata_dir.join("logos"))?;
+                std::fs::write(&path, bytes).with_context(|| format!("Failed to write {}", path.display()))?;
+                written.push(path);
+            }
+        }
+    }
+
+    if accepted.iter().any(|c| c.field != Field::Logo) {
+        let path = metadata_dir.join(format!("{}.toml", id));
+        let content = format!(
+            "# Summary and release date from Wikipedia and Wikidata: {}\n\
+             # Wikipedia text is available under CC BY-SA 4.0\n\n{}",
+            enrichment.page_url,
+            toml::to_string(&info)?
+        );
+        std::fs::create_dir_all(metadata_dir)?;
+        std::fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+fn first_sentence(text: &str) -> String {
+    match text.find(". ") {
+        Some(end) => text[..=end].to_string(),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_wikimedia() {
+        let summary = json!({
+            "type": "standard",
+            "title": "Windows 95",
+            "extract": "Windows 95 is a consumer-oriented operating system developed by Microsoft. It was released on August 24, 1995.",
+            "wikibase_item": "Q81994",
+            "content_urls": {"desktop": {"page": "https://en.wikipedia.org/wiki/Windows_95"}},
+        });
+        let (enrichment, item) = parse_summary(&summary).unwrap();
+        assert_eq!(item.as_deref(), Some("Q81994"));
+        assert_eq!(enrichment.page_url, "https://en.wikipedia.org/wiki/Windows_95");
+        assert!(parse_summary(&json!({"type": "disambiguation", "title": "Tiger", "extract": "Tiger may refer to"})).is_err());
+
+        let claims = json!([
+            {"mainsnak": {"datavalue": {"value": {"time": "+1995-08-24T00:00:00Z", "precision": 11}}}},
+            {"mainsnak": {"datavalue": {"value": {"time": "+1995-07-00T00:00:00Z", "precision": 10}}}},
+        ]);
+        assert_eq!(earliest_date(&claims).as_deref(), Some("1995-07-01"));
+        assert_eq!(encode_title("Mac OS X 10.0 (Cheetah)/é"), "Mac_OS_X_10.0_(Cheetah)%2F%C3%A9");
+        assert_ne!(cache_name("https://a.org/x?y"), cache_name("https://a.org/x_y"));
+    }
+
+    #[test]
+    fn test_changes_and_save() {
+        let dir = std::env::temp_dir().join(format!("vm-curator-enrich-{}", std::process::id()));
+        let source = Source { offline: true, cache_dir: dir.join("cache") };
+        let logo_url = format!("{}Windows_logo.svg", COMMONS_FILE_URL);
+        std::fs::create_dir_all(&source.cache_dir).unwrap();
+        std::fs::write(source.cache_dir.join(cache_name(&logo_url)), "<svg/>").unwrap();
+
+        let enrichment = Enrichment {
+            title: "Windows 95".to_string(),
+            page_url: "https://en.wikipedia.org/wiki/Windows_95".to_string(),
+            summary: "Windows 95 is an operating system. It was a hit.".to_string(),
+            release_date: Some("1995-08-24".to_string()),
+            logo_url: Some(logo_url),
+        };
+        let info = OsInfo { name: "Windows 95".to_string(), release_date: "1995-08-24".to_string(), ..OsInfo::default() };
+        let changes = enrichment.changes(&info, None);
+        assert_eq!(changes.iter().map(|c| c.field).collect::<Vec<_>>(), [Field::Summary, Field::Logo]);
+
+        let metadata_dir = dir.join("metadata");
+        let written = save(&source, &metadata_dir, "windows-95", &info, &enrichment, &changes).unwrap();
+        assert_eq!(written.len(), 2);
+        assert_eq!(logo_path(&metadata_dir, "windows-95"), Some(metadata_dir.join("logos").join("windows-95.svg")));
+        let saved = crate::metadata::MetadataStore::load_from_dir(&metadata_dir).unwrap();
+        let saved = saved.get("windows-95").unwrap();
+        assert_eq!(saved.blurb.short, "Windows 95 is an operating system.");
+        assert_eq!(saved.release_date, "1995-08-24");
+        assert!(enrichment.changes(saved, logo_path(&metadata_dir, "windows-95").as_deref()).is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
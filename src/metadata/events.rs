@@ -0,0 +1,151 @@
+//! "On This Day" Computing History
+//!
+//! Pairs today's date with a bundled dataset of events in computing history
+//! and the release dates in the OS metadata, keeping only what relates to a
+//! VM in the library so the main menu can point at it.
+
+use chrono::{Datelike, NaiveDate};
+use serde::Deserialize;
+
+use super::os_info::strip_numeric_suffix;
+use super::MetadataStore;
+use crate::vm::DiscoveredVm;
+
+/// An event in the bundled dataset
+#[derive(Debug, Clone, Deserialize)]
+pub struct Event {
+    /// Date it happened (YYYY-MM-DD)
+    pub date: NaiveDate,
+    /// What happened, as a sentence without a trailing period
+    pub text: String,
+    /// OS ids it relates to; a trailing `*` matches every id with that prefix
+    #[serde(default)]
+    pub os: Vec<String>,
+}
+
+impl Event {
+    /// Whether the event relates to an OS id
+    pub fn relates_to(&self, id: &str) -> bool {
+        self.os.iter().any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => id.starts_with(prefix),
+            None => id == pattern,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct EventsFile {
+    #[serde(default)]
+    events: Vec<Event>,
+}
+
+/// Store for the events dataset
+#[derive(Debug, Clone, Default)]
+pub struct EventStore {
+    pub events: Vec<Event>,
+}
+
+impl EventStore {
+    /// Load events from embedded assets
+    pub fn load_embedded() -> Self {
+        let content = include_str!("../../assets/metadata/events.toml");
+        let events = toml::from_str::<EventsFile>(content).map(|f| f.events).unwrap_or_default();
+        Self { events }
+    }
+
+    /// Anniversaries falling on `today` that relate to a VM in the library,
+    /// oldest first
+    pub fn on_this_day(&self, today: NaiveDate, metadata: &MetadataStore, vms: &[DiscoveredVm]) -> Vec<OnThisDay> {
+        let same_day = |date: NaiveDate| date.month() == today.month() && date.day() == today.day() && date.year() < today.year();
+        let mut found: Vec<OnThisDay> = Vec::new();
+
+        for event in self.events.iter().filter(|e| same_day(e.date)) {
+            if let Some(vm) = vms.iter().find(|vm| os_ids(vm).any(|id| event.relates_to(id))) {
+                found.push(OnThisDay {
+                    year: event.date.year(),
+                    text: event.text.clone(),
+                    vm_id: vm.id.clone(),
+                });
+            }
+        }
+
+        for vm in vms {
+            let Some(info) = metadata.get(&vm.id) else {
+                continue;
+            };
+            // Dates only known to the year are stored as January 1st
+            let Some(date) = NaiveDate::parse_from_str(&info.release_date, "%Y-%m-%d")
+                .ok()
+                .filter(|d| d.ordinal() != 1 && same_day(*d))
+            else {
+                continue;
+            };
+            let text = format!("{} was released", info.name);
+            // Copies of the same OS share one entry
+            if !found.iter().any(|f| f.text == text) {
+                found.push(OnThisDay { year: date.year(), text, vm_id: vm.id.clone() });
+            }
+        }
+
+        found.sort_by_key(|f| f.year);
+        found
+    }
+}
+
+/// Ids a VM may be known by in the dataset
+fn os_ids(vm: &DiscoveredVm) -> impl Iterator<Item = &str> {
+    [Some(vm.id.as_str()), strip_numeric_suffix(&vm.id), vm.os_profile.as_deref()]
+        .into_iter()
+        .flatten()
+}
+
+/// An anniversary shown on the main menu
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OnThisDay {
+    pub year: i32,
+    pub text: String,
+    /// VM in the library it relates to
+    pub vm_id: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vm(id: &str, os_profile: Option<&str>) -> DiscoveredVm {
+        DiscoveredVm {
+            id: id.to_string(),
+            path: Default::default(),
+            launch_script: Default::default(),
+            config: Default::default(),
+            backend: Default::default(),
+            custom_name: None,
+            os_profile: os_profile.map(str::to_string),
+            remote: None,
+        }
+    }
+
+    #[test]
+    fn test_on_this_day() {
+        let store = EventStore::load_embedded();
+        assert!(!store.events.is_empty());
+        let metadata = MetadataStore::load_embedded();
+        let vms = vec![vm("my-dos-box", Some("ms-dos")), vm("windows-95", None), vm("windows-95-2", None)];
+
+        // IBM PC and MS-DOS share the date; the dataset event comes from the profile
+        let found = store.on_this_day(NaiveDate::from_ymd_opt(2026, 8, 12).unwrap(), &metadata, &vms);
+        assert!(found.iter().any(|f| f.year == 1981 && f.vm_id == "my-dos-box" && f.text.contains("IBM PC")));
+
+        let found = store.on_this_day(NaiveDate::from_ymd_opt(2026, 8, 24).unwrap(), &metadata, &vms);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].vm_id, "windows-95");
+        assert_eq!(found[0].year, 1995);
+
+        // Windows 1.0 relates to every Windows VM; the first one is picked
+        let found = store.on_this_day(NaiveDate::from_ymd_opt(2026, 11, 20).unwrap(), &metadata, &vms);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].vm_id, "windows-95");
+
+        assert!(store.on_this_day(NaiveDate::from_ymd_opt(2026, 3, 14).unwrap(), &metadata, &vms).is_empty());
+    }
+}
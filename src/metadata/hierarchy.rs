@@ -134,7 +134,9 @@ impl HierarchyConfig {
                 }
             })
             .collect();
-        subcategories.sort_by_key(|s| (s.family.clone(), s.order));
+        // In family order, so the catch-all in "other" is tried last
+        let family_order = |id: &str| families.iter().find(|f| f.id == id).map_or(i32::MAX, |f| f.order);
+        subcategories.sort_by_key(|s| (family_order(&s.family), s.family.clone(), s.order));
 
         // Compile patterns
         let mut compiled_patterns = Vec::new();
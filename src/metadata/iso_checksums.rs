@@ -0,0 +1,240 @@
+//! Known install images
+//!
+//! SHA-256 checksums of freely distributable install images and the OS
+//! profile each one installs. The bundled list is extended by the user's
+//! `iso_checksums.toml` and by the checksums of the profiles' download
+//! sources, so an image fetched by the wizard is always recognised.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use super::QemuProfileStore;
+
+const EMBEDDED_CHECKSUMS: &str = include_str!("../../assets/metadata/iso_checksums.toml");
+
+/// An install image, identified by its checksum
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KnownImage {
+    /// What the image is (e.g. "FreeDOS 1.3 LiveCD")
+    pub name: String,
+    /// SHA-256, lowercase hex
+    pub sha256: String,
+    /// OS profile id the image installs
+    pub profile: String,
+    /// Name the image is published under
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    /// Size in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ChecksumFile {
+    #[serde(default, rename = "image")]
+    images: Vec<KnownImage>,
+}
+
+/// Every known image
+#[derive(Debug, Clone, Default)]
+pub struct IsoChecksums {
+    images: Vec<KnownImage>,
+    /// Sizes of the images whose size is known
+    sizes: BTreeSet<u64>,
+    /// Published names (lowercase) of the images whose size isn't known
+    unsized_files: BTreeSet<String>,
+}
+
+impl IsoChecksums {
+    /// The bundled list, the user's additions in `user_file` and the
+    /// profiles' download sources that have a checksum
+    pub fn load(user_file: &Path, profiles: &QemuProfileStore) -> Self {
+        let mut images = Self::parse(EMBEDDED_CHECKSUMS).unwrap_or_else(|e| {
+            eprintln!("Warning: Failed to parse embedded ISO checksums: {}", e);
+            Vec::new()
+        });
+        if let Ok(content) = std::fs::read_to_string(user_file) {
+            match Self::parse(&content) {
+                Ok(user) => images.extend(user),
+                Err(e) => eprintln!("Warning: Failed to parse {}: {}", user_file.display(), e),
+            }
+        }
+        for (id, profile) in profiles.list_all() {
+            for source in &profile.iso_sources {
+                if let Some(ref sha256) = source.sha256 {
+                    images.push(KnownImage {
+                        name: source.name.clone(),
+                        sha256: sha256.trim().to_ascii_lowercase(),
+                        profile: id.clone(),
+                        file: source.file_name(),
+                        size: None,
+                    });
+                }
+            }
+        }
+        Self::new(images)
+    }
+
+    fn new(images: Vec<KnownImage>) -> Self {
+        let sizes = images.iter().filter_map(|image| image.size).collect();
+        let unsized_files = images
+            .iter()
+            .filter(|image| image.size.is_none())
+            .filter_map(|image| image.file.as_deref())
+            .map(str::to_lowercase)
+            .collect();
+        Self { images, sizes, unsized_files }
+    }
+
+    fn parse(content: &str) -> Result<Vec<KnownImage>> {
+        let file: ChecksumFile = toml::from_str(content)?;
+        Ok(file
+            .images
+            .into_iter()
+            .map(|image| KnownImage { sha256: image.sha256.trim().to_ascii_lowercase(), ..image })
+            .collect())
+    }
+
+    /// `[[image]]` entries for `images`, in the checksum file format
+    pub fn to_toml(images: &[KnownImage]) -> Result<String> {
+        Ok(toml::to_string(&ChecksumFile { images: images.to_vec() })?)
+    }
+
+    /// Append the images not already listed in `user_file` to it, creating
+    /// it if needed; returns how many were added
+    pub fn add_user_images(user_file: &Path, images: &[KnownImage]) -> Result<usize> {
+        let mut content = match std::fs::read_to_string(user_file) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", user_file.display())),
+        };
+        let mut listed = Self::parse(&content).with_context(|| format!("Failed to parse {}", user_file.display()))?;
+        let mut added = Vec::new();
+        for image in images {
+            let image = KnownImage { sha256: image.sha256.trim().to_ascii_lowercase(), ..image.clone() };
+            if !listed.iter().any(|known| known.sha256 == image.sha256) {
+                listed.push(image.clone());
+                added.push(image);
+            }
+        }
+        if added.is_empty() {
+            return Ok(0);
+        }
+
+        if !content.is_empty() {
+            content.push_str(if content.ends_with('\n') { "\n" } else { "\n\n" });
+        }
+        content.push_str(&Self::to_toml(&added)?);
+        if let Some(parent) = user_file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(user_file, content).with_context(|| format!("Failed to write {}", user_file.display()))?;
+        Ok(added.len())
+    }
+
+    /// The image with this checksum
+    pub fn identify(&self, sha256: &str) -> Option<&KnownImage> {
+        self.images.iter().find(|image| image.sha256.eq_ignore_ascii_case(sha256))
+    }
+
+    /// Whether the file `file_name` of `size` bytes could be a known image,
+    /// and so is worth checksumming: some image has that size, or has no
+    /// size given and is published under that name
+    pub fn could_match(&self, file_name: &str, size: u64) -> bool {
+        self.sizes.contains(&size) || self.unsized_files.contains(&file_name.to_lowercase())
+    }
+
+    pub fn len(&self) -> usize {
+        self.images.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_identify() {
+        let embedded = IsoChecksums::parse(EMBEDDED_CHECKSUMS).unwrap();
+        assert!(embedded.iter().all(|image| image.sha256.len() == 64));
+
+        let images = IsoChecksums::parse(
+            r#"
+            [[image]]
+            name = "Example Linux 1.0"
+            sha256 = " 0123456789ABCDEF0123456789abcdef0123456789abcdef0123456789ABCDEF "
+            profile = "linux-example"
+            size = 1000
+            "#,
+        )
+        .unwrap();
+        let db = IsoChecksums::new(images);
+        let sha256 = "0123456789abcdef".repeat(4);
+        assert_eq!(db.identify(&sha256).map(|i| i.profile.as_str()), Some("linux-example"));
+        assert_eq!(db.identify(&sha256.to_uppercase()).map(|i| i.name.as_str()), Some("Example Linux 1.0"));
+        assert_eq!(db.identify(&"0".repeat(64)), None);
+        assert!(db.could_match("example.iso", 1000));
+        assert!(!db.could_match("example.iso", 999));
+        assert!(!IsoChecksums::default().could_match("example.iso", 1000));
+
+        // An image without a size doesn't make every file worth reading,
+        // only ones under its published name
+        let mut images = db.images.clone();
+        images.push(KnownImage {
+            name: "Example BSD 2.0".to_string(),
+            sha256: "1".repeat(64),
+            profile: "bsd-example".to_string(),
+            file: Some("install20.iso".to_string()),
+            size: None,
+        });
+        images.push(KnownImage { file: None, ..images[1].clone() });
+        let db = IsoChecksums::new(images);
+        assert!(db.could_match("INSTALL20.ISO", 123));
+        assert!(!db.could_match("other.iso", 123));
+        assert!(db.could_match("other.iso", 1000));
+    }
+
+    #[test]
+    fn test_embedded_images_are_complete() {
+        let profiles = QemuProfileStore::load_embedded();
+        for image in IsoChecksums::parse(EMBEDDED_CHECKSUMS).unwrap() {
+            assert!(!image.name.is_empty(), "{:?} has no name", image);
+            assert!(image.sha256.len() == 64 && image.sha256.chars().all(|c| c.is_ascii_hexdigit()), "{}: bad sha256", image.name);
+            assert!(image.size.is_some_and(|size| size > 0), "{}: no size", image.name);
+            assert!(image.file.is_some(), "{}: no file", image.name);
+            assert!(profiles.get(&image.profile).is_some(), "{}: unknown profile {}", image.name, image.profile);
+        }
+        for (id, profile) in profiles.list_all() {
+            for sha256 in profile.iso_sources.iter().filter_map(|source| source.sha256.as_deref()) {
+                assert!(sha256.trim().len() == 64 && sha256.trim().chars().all(|c| c.is_ascii_hexdigit()), "{}: bad sha256", id);
+            }
+        }
+    }
+
+    #[test]
+    fn test_add_user_images() {
+        let root = std::env::temp_dir().join(format!("vm-curator-iso-checksums-{}", std::process::id()));
+        let user_file = root.join("config").join("iso_checksums.toml");
+        let image = KnownImage {
+            name: "OS/2 Warp 4 (warp4-cd1.iso)".to_string(),
+            sha256: "AB".repeat(32),
+            profile: "os2-warp4".to_string(),
+            file: Some("warp4-cd1.iso".to_string()),
+            size: Some(1000),
+        };
+        assert_eq!(IsoChecksums::add_user_images(&user_file, std::slice::from_ref(&image)).unwrap(), 1);
+
+        // Already listed images are skipped, whatever the case of their checksum
+        let other = KnownImage { sha256: "cd".repeat(32), size: None, ..image.clone() };
+        assert_eq!(IsoChecksums::add_user_images(&user_file, &[image.clone(), other.clone()]).unwrap(), 1);
+        assert_eq!(IsoChecksums::add_user_images(&user_file, &[other]).unwrap(), 0);
+
+        let listed = IsoChecksums::parse(&std::fs::read_to_string(&user_file).unwrap()).unwrap();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0], KnownImage { sha256: "ab".repeat(32), ..image });
+        assert_eq!(listed[1].size, None);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -1,11 +1,18 @@
 pub mod ascii_art;
+pub mod bundle;
+pub mod enrich;
+pub mod events;
 pub mod hierarchy;
+pub mod iso_checksums;
 pub mod os_info;
 pub mod qemu_profiles;
+pub mod quiz;
 pub mod settings_help;
 pub mod shared_folders_help;
+pub mod vmprofile;
 
 pub use ascii_art::AsciiArtStore;
+pub use events::{EventStore, OnThisDay};
 pub use hierarchy::{HierarchyConfig, SortBy};
 pub use os_info::{default_os_info, MetadataStore, OsInfo};
 pub use qemu_profiles::{QemuProfile, QemuProfileStore};
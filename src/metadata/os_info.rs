@@ -100,6 +100,14 @@ impl MetadataStore {
             store.entries = entries;
         }
 
+        // The history pack adds further OSes without replacing the defaults
+        let pack = include_str!("../../assets/metadata/history_pack.toml");
+        if let Ok(entries) = toml::from_str::<HashMap<String, OsInfo>>(pack) {
+            for (id, info) in entries {
+                store.entries.entry(id).or_insert(info);
+            }
+        }
+
         store
     }
 
@@ -119,6 +127,15 @@ impl MetadataStore {
         None
     }
 
+    /// ID a VM's info is stored under: the ID `get` finds an entry for,
+    /// or the VM ID itself for a new entry
+    pub fn key_for(&self, id: &str) -> String {
+        match strip_numeric_suffix(id) {
+            Some(base_id) if !self.entries.contains_key(id) && self.entries.contains_key(base_id) => base_id.to_string(),
+            _ => id.to_string(),
+        }
+    }
+
     /// Merge user overrides with embedded defaults
     pub fn merge(&mut self, overrides: MetadataStore) {
         for (id, info) in overrides.entries {
@@ -197,3 +214,22 @@ fn guess_os_details(vm_id: &str) -> (String, String) {
         ("Unknown".to_string(), "Unknown".to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_pack() {
+        let store = MetadataStore::load_embedded();
+        let pack: HashMap<String, toml::Value> =
+            toml::from_str(include_str!("../../assets/metadata/history_pack.toml")).unwrap();
+        for id in pack.keys() {
+            let info = store.get(id).unwrap_or_else(|| panic!("{} did not load", id));
+            assert!(chrono::NaiveDate::parse_from_str(&info.release_date, "%Y-%m-%d").is_ok(), "{}", id);
+        }
+        assert_eq!(store.get("windows-nt-351-2").unwrap().name, "Windows NT 3.51");
+        // Defaults are kept
+        assert_eq!(store.get("windows-nt").unwrap().name, "Windows NT 4.0");
+    }
+}
@@ -4,12 +4,19 @@
 //! when creating new VMs through the creation wizard.
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
 
 /// Embedded QEMU profiles from assets/metadata/qemu_profiles.toml
 const EMBEDDED_PROFILES: &str = include_str!("../../assets/metadata/qemu_profiles.toml");
 
+/// Profile categories, in the order the creation wizard lists them (profiles
+/// in any other category are not offered)
+pub const WIZARD_CATEGORIES: [&str; 11] = [
+    "windows", "linux", "bsd", "unix", "macos", "mobile", "infrastructure", "utilities", "alternative", "retro", "classic-mac",
+];
+
 /// A QEMU configuration profile for a specific operating system
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QemuProfile {
@@ -22,12 +29,25 @@ pub struct QemuProfile {
     /// QEMU emulator command (e.g., qemu-system-x86_64)
     pub emulator: String,
 
+    /// Non-QEMU emulator ("86box" or "dosbox-x"); machine, CPU and device
+    /// fields then use that emulator's names
+    #[serde(default)]
+    pub backend: Option<String>,
+
     /// Default RAM in megabytes
     pub memory_mb: u32,
 
     /// Default CPU cores
     pub cpu_cores: u32,
 
+    /// Most RAM the OS copes with (Windows 95 fails to boot above 480 MB)
+    #[serde(default)]
+    pub max_memory_mb: Option<u32>,
+
+    /// Most CPUs the OS can use (1 for OSes without SMP support)
+    #[serde(default)]
+    pub max_cpu_cores: Option<u32>,
+
     /// CPU model (host, qemu64, pentium, etc.)
     #[serde(default)]
     pub cpu_model: Option<String>,
@@ -39,6 +59,10 @@ pub struct QemuProfile {
     /// Graphics adapter (qxl, virtio, std, cirrus, vmware, none)
     pub vga: String,
 
+    /// Preferred guest resolution (e.g., "800x600") for era-correct display
+    #[serde(default)]
+    pub resolution: Option<String>,
+
     /// Audio devices (e.g., ["intel-hda", "hda-duplex"])
     #[serde(default)]
     pub audio: Vec<String>,
@@ -60,6 +84,35 @@ pub struct QemuProfile {
     #[serde(default)]
     pub uefi: bool,
 
+    /// Use Secure Boot firmware (defaults to on for UEFI + TPM profiles)
+    #[serde(default)]
+    pub secure_boot: Option<bool>,
+
+    /// Non-UEFI firmware for ARM and RISC-V `virt` boards ("u-boot");
+    /// `uefi` selects EDK2 instead
+    #[serde(default)]
+    pub firmware: Option<String>,
+
+    /// Kernel image for direct kernel boot, relative to the VM folder
+    #[serde(default)]
+    pub kernel: Option<String>,
+
+    /// Initial ramdisk for direct kernel boot, relative to the VM folder
+    #[serde(default)]
+    pub initrd: Option<String>,
+
+    /// Device tree blob for the board, relative to the VM folder
+    #[serde(default)]
+    pub dtb: Option<String>,
+
+    /// Kernel command line for direct kernel boot
+    #[serde(default)]
+    pub kernel_append: Option<String>,
+
+    /// OpenBIOS variables for PowerPC Macs (e.g. "auto-boot?=true")
+    #[serde(default)]
+    pub prom_env: Vec<String>,
+
     /// Enable TPM emulation
     #[serde(default)]
     pub tpm: bool,
@@ -72,6 +125,10 @@ pub struct QemuProfile {
     #[serde(default)]
     pub usb_tablet: bool,
 
+    /// USB controller (none, uhci, ehci, xhci); derived from the machine if unset
+    #[serde(default)]
+    pub usb_controller: Option<String>,
+
     /// Display output (gtk, sdl, spice, vnc)
     #[serde(default = "default_display")]
     pub display: String,
@@ -84,9 +141,17 @@ pub struct QemuProfile {
     #[serde(default)]
     pub iso_url: Option<String>,
 
+    /// Install images the wizard can download, with their mirrors
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub iso_sources: Vec<IsoSource>,
+
     /// Tips/notes for this OS
     #[serde(default)]
     pub notes: Option<String>,
+
+    /// Steps to take once the OS is installed, offered as a checklist
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_install: Vec<String>,
 }
 
 fn default_display() -> String {
@@ -99,33 +164,85 @@ impl Default for QemuProfile {
             display_name: "Unknown OS".to_string(),
             category: "alternative".to_string(),
             emulator: "qemu-system-x86_64".to_string(),
+            backend: None,
             memory_mb: 2048,
             cpu_cores: 2,
+            max_memory_mb: None,
+            max_cpu_cores: None,
             cpu_model: Some("host".to_string()),
             machine: Some("q35".to_string()),
             vga: "std".to_string(),
+            resolution: None,
             audio: vec!["intel-hda".to_string(), "hda-duplex".to_string()],
             network_model: "e1000".to_string(),
             disk_interface: "ide".to_string(),
             disk_size_gb: 32,
             enable_kvm: true,
             uefi: false,
+            secure_boot: None,
+            firmware: None,
+            kernel: None,
+            initrd: None,
+            dtb: None,
+            kernel_append: None,
+            prom_env: vec![],
             tpm: false,
             rtc_localtime: false,
             usb_tablet: true,
+            usb_controller: None,
             display: "gtk".to_string(),
             extra_args: vec![],
             iso_url: None,
+            iso_sources: vec![],
             notes: None,
+            post_install: vec![],
         }
     }
 }
 
+/// A downloadable install image and the places it can be fetched from
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IsoSource {
+    /// What the image is (e.g. "NetBSD 10.0 install CD (amd64)")
+    pub name: String,
+    /// Direct URL of the image file
+    #[serde(default)]
+    pub url: Option<String>,
+    /// URLs of the same file, tried in order when the ones before fail
+    #[serde(default)]
+    pub mirrors: Vec<String>,
+    /// Internet Archive copy as "identifier/file name", tried last
+    #[serde(default)]
+    pub archive_org: Option<String>,
+    /// SHA-256 of the file, checked after downloading
+    #[serde(default)]
+    pub sha256: Option<String>,
+}
+
+impl IsoSource {
+    /// Every URL of the image, in the order to try them
+    pub fn urls(&self) -> Vec<String> {
+        self.url
+            .iter()
+            .chain(&self.mirrors)
+            .cloned()
+            .chain(self.archive_org.iter().map(|item| format!("https://archive.org/download/{}", item.trim_matches('/'))))
+            .collect()
+    }
+
+    /// File name of the image, from its first URL
+    pub fn file_name(&self) -> Option<String> {
+        let url = self.urls().into_iter().next()?;
+        let name = url.split(['?', '#']).next()?.rsplit('/').next()?.to_string();
+        (!name.is_empty()).then_some(name)
+    }
+}
+
 impl QemuProfile {
     /// Check if this profile supports free ISO download
     #[allow(dead_code)]
     pub fn has_free_iso(&self) -> bool {
-        self.iso_url.is_some()
+        self.iso_url.is_some() || !self.iso_sources.is_empty()
     }
 
     /// Check if this profile uses x86 architecture
@@ -140,6 +257,23 @@ impl QemuProfile {
         self.emulator.contains("x86_64")
     }
 
+    /// Whether new VMs from this profile boot with Secure Boot
+    pub fn wants_secure_boot(&self) -> bool {
+        self.uefi && self.secure_boot.unwrap_or(self.tpm)
+    }
+
+    /// USB controller for new VMs from this profile
+    ///
+    /// Profiles without a USB tablet get no controller; otherwise the
+    /// machine type decides (UHCI for i440fx-era guests, XHCI for q35).
+    pub fn usb_controller(&self) -> String {
+        match &self.usb_controller {
+            Some(controller) => controller.clone(),
+            None if !self.usb_tablet => "none".to_string(),
+            None => default_usb_controller(self.machine.as_deref()).to_string(),
+        }
+    }
+
     /// Get a short summary for display in the wizard
     pub fn summary(&self) -> String {
         let mut parts = vec![];
@@ -157,6 +291,11 @@ impl QemuProfile {
         // Notable features
         if self.uefi {
             parts.push("UEFI".to_string());
+        } else if let Some(ref firmware) = self.firmware {
+            parts.push(firmware.to_uppercase());
+        }
+        if self.kernel.is_some() {
+            parts.push("kernel boot".to_string());
         }
         if self.disk_interface == "virtio" {
             parts.push("virtio".to_string());
@@ -166,6 +305,16 @@ impl QemuProfile {
     }
 }
 
+/// USB controller a machine type's guests can be expected to drive
+pub fn default_usb_controller(machine: Option<&str>) -> &'static str {
+    match machine {
+        Some("isapc") => "none",
+        None | Some("pc") => "uhci",
+        Some(m) if m.starts_with("pc-i440fx") => "uhci",
+        Some(_) => "xhci",
+    }
+}
+
 /// Store for QEMU profiles with support for user overrides
 #[derive(Debug, Default)]
 pub struct QemuProfileStore {
@@ -235,7 +384,6 @@ impl QemuProfileStore {
     }
 
     /// List all profiles
-    #[allow(dead_code)]
     pub fn list_all(&self) -> Vec<(&String, &QemuProfile)> {
         let mut profiles: Vec<_> = self.profiles.iter().collect();
         profiles.sort_by(|a, b| a.1.display_name.cmp(&b.1.display_name));
@@ -290,7 +438,7 @@ impl QemuProfileStore {
         let mut profiles: Vec<_> = self
             .profiles
             .iter()
-            .filter(|(_, p)| p.iso_url.is_some())
+            .filter(|(_, p)| p.has_free_iso())
             .collect();
         profiles.sort_by(|a, b| a.1.display_name.cmp(&b.1.display_name));
         profiles
@@ -344,6 +492,23 @@ impl QemuProfileStore {
     }
 }
 
+/// The profiles in a user profiles file (none if it doesn't exist)
+pub fn load_user_profiles(path: &Path) -> Result<BTreeMap<String, QemuProfile>> {
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Replace the profiles in a user profiles file
+pub fn save_user_profiles(path: &Path, profiles: &BTreeMap<String, QemuProfile>) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    std::fs::write(path, toml::to_string_pretty(profiles)?).with_context(|| format!("Failed to write {}", path.display()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -359,6 +524,43 @@ mod tests {
         assert!(store.get("freebsd").is_some(), "Should have FreeBSD");
     }
 
+    #[test]
+    fn test_user_profiles_file() {
+        let path = std::env::temp_dir().join(format!("vm-curator-profiles-{}", std::process::id())).join("qemu_profiles.toml");
+        assert!(load_user_profiles(&path).unwrap().is_empty());
+
+        let mut profiles = BTreeMap::new();
+        let mut profile = QemuProfileStore::load_embedded().get("os2-warp4").cloned().unwrap();
+        profile.display_name = "OS/2 Warp 4 (tuned)".to_string();
+        profile.extra_args = vec!["-no-hpet".to_string()];
+        profiles.insert("os2-warp4".to_string(), profile);
+        save_user_profiles(&path, &profiles).unwrap();
+
+        let mut store = QemuProfileStore::load_embedded();
+        store.load_user_overrides(&path);
+        assert_eq!(store.get("os2-warp4").unwrap().display_name, "OS/2 Warp 4 (tuned)");
+        assert_eq!(load_user_profiles(&path).unwrap()["os2-warp4"].extra_args, ["-no-hpet"]);
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_iso_sources() {
+        let source = IsoSource {
+            name: "Test CD".to_string(),
+            url: Some("https://cdn.example.org/os/10.0/install.iso?download=1".to_string()),
+            mirrors: vec!["https://ftp.example.org/os/10.0/install.iso".to_string()],
+            archive_org: Some("example-os-10/install.iso".to_string()),
+            sha256: None,
+        };
+        assert_eq!(source.urls().len(), 3);
+        assert_eq!(source.urls()[2], "https://archive.org/download/example-os-10/install.iso");
+        assert_eq!(source.file_name().as_deref(), Some("install.iso"));
+
+        let store = QemuProfileStore::load_embedded();
+        let netbsd = store.get("netbsd").unwrap();
+        assert!(netbsd.iso_sources.iter().all(|s| s.urls().len() > 1 && s.file_name().is_some()));
+    }
+
     #[test]
     fn test_profile_summary() {
         let profile = QemuProfile {
@@ -377,6 +579,17 @@ mod tests {
         assert!(summary.contains("virtio"));
     }
 
+    #[test]
+    fn test_usb_controller_by_era() {
+        let store = QemuProfileStore::load_embedded();
+        let controller = |id: &str| store.get(id).unwrap().usb_controller();
+
+        assert_eq!(controller("windows-11"), "xhci");
+        assert_eq!(controller("windows-7"), "ehci");
+        assert_eq!(controller("windows-xp"), "uhci");
+        assert_eq!(controller("ms-dos"), "none");
+    }
+
     #[test]
     fn test_categories() {
         let store = QemuProfileStore::load_embedded();
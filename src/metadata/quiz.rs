@@ -0,0 +1,187 @@
+//! Trivia Quiz
+//!
+//! Builds multiple-choice questions from the fun facts and release dates of
+//! the OSes in the library. Questions are about VMs in the library; wrong
+//! answers may come from any OS in the metadata so small libraries still
+//! get four choices.
+
+use chrono::{Datelike, NaiveDate};
+
+use super::os_info::OsInfo;
+use super::MetadataStore;
+use crate::vm::DiscoveredVm;
+
+/// Choices offered per question (fewer when the metadata runs short)
+const CHOICES: usize = 4;
+
+/// A multiple-choice question
+#[derive(Debug, Clone)]
+pub struct Question {
+    pub prompt: String,
+    pub choices: Vec<String>,
+    /// Index of the right choice
+    pub answer: usize,
+    /// VM the question is about
+    pub vm_id: String,
+}
+
+/// Small xorshift generator; the quiz needs variety, not quality
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// An OS the quiz can ask about
+struct Known<'a> {
+    name: &'a str,
+    info: &'a OsInfo,
+    released: Option<NaiveDate>,
+}
+
+fn known(info: &OsInfo) -> Known<'_> {
+    Known {
+        name: &info.name,
+        info,
+        released: NaiveDate::parse_from_str(&info.release_date, "%Y-%m-%d").ok(),
+    }
+}
+
+/// Up to `count` questions about the VMs in the library, in random order
+pub fn generate(metadata: &MetadataStore, vms: &[DiscoveredVm], rng: &mut Rng, count: usize) -> Vec<Question> {
+    // Every OS once, sorted so the same seed gives the same quiz
+    let mut all: Vec<Known> = metadata.entries.values().filter(|i| !i.name.is_empty()).map(known).collect();
+    all.sort_by(|a, b| a.name.cmp(b.name));
+    all.dedup_by(|a, b| a.name == b.name);
+
+    let mut subjects: Vec<(&DiscoveredVm, Known)> = Vec::new();
+    for vm in vms {
+        if let Some(info) = metadata.get(&vm.id) {
+            if !subjects.iter().any(|(_, k)| k.name == info.name) {
+                subjects.push((vm, known(info)));
+            }
+        }
+    }
+
+    let mut questions = Vec::new();
+    for (vm, subject) in &subjects {
+        if let Some(q) = year_question(subject, rng) {
+            questions.push((vm, q));
+        }
+        if let Some(q) = first_released_question(subject, &all, rng) {
+            questions.push((vm, q));
+        }
+        for fact in &subject.info.fun_facts {
+            if let Some(q) = fact_question(subject, fact, &all, rng) {
+                questions.push((vm, q));
+            }
+        }
+    }
+
+    rng.shuffle(&mut questions);
+    questions.truncate(count);
+    questions
+        .into_iter()
+        .map(|(vm, (prompt, right, wrong))| {
+            let mut choices = wrong;
+            choices.push(right.clone());
+            rng.shuffle(&mut choices);
+            let answer = choices.iter().position(|c| *c == right).unwrap_or(0);
+            Question { prompt, choices, answer, vm_id: vm.id.clone() }
+        })
+        .collect()
+}
+
+/// A prompt with its right answer and the wrong ones
+type Draft = (String, String, Vec<String>);
+
+/// "In what year was X released?"
+fn year_question(subject: &Known, rng: &mut Rng) -> Option<Draft> {
+    let year = subject.released?.year();
+    let mut offsets: Vec<i32> = (-6..=6).filter(|o| *o != 0).collect();
+    rng.shuffle(&mut offsets);
+    let wrong = offsets.iter().take(CHOICES - 1).map(|o| (year + o).to_string()).collect();
+    Some((format!("In what year was {} released?", subject.name), year.to_string(), wrong))
+}
+
+/// "Which of these came out first?", with the subject the oldest
+fn first_released_question(subject: &Known, all: &[Known], rng: &mut Rng) -> Option<Draft> {
+    let year = subject.released?.year();
+    // Only OSes from a later year, so the answer is never a tie
+    let mut later: Vec<&str> = all.iter().filter(|k| k.released.is_some_and(|d| d.year() > year)).map(|k| k.name).collect();
+    if later.is_empty() {
+        return None;
+    }
+    rng.shuffle(&mut later);
+    let wrong = later.into_iter().take(CHOICES - 1).map(str::to_string).collect();
+    Some(("Which of these came out first?".to_string(), subject.name.to_string(), wrong))
+}
+
+/// "Which OS is this about?" with the OS's name blanked out of the fact
+fn fact_question(subject: &Known, fact: &str, all: &[Known], rng: &mut Rng) -> Option<Draft> {
+    let mut text = fact.to_string();
+    for name in [subject.info.display_name.as_deref(), Some(subject.name)].into_iter().flatten() {
+        text = text.replace(name, "this OS");
+    }
+    let mut others: Vec<&str> = all.iter().map(|k| k.name).filter(|n| *n != subject.name).collect();
+    if others.is_empty() {
+        return None;
+    }
+    rng.shuffle(&mut others);
+    let wrong = others.into_iter().take(CHOICES - 1).map(str::to_string).collect();
+    Some((format!("Which OS is this about?\n\n{}", text), subject.name.to_string(), wrong))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let metadata = MetadataStore::load_embedded();
+        let vm = DiscoveredVm {
+            id: "windows-95-2".to_string(),
+            path: Default::default(),
+            launch_script: Default::default(),
+            config: Default::default(),
+            backend: Default::default(),
+            custom_name: None,
+            os_profile: None,
+            remote: None,
+        };
+        let info = metadata.get("windows-95").unwrap();
+
+        let questions = generate(&metadata, &[vm], &mut Rng::new(42), 100);
+        assert_eq!(questions.len(), 2 + info.fun_facts.len());
+        for q in &questions {
+            assert_eq!(q.vm_id, "windows-95-2");
+            assert_eq!(q.choices.len(), CHOICES);
+            assert!(q.choices.iter().filter(|c| **c == q.choices[q.answer]).count() == 1);
+        }
+        let year = questions.iter().find(|q| q.prompt.starts_with("In what year")).unwrap();
+        assert_eq!(year.choices[year.answer], "1995");
+        let fact = questions.iter().find(|q| q.prompt.starts_with("Which OS")).unwrap();
+        assert!(!fact.prompt.contains(&info.name));
+
+        assert!(generate(&metadata, &[], &mut Rng::new(42), 10).is_empty());
+    }
+}
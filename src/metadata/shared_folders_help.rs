@@ -51,6 +51,13 @@ impl SharedFoldersHelpStore {
         }
     }
 
+    /// Get help text for a key, if present
+    pub fn get(&self, key: &str) -> Option<(&str, &str)> {
+        self.entries
+            .get(key)
+            .map(|e| (e.title.as_str(), e.description.as_str()))
+    }
+
     /// Get help text or return default values
     pub fn get_or_default(&self, key: &str) -> (&str, &str) {
         self.entries
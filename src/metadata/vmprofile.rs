@@ -0,0 +1,195 @@
+//! Shareable OS profiles (`.vmprofile`)
+//!
+//! A `.vmprofile` file is a TOML document carrying everything needed to
+//! set up one OS the way someone has tuned it: the QEMU profile, its OS
+//! metadata and ASCII art, and SHA-256 references to the install media it
+//! was tested with. The media themselves are never included; the checksums
+//! let the receiver confirm they have the same disc before using it.
+//!
+//! ```toml
+//! format = 1
+//! id = "os2-warp4"
+//!
+//! [profile]
+//! display_name = "OS/2 Warp 4"
+//! # ... QemuProfile fields
+//!
+//! [[iso]]
+//! file = "os2-warp4-cd1.iso"
+//! sha256 = "3f5c..."
+//! ```
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::bundle::{Item, Kind};
+use super::iso_checksums::KnownImage;
+use super::os_info::OsInfo;
+use super::qemu_profiles::QemuProfile;
+
+/// File extension of shared profiles
+pub const EXTENSION: &str = "vmprofile";
+
+/// Format written by this version
+const FORMAT: u32 = 1;
+
+/// A shareable profile for one OS
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VmProfile {
+    pub format: u32,
+    /// OS id the profile, metadata and art are stored under
+    pub id: String,
+    pub profile: QemuProfile,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<OsInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ascii_art: Option<String>,
+    /// Install media the profile was tested with
+    #[serde(default, rename = "iso", skip_serializing_if = "Vec::is_empty")]
+    pub isos: Vec<IsoRef>,
+}
+
+/// An install image, identified by its checksum
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IsoRef {
+    /// File name the image had
+    pub file: String,
+    /// SHA-256, lowercase hex
+    pub sha256: String,
+    /// Size in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+}
+
+impl IsoRef {
+    /// Reference a local image (reads the whole file to checksum it)
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let file = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .with_context(|| format!("Not a file: {}", path.display()))?
+            .to_string();
+        let size = std::fs::metadata(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?
+            .len();
+        Ok(Self { file, sha256: crate::commands::iso::sha256(path)?, size: Some(size) })
+    }
+}
+
+impl VmProfile {
+    pub fn new(id: &str, profile: QemuProfile, metadata: Option<OsInfo>, ascii_art: Option<String>) -> Self {
+        Self { format: FORMAT, id: id.to_string(), profile, metadata, ascii_art, isos: Vec::new() }
+    }
+
+    /// Read a `.vmprofile` file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let shared: Self = toml::from_str(content)?;
+        if shared.format > FORMAT {
+            bail!("Needs a newer vm-curator (profile format {})", shared.format);
+        }
+        if shared.id.is_empty() || !shared.id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            bail!("Invalid OS id {:?}", shared.id);
+        }
+        Ok(shared)
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    /// The referenced images as known images of this profile's OS
+    pub fn known_images(&self) -> Vec<KnownImage> {
+        self.isos
+            .iter()
+            .map(|iso| KnownImage {
+                name: format!("{} ({})", self.profile.display_name, iso.file),
+                sha256: iso.sha256.clone(),
+                profile: self.id.clone(),
+                file: Some(iso.file.clone()),
+                size: iso.size,
+            })
+            .collect()
+    }
+
+    /// The referenced image with this checksum
+    pub fn find_iso(&self, sha256: &str) -> Option<&IsoRef> {
+        self.isos.iter().find(|iso| iso.sha256.eq_ignore_ascii_case(sha256))
+    }
+
+    /// What importing would do: the profile goes into the user's profiles
+    /// file, the metadata and art into the user metadata and art folders
+    pub fn plan(&self, profiles_file: &Path, metadata_dir: &Path, ascii_dir: &Path) -> Result<Vec<Item>> {
+        let current_profile = std::fs::read_to_string(profiles_file)
+            .ok()
+            .and_then(|content| toml::from_str::<toml::Table>(&content).ok())
+            .and_then(|mut profiles| profiles.remove(&self.id))
+            .and_then(|profile| profile.try_into::<QemuProfile>().ok())
+            .map(|profile| toml::to_string(&profile))
+            .transpose()?;
+        let mut items = vec![Item::new(
+            Kind::Profile,
+            &self.id,
+            profiles_file.to_path_buf(),
+            current_profile,
+            toml::to_string(&self.profile)?,
+        )];
+
+        if let Some(ref info) = self.metadata {
+            let path = metadata_dir.join(format!("{}.toml", self.id));
+            let current = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| toml::from_str::<OsInfo>(&content).ok())
+                .map(|info| toml::to_string(&info))
+                .transpose()?;
+            items.push(Item::new(Kind::Metadata, &self.id, path, current, toml::to_string(info)?));
+        }
+        if let Some(ref art) = self.ascii_art {
+            let path = ascii_dir.join(format!("{}.txt", self.id));
+            let current = std::fs::read_to_string(&path).ok();
+            items.push(Item::new(Kind::AsciiArt, &format!("{}.txt", self.id), path, current, art.clone()));
+        }
+        Ok(items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::bundle::Status;
+
+    #[test]
+    fn test_round_trip_and_import() {
+        let root = std::env::temp_dir().join(format!("vm-curator-vmprofile-{}", std::process::id()));
+        let (profiles_file, metadata_dir, ascii_dir) = (root.join("qemu_profiles.toml"), root.join("metadata"), root.join("ascii"));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(&profiles_file, "[other]\ndisplay_name = \"Other\"\ncategory = \"retro\"\nemulator = \"qemu-system-i386\"\nmemory_mb = 16\ncpu_cores = 1\nvga = \"std\"\nnetwork_model = \"none\"\ndisk_interface = \"ide\"\ndisk_size_gb = 1\n").unwrap();
+
+        let profile = QemuProfile { display_name: "OS/2 Warp 4".to_string(), memory_mb: 64, ..Default::default() };
+        let mut shared = VmProfile::new("os2-warp4", profile, None, Some(" OS/2\n Warp\n".to_string()));
+        shared.isos.push(IsoRef { file: "warp4.iso".to_string(), sha256: "AB".repeat(32), size: None });
+        let shared = VmProfile::parse(&shared.to_toml().unwrap()).unwrap();
+        assert_eq!(shared.ascii_art.as_deref(), Some(" OS/2\n Warp\n"));
+        assert!(shared.find_iso(&"ab".repeat(32)).is_some());
+
+        let items = shared.plan(&profiles_file, &metadata_dir, &ascii_dir).unwrap();
+        assert_eq!(items.len(), 2);
+        assert!(items.iter().all(|item| item.status == Status::New));
+        for item in &items {
+            item.apply().unwrap();
+        }
+        // Other profiles are kept, and importing again changes nothing
+        let profiles: toml::Table = toml::from_str(&std::fs::read_to_string(&profiles_file).unwrap()).unwrap();
+        assert!(profiles.contains_key("other") && profiles.contains_key("os2-warp4"));
+        let items = shared.plan(&profiles_file, &metadata_dir, &ascii_dir).unwrap();
+        assert!(items.iter().all(|item| item.status == Status::Unchanged));
+
+        assert!(VmProfile::parse("format = 1\nid = \"../x\"\n[profile]\n").is_err());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
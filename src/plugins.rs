@@ -0,0 +1,269 @@
+//! Plugins
+//!
+//! A plugin is a folder in `~/.config/vm-curator/plugins/` with a
+//! `plugin.toml` manifest. It can ship OS profiles (a file in the format of
+//! `qemu_profiles.toml`), OS metadata and ASCII art (folders in the format
+//! of the user metadata and art folders), and management actions: programs
+//! listed in the manifest that appear in every local VM's management menu.
+//!
+//! An action runs with the VM's folder as its working directory and the VM
+//! described in environment variables (`VM_CURATOR_VM_ID`, `_VM_NAME`,
+//! `_VM_DIR`, `_LAUNCH_SCRIPT`, `_LIBRARY`, and `_PID` when running) and as
+//! JSON on stdin. The last line it prints is shown in the status bar; a
+//! non-zero exit is reported as an error with its last line of stderr.
+//!
+//! Plugin data is merged over the built-in data and under the user's own
+//! files, so a user override still wins.
+
+use anyhow::{bail, Context, Result};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::config::Config;
+use crate::metadata::{AsciiArtStore, MetadataStore, QemuProfileStore};
+use crate::vm::DiscoveredVm;
+
+/// Manifest file in each plugin folder
+const MANIFEST: &str = "plugin.toml";
+
+/// Plugins found at startup, with the problems met loading them
+static LOADED: Lazy<(Vec<Plugin>, Vec<String>)> = Lazy::new(|| load_all(&plugins_dir()));
+
+/// plugin.toml
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    name: String,
+    /// OS profiles file, relative to the plugin folder
+    profiles: Option<String>,
+    /// OS metadata folder
+    metadata: Option<String>,
+    /// ASCII art folder
+    ascii_art: Option<String>,
+    #[serde(default)]
+    actions: Vec<ActionManifest>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ActionManifest {
+    name: String,
+    #[serde(default)]
+    description: String,
+    /// Program to run, relative to the plugin folder or absolute
+    command: String,
+    #[serde(default)]
+    when: ActionWhen,
+}
+
+/// VM state an action needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActionWhen {
+    #[default]
+    Always,
+    Running,
+    Stopped,
+}
+
+/// A loaded plugin
+#[derive(Debug, Clone)]
+pub struct Plugin {
+    pub name: String,
+    profiles: Option<PathBuf>,
+    metadata: Option<PathBuf>,
+    ascii_art: Option<PathBuf>,
+    pub actions: Vec<PluginAction>,
+}
+
+/// A management menu entry provided by a plugin
+#[derive(Debug, Clone)]
+pub struct PluginAction {
+    pub name: String,
+    pub description: String,
+    pub command: PathBuf,
+    pub when: ActionWhen,
+}
+
+/// Folder plugins are installed in
+pub fn plugins_dir() -> PathBuf {
+    Config::config_file_path()
+        .parent()
+        .map(|p| p.join("plugins"))
+        .unwrap_or_else(|| PathBuf::from("plugins"))
+}
+
+/// Plugins installed when vm-curator started
+pub fn all() -> &'static [Plugin] {
+    &LOADED.0
+}
+
+/// Plugins that could not be loaded, as messages
+pub fn load_errors() -> &'static [String] {
+    &LOADED.1
+}
+
+/// Every plugin action, in menu order (the index identifies an action)
+pub fn actions() -> impl Iterator<Item = &'static PluginAction> {
+    all().iter().flat_map(|p| p.actions.iter())
+}
+
+/// Read every plugin folder in a directory, sorted by folder name
+fn load_all(dir: &Path) -> (Vec<Plugin>, Vec<String>) {
+    let mut folders: Vec<PathBuf> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries.flatten().map(|e| e.path()).filter(|p| p.join(MANIFEST).is_file()).collect(),
+        Err(_) => return (Vec::new(), Vec::new()),
+    };
+    folders.sort();
+
+    let mut plugins = Vec::new();
+    let mut errors = Vec::new();
+    for folder in folders {
+        match load_plugin(&folder) {
+            Ok(plugin) => plugins.push(plugin),
+            Err(e) => errors.push(format!("Plugin {}: {:#}", folder.display(), e)),
+        }
+    }
+    (plugins, errors)
+}
+
+fn load_plugin(dir: &Path) -> Result<Plugin> {
+    let content = std::fs::read_to_string(dir.join(MANIFEST)).context("Cannot read plugin.toml")?;
+    let manifest: Manifest = toml::from_str(&content).context("Invalid plugin.toml")?;
+    let resolve = |path: &str| dir.join(path);
+
+    let mut actions = Vec::new();
+    for action in manifest.actions {
+        let command = resolve(&action.command);
+        if !command.is_file() {
+            bail!("Action \"{}\": {} not found", action.name, command.display());
+        }
+        actions.push(PluginAction {
+            name: action.name,
+            description: action.description,
+            command,
+            when: action.when,
+        });
+    }
+
+    Ok(Plugin {
+        name: manifest.name,
+        profiles: manifest.profiles.as_deref().map(resolve),
+        metadata: manifest.metadata.as_deref().map(resolve),
+        ascii_art: manifest.ascii_art.as_deref().map(resolve),
+        actions,
+    })
+}
+
+/// Add the plugins' OS metadata
+pub fn merge_metadata(metadata: &mut MetadataStore) {
+    for dir in all().iter().filter_map(|p| p.metadata.as_ref()) {
+        if let Ok(store) = MetadataStore::load_from_dir(dir) {
+            metadata.merge(store);
+        }
+    }
+}
+
+/// Add the plugins' ASCII art
+pub fn merge_ascii_art(ascii_art: &mut AsciiArtStore) {
+    for dir in all().iter().filter_map(|p| p.ascii_art.as_ref()) {
+        ascii_art.merge(AsciiArtStore::load_from_dir(dir));
+    }
+}
+
+/// Add the plugins' OS profiles
+pub fn merge_profiles(profiles: &mut QemuProfileStore) {
+    for path in all().iter().filter_map(|p| p.profiles.as_ref()) {
+        profiles.load_user_overrides(path);
+    }
+}
+
+/// Run a plugin action for a VM and return its last line of output
+pub fn run_action(action: &PluginAction, vm: &DiscoveredVm, pid: Option<u32>, library: &Path) -> Result<String> {
+    match (action.when, pid) {
+        (ActionWhen::Running, None) => bail!("{} needs the VM to be running", action.name),
+        (ActionWhen::Stopped, Some(_)) => bail!("{} needs the VM to be shut down", action.name),
+        _ => {}
+    }
+
+    let mut cmd = Command::new(&action.command);
+    cmd.current_dir(&vm.path)
+        .env("VM_CURATOR_VM_ID", &vm.id)
+        .env("VM_CURATOR_VM_NAME", vm.display_name())
+        .env("VM_CURATOR_VM_DIR", &vm.path)
+        .env("VM_CURATOR_LAUNCH_SCRIPT", &vm.launch_script)
+        .env("VM_CURATOR_LIBRARY", library)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(pid) = pid {
+        cmd.env("VM_CURATOR_PID", pid.to_string());
+    }
+    let mut child = cmd
+        .spawn()
+        .with_context(|| format!("Failed to run {}", action.command.display()))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // An action that ignores stdin may exit before reading it
+        let _ = stdin.write_all(serde_json::to_string(vm)?.as_bytes());
+    }
+    let output = child.wait_with_output().context("Failed to run the plugin action")?;
+
+    let last_line = |bytes: &[u8]| {
+        String::from_utf8_lossy(bytes)
+            .lines()
+            .rev()
+            .find(|l| !l.trim().is_empty())
+            .map(|l| l.trim().to_string())
+    };
+    if !output.status.success() {
+        bail!("{}: {}", action.name, last_line(&output.stderr).unwrap_or_else(|| output.status.to_string()));
+    }
+    Ok(last_line(&output.stdout).unwrap_or_else(|| format!("{} finished", action.name)))
+}
+
+// The action is a shell script
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plugin_action() {
+        let dir = std::env::temp_dir().join(format!("vm-curator-plugins-{}", std::process::id()));
+        let plugin_dir = dir.join("backup");
+        std::fs::create_dir_all(&plugin_dir).unwrap();
+        std::fs::write(
+            plugin_dir.join(MANIFEST),
+            "name = \"Backup\"\n\n[[actions]]\nname = \"Back Up\"\ncommand = \"backup.sh\"\nwhen = \"stopped\"\n",
+        )
+        .unwrap();
+        let script = plugin_dir.join("backup.sh");
+        std::fs::write(&script, "#!/bin/sh\necho working\necho \"Backed up $VM_CURATOR_VM_ID from $(pwd)\"\n").unwrap();
+        crate::fs::set_mode(&script, 0o755).unwrap();
+        std::fs::create_dir_all(dir.join("broken")).unwrap();
+        std::fs::write(dir.join("broken").join(MANIFEST), "name = \"Broken\"\n[[actions]]\nname = \"X\"\ncommand = \"missing\"\n").unwrap();
+
+        let (plugins, errors) = load_all(&dir);
+        assert_eq!(plugins.len(), 1);
+        assert_eq!(errors.len(), 1);
+        let action = &plugins[0].actions[0];
+        assert_eq!(action.when, ActionWhen::Stopped);
+
+        let vm = DiscoveredVm {
+            id: "dos".to_string(),
+            path: dir.clone(),
+            launch_script: dir.join("launch.sh"),
+            config: Default::default(),
+            backend: Default::default(),
+            custom_name: None,
+            os_profile: None,
+            remote: None,
+        };
+        let message = run_action(action, &vm, None, &dir).unwrap();
+        assert_eq!(message, format!("Backed up dos from {}", dir.display()));
+        assert!(run_action(action, &vm, Some(42), &dir).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,328 @@
+//! Crash recovery
+//!
+//! However the TUI ends (a panic, `kill`, the terminal window closing) the
+//! terminal is put back the way it was: raw mode off, the normal screen
+//! back, the cursor visible.
+//!
+//! Input in the Create VM wizard isn't lost either: when the wizard is
+//! cancelled, the app exits with it open or the app crashes, what had been
+//! entered is saved as a draft (`wizard-draft.toml` in the config
+//! directory), and the next start offers to resume it.
+
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::app::CreateWizardState;
+use crate::config::Config;
+
+/// Whether the terminal is in TUI mode
+static TUI_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Set when SIGTERM, SIGHUP, SIGINT or SIGQUIT arrives
+static TERMINATE: Lazy<Arc<AtomicBool>> = Lazy::new(|| Arc::new(AtomicBool::new(false)));
+
+/// The wizard input as of the last pass of the event loop, for the panic hook
+static TRACKED_DRAFT: Mutex<Option<WizardDraft>> = Mutex::new(None);
+
+/// Mark the terminal as being in TUI mode (raw mode, alternate screen)
+pub fn tui_started() {
+    TUI_ACTIVE.store(true, Ordering::SeqCst);
+}
+
+/// Leave TUI mode; safe to call more than once
+pub fn restore_terminal() {
+    TUI_ACTIVE.store(false, Ordering::SeqCst);
+    let _ = crossterm::terminal::disable_raw_mode();
+    let _ = crossterm::execute!(
+        io::stdout(),
+        crossterm::terminal::LeaveAlternateScreen,
+        crossterm::event::DisableMouseCapture,
+        crossterm::cursor::Show
+    );
+}
+
+/// Restore the terminal before a panic message is printed
+///
+/// A panic on the UI thread ends the program, so the terminal is restored
+/// and the wizard input saved before the usual message. Background threads
+/// only take themselves down; their panics go to the application log
+/// rather than across the screen.
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let main_thread = std::thread::current().name() == Some("main");
+        tracing::error!(thread = std::thread::current().name().unwrap_or("unnamed"), "{}", info);
+        if !TUI_ACTIVE.load(Ordering::SeqCst) {
+            default_hook(info);
+            return;
+        }
+        if !main_thread {
+            return;
+        }
+        restore_terminal();
+        let saved = save_tracked_draft();
+        default_hook(info);
+        if saved {
+            eprintln!("\nYour input in the Create VM wizard was saved; vm-curator offers to resume it at the next start.");
+        }
+    }));
+}
+
+/// Ask the event loop to stop on termination signals
+///
+/// A second signal of the same kind ends the program at once, in case the
+/// loop is stuck.
+pub fn watch_signals() -> Result<()> {
+    use signal_hook::consts::{SIGINT, SIGTERM};
+    #[cfg(unix)]
+    let signals = [SIGTERM, signal_hook::consts::SIGHUP, SIGINT, signal_hook::consts::SIGQUIT];
+    #[cfg(not(unix))]
+    let signals = [SIGTERM, SIGINT];
+    for signal in signals {
+        signal_hook::flag::register_conditional_shutdown(signal, 128 + signal, Arc::clone(&TERMINATE))
+            .context("Failed to watch for signals")?;
+        signal_hook::flag::register(signal, Arc::clone(&TERMINATE))
+            .context("Failed to watch for signals")?;
+    }
+    Ok(())
+}
+
+/// Whether a termination signal has arrived
+pub fn terminate_requested() -> bool {
+    TERMINATE.load(Ordering::SeqCst)
+}
+
+/// What the user has entered in the Create VM wizard
+///
+/// Typed or picked values and the commonly changed QEMU settings are kept;
+/// the folder name and the rest of the profile's settings are worked out
+/// again from the OS.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WizardDraft {
+    pub vm_name: String,
+    pub selected_os: Option<String>,
+    pub iso_path: Option<PathBuf>,
+    pub disk_size_gb: u32,
+    pub disk_tuning: crate::commands::qemu_img::Qcow2Options,
+    pub use_existing_disk: bool,
+    pub existing_disk_path: Option<PathBuf>,
+    pub remote_disk_url: Option<String>,
+    pub memory_mb: u32,
+    pub cpu_cores: u32,
+    pub vga: String,
+    pub audio: Vec<String>,
+    pub network_model: String,
+    pub network_backend: String,
+    pub disk_interface: String,
+    pub display: String,
+    pub enable_kvm: bool,
+    pub gl_acceleration: bool,
+    pub uefi: bool,
+    pub tpm: bool,
+    pub usb_tablet: bool,
+    pub extra_args: Vec<String>,
+    pub auto_launch: bool,
+    /// When the draft was saved (RFC 3339)
+    pub saved_at: String,
+}
+
+impl WizardDraft {
+    pub fn from_state(state: &CreateWizardState) -> Self {
+        Self {
+            vm_name: state.vm_name.clone(),
+            selected_os: state.selected_os.clone(),
+            iso_path: state.iso_path.clone(),
+            disk_size_gb: state.disk_size_gb,
+            disk_tuning: state.disk_tuning,
+            use_existing_disk: state.use_existing_disk,
+            existing_disk_path: state.existing_disk_path.clone(),
+            remote_disk_url: state.remote_disk_url.clone(),
+            memory_mb: state.qemu_config.memory_mb,
+            cpu_cores: state.qemu_config.cpu_cores,
+            vga: state.qemu_config.vga.clone(),
+            audio: state.qemu_config.audio.clone(),
+            network_model: state.qemu_config.network_model.clone(),
+            network_backend: state.qemu_config.network_backend.clone(),
+            disk_interface: state.qemu_config.disk_interface.clone(),
+            display: state.qemu_config.display.clone(),
+            enable_kvm: state.qemu_config.enable_kvm,
+            gl_acceleration: state.qemu_config.gl_acceleration,
+            uefi: state.qemu_config.uefi,
+            tpm: state.qemu_config.tpm,
+            usb_tablet: state.qemu_config.usb_tablet,
+            extra_args: state.qemu_config.extra_args.clone(),
+            auto_launch: state.auto_launch,
+            saved_at: String::new(),
+        }
+    }
+
+    /// Put the draft's values back into a wizard whose OS is already chosen
+    pub fn apply_to(&self, state: &mut CreateWizardState) {
+        state.vm_name = self.vm_name.clone();
+        state.iso_path = self.iso_path.clone();
+        state.disk_size_gb = self.disk_size_gb;
+        state.disk_tuning = self.disk_tuning;
+        state.use_existing_disk = self.use_existing_disk;
+        state.existing_disk_path = self.existing_disk_path.clone();
+        state.remote_disk_url = self.remote_disk_url.clone();
+        state.auto_launch = self.auto_launch;
+
+        let qemu = &mut state.qemu_config;
+        qemu.memory_mb = self.memory_mb;
+        qemu.cpu_cores = self.cpu_cores;
+        // Drafts from before these were kept leave the profile's values
+        for (value, saved) in [
+            (&mut qemu.vga, &self.vga),
+            (&mut qemu.network_model, &self.network_model),
+            (&mut qemu.network_backend, &self.network_backend),
+            (&mut qemu.disk_interface, &self.disk_interface),
+            (&mut qemu.display, &self.display),
+        ] {
+            if !saved.is_empty() {
+                value.clone_from(saved);
+            }
+        }
+        if !self.vga.is_empty() {
+            qemu.audio = self.audio.clone();
+            qemu.enable_kvm = self.enable_kvm;
+            qemu.gl_acceleration = self.gl_acceleration;
+            qemu.uefi = self.uefi;
+            qemu.tpm = self.tpm;
+            qemu.usb_tablet = self.usb_tablet;
+            qemu.extra_args = self.extra_args.clone();
+        }
+    }
+
+    /// A short description for the resume prompt
+    pub fn summary(&self) -> String {
+        let name = if self.vm_name.is_empty() { "an unnamed VM" } else { self.vm_name.as_str() };
+        let saved = chrono::DateTime::parse_from_rfc3339(&self.saved_at)
+            .map(|time| format!(" (saved {})", time.format("%Y-%m-%d %H:%M")))
+            .unwrap_or_default();
+        format!("{}{}", name, saved)
+    }
+
+    /// Whether anything was entered at all
+    fn is_blank(&self) -> bool {
+        self.vm_name.is_empty()
+            && self.selected_os.is_none()
+            && self.iso_path.is_none()
+            && self.existing_disk_path.is_none()
+            && self.remote_disk_url.is_none()
+    }
+}
+
+/// Where an interrupted wizard's input is kept
+pub fn draft_path() -> PathBuf {
+    Config::config_file_path().with_file_name("wizard-draft.toml")
+}
+
+/// Keep the wizard's current input for the panic hook (call in the event loop)
+pub fn track_wizard(state: Option<&CreateWizardState>) {
+    if let Ok(mut tracked) = TRACKED_DRAFT.try_lock() {
+        *tracked = state.map(WizardDraft::from_state).filter(|draft| !draft.is_blank());
+    }
+}
+
+/// Save the tracked wizard input, returning whether there was any
+pub fn save_tracked_draft() -> bool {
+    let Ok(tracked) = TRACKED_DRAFT.try_lock() else {
+        return false;
+    };
+    match tracked.as_ref() {
+        Some(draft) => save_draft(&draft_path(), draft).is_ok(),
+        None => false,
+    }
+}
+
+/// Save a wizard's input as the draft, returning whether there was any
+pub fn save_wizard_draft(state: &CreateWizardState) -> Result<bool> {
+    let draft = WizardDraft::from_state(state);
+    if draft.is_blank() {
+        return Ok(false);
+    }
+    save_draft(&draft_path(), &draft)?;
+    Ok(true)
+}
+
+fn save_draft(path: &Path, draft: &WizardDraft) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let draft = WizardDraft {
+        saved_at: chrono::Local::now().to_rfc3339(),
+        ..draft.clone()
+    };
+    std::fs::write(path, toml::to_string(&draft)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// The saved draft, if there is one
+pub fn load_draft() -> Option<WizardDraft> {
+    load_draft_from(&draft_path())
+}
+
+fn load_draft_from(path: &Path) -> Option<WizardDraft> {
+    let content = std::fs::read_to_string(path).ok()?;
+    toml::from_str(&content).ok()
+}
+
+/// Delete the saved draft (resumed or discarded)
+pub fn discard_draft() {
+    let _ = std::fs::remove_file(draft_path());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draft_round_trip() {
+        let path = std::env::temp_dir().join(format!("vm-curator-draft-{}.toml", std::process::id()));
+        let mut state = CreateWizardState::default();
+        assert!(WizardDraft::from_state(&state).is_blank());
+
+        state.vm_name = "Windows 98 SE".to_string();
+        state.selected_os = Some("windows-98".to_string());
+        state.iso_path = Some(PathBuf::from("/isos/win98se.iso"));
+        state.disk_size_gb = 8;
+        state.disk_tuning.cluster_kib = 16;
+        state.qemu_config.memory_mb = 256;
+        state.qemu_config.vga = "cirrus".to_string();
+        state.qemu_config.audio = vec!["sb16".to_string()];
+        let draft = WizardDraft::from_state(&state);
+        save_draft(&path, &draft).unwrap();
+
+        let loaded = load_draft_from(&path).unwrap();
+        assert!(!loaded.saved_at.is_empty());
+        assert!(loaded.summary().starts_with("Windows 98 SE (saved "));
+        // The OS is chosen first, bringing its profile
+        let mut restored = CreateWizardState {
+            selected_os: loaded.selected_os.clone(),
+            ..Default::default()
+        };
+        loaded.apply_to(&mut restored);
+        assert_eq!(WizardDraft { saved_at: String::new(), ..WizardDraft::from_state(&restored) }, draft);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(load_draft_from(&path), None);
+    }
+
+    #[test]
+    fn test_old_draft_keeps_profile_settings() {
+        let draft: WizardDraft = toml::from_str("vm_name = \"DOS\"\nmemory_mb = 32\ncpu_cores = 1\n").unwrap();
+        let mut state = CreateWizardState::default();
+        state.qemu_config.vga = "cirrus".to_string();
+        state.qemu_config.enable_kvm = true;
+        draft.apply_to(&mut state);
+        assert_eq!(state.vm_name, "DOS");
+        assert_eq!(state.qemu_config.memory_mb, 32);
+        assert_eq!(state.qemu_config.vga, "cirrus");
+        assert!(state.qemu_config.enable_kvm);
+    }
+}
@@ -5,17 +5,24 @@ use anyhow::Result;
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
 use ratatui::prelude::*;
 use ratatui::backend::CrosstermBackend;
-use regex::Regex;
 use std::io::Stdout;
 use std::time::{Duration, Instant};
 
 use crate::app::{App, BackgroundResult, ConfirmAction, InputMode, Screen, TextInputContext};
 use crate::vm::{launch_vm_with_error_check, BootMode};
+use crate::vm::batch::BatchOperation;
 use std::thread;
 
 /// Run the TUI application
 pub fn run(terminal: &mut Terminal<CrosstermBackend<Stdout>>, app: &mut App) -> Result<()> {
     loop {
+        // Leave cleanly on SIGTERM/SIGHUP; the caller restores the terminal
+        // and saves the wizard draft (tracked here in case of a panic)
+        crate::recovery::track_wizard(app.wizard_state.as_ref());
+        if crate::recovery::terminate_requested() {
+            return Ok(());
+        }
+
         terminal.draw(|frame| render(app, frame))?;
 
         // Check for status message expiry
@@ -27,16 +34,26 @@ pub fn run(terminal: &mut Terminal<CrosstermBackend<Stdout>>, app: &mut App) ->
         // Check for VM status updates from background thread
         app.check_vm_status();
 
+        // Lock the VM being managed against other instances sharing the library
+        app.sync_edit_lock();
+        app.sync_latest_screenshot();
+
+        screens::slideshow::tick(app);
+        screens::log_viewer::tick(app);
+        screens::monitor::tick(app);
+
         // Poll with timeout to allow periodic checks
         if event::poll(Duration::from_millis(100))? {
             match event::read()? {
                 Event::Key(key) => {
+                    app.last_input = Instant::now();
                     // Ignore input while loading
                     if !app.loading {
                         handle_key(app, key)?;
                     }
                 }
                 Event::Mouse(mouse) => {
+                    app.last_input = Instant::now();
                     if !app.loading {
                         handle_mouse(app, mouse)?;
                     }
@@ -45,11 +62,61 @@ pub fn run(terminal: &mut Terminal<CrosstermBackend<Stdout>>, app: &mut App) ->
             }
         }
 
+        if app.bridge_setup_confirmed {
+            app.bridge_setup_confirmed = false;
+            run_bridge_setup(terminal, app)?;
+        }
+
         if app.should_quit {
             break;
         }
     }
 
+    // Don't leave a guestmount daemon holding the disk image
+    screens::guest_files::unmount(app)?;
+
+    Ok(())
+}
+
+/// Leave the TUI, run the consented bridge or tap setup (so pkexec/sudo
+/// can prompt on the terminal), then come back and re-probe networking
+fn run_bridge_setup(terminal: &mut Terminal<CrosstermBackend<Stdout>>, app: &mut App) -> Result<()> {
+    use crossterm::execute;
+    use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+
+    let Some((interface, steps)) = app.bridge_setup_plan.take() else {
+        return Ok(());
+    };
+
+    disable_raw_mode()?;
+    execute!(std::io::stdout(), LeaveAlternateScreen)?;
+    println!("Setting up {} (you may be asked for your password)...", interface);
+    for step in &steps {
+        println!("  # {}", step.description);
+        for command in step.command.lines() {
+            println!("  {}", command);
+        }
+    }
+    let result = crate::commands::bridge_setup::run_bridge_setup(&steps);
+    execute!(std::io::stdout(), EnterAlternateScreen)?;
+    enable_raw_mode()?;
+    terminal.clear()?;
+
+    app.network_caps = crate::commands::qemu_system::detect_network_capabilities();
+    match result {
+        Ok(()) => {
+            if let Some(ref mut ns) = app.network_settings_state {
+                if ns.backend == "tap" {
+                    ns.tap_name = Some(interface.clone());
+                } else {
+                    ns.bridge_name = Some(interface.clone());
+                }
+            }
+            app.set_status(format!("{} is ready", interface));
+        }
+        Err(e) => app.set_status(format!("Setup of {} failed: {}", interface, e)),
+    }
+    app.pop_screen();
     Ok(())
 }
 
@@ -165,7 +232,18 @@ fn handle_confirm_click(app: &mut App, action: ConfirmAction, click_x: u16, clic
                 execute_confirm_action(app, action)?;
             } else if click_x >= center_x && click_x < inner_x + inner_width {
                 // Clicked on No - cancel
-                app.pop_screen();
+                match action {
+                    ConfirmAction::RecoverDiskOperations => discard_interrupted_disk_ops(app),
+                    ConfirmAction::ResumeWizardDraft => {
+                        app.pop_screen();
+                        app.discard_wizard_draft();
+                    }
+                    ConfirmAction::LaunchVm => {
+                        app.pop_screen();
+                        app.launch_overrides = Default::default();
+                    }
+                    _ => app.pop_screen(),
+                }
             }
         }
 
@@ -179,6 +257,26 @@ fn handle_confirm_click(app: &mut App, action: ConfirmAction, click_x: u16, clic
     Ok(())
 }
 
+/// Open the display of a VM that is already running
+fn connect_to_instance(app: &mut App, vm: &crate::vm::DiscoveredVm) {
+    let spice_server = matches!(vm.config.spice, Some(s) if s.mode == crate::vm::spice::SpiceMode::Server);
+    let result = if let Some(ref host) = vm.remote {
+        crate::vm::remote_host::open_display(host, vm, app.config.vnc_client.as_deref())
+    } else if let Some(display) = vm.config.vnc_display {
+        crate::vm::vnc::launch_client(app.config.vnc_client.as_deref(), display)
+    } else if spice_server {
+        crate::vm::spice::launch_viewer(&vm.path, &vm.display_name()).map(String::from)
+    } else {
+        // SDL, GTK and spice-app windows belong to the running QEMU
+        app.set_status(format!("{} is already open in its own window", vm.display_name()));
+        return;
+    };
+    match result {
+        Ok(viewer) => app.set_status(format!("Opened {} for the running {}", viewer, vm.display_name())),
+        Err(e) => app.set_status(format!("Error: {}", e)),
+    }
+}
+
 /// Execute a confirmed action (extracted from handle_confirm for reuse)
 fn execute_confirm_action(app: &mut App, action: ConfirmAction) -> Result<()> {
     match action {
@@ -187,17 +285,77 @@ fn execute_confirm_action(app: &mut App, action: ConfirmAction) -> Result<()> {
             app.pop_screen();
 
             if let Some(vm) = app.selected_vm().cloned() {
-                if app.running_vms.contains_key(&vm.id) {
-                    app.set_status(format!("{} is already running", vm.display_name()));
+                let instance = match vm.remote {
+                    Some(_) => None,
+                    None => crate::vm::instance_lock::find_instance(&vm.path),
+                };
+                if let Some(host) = instance.as_ref().and_then(|i| i.host.clone()) {
+                    app.show_error(format!(
+                        "{} is running on {}.\n\nLaunching it here too would corrupt its disks.",
+                        vm.display_name(),
+                        host
+                    ));
+                } else if app.running_vms.contains_key(&vm.id) || instance.is_some() {
+                    app.push_screen(Screen::Confirm(ConfirmAction::ConnectToInstance));
+                } else if let Some(check) = app
+                    .launch_check(&vm)
+                    .filter(|check| check.refused_by(app.config.launch_resource_check))
+                {
+                    tracing::warn!(vm = %vm.id, "launch refused: {}", check.summary());
+                    app.show_error(format!(
+                        "{} wasn't launched: it {}.\n\nStop another VM or lower its memory, or set Resource Check at Launch to warn in Settings.",
+                        vm.display_name(),
+                        check.summary()
+                    ));
                 } else {
                     let options = app.get_launch_options();
                     let result = launch_vm_with_error_check(&vm, &options);
+                    // A remote host's QEMU may support a different set of options
+                    let issues = if vm.backend.capabilities().qemu_args && vm.remote.is_none() {
+                        crate::vm::validation::validate_script(vm.config.emulator.command(), &vm.config.raw_script)
+                    } else {
+                        Vec::new()
+                    };
 
                     if result.success {
-                        app.set_status(format!("Launched: {}", result.vm_name));
+                        match crate::vm::validation::summarize(&issues) {
+                            Some(warning) => app.set_status(format!("Launched: {} (warning: {})", result.vm_name, warning)),
+                            None => app.set_status(format!("Launched: {}", result.vm_name)),
+                        }
+                        // Launch-only USB rules are hot-plugged once QEMU is up
+                        match crate::vm::lifecycle::attach_launch_usb(&vm) {
+                            Ok(0) => {}
+                            Ok(n) => app.set_status(format!("Launched: {} ({} USB device(s) attached)", result.vm_name, n)),
+                            Err(e) => app.set_status(format!("Launched: {}, but USB attach failed: {:#}", result.vm_name, e)),
+                        }
+                        // SPICE server mode needs an external viewer
+                        if matches!(vm.config.spice, Some(s) if s.mode == crate::vm::spice::SpiceMode::Server) {
+                            let viewer = match vm.remote {
+                                Some(ref host) => crate::vm::remote_host::open_display(host, &vm, None),
+                                None => crate::vm::spice::wait_for_server(&vm.path)
+                                    .and_then(|()| crate::vm::spice::launch_viewer(&vm.path, &result.vm_name))
+                                    .map(String::from),
+                            };
+                            match viewer {
+                                Ok(viewer) => app.set_status(format!("Launched: {} (viewer: {})", result.vm_name, viewer)),
+                                Err(e) => app.set_status(format!("Launched: {}, but {}", result.vm_name, e)),
+                            }
+                        }
                     } else {
                         // Show error in the error dialog for better visibility
-                        let error_msg = result.error.unwrap_or_else(|| "Unknown error".to_string());
+                        let mut error_msg = result.error.unwrap_or_else(|| "Unknown error".to_string());
+                        if !issues.is_empty() {
+                            error_msg.push_str("\n\nUnsupported by the installed QEMU:");
+                            for issue in &issues {
+                                error_msg.push_str(&format!("\n  - {}", issue));
+                            }
+                        }
+                        if vm.remote.is_none() && vm.backend.capabilities().qemu_args {
+                            error_msg.push_str("\n\nDoctor in the management menu (m) checks for common causes.");
+                        }
+                        if vm.remote.is_none() && crate::vm::qemu_log::log_path(&vm.path).exists() {
+                            error_msg.push_str("\nThe full output is in the launch log (l).");
+                        }
                         app.show_error(format!(
                             "Failed to launch {}\n\n{}",
                             result.vm_name, error_msg
@@ -205,6 +363,24 @@ fn execute_confirm_action(app: &mut App, action: ConfirmAction) -> Result<()> {
                     }
                 }
             }
+            // Launch options apply to one launch
+            app.launch_overrides = Default::default();
+        }
+        ConfirmAction::TakeOverVm => {
+            app.pop_screen();
+            if let Some(vm_dir) = app.edit_lock.take().map(|l| l.vm_dir) {
+                // The next pass of the event loop locks it for us
+                match crate::vm::instance_lock::break_lock(&vm_dir) {
+                    Ok(()) => app.set_status("Took over the VM's lock"),
+                    Err(e) => app.set_status(format!("Error: {:#}", e)),
+                }
+            }
+        }
+        ConfirmAction::ConnectToInstance => {
+            app.pop_screen();
+            if let Some(vm) = app.selected_vm().cloned() {
+                connect_to_instance(app, &vm);
+            }
         }
         ConfirmAction::ResetVm => {
             if let Some(vm) = app.selected_vm() {
@@ -235,6 +411,9 @@ fn execute_confirm_action(app: &mut App, action: ConfirmAction) -> Result<()> {
             if let Some(vm) = app.selected_vm() {
                 if app.running_vms.contains_key(&vm.id) {
                     app.set_status("Error: Cannot restore snapshot while VM is running. Please shut down the VM first.");
+                } else if let Err(e) = crate::vm::suspend::discard(&vm.path) {
+                    // A suspended VM's saved state doesn't fit the restored disk (the dialog said so)
+                    app.set_status(format!("Error: {:#}", e));
                 } else if let Some(disk) = vm.config.primary_disk() {
                     let disk_path = disk.path.clone();
                     let snap_name = name.clone();
@@ -289,10 +468,27 @@ fn execute_confirm_action(app: &mut App, action: ConfirmAction) -> Result<()> {
             app.pop_screen();
             if let Some(vm) = app.selected_vm().cloned() {
                 if let Some(pid) = app.running_vms.get(&vm.id).copied() {
-                    match crate::vm::stop_vm_by_pid(pid) {
+                    // Prefer a clean shutdown through the guest agent when it's answering
+                    let via_agent = app.guest_info.contains_key(&vm.id)
+                        && crate::vm::guest_agent::shutdown_guest(&vm.path).is_ok();
+                    let result = if via_agent {
+                        Ok(())
+                    } else if let Some(ref host) = vm.remote {
+                        crate::vm::remote_host::stop(host, &vm, pid)
+                    } else if crate::vm::systemd::is_active(&vm.id) {
+                        // Keep systemd's view of its service in step
+                        crate::vm::systemd::stop(&vm.id)
+                    } else {
+                        crate::vm::stop_vm_by_pid(pid)
+                    };
+                    match result {
                         Ok(()) => {
                             app.stopping_vms.insert(vm.id.clone(), Instant::now());
-                            app.set_status(format!("Stopping {}...", vm.display_name()));
+                            if via_agent {
+                                app.set_status(format!("Shutting down {} via guest agent...", vm.display_name()));
+                            } else {
+                                app.set_status(format!("Stopping {}...", vm.display_name()));
+                            }
                         }
                         Err(e) => {
                             app.set_status(format!("Failed to stop {}: {}", vm.display_name(), e));
@@ -303,11 +499,30 @@ fn execute_confirm_action(app: &mut App, action: ConfirmAction) -> Result<()> {
                 }
             }
         }
+        ConfirmAction::SuspendVm => {
+            app.pop_screen();
+            app.start_suspend();
+        }
+        ConfirmAction::DiscardSavedState => {
+            if let Some(vm) = app.selected_vm().cloned() {
+                if let Err(e) = crate::vm::suspend::discard(&vm.path) {
+                    app.pop_screen();
+                    app.set_status(format!("Error: {:#}", e));
+                    return Ok(());
+                }
+            }
+            // Launches with the boot mode chosen; pops this dialog
+            execute_confirm_action(app, ConfirmAction::LaunchVm)?;
+        }
         ConfirmAction::ForceStopVm => {
             app.pop_screen();
             if let Some(vm) = app.selected_vm().cloned() {
                 if let Some(pid) = app.running_vms.get(&vm.id).copied() {
-                    match crate::vm::force_stop_vm(pid) {
+                    let result = match vm.remote {
+                        Some(ref host) => crate::vm::remote_host::force_stop(host, pid),
+                        None => crate::vm::force_stop_vm(pid),
+                    };
+                    match result {
                         Ok(()) => {
                             app.stopping_vms.remove(&vm.id);
                             app.set_status(format!("Force stopped {}", vm.display_name()));
@@ -319,10 +534,89 @@ fn execute_confirm_action(app: &mut App, action: ConfirmAction) -> Result<()> {
                 }
             }
         }
+        ConfirmAction::CompactDisk => {
+            app.pop_screen();
+            app.start_compaction();
+        }
+        ConfirmAction::BatchSnapshot => {
+            app.pop_screen();
+            let name = chrono::Local::now().format("batch-%Y%m%d-%H%M%S").to_string();
+            app.start_batch(BatchOperation::Snapshot { name }, None);
+        }
+        ConfirmAction::LeaveArchivalMode => {
+            app.pop_screen();
+            if let Some(vm) = app.selected_vm().cloned() {
+                if app.running_vms.contains_key(&vm.id) {
+                    app.set_status("Error: Cannot change archival mode while VM is running. Please shut down the VM first.");
+                } else {
+                    match crate::vm::archival::disable_archival_mode(&vm) {
+                        Ok(()) => {
+                            app.set_status(format!("{} is no longer archived", vm.display_name()));
+                            let _ = app.refresh_vms();
+                        }
+                        Err(e) => app.set_status(format!("Error leaving archival mode: {}", e)),
+                    }
+                }
+            }
+        }
+        ConfirmAction::MigrateLibrary => {
+            app.pop_screen();
+            app.pending_migration = None;
+            match crate::vm::migration::migrate_library(&app.config.vm_library_path) {
+                Ok(report) => {
+                    app.set_status(format!(
+                        "Upgraded library from schema {} to {} (backup: {})",
+                        report.from,
+                        report.to,
+                        report.backup_dir.display()
+                    ));
+                    let _ = app.refresh_vms();
+                }
+                Err(e) => app.show_error(format!(
+                    "Library upgrade failed\n\n{:#}\n\nYour configuration files were backed up before any changes; see the .vm-curator-backups folder in your library.",
+                    e
+                )),
+            }
+        }
+        ConfirmAction::ResumeWizardDraft => {
+            app.pop_screen();
+            app.resume_wizard_draft();
+        }
+        ConfirmAction::RecoverDiskOperations => {
+            app.pop_screen();
+            let ops = std::mem::take(&mut app.interrupted_disk_ops);
+            let tx = app.background_tx.clone();
+            app.loading = true;
+            app.set_status("Recovering interrupted disk operations...");
+
+            thread::spawn(move || {
+                let result = crate::vm::disk_ops::recover_operations(&ops);
+                let _ = tx.send(BackgroundResult::DiskOperationsRecovered {
+                    resumed: *result.as_ref().unwrap_or(&0),
+                    error: result.err().map(|e| e.to_string()),
+                });
+            });
+        }
     }
     Ok(())
 }
 
+/// Delete temp files and journals of interrupted disk operations instead of resuming them
+fn discard_interrupted_disk_ops(app: &mut App) {
+    app.pop_screen();
+    let ops = std::mem::take(&mut app.interrupted_disk_ops);
+    let errors: Vec<String> = ops
+        .iter()
+        .filter_map(|op| crate::vm::disk_ops::discard_operation(op).err())
+        .map(|e| e.to_string())
+        .collect();
+    if errors.is_empty() {
+        app.set_status(format!("Cleaned up {} interrupted disk operation(s)", ops.len()));
+    } else {
+        app.set_status(format!("Error cleaning up disk operations: {}", errors.join("; ")));
+    }
+}
+
 /// Render a dimming overlay over the entire screen
 /// Uses a dark background that the popup's Clear widget will cut through
 fn render_dim_overlay(_frame: &mut Frame) {
@@ -354,21 +648,208 @@ fn render(app: &App, frame: &mut Frame) {
             render_dim_overlay(frame);
             render_detailed_info(app, frame);
         }
+        Screen::Notes => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::configuration::render_text_editor(app, frame, "notes.md");
+        }
         Screen::Snapshots => {
             screens::main_menu::render(app, frame);
             render_dim_overlay(frame);
             screens::management::render_snapshots(app, frame);
         }
+        Screen::AddDataDisk => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::data_disk::render(app, frame);
+        }
+        Screen::IsoLibrary => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::iso_library::render(app, frame);
+        }
+        Screen::CreateSnapshot => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::management::render_snapshots(app, frame);
+            screens::create_snapshot::render(app, frame);
+        }
         Screen::BootOptions => {
             screens::main_menu::render(app, frame);
             render_dim_overlay(frame);
             screens::management::render_boot_options(app, frame);
         }
+        Screen::LaunchOptions => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::launch_options::render(app, frame);
+        }
+        Screen::LaunchEnvironment => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::launch_env::render(app, frame);
+        }
+        Screen::QemuBinary => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::qemu_binary::render(app, frame);
+        }
+        Screen::InputPassthrough => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::input_passthrough::render(app, frame);
+        }
+        Screen::GameControllers => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::game_controllers::render(app, frame);
+        }
         Screen::DisplayOptions => {
             screens::main_menu::render(app, frame);
             render_dim_overlay(frame);
             screens::management::render_display_options(app, frame);
         }
+        Screen::CpuModel => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::management::render_cpu_model(app, frame);
+        }
+        Screen::MachineType => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::management::render_machine_type(app, frame);
+        }
+        Screen::DiskIo => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::management::render_disk_io(app, frame);
+        }
+        Screen::Identity => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::management::render_identity(app, frame);
+        }
+        Screen::GuestFiles => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::guest_files::render(app, frame);
+        }
+        Screen::ConvertDisk => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::convert_disk::render(app, frame);
+        }
+        Screen::BackingChain => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::backing_chain::render(app, frame);
+        }
+        Screen::PhysicalDisks => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::physical_disks::render(app, frame);
+        }
+        Screen::MacFirmware => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::mac_firmware::render(app, frame);
+        }
+        Screen::HostMigration => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::host_migration::render(app, frame);
+        }
+        Screen::SystemdUnit => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::systemd_unit::render(app, frame);
+        }
+        Screen::Slideshow => screens::slideshow::render(app, frame),
+        Screen::Quiz => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::quiz::render(app, frame);
+        }
+        Screen::MetadataEditor => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::metadata_editor::render(app, frame);
+        }
+        Screen::Statistics => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::statistics::render(app, frame);
+        }
+        Screen::Checklist => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::checklist::render(app, frame);
+        }
+        Screen::Doctor => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::doctor::render(app, frame);
+        }
+        Screen::OrphanFiles => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::orphan_files::render(app, frame);
+        }
+        Screen::Storage => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::storage::render(app, frame);
+        }
+        Screen::LogViewer => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::log_viewer::render(app, frame);
+        }
+        Screen::Monitor => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::monitor::render(app, frame);
+        }
+        Screen::Screenshots => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::screenshots::render(app, frame);
+        }
+        Screen::ProfileEditor => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::profile_editor::render(app, frame);
+        }
+        Screen::CpuEra => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::management::render_cpu_era(app, frame);
+        }
+        Screen::AudioBackend => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::management::render_audio_backend(app, frame);
+        }
+        Screen::MidiOutput => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::management::render_midi_output(app, frame);
+        }
+        Screen::SoundBlaster => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::management::render_sound_blaster(app, frame);
+        }
+        Screen::DisplayPreset => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::management::render_display_preset(app, frame);
+        }
+        Screen::GpuAccel => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::management::render_gpu_accel(app, frame);
+        }
         Screen::UsbDevices => {
             screens::main_menu::render(app, frame);
             render_dim_overlay(frame);
@@ -379,6 +860,11 @@ fn render(app: &App, frame: &mut Frame) {
             render_dim_overlay(frame);
             screens::pci_passthrough::render(app, frame);
         }
+        Screen::PciHostCheck => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::pci_passthrough::render_host_check(app, frame);
+        }
         Screen::SharedFolders => {
             screens::main_menu::render(app, frame);
             render_dim_overlay(frame);
@@ -399,6 +885,11 @@ fn render(app: &App, frame: &mut Frame) {
             render_dim_overlay(frame);
             screens::multi_gpu_setup::render(app, frame);
         }
+        Screen::LookingGlassInstructions => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::multi_gpu_setup::render_instructions(app, frame);
+        }
         Screen::Confirm(action) => {
             screens::main_menu::render(app, frame);
             render_dim_overlay(frame);
@@ -449,11 +940,21 @@ fn render(app: &App, frame: &mut Frame) {
             render_dim_overlay(frame);
             screens::network_settings::render(app, frame);
         }
+        Screen::BridgeSetup => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::network_settings::render_bridge_setup(app, frame);
+        }
         Screen::Settings => {
             screens::main_menu::render(app, frame);
             render_dim_overlay(frame);
             screens::settings::render(app, frame);
         }
+        Screen::BatchReport => {
+            screens::main_menu::render(app, frame);
+            render_dim_overlay(frame);
+            screens::batch_report::render(app, frame);
+        }
     }
 }
 
@@ -467,7 +968,7 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
 
     // Global quit with q/Q (except in text input modes where q might be typed)
     if (key.code == KeyCode::Char('q') || key.code == KeyCode::Char('Q'))
-        && !matches!(app.screen, Screen::Search | Screen::TextInput(_) | Screen::RawScript | Screen::CreateWizard | Screen::CreateWizardCustomOs | Screen::NetworkSettings)
+        && !matches!(app.screen, Screen::Search | Screen::TextInput(_) | Screen::RawScript | Screen::Notes | Screen::CreateWizard | Screen::CreateWizardCustomOs | Screen::CreateWizardDownload | Screen::NetworkSettings | Screen::GuestFiles | Screen::ConvertDisk | Screen::PhysicalDisks | Screen::MacFirmware | Screen::HostMigration | Screen::SystemdUnit | Screen::Slideshow | Screen::Quiz | Screen::MetadataEditor | Screen::ProfileEditor | Screen::LaunchOptions | Screen::LaunchEnvironment)
     {
         app.should_quit = true;
         return Ok(());
@@ -479,15 +980,55 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
         Screen::Configuration => handle_configuration(app, key)?,
         Screen::RawScript => handle_raw_script(app, key)?,
         Screen::DetailedInfo => handle_detailed_info(app, key)?,
+        Screen::Notes => handle_notes(app, key)?,
         Screen::Snapshots => handle_snapshots(app, key)?,
+        Screen::CreateSnapshot => screens::create_snapshot::handle_key(app, key),
+        Screen::AddDataDisk => screens::data_disk::handle_key(app, key),
+        Screen::IsoLibrary => screens::iso_library::handle_key(app, key)?,
         Screen::BootOptions => handle_boot_options(app, key)?,
+        Screen::LaunchOptions => screens::launch_options::handle_key(app, key)?,
+        Screen::LaunchEnvironment => screens::launch_env::handle_key(app, key)?,
+        Screen::QemuBinary => screens::qemu_binary::handle_key(app, key)?,
+        Screen::InputPassthrough => screens::input_passthrough::handle_key(app, key)?,
+        Screen::GameControllers => screens::game_controllers::handle_key(app, key)?,
         Screen::DisplayOptions => handle_display_options(app, key)?,
+        Screen::CpuModel => handle_cpu_model(app, key)?,
+        Screen::MachineType => handle_machine_type(app, key)?,
+        Screen::DiskIo => handle_disk_io(app, key)?,
+        Screen::Identity => handle_identity(app, key)?,
+        Screen::GuestFiles => screens::guest_files::handle_key(app, key)?,
+        Screen::ConvertDisk => screens::convert_disk::handle_key(app, key)?,
+        Screen::BackingChain => screens::backing_chain::handle_key(app, key)?,
+        Screen::PhysicalDisks => screens::physical_disks::handle_key(app, key)?,
+        Screen::MacFirmware => screens::mac_firmware::handle_key(app, key)?,
+        Screen::HostMigration => screens::host_migration::handle_key(app, key)?,
+        Screen::SystemdUnit => screens::systemd_unit::handle_key(app, key)?,
+        Screen::Slideshow => screens::slideshow::handle_key(app, key)?,
+        Screen::Quiz => screens::quiz::handle_key(app, key)?,
+        Screen::MetadataEditor => screens::metadata_editor::handle_key(app, key)?,
+        Screen::ProfileEditor => screens::profile_editor::handle_key(app, key)?,
+        Screen::Statistics => screens::statistics::handle_key(app, key)?,
+        Screen::Checklist => screens::checklist::handle_key(app, key)?,
+        Screen::Doctor => screens::doctor::handle_key(app, key)?,
+        Screen::OrphanFiles => screens::orphan_files::handle_key(app, key)?,
+        Screen::Storage => screens::storage::handle_key(app, key)?,
+        Screen::LogViewer => screens::log_viewer::handle_key(app, key)?,
+        Screen::Monitor => screens::monitor::handle_key(app, key)?,
+        Screen::Screenshots => screens::screenshots::handle_key(app, key),
+        Screen::CpuEra => handle_cpu_era(app, key)?,
+        Screen::AudioBackend => handle_audio_backend(app, key)?,
+        Screen::MidiOutput => handle_midi_output(app, key)?,
+        Screen::SoundBlaster => handle_sound_blaster(app, key)?,
+        Screen::DisplayPreset => handle_display_preset(app, key)?,
+        Screen::GpuAccel => handle_gpu_accel(app, key)?,
         Screen::UsbDevices => handle_usb_devices(app, key)?,
         Screen::PciPassthrough => screens::pci_passthrough::handle_key(app, key)?,
+        Screen::PciHostCheck => screens::pci_passthrough::handle_host_check_key(app, key)?,
         Screen::SharedFolders => screens::shared_folders::handle_key(app, key)?,
         Screen::SingleGpuSetup => screens::single_gpu_setup::handle_key(app, key)?,
         Screen::SingleGpuInstructions => handle_single_gpu_instructions(app, key)?,
         Screen::MultiGpuSetup => screens::multi_gpu_setup::handle_input(app, key)?,
+        Screen::LookingGlassInstructions => screens::multi_gpu_setup::handle_instructions_input(app, key)?,
         Screen::Confirm(action) => handle_confirm(app, action.clone(), key)?,
         Screen::Help => handle_help(app, key)?,
         Screen::Search => handle_search(app, key)?,
@@ -498,13 +1039,20 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
         Screen::CreateWizardCustomOs => screens::create_wizard::handle_custom_os_key(app, key)?,
         Screen::CreateWizardDownload => screens::create_wizard::handle_download_key(app, key)?,
         Screen::NetworkSettings => screens::network_settings::handle_key(app, key)?,
+        Screen::BridgeSetup => screens::network_settings::handle_bridge_setup_key(app, key)?,
         Screen::Settings => { screens::settings::handle_input(app, key)?; }
+        Screen::BatchReport => screens::batch_report::handle_key(app, key)?,
     }
 
     Ok(())
 }
 
 fn handle_main_menu(app: &mut App, key: KeyEvent) -> Result<()> {
+    // Create, import, settings, profiles, data disks and batch snapshots change the library
+    if matches!(key.code, KeyCode::Char('c' | 'C' | 'i' | 'I' | 's' | 'S' | 'b' | 'B' | 'e' | 'E' | 'w' | 'W' | 'f' | 'F' | 'a' | 'A')) && app.kiosk_blocks() {
+        return Ok(());
+    }
+
     match key.code {
         KeyCode::Char('j') | KeyCode::Down => app.select_next(),
         KeyCode::Char('k') | KeyCode::Up => app.select_prev(),
@@ -515,8 +1063,10 @@ fn handle_main_menu(app: &mut App, key: KeyEvent) -> Result<()> {
             app.info_scroll = app.info_scroll.saturating_sub(5);
         }
         KeyCode::Enter => {
-            if app.selected_vm().is_some() {
-                if app.config.confirm_before_launch {
+            if let Some(vm) = app.selected_vm() {
+                // A launch the host can't take is always confirmed
+                let unfit = app.launch_check(vm).is_some_and(|check| !check.is_ok());
+                if app.config.confirm_before_launch || unfit {
                     app.push_screen(Screen::Confirm(ConfirmAction::LaunchVm));
                 } else {
                     // Launch directly without confirmation
@@ -534,12 +1084,34 @@ fn handle_main_menu(app: &mut App, key: KeyEvent) -> Result<()> {
             app.push_screen(Screen::Search);
         }
         KeyCode::Char('?') => app.push_screen(Screen::Help),
+        KeyCode::Char('p') | KeyCode::Char('P') => screens::slideshow::open(app),
+        KeyCode::Char('o') | KeyCode::Char('O') => app.show_on_this_day(),
+        KeyCode::Char('t') | KeyCode::Char('T') => screens::quiz::open(app),
+        KeyCode::Char('d') | KeyCode::Char('D') => app.open_details(),
+        KeyCode::Char('u') | KeyCode::Char('U') => screens::statistics::open(app),
+        KeyCode::Char('e') | KeyCode::Char('E') => screens::profile_editor::open(app),
+        KeyCode::Char('w') | KeyCode::Char('W') => screens::orphan_files::open(app),
+        KeyCode::Char('f') | KeyCode::Char('F') => screens::storage::open(app),
+        KeyCode::Char('l') | KeyCode::Char('L') => screens::log_viewer::open(app),
+        KeyCode::Char('r') | KeyCode::Char('R') => screens::monitor::open(app),
+        KeyCode::Char('a') | KeyCode::Char('A') => screens::data_disk::open(app),
+        KeyCode::Char('v') | KeyCode::Char('V') => screens::iso_library::open(app),
         KeyCode::Char('c') | KeyCode::Char('C') => {
             app.start_create_wizard();
         }
+        KeyCode::Char('i') | KeyCode::Char('I') => {
+            app.start_import_disk();
+        }
         KeyCode::Char('s') | KeyCode::Char('S') => {
             app.push_screen(Screen::Settings);
         }
+        KeyCode::Char('b') | KeyCode::Char('B') => {
+            if app.batch_snapshot_targets().is_empty() {
+                app.set_status("No VMs with snapshot support");
+            } else {
+                app.push_screen(Screen::Confirm(ConfirmAction::BatchSnapshot));
+            }
+        }
         KeyCode::Char('x') | KeyCode::Char('X') => {
             if let Some(vm) = app.selected_vm().cloned() {
                 if app.selected_vm_pid().is_some() {
@@ -592,7 +1164,12 @@ fn handle_management(app: &mut App, key: KeyEvent) -> Result<()> {
             // Get the menu items and find the action
             if let Some(vm) = app.selected_vm() {
                 let menu_items = get_menu_items(vm, &app.config);
+                let in_use = app.edit_lock.as_ref().is_some_and(|l| l.held_by.is_some());
                 if let Some(item) = menu_items.get(selected_idx) {
+                    if in_use && !item.action.is_read_only() {
+                        app.push_screen(Screen::Confirm(ConfirmAction::TakeOverVm));
+                        return Ok(());
+                    }
                     match item.action {
                         MenuAction::StopVm => {
                             if let Some(vm) = app.selected_vm().cloned() {
@@ -614,6 +1191,17 @@ fn handle_management(app: &mut App, key: KeyEvent) -> Result<()> {
                                 }
                             }
                         }
+                        MenuAction::SuspendVm => {
+                            if let Some(vm) = app.selected_vm().cloned() {
+                                if app.selected_vm_pid().is_none() {
+                                    app.set_status("VM is not running");
+                                } else if !crate::vm::qmp::is_configured(&vm.config.raw_script) {
+                                    app.set_status("Error: The VM has no QMP socket to save its state through");
+                                } else {
+                                    app.push_screen(Screen::Confirm(ConfirmAction::SuspendVm));
+                                }
+                            }
+                        }
                         MenuAction::BootOptions => {
                             app.selected_menu_item = 0;
                             app.push_screen(Screen::BootOptions);
@@ -624,22 +1212,9 @@ fn handle_management(app: &mut App, key: KeyEvent) -> Result<()> {
                         }
                         MenuAction::UsbPassthrough => {
                             app.load_usb_devices()?;
-                            // Load saved USB passthrough config and pre-select matching devices
-                            if let Some(vm) = app.selected_vm() {
-                                let saved = crate::vm::load_usb_passthrough(vm);
-                                app.selected_usb_devices.clear();
-                                for saved_dev in &saved {
-                                    // Find matching device by vendor/product ID
-                                    for (i, dev) in app.usb_devices.iter().enumerate() {
-                                        if dev.vendor_id == saved_dev.vendor_id
-                                            && dev.product_id == saved_dev.product_id
-                                        {
-                                            app.selected_usb_devices.push(i);
-                                            break;
-                                        }
-                                    }
-                                }
-                            }
+                            // Load saved USB rules and pre-select matching devices
+                            app.load_usb_rules();
+                            app.refresh_usb_attachments();
                             app.selected_menu_item = 0;
                             app.push_screen(Screen::UsbDevices);
                         }
@@ -680,21 +1255,32 @@ fn handle_management(app: &mut App, key: KeyEvent) -> Result<()> {
                             if let Some(vm) = app.selected_vm() {
                                 let net = vm.config.network.as_ref();
                                 let model = net.map(|n| n.model.clone()).unwrap_or_else(|| "e1000".to_string());
-                                let (backend, bridge_name) = net.map(|n| {
+                                let (backend, bridge_name, tap_name) = net.map(|n| {
                                     match &n.backend {
-                                        crate::vm::qemu_config::NetworkBackend::User => ("user".to_string(), None),
-                                        crate::vm::qemu_config::NetworkBackend::Passt => ("passt".to_string(), None),
-                                        crate::vm::qemu_config::NetworkBackend::Bridge(name) => ("bridge".to_string(), Some(name.clone())),
-                                        crate::vm::qemu_config::NetworkBackend::None => ("none".to_string(), None),
+                                        crate::vm::qemu_config::NetworkBackend::User => ("user".to_string(), None, None),
+                                        crate::vm::qemu_config::NetworkBackend::Passt => ("passt".to_string(), None, None),
+                                        crate::vm::qemu_config::NetworkBackend::Bridge(name) => ("bridge".to_string(), Some(name.clone()), None),
+                                        crate::vm::qemu_config::NetworkBackend::Tap(name) => ("tap".to_string(), None, Some(name.clone())),
+                                        crate::vm::qemu_config::NetworkBackend::None => ("none".to_string(), None, None),
                                     }
-                                }).unwrap_or_else(|| ("user".to_string(), None));
+                                }).unwrap_or_else(|| ("user".to_string(), None, None));
                                 let port_forwards = net.map(|n| n.port_forwards.clone()).unwrap_or_default();
+                                let smb_share = net.is_some_and(|n| n.smb_share);
+                                // An existing tap keeps the bridge it's attached to
+                                let tap_uplink = tap_name.as_ref()
+                                    .and_then(|t| app.network_caps.tap_devices.iter().find(|d| &d.name == t))
+                                    .and_then(|d| d.master.clone())
+                                    .map(crate::commands::tap_setup::TapUplink::Bridge)
+                                    .unwrap_or(crate::commands::tap_setup::TapUplink::Routed { nat: true });
 
                                 app.network_settings_state = Some(crate::app::NetworkSettingsState {
                                     model,
                                     backend,
                                     bridge_name,
+                                    tap_name,
+                                    tap_uplink,
                                     port_forwards,
+                                    smb_share,
                                     selected_field: 0,
                                     editing_port_forwards: false,
                                     pf_selected: 0,
@@ -719,22 +1305,221 @@ fn handle_management(app: &mut App, key: KeyEvent) -> Result<()> {
                             app.selected_menu_item = 0;
                             app.push_screen(Screen::DisplayOptions);
                         }
-                        MenuAction::RenameVm => {
-                            if let Some(vm) = app.selected_vm() {
-                                app.text_input_buffer = vm.display_name();
+                        MenuAction::DisplayPreset => {
+                            app.display_preset_edit = app
+                                .selected_vm()
+                                .map(|vm| crate::vm::display_preset::DisplayPreset::from_config(&vm.config));
+                            app.selected_menu_item = 0;
+                            app.push_screen(Screen::DisplayPreset);
+                        }
+                        MenuAction::GpuAccel => {
+                            if let Some(vm) = app.selected_vm().cloned() {
+                                let current = vm.config.gpu_accel.as_deref().unwrap_or("off");
+                                app.qemu_has_virgl =
+                                    crate::commands::qemu_system::get_supported_devices(vm.config.emulator.command())
+                                        .map(|devices| devices.iter().any(|d| d == "virtio-vga-gl"));
+                                app.selected_menu_item = crate::vm::gpu_accel::GPU_ACCEL_MODES
+                                    .iter()
+                                    .position(|m| m.id == current)
+                                    .unwrap_or(0);
+                                app.push_screen(Screen::GpuAccel);
                             }
-                            app.push_screen(Screen::TextInput(TextInputContext::RenameVm));
                         }
-                        MenuAction::ResetVm => {
-                            app.push_screen(Screen::Confirm(ConfirmAction::ResetVm));
+                        MenuAction::OpenVncViewer => {
+                            if let Some(vm) = app.selected_vm().cloned() {
+                                if !app.running_vms.contains_key(&vm.id) {
+                                    app.set_status("VM is not running");
+                                } else if let Some(display) = vm.config.vnc_display {
+                                    match crate::vm::vnc::launch_client(app.config.vnc_client.as_deref(), display) {
+                                        Ok(client) => app.set_status(format!(
+                                            "Opened {} on {}",
+                                            client,
+                                            crate::vm::vnc::address(display)
+                                        )),
+                                        Err(e) => app.set_status(format!("Error: {}", e)),
+                                    }
+                                }
+                            }
                         }
-                        MenuAction::DeleteVm => {
-                            app.push_screen(Screen::Confirm(ConfirmAction::DeleteVm));
+                        MenuAction::OpenRemoteDisplay => {
+                            if let Some(vm) = app.selected_vm().cloned() {
+                                if !app.running_vms.contains_key(&vm.id) {
+                                    app.set_status("VM is not running");
+                                } else if let Some(ref host) = vm.remote {
+                                    match crate::vm::remote_host::open_display(host, &vm, app.config.vnc_client.as_deref()) {
+                                        Ok(viewer) => app.set_status(format!("Opened {} via {}", viewer, host.name)),
+                                        Err(e) => app.set_status(format!("Error: {}", e)),
+                                    }
+                                }
+                            }
+                        }
+                        MenuAction::CpuModel => {
+                            if let Some(vm) = app.selected_vm().cloned() {
+                                let supported =
+                                    crate::commands::qemu_system::get_supported_cpus(vm.config.emulator.command());
+                                app.cpu_model_state =
+                                    Some(crate::app::CpuModelState::new(vm.config.cpu_model.as_deref(), supported));
+                                app.push_screen(Screen::CpuModel);
+                            }
+                        }
+                        MenuAction::MachineType => {
+                            if let Some(vm) = app.selected_vm().cloned() {
+                                let emulator = vm.config.emulator.command();
+                                app.qemu_machines = crate::commands::qemu_system::get_supported_machines(emulator);
+                                app.selected_menu_item = vm
+                                    .config
+                                    .machine
+                                    .as_deref()
+                                    .and_then(|current| {
+                                        crate::vm::machine::machine_options(emulator, Some(current))
+                                            .iter()
+                                            .position(|m| m == current)
+                                    })
+                                    .unwrap_or(0);
+                                app.push_screen(Screen::MachineType);
+                            }
+                        }
+                        MenuAction::EmulatorSettings => {
+                            if let Some(vm) = app.selected_vm().cloned() {
+                                if app.running_vms.contains_key(&vm.id) {
+                                    app.set_status("Error: Close the emulator before changing its settings");
+                                } else {
+                                    let options = crate::vm::LaunchOptions {
+                                        boot_mode: BootMode::Normal,
+                                        extra_args: vec!["--settings".to_string()],
+                                        ..Default::default()
+                                    };
+                                    let result = launch_vm_with_error_check(&vm, &options);
+                                    match result.error {
+                                        Some(e) => app.show_error(format!("Failed to open {} settings\n\n{}", vm.backend.label(), e)),
+                                        None => app.set_status(format!("Opened {} settings for {}", vm.backend.label(), result.vm_name)),
+                                    }
+                                }
+                            }
+                        }
+                        MenuAction::MacFirmware => {
+                            screens::mac_firmware::open(app);
+                        }
+                        MenuAction::CpuEra => {
+                            app.selected_menu_item = 0;
+                            app.push_screen(Screen::CpuEra);
+                        }
+                        MenuAction::SoundBlaster => {
+                            app.sb16_edit = app.selected_vm().and_then(|vm| vm.config.sb16);
+                            app.selected_menu_item = 0;
+                            app.push_screen(Screen::SoundBlaster);
+                        }
+                        MenuAction::MidiOutput => {
+                            app.selected_menu_item = 0;
+                            app.push_screen(Screen::MidiOutput);
+                        }
+                        MenuAction::AudioBackend => {
+                            let current = app.selected_vm().and_then(|vm| vm.config.audio_backend.clone());
+                            app.selected_menu_item = app.vm_audio_backend_options()
+                                .iter()
+                                .position(|b| Some(b) == current.as_ref())
+                                .unwrap_or(0);
+                            app.push_screen(Screen::AudioBackend);
+                        }
+                        MenuAction::RenameVm => {
+                            if let Some(vm) = app.selected_vm() {
+                                app.text_input_buffer = vm.display_name();
+                            }
+                            app.push_screen(Screen::TextInput(TextInputContext::RenameVm));
+                        }
+                        MenuAction::EditOsInfo => screens::metadata_editor::open(app),
+                        MenuAction::Checklist => screens::checklist::open(app),
+                        MenuAction::Doctor => screens::doctor::open(app),
+                        MenuAction::BootScreenshots => screens::screenshots::open(app),
+                        MenuAction::DebugBundle => app.start_debug_bundle(),
+                        MenuAction::ResetVm => {
+                            app.push_screen(Screen::Confirm(ConfirmAction::ResetVm));
+                        }
+                        MenuAction::DeleteVm => {
+                            app.push_screen(Screen::Confirm(ConfirmAction::DeleteVm));
                         }
                         MenuAction::EditRawConfig => {
                             app.load_script_into_editor();
                             app.push_screen(Screen::RawScript);
                         }
+                        MenuAction::ArchivalMode => {
+                            if let Some(vm) = app.selected_vm() {
+                                if vm.config.archival_date.is_some() {
+                                    app.push_screen(Screen::Confirm(ConfirmAction::LeaveArchivalMode));
+                                } else {
+                                    // Suggest the OS release date as the frozen date
+                                    app.text_input_buffer = app.selected_vm_info()
+                                        .map(|info| info.release_date)
+                                        .filter(|d| crate::vm::archival::validate_date(d).is_ok())
+                                        .unwrap_or_default();
+                                    app.push_screen(Screen::TextInput(TextInputContext::ArchivalDate));
+                                }
+                            }
+                        }
+                        MenuAction::DiskIo => {
+                            if let Some(vm) = app.selected_vm() {
+                                app.disk_io_state = Some(crate::app::DiskIoState {
+                                    drives: crate::vm::disk_io::parse_disk_drives(&vm.config.raw_script),
+                                    drive_selected: 0,
+                                });
+                                app.selected_menu_item = 0;
+                                app.push_screen(Screen::DiskIo);
+                            }
+                        }
+                        MenuAction::Identity => {
+                            app.push_screen(Screen::Identity);
+                        }
+                        MenuAction::BrowseDiskFiles => {
+                            screens::guest_files::open(app);
+                        }
+                        MenuAction::ConvertDisk => {
+                            screens::convert_disk::open(app);
+                        }
+                        MenuAction::BackingChain => {
+                            screens::backing_chain::open(app);
+                        }
+                        MenuAction::AddDataDisk => {
+                            screens::data_disk::open(app);
+                        }
+                        MenuAction::PhysicalDisks => {
+                            screens::physical_disks::open(app);
+                        }
+                        MenuAction::MigrateVm => {
+                            screens::host_migration::open(app);
+                        }
+                        MenuAction::LaunchEnvironment => {
+                            screens::launch_env::open(app);
+                        }
+                        MenuAction::QemuBinary => {
+                            screens::qemu_binary::open(app);
+                        }
+                        MenuAction::InputPassthrough => {
+                            screens::input_passthrough::open(app)?;
+                        }
+                        MenuAction::GameControllers => {
+                            screens::game_controllers::open(app)?;
+                        }
+                        MenuAction::Autostart => {
+                            screens::systemd_unit::open(app);
+                        }
+                        MenuAction::PluginAction(index) => {
+                            if let Some(action) = crate::plugins::actions().nth(index) {
+                                let vm = vm.clone();
+                                let pid = app.running_vms.get(&vm.id).copied();
+                                let library = app.config.vm_library_path.clone();
+                                let tx = app.background_tx.clone();
+                                app.set_status(format!("Running {}...", action.name));
+                                std::thread::spawn(move || {
+                                    let result = crate::plugins::run_action(action, &vm, pid, &library);
+                                    let _ = tx.send(BackgroundResult::PluginActionFinished {
+                                        result: result.map_err(|e| format!("{:#}", e)),
+                                    });
+                                });
+                            }
+                        }
+                        MenuAction::CompactDisk => {
+                            app.push_screen(Screen::Confirm(ConfirmAction::CompactDisk));
+                        }
                     }
                 }
             }
@@ -748,8 +1533,33 @@ fn handle_configuration(app: &mut App, key: KeyEvent) -> Result<()> {
     match key.code {
         KeyCode::Esc => app.pop_screen(),
         KeyCode::Char('r') | KeyCode::Char('R') => {
+            if app.kiosk_blocks() {
+                return Ok(());
+            }
             app.push_screen(Screen::RawScript);
         }
+        // Step the guest resolution, rewriting the EDID hint in launch.sh
+        KeyCode::Left | KeyCode::Right => {
+            if app.kiosk_blocks() {
+                return Ok(());
+            }
+            let Some(vm) = app.selected_vm().cloned() else {
+                return Ok(());
+            };
+            let mut preset = crate::vm::display_preset::DisplayPreset::from_config(&vm.config);
+            preset.cycle(0, if key.code == KeyCode::Left { -1 } else { 1 });
+            match crate::vm::display_preset::set_display_preset(&vm.launch_script, &preset) {
+                Ok(()) => {
+                    if app.running_vms.contains_key(&vm.id) {
+                        app.set_status(format!("Resolution set to {} (applies on next start)", preset.resolution));
+                    } else {
+                        app.set_status(format!("Resolution set to {}", preset.resolution));
+                    }
+                    let _ = app.refresh_vms();
+                }
+                Err(e) => app.set_status(format!("Failed to set resolution: {}", e)),
+            }
+        }
         _ => {}
     }
     Ok(())
@@ -785,7 +1595,7 @@ fn handle_raw_script(app: &mut App, key: KeyEvent) -> Result<()> {
                 app.script_editor_cursor.0 -= 1;
                 // Adjust column if new line is shorter
                 let line_len = app.script_editor_lines.get(app.script_editor_cursor.0)
-                    .map(|l| l.len()).unwrap_or(0);
+                    .map(|l| l.chars().count()).unwrap_or(0);
                 if app.script_editor_cursor.1 > line_len {
                     app.script_editor_cursor.1 = line_len;
                 }
@@ -800,7 +1610,7 @@ fn handle_raw_script(app: &mut App, key: KeyEvent) -> Result<()> {
                 app.script_editor_cursor.0 += 1;
                 // Adjust column if new line is shorter
                 let line_len = app.script_editor_lines.get(app.script_editor_cursor.0)
-                    .map(|l| l.len()).unwrap_or(0);
+                    .map(|l| l.chars().count()).unwrap_or(0);
                 if app.script_editor_cursor.1 > line_len {
                     app.script_editor_cursor.1 = line_len;
                 }
@@ -818,7 +1628,7 @@ fn handle_raw_script(app: &mut App, key: KeyEvent) -> Result<()> {
                 // Move to end of previous line
                 app.script_editor_cursor.0 -= 1;
                 app.script_editor_cursor.1 = app.script_editor_lines.get(app.script_editor_cursor.0)
-                    .map(|l| l.len()).unwrap_or(0);
+                    .map(|l| l.chars().count()).unwrap_or(0);
             }
             // Adjust horizontal scroll
             if app.script_editor_cursor.1 < app.script_editor_h_scroll {
@@ -827,7 +1637,7 @@ fn handle_raw_script(app: &mut App, key: KeyEvent) -> Result<()> {
         }
         (KeyCode::Right, _) => {
             let line_len = app.script_editor_lines.get(app.script_editor_cursor.0)
-                .map(|l| l.len()).unwrap_or(0);
+                .map(|l| l.chars().count()).unwrap_or(0);
             if app.script_editor_cursor.1 < line_len {
                 app.script_editor_cursor.1 += 1;
             } else if app.script_editor_cursor.0 < total_lines.saturating_sub(1) {
@@ -847,7 +1657,7 @@ fn handle_raw_script(app: &mut App, key: KeyEvent) -> Result<()> {
         }
         (KeyCode::End, _) => {
             let line_len = app.script_editor_lines.get(app.script_editor_cursor.0)
-                .map(|l| l.len()).unwrap_or(0);
+                .map(|l| l.chars().count()).unwrap_or(0);
             app.script_editor_cursor.1 = line_len;
         }
         (KeyCode::PageUp, _) => {
@@ -856,7 +1666,7 @@ fn handle_raw_script(app: &mut App, key: KeyEvent) -> Result<()> {
             app.raw_script_scroll = app.raw_script_scroll.saturating_sub(jump as u16);
             // Adjust column
             let line_len = app.script_editor_lines.get(app.script_editor_cursor.0)
-                .map(|l| l.len()).unwrap_or(0);
+                .map(|l| l.chars().count()).unwrap_or(0);
             if app.script_editor_cursor.1 > line_len {
                 app.script_editor_cursor.1 = line_len;
             }
@@ -867,7 +1677,7 @@ fn handle_raw_script(app: &mut App, key: KeyEvent) -> Result<()> {
             app.raw_script_scroll = (app.raw_script_scroll + jump as u16).min(total_lines.saturating_sub(1) as u16);
             // Adjust column
             let line_len = app.script_editor_lines.get(app.script_editor_cursor.0)
-                .map(|l| l.len()).unwrap_or(0);
+                .map(|l| l.chars().count()).unwrap_or(0);
             if app.script_editor_cursor.1 > line_len {
                 app.script_editor_cursor.1 = line_len;
             }
@@ -877,8 +1687,9 @@ fn handle_raw_script(app: &mut App, key: KeyEvent) -> Result<()> {
         (KeyCode::Enter, _) => {
             let (line_idx, col) = app.script_editor_cursor;
             if let Some(line) = app.script_editor_lines.get_mut(line_idx) {
-                let remainder = line[col..].to_string();
-                line.truncate(col);
+                let at = byte_index(line, col);
+                let remainder = line[at..].to_string();
+                line.truncate(at);
                 app.script_editor_lines.insert(line_idx + 1, remainder);
                 app.script_editor_cursor = (line_idx + 1, 0);
                 app.script_editor_modified = true;
@@ -890,7 +1701,7 @@ fn handle_raw_script(app: &mut App, key: KeyEvent) -> Result<()> {
             let (line_idx, col) = app.script_editor_cursor;
             if col > 0 {
                 if let Some(line) = app.script_editor_lines.get_mut(line_idx) {
-                    line.remove(col - 1);
+                    line.remove(byte_index(line, col - 1));
                     app.script_editor_cursor.1 -= 1;
                     app.script_editor_modified = true;
                 }
@@ -898,7 +1709,7 @@ fn handle_raw_script(app: &mut App, key: KeyEvent) -> Result<()> {
                 // Join with previous line
                 let current_line = app.script_editor_lines.remove(line_idx);
                 if let Some(prev_line) = app.script_editor_lines.get_mut(line_idx - 1) {
-                    let prev_len = prev_line.len();
+                    let prev_len = prev_line.chars().count();
                     prev_line.push_str(&current_line);
                     app.script_editor_cursor = (line_idx - 1, prev_len);
                     app.script_editor_modified = true;
@@ -910,8 +1721,8 @@ fn handle_raw_script(app: &mut App, key: KeyEvent) -> Result<()> {
         (KeyCode::Delete, _) => {
             let (line_idx, col) = app.script_editor_cursor;
             if let Some(line) = app.script_editor_lines.get_mut(line_idx) {
-                if col < line.len() {
-                    line.remove(col);
+                if col < line.chars().count() {
+                    line.remove(byte_index(line, col));
                     app.script_editor_modified = true;
                 } else if line_idx < total_lines - 1 {
                     // Join with next line
@@ -926,7 +1737,7 @@ fn handle_raw_script(app: &mut App, key: KeyEvent) -> Result<()> {
         (KeyCode::Tab, _) => {
             let (line_idx, col) = app.script_editor_cursor;
             if let Some(line) = app.script_editor_lines.get_mut(line_idx) {
-                line.insert_str(col, "    ");
+                line.insert_str(byte_index(line, col), "    ");
                 app.script_editor_cursor.1 += 4;
                 app.script_editor_modified = true;
             }
@@ -936,7 +1747,7 @@ fn handle_raw_script(app: &mut App, key: KeyEvent) -> Result<()> {
         (KeyCode::Char(c), m) if !m.contains(KeyModifiers::CONTROL) => {
             let (line_idx, col) = app.script_editor_cursor;
             if let Some(line) = app.script_editor_lines.get_mut(line_idx) {
-                line.insert(col, c);
+                line.insert(byte_index(line, col), c);
                 app.script_editor_cursor.1 += 1;
                 app.script_editor_modified = true;
             }
@@ -947,14 +1758,62 @@ fn handle_raw_script(app: &mut App, key: KeyEvent) -> Result<()> {
     Ok(())
 }
 
+/// Byte offset of the editor column `col` (a character count) in `line`
+fn byte_index(line: &str, col: usize) -> usize {
+    line.char_indices().nth(col).map(|(i, _)| i).unwrap_or(line.len())
+}
+
 fn handle_detailed_info(app: &mut App, key: KeyEvent) -> Result<()> {
+    // Notes live in the VM folder, which remote VMs don't have here
+    if matches!(key.code, KeyCode::Char('n' | 'N' | 'a' | 'A')) && (app.notes.is_none() || app.kiosk_blocks()) {
+        return Ok(());
+    }
+
     match key.code {
-        KeyCode::Esc => app.pop_screen(),
+        KeyCode::Esc => {
+            app.notes = None;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down => app.details_scroll = app.details_scroll.saturating_add(1),
+        KeyCode::Char('k') | KeyCode::Up => app.details_scroll = app.details_scroll.saturating_sub(1),
+        KeyCode::PageDown => app.details_scroll = app.details_scroll.saturating_add(10),
+        KeyCode::PageUp => app.details_scroll = app.details_scroll.saturating_sub(10),
+        KeyCode::Char('n') | KeyCode::Char('N') => {
+            app.load_notes_into_editor();
+            app.push_screen(Screen::Notes);
+        }
+        KeyCode::Char('a') | KeyCode::Char('A') => {
+            app.text_input_buffer.clear();
+            app.push_screen(Screen::TextInput(TextInputContext::JournalEntry));
+        }
         _ => {}
     }
     Ok(())
 }
 
+fn handle_notes(app: &mut App, key: KeyEvent) -> Result<()> {
+    match (key.code, key.modifiers) {
+        (KeyCode::Char('s'), m) if m.contains(KeyModifiers::CONTROL) => {
+            match app.save_notes_from_editor() {
+                Ok(()) => app.set_status("Notes saved"),
+                Err(e) => app.set_status(format!("Error saving notes: {}", e)),
+            }
+        }
+        (KeyCode::Esc, _) => {
+            if app.script_editor_modified {
+                app.push_screen(Screen::Confirm(ConfirmAction::DiscardScriptChanges));
+            } else {
+                app.raw_script_scroll = 0;
+                app.script_editor_lines.clear();
+                app.pop_screen();
+            }
+        }
+        // Everything else edits like the launch.sh editor
+        _ => handle_raw_script(app, key)?,
+    }
+    Ok(())
+}
+
 fn handle_snapshots(app: &mut App, key: KeyEvent) -> Result<()> {
     match key.code {
         KeyCode::Esc => {
@@ -972,15 +1831,13 @@ fn handle_snapshots(app: &mut App, key: KeyEvent) -> Result<()> {
             }
         }
         KeyCode::Char('c') => {
-            // Create snapshot - open text input for name
+            // Create snapshot - ask for name, description and context
             if let Some(vm) = app.selected_vm() {
                 // Warn if VM is running - snapshot operations on running VMs can cause corruption
                 if app.running_vms.contains_key(&vm.id) {
                     app.set_status("Warning: VM is running. Snapshot may be inconsistent.");
                 }
-                // Pre-fill with timestamp-based suggestion
-                app.text_input_buffer = format!("snapshot-{}", chrono::Local::now().format("%Y%m%d-%H%M%S"));
-                app.push_screen(Screen::TextInput(TextInputContext::SnapshotName));
+                screens::create_snapshot::open(app);
             }
         }
         KeyCode::Char('r') => {
@@ -1001,34 +1858,48 @@ fn handle_snapshots(app: &mut App, key: KeyEvent) -> Result<()> {
 fn handle_boot_options(app: &mut App, key: KeyEvent) -> Result<()> {
     use crate::app::FileBrowserMode;
 
+    let items = screens::management::boot_option_items(app);
+    let item_count = items.len();
     match key.code {
         KeyCode::Esc => app.pop_screen(),
-        KeyCode::Char('j') | KeyCode::Down => app.menu_next(3),
+        KeyCode::Char('j') | KeyCode::Down => app.menu_next(item_count),
         KeyCode::Char('k') | KeyCode::Up => app.menu_prev(),
-        KeyCode::Enter | KeyCode::Char('1') | KeyCode::Char('2') | KeyCode::Char('3') => {
+        KeyCode::Enter | KeyCode::Char('1'..='6') => {
             let item = match key.code {
-                KeyCode::Char('1') => 0,
-                KeyCode::Char('2') => 1,
-                KeyCode::Char('3') => 2,
+                KeyCode::Char(c) => c as usize - '1' as usize,
                 _ => app.selected_menu_item,
             };
-
-            match item {
-                0 => {
+            match items.get(item).map(|(name, _)| *name) {
+                Some(screens::management::RESUME_BOOT) => {
+                    app.boot_mode = BootMode::Resume;
+                    app.pop_screen();
+                    app.confirm_boot();
+                }
+                Some(screens::management::NORMAL_BOOT) => {
                     app.boot_mode = BootMode::Normal;
                     app.pop_screen();
-                    app.push_screen(Screen::Confirm(ConfirmAction::LaunchVm));
+                    app.confirm_boot();
                 }
-                1 => {
+                Some(screens::management::INSTALL_BOOT) => {
                     app.boot_mode = BootMode::Install;
                     app.pop_screen();
-                    app.push_screen(Screen::Confirm(ConfirmAction::LaunchVm));
+                    app.confirm_boot();
                 }
-                2 => {
+                Some(screens::management::CUSTOM_ISO_BOOT) => {
                     // Open file browser for ISO selection
                     app.load_file_browser(FileBrowserMode::Iso);
                     app.push_screen(Screen::FileBrowser);
                 }
+                Some(screens::management::LAUNCH_WITH_OPTIONS) => screens::launch_options::open(app),
+                Some(screens::management::INSTALL_COMPLETE) => {
+                    if let Some(vm) = app.selected_vm().cloned() {
+                        match crate::vm::first_boot::mark_installed(&vm) {
+                            Ok(()) => app.finish_install(&vm.id),
+                            Err(e) => app.set_status(format!("Error: {}", e)),
+                        }
+                    }
+                    app.pop_screen();
+                }
                 _ => {}
             }
         }
@@ -1060,17 +1931,33 @@ fn handle_display_options(app: &mut App, key: KeyEvent) -> Result<()> {
             if let Some((display_name, _)) = display_options.get(item) {
                 let display_name = display_name.clone();
                 // Update the display setting in launch.sh
-                if let Some(vm) = app.selected_vm() {
-                    match update_vm_display(&vm.launch_script, &display_name) {
-                        Ok(()) => {
-                            // Show spice-app warning if viewer not installed
-                            if display_name.contains("spice") && !crate::commands::qemu_system::is_spice_viewer_available() {
+                if let Some(vm) = app.selected_vm().cloned() {
+                    let used_vnc: Vec<u16> = app.vms.iter()
+                        .filter(|other| other.id != vm.id)
+                        .filter_map(|other| other.config.vnc_display)
+                        .collect();
+                    match update_vm_display_and_sections(&vm, &display_name, &used_vnc) {
+                        Ok(Some(vnc_display)) => {
+                            app.set_status(format!(
+                                "Display changed to vnc ({})",
+                                crate::vm::vnc::address(vnc_display)
+                            ));
+                            let _ = app.refresh_vms();
+                        }
+                        Ok(None) => {
+                            // Show SPICE warning if no viewer is installed
+                            let viewer_missing = match display_name.as_str() {
+                                "spice" => crate::commands::qemu_system::find_spice_viewer().is_none(),
+                                "spice-app" => !crate::commands::qemu_system::is_spice_viewer_available(),
+                                _ => false,
+                            };
+                            if viewer_missing {
                                 app.set_status(format!("Display changed to {}. Warning: virt-viewer/remote-viewer not found!", display_name));
                             } else {
                                 app.set_status(format!("Display changed to {}", display_name));
                             }
-                            // Reload the script to reflect changes
-                            app.reload_selected_vm_script();
+                            // Re-parse so the SPICE settings are picked up
+                            let _ = app.refresh_vms();
                         }
                         Err(e) => {
                             app.set_status(format!("Failed to change display: {}", e));
@@ -1081,33 +1968,489 @@ fn handle_display_options(app: &mut App, key: KeyEvent) -> Result<()> {
                 app.pop_screen();
             }
         }
+        KeyCode::Char('c') | KeyCode::Char('u') => {
+            if let Some(vm) = app.selected_vm().cloned() {
+                if let Some(mut spice) = vm.config.spice {
+                    let (label, enabled) = if key.code == KeyCode::Char('c') {
+                        spice.clipboard = !spice.clipboard;
+                        ("Clipboard sharing", spice.clipboard)
+                    } else {
+                        spice.usb_redir = !spice.usb_redir;
+                        ("USB redirection", spice.usb_redir)
+                    };
+                    match crate::vm::spice::set_spice(&vm.launch_script, Some(&spice)) {
+                        Ok(()) => {
+                            app.set_status(format!("{} {}", label, if enabled { "enabled" } else { "disabled" }));
+                            let _ = app.refresh_vms();
+                        }
+                        Err(e) => app.set_status(format!("Failed to update SPICE settings: {}", e)),
+                    }
+                }
+            }
+        }
         _ => {}
     }
     Ok(())
 }
 
-/// Update the display setting in a VM's launch script
-fn update_vm_display(script_path: &std::path::Path, new_display: &str) -> Result<()> {
-    let content = std::fs::read_to_string(script_path)?;
+/// Switch a VM's display, keeping its SPICE and VNC sections in step
+///
+/// "spice" is vm-curator's server mode: QEMU runs headless with a SPICE
+/// server and a viewer is opened after launch. Existing channel toggles
+/// are kept when switching between the two SPICE modes. "vnc" also runs
+/// headless, with a VNC server on a display number not in `used_vnc`
+/// (kept if the VM already has one). Returns the VNC display, if any.
+fn update_vm_display_and_sections(
+    vm: &crate::vm::DiscoveredVm,
+    display_name: &str,
+    used_vnc: &[u16],
+) -> Result<Option<u16>> {
+    use crate::vm::spice::{set_spice, SpiceConfig, SpiceMode};
+
+    let mode = match display_name {
+        "spice" => Some(SpiceMode::Server),
+        "spice-app" => Some(SpiceMode::App),
+        _ => None,
+    };
+    let vnc_display = match (display_name, vm.config.vnc_display) {
+        ("vnc", Some(existing)) => Some(existing),
+        ("vnc", None) => Some(crate::vm::vnc::allocate_display(used_vnc)?),
+        _ => None,
+    };
+
+    let qemu_display = if mode == Some(SpiceMode::Server) || vnc_display.is_some() {
+        // Remote viewers keep an EGL headless renderer if the VM has one
+        if vm.config.gpu_accel.as_deref() == Some("egl-headless") {
+            "egl-headless"
+        } else {
+            "none"
+        }
+    } else {
+        display_name
+    };
+    update_vm_display(&vm.launch_script, qemu_display)?;
+
+    let spice = mode.map(|mode| match vm.config.spice {
+        Some(existing) => SpiceConfig { mode, ..existing },
+        None => SpiceConfig::new(mode),
+    });
+    set_spice(&vm.launch_script, spice.as_ref())?;
+    crate::vm::vnc::set_vnc(&vm.launch_script, vnc_display)?;
+    Ok(vnc_display)
+}
+
+fn handle_cpu_model(app: &mut App, key: KeyEvent) -> Result<()> {
+    use crate::vm::cpu_model::CPU_FLAGS;
 
-    // Regex to match -display with optional gl=on suffix
-    // Uses [\w-]+ to match hyphenated backends like spice-app
-    let display_re = Regex::new(r"-display\s+([\w-]+)(,gl=on)?")?;
+    let Some(ref mut state) = app.cpu_model_state else {
+        app.pop_screen();
+        return Ok(());
+    };
 
-    let new_content = if display_re.is_match(&content) {
-        // Replace existing -display setting, preserving gl=on if present
-        display_re.replace_all(&content, |caps: &regex::Captures| {
-            if caps.get(2).is_some() {
-                format!("-display {},gl=on", new_display)
+    match key.code {
+        KeyCode::Esc => {
+            app.cpu_model_state = None;
+            app.pop_screen();
+        }
+        KeyCode::Tab | KeyCode::BackTab => state.editing_flags = !state.editing_flags,
+        KeyCode::Char('j') | KeyCode::Down => {
+            if state.editing_flags {
+                state.flag_selected = (state.flag_selected + 1).min(CPU_FLAGS.len() - 1);
             } else {
-                format!("-display {}", new_display)
+                state.model_selected = (state.model_selected + 1).min(state.models.len().saturating_sub(1));
             }
-        }).to_string()
-    } else {
-        // No -display found, this shouldn't happen for wizard-generated scripts
-        // but handle gracefully
-        content
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            if state.editing_flags {
+                state.flag_selected = state.flag_selected.saturating_sub(1);
+            } else {
+                state.model_selected = state.model_selected.saturating_sub(1);
+            }
+        }
+        KeyCode::PageDown if !state.editing_flags => {
+            state.model_selected = (state.model_selected + 10).min(state.models.len().saturating_sub(1));
+        }
+        KeyCode::PageUp if !state.editing_flags => {
+            state.model_selected = state.model_selected.saturating_sub(10);
+        }
+        KeyCode::Char(' ') if state.editing_flags => {
+            state.toggle_flag(CPU_FLAGS[state.flag_selected].token);
+        }
+        KeyCode::Enter => {
+            let spec = state.spec();
+            if let Some(vm) = app.selected_vm().cloned() {
+                match crate::vm::cpu_model::set_cpu_model(&vm.launch_script, spec.as_ref()) {
+                    Ok(()) => {
+                        let name = spec.as_ref().map(|s| s.to_arg()).unwrap_or_else(|| "QEMU default".to_string());
+                        if app.running_vms.contains_key(&vm.id) {
+                            app.set_status(format!("CPU set to {} (applies on next start)", name));
+                        } else {
+                            app.set_status(format!("CPU set to {}", name));
+                        }
+                        let _ = app.refresh_vms();
+                    }
+                    Err(e) => app.set_status(format!("Failed to set CPU model: {}", e)),
+                }
+            }
+            app.cpu_model_state = None;
+            app.pop_screen();
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_cpu_era(app: &mut App, key: KeyEvent) -> Result<()> {
+    use crate::vm::cpu_era::CPU_ERA_PRESETS;
+
+    // "Host CPU" plus one entry per preset
+    let option_count = CPU_ERA_PRESETS.len() + 1;
+
+    match key.code {
+        KeyCode::Esc => {
+            app.selected_menu_item = 0;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down => app.menu_next(option_count),
+        KeyCode::Char('k') | KeyCode::Up => app.menu_prev(),
+        KeyCode::Enter => {
+            let preset = app.selected_menu_item.checked_sub(1).and_then(|i| CPU_ERA_PRESETS.get(i));
+            if let Some(vm) = app.selected_vm().cloned() {
+                match crate::vm::cpu_era::set_cpu_era(&vm, preset) {
+                    Ok(()) => {
+                        let name = preset.map(|p| p.name).unwrap_or("Host CPU");
+                        if app.running_vms.contains_key(&vm.id) {
+                            app.set_status(format!("CPU era set to {} (applies on next start)", name));
+                        } else {
+                            app.set_status(format!("CPU era set to {}", name));
+                        }
+                        let _ = app.refresh_vms();
+                    }
+                    Err(e) => app.set_status(format!("Failed to set CPU era: {}", e)),
+                }
+            }
+            app.selected_menu_item = 0;
+            app.pop_screen();
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_audio_backend(app: &mut App, key: KeyEvent) -> Result<()> {
+    let options = app.vm_audio_backend_options();
+
+    match key.code {
+        KeyCode::Esc => {
+            app.selected_menu_item = 0;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down => app.menu_next(options.len()),
+        KeyCode::Char('k') | KeyCode::Up => app.menu_prev(),
+        KeyCode::Enter => {
+            if let (Some(vm), Some(backend)) = (app.selected_vm().cloned(), options.get(app.selected_menu_item)) {
+                match crate::vm::audio::set_audio_backend(&vm, backend) {
+                    Ok(()) => {
+                        let name = crate::hardware::audio::backend_label(backend);
+                        if app.running_vms.contains_key(&vm.id) {
+                            app.set_status(format!("Audio output set to {} (applies on next start)", name));
+                        } else {
+                            app.set_status(format!("Audio output set to {}", name));
+                        }
+                        let _ = app.refresh_vms();
+                    }
+                    Err(e) => app.set_status(format!("Failed to set audio output: {}", e)),
+                }
+            }
+            app.selected_menu_item = 0;
+            app.pop_screen();
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_sound_blaster(app: &mut App, key: KeyEvent) -> Result<()> {
+    use crate::vm::sound_blaster::SB16_FIELDS;
+
+    match key.code {
+        KeyCode::Esc => {
+            app.sb16_edit = None;
+            app.selected_menu_item = 0;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down => app.menu_next(SB16_FIELDS.len()),
+        KeyCode::Char('k') | KeyCode::Up => app.menu_prev(),
+        KeyCode::Left | KeyCode::Right | KeyCode::Char('h') | KeyCode::Char('l') => {
+            let delta = if matches!(key.code, KeyCode::Right | KeyCode::Char('l')) { 1 } else { -1 };
+            let field = app.selected_menu_item;
+            if let Some(ref mut settings) = app.sb16_edit {
+                settings.cycle(field, delta);
+            }
+        }
+        KeyCode::Enter => {
+            if let (Some(vm), Some(settings)) = (app.selected_vm().cloned(), app.sb16_edit.take()) {
+                match crate::vm::sound_blaster::set_sb16(&vm.launch_script, &settings) {
+                    Ok(()) => {
+                        let blaster = settings.blaster();
+                        if app.running_vms.contains_key(&vm.id) {
+                            app.set_status(format!("Sound Blaster set to {} (applies on next start)", blaster));
+                        } else {
+                            app.set_status(format!("Sound Blaster set to {}", blaster));
+                        }
+                        let _ = app.refresh_vms();
+                    }
+                    Err(e) => app.set_status(format!("Failed to update Sound Blaster: {}", e)),
+                }
+            }
+            app.selected_menu_item = 0;
+            app.pop_screen();
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_display_preset(app: &mut App, key: KeyEvent) -> Result<()> {
+    use crate::vm::display_preset::PRESET_FIELDS;
+
+    match key.code {
+        KeyCode::Esc => {
+            app.display_preset_edit = None;
+            app.selected_menu_item = 0;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down => app.menu_next(PRESET_FIELDS.len()),
+        KeyCode::Char('k') | KeyCode::Up => app.menu_prev(),
+        KeyCode::Left | KeyCode::Right | KeyCode::Char('h') | KeyCode::Char('l') | KeyCode::Char(' ') => {
+            let delta = if matches!(key.code, KeyCode::Left | KeyCode::Char('h')) { -1 } else { 1 };
+            let field = app.selected_menu_item;
+            if let Some(ref mut preset) = app.display_preset_edit {
+                preset.cycle(field, delta);
+            }
+        }
+        KeyCode::Enter => {
+            if let (Some(vm), Some(preset)) = (app.selected_vm().cloned(), app.display_preset_edit.take()) {
+                match crate::vm::display_preset::set_display_preset(&vm.launch_script, &preset) {
+                    Ok(()) => {
+                        let summary = format!("{} at {}", preset.resolution, preset.summary());
+                        if app.running_vms.contains_key(&vm.id) {
+                            app.set_status(format!("Display set to {} (applies on next start)", summary));
+                        } else {
+                            app.set_status(format!("Display set to {}", summary));
+                        }
+                        let _ = app.refresh_vms();
+                    }
+                    Err(e) => app.set_status(format!("Failed to update display: {}", e)),
+                }
+            }
+            app.selected_menu_item = 0;
+            app.pop_screen();
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_gpu_accel(app: &mut App, key: KeyEvent) -> Result<()> {
+    use crate::vm::gpu_accel::GPU_ACCEL_MODES;
+
+    match key.code {
+        KeyCode::Esc => {
+            app.selected_menu_item = 0;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down => app.menu_next(GPU_ACCEL_MODES.len()),
+        KeyCode::Char('k') | KeyCode::Up => app.menu_prev(),
+        KeyCode::Enter => {
+            if let (Some(vm), Some(mode)) = (app.selected_vm().cloned(), GPU_ACCEL_MODES.get(app.selected_menu_item)) {
+                match crate::vm::gpu_accel::set_gpu_accel(&vm, mode.id) {
+                    Ok(()) => {
+                        if app.running_vms.contains_key(&vm.id) {
+                            app.set_status(format!("GPU acceleration set to {} (applies on next start)", mode.name));
+                        } else {
+                            app.set_status(format!("GPU acceleration set to {}", mode.name));
+                        }
+                        let _ = app.refresh_vms();
+                    }
+                    Err(e) => app.set_status(format!("Failed to set GPU acceleration: {}", e)),
+                }
+            }
+            app.selected_menu_item = 0;
+            app.pop_screen();
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_machine_type(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(vm) = app.selected_vm().cloned() else {
+        app.pop_screen();
+        return Ok(());
     };
+    let options = crate::vm::machine::machine_options(vm.config.emulator.command(), vm.config.machine.as_deref());
+
+    match key.code {
+        KeyCode::Esc => {
+            app.selected_menu_item = 0;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down => app.menu_next(options.len()),
+        KeyCode::Char('k') | KeyCode::Up => app.menu_prev(),
+        KeyCode::Enter => {
+            if let Some(machine) = options.get(app.selected_menu_item) {
+                match crate::vm::machine::set_machine(&vm.launch_script, machine) {
+                    Ok(()) => {
+                        if app.running_vms.contains_key(&vm.id) {
+                            app.set_status(format!("Machine type set to {} (applies on next start)", machine));
+                        } else {
+                            app.set_status(format!("Machine type set to {}", machine));
+                        }
+                        let _ = app.refresh_vms();
+                    }
+                    Err(e) => app.set_status(format!("Failed to set machine type: {}", e)),
+                }
+            }
+            app.selected_menu_item = 0;
+            app.pop_screen();
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_disk_io(app: &mut App, key: KeyEvent) -> Result<()> {
+    use crate::vm::disk_io::TUNING_FIELDS;
+
+    match key.code {
+        KeyCode::Esc => {
+            app.disk_io_state = None;
+            app.selected_menu_item = 0;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down => app.menu_next(TUNING_FIELDS.len()),
+        KeyCode::Char('k') | KeyCode::Up => app.menu_prev(),
+        KeyCode::Tab => {
+            if let Some(ref mut state) = app.disk_io_state {
+                state.drive_selected = (state.drive_selected + 1) % state.drives.len().max(1);
+            }
+        }
+        KeyCode::Left | KeyCode::Right | KeyCode::Char('h') | KeyCode::Char('l') | KeyCode::Char(' ') => {
+            let delta = if matches!(key.code, KeyCode::Left | KeyCode::Char('h')) { -1 } else { 1 };
+            let field = app.selected_menu_item;
+            if let Some(ref mut state) = app.disk_io_state {
+                if let Some(drive) = state.drives.get_mut(state.drive_selected) {
+                    drive.tuning.cycle(field, delta);
+                }
+            }
+        }
+        KeyCode::Enter => {
+            let Some(vm) = app.selected_vm().cloned() else {
+                return Ok(());
+            };
+            let Some(state) = app.disk_io_state.as_ref() else {
+                return Ok(());
+            };
+            let tunings: Vec<_> = state.drives.iter().map(|d| d.tuning.clone()).collect();
+            match crate::vm::disk_io::set_disk_tuning(&vm.launch_script, &tunings) {
+                Ok(()) => {
+                    if app.running_vms.contains_key(&vm.id) {
+                        app.set_status("Disk I/O settings saved (applies on next start)");
+                    } else {
+                        app.set_status("Disk I/O settings saved");
+                    }
+                    let _ = app.refresh_vms();
+                    app.disk_io_state = None;
+                    app.selected_menu_item = 0;
+                    app.pop_screen();
+                }
+                // Stay on the editor so the conflicting setting can be fixed
+                Err(e) => app.set_status(format!("Failed to save disk settings: {}", e)),
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_identity(app: &mut App, key: KeyEvent) -> Result<()> {
+    match key.code {
+        KeyCode::Esc | KeyCode::Enter => app.pop_screen(),
+        KeyCode::Char('a') | KeyCode::Char('r') => {
+            let Some(vm) = app.selected_vm().cloned() else {
+                return Ok(());
+            };
+            let result = match (key.code, vm.config.identity.is_some()) {
+                (KeyCode::Char('a'), false) => crate::vm::identity::assign_identity(&vm),
+                (KeyCode::Char('r'), true) => crate::vm::identity::regenerate_identity(&vm),
+                _ => return Ok(()),
+            };
+            match result {
+                Ok(identity) => {
+                    let summary = format!("UUID {}, MAC {}", identity.uuid, identity.mac);
+                    if app.running_vms.contains_key(&vm.id) {
+                        app.set_status(format!("Identity set: {} (applies on next start)", summary));
+                    } else {
+                        app.set_status(format!("Identity set: {}", summary));
+                    }
+                    let _ = app.refresh_vms();
+                }
+                Err(e) => app.set_status(format!("Failed to update identity: {}", e)),
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_midi_output(app: &mut App, key: KeyEvent) -> Result<()> {
+    use crate::vm::midi::MIDI_SYNTHS;
+
+    // "Off" plus one entry per synth
+    let option_count = MIDI_SYNTHS.len() + 1;
+
+    match key.code {
+        KeyCode::Esc => {
+            app.selected_menu_item = 0;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down => app.menu_next(option_count),
+        KeyCode::Char('k') | KeyCode::Up => app.menu_prev(),
+        KeyCode::Enter => {
+            let synth = app.selected_menu_item.checked_sub(1).and_then(|i| MIDI_SYNTHS.get(i));
+            if let Some(vm) = app.selected_vm().cloned() {
+                match crate::vm::midi::set_midi(&vm.launch_script, synth) {
+                    Ok(()) => {
+                        let name = synth.map(|s| s.name).unwrap_or("off");
+                        if app.running_vms.contains_key(&vm.id) {
+                            app.set_status(format!("MIDI output set to {} (applies on next start)", name));
+                        } else {
+                            app.set_status(format!("MIDI output set to {}", name));
+                        }
+                        let _ = app.refresh_vms();
+                    }
+                    Err(e) => app.set_status(format!("Failed to set MIDI output: {}", e)),
+                }
+            }
+            app.selected_menu_item = 0;
+            app.pop_screen();
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Update the display setting in a VM's launch script
+fn update_vm_display(script_path: &std::path::Path, new_display: &str) -> Result<()> {
+    use crate::vm::display_preset::{parse_display_preset, rewrite_display_options};
+
+    let content = std::fs::read_to_string(script_path)?;
+
+    // Swap the backend, keeping gl=on and re-applying the preset's GTK zoom-to-fit.
+    // Scripts without -display (not wizard-generated) are left as they are.
+    let zoom_to_fit = parse_display_preset(&content).and_then(|p| p.zoom_to_fit());
+    let new_content = rewrite_display_options(&content, Some(new_display), zoom_to_fit);
 
     std::fs::write(script_path, new_content)?;
     Ok(())
@@ -1120,7 +2463,7 @@ fn handle_usb_devices(app: &mut App, key: KeyEvent) -> Result<()> {
             app.pop_screen();
         }
         KeyCode::Char('j') | KeyCode::Down => {
-            app.selected_menu_item = (app.selected_menu_item + 1).min(app.usb_devices.len().saturating_sub(1));
+            app.selected_menu_item = (app.selected_menu_item + 1).min(app.usb_list_len().saturating_sub(1));
         }
         KeyCode::Char('k') | KeyCode::Up => {
             if app.selected_menu_item > 0 {
@@ -1129,20 +2472,22 @@ fn handle_usb_devices(app: &mut App, key: KeyEvent) -> Result<()> {
         }
         KeyCode::Char(' ') | KeyCode::Enter => {
             app.toggle_usb_device(app.selected_menu_item);
+            app.selected_menu_item = app.selected_menu_item.min(app.usb_list_len().saturating_sub(1));
+        }
+        KeyCode::Char('p') | KeyCode::Char('P') => {
+            if let Err(e) = app.toggle_usb_pin(app.selected_menu_item) {
+                app.set_status(e.to_string());
+            }
+        }
+        KeyCode::Char('h') | KeyCode::Char('H') => {
+            if let Err(e) = app.toggle_usb_auto_attach(app.selected_menu_item) {
+                app.set_status(e.to_string());
+            }
         }
         KeyCode::Char('s') | KeyCode::Char('S') => {
-            // Save USB passthrough configuration to launch.sh
+            // Save USB passthrough rules to launch.sh
             let save_result = if let Some(vm) = app.selected_vm() {
-                let devices: Vec<crate::vm::UsbPassthrough> = app
-                    .selected_usb_devices
-                    .iter()
-                    .filter_map(|&i| app.usb_devices.get(i))
-                    .map(|d| crate::vm::UsbPassthrough {
-                        vendor_id: d.vendor_id,
-                        product_id: d.product_id,
-                        usb_version: d.usb_version,
-                    })
-                    .collect();
+                let devices = app.usb_rules.clone();
 
                 let result = crate::vm::save_usb_passthrough(vm, &devices);
                 Some((result, devices.len()))
@@ -1157,7 +2502,7 @@ fn handle_usb_devices(app: &mut App, key: KeyEvent) -> Result<()> {
                         app.reload_selected_vm_script();
 
                         let mut status_msg = if count > 0 {
-                            format!("Saved {} USB device(s) to launch.sh", count)
+                            format!("Saved {} USB rule(s) to launch.sh", count)
                         } else {
                             "Cleared USB passthrough from launch.sh".to_string()
                         };
@@ -1193,6 +2538,23 @@ fn handle_usb_devices(app: &mut App, key: KeyEvent) -> Result<()> {
                 }
             }
         }
+        KeyCode::Char('a') | KeyCode::Char('A') => {
+            // Hot-plug into the selected VM while it runs
+            match app.attach_usb_to_selected_vm() {
+                Ok(msg) => app.set_status(msg),
+                Err(e) => app.set_status(format!("USB attach failed: {:#}", e)),
+            }
+        }
+        KeyCode::Char('d') | KeyCode::Char('D') => {
+            match app.detach_usb_from_vms() {
+                Ok(msg) => app.set_status(msg),
+                Err(e) => app.set_status(format!("USB detach failed: {:#}", e)),
+            }
+        }
+        KeyCode::Char('r') | KeyCode::Char('R') => {
+            app.refresh_usb_attachments();
+            app.set_status("Refreshed live USB attachments");
+        }
         KeyCode::Char('u') | KeyCode::Char('U') => {
             // Install udev rules for selected USB devices
             if app.selected_usb_devices.is_empty() {
@@ -1231,6 +2593,20 @@ fn handle_usb_devices(app: &mut App, key: KeyEvent) -> Result<()> {
 
 fn handle_confirm(app: &mut App, action: ConfirmAction, key: KeyEvent) -> Result<()> {
     match key.code {
+        // For interrupted disk operations, "no" means discard; Esc defers the decision
+        KeyCode::Char('n') if action == ConfirmAction::RecoverDiskOperations => {
+            discard_interrupted_disk_ops(app);
+        }
+        // Likewise "no" throws the wizard draft away
+        KeyCode::Char('n') if action == ConfirmAction::ResumeWizardDraft => {
+            app.pop_screen();
+            app.discard_wizard_draft();
+            app.set_status("Draft discarded");
+        }
+        KeyCode::Esc | KeyCode::Char('n') if action == ConfirmAction::LaunchVm => {
+            app.pop_screen();
+            app.launch_overrides = Default::default();
+        }
         KeyCode::Esc | KeyCode::Char('n') => app.pop_screen(),
         KeyCode::Char('y') | KeyCode::Enter => {
             execute_confirm_action(app, action)?;
@@ -1275,8 +2651,8 @@ fn render_detailed_info(app: &App, frame: &mut Frame) {
     use crate::ui::widgets::DetailedInfoWidget;
 
     let area = frame.area();
-    let dialog_width = 60.min(area.width.saturating_sub(4));
-    let dialog_height = 20.min(area.height.saturating_sub(4));
+    let dialog_width = 80.min(area.width.saturating_sub(4));
+    let dialog_height = 30.min(area.height.saturating_sub(4));
 
     let dialog_area = centered_rect(dialog_width, dialog_height, area);
     frame.render_widget(ratatui::widgets::Clear, dialog_area);
@@ -1290,6 +2666,8 @@ fn render_detailed_info(app: &App, frame: &mut Frame) {
     DetailedInfoWidget {
         os_info: os_info.as_ref(),
         vm_name: &vm_name,
+        notes: app.notes.as_ref(),
+        scroll: app.details_scroll,
     }
     .render(dialog_area, frame.buffer_mut());
 }
@@ -1302,7 +2680,73 @@ fn render_confirm(app: &App, action: &ConfirmAction, frame: &mut Frame) {
             let name = app.selected_vm()
                 .map(|vm| vm.display_name())
                 .unwrap_or_else(|| "VM".to_string());
-            ("Launch VM", format!("Launch {}?", name))
+            // Warn about devices the installed QEMU doesn't have
+            let device_warning = app.selected_vm().and_then(|vm| {
+                let issues = crate::vm::validation::validate_script(vm.config.emulator.command(), &vm.config.raw_script);
+                crate::vm::validation::summarize(&issues)
+            });
+            // and about memory and CPUs the host can't spare
+            let check = app.selected_vm().and_then(|vm| app.launch_check(vm)).filter(|check| !check.is_ok());
+            let refused = check.as_ref().is_some_and(|check| check.refused_by(app.config.launch_resource_check));
+            let resource_warning = check.map(|check| format!("{} {}", name, check.summary()));
+            let warning = match (device_warning, resource_warning) {
+                (Some(device), Some(resources)) => Some(format!("{}\n{}", resources, device)),
+                (device, resources) => resources.or(device),
+            };
+            let with_options = if app.launch_overrides.is_empty() {
+                String::new()
+            } else {
+                format!(" with {}", app.launch_overrides.qemu_args().join(" "))
+            };
+            // A normal launch of a suspended VM resumes it
+            let verb = match app.selected_vm() {
+                Some(vm) if matches!(app.boot_mode, BootMode::Normal | BootMode::Resume)
+                    && vm.remote.is_none()
+                    && crate::vm::suspend::has_saved_state(&vm.path) => "Resume",
+                _ => "Launch",
+            };
+            match warning {
+                Some(warning) if refused => (
+                    "Launch VM",
+                    format!("{}\n\nIt won't be launched (Resource Check at Launch is set to refuse).", warning),
+                ),
+                Some(warning) => ("Launch VM", format!("Warning: {}\n\n{} {}{} anyway?", warning, verb, name, with_options)),
+                None => ("Launch VM", format!("{} {}{}?", verb, name, with_options)),
+            }
+        }
+        ConfirmAction::TakeOverVm => {
+            let name = app.selected_vm()
+                .map(|vm| vm.display_name())
+                .unwrap_or_else(|| "VM".to_string());
+            let owner = app
+                .edit_lock
+                .as_ref()
+                .and_then(|l| l.held_by.as_ref())
+                .map(|o| o.describe())
+                .unwrap_or_default();
+            (
+                "VM In Use",
+                format!("{} is {}. Changes made here could clash with it. Take over its lock?", name, owner),
+            )
+        }
+        ConfirmAction::ConnectToInstance => {
+            let (name, pid) = match app.selected_vm() {
+                Some(vm) => {
+                    let pid = app.running_vms.get(&vm.id).copied().or_else(|| {
+                        crate::vm::instance_lock::find_instance(&vm.path).and_then(|instance| instance.pid)
+                    });
+                    (vm.display_name(), pid)
+                }
+                None => ("VM".to_string(), None),
+            };
+            let running = match pid {
+                Some(pid) => format!("{} is already running (PID {})", name, pid),
+                None => format!("{} is already running", name),
+            };
+            (
+                "Already Running",
+                format!("{}. A second copy would corrupt its disks. Connect to it instead?", running),
+            )
         }
         ConfirmAction::ResetVm => {
             ("Reset VM", "This will reset the VM to its initial state. All changes will be lost. Continue?".to_string())
@@ -1314,7 +2758,17 @@ fn render_confirm(app: &App, action: &ConfirmAction, frame: &mut Frame) {
             ("Delete VM", format!("Delete {}? This will move the VM to trash.", name))
         }
         ConfirmAction::RestoreSnapshot(name) => {
-            ("Restore Snapshot", format!("Restore snapshot '{}'? Current state will be lost.", name))
+            let suspended = app
+                .selected_vm()
+                .is_some_and(|vm| vm.remote.is_none() && crate::vm::suspend::has_saved_state(&vm.path));
+            if suspended {
+                (
+                    "Restore Snapshot",
+                    format!("Restore snapshot '{}'? The VM is suspended; its saved state will be discarded too.", name),
+                )
+            } else {
+                ("Restore Snapshot", format!("Restore snapshot '{}'? Current state will be lost.", name))
+            }
         }
         ConfirmAction::DeleteSnapshot(name) => {
             ("Delete Snapshot", format!("Delete snapshot '{}'? This cannot be undone.", name))
@@ -1334,6 +2788,70 @@ fn render_confirm(app: &App, action: &ConfirmAction, frame: &mut Frame) {
                 .unwrap_or_else(|| "VM".to_string());
             ("Force Stop VM", format!("Force stop {}? This may cause data loss.", name))
         }
+        ConfirmAction::SuspendVm => {
+            let name = app.selected_vm()
+                .map(|vm| vm.display_name())
+                .unwrap_or_else(|| "VM".to_string());
+            ("Suspend VM", format!("Save {} to disk and stop it? Launching it again resumes it.", name))
+        }
+        ConfirmAction::DiscardSavedState => {
+            let name = app.selected_vm()
+                .map(|vm| vm.display_name())
+                .unwrap_or_else(|| "VM".to_string());
+            ("Discard Saved State", format!("{} is suspended. Throw its saved state away and boot fresh?", name))
+        }
+        ConfirmAction::CompactDisk => {
+            let name = app.selected_vm()
+                .map(|vm| vm.display_name())
+                .unwrap_or_else(|| "VM".to_string());
+            ("Compact Disk", format!("Compact the disk of {}? This may take a while.", name))
+        }
+        ConfirmAction::BatchSnapshot => {
+            let total = app.batch_snapshot_targets().len();
+            (
+                "Batch Snapshot",
+                format!("Snapshot all {} qcow2 VM(s)? Running VMs are skipped; failures are retried.", total),
+            )
+        }
+        ConfirmAction::LeaveArchivalMode => {
+            let name = app.selected_vm()
+                .map(|vm| vm.display_name())
+                .unwrap_or_else(|| "VM".to_string());
+            ("Leave Archival Mode", format!("Unlock {}? Disk writes, networking, and the real clock will be restored.", name))
+        }
+        ConfirmAction::MigrateLibrary => {
+            let (from, to, steps) = app
+                .pending_migration
+                .as_ref()
+                .map(|m| (m.from, m.to, m.steps.join("; ")))
+                .unwrap_or_default();
+            (
+                "Upgrade VM Library",
+                format!(
+                    "Library layout {} -> {}: {}. Back up config files and upgrade now?",
+                    from, to, steps
+                ),
+            )
+        }
+        ConfirmAction::ResumeWizardDraft => (
+            "Resume Draft VM Creation",
+            format!(
+                "The Create VM wizard for {} wasn't finished. Resume it? (n = discard, Esc = later)",
+                app.wizard_draft.as_ref().map(|d| d.summary()).unwrap_or_default()
+            ),
+        ),
+        ConfirmAction::RecoverDiskOperations => {
+            let ops = &app.interrupted_disk_ops;
+            let resumable = ops.iter().filter(|op| op.can_resume()).count();
+            (
+                "Interrupted Disk Operations",
+                format!(
+                    "{} unfinished disk operation(s), {} resumable. Resume? (n = delete temp files, Esc = later)",
+                    ops.len(),
+                    resumable
+                ),
+            )
+        }
     };
 
     ConfirmDialog::new(title, &message).render(frame.area(), frame.buffer_mut());
@@ -1382,20 +2900,34 @@ fn render_usb_devices(app: &App, frame: &mut Frame) {
         .constraints([
             Constraint::Length(1),  // Top padding
             Constraint::Min(4),     // Device list
-            Constraint::Length(2),  // Help text
+            Constraint::Length(3),  // Help text
         ])
         .split(h_chunks[1]);
 
     let content_area = v_chunks[1];
     let help_area = v_chunks[2];
 
-    if app.usb_devices.is_empty() {
+    if app.usb_list_len() == 0 {
         let msg = Paragraph::new("No USB devices found.\n\nConnect a USB device and reopen this screen.")
             .style(Style::default().fg(Color::DarkGray))
             .alignment(Alignment::Center);
         frame.render_widget(msg, content_area);
     } else {
-        let items: Vec<ListItem> = app.usb_devices
+        let rule_tags = |rule: Option<usize>| -> String {
+            let Some(rule) = rule.and_then(|r| app.usb_rules.get(r)) else {
+                return String::new();
+            };
+            let mut tags = String::new();
+            if let (Some(bus), Some(port)) = (rule.host_bus, &rule.host_port) {
+                tags.push_str(&format!(" [port {}-{}]", bus, port));
+            }
+            if !rule.auto_attach {
+                tags.push_str(" [at launch only]");
+            }
+            tags
+        };
+
+        let mut items: Vec<ListItem> = app.usb_devices
             .iter()
             .enumerate()
             .map(|(i, device)| {
@@ -1409,17 +2941,43 @@ fn render_usb_devices(app: &App, frame: &mut Frame) {
                     Style::default().fg(Color::White)
                 };
 
-                ListItem::new(format!(
-                    "{} {} ({:04x}:{:04x})",
-                    checkbox,
-                    device.display_name(),
-                    device.vendor_id,
-                    device.product_id
-                ))
-                .style(style)
+                let mut spans = vec![Span::styled(
+                    format!(
+                        "{} {} ({:04x}:{:04x})",
+                        checkbox,
+                        device.display_name(),
+                        device.vendor_id,
+                        device.product_id
+                    ),
+                    style,
+                )];
+                spans.push(Span::styled(rule_tags(app.usb_rule_at(i)), Style::default().fg(Color::Cyan)));
+                if let Some((vm, _)) = app.usb_attachment(device) {
+                    spans.push(Span::styled(
+                        format!("  → {}", vm.display_name()),
+                        Style::default().fg(Color::Magenta),
+                    ));
+                }
+
+                ListItem::new(Line::from(spans))
             })
             .collect();
 
+        // Rules for devices that aren't plugged in are kept, not forgotten
+        for (offset, rule) in app.usb_absent_rules().into_iter().enumerate() {
+            let i = app.usb_devices.len() + offset;
+            let r = &app.usb_rules[rule];
+            let style = if i == app.selected_menu_item {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::DarkGray)
+            };
+            items.push(ListItem::new(Line::from(vec![
+                Span::styled(format!("[✓] {:04x}:{:04x} (not connected)", r.vendor_id, r.product_id), style),
+                Span::styled(rule_tags(Some(rule)), Style::default().fg(Color::Cyan)),
+            ])));
+        }
+
         let mut state = ListState::default();
         state.select(Some(app.selected_menu_item));
 
@@ -1429,7 +2987,11 @@ fn render_usb_devices(app: &App, frame: &mut Frame) {
     }
 
     // Help text
-    let help = Paragraph::new("[Space] Toggle  [s] Save  [u] Install USB permissions  [Esc] Back")
+    let help = Paragraph::new(
+        "[Space] Toggle  [s] Save  [u] Install USB permissions  [Esc] Back\n\
+         Rule: [p] Pin to port  [h] Attach when plugged in / at launch only\n\
+         Running VM: [a] Attach live  [d] Detach  [r] Refresh",
+    )
         .style(Style::default().fg(Color::DarkGray))
         .alignment(Alignment::Center);
     frame.render_widget(help, help_area);
@@ -1561,26 +3123,60 @@ fn handle_file_browser(app: &mut App, key: KeyEvent) -> Result<()> {
                     FileBrowserMode::Iso => {
                         // Check if we're in wizard mode
                         if app.wizard_state.is_some() {
+                            // An image checksummed in the ISO library is known without reading it again
+                            let known = crate::vm::iso_library::identify_cached(&selected_path, &app.iso_checksums);
+
                             // Set the ISO path in wizard state
+                            let mut selected_os = None;
                             if let Some(ref mut state) = app.wizard_state {
                                 state.iso_path = Some(selected_path);
+                                selected_os = state.selected_os.clone();
                             }
                             app.pop_screen(); // Close file browser
 
                             // Proceed to next step
                             let _ = app.wizard_next_step();
+
+                            if let Some(known) = known.filter(|k| selected_os.is_some_and(|os| os != k.profile)) {
+                                app.set_status(format!("This image is {} (profile {})", known.name, known.profile));
+                            }
                         } else {
                             // Normal boot mode - selected an ISO file
                             app.boot_mode = BootMode::Cdrom(selected_path);
                             app.pop_screen(); // Close file browser
                             app.pop_screen(); // Close boot options
-                            app.push_screen(Screen::Confirm(ConfirmAction::LaunchVm));
+                            app.confirm_boot();
                         }
                     }
                     FileBrowserMode::Disk => {
                         // Selected a disk file - must be in wizard mode
+                        let library_path = app.config.vm_library_path.clone();
                         if let Some(ref mut state) = app.wizard_state {
-                            state.existing_disk_path = Some(selected_path);
+                            state.existing_disk_path = Some(selected_path.clone());
+                            state.remote_disk_url = None;
+
+                            // Imports are named after the image file
+                            if state.vm_name.is_empty() {
+                                if let Some(stem) = selected_path.file_stem() {
+                                    state.vm_name = stem.to_string_lossy().to_string();
+                                    state.update_folder_name(&library_path);
+                                }
+                            }
+                            // Foreign formats default to conversion so snapshots work
+                            let is_qcow2 = selected_path
+                                .extension()
+                                .is_some_and(|e| e.eq_ignore_ascii_case("qcow2") || e.eq_ignore_ascii_case("qcow"));
+                            state.existing_disk_action = if is_qcow2 { crate::app::DiskAction::Copy } else { crate::app::DiskAction::Convert };
+                            state.detected_os = None;
+                            state.inspecting_disk = true;
+
+                            // Fingerprint the installed OS to pick the matching profile
+                            let tx = app.background_tx.clone();
+                            thread::spawn(move || {
+                                let result = crate::vm::os_fingerprint::inspect_disk(&selected_path)
+                                    .map_err(|e| e.to_string());
+                                let _ = tx.send(BackgroundResult::DiskInspected { path: selected_path, result });
+                            });
                         }
                         app.pop_screen(); // Close file browser, return to disk config step
                     }
@@ -1602,12 +3198,17 @@ fn render_text_input(app: &App, context: &TextInputContext, frame: &mut Frame) {
     use ratatui::widgets::{Block, Borders, Clear, Paragraph};
 
     let title = match context {
-        TextInputContext::SnapshotName => " Enter Snapshot Name ",
         TextInputContext::RenameVm => " Enter New VM Name ",
+        TextInputContext::ArchivalDate => " Freeze Clock At (YYYY-MM-DD) ",
+        TextInputContext::JournalEntry => " Journal Entry (e.g. Installed the SB16 driver) ",
     };
 
     let area = frame.area();
-    let dialog_width = 50.min(area.width.saturating_sub(4));
+    let dialog_width = match context {
+        TextInputContext::JournalEntry => 72,
+        _ => 50,
+    }
+    .min(area.width.saturating_sub(4));
     let dialog_height = 5;
 
     let dialog_area = centered_rect(dialog_width, dialog_height, area);
@@ -1639,25 +3240,31 @@ fn handle_text_input(app: &mut App, context: TextInputContext, key: KeyEvent) ->
             app.pop_screen();
 
             match context {
-                TextInputContext::SnapshotName => {
-                    if !input.is_empty() {
-                        if let Some(vm) = app.selected_vm() {
-                            if let Some(disk) = vm.config.primary_disk() {
-                                // Spawn background thread for snapshot creation
-                                let disk_path = disk.path.clone();
-                                let name = input.clone();
-                                let tx = app.background_tx.clone();
-                                app.loading = true;
-                                app.set_status(format!("Creating snapshot: {}...", name));
-
-                                thread::spawn(move || {
-                                    let result = crate::vm::create_snapshot(&disk_path, &name);
-                                    let _ = tx.send(BackgroundResult::SnapshotCreated {
-                                        name,
-                                        success: result.is_ok(),
-                                        error: result.err().map(|e| e.to_string()),
-                                    });
-                                });
+                TextInputContext::ArchivalDate => {
+                    if let Some(vm) = app.selected_vm().cloned() {
+                        if app.running_vms.contains_key(&vm.id) {
+                            app.set_status("Error: Cannot change archival mode while VM is running. Please shut down the VM first.");
+                        } else {
+                            match crate::vm::archival::enable_archival_mode(&vm, input.trim()) {
+                                Ok(()) => {
+                                    app.set_status(format!("{} archived at {}", vm.display_name(), input.trim()));
+                                    let _ = app.refresh_vms();
+                                }
+                                Err(e) => app.set_status(format!("Error: {}", e)),
+                            }
+                        }
+                    }
+                }
+                TextInputContext::JournalEntry => {
+                    if let Some(vm_path) = app.selected_vm().map(|vm| vm.path.clone()) {
+                        if !input.trim().is_empty() {
+                            let now = chrono::Local::now().naive_local();
+                            match crate::vm::notes::add_journal_entry(&vm_path, now, &input) {
+                                Ok(()) => {
+                                    app.notes = Some(crate::vm::notes::load(&vm_path));
+                                    app.set_status("Journal entry added");
+                                }
+                                Err(e) => app.set_status(format!("Error: {}", e)),
                             }
                         }
                     }
@@ -1686,14 +3293,12 @@ fn handle_text_input(app: &mut App, context: TextInputContext, key: KeyEvent) ->
         KeyCode::Char(c) => {
             // Allow different characters based on context
             let allowed = match context {
-                TextInputContext::SnapshotName => {
-                    // Only safe characters for snapshot names
-                    c.is_alphanumeric() || c == '-' || c == '_' || c == '.'
-                }
                 TextInputContext::RenameVm => {
                     // Allow more characters for VM display names
                     c.is_alphanumeric() || c == '-' || c == '_' || c == '.' || c == ' ' || c == '(' || c == ')'
                 }
+                TextInputContext::ArchivalDate => c.is_ascii_digit() || c == '-',
+                TextInputContext::JournalEntry => !c.is_control(),
             };
             if allowed {
                 app.text_input_buffer.push(c);
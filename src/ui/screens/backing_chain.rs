@@ -0,0 +1,255 @@
+//! Backing Chain Screen
+//!
+//! The images the selected VM's disk is layered on, from the disk down to
+//! its base, with missing and moved backing files flagged. Flatten, commit,
+//! rebase and repair each show what they do to the data and wait for `y`,
+//! then run in the background.
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    prelude::*,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+use std::thread;
+
+use crate::app::{App, BackgroundResult, BackingChainState, Screen};
+use crate::format::format_size;
+use crate::vm::backing_chain::{self, ChainAction, LinkStatus};
+
+/// Walk the selected VM's disk chain and show it
+pub fn open(app: &mut App) {
+    let Some(vm) = app.selected_vm() else {
+        return;
+    };
+    let Some(disk) = vm.config.primary_disk().map(|d| d.path.clone()) else {
+        app.set_status("This VM has no local disk image");
+        return;
+    };
+    let links = backing_chain::load(&disk, &app.config.vm_library_path);
+    app.backing_chain_state = Some(BackingChainState {
+        disk,
+        links,
+        selected: 0,
+        pending: None,
+        busy: false,
+    });
+    app.push_screen(Screen::BackingChain);
+}
+
+/// Render the backing chain dialog
+pub fn render(app: &App, frame: &mut Frame) {
+    let Some(ref state) = app.backing_chain_state else {
+        return;
+    };
+    let area = frame.area();
+
+    let dialog_width = 84.min(area.width.saturating_sub(4));
+    let dialog_height = 24.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let block = Block::default()
+        .title(format!(" Backing Chain ({} images) ", state.links.len()))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(4),    // Chain
+            Constraint::Length(7), // Details or pending change
+            Constraint::Length(1), // Help text
+        ])
+        .split(inner.inner(Margin::new(1, 0)));
+
+    let mut lines = Vec::new();
+    for (i, link) in state.links.iter().enumerate() {
+        let selected = i == state.selected;
+        let style = if selected {
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::White)
+        };
+        let branch = if i == 0 { String::new() } else { format!("{}└ ", "  ".repeat(i - 1)) };
+        let (detail, color) = match (&link.status, &link.info) {
+            (LinkStatus::Missing, _) => ("missing".to_string(), Color::Red),
+            (LinkStatus::Moved(_), _) => ("missing, found elsewhere".to_string(), Color::Magenta),
+            (LinkStatus::Present, Some(info)) => (
+                format!("{}, {} allocated", info.format, format_size(info.actual_size)),
+                Color::DarkGray,
+            ),
+            (LinkStatus::Present, None) => ("unreadable".to_string(), Color::Red),
+        };
+        lines.push(Line::from(vec![
+            Span::styled(if selected { "> " } else { "  " }, style),
+            Span::styled(format!("{}{}", branch, link.file_name()), style),
+            Span::styled(format!("  ({})", detail), Style::default().fg(color)),
+        ]));
+    }
+    if state.links.len() == 1 && state.links[0].status == LinkStatus::Present {
+        lines.push(Line::raw(""));
+        lines.push(Line::styled(
+            "  The disk is a standalone image; it has no backing file.",
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    frame.render_widget(Paragraph::new(lines), chunks[0]);
+
+    let details = match state.pending {
+        Some(ref action) => vec![
+            Line::styled(format!("{}?", action.title()), Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+            Line::raw(action.consequences()),
+        ],
+        None => link_details(state),
+    };
+    let details = Paragraph::new(details)
+        .block(Block::default().borders(Borders::TOP).border_style(Style::default().fg(Color::DarkGray)))
+        .wrap(Wrap { trim: true });
+    frame.render_widget(details, chunks[1]);
+
+    let help = if state.busy {
+        "Working..."
+    } else if state.pending.is_some() {
+        "[y] Go ahead  [any other key] Cancel"
+    } else {
+        "[j/k] Move  [f] Flatten  [c] Commit  [b] Rebase onto selected  [r] Repair  [Esc] Close"
+    };
+    let help = Paragraph::new(help)
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, chunks[2]);
+}
+
+/// Where the selected image is and how the overlay above names it
+fn link_details(state: &BackingChainState) -> Vec<Line<'static>> {
+    let Some(link) = state.links.get(state.selected) else {
+        return Vec::new();
+    };
+    let label = Style::default().fg(Color::Yellow);
+    let mut lines = vec![Line::from(vec![
+        Span::styled("Path:      ", label),
+        Span::raw(link.path.display().to_string()),
+    ])];
+    if let Some(ref recorded) = link.recorded_as {
+        lines.push(Line::from(vec![Span::styled("Named as:  ", label), Span::raw(recorded.clone())]));
+    }
+    if let Some(info) = link.info.as_ref() {
+        lines.push(Line::from(vec![
+            Span::styled("Disk size: ", label),
+            Span::raw(format_size(info.virtual_size)),
+        ]));
+        if !info.bitmaps.is_empty() {
+            let names: Vec<&str> = info.bitmaps.iter().map(|b| b.name.as_str()).collect();
+            lines.push(Line::from(vec![Span::styled("Bitmaps:   ", label), Span::raw(names.join(", "))]));
+        }
+    }
+    if let LinkStatus::Moved(ref found) = link.status {
+        lines.push(Line::from(vec![
+            Span::styled("Found at:  ", label),
+            Span::styled(format!("{} ([r] to repair)", found.display()), Style::default().fg(Color::Magenta)),
+        ]));
+    }
+    lines
+}
+
+/// Handle key input for the backing chain screen
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(ref mut state) = app.backing_chain_state else {
+        app.pop_screen();
+        return Ok(());
+    };
+    if state.busy {
+        return Ok(());
+    }
+
+    if let Some(action) = state.pending.take() {
+        if key.code == KeyCode::Char('y') {
+            start(app, action);
+        }
+        return Ok(());
+    }
+
+    let intact = state.links.iter().all(|l| l.status == LinkStatus::Present);
+    let action = match key.code {
+        KeyCode::Esc => {
+            app.backing_chain_state = None;
+            app.pop_screen();
+            return Ok(());
+        }
+        KeyCode::Char('j') | KeyCode::Down if state.selected + 1 < state.links.len() => {
+            state.selected += 1;
+            return Ok(());
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            state.selected = state.selected.saturating_sub(1);
+            return Ok(());
+        }
+        KeyCode::Char('f') | KeyCode::Char('c') | KeyCode::Char('b') if state.links.len() < 2 => {
+            Err("The disk has no backing file")
+        }
+        KeyCode::Char('f') | KeyCode::Char('c') | KeyCode::Char('b') if !intact => {
+            Err("Repair the chain first: an image in it is missing")
+        }
+        KeyCode::Char('f') => Ok(ChainAction::Flatten),
+        KeyCode::Char('c') => Ok(ChainAction::Commit),
+        KeyCode::Char('b') if state.selected < 2 => {
+            Err("Select an image below the disk's backing file to rebase onto")
+        }
+        KeyCode::Char('b') => Ok(ChainAction::Rebase { base: state.links[state.selected].path.clone() }),
+        KeyCode::Char('r') => match state.links[state.selected].status {
+            LinkStatus::Moved(ref found) if state.selected > 0 => Ok(ChainAction::Repair {
+                overlay: state.links[state.selected - 1].path.clone(),
+                found: found.clone(),
+            }),
+            LinkStatus::Missing => Err("No file of that name was found in the library"),
+            _ => Err("Select a missing image that was found elsewhere"),
+        },
+        _ => return Ok(()),
+    };
+    match action {
+        Ok(action) => state.pending = Some(action),
+        Err(message) => app.set_status(message),
+    }
+    Ok(())
+}
+
+/// Apply the confirmed change in the background
+fn start(app: &mut App, action: ChainAction) {
+    if app.selected_vm().is_some_and(|vm| app.running_vms.contains_key(&vm.id)) {
+        app.set_status("Error: Cannot change the disk while the VM is running. Please shut down the VM first.");
+        return;
+    }
+    if let Some(Err(e)) = app.selected_vm().map(|vm| crate::vm::suspend::ensure_not_suspended(&vm.path)) {
+        app.set_status(format!("Error: {}", e));
+        return;
+    }
+    let Some(ref mut state) = app.backing_chain_state else {
+        return;
+    };
+    state.busy = true;
+    let disk = state.disk.clone();
+    let tx = app.background_tx.clone();
+    app.loading = true;
+    app.set_status(format!("{}...", action.title()));
+
+    thread::spawn(move || {
+        let result = backing_chain::apply(&disk, &action)
+            .map(|()| format!("{} done", action.title()))
+            .map_err(|e| format!("{:#}", e));
+        let _ = tx.send(BackgroundResult::BackingChainChanged { result });
+    });
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    Rect::new(x, y, width, height)
+}
@@ -0,0 +1,147 @@
+//! Batch Report Screen
+//!
+//! Summarizes the outcome of a batch operation: one line per VM with its
+//! result and error, totals in the title, and a key to retry only the
+//! items that failed.
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
+};
+
+use crate::app::App;
+
+/// Render the batch report
+pub fn render(app: &App, frame: &mut Frame) {
+    let Some(ref report) = app.batch_report else {
+        return;
+    };
+
+    let area = frame.area();
+    let dialog_width = 72.min(area.width.saturating_sub(4));
+    let dialog_height = 22.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let border_color = if report.failure_count() == 0 { Color::Green } else { Color::Yellow };
+    let block = Block::default()
+        .title(format!(
+            " {} - {} succeeded, {} failed ",
+            report.operation.label(),
+            report.success_count(),
+            report.failure_count()
+        ))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(border_color))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let h_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Length(2), // Left margin
+            Constraint::Min(1),    // Content
+            Constraint::Length(2), // Right margin
+        ])
+        .split(inner);
+
+    let v_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // Top padding
+            Constraint::Min(3),    // Item list
+            Constraint::Length(4), // Selected item detail
+            Constraint::Length(1), // Help
+        ])
+        .split(h_chunks[1]);
+
+    let items: Vec<ListItem> = report
+        .items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let (mark, color) = if item.succeeded() { ("✓", Color::Green) } else { ("✗", Color::Red) };
+            let name_style = if i == app.batch_report_selected {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            let attempts = match item.attempts {
+                0 => "skipped".to_string(),
+                1 => String::new(),
+                n => format!("{} attempts", n),
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!(" {} ", mark), Style::default().fg(color)),
+                Span::styled(item.vm_name.clone(), name_style),
+                Span::styled(format!("  {}", attempts), Style::default().fg(Color::DarkGray)),
+            ]))
+        })
+        .collect();
+
+    let mut state = ListState::default();
+    state.select(Some(app.batch_report_selected));
+    frame.render_stateful_widget(List::new(items), v_chunks[1], &mut state);
+
+    // Full error for the selected item
+    let detail = match report.items.get(app.batch_report_selected) {
+        Some(item) => match item.error {
+            Some(ref error) => Line::styled(error.trim().to_string(), Style::default().fg(Color::Red)),
+            None => Line::styled("Completed successfully", Style::default().fg(Color::Green)),
+        },
+        None => Line::from(""),
+    };
+    let detail = Paragraph::new(detail)
+        .block(Block::default().borders(Borders::TOP).border_style(Style::default().fg(Color::DarkGray)))
+        .wrap(Wrap { trim: true });
+    frame.render_widget(detail, v_chunks[2]);
+
+    let help_text = if report.failure_count() > 0 {
+        "[j/k] Select  [r] Retry failed  [Esc] Close"
+    } else {
+        "[j/k] Select  [Esc] Close"
+    };
+    let help = Paragraph::new(help_text)
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, v_chunks[3]);
+}
+
+/// Handle key input for the batch report
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    let item_count = app.batch_report.as_ref().map(|r| r.items.len()).unwrap_or(0);
+
+    match key.code {
+        KeyCode::Esc | KeyCode::Enter => app.pop_screen(),
+        KeyCode::Char('j') | KeyCode::Down => {
+            app.batch_report_selected = (app.batch_report_selected + 1).min(item_count.saturating_sub(1));
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            app.batch_report_selected = app.batch_report_selected.saturating_sub(1);
+        }
+        KeyCode::Char('r') | KeyCode::Char('R') => {
+            let retry = app
+                .batch_report
+                .as_ref()
+                .filter(|r| r.failure_count() > 0)
+                .map(|r| (r.operation.clone(), r.failed_ids()));
+            if let Some((operation, failed)) = retry {
+                app.pop_screen();
+                app.start_batch(operation, Some(failed));
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    Rect::new(x, y, width, height)
+}
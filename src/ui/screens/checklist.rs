@@ -0,0 +1,169 @@
+//! Post-install Checklist Screen
+//!
+//! The steps the VM's OS profile suggests once the OS is installed, with
+//! the profile's tips above them. Checked items are saved in the VM folder.
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    prelude::*,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+
+use crate::app::{App, ChecklistState, Screen};
+use crate::vm::checklist;
+
+/// The selected VM's checklist, if its OS profile has one
+fn selected_checklist(app: &App) -> Option<ChecklistState> {
+    let vm = app.selected_vm().filter(|vm| vm.remote.is_none())?;
+    let profile = vm.os_profile.as_deref().and_then(|id| app.qemu_profiles.get(id));
+    let steps = profile.map(|p| p.post_install.as_slice()).unwrap_or_default();
+    let checklist = checklist::load(&vm.path, steps);
+    if checklist.items.is_empty() {
+        return None;
+    }
+    Some(ChecklistState {
+        vm_dir: vm.path.clone(),
+        vm_name: vm.display_name(),
+        notes: profile.and_then(|p| p.notes.clone()),
+        checklist,
+        selected: 0,
+    })
+}
+
+/// Open the selected VM's checklist
+pub fn open(app: &mut App) {
+    match selected_checklist(app) {
+        Some(state) => {
+            app.checklist_state = Some(state);
+            app.push_screen(Screen::Checklist);
+        }
+        None => app.set_status("No post-install checklist for this VM's OS"),
+    }
+}
+
+/// Open the checklist of a new VM, if its OS has one
+pub fn open_after_create(app: &mut App, vm_id: &str) {
+    if !app.select_vm_by_id(vm_id) {
+        return;
+    }
+    if let Some(state) = selected_checklist(app) {
+        app.checklist_state = Some(state);
+        app.push_screen(Screen::Checklist);
+    }
+}
+
+/// Render the checklist dialog
+pub fn render(app: &App, frame: &mut Frame) {
+    let Some(ref state) = app.checklist_state else {
+        return;
+    };
+    let area = frame.area();
+
+    let dialog_width = 76.min(area.width.saturating_sub(4));
+    let dialog_height = 22.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let block = Block::default()
+        .title(format!(
+            " After installing {}: {} of {} done ",
+            state.vm_name,
+            state.checklist.done_count(),
+            state.checklist.items.len()
+        ))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let notes_height = if state.notes.is_some() { 4 } else { 0 };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(notes_height), // Profile tips
+            Constraint::Min(3),               // Steps
+            Constraint::Length(1),            // Help text
+        ])
+        .split(inner.inner(Margin::new(1, 0)));
+
+    if let Some(ref notes) = state.notes {
+        frame.render_widget(
+            Paragraph::new(notes.as_str())
+                .style(Style::default().fg(Color::DarkGray))
+                .wrap(Wrap { trim: true }),
+            chunks[0],
+        );
+    }
+
+    let height = chunks[1].height as usize;
+    let first = state.selected.saturating_sub(height.saturating_sub(1));
+    let lines: Vec<Line> = state
+        .checklist
+        .items
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(height)
+        .map(|(i, item)| {
+            let text = format!(
+                "{}[{}] {}",
+                if i == state.selected { "> " } else { "  " },
+                if item.done { "x" } else { " " },
+                item.text
+            );
+            let style = match (i == state.selected, item.done) {
+                (true, _) => Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                (false, true) => Style::default().fg(Color::Green),
+                (false, false) => Style::default().fg(Color::White),
+            };
+            Line::styled(text, style)
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), chunks[1]);
+
+    let help = Paragraph::new("[j/k] Move  [Space] Check  [Esc] Close")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, chunks[2]);
+}
+
+/// Handle key input for the checklist screen
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(ref mut state) = app.checklist_state else {
+        app.pop_screen();
+        return Ok(());
+    };
+    match key.code {
+        KeyCode::Esc => {
+            app.checklist_state = None;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down if state.selected + 1 < state.checklist.items.len() => state.selected += 1,
+        KeyCode::Char('k') | KeyCode::Up => state.selected = state.selected.saturating_sub(1),
+        KeyCode::Char(' ') | KeyCode::Enter => {
+            if let Some(item) = state.checklist.items.get_mut(state.selected) {
+                item.done = !item.done;
+            }
+            let complete = state.checklist.is_complete();
+            match checklist::save(&state.vm_dir, &state.checklist) {
+                Err(e) => app.set_status(format!("Error: {}", e)),
+                Ok(()) if complete => app.set_status("All post-install steps done"),
+                Ok(()) => {}
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    Rect::new(x, y, width, height)
+}
@@ -4,8 +4,11 @@ use ratatui::{
     widgets::{Block, Borders, Clear, Paragraph, Wrap},
 };
 
+use std::path::Path;
+
 use crate::app::App;
-use crate::vm::QemuConfig;
+use crate::commands::qemu_system;
+use crate::vm::{launch_env, QemuConfig};
 
 /// Render the configuration view
 pub fn render(app: &App, frame: &mut Frame) {
@@ -51,7 +54,7 @@ pub fn render(app: &App, frame: &mut Frame) {
         .split(h_chunks[1]);
 
     if let Some(vm) = app.selected_vm() {
-        render_config(&vm.config, chunks[1], frame);
+        render_config(&vm.config, &vm.path, chunks[1], frame);
     } else {
         let msg = Paragraph::new("No VM selected")
             .style(Style::default().fg(Color::DarkGray))
@@ -60,13 +63,13 @@ pub fn render(app: &App, frame: &mut Frame) {
     }
 
     // Help text
-    let help = Paragraph::new("[r] View raw script  [Esc] Back")
+    let help = Paragraph::new("[←/→] Resolution  [r] View raw script  [Esc] Back")
         .style(Style::default().fg(Color::DarkGray))
         .alignment(Alignment::Center);
     frame.render_widget(help, chunks[3]);
 }
 
-fn render_config(config: &QemuConfig, area: Rect, frame: &mut Frame) {
+fn render_config(config: &QemuConfig, vm_dir: &Path, area: Rect, frame: &mut Frame) {
     let mut lines = Vec::new();
 
     // Emulator
@@ -75,6 +78,25 @@ fn render_config(config: &QemuConfig, area: Rect, frame: &mut Frame) {
         Span::raw(config.emulator.command()),
     ]));
 
+    // QEMU build chosen under QEMU Version, or the one on PATH
+    let binary = launch_env::load(vm_dir).qemu_binary;
+    let qemu = match binary {
+        Some(ref path) if !path.exists() => format!("{} (not found)", path.display()),
+        Some(ref path) => format!(
+            "{} ({})",
+            qemu_system::binary_version(path).unwrap_or_else(|| "unknown version".to_string()),
+            path.display()
+        ),
+        None => match qemu_system::binary_version(Path::new(config.emulator.command())) {
+            Some(version) => format!("{} (default on PATH)", version),
+            None => "not installed".to_string(),
+        },
+    };
+    lines.push(Line::from(vec![
+        Span::styled("QEMU: ", Style::default().fg(Color::Yellow)),
+        Span::raw(qemu),
+    ]));
+
     // Architecture
     lines.push(Line::from(vec![
         Span::styled("Architecture: ", Style::default().fg(Color::Yellow)),
@@ -106,6 +128,23 @@ fn render_config(config: &QemuConfig, area: Rect, frame: &mut Frame) {
             Span::styled("Machine: ", Style::default().fg(Color::Yellow)),
             Span::raw(machine.clone()),
         ]));
+        if let Some(known) = crate::vm::machine::find_machine(config.emulator.command(), machine) {
+            lines.push(Line::styled(format!("  {}", known.name), Style::default().fg(Color::DarkGray)));
+        }
+    }
+
+    if let Some(ref identity) = config.identity {
+        lines.push(Line::from(vec![
+            Span::styled("MAC: ", Style::default().fg(Color::Yellow)),
+            Span::raw(identity.mac.clone()),
+        ]));
+    }
+
+    if let Some(ref controller) = config.usb_controller {
+        lines.push(Line::from(vec![
+            Span::styled("USB: ", Style::default().fg(Color::Yellow)),
+            Span::raw(controller.clone()),
+        ]));
     }
 
     lines.push(Line::from(""));
@@ -116,6 +155,25 @@ fn render_config(config: &QemuConfig, area: Rect, frame: &mut Frame) {
         Span::raw(format!("{:?}", config.vga)),
     ]));
 
+    if let Some(mode) = config.gpu_accel.as_deref().and_then(crate::vm::gpu_accel::find_mode) {
+        lines.push(Line::from(vec![
+            Span::styled("3D: ", Style::default().fg(Color::Yellow)),
+            Span::raw(mode.name),
+        ]));
+    }
+
+    lines.push(Line::from(vec![
+        Span::styled("Resolution: ", Style::default().fg(Color::Yellow)),
+        Span::raw(config.resolution.clone().unwrap_or_else(|| "auto".to_string())),
+        Span::styled("  [←/→]", Style::default().fg(Color::DarkGray)),
+    ]));
+    if let Some(ref preset) = config.display_preset {
+        lines.push(Line::from(vec![
+            Span::styled("Scaling: ", Style::default().fg(Color::Yellow)),
+            Span::raw(preset.summary()),
+        ]));
+    }
+
     // Audio
     if !config.audio_devices.is_empty() {
         let audio_str = config.audio_devices
@@ -127,6 +185,25 @@ fn render_config(config: &QemuConfig, area: Rect, frame: &mut Frame) {
             Span::styled("Audio: ", Style::default().fg(Color::Yellow)),
             Span::raw(audio_str),
         ]));
+        let output = config.audio_backend.as_deref()
+            .map(crate::hardware::audio::backend_label)
+            .unwrap_or("QEMU default (legacy)");
+        lines.push(Line::from(vec![
+            Span::styled("Audio Out: ", Style::default().fg(Color::Yellow)),
+            Span::raw(output.to_string()),
+        ]));
+    }
+    if let Some(ref sb16) = config.sb16 {
+        lines.push(Line::from(vec![
+            Span::styled("BLASTER: ", Style::default().fg(Color::Yellow)),
+            Span::raw(sb16.blaster()),
+        ]));
+    }
+    if let Some(synth) = config.midi_synth.as_deref().and_then(crate::vm::midi::find_synth) {
+        lines.push(Line::from(vec![
+            Span::styled("MIDI: ", Style::default().fg(Color::Yellow)),
+            Span::raw(format!("COM1 -> {}", synth.name)),
+        ]));
     }
 
     // Network
@@ -135,6 +212,7 @@ fn render_config(config: &QemuConfig, area: Rect, frame: &mut Frame) {
             crate::vm::qemu_config::NetworkBackend::User => "user/SLIRP (NAT)".to_string(),
             crate::vm::qemu_config::NetworkBackend::Passt => "passt".to_string(),
             crate::vm::qemu_config::NetworkBackend::Bridge(name) => format!("bridge: {}", name),
+            crate::vm::qemu_config::NetworkBackend::Tap(name) => format!("tap: {}", name),
             crate::vm::qemu_config::NetworkBackend::None => "none".to_string(),
         };
         lines.push(Line::from(vec![
@@ -161,6 +239,13 @@ fn render_config(config: &QemuConfig, area: Rect, frame: &mut Frame) {
     )));
 
     for disk in &config.disks {
+        if let Some(ref remote) = disk.remote {
+            lines.push(Line::from(format!(
+                "  {} {} ({:?}, {})",
+                remote.protocol.label(), remote.display_url(), disk.format, disk.interface
+            )));
+            continue;
+        }
         let path = disk.path.file_name()
             .and_then(|n| n.to_str())
             .unwrap_or("unknown");
@@ -180,6 +265,9 @@ fn render_config(config: &QemuConfig, area: Rect, frame: &mut Frame) {
     if config.uefi {
         features.push("UEFI");
     }
+    if config.secure_boot {
+        features.push("Secure Boot");
+    }
     if config.tpm {
         features.push("TPM");
     }
@@ -191,11 +279,18 @@ fn render_config(config: &QemuConfig, area: Rect, frame: &mut Frame) {
         ]));
     }
 
+    if let Some(ref lg) = config.looking_glass {
+        lines.push(Line::from(vec![
+            Span::styled("Looking Glass: ", Style::default().fg(Color::Yellow)),
+            Span::raw(format!("{}MB at {}", lg.size_mb, lg.mem_path())),
+        ]));
+    }
+
     // Snapshot support
-    let snapshot_support = if config.supports_snapshots() {
-        Span::styled("Yes", Style::default().fg(Color::Green))
-    } else {
-        Span::styled("No (raw disk)", Style::default().fg(Color::Red))
+    let snapshot_support = match config.primary_disk() {
+        _ if config.supports_snapshots() => Span::styled("Yes", Style::default().fg(Color::Green)),
+        Some(disk) => Span::styled(format!("No ({} disk)", disk.format.label()), Style::default().fg(Color::Red)),
+        None => Span::styled("No (no local disk)", Style::default().fg(Color::Red)),
     };
     lines.push(Line::from(vec![
         Span::styled("Snapshots: ", Style::default().fg(Color::Yellow)),
@@ -209,6 +304,11 @@ fn render_config(config: &QemuConfig, area: Rect, frame: &mut Frame) {
 
 /// Render raw script editor
 pub fn render_raw_script(app: &App, frame: &mut Frame) {
+    render_text_editor(app, frame, "launch.sh");
+}
+
+/// Render the text editor shared by launch.sh and the VM's notes
+pub fn render_text_editor(app: &App, frame: &mut Frame, file_name: &str) {
     let area = frame.area();
     let dialog_width = 90.min(area.width.saturating_sub(4));
     let dialog_height = 40.min(area.height.saturating_sub(4));
@@ -223,7 +323,7 @@ pub fn render_raw_script(app: &App, frame: &mut Frame) {
     let modified_indicator = if app.script_editor_modified { " [modified]" } else { "" };
 
     let block = Block::default()
-        .title(format!(" {} - launch.sh{} ", vm_name, modified_indicator))
+        .title(format!(" {} - {}{} ", vm_name, file_name, modified_indicator))
         .borders(Borders::ALL)
         .border_style(if app.script_editor_modified {
             Style::default().fg(Color::Yellow)
@@ -289,15 +389,8 @@ pub fn render_raw_script(app: &App, frame: &mut Frame) {
         .map(|i| {
             let line = app.script_editor_lines.get(i).map(|s| s.as_str()).unwrap_or("");
 
-            // Apply horizontal scroll
-            let visible_line = if h_scroll < line.len() {
-                &line[h_scroll..]
-            } else {
-                ""
-            };
-
-            // Truncate to visible width
-            let display_line: String = visible_line.chars().take(text_width).collect();
+            // Apply horizontal scroll and truncate to visible width
+            let display_line: String = line.chars().skip(h_scroll).take(text_width).collect();
 
             if i == app.script_editor_cursor.0 {
                 // This is the cursor line - highlight it slightly
@@ -0,0 +1,367 @@
+//! Convert Disk Screen
+//!
+//! Guided conversion of a VM's disk image between formats, mostly for
+//! VMware and VirtualBox images brought over to QEMU. Shows the detected
+//! source format and sizes, the space the result needs against what is
+//! free, lets qcow2 output be tuned, runs qemu-img in the background with
+//! progress, then points launch.sh at the new image and keeps or deletes
+//! the original.
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    prelude::*,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Gauge, Paragraph},
+};
+use std::thread;
+
+use crate::app::{App, BackgroundResult, ConvertDiskState, Screen};
+use crate::commands::qemu_img;
+use crate::format::format_size;
+use crate::ui::widgets::disk_tuning;
+use crate::vm::disk_ops;
+
+/// Output formats offered: qemu-img name and description
+const FORMATS: &[(&str, &str)] = &[
+    ("qcow2", "qcow2 (QEMU: snapshots, sparse)"),
+    ("raw", "raw (plain image, fastest)"),
+    ("vmdk", "VMDK (VMware)"),
+    ("vdi", "VDI (VirtualBox)"),
+    ("vpc", "VHD (Virtual PC / Hyper-V)"),
+    ("vhdx", "VHDX (Hyper-V)"),
+];
+
+/// Focusable options: format, compression, the qcow2 tuning rows, original
+const TUNING_FIELD: usize = 2;
+const ORIGINAL_FIELD: usize = TUNING_FIELD + disk_tuning::LABELS.len();
+const FIELD_COUNT: usize = ORIGINAL_FIELD + 1;
+
+/// Probe the selected VM's disk and show the wizard
+pub fn open(app: &mut App) {
+    let Some(vm) = app.selected_vm() else {
+        return;
+    };
+    let Some(disk) = vm.config.primary_disk() else {
+        app.set_status("This VM has no local disk image to convert");
+        return;
+    };
+    let source = disk.path.clone();
+    if !source.exists() {
+        app.set_status(format!("Disk not found: {}", source.display()));
+        return;
+    }
+
+    let mut chain = qemu_img::image_chain(&source).unwrap_or_default().into_iter();
+    let info = chain.next();
+    let backing: Vec<_> = chain.collect();
+    // Default to qcow2, or the first other format if it already is one
+    let format_index = FORMATS
+        .iter()
+        .position(|(f, _)| info.as_ref().map(|i| i.format.as_str()) != Some(*f))
+        .unwrap_or(0);
+    let free_space = source.parent().and_then(crate::fs::available_space);
+    // A qcow2 source keeps its cluster size, compression type and compat
+    let tuning = info.as_ref().and_then(|i| i.qcow2).unwrap_or_default();
+    let snapshots = crate::vm::snapshot::list_snapshots(&source).map(|s| s.len()).unwrap_or(0);
+
+    app.convert_disk_state = Some(ConvertDiskState {
+        source,
+        info,
+        backing,
+        format_index,
+        compress: false,
+        tuning,
+        keep_original: true,
+        field: 0,
+        free_space,
+        snapshots,
+        progress: None,
+    });
+    app.push_screen(Screen::ConvertDisk);
+}
+
+/// Render the disk conversion wizard
+pub fn render(app: &App, frame: &mut Frame) {
+    let Some(ref state) = app.convert_disk_state else {
+        return;
+    };
+    let area = frame.area();
+
+    // Room for the backing chain, bitmaps and snapshots, if any
+    let extra_lines = state.backing.len()
+        + usize::from(state.info.as_ref().is_some_and(|i| !i.bitmaps.is_empty()))
+        + usize::from(state.snapshots > 0);
+    let dialog_width = 70.min(area.width.saturating_sub(4));
+    let dialog_height = (23 + extra_lines as u16).min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let block = Block::default()
+        .title(" Convert Disk ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(8),    // Source and options
+            Constraint::Length(1), // Progress
+            Constraint::Length(1), // Spacer
+            Constraint::Length(1), // Help text
+        ])
+        .split(inner.inner(Margin::new(1, 0)));
+
+    let label = Style::default().fg(Color::Yellow);
+    let (format, _) = FORMATS[state.format_index];
+    let target = disk_ops::converted_disk_path(&state.source, format);
+
+    let mut lines = vec![Line::from(vec![
+        Span::styled("Source:     ", label),
+        Span::raw(state.source.file_name().unwrap_or_default().to_string_lossy().to_string()),
+    ])];
+    match state.info {
+        Some(ref info) => lines.push(Line::from(vec![
+            Span::styled("            ", label),
+            Span::raw(format!(
+                "{}, {} disk, {} allocated",
+                info.format,
+                format_size(info.virtual_size),
+                format_size(info.actual_size)
+            )),
+        ])),
+        None => lines.push(Line::from(Span::styled(
+            "            Format unknown (qemu-img info failed)",
+            Style::default().fg(Color::Red),
+        ))),
+    }
+    // The conversion flattens the overlay and its backing images into one
+    for (depth, image) in state.backing.iter().enumerate() {
+        let name = std::path::Path::new(&image.filename).file_name().unwrap_or_default().to_string_lossy();
+        lines.push(Line::from(vec![
+            Span::styled(if depth == 0 { "Based on:   " } else { "            " }, label),
+            Span::styled(
+                format!("{}└ {} ({}, {} allocated)", "  ".repeat(depth), name, image.format, format_size(image.actual_size)),
+                Style::default().fg(Color::Gray),
+            ),
+        ]));
+    }
+    if let Some(info) = state.info.as_ref().filter(|i| !i.bitmaps.is_empty()) {
+        let names: Vec<&str> = info.bitmaps.iter().map(|b| b.name.as_str()).collect();
+        lines.push(Line::from(vec![
+            Span::styled("Bitmaps:    ", label),
+            Span::styled(format!("{} (not carried over)", names.join(", ")), Style::default().fg(Color::Gray)),
+        ]));
+    }
+    if state.snapshots > 0 {
+        lines.push(Line::from(vec![
+            Span::styled("Snapshots:  ", label),
+            Span::styled(
+                format!("{} internal; delete them before converting", state.snapshots),
+                Style::default().fg(Color::Red),
+            ),
+        ]));
+    }
+    lines.push(Line::from(""));
+
+    let tuning = disk_tuning::values(&state.tuning, format);
+    let mut options = vec![
+        ("Convert to: ".to_string(), format!("< {} >", FORMATS[state.format_index].1)),
+        (
+            "Compress:   ".to_string(),
+            if format != "qcow2" {
+                "n/a".to_string()
+            } else if state.compress {
+                "[x] (smaller, slower writes later)".to_string()
+            } else {
+                "[ ]".to_string()
+            },
+        ),
+    ];
+    for (label, value) in disk_tuning::LABELS.iter().zip(tuning) {
+        options.push((format!("{:12}", format!("{}:", label)), value));
+    }
+    options.push((
+        "Original:   ".to_string(),
+        if state.keep_original { "< Keep >" } else { "< Delete after converting >" }.to_string(),
+    ));
+    for (i, (name, value)) in options.into_iter().enumerate() {
+        let focused = state.field == i && state.progress.is_none();
+        let style = if focused {
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::White)
+        };
+        lines.push(Line::from(vec![
+            Span::styled(if focused { "> " } else { "  " }, style),
+            Span::styled(name, label),
+            Span::styled(value, style),
+        ]));
+    }
+    if (TUNING_FIELD..ORIGINAL_FIELD).contains(&state.field) && state.progress.is_none() {
+        lines.push(Line::styled(
+            format!("  {}", disk_tuning::hint(state.field - TUNING_FIELD)),
+            Style::default().fg(Color::DarkGray),
+        ));
+    } else {
+        lines.push(Line::from(""));
+    }
+
+    lines.push(Line::from(vec![
+        Span::styled("Output:     ", label),
+        Span::raw(target.file_name().unwrap_or_default().to_string_lossy().to_string()),
+    ]));
+    if let Some(ref info) = state.info {
+        let needed = disk_ops::estimate_converted_size(info, &state.backing, format);
+        let (text, color) = match state.free_space {
+            Some(free) if free < needed => (
+                format!("needs up to {}, only {} free", format_size(needed), format_size(free)),
+                Color::Red,
+            ),
+            Some(free) => (
+                format!("needs up to {} ({} free)", format_size(needed), format_size(free)),
+                Color::Green,
+            ),
+            None => (format!("needs up to {}", format_size(needed)), Color::White),
+        };
+        lines.push(Line::from(vec![
+            Span::styled("Space:      ", label),
+            Span::styled(text, Style::default().fg(color)),
+        ]));
+    }
+    frame.render_widget(Paragraph::new(lines), chunks[0]);
+
+    if let Some(progress) = state.progress {
+        let gauge = Gauge::default()
+            .gauge_style(Style::default().fg(Color::Cyan).bg(Color::DarkGray))
+            .ratio(progress.clamp(0.0, 1.0) as f64)
+            .label(format!("Converting... {:.0}%", progress * 100.0));
+        frame.render_widget(gauge, chunks[1]);
+    }
+
+    let help = if state.progress.is_some() {
+        "Converting; launch.sh is updated when done"
+    } else {
+        "[j/k] Option  [←/→/Space] Change  [Enter] Convert  [Esc] Cancel"
+    };
+    let help = Paragraph::new(help)
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, chunks[3]);
+}
+
+/// Handle key input for the disk conversion wizard
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(ref mut state) = app.convert_disk_state else {
+        app.pop_screen();
+        return Ok(());
+    };
+    // The conversion runs to completion; the journal covers a hard exit
+    if state.progress.is_some() {
+        return Ok(());
+    }
+
+    match key.code {
+        KeyCode::Esc => {
+            app.convert_disk_state = None;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down | KeyCode::Tab => state.field = (state.field + 1) % FIELD_COUNT,
+        KeyCode::Char('k') | KeyCode::Up | KeyCode::BackTab => {
+            state.field = (state.field + FIELD_COUNT - 1) % FIELD_COUNT
+        }
+        KeyCode::Left | KeyCode::Right | KeyCode::Char(' ') => match state.field {
+            0 => {
+                state.format_index = if key.code == KeyCode::Left {
+                    (state.format_index + FORMATS.len() - 1) % FORMATS.len()
+                } else {
+                    (state.format_index + 1) % FORMATS.len()
+                };
+            }
+            1 => state.compress = !state.compress,
+            TUNING_FIELD..ORIGINAL_FIELD => {
+                let (format, _) = FORMATS[state.format_index];
+                disk_tuning::change(&mut state.tuning, format, state.field - TUNING_FIELD, key.code == KeyCode::Left);
+            }
+            _ => state.keep_original = !state.keep_original,
+        },
+        KeyCode::Enter => start_conversion(app),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn start_conversion(app: &mut App) {
+    let Some(vm) = app.selected_vm().cloned() else {
+        return;
+    };
+    if app.running_vms.contains_key(&vm.id) {
+        app.set_status("Error: Cannot convert the disk while the VM is running. Please shut down the VM first.");
+        return;
+    }
+    if let Err(e) = crate::vm::suspend::ensure_not_suspended(&vm.path) {
+        app.set_status(format!("Error: {}", e));
+        return;
+    }
+    let snapshots = app.convert_disk_state.as_ref().map_or(0, |s| s.snapshots);
+    if snapshots > 0 {
+        app.set_status(format!(
+            "Error: The disk has {} internal snapshot(s), which conversion would drop; delete them first",
+            snapshots
+        ));
+        return;
+    }
+    let Some(ref mut state) = app.convert_disk_state else {
+        return;
+    };
+
+    let (format, _) = FORMATS[state.format_index];
+    if state.info.as_ref().is_some_and(|i| i.format == format) {
+        app.set_status(format!("The disk is already {}", format));
+        return;
+    }
+    let target = disk_ops::converted_disk_path(&state.source, format);
+    if target.exists() {
+        app.set_status(format!("{} already exists", target.display()));
+        return;
+    }
+    if let Err(e) = state.tuning.validate(state.compress && format == "qcow2") {
+        app.set_status(format!("Error: {}", e));
+        return;
+    }
+    if let (Some(info), Some(free)) = (&state.info, state.free_space) {
+        if free < disk_ops::estimate_converted_size(info, &state.backing, format) {
+            app.set_status("Error: Not enough free space for the converted image");
+            return;
+        }
+    }
+
+    state.progress = Some(0.0);
+    let source = state.source.clone();
+    let compress = state.compress && format == "qcow2";
+    let tuning = state.tuning;
+    let keep_original = state.keep_original;
+    let vm_name = vm.display_name();
+    let tx = app.background_tx.clone();
+
+    thread::spawn(move || {
+        let progress_tx = tx.clone();
+        let result = disk_ops::convert_vm_disk(&vm.launch_script, &source, format, compress, &tuning, keep_original, |progress| {
+            let _ = progress_tx.send(BackgroundResult::DiskConvertProgress { progress });
+        })
+        .map_err(|e| e.to_string());
+        let _ = tx.send(BackgroundResult::DiskConverted { vm_name, result });
+    });
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    Rect::new(x, y, width, height)
+}
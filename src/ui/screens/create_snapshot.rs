@@ -0,0 +1,205 @@
+//! Create Snapshot Screen
+//!
+//! Name, description and reason for a new snapshot of the selected VM.
+//! The description, the reason and the current launch script go to the
+//! disk's snapshot sidecar, since qcow2 only stores the name.
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    prelude::*,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+use std::thread;
+
+use crate::app::{App, BackgroundResult, CreateSnapshotState, Screen};
+use crate::vm::snapshot_meta::{self, SnapshotContext, SnapshotMeta};
+
+/// Rows of the dialog, in the order shown
+const ROWS: [&str; 4] = ["Name", "Description", "Reason", "Create"];
+const CONTEXT_ROW: usize = 2;
+const CREATE_ROW: usize = 3;
+
+/// Open the dialog, with a timestamped name filled in
+pub fn open(app: &mut App) {
+    app.create_snapshot_state = Some(CreateSnapshotState {
+        name: format!("snapshot-{}", chrono::Local::now().format("%Y%m%d-%H%M%S")),
+        ..Default::default()
+    });
+    app.push_screen(Screen::CreateSnapshot);
+}
+
+/// Render the dialog
+pub fn render(app: &App, frame: &mut Frame) {
+    let Some(ref state) = app.create_snapshot_state else {
+        return;
+    };
+    let area = frame.area();
+
+    let dialog_width = 64.min(area.width.saturating_sub(4));
+    let dialog_height = 10.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let block = Block::default()
+        .title(" Create Snapshot ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(5),    // Rows
+            Constraint::Length(1), // Help text
+        ])
+        .split(inner.inner(Margin::new(1, 0)));
+
+    let values = [state.name.as_str(), state.description.as_str()];
+    let mut lines = Vec::new();
+    for (i, label) in ROWS.iter().enumerate() {
+        let selected = i == state.selected;
+        let marker = if selected { "> " } else { "  " };
+        let label_style = if selected {
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Yellow)
+        };
+        let value = match i {
+            CREATE_ROW => {
+                lines.push(Line::raw(""));
+                lines.push(Line::from(vec![Span::raw(marker), Span::styled("[ Create ]", label_style)]));
+                continue;
+            }
+            CONTEXT_ROW => Span::raw(format!("< {} >", state.context.label())),
+            _ if selected && state.editing => Span::raw(format!("{}_", values[i])),
+            _ if values[i].is_empty() => Span::styled("(none)", Style::default().fg(Color::DarkGray)),
+            _ => Span::styled(values[i].to_string(), Style::default().fg(Color::White)),
+        };
+        lines.push(Line::from(vec![
+            Span::raw(marker),
+            Span::styled(format!("{:13}", label), label_style),
+            value,
+        ]));
+    }
+    frame.render_widget(Paragraph::new(lines), chunks[0]);
+
+    let help = if state.editing {
+        "Type to edit  [Enter] Done"
+    } else if state.selected == CONTEXT_ROW {
+        "[Enter/←/→] Change  [Esc] Cancel"
+    } else {
+        "[Enter] Edit  [Esc] Cancel"
+    };
+    let help = Paragraph::new(help)
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, chunks[1]);
+}
+
+/// The typed value of a text row
+fn text_mut(state: &mut CreateSnapshotState, row: usize) -> Option<&mut String> {
+    match row {
+        0 => Some(&mut state.name),
+        1 => Some(&mut state.description),
+        _ => None,
+    }
+}
+
+/// Handle key input for the dialog
+pub fn handle_key(app: &mut App, key: KeyEvent) {
+    let Some(ref mut state) = app.create_snapshot_state else {
+        app.pop_screen();
+        return;
+    };
+
+    if state.editing {
+        let row = state.selected;
+        match key.code {
+            KeyCode::Enter | KeyCode::Esc | KeyCode::Tab => state.editing = false,
+            // Only safe characters for snapshot names
+            KeyCode::Char(c) if row == 0 && !(c.is_alphanumeric() || c == '-' || c == '_' || c == '.') => {}
+            KeyCode::Char(c) if !c.is_control() => {
+                if let Some(text) = text_mut(state, row) {
+                    text.push(c);
+                }
+            }
+            KeyCode::Backspace => {
+                if let Some(text) = text_mut(state, row) {
+                    text.pop();
+                }
+            }
+            _ => {}
+        }
+        return;
+    }
+
+    match key.code {
+        KeyCode::Esc => {
+            app.create_snapshot_state = None;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down | KeyCode::Tab if state.selected < CREATE_ROW => state.selected += 1,
+        KeyCode::Char('k') | KeyCode::Up => state.selected = state.selected.saturating_sub(1),
+        KeyCode::Enter | KeyCode::Right | KeyCode::Left | KeyCode::Char(' ') if state.selected == CONTEXT_ROW => {
+            let choices = SnapshotContext::CHOICES;
+            let position = choices.iter().position(|c| *c == state.context).unwrap_or(0);
+            let next = if key.code == KeyCode::Left {
+                (position + choices.len() - 1) % choices.len()
+            } else {
+                (position + 1) % choices.len()
+            };
+            state.context = choices[next];
+        }
+        KeyCode::Enter if state.selected == CREATE_ROW => create(app),
+        KeyCode::Enter => state.editing = true,
+        _ => {}
+    }
+}
+
+/// Take the snapshot in the background and record its metadata
+fn create(app: &mut App) {
+    let Some(state) = app.create_snapshot_state.clone() else {
+        return;
+    };
+    let name = match crate::vm::snapshot::validate_snapshot_name(&state.name) {
+        Ok(name) => name,
+        Err(e) => {
+            app.set_status(format!("Error: {}", e));
+            return;
+        }
+    };
+    let Some(vm) = app.selected_vm() else {
+        return;
+    };
+    let Some(disk_path) = vm.config.primary_disk().map(|disk| disk.path.clone()) else {
+        return;
+    };
+    let meta = SnapshotMeta::for_vm(vm, &state.description, state.context);
+    let tx = app.background_tx.clone();
+    app.create_snapshot_state = None;
+    app.pop_screen();
+    app.loading = true;
+    app.set_status(format!("Creating snapshot: {}...", name));
+
+    thread::spawn(move || {
+        let result = crate::vm::create_snapshot(&disk_path, &name)
+            .and_then(|()| snapshot_meta::record(&disk_path, &name, meta));
+        let _ = tx.send(BackgroundResult::SnapshotCreated {
+            name,
+            success: result.is_ok(),
+            error: result.err().map(|e| e.to_string()),
+        });
+    });
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    Rect::new(x, y, width, height)
+}
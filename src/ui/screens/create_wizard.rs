@@ -10,29 +10,37 @@ use ratatui::{
     widgets::{Block, Borders, Clear, Paragraph, Wrap},
 };
 
-use crate::app::{App, WizardStep, WizardField, WizardQemuConfig};
+use crate::app::{App, CreateWizardState, WizardStep, WizardField, WizardQemuConfig};
+use crate::commands::qemu_img::Preallocation;
+use crate::commands::tap_setup::DEFAULT_TAP_NAME;
+use crate::metadata::qemu_profiles::{IsoSource, WIZARD_CATEGORIES};
 use crate::metadata::QemuProfileStore;
+use crate::ui::widgets::disk_tuning;
+use crate::vm::cloud_init::CloudInitConfig;
+use crate::vm::unattended::{UnattendedConfig, UnattendedKind};
 use crate::vm::create_vm;
+use crate::vm::sound_blaster::SB16_FIELDS;
 
 /// Parse a size string with optional suffix (KB, MB, GB, case-insensitive)
 /// Returns value normalized to target unit.
 ///
-/// For memory (target="MB"): "8GB" -> 8192, "8192" -> 8192
+/// For memory (target="MB"): "8GB" -> 8192, "4G" -> 4096, "8192" -> 8192
 /// For disk (target="GB"): "500GB" -> 500, "512000MB" -> 500
-fn parse_size_with_suffix(input: &str, target_unit: &str) -> Option<u32> {
+pub(crate) fn parse_size_with_suffix(input: &str, target_unit: &str) -> Option<u32> {
     let input = input.trim().to_uppercase();
     if input.is_empty() {
         return None;
     }
 
-    let (num_str, suffix) = if input.ends_with("GB") {
-        (&input[..input.len()-2], "GB")
-    } else if input.ends_with("MB") {
-        (&input[..input.len()-2], "MB")
-    } else if input.ends_with("KB") {
-        (&input[..input.len()-2], "KB")
-    } else {
-        (input.as_str(), target_unit)
+    // "G", "GB" and "GiB" all mean gigabytes
+    let split = input.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(input.len());
+    let (num_str, suffix) = input.split_at(split);
+    let suffix = match suffix {
+        "" => target_unit,
+        "G" | "GB" | "GIB" => "GB",
+        "M" | "MB" | "MIB" => "MB",
+        "K" | "KB" | "KIB" => "KB",
+        _ => return None,
     };
 
     let value: f64 = num_str.trim().parse().ok()?;
@@ -335,8 +343,8 @@ const BASE_PROFILE_OPTIONS: &[&str] = &[
 /// Render ISO download progress
 pub fn render_download(app: &App, frame: &mut Frame) {
     let area = frame.area();
-    let dialog_width = 60.min(area.width.saturating_sub(4));
-    let dialog_height = 10.min(area.height.saturating_sub(4));
+    let dialog_width = 70.min(area.width.saturating_sub(4));
+    let dialog_height = 11.min(area.height.saturating_sub(4));
 
     let dialog_area = centered_rect(dialog_width, dialog_height, area);
     frame.render_widget(Clear, dialog_area);
@@ -350,13 +358,19 @@ pub fn render_download(app: &App, frame: &mut Frame) {
     let inner = block.inner(dialog_area);
     frame.render_widget(block, dialog_area);
 
-    let progress = app.wizard_state.as_ref()
-        .map(|s| s.iso_download_progress)
-        .unwrap_or(0.0);
-
-    let text = Paragraph::new(format!("Downloading... {:.0}%\n\n[Esc] Cancel", progress * 100.0))
+    let Some(state) = app.wizard_state.as_ref() else {
+        return;
+    };
+    // The size isn't always known; then only the amount so far is shown
+    let percent = if state.iso_download_progress > 0.0 {
+        format!("{:.0}%\n", state.iso_download_progress * 100.0)
+    } else {
+        String::new()
+    };
+    let text = Paragraph::new(format!("\n{}{}\n\nA source that fails or stalls is skipped for the next mirror\n\n[Esc] Cancel", percent, state.iso_download_status))
         .style(Style::default().fg(Color::White))
-        .alignment(Alignment::Center);
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
     frame.render_widget(text, inner);
 }
 
@@ -497,13 +511,20 @@ pub fn handle_custom_os_key(app: &mut App, key: KeyEvent) -> Result<()> {
 
                     // Get profile settings
                     let profile_settings = app.qemu_profiles.get(&base_profile_id).cloned();
+                    let host = app.host_resources;
+                    let audio_backend = profile_settings.as_ref()
+                        .map(|p| app.preferred_audio_backend(&p.emulator));
 
                     // Now apply changes
                     if let Some(ref mut state) = app.wizard_state {
                         // Apply profile settings
                         if let Some(profile) = profile_settings {
                             state.qemu_config = WizardQemuConfig::from_profile(&profile);
+                            state.qemu_config.fit_to_host(&host);
                             state.disk_size_gb = profile.disk_size_gb;
+                            if let Some(backend) = audio_backend {
+                                state.qemu_config.audio_backend = backend;
+                            }
                         }
 
                         // Set VM name if empty
@@ -542,16 +563,8 @@ pub fn handle_custom_os_key(app: &mut App, key: KeyEvent) -> Result<()> {
 
 /// Handle key input for download screen
 pub fn handle_download_key(app: &mut App, key: KeyEvent) -> Result<()> {
-    match key.code {
-        KeyCode::Esc => {
-            // Cancel download
-            if let Some(ref mut state) = app.wizard_state {
-                state.iso_downloading = false;
-                state.iso_download_progress = 0.0;
-            }
-            app.pop_screen();
-        }
-        _ => {}
+    if key.code == KeyCode::Esc {
+        app.cancel_iso_download();
     }
     Ok(())
 }
@@ -586,10 +599,24 @@ fn render_step_select_os(app: &App, frame: &mut Frame, area: Rect) {
         ])
         .split(inner);
 
-    // OS list header
-    let header = Paragraph::new("Select Operating System:")
-        .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
-    frame.render_widget(header, chunks[0]);
+    // OS list header, with the filter box once there is a filter
+    let filter_editing = matches!(state.editing_field, Some(WizardField::OsFilter));
+    let title = "Select Operating System:";
+    let mut header_spans = vec![Span::styled(title, Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))];
+    if filter_editing || !state.os_filter.is_empty() {
+        let filter_style = if filter_editing {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default().fg(Color::White)
+        };
+        header_spans.push(Span::styled("  Filter: ", Style::default().fg(Color::Gray)));
+        header_spans.push(Span::styled(state.os_filter.as_str(), filter_style));
+    }
+    frame.render_widget(Paragraph::new(Line::from(header_spans)), chunks[0]);
+    if filter_editing {
+        let cursor_x = chunks[0].x + (title.len() + "  Filter: ".len() + state.os_filter.chars().count()) as u16;
+        frame.set_cursor_position((cursor_x, chunks[0].y));
+    }
 
     // OS list (grouped by category)
     render_os_list(app, frame, chunks[1]);
@@ -640,8 +667,12 @@ fn render_step_select_os(app: &App, frame: &mut Frame, area: Rect) {
     // Help text
     let help_text = if name_editing {
         "[Enter] Done editing  [Esc] Cancel"
+    } else if filter_editing {
+        "Type to filter  [Up/Down] Select OS  [Enter] Done  [Esc] Clear filter"
+    } else if !state.os_filter.is_empty() {
+        "[j/k] Select OS  [/] Edit filter  [Tab] Edit name  [Enter] Next  [Esc] Clear filter"
     } else {
-        "[j/k] Select OS  [Tab] Edit name  [Enter] Next  [Esc] Cancel"
+        "[j/k] Select OS  [/] Filter  [Tab] Edit name  [Enter] Next  [Esc] Cancel"
     };
     let help = Paragraph::new(help_text)
         .style(Style::default().fg(Color::DarkGray))
@@ -649,6 +680,47 @@ fn render_step_select_os(app: &App, frame: &mut Frame, area: Rect) {
     frame.render_widget(help, chunks[5]);
 }
 
+/// Rows of the OS list, in display order
+enum OsListItem {
+    Category(&'static str),
+    Os(String),
+    CustomOs,
+}
+
+/// Whether a profile matches the OS filter (by name or ID)
+fn matches_os_filter(filter: &str, os_id: &str, display_name: &str) -> bool {
+    let filter = filter.to_lowercase();
+    display_name.to_lowercase().contains(&filter) || os_id.to_lowercase().contains(&filter)
+}
+
+/// The OS list as currently shown
+///
+/// While a filter is typed only matching profiles are listed, with every
+/// category holding one expanded and the others hidden.
+fn os_list_items(app: &App) -> Vec<OsListItem> {
+    let state = app.wizard_state.as_ref().unwrap();
+    let filtering = !state.os_filter.is_empty();
+
+    let mut items = Vec::new();
+    for category in WIZARD_CATEGORIES {
+        let profiles: Vec<_> = app
+            .qemu_profiles
+            .list_by_category(category)
+            .into_iter()
+            .filter(|(os_id, profile)| !filtering || matches_os_filter(&state.os_filter, os_id, &profile.display_name))
+            .collect();
+        if profiles.is_empty() {
+            continue;
+        }
+        items.push(OsListItem::Category(category));
+        if filtering || state.is_category_expanded(category) {
+            items.extend(profiles.into_iter().map(|(os_id, _)| OsListItem::Os(os_id.clone())));
+        }
+    }
+    items.push(OsListItem::CustomOs);
+    items
+}
+
 fn render_os_list(app: &App, frame: &mut Frame, area: Rect) {
     let state = app.wizard_state.as_ref().unwrap();
 
@@ -661,55 +733,34 @@ fn render_os_list(app: &App, frame: &mut Frame, area: Rect) {
 
     // Build the list of items (categories and OSes)
     let mut lines: Vec<Line> = Vec::new();
-    let mut item_index = 0;
-
-    // Get categories in display order
-    let category_order = ["windows", "linux", "bsd", "unix", "macos", "mobile", "infrastructure", "utilities", "alternative", "retro", "classic-mac"];
+    let filtering = !state.os_filter.is_empty();
 
-    for category in &category_order {
-        let profiles = app.qemu_profiles.list_by_category(category);
-        if profiles.is_empty() {
-            continue;
-        }
-
-        let is_expanded = state.is_category_expanded(category);
+    for (item_index, item) in os_list_items(app).into_iter().enumerate() {
         let is_selected = item_index == state.os_list_selected;
-
-        // Category header
-        let expand_icon = if is_expanded { "v" } else { ">" };
-        let category_name = QemuProfileStore::category_display_name(category);
-        let category_style = if is_selected {
-            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
-        } else {
-            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
-        };
-
         let prefix = if is_selected { "> " } else { "  " };
-        lines.push(Line::from(vec![
-            Span::styled(prefix, category_style),
-            Span::styled(expand_icon, category_style),
-            Span::styled(format!(" {}", category_name), category_style),
-        ]));
-
-        item_index += 1;
-
-        // OS items (if expanded)
-        if is_expanded {
-            for (os_id, profile) in &profiles {
-                // Filter by search query
-                if !state.os_filter.is_empty() {
-                    let filter_lower = state.os_filter.to_lowercase();
-                    if !profile.display_name.to_lowercase().contains(&filter_lower)
-                        && !os_id.to_lowercase().contains(&filter_lower)
-                    {
-                        continue;
-                    }
-                }
+        match item {
+            OsListItem::Category(category) => {
+                let expand_icon = if filtering || state.is_category_expanded(category) { "v" } else { ">" };
+                let category_name = QemuProfileStore::category_display_name(category);
+                let category_style = if is_selected {
+                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+                };
 
-                let is_os_selected = item_index == state.os_list_selected;
-                let is_chosen = state.selected_os.as_ref() == Some(*os_id);
+                lines.push(Line::from(vec![
+                    Span::styled(prefix, category_style),
+                    Span::styled(expand_icon, category_style),
+                    Span::styled(format!(" {}", category_name), category_style),
+                ]));
+            }
+            OsListItem::Os(os_id) => {
+                let Some(profile) = app.qemu_profiles.get(&os_id) else {
+                    continue;
+                };
+                let is_chosen = state.selected_os.as_ref() == Some(&os_id);
 
-                let os_style = if is_os_selected {
+                let os_style = if is_selected {
                     Style::default().fg(Color::Yellow)
                 } else if is_chosen {
                     Style::default().fg(Color::Green)
@@ -717,36 +768,32 @@ fn render_os_list(app: &App, frame: &mut Frame, area: Rect) {
                     Style::default().fg(Color::White)
                 };
 
-                let prefix = if is_os_selected { "> " } else { "  " };
                 let chosen_marker = if is_chosen { "*" } else { " " };
                 let summary = profile.summary();
 
                 lines.push(Line::from(vec![
                     Span::styled(prefix, os_style),
                     Span::styled(format!("   {}", chosen_marker), os_style),
-                    Span::styled(format!("{}", profile.display_name), os_style),
+                    Span::styled(profile.display_name.clone(), os_style),
                     Span::styled(format!("  ({})", summary), Style::default().fg(Color::DarkGray)),
                 ]));
-
-                item_index += 1;
+            }
+            OsListItem::CustomOs => {
+                // "Custom OS" option at the end
+                let custom_style = if is_selected {
+                    Style::default().fg(Color::Yellow)
+                } else {
+                    Style::default().fg(Color::Magenta)
+                };
+                lines.push(Line::from(vec![
+                    Span::styled(prefix, custom_style),
+                    Span::styled("   Custom OS...", custom_style),
+                    Span::styled("  (Define your own)", Style::default().fg(Color::DarkGray)),
+                ]));
             }
         }
     }
 
-    // Add "Custom OS" option at the end
-    let is_custom_selected = item_index == state.os_list_selected;
-    let custom_style = if is_custom_selected {
-        Style::default().fg(Color::Yellow)
-    } else {
-        Style::default().fg(Color::Magenta)
-    };
-    let prefix = if is_custom_selected { "> " } else { "  " };
-    lines.push(Line::from(vec![
-        Span::styled(prefix, custom_style),
-        Span::styled("   Custom OS...", custom_style),
-        Span::styled("  (Define your own)", Style::default().fg(Color::DarkGray)),
-    ]));
-
     // Calculate scroll offset
     let visible_height = inner.height as usize;
     let scroll_offset = if state.os_list_selected >= visible_height {
@@ -770,8 +817,42 @@ fn handle_step_select_os(app: &mut App, key: KeyEvent) -> Result<()> {
     let editing_name = app.wizard_state.as_ref()
         .map(|s| matches!(s.editing_field, Some(WizardField::VmName)))
         .unwrap_or(false);
+    let editing_filter = app.wizard_state.as_ref()
+        .map(|s| matches!(s.editing_field, Some(WizardField::OsFilter)))
+        .unwrap_or(false);
 
-    if editing_name {
+    if editing_filter {
+        // Text input mode for the OS filter; the list follows each key
+        let Some(ref mut state) = app.wizard_state else {
+            return Ok(());
+        };
+        match key.code {
+            KeyCode::Enter | KeyCode::Tab => state.editing_field = None,
+            KeyCode::Esc => {
+                state.editing_field = None;
+                state.os_filter.clear();
+                state.os_list_selected = 0;
+            }
+            KeyCode::Char(c) => {
+                state.os_filter.push(c);
+                select_first_os(app);
+            }
+            KeyCode::Backspace => {
+                state.os_filter.pop();
+                select_first_os(app);
+            }
+            KeyCode::Down => {
+                let total = count_os_list_items(app);
+                if let Some(ref mut state) = app.wizard_state {
+                    if state.os_list_selected < total.saturating_sub(1) {
+                        state.os_list_selected += 1;
+                    }
+                }
+            }
+            KeyCode::Up => state.os_list_selected = state.os_list_selected.saturating_sub(1),
+            _ => {}
+        }
+    } else if editing_name {
         // Text input mode for VM name
         match key.code {
             KeyCode::Esc | KeyCode::Enter | KeyCode::Tab => {
@@ -795,9 +876,21 @@ fn handle_step_select_os(app: &mut App, key: KeyEvent) -> Result<()> {
     } else {
         // Normal navigation mode
         match key.code {
+            // Esc clears an active filter before it cancels
+            KeyCode::Esc if app.wizard_state.as_ref().is_some_and(|s| !s.os_filter.is_empty()) => {
+                if let Some(ref mut state) = app.wizard_state {
+                    state.os_filter.clear();
+                    state.os_list_selected = 0;
+                }
+            }
             KeyCode::Esc => {
                 app.cancel_wizard();
             }
+            KeyCode::Char('/') => {
+                if let Some(ref mut state) = app.wizard_state {
+                    state.editing_field = Some(WizardField::OsFilter);
+                }
+            }
             KeyCode::Tab => {
                 // Toggle to name editing
                 if let Some(ref mut state) = app.wizard_state {
@@ -835,95 +928,38 @@ fn handle_step_select_os(app: &mut App, key: KeyEvent) -> Result<()> {
     Ok(())
 }
 
+/// Move the selection to the first OS shown (after the filter changed)
+fn select_first_os(app: &mut App) {
+    let first = os_list_items(app)
+        .iter()
+        .position(|item| matches!(item, OsListItem::Os(_)))
+        .unwrap_or(0);
+    if let Some(ref mut state) = app.wizard_state {
+        state.os_list_selected = first;
+    }
+}
+
 /// Count total items in the OS list (categories + visible OSes + custom)
 fn count_os_list_items(app: &App) -> usize {
-    let state = app.wizard_state.as_ref().unwrap();
-    let category_order = ["windows", "linux", "bsd", "unix", "macos", "mobile", "infrastructure", "utilities", "alternative", "retro", "classic-mac"];
-
-    let mut count = 0;
-    for category in &category_order {
-        let profiles = app.qemu_profiles.list_by_category(category);
-        if profiles.is_empty() {
-            continue;
-        }
-        count += 1; // Category header
-        if state.is_category_expanded(category) {
-            // Count visible profiles (with filter)
-            for (os_id, profile) in &profiles {
-                if !state.os_filter.is_empty() {
-                    let filter_lower = state.os_filter.to_lowercase();
-                    if !profile.display_name.to_lowercase().contains(&filter_lower)
-                        && !os_id.to_lowercase().contains(&filter_lower)
-                    {
-                        continue;
-                    }
-                }
-                count += 1;
-            }
-        }
-    }
-    count += 1; // Custom OS option
-    count
+    os_list_items(app).len()
 }
 
 /// Handle action on OS list item (space to toggle, enter to select and proceed)
 fn handle_os_list_action(app: &mut App, proceed: bool) {
-    // First, collect all the information we need without holding borrows
     let Some(ref state) = app.wizard_state else {
         return;
     };
     let selected = state.os_list_selected;
-    let os_filter = state.os_filter.clone();
-    let expanded_categories: Vec<String> = state.expanded_categories.clone();
-
-    let category_order = ["windows", "linux", "bsd", "unix", "macos", "mobile", "infrastructure", "utilities", "alternative", "retro", "classic-mac"];
-
-    let mut item_index = 0;
-    let mut action: Option<OsListAction> = None;
-
-    for category in &category_order {
-        let profiles = app.qemu_profiles.list_by_category(category);
-        if profiles.is_empty() {
-            continue;
-        }
-
-        // Category header
-        if item_index == selected {
-            action = Some(OsListAction::ToggleCategory(category.to_string()));
-            break;
-        }
-        item_index += 1;
-
-        // OS items (if expanded)
-        let is_expanded = expanded_categories.iter().any(|c| c == *category);
-        if is_expanded {
-            for (os_id, profile) in &profiles {
-                if !os_filter.is_empty() {
-                    let filter_lower = os_filter.to_lowercase();
-                    if !profile.display_name.to_lowercase().contains(&filter_lower)
-                        && !os_id.to_lowercase().contains(&filter_lower)
-                    {
-                        continue;
-                    }
-                }
-
-                if item_index == selected {
-                    action = Some(OsListAction::SelectOs(os_id.to_string()));
-                    break;
-                }
-                item_index += 1;
-            }
-        }
-
-        if action.is_some() {
-            break;
-        }
-    }
-
-    // Check if custom OS was selected (at the end)
-    if action.is_none() && item_index == selected {
-        action = Some(OsListAction::CustomOs);
-    }
+    let filtering = !state.os_filter.is_empty();
+
+    let action = match os_list_items(app).into_iter().nth(selected) {
+        // Categories stay open while filtering
+        Some(OsListItem::Category(_)) if filtering => None,
+        Some(OsListItem::Category(category)) => Some(OsListAction::ToggleCategory(category.to_string())),
+        Some(OsListItem::Os(os_id)) => Some(OsListAction::SelectOs(os_id)),
+        Some(OsListItem::CustomOs) => Some(OsListAction::CustomOs),
+        None => None,
+    };
 
     // Now execute the action
     match action {
@@ -1001,46 +1037,32 @@ fn render_step_select_iso(app: &App, frame: &mut Frame, area: Rect) {
     frame.render_widget(header, chunks[2]);
 
     // Options
-    let mut lines = Vec::new();
-
-    // Check if this OS has a free ISO URL
-    let has_download = state.selected_os.as_ref()
-        .and_then(|id| app.qemu_profiles.get(id))
-        .and_then(|p| p.iso_url.as_ref())
-        .is_some();
-
-    let mut option_idx = 0;
-
-    if has_download {
-        let is_selected = state.field_focus == option_idx;
-        let style = if is_selected {
-            Style::default().fg(Color::Yellow)
-        } else {
-            Style::default().fg(Color::White)
-        };
-        let prefix = if is_selected { "> " } else { "  " };
-        lines.push(Line::styled(format!("{}( ) Open download page in browser", prefix), style));
-        option_idx += 1;
-    }
-
-    let is_browse_selected = state.field_focus == option_idx;
-    let browse_style = if is_browse_selected {
-        Style::default().fg(Color::Yellow)
-    } else {
-        Style::default().fg(Color::White)
-    };
-    let browse_prefix = if is_browse_selected { "> " } else { "  " };
-    lines.push(Line::styled(format!("{}( ) Browse for local ISO file...", browse_prefix), browse_style));
-    option_idx += 1;
-
-    let is_none_selected = state.field_focus == option_idx;
-    let none_style = if is_none_selected {
-        Style::default().fg(Color::Yellow)
-    } else {
-        Style::default().fg(Color::White)
-    };
-    let none_prefix = if is_none_selected { "> " } else { "  " };
-    lines.push(Line::styled(format!("{}( ) No ISO (configure later)", none_prefix), none_style));
+    let lines: Vec<Line> = iso_options(app)
+        .iter()
+        .enumerate()
+        .map(|(i, option)| {
+            let is_selected = state.field_focus == i;
+            let style = if is_selected {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            let prefix = if is_selected { "> " } else { "  " };
+            let label = match option {
+                IsoOption::Download(source) => {
+                    let urls = source.urls().len();
+                    match urls {
+                        0 | 1 => format!("Download {}", source.name),
+                        _ => format!("Download {} ({} sources)", source.name, urls),
+                    }
+                }
+                IsoOption::OpenPage(_) => "Open download page in browser".to_string(),
+                IsoOption::Browse => "Browse for local ISO file...".to_string(),
+                IsoOption::NoIso => "No ISO (configure later)".to_string(),
+            };
+            Line::styled(format!("{}( ) {}", prefix, label), style)
+        })
+        .collect();
 
     let options = Paragraph::new(lines);
     frame.render_widget(options, chunks[3]);
@@ -1059,14 +1081,33 @@ fn render_step_select_iso(app: &App, frame: &mut Frame, area: Rect) {
     frame.render_widget(help, chunks[5]);
 }
 
-fn handle_step_select_iso(app: &mut App, key: KeyEvent) -> Result<()> {
-    let has_download = app.wizard_state.as_ref()
+/// A choice in the wizard's ISO step
+enum IsoOption {
+    /// Download one of the profile's images (falling back to its mirrors)
+    Download(IsoSource),
+    OpenPage(String),
+    Browse,
+    NoIso,
+}
+
+/// The ISO step's choices for the selected OS
+fn iso_options(app: &App) -> Vec<IsoOption> {
+    let profile = app.wizard_state.as_ref()
         .and_then(|s| s.selected_os.as_ref())
-        .and_then(|id| app.qemu_profiles.get(id))
-        .and_then(|p| p.iso_url.as_ref())
-        .is_some();
+        .and_then(|id| app.qemu_profiles.get(id));
+    let mut options: Vec<IsoOption> = profile
+        .map(|p| p.iso_sources.iter().filter(|s| s.file_name().is_some()).cloned().map(IsoOption::Download).collect())
+        .unwrap_or_default();
+    if let Some(url) = profile.and_then(|p| p.iso_url.clone()) {
+        options.push(IsoOption::OpenPage(url));
+    }
+    options.push(IsoOption::Browse);
+    options.push(IsoOption::NoIso);
+    options
+}
 
-    let max_options = if has_download { 3 } else { 2 };
+fn handle_step_select_iso(app: &mut App, key: KeyEvent) -> Result<()> {
+    let mut options = iso_options(app);
 
     match key.code {
         KeyCode::Esc => {
@@ -1074,7 +1115,7 @@ fn handle_step_select_iso(app: &mut App, key: KeyEvent) -> Result<()> {
         }
         KeyCode::Char('j') | KeyCode::Down => {
             if let Some(ref mut state) = app.wizard_state {
-                if state.field_focus < max_options - 1 {
+                if state.field_focus + 1 < options.len() {
                     state.field_focus += 1;
                 }
             }
@@ -1088,38 +1129,30 @@ fn handle_step_select_iso(app: &mut App, key: KeyEvent) -> Result<()> {
         }
         KeyCode::Enter => {
             let focus = app.wizard_state.as_ref().map(|s| s.field_focus).unwrap_or(0);
-            let option_offset = if has_download { 0 } else { 1 };
-
-            match focus + option_offset {
-                0 => {
-                    // Open download page in browser
-                    if let Some(url) = app.wizard_state.as_ref()
-                        .and_then(|s| s.selected_os.as_ref())
-                        .and_then(|id| app.qemu_profiles.get(id))
-                        .and_then(|p| p.iso_url.as_ref())
-                    {
-                        // Try to open in browser
-                        let url = url.clone();
-                        if let Err(e) = open_url_in_browser(&url) {
-                            app.set_status(format!("Failed to open browser: {}", e));
-                        } else {
-                            app.set_status("Opened download page in browser. Use 'Browse for ISO' after downloading.");
-                        }
+            if focus >= options.len() {
+                return Ok(());
+            }
+            match options.swap_remove(focus) {
+                IsoOption::Download(source) => app.start_iso_download(source),
+                IsoOption::OpenPage(url) => {
+                    // Try to open in browser
+                    if let Err(e) = open_url_in_browser(&url) {
+                        app.set_status(format!("Failed to open browser: {}", e));
+                    } else {
+                        app.set_status("Opened download page in browser. Use 'Browse for ISO' after downloading.");
                     }
                 }
-                1 => {
+                IsoOption::Browse => {
                     // Browse for ISO - open file browser
                     app.load_file_browser(crate::app::FileBrowserMode::Iso);
                     app.push_screen(crate::app::Screen::FileBrowser);
                 }
-                2 => {
-                    // No ISO
+                IsoOption::NoIso => {
                     if let Some(ref mut state) = app.wizard_state {
                         state.iso_path = None;
                     }
                     let _ = app.wizard_next_step();
                 }
-                _ => {}
             }
         }
         _ => {}
@@ -1205,21 +1238,35 @@ fn render_step_configure_disk(app: &App, frame: &mut Frame, area: Rect) {
 
     // Help
     let help_text = if state.use_existing_disk {
-        if state.field_focus == 0 {
+        if state.editing_field.is_some() {
+            "[Enter] Done  [Backspace] Delete  [Esc] Cancel"
+        } else if state.field_focus == 0 {
             "[←/→] Toggle mode  [j/k] Navigate  [Enter] Next  [Esc] Back"
         } else if state.field_focus == 1 {
             "[Enter] Browse  [j/k] Navigate  [Esc] Back"
-        } else {
+        } else if state.field_focus == 2 {
             "[←/→] Toggle action  [j/k] Navigate  [Enter] Next  [Esc] Back"
+        } else if state.field_focus == 3 {
+            "[←/→] Toggle cloud-init  [j/k] Navigate  [Enter] Next  [Esc] Back"
+        } else {
+            "[Tab] Edit  [j/k] Navigate  [Enter] Next  [Esc] Back"
         }
     } else {
         let editing = matches!(state.editing_field, Some(WizardField::DiskSize));
         if editing {
             "[Enter] Done  [Backspace] Delete  [0-9] Enter size"
+        } else if state.editing_field.is_some() {
+            "[Enter] Done  [Backspace] Delete  [Esc] Cancel"
         } else if state.field_focus == 0 {
             "[←/→] Toggle mode  [j/k] Navigate  [Enter] Next  [Esc] Back"
-        } else {
+        } else if state.field_focus == 1 {
             "[Tab] Edit size  [←/→] Adjust  [Enter] Next  [Esc] Back"
+        } else if (TUNING_FOCUS..UNATTENDED_FOCUS).contains(&state.field_focus) {
+            "[←/→] Change  [j/k] Navigate  [Enter] Next  [Esc] Back"
+        } else if state.field_focus == UNATTENDED_FOCUS {
+            "[←/→] Toggle unattended install  [j/k] Navigate  [Enter] Next  [Esc] Back"
+        } else {
+            "[Tab] Edit  [j/k] Navigate  [Enter] Next  [Esc] Back"
         }
     };
     let help = Paragraph::new(help_text)
@@ -1228,6 +1275,11 @@ fn render_step_configure_disk(app: &App, frame: &mut Frame, area: Rect) {
     frame.render_widget(help, chunks[5]);
 }
 
+/// First of the qcow2 tuning rows in "Create New" mode (one per `disk_tuning::LABELS`)
+const TUNING_FOCUS: usize = 2;
+/// Unattended install toggle in "Create New" mode, followed by its three fields
+const UNATTENDED_FOCUS: usize = TUNING_FOCUS + disk_tuning::LABELS.len();
+
 /// Render the "Create New" disk mode content
 fn render_new_disk_mode(app: &App, frame: &mut Frame, area: Rect) {
     let state = app.wizard_state.as_ref().unwrap();
@@ -1237,7 +1289,11 @@ fn render_new_disk_mode(app: &App, frame: &mut Frame, area: Rect) {
         .constraints([
             Constraint::Length(3),   // Disk size input
             Constraint::Length(1),   // Spacer
-            Constraint::Min(5),      // Disk info
+            Constraint::Length(5),   // qcow2 tuning and hint
+            Constraint::Length(1),   // Spacer
+            Constraint::Length(5),   // Disk info
+            Constraint::Length(1),   // Spacer
+            Constraint::Min(4),      // Unattended install
         ])
         .split(area);
 
@@ -1278,6 +1334,22 @@ fn render_new_disk_mode(app: &App, frame: &mut Frame, area: Rect) {
         .block(size_block);
     frame.render_widget(size_text, sub_chunks[0]);
 
+    // qcow2 tuning (field_focus TUNING_FOCUS..UNATTENDED_FOCUS)
+    let tuning = disk_tuning::values(&state.disk_tuning, "qcow2");
+    let mut tuning_lines: Vec<Line> = disk_tuning::LABELS
+        .iter()
+        .zip(tuning)
+        .enumerate()
+        .map(|(i, (label, value))| render_field_line(label, &value, state.field_focus == TUNING_FOCUS + i, false, ""))
+        .collect();
+    if (TUNING_FOCUS..UNATTENDED_FOCUS).contains(&state.field_focus) {
+        tuning_lines.push(Line::from(Span::styled(
+            format!("  {}", disk_tuning::hint(state.field_focus - TUNING_FOCUS)),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    frame.render_widget(Paragraph::new(tuning_lines), sub_chunks[2]);
+
     // Disk info box
     let info_block = Block::default()
         .title(" Disk Info ")
@@ -1296,7 +1368,11 @@ fn render_new_disk_mode(app: &App, frame: &mut Frame, area: Rect) {
         ]),
         Line::from(vec![
             Span::styled("Type: ", Style::default().fg(Color::Yellow)),
-            Span::raw("Expandable (only uses space as needed)"),
+            Span::raw(match state.disk_tuning.preallocation {
+                Preallocation::Off | Preallocation::Metadata => "Expandable (only uses space as needed)",
+                Preallocation::Falloc => "Full size reserved up front (not written)",
+                Preallocation::Full => "Full size written out up front",
+            }),
         ]),
         Line::from(vec![
             Span::styled("Location: ", Style::default().fg(Color::Yellow)),
@@ -1307,7 +1383,51 @@ fn render_new_disk_mode(app: &App, frame: &mut Frame, area: Rect) {
     let info = Paragraph::new(info_text)
         .block(info_block)
         .wrap(Wrap { trim: false });
-    frame.render_widget(info, sub_chunks[2]);
+    frame.render_widget(info, sub_chunks[4]);
+
+    if unattended_available(state) {
+        render_unattended_fields(state, frame, sub_chunks[6]);
+    }
+}
+
+/// Render the unattended install toggle and its fields (field_focus UNATTENDED_FOCUS and the three after it)
+fn render_unattended_fields(state: &crate::app::CreateWizardState, frame: &mut Frame, area: Rect) {
+    let mut lines = vec![render_toggle_line("Unattended", state.unattended.is_some(), state.field_focus == UNATTENDED_FOCUS)];
+
+    if let Some(ref unattended) = state.unattended {
+        let password = if unattended.password.is_empty() {
+            "(required)".to_string()
+        } else {
+            "*".repeat(unattended.password.len())
+        };
+        let fields = [
+            (UNATTENDED_FOCUS + 1, "Hostname", WizardField::UnattendedHostname, unattended.hostname.clone()),
+            (UNATTENDED_FOCUS + 2, "Username", WizardField::UnattendedUsername, unattended.username.clone()),
+            (UNATTENDED_FOCUS + 3, "Password", WizardField::UnattendedPassword, password),
+        ];
+        for (focus, label, field, value) in fields {
+            let editing = state.editing_field.as_ref() == Some(&field);
+            let value = if !editing {
+                value
+            } else if field == WizardField::UnattendedPassword {
+                format!("{}|", "*".repeat(state.wizard_edit_buffer.len()))
+            } else {
+                format!("{}|", state.wizard_edit_buffer)
+            };
+            lines.push(render_field_line(label, &value, state.field_focus == focus, editing, "  [Tab] Edit"));
+        }
+    } else {
+        let kind = state.selected_os.as_deref()
+            .and_then(UnattendedKind::for_profile)
+            .map(|k| k.label())
+            .unwrap_or("Answer file");
+        lines.push(Line::from(Span::styled(
+            format!("  {} install: no prompts, boots straight into the installed system", kind),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), area);
 }
 
 /// Render the "Use Existing" disk mode content
@@ -1323,7 +1443,9 @@ fn render_existing_disk_mode(app: &App, frame: &mut Frame, area: Rect) {
             Constraint::Length(1),   // Spacer
             Constraint::Length(1),   // Action toggle
             Constraint::Length(1),   // Spacer
-            Constraint::Min(3),      // Note
+            Constraint::Length(1),   // Note
+            Constraint::Length(1),   // Spacer
+            Constraint::Min(5),      // Cloud-init seed
         ])
         .split(area);
 
@@ -1343,7 +1465,15 @@ fn render_existing_disk_mode(app: &App, frame: &mut Frame, area: Rect) {
     let browse_inner = browse_block.inner(sub_chunks[0]);
     frame.render_widget(browse_block, sub_chunks[0]);
 
-    let browse_text = if let Some(ref path) = state.existing_disk_path {
+    let url_editing = state.editing_field == Some(WizardField::RemoteDiskUrl);
+    let browse_text = if url_editing {
+        Paragraph::new(format!("> URL: {}|", state.wizard_edit_buffer)).style(Style::default().fg(Color::Yellow))
+    } else if let Some(ref url) = state.remote_disk_url {
+        let display = crate::vm::remote_disk::parse_remote(url)
+            .map(|r| format!("{} (remote {})", r.display_url(), r.protocol.label()))
+            .unwrap_or_else(|| url.clone());
+        Paragraph::new(display).style(Style::default().fg(Color::Cyan))
+    } else if let Some(ref path) = state.existing_disk_path {
         let path_str = path.display().to_string();
         // Truncate path if too long
         let max_len = browse_inner.width as usize - 2;
@@ -1355,7 +1485,7 @@ fn render_existing_disk_mode(app: &App, frame: &mut Frame, area: Rect) {
         Paragraph::new(display).style(Style::default().fg(Color::Green))
     } else {
         let prefix = if browse_focused { "> " } else { "  " };
-        Paragraph::new(format!("{}( ) Browse for qcow2 disk file...", prefix))
+        Paragraph::new(format!("{}( ) Browse for disk image (qcow2/vmdk/vhd/raw)... [Tab] NBD/iSCSI URL", prefix))
             .style(if browse_focused {
                 Style::default().fg(Color::Yellow)
             } else {
@@ -1376,6 +1506,11 @@ fn render_existing_disk_mode(app: &App, frame: &mut Frame, area: Rect) {
     } else {
         Style::default().fg(Color::DarkGray)
     };
+    let convert_style = if matches!(state.existing_disk_action, DiskAction::Convert) {
+        Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(Color::DarkGray)
+    };
     let prefix = if action_focused { "> " } else { "  " };
 
     let action_line = Line::from(vec![
@@ -1385,19 +1520,166 @@ fn render_existing_disk_mode(app: &App, frame: &mut Frame, area: Rect) {
         Span::styled("Copy to VM folder", copy_style),
         Span::styled(" ] [ ", Style::default()),
         Span::styled("Move to VM folder", move_style),
+        Span::styled(" ] [ ", Style::default()),
+        Span::styled("Convert to qcow2", convert_style),
         Span::styled(" ]", Style::default()),
     ]);
     let action_toggle = Paragraph::new(action_line);
     frame.render_widget(action_toggle, sub_chunks[2]);
 
     // Note about renaming
-    let note_text = format!(
-        "Note: The disk will be renamed to {}.qcow2",
-        state.folder_name
-    );
+    let note_text = if state.remote_disk_url.is_some() {
+        "Note: The remote disk stays on its server (raw format, never copied)".to_string()
+    } else if state.existing_disk_action == DiskAction::Convert {
+        format!("Note: The disk will be converted to {}.qcow2 (original kept)", state.folder_name)
+    } else {
+        let ext = state
+            .existing_disk_path
+            .as_ref()
+            .and_then(|p| p.extension())
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_else(|| "qcow2".to_string());
+        format!("Note: The disk will be renamed to {}.{}", state.folder_name, ext)
+    };
     let note = Paragraph::new(note_text)
         .style(Style::default().fg(Color::DarkGray));
     frame.render_widget(note, sub_chunks[4]);
+
+    let detected = if state.inspecting_disk {
+        Some(("Detecting the installed OS...".to_string(), Color::DarkGray))
+    } else {
+        state.detected_os.as_ref().map(|os| (format!("Detected: {}", os.label()), Color::Cyan))
+    };
+    if let Some((text, color)) = detected {
+        frame.render_widget(Paragraph::new(text).style(Style::default().fg(color)), sub_chunks[5]);
+    }
+
+    render_cloud_init_fields(state, frame, sub_chunks[6]);
+}
+
+/// Render the cloud-init seed toggle and its fields (field_focus 3..=7)
+fn render_cloud_init_fields(state: &crate::app::CreateWizardState, frame: &mut Frame, area: Rect) {
+    let mut lines = vec![render_toggle_line("Cloud-init", state.cloud_init.is_some(), state.field_focus == 3)];
+
+    if let Some(ref cloud_init) = state.cloud_init {
+        let ssh_key = cloud_init.ssh_key_path
+            .as_ref()
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|| "(none)".to_string());
+        let password = if cloud_init.password.is_empty() {
+            "(key only)".to_string()
+        } else {
+            "*".repeat(cloud_init.password.len())
+        };
+        let fields = [
+            (4, "Hostname", WizardField::CloudInitHostname, cloud_init.hostname.clone()),
+            (5, "Username", WizardField::CloudInitUsername, cloud_init.username.clone()),
+            (6, "Password", WizardField::CloudInitPassword, password),
+            (7, "SSH key", WizardField::CloudInitSshKey, ssh_key),
+        ];
+        for (focus, label, field, value) in fields {
+            let editing = state.editing_field.as_ref() == Some(&field);
+            let value = if editing {
+                if field == WizardField::CloudInitPassword {
+                    format!("{}|", "*".repeat(state.wizard_edit_buffer.len()))
+                } else {
+                    format!("{}|", state.wizard_edit_buffer)
+                }
+            } else {
+                value
+            };
+            lines.push(render_field_line(label, &value, state.field_focus == focus, editing, "  [Tab] Edit"));
+        }
+    } else {
+        lines.push(Line::from(Span::styled(
+            "  Enable for cloud images: builds a NoCloud seed ISO with user, password, and SSH key",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), area);
+}
+
+/// Whether the selected OS and ISO allow a kickstart/preseed install
+fn unattended_available(state: &crate::app::CreateWizardState) -> bool {
+    state.iso_path.is_some()
+        && state.selected_os.as_deref().and_then(UnattendedKind::for_profile).is_some()
+}
+
+/// Map a ConfigureDisk focus index to its editable text field
+fn disk_text_field_for_focus(use_existing: bool, focus: usize) -> Option<WizardField> {
+    match (use_existing, focus) {
+        (true, 4) => Some(WizardField::CloudInitHostname),
+        (true, 5) => Some(WizardField::CloudInitUsername),
+        (true, 6) => Some(WizardField::CloudInitPassword),
+        (true, 7) => Some(WizardField::CloudInitSshKey),
+        (true, 1) => Some(WizardField::RemoteDiskUrl),
+        (false, f) if f == UNATTENDED_FOCUS + 1 => Some(WizardField::UnattendedHostname),
+        (false, f) if f == UNATTENDED_FOCUS + 2 => Some(WizardField::UnattendedUsername),
+        (false, f) if f == UNATTENDED_FOCUS + 3 => Some(WizardField::UnattendedPassword),
+        _ => None,
+    }
+}
+
+/// Current value of a ConfigureDisk text field (None if its section is disabled)
+fn disk_text_field_value(state: &crate::app::CreateWizardState, field: &WizardField) -> Option<String> {
+    match field {
+        WizardField::CloudInitHostname => state.cloud_init.as_ref().map(|c| c.hostname.clone()),
+        WizardField::CloudInitUsername => state.cloud_init.as_ref().map(|c| c.username.clone()),
+        WizardField::CloudInitPassword => state.cloud_init.as_ref().map(|c| c.password.clone()),
+        WizardField::CloudInitSshKey => state.cloud_init.as_ref().map(|c| {
+            c.ssh_key_path
+                .as_ref()
+                .map(|p| p.display().to_string())
+                .unwrap_or_default()
+        }),
+        WizardField::UnattendedHostname => state.unattended.as_ref().map(|u| u.hostname.clone()),
+        WizardField::UnattendedUsername => state.unattended.as_ref().map(|u| u.username.clone()),
+        WizardField::UnattendedPassword => state.unattended.as_ref().map(|u| u.password.clone()),
+        WizardField::RemoteDiskUrl => Some(state.remote_disk_url.clone().unwrap_or_default()),
+        _ => None,
+    }
+}
+
+/// Store an edited ConfigureDisk text field
+fn apply_disk_text_field(state: &mut crate::app::CreateWizardState, field: &WizardField, value: String) {
+    if *field == WizardField::RemoteDiskUrl {
+        let value = value.trim();
+        state.remote_disk_url = (!value.is_empty()).then(|| value.to_string());
+        if state.remote_disk_url.is_some() {
+            state.existing_disk_path = None;
+            state.detected_os = None;
+            state.inspecting_disk = false;
+        }
+        return;
+    }
+    if let Some(ref mut cloud_init) = state.cloud_init {
+        match field {
+            WizardField::CloudInitHostname => cloud_init.hostname = value.trim().to_string(),
+            WizardField::CloudInitUsername => cloud_init.username = value.trim().to_string(),
+            WizardField::CloudInitPassword => cloud_init.password = value.clone(),
+            WizardField::CloudInitSshKey => {
+                let value = value.trim();
+                // Expand ~ to home directory
+                cloud_init.ssh_key_path = if value.is_empty() {
+                    None
+                } else if let (Some(rest), Some(home)) = (value.strip_prefix("~/"), dirs::home_dir()) {
+                    Some(home.join(rest))
+                } else {
+                    Some(std::path::PathBuf::from(value))
+                };
+            }
+            _ => {}
+        }
+    }
+    if let Some(ref mut unattended) = state.unattended {
+        match field {
+            WizardField::UnattendedHostname => unattended.hostname = value.trim().to_string(),
+            WizardField::UnattendedUsername => unattended.username = value.trim().to_string(),
+            WizardField::UnattendedPassword => unattended.password = value,
+            _ => {}
+        }
+    }
 }
 
 fn handle_step_configure_disk(app: &mut App, key: KeyEvent) -> Result<()> {
@@ -1411,6 +1693,32 @@ fn handle_step_configure_disk(app: &mut App, key: KeyEvent) -> Result<()> {
         ))
         .unwrap_or((false, false, 0));
 
+    // Handle cloud-init / unattended install text field editing
+    let text_editing = app.wizard_state.as_ref()
+        .and_then(|s| s.editing_field.clone())
+        .filter(|f| f != &WizardField::DiskSize);
+    if let Some(field) = text_editing {
+        if let Some(ref mut state) = app.wizard_state {
+            match key.code {
+                KeyCode::Esc => {
+                    state.editing_field = None;
+                    state.wizard_edit_buffer.clear();
+                }
+                KeyCode::Enter | KeyCode::Tab => {
+                    let value = std::mem::take(&mut state.wizard_edit_buffer);
+                    apply_disk_text_field(state, &field, value);
+                    state.editing_field = None;
+                }
+                KeyCode::Char(c) => state.wizard_edit_buffer.push(c),
+                KeyCode::Backspace => {
+                    state.wizard_edit_buffer.pop();
+                }
+                _ => {}
+            }
+        }
+        return Ok(());
+    }
+
     // Handle disk size editing mode (only in "Create New" mode)
     if editing && !use_existing {
         match key.code {
@@ -1467,7 +1775,13 @@ fn handle_step_configure_disk(app: &mut App, key: KeyEvent) -> Result<()> {
         }
         KeyCode::Char('j') | KeyCode::Down => {
             if let Some(ref mut state) = app.wizard_state {
-                let max_focus = if state.use_existing_disk { 2 } else { 1 };
+                let max_focus = match (state.use_existing_disk, state.cloud_init.is_some()) {
+                    (true, true) => 7,
+                    (true, false) => 3,
+                    (false, _) if state.unattended.is_some() => UNATTENDED_FOCUS + 3,
+                    (false, _) if unattended_available(state) => UNATTENDED_FOCUS,
+                    (false, _) => UNATTENDED_FOCUS - 1,
+                };
                 if state.field_focus < max_focus {
                     state.field_focus += 1;
                 }
@@ -1495,11 +1809,32 @@ fn handle_step_configure_disk(app: &mut App, key: KeyEvent) -> Result<()> {
                             state.disk_size_gb = (state.disk_size_gb + 8).min(10000);
                         }
                     }
+                    f if !state.use_existing_disk && (TUNING_FOCUS..UNATTENDED_FOCUS).contains(&f) => {
+                        let back = key.code == KeyCode::Left;
+                        disk_tuning::change(&mut state.disk_tuning, "qcow2", f - TUNING_FOCUS, back);
+                    }
+                    f if !state.use_existing_disk && f == UNATTENDED_FOCUS => {
+                        // Toggle unattended install
+                        state.unattended = match state.unattended {
+                            Some(_) => None,
+                            None => state.selected_os.as_deref().and_then(|os| {
+                                UnattendedConfig::for_profile(os, &state.folder_name)
+                            }),
+                        };
+                    }
                     2 if state.use_existing_disk => {
                         // Toggle copy/move action
                         state.existing_disk_action = match state.existing_disk_action {
                             DiskAction::Copy => DiskAction::Move,
-                            DiskAction::Move => DiskAction::Copy,
+                            DiskAction::Move => DiskAction::Convert,
+                            DiskAction::Convert => DiskAction::Copy,
+                        };
+                    }
+                    3 if state.use_existing_disk => {
+                        // Toggle cloud-init seed generation
+                        state.cloud_init = match state.cloud_init {
+                            Some(_) => None,
+                            None => Some(CloudInitConfig::new(&state.folder_name)),
                         };
                     }
                     _ => {}
@@ -1513,6 +1848,13 @@ fn handle_step_configure_disk(app: &mut App, key: KeyEvent) -> Result<()> {
                     state.editing_field = Some(WizardField::DiskSize);
                     state.wizard_edit_buffer = state.disk_size_gb.to_string();
                 }
+            } else if let Some(field) = disk_text_field_for_focus(use_existing, field_focus) {
+                if let Some(ref mut state) = app.wizard_state {
+                    if let Some(value) = disk_text_field_value(state, &field) {
+                        state.wizard_edit_buffer = value;
+                        state.editing_field = Some(field);
+                    }
+                }
             }
         }
         KeyCode::Enter => {
@@ -1543,32 +1885,46 @@ const VGA_OPTIONS: &[&str] = &["std", "virtio", "qxl", "cirrus", "vmware", "none
 const NETWORK_OPTIONS: &[&str] = &["virtio", "e1000", "rtl8139", "ne2k_pci", "pcnet", "none"];
 const DISK_INTERFACE_OPTIONS: &[&str] = &["virtio", "ide", "sata", "scsi"];
 const DISPLAY_OPTIONS: &[&str] = &["gtk", "sdl", "spice-app", "vnc", "none"];
+const USB_CONTROLLER_OPTIONS: &[&str] = &["none", "uhci", "ehci", "xhci"];
 const AUDIO_OPTIONS: &[(&str, &[&str])] = &[
     ("Intel HDA", &["intel-hda", "hda-duplex"]),
     ("AC97", &["ac97"]),
     ("Sound Blaster 16", &["sb16"]),
+    ("SB16 + AdLib", &["sb16", "adlib"]),
+    ("Gravis Ultrasound", &["gus"]),
+    ("SB16 + AdLib + GUS", &["sb16", "adlib", "gus"]),
     ("None", &[]),
 ];
+const MIDI_OPTIONS: &[&str] = &["none", "fluidsynth", "mt32"];
 
 /// Fields in the QEMU config screen
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum QemuField {
     Memory,
     CpuCores,
+    Machine,
     Vga,
+    Resolution,
     Audio,
+    /// Sound Blaster 16 resource (index into `SB16_FIELDS`)
+    Sb16(usize),
+    AudioBackend,
+    Midi,
     Network,
     NetBackend,
     BridgeName,
     PortForwards,
     DiskInterface,
     Display,
+    UsbController,
     Kvm,
     GlAccel,
     Uefi,
+    SecureBoot,
     Tpm,
     UsbTablet,
     RtcLocal,
+    GuestAgent,
 }
 
 impl QemuField {
@@ -1576,25 +1932,33 @@ impl QemuField {
         match idx {
             0 => Self::Memory,
             1 => Self::CpuCores,
-            2 => Self::Vga,
-            3 => Self::Audio,
-            4 => Self::Network,
-            5 => Self::NetBackend,
-            6 => Self::BridgeName,
-            7 => Self::PortForwards,
-            8 => Self::DiskInterface,
-            9 => Self::Display,
-            10 => Self::Kvm,
-            11 => Self::GlAccel,
-            12 => Self::Uefi,
-            13 => Self::Tpm,
-            14 => Self::UsbTablet,
-            _ => Self::RtcLocal,
+            2 => Self::Machine,
+            3 => Self::Vga,
+            4 => Self::Resolution,
+            5 => Self::Audio,
+            6..=9 => Self::Sb16(idx - 6),
+            10 => Self::AudioBackend,
+            11 => Self::Midi,
+            12 => Self::Network,
+            13 => Self::NetBackend,
+            14 => Self::BridgeName,
+            15 => Self::PortForwards,
+            16 => Self::DiskInterface,
+            17 => Self::Display,
+            18 => Self::UsbController,
+            19 => Self::Kvm,
+            20 => Self::GlAccel,
+            21 => Self::Uefi,
+            22 => Self::SecureBoot,
+            23 => Self::Tpm,
+            24 => Self::UsbTablet,
+            25 => Self::RtcLocal,
+            _ => Self::GuestAgent,
         }
     }
 
     fn count() -> usize {
-        16
+        27
     }
 }
 
@@ -1636,7 +2000,15 @@ fn render_step_configure_qemu(app: &App, frame: &mut Frame, area: Rect) {
         .split(h_chunks[1]);
 
     // Left side: Settings header
-    let header = Paragraph::new("QEMU Settings")
+    let backend = state.qemu_config.backend;
+    let header_text = if backend == crate::vm::backend::Backend::Qemu {
+        "QEMU Settings".to_string()
+    } else {
+        // Other emulators take memory and devices from the profile; the rest is
+        // set in their own settings dialog after creation
+        format!("{} VM - memory applies, other settings are made in {}", backend.label(), backend.label())
+    };
+    let header = Paragraph::new(header_text)
         .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
     frame.render_widget(header, left_chunks[0]);
 
@@ -1655,7 +2027,7 @@ fn render_step_configure_qemu(app: &App, frame: &mut Frame, area: Rect) {
         format!("{} MB", config.memory_mb)
     };
     let mem_hint = if mem_editing {
-        "[Enter] Done  [Esc] Cancel"
+        "e.g. 512, 512M, 4G  [Enter] Done  [Esc] Cancel"
     } else if mem_selected {
         "[Tab] Edit  [←/→] ±256MB"
     } else {
@@ -1668,6 +2040,15 @@ fn render_step_configure_qemu(app: &App, frame: &mut Frame, area: Rect) {
         mem_editing,
         mem_hint,
     ));
+    if let (true, Some(error)) = (mem_editing, state.error_message.as_ref()) {
+        lines.push(Line::styled(format!("  ⚠ {}", error), Style::default().fg(Color::Red)));
+    }
+    if let Some(warning) = app.host_resources.memory_warning(config.memory_mb) {
+        lines.push(Line::styled(
+            format!("  ⚠ {}", warning),
+            Style::default().fg(Color::Red),
+        ));
+    }
 
     // CPU Cores (editable)
     let cpu_selected = focus == 1;
@@ -1691,9 +2072,22 @@ fn render_step_configure_qemu(app: &App, frame: &mut Frame, area: Rect) {
         cpu_editing,
         cpu_hint,
     ));
+    if let (true, Some(error)) = (cpu_editing, state.error_message.as_ref()) {
+        lines.push(Line::styled(format!("  ⚠ {}", error), Style::default().fg(Color::Red)));
+    }
+
+    // Machine type (cycle)
+    let machine_selected = focus == 2;
+    lines.push(render_field_line(
+        "Machine:",
+        config.machine.as_deref().unwrap_or("(default)"),
+        machine_selected,
+        false,
+        "[←/→] cycle",
+    ));
 
     // VGA (cycle)
-    let vga_selected = focus == 2;
+    let vga_selected = focus == 3;
     lines.push(render_field_line(
         "Graphics:",
         &config.vga,
@@ -1702,8 +2096,23 @@ fn render_step_configure_qemu(app: &App, frame: &mut Frame, area: Rect) {
         "[←/→] cycle",
     ));
 
+    // Resolution (cycle) - only std and qxl accept EDID/xres/yres
+    let res_selected = focus == 4;
+    let res_display = match config.resolution.as_deref() {
+        Some(res) if supports_resolution(&config.vga) => res.to_string(),
+        Some(res) => format!("{} (n/a)", res),
+        None => "auto".to_string(),
+    };
+    lines.push(render_field_line(
+        "Resolution:",
+        &res_display,
+        res_selected,
+        false,
+        "[←/→] cycle",
+    ));
+
     // Audio (cycle)
-    let audio_selected = focus == 3;
+    let audio_selected = focus == 5;
     let audio_label = get_audio_label(&config.audio);
     lines.push(render_field_line(
         "Audio:",
@@ -1713,8 +2122,44 @@ fn render_step_configure_qemu(app: &App, frame: &mut Frame, area: Rect) {
         "[←/→] cycle",
     ));
 
+    // Sound Blaster 16 resources (cycle) - only with an SB16
+    if config.audio.iter().any(|a| a == "sb16") {
+        for (i, label) in SB16_FIELDS.iter().enumerate() {
+            lines.push(render_field_line(
+                &format!("{}:", label),
+                &config.sb16.field_value(i),
+                focus == 6 + i,
+                false,
+                "[←/→] cycle",
+            ));
+        }
+    }
+
+    // Host audio backend (cycle)
+    let audio_backend_selected = focus == 10;
+    lines.push(render_field_line(
+        "Audio Out:",
+        crate::hardware::audio::backend_label(&config.audio_backend),
+        audio_backend_selected,
+        false,
+        "[←/→] cycle",
+    ));
+
+    // Serial MIDI synth (cycle)
+    let midi_selected = focus == 11;
+    let midi_display = crate::vm::midi::find_synth(&config.midi_synth)
+        .map(|synth| synth.name)
+        .unwrap_or("none");
+    lines.push(render_field_line(
+        "MIDI Out:",
+        midi_display,
+        midi_selected,
+        false,
+        "[←/→] cycle",
+    ));
+
     // Network adapter (cycle)
-    let net_selected = focus == 4;
+    let net_selected = focus == 12;
     lines.push(render_field_line(
         "Network:",
         &config.network_model,
@@ -1725,11 +2170,12 @@ fn render_step_configure_qemu(app: &App, frame: &mut Frame, area: Rect) {
 
     // Network backend (cycle) - hidden if network model is "none"
     if config.network_model != "none" {
-        let backend_selected = focus == 5;
+        let backend_selected = focus == 13;
         let backend_display = match config.network_backend.as_str() {
             "user" => "user/SLIRP (NAT)".to_string(),
             "passt" => "passt".to_string(),
             "bridge" => format!("bridge ({})", config.bridge_name.as_deref().unwrap_or("qemubr0")),
+            "tap" => format!("tap ({})", config.tap_name.as_deref().unwrap_or(DEFAULT_TAP_NAME)),
             "none" => "none".to_string(),
             other => other.to_string(),
         };
@@ -1743,7 +2189,7 @@ fn render_step_configure_qemu(app: &App, frame: &mut Frame, area: Rect) {
 
         // Bridge name (only for bridge backend)
         if config.network_backend == "bridge" {
-            let bridge_selected = focus == 6;
+            let bridge_selected = focus == 14;
             let bridge_display = config.bridge_name.as_deref().unwrap_or("qemubr0");
             lines.push(render_field_line(
                 "Bridge:",
@@ -1754,9 +2200,20 @@ fn render_step_configure_qemu(app: &App, frame: &mut Frame, area: Rect) {
             ));
         }
 
+        // Tap device (only for tap backend, shares the bridge field's slot)
+        if config.network_backend == "tap" {
+            lines.push(render_field_line(
+                "TAP:",
+                config.tap_name.as_deref().unwrap_or(DEFAULT_TAP_NAME),
+                focus == 14,
+                false,
+                "[←/→] cycle",
+            ));
+        }
+
         // Port forwards (only for user/passt)
         if config.network_backend == "user" || config.network_backend == "passt" {
-            let pf_selected = focus == 7;
+            let pf_selected = focus == 15;
             let pf_display = if config.port_forwards.is_empty() {
                 "none".to_string()
             } else {
@@ -1773,7 +2230,7 @@ fn render_step_configure_qemu(app: &App, frame: &mut Frame, area: Rect) {
     }
 
     // Disk Interface (cycle)
-    let disk_selected = focus == 8;
+    let disk_selected = focus == 16;
     lines.push(render_field_line(
         "Disk I/F:",
         &config.disk_interface,
@@ -1783,7 +2240,7 @@ fn render_step_configure_qemu(app: &App, frame: &mut Frame, area: Rect) {
     ));
 
     // Display (cycle)
-    let disp_selected = focus == 9;
+    let disp_selected = focus == 17;
     lines.push(render_field_line(
         "Display:",
         &config.display,
@@ -1792,33 +2249,51 @@ fn render_step_configure_qemu(app: &App, frame: &mut Frame, area: Rect) {
         "[←/→] cycle",
     ));
 
+    // USB controller (cycle)
+    let usb_ctrl_selected = focus == 18;
+    lines.push(render_field_line(
+        "USB Ctrl:",
+        &config.usb_controller,
+        usb_ctrl_selected,
+        false,
+        "[←/→] cycle",
+    ));
+
     lines.push(Line::from(""));
     lines.push(Line::styled("  Features (toggle with Space):", Style::default().fg(Color::DarkGray)));
 
     // KVM toggle
-    let kvm_selected = focus == 10;
+    let kvm_selected = focus == 19;
     lines.push(render_toggle_line("KVM Accel:", config.enable_kvm, kvm_selected));
 
     // 3D/GL acceleration toggle
-    let gl_selected = focus == 11;
+    let gl_selected = focus == 20;
     lines.push(render_toggle_line("3D Accel:", config.gl_acceleration, gl_selected));
 
     // UEFI toggle
-    let uefi_selected = focus == 12;
+    let uefi_selected = focus == 21;
     lines.push(render_toggle_line("UEFI Boot:", config.uefi, uefi_selected));
 
+    // Secure Boot toggle (only meaningful with UEFI)
+    let secboot_selected = focus == 22;
+    lines.push(render_toggle_line("Secure Boot:", config.uefi && config.secure_boot, secboot_selected));
+
     // TPM toggle
-    let tpm_selected = focus == 13;
+    let tpm_selected = focus == 23;
     lines.push(render_toggle_line("TPM 2.0:", config.tpm, tpm_selected));
 
     // USB Tablet toggle
-    let usb_selected = focus == 14;
+    let usb_selected = focus == 24;
     lines.push(render_toggle_line("USB Tablet:", config.usb_tablet, usb_selected));
 
     // RTC Local toggle
-    let rtc_selected = focus == 15;
+    let rtc_selected = focus == 25;
     lines.push(render_toggle_line("RTC Local:", config.rtc_localtime, rtc_selected));
 
+    // Guest agent toggle
+    let agent_selected = focus == 26;
+    lines.push(render_toggle_line("Guest Agent:", config.guest_agent, agent_selected));
+
     let settings = Paragraph::new(lines);
     frame.render_widget(settings, left_chunks[1]);
 
@@ -1843,8 +2318,29 @@ fn render_step_configure_qemu(app: &App, frame: &mut Frame, area: Rect) {
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::DarkGray));
 
-    let notes_inner = notes_block.inner(right_chunks[1]);
-    frame.render_widget(notes_block, right_chunks[1]);
+    // Warnings about the settings take the bottom of the notes panel
+    let warnings = profile_warnings(app);
+    let notes_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(8),
+            Constraint::Length(if warnings.is_empty() { 0 } else { warnings.len() as u16 * 2 + 2 }),
+        ])
+        .split(right_chunks[1]);
+    if !warnings.is_empty() {
+        let lines: Vec<Line> = warnings
+            .iter()
+            .map(|warning| Line::styled(format!("⚠ {}", warning), Style::default().fg(Color::Red)))
+            .collect();
+        let warnings_block = Block::default()
+            .title(" Check before creating ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Red));
+        frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }).block(warnings_block), notes_chunks[1]);
+    }
+
+    let notes_inner = notes_block.inner(notes_chunks[0]);
+    frame.render_widget(notes_block, notes_chunks[0]);
 
     // Build notes based on selected field and profile
     let notes_text = get_field_notes(app, focus);
@@ -1892,8 +2388,8 @@ fn render_toggle_line(label: &str, enabled: bool, selected: bool) -> Line<'stati
 }
 
 fn get_audio_label(audio: &[String]) -> &'static str {
-    if audio.is_empty() {
-        "None"
+    if let Some((label, _)) = AUDIO_OPTIONS.iter().find(|(_, devices)| devices.iter().eq(audio.iter())) {
+        label
     } else if audio.iter().any(|a| a.contains("intel-hda")) {
         "Intel HDA"
     } else if audio.iter().any(|a| a.contains("ac97")) {
@@ -1912,6 +2408,8 @@ fn get_field_notes(app: &App, focus: usize) -> String {
 
     let field = QemuField::from_index(focus);
 
+    let host = &app.host_resources;
+
     let explanation = match field {
         QemuField::Memory => format!(
             "RAM for {}.\n\n\
@@ -1919,16 +2417,38 @@ fn get_field_notes(app: &App, focus: usize) -> String {
             Windows 95: max 480MB\n\
             Windows 98/ME: max 512MB\n\
             Windows XP: 512MB-1GB\n\
-            Linux GUI: 2GB minimum",
-            os_name
+            Linux GUI: 2GB minimum\n\n\
+            This host: {} MB total (max {} MB for VMs){}\n\n\
+            Type a value with Tab: 512, 512M, 4G",
+            os_name, host.total_memory_mb, host.max_memory_mb(),
+            profile.and_then(|p| p.max_memory_mb).map(|max| format!("\n{}: max {} MB", os_name, max)).unwrap_or_default()
         ),
         QemuField::CpuCores => format!(
             "CPU cores for {}.\n\n\
             More cores = faster for multi-threaded tasks.\n\n\
             Old OSes (pre-2000) may not support multiple CPUs.\n\
-            Don't exceed your host's core count.",
-            os_name
+            Don't exceed your host's core count.\n\n\
+            This host: {} cores{}",
+            os_name, host.max_cpu_cores(),
+            profile.and_then(|p| p.max_cpu_cores).map(|max| format!("\n{}: max {}", os_name, max)).unwrap_or_default()
         ),
+        QemuField::Machine => {
+            let config = app.wizard_state.as_ref().map(|s| &s.qemu_config);
+            let about = match config.and_then(|c| c.machine.as_deref().map(|id| (c.emulator.as_str(), id))) {
+                Some((emulator, id)) => match crate::vm::machine::find_machine(emulator, id) {
+                    Some(m) => format!("{}: {}\n\n{}", m.id, m.name, m.notes),
+                    None => format!("{}: not in the curated list.", id),
+                },
+                None => "QEMU's default board for this emulator.".to_string(),
+            };
+            format!(
+                "Emulated board (-machine) for {}.\n\n\
+                {}\n\n\
+                Windows 9x/NT/XP need pc (i440FX);\n\
+                DOS on 286/386 suits isapc.",
+                os_name, about
+            )
+        }
         QemuField::Vga => format!(
             "Graphics adapter for {}.\n\n\
             std: Safe, universal\n\
@@ -1939,6 +2459,17 @@ fn get_field_notes(app: &App, focus: usize) -> String {
             none: Headless server",
             os_name
         ),
+        QemuField::Resolution => format!(
+            "Boot resolution for {}.\n\n\
+            Advertised to the guest via EDID so it comes up \
+            in an era-correct mode.\n\n\
+            Win 3.x/95: 640x480 or 800x600\n\
+            Win 98/2000: 800x600 or 1024x768\n\
+            Win XP: 1024x768\n\
+            auto: QEMU default\n\n\
+            Only applies to std and qxl graphics.",
+            os_name
+        ),
         QemuField::Audio => format!(
             "Audio device for {}.\n\n\
             Intel HDA: Modern (Win Vista+)\n\
@@ -1947,6 +2478,36 @@ fn get_field_notes(app: &App, focus: usize) -> String {
             None: Server/headless",
             os_name
         ),
+        QemuField::AudioBackend => "Host audio output (-audiodev).\n\n\
+            Only sound servers found on\n\
+            this host are offered.\n\n\
+            PipeWire: Modern desktops\n\
+            PulseAudio: Older desktops\n\
+            JACK: Pro-audio setups\n\
+            ALSA: No sound server\n\
+            None: Guest audio is muted".to_string(),
+        QemuField::Sb16(_) => {
+            let sb16 = app.wizard_state.as_ref().map(|s| s.qemu_config.sb16).unwrap_or_default();
+            format!(
+                "Sound Blaster 16 resources.\n\n\
+                DOS games only find the card at\n\
+                the port, IRQ and DMA they expect.\n\
+                Add this to AUTOEXEC.BAT:\n\n\
+                SET BLASTER={}\n\n\
+                Defaults (220h, IRQ 5, DMA 1/5)\n\
+                suit nearly every game. IRQ 7 is\n\
+                shared with LPT1 and a GUS.",
+                sb16.blaster()
+            )
+        }
+        QemuField::Midi => "MIDI music output.\n\n\
+            QEMU has no MPU-401, so MIDI goes out\n\
+            of the guest's COM1 to a host synth.\n\n\
+            DOS: load SoftMPU with /OUTPUT:COM1\n\
+            Win 3.x/9x: serial MIDI driver\n\n\
+            FluidSynth: General MIDI (SoundFont)\n\
+            MT-32: munt's mt32d (needs ROMs)\n\n\
+            Host needs: sudo modprobe snd-virmidi".to_string(),
         QemuField::Network => format!(
             "Network adapter for {}.\n\n\
             virtio: Best perf (needs driver)\n\
@@ -1961,9 +2522,27 @@ fn get_field_notes(app: &App, focus: usize) -> String {
             user: NAT via SLIRP (default)\n  Works everywhere, no setup needed\n\n\
             passt: Fast NAT, ping works\n  Requires passt package\n\n\
             bridge: Full network access\n  VM gets own IP on LAN\n  One-time setup needed\n\n\
+            tap: Persistent tap device, no helper\n  Bridged to the LAN or routed with NAT\n  One-time setup needed\n\n\
             none: No networking",
             os_name
         ),
+        QemuField::BridgeName if app.wizard_state.as_ref().is_some_and(|s| s.qemu_config.network_backend == "tap") => {
+            let taps = &app.network_caps.tap_devices;
+            let taps_str = if taps.is_empty() {
+                "No tap devices on the system yet.".to_string()
+            } else {
+                format!("Existing: {}", taps.iter().map(|t| t.name.as_str()).collect::<Vec<_>>().join(", "))
+            };
+            format!(
+                "Tap device for {}.\n\n\
+                {}\n\n\
+                QEMU opens the device directly, so it must exist and \
+                be owned by you. Network Settings ([s]) can define it \
+                for NetworkManager or systemd-networkd after creation, \
+                bridged to the LAN or routed with nftables NAT.",
+                os_name, taps_str
+            )
+        }
         QemuField::BridgeName => {
             let bridges = &app.network_caps.system_bridges;
             let bridges_str = if bridges.is_empty() {
@@ -1976,7 +2555,8 @@ fn get_field_notes(app: &App, focus: usize) -> String {
                 {}\n\n\
                 The VM will get its own IP on the bridge network, \
                 providing full LAN access.\n\n\
-                Requires qemu-bridge-helper with proper permissions.",
+                Requires qemu-bridge-helper with proper permissions. \
+                Network Settings ([s]) can set it up after creation.",
                 os_name, bridges_str
             )
         },
@@ -2006,6 +2586,12 @@ fn get_field_notes(app: &App, focus: usize) -> String {
             none: Headless, no graphical output",
             os_name
         ),
+        QemuField::UsbController => "USB host controller.\n\n\
+            none: No USB (DOS, Windows 9x without drivers)\n\
+            uhci: USB 1.1, for Windows 98 to XP era guests\n\
+            ehci: USB 2.0, for Vista and Windows 7\n\
+            xhci: USB 3.x, for Windows 8+ and modern Linux\n\n\
+            USB passthrough devices attach to this controller.".to_string(),
         QemuField::Kvm => "KVM hardware acceleration.\n\n\
             Enables near-native speed using CPU virtualization.\n\n\
             Requires: Linux host with Intel VT-x or AMD-V.\n\
@@ -2023,6 +2609,13 @@ fn get_field_notes(app: &App, focus: usize) -> String {
             Incompatible: DOS, Win 9x, old systems",
             os_name
         ),
+        QemuField::SecureBoot => "UEFI Secure Boot.\n\n\
+            Boots with Secure Boot firmware and a per-VM copy\n\
+            of its variables (with Microsoft keys pre-enrolled\n\
+            where your distro ships them).\n\n\
+            Recommended: Windows 11\n\
+            Disable: Unsigned kernels or bootloaders\n\
+            Turning it on also enables UEFI.".to_string(),
         QemuField::Tpm => "TPM 2.0 emulation.\n\n\
             Trusted Platform Module for security features.\n\n\
             Required: Windows 11\n\
@@ -2036,6 +2629,12 @@ fn get_field_notes(app: &App, focus: usize) -> String {
             Sets hardware clock to local timezone.\n\n\
             Enable: Windows (expects local time)\n\
             Disable: Linux/Unix (expects UTC)".to_string(),
+        QemuField::GuestAgent => "QEMU guest agent channel.\n\n\
+            Adds a virtio-serial port for qemu-guest-agent.\n\
+            While running, shows guest IPs and OS in the\n\
+            info panel and enables clean shutdown.\n\n\
+            Requires: qemu-guest-agent installed in guest\n\
+            Not for: Guests without virtio drivers".to_string(),
     };
 
     if profile_notes.is_empty() {
@@ -2045,6 +2644,30 @@ fn get_field_notes(app: &App, focus: usize) -> String {
     }
 }
 
+/// The wizard's settings checked against the selected OS profile
+fn profile_warnings(app: &App) -> Vec<String> {
+    match (app.wizard_selected_profile(), app.wizard_state.as_ref()) {
+        (Some(profile), Some(state)) => crate::vm::profile_check::check(profile, &state.qemu_config),
+        _ => Vec::new(),
+    }
+}
+
+/// Check the chosen devices against the selected emulator's QEMU build
+fn wizard_device_warning(state: &CreateWizardState) -> Option<String> {
+    if state.qemu_config.backend != crate::vm::backend::Backend::Qemu {
+        return None;
+    }
+    let script = crate::vm::create::generate_launch_script_with_os(
+        &state.vm_name,
+        "disk.qcow2",
+        state.iso_path.as_deref(),
+        &state.qemu_config,
+        state.selected_os.as_deref(),
+    );
+    let issues = crate::vm::validation::validate_script(&state.qemu_config.emulator, &script);
+    crate::vm::validation::summarize(&issues)
+}
+
 fn handle_step_configure_qemu(app: &mut App, key: KeyEvent) -> Result<()> {
     let field_count = QemuField::count();
 
@@ -2069,30 +2692,39 @@ fn handle_step_configure_qemu(app: &mut App, key: KeyEvent) -> Result<()> {
                 if let Some(ref mut state) = app.wizard_state {
                     state.editing_field = None;
                     state.wizard_edit_buffer.clear();
+                    state.error_message = None;
                 }
             }
             KeyCode::Enter | KeyCode::Tab => {
-                // Apply the edit
+                // Apply the edit; a value that doesn't fit stays in the box
+                let host = app.host_resources;
+                let profile = app.wizard_selected_profile().cloned();
                 if let Some(ref mut state) = app.wizard_state {
                     let buffer = state.wizard_edit_buffer.clone();
-                    if editing_memory {
+                    let result = if editing_memory {
                         // Parse with suffix support (target: MB)
-                        if let Some(value) = parse_size_with_suffix(&buffer, "MB") {
-                            let clamped = value.max(128).min(1048576);
-                            state.qemu_config.memory_mb = clamped;
-                        }
-                    } else if editing_cpu {
+                        parse_size_with_suffix(&buffer, "MB")
+                            .ok_or_else(|| "Enter a size such as 512, 512M or 4G".to_string())
+                            .and_then(|value| memory_limit(&host, profile.as_ref()).check(value))
+                            .map(|value| state.qemu_config.memory_mb = value)
+                    } else {
                         // Parse as plain number
-                        if let Ok(value) = buffer.trim().parse::<u32>() {
-                            let clamped = value.max(1).min(256);
-                            state.qemu_config.cpu_cores = clamped;
+                        buffer.trim().parse::<u32>()
+                            .map_err(|_| "Enter a number of CPUs".to_string())
+                            .and_then(|value| cpu_limit(&host, profile.as_ref()).check(value))
+                            .map(|value| state.qemu_config.cpu_cores = value)
+                    };
+                    match result {
+                        Ok(()) => {
+                            state.editing_field = None;
+                            state.wizard_edit_buffer.clear();
+                            state.error_message = None;
                         }
+                        Err(e) => state.error_message = Some(e),
                     }
-                    state.editing_field = None;
-                    state.wizard_edit_buffer.clear();
                 }
             }
-            KeyCode::Char(c) if c.is_ascii_alphanumeric() => {
+            KeyCode::Char(c) if c.is_ascii_alphanumeric() || c == '.' => {
                 if let Some(ref mut state) = app.wizard_state {
                     state.wizard_edit_buffer.push(c);
                 }
@@ -2133,8 +2765,11 @@ fn handle_step_configure_qemu(app: &mut App, key: KeyEvent) -> Result<()> {
                 app.wizard_editing_port_forwards = true;
                 app.wizard_pf_selected = 0;
                 app.wizard_adding_pf = None;
-            } else {
-                let _ = app.wizard_next_step();
+            } else if app.wizard_next_step().is_ok() {
+                // Warn (without blocking) about devices the installed QEMU lacks
+                if let Some(warning) = app.wizard_state.as_ref().and_then(wizard_device_warning) {
+                    app.set_status(format!("Warning: {}", warning));
+                }
             }
         }
         KeyCode::Tab => {
@@ -2200,9 +2835,26 @@ fn handle_step_configure_qemu(app: &mut App, key: KeyEvent) -> Result<()> {
                         }
                     }
                     QemuField::Uefi => state.qemu_config.uefi = !state.qemu_config.uefi,
+                    QemuField::SecureBoot => {
+                        let enable = !(state.qemu_config.uefi && state.qemu_config.secure_boot);
+                        state.qemu_config.secure_boot = enable;
+                        // Secure Boot is a UEFI feature
+                        if enable {
+                            state.qemu_config.uefi = true;
+                        }
+                    }
                     QemuField::Tpm => state.qemu_config.tpm = !state.qemu_config.tpm,
-                    QemuField::UsbTablet => state.qemu_config.usb_tablet = !state.qemu_config.usb_tablet,
+                    QemuField::UsbTablet => {
+                        state.qemu_config.usb_tablet = !state.qemu_config.usb_tablet;
+                        // The tablet needs a controller; pick the machine's usual one
+                        if state.qemu_config.usb_tablet && state.qemu_config.usb_controller == "none" {
+                            let machine = state.qemu_config.machine.as_deref();
+                            state.qemu_config.usb_controller =
+                                crate::metadata::qemu_profiles::default_usb_controller(machine).to_string();
+                        }
+                    }
                     QemuField::RtcLocal => state.qemu_config.rtc_localtime = !state.qemu_config.rtc_localtime,
+                    QemuField::GuestAgent => state.qemu_config.guest_agent = !state.qemu_config.guest_agent,
                     _ => {}
                 }
             }
@@ -2210,8 +2862,12 @@ fn handle_step_configure_qemu(app: &mut App, key: KeyEvent) -> Result<()> {
         KeyCode::Char('r') | KeyCode::Char('R') => {
             // Reset to profile defaults
             if let Some(profile) = app.wizard_selected_profile().cloned() {
+                let host = app.host_resources;
+                let audio_backend = app.preferred_audio_backend(&profile.emulator);
                 if let Some(ref mut state) = app.wizard_state {
                     state.qemu_config = WizardQemuConfig::from_profile(&profile);
+                    state.qemu_config.fit_to_host(&host);
+                    state.qemu_config.audio_backend = audio_backend;
                 }
             }
         }
@@ -2354,6 +3010,7 @@ fn handle_qemu_field_change(app: &mut App, delta: i32) {
         .map(|s| s.qemu_config.emulator.clone())
         .unwrap_or_else(|| "qemu-system-x86_64".to_string());
     let dynamic_display_options = app.get_display_options_for_emulator(&emulator);
+    let audio_backend_options = app.get_audio_backend_options(&emulator);
 
     // Collect network backend options before mutable borrow
     let backend_options: Vec<String> = app.get_network_backend_options()
@@ -2361,8 +3018,11 @@ fn handle_qemu_field_change(app: &mut App, delta: i32) {
         .map(|(id, _)| id.to_string())
         .collect();
     let system_bridges = app.network_caps.system_bridges.clone();
+    let host = app.host_resources;
+    let profile = app.wizard_selected_profile().cloned();
     let default_bridge = system_bridges.first().cloned()
         .or_else(|| Some("qemubr0".to_string()));
+    let tap_names = crate::commands::tap_setup::tap_name_options(&app.network_caps.tap_devices);
 
     let Some(ref mut state) = app.wizard_state else { return };
     let field = QemuField::from_index(state.field_focus);
@@ -2370,19 +3030,44 @@ fn handle_qemu_field_change(app: &mut App, delta: i32) {
     match field {
         QemuField::Memory => {
             let change = 256 * delta;
-            let new_val = (state.qemu_config.memory_mb as i32 + change).max(128).min(1048576);
+            let max = memory_limit(&host, profile.as_ref()).max;
+            let new_val = (state.qemu_config.memory_mb as i32 + change).max(128).min(max as i32);
             state.qemu_config.memory_mb = new_val as u32;
         }
         QemuField::CpuCores => {
-            let new_val = (state.qemu_config.cpu_cores as i32 + delta).max(1).min(256);
+            let max = cpu_limit(&host, profile.as_ref()).max;
+            let new_val = (state.qemu_config.cpu_cores as i32 + delta).max(1).min(max as i32);
             state.qemu_config.cpu_cores = new_val as u32;
         }
+        QemuField::Machine => {
+            let options = crate::vm::machine::machine_options(&emulator, state.qemu_config.machine.as_deref());
+            let option_refs: Vec<&str> = options.iter().map(String::as_str).collect();
+            match state.qemu_config.machine {
+                Some(ref mut current) => cycle_option(current, &option_refs, delta),
+                None => state.qemu_config.machine = options.first().cloned(),
+            }
+        }
         QemuField::Vga => {
             cycle_option(&mut state.qemu_config.vga, VGA_OPTIONS, delta);
         }
+        QemuField::Resolution => {
+            let mut current = state.qemu_config.resolution.clone().unwrap_or_else(|| "auto".to_string());
+            cycle_option(&mut current, crate::vm::display_preset::RESOLUTION_PRESETS, delta);
+            state.qemu_config.resolution = if current == "auto" { None } else { Some(current) };
+        }
         QemuField::Audio => {
             cycle_audio(&mut state.qemu_config.audio, delta);
         }
+        QemuField::Sb16(field) => {
+            state.qemu_config.sb16.cycle(field, delta);
+        }
+        QemuField::Midi => {
+            cycle_option(&mut state.qemu_config.midi_synth, MIDI_OPTIONS, delta);
+        }
+        QemuField::AudioBackend => {
+            let audio_strs: Vec<&str> = audio_backend_options.iter().map(|s| s.as_str()).collect();
+            cycle_option(&mut state.qemu_config.audio_backend, &audio_strs, delta);
+        }
         QemuField::Network => {
             cycle_option(&mut state.qemu_config.network_model, NETWORK_OPTIONS, delta);
         }
@@ -2394,6 +3079,15 @@ fn handle_qemu_field_change(app: &mut App, delta: i32) {
             if state.qemu_config.network_backend == "bridge" && state.qemu_config.bridge_name.is_none() {
                 state.qemu_config.bridge_name = default_bridge.clone();
             }
+            if state.qemu_config.network_backend == "tap" && state.qemu_config.tap_name.is_none() {
+                state.qemu_config.tap_name = tap_names.first().cloned();
+            }
+        }
+        QemuField::BridgeName if state.qemu_config.network_backend == "tap" => {
+            let mut current = state.qemu_config.tap_name.clone().unwrap_or_else(|| DEFAULT_TAP_NAME.to_string());
+            let names: Vec<&str> = tap_names.iter().map(String::as_str).collect();
+            cycle_option(&mut current, &names, delta);
+            state.qemu_config.tap_name = Some(current);
         }
         QemuField::BridgeName => {
             // Cycle through available system bridges
@@ -2422,11 +3116,76 @@ fn handle_qemu_field_change(app: &mut App, delta: i32) {
                 cycle_option(&mut state.qemu_config.display, DISPLAY_OPTIONS, delta);
             }
         }
+        QemuField::UsbController => {
+            cycle_option(&mut state.qemu_config.usb_controller, USB_CONTROLLER_OPTIONS, delta);
+            // The tablet needs a controller to plug into
+            if state.qemu_config.usb_controller == "none" {
+                state.qemu_config.usb_tablet = false;
+            }
+        }
         // Toggles use space, not left/right
         _ => {}
     }
 }
 
+/// Smallest RAM that can be typed (what the smallest profiles use)
+const MIN_TYPED_MEMORY_MB: u32 = 4;
+
+/// Bounds for a Memory or CPU value and where the upper one comes from
+pub(crate) struct TypedLimit {
+    min: u32,
+    max: u32,
+    /// Who sets the maximum ("Windows 95 supports", "This host allows")
+    source: String,
+    memory: bool,
+}
+
+impl TypedLimit {
+    fn describe(&self, value: u32) -> String {
+        match (self.memory, value) {
+            (true, _) => format!("{} MB", value),
+            (false, 1) => "1 CPU".to_string(),
+            (false, _) => format!("{} CPUs", value),
+        }
+    }
+
+    /// The value if it is within bounds, otherwise why not
+    pub(crate) fn check(&self, value: u32) -> Result<u32, String> {
+        if value < self.min {
+            Err(format!("At least {}", self.describe(self.min)))
+        } else if value > self.max {
+            Err(format!("{} at most {}", self.source, self.describe(self.max)))
+        } else {
+            Ok(value)
+        }
+    }
+}
+
+/// RAM bounds: the host's, or the OS's maximum where that is lower
+pub(crate) fn memory_limit(host: &crate::hardware::HostResources, profile: Option<&crate::metadata::QemuProfile>) -> TypedLimit {
+    let host_max = host.max_memory_mb();
+    let (max, source) = match profile.and_then(|p| p.max_memory_mb.filter(|max| *max < host_max).map(|max| (p, max))) {
+        Some((profile, max)) => (max, format!("{} supports", profile.display_name)),
+        None => (host_max, "This host allows".to_string()),
+    };
+    TypedLimit { min: MIN_TYPED_MEMORY_MB, max, source, memory: true }
+}
+
+/// CPU bounds: the host's, or the OS's maximum where that is lower
+pub(crate) fn cpu_limit(host: &crate::hardware::HostResources, profile: Option<&crate::metadata::QemuProfile>) -> TypedLimit {
+    let host_max = host.max_cpu_cores();
+    let (max, source) = match profile.and_then(|p| p.max_cpu_cores.filter(|max| *max < host_max).map(|max| (p, max))) {
+        Some((profile, max)) => (max, format!("{} supports", profile.display_name)),
+        None => (host_max, "This host allows".to_string()),
+    };
+    TypedLimit { min: 1, max, source, memory: false }
+}
+
+/// Whether a VGA adapter accepts EDID/xres/yres resolution hints
+fn supports_resolution(vga: &str) -> bool {
+    matches!(vga, "std" | "qxl")
+}
+
 fn cycle_option(current: &mut String, options: &[&str], delta: i32) {
     let current_idx = options.iter().position(|&o| o == current.as_str()).unwrap_or(0);
     let new_idx = (current_idx as i32 + delta).rem_euclid(options.len() as i32) as usize;
@@ -2434,16 +3193,21 @@ fn cycle_option(current: &mut String, options: &[&str], delta: i32) {
 }
 
 fn cycle_audio(current: &mut Vec<String>, delta: i32) {
-    // Find current audio preset
-    let current_idx = AUDIO_OPTIONS.iter().position(|(_, devices)| {
-        if devices.is_empty() && current.is_empty() {
-            true
-        } else if !devices.is_empty() && !current.is_empty() {
-            current.iter().any(|c| devices.iter().any(|d| c.contains(d)))
-        } else {
-            false
-        }
-    }).unwrap_or(0);
+    // Find current audio preset (exact match first, then by shared device)
+    let exact = AUDIO_OPTIONS.iter().position(|(_, devices)| devices.iter().eq(current.iter()));
+    let current_idx = exact
+        .or_else(|| {
+            AUDIO_OPTIONS.iter().position(|(_, devices)| {
+                if devices.is_empty() && current.is_empty() {
+                    true
+                } else if !devices.is_empty() && !current.is_empty() {
+                    current.iter().any(|c| devices.iter().any(|d| c.contains(d)))
+                } else {
+                    false
+                }
+            })
+        })
+        .unwrap_or(0);
 
     let new_idx = (current_idx as i32 + delta).rem_euclid(AUDIO_OPTIONS.len() as i32) as usize;
     let (_, devices) = AUDIO_OPTIONS[new_idx];
@@ -2516,12 +3280,33 @@ fn render_step_confirm(app: &App, frame: &mut Frame, area: Rect) {
     lines.push(Line::from(""));
     lines.push(Line::from(vec![
         Span::styled("Disk:           ", Style::default().fg(Color::Yellow)),
-        Span::raw(format!("{} GB qcow2 (expandable)", state.disk_size_gb)),
+        Span::raw(match (state.use_existing_disk, &state.remote_disk_url, &state.existing_disk_path) {
+            (true, Some(url), _) => crate::vm::remote_disk::parse_remote(url)
+                .map(|r| format!("{} ({}, stays remote)", r.display_url(), r.protocol.label()))
+                .unwrap_or_else(|| url.clone()),
+            (true, None, Some(path)) if state.existing_disk_action == crate::app::DiskAction::Convert => {
+                format!("{} (converted to qcow2)", path.display())
+            }
+            (true, None, Some(path)) => path.display().to_string(),
+            _ => format!("{} GB qcow2 ({})", state.disk_size_gb, state.disk_tuning.summary()),
+        }),
     ]));
     lines.push(Line::from(vec![
         Span::styled("ISO:            ", Style::default().fg(Color::Yellow)),
         Span::raw(iso_str),
     ]));
+    if let Some(unattended) = state.unattended.as_ref().filter(|_| !state.use_existing_disk) {
+        lines.push(Line::from(vec![
+            Span::styled("Unattended:     ", Style::default().fg(Color::Yellow)),
+            Span::raw(format!("{} ({}@{})", unattended.kind.label(), unattended.username, unattended.hostname)),
+        ]));
+    }
+    if let Some(cloud_init) = state.cloud_init.as_ref().filter(|_| state.use_existing_disk) {
+        lines.push(Line::from(vec![
+            Span::styled("Cloud-init:     ", Style::default().fg(Color::Yellow)),
+            Span::raw(format!("seed.iso ({}@{})", cloud_init.username, cloud_init.hostname)),
+        ]));
+    }
     lines.push(Line::from(""));
     lines.push(Line::from(vec![
         Span::styled("Hardware:       ", Style::default().fg(Color::Yellow)),
@@ -2533,14 +3318,29 @@ fn render_step_confirm(app: &App, frame: &mut Frame, area: Rect) {
     ]));
     lines.push(Line::from(vec![
         Span::styled("Audio:          ", Style::default().fg(Color::Yellow)),
-        Span::raw(config.audio.first().cloned().unwrap_or_else(|| "None".to_string())),
+        Span::raw(if config.audio.is_empty() {
+            "None".to_string()
+        } else {
+            format!(
+                "{} via {}",
+                get_audio_label(&config.audio),
+                crate::hardware::audio::backend_label(&config.audio_backend)
+            )
+        }),
     ]));
+    if let Some(synth) = crate::vm::midi::find_synth(&config.midi_synth) {
+        lines.push(Line::from(vec![
+            Span::styled("MIDI:           ", Style::default().fg(Color::Yellow)),
+            Span::raw(format!("COM1 -> {}", synth.name)),
+        ]));
+    }
     let net_display = if config.network_model == "none" {
         "none".to_string()
     } else {
         let backend_str = match config.network_backend.as_str() {
             "passt" => "passt".to_string(),
             "bridge" => format!("bridge ({})", config.bridge_name.as_deref().unwrap_or("qemubr0")),
+            "tap" => format!("tap ({})", config.tap_name.as_deref().unwrap_or(DEFAULT_TAP_NAME)),
             "none" => "disabled".to_string(),
             _ => "user/SLIRP (NAT)".to_string(),
         };
@@ -2562,6 +3362,15 @@ fn render_step_confirm(app: &App, frame: &mut Frame, area: Rect) {
         Span::raw(accel),
     ]));
 
+    // Settings the OS is unlikely to cope with
+    let warnings = profile_warnings(app);
+    if !warnings.is_empty() {
+        lines.push(Line::from(""));
+        for warning in warnings {
+            lines.push(Line::styled(format!("⚠ {}", warning), Style::default().fg(Color::Red)));
+        }
+    }
+
     let summary = Paragraph::new(lines)
         .wrap(Wrap { trim: false });
     frame.render_widget(summary, chunks[2]);
@@ -2615,8 +3424,8 @@ fn handle_step_confirm(app: &mut App, key: KeyEvent) -> Result<()> {
 
             match create_vm(&library_path, &state) {
                 Ok(created) => {
-                    // Cancel wizard first (closes screens)
-                    app.cancel_wizard();
+                    // Close wizard first (closes screens)
+                    app.close_wizard();
 
                     // Refresh VM list to include the new VM
                     match app.refresh_vms() {
@@ -2655,6 +3464,11 @@ fn handle_step_confirm(app: &mut App, key: KeyEvent) -> Result<()> {
                             }
                         }
                     }
+
+                    // Guide the first steps in the new OS
+                    if let Some(vm_id) = app.vms.iter().find(|vm| vm.launch_script == created.launch_script).map(|vm| vm.id.clone()) {
+                        super::checklist::open_after_create(app, &vm_id);
+                    }
                 }
                 Err(e) => {
                     if let Some(ref mut state) = app.wizard_state {
@@ -2733,6 +3547,11 @@ mod tests {
         // KB to MB conversion
         assert_eq!(parse_size_with_suffix("8388608KB", "MB"), Some(8192));
 
+        // Short and binary suffixes
+        assert_eq!(parse_size_with_suffix("4G", "MB"), Some(4096));
+        assert_eq!(parse_size_with_suffix("480m", "MB"), Some(480));
+        assert_eq!(parse_size_with_suffix("1.5GiB", "MB"), Some(1536));
+
         // Whitespace handling
         assert_eq!(parse_size_with_suffix("  8192  ", "MB"), Some(8192));
         assert_eq!(parse_size_with_suffix("8 GB", "MB"), Some(8192));
@@ -2764,5 +3583,27 @@ mod tests {
 
         // Negative values
         assert_eq!(parse_size_with_suffix("-100", "MB"), None);
+
+        // Unknown units
+        assert_eq!(parse_size_with_suffix("4TB", "MB"), None);
+        assert_eq!(parse_size_with_suffix("4 cores", "MB"), None);
+    }
+
+    #[test]
+    fn test_typed_limits() {
+        let win95 = crate::metadata::QemuProfileStore::load_embedded();
+        let win95 = win95.get("windows-95").unwrap();
+        let host = crate::hardware::HostResources { total_memory_mb: 16384, cpu_cores: 8 };
+
+        let memory = memory_limit(&host, Some(win95));
+        assert_eq!(memory.max, 480);
+        assert_eq!(memory.check(512), Err("Windows 95 supports at most 480 MB".to_string()));
+        assert_eq!(memory.check(480), Ok(480));
+        assert!(memory_limit(&host, None).check(8192).is_ok());
+        assert!(memory_limit(&host, None).check(65536).unwrap_err().contains("This host"));
+
+        let cpus = cpu_limit(&host, Some(win95));
+        assert_eq!(cpus.check(2), Err("Windows 95 supports at most 1 CPU".to_string()));
+        assert_eq!(cpus.check(0), Err("At least 1 CPU".to_string()));
     }
 }
@@ -0,0 +1,240 @@
+//! Add Data Disk Screen
+//!
+//! Size, image format, bus, filesystem and qcow2 tuning of a blank extra
+//! disk for the selected VM. The image is created (and formatted, if asked)
+//! in the background and attached in launch.sh for the next start.
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    prelude::*,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+use std::thread;
+
+use crate::app::{App, BackgroundResult, DataDiskState, Screen};
+use crate::ui::widgets::disk_tuning;
+use crate::vm::data_disk::{self, DataDiskOptions, DataFilesystem, DataInterface};
+
+/// Rows of the dialog above the qcow2 tuning rows, in the order shown
+const ROWS: [&str; 4] = ["Size (GB)", "Format", "Interface", "Filesystem"];
+const SIZE_ROW: usize = 0;
+const TUNING_ROW: usize = 4;
+const CREATE_ROW: usize = TUNING_ROW + disk_tuning::LABELS.len();
+
+/// Open the dialog for the selected VM
+pub fn open(app: &mut App) {
+    let Some(vm) = app.selected_vm() else {
+        return;
+    };
+    if vm.remote.is_some() {
+        app.set_status("Data disks can only be added to local VMs");
+        return;
+    }
+    // Follow the system disk's bus, so a guest with virtio drivers gets virtio
+    let interface = match vm.config.primary_disk() {
+        Some(disk) if disk.interface == "virtio" => DataInterface::Virtio,
+        _ => DataInterface::Ide,
+    };
+    app.data_disk_state = Some(DataDiskState {
+        size: "2".to_string(),
+        format_index: 0,
+        interface,
+        filesystem: DataFilesystem::None,
+        tuning: Default::default(),
+        selected: 0,
+        editing: false,
+    });
+    app.push_screen(Screen::AddDataDisk);
+}
+
+/// Render the dialog
+pub fn render(app: &App, frame: &mut Frame) {
+    let Some(ref state) = app.data_disk_state else {
+        return;
+    };
+    let area = frame.area();
+
+    let dialog_width = 64.min(area.width.saturating_sub(4));
+    let dialog_height = 18.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let block = Block::default()
+        .title(" Add Data Disk ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(11), // Rows
+            Constraint::Min(2),    // Note
+            Constraint::Length(1), // Help text
+        ])
+        .split(inner.inner(Margin::new(1, 0)));
+
+    let format = data_disk::FORMATS[state.format_index];
+    let tuning = disk_tuning::values(&state.tuning, format);
+    let mut lines = Vec::new();
+    for i in 0..=CREATE_ROW {
+        let label = match i {
+            TUNING_ROW..CREATE_ROW => disk_tuning::LABELS[i - TUNING_ROW],
+            CREATE_ROW => "",
+            _ => ROWS[i],
+        };
+        let selected = i == state.selected;
+        let marker = if selected { "> " } else { "  " };
+        let label_style = if selected {
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Yellow)
+        };
+        let value = match i {
+            SIZE_ROW if state.editing => format!("{}_", state.size),
+            SIZE_ROW => state.size.clone(),
+            1 => format!("< {} >", format),
+            2 => format!("< {} >", state.interface.label()),
+            3 => format!("< {} >", state.filesystem.label()),
+            TUNING_ROW..CREATE_ROW => tuning[i - TUNING_ROW].clone(),
+            _ => {
+                lines.push(Line::raw(""));
+                lines.push(Line::from(vec![Span::raw(marker), Span::styled("[ Create ]", label_style)]));
+                continue;
+            }
+        };
+        lines.push(Line::from(vec![
+            Span::raw(marker),
+            Span::styled(format!("{:12}", label), label_style),
+            Span::styled(value, Style::default().fg(Color::White)),
+        ]));
+    }
+    frame.render_widget(Paragraph::new(lines), chunks[0]);
+
+    let note = if (TUNING_ROW..CREATE_ROW).contains(&state.selected) {
+        disk_tuning::hint(state.selected - TUNING_ROW)
+    } else if state.filesystem == DataFilesystem::None {
+        "The disk is left blank: partition and format it in the guest (FDISK and FORMAT on DOS)."
+    } else {
+        "The disk gets one partition with this filesystem, written with virt-format (libguestfs)."
+    };
+    frame.render_widget(
+        Paragraph::new(note).style(Style::default().fg(Color::DarkGray)).wrap(Wrap { trim: true }),
+        chunks[1],
+    );
+
+    let help = if state.editing {
+        "Type the size  [Enter] Done"
+    } else {
+        "[j/k] Move  [Enter/←/→] Change  [Esc] Cancel"
+    };
+    let help = Paragraph::new(help)
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, chunks[2]);
+}
+
+/// Next (or with `back`, previous) entry of a list of choices
+fn cycle<T: Copy + PartialEq>(choices: &[T], current: T, back: bool) -> T {
+    let position = choices.iter().position(|c| *c == current).unwrap_or(0);
+    let next = if back {
+        (position + choices.len() - 1) % choices.len()
+    } else {
+        (position + 1) % choices.len()
+    };
+    choices[next]
+}
+
+/// Handle key input for the dialog
+pub fn handle_key(app: &mut App, key: KeyEvent) {
+    let Some(ref mut state) = app.data_disk_state else {
+        app.pop_screen();
+        return;
+    };
+
+    if state.editing {
+        match key.code {
+            KeyCode::Enter | KeyCode::Esc | KeyCode::Tab => state.editing = false,
+            KeyCode::Char(c) if c.is_ascii_digit() && state.size.len() < 5 => state.size.push(c),
+            KeyCode::Backspace => {
+                state.size.pop();
+            }
+            _ => {}
+        }
+        return;
+    }
+
+    let back = key.code == KeyCode::Left;
+    match key.code {
+        KeyCode::Esc => {
+            app.data_disk_state = None;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down | KeyCode::Tab if state.selected < CREATE_ROW => state.selected += 1,
+        KeyCode::Char('k') | KeyCode::Up => state.selected = state.selected.saturating_sub(1),
+        KeyCode::Enter if state.selected == SIZE_ROW => state.editing = true,
+        KeyCode::Enter if state.selected == CREATE_ROW => create(app),
+        KeyCode::Enter | KeyCode::Left | KeyCode::Right | KeyCode::Char(' ') => match state.selected {
+            1 => {
+                let count = data_disk::FORMATS.len();
+                state.format_index = if back { (state.format_index + count - 1) % count } else { (state.format_index + 1) % count };
+            }
+            2 => state.interface = cycle(DataInterface::ALL, state.interface, back),
+            3 => state.filesystem = cycle(DataFilesystem::ALL, state.filesystem, back),
+            TUNING_ROW..CREATE_ROW => {
+                let format = data_disk::FORMATS[state.format_index];
+                disk_tuning::change(&mut state.tuning, format, state.selected - TUNING_ROW, back);
+            }
+            _ => {}
+        },
+        _ => {}
+    }
+}
+
+/// Create and attach the disk in the background
+fn create(app: &mut App) {
+    let Some(state) = app.data_disk_state.clone() else {
+        return;
+    };
+    let size_gb = state.size.parse::<u32>().unwrap_or(0);
+    if size_gb == 0 {
+        app.set_status("Error: Enter a size in GB");
+        return;
+    }
+    if let Err(e) = state.tuning.validate(false) {
+        app.set_status(format!("Error: {}", e));
+        return;
+    }
+    let Some(vm) = app.selected_vm().cloned() else {
+        return;
+    };
+    let options = DataDiskOptions {
+        size_gb,
+        format: data_disk::FORMATS[state.format_index].to_string(),
+        interface: state.interface,
+        filesystem: state.filesystem,
+        tuning: state.tuning,
+    };
+    let tx = app.background_tx.clone();
+    app.data_disk_state = None;
+    app.pop_screen();
+    app.loading = true;
+    app.set_status(format!("Creating a {} GB data disk for {}...", size_gb, vm.display_name()));
+
+    thread::spawn(move || {
+        let result = data_disk::add_data_disk(&vm, &options).map_err(|e| format!("{:#}", e));
+        let _ = tx.send(BackgroundResult::DataDiskAdded { vm_name: vm.display_name(), result });
+    });
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    Rect::new(x, y, width, height)
+}
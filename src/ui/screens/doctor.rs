@@ -0,0 +1,165 @@
+//! VM Doctor Screen
+//!
+//! Lists the problems found in the selected VM. Enter applies the selected
+//! problem's fix to launch.sh and checks the VM again; `a` applies every
+//! fix at once.
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    prelude::*,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+use crate::app::{App, DoctorState, Screen};
+use crate::vm::doctor::{self, Fix};
+
+/// Diagnose the selected VM and show what was found
+pub fn open(app: &mut App) {
+    let Some(vm) = app.selected_vm().filter(|vm| vm.remote.is_none()).cloned() else {
+        return;
+    };
+    let findings = doctor::diagnose(&vm);
+    if findings.is_empty() {
+        app.set_status(format!("No problems found in {}", vm.display_name()));
+        return;
+    }
+    app.doctor_state = Some(DoctorState {
+        vm_id: vm.id.clone(),
+        vm_name: vm.display_name(),
+        findings,
+        selected: 0,
+    });
+    app.push_screen(Screen::Doctor);
+}
+
+/// Render the doctor dialog
+pub fn render(app: &App, frame: &mut Frame) {
+    let Some(ref state) = app.doctor_state else {
+        return;
+    };
+    let area = frame.area();
+
+    let dialog_width = 84.min(area.width.saturating_sub(4));
+    let dialog_height = 22.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let block = Block::default()
+        .title(format!(" Doctor: {} ({} found) ", state.vm_name, state.findings.len()))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(3),    // Findings
+            Constraint::Length(1), // Help text
+        ])
+        .split(inner.inner(Margin::new(1, 0)));
+
+    // Three lines per finding: the problem, its fix or advice, a gap
+    let per_page = (chunks[0].height as usize / 3).max(1);
+    let first = state.selected.saturating_sub(per_page - 1);
+    let mut lines: Vec<Line> = Vec::new();
+    for (i, finding) in state.findings.iter().enumerate().skip(first).take(per_page) {
+        let selected = i == state.selected;
+        let style = if selected {
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::White)
+        };
+        lines.push(Line::styled(format!("{}{}", if selected { "> " } else { "  " }, finding.problem), style));
+        lines.push(match (&finding.fix, &finding.advice) {
+            (Some(fix), _) => Line::styled(format!("    Fix: {}", fix.label()), Style::default().fg(Color::Green)),
+            (None, Some(advice)) => Line::styled(format!("    {}", advice), Style::default().fg(Color::DarkGray)),
+            (None, None) => Line::from(""),
+        });
+        lines.push(Line::from(""));
+    }
+    frame.render_widget(Paragraph::new(lines), chunks[0]);
+
+    let help = Paragraph::new("[j/k] Move  [Enter] Fix  [a] Fix all  [Esc] Close")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, chunks[1]);
+}
+
+/// Handle key input for the doctor screen
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(ref mut state) = app.doctor_state else {
+        app.pop_screen();
+        return Ok(());
+    };
+    match key.code {
+        KeyCode::Esc => {
+            app.doctor_state = None;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down if state.selected + 1 < state.findings.len() => state.selected += 1,
+        KeyCode::Char('k') | KeyCode::Up => state.selected = state.selected.saturating_sub(1),
+        KeyCode::Enter | KeyCode::Char('f') => {
+            match state.findings.get(state.selected).and_then(|f| f.fix.clone()) {
+                Some(fix) => apply(app, &[fix]),
+                None => app.set_status("No automatic fix for this one"),
+            }
+        }
+        KeyCode::Char('a') => {
+            let fixes: Vec<Fix> = state.findings.iter().filter_map(|f| f.fix.clone()).collect();
+            apply(app, &fixes);
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Apply fixes to the VM's script, then check it again
+fn apply(app: &mut App, fixes: &[Fix]) {
+    let Some(ref state) = app.doctor_state else {
+        return;
+    };
+    let vm_id = state.vm_id.clone();
+    let Some(vm) = app.vms.iter().find(|vm| vm.id == vm_id).cloned() else {
+        return;
+    };
+    for fix in fixes {
+        if let Err(e) = doctor::apply_fix(&vm, fix) {
+            app.set_status(format!("Error: {}", e));
+            return;
+        }
+    }
+
+    // The script's disks and options are parsed again from the new script
+    if let Err(e) = app.refresh_vms() {
+        app.set_status(format!("Fixed, but refresh failed: {}", e));
+    }
+    app.select_vm_by_id(&vm_id);
+    let findings = app
+        .vms
+        .iter()
+        .find(|vm| vm.id == vm_id)
+        .map(doctor::diagnose)
+        .unwrap_or_default();
+    if findings.is_empty() {
+        app.doctor_state = None;
+        app.pop_screen();
+        app.set_status(format!("{}: all problems fixed", vm.display_name()));
+    } else if let Some(ref mut state) = app.doctor_state {
+        state.selected = state.selected.min(findings.len() - 1);
+        state.findings = findings;
+        app.set_status(format!("Applied {} fix(es)", fixes.len()));
+    }
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    Rect::new(x, y, width, height)
+}
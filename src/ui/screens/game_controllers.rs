@@ -0,0 +1,261 @@
+//! Game Controllers Screen
+//!
+//! QEMU VMs: passes each host gamepad or joystick through as a USB device
+//! or over evdev. 86Box and DOSBox-X VMs: picks the emulated game port
+//! joystick and, for 86Box, the host controller that drives it.
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    prelude::*,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+use crate::app::{App, GameControllerState, Screen};
+use crate::hardware::input;
+use crate::vm::backend::Backend;
+use crate::vm::game_controller::{self, ControllerRoute};
+use crate::vm::load_usb_passthrough;
+
+/// Open the screen for the selected VM
+pub fn open(app: &mut App) -> Result<()> {
+    let Some(vm) = app.selected_vm() else {
+        return Ok(());
+    };
+    let controllers = input::enumerate_game_controllers()?;
+    let usb_rules = load_usb_passthrough(vm);
+    let routes = controllers
+        .iter()
+        .map(|c| game_controller::current_route(c, &usb_rules, vm.config.input_passthrough.as_ref()))
+        .collect();
+    app.game_controller_state = Some(GameControllerState {
+        backend: vm.backend,
+        gameport: game_controller::read_gameport(vm.backend, &vm.path),
+        controllers,
+        routes,
+        selected: 0,
+        dirty: false,
+        confirm_discard: false,
+    });
+    app.push_screen(Screen::GameControllers);
+    Ok(())
+}
+
+/// Rows: one per controller for QEMU; joystick type (and host controller
+/// for 86Box) otherwise
+fn row_count(state: &GameControllerState) -> usize {
+    match state.backend {
+        Backend::Qemu => state.controllers.len(),
+        Backend::EightySixBox => 2,
+        Backend::DosboxX => 1,
+    }
+}
+
+/// Render the screen
+pub fn render(app: &App, frame: &mut Frame) {
+    let Some(ref state) = app.game_controller_state else {
+        return;
+    };
+    let area = frame.area();
+
+    let dialog_width = 72.min(area.width.saturating_sub(4));
+    let dialog_height = 16.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let title = if state.dirty { " Game Controllers (modified) " } else { " Game Controllers " };
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(3),    // Rows
+            Constraint::Length(3), // Notes
+            Constraint::Length(1), // Help text
+        ])
+        .split(inner.inner(Margin::new(1, 0)));
+
+    let row_style = |i: usize| {
+        if i == state.selected {
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::White)
+        }
+    };
+    let marker = |i: usize| if i == state.selected { "> " } else { "  " };
+
+    let mut lines = Vec::new();
+    let note;
+    if state.backend == Backend::Qemu {
+        for (i, (controller, route)) in state.controllers.iter().zip(&state.routes).enumerate() {
+            let mut spans = vec![
+                Span::raw(marker(i)),
+                Span::styled(format!("{:36}", truncate(&controller.name, 34)), row_style(i)),
+                Span::raw(format!("< {} >", route.label())),
+            ];
+            if !controller.readable && *route == ControllerRoute::Evdev {
+                spans.push(Span::styled(" (no access)", Style::default().fg(Color::Red)));
+            }
+            lines.push(Line::from(spans));
+        }
+        if state.controllers.is_empty() {
+            lines.push(Line::styled("No game controllers found.", Style::default().fg(Color::DarkGray)));
+        }
+        note = "USB: Windows 98 and later see the controller itself (HID).\n\
+                evdev: needs virtio-input drivers in the guest.\n\
+                QEMU has no game port; DOS games want an 86Box or DOSBox-X VM.";
+    } else {
+        let joystick = state.gameport.joystick.map_or("None", |j| j.name);
+        lines.push(Line::from(vec![
+            Span::raw(marker(0)),
+            Span::styled(format!("{:18}", "Game port"), row_style(0)),
+            Span::raw(format!("< {} >", joystick)),
+        ]));
+        if let Some(j) = state.gameport.joystick {
+            lines.push(Line::styled(
+                format!("    {} axes, {} buttons{}", j.axes, j.buttons, if j.povs > 0 { ", hat switch" } else { "" }),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        if state.backend == Backend::EightySixBox {
+            let host = state
+                .controllers
+                .get(state.gameport.host_index)
+                .map_or_else(|| format!("Controller {}", state.gameport.host_index + 1), |c| c.name.clone());
+            lines.push(Line::from(vec![
+                Span::raw(marker(1)),
+                Span::styled(format!("{:18}", "Host controller"), row_style(1)),
+                Span::raw(format!("< {} >", host)),
+            ]));
+            note = "Host axes and buttons map in order onto the joystick's;\n\
+                    fine-tune them in 86Box's settings (Input devices).";
+        } else {
+            note = "DOSBox-X drives it from the first host controller;\n\
+                    remap buttons with its mapper (Ctrl+F1).";
+        }
+    }
+    frame.render_widget(Paragraph::new(lines), chunks[0]);
+    frame.render_widget(
+        Paragraph::new(note).style(Style::default().fg(Color::DarkGray)),
+        chunks[1],
+    );
+
+    let help = Paragraph::new("[Enter/←/→] Change  [s] Save  [Esc] Close")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, chunks[2]);
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() > max {
+        format!("{}…", text.chars().take(max - 1).collect::<String>())
+    } else {
+        text.to_string()
+    }
+}
+
+/// Handle key input for the screen
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(ref mut state) = app.game_controller_state else {
+        app.pop_screen();
+        return Ok(());
+    };
+    let rows = row_count(state);
+
+    if key.code != KeyCode::Esc {
+        state.confirm_discard = false;
+    }
+    match key.code {
+        KeyCode::Esc if state.dirty && !state.confirm_discard => {
+            state.confirm_discard = true;
+            app.set_status("Unsaved changes: press Esc again to discard them, or s to save");
+        }
+        KeyCode::Esc => {
+            app.game_controller_state = None;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down if state.selected + 1 < rows => state.selected += 1,
+        KeyCode::Char('k') | KeyCode::Up => state.selected = state.selected.saturating_sub(1),
+        KeyCode::Enter | KeyCode::Char(' ') | KeyCode::Left | KeyCode::Right if rows > 0 => {
+            let forward = key.code != KeyCode::Left;
+            change(state, forward);
+            state.dirty = true;
+        }
+        KeyCode::Char('s') | KeyCode::Char('S') => save(app),
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Step the selected row's value
+fn change(state: &mut GameControllerState, forward: bool) {
+    let step = |position: usize, count: usize| {
+        if forward { (position + 1) % count } else { (position + count - 1) % count }
+    };
+    match (state.backend, state.selected) {
+        (Backend::Qemu, i) => {
+            let usb = state.controllers[i].usb_id.is_some();
+            let route = &mut state.routes[i];
+            *route = if forward { route.next(usb) } else { route.prev(usb) };
+        }
+        (backend, 0) => {
+            // Position 0 is no joystick
+            let joysticks = game_controller::gameport_joysticks(backend);
+            let position = state
+                .gameport
+                .joystick
+                .and_then(|current| joysticks.iter().position(|j| j == current))
+                .map_or(0, |i| i + 1);
+            state.gameport.joystick = step(position, joysticks.len() + 1).checked_sub(1).map(|i| &joysticks[i]);
+        }
+        (_, _) => {
+            let count = state.controllers.len().max(state.gameport.host_index + 1);
+            state.gameport.host_index = step(state.gameport.host_index, count);
+        }
+    }
+}
+
+/// Save the choices to the launch script or the emulator's configuration
+fn save(app: &mut App) {
+    let Some(ref state) = app.game_controller_state else {
+        return;
+    };
+    let Some(vm) = app.selected_vm().cloned() else {
+        return;
+    };
+    let result = if state.backend == Backend::Qemu {
+        let routes: Vec<_> = state.controllers.iter().cloned().zip(state.routes.iter().copied()).collect();
+        game_controller::set_routes(&vm, &routes)
+    } else {
+        game_controller::set_gameport(state.backend, &vm.path, &state.gameport)
+    };
+    match result {
+        Ok(()) => {
+            if app.running_vms.contains_key(&vm.id) {
+                app.set_status("Game controllers saved (applies on next start)");
+            } else {
+                app.set_status("Game controllers saved");
+            }
+            app.game_controller_state = None;
+            app.pop_screen();
+            let _ = app.refresh_vms();
+        }
+        Err(e) => app.set_status(format!("Failed to save game controllers: {}", e)),
+    }
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    Rect::new(x, y, width, height)
+}
@@ -0,0 +1,292 @@
+//! Guest Files Screen
+//!
+//! Two-pane browser over a VM disk mounted with guestmount: the guest
+//! filesystem on the left, a host directory on the right. Copies go from
+//! the active pane into the other pane's directory. The disk is mounted
+//! read-only; [w] remounts it read-write while the VM is stopped.
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    prelude::*,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+};
+use std::path::PathBuf;
+
+use crate::app::{App, FilePane, GuestFilesState, Screen};
+use crate::commands::guest_mount;
+
+/// Mount the selected VM's primary disk read-only and show its files
+pub fn open(app: &mut App) {
+    let Some(vm) = app.selected_vm() else {
+        return;
+    };
+    let Some(disk) = vm.config.primary_disk().cloned() else {
+        app.set_status("This VM has no local disk image to mount");
+        return;
+    };
+    let vm_id = vm.id.clone();
+    // The SMB share folder is the natural place to stage files for the guest
+    let share = vm.path.join(crate::vm::create::SMB_SHARE_DIR);
+    let host_dir = if share.is_dir() {
+        share
+    } else {
+        dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"))
+    };
+
+    match guest_mount::mount_disk(&disk, &vm_id, true) {
+        Ok(mount) => {
+            let guest = FilePane::new(mount.mountpoint.clone(), Some(mount.mountpoint.clone()));
+            app.guest_files_state = Some(GuestFilesState {
+                mount,
+                panes: [guest, FilePane::new(host_dir, None)],
+                active: 0,
+            });
+            app.push_screen(Screen::GuestFiles);
+        }
+        Err(e) => app.set_status(format!("Error: {}", e)),
+    }
+}
+
+/// Unmount the disk if one is still mounted (also used when quitting)
+pub fn unmount(app: &mut App) -> Result<()> {
+    if let Some(state) = app.guest_files_state.take() {
+        if let Err(e) = guest_mount::unmount(&state.mount) {
+            app.guest_files_state = Some(state);
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// Render the guest files screen
+pub fn render(app: &App, frame: &mut Frame) {
+    let Some(ref state) = app.guest_files_state else {
+        return;
+    };
+    let area = frame.area();
+
+    let dialog_width = 100.min(area.width.saturating_sub(4));
+    let dialog_height = 30.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let name = app
+        .selected_vm()
+        .map(|vm| vm.display_name())
+        .unwrap_or_else(|| "VM".to_string());
+    let mode = if state.mount.read_only { "read-only" } else { "read-write" };
+    let title = format!(" Disk Files: {} ({}) ", name, mode);
+
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let v_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(4),    // Panes
+            Constraint::Length(1), // Note
+            Constraint::Length(1), // Help text
+        ])
+        .split(inner);
+
+    let pane_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(v_chunks[0]);
+
+    let guest_path = state.panes[0]
+        .dir
+        .strip_prefix(&state.mount.mountpoint)
+        .map(|p| format!("/{}", p.display()))
+        .unwrap_or_default();
+    let titles = [
+        format!(" Guest: {} ", guest_path),
+        format!(" Host: {} ", state.panes[1].dir.display()),
+    ];
+    for (i, pane) in state.panes.iter().enumerate() {
+        render_pane(frame, pane_chunks[i], pane, &titles[i], state.active == i);
+    }
+
+    let running = app
+        .selected_vm()
+        .is_some_and(|vm| app.running_vms.contains_key(&vm.id));
+    let (note, color) = if running {
+        ("VM is running: files may lag behind what the guest has cached", Color::Yellow)
+    } else if state.mount.read_only {
+        ("Copies into the guest need a read-write mount ([w])", Color::DarkGray)
+    } else {
+        ("Changes are written to the disk image when you leave this screen", Color::DarkGray)
+    };
+    let note = Paragraph::new(note)
+        .style(Style::default().fg(color))
+        .alignment(Alignment::Center);
+    frame.render_widget(note, v_chunks[1]);
+
+    let help = Paragraph::new("[Tab] Switch  [Enter] Open  [Bksp] Up  [c] Copy to other side  [w] Read-write  [r] Refresh  [Esc] Unmount")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, v_chunks[2]);
+}
+
+fn render_pane(frame: &mut Frame, area: Rect, pane: &FilePane, title: &str, active: bool) {
+    let border = if active { Color::Yellow } else { Color::DarkGray };
+    let block = Block::default()
+        .title(title.to_string())
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(border));
+
+    let items: Vec<ListItem> = pane
+        .entries
+        .iter()
+        .map(|entry| {
+            let (label, style) = if entry.is_dir {
+                (format!("{}/", entry.name), Style::default().fg(Color::Cyan))
+            } else {
+                (entry.name.clone(), Style::default().fg(Color::White))
+            };
+            ListItem::new(Span::styled(label, style))
+        })
+        .collect();
+
+    let mut list_state = ListState::default();
+    if active && !pane.entries.is_empty() {
+        list_state.select(Some(pane.selected));
+    }
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+        .highlight_symbol("> ");
+    frame.render_stateful_widget(list, area, &mut list_state);
+}
+
+/// Handle key input for the guest files screen
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(ref mut state) = app.guest_files_state else {
+        app.pop_screen();
+        return Ok(());
+    };
+    let pane = &mut state.panes[state.active];
+
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') => match unmount(app) {
+            Ok(()) => {
+                app.set_status("Disk unmounted");
+                app.pop_screen();
+            }
+            Err(e) => app.set_status(format!("Error: {}", e)),
+        },
+        KeyCode::Tab | KeyCode::BackTab => state.active = 1 - state.active,
+        KeyCode::Char('j') | KeyCode::Down if pane.selected + 1 < pane.entries.len() => {
+            pane.selected += 1;
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            pane.selected = pane.selected.saturating_sub(1);
+        }
+        KeyCode::Enter => pane.enter(),
+        KeyCode::Backspace if pane.entries.first().is_some_and(|e| e.name == "..") => {
+            pane.selected = 0;
+            pane.enter();
+        }
+        KeyCode::Char('r') => {
+            for pane in state.panes.iter_mut() {
+                pane.load();
+            }
+        }
+        KeyCode::Char('c') => copy_selected(app),
+        KeyCode::Char('w') => remount_read_write(app),
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Copy the active pane's selection into the other pane's directory
+fn copy_selected(app: &mut App) {
+    let Some(ref mut state) = app.guest_files_state else {
+        return;
+    };
+    let to_guest = state.active == 1;
+    if to_guest && state.mount.read_only {
+        app.set_status("Disk is mounted read-only; press [w] to mount read-write");
+        return;
+    }
+    let Some(source) = state.panes[state.active].selected_entry().map(|e| e.path.clone()) else {
+        return;
+    };
+
+    let target = &mut state.panes[1 - state.active];
+    let result = guest_mount::copy_into(&source, &target.dir);
+    target.load();
+    match result {
+        Ok(dest) => app.set_status(format!(
+            "Copied to {}",
+            dest.file_name().unwrap_or_default().to_string_lossy()
+        )),
+        Err(e) => app.set_status(format!("Error: {}", e)),
+    }
+}
+
+/// Remount the disk read-write, keeping both panes where they were
+fn remount_read_write(app: &mut App) {
+    let Some(vm) = app.selected_vm() else {
+        return;
+    };
+    if app.running_vms.contains_key(&vm.id) {
+        app.set_status("Stop the VM before mounting its disk read-write");
+        return;
+    }
+    if let Err(e) = crate::vm::suspend::ensure_not_suspended(&vm.path) {
+        app.set_status(format!("Error: {}", e));
+        return;
+    }
+    let Some(disk) = vm.config.primary_disk().cloned() else {
+        return;
+    };
+    let vm_id = vm.id.clone();
+    let Some(ref mut state) = app.guest_files_state else {
+        return;
+    };
+    if !state.mount.read_only {
+        return;
+    }
+
+    if let Err(e) = guest_mount::unmount(&state.mount) {
+        app.set_status(format!("Error: {}", e));
+        return;
+    }
+    let (mount, message) = match guest_mount::mount_disk(&disk, &vm_id, false) {
+        Ok(mount) => (mount, "Disk mounted read-write".to_string()),
+        Err(e) => match guest_mount::mount_disk(&disk, &vm_id, true) {
+            Ok(mount) => (mount, format!("Error: {} (still read-only)", e)),
+            Err(e) => {
+                app.guest_files_state = None;
+                app.pop_screen();
+                app.set_status(format!("Error: {}", e));
+                return;
+            }
+        },
+    };
+
+    // The mountpoint path is stable, so the guest pane can stay in place
+    state.mount = mount;
+    for pane in state.panes.iter_mut() {
+        pane.load();
+    }
+    app.set_status(message);
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    Rect::new(x, y, width, height)
+}
@@ -7,7 +7,7 @@ use ratatui::{
 pub fn render(frame: &mut Frame) {
     let area = frame.area();
     let dialog_width = 55.min(area.width.saturating_sub(4));
-    let dialog_height = 28.min(area.height.saturating_sub(4));
+    let dialog_height = 38.min(area.height.saturating_sub(4));
 
     let dialog_area = centered_rect(dialog_width, dialog_height, area);
     frame.render_widget(Clear, dialog_area);
@@ -40,7 +40,21 @@ pub fn render(frame: &mut Frame) {
         key_line("m", "Open Management menu"),
         key_line("x", "Stop selected VM (graceful shutdown)"),
         key_line("c", "Create new VM"),
+        key_line("i", "Import a disk image as a new VM"),
+        key_line("b", "Snapshot all stopped VMs (batch)"),
         key_line("/", "Search/filter VMs"),
+        key_line("p", "Slideshow of the library (any key stops it)"),
+        key_line("o", "Go to the VM in today's \"on this day\" banner"),
+        key_line("t", "Trivia quiz about the OSes in the library"),
+        key_line("d", "Details, notes and journal of the selected VM"),
+        key_line("u", "Usage statistics: the most-used VMs"),
+        key_line("e", "Create and edit QEMU profiles for the wizard"),
+        key_line("w", "Unused files in VM folders, with cleanup"),
+        key_line("f", "Storage usage of each VM and the library"),
+        key_line("l", "Output of the selected VM's last launch"),
+        key_line("r", "CPU, memory, disk and network of a running VM"),
+        key_line("a", "Add a blank data disk to the selected VM"),
+        key_line("v", "ISO library, identified against known images"),
         Line::from(""),
         Line::from(Span::styled(
             "Management Menu",
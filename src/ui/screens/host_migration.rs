@@ -0,0 +1,232 @@
+//! Host Migration Screen
+//!
+//! Moves the selected VM to another library host: this computer or one of
+//! the configured remote hosts. A stopped VM's folder is copied (and the
+//! original removed unless kept); a running VM is live-migrated, which
+//! needs its disks on storage both hosts share.
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    prelude::*,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Gauge, List, ListItem, ListState, Paragraph, Wrap},
+};
+use std::thread;
+
+use crate::app::{App, BackgroundResult, HostMigrationState, Screen};
+use crate::vm::host_migration;
+
+/// List the hosts the selected VM can move to and show the dialog
+pub fn open(app: &mut App) {
+    let Some(vm) = app.selected_vm() else {
+        return;
+    };
+    let source = vm.remote.as_ref().map(|h| h.name.clone());
+    let live = app.running_vms.contains_key(&vm.id);
+    if live && !crate::vm::qmp::is_configured(&vm.config.raw_script) {
+        app.set_status("Error: Live migration needs the VM's QMP socket. Shut the VM down to copy it instead.");
+        return;
+    }
+
+    let mut destinations = Vec::new();
+    if source.is_some() {
+        destinations.push(None);
+    }
+    destinations.extend(
+        app.config
+            .remote_hosts
+            .iter()
+            .filter(|h| Some(&h.name) != source.as_ref())
+            .cloned()
+            .map(Some),
+    );
+    if destinations.is_empty() {
+        app.set_status("No other hosts configured");
+        return;
+    }
+
+    app.host_migration_state = Some(HostMigrationState {
+        destinations,
+        selected: 0,
+        live,
+        keep_source: false,
+        progress: None,
+    });
+    app.push_screen(Screen::HostMigration);
+}
+
+/// Render the migrate-to-host dialog
+pub fn render(app: &App, frame: &mut Frame) {
+    let Some(ref state) = app.host_migration_state else {
+        return;
+    };
+    let area = frame.area();
+
+    let dialog_width = 70.min(area.width.saturating_sub(4));
+    let dialog_height = 18.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let block = Block::default()
+        .title(" Migrate to Host ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(2), // VM and mode
+            Constraint::Min(3),    // Destinations
+            Constraint::Length(3), // Details
+            Constraint::Length(1), // Progress
+            Constraint::Length(1), // Help text
+        ])
+        .split(inner.inner(Margin::new(1, 0)));
+
+    let label = Style::default().fg(Color::Yellow);
+    let vm_name = app.selected_vm().map(|vm| vm.display_name()).unwrap_or_default();
+    let mode = if state.live {
+        "Live migration (the VM keeps running)"
+    } else if state.keep_source {
+        "Copy the folder, keep the original"
+    } else {
+        "Move the folder"
+    };
+    let header = vec![
+        Line::from(vec![Span::styled("VM:    ", label), Span::raw(vm_name)]),
+        Line::from(vec![Span::styled("Mode:  ", label), Span::raw(mode)]),
+    ];
+    frame.render_widget(Paragraph::new(header), chunks[0]);
+
+    let items: Vec<ListItem> = state
+        .destinations
+        .iter()
+        .map(|dest| {
+            let text = match dest {
+                Some(host) => format!("{:<16} {}:{}", host.name, host.ssh, host.library_path),
+                None => format!("{:<16} {}", "this computer", app.config.vm_library_path.display()),
+            };
+            ListItem::new(text)
+        })
+        .collect();
+    let mut list_state = ListState::default();
+    list_state.select(Some(state.selected));
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::BOTTOM))
+        .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+        .highlight_symbol("> ");
+    frame.render_stateful_widget(list, chunks[1], &mut list_state);
+
+    let detail = if state.live {
+        "The VM's folder must also be in the destination library, with its disks on storage both hosts share"
+    } else {
+        "Interrupted copies resume where they stopped when started again"
+    };
+    frame.render_widget(
+        Paragraph::new(Span::styled(detail, Style::default().fg(Color::DarkGray))).wrap(Wrap { trim: true }),
+        chunks[2],
+    );
+
+    if let Some(progress) = state.progress {
+        let gauge = Gauge::default()
+            .gauge_style(Style::default().fg(Color::Cyan).bg(Color::DarkGray))
+            .ratio(progress.clamp(0.0, 1.0) as f64)
+            .label(format!("Migrating... {:.0}%", progress * 100.0));
+        frame.render_widget(gauge, chunks[3]);
+    }
+
+    let help = if state.progress.is_some() {
+        "Migrating; the VM list is updated when done"
+    } else if state.live {
+        "[j/k] Host  [Enter] Migrate  [Esc] Cancel"
+    } else {
+        "[j/k] Host  [o] Keep/remove original  [Enter] Move  [Esc] Cancel"
+    };
+    let help = Paragraph::new(help)
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, chunks[4]);
+}
+
+/// Handle key input for the migrate-to-host dialog
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(ref mut state) = app.host_migration_state else {
+        app.pop_screen();
+        return Ok(());
+    };
+    // Runs to completion; rsync resumes a copy that is cut short
+    if state.progress.is_some() {
+        return Ok(());
+    }
+
+    match key.code {
+        KeyCode::Esc => {
+            app.host_migration_state = None;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down if state.selected + 1 < state.destinations.len() => {
+            state.selected += 1;
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            state.selected = state.selected.saturating_sub(1);
+        }
+        KeyCode::Char('o') if !state.live => state.keep_source = !state.keep_source,
+        KeyCode::Enter => start_migration(app),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn start_migration(app: &mut App) {
+    let Some(vm) = app.selected_vm().cloned() else {
+        return;
+    };
+    let running = app.running_vms.contains_key(&vm.id);
+    let Some(ref mut state) = app.host_migration_state else {
+        return;
+    };
+    // The VM was started or stopped since the dialog opened
+    if running != state.live {
+        app.set_status("The VM's state changed; open the dialog again");
+        return;
+    }
+
+    state.progress = Some(0.0);
+    let dest = state.destinations[state.selected].clone();
+    let live = state.live;
+    let keep_source = state.keep_source;
+    let library = app.config.vm_library_path.clone();
+    let vm_name = vm.display_name();
+    let tx = app.background_tx.clone();
+
+    thread::spawn(move || {
+        let progress_tx = tx.clone();
+        let on_progress = |progress| {
+            let _ = progress_tx.send(BackgroundResult::HostMigrationProgress { progress });
+        };
+        let result = if live {
+            host_migration::live_migrate(&vm, dest.as_ref(), &library, on_progress)
+        } else {
+            host_migration::transfer(&vm, dest.as_ref(), &library, keep_source, on_progress)
+        };
+        let _ = tx.send(BackgroundResult::HostMigrated {
+            vm_name,
+            dest: host_migration::host_name(dest.as_ref()).to_string(),
+            live,
+            result: result.map_err(|e| format!("{:#}", e)),
+        });
+    });
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    Rect::new(x, y, width, height)
+}
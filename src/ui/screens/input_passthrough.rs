@@ -0,0 +1,268 @@
+//! Input Passthrough Screen
+//!
+//! Picks host keyboards, mice and game controllers to hand to the selected
+//! VM through evdev, the mode they reach the guest in, and the hotkey that
+//! grabs and releases them. Saved to a managed section of launch.sh.
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    prelude::*,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+use std::path::PathBuf;
+
+use crate::app::{App, InputPassthroughState, Screen};
+use crate::hardware::input::{self, InputDevice};
+use crate::vm::input_passthrough::{self, InputMode};
+
+/// A row of the editor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Row {
+    Mode,
+    Hotkey,
+    /// A device on the host
+    Device(usize),
+    /// A saved device that isn't plugged in, by index into the settings
+    Absent(usize),
+}
+
+fn absent(state: &InputPassthroughState) -> Vec<usize> {
+    (0..state.settings.devices.len())
+        .filter(|&i| !state.devices.iter().any(|d| d.path == state.settings.devices[i]))
+        .collect()
+}
+
+fn rows(state: &InputPassthroughState) -> Vec<Row> {
+    let mut rows = vec![Row::Mode, Row::Hotkey];
+    rows.extend((0..state.devices.len()).map(Row::Device));
+    rows.extend(absent(state).into_iter().map(Row::Absent));
+    rows
+}
+
+/// Open the editor for the selected VM
+pub fn open(app: &mut App) -> Result<()> {
+    let Some(vm) = app.selected_vm() else {
+        return Ok(());
+    };
+    let settings = vm.config.input_passthrough.clone().unwrap_or_default();
+    app.input_passthrough_state = Some(InputPassthroughState {
+        devices: input::enumerate_input_devices()?,
+        settings,
+        selected: 0,
+        dirty: false,
+        confirm_discard: false,
+    });
+    app.push_screen(Screen::InputPassthrough);
+    Ok(())
+}
+
+/// Render the editor
+pub fn render(app: &App, frame: &mut Frame) {
+    let Some(ref state) = app.input_passthrough_state else {
+        return;
+    };
+    let area = frame.area();
+
+    let dialog_width = 76.min(area.width.saturating_sub(4));
+    let dialog_height = 22.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let title = if state.dirty { " Input Passthrough (modified) " } else { " Input Passthrough " };
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(4),    // Rows
+            Constraint::Length(3), // Notes
+            Constraint::Length(1), // Help text
+        ])
+        .split(inner.inner(Margin::new(1, 0)));
+
+    let settings = &state.settings;
+    let rows = rows(state);
+    let height = chunks[0].height as usize;
+    let first = state.selected.saturating_sub(height.saturating_sub(1));
+    let lines: Vec<Line> = rows
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(height)
+        .map(|(i, row)| {
+            let selected = i == state.selected;
+            let marker = if selected { "> " } else { "  " };
+            let style = if selected {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            match *row {
+                Row::Mode => Line::from(vec![
+                    Span::raw(marker),
+                    Span::styled(format!("{:10}", "Mode"), style),
+                    Span::raw(format!("< {} >", settings.mode.label())),
+                ]),
+                Row::Hotkey => {
+                    let hotkey = input_passthrough::grab_toggle_label(&settings.grab_toggle);
+                    let value = match settings.mode {
+                        InputMode::InputLinux => Span::raw(format!("< {} >", hotkey)),
+                        InputMode::VirtioInputHost => {
+                            Span::styled("(held until the VM exits)", Style::default().fg(Color::DarkGray))
+                        }
+                    };
+                    Line::from(vec![Span::raw(marker), Span::styled(format!("{:10}", "Grab key"), style), value])
+                }
+                Row::Device(d) => {
+                    let device = &state.devices[d];
+                    device_line(marker, style, device, settings.devices.contains(&device.path))
+                }
+                Row::Absent(s) => Line::from(vec![
+                    Span::raw(marker),
+                    Span::styled(format!("[x] {}", settings.devices[s].display()), style),
+                    Span::styled(" (not plugged in)", Style::default().fg(Color::Yellow)),
+                ]),
+            }
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), chunks[0]);
+
+    let note = if state.devices.is_empty() {
+        "No devices under /dev/input/by-id."
+    } else if state.devices.iter().any(|d| !d.readable) {
+        "Some devices can't be opened: add yourself to the input group\n\
+         (sudo usermod -aG input $USER) and log in again."
+    } else {
+        match settings.mode {
+            InputMode::InputLinux => {
+                "Works with DOS and Windows 9x (no drivers). Press the grab key on a\n\
+                 passed keyboard to switch all devices between host and guest."
+            }
+            InputMode::VirtioInputHost => {
+                "Full controllers, but the guest needs virtio-input drivers\n\
+                 (Linux, or Windows with virtio-win). Not for DOS."
+            }
+        }
+    };
+    frame.render_widget(
+        Paragraph::new(note).style(Style::default().fg(Color::DarkGray)),
+        chunks[1],
+    );
+
+    let help = Paragraph::new("[Space] Toggle  [←/→] Change  [s] Save  [Esc] Close")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, chunks[2]);
+}
+
+fn device_line<'a>(marker: &'a str, style: Style, device: &InputDevice, chosen: bool) -> Line<'a> {
+    let check = if chosen { "[x]" } else { "[ ]" };
+    let mut spans = vec![
+        Span::raw(marker),
+        Span::styled(format!("{} {:9}{}", check, device.kind.label(), device.name), style),
+    ];
+    if !device.readable {
+        spans.push(Span::styled(" (no access)", Style::default().fg(Color::Red)));
+    }
+    Line::from(spans)
+}
+
+/// Handle key input for the editor
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(ref mut state) = app.input_passthrough_state else {
+        app.pop_screen();
+        return Ok(());
+    };
+    let rows = rows(state);
+    let row = rows.get(state.selected).copied();
+
+    if key.code != KeyCode::Esc {
+        state.confirm_discard = false;
+    }
+    match key.code {
+        KeyCode::Esc if state.dirty && !state.confirm_discard => {
+            state.confirm_discard = true;
+            app.set_status("Unsaved changes: press Esc again to discard them, or s to save");
+        }
+        KeyCode::Esc => {
+            app.input_passthrough_state = None;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down if state.selected + 1 < rows.len() => state.selected += 1,
+        KeyCode::Char('k') | KeyCode::Up => state.selected = state.selected.saturating_sub(1),
+        KeyCode::Enter | KeyCode::Char(' ') | KeyCode::Left | KeyCode::Right => {
+            let delta = if key.code == KeyCode::Left { -1 } else { 1 };
+            match row {
+                Some(Row::Mode) => state.settings.mode = state.settings.mode.toggle(),
+                Some(Row::Hotkey) => state.settings.cycle_grab_toggle(delta),
+                Some(Row::Device(d)) if matches!(key.code, KeyCode::Enter | KeyCode::Char(' ')) => {
+                    toggle_device(&mut state.settings.devices, state.devices[d].path.clone());
+                }
+                Some(Row::Absent(s)) if matches!(key.code, KeyCode::Enter | KeyCode::Char(' ')) => {
+                    state.settings.devices.remove(s);
+                    state.selected = state.selected.min(rows.len().saturating_sub(2));
+                }
+                _ => return Ok(()),
+            }
+            state.dirty = true;
+        }
+        KeyCode::Char('s') | KeyCode::Char('S') => save(app),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn toggle_device(devices: &mut Vec<PathBuf>, path: PathBuf) {
+    match devices.iter().position(|p| *p == path) {
+        Some(i) => {
+            devices.remove(i);
+        }
+        None => devices.push(path),
+    }
+}
+
+/// Write the settings to the VM's launch script
+fn save(app: &mut App) {
+    let Some(ref state) = app.input_passthrough_state else {
+        return;
+    };
+    let Some(vm) = app.selected_vm().cloned() else {
+        return;
+    };
+    let settings = state.settings.clone();
+    match input_passthrough::set_input_passthrough(&vm.launch_script, &settings) {
+        Ok(()) => {
+            let summary = match settings.devices.len() {
+                0 => "off".to_string(),
+                1 => "1 device".to_string(),
+                n => format!("{} devices", n),
+            };
+            if app.running_vms.contains_key(&vm.id) {
+                app.set_status(format!("Input passthrough: {} (applies on next start)", summary));
+            } else {
+                app.set_status(format!("Input passthrough: {}", summary));
+            }
+            app.input_passthrough_state = None;
+            app.pop_screen();
+            let _ = app.refresh_vms();
+        }
+        Err(e) => app.set_status(format!("Failed to save input passthrough: {}", e)),
+    }
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    Rect::new(x, y, width, height)
+}
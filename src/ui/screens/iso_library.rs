@@ -0,0 +1,185 @@
+//! ISO Library Screen
+//!
+//! The install images in the ISO library folder, each checksummed in the
+//! background and tagged with the known image (and so the OS profile) it
+//! is. Enter starts a new VM from the selected image, with the profile
+//! already chosen when the image was recognised.
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    prelude::*,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+use std::thread;
+
+use crate::app::{App, BackgroundResult, IsoLibraryState, Screen};
+use crate::format::format_size;
+use crate::vm::iso_library;
+
+/// Show the library and identify its images in the background
+pub fn open(app: &mut App) {
+    let dir = app.config.iso_library();
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        app.set_status(format!("Cannot create {}: {}", dir.display(), e));
+        return;
+    }
+    app.iso_library_state = Some(IsoLibraryState {
+        dir,
+        isos: Vec::new(),
+        scanning: false,
+        selected: 0,
+    });
+    app.push_screen(Screen::IsoLibrary);
+    scan(app);
+}
+
+/// Checksum the library's images again (only changed ones are read)
+fn scan(app: &mut App) {
+    let Some(ref mut state) = app.iso_library_state else {
+        return;
+    };
+    state.scanning = true;
+    let dir = state.dir.clone();
+    let known = app.iso_checksums.clone();
+    app.loading = true;
+    app.set_status("Checksumming images...");
+
+    let tx = app.background_tx.clone();
+    thread::spawn(move || {
+        let isos = iso_library::scan(&dir, &known);
+        let _ = tx.send(BackgroundResult::IsoLibraryScanned { isos });
+    });
+}
+
+/// Render the ISO library dialog
+pub fn render(app: &App, frame: &mut Frame) {
+    let Some(ref state) = app.iso_library_state else {
+        return;
+    };
+    let area = frame.area();
+
+    let dialog_width = 100.min(area.width.saturating_sub(4));
+    let dialog_height = 24.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let block = Block::default()
+        .title(format!(" ISO Library: {} ", state.dir.display()))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // Column headings
+            Constraint::Min(3),    // Images
+            Constraint::Length(1), // Summary
+            Constraint::Length(1), // Help text
+        ])
+        .split(inner.inner(Margin::new(1, 0)));
+
+    let name_width = (chunks[1].width as usize).saturating_sub(50).max(16);
+    let heading = format!("  {:name_width$}  {:>10}  {}", "File", "Size", "Identified as");
+    frame.render_widget(
+        Paragraph::new(heading).style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+        chunks[0],
+    );
+
+    let lines: Vec<Line> = if state.isos.is_empty() {
+        let text = if state.scanning {
+            "  Checksumming images..."
+        } else {
+            "  No .iso files here yet; copy install images into this folder"
+        };
+        vec![Line::styled(text, Style::default().fg(Color::DarkGray))]
+    } else {
+        let height = chunks[1].height as usize;
+        let first = state.selected.saturating_sub(height.saturating_sub(1));
+        state
+            .isos
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(height)
+            .map(|(i, iso)| {
+                let name: String = iso.file_name().chars().take(name_width).collect();
+                let identified = match (&iso.known, &iso.sha256) {
+                    (Some(known), _) => format!("{} ({})", known.name, known.profile),
+                    (None, Some(_)) => "unknown image".to_string(),
+                    (None, None) => "not checked (no known image this size or name)".to_string(),
+                };
+                let text = format!(
+                    "{}{:name_width$}  {:>10}  {}",
+                    if i == state.selected { "> " } else { "  " },
+                    name,
+                    format_size(iso.size),
+                    identified
+                );
+                match (i == state.selected, iso.known.is_some()) {
+                    (true, _) => Line::styled(text, Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+                    (false, true) => Line::styled(text, Style::default().fg(Color::Green)),
+                    (false, false) => Line::from(text),
+                }
+            })
+            .collect()
+    };
+    frame.render_widget(Paragraph::new(lines), chunks[1]);
+
+    let identified = state.isos.iter().filter(|iso| iso.known.is_some()).count();
+    let summary = format!(
+        "{} images, {} identified  ({} known checksums)",
+        state.isos.len(),
+        identified,
+        app.iso_checksums.len()
+    );
+    frame.render_widget(Paragraph::new(summary).style(Style::default().fg(Color::Gray)), chunks[2]);
+
+    let help = Paragraph::new("[j/k] Move  [Enter] Create VM from image  [r] Rescan  [Esc] Close")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, chunks[3]);
+}
+
+/// Handle key input for the ISO library screen
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(ref mut state) = app.iso_library_state else {
+        app.pop_screen();
+        return Ok(());
+    };
+    match key.code {
+        KeyCode::Esc => {
+            app.iso_library_state = None;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down if state.selected + 1 < state.isos.len() => state.selected += 1,
+        KeyCode::Char('k') | KeyCode::Up => state.selected = state.selected.saturating_sub(1),
+        KeyCode::Char('r') if !state.scanning => scan(app),
+        KeyCode::Enter => {
+            let Some(iso) = state.isos.get(state.selected).cloned() else {
+                return Ok(());
+            };
+            if app.kiosk_blocks() {
+                return Ok(());
+            }
+            app.iso_library_state = None;
+            app.pop_screen();
+            app.start_wizard_with_iso(iso.path, iso.known.as_ref().map(|k| k.profile.as_str()));
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    Rect::new(x, y, width, height)
+}
@@ -0,0 +1,256 @@
+//! Launch Environment Screen
+//!
+//! Edits the selected VM's launch environment: a wrapper command the
+//! launch script is run through (`nice -n 10`, `gamemoderun`) and
+//! environment variables (`SDL_VIDEODRIVER=x11`), one per row. Saved to
+//! the VM's vm-curator.toml; an installed autostart service is rewritten
+//! to match.
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    prelude::*,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+use std::path::Path;
+
+use crate::app::{App, LaunchEnvState, Screen};
+use crate::vm::launch_env::{self, LaunchEnvironment};
+use crate::vm::systemd;
+
+/// A row of the editor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Row {
+    Wrapper,
+    Var(usize),
+    AddVar,
+}
+
+fn rows(state: &LaunchEnvState) -> Vec<Row> {
+    let mut rows = vec![Row::Wrapper];
+    rows.extend((0..state.environment.env.len()).map(Row::Var));
+    rows.push(Row::AddVar);
+    rows
+}
+
+fn text_mut(state: &mut LaunchEnvState, row: Row) -> Option<&mut String> {
+    match row {
+        Row::Wrapper => Some(&mut state.environment.wrapper),
+        Row::Var(i) => state.environment.env.get_mut(i),
+        Row::AddVar => None,
+    }
+}
+
+/// Open the editor for the selected VM
+pub fn open(app: &mut App) {
+    let Some(vm) = app.selected_vm().filter(|vm| vm.remote.is_none()) else {
+        return;
+    };
+    app.launch_env_state = Some(LaunchEnvState {
+        vm_dir: vm.path.clone(),
+        vm_id: vm.id.clone(),
+        vm_name: vm.display_name(),
+        environment: launch_env::load(&vm.path),
+        selected: 0,
+        editing: false,
+        dirty: false,
+        confirm_discard: false,
+    });
+    app.push_screen(Screen::LaunchEnvironment);
+}
+
+/// Render the editor
+pub fn render(app: &App, frame: &mut Frame) {
+    let Some(ref state) = app.launch_env_state else {
+        return;
+    };
+    let area = frame.area();
+
+    let dialog_width = 70.min(area.width.saturating_sub(4));
+    let dialog_height = 18.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let title = if state.dirty {
+        format!(" Launch Environment: {} (modified) ", state.vm_name)
+    } else {
+        format!(" Launch Environment: {} ", state.vm_name)
+    };
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3), // Explanation
+            Constraint::Min(3),    // Rows
+            Constraint::Length(1), // Help text
+        ])
+        .split(inner.inner(Margin::new(1, 0)));
+
+    let intro = Paragraph::new(
+        "Applied when vm-curator starts the VM (not by a plain ./launch.sh).\n\
+         The wrapper runs the launch script, e.g. \"nice -n 10\" or \"gamemoderun\";\n\
+         variables are NAME=value, e.g. SDL_VIDEODRIVER=x11.",
+    )
+    .style(Style::default().fg(Color::DarkGray));
+    frame.render_widget(intro, chunks[0]);
+
+    // One line per row, scrolled to keep the selection in view
+    let rows = rows(state);
+    let height = chunks[1].height as usize;
+    let first = state.selected.saturating_sub(height.saturating_sub(1));
+    let lines: Vec<Line> = rows
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(height)
+        .map(|(i, row)| {
+            let selected = i == state.selected;
+            let marker = if selected { "> " } else { "  " };
+            let label_style = match row {
+                Row::AddVar => Style::default().fg(Color::DarkGray),
+                _ if selected => Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                _ => Style::default().fg(Color::Yellow),
+            };
+            let (label, value) = match row {
+                Row::Wrapper => ("Wrapper command".to_string(), state.environment.wrapper.as_str()),
+                Row::Var(i) => (format!("Variable {}", i + 1), state.environment.env[*i].as_str()),
+                Row::AddVar => ("+ Add variable".to_string(), ""),
+            };
+            let value = if selected && state.editing {
+                Span::raw(format!("{}_", value))
+            } else if *row == Row::Wrapper && value.is_empty() {
+                Span::styled("(none)", Style::default().fg(Color::DarkGray))
+            } else {
+                Span::raw(value.to_string())
+            };
+            Line::from(vec![Span::raw(marker), Span::styled(format!("{:18}", label), label_style), value])
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), chunks[1]);
+
+    let help = if state.editing {
+        "Type to edit  [Enter] Done"
+    } else if matches!(rows.get(state.selected), Some(Row::Var(_))) {
+        "[Enter] Edit  [d] Remove  [s] Save  [Esc] Close"
+    } else {
+        "[Enter] Edit  [s] Save  [Esc] Close"
+    };
+    let help = Paragraph::new(help)
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, chunks[2]);
+}
+
+/// Handle key input for the editor
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(ref mut state) = app.launch_env_state else {
+        app.pop_screen();
+        return Ok(());
+    };
+    let rows = rows(state);
+    let row = rows.get(state.selected).copied();
+
+    if state.editing {
+        match key.code {
+            KeyCode::Enter | KeyCode::Esc | KeyCode::Tab => state.editing = false,
+            KeyCode::Char(c) => {
+                if let Some(text) = row.and_then(|row| text_mut(state, row)) {
+                    text.push(c);
+                    state.dirty = true;
+                }
+            }
+            KeyCode::Backspace => {
+                if let Some(text) = row.and_then(|row| text_mut(state, row)) {
+                    text.pop();
+                    state.dirty = true;
+                }
+            }
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    if key.code != KeyCode::Esc {
+        state.confirm_discard = false;
+    }
+    match key.code {
+        KeyCode::Esc if state.dirty && !state.confirm_discard => {
+            state.confirm_discard = true;
+            app.set_status("Unsaved changes: press Esc again to discard them, or s to save");
+        }
+        KeyCode::Esc => {
+            app.launch_env_state = None;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down if state.selected + 1 < rows.len() => state.selected += 1,
+        KeyCode::Char('k') | KeyCode::Up => state.selected = state.selected.saturating_sub(1),
+        KeyCode::Enter | KeyCode::Char(' ') => {
+            // A new variable takes the "add" row's place
+            if row == Some(Row::AddVar) {
+                state.environment.env.push(String::new());
+            }
+            state.editing = true;
+        }
+        KeyCode::Char('d') | KeyCode::Delete => {
+            if let Some(Row::Var(i)) = row {
+                state.environment.env.remove(i);
+                state.dirty = true;
+            }
+        }
+        KeyCode::Char('s') | KeyCode::Char('S') => save(app),
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Save the environment, keeping an installed autostart service in step
+fn save(app: &mut App) {
+    let Some(ref mut state) = app.launch_env_state else {
+        return;
+    };
+    let environment = &mut state.environment;
+    environment.env.retain(|var| !var.trim().is_empty());
+    environment.wrapper = environment.wrapper.trim().to_string();
+    state.selected = state.selected.min(environment.env.len() + 1);
+    let (vm_id, vm_dir, environment) = (state.vm_id.clone(), state.vm_dir.clone(), environment.clone());
+    match save_environment(app, &vm_id, &vm_dir, &environment) {
+        Ok(note) => {
+            app.launch_env_state = None;
+            app.pop_screen();
+            app.set_status(format!("Launch environment saved{}", note));
+        }
+        Err(e) => app.set_status(format!("Error: {:#}", e)),
+    }
+}
+
+/// Save a VM's launch environment and rewrite its autostart service, if
+/// installed, to match; returns a note on the service for the status line
+pub fn save_environment(app: &App, vm_id: &str, vm_dir: &Path, environment: &LaunchEnvironment) -> Result<String> {
+    launch_env::save(vm_dir, environment)?;
+    let status = systemd::status(vm_id);
+    let note = match app.vms.iter().find(|vm| vm.id == vm_id).filter(|_| status.installed) {
+        Some(vm) => match systemd::install(vm, status.start_at) {
+            Ok(_) => " (autostart service updated)".to_string(),
+            Err(e) => format!(", but the autostart service wasn't updated: {:#}", e),
+        },
+        None => String::new(),
+    };
+    Ok(note)
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    Rect::new(x, y, width, height)
+}
@@ -0,0 +1,269 @@
+//! Launch With Options Screen
+//!
+//! Memory, CPU cores, display and extra QEMU arguments for one launch of
+//! the selected VM. Nothing is written to launch.sh: the values are added
+//! to the QEMU command line for this launch, and blank fields keep what the
+//! script says.
+
+use anyhow::{bail, Result};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    prelude::*,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+use crate::app::{App, LaunchOptionsState, Screen};
+use crate::ui::screens::create_wizard::parse_size_with_suffix;
+use crate::ui::screens::management;
+use crate::vm::{BootMode, LaunchOverrides};
+
+/// Rows of the dialog, in the order shown
+const ROWS: [&str; 5] = ["Memory", "CPU cores", "Display", "QEMU arguments", "Launch"];
+const DISPLAY_ROW: usize = 2;
+const LAUNCH_ROW: usize = 4;
+
+/// Smallest memory QEMU is asked for
+const MIN_MEMORY_MB: u32 = 4;
+
+/// Open the dialog for the selected VM
+pub fn open(app: &mut App) {
+    let Some(vm) = app.selected_vm() else {
+        return;
+    };
+    app.launch_options_state = Some(LaunchOptionsState {
+        vm_name: vm.display_name(),
+        ..Default::default()
+    });
+    app.push_screen(Screen::LaunchOptions);
+}
+
+/// Plain `-display` backends; SPICE server mode and VNC need sections in
+/// the script
+fn display_choices(app: &App) -> Vec<String> {
+    management::get_display_options(app)
+        .into_iter()
+        .map(|(name, _)| name)
+        .filter(|name| name != "spice" && name != "vnc")
+        .collect()
+}
+
+/// The typed value of a text row
+fn text_mut(state: &mut LaunchOptionsState, row: usize) -> Option<&mut String> {
+    match row {
+        0 => Some(&mut state.memory),
+        1 => Some(&mut state.cpu_cores),
+        3 => Some(&mut state.extra_args),
+        _ => None,
+    }
+}
+
+/// Render the dialog
+pub fn render(app: &App, frame: &mut Frame) {
+    let Some(ref state) = app.launch_options_state else {
+        return;
+    };
+    let Some(vm) = app.selected_vm() else {
+        return;
+    };
+    let area = frame.area();
+
+    let dialog_width = 64.min(area.width.saturating_sub(4));
+    let dialog_height = 14.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let block = Block::default()
+        .title(format!(" Launch {} with options ", state.vm_name))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(2), // Explanation
+            Constraint::Min(5),    // Rows
+            Constraint::Length(1), // Help text
+        ])
+        .split(inner.inner(Margin::new(1, 0)));
+
+    let intro = Paragraph::new("For this launch only; launch.sh is left as it is.\nBlank fields keep the script's settings.")
+        .style(Style::default().fg(Color::DarkGray));
+    frame.render_widget(intro, chunks[0]);
+
+    let script_display = management::current_display(vm);
+    let placeholders = [
+        format!("{} MB", vm.config.memory_mb),
+        vm.config.cpu_cores.to_string(),
+        script_display,
+        "none".to_string(),
+    ];
+    let values = [
+        state.memory.as_str(),
+        state.cpu_cores.as_str(),
+        state.display.as_deref().unwrap_or(""),
+        state.extra_args.as_str(),
+    ];
+    let mut lines = Vec::new();
+    for (i, label) in ROWS.iter().enumerate() {
+        let selected = i == state.selected;
+        let marker = if selected { "> " } else { "  " };
+        let label_style = if selected {
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Yellow)
+        };
+        if i == LAUNCH_ROW {
+            lines.push(Line::raw(""));
+            lines.push(Line::from(vec![Span::raw(marker), Span::styled("[ Launch ]", label_style)]));
+            continue;
+        }
+        let value = if selected && state.editing {
+            Span::raw(format!("{}_", values[i]))
+        } else if values[i].is_empty() {
+            Span::styled(format!("({})", placeholders[i]), Style::default().fg(Color::DarkGray))
+        } else {
+            Span::styled(values[i].to_string(), Style::default().fg(Color::White))
+        };
+        lines.push(Line::from(vec![
+            Span::raw(marker),
+            Span::styled(format!("{:16}", label), label_style),
+            value,
+        ]));
+    }
+    frame.render_widget(Paragraph::new(lines), chunks[1]);
+
+    let help = if state.editing {
+        "Type to edit  [Enter] Done"
+    } else if state.selected == DISPLAY_ROW {
+        "[Enter/←/→] Change  [l] Launch  [Esc] Back"
+    } else {
+        "[Enter] Edit  [l] Launch  [Esc] Back"
+    };
+    let help = Paragraph::new(help)
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, chunks[2]);
+}
+
+/// Handle key input for the dialog
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    let choices = display_choices(app);
+    let Some(ref mut state) = app.launch_options_state else {
+        app.pop_screen();
+        return Ok(());
+    };
+
+    if state.editing {
+        let row = state.selected;
+        match key.code {
+            KeyCode::Enter | KeyCode::Esc | KeyCode::Tab => state.editing = false,
+            KeyCode::Char(c) => {
+                if let Some(text) = text_mut(state, row) {
+                    text.push(c);
+                }
+            }
+            KeyCode::Backspace => {
+                if let Some(text) = text_mut(state, row) {
+                    text.pop();
+                }
+            }
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    match key.code {
+        KeyCode::Esc => {
+            app.launch_options_state = None;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down | KeyCode::Tab if state.selected < LAUNCH_ROW => state.selected += 1,
+        KeyCode::Char('k') | KeyCode::Up => state.selected = state.selected.saturating_sub(1),
+        KeyCode::Enter | KeyCode::Right | KeyCode::Char(' ') if state.selected == DISPLAY_ROW => {
+            state.display = cycle(&choices, state.display.as_deref(), true);
+        }
+        KeyCode::Left if state.selected == DISPLAY_ROW => {
+            state.display = cycle(&choices, state.display.as_deref(), false);
+        }
+        KeyCode::Enter if state.selected == LAUNCH_ROW => launch(app),
+        KeyCode::Char('l') => launch(app),
+        KeyCode::Enter => state.editing = true,
+        KeyCode::Delete | KeyCode::Backspace => {
+            if state.selected == DISPLAY_ROW {
+                state.display = None;
+            } else if let Some(text) = text_mut(state, state.selected) {
+                text.clear();
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// The next (or previous) display choice, passing through the script's own
+fn cycle(choices: &[String], current: Option<&str>, forward: bool) -> Option<String> {
+    // Position 0 is "keep the script's display"
+    let count = choices.len() + 1;
+    let position = current
+        .and_then(|current| choices.iter().position(|c| c == current))
+        .map_or(0, |i| i + 1);
+    let next = if forward { (position + 1) % count } else { (position + count - 1) % count };
+    next.checked_sub(1).map(|i| choices[i].clone())
+}
+
+/// Check the typed values and ask to launch with them
+fn launch(app: &mut App) {
+    let Some(ref state) = app.launch_options_state else {
+        return;
+    };
+    match overrides(state, app.host_resources.cpu_cores) {
+        Ok(overrides) => {
+            app.launch_overrides = overrides;
+            app.boot_mode = BootMode::Normal;
+            app.launch_options_state = None;
+            app.pop_screen(); // Close this dialog
+            app.pop_screen(); // Close boot options
+            app.confirm_boot();
+        }
+        Err(e) => app.set_status(format!("Error: {}", e)),
+    }
+}
+
+/// The overrides the typed values make
+fn overrides(state: &LaunchOptionsState, host_cores: u32) -> Result<LaunchOverrides> {
+    let memory_mb = match state.memory.trim() {
+        "" => None,
+        text => match parse_size_with_suffix(text, "MB") {
+            Some(mb) if mb >= MIN_MEMORY_MB => Some(mb),
+            Some(_) => bail!("Memory must be at least {} MB", MIN_MEMORY_MB),
+            None => bail!("\"{}\" isn't a memory size (try 2048 or 2G)", text),
+        },
+    };
+    let cpu_cores = match state.cpu_cores.trim() {
+        "" => None,
+        text => match text.parse::<u32>() {
+            Ok(cores) if (1..=host_cores).contains(&cores) => Some(cores),
+            Ok(_) => bail!("CPU cores must be between 1 and {} (this host's threads)", host_cores),
+            Err(_) => bail!("\"{}\" isn't a number of CPU cores", text),
+        },
+    };
+    Ok(LaunchOverrides {
+        memory_mb,
+        cpu_cores,
+        display: state.display.clone(),
+        extra_args: state.extra_args.split_whitespace().map(String::from).collect(),
+    })
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    Rect::new(x, y, width, height)
+}
@@ -0,0 +1,198 @@
+//! QEMU Log Screen
+//!
+//! The output of the selected VM's latest launch, with error lines in red.
+//! In follow mode the view sticks to the end of the log and picks up new
+//! output as QEMU writes it; scrolling up leaves follow mode. `p` switches
+//! to the launch before.
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    prelude::*,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+use crate::app::{App, LogViewerState, Screen};
+use crate::vm::qemu_log;
+
+/// Lines moved by PgUp/PgDn
+const PAGE: usize = 10;
+
+const DIALOG_HEIGHT: u16 = 32;
+
+/// Show the log of the selected VM's latest launch
+pub fn open(app: &mut App) {
+    let Some(vm) = app.selected_vm().filter(|vm| vm.remote.is_none()) else {
+        return;
+    };
+    if !qemu_log::log_path(&vm.path).exists() {
+        app.set_status(format!("{} has no launch log yet", vm.display_name()));
+        return;
+    }
+    let mut state = LogViewerState {
+        vm_dir: vm.path.clone(),
+        vm_name: vm.display_name(),
+        previous: false,
+        lines: Vec::new(),
+        log_len: 0,
+        from_bottom: 0,
+        follow: true,
+    };
+    reload(&mut state);
+    app.log_viewer_state = Some(state);
+    app.push_screen(Screen::LogViewer);
+}
+
+fn log_path(state: &LogViewerState) -> std::path::PathBuf {
+    if state.previous {
+        qemu_log::previous_log_path(&state.vm_dir)
+    } else {
+        qemu_log::log_path(&state.vm_dir)
+    }
+}
+
+/// Read the log again, keeping the view on the same lines unless following
+fn reload(state: &mut LogViewerState) {
+    let path = log_path(state);
+    let lines = qemu_log::read_lines(&path);
+    if !state.follow && lines.len() > state.lines.len() {
+        state.from_bottom += lines.len() - state.lines.len();
+    }
+    state.lines = lines;
+    state.log_len = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    state.from_bottom = state.from_bottom.min(state.lines.len());
+}
+
+/// Pick up new output while following (call in the event loop)
+pub fn tick(app: &mut App) {
+    let Some(ref mut state) = app.log_viewer_state else {
+        return;
+    };
+    if !state.follow {
+        return;
+    }
+    let len = std::fs::metadata(log_path(state)).map(|m| m.len()).unwrap_or(0);
+    if len != state.log_len {
+        reload(state);
+    }
+}
+
+/// Render the log dialog
+pub fn render(app: &App, frame: &mut Frame) {
+    let Some(ref state) = app.log_viewer_state else {
+        return;
+    };
+    let area = frame.area();
+
+    let dialog_width = 110.min(area.width.saturating_sub(4));
+    let dialog_height = DIALOG_HEIGHT.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let title = format!(
+        " {} log: {}{} ",
+        if state.previous { "Previous launch" } else { "Launch" },
+        state.vm_name,
+        if state.follow { " (following)" } else { "" }
+    );
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(3),    // Log
+            Constraint::Length(1), // Help text
+        ])
+        .split(inner.inner(Margin::new(1, 0)));
+
+    let height = chunks[0].height as usize;
+    let last = (state.lines.len() - state.from_bottom).max(height.min(state.lines.len()));
+    let first = last.saturating_sub(height);
+    let lines: Vec<Line> = if state.lines.is_empty() {
+        vec![Line::styled("(nothing was printed)", Style::default().fg(Color::DarkGray))]
+    } else {
+        state.lines[first..last]
+            .iter()
+            .map(|line| {
+                if qemu_log::is_error_line(line) {
+                    Line::styled(line.as_str(), Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+                } else {
+                    Line::from(line.as_str())
+                }
+            })
+            .collect()
+    };
+    frame.render_widget(Paragraph::new(lines), chunks[0]);
+
+    let help = Paragraph::new("[j/k/PgUp/PgDn] Scroll  [g/G] Top/End  [f] Follow  [p] Previous launch  [Esc] Close")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, chunks[1]);
+}
+
+/// Handle key input for the log screen
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(ref mut state) = app.log_viewer_state else {
+        app.pop_screen();
+        return Ok(());
+    };
+    let len = state.lines.len();
+    match key.code {
+        KeyCode::Esc => {
+            app.log_viewer_state = None;
+            app.pop_screen();
+        }
+        KeyCode::Char('k') | KeyCode::Up => scroll_up(state, 1),
+        KeyCode::PageUp => scroll_up(state, PAGE),
+        KeyCode::Char('g') | KeyCode::Home => scroll_up(state, len),
+        KeyCode::Char('j') | KeyCode::Down => state.from_bottom = state.from_bottom.saturating_sub(1),
+        KeyCode::PageDown => state.from_bottom = state.from_bottom.saturating_sub(PAGE),
+        KeyCode::Char('G') | KeyCode::End => state.from_bottom = 0,
+        KeyCode::Char('f') => {
+            state.follow = !state.follow;
+            if state.follow {
+                state.from_bottom = 0;
+                reload(state);
+            }
+        }
+        KeyCode::Char('p') => {
+            if !state.previous && !qemu_log::previous_log_path(&state.vm_dir).exists() {
+                app.set_status("No earlier launch log");
+                return Ok(());
+            }
+            state.previous = !state.previous;
+            // The previous log doesn't grow
+            state.follow = !state.previous;
+            state.from_bottom = 0;
+            state.lines.clear();
+            reload(state);
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Scroll towards the start of the log, leaving follow mode
+fn scroll_up(state: &mut LogViewerState, lines: usize) {
+    // Stop once the first line is at the top of the view
+    let rows = crossterm::terminal::size().map(|(_, rows)| rows).unwrap_or(DIALOG_HEIGHT);
+    let view = (DIALOG_HEIGHT.min(rows.saturating_sub(4)) as usize).saturating_sub(3);
+    state.follow = false;
+    state.from_bottom = (state.from_bottom + lines).min(state.lines.len().saturating_sub(view));
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    Rect::new(x, y, width, height)
+}
@@ -0,0 +1,280 @@
+//! Mac Firmware Screen
+//!
+//! Chooses what a classic Mac VM boots: a ROM dump from the ROM folder
+//! (required by the Quadra 800) or, on PowerPC, QEMU's built-in OpenBIOS
+//! with its `-prom-env` variables.
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    prelude::*,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
+};
+use std::path::PathBuf;
+
+use crate::app::{App, MacFirmwareState, Screen};
+use crate::vm::mac_firmware::{self, PROM_ENV_OPTIONS};
+use crate::vm::qemu_config::QemuEmulator;
+
+/// Read the VM's firmware settings and scan the ROM folder
+pub fn open(app: &mut App) {
+    let Some(vm) = app.selected_vm() else {
+        return;
+    };
+    let firmware = mac_firmware::parse_mac_firmware(&vm.config.raw_script);
+    let _ = std::fs::create_dir_all(mac_firmware::rom_dir());
+    app.mac_firmware_state = Some(MacFirmwareState {
+        roms: mac_firmware::list_roms(),
+        firmware,
+        selected: 0,
+    });
+    app.push_screen(Screen::MacFirmware);
+}
+
+/// A row in the list
+enum Row {
+    /// QEMU's built-in OpenBIOS (PowerPC only)
+    OpenBios,
+    /// A dump in the ROM folder
+    Rom(usize),
+    /// The configured ROM, which is not in the ROM folder
+    MissingRom(PathBuf),
+    /// An entry of PROM_ENV_OPTIONS (PowerPC only)
+    PromEnv(usize),
+}
+
+fn rows(state: &MacFirmwareState, ppc: bool) -> Vec<Row> {
+    let mut rows = Vec::new();
+    if ppc {
+        rows.push(Row::OpenBios);
+    }
+    rows.extend((0..state.roms.len()).map(Row::Rom));
+    if let Some(ref rom) = state.firmware.rom {
+        if !state.roms.iter().any(|r| &r.path == rom) {
+            rows.push(Row::MissingRom(rom.clone()));
+        }
+    }
+    if ppc {
+        rows.extend((0..PROM_ENV_OPTIONS.len()).map(Row::PromEnv));
+    }
+    rows
+}
+
+/// Whether the VM is a PowerPC Mac, and its machine type
+fn vm_kind(app: &App) -> (bool, String) {
+    app.selected_vm()
+        .map(|vm| {
+            (
+                vm.config.emulator == QemuEmulator::Ppc,
+                vm.config.machine.clone().unwrap_or_default(),
+            )
+        })
+        .unwrap_or_default()
+}
+
+/// Render the Mac firmware screen
+pub fn render(app: &App, frame: &mut Frame) {
+    let Some(ref state) = app.mac_firmware_state else {
+        return;
+    };
+    let (ppc, machine) = vm_kind(app);
+    let area = frame.area();
+
+    let dialog_width = 80.min(area.width.saturating_sub(4));
+    let dialog_height = 20.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let block = Block::default()
+        .title(" Mac Firmware ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(4),    // Firmware and variables
+            Constraint::Length(3), // Details
+            Constraint::Length(1), // Help text
+        ])
+        .split(inner.inner(Margin::new(1, 0)));
+
+    let rows = rows(state, ppc);
+    let items: Vec<ListItem> = rows
+        .iter()
+        .map(|row| {
+            let (text, color) = match row {
+                Row::OpenBios => {
+                    let mark = if state.firmware.rom.is_none() { "(*)" } else { "( )" };
+                    (format!("{} OpenBIOS (built into QEMU)", mark), Color::White)
+                }
+                Row::Rom(i) => {
+                    let rom = &state.roms[*i];
+                    let mark = if state.firmware.rom.as_ref() == Some(&rom.path) { "(*)" } else { "( )" };
+                    let color = if rom.machine == Some(machine.as_str()) {
+                        Color::Green
+                    } else {
+                        Color::White
+                    };
+                    (
+                        format!(
+                            "{} {:<24} {:08X}  {}",
+                            mark,
+                            rom.file_name(),
+                            rom.checksum,
+                            rom.model.unwrap_or("unknown ROM")
+                        ),
+                        color,
+                    )
+                }
+                Row::MissingRom(path) => (format!("(*) {} (not found)", path.display()), Color::Yellow),
+                Row::PromEnv(i) => {
+                    let (value, _) = PROM_ENV_OPTIONS[*i];
+                    let check = if state.firmware.prom_env.iter().any(|v| v == value) { "[x]" } else { "[ ]" };
+                    (format!("    {} {}", check, value), Color::White)
+                }
+            };
+            ListItem::new(Span::styled(text, Style::default().fg(color)))
+        })
+        .collect();
+
+    let mut list_state = ListState::default();
+    if !rows.is_empty() {
+        list_state.select(Some(state.selected));
+    }
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::BOTTOM))
+        .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+        .highlight_symbol("> ");
+    frame.render_stateful_widget(list, chunks[0], &mut list_state);
+
+    let (detail, color) = match rows.get(state.selected) {
+        Some(Row::OpenBios) => ("Free firmware that boots Mac OS 8.5 to 9.2 and Mac OS X up to 10.5".to_string(), Color::DarkGray),
+        Some(Row::Rom(i)) => rom_notice(&state.roms[*i], ppc, &machine),
+        Some(Row::MissingRom(_)) => (
+            format!("Copy the ROM dump into {} or pick another", mac_firmware::rom_dir().display()),
+            Color::Yellow,
+        ),
+        Some(Row::PromEnv(i)) => (PROM_ENV_OPTIONS[*i].1.to_string(), Color::DarkGray),
+        None => (
+            format!("No ROM dumps found: copy them into {} and press r", mac_firmware::rom_dir().display()),
+            Color::Yellow,
+        ),
+    };
+    frame.render_widget(
+        Paragraph::new(Span::styled(detail, Style::default().fg(color))).wrap(Wrap { trim: true }),
+        chunks[1],
+    );
+
+    let help = Paragraph::new("[Space] Select  [r] Rescan ROM folder  [s] Save  [Esc] Cancel")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, chunks[2]);
+}
+
+/// Whether QEMU's board can boot this ROM
+fn rom_notice(rom: &mac_firmware::MacRom, ppc: bool, machine: &str) -> (String, Color) {
+    match (rom.machine, rom.model) {
+        (Some(m), _) if m == machine => (format!("Boots the {} board", machine), Color::Green),
+        (Some(m), _) => (format!("This ROM is for the {} board, not {}", m, machine), Color::Yellow),
+        (None, Some(model)) => (
+            format!("{} ROM: no QEMU board emulates this model", model),
+            Color::Yellow,
+        ),
+        (None, None) if ppc => (
+            "Unknown image: used as a replacement for OpenBIOS".to_string(),
+            Color::DarkGray,
+        ),
+        (None, None) => ("Unknown ROM: the Quadra 800 needs checksum F1ACAD13".to_string(), Color::Yellow),
+    }
+}
+
+/// Handle key input for the Mac firmware screen
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    let (ppc, _) = vm_kind(app);
+    let Some(ref mut state) = app.mac_firmware_state else {
+        app.pop_screen();
+        return Ok(());
+    };
+    let rows = rows(state, ppc);
+
+    match key.code {
+        KeyCode::Esc => {
+            app.mac_firmware_state = None;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down if state.selected + 1 < rows.len() => {
+            state.selected += 1;
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            state.selected = state.selected.saturating_sub(1);
+        }
+        KeyCode::Char(' ') | KeyCode::Enter => match rows.get(state.selected) {
+            Some(Row::OpenBios) => state.firmware.rom = None,
+            Some(Row::Rom(i)) => state.firmware.rom = Some(state.roms[*i].path.clone()),
+            Some(Row::PromEnv(i)) => {
+                let (value, _) = PROM_ENV_OPTIONS[*i];
+                if let Some(pos) = state.firmware.prom_env.iter().position(|v| v == value) {
+                    state.firmware.prom_env.remove(pos);
+                } else {
+                    state.firmware.prom_env.push(value.to_string());
+                }
+            }
+            Some(Row::MissingRom(_)) | None => {}
+        },
+        KeyCode::Char('r') => {
+            state.roms = mac_firmware::list_roms();
+            state.selected = 0;
+            let count = state.roms.len();
+            app.set_status(format!("{} ROM file(s) in {}", count, mac_firmware::rom_dir().display()));
+        }
+        KeyCode::Char('s') => save(app),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn save(app: &mut App) {
+    let Some(vm) = app.selected_vm().cloned() else {
+        return;
+    };
+    if app.running_vms.contains_key(&vm.id) {
+        app.set_status("Error: Cannot change firmware while the VM is running. Please shut down the VM first.");
+        return;
+    }
+    let Some(ref state) = app.mac_firmware_state else {
+        return;
+    };
+    let mut firmware = state.firmware.clone();
+    let machine = vm.config.machine.clone().unwrap_or_default();
+    if mac_firmware::needs_rom(&machine) {
+        if firmware.rom.is_none() {
+            app.set_status("Error: The Quadra 800 cannot boot without a ROM");
+            return;
+        }
+        firmware.pram = true;
+    }
+
+    match mac_firmware::set_mac_firmware(&vm.launch_script, Some(&firmware)) {
+        Ok(()) => {
+            app.mac_firmware_state = None;
+            app.pop_screen();
+            let _ = app.refresh_vms();
+            app.set_status("Mac firmware saved");
+        }
+        Err(e) => app.set_status(format!("Error: {}", e)),
+    }
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    Rect::new(x, y, width, height)
+}
@@ -39,11 +39,28 @@ pub fn render(app: &App, frame: &mut Frame) {
 
     let os_info = app.selected_vm_info();
     let ascii_art = app.selected_vm_ascii();
+    let vnc_address = app.selected_vm()
+        .and_then(|vm| vm.config.vnc_display)
+        .map(crate::vm::vnc::address);
 
     AsciiInfoWidget {
         ascii_art,
+        screenshot: app
+            .latest_screenshot
+            .as_ref()
+            .filter(|latest| app.selected_vm().is_some_and(|vm| vm.id == latest.vm_id))
+            .and_then(|latest| latest.image.as_ref()),
         os_info: os_info.as_ref(),
         vm_name: &vm_name,
+        archival_date: app.selected_vm().and_then(|vm| vm.config.archival_date.as_deref()),
+        guest_info: app.selected_vm().and_then(|vm| app.guest_info.get(&vm.id)),
+        vnc_address: vnc_address.as_deref(),
+        install_pending: app.selected_vm().is_some_and(|vm| vm.remote.is_none() && vm.config.install_pending),
+        suspended: app
+            .selected_vm()
+            .filter(|vm| vm.remote.is_none())
+            .and_then(|vm| crate::vm::suspend::saved_state_size(&vm.path)),
+        launch_history: app.selected_vm().and_then(|vm| app.launch_history.get(&vm.id)),
         scroll: app.info_scroll,
     }
     .render(main_chunks[1], frame.buffer_mut());
@@ -77,16 +94,36 @@ fn render_title(app: &App, area: Rect, frame: &mut Frame) {
             Style::default().fg(Color::Gray),
         ),
     ])])
-    .block(
-        Block::default()
-            .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::DarkGray)),
-    )
+    .block(title_block(app))
     .alignment(Alignment::Center);
 
     frame.render_widget(title, area);
 }
 
+/// Title border, carrying today's "on this day" anniversary if there is one
+fn title_block(app: &App) -> Block<'static> {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::DarkGray));
+    let Some(event) = app.on_this_day.get(app.on_this_day_index) else {
+        return block;
+    };
+
+    let mut spans = vec![
+        Span::styled(format!(" On this day in {}: ", event.year), Style::default().fg(Color::Magenta)),
+        Span::styled(format!("{} ", event.text), Style::default().fg(Color::White)),
+        Span::styled("[o]", Style::default().fg(Color::Yellow)),
+        Span::styled(" Go to VM ", Style::default().fg(Color::Gray)),
+    ];
+    if app.on_this_day.len() > 1 {
+        spans.push(Span::styled(
+            format!("({}/{}) ", app.on_this_day_index + 1, app.on_this_day.len()),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    block.title_bottom(Line::from(spans).centered())
+}
+
 fn render_help_bar(app: &App, area: Rect, frame: &mut Frame) {
     let mut hints = vec![
         Span::styled(" [Enter]", Style::default().fg(Color::Yellow)),
@@ -95,17 +132,26 @@ fn render_help_bar(app: &App, area: Rect, frame: &mut Frame) {
         Span::raw(" Stop "),
         Span::styled(" [m]", Style::default().fg(Color::Yellow)),
         Span::raw(" Manage "),
-        Span::styled(" [c]", Style::default().fg(Color::Yellow)),
-        Span::raw(" Create "),
-        Span::styled(" [s]", Style::default().fg(Color::Yellow)),
-        Span::raw(" Settings "),
+    ];
+    // Kiosk mode leaves the library as it is
+    if !app.config.kiosk_mode {
+        hints.extend([
+            Span::styled(" [c]", Style::default().fg(Color::Yellow)),
+            Span::raw(" Create "),
+            Span::styled(" [i]", Style::default().fg(Color::Yellow)),
+            Span::raw(" Import "),
+            Span::styled(" [s]", Style::default().fg(Color::Yellow)),
+            Span::raw(" Settings "),
+        ]);
+    }
+    hints.extend([
         Span::styled(" [/]", Style::default().fg(Color::Yellow)),
         Span::raw(" Search "),
         Span::styled(" [?]", Style::default().fg(Color::Yellow)),
         Span::raw(" Help "),
         Span::styled(" [q]", Style::default().fg(Color::Yellow)),
         Span::raw(" Quit "),
-    ];
+    ]);
 
     // Show stopping VM status
     if app.status_message.is_none() {
@@ -1,10 +1,17 @@
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
 };
 
 use crate::app::App;
 use crate::config::Config;
+use crate::format::{format_size, format_timestamp};
+use crate::vm::cpu_era::CPU_ERA_PRESETS;
+use crate::vm::midi::MIDI_SYNTHS;
+use crate::vm::display_preset::PRESET_FIELDS;
+use crate::vm::sound_blaster::SB16_FIELDS;
+use crate::vm::qemu_config::QemuEmulator;
+use crate::vm::spice::SpiceMode;
 use crate::vm::DiscoveredVm;
 
 /// Menu item with name and description
@@ -19,23 +26,84 @@ pub struct MenuItem {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MenuAction {
     StopVm,
+    SuspendVm,
     BootOptions,
     Snapshots,
     UsbPassthrough,
+    InputPassthrough,
+    GameControllers,
     PciPassthrough,
     SharedFolders,
     NetworkSettings,
     MultiGpuPassthrough,
     SingleGpuPassthrough,
     ChangeDisplay,
+    DisplayPreset,
+    GpuAccel,
+    OpenVncViewer,
+    OpenRemoteDisplay,
+    CpuModel,
+    MachineType,
+    QemuBinary,
+    MacFirmware,
+    EmulatorSettings,
+    CpuEra,
+    AudioBackend,
+    MidiOutput,
+    SoundBlaster,
     RenameVm,
+    EditOsInfo,
+    Checklist,
+    ArchivalMode,
+    DiskIo,
+    Identity,
+    BrowseDiskFiles,
+    ConvertDisk,
+    BackingChain,
+    AddDataDisk,
+    PhysicalDisks,
+    CompactDisk,
+    Doctor,
+    BootScreenshots,
+    DebugBundle,
+    MigrateVm,
+    LaunchEnvironment,
+    Autostart,
+    /// An action from a plugin (index into `plugins::actions()`)
+    PluginAction(usize),
     ResetVm,
     DeleteVm,
     EditRawConfig,
 }
 
+impl MenuAction {
+    /// Whether the action leaves the VM alone (allowed while another instance holds it)
+    pub fn is_read_only(&self) -> bool {
+        matches!(self, Self::OpenVncViewer | Self::OpenRemoteDisplay | Self::BootScreenshots)
+    }
+}
+
 /// Get menu items based on config and VM state
 pub fn get_menu_items(vm: &DiscoveredVm, config: &Config) -> Vec<MenuItem> {
+    let mut items = all_menu_items(vm, config);
+    // Kiosk visitors may look at and stop a VM, not change it
+    if config.kiosk_mode {
+        items.retain(|item| item.action.is_read_only() || item.action == MenuAction::StopVm);
+    }
+    items
+}
+
+fn all_menu_items(vm: &DiscoveredVm, config: &Config) -> Vec<MenuItem> {
+    // The launch script and disks live on the remote host
+    if vm.remote.is_some() {
+        return remote_menu_items(vm);
+    }
+
+    // 86Box and DOSBox-X keep their settings in their own config files
+    if !vm.backend.capabilities().qemu_args {
+        return backend_menu_items(vm);
+    }
+
     let mut items = vec![
         MenuItem {
             name: "Boot Options",
@@ -69,6 +137,22 @@ pub fn get_menu_items(vm: &DiscoveredVm, config: &Config) -> Vec<MenuItem> {
         },
     ];
 
+    // evdev devices exist on Linux hosts only
+    if cfg!(target_os = "linux") {
+        items.splice(3..3, [
+            MenuItem {
+                name: "Input Passthrough",
+                description: "Low-latency keyboard, mouse and controllers (evdev)",
+                action: MenuAction::InputPassthrough,
+            },
+            MenuItem {
+                name: "Game Controllers",
+                description: "Pass gamepads and joysticks through (USB or evdev)",
+                action: MenuAction::GameControllers,
+            },
+        ]);
+    }
+
     // Add Multi-GPU Passthrough option if enabled in settings
     if config.enable_multi_gpu_passthrough {
         items.push(MenuItem {
@@ -87,24 +171,211 @@ pub fn get_menu_items(vm: &DiscoveredVm, config: &Config) -> Vec<MenuItem> {
         });
     }
 
+    items.push(MenuItem {
+        name: "Change Display",
+        description: "GTK, SDL, SPICE-app, or VNC output",
+        action: MenuAction::ChangeDisplay,
+    });
+
+    items.push(MenuItem {
+        name: "Display Preset",
+        description: "Guest resolution, window scaling, and full screen",
+        action: MenuAction::DisplayPreset,
+    });
+
+    // virtio-vga-gl is a PC (x86) display adapter
+    if matches!(vm.config.emulator, QemuEmulator::X86_64 | QemuEmulator::I386) {
+        items.push(MenuItem {
+            name: "GPU Acceleration",
+            description: "virtio-gpu 3D via VirGL or EGL headless",
+            action: MenuAction::GpuAccel,
+        });
+    }
+
+    if vm.config.vnc_display.is_some() {
+        items.push(MenuItem {
+            name: "Open VNC Viewer",
+            description: "Connect a VNC client to the running VM",
+            action: MenuAction::OpenVncViewer,
+        });
+    }
+
+    if !vm.config.audio_devices.is_empty() {
+        items.push(MenuItem {
+            name: "Audio Output",
+            description: "PipeWire, PulseAudio, JACK, or ALSA on the host",
+            action: MenuAction::AudioBackend,
+        });
+    }
+
+    if vm.config.sb16.is_some() {
+        items.push(MenuItem {
+            name: "Sound Blaster",
+            description: "SB16 port, IRQ and DMA (and the BLASTER line)",
+            action: MenuAction::SoundBlaster,
+        });
+    }
+
+    items.push(MenuItem {
+        name: "CPU Model",
+        description: "Processor model, host passthrough, and CPU flags",
+        action: MenuAction::CpuModel,
+    });
+
+    if !crate::vm::machine::machines_for(vm.config.emulator.command()).is_empty() {
+        items.push(MenuItem {
+            name: "Machine Type",
+            description: "Emulated board: q35, i440FX, ISA PC, Mac, ...",
+            action: MenuAction::MachineType,
+        });
+    }
+
+    items.push(MenuItem {
+        name: "QEMU Version",
+        description: "Which QEMU build runs the VM (PATH, custom prefix, Flatpak)",
+        action: MenuAction::QemuBinary,
+    });
+
+    if matches!(vm.config.emulator, QemuEmulator::Ppc | QemuEmulator::M68k) {
+        items.push(MenuItem {
+            name: "Mac Firmware",
+            description: "Mac ROM file, OpenBIOS boot variables and PRAM",
+            action: MenuAction::MacFirmware,
+        });
+    }
+
+    // CPU era presets are x86 CPU models
+    if matches!(vm.config.emulator, QemuEmulator::X86_64 | QemuEmulator::I386) {
+        items.push(MenuItem {
+            name: "CPU Era",
+            description: "Hide modern CPU features from period software",
+            action: MenuAction::CpuEra,
+        });
+        items.push(MenuItem {
+            name: "MIDI Output",
+            description: "Route the guest's serial MIDI to FluidSynth or an MT-32",
+            action: MenuAction::MidiOutput,
+        });
+    }
+
     items.extend([
-        MenuItem {
-            name: "Change Display",
-            description: "GTK, SDL, SPICE-app, or VNC output",
-            action: MenuAction::ChangeDisplay,
-        },
         MenuItem {
             name: "Rename VM",
             description: "Change the VM's display name",
             action: MenuAction::RenameVm,
         },
+        MENU_OS_INFO,
+        MenuItem {
+            name: "Post-install Checklist",
+            description: "Steps the OS profile suggests once the OS is installed",
+            action: MenuAction::Checklist,
+        },
     ]);
 
+    items.push(if vm.config.archival_date.is_some() {
+        MenuItem {
+            name: "Leave Archival Mode",
+            description: "Restore disk writes, networking, and the real clock",
+            action: MenuAction::ArchivalMode,
+        }
+    } else {
+        MenuItem {
+            name: "Archival Mode",
+            description: "Freeze the VM: snapshot-only, no network, fixed date",
+            action: MenuAction::ArchivalMode,
+        }
+    });
+
+    if !crate::vm::disk_io::parse_disk_drives(&vm.config.raw_script).is_empty() {
+        items.push(MenuItem {
+            name: "Disk I/O",
+            description: "Cache mode, AIO, discard/TRIM and throttling per disk",
+            action: MenuAction::DiskIo,
+        });
+    }
+
+    items.push(MenuItem {
+        name: "VM Identity",
+        description: "System UUID and NIC MAC address (regenerate for copies)",
+        action: MenuAction::Identity,
+    });
+
+    if vm.config.primary_disk().is_some() {
+        items.push(MenuItem {
+            name: "Browse Disk Files",
+            description: "Mount the disk on the host to copy files in and out",
+            action: MenuAction::BrowseDiskFiles,
+        });
+    }
+
+    if vm.config.primary_disk().is_some() {
+        items.push(MenuItem {
+            name: "Convert Disk Format",
+            description: "Convert the disk between qcow2, raw, VMDK, VDI and VHD",
+            action: MenuAction::ConvertDisk,
+        });
+    }
+
+    items.push(MenuItem {
+        name: "Add Data Disk",
+        description: "Create a blank extra disk, optionally partitioned and formatted",
+        action: MenuAction::AddDataDisk,
+    });
+
+    items.push(MenuItem {
+        name: "Physical Disks",
+        description: "Attach a host hard drive or USB stick (read-only by default)",
+        action: MenuAction::PhysicalDisks,
+    });
+
+    if vm.config.supports_snapshots() {
+        items.push(MenuItem {
+            name: "Compact Disk",
+            description: "Rewrite the qcow2 image to reclaim unused space",
+            action: MenuAction::CompactDisk,
+        });
+    }
+
+    if vm.config.primary_disk().is_some_and(|d| d.format.supports_backing_file()) {
+        items.push(MenuItem {
+            name: "Backing Chain",
+            description: "Images the disk is layered on; flatten, commit, rebase or repair",
+            action: MenuAction::BackingChain,
+        });
+    }
+
+    items.push(MenuItem {
+        name: "Doctor",
+        description: "Find and fix missing disks, old paths, unsupported devices and KVM problems",
+        action: MenuAction::Doctor,
+    });
     items.push(MenuItem {
-        name: "Stop VM",
-        description: "Shut down the running VM (ACPI poweroff)",
-        action: MenuAction::StopVm,
+        name: "Boot Screenshots",
+        description: "Screens captured shortly after each launch",
+        action: MenuAction::BootScreenshots,
     });
+    items.push(MENU_DEBUG_BUNDLE);
+
+    if !config.remote_hosts.is_empty() {
+        items.push(MENU_MIGRATE);
+    }
+
+    items.push(MENU_LAUNCH_ENV);
+    items.push(MENU_AUTOSTART);
+    items.extend(plugin_menu_items());
+
+    items.extend([
+        MenuItem {
+            name: "Stop VM",
+            description: "Shut down the running VM (ACPI poweroff)",
+            action: MenuAction::StopVm,
+        },
+        MenuItem {
+            name: "Suspend VM",
+            description: "Save the whole machine to disk and stop; the next launch resumes it",
+            action: MenuAction::SuspendVm,
+        },
+    ]);
 
     // Add dangerous operations at the end
     items.extend([
@@ -132,6 +403,133 @@ pub fn get_menu_items(vm: &DiscoveredVm, config: &Config) -> Vec<MenuItem> {
     items
 }
 
+/// Menu for a VM that runs under another emulator
+fn backend_menu_items(vm: &DiscoveredVm) -> Vec<MenuItem> {
+    let mut items = vec![MenuItem {
+        name: "Boot Options",
+        description: "Normal boot, or with a CD image mounted",
+        action: MenuAction::BootOptions,
+    }];
+    if vm.backend.capabilities().settings_dialog {
+        items.push(MenuItem {
+            name: "Emulator Settings",
+            description: "Open the emulator's own configuration dialog",
+            action: MenuAction::EmulatorSettings,
+        });
+    }
+    items.extend([
+        MenuItem {
+            name: "Game Controllers",
+            description: "Emulated game port joystick and the host controller behind it",
+            action: MenuAction::GameControllers,
+        },
+        MenuItem {
+            name: "Rename VM",
+            description: "Change the VM's display name",
+            action: MenuAction::RenameVm,
+        },
+        MENU_OS_INFO,
+        MENU_DEBUG_BUNDLE,
+        MENU_LAUNCH_ENV,
+        MENU_AUTOSTART,
+    ]);
+    items.extend(plugin_menu_items());
+    items.extend([
+        MenuItem {
+            name: "Stop VM",
+            description: "Close the emulator",
+            action: MenuAction::StopVm,
+        },
+        MenuItem {
+            name: "Delete VM",
+            description: "Permanently remove this VM",
+            action: MenuAction::DeleteVm,
+        },
+        MenuItem {
+            name: "Edit Raw Configuration",
+            description: "Edit the launch.sh script directly",
+            action: MenuAction::EditRawConfig,
+        },
+    ]);
+    items
+}
+
+/// Menu for a VM in a remote host's library
+fn remote_menu_items(vm: &DiscoveredVm) -> Vec<MenuItem> {
+    let mut items = vec![MenuItem {
+        name: "Boot Options",
+        description: "Normal, install, or ISO boot (ISO paths are on the remote host)",
+        action: MenuAction::BootOptions,
+    }];
+    let spice_server = matches!(vm.config.spice, Some(s) if s.mode == crate::vm::spice::SpiceMode::Server);
+    if vm.config.vnc_display.is_some() || spice_server {
+        items.push(MenuItem {
+            name: "Open Display",
+            description: "View the running VM through an SSH tunnel",
+            action: MenuAction::OpenRemoteDisplay,
+        });
+    }
+    items.extend([
+        MENU_OS_INFO,
+        MENU_MIGRATE,
+        MenuItem {
+            name: "Stop VM",
+            description: "Shut down the VM on the remote host",
+            action: MenuAction::StopVm,
+        },
+    ]);
+    items
+}
+
+/// Actions added by plugins (described by their plugin when they have no description)
+fn plugin_menu_items() -> Vec<MenuItem> {
+    crate::plugins::all()
+        .iter()
+        .flat_map(|plugin| plugin.actions.iter().map(move |action| (plugin, action)))
+        .enumerate()
+        .map(|(index, (plugin, action))| MenuItem {
+            name: &action.name,
+            description: if action.description.is_empty() { &plugin.name } else { &action.description },
+            action: MenuAction::PluginAction(index),
+        })
+        .collect()
+}
+
+/// Offered for every VM (the info lives in this machine's metadata folder)
+const MENU_OS_INFO: MenuItem = MenuItem {
+    name: "Edit OS Info",
+    description: "Name, publisher, release date, description and fun facts",
+    action: MenuAction::EditOsInfo,
+};
+
+/// Offered for every VM in the local library
+const MENU_AUTOSTART: MenuItem = MenuItem {
+    name: "Autostart (systemd)",
+    description: "Start the VM at login or boot as a systemd user service",
+    action: MenuAction::Autostart,
+};
+
+/// Offered for every VM in the local library
+const MENU_LAUNCH_ENV: MenuItem = MenuItem {
+    name: "Launch Environment",
+    description: "Environment variables and a wrapper command (nice, gamemoderun)",
+    action: MenuAction::LaunchEnvironment,
+};
+
+/// Offered for every VM in the local library
+const MENU_DEBUG_BUNDLE: MenuItem = MenuItem {
+    name: "Create Debug Bundle",
+    description: "Collect logs, host info and this VM's launch script for a bug report",
+    action: MenuAction::DebugBundle,
+};
+
+/// Offered in the local and remote menus when remote hosts are configured
+const MENU_MIGRATE: MenuItem = MenuItem {
+    name: "Migrate to Host",
+    description: "Move the VM to another library host (live if it is running)",
+    action: MenuAction::MigrateVm,
+};
+
 /// Get the count of menu items (for navigation bounds)
 pub fn menu_item_count(app: &App) -> usize {
     if let Some(vm) = app.selected_vm() {
@@ -146,6 +544,7 @@ const DISPLAY_OPTIONS: &[(&str, &str)] = &[
     ("gtk", "GTK - Default windowed display"),
     ("sdl", "SDL - Better for 3D acceleration"),
     ("spice-app", "SPICE - Remote desktop (needs virt-viewer)"),
+    ("spice", "SPICE server - opens remote-viewer/spicy"),
     ("vnc", "VNC - Network accessible display"),
     ("none", "None - Headless, no graphical output"),
 ];
@@ -160,8 +559,14 @@ pub fn get_display_options(app: &App) -> Vec<(String, String)> {
 
     let detected = app.get_display_options_for_emulator(emulator);
 
+    // A QEMU with spice-app can also run a plain SPICE server for an external viewer
+    let mut backends = detected.clone();
+    if let Some(pos) = backends.iter().position(|b| b == "spice-app") {
+        backends.insert(pos + 1, "spice".to_string());
+    }
+
     // Map detected backends to (name, description) pairs using DISPLAY_OPTIONS for descriptions
-    detected.iter().map(|backend| {
+    backends.iter().map(|backend| {
         let desc = DISPLAY_OPTIONS.iter()
             .find(|(name, _)| *name == backend.as_str())
             .map(|(_, desc)| desc.to_string())
@@ -170,6 +575,20 @@ pub fn get_display_options(app: &App) -> Vec<(String, String)> {
     }).collect()
 }
 
+/// Current display name for a VM
+///
+/// SPICE server mode and VNC both run QEMU with `-display none`, so they're
+/// recognized from their managed sections instead.
+pub fn current_display(vm: &DiscoveredVm) -> String {
+    if vm.config.vnc_display.is_some() {
+        return "vnc".to_string();
+    }
+    match vm.config.spice {
+        Some(spice) if spice.mode == SpiceMode::Server => spice.mode.display_name().to_string(),
+        _ => extract_display_from_script(&vm.config.raw_script),
+    }
+}
+
 /// Render the management menu
 pub fn render(app: &App, frame: &mut Frame) {
     let area = frame.area();
@@ -255,18 +674,60 @@ pub fn render(app: &App, frame: &mut Frame) {
 
     frame.render_stateful_widget(list, chunks[1], &mut state);
 
-    // Help text
-    let help = Paragraph::new("[Enter] Select  [Esc] Back")
-        .style(Style::default().fg(Color::DarkGray))
-        .alignment(Alignment::Center);
+    // Help text, under a warning when another machine holds the VM
+    let mut help = vec![Line::styled("[Enter] Select  [Esc] Back", Style::default().fg(Color::DarkGray))];
+    if let Some(owner) = app.edit_lock.as_ref().and_then(|l| l.held_by.as_ref()) {
+        help.insert(0, Line::styled(format!("In use: {}", owner.describe()), Style::default().fg(Color::Red)));
+    }
+    let help = Paragraph::new(help).alignment(Alignment::Center);
     frame.render_widget(help, chunks[2]);
 }
 
+pub const RESUME_BOOT: &str = "Resume from saved state";
+pub const NORMAL_BOOT: &str = "Normal boot";
+pub const INSTALL_BOOT: &str = "Install mode";
+pub const CUSTOM_ISO_BOOT: &str = "Boot with custom ISO";
+pub const LAUNCH_WITH_OPTIONS: &str = "Launch with options...";
+pub const INSTALL_COMPLETE: &str = "Installation complete";
+
+/// Boot options for the selected VM; local QEMU VMs can be launched with
+/// one-off settings, and a VM still being installed also offers to end
+/// install mode
+pub fn boot_option_items(app: &App) -> Vec<(&'static str, &'static str)> {
+    let local_qemu = app.selected_vm().is_some_and(|vm| vm.remote.is_none() && vm.backend.capabilities().qemu_args);
+    let install_pending = app.selected_vm().is_some_and(|vm| vm.remote.is_none() && vm.config.install_pending);
+    let suspended = app
+        .selected_vm()
+        .is_some_and(|vm| vm.remote.is_none() && crate::vm::suspend::has_saved_state(&vm.path));
+    let mut items = Vec::new();
+    if suspended {
+        items.push((RESUME_BOOT, "Continue the suspended session"));
+    }
+    items.extend([
+        if suspended {
+            (NORMAL_BOOT, "Discard the saved state, boot fresh")
+        } else if install_pending {
+            (NORMAL_BOOT, "Boots the install ISO until installed")
+        } else {
+            (NORMAL_BOOT, "Start the VM normally")
+        },
+        (INSTALL_BOOT, "Boot from installation media"),
+        (CUSTOM_ISO_BOOT, "Select an ISO file to boot"),
+    ]);
+    if local_qemu {
+        items.push((LAUNCH_WITH_OPTIONS, "One-off memory, CPUs or display"));
+    }
+    if install_pending {
+        items.push((INSTALL_COMPLETE, "Boot from the disk from now on"));
+    }
+    items
+}
+
 /// Render boot options submenu
 pub fn render_boot_options(app: &App, frame: &mut Frame) {
     let area = frame.area();
     let dialog_width = 45.min(area.width.saturating_sub(4));
-    let dialog_height = 14.min(area.height.saturating_sub(4));
+    let dialog_height = 16.min(area.height.saturating_sub(4));
 
     let dialog_area = centered_rect(dialog_width, dialog_height, area);
     frame.render_widget(Clear, dialog_area);
@@ -299,13 +760,7 @@ pub fn render_boot_options(app: &App, frame: &mut Frame) {
         ])
         .split(h_chunks[1]);
 
-    let boot_items = [
-        ("Normal boot", "Start the VM normally"),
-        ("Install mode", "Boot from installation media"),
-        ("Boot with custom ISO", "Select an ISO file to boot"),
-    ];
-
-    let items: Vec<ListItem> = boot_items
+    let items: Vec<ListItem> = boot_option_items(app)
         .iter()
         .enumerate()
         .map(|(i, (name, desc))| {
@@ -333,15 +788,16 @@ pub fn render_boot_options(app: &App, frame: &mut Frame) {
 pub fn render_display_options(app: &App, frame: &mut Frame) {
     let area = frame.area();
     let dialog_width = 50.min(area.width.saturating_sub(4));
-    let dialog_height = 16.min(area.height.saturating_sub(4));
+    let dialog_height = 19.min(area.height.saturating_sub(4));
 
     let dialog_area = centered_rect(dialog_width, dialog_height, area);
     frame.render_widget(Clear, dialog_area);
 
     // Get current display setting from VM
     let current_display = app.selected_vm()
-        .map(|vm| extract_display_from_script(&vm.config.raw_script))
+        .map(current_display)
         .unwrap_or_else(|| "gtk".to_string());
+    let spice = app.selected_vm().and_then(|vm| vm.config.spice);
 
     let block = Block::default()
         .title(format!(" Display Options (current: {}) ", current_display))
@@ -402,54 +858,36 @@ pub fn render_display_options(app: &App, frame: &mut Frame) {
     let list = List::new(items);
     frame.render_stateful_widget(list, v_chunks[1], &mut state);
 
-    // Help text
-    let help = Paragraph::new("[Enter] Select  [Esc] Back")
+    // Help text, with the SPICE channel toggles when a SPICE display is active
+    let mut help_lines = vec![Line::from("[Enter] Select  [Esc] Back")];
+    if let Some(spice) = spice {
+        let on_off = |enabled: bool| if enabled { "on" } else { "off" };
+        help_lines.push(Line::from(format!(
+            "[c] Clipboard: {}  [u] USB redirect: {}",
+            on_off(spice.clipboard),
+            on_off(spice.usb_redir)
+        )));
+    }
+    let help = Paragraph::new(help_lines)
         .style(Style::default().fg(Color::DarkGray))
         .alignment(Alignment::Center);
     frame.render_widget(help, v_chunks[2]);
 }
 
-/// Extract display setting from launch script
-fn extract_display_from_script(script: &str) -> String {
-    // Look for -display X pattern
-    if let Some(pos) = script.find("-display ") {
-        let rest = &script[pos + 9..];
-        // Find the display value (ends at space, comma, or backslash)
-        let end = rest.find(|c: char| c.is_whitespace() || c == ',' || c == '\\')
-            .unwrap_or(rest.len());
-        let display = rest[..end].trim();
-        // Handle gl=on suffix
-        if let Some(comma_pos) = display.find(',') {
-            return display[..comma_pos].to_string();
-        }
-        return display.to_string();
-    }
-    "gtk".to_string() // Default
-}
-
-/// Render snapshot management submenu
-pub fn render_snapshots(app: &App, frame: &mut Frame) {
-    use ratatui::widgets::Wrap;
+/// Render the host audio backend picker
+pub fn render_audio_backend(app: &App, frame: &mut Frame) {
+    let options = app.vm_audio_backend_options();
+    let current = app.selected_vm().and_then(|vm| vm.config.audio_backend.clone());
 
     let area = frame.area();
-    let dialog_width = 55.min(area.width.saturating_sub(4));
-    let dialog_height = 18.min(area.height.saturating_sub(4));
+    let dialog_width = 50.min(area.width.saturating_sub(4));
+    let dialog_height = (7 + options.len() as u16).min(area.height.saturating_sub(4));
 
     let dialog_area = centered_rect(dialog_width, dialog_height, area);
     frame.render_widget(Clear, dialog_area);
 
-    let supports_snapshots = app.selected_vm()
-        .map(|vm| vm.config.supports_snapshots())
-        .unwrap_or(false);
-
-    let title = if supports_snapshots {
-        format!(" Snapshots ({}) ", app.snapshots.len())
-    } else {
-        " Snapshots (not supported) ".to_string()
-    };
-
     let block = Block::default()
-        .title(title)
+        .title(" Audio Output ")
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::Cyan))
         .style(Style::default().bg(Color::Black));
@@ -457,7 +895,6 @@ pub fn render_snapshots(app: &App, frame: &mut Frame) {
     let inner = block.inner(dialog_area);
     frame.render_widget(block, dialog_area);
 
-    // Add horizontal margins
     let h_chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
@@ -467,38 +904,981 @@ pub fn render_snapshots(app: &App, frame: &mut Frame) {
         ])
         .split(inner);
 
-    // Add top padding
     let v_chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(1),  // Top padding
             Constraint::Min(1),     // Content
+            Constraint::Length(2),  // Help
         ])
         .split(h_chunks[1]);
 
-    let content_area = v_chunks[1];
-
-    if !supports_snapshots {
-        let msg = Paragraph::new("This VM uses a raw disk image which doesn't support snapshots.\n\nOnly qcow2 format disks support snapshots.")
-            .style(Style::default().fg(Color::Yellow))
-            .wrap(Wrap { trim: false });
-        frame.render_widget(msg, content_area);
-        return;
-    }
+    let items: Vec<ListItem> = options
+        .iter()
+        .enumerate()
+        .map(|(i, driver)| {
+            let is_current = current.as_deref() == Some(driver.as_str());
+            let style = if i == app.selected_menu_item {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else if is_current {
+                Style::default().fg(Color::Green)
+            } else {
+                Style::default().fg(Color::White)
+            };
 
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Length(3), Constraint::Min(4), Constraint::Length(2)])
-        .split(content_area);
+            let marker = if is_current { " *" } else { "" };
+            let label = crate::hardware::audio::backend_label(driver);
+            ListItem::new(Line::styled(format!("[{}] {}{}", i + 1, label, marker), style))
+        })
+        .collect();
 
-    // Action buttons
-    let actions = Paragraph::new(vec![
-        Line::from(vec![
-            Span::styled("[c]", Style::default().fg(Color::Yellow)),
-            Span::raw(" Create new snapshot"),
-        ]),
-    ]);
-    frame.render_widget(actions, chunks[0]);
+    let mut state = ListState::default();
+    state.select(Some(app.selected_menu_item));
+
+    let list = List::new(items);
+    frame.render_stateful_widget(list, v_chunks[1], &mut state);
+
+    let help_text = if current.is_none() {
+        "Converts legacy sound setup  [Enter] Select  [Esc] Back"
+    } else {
+        "[Enter] Select  [Esc] Back"
+    };
+    let help = Paragraph::new(help_text)
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, v_chunks[2]);
+}
+
+/// Render the MIDI synth picker (first entry turns MIDI output off)
+pub fn render_midi_output(app: &App, frame: &mut Frame) {
+    let area = frame.area();
+    let dialog_width = 64.min(area.width.saturating_sub(4));
+    let dialog_height = (7 + (MIDI_SYNTHS.len() as u16 + 1) * 2).min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let current = app.selected_vm().and_then(|vm| vm.config.midi_synth.clone());
+
+    let block = Block::default()
+        .title(" MIDI Output ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let h_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Length(2),  // Left margin
+            Constraint::Min(1),     // Content
+            Constraint::Length(2),  // Right margin
+        ])
+        .split(inner);
+
+    let v_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),  // Top padding
+            Constraint::Min(1),     // Content
+            Constraint::Length(2),  // Help
+        ])
+        .split(h_chunks[1]);
+
+    let options = std::iter::once(("Off", "COM1 stays a plain serial port", None))
+        .chain(MIDI_SYNTHS.iter().map(|s| (s.name, s.description, Some(s.id))));
+
+    let items: Vec<ListItem> = options
+        .enumerate()
+        .map(|(i, (name, desc, id))| {
+            let is_current = current.as_deref() == id;
+            let style = if i == app.selected_menu_item {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else if is_current {
+                Style::default().fg(Color::Green)
+            } else {
+                Style::default().fg(Color::White)
+            };
+
+            let marker = if is_current { " *" } else { "" };
+
+            ListItem::new(vec![
+                Line::styled(format!("[{}] {}{}", i + 1, name, marker), style),
+                Line::styled(format!("    {}", desc), Style::default().fg(Color::DarkGray)),
+            ])
+        })
+        .collect();
+
+    let mut state = ListState::default();
+    state.select(Some(app.selected_menu_item));
+
+    let list = List::new(items);
+    frame.render_stateful_widget(list, v_chunks[1], &mut state);
+
+    let help = Paragraph::new(vec![
+        Line::from("Guest: SoftMPU /OUTPUT:COM1 or a serial MIDI driver"),
+        Line::from("[Enter] Select  [Esc] Back"),
+    ])
+    .style(Style::default().fg(Color::DarkGray))
+    .alignment(Alignment::Center);
+    frame.render_widget(help, v_chunks[2]);
+}
+
+/// Render the Sound Blaster 16 resource editor
+pub fn render_sound_blaster(app: &App, frame: &mut Frame) {
+    let Some(settings) = app.sb16_edit else {
+        return;
+    };
+
+    let area = frame.area();
+    let dialog_width = 50.min(area.width.saturating_sub(4));
+    let dialog_height = (10 + SB16_FIELDS.len() as u16).min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let block = Block::default()
+        .title(" Sound Blaster 16 ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let h_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Length(2),  // Left margin
+            Constraint::Min(1),     // Content
+            Constraint::Length(2),  // Right margin
+        ])
+        .split(inner);
+
+    let v_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),  // Top padding
+            Constraint::Min(1),     // Content
+            Constraint::Length(2),  // Help
+        ])
+        .split(h_chunks[1]);
+
+    let mut lines: Vec<Line> = SB16_FIELDS
+        .iter()
+        .enumerate()
+        .map(|(i, label)| {
+            let selected = i == app.selected_menu_item;
+            let style = if selected {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            let prefix = if selected { "> " } else { "  " };
+            Line::styled(format!("{}{:<10} {}", prefix, format!("{}:", label), settings.field_value(i)), style)
+        })
+        .collect();
+
+    lines.push(Line::from(""));
+    lines.push(Line::styled("In the guest's AUTOEXEC.BAT:", Style::default().fg(Color::DarkGray)));
+    lines.push(Line::styled(
+        format!("SET BLASTER={}", settings.blaster()),
+        Style::default().fg(Color::Green),
+    ));
+
+    frame.render_widget(Paragraph::new(lines), v_chunks[1]);
+
+    let help = Paragraph::new("[←/→] Change  [Enter] Save  [Esc] Cancel")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, v_chunks[2]);
+}
+
+/// Render the display preset editor (resolution, scaling, full screen)
+pub fn render_display_preset(app: &App, frame: &mut Frame) {
+    let Some(ref preset) = app.display_preset_edit else {
+        return;
+    };
+
+    let area = frame.area();
+    let dialog_width = 56.min(area.width.saturating_sub(4));
+    let dialog_height = (10 + PRESET_FIELDS.len() as u16).min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let block = Block::default()
+        .title(" Display Preset ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let h_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Length(2),  // Left margin
+            Constraint::Min(1),     // Content
+            Constraint::Length(2),  // Right margin
+        ])
+        .split(inner);
+
+    let v_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),  // Top padding
+            Constraint::Min(1),     // Content
+            Constraint::Length(2),  // Help
+        ])
+        .split(h_chunks[1]);
+
+    let mut lines: Vec<Line> = PRESET_FIELDS
+        .iter()
+        .enumerate()
+        .map(|(i, label)| {
+            let selected = i == app.selected_menu_item;
+            let style = if selected {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            let prefix = if selected { "> " } else { "  " };
+            Line::styled(format!("{}{:<13} {}", prefix, format!("{}:", label), preset.field_value(i)), style)
+        })
+        .collect();
+
+    let note = match app.selected_menu_item {
+        0 => "EDID hint for std/qxl graphics; the guest driver still picks the mode",
+        1 => "fit stretches to the window; 2x/3x scale by whole pixels (GTK)",
+        _ => "Starts QEMU full screen (Ctrl+Alt+F toggles it back)",
+    };
+    lines.push(Line::from(""));
+    lines.push(Line::styled(note, Style::default().fg(Color::DarkGray)));
+
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: true }), v_chunks[1]);
+
+    let help = Paragraph::new("[←/→] Change  [Enter] Save  [Esc] Cancel")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, v_chunks[2]);
+}
+
+/// Render the GPU acceleration picker with host GL status and warnings
+pub fn render_gpu_accel(app: &App, frame: &mut Frame) {
+    use crate::vm::gpu_accel::{accel_warnings, effective_display, GPU_ACCEL_MODES};
+
+    let Some(vm) = app.selected_vm() else {
+        return;
+    };
+
+    let area = frame.area();
+    let dialog_width = 70.min(area.width.saturating_sub(4));
+    let dialog_height = 22.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let current = vm.config.gpu_accel.as_deref().unwrap_or("off");
+    let display = effective_display(&vm.config);
+
+    let block = Block::default()
+        .title(" GPU Acceleration ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let h_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Length(2),  // Left margin
+            Constraint::Min(1),     // Content
+            Constraint::Length(2),  // Right margin
+        ])
+        .split(inner);
+
+    let v_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),                                  // Top padding
+            Constraint::Length(GPU_ACCEL_MODES.len() as u16 * 2),   // Modes
+            Constraint::Min(1),                                     // Host status and warnings
+            Constraint::Length(1),                                  // Help
+        ])
+        .split(h_chunks[1]);
+
+    let items: Vec<ListItem> = GPU_ACCEL_MODES
+        .iter()
+        .enumerate()
+        .map(|(i, mode)| {
+            let is_current = mode.id == current;
+            let style = if i == app.selected_menu_item {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else if is_current {
+                Style::default().fg(Color::Green)
+            } else {
+                Style::default().fg(Color::White)
+            };
+
+            let marker = if is_current { " *" } else { "" };
+
+            ListItem::new(vec![
+                Line::styled(format!("[{}] {}{}", i + 1, mode.name, marker), style),
+                Line::styled(format!("    {}", mode.description), Style::default().fg(Color::DarkGray)),
+            ])
+        })
+        .collect();
+
+    let mut state = ListState::default();
+    state.select(Some(app.selected_menu_item));
+    frame.render_stateful_widget(List::new(items), v_chunks[1], &mut state);
+
+    // Host status
+    let label = Style::default().fg(Color::Yellow);
+    let nodes = if app.host_gl.render_nodes.is_empty() {
+        "none".to_string()
+    } else {
+        app.host_gl
+            .render_nodes
+            .iter()
+            .map(|n| {
+                let name = n.path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+                match (&n.driver, n.accessible) {
+                    (Some(driver), true) => format!("{} ({})", name, driver),
+                    (Some(driver), false) => format!("{} ({}, no access)", name, driver),
+                    (None, true) => name,
+                    (None, false) => format!("{} (no access)", name),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let yes_no = |v: bool| if v { "yes" } else { "no" };
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(vec![Span::styled("Render nodes:  ", label), Span::raw(nodes)]),
+        Line::from(vec![
+            Span::styled("virglrenderer: ", label),
+            Span::raw(yes_no(app.host_gl.virglrenderer)),
+        ]),
+        Line::from(vec![
+            Span::styled("virtio-vga-gl: ", label),
+            Span::raw(app.qemu_has_virgl.map(yes_no).unwrap_or("unknown")),
+        ]),
+        Line::from(vec![Span::styled("Display:       ", label), Span::raw(display.clone())]),
+        Line::from(""),
+    ];
+
+    let selected = GPU_ACCEL_MODES.get(app.selected_menu_item).map(|m| m.id).unwrap_or("off");
+    for warning in accel_warnings(selected, &display, &app.host_gl, app.qemu_has_virgl) {
+        lines.push(Line::styled(format!("! {}", warning), Style::default().fg(Color::Red)));
+    }
+
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: true }), v_chunks[2]);
+
+    let help = Paragraph::new("[Enter] Select  [Esc] Back")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, v_chunks[3]);
+}
+
+/// Render the CPU model picker and flag toggles
+pub fn render_cpu_model(app: &App, frame: &mut Frame) {
+    use crate::vm::cpu_model::CPU_FLAGS;
+
+    let (Some(state), Some(vm)) = (app.cpu_model_state.as_ref(), app.selected_vm()) else {
+        return;
+    };
+
+    let area = frame.area();
+    let dialog_width = 76.min(area.width.saturating_sub(4));
+    let dialog_height = 28.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let block = Block::default()
+        .title(" CPU Model ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let h_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Length(2),  // Left margin
+            Constraint::Min(1),     // Content
+            Constraint::Length(2),  // Right margin
+        ])
+        .split(inner);
+
+    let v_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),                          // Top padding
+            Constraint::Min(4),                             // Models
+            Constraint::Length(1),                          // Flags header
+            Constraint::Length(CPU_FLAGS.len() as u16),     // Flags
+            Constraint::Length(2),                          // Notes
+            Constraint::Length(1),                          // Help
+        ])
+        .split(h_chunks[1]);
+
+    let current_model = vm.config.cpu_model.as_deref()
+        .map(|m| m.split(',').next().unwrap_or(m))
+        .unwrap_or("");
+
+    let items: Vec<ListItem> = state
+        .models
+        .iter()
+        .enumerate()
+        .map(|(i, (name, desc))| {
+            let is_current = name == current_model;
+            let style = if i == state.model_selected && !state.editing_flags {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else if i == state.model_selected {
+                Style::default().fg(Color::Yellow)
+            } else if is_current {
+                Style::default().fg(Color::Green)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            let label = if name.is_empty() { "(default)" } else { name.as_str() };
+            let marker = if is_current { "*" } else { " " };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{} {:<24}", marker, label), style),
+                Span::styled(desc.clone(), Style::default().fg(Color::DarkGray)),
+            ]))
+        })
+        .collect();
+
+    let mut list_state = ListState::default();
+    list_state.select(Some(state.model_selected));
+    frame.render_stateful_widget(List::new(items), v_chunks[1], &mut list_state);
+
+    let flags_title = if state.editing_flags { "Flags (Space toggles):" } else { "Flags:" };
+    frame.render_widget(
+        Paragraph::new(Line::styled(flags_title, Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))),
+        v_chunks[2],
+    );
+
+    let flag_lines: Vec<Line> = CPU_FLAGS
+        .iter()
+        .enumerate()
+        .map(|(i, flag)| {
+            let on = state.has_flag(flag.token);
+            let style = if state.editing_flags && i == state.flag_selected {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            Line::from(vec![
+                Span::styled(format!("[{}] {:<24}", if on { "x" } else { " " }, flag.name), style),
+                Span::styled(flag.description, Style::default().fg(Color::DarkGray)),
+            ])
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(flag_lines), v_chunks[3]);
+
+    // Notes on the pending choice
+    let spec = state.spec();
+    let mut notes = Vec::new();
+    match spec {
+        Some(ref spec) => {
+            if spec.model == "host" && !vm.config.enable_kvm {
+                notes.push("host needs KVM; pick a named model for TCG".to_string());
+            }
+            notes.push(format!("-cpu {}", spec.to_arg()));
+        }
+        None if !state.flags.is_empty() => notes.push("Flags need a named model; they are dropped".to_string()),
+        None => {}
+    }
+    if let Some(preset) = vm.config.cpu_era.as_deref().and_then(crate::vm::cpu_era::find_preset) {
+        notes.insert(0, format!("CPU Era \"{}\" overrides this until removed", preset.name));
+    }
+    let note_lines: Vec<Line> = notes
+        .into_iter()
+        .take(2)
+        .map(|n| Line::styled(n, Style::default().fg(Color::DarkGray)))
+        .collect();
+    frame.render_widget(Paragraph::new(note_lines), v_chunks[4]);
+
+    let help = Paragraph::new("[↑/↓] Move  [Tab] Models/Flags  [Enter] Save  [Esc] Cancel")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, v_chunks[5]);
+}
+
+/// Render the machine type picker with compatibility notes
+pub fn render_machine_type(app: &App, frame: &mut Frame) {
+    use crate::vm::machine::{find_machine, machine_options, machine_warnings};
+
+    let Some(vm) = app.selected_vm() else {
+        return;
+    };
+
+    let emulator = vm.config.emulator.command();
+    let current = vm.config.machine.as_deref();
+    let options = machine_options(emulator, current);
+
+    let area = frame.area();
+    let dialog_width = 72.min(area.width.saturating_sub(4));
+    let dialog_height = (12 + options.len() as u16).min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let block = Block::default()
+        .title(" Machine Type ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let h_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Length(2),  // Left margin
+            Constraint::Min(1),     // Content
+            Constraint::Length(2),  // Right margin
+        ])
+        .split(inner);
+
+    let v_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),                      // Top padding
+            Constraint::Length(options.len() as u16),   // Machines
+            Constraint::Min(1),                         // Notes and warnings
+            Constraint::Length(1),                      // Help
+        ])
+        .split(h_chunks[1]);
+
+    let items: Vec<ListItem> = options
+        .iter()
+        .enumerate()
+        .map(|(i, id)| {
+            let is_current = Some(id.as_str()) == current;
+            let style = if i == app.selected_menu_item {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else if is_current {
+                Style::default().fg(Color::Green)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            let marker = if is_current { "*" } else { " " };
+            let name = find_machine(emulator, id).map(|m| m.name).unwrap_or("custom");
+            let missing = app.qemu_machines.as_ref().is_some_and(|machines| !machines.contains(id));
+            let mut spans = vec![
+                Span::styled(format!("{} {:<12}", marker, id), style),
+                Span::styled(name, Style::default().fg(Color::DarkGray)),
+            ];
+            if missing {
+                spans.push(Span::styled("  (not in this QEMU)", Style::default().fg(Color::Red)));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let mut state = ListState::default();
+    state.select(Some(app.selected_menu_item));
+    frame.render_stateful_widget(List::new(items), v_chunks[1], &mut state);
+
+    let mut lines = vec![Line::from("")];
+    if let Some(selected) = options.get(app.selected_menu_item) {
+        if let Some(machine) = find_machine(emulator, selected) {
+            lines.push(Line::styled(machine.notes, Style::default().fg(Color::White)));
+        }
+        if Some(selected.as_str()) != current {
+            for warning in machine_warnings(selected, &vm.config) {
+                lines.push(Line::styled(format!("! {}", warning), Style::default().fg(Color::Red)));
+            }
+        }
+    }
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: true }), v_chunks[2]);
+
+    let help = Paragraph::new("[Enter] Select  [Esc] Back")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, v_chunks[3]);
+}
+
+/// Render the per-disk I/O tuning editor
+pub fn render_disk_io(app: &App, frame: &mut Frame) {
+    use crate::vm::disk_io::TUNING_FIELDS;
+
+    let Some(state) = app.disk_io_state.as_ref() else {
+        return;
+    };
+    let Some(drive) = state.drives.get(state.drive_selected) else {
+        return;
+    };
+
+    let area = frame.area();
+    let dialog_width = 64.min(area.width.saturating_sub(4));
+    let dialog_height = 18.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let block = Block::default()
+        .title(" Disk I/O ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let h_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Length(2),  // Left margin
+            Constraint::Min(1),     // Content
+            Constraint::Length(2),  // Right margin
+        ])
+        .split(inner);
+
+    let v_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),  // Top padding
+            Constraint::Length(2),  // Disk
+            Constraint::Min(1),     // Fields and notes
+            Constraint::Length(1),  // Help
+        ])
+        .split(h_chunks[1]);
+
+    let disk_line = Line::from(vec![
+        Span::styled("Disk: ", Style::default().fg(Color::Yellow)),
+        Span::raw(format!("{} ({})", drive.file, drive.interface)),
+        Span::styled(
+            format!("  {}/{}", state.drive_selected + 1, state.drives.len()),
+            Style::default().fg(Color::DarkGray),
+        ),
+    ]);
+    frame.render_widget(Paragraph::new(disk_line), v_chunks[1]);
+
+    let mut lines: Vec<Line> = TUNING_FIELDS
+        .iter()
+        .enumerate()
+        .map(|(i, label)| {
+            let selected = i == app.selected_menu_item;
+            let style = if selected {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            let prefix = if selected { "> " } else { "  " };
+            Line::styled(format!("{}{:<15} {}", prefix, format!("{}:", label), drive.tuning.field_value(i)), style)
+        })
+        .collect();
+
+    let note = match app.selected_menu_item {
+        0 => "none bypasses the host page cache; unsafe skips flushes (fast installs)",
+        1 => "io_uring or native cut latency; native needs cache=none",
+        2 => "Frees image space when the guest trims (qcow2 and raw)",
+        3 => "Caps reads + writes per second so one VM can't hog the disk",
+        _ => "Caps read + write throughput",
+    };
+    lines.push(Line::from(""));
+    lines.push(Line::styled(note, Style::default().fg(Color::DarkGray)));
+    for warning in drive.tuning.warnings() {
+        lines.push(Line::styled(format!("! {}", warning), Style::default().fg(Color::Red)));
+    }
+
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: true }), v_chunks[2]);
+
+    let help = if state.drives.len() > 1 {
+        "[←/→] Change  [Tab] Next disk  [Enter] Save  [Esc] Cancel"
+    } else {
+        "[←/→] Change  [Enter] Save  [Esc] Cancel"
+    };
+    let help = Paragraph::new(help)
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, v_chunks[3]);
+}
+
+/// Render a VM's UUID and MAC, flagging copies that share them
+pub fn render_identity(app: &App, frame: &mut Frame) {
+    let Some(vm) = app.selected_vm() else {
+        return;
+    };
+
+    let area = frame.area();
+    let dialog_width = 66.min(area.width.saturating_sub(4));
+    let dialog_height = 16.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let block = Block::default()
+        .title(" VM Identity ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let h_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Length(2),  // Left margin
+            Constraint::Min(1),     // Content
+            Constraint::Length(2),  // Right margin
+        ])
+        .split(inner);
+
+    let v_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),  // Top padding
+            Constraint::Min(1),     // Identity and notes
+            Constraint::Length(1),  // Help
+        ])
+        .split(h_chunks[1]);
+
+    let label = Style::default().fg(Color::Yellow);
+    let note = Style::default().fg(Color::DarkGray);
+    let mut lines = Vec::new();
+    let help = match vm.config.identity {
+        Some(ref identity) => {
+            lines.push(Line::from(vec![Span::styled("UUID: ", label), Span::raw(identity.uuid.clone())]));
+            lines.push(Line::from(vec![Span::styled("MAC:  ", label), Span::raw(identity.mac.clone())]));
+            lines.push(Line::from(""));
+
+            let copies = crate::vm::identity::shared_with(vm, &app.vms);
+            if copies.is_empty() {
+                lines.push(Line::styled("Unique in this library.", Style::default().fg(Color::Green)));
+            } else {
+                let names: Vec<String> = copies.iter().map(|c| c.display_name()).collect();
+                lines.push(Line::styled(
+                    format!("! Shared with {}: regenerate one of the copies", names.join(", ")),
+                    Style::default().fg(Color::Red),
+                ));
+            }
+            lines.push(Line::from(""));
+            lines.push(Line::styled(
+                "Regenerating gives the guest a new machine and NIC: Windows may ask to reactivate.",
+                note,
+            ));
+            "[r] Regenerate  [Esc] Back"
+        }
+        None => {
+            lines.push(Line::styled("No identity assigned.", Style::default().fg(Color::White)));
+            lines.push(Line::from(""));
+            lines.push(Line::styled(
+                "QEMU reports an all-zero UUID and MAC 52:54:00:12:34:56, the same for every VM.",
+                note,
+            ));
+            lines.push(Line::styled(
+                "Assigning keeps the UUID from SMBIOS or vm-curator.toml if there is one.",
+                note,
+            ));
+            "[a] Assign  [Esc] Back"
+        }
+    };
+
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: true }), v_chunks[1]);
+
+    let help = Paragraph::new(help)
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, v_chunks[2]);
+}
+
+/// Render the CPU era preset picker (first entry removes masking)
+pub fn render_cpu_era(app: &App, frame: &mut Frame) {
+    let area = frame.area();
+    let dialog_width = 54.min(area.width.saturating_sub(4));
+    let dialog_height = (7 + (CPU_ERA_PRESETS.len() as u16 + 1) * 2).min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let current = app.selected_vm().and_then(|vm| vm.config.cpu_era.clone());
+    let kvm = app.selected_vm().map(|vm| vm.config.enable_kvm).unwrap_or(false);
+
+    let block = Block::default()
+        .title(" CPU Era ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let h_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Length(2),  // Left margin
+            Constraint::Min(1),     // Content
+            Constraint::Length(2),  // Right margin
+        ])
+        .split(inner);
+
+    let v_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),  // Top padding
+            Constraint::Min(1),     // Content
+            Constraint::Length(2),  // Help
+        ])
+        .split(h_chunks[1]);
+
+    let options = std::iter::once(("Host CPU (no masking)", "Guest sees the real host CPUID", None))
+        .chain(CPU_ERA_PRESETS.iter().map(|p| (p.name, p.description, Some(p.id))));
+
+    let items: Vec<ListItem> = options
+        .enumerate()
+        .map(|(i, (name, desc, id))| {
+            let is_current = current.as_deref() == id;
+            let style = if i == app.selected_menu_item {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else if is_current {
+                Style::default().fg(Color::Green)
+            } else {
+                Style::default().fg(Color::White)
+            };
+
+            let marker = if is_current { " *" } else { "" };
+
+            ListItem::new(vec![
+                Line::styled(format!("[{}] {}{}", i + 1, name, marker), style),
+                Line::styled(format!("    {}", desc), Style::default().fg(Color::DarkGray)),
+            ])
+        })
+        .collect();
+
+    let mut state = ListState::default();
+    state.select(Some(app.selected_menu_item));
+
+    let list = List::new(items);
+    frame.render_stateful_widget(list, v_chunks[1], &mut state);
+
+    let help_text = if kvm {
+        "[Enter] Select  [Esc] Back"
+    } else {
+        "TCG already emulates these models  [Enter] Select  [Esc] Back"
+    };
+    let help = Paragraph::new(help_text)
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, v_chunks[2]);
+}
+
+/// Extract display setting from launch script
+fn extract_display_from_script(script: &str) -> String {
+    // Look for -display X pattern
+    if let Some(pos) = script.find("-display ") {
+        let rest = &script[pos + 9..];
+        // Find the display value (ends at space, comma, or backslash)
+        let end = rest.find(|c: char| c.is_whitespace() || c == ',' || c == '\\')
+            .unwrap_or(rest.len());
+        let display = rest[..end].trim();
+        // Handle gl=on suffix
+        if let Some(comma_pos) = display.find(',') {
+            return display[..comma_pos].to_string();
+        }
+        return display.to_string();
+    }
+    "gtk".to_string() // Default
+}
+
+/// Render snapshot management submenu
+pub fn render_snapshots(app: &App, frame: &mut Frame) {
+    use ratatui::widgets::Wrap;
+
+    let area = frame.area();
+    let dialog_width = 72.min(area.width.saturating_sub(4));
+    let dialog_height = 26.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let supports_snapshots = app.selected_vm()
+        .map(|vm| vm.config.supports_snapshots())
+        .unwrap_or(false);
+
+    let title = if supports_snapshots {
+        format!(" Snapshots ({}) ", app.snapshots.len())
+    } else {
+        " Snapshots (not supported) ".to_string()
+    };
+
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    // Add horizontal margins
+    let h_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Length(2),  // Left margin
+            Constraint::Min(1),     // Content
+            Constraint::Length(2),  // Right margin
+        ])
+        .split(inner);
+
+    // Add top padding
+    let v_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),  // Top padding
+            Constraint::Min(1),     // Content
+        ])
+        .split(h_chunks[1]);
+
+    let content_area = v_chunks[1];
+
+    if !supports_snapshots {
+        let reason = app
+            .selected_vm()
+            .and_then(|vm| vm.config.primary_disk())
+            .and_then(|disk| disk.format.limitation())
+            .unwrap_or_else(|| "This VM has no local disk image".to_string());
+        let text = format!(
+            "{}.\n\nOnly qcow2 disks support snapshots. \"Convert Disk Format\" in the management menu can convert this one to qcow2.",
+            reason
+        );
+        let msg = Paragraph::new(text)
+            .style(Style::default().fg(Color::Yellow))
+            .wrap(Wrap { trim: false });
+        frame.render_widget(msg, content_area);
+        return;
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(4), Constraint::Length(7), Constraint::Length(2)])
+        .split(content_area);
+
+    // Action buttons
+    let actions = Paragraph::new(vec![
+        Line::from(vec![
+            Span::styled("[c]", Style::default().fg(Color::Yellow)),
+            Span::raw(" Create new snapshot"),
+        ]),
+    ]);
+    frame.render_widget(actions, chunks[0]);
 
     // Snapshot list
     if app.snapshots.is_empty() {
@@ -517,12 +1897,18 @@ pub fn render_snapshots(app: &App, frame: &mut Frame) {
                     Style::default().fg(Color::White)
                 };
 
+                let mut when = format!(
+                    "    {} - {}",
+                    format_timestamp(snap.timestamp, app.config.date_format),
+                    format_size(snap.size_bytes)
+                );
+                if let Some(meta) = app.snapshot_meta.get(&snap.name) {
+                    when.push_str(&format!(" - {}", meta.context.label()));
+                }
+
                 ListItem::new(vec![
                     Line::styled(format!("  {}", snap.name), style),
-                    Line::styled(
-                        format!("    {} - {}", snap.date, snap.size),
-                        Style::default().fg(Color::DarkGray),
-                    ),
+                    Line::styled(when, Style::default().fg(Color::DarkGray)),
                 ])
             })
             .collect();
@@ -535,11 +1921,56 @@ pub fn render_snapshots(app: &App, frame: &mut Frame) {
         frame.render_stateful_widget(list, chunks[1], &mut state);
     }
 
+    if let Some(snap) = app.snapshots.get(app.selected_snapshot) {
+        let details = snapshot_details(app, &snap.name, chunks[2].height.saturating_sub(1) as usize);
+        let details = Paragraph::new(details)
+            .block(Block::default().borders(Borders::TOP).border_style(Style::default().fg(Color::DarkGray)))
+            .wrap(Wrap { trim: false });
+        frame.render_widget(details, chunks[2]);
+    }
+
     // Help
     let help = Paragraph::new("[r] Restore  [d] Delete  [Esc] Back")
         .style(Style::default().fg(Color::DarkGray))
         .alignment(Alignment::Center);
-    frame.render_widget(help, chunks[2]);
+    frame.render_widget(help, chunks[3]);
+}
+
+/// Description of the selected snapshot, and how launch.sh has changed
+/// since it was taken, in at most `max_lines` lines
+fn snapshot_details(app: &App, name: &str, max_lines: usize) -> Vec<Line<'static>> {
+    let dim = Style::default().fg(Color::DarkGray);
+    let Some(meta) = app.snapshot_meta.get(name) else {
+        return vec![Line::styled("No description recorded for this snapshot.", dim)];
+    };
+
+    let mut lines = Vec::new();
+    if !meta.description.is_empty() {
+        lines.push(Line::styled(meta.description.clone(), Style::default().fg(Color::White)));
+    }
+
+    let current = app.selected_vm().map(|vm| vm.config.raw_script.as_str()).unwrap_or_default();
+    match meta.launch_script {
+        None => lines.push(Line::styled("No launch config recorded.", dim)),
+        Some(ref saved) => {
+            let changes = crate::vm::snapshot_meta::script_changes(saved, current);
+            if changes.is_empty() {
+                lines.push(Line::styled("launch.sh unchanged since this snapshot.", dim));
+            } else {
+                lines.push(Line::styled("launch.sh changes since this snapshot:", Style::default().fg(Color::Yellow)));
+                let room = max_lines.saturating_sub(lines.len());
+                let shown = if changes.len() > room { room.saturating_sub(1) } else { changes.len() };
+                for change in &changes[..shown] {
+                    let color = if change.starts_with('+') { Color::Green } else { Color::Red };
+                    lines.push(Line::styled(format!("  {}", change), Style::default().fg(color)));
+                }
+                if shown < changes.len() {
+                    lines.push(Line::styled(format!("  ...and {} more", changes.len() - shown), dim));
+                }
+            }
+        }
+    }
+    lines
 }
 
 fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
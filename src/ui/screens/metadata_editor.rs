@@ -0,0 +1,326 @@
+//! OS Info Editor Screen
+//!
+//! Edits the OS info shown for the selected VM (names, publisher, release
+//! date, blurbs and fun facts) and saves it as a user override in
+//! `<metadata_path>/<id>.toml`, the file `MetadataStore::load_from_dir`
+//! reads. The long blurb and the fun facts are edited one paragraph or
+//! fact per row.
+
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    prelude::*,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+
+use crate::app::{App, MetadataEditorState, Screen};
+
+/// Single-value fields, in the order shown
+const FIELDS: [&str; 6] = ["Display name", "Name", "Publisher", "Release date", "Architecture", "Short blurb"];
+
+/// A row of the editor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Row {
+    Field(usize),
+    Paragraph(usize),
+    AddParagraph,
+    Fact(usize),
+    AddFact,
+}
+
+fn rows(state: &MetadataEditorState) -> Vec<Row> {
+    let mut rows: Vec<Row> = (0..FIELDS.len()).map(Row::Field).collect();
+    rows.extend((0..state.paragraphs.len()).map(Row::Paragraph));
+    rows.push(Row::AddParagraph);
+    rows.extend((0..state.info.fun_facts.len()).map(Row::Fact));
+    rows.push(Row::AddFact);
+    rows
+}
+
+fn label(row: Row) -> String {
+    match row {
+        Row::Field(i) => FIELDS[i].to_string(),
+        Row::Paragraph(i) => format!("Description ¶{}", i + 1),
+        Row::AddParagraph => "+ Add paragraph".to_string(),
+        Row::Fact(i) => format!("Fun fact {}", i + 1),
+        Row::AddFact => "+ Add fun fact".to_string(),
+    }
+}
+
+fn text(state: &MetadataEditorState, row: Row) -> Option<&str> {
+    let info = &state.info;
+    match row {
+        Row::Field(0) => Some(info.display_name.as_deref().unwrap_or("")),
+        Row::Field(1) => Some(&info.name),
+        Row::Field(2) => Some(&info.publisher),
+        Row::Field(3) => Some(&info.release_date),
+        Row::Field(4) => Some(&info.architecture),
+        Row::Field(_) => Some(&info.blurb.short),
+        Row::Paragraph(i) => state.paragraphs.get(i).map(String::as_str),
+        Row::Fact(i) => info.fun_facts.get(i).map(String::as_str),
+        Row::AddParagraph | Row::AddFact => None,
+    }
+}
+
+fn text_mut(state: &mut MetadataEditorState, row: Row) -> Option<&mut String> {
+    let info = &mut state.info;
+    match row {
+        Row::Field(0) => Some(info.display_name.get_or_insert_with(String::new)),
+        Row::Field(1) => Some(&mut info.name),
+        Row::Field(2) => Some(&mut info.publisher),
+        Row::Field(3) => Some(&mut info.release_date),
+        Row::Field(4) => Some(&mut info.architecture),
+        Row::Field(_) => Some(&mut info.blurb.short),
+        Row::Paragraph(i) => state.paragraphs.get_mut(i),
+        Row::Fact(i) => info.fun_facts.get_mut(i),
+        Row::AddParagraph | Row::AddFact => None,
+    }
+}
+
+/// Open the editor for the selected VM's OS info
+pub fn open(app: &mut App) {
+    let Some(vm) = app.selected_vm() else {
+        return;
+    };
+    let os_id = app.metadata.key_for(&vm.id);
+    let info = app
+        .metadata
+        .get(&vm.id)
+        .cloned()
+        .unwrap_or_else(|| crate::metadata::default_os_info(&vm.id));
+    let paragraphs = info
+        .blurb
+        .long
+        .split("\n\n")
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect();
+    app.metadata_editor_state = Some(MetadataEditorState {
+        os_id,
+        info,
+        paragraphs,
+        selected: 0,
+        editing: false,
+        dirty: false,
+        confirm_discard: false,
+    });
+    app.push_screen(Screen::MetadataEditor);
+}
+
+/// Render the OS info editor
+pub fn render(app: &App, frame: &mut Frame) {
+    let Some(ref state) = app.metadata_editor_state else {
+        return;
+    };
+    let area = frame.area();
+
+    let dialog_width = 90.min(area.width.saturating_sub(4));
+    let dialog_height = 30.min(area.height.saturating_sub(2));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let title = if state.dirty {
+        format!(" OS Info: {} (modified) ", state.os_id)
+    } else {
+        format!(" OS Info: {} ", state.os_id)
+    };
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(5),    // Rows
+            Constraint::Length(7), // Selected text in full
+            Constraint::Length(1), // Help text
+        ])
+        .split(inner.inner(Margin::new(1, 0)));
+
+    // One line per row, scrolled to keep the selection in view
+    let rows = rows(state);
+    let height = chunks[0].height as usize;
+    let first = state.selected.saturating_sub(height.saturating_sub(1));
+    let label_width = 16;
+    let lines: Vec<Line> = rows
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(height)
+        .map(|(i, row)| {
+            let selected = i == state.selected;
+            let marker = if selected { "> " } else { "  " };
+            let label_style = match row {
+                Row::AddParagraph | Row::AddFact => Style::default().fg(Color::DarkGray),
+                _ if selected => Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                _ => Style::default().fg(Color::Yellow),
+            };
+            let value = text(state, *row).unwrap_or("").replace('\n', " ");
+            Line::from(vec![
+                Span::raw(marker),
+                Span::styled(format!("{:width$}", label(*row), width = label_width), label_style),
+                Span::raw(value),
+            ])
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), chunks[0]);
+
+    // The selected row in full, where typing goes
+    let row = rows.get(state.selected).copied();
+    let border_style = if state.editing {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default().fg(Color::Gray)
+    };
+    let full = row.and_then(|row| text(state, row)).unwrap_or("");
+    let full = if state.editing { format!("{}_", full) } else { full.to_string() };
+    let detail = Paragraph::new(full)
+        .block(
+            Block::default()
+                .title(format!(" {} ", row.map(label).unwrap_or_default()))
+                .borders(Borders::ALL)
+                .border_style(border_style),
+        )
+        .wrap(Wrap { trim: false });
+    frame.render_widget(detail, chunks[1]);
+
+    let help = if state.editing {
+        "Type to edit  [Enter] Done"
+    } else if matches!(row, Some(Row::Paragraph(_) | Row::Fact(_))) {
+        "[Enter] Edit  [d] Remove  [s] Save  [Esc] Close"
+    } else {
+        "[Enter] Edit  [s] Save  [Esc] Close"
+    };
+    let help = Paragraph::new(help)
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, chunks[2]);
+}
+
+/// Handle key input for the OS info editor
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(ref mut state) = app.metadata_editor_state else {
+        app.pop_screen();
+        return Ok(());
+    };
+    let rows = rows(state);
+    let row = rows.get(state.selected).copied();
+
+    if state.editing {
+        match key.code {
+            KeyCode::Enter | KeyCode::Esc | KeyCode::Tab => state.editing = false,
+            KeyCode::Char(c) => {
+                if let Some(text) = row.and_then(|row| text_mut(state, row)) {
+                    text.push(c);
+                    state.dirty = true;
+                }
+            }
+            KeyCode::Backspace => {
+                if let Some(text) = row.and_then(|row| text_mut(state, row)) {
+                    text.pop();
+                    state.dirty = true;
+                }
+            }
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    if key.code != KeyCode::Esc {
+        state.confirm_discard = false;
+    }
+    match key.code {
+        KeyCode::Esc if state.dirty && !state.confirm_discard => {
+            state.confirm_discard = true;
+            app.set_status("Unsaved changes: press Esc again to discard them, or s to save");
+        }
+        KeyCode::Esc => {
+            app.metadata_editor_state = None;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down if state.selected + 1 < rows.len() => state.selected += 1,
+        KeyCode::Char('k') | KeyCode::Up => state.selected = state.selected.saturating_sub(1),
+        KeyCode::Enter | KeyCode::Char(' ') => {
+            // A new paragraph or fact takes the "add" row's place
+            match row {
+                Some(Row::AddParagraph) => state.paragraphs.push(String::new()),
+                Some(Row::AddFact) => state.info.fun_facts.push(String::new()),
+                _ => {}
+            }
+            state.editing = true;
+        }
+        KeyCode::Char('d') | KeyCode::Delete => {
+            match row {
+                Some(Row::Paragraph(i)) => {
+                    state.paragraphs.remove(i);
+                }
+                Some(Row::Fact(i)) => {
+                    state.info.fun_facts.remove(i);
+                }
+                _ => return Ok(()),
+            }
+            state.dirty = true;
+        }
+        KeyCode::Char('s') | KeyCode::Char('S') => match save(app) {
+            Ok(path) => {
+                let vm_id = app.selected_vm().map(|vm| vm.id.clone());
+                app.metadata_editor_state = None;
+                app.pop_screen();
+                // Names and dates decide the list's order
+                app.update_filter();
+                if let Some(vm_id) = vm_id {
+                    app.select_vm_by_id(&vm_id);
+                }
+                app.set_status(format!("Saved {}", path.display()));
+            }
+            Err(e) => app.set_status(format!("Error: {:#}", e)),
+        },
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Write the edited info to the user metadata folder and use it
+fn save(app: &mut App) -> Result<std::path::PathBuf> {
+    let Some(ref state) = app.metadata_editor_state else {
+        bail!("Nothing to save");
+    };
+    let mut info = state.info.clone();
+    if info.name.trim().is_empty() {
+        bail!("The OS needs a name");
+    }
+    if !info.release_date.is_empty() && NaiveDate::parse_from_str(&info.release_date, "%Y-%m-%d").is_err() {
+        bail!("Release date must be YYYY-MM-DD (January 1st if only the year is known)");
+    }
+    info.display_name = info.display_name.filter(|name| !name.trim().is_empty());
+    info.blurb.long = state
+        .paragraphs
+        .iter()
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    info.fun_facts.retain(|fact| !fact.trim().is_empty());
+
+    let path = app.config.metadata_path.join(format!("{}.toml", state.os_id));
+    std::fs::create_dir_all(&app.config.metadata_path)
+        .with_context(|| format!("Failed to create {}", app.config.metadata_path.display()))?;
+    std::fs::write(&path, toml::to_string(&info)?).with_context(|| format!("Failed to write {}", path.display()))?;
+    app.metadata.entries.insert(state.os_id.clone(), info);
+    Ok(path)
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    Rect::new(x, y, width, height)
+}
@@ -1,11 +1,39 @@
+pub mod backing_chain;
+pub mod batch_report;
+pub mod checklist;
 pub mod configuration;
+pub mod convert_disk;
+pub mod create_snapshot;
 pub mod create_wizard;
+pub mod data_disk;
+pub mod doctor;
+pub mod game_controllers;
+pub mod guest_files;
 pub mod help;
+pub mod input_passthrough;
+pub mod iso_library;
+pub mod launch_env;
+pub mod launch_options;
+pub mod host_migration;
+pub mod log_viewer;
+pub mod monitor;
+pub mod mac_firmware;
 pub mod main_menu;
 pub mod management;
+pub mod metadata_editor;
 pub mod multi_gpu_setup;
 pub mod network_settings;
+pub mod orphan_files;
 pub mod pci_passthrough;
+pub mod physical_disks;
+pub mod profile_editor;
+pub mod qemu_binary;
+pub mod quiz;
+pub mod screenshots;
 pub mod settings;
 pub mod shared_folders;
 pub mod single_gpu_setup;
+pub mod slideshow;
+pub mod statistics;
+pub mod storage;
+pub mod systemd_unit;
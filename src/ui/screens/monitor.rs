@@ -0,0 +1,190 @@
+//! Resource Monitor Screen
+//!
+//! Graphs of a running VM's CPU use, memory, disk and network traffic
+//! over the last couple of minutes, sampled once a second while the
+//! screen is open.
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    prelude::*,
+    style::{Color, Style},
+    widgets::{Block, Borders, Clear, Paragraph, Sparkline},
+};
+use std::time::Duration;
+
+use crate::app::{App, MonitorState, Screen};
+use crate::format::format_size;
+use crate::vm::{monitor, qmp};
+
+/// Time between samples
+const INTERVAL: Duration = Duration::from_secs(1);
+
+/// Open the monitor for the selected VM, which must be running here
+pub fn open(app: &mut App) {
+    let Some(vm) = app.selected_vm().cloned() else {
+        return;
+    };
+    if vm.remote.is_some() {
+        app.set_status("The monitor is only available for VMs on this computer");
+        return;
+    }
+    let Some(pid) = app.selected_vm_pid() else {
+        app.set_status(format!("{} is not running", vm.display_name()));
+        return;
+    };
+    let qmp = qmp::is_configured(&vm.config.raw_script);
+    let taps = monitor::tap_interfaces(pid);
+    let last = monitor::sample(pid, &vm.path, qmp, &taps);
+    app.monitor_state = Some(MonitorState {
+        vm_name: vm.display_name(),
+        vm_dir: vm.path.clone(),
+        pid,
+        memory_mb: vm.config.memory_mb,
+        qmp,
+        stopped: last.is_none(),
+        taps,
+        last,
+        history: monitor::History::default(),
+    });
+    app.push_screen(Screen::Monitor);
+}
+
+/// Take a new sample when one is due (call in the event loop)
+pub fn tick(app: &mut App) {
+    let Some(ref mut state) = app.monitor_state else {
+        return;
+    };
+    if state.stopped || state.last.is_some_and(|last| last.at.elapsed() < INTERVAL) {
+        return;
+    }
+    match monitor::sample(state.pid, &state.vm_dir, state.qmp, &state.taps) {
+        Some(sample) => {
+            if let Some(ref last) = state.last {
+                state.history.push(last, &sample);
+            }
+            state.last = Some(sample);
+        }
+        None => state.stopped = true,
+    }
+}
+
+/// Render the monitor
+pub fn render(app: &App, frame: &mut Frame) {
+    let Some(ref state) = app.monitor_state else {
+        return;
+    };
+    let area = frame.area();
+
+    let dialog_width = 90.min(area.width.saturating_sub(4));
+    let dialog_height = 27.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let title = if state.stopped {
+        format!(" Monitor: {} (stopped) ", state.vm_name)
+    } else {
+        format!(" Monitor: {} ", state.vm_name)
+    };
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Ratio(1, 4), // CPU
+            Constraint::Ratio(1, 4), // Memory
+            Constraint::Ratio(1, 4), // Disk
+            Constraint::Ratio(1, 4), // Network
+            Constraint::Length(1),   // Help text
+        ])
+        .split(inner.inner(Margin::new(1, 0)));
+
+    let history = &state.history;
+    let cpu = history.cpu_percent.last().map_or("-".to_string(), |cpu| format!("{}%", cpu));
+    let cpu_peak = history.cpu_percent.iter().max().copied().unwrap_or(0);
+    graph(
+        frame,
+        chunks[0],
+        format!(" CPU {} (peak {}%, 100% is one host core) ", cpu, cpu_peak),
+        &history.cpu_percent,
+        None,
+        Color::Green,
+    );
+
+    let guest_ram = u64::from(state.memory_mb) * 1024 * 1024;
+    let memory = history.memory_bytes.last().map_or("-".to_string(), |bytes| format_size(*bytes));
+    graph(
+        frame,
+        chunks[1],
+        format!(" Memory {} ({} guest RAM) ", memory, format_size(guest_ram)),
+        &history.memory_bytes,
+        Some(guest_ram.max(history.memory_bytes.iter().max().copied().unwrap_or(0))),
+        Color::Magenta,
+    );
+
+    let disk_title = if state.qmp {
+        format!(" Disk {} (read + write) ", rate(&history.disk_rate))
+    } else {
+        " Disk (needs the QMP socket: restart the VM after adding it) ".to_string()
+    };
+    graph(frame, chunks[2], disk_title, &history.disk_rate, None, Color::Yellow);
+
+    let net_title = if state.taps.is_empty() {
+        " Network (no counters for user-mode networking) ".to_string()
+    } else {
+        format!(" Network {} ({}) ", rate(&history.net_rate), state.taps.join(", "))
+    };
+    graph(frame, chunks[3], net_title, &history.net_rate, None, Color::Cyan);
+
+    let help = Paragraph::new("[Esc] Close")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, chunks[4]);
+}
+
+/// Latest rate of a series
+fn rate(values: &[u64]) -> String {
+    values.last().map_or("-".to_string(), |bytes| format!("{}/s", format_size(*bytes)))
+}
+
+/// A titled sparkline of the most recent values that fit
+fn graph(frame: &mut Frame, area: Rect, title: String, values: &[u64], max: Option<u64>, color: Color) {
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::TOP)
+        .border_style(Style::default().fg(Color::DarkGray));
+    let width = block.inner(area).width as usize;
+    let recent = &values[values.len().saturating_sub(width)..];
+    let mut sparkline = Sparkline::default()
+        .block(block)
+        .data(recent)
+        .style(Style::default().fg(color));
+    if let Some(max) = max {
+        sparkline = sparkline.max(max);
+    }
+    frame.render_widget(sparkline, area);
+}
+
+/// Handle key input for the monitor
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    if matches!(key.code, KeyCode::Esc | KeyCode::Char('q')) {
+        app.monitor_state = None;
+        app.pop_screen();
+    }
+    Ok(())
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    Rect::new(x, y, width, height)
+}
@@ -3,7 +3,8 @@
 //! Provides UI for configuring multi-GPU passthrough with Looking Glass:
 //! - System requirements status (IOMMU, VFIO, Looking Glass client)
 //! - GPU selection status
-//! - Script generation and configuration
+//! - Looking Glass IVSHMEM shared memory for the selected VM
+//! - Host, guest and client setup instructions
 
 use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{
@@ -15,6 +16,7 @@ use ratatui::{
 
 use crate::app::{App, Screen};
 use crate::hardware::{check_multi_gpu_passthrough_status, LookingGlassConfig};
+use crate::vm::looking_glass::{recommended_size_mb, set_looking_glass, setup_instructions, LookingGlassSettings};
 
 /// Render the multi-GPU setup screen
 pub fn render(app: &App, frame: &mut Frame) {
@@ -22,7 +24,7 @@ pub fn render(app: &App, frame: &mut Frame) {
 
     // Calculate dialog size
     let dialog_width = 72.min(area.width.saturating_sub(4));
-    let dialog_height = 24.min(area.height.saturating_sub(4));
+    let dialog_height = 26.min(area.height.saturating_sub(4));
 
     let dialog_area = centered_rect(dialog_width, dialog_height, area);
     frame.render_widget(Clear, dialog_area);
@@ -45,7 +47,7 @@ pub fn render(app: &App, frame: &mut Frame) {
             Constraint::Length(1),  // Separator
             Constraint::Length(5),  // GPU info
             Constraint::Length(1),  // Separator
-            Constraint::Length(4),  // Looking Glass config
+            Constraint::Length(6),  // Looking Glass config
             Constraint::Min(1),     // Spacer
             Constraint::Length(2),  // Help
         ])
@@ -197,18 +199,43 @@ fn render_looking_glass_config(app: &App, frame: &mut Frame, area: Rect) {
         Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
     ));
 
-    // IVSHMEM size
-    lines.push(Line::from(vec![
-        Span::raw("  IVSHMEM Size: "),
-        Span::styled(
-            format!("{}MB", app.config.default_ivshmem_size_mb),
-            Style::default().fg(Color::White),
-        ),
-        Span::styled(
-            " (configure in Settings)",
-            Style::default().fg(Color::DarkGray),
-        ),
-    ]));
+    // IVSHMEM device for this VM
+    match app.selected_vm().and_then(|vm| vm.config.looking_glass) {
+        Some(settings) => {
+            lines.push(Line::from(vec![
+                Span::raw("  IVSHMEM: "),
+                Span::styled("enabled", Style::default().fg(Color::Green)),
+            ]));
+            lines.push(Line::from(vec![
+                Span::raw("  Size: "),
+                Span::styled(format!("{}MB", settings.size_mb), Style::default().fg(Color::White)),
+                Span::styled(
+                    format!(
+                        "  ({}MB up to 1080p, {}MB 1440p, {}MB 4K)",
+                        recommended_size_mb(1920, 1080),
+                        recommended_size_mb(2560, 1440),
+                        recommended_size_mb(3840, 2160)
+                    ),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]));
+            let backend = if settings.kvmfr { "KVMFR module" } else { "/dev/shm file" };
+            lines.push(Line::from(vec![
+                Span::raw("  Backing: "),
+                Span::styled(format!("{} ({})", backend, settings.mem_path()), Style::default().fg(Color::White)),
+            ]));
+        }
+        None => {
+            lines.push(Line::from(vec![
+                Span::raw("  IVSHMEM: "),
+                Span::styled("not configured", Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    format!(" (default size {}MB in Settings)", app.config.default_ivshmem_size_mb),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]));
+        }
+    }
 
     // Auto-launch
     let auto_launch = if app.config.looking_glass_auto_launch { "Yes" } else { "No" };
@@ -223,7 +250,10 @@ fn render_looking_glass_config(app: &App, frame: &mut Frame, area: Rect) {
 
 /// Render help text
 fn render_help(frame: &mut Frame, area: Rect) {
-    let help = Paragraph::new("[p] PCI Passthrough  [s] Settings  [Esc] Back")
+    let help = Paragraph::new(vec![
+        Line::from("[l] Looking Glass on/off  [←/→] Size  [k] KVMFR  [i] Setup steps"),
+        Line::from("[p] PCI Passthrough  [s] Settings  [Esc] Back"),
+    ])
         .style(Style::default().fg(Color::DarkGray))
         .alignment(Alignment::Center);
     frame.render_widget(help, area);
@@ -244,12 +274,124 @@ pub fn handle_input(app: &mut App, key: KeyEvent) -> anyhow::Result<()> {
             // Go to Settings screen
             app.push_screen(Screen::Settings);
         }
+        KeyCode::Char('l') | KeyCode::Char('L') => {
+            let current = app.selected_vm().and_then(|vm| vm.config.looking_glass);
+            let settings = match current {
+                Some(_) => None,
+                None => Some(LookingGlassSettings {
+                    // IVSHMEM sizes must be a power of two
+                    size_mb: app.config.default_ivshmem_size_mb.next_power_of_two().clamp(16, 512),
+                    kvmfr: false,
+                }),
+            };
+            update_looking_glass(app, settings);
+        }
+        KeyCode::Left | KeyCode::Right => {
+            let delta = if key.code == KeyCode::Right { 1 } else { -1 };
+            if let Some(mut settings) = app.selected_vm().and_then(|vm| vm.config.looking_glass) {
+                settings.cycle_size(delta);
+                update_looking_glass(app, Some(settings));
+            }
+        }
+        KeyCode::Char('k') | KeyCode::Char('K') => {
+            if let Some(mut settings) = app.selected_vm().and_then(|vm| vm.config.looking_glass) {
+                settings.kvmfr = !settings.kvmfr;
+                update_looking_glass(app, Some(settings));
+            }
+        }
+        KeyCode::Char('i') | KeyCode::Char('I') => {
+            if app.selected_vm().is_some_and(|vm| vm.config.looking_glass.is_some()) {
+                app.push_screen(Screen::LookingGlassInstructions);
+            } else {
+                app.set_status("Enable Looking Glass first ([l])");
+            }
+        }
         _ => {}
     }
 
     Ok(())
 }
 
+/// Write the VM's Looking Glass section and report the result
+fn update_looking_glass(app: &mut App, settings: Option<LookingGlassSettings>) {
+    let Some(vm) = app.selected_vm().cloned() else {
+        return;
+    };
+    match set_looking_glass(&vm.launch_script, settings.as_ref()) {
+        Ok(()) => {
+            let summary = match settings {
+                Some(s) => format!("Looking Glass: {}MB at {}", s.size_mb, s.mem_path()),
+                None => "Looking Glass removed".to_string(),
+            };
+            if app.running_vms.contains_key(&vm.id) {
+                app.set_status(format!("{} (applies on next start)", summary));
+            } else {
+                app.set_status(summary);
+            }
+            let _ = app.refresh_vms();
+        }
+        Err(e) => app.set_status(format!("Failed to update Looking Glass: {}", e)),
+    }
+}
+
+/// Render the Looking Glass setup steps for the selected VM
+pub fn render_instructions(app: &App, frame: &mut Frame) {
+    let Some(settings) = app.selected_vm().and_then(|vm| vm.config.looking_glass) else {
+        return;
+    };
+
+    let steps = setup_instructions(&settings);
+
+    let area = frame.area();
+    let dialog_width = 90.min(area.width.saturating_sub(4));
+    let dialog_height = (steps.len() as u16 + 4).min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let block = Block::default()
+        .title(" Looking Glass Setup ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Green))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(inner);
+
+    let lines: Vec<Line> = steps
+        .into_iter()
+        .map(|step| {
+            if step.ends_with(':') {
+                Line::styled(step, Style::default().fg(Color::White).add_modifier(Modifier::BOLD))
+            } else if step.trim_start().starts_with(|c: char| c.is_ascii_lowercase()) {
+                // Commands to run
+                Line::styled(step, Style::default().fg(Color::Cyan))
+            } else {
+                Line::raw(step)
+            }
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), chunks[0]);
+
+    let help = Paragraph::new("[Enter/Esc] Close")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, chunks[1]);
+}
+
+/// Handle input for the Looking Glass instructions dialog
+pub fn handle_instructions_input(app: &mut App, key: KeyEvent) -> anyhow::Result<()> {
+    if matches!(key.code, KeyCode::Esc | KeyCode::Enter) {
+        app.pop_screen();
+    }
+    Ok(())
+}
+
 /// Helper function to create a centered rect
 fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
     let x = area.x + (area.width.saturating_sub(width)) / 2;
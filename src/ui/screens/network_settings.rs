@@ -6,10 +6,11 @@
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     prelude::*,
-    widgets::{Block, Borders, Clear, Paragraph},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
 };
 
 use crate::app::{AddPfStep, AddingPortForward, App, NetworkSettingsState};
+use crate::commands::tap_setup::{self, TapUplink, DEFAULT_TAP_NAME};
 use crate::vm::qemu_config::{PortForward, PortProtocol};
 
 /// Network adapter model options (same as create wizard)
@@ -44,6 +45,7 @@ pub fn render(app: &App, frame: &mut Frame) {
     }
 
     let is_bridge = ns.backend == "bridge";
+    let is_tap = ns.backend == "tap";
 
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -53,9 +55,10 @@ pub fn render(app: &App, frame: &mut Frame) {
             Constraint::Length(1),   // Spacer
             Constraint::Length(1),   // Adapter field
             Constraint::Length(1),   // Backend field
-            Constraint::Length(1),   // Bridge name / Port forwards field
+            Constraint::Length(1),   // Bridge name / TAP name / Port forwards field
+            Constraint::Length(1),   // TAP uplink / SMB share field
             Constraint::Length(1),   // Spacer
-            Constraint::Min(6),      // Info area (port forward list or bridge status)
+            Constraint::Min(6),      // Info area (port forward list, bridge or tap status)
             Constraint::Length(2),   // Help
         ])
         .split(inner);
@@ -76,6 +79,7 @@ pub fn render(app: &App, frame: &mut Frame) {
         "user" => "user/SLIRP (NAT)".to_string(),
         "passt" => "passt".to_string(),
         "bridge" => format!("bridge ({})", ns.bridge_name.as_deref().unwrap_or("qemubr0")),
+        "tap" => format!("tap ({})", ns.tap_name.as_deref().unwrap_or(DEFAULT_TAP_NAME)),
         "none" => "none".to_string(),
         other => other.to_string(),
     };
@@ -89,6 +93,12 @@ pub fn render(app: &App, frame: &mut Frame) {
         let bridge_display = ns.bridge_name.as_deref().unwrap_or("qemubr0");
         let bridge_line = render_field_line("Bridge:", bridge_display, bridge_selected, "[Left/Right] cycle");
         frame.render_widget(Paragraph::new(bridge_line), chunks[4]);
+    } else if is_tap {
+        let tap_display = ns.tap_name.as_deref().unwrap_or(DEFAULT_TAP_NAME);
+        let tap_line = render_field_line("TAP:", tap_display, ns.selected_field == 2, "[Left/Right] cycle");
+        frame.render_widget(Paragraph::new(tap_line), chunks[4]);
+        let uplink_line = render_field_line("Uplink:", &ns.tap_uplink.to_string(), ns.selected_field == 3, "[Left/Right] cycle");
+        frame.render_widget(Paragraph::new(uplink_line), chunks[5]);
     } else if show_pf {
         let pf_selected = ns.selected_field == 2;
         let pf_count = ns.port_forwards.len();
@@ -100,6 +110,12 @@ pub fn render(app: &App, frame: &mut Frame) {
         let pf_hint = if pf_selected { "[Enter] edit" } else { "" };
         let pf_line = render_field_line("Forwards:", &pf_display, pf_selected, pf_hint);
         frame.render_widget(Paragraph::new(pf_line), chunks[4]);
+
+        if ns.backend == "user" {
+            let smb_display = if ns.smb_share { "on" } else { "off" };
+            let smb_line = render_field_line("SMB share:", smb_display, ns.selected_field == 3, "[Left/Right/Space] toggle");
+            frame.render_widget(Paragraph::new(smb_line), chunks[5]);
+        }
     }
 
     // Info area: bridge status (when bridge) or port forward list (when user/passt)
@@ -142,39 +158,228 @@ pub fn render(app: &App, frame: &mut Frame) {
             Span::styled(bridges_str, Style::default().fg(bridges_color)),
         ]));
 
+        // ACL entry for the chosen bridge
+        let bridge = ns.bridge_name.as_deref().unwrap_or("qemubr0");
+        let (acl_str, acl_color) = match caps.bridge_acl {
+            Some(ref acl) if acl.iter().any(|b| b == bridge || b == "all") => (format!("{} allowed", bridge), Color::Green),
+            Some(_) => (format!("{} not allowed", bridge), Color::Red),
+            None => ("unreadable (assumed missing)".to_string(), Color::Yellow),
+        };
+        lines.push(Line::from(vec![
+            Span::styled("  ACL:           ", Style::default().fg(Color::Yellow)),
+            Span::styled(acl_str, Style::default().fg(acl_color)),
+        ]));
+
         // Setup guidance if incomplete
-        if caps.bridge_helper_path.is_none() || !caps.bridge_helper_configured || caps.system_bridges.is_empty() {
-            lines.push(Line::from(""));
-            lines.push(Line::styled("  Setup needed:", Style::default().fg(Color::Yellow)));
-            if caps.bridge_helper_path.is_none() {
-                lines.push(Line::styled("    Install: qemu-bridge-helper (part of QEMU)", Style::default().fg(Color::DarkGray)));
-            }
-            if !caps.bridge_helper_configured {
-                lines.push(Line::styled("    Run: sudo setcap cap_net_admin+ep /usr/lib/qemu/qemu-bridge-helper", Style::default().fg(Color::DarkGray)));
+        match crate::commands::bridge_setup::plan_bridge_setup(caps, bridge) {
+            Ok(steps) if steps.is_empty() => {}
+            Ok(steps) => {
+                lines.push(Line::from(""));
+                lines.push(Line::styled(
+                    format!("  Setup needed ({} step(s)): press [s] to run it", steps.len()),
+                    Style::default().fg(Color::Yellow),
+                ));
+                for step in &steps {
+                    lines.push(Line::styled(format!("    {}", step.description), Style::default().fg(Color::DarkGray)));
+                }
             }
-            if caps.system_bridges.is_empty() {
-                lines.push(Line::styled("    Create bridge: sudo ip link add qemubr0 type bridge", Style::default().fg(Color::DarkGray)));
-                lines.push(Line::styled("    Enable:        sudo ip link set qemubr0 up", Style::default().fg(Color::DarkGray)));
+            Err(e) => {
+                lines.push(Line::from(""));
+                lines.push(Line::styled(format!("  {}", e), Style::default().fg(Color::Red)));
             }
         }
 
         let info = Paragraph::new(lines);
-        frame.render_widget(info, chunks[6]);
-    } else if show_pf && !ns.port_forwards.is_empty() {
+        frame.render_widget(info, chunks[7]);
+    } else if is_tap {
+        let tap = ns.tap_name.as_deref().unwrap_or(DEFAULT_TAP_NAME);
+        frame.render_widget(Paragraph::new(tap_status_lines(app, tap, &ns.tap_uplink)).wrap(Wrap { trim: false }), chunks[7]);
+    } else if show_pf && (!ns.port_forwards.is_empty() || ns.smb_share) {
         let mut lines = Vec::new();
-        lines.push(Line::styled("  Current port forwarding rules:", Style::default().fg(Color::DarkGray)));
-        for pf in &ns.port_forwards {
-            lines.push(Line::from(format!("    {} {} -> {}", pf.protocol, pf.host_port, pf.guest_port)));
+        if !ns.port_forwards.is_empty() {
+            lines.push(Line::styled("  Current port forwarding rules:", Style::default().fg(Color::DarkGray)));
+            for pf in &ns.port_forwards {
+                lines.push(Line::from(format!("    {} {} -> {}", pf.protocol, pf.host_port, pf.guest_port)));
+            }
+        }
+        if ns.smb_share && ns.backend == "user" {
+            lines.extend(smb_share_lines(app));
         }
         let list = Paragraph::new(lines);
-        frame.render_widget(list, chunks[6]);
+        frame.render_widget(list, chunks[7]);
     }
 
     // Help
-    let help = Paragraph::new("[Enter] Apply  [Esc] Cancel  [j/k] Navigate  [Left/Right] Change")
+    let help_text = if is_bridge {
+        "[Enter] Apply  [Esc] Cancel  [j/k] Navigate  [Left/Right] Change  [s] Set up bridge"
+    } else if is_tap {
+        "[Enter] Apply  [Esc] Cancel  [j/k] Navigate  [Left/Right] Change  [s] Set up TAP"
+    } else {
+        "[Enter] Apply  [Esc] Cancel  [j/k] Navigate  [Left/Right] Change"
+    };
+    let help = Paragraph::new(help_text)
         .style(Style::default().fg(Color::DarkGray))
         .alignment(Alignment::Center);
-    frame.render_widget(help, chunks[7]);
+    frame.render_widget(help, chunks[8]);
+}
+
+/// Where the SMB share lives and how a Windows guest reaches it
+fn smb_share_lines(app: &App) -> Vec<Line<'static>> {
+    let folder = app
+        .selected_vm()
+        .map(|vm| vm.path.join(crate::vm::create::SMB_SHARE_DIR).display().to_string())
+        .unwrap_or_default();
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  SMB folder:    ", Style::default().fg(Color::Yellow)),
+            Span::styled(folder, Style::default().fg(Color::White)),
+        ]),
+        Line::styled("  Guest: \\\\10.0.2.4\\qemu (Win 9x/NT: add \"10.0.2.4 SMBSERVER\"", Style::default().fg(Color::DarkGray)),
+        Line::styled("  to LMHOSTS, then use \\\\SMBSERVER\\qemu)", Style::default().fg(Color::DarkGray)),
+    ];
+    if !crate::commands::qemu_system::is_smbd_available() {
+        lines.push(Line::styled("  Samba (smbd) not found: install it on the host", Style::default().fg(Color::Red)));
+    }
+    lines
+}
+
+/// Tap device status and remaining setup for the info area
+fn tap_status_lines(app: &App, tap: &str, uplink: &TapUplink) -> Vec<Line<'static>> {
+    let caps = &app.network_caps;
+    let mut lines = Vec::new();
+
+    let (manager_str, manager_color) = match caps.tap_manager {
+        Some(manager) => (manager.label().to_string(), Color::Green),
+        None => ("none running (manual setup only)".to_string(), Color::Red),
+    };
+    lines.push(Line::from(vec![
+        Span::styled("  Manager:       ", Style::default().fg(Color::Yellow)),
+        Span::styled(manager_str, Style::default().fg(manager_color)),
+    ]));
+
+    let uid = crate::fs::current_uid();
+    let (device_str, device_color) = match caps.tap_devices.iter().find(|d| d.name == tap) {
+        None => ("not created".to_string(), Color::Red),
+        Some(d) if d.owner.is_none() || d.owner != uid => ("exists, not owned by you".to_string(), Color::Red),
+        Some(d) => match d.master {
+            Some(ref bridge) => (format!("ready, on bridge {}", bridge), Color::Green),
+            None => ("ready, routed".to_string(), Color::Green),
+        },
+    };
+    lines.push(Line::from(vec![
+        Span::styled("  Device:        ", Style::default().fg(Color::Yellow)),
+        Span::styled(device_str, Style::default().fg(device_color)),
+    ]));
+
+    if let TapUplink::Routed { nat } = uplink {
+        let (gateway, _) = tap_setup::routed_subnet(tap);
+        let nat_str = match (nat, caps.nft_path.is_some()) {
+            (true, true) => ", NAT via nftables",
+            (true, false) => ", NAT needs nft (not found)",
+            (false, _) => ", no NAT",
+        };
+        lines.push(Line::from(vec![
+            Span::styled("  Host address:  ", Style::default().fg(Color::Yellow)),
+            Span::styled(format!("{}{}", gateway, nat_str), Style::default().fg(Color::White)),
+        ]));
+        if let Some(hint) = caps.tap_manager.and_then(|m| tap_setup::guest_hint(m, tap, uplink)) {
+            lines.push(Line::styled(format!("  {}", hint), Style::default().fg(Color::DarkGray)));
+        }
+    }
+
+    match tap_setup::plan_tap_setup(caps, tap, uplink) {
+        Ok(steps) if steps.is_empty() => {}
+        Ok(steps) => {
+            lines.push(Line::from(""));
+            lines.push(Line::styled(
+                format!("  Setup needed ({} step(s)): press [s] to run it", steps.len()),
+                Style::default().fg(Color::Yellow),
+            ));
+            for step in &steps {
+                lines.push(Line::styled(format!("    {}", step.description), Style::default().fg(Color::DarkGray)));
+            }
+        }
+        Err(e) => {
+            lines.push(Line::from(""));
+            lines.push(Line::styled(format!("  {}", e), Style::default().fg(Color::Red)));
+        }
+    }
+
+    lines
+}
+
+/// Render the bridge/tap setup consent dialog: every command that will run as root
+pub fn render_bridge_setup(app: &App, frame: &mut Frame) {
+    let Some((ref interface, ref steps)) = app.bridge_setup_plan else {
+        return;
+    };
+    let kind = match app.network_settings_state {
+        Some(ref ns) if ns.backend == "tap" => "TAP",
+        _ => "Bridge",
+    };
+
+    let command_lines: usize = steps.iter().map(|s| s.command.lines().count()).sum();
+    let area = frame.area();
+    let dialog_width = 90.min(area.width.saturating_sub(4));
+    let dialog_height = (10 + steps.len() as u16 * 2 + command_lines as u16).min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let block = Block::default()
+        .title(format!(" Set Up {} {} ", kind, interface))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Min(4),      // Steps
+            Constraint::Length(2),   // Help
+        ])
+        .split(inner);
+
+    let mut lines = vec![
+        Line::styled(
+            "These commands will run as root (pkexec, or sudo on the terminal):",
+            Style::default().fg(Color::White),
+        ),
+        Line::from(""),
+    ];
+    for step in steps {
+        lines.push(Line::styled(format!("  {}", step.description), Style::default().fg(Color::Yellow)));
+        for command in step.command.lines() {
+            lines.push(Line::styled(format!("    {}", command), Style::default().fg(Color::Cyan)));
+        }
+        lines.push(Line::from(""));
+    }
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), chunks[0]);
+
+    let help = Paragraph::new("[y] Run as root  [Esc] Cancel")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, chunks[1]);
+}
+
+/// Handle the bridge setup consent dialog
+pub fn handle_bridge_setup_key(app: &mut App, key: crossterm::event::KeyEvent) -> anyhow::Result<()> {
+    use crossterm::event::KeyCode;
+
+    match key.code {
+        KeyCode::Char('y') | KeyCode::Char('Y') => app.bridge_setup_confirmed = true,
+        KeyCode::Esc | KeyCode::Char('n') => {
+            app.bridge_setup_plan = None;
+            app.pop_screen();
+        }
+        _ => {}
+    }
+    Ok(())
 }
 
 /// Render the port forward editor overlay
@@ -408,7 +613,23 @@ pub fn handle_key(app: &mut App, key: crossterm::event::KeyEvent) -> anyhow::Res
         let ns = app.network_settings_state.as_ref().unwrap();
         ns.backend == "bridge"
     };
-    let max_field = if show_pf || is_bridge { 2 } else { 1 };
+    let is_tap = {
+        let ns = app.network_settings_state.as_ref().unwrap();
+        ns.backend == "tap"
+    };
+    let tap_names = tap_setup::tap_name_options(&app.network_caps.tap_devices);
+    let uplinks = tap_setup::uplink_options(&system_bridges);
+    let is_user = {
+        let ns = app.network_settings_state.as_ref().unwrap();
+        ns.backend == "user"
+    };
+    let max_field = if is_tap || is_user {
+        3
+    } else if show_pf || is_bridge {
+        2
+    } else {
+        1
+    };
 
     match key.code {
         KeyCode::Esc => {
@@ -451,6 +672,24 @@ pub fn handle_key(app: &mut App, key: crossterm::event::KeyEvent) -> anyhow::Res
                             ns.bridge_name = system_bridges.first().cloned()
                                 .or_else(|| Some("qemubr0".to_string()));
                         }
+                        if ns.backend == "tap" && ns.tap_name.is_none() {
+                            ns.tap_name = tap_names.first().cloned();
+                        }
+                        if ns.selected_field > 1 && ns.backend != "tap" && ns.backend != "user" {
+                            ns.selected_field = 1;
+                        }
+                    }
+                    2 if ns.backend == "tap" => {
+                        let mut current = ns.tap_name.clone().unwrap_or_else(|| DEFAULT_TAP_NAME.to_string());
+                        let names: Vec<&str> = tap_names.iter().map(String::as_str).collect();
+                        cycle_option(&mut current, &names, delta);
+                        ns.tap_name = Some(current);
+                    }
+                    3 if ns.backend == "user" => ns.smb_share = !ns.smb_share,
+                    3 if ns.backend == "tap" => {
+                        let current_idx = uplinks.iter().position(|u| *u == ns.tap_uplink).unwrap_or(0);
+                        let new_idx = (current_idx as i32 + delta).rem_euclid(uplinks.len() as i32) as usize;
+                        ns.tap_uplink = uplinks[new_idx].clone();
                     }
                     2 if ns.backend == "bridge" => {
                         // Cycle bridge name
@@ -468,6 +707,40 @@ pub fn handle_key(app: &mut App, key: crossterm::event::KeyEvent) -> anyhow::Res
                 }
             }
         }
+        KeyCode::Char('s') if is_bridge => {
+            let bridge = app.network_settings_state.as_ref()
+                .and_then(|ns| ns.bridge_name.clone())
+                .unwrap_or_else(|| "qemubr0".to_string());
+            match crate::commands::bridge_setup::plan_bridge_setup(&app.network_caps, &bridge) {
+                Ok(steps) if steps.is_empty() => app.set_status(format!("Bridge {} is already set up", bridge)),
+                Ok(steps) => {
+                    app.bridge_setup_plan = Some((bridge, steps));
+                    app.push_screen(crate::app::Screen::BridgeSetup);
+                }
+                Err(e) => app.set_status(format!("Cannot set up bridge: {}", e)),
+            }
+        }
+        KeyCode::Char(' ') if is_user => {
+            if let Some(ref mut ns) = app.network_settings_state {
+                if ns.selected_field == 3 {
+                    ns.smb_share = !ns.smb_share;
+                }
+            }
+        }
+        KeyCode::Char('s') if is_tap => {
+            let (tap, uplink) = {
+                let ns = app.network_settings_state.as_ref().unwrap();
+                (ns.tap_name.clone().unwrap_or_else(|| DEFAULT_TAP_NAME.to_string()), ns.tap_uplink.clone())
+            };
+            match tap_setup::plan_tap_setup(&app.network_caps, &tap, &uplink) {
+                Ok(steps) if steps.is_empty() => app.set_status(format!("TAP {} is already set up", tap)),
+                Ok(steps) => {
+                    app.bridge_setup_plan = Some((tap, steps));
+                    app.push_screen(crate::app::Screen::BridgeSetup);
+                }
+                Err(e) => app.set_status(format!("Cannot set up TAP: {}", e)),
+            }
+        }
         KeyCode::Enter => {
             let ns = app.network_settings_state.as_ref().unwrap();
             if ns.selected_field == 2 && show_pf {
@@ -576,8 +849,9 @@ fn apply_network_settings(app: &mut App) -> anyhow::Result<()> {
             &vm_path,
             &ns.model,
             &ns.backend,
-            ns.bridge_name.as_deref(),
+            if ns.backend == "tap" { ns.tap_name.as_deref() } else { ns.bridge_name.as_deref() },
             &ns.port_forwards,
+            ns.smb_share,
         )?;
 
         app.reload_selected_vm_script();
@@ -588,7 +862,11 @@ fn apply_network_settings(app: &mut App) -> anyhow::Result<()> {
             app.update_filter();
         }
 
-        app.set_status("Network settings updated");
+        if ns.smb_share && ns.backend == "user" && !crate::commands::qemu_system::is_smbd_available() {
+            app.set_status("Network settings updated (SMB share needs Samba's smbd on the host)");
+        } else {
+            app.set_status("Network settings updated");
+        }
     }
 
     app.network_settings_state = None;
@@ -0,0 +1,207 @@
+//! Unused Files Screen
+//!
+//! Files in VM folders that no launch script, manifest or backing chain
+//! refers to, largest first. Leftovers of interrupted operations start
+//! out selected; everything else is only removed when picked. `t` moves
+//! the selected files to the trash, `D` (pressed twice) deletes them.
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    prelude::*,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+use crate::app::{App, OrphanFilesState, Screen};
+use crate::format::format_size;
+use crate::vm::orphans;
+
+/// Scan the library's stopped VMs for unused files
+pub fn open(app: &mut App) {
+    // Running VMs may be writing files the script doesn't name yet
+    let vms: Vec<_> = app
+        .vms
+        .iter()
+        .filter(|vm| vm.remote.is_none() && !app.running_vms.contains_key(&vm.id))
+        .collect();
+    let files = orphans::scan(&vms);
+    if files.is_empty() {
+        app.set_status("No unused files found in VM folders");
+        return;
+    }
+    app.orphan_files_state = Some(OrphanFilesState {
+        marked: files.iter().map(|f| f.kind.preselected()).collect(),
+        files,
+        selected: 0,
+        confirm_delete: false,
+    });
+    app.push_screen(Screen::OrphanFiles);
+}
+
+/// Render the unused files dialog
+pub fn render(app: &App, frame: &mut Frame) {
+    let Some(ref state) = app.orphan_files_state else {
+        return;
+    };
+    let area = frame.area();
+
+    let dialog_width = 90.min(area.width.saturating_sub(4));
+    let dialog_height = 24.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let total: u64 = state.files.iter().map(|f| f.size).sum();
+    let block = Block::default()
+        .title(format!(" Unused Files: {} in {} files ", format_size(total), state.files.len()))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(3),    // Files
+            Constraint::Length(1), // Selection summary
+            Constraint::Length(1), // Help text
+        ])
+        .split(inner.inner(Margin::new(1, 0)));
+
+    let height = chunks[0].height as usize;
+    let first = state.selected.saturating_sub(height.saturating_sub(1));
+    let lines: Vec<Line> = state
+        .files
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(height)
+        .map(|(i, file)| {
+            let text = format!(
+                "{}[{}] {:>10}  {:<10}  {} / {}",
+                if i == state.selected { "> " } else { "  " },
+                if state.marked[i] { "x" } else { " " },
+                format_size(file.size),
+                file.kind.label(),
+                file.vm_name,
+                file.file_name()
+            );
+            let style = match (i == state.selected, state.marked[i]) {
+                (true, _) => Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                (false, true) => Style::default().fg(Color::Red),
+                (false, false) => Style::default().fg(Color::White),
+            };
+            Line::styled(text, style)
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), chunks[0]);
+
+    let (count, size) = selected_totals(state);
+    let summary = if state.confirm_delete {
+        Line::styled(
+            format!("Press D again to permanently delete {} files ({})", count, format_size(size)),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )
+    } else {
+        Line::styled(format!("Selected: {} files, {}", count, format_size(size)), Style::default().fg(Color::Gray))
+    };
+    frame.render_widget(Paragraph::new(summary), chunks[1]);
+
+    let help = Paragraph::new("[j/k] Move  [Space] Select  [a] All  [t] Trash  [D] Delete  [Esc] Close")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, chunks[2]);
+}
+
+fn selected_totals(state: &OrphanFilesState) -> (usize, u64) {
+    state
+        .files
+        .iter()
+        .zip(&state.marked)
+        .filter(|(_, marked)| **marked)
+        .fold((0, 0), |(count, size), (file, _)| (count + 1, size + file.size))
+}
+
+/// Handle key input for the unused files screen
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(ref mut state) = app.orphan_files_state else {
+        app.pop_screen();
+        return Ok(());
+    };
+    let confirming = std::mem::take(&mut state.confirm_delete);
+    match key.code {
+        KeyCode::Esc => {
+            app.orphan_files_state = None;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down if state.selected + 1 < state.files.len() => state.selected += 1,
+        KeyCode::Char('k') | KeyCode::Up => state.selected = state.selected.saturating_sub(1),
+        KeyCode::Char(' ') => {
+            if let Some(marked) = state.marked.get_mut(state.selected) {
+                *marked = !*marked;
+            }
+        }
+        KeyCode::Char('a') => {
+            let all = state.marked.iter().all(|m| *m);
+            state.marked.iter_mut().for_each(|m| *m = !all);
+        }
+        KeyCode::Char('t') => remove_selected(app, false),
+        KeyCode::Char('D') if confirming => remove_selected(app, true),
+        KeyCode::Char('D') if state.marked.iter().any(|m| *m) => state.confirm_delete = true,
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Trash or delete the selected files and drop them from the list
+fn remove_selected(app: &mut App, permanent: bool) {
+    let library_path = app.config.vm_library_path.clone();
+    let Some(ref mut state) = app.orphan_files_state else {
+        return;
+    };
+    if !state.marked.iter().any(|m| *m) {
+        app.set_status("No files selected");
+        return;
+    }
+
+    let mut removed = 0;
+    let mut freed = 0;
+    let mut error = None;
+    let mut kept = Vec::new();
+    for (file, marked) in std::mem::take(&mut state.files).into_iter().zip(std::mem::take(&mut state.marked)) {
+        if marked && error.is_none() {
+            match orphans::remove(&file, &library_path, permanent) {
+                Ok(()) => {
+                    removed += 1;
+                    freed += file.size;
+                    continue;
+                }
+                Err(e) => error = Some(e),
+            }
+        }
+        kept.push((file, marked));
+    }
+    (state.files, state.marked) = kept.into_iter().unzip();
+    state.selected = state.selected.min(state.files.len().saturating_sub(1));
+
+    let done = if permanent { "Deleted" } else { "Moved to trash:" };
+    let message = format!("{} {} files ({})", done, removed, format_size(freed));
+    if state.files.is_empty() {
+        app.orphan_files_state = None;
+        app.pop_screen();
+    }
+    match error {
+        Some(e) => app.set_status(format!("{}; error: {}", message, e)),
+        None => app.set_status(message),
+    }
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    Rect::new(x, y, width, height)
+}
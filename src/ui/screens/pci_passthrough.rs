@@ -79,9 +79,9 @@ pub fn render(app: &App, frame: &mut Frame) {
 
     // Help text - show GPU options only when multi-GPU passthrough is enabled (not single GPU)
     let help_text = if app.config.enable_multi_gpu_passthrough && !app.config.single_gpu_enabled {
-        "[Space/Enter] Toggle  [g] Auto-select GPU  [s] Save  [p] Prerequisites  [Esc] Back"
+        "[Space/Enter] Toggle  [g] Auto-select GPU  [s] Save  [p] Host check  [Esc] Back"
     } else {
-        "[Space/Enter] Toggle  [s] Save  [Esc] Back"
+        "[Space/Enter] Toggle  [s] Save  [p] Host check  [Esc] Back"
     };
     let help = Paragraph::new(help_text)
         .style(Style::default().fg(Color::DarkGray))
@@ -283,19 +283,18 @@ fn render_device_list(app: &App, frame: &mut Frame, area: Rect) {
     frame.render_stateful_widget(list, area, &mut state);
 }
 
-/// Render the prerequisites screen
-#[allow(dead_code)]
-pub fn render_prerequisites(app: &App, frame: &mut Frame) {
+/// Render the host setup check for the selected devices
+pub fn render_host_check(app: &App, frame: &mut Frame) {
     let area = frame.area();
 
-    let dialog_width = 70.min(area.width.saturating_sub(4));
-    let dialog_height = 22.min(area.height.saturating_sub(4));
+    let dialog_width = 90.min(area.width.saturating_sub(4));
+    let dialog_height = 30.min(area.height.saturating_sub(4));
 
     let dialog_area = centered_rect(dialog_width, dialog_height, area);
     frame.render_widget(Clear, dialog_area);
 
     let block = Block::default()
-        .title(" GPU Passthrough Prerequisites ")
+        .title(" Passthrough Host Check ")
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::Cyan))
         .style(Style::default().bg(Color::Black));
@@ -303,110 +302,147 @@ pub fn render_prerequisites(app: &App, frame: &mut Frame) {
     let inner = block.inner(dialog_area);
     frame.render_widget(block, dialog_area);
 
-    let status = app.multi_gpu_status.as_ref();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(5), Constraint::Length(1)])
+        .split(inner);
 
-    let mut lines: Vec<Line> = Vec::new();
+    let Some(check) = app.pci_host_check.as_ref() else {
+        frame.render_widget(
+            Paragraph::new("Unable to check host setup").style(Style::default().fg(Color::Red)),
+            chunks[0],
+        );
+        return;
+    };
 
-    lines.push(Line::styled(
-        "GPU Passthrough Prerequisites",
-        Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
-    ));
-    lines.push(Line::raw(""));
+    let status_line = |ok: bool, label: &str| {
+        let (icon, color) = if ok { (" OK ", Color::Green) } else { ("FAIL", Color::Red) };
+        Line::from(vec![
+            Span::styled(format!("[{}] ", icon), Style::default().fg(color)),
+            Span::raw(label.to_string()),
+        ])
+    };
+    let hint = |text: &str| Line::styled(format!("    {}", text), Style::default().fg(Color::Yellow));
+    let heading = |text: &str| {
+        Line::styled(text.to_string(), Style::default().fg(Color::White).add_modifier(Modifier::BOLD))
+    };
 
-    if let Some(status) = status {
-        // IOMMU check
-        let iommu_icon = if status.iommu_enabled { " OK " } else { "FAIL" };
-        let iommu_color = if status.iommu_enabled { Color::Green } else { Color::Red };
-        lines.push(Line::from(vec![
-            Span::styled(format!("[{}] ", iommu_icon), Style::default().fg(iommu_color)),
-            Span::raw("IOMMU enabled in kernel"),
-        ]));
-        if !status.iommu_enabled {
-            lines.push(Line::styled(
-                "    Add intel_iommu=on or amd_iommu=on to kernel parameters",
-                Style::default().fg(Color::Yellow),
-            ));
-        }
+    let mut lines: Vec<Line> = vec![heading("Host"), status_line(check.iommu_enabled, "IOMMU enabled in kernel")];
+    if !check.iommu_enabled {
+        lines.push(hint("Enable VT-d/AMD-Vi in firmware and add intel_iommu=on or amd_iommu=on"));
+    }
+    lines.push(status_line(check.vfio_loaded, "VFIO modules loaded"));
+    if !check.vfio_loaded {
+        lines.push(hint("Run: sudo modprobe vfio-pci"));
+    }
 
-        // VFIO check
-        let vfio_icon = if status.vfio_loaded { " OK " } else { "FAIL" };
-        let vfio_color = if status.vfio_loaded { Color::Green } else { Color::Red };
-        lines.push(Line::from(vec![
-            Span::styled(format!("[{}] ", vfio_icon), Style::default().fg(vfio_color)),
-            Span::raw("VFIO modules loaded"),
-        ]));
-        if !status.vfio_loaded {
-            lines.push(Line::styled(
-                "    Run: sudo modprobe vfio-pci",
-                Style::default().fg(Color::Yellow),
+    // Secondary GPU availability matters only for multi-GPU passthrough
+    if app.config.enable_multi_gpu_passthrough && !app.config.single_gpu_enabled {
+        if let Some(status) = app.multi_gpu_status.as_ref() {
+            lines.push(status_line(
+                !status.passthrough_gpus.is_empty(),
+                &format!("Secondary GPU available ({} found)", status.passthrough_gpus.len()),
             ));
         }
+    }
 
-        // GPU availability
-        let gpu_available = !status.passthrough_gpus.is_empty();
-        let gpu_icon = if gpu_available { " OK " } else { "FAIL" };
-        let gpu_color = if gpu_available { Color::Green } else { Color::Red };
-        lines.push(Line::from(vec![
-            Span::styled(format!("[{}] ", gpu_icon), Style::default().fg(gpu_color)),
-            Span::raw(format!(
-                "Secondary GPU available ({} found)",
-                status.passthrough_gpus.len()
-            )),
-        ]));
-
-        if let Some(ref boot_vga) = status.boot_vga {
-            lines.push(Line::styled(
-                format!("    Boot VGA: {} (cannot be passed through)", boot_vga.display_name()),
-                Style::default().fg(Color::DarkGray),
-            ));
+    // Selected devices and the rest of their IOMMU groups
+    lines.push(Line::raw(""));
+    lines.push(heading("Selected devices"));
+    let selected: Vec<&PciDevice> = app
+        .selected_pci_devices
+        .iter()
+        .filter_map(|&i| app.pci_devices.get(i))
+        .collect();
+    if selected.is_empty() {
+        lines.push(Line::styled("  None selected", Style::default().fg(Color::DarkGray)));
+    }
+    for device in &selected {
+        lines.push(status_line(
+            device.is_vfio_bound(),
+            &format!(
+                "{} {} [{}]",
+                device.address,
+                truncate_str(&device.display_name(), 44),
+                device.driver.as_deref().unwrap_or("no driver")
+            ),
+        ));
+        if let Some(group) = device.iommu_group {
+            for peer in app
+                .pci_devices
+                .iter()
+                .filter(|d| d.iommu_group == Some(group) && d.address != device.address)
+            {
+                lines.push(Line::styled(
+                    format!(
+                        "       group {}: {} {} [{}]",
+                        group,
+                        peer.address,
+                        truncate_str(&peer.display_name(), 36),
+                        peer.driver.as_deref().unwrap_or("no driver")
+                    ),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
         }
+    }
 
-        for gpu in &status.passthrough_gpus {
-            let driver_status = if gpu.is_vfio_bound() {
-                "vfio-pci"
-            } else {
-                gpu.driver.as_deref().unwrap_or("no driver")
-            };
-            lines.push(Line::styled(
-                format!("    {} [{}]", gpu.display_name(), driver_status),
-                Style::default().fg(Color::Cyan),
-            ));
+    if !check.issues.is_empty() {
+        lines.push(Line::raw(""));
+        lines.push(heading("Problems"));
+        for issue in &check.issues {
+            lines.push(Line::styled(format!("  - {}", issue), Style::default().fg(Color::Yellow)));
         }
+    }
 
-        // Warnings
-        if !status.warnings.is_empty() {
-            lines.push(Line::raw(""));
-            lines.push(Line::styled("Warnings:", Style::default().fg(Color::Yellow)));
-            for warning in &status.warnings {
-                lines.push(Line::styled(format!("  - {}", warning), Style::default().fg(Color::Yellow)));
-            }
+    let guidance = crate::hardware::vfio_binding_guidance(&check.unbound);
+    if !guidance.is_empty() {
+        lines.push(Line::raw(""));
+        lines.push(heading("Binding to vfio-pci"));
+        for line in guidance {
+            lines.push(Line::styled(format!("  {}", line), Style::default().fg(Color::Cyan)));
         }
+    }
 
-        // Looking Glass info
+    if check.is_ready() && !selected.is_empty() {
         lines.push(Line::raw(""));
         lines.push(Line::styled(
-            "For Looking Glass:",
-            Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
-        ));
-        lines.push(Line::raw("  - Install looking-glass-client on host"));
-        lines.push(Line::raw("  - Install Looking Glass Host in guest VM"));
-        lines.push(Line::raw("  - Connect HDMI/DP dummy plug to guest GPU"));
-    } else {
-        lines.push(Line::styled(
-            "Unable to check prerequisites",
-            Style::default().fg(Color::Red),
+            "Host is ready to pass these devices through.",
+            Style::default().fg(Color::Green),
         ));
     }
 
-    lines.push(Line::raw(""));
-    lines.push(Line::styled(
-        "[Press any key to close]",
-        Style::default().fg(Color::DarkGray),
-    ));
-
     let para = Paragraph::new(lines)
-        .wrap(Wrap { trim: false });
-    frame.render_widget(para, inner);
+        .wrap(Wrap { trim: false })
+        .scroll((app.pci_host_check_scroll, 0));
+    frame.render_widget(para, chunks[0]);
+
+    let help = Paragraph::new("[j/k] Scroll  [r] Re-check  [Esc] Back")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, chunks[1]);
+}
+
+/// Handle key input for the host check screen
+pub fn handle_host_check_key(app: &mut App, key: crossterm::event::KeyEvent) -> anyhow::Result<()> {
+    use crossterm::event::KeyCode;
+
+    match key.code {
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => app.pop_screen(),
+        KeyCode::Char('j') | KeyCode::Down => {
+            app.pci_host_check_scroll = app.pci_host_check_scroll.saturating_add(1);
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            app.pci_host_check_scroll = app.pci_host_check_scroll.saturating_sub(1);
+        }
+        KeyCode::Char('r') | KeyCode::Char('R') => {
+            app.load_pci_devices_keep_selection();
+            app.check_pci_host_setup();
+        }
+        _ => {}
+    }
+
+    Ok(())
 }
 
 /// Check if a device is in the same IOMMU group as any GPU
@@ -512,6 +548,17 @@ pub fn handle_key(app: &mut App, key: crossterm::event::KeyEvent) -> anyhow::Res
                     // Reload script
                     app.reload_selected_vm_script();
 
+                    // Warn now rather than at launch if the host isn't set up
+                    if count > 0 {
+                        let check = app.check_pci_host_setup();
+                        if !check.is_ready() {
+                            status_msg.push_str(&format!(
+                                "; host not ready ({} problem(s), [p] for details)",
+                                check.problem_count()
+                            ));
+                        }
+                    }
+
                     // Regenerate single-GPU scripts if they exist
                     if let Some(vm) = app.selected_vm() {
                         if crate::hardware::scripts_exist(&vm.path) {
@@ -541,12 +588,11 @@ pub fn handle_key(app: &mut App, key: crossterm::event::KeyEvent) -> anyhow::Res
                 }
             }
         }
-        KeyCode::Char('p') | KeyCode::Char('P') if gpu_enabled => {
-            // Refresh and show GPU prerequisites
+        KeyCode::Char('p') | KeyCode::Char('P') => {
+            // Refresh prerequisites and show the host check for the selection
             app.multi_gpu_status = Some(crate::hardware::check_multi_gpu_passthrough_status());
-            // We'll need a separate screen state for prerequisites
-            // For now, just refresh the status
-            app.set_status("GPU prerequisites refreshed");
+            app.check_pci_host_setup();
+            app.push_screen(crate::app::Screen::PciHostCheck);
         }
         _ => {}
     }
@@ -592,34 +638,7 @@ const PCI_MARKER_START: &str = "# >>> PCI Passthrough (managed by vm-curator) >>
 const PCI_MARKER_END: &str = "# <<< PCI Passthrough <<<";
 
 fn remove_pci_section(content: &str) -> String {
-    let mut result = String::new();
-    let mut in_pci_section = false;
-
-    for line in content.lines() {
-        if line.trim() == PCI_MARKER_START {
-            in_pci_section = true;
-            continue;
-        }
-        if line.trim() == PCI_MARKER_END {
-            in_pci_section = false;
-            continue;
-        }
-        if !in_pci_section {
-            // Also remove any $PCI_PASSTHROUGH_ARGS references
-            let cleaned_line = line
-                .replace(" $PCI_PASSTHROUGH_ARGS", "")
-                .replace("$PCI_PASSTHROUGH_ARGS ", "")
-                .replace("$PCI_PASSTHROUGH_ARGS", "");
-            result.push_str(&cleaned_line);
-            result.push('\n');
-        }
-    }
-
-    while result.ends_with("\n\n") {
-        result.pop();
-    }
-
-    result
+    crate::vm::lifecycle::remove_args_section(content, PCI_MARKER_START, PCI_MARKER_END, "$PCI_PASSTHROUGH_ARGS")
 }
 
 fn generate_pci_section(devices: &[&PciDevice]) -> String {
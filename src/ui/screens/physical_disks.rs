@@ -0,0 +1,356 @@
+//! Physical Disks Screen
+//!
+//! Attaches whole host disks to a VM, e.g. a vintage IDE drive on a USB
+//! adapter. Disks are attached read-only unless made writable, the disk
+//! holding the host system is refused, and disks the host has mounted can
+//! only be attached read-only.
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    prelude::*,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
+};
+use std::path::Path;
+
+use crate::app::{App, PhysicalDisksState, Screen};
+use crate::format::format_size;
+use crate::hardware::block::{enumerate_block_devices, BlockDevice};
+use crate::vm::physical_disk::{self, PhysicalDisk};
+
+/// Scan the host's disks and show them with the VM's attachments
+pub fn open(app: &mut App) {
+    let Some(vm) = app.selected_vm() else {
+        return;
+    };
+    let attached = physical_disk::parse_physical_disks(&vm.config.raw_script);
+    app.physical_disks_state = Some(PhysicalDisksState {
+        devices: enumerate_block_devices(),
+        attached,
+        selected: 0,
+    });
+    app.push_screen(Screen::PhysicalDisks);
+}
+
+/// A row in the list: a host disk, or an attached path that is not plugged in
+enum Row<'a> {
+    Device(&'a BlockDevice),
+    Missing(&'a str),
+}
+
+impl Row<'_> {
+    fn path(&self) -> String {
+        match self {
+            Row::Device(dev) => dev.path.to_string_lossy().to_string(),
+            Row::Missing(path) => path.to_string(),
+        }
+    }
+}
+
+fn rows(state: &PhysicalDisksState) -> Vec<Row<'_>> {
+    let mut rows: Vec<Row> = state.devices.iter().map(Row::Device).collect();
+    for disk in &state.attached {
+        if !state.devices.iter().any(|dev| is_same_disk(dev, &disk.path)) {
+            rows.push(Row::Missing(&disk.path));
+        }
+    }
+    rows
+}
+
+/// Whether an attached path (by-id link or /dev node) refers to this disk
+fn is_same_disk(dev: &BlockDevice, path: &str) -> bool {
+    let path = Path::new(path);
+    dev.path == path
+        || std::fs::canonicalize(path).is_ok_and(|target| target == Path::new("/dev").join(&dev.name))
+}
+
+fn attachment_index(state: &PhysicalDisksState, row: &Row) -> Option<usize> {
+    state.attached.iter().position(|disk| match row {
+        Row::Device(dev) => is_same_disk(dev, &disk.path),
+        Row::Missing(path) => disk.path == *path,
+    })
+}
+
+/// Render the physical disks screen
+pub fn render(app: &App, frame: &mut Frame) {
+    let Some(ref state) = app.physical_disks_state else {
+        return;
+    };
+    let area = frame.area();
+
+    let dialog_width = 84.min(area.width.saturating_sub(4));
+    let dialog_height = 22.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let block = Block::default()
+        .title(" Physical Disks ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(4),    // Disk list
+            Constraint::Length(4), // Details and warnings
+            Constraint::Length(1), // Help text
+        ])
+        .split(inner.inner(Margin::new(1, 0)));
+
+    let rows = rows(state);
+    let items: Vec<ListItem> = rows
+        .iter()
+        .map(|row| {
+            let attached = attachment_index(state, row).map(|i| &state.attached[i]);
+            let check = if attached.is_some() { "[x]" } else { "[ ]" };
+            let mode = match attached {
+                Some(disk) if disk.read_only => " read-only",
+                Some(_) => " WRITABLE",
+                None => "",
+            };
+            let boot = if attached.is_some_and(|d| d.boot) { " boot" } else { "" };
+            let (text, color) = match row {
+                Row::Device(dev) => {
+                    let bus = if dev.usb { "USB" } else { "internal" };
+                    let color = if dev.is_system_disk() {
+                        Color::DarkGray
+                    } else if attached.is_some() {
+                        Color::Green
+                    } else {
+                        Color::White
+                    };
+                    (
+                        format!(
+                            "{} {:<8} {:<28} {:>9}  {}{}{}",
+                            check,
+                            dev.name,
+                            dev.model,
+                            format_size(dev.size_bytes),
+                            bus,
+                            mode,
+                            boot
+                        ),
+                        color,
+                    )
+                }
+                Row::Missing(path) => (format!("{} {} (not connected){}{}", check, path, mode, boot), Color::Yellow),
+            };
+            ListItem::new(Span::styled(text, Style::default().fg(color)))
+        })
+        .collect();
+
+    let mut list_state = ListState::default();
+    if !rows.is_empty() {
+        list_state.select(Some(state.selected));
+    }
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::BOTTOM))
+        .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+        .highlight_symbol("> ");
+    frame.render_stateful_widget(list, chunks[0], &mut list_state);
+
+    let (detail, color) = match rows.get(state.selected) {
+        Some(Row::Device(dev)) => device_notice(dev),
+        Some(Row::Missing(_)) => (
+            "Not connected: the VM will refuse to start until it is plugged in".to_string(),
+            Color::Yellow,
+        ),
+        None => ("No disks found".to_string(), Color::DarkGray),
+    };
+    let mut lines = Vec::new();
+    if let Some(row) = rows.get(state.selected) {
+        lines.push(Line::from(Span::styled(row.path(), Style::default().fg(Color::Cyan))));
+    }
+    lines.push(Line::from(Span::styled(detail, Style::default().fg(color))));
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: true }), chunks[1]);
+
+    let help = Paragraph::new("[Space] Attach  [w] Writable  [b] Boot from it  [s] Save  [Esc] Cancel")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, chunks[2]);
+}
+
+/// The most important thing to know before attaching this disk
+fn device_notice(dev: &BlockDevice) -> (String, Color) {
+    if dev.is_system_disk() {
+        ("This disk holds the running system and cannot be attached".to_string(), Color::Red)
+    } else if dev.in_use() {
+        let places = if dev.mountpoints.is_empty() {
+            "LVM, LUKS or RAID".to_string()
+        } else {
+            dev.mountpoints.join(", ")
+        };
+        (
+            format!("In use by the host ({}): read-only only; unmount it to allow writes", places),
+            Color::Yellow,
+        )
+    } else if !dev.is_readable() {
+        (
+            "No permission to read the device: add yourself to the disk group (sudo usermod -aG disk $USER) and log in again"
+                .to_string(),
+            Color::Yellow,
+        )
+    } else {
+        (
+            "Writable disks are changed for real; image the drive first if it is irreplaceable".to_string(),
+            Color::DarkGray,
+        )
+    }
+}
+
+/// Handle key input for the physical disks screen
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(ref mut state) = app.physical_disks_state else {
+        app.pop_screen();
+        return Ok(());
+    };
+    let row_count = rows(state).len();
+
+    match key.code {
+        KeyCode::Esc => {
+            app.physical_disks_state = None;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down if state.selected + 1 < row_count => {
+            state.selected += 1;
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            state.selected = state.selected.saturating_sub(1);
+        }
+        KeyCode::Char(' ') | KeyCode::Enter => toggle_attached(app),
+        KeyCode::Char('w') => toggle_writable(app),
+        KeyCode::Char('b') => toggle_boot(app),
+        KeyCode::Char('s') => save(app),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn toggle_attached(app: &mut App) {
+    let Some(ref mut state) = app.physical_disks_state else {
+        return;
+    };
+    let rows = rows(state);
+    let Some(row) = rows.get(state.selected) else {
+        return;
+    };
+    let path = row.path();
+    let index = attachment_index(state, row);
+    let device = match row {
+        Row::Device(dev) => Some((*dev).clone()),
+        Row::Missing(_) => None,
+    };
+    let row_count = rows.len();
+
+    if let Some(i) = index {
+        state.attached.remove(i);
+        // Dropping a disconnected entry also drops its row
+        if device.is_none() {
+            state.selected = state.selected.min(row_count.saturating_sub(2));
+        }
+        return;
+    }
+    let Some(dev) = device else {
+        return;
+    };
+    if dev.is_system_disk() {
+        app.set_status("Error: Refusing to attach the disk the host is running from");
+        return;
+    }
+    state.attached.push(PhysicalDisk {
+        path,
+        read_only: true,
+        boot: false,
+    });
+    if !dev.is_readable() {
+        app.set_status("Attached, but you cannot read the device yet: join the disk group (sudo usermod -aG disk $USER)");
+    }
+}
+
+fn toggle_writable(app: &mut App) {
+    let Some(ref mut state) = app.physical_disks_state else {
+        return;
+    };
+    let rows = rows(state);
+    let Some(row) = rows.get(state.selected) else {
+        return;
+    };
+    let Some(index) = attachment_index(state, row) else {
+        app.set_status("Attach the disk first");
+        return;
+    };
+    let in_use = matches!(row, Row::Device(dev) if dev.in_use());
+    let can_write = matches!(row, Row::Device(dev) if dev.is_writable());
+
+    let disk = &mut state.attached[index];
+    if disk.read_only && in_use {
+        app.set_status("Error: The host is using this disk; unmount it before giving the guest write access");
+        return;
+    }
+    disk.read_only = !disk.read_only;
+    if !disk.read_only && !can_write {
+        app.set_status("Writable, but you cannot write to the device yet: join the disk group (sudo usermod -aG disk $USER)");
+    } else if !disk.read_only {
+        app.set_status("Warning: The guest will write directly to this drive");
+    }
+}
+
+fn toggle_boot(app: &mut App) {
+    let Some(ref mut state) = app.physical_disks_state else {
+        return;
+    };
+    let rows = rows(state);
+    let Some(index) = rows.get(state.selected).and_then(|row| attachment_index(state, row)) else {
+        app.set_status("Attach the disk first");
+        return;
+    };
+    // Only one disk can boot first
+    let boot = !state.attached[index].boot;
+    for disk in state.attached.iter_mut() {
+        disk.boot = false;
+    }
+    state.attached[index].boot = boot;
+}
+
+fn save(app: &mut App) {
+    let Some(vm) = app.selected_vm().cloned() else {
+        return;
+    };
+    if app.running_vms.contains_key(&vm.id) {
+        app.set_status("Error: Cannot change disks while the VM is running. Please shut down the VM first.");
+        return;
+    }
+    let Some(ref state) = app.physical_disks_state else {
+        return;
+    };
+    let virtio = vm.config.primary_disk().is_some_and(|d| d.interface == "virtio");
+    let q35 = vm.config.machine.as_deref().is_some_and(|m| m.contains("q35"));
+
+    match physical_disk::set_physical_disks(&vm.launch_script, &state.attached, virtio, q35) {
+        Ok(()) => {
+            let count = state.attached.len();
+            app.physical_disks_state = None;
+            app.pop_screen();
+            let _ = app.refresh_vms();
+            if count == 0 {
+                app.set_status("Physical disks detached");
+            } else {
+                app.set_status(format!("{} physical disk(s) attached", count));
+            }
+        }
+        Err(e) => app.set_status(format!("Error: {}", e)),
+    }
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    Rect::new(x, y, width, height)
+}
@@ -0,0 +1,589 @@
+//! QEMU Profile Editor Screen
+//!
+//! Lists every QEMU profile and manages the user's own in
+//! `qemu_profiles.toml` in the config directory: new profiles, duplicates
+//! of existing ones, edits (an edited built-in profile is saved as a user
+//! override) and deletion. The wizard's OS list is reloaded on every change,
+//! so a saved profile can be used straight away.
+
+use anyhow::{bail, Result};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    prelude::*,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+
+use crate::app::{App, ProfileEditorState, ProfileForm, Screen};
+use crate::config::Config;
+use crate::metadata::qemu_profiles::{load_user_profiles, save_user_profiles, WIZARD_CATEGORIES};
+use crate::metadata::{QemuProfile, QemuProfileStore};
+
+/// How a form field is edited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldKind {
+    Text,
+    Number,
+    /// yes/no, toggled
+    Toggle,
+    /// One of the wizard's categories, cycled
+    Category,
+}
+
+/// Form fields, in the order shown
+const FIELDS: [(&str, FieldKind); 24] = [
+    ("ID", FieldKind::Text),
+    ("Display name", FieldKind::Text),
+    ("Category", FieldKind::Category),
+    ("Emulator", FieldKind::Text),
+    ("Memory (MB)", FieldKind::Number),
+    ("CPU cores", FieldKind::Number),
+    ("CPU model", FieldKind::Text),
+    ("Machine", FieldKind::Text),
+    ("Graphics", FieldKind::Text),
+    ("Resolution", FieldKind::Text),
+    ("Audio", FieldKind::Text),
+    ("Network model", FieldKind::Text),
+    ("Disk interface", FieldKind::Text),
+    ("Disk size (GB)", FieldKind::Number),
+    ("KVM", FieldKind::Toggle),
+    ("UEFI", FieldKind::Toggle),
+    ("TPM", FieldKind::Toggle),
+    ("RTC local time", FieldKind::Toggle),
+    ("USB tablet", FieldKind::Toggle),
+    ("Display", FieldKind::Text),
+    ("Extra args", FieldKind::Text),
+    ("ISO URL", FieldKind::Text),
+    ("Notes", FieldKind::Text),
+    ("Post-install", FieldKind::Text),
+];
+
+/// What the selected field takes
+fn hint(field: usize) -> &'static str {
+    match field {
+        0 => "Lowercase letters, digits and dashes (e.g. os2-warp4-tuned); fixed once saved",
+        2 => "Left/Right to change; the wizard lists profiles under their category",
+        3 => "e.g. qemu-system-x86_64, qemu-system-i386, qemu-system-ppc",
+        6 => "e.g. host, qemu64, pentium, 486 (empty for QEMU's default)",
+        7 => "e.g. q35, pc, isapc, mac99 (empty for QEMU's default)",
+        8 => "std, cirrus, vmware, qxl, virtio or none",
+        9 => "Preferred guest resolution, e.g. 800x600 (empty for none)",
+        10 => "Comma-separated devices, e.g. sb16, ac97, intel-hda, hda-duplex",
+        11 => "virtio, e1000, rtl8139, ne2k_pci, pcnet or none",
+        12 => "virtio, ide, sata, scsi or sd",
+        19 => "gtk, sdl, spice or vnc",
+        20 => "QEMU arguments, e.g. -device usb-kbd -no-hpet",
+        21 => "Download URL for free/open-source OSes (empty for none)",
+        22 => "Tips shown in the wizard",
+        23 => "Checklist steps after installing, separated by semicolons",
+        _ => "",
+    }
+}
+
+fn yes_no(value: bool) -> String {
+    if value { "yes" } else { "no" }.to_string()
+}
+
+/// Text of each field for a profile
+fn form_values(id: &str, profile: &QemuProfile) -> Vec<String> {
+    let optional = |value: &Option<String>| value.clone().unwrap_or_default();
+    vec![
+        id.to_string(),
+        profile.display_name.clone(),
+        profile.category.clone(),
+        profile.emulator.clone(),
+        profile.memory_mb.to_string(),
+        profile.cpu_cores.to_string(),
+        optional(&profile.cpu_model),
+        optional(&profile.machine),
+        profile.vga.clone(),
+        optional(&profile.resolution),
+        profile.audio.join(", "),
+        profile.network_model.clone(),
+        profile.disk_interface.clone(),
+        profile.disk_size_gb.to_string(),
+        yes_no(profile.enable_kvm),
+        yes_no(profile.uefi),
+        yes_no(profile.tpm),
+        yes_no(profile.rtc_localtime),
+        yes_no(profile.usb_tablet),
+        profile.display.clone(),
+        profile.extra_args.join(" "),
+        optional(&profile.iso_url),
+        optional(&profile.notes),
+        profile.post_install.join("; "),
+    ]
+}
+
+/// The profile the form describes
+fn form_profile(form: &ProfileForm) -> Result<QemuProfile> {
+    let value = |i: usize| form.values[i].trim();
+    let optional = |i: usize| Some(value(i).to_string()).filter(|v| !v.is_empty());
+    let number = |i: usize| -> Result<u32> {
+        match value(i).parse::<u32>() {
+            Ok(n) if n > 0 || i == 13 => Ok(n),
+            _ => bail!("{} must be a positive whole number", FIELDS[i].0),
+        }
+    };
+    let required = |i: usize| -> Result<String> {
+        if value(i).is_empty() {
+            bail!("{} can't be empty", FIELDS[i].0);
+        }
+        Ok(value(i).to_string())
+    };
+
+    let mut profile = form.profile.clone();
+    profile.display_name = required(1)?;
+    profile.category = value(2).to_string();
+    profile.emulator = required(3)?;
+    profile.memory_mb = number(4)?;
+    profile.cpu_cores = number(5)?;
+    profile.cpu_model = optional(6);
+    profile.machine = optional(7);
+    profile.vga = required(8)?;
+    profile.resolution = optional(9);
+    profile.audio = value(10)
+        .split(',')
+        .map(|device| device.trim().to_string())
+        .filter(|device| !device.is_empty())
+        .collect();
+    profile.network_model = required(11)?;
+    profile.disk_interface = required(12)?;
+    profile.disk_size_gb = number(13)?;
+    profile.enable_kvm = value(14) == "yes";
+    profile.uefi = value(15) == "yes";
+    profile.tpm = value(16) == "yes";
+    profile.rtc_localtime = value(17) == "yes";
+    profile.usb_tablet = value(18) == "yes";
+    profile.display = required(19)?;
+    profile.extra_args = split_args(value(20));
+    profile.iso_url = optional(21);
+    profile.notes = optional(22);
+    profile.post_install = value(23)
+        .split(';')
+        .map(|step| step.trim().to_string())
+        .filter(|step| !step.is_empty())
+        .collect();
+    Ok(profile)
+}
+
+/// Split arguments before each option, the way profiles store them
+/// ("-device usb-kbd -no-hpet" is ["-device usb-kbd", "-no-hpet"])
+fn split_args(text: &str) -> Vec<String> {
+    let mut args: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        match args.last_mut() {
+            Some(arg) if !word.starts_with('-') => {
+                arg.push(' ');
+                arg.push_str(word);
+            }
+            _ => args.push(word.to_string()),
+        }
+    }
+    args
+}
+
+/// Profile IDs in the wizard's category order, then by name
+fn sorted_ids(profiles: &QemuProfileStore) -> Vec<String> {
+    let mut list = profiles.list_all();
+    let rank = |category: &str| WIZARD_CATEGORIES.iter().position(|c| *c == category).unwrap_or(WIZARD_CATEGORIES.len());
+    list.sort_by_key(|(id, profile)| (rank(&profile.category), profile.display_name.to_lowercase(), (*id).clone()));
+    list.into_iter().map(|(id, _)| id.clone()).collect()
+}
+
+/// Open the profile list
+pub fn open(app: &mut App) {
+    let user = match load_user_profiles(&Config::qemu_profiles_path()) {
+        Ok(user) => user,
+        Err(e) => {
+            app.set_status(format!("Error: {:#}", e));
+            return;
+        }
+    };
+    let mut builtin = QemuProfileStore::load_embedded();
+    crate::plugins::merge_profiles(&mut builtin);
+    app.profile_editor_state = Some(ProfileEditorState {
+        ids: sorted_ids(&app.qemu_profiles),
+        builtin: builtin.list_all().into_iter().map(|(id, _)| id.clone()).collect(),
+        user,
+        selected: 0,
+        confirm_delete: false,
+        form: None,
+    });
+    app.push_screen(Screen::ProfileEditor);
+}
+
+/// Render the profile list, or the form of the profile being edited
+pub fn render(app: &App, frame: &mut Frame) {
+    let Some(ref state) = app.profile_editor_state else {
+        return;
+    };
+    let area = frame.area();
+
+    let dialog_width = 96.min(area.width.saturating_sub(4));
+    let dialog_height = 32.min(area.height.saturating_sub(2));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    match state.form {
+        Some(ref form) => render_form(form, frame, dialog_area),
+        None => render_list(app, state, frame, dialog_area),
+    }
+}
+
+fn render_list(app: &App, state: &ProfileEditorState, frame: &mut Frame, area: Rect) {
+    let block = Block::default()
+        .title(format!(" QEMU Profiles ({} yours) ", state.user.len()))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // Column headings
+            Constraint::Min(3),    // Profiles
+            Constraint::Length(1), // Help text
+        ])
+        .split(inner.inner(Margin::new(1, 0)));
+
+    let heading = format!("  {:16}  {:28}  {}", "Category", "ID", "Name");
+    frame.render_widget(
+        Paragraph::new(heading).style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+        chunks[0],
+    );
+
+    let height = chunks[1].height as usize;
+    let first = state.selected.saturating_sub(height.saturating_sub(1));
+    let lines: Vec<Line> = state
+        .ids
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(height)
+        .filter_map(|(i, id)| {
+            let profile = app.qemu_profiles.get(id)?;
+            let tag = match (state.user.contains_key(id), state.builtin.contains(id)) {
+                (true, true) => " (modified)",
+                (true, false) => " (yours)",
+                _ => "",
+            };
+            let text = format!(
+                "{}{:16}  {:28}  {}{}",
+                if i == state.selected { "> " } else { "  " },
+                QemuProfileStore::category_display_name(&profile.category),
+                id,
+                profile.display_name,
+                tag
+            );
+            let style = match (i == state.selected, tag.is_empty()) {
+                (true, _) => Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                (false, false) => Style::default().fg(Color::Green),
+                (false, true) => Style::default(),
+            };
+            Some(Line::styled(text, style))
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), chunks[1]);
+
+    let help = Paragraph::new("[Enter] Edit  [n] New  [c] Duplicate  [d] Delete yours  [Esc] Close")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, chunks[2]);
+}
+
+fn render_form(form: &ProfileForm, frame: &mut Frame, area: Rect) {
+    let title = match (form.id.is_empty(), form.dirty) {
+        (true, _) => " New QEMU Profile ".to_string(),
+        (false, true) => format!(" QEMU Profile: {} (modified) ", form.id),
+        (false, false) => format!(" QEMU Profile: {} ", form.id),
+    };
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(5),    // Fields
+            Constraint::Length(2), // Hint for the selected field
+            Constraint::Length(1), // Help text
+        ])
+        .split(inner.inner(Margin::new(1, 0)));
+
+    let height = chunks[0].height as usize;
+    let first = form.selected.saturating_sub(height.saturating_sub(1));
+    let lines: Vec<Line> = FIELDS
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(height)
+        .map(|(i, (label, _))| {
+            let selected = i == form.selected;
+            let label_style = if i == 0 && !form.new_id {
+                Style::default().fg(Color::DarkGray)
+            } else if selected {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::Yellow)
+            };
+            let value = if selected && form.editing {
+                format!("{}_", form.values[i])
+            } else {
+                form.values[i].clone()
+            };
+            let value = match FIELDS[i].1 {
+                FieldKind::Category => format!("< {} >", QemuProfileStore::category_display_name(&value)),
+                _ => value,
+            };
+            Line::from(vec![
+                Span::raw(if selected { "> " } else { "  " }),
+                Span::styled(format!("{:16}", label), label_style),
+                Span::raw(value),
+            ])
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), chunks[0]);
+
+    let hint = Paragraph::new(hint(form.selected))
+        .style(Style::default().fg(Color::Gray))
+        .wrap(Wrap { trim: true });
+    frame.render_widget(hint, chunks[1]);
+
+    let help = if form.editing {
+        "Type to edit  [Enter] Done"
+    } else {
+        "[Enter] Edit/Toggle  [Left/Right] Change category  [s] Save  [Esc] Back"
+    };
+    let help = Paragraph::new(help)
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, chunks[2]);
+}
+
+/// Handle key input for the profile editor
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(ref mut state) = app.profile_editor_state else {
+        app.pop_screen();
+        return Ok(());
+    };
+    if state.form.is_some() {
+        return handle_form_key(app, key);
+    }
+
+    if key.code != KeyCode::Char('d') {
+        state.confirm_delete = false;
+    }
+    let selected_id = state.ids.get(state.selected).cloned();
+    match key.code {
+        KeyCode::Esc => {
+            app.profile_editor_state = None;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down if state.selected + 1 < state.ids.len() => state.selected += 1,
+        KeyCode::Char('k') | KeyCode::Up => state.selected = state.selected.saturating_sub(1),
+        KeyCode::PageDown => state.selected = (state.selected + 10).min(state.ids.len().saturating_sub(1)),
+        KeyCode::PageUp => state.selected = state.selected.saturating_sub(10),
+        KeyCode::Enter | KeyCode::Char('e') => {
+            let Some(id) = selected_id else {
+                return Ok(());
+            };
+            let profile = app.qemu_profiles.get_or_default(&id);
+            state.form = Some(new_form(&id, false, profile));
+        }
+        KeyCode::Char('n') => {
+            let profile = QemuProfile::default();
+            state.form = Some(new_form("", true, profile));
+        }
+        KeyCode::Char('c') => {
+            let Some(id) = selected_id else {
+                return Ok(());
+            };
+            let mut profile = app.qemu_profiles.get_or_default(&id);
+            profile.display_name = format!("{} (custom)", profile.display_name);
+            let copy_id = (1..)
+                .map(|n| if n == 1 { format!("{}-custom", id) } else { format!("{}-custom-{}", id, n) })
+                .find(|candidate| app.qemu_profiles.get(candidate).is_none())
+                .unwrap_or_default();
+            let mut form = new_form(&copy_id, true, profile);
+            form.dirty = true;
+            state.form = Some(form);
+        }
+        KeyCode::Char('d') => {
+            let Some(id) = selected_id.filter(|id| state.user.contains_key(id)) else {
+                app.set_status("Only your own profiles can be deleted");
+                return Ok(());
+            };
+            let builtin = state.builtin.contains(&id);
+            if !state.confirm_delete {
+                state.confirm_delete = true;
+                if builtin {
+                    app.set_status(format!("Press d again to drop your changes to {}", id));
+                } else {
+                    app.set_status(format!("Press d again to delete {}", id));
+                }
+                return Ok(());
+            }
+            match delete(app, &id) {
+                Ok(()) if builtin => app.set_status(format!("{} is back to the built-in profile", id)),
+                Ok(()) => app.set_status(format!("Deleted {}", id)),
+                Err(e) => app.set_status(format!("Error: {:#}", e)),
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn new_form(id: &str, new_id: bool, profile: QemuProfile) -> ProfileForm {
+    ProfileForm {
+        id: id.to_string(),
+        new_id,
+        values: form_values(id, &profile),
+        profile,
+        selected: if new_id { 0 } else { 1 },
+        editing: false,
+        dirty: false,
+        confirm_discard: false,
+    }
+}
+
+fn handle_form_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(form) = app.profile_editor_state.as_mut().and_then(|state| state.form.as_mut()) else {
+        return Ok(());
+    };
+    let field = form.selected;
+
+    if form.editing {
+        match key.code {
+            KeyCode::Enter | KeyCode::Esc | KeyCode::Tab => form.editing = false,
+            KeyCode::Char(c) if !c.is_control() => {
+                let allowed = match FIELDS[field].1 {
+                    FieldKind::Number => c.is_ascii_digit(),
+                    _ if field == 0 => c.is_ascii_alphanumeric() || c == '-' || c == '_',
+                    _ => true,
+                };
+                if allowed {
+                    form.values[field].push(if field == 0 { c.to_ascii_lowercase() } else { c });
+                    form.dirty = true;
+                }
+            }
+            KeyCode::Backspace => {
+                form.values[field].pop();
+                form.dirty = true;
+            }
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    if key.code != KeyCode::Esc {
+        form.confirm_discard = false;
+    }
+    match key.code {
+        KeyCode::Esc if form.dirty && !form.confirm_discard => {
+            form.confirm_discard = true;
+            app.set_status("Unsaved changes: press Esc again to discard them, or s to save");
+        }
+        KeyCode::Esc => {
+            if let Some(ref mut state) = app.profile_editor_state {
+                state.form = None;
+            }
+        }
+        KeyCode::Char('j') | KeyCode::Down if field + 1 < FIELDS.len() => form.selected += 1,
+        KeyCode::Char('k') | KeyCode::Up => form.selected = field.saturating_sub(1),
+        KeyCode::Left | KeyCode::Right | KeyCode::Enter | KeyCode::Char(' ') if FIELDS[field].1 == FieldKind::Category => {
+            let current = WIZARD_CATEGORIES.iter().position(|c| *c == form.values[field]).unwrap_or(0);
+            let next = if key.code == KeyCode::Left {
+                (current + WIZARD_CATEGORIES.len() - 1) % WIZARD_CATEGORIES.len()
+            } else {
+                (current + 1) % WIZARD_CATEGORIES.len()
+            };
+            form.values[field] = WIZARD_CATEGORIES[next].to_string();
+            form.dirty = true;
+        }
+        KeyCode::Enter | KeyCode::Char(' ') if FIELDS[field].1 == FieldKind::Toggle => {
+            form.values[field] = yes_no(form.values[field] != "yes");
+            form.dirty = true;
+        }
+        KeyCode::Enter if field == 0 && !form.new_id => {
+            app.set_status("A saved profile keeps its ID; duplicate it (c) to save it under another");
+        }
+        KeyCode::Enter => form.editing = true,
+        KeyCode::Char('s') | KeyCode::Char('S') => match save(app) {
+            Ok(id) => app.set_status(format!("Saved profile {} to {}", id, Config::qemu_profiles_path().display())),
+            Err(e) => app.set_status(format!("Error: {:#}", e)),
+        },
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Save the form to the user's profiles file and select the saved profile
+fn save(app: &mut App) -> Result<String> {
+    let Some(form) = app.profile_editor_state.as_ref().and_then(|state| state.form.as_ref()) else {
+        bail!("Nothing to save");
+    };
+    let id = form.values[0].trim().to_string();
+    if id.is_empty() {
+        bail!("The profile needs an ID");
+    }
+    if form.new_id && app.qemu_profiles.get(&id).is_some() {
+        bail!("There is already a profile {}", id);
+    }
+    let profile = form_profile(form)?;
+
+    let Some(ref mut state) = app.profile_editor_state else {
+        bail!("Nothing to save");
+    };
+    let mut user = state.user.clone();
+    user.insert(id.clone(), profile);
+    save_user_profiles(&Config::qemu_profiles_path(), &user)?;
+    state.user = user;
+    state.form = None;
+    refresh(app, Some(&id));
+    Ok(id)
+}
+
+/// Remove a profile from the user's profiles file
+fn delete(app: &mut App, id: &str) -> Result<()> {
+    let Some(ref mut state) = app.profile_editor_state else {
+        return Ok(());
+    };
+    let mut user = state.user.clone();
+    user.remove(id);
+    save_user_profiles(&Config::qemu_profiles_path(), &user)?;
+    state.user = user;
+    state.confirm_delete = false;
+    refresh(app, Some(id));
+    Ok(())
+}
+
+/// Reload the profiles everywhere after the user's file changed
+fn refresh(app: &mut App, select: Option<&str>) {
+    app.reload_qemu_profiles();
+    let ids = sorted_ids(&app.qemu_profiles);
+    if let Some(ref mut state) = app.profile_editor_state {
+        if let Some(pos) = select.and_then(|id| ids.iter().position(|i| i == id)) {
+            state.selected = pos;
+        }
+        state.selected = state.selected.min(ids.len().saturating_sub(1));
+        state.ids = ids;
+    }
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    Rect::new(x, y, width, height)
+}
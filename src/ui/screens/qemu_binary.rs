@@ -0,0 +1,207 @@
+//! QEMU Version Screen
+//!
+//! Chooses which build of the VM's emulator runs it: the one on PATH, or
+//! another found on PATH, under the configured `qemu_prefixes` or in a
+//! Flatpak app. Old OSes sometimes only run well on an older QEMU.
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    prelude::*,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+};
+use std::path::{Path, PathBuf};
+
+use crate::app::{App, QemuBinaryState, Screen};
+use crate::commands::qemu_system;
+use crate::ui::screens::launch_env::save_environment;
+use crate::vm::launch_env;
+
+/// Look for builds of the selected VM's emulator
+pub fn open(app: &mut App) {
+    let Some(vm) = app.selected_vm().filter(|vm| vm.remote.is_none()) else {
+        return;
+    };
+    let emulator = vm.config.emulator.command().to_string();
+    let current = launch_env::load(&vm.path).qemu_binary;
+    let binaries = qemu_system::find_qemu_binaries(&app.config.qemu_prefixes)
+        .into_iter()
+        .filter(|binary| binary.emulator == emulator)
+        .map(|binary| {
+            let version = qemu_system::binary_version(&binary.path);
+            (binary, version)
+        })
+        .collect::<Vec<_>>();
+    // Start on the current choice
+    let selected = current
+        .as_ref()
+        .and_then(|current| binaries.iter().position(|(binary, _)| &binary.path == current))
+        .map_or(0, |i| i + 1);
+    app.qemu_binary_state = Some(QemuBinaryState {
+        binaries,
+        default_version: qemu_system::binary_version(Path::new(&emulator)),
+        current,
+        selected,
+    });
+    app.push_screen(Screen::QemuBinary);
+}
+
+/// Render the QEMU version list
+pub fn render(app: &App, frame: &mut Frame) {
+    let Some(ref state) = app.qemu_binary_state else {
+        return;
+    };
+    let emulator = app
+        .selected_vm()
+        .map(|vm| vm.config.emulator.command().to_string())
+        .unwrap_or_default();
+    let area = frame.area();
+
+    let dialog_width = 80.min(area.width.saturating_sub(4));
+    let dialog_height = 16.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let block = Block::default()
+        .title(format!(" QEMU Version: {} ", emulator))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(3),    // Builds
+            Constraint::Length(2), // Details
+            Constraint::Length(1), // Help text
+        ])
+        .split(inner.inner(Margin::new(1, 0)));
+
+    let version = |version: &Option<String>| version.clone().unwrap_or_else(|| "version unknown".to_string());
+    let mark = |chosen: bool| if chosen { "(*)" } else { "( )" };
+
+    let mut items = vec![ListItem::new(format!(
+        "{} Default: {} on PATH ({})",
+        mark(state.current.is_none()),
+        emulator,
+        version(&state.default_version)
+    ))];
+    items.extend(state.binaries.iter().map(|(binary, v)| {
+        ListItem::new(format!(
+            "{} {:<12} {}  [{}]",
+            mark(state.current.as_ref() == Some(&binary.path)),
+            version(v),
+            binary.path.display(),
+            binary.source_label()
+        ))
+    }));
+    // A choice that has since gone away is still shown
+    let missing = state
+        .current
+        .as_ref()
+        .filter(|current| !state.binaries.iter().any(|(binary, _)| &binary.path == *current));
+    if let Some(current) = missing {
+        items.push(ListItem::new(Span::styled(
+            format!("(*) {} (not found)", current.display()),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+
+    let mut list_state = ListState::default();
+    list_state.select(Some(state.selected));
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::BOTTOM))
+        .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+        .highlight_symbol("> ");
+    frame.render_stateful_widget(list, chunks[0], &mut list_state);
+
+    let detail = if state.binaries.is_empty() {
+        "No other builds found. Add install prefixes to qemu_prefixes in config.toml.".to_string()
+    } else {
+        format!(
+            "Builds are looked for on PATH, in Flatpak apps and under qemu_prefixes ({} configured).",
+            app.config.qemu_prefixes.len()
+        )
+    };
+    frame.render_widget(
+        Paragraph::new(detail).style(Style::default().fg(Color::DarkGray)),
+        chunks[1],
+    );
+
+    let help = Paragraph::new("[Enter] Use this build  [Esc] Cancel")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, chunks[2]);
+}
+
+/// Handle key input for the QEMU version list
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(ref mut state) = app.qemu_binary_state else {
+        app.pop_screen();
+        return Ok(());
+    };
+    let missing = state
+        .current
+        .as_ref()
+        .is_some_and(|current| !state.binaries.iter().any(|(binary, _)| binary.path == *current));
+    let rows = 1 + state.binaries.len() + usize::from(missing);
+
+    match key.code {
+        KeyCode::Esc => {
+            app.qemu_binary_state = None;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down if state.selected + 1 < rows => state.selected += 1,
+        KeyCode::Char('k') | KeyCode::Up => state.selected = state.selected.saturating_sub(1),
+        KeyCode::Enter | KeyCode::Char(' ') => {
+            let choice = match state.selected {
+                0 => None,
+                i => match state.binaries.get(i - 1) {
+                    Some((binary, _)) => Some(binary.path.clone()),
+                    // The missing choice: keep it as it is
+                    None => state.current.clone(),
+                },
+            };
+            choose(app, choice);
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Save the chosen build (None for the one on PATH) for the selected VM
+fn choose(app: &mut App, choice: Option<PathBuf>) {
+    let Some(vm) = app.selected_vm() else {
+        return;
+    };
+    let (vm_id, vm_dir) = (vm.id.clone(), vm.path.clone());
+    let mut environment = launch_env::load(&vm_dir);
+    environment.qemu_binary = choice.clone();
+    match save_environment(app, &vm_id, &vm_dir, &environment) {
+        Ok(note) => {
+            let chosen = match choice {
+                Some(ref path) => {
+                    let version = qemu_system::binary_version(path).unwrap_or_else(|| "unknown version".to_string());
+                    format!("{} ({})", version, path.display())
+                }
+                None => "the default on PATH".to_string(),
+            };
+            app.qemu_binary_state = None;
+            app.pop_screen();
+            app.set_status(format!("QEMU set to {}{}", chosen, note));
+        }
+        Err(e) => app.set_status(format!("Error: {:#}", e)),
+    }
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    Rect::new(x, y, width, height)
+}
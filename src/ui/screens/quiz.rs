@@ -0,0 +1,216 @@
+//! Quiz Screen
+//!
+//! Ten multiple-choice questions drawn from the fun facts and release dates
+//! of the OSes in the library, with a running score. After each answer the
+//! VM the question was about can be selected in the list.
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    prelude::*,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+
+use crate::app::{App, QuizState, Screen};
+use crate::metadata::quiz::{self, Rng};
+
+/// Questions per round
+const QUESTIONS: usize = 10;
+
+/// Start a round of questions about the VMs in the list
+pub fn open(app: &mut App) {
+    let Some(state) = new_round(app) else {
+        app.set_status("No trivia for the VMs in the list");
+        return;
+    };
+    app.quiz_state = Some(state);
+    app.push_screen(Screen::Quiz);
+}
+
+fn new_round(app: &App) -> Option<QuizState> {
+    let vms: Vec<_> = app
+        .filtered_indices
+        .iter()
+        .filter_map(|idx| app.vms.get(*idx).cloned())
+        .collect();
+    let seed = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default();
+    let questions = quiz::generate(&app.metadata, &vms, &mut Rng::new(seed), QUESTIONS);
+    if questions.is_empty() {
+        return None;
+    }
+    Some(QuizState {
+        questions,
+        current: 0,
+        selected: 0,
+        picked: None,
+        score: 0,
+    })
+}
+
+/// Render the quiz dialog
+pub fn render(app: &App, frame: &mut Frame) {
+    let Some(ref state) = app.quiz_state else {
+        return;
+    };
+    let area = frame.area();
+
+    let dialog_width = 72.min(area.width.saturating_sub(4));
+    let dialog_height = 20.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let title = match state.questions.get(state.current) {
+        Some(_) => format!(" Trivia: {} of {}  ·  Score {} ", state.current + 1, state.questions.len(), state.score),
+        None => " Trivia ".to_string(),
+    };
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(5),    // Question and choices
+            Constraint::Length(1), // Help text
+        ])
+        .split(inner.inner(Margin::new(2, 1)));
+
+    let Some(question) = state.questions.get(state.current) else {
+        let total = state.questions.len();
+        let verdict = match state.score * 10 / total {
+            10 => "A true computer historian!",
+            7..=9 => "Well done, curator.",
+            4..=6 => "Not bad. The VMs have more to teach you.",
+            _ => "Time to boot a few of these and read up.",
+        };
+        let lines = vec![
+            Line::from(""),
+            Line::styled(
+                format!("You scored {} out of {}", state.score, total),
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            ),
+            Line::from(""),
+            Line::from(verdict),
+        ];
+        frame.render_widget(Paragraph::new(lines).alignment(Alignment::Center), chunks[0]);
+        render_help(frame, chunks[1], "[Enter] Play again  [Esc] Close");
+        return;
+    };
+
+    let mut lines: Vec<Line> = question.prompt.lines().map(|l| Line::from(l.to_string())).collect();
+    lines.push(Line::from(""));
+    for (i, choice) in question.choices.iter().enumerate() {
+        let marker = if i == state.selected && state.picked.is_none() { "> " } else { "  " };
+        let style = match state.picked {
+            Some(_) if i == question.answer => Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+            Some(picked) if i == picked => Style::default().fg(Color::Red),
+            Some(_) => Style::default().fg(Color::DarkGray),
+            None if i == state.selected => Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            None => Style::default(),
+        };
+        lines.push(Line::styled(format!("{}{}. {}", marker, i + 1, choice), style));
+    }
+    if let Some(picked) = state.picked {
+        lines.push(Line::from(""));
+        lines.push(if picked == question.answer {
+            Line::styled("Correct!", Style::default().fg(Color::Green))
+        } else {
+            Line::styled(format!("The answer is {}", question.choices[question.answer]), Style::default().fg(Color::Red))
+        });
+    }
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), chunks[0]);
+
+    let help = if state.picked.is_some() {
+        "[Enter] Next  [g] Go to the VM  [Esc] Close"
+    } else {
+        "[1-4] or [Enter] Answer  [Esc] Close"
+    };
+    render_help(frame, chunks[1], help);
+}
+
+fn render_help(frame: &mut Frame, area: Rect, text: &str) {
+    let help = Paragraph::new(text)
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, area);
+}
+
+/// Handle key input for the quiz
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(ref mut state) = app.quiz_state else {
+        app.pop_screen();
+        return Ok(());
+    };
+    if key.code == KeyCode::Esc {
+        app.quiz_state = None;
+        app.pop_screen();
+        return Ok(());
+    }
+
+    let Some(question) = state.questions.get(state.current) else {
+        // Round over
+        if key.code == KeyCode::Enter {
+            app.quiz_state = new_round(app);
+        }
+        return Ok(());
+    };
+    let choices = question.choices.len();
+
+    match (state.picked, key.code) {
+        (None, KeyCode::Char('j') | KeyCode::Down) => state.selected = (state.selected + 1).min(choices - 1),
+        (None, KeyCode::Char('k') | KeyCode::Up) => state.selected = state.selected.saturating_sub(1),
+        (None, KeyCode::Enter) => answer(state, state.selected),
+        (None, KeyCode::Char(c @ '1'..='9')) => {
+            let choice = c as usize - '1' as usize;
+            if choice < choices {
+                answer(state, choice);
+            }
+        }
+        (Some(_), KeyCode::Enter | KeyCode::Char(' ') | KeyCode::Right) => {
+            state.current += 1;
+            state.selected = 0;
+            state.picked = None;
+        }
+        (Some(_), KeyCode::Char('g') | KeyCode::Char('G')) => go_to_vm(app),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn answer(state: &mut QuizState, choice: usize) {
+    state.selected = choice;
+    state.picked = Some(choice);
+    if state.questions.get(state.current).is_some_and(|q| q.answer == choice) {
+        state.score += 1;
+    }
+}
+
+/// Leave the quiz with the VM the question was about selected
+fn go_to_vm(app: &mut App) {
+    let Some(vm_id) = app
+        .quiz_state
+        .take()
+        .and_then(|state| state.questions.get(state.current).map(|q| q.vm_id.clone()))
+    else {
+        return;
+    };
+    app.select_vm_by_id(&vm_id);
+    app.pop_screen();
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    Rect::new(x, y, width, height)
+}
@@ -0,0 +1,135 @@
+//! Boot Screenshots Screen
+//!
+//! Browses the screens captured shortly after each launch of the selected
+//! VM, newest first, drawn with half blocks.
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    prelude::*,
+    style::{Color, Style},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+
+use crate::app::{App, Screen, ScreenshotGalleryState};
+use crate::format::format_date;
+use crate::ui::widgets::thumbnail;
+use crate::vm::screenshots;
+
+/// Open the gallery for the selected VM
+pub fn open(app: &mut App) {
+    let Some(vm) = app.selected_vm() else {
+        return;
+    };
+    let shots = screenshots::list(&vm.path);
+    let image = shots.first().and_then(|shot| screenshots::load(&shot.path).ok());
+    app.screenshot_gallery_state = Some(ScreenshotGalleryState {
+        vm_name: vm.display_name(),
+        shots,
+        selected: 0,
+        image,
+    });
+    app.push_screen(Screen::Screenshots);
+}
+
+/// Render the gallery
+pub fn render(app: &App, frame: &mut Frame) {
+    let Some(ref state) = app.screenshot_gallery_state else {
+        return;
+    };
+    let area = frame.area();
+
+    let dialog_width = 100.min(area.width.saturating_sub(4));
+    let dialog_height = 40.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let block = Block::default()
+        .title(format!(" {} - Boot Screenshots ", state.vm_name))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // Caption
+            Constraint::Min(3),    // Image
+            Constraint::Length(1), // Help text
+        ])
+        .split(inner.inner(Margin::new(1, 0)));
+
+    let Some(shot) = state.shots.get(state.selected) else {
+        let text = "No boot screenshots yet.\n\n\
+                    The screen of a VM with a QMP socket is captured about 20 seconds after \
+                    each launch; the newest few are kept in its screenshots folder.";
+        frame.render_widget(
+            Paragraph::new(text).style(Style::default().fg(Color::DarkGray)).wrap(Wrap { trim: true }),
+            chunks[1],
+        );
+        render_help(frame, chunks[2]);
+        return;
+    };
+
+    let taken = shot
+        .taken
+        .and_local_timezone(chrono::Local)
+        .single()
+        .map_or_else(|| shot.taken.to_string(), |dt| format_date(&dt, app.config.date_format));
+    let caption = Line::from(vec![
+        Span::styled(format!("{} of {}  ", state.selected + 1, state.shots.len()), Style::default().fg(Color::Yellow)),
+        Span::styled(taken, Style::default().fg(Color::White)),
+    ]);
+    frame.render_widget(Paragraph::new(caption).alignment(Alignment::Center), chunks[0]);
+
+    match state.image {
+        Some(ref image) => {
+            let lines = thumbnail::image_lines(image, chunks[1].width, chunks[1].height);
+            frame.render_widget(Paragraph::new(lines).alignment(Alignment::Center), chunks[1]);
+        }
+        None => frame.render_widget(
+            Paragraph::new("This screenshot can't be read.").style(Style::default().fg(Color::Red)),
+            chunks[1],
+        ),
+    }
+    render_help(frame, chunks[2]);
+}
+
+fn render_help(frame: &mut Frame, area: Rect) {
+    let help = Paragraph::new("[←/→] Newer/older  [Esc] Close")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, area);
+}
+
+/// Handle key input for the gallery
+pub fn handle_key(app: &mut App, key: KeyEvent) {
+    let Some(ref mut state) = app.screenshot_gallery_state else {
+        app.pop_screen();
+        return;
+    };
+    let selected = match key.code {
+        KeyCode::Esc | KeyCode::Char('q') => {
+            app.screenshot_gallery_state = None;
+            app.pop_screen();
+            return;
+        }
+        KeyCode::Right | KeyCode::Char('l') if state.selected + 1 < state.shots.len() => state.selected + 1,
+        KeyCode::Left | KeyCode::Char('h') => state.selected.saturating_sub(1),
+        _ => return,
+    };
+    if selected != state.selected {
+        state.selected = selected;
+        state.image = screenshots::load(&state.shots[selected].path).ok();
+    }
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    Rect::new(x, y, width, height)
+}
@@ -10,8 +10,9 @@ use ratatui::widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap};
 
 use crate::app::App;
 use crate::config::Config;
+use crate::format::DateStyle;
 use crate::fs;
-use crate::hardware::{check_multi_gpu_passthrough_status, check_single_gpu_support, MultiGpuPassthroughStatus, LookingGlassConfig, SingleGpuSupport};
+use crate::hardware::{check_multi_gpu_passthrough_status, check_single_gpu_support, MultiGpuPassthroughStatus, LookingGlassConfig, ResourceCheck, SingleGpuSupport};
 use crate::vm::single_gpu_scripts::{run_system_setup, SystemSetupResult};
 
 /// GPU passthrough validation result
@@ -32,6 +33,10 @@ pub enum SettingsItem {
     DefaultDisplay,
     DefaultEnableKvm,
     ConfirmBeforeLaunch,
+    LaunchResourceCheck,
+    DateFormat,
+    VncClient,
+    SessionSnapshots,
     // GPU Passthrough section header (not selectable, just a label)
     GpuPassthroughHeader,
     // GPU Passthrough disabled - radio button
@@ -71,6 +76,10 @@ impl SettingsItem {
             SettingsItem::DefaultDisplay => "Default Display",
             SettingsItem::DefaultEnableKvm => "Enable KVM by Default",
             SettingsItem::ConfirmBeforeLaunch => "Confirm Before Launch",
+            SettingsItem::LaunchResourceCheck => "Resource Check at Launch",
+            SettingsItem::DateFormat => "Date Format",
+            SettingsItem::VncClient => "VNC Client",
+            SettingsItem::SessionSnapshots => "Session Snapshots to Keep",
             // GPU Passthrough
             SettingsItem::GpuPassthroughHeader => "GPU Passthrough",
             SettingsItem::GpuPassthroughDisabled => "Disabled",
@@ -95,6 +104,13 @@ impl SettingsItem {
             SettingsItem::DefaultDisplay => config.default_display.clone(),
             SettingsItem::DefaultEnableKvm => bool_to_yes_no(config.default_enable_kvm),
             SettingsItem::ConfirmBeforeLaunch => bool_to_yes_no(config.confirm_before_launch),
+            SettingsItem::LaunchResourceCheck => config.launch_resource_check.as_str().to_string(),
+            SettingsItem::DateFormat => config.date_format.as_str().to_string(),
+            SettingsItem::VncClient => config.vnc_client.clone().unwrap_or_default(),
+            SettingsItem::SessionSnapshots => match config.session_snapshots {
+                0 => "Off".to_string(),
+                n => n.to_string(),
+            },
             // GPU Passthrough
             SettingsItem::GpuPassthroughHeader => String::new(),
             SettingsItem::GpuPassthroughDisabled => String::new(), // Radio button, no value display
@@ -132,9 +148,15 @@ impl SettingsItem {
         )
     }
 
-    /// Check if this is a cycle setting (display backend)
+    /// Check if this is a cycle setting (display backend, resource check, date format, session snapshots)
     pub fn is_cycle(&self) -> bool {
-        matches!(self, SettingsItem::DefaultDisplay)
+        matches!(
+            self,
+            SettingsItem::DefaultDisplay
+                | SettingsItem::LaunchResourceCheck
+                | SettingsItem::DateFormat
+                | SettingsItem::SessionSnapshots
+        )
     }
 
     /// Check if this is a section header (not editable)
@@ -151,6 +173,9 @@ impl SettingsItem {
     pub fn cycle_options(&self) -> Option<&'static [&'static str]> {
         match self {
             SettingsItem::DefaultDisplay => Some(&["gtk", "sdl", "spice-app"]),
+            SettingsItem::LaunchResourceCheck => Some(ResourceCheck::ALL),
+            SettingsItem::DateFormat => Some(DateStyle::ALL),
+            SettingsItem::SessionSnapshots => Some(&["Off", "1", "3", "5", "10"]),
             _ => None,
         }
     }
@@ -165,6 +190,10 @@ impl SettingsItem {
             SettingsItem::DefaultDisplay => "default_display",
             SettingsItem::DefaultEnableKvm => "default_enable_kvm",
             SettingsItem::ConfirmBeforeLaunch => "confirm_before_launch",
+            SettingsItem::LaunchResourceCheck => "launch_resource_check",
+            SettingsItem::DateFormat => "date_format",
+            SettingsItem::VncClient => "vnc_client",
+            SettingsItem::SessionSnapshots => "session_snapshots",
             SettingsItem::GpuPassthroughHeader => "gpu_passthrough_header",
             SettingsItem::GpuPassthroughDisabled => "gpu_passthrough_disabled",
             SettingsItem::EnableMultiGpuPassthrough => "enable_multi_gpu_passthrough",
@@ -205,6 +234,10 @@ fn build_visible_items(config: &Config) -> Vec<VisibleItem> {
     items.push(make_visible(SettingsItem::DefaultDisplay, 0));
     items.push(make_visible(SettingsItem::DefaultEnableKvm, 0));
     items.push(make_visible(SettingsItem::ConfirmBeforeLaunch, 0));
+    items.push(make_visible(SettingsItem::LaunchResourceCheck, 0));
+    items.push(make_visible(SettingsItem::DateFormat, 0));
+    items.push(make_visible(SettingsItem::VncClient, 0));
+    items.push(make_visible(SettingsItem::SessionSnapshots, 0));
 
     // GPU Passthrough section
     items.push(make_visible(SettingsItem::GpuPassthroughHeader, 0));
@@ -799,6 +832,15 @@ fn cycle_setting(app: &mut App, item: SettingsItem) -> anyhow::Result<()> {
             SettingsItem::DefaultDisplay => {
                 app.config.default_display = options[next_idx].to_string();
             }
+            SettingsItem::LaunchResourceCheck => {
+                app.config.launch_resource_check = ResourceCheck::from_str(options[next_idx]).unwrap_or_default();
+            }
+            SettingsItem::DateFormat => {
+                app.config.date_format = DateStyle::from_str(options[next_idx]).unwrap_or_default();
+            }
+            SettingsItem::SessionSnapshots => {
+                app.config.session_snapshots = options[next_idx].parse().unwrap_or(0);
+            }
             _ => {}
         }
         save_config(app)?;
@@ -861,6 +903,10 @@ fn apply_edit(app: &mut App, item: SettingsItem) -> anyhow::Result<()> {
         SettingsItem::DefaultDisplay => {
             app.config.default_display = value.to_string();
         }
+        SettingsItem::VncClient => {
+            // Empty means auto-detect
+            app.config.vnc_client = Some(value.to_string()).filter(|v| !v.is_empty());
+        }
         SettingsItem::MultiGpuIvshmemSize => {
             if let Ok(mb) = value.parse::<u32>() {
                 // Clamp to reasonable range (16-512 MB)
@@ -1,8 +1,9 @@
 //! Shared Folders Screen
 //!
-//! Manages virtio-9p shared folders between host and guest VM.
-//! Shows configured folders, mount instructions based on OS tier,
-//! and allows adding/removing shared directories.
+//! Manages shared folders between host and guest VM, exported via
+//! virtio-9p, virtiofs, or SMB over user networking. Shows configured folders,
+//! mount instructions based on export method and OS tier, and allows
+//! adding/removing shared directories.
 
 use anyhow::Result;
 use crossterm::event::{KeyCode, KeyEvent};
@@ -14,6 +15,7 @@ use ratatui::{
 };
 
 use crate::app::{App, FileBrowserMode, Screen};
+use crate::vm::SharedFolderMethod;
 
 /// Render the shared folders screen
 pub fn render(app: &App, frame: &mut Frame) {
@@ -26,7 +28,11 @@ pub fn render(app: &App, frame: &mut Frame) {
     frame.render_widget(Clear, dialog_area);
 
     let folder_count = app.shared_folders.len();
-    let title = format!(" Shared Folders ({} configured) ", folder_count);
+    let title = format!(
+        " Shared Folders ({} configured, {}) ",
+        folder_count,
+        app.shared_folder_method.label()
+    );
 
     let block = Block::default()
         .title(title)
@@ -91,7 +97,11 @@ pub fn render(app: &App, frame: &mut Frame) {
                     ),
                     Span::styled(&folder.host_path, style),
                     Span::styled(
-                        format!("  (tag: {})", folder.mount_tag),
+                        if app.shared_folder_method == SharedFolderMethod::Smb {
+                            format!("  (\\\\10.0.2.4\\{})", folder.mount_tag)
+                        } else {
+                            format!("  (tag: {})", folder.mount_tag)
+                        },
                         Style::default().fg(Color::DarkGray),
                     ),
                 ]))
@@ -114,7 +124,7 @@ pub fn render(app: &App, frame: &mut Frame) {
     render_mount_instructions(app, frame, instructions_area);
 
     // Help text
-    let help = Paragraph::new("[a] Add  [d] Remove  [s] Save  [Esc] Back")
+    let help = Paragraph::new("[a] Add  [d] Remove  [m] Method  [s] Save  [Esc] Back")
         .style(Style::default().fg(Color::DarkGray))
         .alignment(Alignment::Center);
     frame.render_widget(help, help_area);
@@ -141,7 +151,11 @@ fn render_mount_instructions(app: &App, frame: &mut Frame, area: Rect) {
         tier
     };
 
-    let (title, description) = app.shared_folders_help.get_or_default(effective_tier);
+    let (title, description) = if app.shared_folders.is_empty() {
+        app.shared_folders_help.get_or_default(effective_tier)
+    } else {
+        method_help(app, tier)
+    };
 
     // Expand {TAG} placeholders: lines containing {TAG} are repeated once per
     // folder with that folder's mount tag. Lines without {TAG} render once.
@@ -185,6 +199,28 @@ fn render_mount_instructions(app: &App, frame: &mut Frame, area: Rect) {
     frame.render_widget(paragraph, area);
 }
 
+/// Look up instructions for virtiofs/SMB, preferring a tier-specific entry
+fn method_help<'a>(app: &'a App, tier: &str) -> (&'a str, &'a str) {
+    let prefix = match app.shared_folder_method {
+        SharedFolderMethod::NineP => return app.shared_folders_help.get_or_default(tier),
+        SharedFolderMethod::Virtiofs => "virtiofs",
+        SharedFolderMethod::Smb => "smb",
+    };
+
+    // Retro Windows is "not_supported" for virtio, but is the main SMB audience
+    let is_windows = app
+        .selected_vm()
+        .and_then(|vm| vm.os_profile.clone().or_else(|| Some(vm.id.clone())))
+        .map(|id| id.starts_with("windows"))
+        .unwrap_or(false);
+    let tier = if is_windows { "windows" } else { tier };
+
+    app.shared_folders_help
+        .get(&format!("{}_{}", prefix, tier))
+        .or_else(|| app.shared_folders_help.get(prefix))
+        .unwrap_or_else(|| app.shared_folders_help.get_or_default(tier))
+}
+
 /// Determine the mount instruction tier based on the selected VM's OS profile
 pub fn get_mount_tier(app: &App) -> &'static str {
     let vm = match app.selected_vm() {
@@ -276,6 +312,18 @@ pub fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
         KeyCode::Char('d') | KeyCode::Char('D') => {
             app.remove_shared_folder();
         }
+        KeyCode::Char('m') | KeyCode::Char('M') => {
+            app.shared_folder_method = app.shared_folder_method.next();
+            if app.shared_folder_method == SharedFolderMethod::Virtiofs
+                && !crate::commands::qemu_system::is_virtiofsd_available()
+            {
+                app.set_status("Warning: virtiofs requires virtiofsd to be installed");
+            } else if app.shared_folder_method == SharedFolderMethod::Smb
+                && !crate::commands::qemu_system::is_smbd_available()
+            {
+                app.set_status("Warning: SMB sharing requires Samba (smbd) to be installed");
+            }
+        }
         KeyCode::Char('s') | KeyCode::Char('S') => {
             save_shared_folders(app);
         }
@@ -287,7 +335,7 @@ pub fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
 /// Save shared folders to launch.sh
 fn save_shared_folders(app: &mut App) {
     let save_result = if let Some(vm) = app.selected_vm() {
-        let result = crate::vm::save_shared_folders(vm, &app.shared_folders);
+        let result = crate::vm::save_shared_folders(vm, &app.shared_folders, app.shared_folder_method);
         Some((result, app.shared_folders.len()))
     } else {
         None
@@ -0,0 +1,277 @@
+//! Slideshow Screen
+//!
+//! Turns the library into an exhibit: a full-screen, screensaver-like view
+//! that cycles through the VMs with their ASCII art, history and a fun
+//! fact. It starts with `p`, or by itself after `slideshow_idle_secs` on the
+//! VM list. With `slideshow_demo_snapshot` set, a VM whose disk has a
+//! snapshot of that name is reverted to it and launched while its slide is
+//! up, and stopped when the show moves on.
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    prelude::*,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::app::{App, BackgroundResult, Screen, SlideshowState};
+use crate::vm::{self, DiscoveredVm};
+
+/// Shortest time a slide stays up (a demo needs a moment to boot)
+const MIN_INTERVAL_SECS: u64 = 5;
+
+/// Start the slideshow at the selected VM
+pub fn open(app: &mut App) {
+    let slides: Vec<usize> = app
+        .visual_order
+        .iter()
+        .filter_map(|filtered_idx| app.filtered_indices.get(*filtered_idx).copied())
+        .collect();
+    if slides.is_empty() {
+        app.set_status("No VMs to show");
+        return;
+    }
+    let current = app.selected_vm.min(slides.len() - 1);
+    app.slideshow_state = Some(SlideshowState {
+        slides,
+        current,
+        shown_at: Instant::now(),
+        demo_vm: None,
+        demo_cancel: None,
+        shown: 0,
+    });
+    app.push_screen(Screen::Slideshow);
+    start_demo(app);
+}
+
+/// Advance the slideshow, or start it once the VM list has been idle long enough
+pub fn tick(app: &mut App) {
+    let interval = Duration::from_secs(app.config.slideshow_interval_secs.max(MIN_INTERVAL_SECS));
+    match app.slideshow_state {
+        Some(ref state) if state.shown_at.elapsed() >= interval => show(app, 1),
+        Some(_) => {}
+        None => {
+            let idle = app.config.slideshow_idle_secs.map(Duration::from_secs);
+            if app.screen == Screen::MainMenu && idle.is_some_and(|idle| app.last_input.elapsed() >= idle) {
+                open(app);
+            }
+        }
+    }
+}
+
+/// Move `step` slides on (wrapping), swapping the demo VM
+fn show(app: &mut App, step: isize) {
+    stop_demo(app);
+    if let Some(ref mut state) = app.slideshow_state {
+        let count = state.slides.len() as isize;
+        state.current = (state.current as isize + step).rem_euclid(count) as usize;
+        state.shown_at = Instant::now();
+        state.shown += 1;
+    }
+    start_demo(app);
+}
+
+fn current_vm(app: &App) -> Option<&DiscoveredVm> {
+    let state = app.slideshow_state.as_ref()?;
+    app.vms.get(*state.slides.get(state.current)?)
+}
+
+/// Revert the current VM to its demo snapshot and launch it, if it has one
+fn start_demo(app: &mut App) {
+    let Some(snapshot) = app.config.slideshow_demo_snapshot.clone() else {
+        return;
+    };
+    let Some(vm) = current_vm(app).cloned() else {
+        return;
+    };
+    if vm.remote.is_some() || !vm.config.supports_snapshots() || app.running_vms.contains_key(&vm.id) {
+        return;
+    }
+    let Some(disk) = vm.config.primary_disk().map(|d| d.path.clone()) else {
+        return;
+    };
+    let cancel = Arc::new(AtomicBool::new(false));
+    if let Some(ref mut state) = app.slideshow_state {
+        state.demo_vm = Some(vm.id.clone());
+        state.demo_cancel = Some(cancel.clone());
+    }
+
+    let tx = app.background_tx.clone();
+    std::thread::spawn(move || {
+        let has_demo = vm::list_snapshots(&disk).is_ok_and(|list| list.iter().any(|s| s.name == snapshot));
+        if !has_demo || cancel.load(Ordering::Relaxed) {
+            return;
+        }
+        if let Err(e) = vm::restore_snapshot(&disk, &snapshot) {
+            let _ = tx.send(BackgroundResult::SlideshowDemoFailed { vm_name: vm.display_name(), error: format!("{:#}", e) });
+            return;
+        }
+        if cancel.load(Ordering::Relaxed) {
+            return;
+        }
+        let result = vm::launch_vm_with_error_check(&vm, &vm::LaunchOptions::default());
+        // The slide moved on while the demo was starting
+        if cancel.load(Ordering::Relaxed) {
+            end_demo(&vm.path);
+        } else if let Some(error) = result.error {
+            let _ = tx.send(BackgroundResult::SlideshowDemoFailed { vm_name: vm.display_name(), error });
+        }
+    });
+}
+
+/// Stop a demo VM: QMP `quit` ends QEMU itself; without a QMP socket the
+/// launch script's processes (QEMU is a child of bash) and the script are
+/// killed. The disk goes back to the demo snapshot next time, so nothing is lost.
+fn end_demo(vm_dir: &Path) {
+    if vm::qmp::quit(vm_dir).is_ok() {
+        return;
+    }
+    let instance = vm::instance_lock::find_instance(vm_dir).filter(|instance| instance.host.is_none());
+    if let Some(pid) = instance.and_then(|instance| instance.pid) {
+        let _ = Command::new("pkill").args(["-KILL", "-P", &pid.to_string()]).status();
+        let _ = vm::force_stop_vm(pid);
+    }
+}
+
+/// Stop the VM launched for the current slide
+fn stop_demo(app: &mut App) {
+    let Some(state) = app.slideshow_state.as_mut() else {
+        return;
+    };
+    if let Some(cancel) = state.demo_cancel.take() {
+        cancel.store(true, Ordering::Relaxed);
+    }
+    let Some(vm_id) = state.demo_vm.take() else {
+        return;
+    };
+    if let Some(vm) = app.vms.iter().find(|vm| vm.id == vm_id) {
+        end_demo(&vm.path);
+    }
+}
+
+/// Leave the slideshow with the last VM shown selected
+fn close(app: &mut App) {
+    stop_demo(app);
+    if let Some(state) = app.slideshow_state.take() {
+        let vm_idx = state.slides.get(state.current).copied();
+        if let Some(pos) = app
+            .visual_order
+            .iter()
+            .position(|filtered_idx| app.filtered_indices.get(*filtered_idx).copied() == vm_idx)
+        {
+            app.selected_vm = pos;
+            app.info_scroll = 0;
+        }
+    }
+    app.pop_screen();
+}
+
+/// Render the current slide over the whole terminal
+pub fn render(app: &App, frame: &mut Frame) {
+    let Some(ref state) = app.slideshow_state else {
+        return;
+    };
+    let Some(vm) = current_vm(app) else {
+        return;
+    };
+    let area = frame.area();
+    frame.render_widget(Clear, area);
+
+    let info = app
+        .metadata
+        .get(&vm.id)
+        .cloned()
+        .unwrap_or_else(|| crate::metadata::default_os_info(&vm.id));
+
+    let block = Block::default()
+        .title(format!(" {} ", vm.display_name()))
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(4),    // Art and text
+            Constraint::Length(1), // Footer
+        ])
+        .split(inner.inner(Margin::new(2, 1)));
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(rows[0]);
+
+    // ASCII art, centered in its half
+    let art = app.vm_ascii(vm).trim_start_matches('\n');
+    let art_height = art.lines().count() as u16;
+    let art_area = Rect {
+        y: columns[0].y + columns[0].height.saturating_sub(art_height) / 2,
+        height: art_height.min(columns[0].height),
+        ..columns[0]
+    };
+    let art = Paragraph::new(art)
+        .style(Style::default().fg(Color::Cyan))
+        .alignment(Alignment::Center);
+    frame.render_widget(art, art_area);
+
+    // Name, history and one fun fact per slide
+    let mut lines = vec![
+        Line::styled(
+            info.display_name.clone().unwrap_or_else(|| info.name.clone()),
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+        ),
+        Line::styled(
+            [info.publisher.as_str(), info.release_date.as_str(), info.architecture.as_str()]
+                .iter()
+                .filter(|s| !s.is_empty())
+                .copied()
+                .collect::<Vec<_>>()
+                .join("  ·  "),
+            Style::default().fg(Color::DarkGray),
+        ),
+        Line::from(""),
+    ];
+    let blurb = if info.blurb.long.is_empty() { &info.blurb.short } else { &info.blurb.long };
+    lines.extend(blurb.lines().map(|line| Line::from(line.to_string())));
+    if !info.fun_facts.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::styled("Did you know?", Style::default().fg(Color::Yellow)));
+        lines.push(Line::from(info.fun_facts[state.shown % info.fun_facts.len()].clone()));
+    }
+    let text = Paragraph::new(lines).wrap(Wrap { trim: true });
+    frame.render_widget(text, columns[1].inner(Margin::new(2, 0)));
+
+    // Position, demo state or a status message, and the keys
+    let demo_running = state.demo_vm.as_ref().is_some_and(|id| app.running_vms.contains_key(id));
+    let mut footer = format!("{} / {}", state.current + 1, state.slides.len());
+    match app.status_message {
+        Some(ref message) => footer.push_str(&format!("  ·  {}", message)),
+        None if demo_running => footer.push_str("  ·  Demo running"),
+        None => {}
+    }
+    footer.push_str("  ·  [Left/Right] Browse  [any other key] Exit");
+    let footer = Paragraph::new(footer)
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(footer, rows[1]);
+}
+
+/// Handle key input during the slideshow: arrows browse, anything else exits
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    match key.code {
+        KeyCode::Right | KeyCode::Char('l') => show(app, 1),
+        KeyCode::Left | KeyCode::Char('h') => show(app, -1),
+        _ => close(app),
+    }
+    Ok(())
+}
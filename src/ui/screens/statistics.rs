@@ -0,0 +1,155 @@
+//! Usage Statistics Screen
+//!
+//! Ranks the VMs in the collection by the time spent in them, with their
+//! launch counts and when each was last launched. Enter selects the VM in
+//! the list.
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    prelude::*,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+use crate::app::{App, Screen, StatisticsState};
+use crate::vm::launch_history::{format_ago, format_duration};
+
+/// Open the statistics for the VMs that have been launched
+pub fn open(app: &mut App) {
+    let mut ranking: Vec<(&String, i64, usize)> = app
+        .launch_history
+        .iter()
+        .filter(|(_, history)| history.launches() > 0)
+        .map(|(id, history)| (id, history.total_secs(), history.launches()))
+        .collect();
+    if ranking.is_empty() {
+        app.set_status("No launches recorded yet");
+        return;
+    }
+    ranking.sort_by(|a, b| b.1.cmp(&a.1).then(b.2.cmp(&a.2)).then(a.0.cmp(b.0)));
+    app.statistics_state = Some(StatisticsState {
+        ranking: ranking.into_iter().map(|(id, _, _)| id.clone()).collect(),
+        selected: 0,
+    });
+    app.push_screen(Screen::Statistics);
+}
+
+/// Render the statistics dialog
+pub fn render(app: &App, frame: &mut Frame) {
+    let Some(ref state) = app.statistics_state else {
+        return;
+    };
+    let area = frame.area();
+
+    let dialog_width = 84.min(area.width.saturating_sub(4));
+    let dialog_height = 24.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    // Totals across the collection
+    let (total_secs, launches) = app
+        .launch_history
+        .values()
+        .fold((0, 0), |(secs, n), h| (secs + h.total_secs(), n + h.launches()));
+    let block = Block::default()
+        .title(format!(" Usage: {} over {} launches ", format_duration(total_secs), launches))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // Column headings
+            Constraint::Min(3),    // Ranking
+            Constraint::Length(1), // Help text
+        ])
+        .split(inner.inner(Margin::new(1, 0)));
+
+    let name_width = (chunks[1].width as usize).saturating_sub(44).max(10);
+    let heading = format!("  {:>3}  {:name_width$}  {:>9}  {:>8}  {}", "#", "VM", "Time", "Launches", "Last launched");
+    frame.render_widget(
+        Paragraph::new(heading).style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+        chunks[0],
+    );
+
+    let now = chrono::Utc::now().timestamp();
+    let height = chunks[1].height as usize;
+    let first = state.selected.saturating_sub(height.saturating_sub(1));
+    let lines: Vec<Line> = state
+        .ranking
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(height)
+        .map(|(i, id)| {
+            let history = app.launch_history.get(id).cloned().unwrap_or_default();
+            let name = app
+                .vms
+                .iter()
+                .find(|vm| vm.id == *id)
+                .map(|vm| vm.display_name())
+                .unwrap_or_else(|| id.clone());
+            let name: String = name.chars().take(name_width).collect();
+            let last = history.last_launched().map(|at| format_ago(at, now)).unwrap_or_default();
+            let text = format!(
+                "{}{:>3}  {:name_width$}  {:>9}  {:>8}  {}",
+                if i == state.selected { "> " } else { "  " },
+                i + 1,
+                name,
+                format_duration(history.total_secs()),
+                history.launches(),
+                last
+            );
+            if i == state.selected {
+                Line::styled(text, Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+            } else {
+                Line::from(text)
+            }
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), chunks[1]);
+
+    let help = Paragraph::new("[j/k] Move  [Enter] Go to the VM  [Esc] Close")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, chunks[2]);
+}
+
+/// Handle key input for the statistics screen
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(ref mut state) = app.statistics_state else {
+        app.pop_screen();
+        return Ok(());
+    };
+    match key.code {
+        KeyCode::Esc => {
+            app.statistics_state = None;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down if state.selected + 1 < state.ranking.len() => state.selected += 1,
+        KeyCode::Char('k') | KeyCode::Up => state.selected = state.selected.saturating_sub(1),
+        KeyCode::Enter => {
+            let vm_id = state.ranking.get(state.selected).cloned();
+            app.statistics_state = None;
+            app.pop_screen();
+            if let Some(vm_id) = vm_id {
+                app.select_vm_by_id(&vm_id);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    Rect::new(x, y, width, height)
+}
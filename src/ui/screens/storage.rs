@@ -0,0 +1,198 @@
+//! Storage Usage Screen
+//!
+//! Disk usage of every local VM: virtual and actual disk sizes, snapshot
+//! state, ISOs and the folder's total, with the library's footprint in the
+//! title. qcow2 disks holding much more than their data are flagged with
+//! what compacting them would reclaim. `s` changes the sort column; `r`
+//! compacts the selected VM's disk in the background.
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    prelude::*,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+use std::thread;
+
+use crate::app::{App, BackgroundResult, Screen, StorageState};
+use crate::format::format_size;
+use crate::vm::storage::{self, StorageSort};
+
+/// Measure the library's VMs in the background and show the dashboard
+pub fn open(app: &mut App) {
+    let vms: Vec<_> = app.vms.iter().filter(|vm| vm.remote.is_none()).cloned().collect();
+    if vms.is_empty() {
+        app.set_status("No local VMs to measure");
+        return;
+    }
+    app.storage_state = Some(StorageState {
+        rows: Vec::new(),
+        sort: StorageSort::default(),
+        selected: 0,
+    });
+    app.push_screen(Screen::Storage);
+    app.loading = true;
+    app.set_status(format!("Measuring {} VMs...", vms.len()));
+
+    let tx = app.background_tx.clone();
+    thread::spawn(move || {
+        let rows = vms.iter().map(storage::measure).collect();
+        let _ = tx.send(BackgroundResult::StorageMeasured { rows });
+    });
+}
+
+/// Render the storage dialog
+pub fn render(app: &App, frame: &mut Frame) {
+    let Some(ref state) = app.storage_state else {
+        return;
+    };
+    let area = frame.area();
+
+    let dialog_width = 108.min(area.width.saturating_sub(4));
+    let dialog_height = 26.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let total: u64 = state.rows.iter().map(|r| r.total_size).sum();
+    let title = if state.rows.is_empty() {
+        " Storage: measuring... ".to_string()
+    } else {
+        format!(" Storage: {} in {} VMs (by {}) ", format_size(total), state.rows.len(), state.sort.label())
+    };
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // Column headings
+            Constraint::Min(3),    // VMs
+            Constraint::Length(1), // Library totals
+            Constraint::Length(1), // Help text
+        ])
+        .split(inner.inner(Margin::new(1, 0)));
+
+    let name_width = (chunks[1].width as usize).saturating_sub(76).max(10);
+    let heading = format!(
+        "  {:name_width$}  {:>10}  {:>10}  {:>14}  {:>10}  {:>10}  {:>10}",
+        "VM", "Virtual", "Actual", "Snapshots", "ISOs", "Total", "Reclaim"
+    );
+    frame.render_widget(
+        Paragraph::new(heading).style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+        chunks[0],
+    );
+
+    let height = chunks[1].height as usize;
+    let first = state.selected.saturating_sub(height.saturating_sub(1));
+    let lines: Vec<Line> = state
+        .rows
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(height)
+        .map(|(i, row)| {
+            let name: String = row.vm_name.chars().take(name_width).collect();
+            let snapshots = if row.snapshots > 0 {
+                format!("{} ({})", format_size(row.snapshot_size), row.snapshots)
+            } else {
+                "-".to_string()
+            };
+            let text = format!(
+                "{}{:name_width$}  {:>10}  {:>10}  {:>14}  {:>10}  {:>10}  {:>10}",
+                if i == state.selected { "> " } else { "  " },
+                name,
+                format_size(row.virtual_size),
+                format_size(row.actual_size),
+                snapshots,
+                if row.iso_size > 0 { format_size(row.iso_size) } else { "-".to_string() },
+                format_size(row.total_size),
+                row.reclaimable.map(|bytes| format!("~{}", format_size(bytes))).unwrap_or_default()
+            );
+            match (i == state.selected, row.reclaimable.is_some()) {
+                (true, _) => Line::styled(text, Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+                (false, true) => Line::styled(text, Style::default().fg(Color::Magenta)),
+                (false, false) => Line::from(text),
+            }
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), chunks[1]);
+
+    let sum = |f: fn(&storage::VmStorage) -> u64| state.rows.iter().map(f).sum::<u64>();
+    let totals = format!(
+        "Disks: {} actual of {} virtual ({} not yet allocated)  Snapshots: {}  ISOs: {}  Reclaimable: ~{}",
+        format_size(sum(|r| r.actual_size)),
+        format_size(sum(|r| r.virtual_size)),
+        format_size(sum(|r| r.sparse_savings())),
+        format_size(sum(|r| r.snapshot_size)),
+        format_size(sum(|r| r.iso_size)),
+        format_size(sum(|r| r.reclaimable.unwrap_or(0)))
+    );
+    frame.render_widget(Paragraph::new(totals).style(Style::default().fg(Color::Gray)), chunks[2]);
+
+    let help = Paragraph::new("[j/k] Move  [s] Sort  [r] Compact disk  [Enter] Go to the VM  [Esc] Close")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, chunks[3]);
+}
+
+/// Handle key input for the storage screen
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(ref mut state) = app.storage_state else {
+        app.pop_screen();
+        return Ok(());
+    };
+    match key.code {
+        KeyCode::Esc => {
+            app.storage_state = None;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down if state.selected + 1 < state.rows.len() => state.selected += 1,
+        KeyCode::Char('k') | KeyCode::Up => state.selected = state.selected.saturating_sub(1),
+        KeyCode::Char('s') => {
+            state.sort = state.sort.next();
+            storage::sort(&mut state.rows, state.sort);
+            state.selected = 0;
+        }
+        KeyCode::Enter => {
+            let vm_id = state.rows.get(state.selected).map(|r| r.vm_id.clone());
+            app.storage_state = None;
+            app.pop_screen();
+            if let Some(vm_id) = vm_id {
+                app.select_vm_by_id(&vm_id);
+            }
+        }
+        KeyCode::Char('r') => {
+            let Some(vm_id) = state.rows.get(state.selected).map(|r| r.vm_id.clone()) else {
+                return Ok(());
+            };
+            if !app.select_vm_by_id(&vm_id) {
+                return Ok(());
+            }
+            match app.selected_vm() {
+                Some(vm) if !vm.config.supports_snapshots() => {
+                    let format = vm.config.primary_disk().map(|d| d.format.label().to_string()).unwrap_or_default();
+                    app.set_status(format!("Only qcow2 disks can be compacted, not {}", format));
+                }
+                Some(_) => app.start_compaction(),
+                None => {}
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    Rect::new(x, y, width, height)
+}
@@ -0,0 +1,239 @@
+//! Autostart Screen
+//!
+//! Installs, changes or removes the selected VM's systemd user service,
+//! and starts or stops it. QEMU VMs without a QMP socket are offered one,
+//! so a VM started by systemd can still be powered down and have USB
+//! devices attached from the TUI.
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    prelude::*,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+};
+use std::time::Instant;
+
+use crate::app::{App, Screen, SystemdUnitState};
+use crate::vm::backend::Backend;
+use crate::vm::qmp;
+use crate::vm::systemd::{self, StartAt};
+
+/// Read what is installed for the selected VM and show the dialog
+pub fn open(app: &mut App) {
+    let Some(vm) = app.selected_vm() else {
+        return;
+    };
+    let status = systemd::status(&vm.id);
+    let offer_qmp = vm.backend == Backend::Qemu && !qmp::is_configured(&vm.config.raw_script);
+    app.systemd_unit_state = Some(SystemdUnitState {
+        status,
+        start_at: if status.installed { status.start_at } else { StartAt::Login },
+        add_qmp: offer_qmp.then_some(true),
+        selected: 0,
+    });
+    app.push_screen(Screen::SystemdUnit);
+}
+
+fn row_count(state: &SystemdUnitState) -> usize {
+    StartAt::ALL.len() + usize::from(state.add_qmp.is_some())
+}
+
+/// Render the autostart dialog
+pub fn render(app: &App, frame: &mut Frame) {
+    let Some(ref state) = app.systemd_unit_state else {
+        return;
+    };
+    let Some(vm) = app.selected_vm() else {
+        return;
+    };
+    let area = frame.area();
+
+    let dialog_width = 70.min(area.width.saturating_sub(4));
+    let dialog_height = 16.min(area.height.saturating_sub(4));
+
+    let dialog_area = centered_rect(dialog_width, dialog_height, area);
+    frame.render_widget(Clear, dialog_area);
+
+    let block = Block::default()
+        .title(" Autostart (systemd) ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+
+    let inner = block.inner(dialog_area);
+    frame.render_widget(block, dialog_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3), // Service and status
+            Constraint::Min(4),    // Options
+            Constraint::Length(1), // Help text
+        ])
+        .split(inner.inner(Margin::new(1, 0)));
+
+    let label = Style::default().fg(Color::Yellow);
+    let status = match (state.status.installed, state.status.active) {
+        (false, _) => Span::styled("not installed", Style::default().fg(Color::DarkGray)),
+        (true, true) => Span::styled("installed, running", Style::default().fg(Color::Green)),
+        (true, false) => Span::raw("installed, stopped"),
+    };
+    let started = if state.status.installed {
+        match state.status.start_at {
+            StartAt::Manual => "manually",
+            StartAt::Login => "at login",
+            StartAt::Boot => "at boot",
+        }
+    } else {
+        "-"
+    };
+    let header = vec![
+        Line::from(vec![Span::styled("Service: ", label), Span::raw(systemd::unit_name(&vm.id))]),
+        Line::from(vec![Span::styled("Status:  ", label), status]),
+        Line::from(vec![Span::styled("Starts:  ", label), Span::raw(started)]),
+    ];
+    frame.render_widget(Paragraph::new(header), chunks[0]);
+
+    let mut items: Vec<ListItem> = StartAt::ALL
+        .iter()
+        .map(|start_at| {
+            let mark = if *start_at == state.start_at { "(*)" } else { "( )" };
+            ListItem::new(format!("{} {}", mark, start_at.label()))
+        })
+        .collect();
+    if let Some(add_qmp) = state.add_qmp {
+        let mark = if add_qmp { "[x]" } else { "[ ]" };
+        items.push(ListItem::new(format!("{} Add a QMP socket (power down and USB from the TUI)", mark)));
+    }
+    let mut list_state = ListState::default();
+    list_state.select(Some(state.selected));
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::TOP))
+        .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+        .highlight_symbol("> ");
+    frame.render_stateful_widget(list, chunks[1], &mut list_state);
+
+    let help = if state.status.installed {
+        "[Space] Choose  [Enter] Update  [s] Start/stop  [d] Remove  [Esc] Close"
+    } else {
+        "[Space] Choose  [Enter] Install  [Esc] Close"
+    };
+    let help = Paragraph::new(help)
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    frame.render_widget(help, chunks[2]);
+}
+
+/// Handle key input for the autostart dialog
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
+    let Some(ref mut state) = app.systemd_unit_state else {
+        app.pop_screen();
+        return Ok(());
+    };
+
+    match key.code {
+        KeyCode::Esc => {
+            app.systemd_unit_state = None;
+            app.pop_screen();
+        }
+        KeyCode::Char('j') | KeyCode::Down if state.selected + 1 < row_count(state) => {
+            state.selected += 1;
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            state.selected = state.selected.saturating_sub(1);
+        }
+        KeyCode::Char(' ') => match StartAt::ALL.get(state.selected) {
+            Some(start_at) => state.start_at = *start_at,
+            None => state.add_qmp = state.add_qmp.map(|add| !add),
+        },
+        KeyCode::Enter => install(app),
+        KeyCode::Char('s') if state.status.installed => start_or_stop(app),
+        KeyCode::Char('d') if state.status.installed => remove(app),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn install(app: &mut App) {
+    let Some(vm) = app.selected_vm().cloned() else {
+        return;
+    };
+    let Some(ref state) = app.systemd_unit_state else {
+        return;
+    };
+    let start_at = state.start_at;
+
+    if state.add_qmp == Some(true) {
+        let result = std::fs::read_to_string(&vm.launch_script)
+            .and_then(|content| std::fs::write(&vm.launch_script, qmp::add_qmp_section(&content)));
+        if let Err(e) = result {
+            app.set_status(format!("Error: Failed to add the QMP socket: {}", e));
+            return;
+        }
+        let _ = app.refresh_vms();
+    }
+
+    match systemd::install(&vm, start_at) {
+        Ok(warning) => {
+            app.systemd_unit_state = None;
+            app.pop_screen();
+            match warning {
+                Some(warning) => app.set_status(format!("Service installed. {}", warning)),
+                None => app.set_status(format!("Installed {}", systemd::unit_name(&vm.id))),
+            }
+        }
+        Err(e) => app.set_status(format!("Error: {:#}", e)),
+    }
+}
+
+fn start_or_stop(app: &mut App) {
+    let Some(vm) = app.selected_vm().cloned() else {
+        return;
+    };
+    let Some(active) = app.systemd_unit_state.as_ref().map(|s| s.status.active) else {
+        return;
+    };
+
+    if active {
+        match systemd::stop(&vm.id) {
+            Ok(()) => {
+                app.stopping_vms.insert(vm.id.clone(), Instant::now());
+                app.set_status(format!("Stopping {}...", vm.display_name()));
+            }
+            Err(e) => app.set_status(format!("Error: {:#}", e)),
+        }
+    } else if app.running_vms.contains_key(&vm.id) {
+        app.set_status(format!("{} is already running outside its service", vm.display_name()));
+        return;
+    } else {
+        match systemd::start(&vm.id) {
+            Ok(()) => app.set_status(format!("Started {}", systemd::unit_name(&vm.id))),
+            Err(e) => app.set_status(format!("Error: {:#}", e)),
+        }
+    }
+    if let Some(ref mut state) = app.systemd_unit_state {
+        state.status = systemd::status(&vm.id);
+    }
+}
+
+fn remove(app: &mut App) {
+    let Some(vm) = app.selected_vm().cloned() else {
+        return;
+    };
+    match systemd::remove(&vm.id) {
+        Ok(()) => {
+            app.systemd_unit_state = None;
+            app.pop_screen();
+            app.set_status(format!("Removed {}", systemd::unit_name(&vm.id)));
+        }
+        Err(e) => app.set_status(format!("Error: {:#}", e)),
+    }
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    Rect::new(x, y, width, height)
+}
@@ -4,12 +4,33 @@ use ratatui::{
 };
 
 use crate::metadata::OsInfo;
+use crate::vm::guest_agent::GuestInfo;
+use crate::vm::launch_history::{self, LaunchHistory};
+use crate::vm::notes::Notes;
+use crate::vm::screenshots::Image;
+
+/// Most rows a boot screenshot takes in the info panel
+const SCREENSHOT_ROWS: u16 = 16;
 
 /// ASCII art and info display widget with scrolling support
 pub struct AsciiInfoWidget<'a> {
     pub ascii_art: &'a str,
+    /// Latest boot screenshot, shown instead of the ASCII art
+    pub screenshot: Option<&'a Image>,
     pub os_info: Option<&'a OsInfo>,
     pub vm_name: &'a str,
+    /// Frozen date when the VM is in archival mode
+    pub archival_date: Option<&'a str>,
+    /// Live guest agent report when the VM is running
+    pub guest_info: Option<&'a GuestInfo>,
+    /// VNC server address for VMs on the VNC display
+    pub vnc_address: Option<&'a str>,
+    /// The VM boots its install ISO until the OS is installed
+    pub install_pending: bool,
+    /// Size of the saved state when the VM is suspended
+    pub suspended: Option<u64>,
+    /// Launches of the VM, if it has been launched
+    pub launch_history: Option<&'a LaunchHistory>,
     pub scroll: u16,
 }
 
@@ -36,9 +57,101 @@ impl<'a> AsciiInfoWidget<'a> {
         // Build the full content as a single scrollable text
         let mut lines: Vec<Line> = Vec::new();
 
-        // ASCII art - preserve exact spacing (no trimming)
-        for line in self.ascii_art.trim_start_matches('\n').lines() {
-            lines.push(Line::styled(line, Style::default().fg(Color::Green)));
+        // Archival banner - make the preserved state impossible to miss
+        if let Some(date) = self.archival_date {
+            lines.push(Line::from(Span::styled(
+                format!(" ARCHIVED - frozen at {} | snapshot-only | network off ", date),
+                Style::default().fg(Color::Black).bg(Color::Yellow).add_modifier(Modifier::BOLD),
+            )));
+            lines.push(Line::from(""));
+        }
+
+        if self.install_pending {
+            lines.push(Line::from(vec![
+                Span::styled("Installing: ", Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    "boots the install ISO until installed (Boot Options to finish)",
+                    Style::default().fg(Color::White),
+                ),
+            ]));
+            lines.push(Line::from(""));
+        }
+
+        if let Some(size) = self.suspended {
+            lines.push(Line::from(vec![
+                Span::styled("Suspended: ", Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    format!("{} saved; launching resumes it", crate::format::format_size(size)),
+                    Style::default().fg(Color::White),
+                ),
+            ]));
+            lines.push(Line::from(""));
+        }
+
+        // Where to point a VNC client
+        if let Some(addr) = self.vnc_address {
+            lines.push(Line::from(vec![
+                Span::styled("VNC: ", Style::default().fg(Color::Yellow)),
+                Span::styled(addr, Style::default().fg(Color::White)),
+            ]));
+            lines.push(Line::from(""));
+        }
+
+        // When and how much the VM has been used
+        if let Some((history, last)) = self.launch_history.and_then(|h| Some((h, h.last_launched()?))) {
+            let now = chrono::Utc::now().timestamp();
+            lines.push(Line::from(vec![
+                Span::styled("Last launched: ", Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    format!("{} ({})", launch_history::format_time(last), launch_history::format_ago(last, now)),
+                    Style::default().fg(Color::White),
+                ),
+            ]));
+            let launches = match history.launches() {
+                1 => "1 launch".to_string(),
+                n => format!("{} launches", n),
+            };
+            lines.push(Line::from(vec![
+                Span::styled("Usage: ", Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    format!("{} over {}", launch_history::format_duration(history.total_secs()), launches),
+                    Style::default().fg(Color::White),
+                ),
+            ]));
+            lines.push(Line::from(""));
+        }
+
+        // Live guest details from the guest agent
+        if let Some(guest) = self.guest_info {
+            let label = Style::default().fg(Color::Yellow);
+            if let Some(ref os_name) = guest.os_name {
+                lines.push(Line::from(vec![
+                    Span::styled("Guest OS: ", label),
+                    Span::styled(os_name.as_str(), Style::default().fg(Color::White)),
+                ]));
+            }
+            if guest.ip_addresses.is_empty() {
+                lines.push(Line::from(vec![
+                    Span::styled("Guest IP: ", label),
+                    Span::styled("none reported", Style::default().fg(Color::DarkGray)),
+                ]));
+            }
+            for addr in &guest.ip_addresses {
+                lines.push(Line::from(vec![
+                    Span::styled("Guest IP: ", label),
+                    Span::styled(addr.as_str(), Style::default().fg(Color::White)),
+                ]));
+            }
+            lines.push(Line::from(""));
+        }
+
+        if let Some(image) = self.screenshot {
+            lines.extend(super::thumbnail::image_lines(image, padded.width, SCREENSHOT_ROWS));
+        } else {
+            // ASCII art - preserve exact spacing (no trimming)
+            for line in self.ascii_art.trim_start_matches('\n').lines() {
+                lines.push(Line::styled(line, Style::default().fg(Color::Green)));
+            }
         }
         lines.push(Line::from(""));
 
@@ -106,20 +219,37 @@ impl<'a> AsciiInfoWidget<'a> {
 pub struct DetailedInfoWidget<'a> {
     pub os_info: Option<&'a OsInfo>,
     pub vm_name: &'a str,
+    /// The VM's notes and journal (None for remote VMs)
+    pub notes: Option<&'a Notes>,
+    pub scroll: u16,
 }
 
 impl<'a> DetailedInfoWidget<'a> {
     pub fn render(self, area: Rect, buf: &mut Buffer) {
+        let help = if self.notes.is_some() {
+            " [j/k] Scroll  [n] Edit notes  [a] Add journal entry  [Esc] Back "
+        } else {
+            " [j/k] Scroll  [Esc] Back "
+        };
         let block = Block::default()
             .title(format!(" {} - Details ", self.vm_name))
+            .title_bottom(help)
             .borders(Borders::ALL)
             .border_style(Style::default().fg(Color::Cyan));
 
         let inner = block.inner(area);
         block.render(area, buf);
 
+        let heading = |title: &'a str| {
+            Line::from(Span::styled(
+                title,
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            ))
+        };
+
+        let mut text = Vec::new();
         if let Some(info) = self.os_info {
-            let mut text = vec![
+            text.extend([
                 Line::from(vec![
                     Span::styled("Name: ", Style::default().fg(Color::Yellow)),
                     Span::raw(&info.name),
@@ -137,14 +267,11 @@ impl<'a> DetailedInfoWidget<'a> {
                     Span::raw(&info.architecture),
                 ]),
                 Line::from(""),
-            ];
+            ]);
 
             // Add long description
             if !info.blurb.long.is_empty() {
-                text.push(Line::from(Span::styled(
-                    "About",
-                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
-                )));
+                text.push(heading("About"));
                 for line in info.blurb.long.lines() {
                     text.push(Line::from(line.to_string()));
                 }
@@ -153,22 +280,47 @@ impl<'a> DetailedInfoWidget<'a> {
 
             // Add fun facts
             if !info.fun_facts.is_empty() {
-                text.push(Line::from(Span::styled(
-                    "Fun Facts",
-                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
-                )));
+                text.push(heading("Fun Facts"));
                 for fact in &info.fun_facts {
                     text.push(Line::from(format!("• {}", fact)));
                 }
+                text.push(Line::from(""));
             }
-
-            let para = Paragraph::new(text)
-                .wrap(Wrap { trim: true });
-            para.render(inner, buf);
         } else {
-            let text = Paragraph::new("No detailed information available for this VM.")
-                .style(Style::default().fg(Color::Gray));
-            text.render(inner, buf);
+            text.push(Line::styled(
+                "No detailed information available for this VM.",
+                Style::default().fg(Color::Gray),
+            ));
+            text.push(Line::from(""));
+        }
+
+        if let Some(notes) = self.notes {
+            text.push(heading("Notes"));
+            if notes.text.trim().is_empty() {
+                text.push(Line::styled("No notes yet (press n to write some)", Style::default().fg(Color::DarkGray)));
+            } else {
+                for line in notes.text.lines() {
+                    text.push(Line::from(line.to_string()));
+                }
+            }
+            text.push(Line::from(""));
+
+            // Newest first
+            text.push(heading("Journal"));
+            if notes.journal.is_empty() {
+                text.push(Line::styled("No entries yet (press a to add one)", Style::default().fg(Color::DarkGray)));
+            }
+            for entry in notes.journal.iter().rev() {
+                text.push(Line::from(vec![
+                    Span::styled(format!("{}  ", entry.at.format("%Y-%m-%d %H:%M")), Style::default().fg(Color::DarkGray)),
+                    Span::raw(&entry.text),
+                ]));
+            }
         }
+
+        let para = Paragraph::new(text)
+            .wrap(Wrap { trim: true })
+            .scroll((self.scroll, 0));
+        para.render(inner, buf);
     }
 }
@@ -0,0 +1,59 @@
+//! qcow2 tuning rows, shared by the dialogs that create or rewrite images
+
+use crate::commands::qemu_img::{Compat, Compression, Preallocation, Qcow2Options, CLUSTER_SIZES_KIB};
+
+/// Labels of the rows, in the order shown
+pub const LABELS: [&str; 4] = ["Clusters", "Preallocate", "Compression", "Compat"];
+
+/// Shown value of each row for an image of `format`
+pub fn values(options: &Qcow2Options, format: &str) -> [String; 4] {
+    let qcow2 = format == "qcow2";
+    let choice = |applies: bool, value: String| if applies { format!("< {} >", value) } else { "n/a".to_string() };
+    let clusters = if options.cluster_kib >= 1024 {
+        format!("{} MiB", options.cluster_kib / 1024)
+    } else {
+        format!("{} KiB", options.cluster_kib)
+    };
+    [
+        choice(qcow2, clusters),
+        choice(qcow2 || format == "raw", options.preallocation.name().to_string()),
+        choice(qcow2, options.compression.name().to_string()),
+        choice(qcow2, options.compat.name().to_string()),
+    ]
+}
+
+/// What a row is for, shown while it has focus
+pub fn hint(row: usize) -> &'static str {
+    match row {
+        0 => "Large clusters suit big modern guests; small ones waste less on tiny disks",
+        1 => "metadata: faster first writes, still sparse; falloc/full: reserve the whole size",
+        2 => "For compressed clusters; zstd is faster but needs QEMU 5.1 or later",
+        _ => "0.10 is only for QEMU older than 1.1, and can't use zstd",
+    }
+}
+
+/// Step a row to its next (or with `back`, previous) choice
+pub fn change(options: &mut Qcow2Options, format: &str, row: usize, back: bool) {
+    match (format, row) {
+        ("qcow2", 0) => options.cluster_kib = step(CLUSTER_SIZES_KIB, options.cluster_kib, back),
+        ("qcow2", 1) => options.preallocation = step(Preallocation::ALL, options.preallocation, back),
+        // Raw images are sparse already, so metadata preallocation means nothing
+        ("raw", 1) => {
+            let choices = [Preallocation::Off, Preallocation::Falloc, Preallocation::Full];
+            options.preallocation = step(&choices, options.preallocation, back);
+        }
+        ("qcow2", 2) => options.compression = step(Compression::ALL, options.compression, back),
+        ("qcow2", 3) => options.compat = step(Compat::ALL, options.compat, back),
+        _ => {}
+    }
+}
+
+fn step<T: Copy + PartialEq>(choices: &[T], current: T, back: bool) -> T {
+    let position = choices.iter().position(|c| *c == current).unwrap_or(0);
+    let next = if back {
+        (position + choices.len() - 1) % choices.len()
+    } else {
+        (position + 1) % choices.len()
+    };
+    choices[next]
+}
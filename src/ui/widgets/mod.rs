@@ -1,5 +1,7 @@
 pub mod ascii_display;
 pub mod dialog;
+pub mod disk_tuning;
+pub mod thumbnail;
 pub mod vm_list;
 
 pub use ascii_display::{AsciiInfoWidget, DetailedInfoWidget};
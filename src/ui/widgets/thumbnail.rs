@@ -0,0 +1,39 @@
+use ratatui::prelude::*;
+
+use crate::vm::screenshots::Image;
+
+/// Whether the terminal draws 24-bit colour, which thumbnails need
+pub fn truecolor() -> bool {
+    std::env::var("COLORTERM").is_ok_and(|v| v == "truecolor" || v == "24bit")
+}
+
+/// An image drawn with half blocks, at most `max_cols` by `max_rows` cells
+///
+/// Each cell shows two pixels: the upper as the foreground of `▀`, the
+/// lower as its background. A cell is about twice as tall as it is wide,
+/// so the pixels come out roughly square.
+pub fn image_lines(image: &Image, max_cols: u16, max_rows: u16) -> Vec<Line<'static>> {
+    let mut cols = (max_cols as usize).min(image.width);
+    let mut rows = (cols * image.height).div_ceil(image.width * 2);
+    if rows > max_rows as usize {
+        rows = max_rows as usize;
+        cols = (rows * 2 * image.width / image.height).max(1);
+    }
+    if cols == 0 || rows == 0 {
+        return Vec::new();
+    }
+
+    let scaled = image.scaled(cols, rows * 2);
+    (0..rows)
+        .map(|row| {
+            let spans: Vec<Span> = (0..cols)
+                .map(|x| {
+                    let (r, g, b) = scaled.pixel(x, row * 2);
+                    let (br, bg, bb) = scaled.pixel(x, row * 2 + 1);
+                    Span::styled("▀", Style::default().fg(Color::Rgb(r, g, b)).bg(Color::Rgb(br, bg, bb)))
+                })
+                .collect();
+            Line::from(spans)
+        })
+        .collect()
+}
@@ -0,0 +1,143 @@
+//! Archival ("freeze date") mode
+//!
+//! Locks a VM into a preserved state: every launch runs with `-snapshot` so
+//! nothing is written back to the disk, networking is removed, and the guest
+//! RTC is pinned to a fixed date. The network configuration in effect when
+//! the VM was locked is kept in `archival-network.json` so unlocking can put
+//! it back exactly.
+
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
+
+use crate::vm::create::update_network_in_script;
+use crate::vm::lifecycle::{insert_args_section, remove_args_section};
+use crate::vm::qemu_config::{NetworkBackend, NetworkConfig};
+use crate::vm::DiscoveredVm;
+
+// Archival section markers
+const ARCHIVAL_MARKER_START: &str = "# >>> Archival Mode (managed by vm-curator) >>>";
+const ARCHIVAL_MARKER_END: &str = "# <<< Archival Mode <<<";
+
+/// Saved network settings, restored when archival mode is turned off
+const SAVED_NETWORK_FILE: &str = "archival-network.json";
+
+/// Check that a freeze date is a real YYYY-MM-DD date
+pub fn validate_date(date: &str) -> Result<()> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .with_context(|| format!("Invalid date '{}' (expected YYYY-MM-DD)", date))?;
+    Ok(())
+}
+
+/// Lock a VM into archival mode with its clock frozen at `date`
+pub fn enable_archival_mode(vm: &DiscoveredVm, date: &str) -> Result<()> {
+    validate_date(date)?;
+
+    // Keep the current network settings so they can be restored later
+    if let Some(ref net) = vm.config.network {
+        let saved = serde_json::to_string_pretty(net)?;
+        std::fs::write(vm.path.join(SAVED_NETWORK_FILE), saved)
+            .context("Failed to save network settings")?;
+        update_network_in_script(&vm.path, &net.model, "none", None, &[], false)?;
+    }
+
+    let content =
+        std::fs::read_to_string(&vm.launch_script).context("Failed to read launch.sh")?;
+    let content = remove_archival_section(&content);
+    let new_content =
+        insert_args_section(&content, &generate_archival_section(date), "$ARCHIVAL_ARGS");
+    std::fs::write(&vm.launch_script, new_content).context("Failed to write launch.sh")?;
+
+    Ok(())
+}
+
+/// Unlock a VM, restoring normal writes, networking, and clock
+pub fn disable_archival_mode(vm: &DiscoveredVm) -> Result<()> {
+    if parse_archival_date(&vm.config.raw_script).is_none() {
+        bail!("{} is not in archival mode", vm.display_name());
+    }
+
+    let content =
+        std::fs::read_to_string(&vm.launch_script).context("Failed to read launch.sh")?;
+    std::fs::write(&vm.launch_script, remove_archival_section(&content))
+        .context("Failed to write launch.sh")?;
+
+    let saved_path = vm.path.join(SAVED_NETWORK_FILE);
+    if let Ok(saved) = std::fs::read_to_string(&saved_path) {
+        let net: NetworkConfig =
+            serde_json::from_str(&saved).context("Failed to read saved network settings")?;
+        let (backend, bridge) = match &net.backend {
+            NetworkBackend::User => ("user", None),
+            NetworkBackend::Passt => ("passt", None),
+            NetworkBackend::Bridge(name) => ("bridge", Some(name.as_str())),
+            NetworkBackend::Tap(name) => ("tap", Some(name.as_str())),
+            NetworkBackend::None => ("none", None),
+        };
+        update_network_in_script(&vm.path, &net.model, backend, bridge, &net.port_forwards, net.smb_share)?;
+        std::fs::remove_file(&saved_path).context("Failed to remove saved network settings")?;
+    }
+
+    Ok(())
+}
+
+/// Read the freeze date from a launch script (None when not archived)
+pub fn parse_archival_date(content: &str) -> Option<String> {
+    let mut in_section = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed == ARCHIVAL_MARKER_START {
+            in_section = true;
+        } else if trimmed == ARCHIVAL_MARKER_END {
+            return None;
+        } else if in_section {
+            if let Some(value) = trimmed.strip_prefix("ARCHIVAL_DATE=") {
+                return Some(value.trim_matches('"').to_string());
+            }
+        }
+    }
+    None
+}
+
+fn generate_archival_section(date: &str) -> String {
+    let mut section = String::new();
+    section.push_str(ARCHIVAL_MARKER_START);
+    section.push('\n');
+    section.push_str(&format!("ARCHIVAL_DATE=\"{}\"\n", date));
+    // -snapshot: discard disk writes; -nic none: no default NIC;
+    // clock=vm: RTC only advances while the guest runs
+    section.push_str(
+        "ARCHIVAL_ARGS=\"-snapshot -nic none -rtc base=${ARCHIVAL_DATE}T12:00:00,clock=vm\"\n",
+    );
+    section.push_str(ARCHIVAL_MARKER_END);
+    section.push('\n');
+    section
+}
+
+fn remove_archival_section(content: &str) -> String {
+    remove_args_section(content, ARCHIVAL_MARKER_START, ARCHIVAL_MARKER_END, "$ARCHIVAL_ARGS")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archival_section_round_trip() {
+        let script = "VM_DIR=\"/vms/t\"\ncase \"$1\" in\n    \"\")\n        qemu-system-i386 \\\n            -m 64M\n        ;;\nesac\n";
+        let archived = insert_args_section(script, &generate_archival_section("1998-06-25"), "$ARCHIVAL_ARGS");
+
+        assert_eq!(parse_archival_date(&archived), Some("1998-06-25".to_string()));
+        assert!(archived.contains("-m 64M $ARCHIVAL_ARGS"));
+        assert!(archived.contains("-snapshot -nic none -rtc base=${ARCHIVAL_DATE}T12:00:00,clock=vm"));
+
+        let restored = remove_archival_section(&archived);
+        assert_eq!(parse_archival_date(&restored), None);
+        assert!(!restored.contains("ARCHIVAL"));
+    }
+
+    #[test]
+    fn test_validate_date() {
+        assert!(validate_date("1995-08-24").is_ok());
+        assert!(validate_date("1995-13-01").is_err());
+        assert!(validate_date("August 1995").is_err());
+    }
+}
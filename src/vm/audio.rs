@@ -0,0 +1,135 @@
+//! Host audio backend for existing VMs
+//!
+//! New VMs declare their audio output with `-audiodev <driver>,id=audio0`
+//! and point each sound device at it. This module switches that driver in
+//! place, and converts older scripts (`-soundhw`, or sound devices with no
+//! audiodev) to the same form, since QEMU 7.1 dropped `-soundhw` and
+//! undeclared devices fall back to whatever default QEMU was built with.
+
+use anyhow::{bail, Context, Result};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+
+use crate::vm::DiscoveredVm;
+
+/// Id used for the audiodev in generated scripts
+const AUDIODEV_ID: &str = "audio0";
+
+static RE_AUDIODEV: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"-audiodev\s+"?([^\s"\\]+)"?"#).expect("Invalid regex: RE_AUDIODEV")
+});
+
+static RE_AUDIO_SHORTHAND: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(-audio\s+(?:driver=)?)[\w-]+").expect("Invalid regex: RE_AUDIO_SHORTHAND")
+});
+
+static RE_SOUNDHW: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"-soundhw\s+([\w,-]+)").expect("Invalid regex: RE_SOUNDHW")
+});
+
+/// Sound devices that take an `audiodev=` property
+static RE_SOUND_DEVICE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"-device\s+(hda-duplex|hda-output|hda-micro|AC97|ac97|sb16|es1370|ES1370|adlib|gus|cs4231a)\b([^\s\\]*)")
+        .expect("Invalid regex: RE_SOUND_DEVICE")
+});
+
+/// Switch a VM's host audio backend (pipewire, pa, jack, alsa, none)
+pub fn set_audio_backend(vm: &DiscoveredVm, backend: &str) -> Result<()> {
+    let content =
+        std::fs::read_to_string(&vm.launch_script).context("Failed to read launch.sh")?;
+    let new_content = rewrite_audio_backend(&content, backend)?;
+    std::fs::write(&vm.launch_script, new_content).context("Failed to write launch.sh")?;
+    Ok(())
+}
+
+/// Point a launch script's audio at a new host backend
+fn rewrite_audio_backend(content: &str, backend: &str) -> Result<String> {
+    // Modern scripts: swap the driver, dropping driver-specific options
+    if RE_AUDIODEV.is_match(content) {
+        let rewritten = RE_AUDIODEV.replace_all(content, |caps: &Captures| {
+            let id = caps[1]
+                .split(',')
+                .find_map(|opt| opt.strip_prefix("id="))
+                .unwrap_or(AUDIODEV_ID);
+            format!("-audiodev {},id={}", backend, id)
+        });
+        return Ok(rewritten.into_owned());
+    }
+
+    // `-audio driver,model=...` shorthand (QEMU 7.2+)
+    if RE_AUDIO_SHORTHAND.is_match(content) {
+        let replacement = format!("${{1}}{}", backend);
+        return Ok(RE_AUDIO_SHORTHAND.replace_all(content, replacement.as_str()).into_owned());
+    }
+
+    // Legacy scripts: declare the audiodev once, before the first sound device
+    let declaration = format!("-audiodev {},id={} ", backend, AUDIODEV_ID);
+    let mut declared = false;
+    let mut declare = |arg: String| -> String {
+        if declared {
+            arg
+        } else {
+            declared = true;
+            format!("{}{}", declaration, arg)
+        }
+    };
+
+    let content = RE_SOUND_DEVICE.replace_all(content, |caps: &Captures| {
+        if caps[2].contains("audiodev=") {
+            return caps[0].to_string();
+        }
+        declare(format!("-device {}{},audiodev={}", &caps[1], &caps[2], AUDIODEV_ID))
+    });
+    let content = RE_SOUNDHW.replace_all(&content, |caps: &Captures| {
+        let args: Vec<String> = caps[1]
+            .split(',')
+            .filter_map(|card| match card {
+                "hda" => Some(format!("-device intel-hda -device hda-duplex,audiodev={}", AUDIODEV_ID)),
+                "ac97" => Some(format!("-device AC97,audiodev={}", AUDIODEV_ID)),
+                "pcspk" => Some(format!("-machine pcspk-audiodev={}", AUDIODEV_ID)),
+                "all" | "" => None,
+                other => Some(format!("-device {},audiodev={}", other, AUDIODEV_ID)),
+            })
+            .collect();
+        declare(args.join(" "))
+    });
+
+    if !declared {
+        bail!("This VM has no sound devices");
+    }
+    Ok(content.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::launch_parser::extract_audio_backend;
+
+    #[test]
+    fn test_rewrite_audio_backend() {
+        let modern = "qemu-system-x86_64 \\\n    -audiodev pa,id=audio0,server=unix:/run/pulse \\\n    -device intel-hda -device hda-duplex,audiodev=audio0\n";
+        let switched = rewrite_audio_backend(modern, "pipewire").unwrap();
+        assert!(switched.contains("-audiodev pipewire,id=audio0 \\"));
+        assert_eq!(extract_audio_backend(&switched).as_deref(), Some("pipewire"));
+
+        let shorthand = rewrite_audio_backend("qemu-system-i386 -audio pa,model=sb16\n", "alsa").unwrap();
+        assert_eq!(shorthand, "qemu-system-i386 -audio alsa,model=sb16\n");
+
+        assert!(rewrite_audio_backend("qemu-system-x86_64 -m 512M\n", "pa").is_err());
+    }
+
+    #[test]
+    fn test_rewrite_audio_backend_converts_legacy() {
+        let soundhw = rewrite_audio_backend("qemu-system-i386 -soundhw sb16,pcspk -m 64M\n", "pa").unwrap();
+        assert_eq!(
+            soundhw,
+            "qemu-system-i386 -audiodev pa,id=audio0 -device sb16,audiodev=audio0 -machine pcspk-audiodev=audio0 -m 64M\n"
+        );
+
+        let bare = rewrite_audio_backend("qemu-system-i386 -device AC97 -device usb-tablet\n", "alsa").unwrap();
+        assert_eq!(
+            bare,
+            "qemu-system-i386 -audiodev alsa,id=audio0 -device AC97,audiodev=audio0 -device usb-tablet\n"
+        );
+    }
+}
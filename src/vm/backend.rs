@@ -0,0 +1,434 @@
+//! Alternative emulator backends
+//!
+//! Most VMs run under QEMU, but some eras are better served elsewhere:
+//! 86Box emulates period motherboards, chipsets and sound cards cycle by
+//! cycle (Windows 3.x, early 9x), and DOSBox-X runs DOS games with far less
+//! setup. A backend VM lives in the library like any other: a folder with a
+//! launch.sh that takes the same `--install`/`--cdrom` options, next to the
+//! emulator's own configuration file. The script names its backend in a
+//! header comment, which is how discovery tells the VMs apart.
+
+use anyhow::{Context, Result};
+use serde::{Serialize, Serializer};
+use std::path::Path;
+
+use crate::app::WizardQemuConfig;
+use crate::vm::qemu_config::{QemuConfig, QemuEmulator};
+
+/// Header comment naming the backend of a launch script
+const BACKEND_MARKER: &str = "# vm-curator backend: ";
+
+/// The emulator a VM runs under
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Backend {
+    #[default]
+    Qemu,
+    EightySixBox,
+    DosboxX,
+}
+
+/// What vm-curator can manage for a backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Settings live in QEMU arguments in launch.sh (CPU, machine, display,
+    /// audio, network, passthrough, snapshots, ...)
+    pub qemu_args: bool,
+    /// The emulator has its own settings dialog (`launch.sh --settings`)
+    pub settings_dialog: bool,
+}
+
+impl Backend {
+    /// Backends other than QEMU, in menu order
+    pub const ALTERNATIVES: [Backend; 2] = [Backend::EightySixBox, Backend::DosboxX];
+
+    /// Identifier used in profiles and launch scripts
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::Qemu => "qemu",
+            Self::EightySixBox => "86box",
+            Self::DosboxX => "dosbox-x",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id.trim().to_lowercase().as_str() {
+            "qemu" => Some(Self::Qemu),
+            "86box" => Some(Self::EightySixBox),
+            "dosbox-x" | "dosboxx" => Some(Self::DosboxX),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Qemu => "QEMU",
+            Self::EightySixBox => "86Box",
+            Self::DosboxX => "DOSBox-X",
+        }
+    }
+
+    /// Executable the launch script runs (and the process name to look for)
+    pub fn command(&self) -> &'static str {
+        match self {
+            Self::Qemu => "qemu-system",
+            Self::EightySixBox => "86Box",
+            Self::DosboxX => "dosbox-x",
+        }
+    }
+
+    /// The emulator's configuration file in the VM folder
+    pub fn config_file(&self) -> Option<&'static str> {
+        match self {
+            Self::Qemu => None,
+            Self::EightySixBox => Some("86box.cfg"),
+            Self::DosboxX => Some("dosbox-x.conf"),
+        }
+    }
+
+    pub fn capabilities(&self) -> Capabilities {
+        match self {
+            Self::Qemu => Capabilities {
+                qemu_args: true,
+                settings_dialog: false,
+            },
+            Self::EightySixBox | Self::DosboxX => Capabilities {
+                qemu_args: false,
+                settings_dialog: true,
+            },
+        }
+    }
+
+    /// The backend a launch script was written for
+    pub fn detect(script: &str) -> Self {
+        script
+            .lines()
+            .find_map(|line| line.trim().strip_prefix(BACKEND_MARKER))
+            .and_then(Self::from_id)
+            .unwrap_or_default()
+    }
+}
+
+/// Serialized as its identifier ("qemu", "86box", "dosbox-x")
+impl Serialize for Backend {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.id())
+    }
+}
+
+/// Pattern for `pgrep` matching the processes of every backend
+pub fn process_pattern() -> String {
+    let mut names = vec![Backend::Qemu.command()];
+    names.extend(Backend::ALTERNATIVES.iter().map(|b| b.command()));
+    names.join("|")
+}
+
+/// Read what the library shows about a backend VM from its config file
+pub fn read_config(backend: Backend, vm_dir: &Path, script: &str) -> QemuConfig {
+    let content = backend
+        .config_file()
+        .and_then(|file| std::fs::read_to_string(vm_dir.join(file)).ok())
+        .unwrap_or_default();
+
+    let mut config = QemuConfig {
+        emulator: QemuEmulator::Other(backend.command().to_string()),
+        network: None,
+        raw_script: script.to_string(),
+        ..Default::default()
+    };
+    match backend {
+        Backend::Qemu => {}
+        Backend::EightySixBox => {
+            if let Some(kb) = ini_value(&content, "mem_size").and_then(|v| v.parse::<u32>().ok()) {
+                config.memory_mb = kb / 1024;
+            }
+            config.machine = ini_value(&content, "machine");
+            config.cpu_model = ini_value(&content, "cpu_family");
+        }
+        Backend::DosboxX => {
+            if let Some(mb) = ini_value(&content, "memsize").and_then(|v| v.parse().ok()) {
+                config.memory_mb = mb;
+            }
+            config.machine = ini_value(&content, "machine");
+            config.cpu_model = ini_value(&content, "cputype");
+        }
+    }
+    config
+}
+
+/// Value of `key = value` in an INI-style file (first occurrence)
+pub fn ini_value(content: &str, key: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let (k, v) = line.split_once('=')?;
+        (k.trim() == key).then(|| v.trim().to_string()).filter(|v| !v.is_empty())
+    })
+}
+
+/// Set `key = value` in `[section]` of an INI-style file, adding the key
+/// at the end of the section, or the section itself, when missing
+///
+/// New sections go before DOSBox-X's `[autoexec]`, which runs to the end
+/// of the file.
+pub fn set_ini_value(content: &str, section: &str, key: &str, value: &str) -> String {
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    let header = format!("[{}]", section);
+    let entry = format!("{} = {}", key, value);
+    let is_header = |line: &str| line.trim().starts_with('[');
+
+    match lines.iter().position(|l| l.trim().eq_ignore_ascii_case(&header)) {
+        Some(start) => {
+            let end = (start + 1..lines.len()).find(|&i| is_header(&lines[i])).unwrap_or(lines.len());
+            let existing = (start + 1..end).find(|&i| lines[i].split_once('=').is_some_and(|(k, _)| k.trim() == key));
+            match existing {
+                Some(i) => lines[i] = entry,
+                None => {
+                    let last = (start..end).rev().find(|&i| !lines[i].trim().is_empty()).unwrap_or(start);
+                    lines.insert(last + 1, entry);
+                }
+            }
+        }
+        None => {
+            let at = lines
+                .iter()
+                .position(|l| l.trim().eq_ignore_ascii_case("[autoexec]"))
+                .unwrap_or(lines.len());
+            lines.splice(at..at, [header, entry, String::new()]);
+        }
+    }
+
+    let mut result = lines.join("\n");
+    result.push('\n');
+    result
+}
+
+/// Generate the emulator's configuration file for a new VM
+///
+/// Profile fields carry the backend's own names: for 86Box `machine`,
+/// `cpu_model`, `vga`, the first `audio` entry and `network_model` are its
+/// internal machine, CPU family, video, sound and network card names; for
+/// DOSBox-X `machine` and `cpu_model` are its `machine` and `cputype`.
+/// `disk` is a raw image (86Box, DOSBox-X) or a folder mounted as C:
+/// (DOSBox-X); `disk_size_mb` sets the 86Box drive geometry.
+pub fn generate_config(backend: Backend, config: &WizardQemuConfig, disk: &str, disk_size_mb: u64) -> String {
+    let mut out = String::new();
+    match backend {
+        Backend::Qemu => {}
+        Backend::EightySixBox => {
+            // 63 sectors of 512 bytes on 16 heads
+            let cylinders = (disk_size_mb * 1024 * 1024 / (63 * 16 * 512)).max(1);
+            out.push_str("[General]\n");
+            out.push_str("vid_renderer = qt_software\n");
+            out.push_str("confirm_exit = 0\n\n");
+            out.push_str("[Machine]\n");
+            if let Some(ref machine) = config.machine {
+                out.push_str(&format!("machine = {}\n", machine));
+            }
+            if let Some(ref cpu) = config.cpu_model {
+                out.push_str(&format!("cpu_family = {}\n", cpu));
+            }
+            out.push_str(&format!("mem_size = {}\n\n", config.memory_mb * 1024));
+            out.push_str("[Video]\n");
+            out.push_str(&format!("gfxcard = {}\n\n", config.vga));
+            out.push_str("[Input devices]\n");
+            out.push_str("mouse_type = ps2\n\n");
+            if let Some(card) = config.audio.first() {
+                out.push_str("[Sound]\n");
+                out.push_str(&format!("sndcard = {}\n", card));
+                out.push_str("fm_driver = nuked\n\n");
+            }
+            if config.network_model != "none" {
+                out.push_str("[Network]\n");
+                out.push_str("net_01_net_type = slirp\n");
+                out.push_str(&format!("net_01_card = {}\n\n", config.network_model));
+            }
+            out.push_str("[Storage controllers]\n");
+            out.push_str("hdc = ide_isa\n\n");
+            out.push_str("[Hard disks]\n");
+            out.push_str(&format!("hdd_01_parameters = 63, 16, {}, 0, ide\n", cylinders));
+            out.push_str(&format!("hdd_01_fn = {}\n", disk));
+            out.push_str("hdd_01_ide_channel = 0:0\n\n");
+            out.push_str("[Floppy and CD-ROM drives]\n");
+            out.push_str("fdd_01_type = 35_2hd\n");
+            out.push_str("cdrom_01_parameters = 8, atapi\n");
+            out.push_str("cdrom_01_ide_channel = 0:1\n");
+        }
+        Backend::DosboxX => {
+            out.push_str("# DOSBox-X configuration generated by vm-curator\n\n");
+            out.push_str("[sdl]\n");
+            out.push_str("autolock = true\n\n");
+            out.push_str("[dosbox]\n");
+            out.push_str(&format!("machine = {}\n", config.machine.as_deref().unwrap_or("svga_s3")));
+            out.push_str(&format!("memsize = {}\n\n", config.memory_mb));
+            out.push_str("[cpu]\n");
+            out.push_str("core = auto\n");
+            out.push_str(&format!("cputype = {}\n", config.cpu_model.as_deref().unwrap_or("auto")));
+            out.push_str("cycles = auto\n\n");
+            out.push_str("[sblaster]\n");
+            let sb16 = config.audio.iter().any(|a| a == "sb16");
+            out.push_str(&format!("sbtype = {}\n\n", if sb16 { "sb16" } else { "none" }));
+            if config.network_model != "none" {
+                out.push_str("[ne2000]\n");
+                out.push_str("ne2000 = true\n");
+                out.push_str("backend = slirp\n\n");
+            }
+            out.push_str("[autoexec]\n");
+            if Path::new(disk).extension().is_some() {
+                out.push_str(&format!("IMGMOUNT C \"{}\" -t hdd\n", disk));
+            } else {
+                out.push_str(&format!("MOUNT C \"{}\"\n", disk));
+            }
+            out.push_str("C:\n");
+        }
+    }
+    out
+}
+
+/// Generate launch.sh for a backend VM
+///
+/// The script takes the same options as a QEMU one (`--install`, `--cdrom`)
+/// plus `--settings`, which opens the emulator's configuration dialog.
+pub fn generate_launch_script(backend: Backend, vm_name: &str, iso: Option<&str>) -> String {
+    let config_file = backend.config_file().unwrap_or_default();
+    let command = backend.command();
+
+    let mut script = String::new();
+    script.push_str("#!/bin/bash\n\n");
+    script.push_str(&format!("# {} VM Launch Script\n", vm_name));
+    script.push_str(&format!("{}{}\n", BACKEND_MARKER, backend.id()));
+    script.push_str("# Generated by vm-curator\n\n");
+    script.push_str("VM_DIR=\"$(dirname \"$(readlink -f \"$0\")\")\"\n");
+    script.push_str(&format!("CONFIG=\"$VM_DIR/{}\"\n", config_file));
+    script.push_str(&format!("ISO={}\n", iso.unwrap_or("\"\"")));
+    // An AppImage or a build outside the PATH can be set here
+    script.push_str(&format!("EMULATOR=\"{}\"\n\n", command));
+
+    script.push_str("if ! command -v \"$EMULATOR\" >/dev/null 2>&1; then\n");
+    script.push_str(&format!("    echo \"{} is not installed (or set EMULATOR in this script)\" >&2\n", backend.label()));
+    script.push_str("    exit 1\n");
+    script.push_str("fi\n");
+    script.push_str("cd \"$VM_DIR\" || exit 1\n\n");
+
+    match backend {
+        Backend::Qemu => {}
+        Backend::EightySixBox => {
+            // The CD image is a config setting, so it is written in before launch
+            script.push_str("set_cdrom() {\n");
+            script.push_str("    awk -v img=\"$1\" '\n");
+            script.push_str("        /^cdrom_01_image_path/ { next }\n");
+            script.push_str("        { print }\n");
+            script.push_str("        /^\\[Floppy and CD-ROM drives\\]/ { print \"cdrom_01_image_path = \" img }\n");
+            script.push_str("    ' \"$CONFIG\" > \"$CONFIG.tmp\" && mv \"$CONFIG.tmp\" \"$CONFIG\"\n");
+            script.push_str("}\n\n");
+        }
+        Backend::DosboxX => {}
+    }
+
+    let (run, with_cd, settings) = match backend {
+        Backend::Qemu | Backend::EightySixBox => (
+            "exec \"$EMULATOR\" --vmpath \"$VM_DIR\"".to_string(),
+            "set_cdrom \"$CD\"\n        exec \"$EMULATOR\" --vmpath \"$VM_DIR\"".to_string(),
+            "exec \"$EMULATOR\" --vmpath \"$VM_DIR\" --settings",
+        ),
+        Backend::DosboxX => (
+            "exec \"$EMULATOR\" -conf \"$CONFIG\"".to_string(),
+            "exec \"$EMULATOR\" -conf \"$CONFIG\" -c \"IMGMOUNT D \\\"$CD\\\" -t iso\"".to_string(),
+            "exec \"$EMULATOR\" -conf \"$CONFIG\" -startui",
+        ),
+    };
+
+    script.push_str("case \"$1\" in\n");
+    script.push_str("    --install|--cdrom)\n");
+    script.push_str("        CD=\"$ISO\"\n");
+    script.push_str("        [[ \"$1\" == \"--cdrom\" ]] && CD=\"$2\"\n");
+    script.push_str("        if [[ -z \"$CD\" ]] || [[ ! -f \"$CD\" ]]; then\n");
+    script.push_str("            echo \"Error: CD image not found at $CD\" >&2\n");
+    script.push_str("            exit 1\n");
+    script.push_str("        fi\n");
+    script.push_str(&format!("        {}\n", with_cd));
+    script.push_str("        ;;\n");
+    script.push_str("    --settings)\n");
+    script.push_str(&format!("        {}\n", settings));
+    script.push_str("        ;;\n");
+    script.push_str("    *)\n");
+    script.push_str(&format!("        {}\n", run));
+    script.push_str("        ;;\n");
+    script.push_str("esac\n");
+    script
+}
+
+/// Create the disk for a new backend VM: a sparse raw image for 86Box, a
+/// folder mounted as drive C: for DOSBox-X. Returns the name in the VM folder.
+pub fn create_disk(backend: Backend, vm_dir: &Path, folder_name: &str, size_gb: u32) -> Result<String> {
+    match backend {
+        Backend::DosboxX => {
+            std::fs::create_dir_all(vm_dir.join("drive_c")).context("Failed to create drive_c")?;
+            Ok("drive_c".to_string())
+        }
+        _ => {
+            let name = format!("{}.img", folder_name);
+            let file = std::fs::File::create(vm_dir.join(&name)).context("Failed to create disk image")?;
+            file.set_len(size_gb as u64 * 1024 * 1024 * 1024)
+                .context("Failed to size disk image")?;
+            Ok(name)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_backend() {
+        let script = generate_launch_script(Backend::DosboxX, "Doom", None);
+        assert_eq!(Backend::detect(&script), Backend::DosboxX);
+        assert!(script.contains("exec \"$EMULATOR\" -conf \"$CONFIG\"\n"));
+        assert_eq!(Backend::detect("#!/bin/bash\nqemu-system-i386 -m 16\n"), Backend::Qemu);
+        assert_eq!(process_pattern(), "qemu-system|86Box|dosbox-x");
+    }
+
+    #[test]
+    fn test_set_ini_value() {
+        let conf = "[sdl]\nautolock = true\n\n[dosbox]\nmemsize = 16\n\n[autoexec]\nC:\n";
+        let conf = set_ini_value(conf, "dosbox", "memsize", "32");
+        let conf = set_ini_value(&conf, "sdl", "output", "opengl");
+        let conf = set_ini_value(&conf, "joystick", "joysticktype", "fcs");
+        assert_eq!(
+            conf,
+            "[sdl]\nautolock = true\noutput = opengl\n\n[dosbox]\nmemsize = 32\n\n\
+             [joystick]\njoysticktype = fcs\n\n[autoexec]\nC:\n"
+        );
+        assert_eq!(set_ini_value("", "Input devices", "joystick_type", "none"), "[Input devices]\njoystick_type = none\n\n");
+    }
+
+    #[test]
+    fn test_config_round_trip() {
+        let config = WizardQemuConfig {
+            memory_mb: 16,
+            machine: Some("ami486".to_string()),
+            cpu_model: Some("i486dx2".to_string()),
+            vga: "et4000ax".to_string(),
+            audio: vec!["sb16".to_string()],
+            network_model: "none".to_string(),
+            ..Default::default()
+        };
+        let dir = std::env::temp_dir().join(format!("vm-curator-backend-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let cfg = generate_config(Backend::EightySixBox, &config, "win31.img", 504);
+        assert!(cfg.contains("hdd_01_parameters = 63, 16, 1024, 0, ide\n"));
+        assert!(!cfg.contains("[Network]"));
+        std::fs::write(dir.join("86box.cfg"), &cfg).unwrap();
+        let read = read_config(Backend::EightySixBox, &dir, "");
+        assert_eq!(read.memory_mb, 16);
+        assert_eq!(read.machine.as_deref(), Some("ami486"));
+
+        let conf = generate_config(Backend::DosboxX, &config, "drive_c", 0);
+        assert!(conf.contains("MOUNT C \"drive_c\"\n"));
+        std::fs::write(dir.join("dosbox-x.conf"), &conf).unwrap();
+        let read = read_config(Backend::DosboxX, &dir, "");
+        assert_eq!(read.memory_mb, 16);
+        assert_eq!(read.emulator.command(), "dosbox-x");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
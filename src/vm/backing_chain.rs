@@ -0,0 +1,224 @@
+//! Backing chains
+//!
+//! A qcow2 overlay only stores what changed since its backing image, which
+//! may be an overlay itself. The chain is walked one image at a time rather
+//! than with `qemu-img info --backing-chain`, which gives up on the first
+//! missing file; a missing backing file is looked for by name in the VM
+//! folder and the rest of the library, as it has usually just been moved.
+//!
+//! Changing a chain rewrites images: flattening copies the backing data
+//! into the disk, committing writes the disk's changes down into its
+//! backing image, rebasing copies what skipped layers held, and repairing
+//! only rewrites the backing file name in the overlay's header.
+
+use anyhow::{bail, Result};
+use std::path::{Path, PathBuf};
+
+use crate::commands::qemu_img::{self, ImageInfo};
+
+/// Longest chain walked; anything deeper is almost certainly a loop
+const MAX_DEPTH: usize = 16;
+
+/// Whether a link's image could be found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkStatus {
+    Present,
+    /// Not where the overlay says, and nowhere else either
+    Missing,
+    /// Not where the overlay says, but a file of that name is here
+    Moved(PathBuf),
+}
+
+/// One image in a chain
+#[derive(Debug, Clone)]
+pub struct ChainLink {
+    /// Where the image is (or should be)
+    pub path: PathBuf,
+    /// Backing file name as recorded in the overlay above; None for the disk
+    pub recorded_as: Option<String>,
+    /// Format and sizes, when qemu-img could read the image
+    pub info: Option<ImageInfo>,
+    pub status: LinkStatus,
+}
+
+impl ChainLink {
+    pub fn file_name(&self) -> String {
+        self.path.file_name().unwrap_or_default().to_string_lossy().to_string()
+    }
+}
+
+/// A change to a disk's chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainAction {
+    /// Copy everything the disk reads from its backing images into it
+    Flatten,
+    /// Write the disk's changes into its backing image
+    Commit,
+    /// Read straight from a deeper image, copying what the skipped layers held
+    Rebase { base: PathBuf },
+    /// Point an overlay at its moved backing file
+    Repair { overlay: PathBuf, found: PathBuf },
+}
+
+impl ChainAction {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::Flatten => "Flatten",
+            Self::Commit => "Commit into backing image",
+            Self::Rebase { .. } => "Rebase",
+            Self::Repair { .. } => "Repair link",
+        }
+    }
+
+    /// What happens to the data
+    pub fn consequences(&self) -> &'static str {
+        match self {
+            Self::Flatten => {
+                "The disk becomes a standalone image: everything it reads from its backing images is \
+                 copied into it and the link is dropped. The backing images are not changed and the \
+                 disk no longer needs them, but it grows by up to the data they hold."
+            }
+            Self::Commit => {
+                "The disk's changes are written into its backing image, which is modified in place, \
+                 and the disk is emptied. Any other disk built on that backing image will then see \
+                 data it was never meant to and is likely to be corrupted. Make sure nothing else \
+                 uses it."
+            }
+            Self::Rebase { .. } => {
+                "The disk is pointed straight at the selected image. What the layers in between \
+                 held is copied into the disk first, so the guest sees the same data. Those layers \
+                 are not changed or deleted."
+            }
+            Self::Repair { .. } => {
+                "Only the backing file name in the overlay's header is rewritten to the file found. \
+                 No data is copied, so this is only correct if the file found is the very image \
+                 that was moved; pointing an overlay at a different image corrupts it."
+            }
+        }
+    }
+}
+
+/// Walk a disk's chain, looking for missing backing files next to the disk
+/// and in the library's VM folders
+pub fn load(disk: &Path, library: &Path) -> Vec<ChainLink> {
+    let search_dirs = [disk.parent().unwrap_or(Path::new("")), library];
+    let mut links = Vec::new();
+    let mut path = disk.to_path_buf();
+    let mut recorded_as = None;
+    while links.len() < MAX_DEPTH {
+        if !path.exists() {
+            let status = match find_moved(&path, &search_dirs) {
+                Some(found) => LinkStatus::Moved(found),
+                None => LinkStatus::Missing,
+            };
+            links.push(ChainLink { path, recorded_as, info: None, status });
+            break;
+        }
+        let info = qemu_img::image_info(&path);
+        let backing = info.as_ref().and_then(|i| i.backing_file.clone());
+        let next = backing.as_deref().map(|name| resolve(&path, name));
+        links.push(ChainLink { path, recorded_as, info, status: LinkStatus::Present });
+        match next {
+            Some(next) => {
+                path = next;
+                recorded_as = backing;
+            }
+            None => break,
+        }
+    }
+    links
+}
+
+/// A backing file name as qemu resolves it: relative to the overlay's folder
+pub fn resolve(overlay: &Path, backing: &str) -> PathBuf {
+    let backing = Path::new(backing);
+    if backing.is_absolute() {
+        backing.to_path_buf()
+    } else {
+        overlay.parent().unwrap_or(Path::new("")).join(backing)
+    }
+}
+
+/// The name to record for `base` in `overlay`: relative when they share a
+/// folder, so the pair can be moved together
+pub fn backing_reference(overlay: &Path, base: &Path) -> String {
+    match (overlay.parent(), base.parent(), base.file_name()) {
+        (Some(a), Some(b), Some(name)) if a == b => name.to_string_lossy().to_string(),
+        _ => std::fs::canonicalize(base).unwrap_or_else(|_| base.to_path_buf()).to_string_lossy().to_string(),
+    }
+}
+
+/// A file with the missing image's name in one of the folders
+fn find_moved(missing: &Path, search_dirs: &[&Path]) -> Option<PathBuf> {
+    let name = missing.file_name()?;
+    for dir in search_dirs {
+        let candidate = dir.join(name);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten().filter(|e| e.path().is_dir()) {
+            let candidate = entry.path().join(name);
+            if candidate.is_file() {
+                return Some(candidate);
+            }
+        }
+    }
+    None
+}
+
+/// Apply a change to `disk`'s chain
+pub fn apply(disk: &Path, action: &ChainAction) -> Result<()> {
+    tracing::info!(disk = %disk.display(), action = ?action, "changing backing chain");
+    match action {
+        ChainAction::Flatten => qemu_img::rebase(disk, None, false),
+        ChainAction::Commit => qemu_img::commit(disk),
+        ChainAction::Rebase { base } => {
+            let Some(info) = qemu_img::image_info(base) else {
+                bail!("Can't read {}", base.display());
+            };
+            qemu_img::rebase(disk, Some((&backing_reference(disk, base), &info.format)), false)
+        }
+        ChainAction::Repair { overlay, found } => {
+            let Some(info) = qemu_img::image_info(found) else {
+                bail!("Can't read {}", found.display());
+            };
+            qemu_img::rebase(overlay, Some((&backing_reference(overlay, found), &info.format)), true)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_and_find_moved() {
+        let dir = std::env::temp_dir().join(format!("vm-curator-chain-{}", std::process::id()));
+        let vm_dir = dir.join("win98");
+        std::fs::create_dir_all(&vm_dir).unwrap();
+        std::fs::create_dir_all(dir.join("bases")).unwrap();
+        std::fs::write(dir.join("bases").join("win98-base.qcow2"), b"").unwrap();
+
+        let disk = vm_dir.join("disk.qcow2");
+        assert_eq!(resolve(&disk, "../old/win98-base.qcow2"), vm_dir.join("../old/win98-base.qcow2"));
+        assert_eq!(resolve(&disk, "/srv/base.qcow2"), PathBuf::from("/srv/base.qcow2"));
+
+        let missing = resolve(&disk, "../old/win98-base.qcow2");
+        assert_eq!(find_moved(&missing, &[&vm_dir, &dir]), Some(dir.join("bases").join("win98-base.qcow2")));
+        assert_eq!(find_moved(&vm_dir.join("other.qcow2"), &[&vm_dir, &dir]), None);
+
+        assert_eq!(backing_reference(&disk, &vm_dir.join("base.qcow2")), "base.qcow2");
+        let elsewhere = backing_reference(&disk, &dir.join("bases").join("win98-base.qcow2"));
+        assert!(Path::new(&elsewhere).is_absolute() && elsewhere.ends_with("bases/win98-base.qcow2"));
+
+        // Walking a disk that doesn't exist reports it, found or not
+        let links = load(&vm_dir.join("win98-base.qcow2"), &dir);
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].status, LinkStatus::Moved(dir.join("bases").join("win98-base.qcow2")));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
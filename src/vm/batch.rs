@@ -0,0 +1,202 @@
+//! Batch operations across many VMs
+//!
+//! A batch runs one operation per VM and never stops at the first failure:
+//! each item is retried with exponential backoff, its final error is kept,
+//! and the caller gets a report that can be shown to the user and used to
+//! retry just the items that failed.
+
+use anyhow::Result;
+use std::time::Duration;
+
+use crate::vm::snapshot_meta::{self, SnapshotContext, SnapshotMeta};
+use crate::vm::DiscoveredVm;
+
+/// Attempts per item before it's recorded as failed
+pub const MAX_ATTEMPTS: u32 = 3;
+
+/// Delay before the first retry; doubles on each further attempt
+const BASE_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// An operation that can be applied to many VMs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchOperation {
+    /// Create an internal qcow2 snapshot with the same name on every VM
+    Snapshot { name: String },
+}
+
+impl BatchOperation {
+    /// Human-readable description for the report title
+    pub fn label(&self) -> String {
+        match self {
+            Self::Snapshot { name } => format!("Snapshot '{}'", name),
+        }
+    }
+
+    fn run(&self, vm: &DiscoveredVm) -> Result<()> {
+        match self {
+            Self::Snapshot { name } => {
+                let disk = vm
+                    .config
+                    .primary_disk()
+                    .ok_or_else(|| anyhow::anyhow!("No disk found"))?;
+                crate::vm::create_snapshot(&disk.path, name)?;
+                // The snapshot exists now; a retry would only fail on its name
+                let meta = SnapshotMeta::for_vm(vm, "", SnapshotContext::Batch);
+                if let Err(e) = snapshot_meta::record(&disk.path, name, meta) {
+                    tracing::warn!(vm = %vm.id, "{:#}", e);
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Outcome of one VM in a batch
+#[derive(Debug, Clone)]
+pub struct BatchItem {
+    pub vm_id: String,
+    pub vm_name: String,
+    /// Final error, or None on success
+    pub error: Option<String>,
+    /// How many times the operation was tried (0 = skipped by precheck)
+    pub attempts: u32,
+}
+
+impl BatchItem {
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Result of a whole batch
+#[derive(Debug, Clone)]
+pub struct BatchReport {
+    pub operation: BatchOperation,
+    pub items: Vec<BatchItem>,
+}
+
+impl BatchReport {
+    pub fn success_count(&self) -> usize {
+        self.items.iter().filter(|i| i.succeeded()).count()
+    }
+
+    pub fn failure_count(&self) -> usize {
+        self.items.len() - self.success_count()
+    }
+
+    /// IDs of VMs whose item failed
+    pub fn failed_ids(&self) -> Vec<String> {
+        self.items
+            .iter()
+            .filter(|i| !i.succeeded())
+            .map(|i| i.vm_id.clone())
+            .collect()
+    }
+
+    /// Replace items with the results of retrying them
+    pub fn merge_retry(&mut self, retry: BatchReport) {
+        for item in retry.items {
+            if let Some(existing) = self.items.iter_mut().find(|i| i.vm_id == item.vm_id) {
+                *existing = item;
+            } else {
+                self.items.push(item);
+            }
+        }
+    }
+}
+
+/// Run `op` on every VM, continuing past failures
+///
+/// `precheck` can reject a VM up front (e.g. because it's running); such
+/// items are recorded as failed without being attempted.
+pub fn run_batch<F>(op: &BatchOperation, vms: &[DiscoveredVm], precheck: F) -> BatchReport
+where
+    F: Fn(&DiscoveredVm) -> Option<String>,
+{
+    let items = vms
+        .iter()
+        .map(|vm| {
+            let (error, attempts) = match precheck(vm) {
+                Some(reason) => (Some(reason), 0),
+                None => {
+                    let (result, attempts) =
+                        retry_with_backoff(MAX_ATTEMPTS, BASE_RETRY_DELAY, || op.run(vm));
+                    (result.err().map(|e| e.to_string()), attempts)
+                }
+            };
+            BatchItem {
+                vm_id: vm.id.clone(),
+                vm_name: vm.display_name(),
+                error,
+                attempts,
+            }
+        })
+        .collect();
+
+    BatchReport {
+        operation: op.clone(),
+        items,
+    }
+}
+
+/// Call `f` until it succeeds or `max_attempts` is reached, doubling the delay
+/// between attempts. Returns the last result and the number of attempts made.
+fn retry_with_backoff<T, F>(max_attempts: u32, base_delay: Duration, mut f: F) -> (Result<T>, u32)
+where
+    F: FnMut() -> Result<T>,
+{
+    let mut attempt = 1;
+    loop {
+        let result = f();
+        if result.is_ok() || attempt >= max_attempts {
+            return (result, attempt);
+        }
+        std::thread::sleep(base_delay * 2u32.pow(attempt - 1));
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_with_backoff_recovers_and_gives_up() {
+        let mut calls = 0;
+        let (result, attempts) = retry_with_backoff(3, Duration::ZERO, || {
+            calls += 1;
+            if calls < 2 { anyhow::bail!("busy") } else { Ok(calls) }
+        });
+        assert_eq!(result.unwrap(), 2);
+        assert_eq!(attempts, 2);
+
+        let (result, attempts) = retry_with_backoff(3, Duration::ZERO, || -> Result<()> {
+            anyhow::bail!("locked")
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, 3);
+    }
+
+    #[test]
+    fn test_merge_retry_replaces_failed_items() {
+        let item = |id: &str, error: Option<&str>| BatchItem {
+            vm_id: id.to_string(),
+            vm_name: id.to_string(),
+            error: error.map(str::to_string),
+            attempts: 1,
+        };
+        let op = BatchOperation::Snapshot { name: "s".to_string() };
+        let mut report = BatchReport {
+            operation: op.clone(),
+            items: vec![item("a", None), item("b", Some("locked")), item("c", Some("running"))],
+        };
+        assert_eq!(report.failed_ids(), vec!["b".to_string(), "c".to_string()]);
+
+        report.merge_retry(BatchReport {
+            operation: op,
+            items: vec![item("b", None), item("c", Some("running"))],
+        });
+        assert_eq!(report.success_count(), 2);
+        assert_eq!(report.failed_ids(), vec!["c".to_string()]);
+    }
+}
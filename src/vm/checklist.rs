@@ -0,0 +1,87 @@
+//! Post-install checklists
+//!
+//! OS profiles can list the steps that make a fresh install pleasant to use
+//! (drivers to install, color depth, APM quirks). A VM gets its own copy of
+//! the list in `checklist.json` the first time an item is checked, so the
+//! ticks survive profile edits; until then the profile's list is shown.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Checklist file in each VM folder
+const CHECKLIST_FILE: &str = "checklist.json";
+
+/// A step to take after installing the OS
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChecklistItem {
+    pub text: String,
+    #[serde(default)]
+    pub done: bool,
+}
+
+/// A VM's post-install steps
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checklist {
+    #[serde(default)]
+    pub items: Vec<ChecklistItem>,
+}
+
+impl Checklist {
+    /// A fresh checklist from a profile's steps
+    pub fn from_steps(steps: &[String]) -> Self {
+        Self {
+            items: steps.iter().map(|text| ChecklistItem { text: text.clone(), done: false }).collect(),
+        }
+    }
+
+    pub fn done_count(&self) -> usize {
+        self.items.iter().filter(|item| item.done).count()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.done_count() == self.items.len()
+    }
+}
+
+fn checklist_path(vm_dir: &Path) -> PathBuf {
+    vm_dir.join(CHECKLIST_FILE)
+}
+
+/// A VM's checklist, or the profile's steps if none has been saved
+pub fn load(vm_dir: &Path, profile_steps: &[String]) -> Checklist {
+    std::fs::read_to_string(checklist_path(vm_dir))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_else(|| Checklist::from_steps(profile_steps))
+}
+
+pub fn save(vm_dir: &Path, checklist: &Checklist) -> Result<()> {
+    let path = checklist_path(vm_dir);
+    std::fs::write(&path, serde_json::to_string_pretty(checklist)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checklist_persists() {
+        let dir = std::env::temp_dir().join(format!("vm-curator-checklist-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let steps = vec!["Install the SB16 driver".to_string(), "Set 256 colors".to_string()];
+
+        let mut checklist = load(&dir, &steps);
+        assert_eq!(checklist.items.len(), 2);
+        assert_eq!(checklist.done_count(), 0);
+        checklist.items[1].done = true;
+        save(&dir, &checklist).unwrap();
+
+        // The VM's copy wins over later profile changes
+        let checklist = load(&dir, &["Something else".to_string()]);
+        assert_eq!(checklist.items[1], ChecklistItem { text: "Set 256 colors".to_string(), done: true });
+        assert!(!checklist.is_complete());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,269 @@
+//! cloud-init NoCloud seed generation
+//!
+//! Cloud images boot without an installer and expect their first-boot
+//! configuration from a datasource. This module writes `user-data` and
+//! `meta-data` files and packs them into a `cidata`-labelled ISO that is
+//! attached to the VM as a second CD-ROM. The password goes in as a
+//! SHA-512 crypt hash where `openssl` can make one, and only the ISO is
+//! kept, readable by its owner alone.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::commands::iso;
+use crate::vm::lifecycle::insert_args_section;
+use crate::vm::unattended::{validate_hostname, validate_username};
+
+/// File name of the generated seed ISO inside the VM directory
+pub const SEED_ISO_NAME: &str = "seed.iso";
+
+// Cloud-init section markers
+const CLOUD_INIT_MARKER_START: &str = "# >>> Cloud-init Seed (managed by vm-curator) >>>";
+const CLOUD_INIT_MARKER_END: &str = "# <<< Cloud-init Seed <<<";
+
+/// User-facing cloud-init settings collected by the wizard
+#[derive(Debug, Clone, Default)]
+pub struct CloudInitConfig {
+    /// Guest hostname
+    pub hostname: String,
+    /// Default user account name
+    pub username: String,
+    /// Password for the default user (empty = key-only login)
+    pub password: String,
+    /// Path to an SSH public key to authorize
+    pub ssh_key_path: Option<PathBuf>,
+}
+
+impl CloudInitConfig {
+    /// Create a config with sensible defaults for a new VM
+    pub fn new(hostname: &str) -> Self {
+        Self {
+            hostname: hostname.to_string(),
+            username: "user".to_string(),
+            password: String::new(),
+            ssh_key_path: find_default_ssh_key(),
+        }
+    }
+
+    /// Validate the settings before building a seed
+    pub fn validate(&self) -> Result<(), String> {
+        if self.hostname.trim().is_empty() {
+            return Err("Cloud-init hostname cannot be empty".to_string());
+        }
+        validate_hostname(&self.hostname)?;
+        if self.username.trim().is_empty() {
+            return Err("Cloud-init username cannot be empty".to_string());
+        }
+        validate_username(&self.username)?;
+        if self.password.is_empty() && self.ssh_key_path.is_none() {
+            return Err("Cloud-init needs a password or an SSH key to log in".to_string());
+        }
+        if let Some(ref key) = self.ssh_key_path {
+            if !key.exists() {
+                return Err(format!("SSH key not found: {}", key.display()));
+            }
+        }
+        if iso::find_builder().is_none() {
+            return Err("Cloud-init seed requires genisoimage, mkisofs, or xorriso".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Find the user's default SSH public key
+pub fn find_default_ssh_key() -> Option<PathBuf> {
+    let ssh_dir = dirs::home_dir()?.join(".ssh");
+    ["id_ed25519.pub", "id_ecdsa.pub", "id_rsa.pub"]
+        .iter()
+        .map(|name| ssh_dir.join(name))
+        .find(|path| path.exists())
+}
+
+/// SHA-512 crypt hash of a password (`$6$...`), via `openssl passwd`
+pub(crate) fn crypt_password(password: &str) -> Option<String> {
+    openssl_passwd("-6", password)
+}
+
+/// MD5 crypt hash of a password (`$1$...`), for systems older than SHA-512 crypt
+pub(crate) fn crypt_password_md5(password: &str) -> Option<String> {
+    openssl_passwd("-1", password)
+}
+
+/// `openssl passwd` with the scheme option `scheme` (`-6` gives `$6$...`)
+fn openssl_passwd(scheme: &str, password: &str) -> Option<String> {
+    let mut child = Command::new("openssl")
+        .args(["passwd", scheme, "-stdin"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    child.stdin.take()?.write_all(format!("{}\n", password).as_bytes()).ok()?;
+    let output = child.wait_with_output().ok()?;
+    let hash = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let prefix = format!("${}$", scheme.trim_start_matches('-'));
+    (output.status.success() && hash.starts_with(&prefix)).then_some(hash)
+}
+
+/// Generate the `user-data` document; the password is written as
+/// `password_hash` when given, in plain text otherwise
+pub fn generate_user_data(config: &CloudInitConfig, ssh_key: Option<&str>, password_hash: Option<&str>) -> String {
+    let mut doc = String::from("#cloud-config\n");
+    doc.push_str(&format!("hostname: {}\n", yaml_quote(&config.hostname)));
+    doc.push_str("users:\n");
+    doc.push_str(&format!("  - name: {}\n", yaml_quote(&config.username)));
+    doc.push_str("    groups: [adm, sudo, wheel]\n");
+    doc.push_str("    sudo: ALL=(ALL) NOPASSWD:ALL\n");
+    doc.push_str("    shell: /bin/bash\n");
+
+    if config.password.is_empty() {
+        doc.push_str("    lock_passwd: true\n");
+    } else {
+        doc.push_str("    lock_passwd: false\n");
+        match password_hash {
+            Some(hash) => doc.push_str(&format!("    passwd: {}\n", yaml_quote(hash))),
+            None => doc.push_str(&format!("    plain_text_passwd: {}\n", yaml_quote(&config.password))),
+        }
+    }
+
+    if let Some(key) = ssh_key.map(str::trim).filter(|k| !k.is_empty()) {
+        doc.push_str("    ssh_authorized_keys:\n");
+        doc.push_str(&format!("      - {}\n", yaml_quote(key)));
+    }
+
+    if !config.password.is_empty() {
+        doc.push_str("ssh_pwauth: true\n");
+        doc.push_str("chpasswd:\n");
+        doc.push_str("  expire: false\n");
+    }
+
+    doc
+}
+
+/// Generate the `meta-data` document
+pub fn generate_meta_data(instance_id: &str, hostname: &str) -> String {
+    format!("instance-id: {}\nlocal-hostname: {}\n", yaml_quote(instance_id), yaml_quote(hostname))
+}
+
+/// Quote a string for YAML (single-quoted style)
+fn yaml_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+/// Write the cloud-init documents and build `seed.iso` in the VM directory
+pub fn create_seed_iso(vm_dir: &Path, instance_id: &str, config: &CloudInitConfig) -> Result<PathBuf> {
+    let ssh_key = match config.ssh_key_path {
+        Some(ref path) => Some(
+            fs::read_to_string(path)
+                .with_context(|| format!("Failed to read SSH key: {}", path.display()))?,
+        ),
+        None => None,
+    };
+
+    let data_dir = vm_dir.join("cloud-init");
+    fs::create_dir_all(&data_dir)
+        .with_context(|| format!("Failed to create {}", data_dir.display()))?;
+
+    let user_data = data_dir.join("user-data");
+    let meta_data = data_dir.join("meta-data");
+    let password_hash = if config.password.is_empty() { None } else { crypt_password(&config.password) };
+    if !config.password.is_empty() && password_hash.is_none() {
+        tracing::warn!("openssl not available; the cloud-init password is stored unhashed in {}", SEED_ISO_NAME);
+    }
+    fs::write(&user_data, generate_user_data(config, ssh_key.as_deref(), password_hash.as_deref()))
+        .context("Failed to write user-data")?;
+    fs::write(&meta_data, generate_meta_data(instance_id, &config.hostname))
+        .context("Failed to write meta-data")?;
+
+    let seed_path = vm_dir.join(SEED_ISO_NAME);
+    let built = iso::build_data_iso(&seed_path, "cidata", &[&user_data, &meta_data]);
+    // The documents were only needed to build the ISO
+    let _ = fs::remove_dir_all(&data_dir);
+    built?;
+    crate::fs::set_mode(&seed_path, 0o600)
+        .with_context(|| format!("Failed to restrict {}", seed_path.display()))?;
+
+    Ok(seed_path)
+}
+
+/// Attach the seed ISO to every QEMU command in a launch script
+pub fn add_seed_to_script(content: &str) -> String {
+    let section = format!(
+        "{}\nCLOUD_INIT_ARGS=\"-drive file=$VM_DIR/{},media=cdrom,index=2,readonly=on\"\n{}\n",
+        CLOUD_INIT_MARKER_START, SEED_ISO_NAME, CLOUD_INIT_MARKER_END
+    );
+    insert_args_section(content, &section, "$CLOUD_INIT_ARGS")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_user_data_password_and_key() {
+        let config = CloudInitConfig {
+            hostname: "debian-cloud".to_string(),
+            username: "alice".to_string(),
+            password: "it's secret".to_string(),
+            ssh_key_path: None,
+        };
+        let doc = generate_user_data(&config, Some("ssh-ed25519 AAAA alice@host\n"), None);
+
+        assert!(doc.starts_with("#cloud-config\n"));
+        assert!(doc.contains("hostname: 'debian-cloud'"));
+        assert!(doc.contains("  - name: 'alice'"));
+        assert!(doc.contains("plain_text_passwd: 'it''s secret'"));
+        assert!(doc.contains("      - 'ssh-ed25519 AAAA alice@host'"));
+        assert!(doc.contains("ssh_pwauth: true"));
+
+        let hashed = generate_user_data(&config, None, Some("$6$salt$hash"));
+        assert!(hashed.contains("    passwd: '$6$salt$hash'"));
+        assert!(!hashed.contains("secret"));
+        if let Some(hash) = crypt_password("it's secret") {
+            assert!(hash.starts_with("$6$") && !hash.contains("secret"));
+        }
+        if let Some(hash) = crypt_password_md5("it's secret") {
+            assert!(hash.starts_with("$1$"));
+        }
+    }
+
+    #[test]
+    fn test_validate_names() {
+        let mut config = CloudInitConfig {
+            hostname: "vm: #1".to_string(),
+            username: "user".to_string(),
+            password: "secret".to_string(),
+            ssh_key_path: None,
+        };
+        assert!(config.validate().unwrap_err().contains("Hostname"));
+        config.hostname = "vm1".to_string();
+        config.username = "me: #x".to_string();
+        assert!(config.validate().unwrap_err().contains("Username"));
+        assert_eq!(generate_meta_data("id", "vm1"), "instance-id: 'id'\nlocal-hostname: 'vm1'\n");
+    }
+
+    #[test]
+    fn test_generate_user_data_key_only() {
+        let config = CloudInitConfig {
+            hostname: "vm".to_string(),
+            username: "user".to_string(),
+            password: String::new(),
+            ssh_key_path: None,
+        };
+        let doc = generate_user_data(&config, Some("ssh-rsa AAAA"), None);
+        assert!(doc.contains("lock_passwd: true"));
+        assert!(!doc.contains("ssh_pwauth"));
+    }
+
+    #[test]
+    fn test_add_seed_to_script() {
+        let script = "#!/bin/bash\nVM_DIR=\"/vms/test\"\ncase \"$1\" in\n    \"\")\n        qemu-system-x86_64 \\\n            -m 2048M\n        ;;\nesac\n";
+        let result = add_seed_to_script(script);
+        assert!(result.contains(CLOUD_INIT_MARKER_START));
+        assert!(result.contains("CLOUD_INIT_ARGS=\"-drive file=$VM_DIR/seed.iso,media=cdrom,index=2,readonly=on\""));
+        assert!(result.contains("-m 2048M $CLOUD_INIT_ARGS"));
+    }
+}
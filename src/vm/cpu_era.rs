@@ -0,0 +1,144 @@
+//! CPU era presets
+//!
+//! With KVM the guest sees the host's CPUID by default, and plenty of 90s
+//! software misdetects (or crashes on) a modern CPU. A CPU era preset adds a
+//! managed section to launch.sh whose `-cpu` flag is appended after the
+//! script's own, so QEMU uses the older model and KVM masks everything newer.
+
+use anyhow::{Context, Result};
+
+use crate::vm::lifecycle::{insert_args_section, remove_args_section};
+use crate::vm::DiscoveredVm;
+
+// CPU era section markers
+const CPU_ERA_MARKER_START: &str = "# >>> CPU Era (managed by vm-curator) >>>";
+const CPU_ERA_MARKER_END: &str = "# <<< CPU Era <<<";
+
+/// A named CPU feature mask
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuEraPreset {
+    /// Identifier stored in launch.sh
+    pub id: &'static str,
+    /// Display name
+    pub name: &'static str,
+    /// Short description for the picker
+    pub description: &'static str,
+    /// Value passed to `-cpu`
+    pub cpu: &'static str,
+}
+
+/// Available presets, oldest first
+pub const CPU_ERA_PRESETS: &[CpuEraPreset] = &[
+    CpuEraPreset {
+        id: "pentium",
+        name: "Pentium-class",
+        description: "P5, no MMX/SSE (DOS, Win 3.x, Win 95)",
+        cpu: "pentium",
+    },
+    CpuEraPreset {
+        id: "pentium2",
+        name: "Pentium II-class",
+        description: "MMX, no SSE (Win 95/98, NT 4)",
+        cpu: "pentium2",
+    },
+    CpuEraPreset {
+        id: "pentium3",
+        name: "Pentium III-class",
+        description: "SSE (Win 98 SE/ME, 2000)",
+        cpu: "pentium3",
+    },
+    CpuEraPreset {
+        id: "athlon",
+        name: "Athlon XP-class",
+        description: "3DNow!, MMX, SSE (Win 98-XP era)",
+        cpu: "athlon",
+    },
+    CpuEraPreset {
+        id: "core2duo",
+        name: "Core 2-class",
+        description: "SSE3/SSSE3, no SSE4 (XP, Vista, 7)",
+        cpu: "core2duo",
+    },
+    CpuEraPreset {
+        id: "host-no-simd",
+        name: "Host without SSE4/AVX",
+        description: "Host CPU with SSE4 and AVX hidden",
+        cpu: "host,-sse4.1,-sse4.2,-avx,-avx2,-avx512f",
+    },
+];
+
+/// Look up a preset by id
+pub fn find_preset(id: &str) -> Option<&'static CpuEraPreset> {
+    CPU_ERA_PRESETS.iter().find(|p| p.id == id)
+}
+
+/// Apply a preset to a VM (None removes any CPU era masking)
+pub fn set_cpu_era(vm: &DiscoveredVm, preset: Option<&CpuEraPreset>) -> Result<()> {
+    let content =
+        std::fs::read_to_string(&vm.launch_script).context("Failed to read launch.sh")?;
+    let content = remove_cpu_era_section(&content);
+
+    let new_content = match preset {
+        Some(preset) => {
+            insert_args_section(&content, &generate_cpu_era_section(preset), "$CPU_ERA_ARGS")
+        }
+        None => content,
+    };
+
+    std::fs::write(&vm.launch_script, new_content).context("Failed to write launch.sh")?;
+    Ok(())
+}
+
+/// Read the active preset id from a launch script
+pub fn parse_cpu_era(content: &str) -> Option<String> {
+    let mut in_section = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed == CPU_ERA_MARKER_START {
+            in_section = true;
+        } else if trimmed == CPU_ERA_MARKER_END {
+            return None;
+        } else if in_section {
+            if let Some(value) = trimmed.strip_prefix("CPU_ERA=") {
+                return Some(value.trim_matches('"').to_string());
+            }
+        }
+    }
+    None
+}
+
+fn generate_cpu_era_section(preset: &CpuEraPreset) -> String {
+    let mut section = String::new();
+    section.push_str(CPU_ERA_MARKER_START);
+    section.push('\n');
+    section.push_str(&format!("CPU_ERA=\"{}\"\n", preset.id));
+    // A later -cpu overrides the one in the base command
+    section.push_str(&format!("CPU_ERA_ARGS=\"-cpu {}\"\n", preset.cpu));
+    section.push_str(CPU_ERA_MARKER_END);
+    section.push('\n');
+    section
+}
+
+fn remove_cpu_era_section(content: &str) -> String {
+    remove_args_section(content, CPU_ERA_MARKER_START, CPU_ERA_MARKER_END, "$CPU_ERA_ARGS")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpu_era_section_round_trip() {
+        let script = "VM_DIR=\"/vms/t\"\ncase \"$1\" in\n    \"\")\n        qemu-system-i386 -enable-kvm \\\n            -cpu host -m 128M\n        ;;\nesac\n";
+        let preset = find_preset("pentium2").unwrap();
+        let masked = insert_args_section(script, &generate_cpu_era_section(preset), "$CPU_ERA_ARGS");
+
+        assert_eq!(parse_cpu_era(&masked), Some("pentium2".to_string()));
+        assert!(masked.contains("CPU_ERA_ARGS=\"-cpu pentium2\""));
+        assert!(masked.contains("-m 128M $CPU_ERA_ARGS"));
+
+        let restored = remove_cpu_era_section(&masked);
+        assert_eq!(parse_cpu_era(&restored), None);
+        assert!(!restored.contains("CPU_ERA"));
+    }
+}
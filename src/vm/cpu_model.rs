@@ -0,0 +1,164 @@
+//! CPU model and flags
+//!
+//! The base command's `-cpu` picks the processor the guest sees. Models come
+//! from the emulator's `-cpu help`, and `host` passes the host CPU through
+//! under KVM. Flags follow the model: `+feat`/`-feat` add or hide CPUID
+//! features, and `kvm=off` hides the KVM signature that some guests (and
+//! older NVIDIA drivers) refuse to run under.
+
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::path::Path;
+
+use crate::vm::lifecycle::insert_base_arg;
+
+/// A CPU flag that can be toggled from the picker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuFlag {
+    /// Text appended to the `-cpu` value
+    pub token: &'static str,
+    /// Display name
+    pub name: &'static str,
+    /// Short description for the picker
+    pub description: &'static str,
+}
+
+/// Flags offered as toggles
+pub const CPU_FLAGS: &[CpuFlag] = &[
+    CpuFlag {
+        token: "-hypervisor",
+        name: "Hide hypervisor bit",
+        description: "For guests and installers that refuse to run in a VM",
+    },
+    CpuFlag {
+        token: "kvm=off",
+        name: "Hide KVM signature",
+        description: "NVIDIA drivers before 465 (Code 43 with passthrough)",
+    },
+    CpuFlag {
+        token: "+invtsc",
+        name: "Invariant TSC",
+        description: "Stable guest timekeeping; prevents live migration",
+    },
+    CpuFlag {
+        token: "+topoext",
+        name: "AMD topology extensions",
+        description: "Lets guests see SMT threads on AMD hosts",
+    },
+    CpuFlag {
+        token: "+vmx",
+        name: "Nested VMX",
+        description: "Run a hypervisor inside the guest (Intel hosts)",
+    },
+];
+
+static RE_CPU_ARG: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"\s-cpu\s+[^\s\\"]+"#).expect("Invalid regex: RE_CPU_ARG")
+});
+
+/// Shell variable assignments (managed sections carry their own `-cpu`)
+static RE_ASSIGNMENT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*[A-Za-z_][A-Za-z0-9_]*=").expect("Invalid regex: RE_ASSIGNMENT")
+});
+
+/// A `-cpu` value split into model and flags
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpuSpec {
+    pub model: String,
+    pub flags: Vec<String>,
+}
+
+impl CpuSpec {
+    /// Split a `-cpu` value (e.g. "host,-hypervisor,kvm=off")
+    pub fn parse(value: &str) -> Self {
+        let mut parts = value.split(',').filter(|p| !p.is_empty());
+        Self {
+            model: parts.next().unwrap_or_default().to_string(),
+            flags: parts.map(str::to_string).collect(),
+        }
+    }
+
+    /// The `-cpu` value
+    pub fn to_arg(&self) -> String {
+        std::iter::once(self.model.as_str())
+            .chain(self.flags.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// Set the base command's CPU model (None leaves QEMU's default)
+pub fn set_cpu_model(script_path: &Path, spec: Option<&CpuSpec>) -> Result<()> {
+    let content = std::fs::read_to_string(script_path).context("Failed to read launch.sh")?;
+    let new_content = rewrite_cpu(&content, spec)?;
+    std::fs::write(script_path, new_content).context("Failed to write launch.sh")?;
+    Ok(())
+}
+
+fn rewrite_cpu(content: &str, spec: Option<&CpuSpec>) -> Result<String> {
+    let is_command_line =
+        |line: &str| !line.trim_start().starts_with('#') && !RE_ASSIGNMENT.is_match(line);
+
+    let has_cpu = content.lines().any(|l| is_command_line(l) && RE_CPU_ARG.is_match(l));
+    if !has_cpu {
+        return match spec {
+            Some(spec) => insert_base_arg(content, &format!("-cpu {}", spec.to_arg())),
+            None => Ok(content.to_string()),
+        };
+    }
+
+    let replacement = spec.map(|s| format!(" -cpu {}", s.to_arg())).unwrap_or_default();
+    let mut result = String::new();
+    for line in content.lines() {
+        if is_command_line(line) && RE_CPU_ARG.is_match(line) {
+            let rewritten = RE_CPU_ARG.replace_all(line, replacement.as_str());
+            // Drop a line that only held the -cpu argument
+            if rewritten.trim().is_empty() || rewritten.trim() == "\\" {
+                continue;
+            }
+            result.push_str(&rewritten);
+        } else {
+            result.push_str(line);
+        }
+        result.push('\n');
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = "CPU_ERA_ARGS=\"-cpu pentium\"\nqemu-system-x86_64 \\\n        -machine q35,accel=kvm \\\n        -cpu host \\\n        -m 4G $CPU_ERA_ARGS\n";
+
+    #[test]
+    fn test_cpu_spec_flags() {
+        let spec = CpuSpec::parse("host,-hypervisor,kvm=off");
+        assert_eq!(spec.model, "host");
+        assert_eq!(spec.flags, vec!["-hypervisor", "kvm=off"]);
+        assert_eq!(spec.to_arg(), "host,-hypervisor,kvm=off");
+        assert_eq!(CpuSpec::parse("max").to_arg(), "max");
+    }
+
+    #[test]
+    fn test_rewrite_cpu() {
+        let spec = CpuSpec::parse("Skylake-Client,kvm=off");
+        let replaced = rewrite_cpu(SCRIPT, Some(&spec)).unwrap();
+        assert!(replaced.contains("        -cpu Skylake-Client,kvm=off \\\n"));
+        assert!(replaced.starts_with("CPU_ERA_ARGS=\"-cpu pentium\"\n"));
+
+        let removed = rewrite_cpu(SCRIPT, None).unwrap();
+        assert_eq!(
+            removed,
+            "CPU_ERA_ARGS=\"-cpu pentium\"\nqemu-system-x86_64 \\\n        -machine q35,accel=kvm \\\n        -m 4G $CPU_ERA_ARGS\n"
+        );
+
+        let inserted = rewrite_cpu(&removed, Some(&CpuSpec::parse("max"))).unwrap();
+        assert!(inserted.contains("qemu-system-x86_64 \\\n        -cpu max \\\n        -machine"));
+
+        let one_line = rewrite_cpu("qemu-system-i386 -m 64M\n", Some(&CpuSpec::parse("486"))).unwrap();
+        assert_eq!(one_line, "qemu-system-i386 -cpu 486 -m 64M\n");
+    }
+}
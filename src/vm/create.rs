@@ -9,7 +9,7 @@ use std::path::{Path, PathBuf};
 
 /// Shell-escape a string for safe interpolation in bash scripts.
 /// This handles special characters that could cause command injection.
-fn shell_escape(s: &str) -> String {
+pub(crate) fn shell_escape(s: &str) -> String {
     // If the string contains only safe characters, return as-is
     if s.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == '.' || c == '/') {
         return s.to_string();
@@ -22,11 +22,23 @@ fn shell_escape(s: &str) -> String {
 }
 
 use crate::app::{CreateWizardState, DiskAction, WizardQemuConfig};
+use crate::vm::backend::{self, Backend};
 use crate::commands::qemu_img;
-use crate::vm::qemu_config::{PortForward, PortProtocol};
+use crate::commands::qemu_system::Accelerator;
+use crate::commands::tap_setup::DEFAULT_TAP_NAME;
+use crate::vm::cloud_init::{add_seed_to_script, create_seed_iso};
+use crate::vm::disk_ops::copy_disk;
+use crate::vm::firmware::{
+    default_edk2, default_uboot, detect_edk2, detect_ovmf, detect_uboot, emulator_arch, OvmfFirmware,
+};
+use crate::vm::qemu_config::{parse_resolution, DiskFormat, PortForward, PortProtocol};
+use crate::vm::unattended::{add_unattended_to_script, prepare_unattended};
+
+/// Per-VM copy of the UEFI variables, referenced as `$OVMF_VARS` in launch.sh
+const OVMF_VARS_FILE: &str = "OVMF_VARS.fd";
 
 /// Generate a random UUID for SMBIOS
-fn generate_uuid() -> String {
+pub(crate) fn generate_uuid() -> String {
     use std::time::{SystemTime, UNIX_EPOCH};
 
     // Use time-based pseudo-random generation
@@ -82,96 +94,10 @@ fn generate_serial() -> String {
     serial
 }
 
-/// Known OVMF firmware paths across different Linux distributions
-const OVMF_SEARCH_PATHS: &[&str] = &[
-    // Arch Linux (current naming with .4m suffix for 4MB variant)
-    "/usr/share/edk2/x64/OVMF_CODE.4m.fd",
-    "/usr/share/edk2-ovmf/x64/OVMF_CODE.4m.fd",
-    "/usr/share/OVMF/x64/OVMF_CODE.4m.fd",
-    "/usr/share/ovmf/x64/OVMF_CODE.4m.fd",
-    // Arch Linux (legacy naming without .4m)
-    "/usr/share/edk2-ovmf/x64/OVMF_CODE.fd",
-    "/usr/share/edk2/x64/OVMF_CODE.fd",
-    // Debian/Ubuntu
-    "/usr/share/OVMF/OVMF_CODE.fd",
-    "/usr/share/OVMF/OVMF_CODE_4M.fd",
-    // Fedora/RHEL/CentOS
-    "/usr/share/edk2/ovmf/OVMF_CODE.fd",
-    "/usr/share/edk2/ovmf/OVMF_CODE.cc.fd",
-    // openSUSE
-    "/usr/share/qemu/ovmf-x86_64.bin",
-    "/usr/share/qemu/ovmf-x86_64-code.bin",
-    // NixOS
-    "/run/libvirt/nix-ovmf/OVMF_CODE.fd",
-    // Generic/fallback paths
-    "/usr/share/ovmf/OVMF_CODE.fd",
-    "/usr/share/qemu/OVMF_CODE.fd",
-    "/usr/share/ovmf/x64/OVMF_CODE.fd",
-];
-
-/// Find the OVMF_CODE.fd firmware file by checking known paths
-fn find_ovmf_code_path() -> Option<String> {
-    for path in OVMF_SEARCH_PATHS {
-        if Path::new(path).exists() {
-            return Some(path.to_string());
-        }
-    }
-    None
-}
-
-/// Known OVMF Secure Boot firmware paths across different Linux distributions
-const OVMF_SECBOOT_SEARCH_PATHS: &[&str] = &[
-    // Arch Linux
-    "/usr/share/edk2/x64/OVMF_CODE.secboot.4m.fd",
-    "/usr/share/OVMF/x64/OVMF_CODE.secboot.4m.fd",
-    "/usr/share/ovmf/x64/OVMF_CODE.secboot.4m.fd",
-    "/usr/share/edk2-ovmf/x64/OVMF_CODE.secboot.fd",
-    // Debian/Ubuntu
-    "/usr/share/OVMF/OVMF_CODE_4M.secboot.fd",
-    "/usr/share/OVMF/OVMF_CODE_4M.ms.fd",
-    "/usr/share/OVMF/OVMF_CODE.secboot.fd",
-    "/usr/share/OVMF/OVMF_CODE.ms.fd",
-    // Fedora/RHEL
-    "/usr/share/edk2/ovmf/OVMF_CODE.secboot.fd",
-    // Generic/fallback
-    "/usr/share/ovmf/OVMF_CODE.secboot.fd",
-    "/usr/share/qemu/OVMF_CODE.secboot.fd",
-];
-
-/// Find the OVMF Secure Boot firmware file by checking known paths
-fn find_ovmf_secboot_code_path() -> Option<String> {
-    for path in OVMF_SECBOOT_SEARCH_PATHS {
-        if Path::new(path).exists() {
-            return Some(path.to_string());
-        }
-    }
-    None
-}
-
-/// Find OVMF_VARS template with pre-enrolled Secure Boot keys (Microsoft keys)
-fn find_ovmf_secboot_vars_template() -> Option<String> {
-    let search_paths = [
-        // Debian/Ubuntu (pre-enrolled Microsoft keys)
-        "/usr/share/OVMF/OVMF_VARS_4M.ms.fd",
-        "/usr/share/OVMF/OVMF_VARS.ms.fd",
-        // Fedora/RHEL
-        "/usr/share/edk2/ovmf/OVMF_VARS.secboot.fd",
-    ];
-
-    for path in search_paths {
-        if Path::new(path).exists() {
-            return Some(path.to_string());
-        }
-    }
-    // Fall back to standard VARS template
-    find_ovmf_vars_template()
-}
-
 /// Result of creating a new VM
 #[derive(Debug)]
 pub struct CreatedVm {
-    /// Path to the VM directory - reserved for future use
-    #[allow(dead_code)]
+    /// Path to the VM directory
     pub path: PathBuf,
     /// Path to the launch script
     pub launch_script: PathBuf,
@@ -191,7 +117,16 @@ pub fn create_vm(library_path: &Path, state: &CreateWizardState) -> Result<Creat
     }
 
     // Validate disk configuration
-    if state.use_existing_disk {
+    let remote_disk = match (state.use_existing_disk, state.remote_disk_url.as_deref()) {
+        (true, Some(url)) => Some(crate::vm::remote_disk::validate_remote_url(url)?),
+        _ => None,
+    };
+    if remote_disk.is_some() && state.qemu_config.backend != Backend::Qemu {
+        bail!("{} cannot use a network disk", state.qemu_config.backend.label());
+    }
+    if remote_disk.is_some() {
+        // The disk stays on its server
+    } else if state.use_existing_disk {
         if state.existing_disk_path.is_none() {
             bail!("No existing disk selected");
         }
@@ -206,9 +141,24 @@ pub fn create_vm(library_path: &Path, state: &CreateWizardState) -> Result<Creat
     // Create VM directory
     let vm_dir = create_vm_directory(library_path, &state.folder_name)?;
 
+    // 86Box and DOSBox-X VMs get their emulator's config file instead of QEMU arguments
+    if state.qemu_config.backend != Backend::Qemu {
+        return create_backend_vm(&vm_dir, state);
+    }
+
+    // Imported images keep their format unless converted to qcow2
+    let disk_format = match state.existing_disk_path.as_deref() {
+        Some(path) if state.use_existing_disk && state.existing_disk_action != DiskAction::Convert => {
+            existing_disk_format(path)
+        }
+        _ => "qcow2".to_string(),
+    };
+
     // Create or copy/move disk image
-    let disk_filename = format!("{}.qcow2", state.folder_name);
-    let disk_path = if state.use_existing_disk {
+    let disk_filename = format!("{}.{}", state.folder_name, disk_extension(&disk_format));
+    let disk_path = if let Some(ref remote) = remote_disk {
+        PathBuf::from(&remote.url)
+    } else if state.use_existing_disk {
         handle_existing_disk(
             &vm_dir,
             &disk_filename,
@@ -216,21 +166,86 @@ pub fn create_vm(library_path: &Path, state: &CreateWizardState) -> Result<Creat
             &state.existing_disk_action,
         )?
     } else {
-        create_disk_image(&vm_dir, &disk_filename, state.disk_size_gb)?
+        create_disk_image(&vm_dir, &disk_filename, state.disk_size_gb, &state.disk_tuning)?
     };
 
     // Generate and write launch script with OS-awareness
-    let script_content = generate_launch_script_with_os(
+    let mut script_content = generate_launch_script_with_os(
         &state.vm_name,
         &disk_filename,
         state.iso_path.as_deref(),
         &state.qemu_config,
         state.selected_os.as_deref(),
     );
+
+    if let Some(ref remote) = remote_disk {
+        script_content = crate::vm::remote_disk::use_remote_disk(&script_content, remote);
+    }
+    if disk_format != "qcow2" {
+        script_content = set_disk_format(&script_content, &disk_format);
+    }
+
+    // Cloud images get their first-boot config from a NoCloud seed CD-ROM
+    if state.use_existing_disk {
+        if let Some(ref cloud_init) = state.cloud_init {
+            create_seed_iso(&vm_dir, &state.folder_name, cloud_init)?;
+            script_content = add_seed_to_script(&script_content);
+        }
+    } else if let (Some(unattended), Some(iso)) = (&state.unattended, &state.iso_path) {
+        // Fresh installs can be driven by a kickstart/preseed answer file
+        let section = prepare_unattended(&vm_dir, iso, unattended)?;
+        script_content = add_unattended_to_script(&script_content, &section);
+    }
+
+    // A blank disk boots its install ISO until the OS is installed
+    if !state.use_existing_disk && state.iso_path.is_some() {
+        script_content = crate::vm::first_boot::add_first_boot_section(&script_content);
+    }
+
+    // A QMP socket lets USB devices be hot-plugged while the VM runs
+    script_content = crate::vm::qmp::add_qmp_section(&script_content);
+
+    // A stable UUID and MAC keep Windows activation and DHCP leases put
+    let identity = crate::vm::identity::identity_for_script(&script_content);
+    script_content = crate::vm::identity::apply_identity(&script_content, &identity);
+
     let launch_script_path = write_launch_script(&vm_dir, &script_content)?;
 
+    // UEFI VMs get their own NVRAM from the matching VARS template
+    if state.qemu_config.uefi || is_windows_11(state.selected_os.as_deref()) {
+        create_ovmf_vars(&vm_dir, &ovmf_firmware(&state.qemu_config.emulator, state.qemu_config.secure_boot))?;
+    }
+
+    // spice-app displays get clipboard and USB redirection channels by default
+    if state.qemu_config.display == "spice-app" {
+        let spice = crate::vm::spice::SpiceConfig::new(crate::vm::spice::SpiceMode::App);
+        crate::vm::spice::set_spice(&launch_script_path, Some(&spice))?;
+    }
+
+    // VNC displays get a display number no other VM in the library uses
+    if state.qemu_config.display == "vnc" {
+        let used: Vec<u16> = crate::vm::discover_vms(library_path)?
+            .iter()
+            .filter_map(|vm| vm.config.vnc_display)
+            .collect();
+        let display = crate::vm::vnc::allocate_display(&used)?;
+        crate::vm::vnc::set_vnc(&launch_script_path, Some(display))?;
+    }
+
+    // Retro guests can send serial MIDI to a host synth
+    if let Some(synth) = crate::vm::midi::find_synth(&state.qemu_config.midi_synth) {
+        crate::vm::midi::set_midi(&launch_script_path, Some(synth))?;
+    }
+
+    // Classic Macs boot a ROM dump (Quadra) or OpenBIOS with -prom-env settings
+    if let Some(ref machine) = state.qemu_config.machine {
+        if let Some(firmware) = crate::vm::mac_firmware::default_firmware(machine, &state.qemu_config.prom_env) {
+            crate::vm::mac_firmware::set_mac_firmware(&launch_script_path, Some(&firmware))?;
+        }
+    }
+
     // Write VM metadata file with custom display name
-    write_vm_metadata(&vm_dir, &state.vm_name, state.selected_os.as_deref())?;
+    write_vm_metadata(&vm_dir, &state.vm_name, state.selected_os.as_deref(), &identity)?;
 
     Ok(CreatedVm {
         path: vm_dir,
@@ -239,6 +254,49 @@ pub fn create_vm(library_path: &Path, state: &CreateWizardState) -> Result<Creat
     })
 }
 
+/// Create a VM that runs under 86Box or DOSBox-X
+fn create_backend_vm(vm_dir: &Path, state: &CreateWizardState) -> Result<CreatedVm> {
+    let backend = state.qemu_config.backend;
+
+    let (disk, size_mb) = match state.existing_disk_path {
+        Some(ref source) if state.use_existing_disk => {
+            let ext = source.extension().and_then(|e| e.to_str()).unwrap_or("img").to_lowercase();
+            let name = format!("{}.{}", state.folder_name, ext);
+            // Neither emulator reads qcow2, so the image is kept as it is
+            let action = match state.existing_disk_action {
+                DiskAction::Move => DiskAction::Move,
+                _ => DiskAction::Copy,
+            };
+            let path = handle_existing_disk(vm_dir, &name, source, &action)?;
+            let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            (name, size / (1024 * 1024))
+        }
+        _ => (
+            backend::create_disk(backend, vm_dir, &state.folder_name, state.disk_size_gb)?,
+            state.disk_size_gb as u64 * 1024,
+        ),
+    };
+
+    if let Some(config_file) = backend.config_file() {
+        let content = backend::generate_config(backend, &state.qemu_config, &disk, size_mb);
+        fs::write(vm_dir.join(config_file), content)
+            .with_context(|| format!("Failed to write {}", config_file))?;
+    }
+
+    let iso = state.iso_path.as_ref().map(|p| shell_escape(&p.display().to_string()));
+    let script = backend::generate_launch_script(backend, &state.vm_name, iso.as_deref());
+    let identity = crate::vm::identity::identity_for_script(&script);
+    let launch_script_path = write_launch_script(vm_dir, &script)?;
+
+    write_vm_metadata(vm_dir, &state.vm_name, state.selected_os.as_deref(), &identity)?;
+
+    Ok(CreatedVm {
+        path: vm_dir.to_path_buf(),
+        launch_script: launch_script_path,
+        disk_image: vm_dir.join(disk),
+    })
+}
+
 /// Handle an existing disk by copying or moving it to the VM directory
 fn handle_existing_disk(
     vm_dir: &Path,
@@ -250,23 +308,16 @@ fn handle_existing_disk(
 
     match action {
         DiskAction::Copy => {
-            fs::copy(source, &dest)
-                .with_context(|| format!(
-                    "Failed to copy disk from {} to {}",
-                    source.display(),
-                    dest.display()
-                ))?;
+            copy_disk(source, &dest)?;
+        }
+        DiskAction::Convert => {
+            qemu_img::convert_image(source, &dest, "qcow2", false, &qemu_img::Qcow2Options::default())?;
         }
         DiskAction::Move => {
             // Try rename first (works if on same filesystem)
             if fs::rename(source, &dest).is_err() {
                 // Rename failed (likely different filesystem), fall back to copy+delete
-                fs::copy(source, &dest)
-                    .with_context(|| format!(
-                        "Failed to copy disk from {} to {}",
-                        source.display(),
-                        dest.display()
-                    ))?;
+                copy_disk(source, &dest)?;
                 fs::remove_file(source)
                     .with_context(|| format!(
                         "Failed to remove original disk after copying: {}",
@@ -279,13 +330,56 @@ fn handle_existing_disk(
     Ok(dest)
 }
 
+/// QEMU format name of an existing image, from its header or extension
+fn existing_disk_format(path: &Path) -> String {
+    if let Some(format) = qemu_img::detect_disk_format(path) {
+        return format;
+    }
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_lowercase();
+    match DiskFormat::from_extension(&ext) {
+        DiskFormat::Other(ref other) if other == "qcow" => "qcow2".to_string(),
+        DiskFormat::Other(_) => "raw".to_string(),
+        format => format.qemu_name().to_string(),
+    }
+}
+
+/// File extension for a disk of the given QEMU format
+pub(crate) fn disk_extension(format: &str) -> &str {
+    match format {
+        "vpc" => "vhd",
+        "raw" => "img",
+        other => other,
+    }
+}
+
+/// Change the generated `format=qcow2` on the primary disk drive
+fn set_disk_format(content: &str, format: &str) -> String {
+    let mut result = String::new();
+    for line in content.lines() {
+        if line.contains("file=\"$DISK\"") {
+            result.push_str(&line.replace("format=qcow2", &format!("format={}", format)));
+        } else {
+            result.push_str(line);
+        }
+        result.push('\n');
+    }
+    result
+}
+
 /// Write VM metadata file (vm-curator.toml)
-fn write_vm_metadata(vm_dir: &Path, display_name: &str, os_profile: Option<&str>) -> Result<()> {
+fn write_vm_metadata(
+    vm_dir: &Path,
+    display_name: &str,
+    os_profile: Option<&str>,
+    identity: &crate::vm::identity::VmIdentity,
+) -> Result<()> {
     let metadata_path = vm_dir.join("vm-curator.toml");
 
     let mut content = String::new();
     content.push_str("# VM Curator metadata\n\n");
     content.push_str(&format!("display_name = \"{}\"\n", display_name.replace('"', "\\\"")));
+    content.push_str(&format!("uuid = \"{}\"\n", identity.uuid));
+    content.push_str(&format!("mac = \"{}\"\n", identity.mac));
 
     if let Some(profile) = os_profile {
         content.push_str(&format!("os_profile = \"{}\"\n", profile));
@@ -312,11 +406,11 @@ pub fn create_vm_directory(library_path: &Path, folder_name: &str) -> Result<Pat
 }
 
 /// Create a new qcow2 disk image
-pub fn create_disk_image(vm_dir: &Path, filename: &str, size_gb: u32) -> Result<PathBuf> {
+pub fn create_disk_image(vm_dir: &Path, filename: &str, size_gb: u32, tuning: &qemu_img::Qcow2Options) -> Result<PathBuf> {
     let disk_path = vm_dir.join(filename);
     let size_str = format!("{}G", size_gb);
 
-    qemu_img::create_disk(&disk_path, &size_str)
+    qemu_img::create_disk(&disk_path, &size_str, tuning)
         .with_context(|| format!("Failed to create disk image: {}", disk_path.display()))?;
 
     Ok(disk_path)
@@ -407,17 +501,36 @@ trap cleanup EXIT
 "#.to_string()
 }
 
-/// Generate OVMF variables setup for UEFI
-fn generate_ovmf_vars_setup(needs_secboot: bool) -> String {
-    // Find OVMF_VARS template (prefer secboot variant with pre-enrolled keys when needed)
-    let ovmf_vars_template = if needs_secboot {
-        find_ovmf_secboot_vars_template()
-            .unwrap_or_else(|| "/usr/share/OVMF/OVMF_VARS.fd".to_string())
+/// OVMF firmware for a new VM
+///
+/// Falls back to plain UEFI when no Secure Boot build is installed, and to
+/// Debian's paths when no firmware is found at all so the generated script
+/// still shows what to install. ARM and RISC-V guests get EDK2's AAVMF or
+/// RISCV_VIRT builds, which have no Secure Boot variants to choose from.
+fn ovmf_firmware(emulator: &str, secure_boot: bool) -> OvmfFirmware {
+    let arch = emulator_arch(emulator);
+    if arch != "x86_64" {
+        if let Some(firmware) = detect_edk2(arch).or_else(|| default_edk2(arch)) {
+            return firmware;
+        }
+    }
+
+    let detected = if secure_boot {
+        detect_ovmf(true).or_else(|| detect_ovmf(false))
     } else {
-        find_ovmf_vars_template()
-            .unwrap_or_else(|| "/usr/share/OVMF/OVMF_VARS.fd".to_string())
+        detect_ovmf(false)
     };
 
+    detected.unwrap_or_else(|| OvmfFirmware {
+        code: PathBuf::from("/usr/share/OVMF/OVMF_CODE_4M.fd"),
+        vars_template: PathBuf::from("/usr/share/OVMF/OVMF_VARS_4M.fd"),
+        secure_boot: false,
+        enrolled_keys: false,
+    })
+}
+
+/// Generate OVMF variables setup for UEFI
+fn generate_ovmf_vars_setup(firmware: &OvmfFirmware) -> String {
     format!(r#"# UEFI variables (writable copy per VM)
 OVMF_VARS_TEMPLATE="{template}"
 OVMF_VARS="$VM_DIR/OVMF_VARS.fd"
@@ -433,35 +546,42 @@ if [[ ! -f "$OVMF_VARS" ]]; then
     fi
 fi
 
-"#, template = ovmf_vars_template)
+"#, template = firmware.vars_template.display())
 }
 
-/// Find OVMF_VARS template path
-fn find_ovmf_vars_template() -> Option<String> {
-    let search_paths = [
-        // Arch Linux (4M variant for modern UEFI)
-        "/usr/share/edk2/x64/OVMF_VARS.4m.fd",
-        "/usr/share/edk2-ovmf/x64/OVMF_VARS.4m.fd",
-        "/usr/share/OVMF/x64/OVMF_VARS.4m.fd",
-        // Arch Linux (legacy)
-        "/usr/share/edk2-ovmf/x64/OVMF_VARS.fd",
-        "/usr/share/edk2/x64/OVMF_VARS.fd",
-        // Debian/Ubuntu
-        "/usr/share/OVMF/OVMF_VARS.fd",
-        "/usr/share/OVMF/OVMF_VARS_4M.fd",
-        // Fedora/RHEL
-        "/usr/share/edk2/ovmf/OVMF_VARS.fd",
-        // Generic
-        "/usr/share/ovmf/OVMF_VARS.fd",
-        "/usr/share/qemu/OVMF_VARS.fd",
-    ];
-
-    for path in search_paths {
-        if Path::new(path).exists() {
-            return Some(path.to_string());
-        }
+/// Generate the check for direct kernel boot files, which are copied out of
+/// the guest image (e.g. a Raspberry Pi OS boot partition) rather than created
+fn generate_boot_files_check(config: &WizardQemuConfig) -> String {
+    let vars: Vec<&str> = [("\"$KERNEL\"", &config.kernel), ("\"$INITRD\"", &config.initrd), ("\"$DTB\"", &config.dtb)]
+        .into_iter()
+        .filter(|(_, file)| file.is_some())
+        .map(|(var, _)| var)
+        .collect();
+
+    format!(r#"# Direct kernel boot files (copy them from the guest image's boot partition)
+check_boot_files() {{
+    for BOOT_FILE in {files}; do
+        if [[ ! -f "$BOOT_FILE" ]]; then
+            echo "Error: $BOOT_FILE not found"
+            echo "Copy the kernel and device tree from the guest image into $VM_DIR"
+            exit 1
+        fi
+    done
+}}
+
+"#, files = vars.join(" "))
+}
+
+/// Copy the OVMF VARS template into the VM directory so each VM keeps its
+/// own UEFI variables (boot entries, enrolled keys) from the start
+fn create_ovmf_vars(vm_dir: &Path, firmware: &OvmfFirmware) -> Result<()> {
+    let dest = vm_dir.join(OVMF_VARS_FILE);
+    if dest.exists() || !firmware.vars_template.exists() {
+        return Ok(());
     }
-    None
+    fs::copy(&firmware.vars_template, &dest)
+        .with_context(|| format!("Failed to copy UEFI variables from {}", firmware.vars_template.display()))?;
+    Ok(())
 }
 
 /// Generate the launch.sh script content with OS profile awareness
@@ -477,6 +597,8 @@ pub fn generate_launch_script_with_os(
     let is_windows = is_windows_10_or_11(os_profile);
     let needs_tpm = config.tpm || is_windows_11(os_profile);
     let needs_uefi = config.uefi || is_windows_11(os_profile);
+    let firmware = needs_uefi.then(|| ovmf_firmware(&config.emulator, config.secure_boot));
+    let needs_secboot = firmware.as_ref().is_some_and(|fw| fw.secure_boot);
 
     // Shebang and header
     script.push_str("#!/bin/bash\n\n");
@@ -491,7 +613,7 @@ pub fn generate_launch_script_with_os(
     if needs_tpm {
         script.push_str("# TPM 2.0 enabled (requires swtpm package)\n");
     }
-    if needs_tpm && needs_uefi {
+    if needs_secboot {
         script.push_str("# Secure Boot enabled (OVMF secboot + SMM)\n");
     }
     script.push_str("# Generated by vm-curator\n\n");
@@ -506,6 +628,13 @@ pub fn generate_launch_script_with_os(
     } else {
         script.push_str("ISO=\"\"\n");
     }
+    let boot_files = [("KERNEL", &config.kernel), ("INITRD", &config.initrd), ("DTB", &config.dtb)];
+    for (var, file) in boot_files {
+        if let Some(file) = file {
+            script.push_str(&format!("{}=\"$VM_DIR/{}\"\n", var, file));
+        }
+    }
+    let needs_boot_files = boot_files.iter().any(|(_, file)| file.is_some());
     script.push('\n');
 
     // Windows-specific: SMBIOS options
@@ -514,8 +643,8 @@ pub fn generate_launch_script_with_os(
     }
 
     // UEFI setup with writable OVMF_VARS
-    if needs_uefi {
-        script.push_str(&generate_ovmf_vars_setup(needs_tpm));
+    if let Some(ref firmware) = firmware {
+        script.push_str(&generate_ovmf_vars_setup(firmware));
     }
 
     // TPM functions
@@ -523,6 +652,10 @@ pub fn generate_launch_script_with_os(
         script.push_str(&generate_tpm_functions());
     }
 
+    if needs_boot_files {
+        script.push_str(&generate_boot_files_check(config));
+    }
+
     // Help function
     script.push_str("show_help() {\n");
     script.push_str("    echo \"Usage: $0 [OPTIONS]\"\n");
@@ -551,6 +684,9 @@ pub fn generate_launch_script_with_os(
     if needs_tpm {
         script.push_str("        start_tpm\n");
     }
+    if needs_boot_files {
+        script.push_str("        check_boot_files\n");
+    }
 
     script.push_str(&format!("        {}\n", install_cmd));
     script.push_str("        ;;\n");
@@ -564,6 +700,9 @@ pub fn generate_launch_script_with_os(
     if needs_tpm {
         script.push_str("        start_tpm\n");
     }
+    if needs_boot_files {
+        script.push_str("        check_boot_files\n");
+    }
 
     // Build command for custom ISO (will substitute $2)
     let cdrom_cmd = build_qemu_command_with_os(config, disk_filename, true, Some("\"$2\""), os_profile);
@@ -579,6 +718,9 @@ pub fn generate_launch_script_with_os(
     if needs_tpm {
         script.push_str("        start_tpm\n");
     }
+    if needs_boot_files {
+        script.push_str("        check_boot_files\n");
+    }
 
     script.push_str(&format!("        {}\n", base_cmd));
     script.push_str("        ;;\n");
@@ -605,22 +747,29 @@ fn build_qemu_command_with_os(
     let is_windows = is_windows_10_or_11(os_profile);
     let needs_tpm = config.tpm || is_windows_11(os_profile);
     let needs_uefi = config.uefi || is_windows_11(os_profile);
+    let firmware = needs_uefi.then(|| ovmf_firmware(&config.emulator, config.secure_boot));
+    let needs_secboot = firmware.as_ref().is_some_and(|fw| fw.secure_boot);
+    let arch = emulator_arch(&config.emulator);
+    // ARM and RISC-V virt boards have no IDE or VGA; devices go on PCI
+    let virt_board = arch != "x86_64" && config.machine.as_deref() == Some("virt");
 
     // Emulator
     args.push(config.emulator.clone());
 
-    // KVM acceleration
-    if config.enable_kvm {
-        args.push("-enable-kvm".to_string());
+    // Hardware acceleration: KVM on Linux, WHPX on Windows
+    let accel = config.enable_kvm.then(Accelerator::host);
+    match accel {
+        Some(Accelerator::Kvm) => args.push("-enable-kvm".to_string()),
+        Some(Accelerator::Whpx) if config.machine.is_none() => args.push("-accel whpx".to_string()),
+        _ => {}
     }
 
     // Machine type (escaped to prevent injection)
     if let Some(ref machine) = config.machine {
         let safe_machine = shell_escape(machine);
-        let needs_secboot = needs_tpm && needs_uefi;
         let mut machine_opts = vec![safe_machine.to_string()];
-        if config.enable_kvm {
-            machine_opts.push("accel=kvm".to_string());
+        if let Some(accel) = accel {
+            machine_opts.push(format!("accel={}", accel.name()));
         }
         if needs_secboot {
             machine_opts.push("smm=on".to_string());
@@ -629,7 +778,12 @@ fn build_qemu_command_with_os(
     }
 
     // CPU (escaped to prevent injection)
+    // (WHPX has no host passthrough model; max is the closest)
     if let Some(ref cpu_model) = config.cpu_model {
+        let cpu_model = match cpu_model.as_str() {
+            "host" if accel == Some(Accelerator::Whpx) => "max",
+            model => model,
+        };
         args.push(format!("-cpu {}", shell_escape(cpu_model)));
     }
 
@@ -648,20 +802,11 @@ fn build_qemu_command_with_os(
     }
 
     // UEFI boot with writable OVMF_VARS
-    if needs_uefi {
-        let needs_secboot = needs_tpm;
-        let ovmf_code = if needs_secboot {
-            find_ovmf_secboot_code_path()
-                .or_else(find_ovmf_code_path)
-                .unwrap_or_else(|| "/usr/share/OVMF/OVMF_CODE.fd".to_string())
-        } else {
-            find_ovmf_code_path()
-                .unwrap_or_else(|| "/usr/share/OVMF/OVMF_CODE.fd".to_string())
-        };
+    if let Some(ref firmware) = firmware {
         // OVMF_CODE is read-only
         args.push(format!(
             "-drive if=pflash,format=raw,readonly=on,file={}",
-            ovmf_code
+            shell_escape(&firmware.code.display().to_string())
         ));
         // OVMF_VARS is writable (uses variable set up in script)
         args.push("-drive if=pflash,format=raw,file=\"$OVMF_VARS\"".to_string());
@@ -670,6 +815,26 @@ fn build_qemu_command_with_os(
         if needs_secboot {
             args.push("-global driver=cfi.pflash01,property=secure,value=on".to_string());
         }
+    } else if config.firmware.as_deref() == Some("u-boot") && config.kernel.is_none() {
+        // RISC-V runs S-mode U-Boot as the kernel of QEMU's built-in OpenSBI
+        if let Some(uboot) = detect_uboot(arch).or_else(|| default_uboot(arch)) {
+            let opt = if arch == "riscv64" { "-kernel" } else { "-bios" };
+            args.push(format!("{} {}", opt, shell_escape(&uboot.display().to_string())));
+        }
+    }
+
+    // Direct kernel boot (files set up as variables at the top of the script)
+    if config.kernel.is_some() {
+        args.push("-kernel \"$KERNEL\"".to_string());
+    }
+    if config.initrd.is_some() {
+        args.push("-initrd \"$INITRD\"".to_string());
+    }
+    if config.dtb.is_some() {
+        args.push("-dtb \"$DTB\"".to_string());
+    }
+    if let Some(ref append) = config.kernel_append {
+        args.push(format!("-append {}", shell_escape(append)));
     }
 
     // Disk (interface escaped to prevent injection)
@@ -679,7 +844,13 @@ fn build_qemu_command_with_os(
     ));
 
     // CD-ROM (for install mode)
-    if with_cdrom {
+    if with_cdrom && virt_board {
+        // No IDE on virt boards: the disc goes on virtio-scsi and boots first
+        let iso_ref = custom_iso.unwrap_or("\"$ISO\"");
+        args.push("-device virtio-scsi-pci,id=scsi0".to_string());
+        args.push(format!("-drive file={},media=cdrom,if=none,id=cdrom0", iso_ref));
+        args.push("-device scsi-cd,drive=cdrom0,bootindex=0".to_string());
+    } else if with_cdrom {
         let iso_ref = custom_iso.unwrap_or("\"$ISO\"");
         args.push(format!(
             "-drive file={},media=cdrom,index=1",
@@ -690,23 +861,35 @@ fn build_qemu_command_with_os(
     }
 
     // VGA / Graphics (escaped to prevent injection)
-    if config.gl_acceleration && config.vga == "virtio" {
+    let resolution = config.resolution.as_deref().and_then(parse_resolution);
+    if arch != "x86_64" && config.vga == "virtio" {
+        // Non-PC boards have no VGA; virtio-gpu is the display
+        let device = if config.gl_acceleration { "virtio-gpu-gl-pci" } else { "virtio-gpu-pci" };
+        args.push(format!("-device {}", device));
+    } else if config.gl_acceleration && config.vga == "virtio" {
         // Use virtio-vga-gl for 3D acceleration
         args.push("-device virtio-vga-gl".to_string());
+    } else if let (Some((xres, yres)), "std") = (resolution, config.vga.as_str()) {
+        // Advertise the era-correct resolution via EDID
+        args.push(format!("-device VGA,edid=on,xres={},yres={}", xres, yres));
+    } else if let (Some((xres, yres)), "qxl") = (resolution, config.vga.as_str()) {
+        args.push(format!("-device qxl-vga,xres={},yres={}", xres, yres));
     } else {
         args.push(format!("-vga {}", shell_escape(&config.vga)));
     }
 
-    // Display (with GL if enabled, escaped to prevent injection)
+    // Display (with GL if enabled, escaped to prevent injection).
+    // VNC runs headless; its server comes from the managed VNC section.
+    let display = if config.display == "vnc" { "none" } else { config.display.as_str() };
     if config.gl_acceleration {
-        args.push(format!("-display {},gl=on", shell_escape(&config.display)));
+        args.push(format!("-display {},gl=on", shell_escape(display)));
     } else {
-        args.push(format!("-display {}", shell_escape(&config.display)));
+        args.push(format!("-display {}", shell_escape(display)));
     }
 
     // Audio backend (must be declared before devices that use it)
     if !config.audio.is_empty() {
-        args.push("-audiodev pa,id=audio0".to_string());
+        args.push(format!("-audiodev {},id=audio0", shell_escape(&config.audio_backend)));
     }
 
     // Audio devices (known safe values from profiles, but escape for safety)
@@ -718,7 +901,7 @@ fn build_qemu_command_with_os(
                 args.push(format!("-device {},audiodev=audio0", shell_escape(audio)));
             }
             "ac97" => args.push("-device AC97,audiodev=audio0".to_string()),
-            "sb16" => args.push("-device sb16,audiodev=audio0".to_string()),
+            "sb16" => args.push(format!("-device sb16{},audiodev=audio0", config.sb16.device_properties())),
             _ => {
                 // Unknown audio device - escape it
                 args.push(format!("-device {},audiodev=audio0", shell_escape(audio)));
@@ -747,6 +930,11 @@ fn build_qemu_command_with_os(
                 args.push(format!("-netdev bridge,id=net0,br={}", shell_escape(br)));
                 args.push(format!("-device {},netdev=net0", net_device));
             }
+            "tap" => {
+                let tap = config.tap_name.as_deref().unwrap_or(DEFAULT_TAP_NAME);
+                args.push(format!("-netdev tap,id=net0,ifname={},script=no,downscript=no", shell_escape(tap)));
+                args.push(format!("-device {},netdev=net0", net_device));
+            }
             _ => {
                 // User/SLIRP (default)
                 let mut netdev = "-netdev user,id=net0".to_string();
@@ -763,9 +951,11 @@ fn build_qemu_command_with_os(
         }
     }
 
-    // USB tablet for mouse
-    if config.usb_tablet {
-        args.push("-usb".to_string());
+    // USB controller, then the tablet for the mouse (needs a controller)
+    let usb_controller = usb_controller_args(&config.usb_controller);
+    let has_usb = !usb_controller.is_empty();
+    args.extend(usb_controller);
+    if config.usb_tablet && has_usb {
         args.push("-device usb-tablet".to_string());
     }
 
@@ -774,6 +964,11 @@ fn build_qemu_command_with_os(
         args.push("-rtc base=localtime".to_string());
     }
 
+    // Guest agent channel (virtio-serial)
+    if config.guest_agent {
+        args.extend(crate::vm::guest_agent::qemu_args());
+    }
+
     // TPM 2.0 (if enabled, uses socket set up by start_tpm function)
     if needs_tpm {
         args.push("-chardev socket,id=chrtpm,path=\"$TPM_DIR/swtpm-sock\"".to_string());
@@ -791,38 +986,73 @@ fn build_qemu_command_with_os(
     args.join(" \\\n        ")
 }
 
+/// QEMU arguments for a USB controller (none, uhci, ehci, xhci)
+///
+/// The controller's bus is always `usb.0`, which USB passthrough attaches to.
+/// EHCI gets UHCI companions so full- and low-speed devices keep working.
+pub fn usb_controller_args(controller: &str) -> Vec<String> {
+    match controller {
+        "uhci" => vec!["-device piix3-usb-uhci,id=usb".to_string()],
+        "ehci" => vec![
+            "-device ich9-usb-ehci1,id=usb".to_string(),
+            "-device ich9-usb-uhci1,masterbus=usb.0,firstport=0".to_string(),
+            "-device ich9-usb-uhci2,masterbus=usb.0,firstport=2".to_string(),
+            "-device ich9-usb-uhci3,masterbus=usb.0,firstport=4".to_string(),
+        ],
+        "xhci" => vec!["-device qemu-xhci,id=usb".to_string()],
+        _ => Vec::new(),
+    }
+}
+
 /// Write the launch script to disk and make it executable
 pub fn write_launch_script(vm_dir: &Path, content: &str) -> Result<PathBuf> {
-    use std::os::unix::fs::PermissionsExt;
-
     let script_path = vm_dir.join("launch.sh");
 
     fs::write(&script_path, content)
         .with_context(|| format!("Failed to write launch script: {}", script_path.display()))?;
 
     // Make executable (chmod +x)
-    let mut perms = fs::metadata(&script_path)?.permissions();
-    perms.set_mode(0o755);
-    fs::set_permissions(&script_path, perms)
+    crate::fs::set_mode(&script_path, 0o755)
         .with_context(|| format!("Failed to set permissions on: {}", script_path.display()))?;
 
     Ok(script_path)
 }
 
+/// Folder (inside the VM directory) exported by the SLIRP SMB share
+pub const SMB_SHARE_DIR: &str = "share";
+
 /// Update network arguments in an existing launch.sh script
+///
+/// `interface` is the bridge (bridge backend) or tap device (tap backend).
+/// `smb_share` exports `SMB_SHARE_DIR` through SLIRP (user backend only).
 pub fn update_network_in_script(
     vm_path: &Path,
     model: &str,
     backend: &str,
-    bridge_name: Option<&str>,
+    interface: Option<&str>,
     port_forwards: &[PortForward],
+    smb_share: bool,
 ) -> Result<()> {
     let script_path = vm_path.join("launch.sh");
     let content = std::fs::read_to_string(&script_path)
         .with_context(|| format!("Failed to read launch script: {}", script_path.display()))?;
 
+    // SMB shared folders are served on the user netdev, which must stay
+    let smb_folders = content.contains(crate::vm::lifecycle::SMB_SHARES_NETDEV);
+    if smb_folders && (backend != "user" || model == "none") {
+        bail!("Shared folders are exported over SMB, which needs the user network backend; change their method first");
+    }
+    if smb_folders && smb_share {
+        bail!("Shared folders are already exported over SMB; add the share folder there instead");
+    }
+
     // Build new network arguments
-    let new_net_args = generate_network_args(model, backend, bridge_name, port_forwards);
+    let new_net_args = generate_network_args(model, backend, interface, port_forwards, smb_share);
+    if smb_share && backend == "user" {
+        let share_dir = vm_path.join(SMB_SHARE_DIR);
+        std::fs::create_dir_all(&share_dir)
+            .with_context(|| format!("Failed to create SMB share folder: {}", share_dir.display()))?;
+    }
 
     // Remove existing network lines and replace
     let mut new_lines = Vec::new();
@@ -834,8 +1064,8 @@ pub fn update_network_in_script(
         let line = lines[i];
         let trimmed = line.trim();
 
-        // Skip comment lines
-        if trimmed.starts_with('#') {
+        // Skip comment lines and (in older scripts) the SMB shared folders' second NIC
+        if trimmed.starts_with('#') || trimmed.starts_with("SHARED_FOLDERS_ARGS=") {
             new_lines.push(line.to_string());
             i += 1;
             continue;
@@ -876,6 +1106,11 @@ pub fn update_network_in_script(
     }
 
     let new_content = new_lines.join("\n");
+    // Keep the VM's own MAC on the regenerated NIC
+    let mut new_content = crate::vm::identity::add_nic_mac(&new_content);
+    if smb_folders {
+        new_content = crate::vm::lifecycle::attach_smb_shares(&new_content)?;
+    }
     // Ensure trailing newline
     let new_content = if new_content.ends_with('\n') {
         new_content
@@ -893,8 +1128,9 @@ pub fn update_network_in_script(
 fn generate_network_args(
     model: &str,
     backend: &str,
-    bridge_name: Option<&str>,
+    interface: Option<&str>,
     port_forwards: &[PortForward],
+    smb_share: bool,
 ) -> Vec<String> {
     if model == "none" {
         return Vec::new();
@@ -916,13 +1152,21 @@ fn generate_network_args(
             args.push(format!("        -device {},netdev=net0 \\", net_device));
         }
         "bridge" => {
-            let br = bridge_name.unwrap_or("qemubr0");
+            let br = interface.unwrap_or("qemubr0");
             args.push(format!("        -netdev bridge,id=net0,br={} \\", shell_escape(br)));
             args.push(format!("        -device {},netdev=net0 \\", net_device));
         }
+        "tap" => {
+            let tap = interface.unwrap_or(DEFAULT_TAP_NAME);
+            args.push(format!("        -netdev tap,id=net0,ifname={},script=no,downscript=no \\", shell_escape(tap)));
+            args.push(format!("        -device {},netdev=net0 \\", net_device));
+        }
         _ => {
             // User/SLIRP
             let mut netdev = "        -netdev user,id=net0".to_string();
+            if smb_share {
+                netdev.push_str(&format!(",smb=\"$VM_DIR/{}\"", SMB_SHARE_DIR));
+            }
             for pf in port_forwards {
                 let proto = match pf.protocol {
                     PortProtocol::Tcp => "tcp",
@@ -992,28 +1236,53 @@ mod tests {
         assert!(script.contains("--cdrom"));
     }
 
+    #[test]
+    fn test_set_disk_format() {
+        let script = "DISK=\"$VM_DIR/xp.vhd\"\nqemu-system-i386 \\\n    -drive file=\"$DISK\",format=qcow2,if=ide,index=0,media=disk \\\n    -m 512M\n";
+        let result = set_disk_format(script, "vpc");
+        assert!(result.contains("-drive file=\"$DISK\",format=vpc,if=ide,index=0,media=disk \\"));
+        assert_eq!(disk_extension("vpc"), "vhd");
+        assert_eq!(disk_extension("raw"), "img");
+        assert_eq!(disk_extension("vmdk"), "vmdk");
+    }
+
     #[test]
     fn test_build_qemu_command_basic() {
         let config = WizardQemuConfig {
             emulator: "qemu-system-x86_64".to_string(),
+            backend: Default::default(),
             memory_mb: 2048,
             cpu_cores: 2,
             cpu_model: Some("host".to_string()),
             machine: Some("q35".to_string()),
             vga: "std".to_string(),
+            resolution: None,
             audio: vec![],
+            audio_backend: "pa".to_string(),
+            midi_synth: "none".to_string(),
+            sb16: Default::default(),
             network_model: "e1000".to_string(),
             disk_interface: "ide".to_string(),
             enable_kvm: true,
             uefi: false,
+            secure_boot: false,
+            firmware: None,
+            kernel: None,
+            initrd: None,
+            dtb: None,
+            kernel_append: None,
+            prom_env: vec![],
             tpm: false,
             rtc_localtime: false,
             usb_tablet: true,
+            usb_controller: "xhci".to_string(),
+            guest_agent: false,
             display: "gtk".to_string(),
             gl_acceleration: false,
             network_backend: "user".to_string(),
             port_forwards: vec![],
             bridge_name: None,
+            tap_name: None,
             extra_args: vec![],
         };
 
@@ -1026,8 +1295,58 @@ mod tests {
         assert!(cmd.contains("-vga std"));
         assert!(cmd.contains("-display gtk"));
         assert!(cmd.contains("-device e1000"));
-        assert!(cmd.contains("-usb"));
+        assert!(cmd.contains("-device qemu-xhci,id=usb"));
+        assert!(cmd.contains("-device usb-tablet"));
+    }
+
+    #[test]
+    fn test_build_qemu_command_usb_controller() {
+        let mut config = WizardQemuConfig {
+            usb_controller: "ehci".to_string(),
+            ..Default::default()
+        };
+        let cmd = build_qemu_command_with_os(&config, "disk.qcow2", false, None, None);
+        assert!(cmd.contains("-device ich9-usb-ehci1,id=usb"));
+        assert!(cmd.contains("-device ich9-usb-uhci1,masterbus=usb.0,firstport=0"));
         assert!(cmd.contains("-device usb-tablet"));
+
+        // No controller means no tablet either
+        config.usb_controller = "none".to_string();
+        let cmd = build_qemu_command_with_os(&config, "disk.qcow2", false, None, None);
+        assert!(!cmd.contains("usb"));
+    }
+
+    #[test]
+    fn test_build_qemu_command_with_resolution() {
+        let mut config = WizardQemuConfig {
+            resolution: Some("800x600".to_string()),
+            ..Default::default()
+        };
+        let cmd = build_qemu_command_with_os(&config, "disk.qcow2", false, None, None);
+        assert!(cmd.contains("-device VGA,edid=on,xres=800,yres=600"));
+        assert!(!cmd.contains("-vga std"));
+
+        // Adapters without xres/yres support ignore the hint
+        config.vga = "cirrus".to_string();
+        let cmd = build_qemu_command_with_os(&config, "disk.qcow2", false, None, None);
+        assert!(cmd.contains("-vga cirrus"));
+        assert!(!cmd.contains("xres="));
+    }
+
+    #[test]
+    fn test_build_qemu_command_uefi_without_secure_boot() {
+        let config = WizardQemuConfig {
+            uefi: true,
+            tpm: true,
+            secure_boot: false,
+            ..Default::default()
+        };
+        let cmd = build_qemu_command_with_os(&config, "disk.qcow2", false, None, None);
+
+        assert!(cmd.contains("-drive if=pflash,format=raw,readonly=on,file="));
+        assert!(cmd.contains("-drive if=pflash,format=raw,file=\"$OVMF_VARS\""));
+        assert!(!cmd.contains("smm=on"));
+        assert!(!cmd.contains("property=secure"));
     }
 
     #[test]
@@ -1045,31 +1364,49 @@ mod tests {
             PortForward { protocol: PortProtocol::Tcp, host_port: 2222, guest_port: 22 },
             PortForward { protocol: PortProtocol::Tcp, host_port: 8080, guest_port: 80 },
         ];
-        let args = generate_network_args("e1000", "user", None, &forwards);
+        let args = generate_network_args("e1000", "user", None, &forwards, false);
         assert_eq!(args.len(), 2);
         assert!(args[0].contains("hostfwd=tcp::2222-:22"));
         assert!(args[0].contains("hostfwd=tcp::8080-:80"));
         assert!(args[1].contains("e1000,netdev=net0"));
     }
 
+    #[test]
+    fn test_generate_network_args_user_with_smb() {
+        let args = generate_network_args("rtl8139", "user", None, &[], true);
+        assert!(args[0].contains("-netdev user,id=net0,smb=\"$VM_DIR/share\""));
+
+        // passt has no SMB server
+        let args = generate_network_args("rtl8139", "passt", None, &[], true);
+        assert!(!args[0].contains("smb="));
+    }
+
     #[test]
     fn test_generate_network_args_passt() {
-        let args = generate_network_args("virtio", "passt", None, &[]);
+        let args = generate_network_args("virtio", "passt", None, &[], false);
         assert_eq!(args.len(), 2);
         assert!(args[0].contains("-netdev passt,id=net0"));
         assert!(args[1].contains("virtio-net-pci,netdev=net0"));
     }
 
+    #[test]
+    fn test_generate_network_args_tap() {
+        let args = generate_network_args("virtio", "tap", Some("vmtap1"), &[], false);
+        assert_eq!(args.len(), 2);
+        assert!(args[0].contains("-netdev tap,id=net0,ifname=vmtap1,script=no,downscript=no"));
+        assert!(args[1].contains("virtio-net-pci,netdev=net0"));
+    }
+
     #[test]
     fn test_generate_network_args_bridge() {
-        let args = generate_network_args("e1000", "bridge", Some("virbr0"), &[]);
+        let args = generate_network_args("e1000", "bridge", Some("virbr0"), &[], false);
         assert_eq!(args.len(), 2);
         assert!(args[0].contains("-netdev bridge,id=net0,br=virbr0"));
     }
 
     #[test]
     fn test_generate_network_args_none() {
-        let args = generate_network_args("none", "user", None, &[]);
+        let args = generate_network_args("none", "user", None, &[], false);
         assert!(args.is_empty());
     }
 
@@ -1077,13 +1414,86 @@ mod tests {
     fn test_build_qemu_command_with_audio() {
         let config = WizardQemuConfig {
             audio: vec!["intel-hda".to_string(), "hda-duplex".to_string()],
+            audio_backend: "pipewire".to_string(),
             ..Default::default()
         };
 
         let cmd = build_qemu_command_with_os(&config, "disk.qcow2", false, None, None);
 
-        assert!(cmd.contains("-audiodev pa,id=audio0"));
+        assert!(cmd.contains("-audiodev pipewire,id=audio0"));
         assert!(cmd.contains("-device intel-hda"));
         assert!(cmd.contains("-device hda-duplex,audiodev=audio0"));
     }
+
+    #[test]
+    fn test_build_qemu_command_retro_audio() {
+        let config = WizardQemuConfig {
+            audio: vec!["sb16".to_string(), "adlib".to_string(), "gus".to_string()],
+            ..Default::default()
+        };
+
+        let cmd = build_qemu_command_with_os(&config, "disk.qcow2", false, None, None);
+
+        assert!(cmd.contains("-device sb16,audiodev=audio0"));
+        assert!(cmd.contains("-device adlib,audiodev=audio0"));
+        assert!(cmd.contains("-device gus,audiodev=audio0"));
+    }
+
+    #[test]
+    fn test_build_qemu_command_sb16_resources() {
+        let config = WizardQemuConfig {
+            audio: vec!["sb16".to_string()],
+            sb16: crate::vm::sound_blaster::Sb16Settings { port: 0x240, irq: 7, dma: 3, hdma: 5 },
+            ..Default::default()
+        };
+
+        let cmd = build_qemu_command_with_os(&config, "disk.qcow2", false, None, None);
+
+        assert!(cmd.contains("-device sb16,iobase=0x240,irq=7,dma=3,audiodev=audio0"));
+    }
+
+    #[test]
+    fn test_build_qemu_command_arm_virt() {
+        let config = WizardQemuConfig {
+            emulator: "qemu-system-aarch64".to_string(),
+            machine: Some("virt".to_string()),
+            vga: "virtio".to_string(),
+            enable_kvm: false,
+            uefi: true,
+            ..Default::default()
+        };
+
+        let cmd = build_qemu_command_with_os(&config, "disk.qcow2", true, None, None);
+
+        assert!(cmd.contains("-drive if=pflash,format=raw,readonly=on,file="));
+        assert!(!cmd.contains("OVMF_CODE"));
+        assert!(!cmd.contains("smm=on"));
+        assert!(cmd.contains("-device virtio-gpu-pci"));
+        assert!(cmd.contains("-drive file=\"$ISO\",media=cdrom,if=none,id=cdrom0"));
+        assert!(cmd.contains("-device scsi-cd,drive=cdrom0,bootindex=0"));
+        assert!(!cmd.contains("-boot d"));
+    }
+
+    #[test]
+    fn test_generate_launch_script_kernel_boot() {
+        let config = WizardQemuConfig {
+            emulator: "qemu-system-aarch64".to_string(),
+            machine: Some("raspi3b".to_string()),
+            kernel: Some("kernel8.img".to_string()),
+            dtb: Some("bcm2710-rpi-3-b.dtb".to_string()),
+            kernel_append: Some("console=ttyAMA0 root=/dev/mmcblk0p2".to_string()),
+            ..Default::default()
+        };
+
+        let script = generate_launch_script_with_os("Pi", "pi.qcow2", None, &config, None);
+
+        assert!(script.contains("KERNEL=\"$VM_DIR/kernel8.img\"\n"));
+        assert!(script.contains("DTB=\"$VM_DIR/bcm2710-rpi-3-b.dtb\"\n"));
+        assert!(!script.contains("INITRD="));
+        assert!(script.contains("for BOOT_FILE in \"$KERNEL\" \"$DTB\"; do"));
+        assert_eq!(script.matches("        check_boot_files\n").count(), 3);
+        assert!(script.contains("-kernel \"$KERNEL\""));
+        assert!(script.contains("-dtb \"$DTB\""));
+        assert!(script.contains("-append 'console=ttyAMA0 root=/dev/mmcblk0p2'"));
+    }
 }
@@ -0,0 +1,210 @@
+//! Data disks
+//!
+//! A blank extra hard disk for scratch space, or for moving files in and
+//! out without touching the system disk. The image is created next to
+//! launch.sh as `data<N>.<ext>` and added as a `-drive` to every QEMU
+//! command. It can be partitioned and formatted on the host with
+//! virt-format (libguestfs), so the guest finds a usable drive without
+//! running FDISK and FORMAT itself.
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::lifecycle::insert_base_arg;
+use super::physical_disk::used_ide_indexes;
+use super::DiscoveredVm;
+use crate::commands::qemu_img::{self, Qcow2Options};
+
+/// Image formats offered for data disks
+pub const FORMATS: &[&str] = &["qcow2", "raw"];
+
+/// Bus the disk is attached to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DataInterface {
+    /// Seen by every guest, DOS included
+    #[default]
+    Ide,
+    /// Faster, but needs virtio drivers in the guest
+    Virtio,
+}
+
+impl DataInterface {
+    pub const ALL: &'static [DataInterface] = &[Self::Ide, Self::Virtio];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Ide => "IDE (any guest)",
+            Self::Virtio => "virtio (needs guest drivers)",
+        }
+    }
+}
+
+/// Filesystem virt-format puts on the disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DataFilesystem {
+    /// Left blank for the guest to partition and format
+    #[default]
+    None,
+    Fat,
+    Ext4,
+    Ntfs,
+}
+
+impl DataFilesystem {
+    pub const ALL: &'static [DataFilesystem] = &[Self::None, Self::Fat, Self::Ext4, Self::Ntfs];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::None => "None (format it in the guest)",
+            Self::Fat => "FAT (DOS, Windows 9x, anything)",
+            Self::Ext4 => "ext4 (Linux)",
+            Self::Ntfs => "NTFS (Windows NT and later)",
+        }
+    }
+
+    /// Name for `virt-format --filesystem`
+    fn virt_format_name(&self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Fat => Some("vfat"),
+            Self::Ext4 => Some("ext4"),
+            Self::Ntfs => Some("ntfs"),
+        }
+    }
+}
+
+/// What to create
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataDiskOptions {
+    pub size_gb: u32,
+    /// qemu-img format name, one of `FORMATS`
+    pub format: String,
+    pub interface: DataInterface,
+    pub filesystem: DataFilesystem,
+    pub tuning: Qcow2Options,
+}
+
+/// First `data<N>` image name not taken in the VM folder
+pub fn next_disk_path(vm_dir: &Path, format: &str) -> PathBuf {
+    let ext = super::create::disk_extension(format);
+    (1..)
+        .map(|n| vm_dir.join(format!("data{}.{}", n, ext)))
+        .find(|path| !path.exists())
+        .expect("unbounded range")
+}
+
+/// The `-drive` argument for the new image
+///
+/// IDE disks take the first index the script leaves free (4 on pc, 6 AHCI
+/// ports on q35), as an unindexed drive ahead of the others would take the
+/// system disk's slot.
+pub fn drive_arg(content: &str, disk: &Path, format: &str, interface: DataInterface, q35: bool) -> Result<String> {
+    let file_name = disk.file_name().unwrap_or_default().to_string_lossy();
+    let file = if content.contains("VM_DIR=") {
+        format!("$VM_DIR/{}", file_name)
+    } else {
+        disk.to_string_lossy().to_string()
+    };
+    if file.contains(['"', '`', '\\', ',']) {
+        bail!("Unsupported characters in disk path: {}", file);
+    }
+
+    match interface {
+        DataInterface::Virtio => Ok(format!("-drive file=\"{}\",format={},if=virtio", file, format)),
+        DataInterface::Ide => {
+            let used = used_ide_indexes(content);
+            let slot_count = if q35 { 6 } else { 4 };
+            let Some(index) = (0..slot_count).find(|i| !used.contains(i)) else {
+                bail!("No free IDE slot for another disk (pc has 4, q35 has 6); use virtio");
+            };
+            Ok(format!("-drive file=\"{}\",format={},if=ide,index={}", file, format, index))
+        }
+    }
+}
+
+/// Create a blank disk for `vm`, format it if asked, and attach it in
+/// launch.sh; returns the new image
+pub fn add_data_disk(vm: &DiscoveredVm, options: &DataDiskOptions) -> Result<PathBuf> {
+    if options.size_gb == 0 {
+        bail!("The disk needs a size");
+    }
+    let content = std::fs::read_to_string(&vm.launch_script).context("Failed to read launch.sh")?;
+    let path = next_disk_path(&vm.path, &options.format);
+    let q35 = vm.config.machine.as_deref().is_some_and(|m| m.contains("q35"));
+    let arg = drive_arg(&content, &path, &options.format, options.interface, q35)?;
+    let new_content = insert_base_arg(&content, &arg)?;
+
+    tracing::info!(vm = %vm.id, disk = %path.display(), "adding data disk");
+    qemu_img::create_image(&path, &options.format, &format!("{}G", options.size_gb), &options.tuning)?;
+    if let Some(filesystem) = options.filesystem.virt_format_name() {
+        if let Err(e) = format_disk(&path, &options.format, filesystem) {
+            let _ = std::fs::remove_file(&path);
+            return Err(e);
+        }
+    }
+    if let Err(e) = std::fs::write(&vm.launch_script, new_content) {
+        let _ = std::fs::remove_file(&path);
+        return Err(e).context("Failed to write launch.sh");
+    }
+    Ok(path)
+}
+
+/// One MBR partition spanning the disk, with a filesystem on it
+fn format_disk(path: &Path, format: &str, filesystem: &str) -> Result<()> {
+    let output = Command::new("virt-format")
+        .arg("-a")
+        .arg(path)
+        .arg(format!("--format={}", format))
+        .arg(format!("--filesystem={}", filesystem))
+        .arg("--partition=mbr")
+        .output()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => {
+                anyhow!("virt-format not found; install guestfs-tools (or libguestfs-tools)")
+            }
+            _ => anyhow!(e),
+        })
+        .context("Failed to run virt-format")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("{}", stderr.trim().lines().last().unwrap_or("virt-format failed"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_disk_naming_and_drive() {
+        let dir = std::env::temp_dir().join(format!("vm-curator-data-disk-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert_eq!(next_disk_path(&dir, "qcow2"), dir.join("data1.qcow2"));
+        std::fs::write(dir.join("data1.qcow2"), b"").unwrap();
+        assert_eq!(next_disk_path(&dir, "qcow2"), dir.join("data2.qcow2"));
+        assert_eq!(next_disk_path(&dir, "raw"), dir.join("data1.img"));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let script = "VM_DIR=\"$(dirname \"$(readlink -f \"$0\")\")\"\nqemu-system-i386 \\\n  -hda \"$VM_DIR/dos.qcow2\" \\\n  -cdrom \"$VM_DIR/dos.iso\"\n";
+        let disk = Path::new("/vms/dos/data1.qcow2");
+        assert_eq!(
+            drive_arg(script, disk, "qcow2", DataInterface::Ide, false).unwrap(),
+            "-drive file=\"$VM_DIR/data1.qcow2\",format=qcow2,if=ide,index=1"
+        );
+        assert_eq!(
+            drive_arg(script, disk, "qcow2", DataInterface::Virtio, false).unwrap(),
+            "-drive file=\"$VM_DIR/data1.qcow2\",format=qcow2,if=virtio"
+        );
+
+        // Without VM_DIR the path is written out; a full pc IDE bus is refused
+        let full = "qemu-system-i386 -hda a.img -hdb b.img -cdrom c.iso -hdd d.img";
+        assert!(drive_arg(full, disk, "raw", DataInterface::Ide, false).is_err());
+        assert_eq!(
+            drive_arg(full, disk, "raw", DataInterface::Ide, true).unwrap(),
+            "-drive file=\"/vms/dos/data1.qcow2\",format=raw,if=ide,index=4"
+        );
+    }
+}
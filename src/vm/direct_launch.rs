@@ -0,0 +1,369 @@
+//! Starting QEMU without bash
+//!
+//! launch.sh is a bash script, and Windows hosts have no bash to run it.
+//! There the QEMU command for the boot mode is read out of the script (the
+//! `--install)`, `--cdrom)`, `--netboot)` or `"")` branch of its `case`, or
+//! its only QEMU command), the script's variables and arrays are filled in
+//! and QEMU is started directly. Setup the script does before its `case`
+//! (copying UEFI variables) is left out; a branch that runs anything before
+//! QEMU besides echoes and file checks (swtpm for a TPM) needs bash.
+
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::launch_parser::extract_shell_variables;
+use super::qemu_config::BootMode;
+
+/// Whether launch scripts are read rather than run through bash
+pub fn is_needed() -> bool {
+    cfg!(windows)
+}
+
+/// The QEMU command launch.sh runs for `boot_mode`: the program, then its
+/// arguments
+pub fn qemu_command(script: &str, vm_dir: &Path, boot_mode: &BootMode) -> Result<Vec<String>> {
+    let commands = commands(script);
+    let label = match boot_mode {
+        BootMode::Normal | BootMode::Resume => "\"\")",
+        BootMode::Install => "--install)",
+        BootMode::Cdrom(_) => "--cdrom)",
+        BootMode::Network => "--netboot)",
+    };
+
+    let command = if commands.iter().any(|c| c.starts_with("case ")) {
+        let start = commands
+            .iter()
+            .position(|c| c.starts_with(label))
+            .with_context(|| format!("launch.sh has no {} branch", label))?;
+        let mut qemu = None;
+        let rest = commands[start][label.len()..].trim();
+        let branch = std::iter::once(rest).chain(commands[start + 1..].iter().map(String::as_str));
+        for command in branch {
+            if command == "esac" {
+                break;
+            }
+            let end = command.ends_with(";;");
+            let command = command.trim_end_matches(";;").trim();
+            let first = command.split_whitespace().next().unwrap_or_default();
+            if qemu.is_none() && is_qemu(command) {
+                qemu = Some(command);
+            } else if qemu.is_none() && !(command.is_empty() || first.starts_with("check_") || SKIPPED.contains(&first)) {
+                bail!("launch.sh runs `{}` before QEMU starts, which needs bash", first);
+            }
+            if end {
+                break;
+            }
+        }
+        qemu.with_context(|| format!("launch.sh's {} branch doesn't start QEMU", label))?
+    } else {
+        commands
+            .iter()
+            .rev()
+            .find(|c| is_qemu(c))
+            .context("launch.sh doesn't start QEMU")?
+    };
+
+    let vars = extract_shell_variables(script, vm_dir);
+    let arrays = arrays(script, &vars)?;
+    let positional = match boot_mode {
+        BootMode::Cdrom(iso) => vec!["--cdrom".to_string(), iso.to_string_lossy().to_string()],
+        _ => Vec::new(),
+    };
+    let mut words = words(command, &vars, &arrays, &positional)?;
+    if words.first().is_some_and(|word| word == "exec") {
+        words.remove(0);
+    }
+    Ok(words)
+}
+
+/// Commands in a branch that don't affect QEMU
+const SKIPPED: &[&str] = &["echo", "printf", "if", "then", "else", "fi", "exit", "[["];
+
+fn is_qemu(command: &str) -> bool {
+    let command = command.strip_prefix("exec ").unwrap_or(command).trim_start();
+    command.starts_with("qemu-system-")
+}
+
+/// The script's commands, one per entry, with continuation lines joined
+/// and comments dropped
+fn commands(script: &str) -> Vec<String> {
+    let mut commands = Vec::new();
+    let mut current = String::new();
+    for line in script.lines() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        match line.strip_suffix('\\') {
+            Some(part) => {
+                current.push_str(part);
+                current.push(' ');
+            }
+            None => {
+                current.push_str(line);
+                commands.push(std::mem::take(&mut current).trim().to_string());
+            }
+        }
+    }
+    commands
+}
+
+/// Arrays the script sets (`NAME=( ... )`), by name
+fn arrays(script: &str, vars: &HashMap<String, String>) -> Result<HashMap<String, Vec<String>>> {
+    let mut arrays = HashMap::new();
+    let mut open: Option<(String, String)> = None;
+    for line in script.lines() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        if let Some((name, mut items)) = open.take() {
+            match line.strip_suffix(')') {
+                Some(last) => {
+                    items.push_str(last);
+                    arrays.insert(name, words(&items, vars, &HashMap::new(), &[])?);
+                }
+                None => {
+                    items.push_str(line.trim_end_matches('\\'));
+                    items.push(' ');
+                    open = Some((name, items));
+                }
+            }
+            continue;
+        }
+        let Some((name, items)) = line.split_once("=(") else {
+            continue;
+        };
+        if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            continue;
+        }
+        match items.strip_suffix(')') {
+            Some(items) => {
+                arrays.insert(name.to_string(), words(items, vars, &HashMap::new(), &[])?);
+            }
+            None => open = Some((name.to_string(), format!("{} ", items))),
+        }
+    }
+    Ok(arrays)
+}
+
+/// Split a command into words the way bash would, filling in variables
+fn words(
+    command: &str,
+    vars: &HashMap<String, String>,
+    arrays: &HashMap<String, Vec<String>>,
+    positional: &[String],
+) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = command.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some('\''), '\'') | (Some('"'), '"') => quote = None,
+            (Some('\''), c) => word.push(c),
+            (Some('"'), '\\') if matches!(chars.peek(), Some('$' | '"' | '\\' | '`')) => {
+                word.extend(chars.next());
+            }
+            (None, '\\') => {
+                word.extend(chars.next());
+                in_word = true;
+            }
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (_, '$') => {
+                let reference = reference(&mut chars)?;
+                let values = match reference {
+                    Reference::Literal => vec!["$".to_string()],
+                    Reference::Positional(n) => positional.get(n - 1).cloned().into_iter().collect(),
+                    Reference::Var(name) => vars
+                        .get(&name)
+                        .cloned()
+                        .or_else(|| std::env::var(&name).ok())
+                        .into_iter()
+                        .collect(),
+                    Reference::Array(name) => arrays
+                        .get(&name)
+                        .cloned()
+                        .with_context(|| format!("launch.sh uses the array {}, which it doesn't set", name))?,
+                    Reference::Unsupported(text) => {
+                        bail!("launch.sh builds part of the QEMU command at run time ({}), which needs bash", text)
+                    }
+                };
+                // Unquoted values split into words; array elements always do
+                let is_array = values.len() != 1;
+                let fields: Vec<String> = if quote.is_some() || is_array {
+                    values
+                } else {
+                    values[0].split_whitespace().map(String::from).collect()
+                };
+                for (i, field) in fields.into_iter().enumerate() {
+                    if i > 0 {
+                        words.push(std::mem::take(&mut word));
+                    }
+                    word.push_str(&field);
+                    in_word = true;
+                }
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, ';' | '&' | '|' | '<' | '>' | '`') => {
+                bail!("launch.sh's QEMU command uses `{}`, which needs bash", c)
+            }
+            (_, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        bail!("launch.sh's QEMU command has an unclosed quote");
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// What follows a `$`
+enum Reference {
+    Literal,
+    Positional(usize),
+    Var(String),
+    Array(String),
+    Unsupported(String),
+}
+
+fn reference(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<Reference> {
+    match chars.peek().copied() {
+        Some('{') => {
+            chars.next();
+            let inner: String = chars.by_ref().take_while(|c| *c != '}').collect();
+            if let Some(name) = inner.strip_suffix("[@]") {
+                return Ok(Reference::Array(name.to_string()));
+            }
+            if inner.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                return Ok(match inner.parse() {
+                    Ok(n) if n > 0 => Reference::Positional(n),
+                    _ => Reference::Var(inner),
+                });
+            }
+            Ok(Reference::Unsupported(format!("${{{}}}", inner)))
+        }
+        Some('(') => Ok(Reference::Unsupported("$(...)".to_string())),
+        Some(c) if c.is_ascii_digit() => {
+            chars.next();
+            match c.to_digit(10) {
+                Some(n) if n > 0 => Ok(Reference::Positional(n as usize)),
+                _ => Ok(Reference::Unsupported("$0".to_string())),
+            }
+        }
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            let mut name = String::new();
+            while let Some(c) = chars.peek().copied().filter(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                name.push(c);
+                chars.next();
+            }
+            Ok(Reference::Var(name))
+        }
+        _ => Ok(Reference::Literal),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const SCRIPT: &str = r#"#!/bin/bash
+VM_DIR="$(dirname "$(readlink -f "$0")")"
+DISK="$VM_DIR/disk.qcow2"
+ISO='C:\isos\Windows 10.iso'
+NET_ARGS="-netdev user,id=net0 -device e1000,netdev=net0"
+
+SMBIOS_OPTS=(
+    -smbios "type=1,manufacturer=QEMU,product=Standard PC"
+    -smbios "type=2,manufacturer=QEMU"
+)
+
+case "$1" in
+    --install)
+        if [[ -z "$ISO" ]] || [[ ! -f "$ISO" ]]; then
+            echo "Error: Installation ISO not found at $ISO"
+            exit 1
+        fi
+        qemu-system-x86_64 -accel whpx -m 4096M "${SMBIOS_OPTS[@]}" \
+            -drive file="$DISK",format=qcow2 -cdrom "$ISO" -boot d $NET_ARGS
+        ;;
+    --cdrom)
+        qemu-system-x86_64 -m 4096M -drive file="$DISK",format=qcow2 -cdrom "$2"
+        ;;
+    "")
+        echo "Booting..."
+        start_tpm
+        qemu-system-x86_64 -m 4096M -drive file="$DISK",format=qcow2
+        ;;
+esac
+"#;
+
+    #[test]
+    fn test_install_command() {
+        let vm_dir = Path::new("C:\\vms\\win10");
+        let words = qemu_command(SCRIPT, vm_dir, &BootMode::Install).unwrap();
+        assert_eq!(
+            words,
+            [
+                "qemu-system-x86_64",
+                "-accel",
+                "whpx",
+                "-m",
+                "4096M",
+                "-smbios",
+                "type=1,manufacturer=QEMU,product=Standard PC",
+                "-smbios",
+                "type=2,manufacturer=QEMU",
+                "-drive",
+                "file=C:\\vms\\win10/disk.qcow2,format=qcow2",
+                "-cdrom",
+                "C:\\isos\\Windows 10.iso",
+                "-boot",
+                "d",
+                "-netdev",
+                "user,id=net0",
+                "-device",
+                "e1000,netdev=net0",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cdrom_and_unsupported() {
+        let iso = PathBuf::from("D:\\other.iso");
+        let words = qemu_command(SCRIPT, Path::new("/vms/win10"), &BootMode::Cdrom(iso)).unwrap();
+        assert_eq!(words.last().map(String::as_str), Some("D:\\other.iso"));
+
+        // The normal boot starts swtpm first
+        let error = qemu_command(SCRIPT, Path::new("/vms/win10"), &BootMode::Normal).unwrap_err();
+        assert!(error.to_string().contains("start_tpm"));
+        assert!(qemu_command(SCRIPT, Path::new("/vms/win10"), &BootMode::Network).is_err());
+
+        let error = qemu_command("exec qemu-system-i386 -m 64 | tee log\n", Path::new("/vms"), &BootMode::Normal).unwrap_err();
+        assert!(error.to_string().contains('|'));
+    }
+
+    #[test]
+    fn test_plain_script() {
+        let script = "#!/bin/bash\nexec qemu-system-i386 \\\n  -m 64 \\\n  -hda 'dos disk.img'\n";
+        let words = qemu_command(script, Path::new("/vms/dos"), &BootMode::Normal).unwrap();
+        assert_eq!(words, ["qemu-system-i386", "-m", "64", "-hda", "dos disk.img"]);
+    }
+}
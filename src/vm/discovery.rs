@@ -1,11 +1,14 @@
 use anyhow::{Context, Result};
+use serde::Serialize;
 use std::path::{Path, PathBuf};
 
+use super::backend::{self, Backend};
 use super::launch_parser::parse_launch_script;
 use super::qemu_config::QemuConfig;
+use crate::config::RemoteHost;
 
 /// A discovered VM in the library
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct DiscoveredVm {
     /// Directory name (e.g., "windows-95")
     pub id: String,
@@ -13,21 +16,32 @@ pub struct DiscoveredVm {
     pub path: PathBuf,
     /// Path to launch.sh
     pub launch_script: PathBuf,
-    /// Parsed QEMU configuration
+    /// Parsed QEMU configuration (a summary of the config file for other backends)
     pub config: QemuConfig,
+    /// Emulator the VM runs under
+    pub backend: Backend,
     /// Custom display name from vm-curator.toml (if set)
     pub custom_name: Option<String>,
     /// OS profile ID from vm-curator.toml (if set)
     pub os_profile: Option<String>,
+    /// Host the VM runs on, for VMs in a remote library (paths are on that host)
+    pub remote: Option<RemoteHost>,
 }
 
 impl DiscoveredVm {
     /// Get a display name - uses custom name if set, otherwise generates from ID
     pub fn display_name(&self) -> String {
-        if let Some(ref name) = self.custom_name {
-            return name.clone();
-        }
-        format_os_display_name(&self.id)
+        let Some(ref host) = self.remote else {
+            return self.custom_name.clone().unwrap_or_else(|| format_os_display_name(&self.id));
+        };
+        let name = match self.custom_name {
+            Some(ref name) => name.clone(),
+            None => {
+                let dir_name = self.id.strip_suffix(&format!("@{}", host.name)).unwrap_or(&self.id);
+                format_os_display_name(dir_name)
+            }
+        };
+        format!("{} ({})", name, host.name)
     }
 }
 
@@ -367,11 +381,14 @@ fn read_vm_metadata(vm_path: &Path) -> (Option<String>, Option<String>) {
         return (None, None);
     }
 
-    let content = match std::fs::read_to_string(&metadata_path) {
-        Ok(c) => c,
-        Err(_) => return (None, None),
-    };
+    match std::fs::read_to_string(&metadata_path) {
+        Ok(content) => parse_vm_metadata(&content),
+        Err(_) => (None, None),
+    }
+}
 
+/// Read the display name and OS profile from vm-curator.toml content
+pub(crate) fn parse_vm_metadata(content: &str) -> (Option<String>, Option<String>) {
     // Simple TOML parsing for our specific keys
     let mut display_name = None;
     let mut os_profile = None;
@@ -441,12 +458,17 @@ pub fn discover_vms(library_path: &Path) -> Result<Vec<DiscoveredVm>> {
         let script_content = std::fs::read_to_string(&launch_script)
             .unwrap_or_default();
 
-        let config = match parse_launch_script(&launch_script, &script_content) {
-            Ok(cfg) => cfg,
-            Err(_) => {
-                let mut default_config = QemuConfig::default();
-                default_config.raw_script = script_content;
-                default_config
+        let backend = Backend::detect(&script_content);
+        let config = if backend != Backend::Qemu {
+            backend::read_config(backend, &path, &script_content)
+        } else {
+            match parse_launch_script(&launch_script, &script_content) {
+                Ok(cfg) => cfg,
+                Err(_) => {
+                    let mut default_config = QemuConfig::default();
+                    default_config.raw_script = script_content;
+                    default_config
+                }
             }
         };
 
@@ -458,8 +480,10 @@ pub fn discover_vms(library_path: &Path) -> Result<Vec<DiscoveredVm>> {
             path,
             launch_script,
             config,
+            backend,
             custom_name,
             os_profile,
+            remote: None,
         });
     }
 
@@ -522,8 +546,10 @@ mod tests {
             path: PathBuf::from("/test"),
             launch_script: PathBuf::from("/test/launch.sh"),
             config: QemuConfig::default(),
+            backend: Backend::Qemu,
             custom_name: None,
             os_profile: None,
+            remote: None,
         };
         assert_eq!(vm.display_name(), "Microsoft® Windows 95");
     }
@@ -535,8 +561,10 @@ mod tests {
             path: PathBuf::from("/test"),
             launch_script: PathBuf::from("/test/launch.sh"),
             config: QemuConfig::default(),
+            backend: Backend::Qemu,
             custom_name: Some("CachyOS Gaming Rig".to_string()),
             os_profile: Some("linux-cachyos".to_string()),
+            remote: None,
         };
         // Custom name takes priority
         assert_eq!(vm.display_name(), "CachyOS Gaming Rig");
@@ -0,0 +1,357 @@
+//! Disk I/O tuning
+//!
+//! Per-disk `-drive` options that trade safety for speed or keep a VM from
+//! starving its neighbours: the host cache mode, the AIO engine, discard
+//! (TRIM) passthrough, and I/O throttling. Options are edited in place on
+//! each disk's `-drive` argument; CD-ROM and firmware drives are left alone.
+
+use anyhow::{bail, Context, Result};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::path::Path;
+
+/// Host cache modes ("default" leaves QEMU's writeback)
+pub const CACHE_MODES: &[&str] = &["default", "writeback", "none", "unsafe", "writethrough", "directsync"];
+
+/// AIO engines ("default" leaves QEMU's thread pool)
+pub const AIO_MODES: &[&str] = &["default", "threads", "io_uring", "native"];
+
+/// IOPS limits offered (0 = unlimited)
+pub const IOPS_LIMITS: &[u32] = &[0, 100, 250, 500, 1000, 2500, 5000, 10000];
+
+/// Bandwidth limits offered in MB/s (0 = unlimited)
+pub const BANDWIDTH_LIMITS_MB: &[u32] = &[0, 5, 10, 25, 50, 100, 250, 500];
+
+/// Editable fields, in display order
+pub const TUNING_FIELDS: &[&str] = &["Cache", "AIO", "Discard/TRIM", "IOPS limit", "Bandwidth"];
+
+/// `-drive` keys owned by the tuning editor
+const TUNING_KEYS: &[&str] = &[
+    "cache",
+    "aio",
+    "discard",
+    "detect-zeroes",
+    "throttling.iops-total",
+    "throttling.bps-total",
+];
+
+static RE_DRIVE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"-drive\s+((?:"[^"]*"|[^\s"\\])+)"#).expect("Invalid regex: RE_DRIVE")
+});
+
+/// Shell variable assignments (managed sections carry their own drives)
+static RE_ASSIGNMENT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*[A-Za-z_][A-Za-z0-9_]*=").expect("Invalid regex: RE_ASSIGNMENT")
+});
+
+/// I/O settings of one disk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskTuning {
+    /// One of `CACHE_MODES`
+    pub cache: String,
+    /// One of `AIO_MODES`
+    pub aio: String,
+    /// Pass guest TRIM through to the image (discard=unmap)
+    pub discard: bool,
+    /// Total IOPS limit (0 = unlimited)
+    pub iops: u32,
+    /// Total bandwidth limit in MB/s (0 = unlimited)
+    pub bandwidth_mb: u32,
+}
+
+impl Default for DiskTuning {
+    fn default() -> Self {
+        Self {
+            cache: "default".to_string(),
+            aio: "default".to_string(),
+            discard: false,
+            iops: 0,
+            bandwidth_mb: 0,
+        }
+    }
+}
+
+impl DiskTuning {
+    /// Display value of a field (index into `TUNING_FIELDS`)
+    pub fn field_value(&self, field: usize) -> String {
+        let limit = |value: u32, unit: &str| {
+            if value == 0 {
+                "unlimited".to_string()
+            } else {
+                format!("{} {}", value, unit)
+            }
+        };
+        match field {
+            0 => self.cache.clone(),
+            1 => self.aio.clone(),
+            2 => if self.discard { "on" } else { "off" }.to_string(),
+            3 => limit(self.iops, "IOPS"),
+            _ => limit(self.bandwidth_mb, "MB/s"),
+        }
+    }
+
+    /// Step a field (index into `TUNING_FIELDS`) through its values
+    pub fn cycle(&mut self, field: usize, delta: i32) {
+        match field {
+            0 => cycle_option(&mut self.cache, CACHE_MODES, delta),
+            1 => cycle_option(&mut self.aio, AIO_MODES, delta),
+            2 => self.discard = !self.discard,
+            3 => cycle_limit(&mut self.iops, IOPS_LIMITS, delta),
+            _ => cycle_limit(&mut self.bandwidth_mb, BANDWIDTH_LIMITS_MB, delta),
+        }
+    }
+
+    /// Non-default settings, for info displays (e.g. "cache=none, aio=native")
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if self.cache != "default" {
+            parts.push(format!("cache={}", self.cache));
+        }
+        if self.aio != "default" {
+            parts.push(format!("aio={}", self.aio));
+        }
+        if self.discard {
+            parts.push("discard".to_string());
+        }
+        if self.iops > 0 {
+            parts.push(format!("{} IOPS", self.iops));
+        }
+        if self.bandwidth_mb > 0 {
+            parts.push(format!("{} MB/s", self.bandwidth_mb));
+        }
+        if parts.is_empty() {
+            "defaults".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+
+    /// Combinations QEMU refuses to start with
+    pub fn validate(&self) -> Result<()> {
+        if self.aio == "native" && !matches!(self.cache.as_str(), "none" | "directsync") {
+            bail!("aio=native needs cache=none or directsync");
+        }
+        Ok(())
+    }
+
+    /// Risks of the chosen settings
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if let Err(e) = self.validate() {
+            warnings.push(e.to_string());
+        }
+        if self.cache == "unsafe" {
+            warnings.push("unsafe ignores flushes: a host crash can corrupt the disk".to_string());
+        }
+        if self.aio == "io_uring" {
+            warnings.push("io_uring needs Linux 5.1+ and a QEMU built with it".to_string());
+        }
+        warnings
+    }
+
+    fn from_options(options: &[&str]) -> Self {
+        let mut tuning = Self::default();
+        for option in options {
+            let Some((key, value)) = option.split_once('=') else { continue };
+            match key {
+                "cache" => tuning.cache = value.to_string(),
+                "aio" => tuning.aio = value.to_string(),
+                "discard" => tuning.discard = matches!(value, "unmap" | "on"),
+                "throttling.iops-total" => tuning.iops = value.parse().unwrap_or(0),
+                "throttling.bps-total" => {
+                    tuning.bandwidth_mb = value.parse::<u64>().map(|b| (b / (1024 * 1024)) as u32).unwrap_or(0)
+                }
+                _ => {}
+            }
+        }
+        tuning
+    }
+
+    fn to_options(&self) -> Vec<String> {
+        let mut options = Vec::new();
+        if self.cache != "default" {
+            options.push(format!("cache={}", self.cache));
+        }
+        if self.aio != "default" {
+            options.push(format!("aio={}", self.aio));
+        }
+        if self.discard {
+            options.push("discard=unmap".to_string());
+            options.push("detect-zeroes=unmap".to_string());
+        }
+        if self.iops > 0 {
+            options.push(format!("throttling.iops-total={}", self.iops));
+        }
+        if self.bandwidth_mb > 0 {
+            options.push(format!("throttling.bps-total={}", self.bandwidth_mb as u64 * 1024 * 1024));
+        }
+        options
+    }
+}
+
+/// A disk `-drive` in a launch script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskDrive {
+    /// `file=` value as written (may be a variable like "$DISK")
+    pub file: String,
+    /// `if=` value ("ide" when unset)
+    pub interface: String,
+    pub tuning: DiskTuning,
+}
+
+/// The disk drives of a launch script, in order
+pub fn parse_disk_drives(content: &str) -> Vec<DiskDrive> {
+    let mut drives = Vec::new();
+    for line in command_lines(content) {
+        for caps in RE_DRIVE.captures_iter(line) {
+            let options = split_options(&caps[1]);
+            if !is_disk(&options) {
+                continue;
+            }
+            let value = |key: &str| {
+                options
+                    .iter()
+                    .find_map(|o| o.strip_prefix(key).and_then(|v| v.strip_prefix('=')))
+                    .map(|v| v.trim_matches('"').to_string())
+            };
+            drives.push(DiskDrive {
+                file: value("file").unwrap_or_default(),
+                interface: value("if").unwrap_or_else(|| "ide".to_string()),
+                tuning: DiskTuning::from_options(&options),
+            });
+        }
+    }
+    drives
+}
+
+/// Apply tuning to a VM's disks (one entry per `parse_disk_drives` drive)
+pub fn set_disk_tuning(script_path: &Path, tunings: &[DiskTuning]) -> Result<()> {
+    for tuning in tunings {
+        tuning.validate()?;
+    }
+    let content = std::fs::read_to_string(script_path).context("Failed to read launch.sh")?;
+    let new_content = rewrite_drives(&content, tunings);
+    std::fs::write(script_path, new_content).context("Failed to write launch.sh")?;
+    Ok(())
+}
+
+fn rewrite_drives(content: &str, tunings: &[DiskTuning]) -> String {
+    let mut disk_idx = 0;
+    let mut result = String::new();
+
+    for line in content.lines() {
+        if is_command_line(line) {
+            let rewritten = RE_DRIVE.replace_all(line, |caps: &Captures| {
+                let options = split_options(&caps[1]);
+                if !is_disk(&options) {
+                    return caps[0].to_string();
+                }
+                let tuning = tunings.get(disk_idx);
+                disk_idx += 1;
+                let Some(tuning) = tuning else {
+                    return caps[0].to_string();
+                };
+
+                let kept = options.iter().filter(|o| {
+                    let key = o.split('=').next().unwrap_or_default();
+                    !TUNING_KEYS.contains(&key)
+                });
+                let spec: Vec<String> = kept.map(|o| o.to_string()).chain(tuning.to_options()).collect();
+                format!("-drive {}", spec.join(","))
+            });
+            result.push_str(&rewritten);
+        } else {
+            result.push_str(line);
+        }
+        result.push('\n');
+    }
+
+    result
+}
+
+fn is_command_line(line: &str) -> bool {
+    !line.trim_start().starts_with('#') && !RE_ASSIGNMENT.is_match(line)
+}
+
+fn command_lines(content: &str) -> impl Iterator<Item = &str> {
+    content.lines().filter(|l| is_command_line(l))
+}
+
+/// A drive with an image that isn't a CD-ROM or firmware flash
+fn is_disk(options: &[&str]) -> bool {
+    options.iter().any(|o| o.starts_with("file="))
+        && !options.contains(&"media=cdrom")
+        && !options.contains(&"if=pflash")
+}
+
+/// Split `-drive` options on commas outside quotes
+fn split_options(spec: &str) -> Vec<&str> {
+    let mut options = Vec::new();
+    let mut start = 0;
+    let mut in_quotes = false;
+    for (i, c) in spec.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => {
+                options.push(&spec[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    options.push(&spec[start..]);
+    options.retain(|o| !o.is_empty());
+    options
+}
+
+fn cycle_option(current: &mut String, options: &[&str], delta: i32) {
+    let current_idx = options.iter().position(|&o| o == current.as_str()).unwrap_or(0);
+    let new_idx = (current_idx as i32 + delta).rem_euclid(options.len() as i32) as usize;
+    *current = options[new_idx].to_string();
+}
+
+fn cycle_limit(current: &mut u32, limits: &[u32], delta: i32) {
+    let current_idx = limits.iter().position(|&l| l == *current).unwrap_or(0);
+    let new_idx = (current_idx as i32 + delta).rem_euclid(limits.len() as i32) as usize;
+    *current = limits[new_idx];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = "qemu-system-x86_64 \\\n    -drive if=pflash,format=raw,file=\"$OVMF_VARS\" \\\n    -drive file=\"$DISK\",format=qcow2,if=virtio,index=0,media=disk,cache=writeback \\\n    -drive file=\"$ISO\",media=cdrom,index=1\n";
+
+    #[test]
+    fn test_parse_disk_drives() {
+        let drives = parse_disk_drives(SCRIPT);
+        assert_eq!(drives.len(), 1);
+        assert_eq!(drives[0].file, "$DISK");
+        assert_eq!(drives[0].interface, "virtio");
+        assert_eq!(drives[0].tuning.cache, "writeback");
+        assert_eq!(drives[0].tuning.summary(), "cache=writeback");
+    }
+
+    #[test]
+    fn test_rewrite_drives() {
+        let tuning = DiskTuning {
+            cache: "none".to_string(),
+            aio: "native".to_string(),
+            discard: true,
+            iops: 500,
+            bandwidth_mb: 50,
+        };
+        let rewritten = rewrite_drives(SCRIPT, std::slice::from_ref(&tuning));
+        assert!(rewritten.contains(
+            "-drive file=\"$DISK\",format=qcow2,if=virtio,index=0,media=disk,cache=none,aio=native,discard=unmap,detect-zeroes=unmap,throttling.iops-total=500,throttling.bps-total=52428800 \\"
+        ));
+        assert!(rewritten.contains("-drive file=\"$ISO\",media=cdrom,index=1\n"));
+        assert_eq!(parse_disk_drives(&rewritten)[0].tuning, tuning);
+
+        let reset = rewrite_drives(&rewritten, &[DiskTuning::default()]);
+        assert!(reset.contains("-drive file=\"$DISK\",format=qcow2,if=virtio,index=0,media=disk \\"));
+
+        let invalid = DiskTuning { cache: "writeback".to_string(), ..tuning };
+        assert!(invalid.validate().is_err());
+    }
+}
@@ -0,0 +1,530 @@
+//! Journaled Disk Operations
+//!
+//! Long-running disk rewrites (compaction, copies) never touch the final
+//! image until they finish: output goes to a uniquely named temp file next
+//! to the target, and an intent record (`.journal`) is written beside it
+//! before any work starts. If vm-curator is killed mid-operation, the
+//! journal survives and the startup scan can resume or clean up the work.
+
+use anyhow::{bail, Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::commands::qemu_img::{self, Qcow2Options};
+
+static RE_DRIVE_FORMAT: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\bformat=[a-z0-9]+").expect("Invalid regex: RE_DRIVE_FORMAT"));
+
+/// Suffix for temp output files
+const TEMP_SUFFIX: &str = ".tmp";
+
+/// Suffix for intent records
+const JOURNAL_SUFFIX: &str = ".journal";
+
+/// Kind of disk operation recorded in a journal
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiskOperationKind {
+    /// Rewrite a qcow2 image in place to reclaim unused space
+    Compact,
+    /// Copy an image file byte-for-byte
+    Copy,
+    /// Rewrite an image in another format
+    Convert,
+}
+
+impl DiskOperationKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Compact => "compact",
+            Self::Copy => "copy",
+            Self::Convert => "convert",
+        }
+    }
+}
+
+/// Intent record written before a disk operation starts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskJournal {
+    pub operation: DiskOperationKind,
+    /// Image being read
+    pub source: PathBuf,
+    /// Final destination (replaced only when the operation completes)
+    pub target: PathBuf,
+    /// Temp file the operation writes to
+    pub temp: PathBuf,
+    /// When the operation started (RFC 3339)
+    pub started: String,
+    /// Output format of a conversion
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// qcow2 options the conversion was started with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tuning: Option<Qcow2Options>,
+}
+
+/// An operation found on startup that never completed
+#[derive(Debug, Clone)]
+pub struct InterruptedDiskOp {
+    /// Journal file (None for legacy temp files left without one)
+    pub journal_path: Option<PathBuf>,
+    /// Parsed journal, if readable
+    pub journal: Option<DiskJournal>,
+    /// Leftover temp file, if any
+    pub temp_path: Option<PathBuf>,
+}
+
+impl InterruptedDiskOp {
+    /// Whether the recorded operation can be re-run
+    pub fn can_resume(&self) -> bool {
+        self.journal.as_ref().is_some_and(|j| j.source.exists())
+    }
+
+    /// Short description for dialogs and status messages
+    pub fn describe(&self) -> String {
+        match (&self.journal, &self.temp_path) {
+            (Some(journal), _) => format!(
+                "{} {}",
+                journal.operation.label(),
+                journal.target.file_name().and_then(|n| n.to_str()).unwrap_or("disk")
+            ),
+            (None, Some(temp)) => format!(
+                "orphaned {}",
+                temp.file_name().and_then(|n| n.to_str()).unwrap_or("temp file")
+            ),
+            (None, None) => "unknown operation".to_string(),
+        }
+    }
+}
+
+/// Build a unique temp path and journal path next to a target image
+fn operation_paths(target: &Path) -> Result<(PathBuf, PathBuf)> {
+    let dir = target.parent().context("Disk path has no parent directory")?;
+    let name = target
+        .file_name()
+        .and_then(|n| n.to_str())
+        .context("Disk path has no file name")?;
+    let id = format!(
+        "{}-{}",
+        std::process::id(),
+        chrono::Local::now().format("%Y%m%d%H%M%S%f")
+    );
+    let base = format!(".{}.{}", name, id);
+    Ok((
+        dir.join(format!("{}{}", base, TEMP_SUFFIX)),
+        dir.join(format!("{}{}", base, JOURNAL_SUFFIX)),
+    ))
+}
+
+/// Run an operation that writes `journal.temp`, then atomically move it over the target
+fn run_journaled<F>(journal: &DiskJournal, journal_path: &Path, op: F) -> Result<()>
+where
+    F: FnOnce(&Path, &Path) -> Result<()>,
+{
+    let record = serde_json::to_string_pretty(journal)?;
+    fs::write(journal_path, record)
+        .with_context(|| format!("Failed to write journal {}", journal_path.display()))?;
+
+    tracing::info!(
+        operation = journal.operation.label(),
+        source = %journal.source.display(),
+        target = %journal.target.display(),
+        "disk operation started"
+    );
+    let result = op(&journal.source, &journal.temp).and_then(|_| {
+        fs::rename(&journal.temp, &journal.target).with_context(|| {
+            format!(
+                "Failed to move {} into place at {}",
+                journal.temp.display(),
+                journal.target.display()
+            )
+        })
+    });
+
+    match result {
+        Ok(()) => tracing::info!(operation = journal.operation.label(), "disk operation finished"),
+        Err(ref e) => {
+            tracing::warn!(operation = journal.operation.label(), error = format!("{:#}", e), "disk operation failed");
+            let _ = fs::remove_file(&journal.temp);
+        }
+    }
+    let _ = fs::remove_file(journal_path);
+    result
+}
+
+/// Start a new journaled operation from `source` to `target`
+fn start_operation<F>(kind: DiskOperationKind, source: &Path, target: &Path, op: F) -> Result<()>
+where
+    F: FnOnce(&Path, &Path) -> Result<()>,
+{
+    let (temp, journal_path) = operation_paths(target)?;
+    let journal = DiskJournal {
+        operation: kind,
+        source: source.to_path_buf(),
+        target: target.to_path_buf(),
+        temp,
+        started: chrono::Local::now().to_rfc3339(),
+        format: None,
+        tuning: None,
+    };
+    run_journaled(&journal, &journal_path, op)
+}
+
+fn copy_file(source: &Path, dest: &Path) -> Result<()> {
+    fs::copy(source, dest).with_context(|| {
+        format!(
+            "Failed to copy disk from {} to {}",
+            source.display(),
+            dest.display()
+        )
+    })?;
+    Ok(())
+}
+
+/// Compact a qcow2 image in place, reclaiming space freed inside the guest
+pub fn compact_disk(path: &Path) -> Result<()> {
+    if !path.exists() {
+        bail!("Disk not found: {}", path.display());
+    }
+    ensure_no_snapshots(path)?;
+    start_operation(DiskOperationKind::Compact, path, path, |src, tmp| {
+        qemu_img::convert_image(src, tmp, "qcow2", true, &image_tuning(src))
+    })
+}
+
+/// Refuse to rewrite an image that has internal snapshots: `qemu-img
+/// convert` copies only the current state, so they (and the entries in
+/// their metadata sidecar) would be lost
+fn ensure_no_snapshots(path: &Path) -> Result<()> {
+    let snapshots = super::snapshot::list_snapshots(path)?;
+    if !snapshots.is_empty() {
+        bail!(
+            "{} has {} internal snapshot(s), which rewriting the image would drop; delete them first",
+            path.file_name().unwrap_or_default().to_string_lossy(),
+            snapshots.len()
+        );
+    }
+    Ok(())
+}
+
+/// Copy a disk image so that an interrupted copy never leaves a partial target
+pub fn copy_disk(source: &Path, target: &Path) -> Result<()> {
+    start_operation(DiskOperationKind::Copy, source, target, copy_file)
+}
+
+/// The qcow2 options an existing image was created with, so that
+/// rewriting it doesn't quietly drop them
+fn image_tuning(path: &Path) -> Qcow2Options {
+    qemu_img::image_info(path).and_then(|info| info.qcow2).unwrap_or_default()
+}
+
+/// Convert an image into `target` in another format, reporting progress
+pub fn convert_disk<F>(
+    source: &Path,
+    target: &Path,
+    format: &str,
+    compress: bool,
+    tuning: &Qcow2Options,
+    on_progress: F,
+) -> Result<()>
+where
+    F: FnMut(f32),
+{
+    if !source.exists() {
+        bail!("Disk not found: {}", source.display());
+    }
+    if target.exists() {
+        bail!("{} already exists", target.display());
+    }
+    let (temp, journal_path) = operation_paths(target)?;
+    let journal = DiskJournal {
+        operation: DiskOperationKind::Convert,
+        source: source.to_path_buf(),
+        target: target.to_path_buf(),
+        temp,
+        started: chrono::Local::now().to_rfc3339(),
+        format: Some(format.to_string()),
+        tuning: Some(*tuning),
+    };
+    run_journaled(&journal, &journal_path, |src, tmp| {
+        qemu_img::convert_image_with_progress(src, tmp, format, compress, tuning, on_progress)
+    })
+}
+
+/// Convert a VM's disk and point its launch script at the result
+///
+/// The launch script is only rewritten once the new image is complete,
+/// and the original is deleted last, so an interruption at any point
+/// leaves a bootable VM.
+pub fn convert_vm_disk<F>(
+    launch_script: &Path,
+    source: &Path,
+    format: &str,
+    compress: bool,
+    tuning: &Qcow2Options,
+    keep_original: bool,
+    on_progress: F,
+) -> Result<PathBuf>
+where
+    F: FnMut(f32),
+{
+    if let Some(vm_dir) = launch_script.parent() {
+        super::suspend::ensure_not_suspended(vm_dir)?;
+    }
+    ensure_no_snapshots(source)?;
+    let target = converted_disk_path(source, format);
+    convert_disk(source, &target, format, compress, tuning, on_progress)?;
+
+    let (Some(old_name), Some(new_name)) = (
+        source.file_name().and_then(|n| n.to_str()),
+        target.file_name().and_then(|n| n.to_str()),
+    ) else {
+        bail!("Disk file names must be valid UTF-8");
+    };
+    let content = fs::read_to_string(launch_script)
+        .with_context(|| format!("Failed to read {}", launch_script.display()))?;
+    fs::write(launch_script, retarget_disk_in_script(&content, old_name, new_name, format))
+        .with_context(|| format!("Failed to write {}", launch_script.display()))?;
+
+    if !keep_original {
+        fs::remove_file(source)
+            .with_context(|| format!("Converted, but failed to remove {}", source.display()))?;
+    }
+    Ok(target)
+}
+
+/// Where a converted copy of `source` goes: same folder and stem, new extension
+pub fn converted_disk_path(source: &Path, format: &str) -> PathBuf {
+    source.with_extension(crate::vm::create::disk_extension(format))
+}
+
+/// Space the converted image will need, at most
+///
+/// Sparse-aware formats only store allocated data, which for an overlay
+/// includes what its `backing` images hold, since conversion flattens the
+/// chain; raw images are as large as the virtual disk (sparse on most
+/// filesystems, but not on all).
+pub fn estimate_converted_size(info: &qemu_img::ImageInfo, backing: &[qemu_img::ImageInfo], format: &str) -> u64 {
+    if format == "raw" {
+        info.virtual_size
+    } else {
+        info.actual_size + qemu_img::chain_actual_size(backing)
+    }
+}
+
+/// Swap the disk file name and its `format=` in launch.sh
+///
+/// Drives may name the image directly or through `DISK="..."`; in the
+/// second case the format lives on the `file="$DISK"` drive line.
+pub fn retarget_disk_in_script(content: &str, old_name: &str, new_name: &str, format: &str) -> String {
+    let via_var = content
+        .lines()
+        .any(|line| line.trim_start().starts_with("DISK=") && line.contains(old_name));
+    let new_format = format!("format={}", format);
+
+    let mut result = String::new();
+    for line in content.lines() {
+        let is_drive = line.contains(old_name) || (via_var && line.contains("file=\"$DISK\""));
+        let line = line.replace(old_name, new_name);
+        if is_drive && line.contains("-drive") {
+            result.push_str(&RE_DRIVE_FORMAT.replace(&line, new_format.as_str()));
+        } else {
+            result.push_str(&line);
+        }
+        result.push('\n');
+    }
+    result
+}
+
+/// Scan VM folders for journals and temp files left by interrupted operations
+pub fn find_interrupted_operations(library_path: &Path) -> Vec<InterruptedDiskOp> {
+    let mut found = Vec::new();
+    let Ok(vm_dirs) = fs::read_dir(library_path) else {
+        return found;
+    };
+
+    for vm_dir in vm_dirs.flatten().map(|e| e.path()).filter(|p| p.is_dir()) {
+        let Ok(entries) = fs::read_dir(&vm_dir) else {
+            continue;
+        };
+        let files: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+
+        let mut claimed_temps = Vec::new();
+        for path in files.iter().filter(|p| has_suffix(p, JOURNAL_SUFFIX)) {
+            let journal = fs::read_to_string(path)
+                .ok()
+                .and_then(|s| serde_json::from_str::<DiskJournal>(&s).ok());
+            let temp_path = journal
+                .as_ref()
+                .map(|j| j.temp.clone())
+                .filter(|t| t.exists());
+            if let Some(ref temp) = temp_path {
+                claimed_temps.push(temp.clone());
+            }
+            found.push(InterruptedDiskOp {
+                journal_path: Some(path.clone()),
+                journal,
+                temp_path,
+            });
+        }
+
+        // Temp files with no journal (older versions used `<disk>.qcow2.tmp`)
+        for path in files.iter().filter(|p| is_orphan_temp(p)) {
+            if !claimed_temps.contains(path) {
+                found.push(InterruptedDiskOp {
+                    journal_path: None,
+                    journal: None,
+                    temp_path: Some(path.clone()),
+                });
+            }
+        }
+    }
+
+    found
+}
+
+fn has_suffix(path: &Path, suffix: &str) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.ends_with(suffix))
+}
+
+/// Temp file produced by a disk operation (journaled or legacy naming)
+fn is_orphan_temp(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    name.ends_with(TEMP_SUFFIX)
+        && [".qcow2", ".img", ".raw", ".vmdk", ".vdi", ".vhd"].iter().any(|ext| name.contains(ext))
+}
+
+/// Re-run an interrupted operation from scratch using its journal
+pub fn resume_operation(op: &InterruptedDiskOp) -> Result<()> {
+    let Some(ref journal) = op.journal else {
+        bail!("No journal for {}; it can only be cleaned up", op.describe());
+    };
+    if !journal.source.exists() {
+        bail!("Source disk no longer exists: {}", journal.source.display());
+    }
+
+    let _ = fs::remove_file(&journal.temp);
+    let journal_path = op
+        .journal_path
+        .clone()
+        .context("Journal path missing")?;
+
+    match journal.operation {
+        DiskOperationKind::Compact => run_journaled(journal, &journal_path, |src, tmp| {
+            qemu_img::convert_image(src, tmp, "qcow2", true, &image_tuning(src))
+        }),
+        DiskOperationKind::Copy => run_journaled(journal, &journal_path, copy_file),
+        DiskOperationKind::Convert => {
+            let format = journal.format.clone().unwrap_or_else(|| "qcow2".to_string());
+            let tuning = journal.tuning.unwrap_or_default();
+            run_journaled(journal, &journal_path, |src, tmp| {
+                qemu_img::convert_image(src, tmp, &format, false, &tuning)
+            })
+        }
+    }
+}
+
+/// Remove the temp file and journal of an interrupted operation
+pub fn discard_operation(op: &InterruptedDiskOp) -> Result<()> {
+    if let Some(ref temp) = op.temp_path {
+        fs::remove_file(temp)
+            .with_context(|| format!("Failed to remove {}", temp.display()))?;
+    }
+    if let Some(ref journal) = op.journal_path {
+        fs::remove_file(journal)
+            .with_context(|| format!("Failed to remove {}", journal.display()))?;
+    }
+    Ok(())
+}
+
+/// Resume what can be resumed and clean up the rest
+pub fn recover_operations(ops: &[InterruptedDiskOp]) -> Result<usize> {
+    let mut resumed = 0;
+    for op in ops {
+        if op.can_resume() {
+            resume_operation(op)?;
+            resumed += 1;
+        } else {
+            discard_operation(op)?;
+        }
+    }
+    Ok(resumed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("vm-curator-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("vm")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_copy_disk_leaves_no_temp_files() {
+        let dir = test_dir("copy");
+        let source = dir.join("source.img");
+        let target = dir.join("vm").join("vm.qcow2");
+        fs::write(&source, b"disk data").unwrap();
+
+        copy_disk(&source, &target).unwrap();
+
+        assert_eq!(fs::read(&target).unwrap(), b"disk data");
+        assert!(find_interrupted_operations(&dir).is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_retarget_disk_in_script() {
+        let script = "DISK=\"$VM_DIR/xp.vmdk\"\nqemu-system-i386 \\\n    -drive if=pflash,format=raw,file=\"$OVMF_VARS\" \\\n    -drive file=\"$DISK\",format=vmdk,if=ide,index=0,media=disk \\\n    -m 512M\n";
+        let result = retarget_disk_in_script(script, "xp.vmdk", "xp.qcow2", "qcow2");
+        assert!(result.contains("DISK=\"$VM_DIR/xp.qcow2\"\n"));
+        assert!(result.contains("-drive file=\"$DISK\",format=qcow2,if=ide,index=0,media=disk \\"));
+        assert!(result.contains("-drive if=pflash,format=raw,file=\"$OVMF_VARS\""));
+
+        let direct = "qemu-system-i386 -drive file=/vms/dos/dos.img,format=raw,if=ide -m 16M\n";
+        let result = retarget_disk_in_script(direct, "dos.img", "dos.qcow2", "qcow2");
+        assert_eq!(result, "qemu-system-i386 -drive file=/vms/dos/dos.qcow2,format=qcow2,if=ide -m 16M\n");
+    }
+
+    #[test]
+    fn test_interrupted_copy_is_found_and_resumed() {
+        let dir = test_dir("resume");
+        let source = dir.join("source.img");
+        let target = dir.join("vm").join("vm.qcow2");
+        fs::write(&source, b"disk data").unwrap();
+
+        // Simulate a crash: journal and partial temp written, target never created
+        let (temp, journal_path) = operation_paths(&target).unwrap();
+        let journal = DiskJournal {
+            operation: DiskOperationKind::Copy,
+            source: source.clone(),
+            target: target.clone(),
+            temp: temp.clone(),
+            started: "2024-01-01T00:00:00+00:00".to_string(),
+            format: None,
+            tuning: None,
+        };
+        fs::write(&journal_path, serde_json::to_string(&journal).unwrap()).unwrap();
+        fs::write(&temp, b"disk").unwrap();
+        fs::write(dir.join("vm").join("old.qcow2.tmp"), b"legacy").unwrap();
+
+        let ops = find_interrupted_operations(&dir);
+        assert_eq!(ops.len(), 2);
+        assert_eq!(ops.iter().filter(|op| op.can_resume()).count(), 1);
+
+        assert_eq!(recover_operations(&ops).unwrap(), 1);
+        assert_eq!(fs::read(&target).unwrap(), b"disk data");
+        assert!(find_interrupted_operations(&dir).is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
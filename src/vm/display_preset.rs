@@ -0,0 +1,288 @@
+//! Display presets
+//!
+//! Retro guests run at 640x480 or 800x600, a postage stamp on a modern
+//! monitor. A display preset picks the guest resolution (an EDID hint on
+//! std/qxl graphics), how the window scales it, and whether QEMU starts
+//! full screen. Integer scaling and full screen live in a managed section
+//! of launch.sh; zoom-to-fit is an option of the GTK `-display` itself.
+
+use anyhow::{bail, Context, Result};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::vm::lifecycle::{insert_args_section, remove_args_section};
+use crate::vm::qemu_config::{parse_resolution, QemuConfig};
+
+// Display preset section markers
+const PRESET_MARKER_START: &str = "# >>> Display Preset (managed by vm-curator) >>>";
+const PRESET_MARKER_END: &str = "# <<< Display Preset <<<";
+
+/// Guest resolutions offered ("auto" leaves the guest to pick)
+pub const RESOLUTION_PRESETS: &[&str] = &["auto", "640x480", "800x600", "1024x768", "1280x1024", "1600x1200", "1920x1080"];
+
+/// Window scaling: 1x is QEMU's default, fit stretches to the window,
+/// 2x/3x enlarge by whole pixels (GTK display)
+pub const SCALE_OPTIONS: &[&str] = &["1x", "fit", "2x", "3x"];
+
+/// Editable fields, in display order
+pub const PRESET_FIELDS: &[&str] = &["Resolution", "Scaling", "Full screen"];
+
+static RE_DISPLAY: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"-display\s+([\w-]+)((?:,[\w.-]+=[\w./-]+)*)").expect("Invalid regex: RE_DISPLAY")
+});
+
+static RE_VGA_EDID: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"-device\s+VGA,edid=on,xres=\d+,yres=\d+").expect("Invalid regex: RE_VGA_EDID")
+});
+
+static RE_QXL_RES: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"-device\s+qxl-vga,xres=\d+,yres=\d+").expect("Invalid regex: RE_QXL_RES")
+});
+
+static RE_VGA_PLAIN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"-vga\s+(std|qxl)\b").expect("Invalid regex: RE_VGA_PLAIN")
+});
+
+/// How a VM's display is sized and shown
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DisplayPreset {
+    /// Guest resolution hint ("auto" for none)
+    pub resolution: String,
+    /// One of `SCALE_OPTIONS`
+    pub scale: String,
+    /// Start QEMU with `-full-screen`
+    pub full_screen: bool,
+}
+
+impl Default for DisplayPreset {
+    fn default() -> Self {
+        Self {
+            resolution: "auto".to_string(),
+            scale: "1x".to_string(),
+            full_screen: false,
+        }
+    }
+}
+
+impl DisplayPreset {
+    /// The preset a parsed VM is currently using
+    pub fn from_config(config: &QemuConfig) -> Self {
+        let mut preset = config.display_preset.clone().unwrap_or_default();
+        preset.resolution = config.resolution.clone().unwrap_or_else(|| "auto".to_string());
+        preset
+    }
+
+    /// Display value of a field (index into `PRESET_FIELDS`)
+    pub fn field_value(&self, field: usize) -> String {
+        match field {
+            0 => self.resolution.clone(),
+            1 => self.scale.clone(),
+            _ => if self.full_screen { "on" } else { "off" }.to_string(),
+        }
+    }
+
+    /// Step a field (index into `PRESET_FIELDS`) through its values
+    pub fn cycle(&mut self, field: usize, delta: i32) {
+        match field {
+            0 => cycle_option(&mut self.resolution, RESOLUTION_PRESETS, delta),
+            1 => cycle_option(&mut self.scale, SCALE_OPTIONS, delta),
+            _ => self.full_screen = !self.full_screen,
+        }
+    }
+
+    /// Scaling and full screen, for info displays (e.g. "2x, full screen")
+    pub fn summary(&self) -> String {
+        if self.full_screen {
+            format!("{}, full screen", self.scale)
+        } else {
+            self.scale.clone()
+        }
+    }
+
+    /// Integer scale factor, if any
+    fn integer_scale(&self) -> Option<u32> {
+        self.scale.strip_suffix('x')?.parse().ok().filter(|n| *n > 1)
+    }
+
+    /// GTK zoom-to-fit value for this scaling mode
+    pub fn zoom_to_fit(&self) -> Option<&'static str> {
+        match self.scale.as_str() {
+            "fit" => Some("on"),
+            _ if self.integer_scale().is_some() => Some("off"),
+            _ => None,
+        }
+    }
+
+    /// Whether the managed section is needed at all
+    fn needs_section(&self) -> bool {
+        self.full_screen || self.scale != "1x"
+    }
+}
+
+fn cycle_option(current: &mut String, options: &[&str], delta: i32) {
+    let current_idx = options.iter().position(|&o| o == current.as_str()).unwrap_or(0);
+    let new_idx = (current_idx as i32 + delta).rem_euclid(options.len() as i32) as usize;
+    *current = options[new_idx].to_string();
+}
+
+/// Apply a display preset to a VM's launch script
+pub fn set_display_preset(script_path: &Path, preset: &DisplayPreset) -> Result<()> {
+    let content = std::fs::read_to_string(script_path).context("Failed to read launch.sh")?;
+    let new_content = apply_display_preset(&content, preset)?;
+    std::fs::write(script_path, new_content).context("Failed to write launch.sh")?;
+    Ok(())
+}
+
+/// Read the scaling and full-screen settings from a launch script
+pub fn parse_display_preset(content: &str) -> Option<DisplayPreset> {
+    let mut in_section = false;
+    let mut preset = DisplayPreset::default();
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed == PRESET_MARKER_START {
+            in_section = true;
+        } else if trimmed == PRESET_MARKER_END {
+            return in_section.then_some(preset);
+        } else if in_section {
+            if let Some(value) = trimmed.strip_prefix("DISPLAY_SCALE=") {
+                preset.scale = value.trim_matches('"').to_string();
+            } else if let Some(value) = trimmed.strip_prefix("DISPLAY_FULL_SCREEN=") {
+                preset.full_screen = value.trim_matches('"') == "on";
+            }
+        }
+    }
+    None
+}
+
+/// Keep only the `-display` options a backend understands, setting zoom-to-fit on GTK
+pub fn rewrite_display_options(content: &str, backend: Option<&str>, zoom_to_fit: Option<&str>) -> String {
+    RE_DISPLAY
+        .replace_all(content, |caps: &Captures| {
+            let name = backend.unwrap_or(&caps[1]);
+            let mut spec = name.to_string();
+            for opt in caps[2].split(',').filter(|o| !o.is_empty()) {
+                let key = opt.split('=').next().unwrap_or("");
+                let keep = match key {
+                    "zoom-to-fit" => false,
+                    "gl" => matches!(name, "gtk" | "sdl" | "spice-app" | "egl-headless"),
+                    // Other options are backend-specific
+                    _ => name == &caps[1],
+                };
+                if keep {
+                    spec.push(',');
+                    spec.push_str(opt);
+                }
+            }
+            if let ("gtk", Some(value)) = (name, zoom_to_fit) {
+                spec.push_str(&format!(",zoom-to-fit={}", value));
+            }
+            format!("-display {}", spec)
+        })
+        .into_owned()
+}
+
+fn apply_display_preset(content: &str, preset: &DisplayPreset) -> Result<String> {
+    let content = remove_preset_section(content);
+    let content = set_resolution(&content, parse_resolution(&preset.resolution))?;
+    let content = rewrite_display_options(&content, None, preset.zoom_to_fit());
+
+    Ok(if preset.needs_section() {
+        insert_args_section(&content, &generate_preset_section(preset), "$DISPLAY_PRESET_ARGS")
+    } else {
+        content
+    })
+}
+
+/// Point the std/qxl adapter's EDID hint at a resolution (None removes it)
+fn set_resolution(content: &str, resolution: Option<(u32, u32)>) -> Result<String> {
+    let has_hint = RE_VGA_EDID.is_match(content) || RE_QXL_RES.is_match(content);
+
+    let Some((xres, yres)) = resolution else {
+        let content = RE_VGA_EDID.replace_all(content, "-vga std");
+        return Ok(RE_QXL_RES.replace_all(&content, "-vga qxl").into_owned());
+    };
+
+    if !has_hint && !RE_VGA_PLAIN.is_match(content) {
+        bail!("Resolution presets need std or qxl graphics");
+    }
+
+    let vga = format!("-device VGA,edid=on,xres={},yres={}", xres, yres);
+    let qxl = format!("-device qxl-vga,xres={},yres={}", xres, yres);
+    let content = RE_VGA_EDID.replace_all(content, vga.as_str());
+    let content = RE_QXL_RES.replace_all(&content, qxl.as_str());
+    let content = RE_VGA_PLAIN.replace_all(&content, |caps: &Captures| {
+        if &caps[1] == "std" { vga.clone() } else { qxl.clone() }
+    });
+    Ok(content.into_owned())
+}
+
+fn generate_preset_section(preset: &DisplayPreset) -> String {
+    let mut section = String::new();
+    section.push_str(PRESET_MARKER_START);
+    section.push('\n');
+    section.push_str(&format!("DISPLAY_SCALE=\"{}\"\n", preset.scale));
+    section.push_str(&format!(
+        "DISPLAY_FULL_SCREEN=\"{}\"\n",
+        if preset.full_screen { "on" } else { "off" }
+    ));
+    // GTK draws the guest at the toolkit's integer scale factor
+    if let Some(factor) = preset.integer_scale() {
+        section.push_str(&format!("export GDK_SCALE={}\n", factor));
+    }
+    let args = if preset.full_screen { "-full-screen" } else { "" };
+    section.push_str(&format!("DISPLAY_PRESET_ARGS=\"{}\"\n", args));
+    section.push_str(PRESET_MARKER_END);
+    section.push('\n');
+    section
+}
+
+fn remove_preset_section(content: &str) -> String {
+    remove_args_section(content, PRESET_MARKER_START, PRESET_MARKER_END, "$DISPLAY_PRESET_ARGS")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = "VM_DIR=\"/vms/t\"\ncase \"$1\" in\n    \"\")\n        qemu-system-i386 \\\n            -vga std -display gtk,gl=on -m 64M\n        ;;\nesac\n";
+
+    #[test]
+    fn test_display_preset_round_trip() {
+        let preset = DisplayPreset {
+            resolution: "640x480".to_string(),
+            scale: "2x".to_string(),
+            full_screen: true,
+        };
+        let applied = apply_display_preset(SCRIPT, &preset).unwrap();
+
+        assert!(applied.contains("-device VGA,edid=on,xres=640,yres=480"));
+        assert!(applied.contains("-display gtk,gl=on,zoom-to-fit=off"));
+        assert!(applied.contains("export GDK_SCALE=2"));
+        assert!(applied.contains("DISPLAY_PRESET_ARGS=\"-full-screen\""));
+        assert!(applied.contains("-m 64M $DISPLAY_PRESET_ARGS"));
+
+        let parsed = parse_display_preset(&applied).unwrap();
+        assert_eq!((parsed.scale.as_str(), parsed.full_screen), ("2x", true));
+
+        let restored = apply_display_preset(&applied, &DisplayPreset::default()).unwrap();
+        assert!(restored.contains("-vga std -display gtk,gl=on -m 64M\n"));
+        assert!(!restored.contains("DISPLAY_"));
+        assert_eq!(parse_display_preset(&restored), None);
+    }
+
+    #[test]
+    fn test_rewrite_display_options_on_backend_change() {
+        let gtk = "qemu-system-i386 -display gtk,gl=on,zoom-to-fit=on,show-menubar=off\n";
+        assert_eq!(
+            rewrite_display_options(gtk, Some("sdl"), None),
+            "qemu-system-i386 -display sdl,gl=on\n"
+        );
+        assert_eq!(
+            rewrite_display_options(gtk, None, Some("on")),
+            "qemu-system-i386 -display gtk,gl=on,show-menubar=off,zoom-to-fit=on\n"
+        );
+        assert!(set_resolution("qemu-system-i386 -vga cirrus\n", Some((800, 600))).is_err());
+    }
+}
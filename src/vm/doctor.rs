@@ -0,0 +1,315 @@
+//! VM doctor: diagnose and repair common launch script problems
+//!
+//! Checks a VM for the things that most often stop it from booting after
+//! it has been moved, copied from another machine or left alone for a few
+//! QEMU releases: disk images that aren't where the script says, absolute
+//! paths from the machine it was created on, a missing install ISO,
+//! devices the installed QEMU lacks, and KVM on a host that can't provide
+//! it. Each finding carries a fix to the launch script when there is a
+//! safe one.
+
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+
+use super::launch_parser::extract_shell_variables;
+use super::qemu_config::QemuConfig;
+use super::validation::{IssueKind, ValidationIssue};
+use super::DiscoveredVm;
+use crate::commands::qemu_system::Accelerator;
+
+/// Absolute paths in a script, starting where a shell word or option value does
+static RE_ABSOLUTE_PATH: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?:^|[\s"'=,])(/[A-Za-z0-9._+~@%/-]+)"#).unwrap());
+
+/// Disk image extensions, for finding a renamed disk
+const DISK_EXTENSIONS: &[&str] = &["qcow2", "img", "raw", "vmdk", "vdi", "vhd", "vhdx"];
+
+/// Host paths that are never copied along with a VM
+const SYSTEM_PREFIXES: &[&str] = &["/dev/", "/proc/", "/sys/", "/run/", "/tmp/", "/usr/", "/etc/"];
+
+/// A problem found in a VM
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub problem: String,
+    /// What to do when there's no automatic fix
+    pub advice: Option<String>,
+    pub fix: Option<Fix>,
+}
+
+/// A repair to the launch script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fix {
+    /// Replace a path or option (whole words only)
+    Replace { from: String, to: String },
+    /// Empty the ISO variable so the VM boots without install media
+    ClearIso,
+    /// Run under TCG: drop KVM acceleration and the host CPU model
+    UseTcg,
+}
+
+impl Fix {
+    pub fn label(&self) -> String {
+        match self {
+            Fix::Replace { from, to } => format!("Replace {} with {}", from, to),
+            Fix::ClearIso => "Detach the ISO (ISO=\"\")".to_string(),
+            Fix::UseTcg => "Turn off KVM/WHPX (software emulation, -cpu max for -cpu host)".to_string(),
+        }
+    }
+
+    /// The script with the fix applied
+    pub fn apply(&self, content: &str) -> String {
+        match self {
+            Fix::Replace { from, to } => replace_word(content, from, to),
+            Fix::ClearIso => content
+                .lines()
+                .map(|line| if line.starts_with("ISO=") { "ISO=\"\"" } else { line })
+                .map(|line| format!("{}\n", line))
+                .collect(),
+            Fix::UseTcg => {
+                let mut lines: Vec<String> = Vec::new();
+                for line in content.lines() {
+                    match line.trim() {
+                        "-enable-kvm \\" => continue,
+                        "-enable-kvm" => {
+                            // The previous argument now ends the command
+                            if let Some(prev) = lines.last_mut() {
+                                *prev = prev.trim_end().trim_end_matches('\\').trim_end().to_string();
+                            }
+                            continue;
+                        }
+                        _ => lines.push(line.replace(" -enable-kvm", "")),
+                    }
+                }
+                let content = lines.join("\n") + "\n";
+                let content = replace_word(&content, "accel=kvm", "accel=tcg");
+                let content = replace_word(&content, "-accel kvm", "-accel tcg");
+                let content = replace_word(&content, "accel=whpx", "accel=tcg");
+                let content = replace_word(&content, "-accel whpx", "-accel tcg");
+                replace_word(&content, "-cpu host", "-cpu max")
+            }
+        }
+    }
+}
+
+/// Replace `from` where it isn't followed by more of the same word
+fn replace_word(content: &str, from: &str, to: &str) -> String {
+    let mut result = String::new();
+    let mut rest = content;
+    while let Some(pos) = rest.find(from) {
+        let after = &rest[pos + from.len()..];
+        let whole = after.chars().next().is_none_or(|c| c.is_whitespace() || ",\"'\\;".contains(c));
+        result.push_str(&rest[..pos]);
+        result.push_str(if whole { to } else { from });
+        rest = after;
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Diagnose a local QEMU VM
+pub fn diagnose(vm: &DiscoveredVm) -> Vec<Finding> {
+    let emulator = vm.config.emulator.command();
+    let issues = super::validation::validate_script(emulator, &vm.config.raw_script);
+    let accel_usable = crate::commands::qemu_system::is_accel_available() && is_host_arch(emulator);
+    check(&vm.path, &vm.config, accel_usable, &issues)
+}
+
+/// Repair a VM's launch script
+pub fn apply_fix(vm: &DiscoveredVm, fix: &Fix) -> Result<()> {
+    let content = std::fs::read_to_string(&vm.launch_script).context("Failed to read launch.sh")?;
+    std::fs::write(&vm.launch_script, fix.apply(&content)).context("Failed to write launch.sh")
+}
+
+/// Whether KVM or WHPX can accelerate this emulator on this host
+fn is_host_arch(emulator: &str) -> bool {
+    let arch = emulator.strip_prefix("qemu-system-").unwrap_or(emulator);
+    match std::env::consts::ARCH {
+        "x86_64" => arch == "x86_64" || arch == "i386",
+        "aarch64" => arch == "aarch64" || arch == "arm",
+        host => arch == host,
+    }
+}
+
+fn check(vm_dir: &Path, config: &QemuConfig, accel_usable: bool, issues: &[ValidationIssue]) -> Vec<Finding> {
+    let content = &config.raw_script;
+    let vars = extract_shell_variables(content, vm_dir);
+    let iso = vars.get("ISO").filter(|iso| !iso.is_empty()).map(PathBuf::from);
+    let mut findings = Vec::new();
+    let mut relinked: Vec<String> = Vec::new();
+
+    // Disk images
+    let disks: Vec<&Path> = config
+        .disks
+        .iter()
+        .filter(|disk| disk.remote.is_none() && Some(&disk.path) != iso.as_ref())
+        .map(|disk| disk.path.as_path())
+        .collect();
+    for path in disks.iter().filter(|path| !path.exists()) {
+        let name = file_name(path);
+        let literal = path.to_string_lossy().to_string();
+        let fix = if vm_dir.join(&name).is_file() && content.contains(&literal) {
+            // Copied from another machine with its absolute path
+            relinked.push(literal.clone());
+            Some(Fix::Replace { from: literal, to: format!("$VM_DIR/{}", name) })
+        } else {
+            // Renamed: the folder's only disk image no other drive uses
+            let candidates = unused_disk_images(vm_dir, &disks);
+            match candidates.as_slice() {
+                [candidate] if content.contains(&format!("$VM_DIR/{}", name)) => Some(Fix::Replace {
+                    from: format!("$VM_DIR/{}", name),
+                    to: format!("$VM_DIR/{}", candidate),
+                }),
+                _ => None,
+            }
+        };
+        findings.push(Finding {
+            problem: format!("Disk image not found: {}", path.display()),
+            advice: fix.is_none().then(|| "Copy the disk image into the VM folder, or restore it from a backup".to_string()),
+            fix,
+        });
+    }
+
+    // Other files referenced by paths from the machine the VM came from
+    for line in content.lines().filter(|l| !l.trim_start().starts_with('#')) {
+        for caps in RE_ABSOLUTE_PATH.captures_iter(line) {
+            let literal = caps[1].to_string();
+            let path = Path::new(&literal);
+            let name = file_name(path);
+            let foreign = !path.exists()
+                && !SYSTEM_PREFIXES.iter().any(|prefix| literal.starts_with(prefix))
+                && !name.is_empty()
+                && vm_dir.join(&name).is_file();
+            if foreign && !relinked.contains(&literal) {
+                relinked.push(literal.clone());
+                findings.push(Finding {
+                    problem: format!("Path from another machine: {}", literal),
+                    advice: None,
+                    fix: Some(Fix::Replace { from: literal, to: format!("$VM_DIR/{}", name) }),
+                });
+            }
+        }
+    }
+
+    // Install media
+    if let Some(iso) = iso.filter(|iso| !iso.exists() && !relinked.contains(&iso.to_string_lossy().to_string())) {
+        findings.push(Finding {
+            problem: format!("Install ISO not found: {}", iso.display()),
+            advice: None,
+            fix: Some(Fix::ClearIso),
+        });
+    }
+
+    // Devices and machine types the installed QEMU lacks
+    for issue in issues {
+        let flags: &[&str] = match issue.kind {
+            IssueKind::Device => &["-device"],
+            IssueKind::Machine => &["-machine", "-M"],
+        };
+        let fix = issue.suggestion.as_ref().and_then(|suggestion| {
+            flags
+                .iter()
+                .map(|flag| format!("{} {}", flag, issue.name))
+                .find(|option| content.contains(option.as_str()))
+                .map(|option| Fix::Replace {
+                    to: option.replace(&issue.name, suggestion),
+                    from: option,
+                })
+        });
+        findings.push(Finding {
+            problem: format!("{}", issue),
+            advice: fix.is_none().then(|| "Change it in Edit Raw Config, or install a QEMU build that has it".to_string()),
+            fix,
+        });
+    }
+
+    // KVM or WHPX on a host (or for a guest architecture) without it
+    let uses = |accel: Accelerator| {
+        let (setting, option) = (format!("accel={}", accel.name()), format!("-accel {}", accel.name()));
+        content
+            .lines()
+            .filter(|l| !l.trim_start().starts_with('#'))
+            .any(|l| l.contains(&setting) || l.contains(&option) || accel == Accelerator::Kvm && l.contains("-enable-kvm"))
+    };
+    let host = Accelerator::host();
+    let unusable = [Accelerator::Kvm, Accelerator::Whpx]
+        .into_iter()
+        .find(|accel| uses(*accel) && !(*accel == host && accel_usable));
+    if let Some(accel) = unusable {
+        findings.push(Finding {
+            problem: format!("{} is enabled but not available for this emulator on this host", accel.label()),
+            advice: None,
+            fix: Some(Fix::UseTcg),
+        });
+    }
+
+    findings
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+/// Disk images in the VM folder that no drive refers to (by file name)
+fn unused_disk_images(vm_dir: &Path, disks: &[&Path]) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(vm_dir) else {
+        return Vec::new();
+    };
+    let mut images: Vec<String> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|e| e.to_str()).is_some_and(|e| DISK_EXTENSIONS.contains(&e)))
+        .map(|path| file_name(&path))
+        .filter(|name| !disks.iter().any(|disk| file_name(disk) == *name))
+        .collect();
+    images.sort();
+    images
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnose_and_fix() {
+        let dir = std::env::temp_dir().join(format!("vm-curator-doctor-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("win98.qcow2"), "").unwrap();
+        std::fs::write(dir.join("win98-data.img"), "").unwrap();
+        let script = "VM_DIR=\"$(dirname \"$(readlink -f \"$0\")\")\"\n\
+            ISO=\"/nowhere/win98.iso\"\n\
+            qemu-system-i386 \\\n    -enable-kvm \\\n    -machine pc,accel=kvm \\\n    -cpu host \\\n    \
+            -drive file=/home/old/vms/win98/win98.qcow2,if=ide \\\n    -drive file=\"$VM_DIR/data.img\",if=ide \\\n    \
+            -device sb16 -device sb16x\n";
+        let config = crate::vm::launch_parser::parse_launch_script(&dir.join("launch.sh"), script).unwrap();
+        let issue = ValidationIssue {
+            kind: IssueKind::Device,
+            name: "sb16".to_string(),
+            emulator: "qemu-system-i386".to_string(),
+            suggestion: Some("AC97".to_string()),
+        };
+
+        let findings = check(&dir, &config, false, &[issue]);
+        let fixes: Vec<Option<Fix>> = findings.iter().map(|f| f.fix.clone()).collect();
+        assert_eq!(
+            fixes,
+            vec![
+                Some(Fix::Replace { from: "/home/old/vms/win98/win98.qcow2".to_string(), to: "$VM_DIR/win98.qcow2".to_string() }),
+                Some(Fix::Replace { from: "$VM_DIR/data.img".to_string(), to: "$VM_DIR/win98-data.img".to_string() }),
+                Some(Fix::ClearIso),
+                Some(Fix::Replace { from: "-device sb16".to_string(), to: "-device AC97".to_string() }),
+                Some(Fix::UseTcg),
+            ]
+        );
+
+        let fixed = fixes.iter().flatten().fold(script.to_string(), |content, fix| fix.apply(&content));
+        assert!(fixed.contains("file=$VM_DIR/win98.qcow2,") && fixed.contains("\"$VM_DIR/win98-data.img\""));
+        assert!(fixed.contains("ISO=\"\"\n") && fixed.contains("-device AC97 -device sb16x"));
+        assert!(!fixed.contains("kvm") && fixed.contains("accel=tcg") && fixed.contains("-cpu max"));
+
+        let config = crate::vm::launch_parser::parse_launch_script(&dir.join("launch.sh"), &fixed).unwrap();
+        assert!(check(&dir, &config, false, &[]).is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
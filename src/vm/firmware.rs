@@ -0,0 +1,330 @@
+//! OVMF (UEFI) and U-Boot firmware detection
+//!
+//! UEFI guests boot from two pflash drives: the read-only firmware CODE
+//! image and a writable copy of the matching VARS (NVRAM) template. The two
+//! must come from the same build (a 4 MiB CODE image won't boot with a
+//! 2 MiB VARS file), so they are always detected as a pair.
+//!
+//! Detection first reads the QEMU firmware descriptors that distributions
+//! install under `/usr/share/qemu/firmware` (the same files libvirt uses),
+//! then falls back to the well-known paths of the common distributions.
+//! ARM and RISC-V `virt` boards use the same scheme with EDK2's AAVMF and
+//! RISCV_VIRT builds, or boot U-Boot from the distribution's u-boot package.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Directories holding QEMU firmware descriptors, lowest precedence first.
+/// A descriptor in a later directory overrides one with the same file name.
+const DESCRIPTOR_DIRS: &[&str] = &["/usr/share/qemu/firmware", "/etc/qemu/firmware"];
+
+/// Features that mark firmware builds for confidential-computing guests
+const UNSUPPORTED_FEATURES: &[&str] = &["amd-sev", "amd-sev-es", "amd-sev-snp", "intel-tdx"];
+
+/// Known CODE/VARS pairs without Secure Boot, in order of preference
+const PLAIN_PAIRS: &[(&str, &str)] = &[
+    // Arch Linux (4 MiB builds)
+    ("/usr/share/edk2/x64/OVMF_CODE.4m.fd", "/usr/share/edk2/x64/OVMF_VARS.4m.fd"),
+    ("/usr/share/edk2-ovmf/x64/OVMF_CODE.4m.fd", "/usr/share/edk2-ovmf/x64/OVMF_VARS.4m.fd"),
+    ("/usr/share/OVMF/x64/OVMF_CODE.4m.fd", "/usr/share/OVMF/x64/OVMF_VARS.4m.fd"),
+    ("/usr/share/ovmf/x64/OVMF_CODE.4m.fd", "/usr/share/ovmf/x64/OVMF_VARS.4m.fd"),
+    // Arch Linux (legacy naming)
+    ("/usr/share/edk2-ovmf/x64/OVMF_CODE.fd", "/usr/share/edk2-ovmf/x64/OVMF_VARS.fd"),
+    ("/usr/share/edk2/x64/OVMF_CODE.fd", "/usr/share/edk2/x64/OVMF_VARS.fd"),
+    // Debian/Ubuntu
+    ("/usr/share/OVMF/OVMF_CODE_4M.fd", "/usr/share/OVMF/OVMF_VARS_4M.fd"),
+    ("/usr/share/OVMF/OVMF_CODE.fd", "/usr/share/OVMF/OVMF_VARS.fd"),
+    // Fedora/RHEL/CentOS
+    ("/usr/share/edk2/ovmf/OVMF_CODE.fd", "/usr/share/edk2/ovmf/OVMF_VARS.fd"),
+    // openSUSE
+    ("/usr/share/qemu/ovmf-x86_64-code.bin", "/usr/share/qemu/ovmf-x86_64-vars.bin"),
+    // NixOS
+    ("/run/libvirt/nix-ovmf/OVMF_CODE.fd", "/run/libvirt/nix-ovmf/OVMF_VARS.fd"),
+    // Generic
+    ("/usr/share/ovmf/OVMF_CODE.fd", "/usr/share/ovmf/OVMF_VARS.fd"),
+    ("/usr/share/qemu/OVMF_CODE.fd", "/usr/share/qemu/OVMF_VARS.fd"),
+    ("/usr/share/ovmf/x64/OVMF_CODE.fd", "/usr/share/ovmf/x64/OVMF_VARS.fd"),
+];
+
+/// Known Secure Boot CODE/VARS pairs, in order of preference. VARS templates
+/// with pre-enrolled Microsoft keys come first where the distro ships them.
+const SECURE_BOOT_PAIRS: &[(&str, &str)] = &[
+    // Debian/Ubuntu (pre-enrolled Microsoft keys)
+    ("/usr/share/OVMF/OVMF_CODE_4M.secboot.fd", "/usr/share/OVMF/OVMF_VARS_4M.ms.fd"),
+    ("/usr/share/OVMF/OVMF_CODE_4M.ms.fd", "/usr/share/OVMF/OVMF_VARS_4M.ms.fd"),
+    ("/usr/share/OVMF/OVMF_CODE.secboot.fd", "/usr/share/OVMF/OVMF_VARS.ms.fd"),
+    ("/usr/share/OVMF/OVMF_CODE.ms.fd", "/usr/share/OVMF/OVMF_VARS.ms.fd"),
+    // Fedora/RHEL (pre-enrolled Microsoft keys)
+    ("/usr/share/edk2/ovmf/OVMF_CODE.secboot.fd", "/usr/share/edk2/ovmf/OVMF_VARS.secboot.fd"),
+    // openSUSE (pre-enrolled Microsoft keys)
+    ("/usr/share/qemu/ovmf-x86_64-ms-code.bin", "/usr/share/qemu/ovmf-x86_64-ms-vars.bin"),
+    // Arch Linux (no enrolled keys; Secure Boot capable)
+    ("/usr/share/edk2/x64/OVMF_CODE.secboot.4m.fd", "/usr/share/edk2/x64/OVMF_VARS.4m.fd"),
+    ("/usr/share/OVMF/x64/OVMF_CODE.secboot.4m.fd", "/usr/share/OVMF/x64/OVMF_VARS.4m.fd"),
+    ("/usr/share/ovmf/x64/OVMF_CODE.secboot.4m.fd", "/usr/share/ovmf/x64/OVMF_VARS.4m.fd"),
+    ("/usr/share/edk2-ovmf/x64/OVMF_CODE.secboot.fd", "/usr/share/edk2-ovmf/x64/OVMF_VARS.fd"),
+    // Generic
+    ("/usr/share/ovmf/OVMF_CODE.secboot.fd", "/usr/share/ovmf/OVMF_VARS.fd"),
+    ("/usr/share/qemu/OVMF_CODE.secboot.fd", "/usr/share/qemu/OVMF_VARS.fd"),
+];
+
+/// Known EDK2 CODE/VARS pairs for ARM and RISC-V `virt` boards:
+/// (architecture, CODE, VARS), in order of preference
+const EDK2_PAIRS: &[(&str, &str, &str)] = &[
+    // Debian/Ubuntu (qemu-efi-aarch64, qemu-efi-arm, qemu-efi-riscv64)
+    ("aarch64", "/usr/share/AAVMF/AAVMF_CODE.fd", "/usr/share/AAVMF/AAVMF_VARS.fd"),
+    ("arm", "/usr/share/AAVMF/AAVMF32_CODE.fd", "/usr/share/AAVMF/AAVMF32_VARS.fd"),
+    ("riscv64", "/usr/share/qemu-efi-riscv64/RISCV_VIRT_CODE.fd", "/usr/share/qemu-efi-riscv64/RISCV_VIRT_VARS.fd"),
+    // Fedora/RHEL
+    ("aarch64", "/usr/share/edk2/aarch64/QEMU_EFI-pflash.raw", "/usr/share/edk2/aarch64/vars-template-pflash.raw"),
+    ("arm", "/usr/share/edk2/arm/QEMU_EFI-pflash.raw", "/usr/share/edk2/arm/vars-template-pflash.raw"),
+    ("riscv64", "/usr/share/edk2/riscv/RISCV_VIRT_CODE.fd", "/usr/share/edk2/riscv/RISCV_VIRT_VARS.fd"),
+    // Arch Linux
+    ("aarch64", "/usr/share/edk2/aarch64/QEMU_CODE.fd", "/usr/share/edk2/aarch64/QEMU_VARS.fd"),
+    ("arm", "/usr/share/edk2/arm/QEMU_CODE.fd", "/usr/share/edk2/arm/QEMU_VARS.fd"),
+    ("riscv64", "/usr/share/edk2/riscv64/RISCV_VIRT_CODE.fd", "/usr/share/edk2/riscv64/RISCV_VIRT_VARS.fd"),
+    // openSUSE
+    ("aarch64", "/usr/share/qemu/aavmf-aarch64-code.bin", "/usr/share/qemu/aavmf-aarch64-vars.bin"),
+];
+
+/// Known U-Boot builds for QEMU's `virt` boards: (architecture, image).
+/// The RISC-V one is the S-mode build that OpenSBI hands over to.
+const UBOOT_IMAGES: &[(&str, &str)] = &[
+    // Debian/Ubuntu (u-boot-qemu)
+    ("aarch64", "/usr/lib/u-boot/qemu_arm64/u-boot.bin"),
+    ("arm", "/usr/lib/u-boot/qemu_arm/u-boot.bin"),
+    ("riscv64", "/usr/lib/u-boot/qemu-riscv64_smode/uboot.elf"),
+    // Fedora (uboot-images-armv8, uboot-images-riscv64)
+    ("aarch64", "/usr/share/uboot/qemu_arm64/u-boot.bin"),
+    ("arm", "/usr/share/uboot/qemu_arm/u-boot.bin"),
+    ("riscv64", "/usr/share/uboot/qemu-riscv64_smode/u-boot.bin"),
+];
+
+/// A matched OVMF CODE image and VARS template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OvmfFirmware {
+    /// Read-only firmware image
+    pub code: PathBuf,
+    /// NVRAM template copied into each VM's directory
+    pub vars_template: PathBuf,
+    /// Firmware enforces Secure Boot (needs SMM and secure pflash)
+    pub secure_boot: bool,
+    /// VARS template ships with Secure Boot keys already enrolled
+    pub enrolled_keys: bool,
+}
+
+/// Find installed OVMF firmware, with or without Secure Boot
+///
+/// For Secure Boot, builds with pre-enrolled keys are preferred so Windows
+/// boots with Secure Boot active out of the box.
+pub fn detect_ovmf(secure_boot: bool) -> Option<OvmfFirmware> {
+    let candidates: Vec<OvmfFirmware> = descriptor_firmware("x86_64")
+        .into_iter()
+        .filter(|fw| fw.secure_boot == secure_boot)
+        .filter(|fw| fw.code.exists() && fw.vars_template.exists())
+        .collect();
+
+    let from_descriptors = if secure_boot {
+        candidates
+            .iter()
+            .find(|fw| fw.enrolled_keys)
+            .or(candidates.first())
+            .cloned()
+    } else {
+        candidates.into_iter().next()
+    };
+
+    from_descriptors.or_else(|| known_pair(secure_boot))
+}
+
+/// Firmware architecture for an emulator command ("x86_64", "aarch64", "arm", "riscv64")
+///
+/// 32-bit x86 guests use the x86_64 OVMF builds, like libvirt does.
+pub fn emulator_arch(emulator: &str) -> &str {
+    match emulator.strip_prefix("qemu-system-") {
+        Some(arch @ ("aarch64" | "arm" | "riscv64")) => arch,
+        _ => "x86_64",
+    }
+}
+
+/// Find installed EDK2 firmware for an ARM or RISC-V `virt` board
+pub fn detect_edk2(arch: &str) -> Option<OvmfFirmware> {
+    descriptor_firmware(arch)
+        .into_iter()
+        .filter(|fw| !fw.secure_boot)
+        .find(|fw| fw.code.exists() && fw.vars_template.exists())
+        .or_else(|| {
+            EDK2_PAIRS
+                .iter()
+                .filter(|(a, _, _)| *a == arch)
+                .find(|(_, code, vars)| Path::new(code).exists() && Path::new(vars).exists())
+                .map(|(_, code, vars)| edk2_pair(code, vars))
+        })
+}
+
+/// Debian's EDK2 paths for an architecture, for scripts written before the
+/// firmware is installed
+pub fn default_edk2(arch: &str) -> Option<OvmfFirmware> {
+    EDK2_PAIRS
+        .iter()
+        .find(|(a, _, _)| *a == arch)
+        .map(|(_, code, vars)| edk2_pair(code, vars))
+}
+
+fn edk2_pair(code: &str, vars: &str) -> OvmfFirmware {
+    OvmfFirmware {
+        code: PathBuf::from(code),
+        vars_template: PathBuf::from(vars),
+        secure_boot: false,
+        enrolled_keys: false,
+    }
+}
+
+/// Find an installed U-Boot image for an ARM or RISC-V `virt` board
+pub fn detect_uboot(arch: &str) -> Option<PathBuf> {
+    UBOOT_IMAGES
+        .iter()
+        .filter(|(a, _)| *a == arch)
+        .map(|(_, path)| PathBuf::from(path))
+        .find(|path| path.exists())
+}
+
+/// Debian's U-Boot path for an architecture, used when none is installed
+pub fn default_uboot(arch: &str) -> Option<PathBuf> {
+    UBOOT_IMAGES
+        .iter()
+        .find(|(a, _)| *a == arch)
+        .map(|(_, path)| PathBuf::from(path))
+}
+
+/// Firmware listed in the installed QEMU descriptors, in precedence order
+fn descriptor_firmware(arch: &str) -> Vec<OvmfFirmware> {
+    let mut dirs: Vec<PathBuf> = DESCRIPTOR_DIRS.iter().map(PathBuf::from).collect();
+    if let Some(config) = dirs::config_dir() {
+        dirs.push(config.join("qemu").join("firmware"));
+    }
+
+    // Descriptors are ordered by file name; later directories override earlier ones
+    let mut by_name: BTreeMap<String, PathBuf> = BTreeMap::new();
+    for dir in &dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) == Some("json") {
+                by_name.insert(entry.file_name().to_string_lossy().to_string(), path);
+            }
+        }
+    }
+
+    by_name
+        .values()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .filter_map(|content| parse_descriptor(&content, arch))
+        .collect()
+}
+
+/// Parse a QEMU firmware descriptor, keeping only split-flash UEFI builds
+/// for `arch` in raw format (what the generated launch scripts expect)
+fn parse_descriptor(content: &str, arch: &str) -> Option<OvmfFirmware> {
+    let json: serde_json::Value = serde_json::from_str(content).ok()?;
+
+    let strings = |value: &serde_json::Value| -> Vec<String> {
+        value
+            .as_array()
+            .map(|items| items.iter().filter_map(|i| i.as_str().map(String::from)).collect())
+            .unwrap_or_default()
+    };
+
+    if !strings(&json["interface-types"]).iter().any(|t| t == "uefi") {
+        return None;
+    }
+
+    let targets_arch = json["targets"]
+        .as_array()?
+        .iter()
+        .any(|t| t["architecture"].as_str() == Some(arch));
+    if !targets_arch {
+        return None;
+    }
+
+    let features = strings(&json["features"]);
+    if features.iter().any(|f| UNSUPPORTED_FEATURES.contains(&f.as_str())) {
+        return None;
+    }
+
+    let mapping = &json["mapping"];
+    if mapping["device"].as_str() != Some("flash") {
+        return None;
+    }
+    let is_raw = |file: &serde_json::Value| file["format"].as_str().is_none_or(|f| f == "raw");
+    let executable = &mapping["executable"];
+    let template = &mapping["nvram-template"];
+    if !is_raw(executable) || !is_raw(template) {
+        return None;
+    }
+
+    Some(OvmfFirmware {
+        code: PathBuf::from(executable["filename"].as_str()?),
+        vars_template: PathBuf::from(template["filename"].as_str()?),
+        secure_boot: features.iter().any(|f| f == "secure-boot"),
+        enrolled_keys: features.iter().any(|f| f == "enrolled-keys"),
+    })
+}
+
+/// First well-known CODE/VARS pair present on this system
+fn known_pair(secure_boot: bool) -> Option<OvmfFirmware> {
+    let pairs = if secure_boot { SECURE_BOOT_PAIRS } else { PLAIN_PAIRS };
+    pairs
+        .iter()
+        .find(|(code, vars)| Path::new(code).exists() && Path::new(vars).exists())
+        .map(|(code, vars)| OvmfFirmware {
+            code: PathBuf::from(code),
+            vars_template: PathBuf::from(vars),
+            secure_boot,
+            enrolled_keys: vars.contains(".ms.") || vars.contains("-ms-") || vars.ends_with(".secboot.fd"),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_descriptor() {
+        let secboot = r#"{
+            "interface-types": ["uefi"],
+            "mapping": {
+                "device": "flash",
+                "executable": {"filename": "/usr/share/edk2/ovmf/OVMF_CODE.secboot.fd", "format": "raw"},
+                "nvram-template": {"filename": "/usr/share/edk2/ovmf/OVMF_VARS.secboot.fd", "format": "raw"}
+            },
+            "targets": [{"architecture": "x86_64", "machines": ["pc-q35-*"]}],
+            "features": ["acpi-s3", "requires-smm", "secure-boot", "enrolled-keys"]
+        }"#;
+        let fw = parse_descriptor(secboot, "x86_64").unwrap();
+        assert_eq!(fw.code, PathBuf::from("/usr/share/edk2/ovmf/OVMF_CODE.secboot.fd"));
+        assert_eq!(fw.vars_template, PathBuf::from("/usr/share/edk2/ovmf/OVMF_VARS.secboot.fd"));
+        assert!(fw.secure_boot && fw.enrolled_keys);
+
+        // SEV builds, qcow2 images and other architectures are skipped
+        let sev = secboot.replace("\"acpi-s3\"", "\"amd-sev\"");
+        assert!(parse_descriptor(&sev, "x86_64").is_none());
+        let qcow2 = secboot.replace("\"raw\"", "\"qcow2\"");
+        assert!(parse_descriptor(&qcow2, "x86_64").is_none());
+        let aarch64 = secboot.replace("x86_64", "aarch64");
+        assert!(parse_descriptor(&aarch64, "x86_64").is_none());
+        assert!(parse_descriptor(&aarch64, "aarch64").is_some());
+    }
+
+    #[test]
+    fn test_emulator_arch() {
+        assert_eq!(emulator_arch("qemu-system-x86_64"), "x86_64");
+        assert_eq!(emulator_arch("qemu-system-i386"), "x86_64");
+        assert_eq!(emulator_arch("qemu-system-aarch64"), "aarch64");
+        assert_eq!(emulator_arch("qemu-system-riscv64"), "riscv64");
+        assert_eq!(default_edk2("arm").unwrap().code, PathBuf::from("/usr/share/AAVMF/AAVMF32_CODE.fd"));
+        assert!(default_uboot("riscv64").unwrap().ends_with("uboot.elf"));
+    }
+}
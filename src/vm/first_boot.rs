@@ -0,0 +1,180 @@
+//! First-boot install mode
+//!
+//! A VM created with an install ISO and a blank disk boots the ISO until its
+//! OS is installed. A managed section ahead of the launch script's `case`
+//! turns a plain `./launch.sh` into `./launch.sh --install`; removing the
+//! section switches the script to normal boot. That happens when the user
+//! marks the install complete, or when the VM shuts down with a boot sector
+//! on its disk.
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use super::lifecycle::remove_args_section;
+use super::qemu_config::DiskFormat;
+use super::DiscoveredVm;
+
+const FIRST_BOOT_MARKER_START: &str = "# >>> First boot (managed by vm-curator) >>>";
+const FIRST_BOOT_MARKER_END: &str = "# <<< First boot <<<";
+
+/// Whether the script still boots its install ISO by default
+pub fn is_pending(content: &str) -> bool {
+    content.lines().any(|l| l.trim() == FIRST_BOOT_MARKER_START)
+}
+
+/// Add the first-boot section to a launch script's content, ahead of its
+/// option handling (no-op if present or the script has none)
+pub fn add_first_boot_section(content: &str) -> String {
+    if is_pending(content) {
+        return content.to_string();
+    }
+    let Some(pos) = content.find("\ncase \"$1\" in") else {
+        return content.to_string();
+    };
+    let mut result = content[..=pos].to_string();
+    result.push_str(&generate_first_boot_section());
+    result.push_str(&content[pos + 1..]);
+    result
+}
+
+/// Remove the first-boot section from a launch script's content
+pub fn remove_first_boot_section(content: &str) -> String {
+    remove_args_section(content, FIRST_BOOT_MARKER_START, FIRST_BOOT_MARKER_END, "")
+}
+
+/// Switch a VM's launch script to normal boot
+pub fn mark_installed(vm: &DiscoveredVm) -> Result<()> {
+    let content = std::fs::read_to_string(&vm.launch_script).context("Failed to read launch.sh")?;
+    std::fs::write(&vm.launch_script, remove_first_boot_section(&content)).context("Failed to write launch.sh")
+}
+
+/// Switch to normal boot if the VM's disk now has a boot sector; true if it did
+pub fn complete_if_bootable(vm: &DiscoveredVm) -> Result<bool> {
+    let bootable = vm
+        .config
+        .primary_disk()
+        .and_then(|disk| disk_is_bootable(&disk.path, &disk.format));
+    if !vm.config.install_pending || bootable != Some(true) {
+        return Ok(false);
+    }
+    mark_installed(vm)?;
+    Ok(true)
+}
+
+/// Whether the first sector of a disk image ends in the 0x55AA boot
+/// signature (MBR disks and GPT's protective MBR both have it; a new image
+/// is all zeros). None for formats this can't read.
+pub fn disk_is_bootable(path: &Path, format: &DiskFormat) -> Option<bool> {
+    let mut file = File::open(path).ok()?;
+    let sector_offset = match format {
+        DiskFormat::Raw => 0,
+        DiskFormat::Qcow2 => match qcow2_first_cluster(&mut file)? {
+            Some(offset) => offset,
+            None => return Some(false),
+        },
+        _ => return None,
+    };
+    let mut sector = [0u8; 512];
+    file.seek(SeekFrom::Start(sector_offset)).ok()?;
+    file.read_exact(&mut sector).ok()?;
+    Some(sector[510..] == [0x55, 0xAA])
+}
+
+/// Host offset of a qcow2 image's first guest cluster (Ok(None) while
+/// unallocated or zeroed; None for compressed clusters and backing files)
+fn qcow2_first_cluster(file: &mut File) -> Option<Option<u64>> {
+    const OFFSET_MASK: u64 = 0x00ff_ffff_ffff_fe00;
+    const COMPRESSED: u64 = 1 << 62;
+    const ZERO: u64 = 1;
+
+    let mut header = [0u8; 48];
+    file.read_exact(&mut header).ok()?;
+    if header[..4] != *b"QFI\xfb" {
+        return None;
+    }
+    let backing_file_offset = u64::from_be_bytes(header[8..16].try_into().ok()?);
+    let l1_table_offset = u64::from_be_bytes(header[40..48].try_into().ok()?);
+
+    let l2_table_offset = read_u64(file, l1_table_offset)? & OFFSET_MASK;
+    if l2_table_offset == 0 {
+        // Sectors never written are read from the backing file, if any
+        return if backing_file_offset == 0 { Some(None) } else { None };
+    }
+    let l2_entry = read_u64(file, l2_table_offset)?;
+    if l2_entry & COMPRESSED != 0 {
+        return None;
+    }
+    match l2_entry & OFFSET_MASK {
+        _ if l2_entry & ZERO != 0 => Some(None),
+        0 if backing_file_offset != 0 => None,
+        0 => Some(None),
+        offset => Some(Some(offset)),
+    }
+}
+
+fn read_u64(file: &mut File, offset: u64) -> Option<u64> {
+    let mut bytes = [0u8; 8];
+    file.seek(SeekFrom::Start(offset)).ok()?;
+    file.read_exact(&mut bytes).ok()?;
+    Some(u64::from_be_bytes(bytes))
+}
+
+fn generate_first_boot_section() -> String {
+    let mut section = String::new();
+    section.push_str(FIRST_BOOT_MARKER_START);
+    section.push('\n');
+    section.push_str("# Boot the install ISO until the OS is installed\n");
+    section.push_str("if [[ -z \"$1\" ]] && [[ -f \"$ISO\" ]]; then\n");
+    section.push_str("    set -- --install\n");
+    section.push_str("fi\n");
+    section.push_str(FIRST_BOOT_MARKER_END);
+    section.push('\n');
+    section
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_boot_section() {
+        let script = "ISO=\"/isos/netbsd.iso\"\n\ncase \"$1\" in\n    \"\")\n        qemu-system-x86_64\n        ;;\nesac\n";
+        let pending = add_first_boot_section(script);
+        assert!(is_pending(&pending));
+        assert!(pending.contains("set -- --install\nfi\n# <<< First boot <<<\ncase \"$1\" in"));
+        assert_eq!(add_first_boot_section(&pending), pending);
+        assert_eq!(remove_first_boot_section(&pending), script);
+        assert!(!is_pending(script));
+    }
+
+    #[test]
+    fn test_disk_is_bootable() {
+        let dir = std::env::temp_dir().join(format!("vm-curator-first-boot-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let raw = dir.join("disk.img");
+        let mut sector = vec![0u8; 1024];
+        std::fs::write(&raw, &sector).unwrap();
+        assert_eq!(disk_is_bootable(&raw, &DiskFormat::Raw), Some(false));
+        sector[510..512].copy_from_slice(&[0x55, 0xAA]);
+        std::fs::write(&raw, &sector).unwrap();
+        assert_eq!(disk_is_bootable(&raw, &DiskFormat::Raw), Some(true));
+        assert_eq!(disk_is_bootable(&raw, &DiskFormat::Vdi), None);
+
+        // qcow2: L1 table at 0x200, L2 table at 0x400, first cluster at 0x600
+        let qcow2 = dir.join("disk.qcow2");
+        let mut image = vec![0u8; 0x800];
+        image[..4].copy_from_slice(b"QFI\xfb");
+        image[40..48].copy_from_slice(&0x200u64.to_be_bytes());
+        std::fs::write(&qcow2, &image).unwrap();
+        assert_eq!(disk_is_bootable(&qcow2, &DiskFormat::Qcow2), Some(false));
+        image[0x200..0x208].copy_from_slice(&(0x400u64 | 1 << 63).to_be_bytes());
+        image[0x400..0x408].copy_from_slice(&(0x600u64 | 1 << 63).to_be_bytes());
+        image[0x600 + 510..0x600 + 512].copy_from_slice(&[0x55, 0xAA]);
+        std::fs::write(&qcow2, &image).unwrap();
+        assert_eq!(disk_is_bootable(&qcow2, &DiskFormat::Qcow2), Some(true));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,265 @@
+//! Game controllers
+//!
+//! A host gamepad or joystick reaches a guest one of three ways:
+//!
+//! - QEMU VMs take it as a USB device (`usb-host`, through the USB
+//!   passthrough rules), which Windows 98 and later drive as a HID game
+//!   controller, or as an evdev device through virtio-input (see
+//!   `input_passthrough`).
+//! - 86Box and DOSBox-X VMs emulate a period game port joystick, mapped
+//!   from the host controller. That is what DOS games and Windows 3.x/95
+//!   expect, and QEMU has no game port.
+//!
+//! The QEMU choices live in launch.sh; the game port in the emulator's
+//! configuration file.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use crate::hardware::input::InputDevice;
+use crate::hardware::UsbVersion;
+use crate::vm::backend::{ini_value, set_ini_value, Backend};
+use crate::vm::input_passthrough::{self, InputPassthrough};
+use crate::vm::{load_usb_passthrough, save_usb_passthrough, DiscoveredVm, UsbPassthrough};
+
+/// How a controller reaches a QEMU guest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControllerRoute {
+    Off,
+    /// `usb-host` by vendor:product
+    Usb,
+    /// virtio-input-host from /dev/input
+    Evdev,
+}
+
+impl ControllerRoute {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Off => "Not passed",
+            Self::Usb => "USB device",
+            Self::Evdev => "evdev (virtio-input)",
+        }
+    }
+
+    /// The next route, skipping USB for controllers that aren't on USB
+    pub fn next(&self, usb: bool) -> Self {
+        match self {
+            Self::Off if usb => Self::Usb,
+            Self::Off | Self::Usb => Self::Evdev,
+            Self::Evdev => Self::Off,
+        }
+    }
+
+    /// The previous route, the other way round
+    pub fn prev(&self, usb: bool) -> Self {
+        match self {
+            Self::Off => Self::Evdev,
+            Self::Evdev if usb => Self::Usb,
+            Self::Evdev | Self::Usb => Self::Off,
+        }
+    }
+}
+
+/// An emulated game port joystick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameportJoystick {
+    /// Value written to the emulator's configuration
+    pub id: &'static str,
+    pub name: &'static str,
+    pub axes: u8,
+    pub buttons: u8,
+    /// Hat switches
+    pub povs: u8,
+}
+
+/// 86Box `joystick_type` values
+pub const JOYSTICKS_86BOX: &[GameportJoystick] = &[
+    GameportJoystick { id: "2axis_2button", name: "Standard 2-button joystick", axes: 2, buttons: 2, povs: 0 },
+    GameportJoystick { id: "2axis_4button", name: "Standard 4-button joystick", axes: 2, buttons: 4, povs: 0 },
+    GameportJoystick { id: "3axis_4button", name: "4-button joystick with throttle", axes: 3, buttons: 4, povs: 0 },
+    GameportJoystick { id: "4axis_4button", name: "4-axis, 4-button joystick", axes: 4, buttons: 4, povs: 0 },
+    GameportJoystick { id: "ch_flightstick_pro", name: "CH Flightstick Pro", axes: 3, buttons: 4, povs: 1 },
+    GameportJoystick { id: "thrustmaster_fcs", name: "Thrustmaster FCS", axes: 2, buttons: 4, povs: 1 },
+    GameportJoystick { id: "sidewinder_pad", name: "Microsoft SideWinder Game Pad", axes: 2, buttons: 10, povs: 0 },
+];
+
+/// DOSBox-X `joysticktype` values
+pub const JOYSTICKS_DOSBOX_X: &[GameportJoystick] = &[
+    GameportJoystick { id: "2axis", name: "Two 2-axis, 2-button joysticks", axes: 2, buttons: 2, povs: 0 },
+    GameportJoystick { id: "4axis", name: "4-axis, 4-button joystick", axes: 4, buttons: 4, povs: 0 },
+    GameportJoystick { id: "fcs", name: "Thrustmaster FCS", axes: 3, buttons: 4, povs: 1 },
+    GameportJoystick { id: "ch", name: "CH Flightstick Pro", axes: 4, buttons: 6, povs: 1 },
+];
+
+/// The game port joysticks a backend emulates (none for QEMU)
+pub fn gameport_joysticks(backend: Backend) -> &'static [GameportJoystick] {
+    match backend {
+        Backend::Qemu => &[],
+        Backend::EightySixBox => JOYSTICKS_86BOX,
+        Backend::DosboxX => JOYSTICKS_DOSBOX_X,
+    }
+}
+
+/// Game port settings in a backend's configuration file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Gameport {
+    pub joystick: Option<&'static GameportJoystick>,
+    /// Host controller (in kernel order) driving it; DOSBox-X always takes
+    /// the first
+    pub host_index: usize,
+}
+
+fn config_path(backend: Backend, vm_dir: &Path) -> Option<PathBuf> {
+    backend.config_file().map(|file| vm_dir.join(file))
+}
+
+/// Read the game port joystick from a backend VM's configuration
+pub fn read_gameport(backend: Backend, vm_dir: &Path) -> Gameport {
+    let content = config_path(backend, vm_dir)
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .unwrap_or_default();
+    parse_gameport(backend, &content)
+}
+
+fn parse_gameport(backend: Backend, content: &str) -> Gameport {
+    let key = match backend {
+        Backend::Qemu => return Gameport::default(),
+        Backend::EightySixBox => "joystick_type",
+        Backend::DosboxX => "joysticktype",
+    };
+    let joystick = ini_value(content, key)
+        .and_then(|id| gameport_joysticks(backend).iter().find(|j| j.id == id));
+    // 86Box counts host joysticks from 1, with 0 for none
+    let host_index = ini_value(content, "joystick_0_nr")
+        .and_then(|nr| nr.parse::<usize>().ok())
+        .map_or(0, |nr| nr.saturating_sub(1));
+    Gameport { joystick, host_index }
+}
+
+/// Write the game port joystick (None for none) to a backend VM's configuration
+pub fn set_gameport(backend: Backend, vm_dir: &Path, gameport: &Gameport) -> Result<()> {
+    let Some(path) = config_path(backend, vm_dir) else {
+        return Ok(());
+    };
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    std::fs::write(&path, apply_gameport(backend, &content, gameport))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn apply_gameport(backend: Backend, content: &str, gameport: &Gameport) -> String {
+    let id = gameport.joystick.map_or("none", |j| j.id);
+    match backend {
+        Backend::Qemu => content.to_string(),
+        Backend::EightySixBox => {
+            const SECTION: &str = "Input devices";
+            let mut content = set_ini_value(content, SECTION, "joystick_type", id);
+            let nr = gameport.joystick.map_or(0, |_| gameport.host_index + 1);
+            content = set_ini_value(&content, SECTION, "joystick_0_nr", &nr.to_string());
+            // Host axes, buttons and hats in order onto the emulated ones
+            if let Some(joystick) = gameport.joystick {
+                for i in 0..joystick.axes {
+                    content = set_ini_value(&content, SECTION, &format!("joystick_0_axis_{}", i), &i.to_string());
+                }
+                for i in 0..joystick.buttons {
+                    content = set_ini_value(&content, SECTION, &format!("joystick_0_button_{}", i), &i.to_string());
+                }
+                for i in 0..joystick.povs {
+                    content = set_ini_value(&content, SECTION, &format!("joystick_0_pov_{}", i), &i.to_string());
+                }
+            }
+            content
+        }
+        Backend::DosboxX => {
+            let content = set_ini_value(content, "joystick", "joysticktype", id);
+            // Timed intervals behave like a real game port for old calibration code
+            set_ini_value(&content, "joystick", "timed", "true")
+        }
+    }
+}
+
+/// How a controller currently reaches a QEMU VM
+pub fn current_route(device: &InputDevice, usb_rules: &[UsbPassthrough], input: Option<&InputPassthrough>) -> ControllerRoute {
+    let usb = device
+        .usb_id
+        .is_some_and(|(vendor, product)| usb_rules.iter().any(|r| r.vendor_id == vendor && r.product_id == product));
+    if usb {
+        ControllerRoute::Usb
+    } else if input.is_some_and(|input| input.devices.contains(&device.path)) {
+        ControllerRoute::Evdev
+    } else {
+        ControllerRoute::Off
+    }
+}
+
+/// Save the routes of a QEMU VM's controllers to its launch script
+///
+/// USB rules and input passthrough devices for other hardware are kept.
+pub fn set_routes(vm: &DiscoveredVm, routes: &[(InputDevice, ControllerRoute)]) -> Result<()> {
+    let mut usb_rules = load_usb_passthrough(vm);
+    let usb_before = usb_rules.clone();
+    for (device, route) in routes {
+        let Some((vendor, product)) = device.usb_id else {
+            continue;
+        };
+        let position = usb_rules.iter().position(|r| r.vendor_id == vendor && r.product_id == product);
+        match (route, position) {
+            (ControllerRoute::Usb, None) => usb_rules.push(UsbPassthrough {
+                vendor_id: vendor,
+                product_id: product,
+                usb_version: UsbVersion::default(),
+                host_bus: None,
+                host_port: None,
+                auto_attach: true,
+            }),
+            (ControllerRoute::Off | ControllerRoute::Evdev, Some(i)) => {
+                usb_rules.remove(i);
+            }
+            _ => {}
+        }
+    }
+    if usb_rules != usb_before {
+        save_usb_passthrough(vm, &usb_rules)?;
+    }
+
+    let script = std::fs::read_to_string(&vm.launch_script).context("Failed to read launch.sh")?;
+    let mut input = input_passthrough::parse_input_passthrough(&script).unwrap_or_default();
+    let input_before = input.clone();
+    for (device, route) in routes {
+        let listed = input.devices.contains(&device.path);
+        if *route == ControllerRoute::Evdev && !listed {
+            input.devices.push(device.path.clone());
+        } else if *route != ControllerRoute::Evdev && listed {
+            input.devices.retain(|path| *path != device.path);
+        }
+    }
+    if input != input_before {
+        input_passthrough::set_input_passthrough(&vm.launch_script, &input)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gameport_round_trip() {
+        let cfg = "[Machine]\nmachine = ami486\n\n[Input devices]\nmouse_type = ps2\n\n[Hard disks]\nhdd_01_fn = win31.img\n";
+        let gameport = Gameport { joystick: Some(&JOYSTICKS_86BOX[4]), host_index: 1 };
+        let cfg = apply_gameport(Backend::EightySixBox, cfg, &gameport);
+        assert!(cfg.contains("[Input devices]\nmouse_type = ps2\njoystick_type = ch_flightstick_pro\njoystick_0_nr = 2\n"));
+        assert!(cfg.contains("joystick_0_button_3 = 3\njoystick_0_pov_0 = 0\n\n[Hard disks]"));
+        assert_eq!(parse_gameport(Backend::EightySixBox, &cfg), gameport);
+
+        let off = apply_gameport(Backend::EightySixBox, &cfg, &Gameport::default());
+        assert!(off.contains("joystick_type = none\njoystick_0_nr = 0\n"));
+        assert_eq!(parse_gameport(Backend::EightySixBox, &off), Gameport::default());
+
+        let conf = "[dosbox]\nmemsize = 16\n\n[autoexec]\nC:\n";
+        let gameport = Gameport { joystick: Some(&JOYSTICKS_DOSBOX_X[2]), host_index: 0 };
+        let conf = apply_gameport(Backend::DosboxX, conf, &gameport);
+        assert!(conf.contains("[joystick]\njoysticktype = fcs\ntimed = true\n\n[autoexec]"));
+        assert_eq!(parse_gameport(Backend::DosboxX, &conf), gameport);
+    }
+}
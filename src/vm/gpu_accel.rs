@@ -0,0 +1,233 @@
+//! virtio-gpu 3D acceleration
+//!
+//! Guest OpenGL reaches the host GPU through `virtio-vga-gl` and virgl. The
+//! rendered frames then need a display that can take GL output: GTK, SDL and
+//! spice-app can show them locally, while VNC and SPICE-server displays need
+//! QEMU's `egl-headless` display to render on the GPU and hand them 2D
+//! copies. This module switches a launch script between those setups and
+//! explains what won't work with the VM's current display.
+
+use anyhow::{bail, Context, Result};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+
+use crate::hardware::gl::HostGl;
+use crate::vm::qemu_config::QemuConfig;
+use crate::vm::spice::SpiceMode;
+use crate::vm::DiscoveredVm;
+
+/// A way of giving the guest 3D acceleration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GpuAccelMode {
+    /// Identifier (as reported by `parse_gpu_accel`)
+    pub id: &'static str,
+    /// Display name
+    pub name: &'static str,
+    /// Short description for the picker
+    pub description: &'static str,
+}
+
+/// Available modes, "off" first
+pub const GPU_ACCEL_MODES: &[GpuAccelMode] = &[
+    GpuAccelMode {
+        id: "off",
+        name: "Off",
+        description: "2D virtio-vga; no guest OpenGL",
+    },
+    GpuAccelMode {
+        id: "virgl",
+        name: "VirGL (local window)",
+        description: "virtio-vga-gl with gl=on on the GTK, SDL or spice-app display",
+    },
+    GpuAccelMode {
+        id: "egl-headless",
+        name: "EGL headless (remote)",
+        description: "virtio-vga-gl rendered on the host GPU, viewed over VNC or SPICE",
+    },
+];
+
+/// Displays that can show GL output in a local window
+const GL_DISPLAYS: &[&str] = &["gtk", "sdl", "spice-app"];
+
+static RE_DISPLAY: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"-display\s+([\w-]+)((?:,[\w.-]+=[\w./-]+)*)").expect("Invalid regex: RE_DISPLAY")
+});
+
+static RE_VGA_ARG: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"-vga\s+[\w-]+").expect("Invalid regex: RE_VGA_ARG")
+});
+
+static RE_VGA_DEVICE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"-device\s+(?:virtio-vga-gl|virtio-vga|qxl-vga|VGA)\b[^\s\\]*")
+        .expect("Invalid regex: RE_VGA_DEVICE")
+});
+
+static RE_VIRGL_DEVICE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"-device\s+virtio-vga-gl[^\s\\]*").expect("Invalid regex: RE_VIRGL_DEVICE")
+});
+
+/// Look up a mode by id
+pub fn find_mode(id: &str) -> Option<&'static GpuAccelMode> {
+    GPU_ACCEL_MODES.iter().find(|m| m.id == id)
+}
+
+/// Read the acceleration mode from a launch script (None when 3D is off)
+pub fn parse_gpu_accel(content: &str) -> Option<String> {
+    let display = RE_DISPLAY.captures(content);
+    if display.as_ref().is_some_and(|caps| &caps[1] == "egl-headless") {
+        return Some("egl-headless".to_string());
+    }
+    let gl_display = display.is_some_and(|caps| caps[2].split(',').any(|o| o == "gl=on"));
+    (gl_display || RE_VIRGL_DEVICE.is_match(content)).then(|| "virgl".to_string())
+}
+
+/// The display a VM is viewed through: a `-display` backend, "vnc" or "spice"
+pub fn effective_display(config: &QemuConfig) -> String {
+    if config.vnc_display.is_some() {
+        return "vnc".to_string();
+    }
+    if config.spice.is_some_and(|s| s.mode == SpiceMode::Server) {
+        return "spice".to_string();
+    }
+    RE_DISPLAY
+        .captures(&config.raw_script)
+        .map(|caps| caps[1].to_string())
+        .unwrap_or_else(|| "gtk".to_string())
+}
+
+/// Problems a mode would run into on this host with this display
+///
+/// `qemu_has_virgl` is whether the emulator lists `virtio-vga-gl` (None if unknown).
+pub fn accel_warnings(mode: &str, display: &str, host: &HostGl, qemu_has_virgl: Option<bool>) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if mode == "off" {
+        return warnings;
+    }
+
+    if host.render_nodes.is_empty() {
+        warnings.push("No /dev/dri render node: the host GPU driver can't serve 3D".to_string());
+    } else if host.usable_nodes().next().is_none() {
+        warnings.push("Render nodes aren't accessible: add your user to the 'render' group".to_string());
+    }
+    if !host.virglrenderer {
+        warnings.push("libvirglrenderer not found; QEMU needs it for virgl".to_string());
+    }
+    if qemu_has_virgl == Some(false) {
+        warnings.push("This QEMU build has no virtio-vga-gl device".to_string());
+    }
+
+    match (mode, display) {
+        ("virgl", d) if GL_DISPLAYS.contains(&d) => {}
+        ("virgl", "vnc") => warnings.push("VNC can't carry GL output; use EGL headless instead".to_string()),
+        ("virgl", "spice") => {
+            warnings.push("The SPICE server display can't show GL; use EGL headless or spice-app".to_string())
+        }
+        ("virgl", "none") => warnings.push("No display is configured to show the 3D output".to_string()),
+        ("virgl", d) => warnings.push(format!("The {} display can't do 3D", d)),
+        ("egl-headless", "vnc" | "spice") => {}
+        ("egl-headless", _) => {
+            warnings.push("EGL headless needs a VNC or SPICE display; change the display first".to_string())
+        }
+        _ => {}
+    }
+
+    warnings
+}
+
+/// Switch a VM's launch script to an acceleration mode
+pub fn set_gpu_accel(vm: &DiscoveredVm, mode: &str) -> Result<()> {
+    let remote = matches!(effective_display(&vm.config).as_str(), "vnc" | "spice");
+    if mode == "egl-headless" && !remote {
+        bail!("EGL headless needs a VNC or SPICE display; change the display first");
+    }
+
+    let content = std::fs::read_to_string(&vm.launch_script).context("Failed to read launch.sh")?;
+    let new_content = rewrite_gpu_accel(&content, mode)?;
+    std::fs::write(&vm.launch_script, new_content).context("Failed to write launch.sh")?;
+    Ok(())
+}
+
+fn rewrite_gpu_accel(content: &str, mode: &str) -> Result<String> {
+    let enable = mode != "off";
+
+    // Graphics adapter
+    let content = if enable {
+        if !RE_VGA_ARG.is_match(content) && !RE_VGA_DEVICE.is_match(content) {
+            bail!("No graphics adapter found in launch.sh");
+        }
+        let content = RE_VGA_ARG.replace_all(content, "-device virtio-vga-gl");
+        RE_VGA_DEVICE.replace_all(&content, "-device virtio-vga-gl").into_owned()
+    } else {
+        RE_VIRGL_DEVICE.replace_all(content, "-vga virtio").into_owned()
+    };
+
+    // Display: gl=on locally, egl-headless for remote viewers
+    let content = RE_DISPLAY.replace_all(&content, |caps: &Captures| {
+        let current = &caps[1];
+        let name = match mode {
+            "egl-headless" => "egl-headless",
+            _ if current == "egl-headless" => "none",
+            _ => current,
+        };
+        let mut spec = name.to_string();
+        if mode == "virgl" && GL_DISPLAYS.contains(&name) {
+            spec.push_str(",gl=on");
+        }
+        if name == current {
+            for opt in caps[2].split(',').filter(|o| !o.is_empty() && *o != "gl=on") {
+                spec.push(',');
+                spec.push_str(opt);
+            }
+        }
+        format!("-display {}", spec)
+    });
+
+    Ok(content.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::gl::RenderNode;
+
+    const SCRIPT: &str = "qemu-system-x86_64 \\\n    -vga std \\\n    -display gtk,zoom-to-fit=on \\\n    -m 2G\n";
+
+    #[test]
+    fn test_rewrite_gpu_accel_modes() {
+        let virgl = rewrite_gpu_accel(SCRIPT, "virgl").unwrap();
+        assert_eq!(
+            virgl,
+            "qemu-system-x86_64 \\\n    -device virtio-vga-gl \\\n    -display gtk,gl=on,zoom-to-fit=on \\\n    -m 2G\n"
+        );
+        assert_eq!(parse_gpu_accel(&virgl).as_deref(), Some("virgl"));
+
+        let headless = rewrite_gpu_accel(&virgl.replace("-display gtk", "-display none"), "egl-headless").unwrap();
+        assert!(headless.contains("-display egl-headless \\"));
+        assert_eq!(parse_gpu_accel(&headless).as_deref(), Some("egl-headless"));
+
+        let off = rewrite_gpu_accel(&headless, "off").unwrap();
+        assert!(off.contains("-vga virtio \\\n    -display none \\"));
+        assert_eq!(parse_gpu_accel(&off), None);
+        assert_eq!(parse_gpu_accel(SCRIPT), None);
+    }
+
+    #[test]
+    fn test_accel_warnings() {
+        let host = HostGl {
+            render_nodes: vec![RenderNode {
+                path: "/dev/dri/renderD128".into(),
+                driver: Some("amdgpu".to_string()),
+                accessible: true,
+            }],
+            virglrenderer: true,
+        };
+        assert!(accel_warnings("virgl", "sdl", &host, Some(true)).is_empty());
+        assert!(accel_warnings("egl-headless", "vnc", &host, None).is_empty());
+        assert_eq!(accel_warnings("virgl", "vnc", &host, None).len(), 1);
+        assert_eq!(accel_warnings("egl-headless", "gtk", &host, None).len(), 1);
+
+        let bare = HostGl::default();
+        assert_eq!(accel_warnings("virgl", "gtk", &bare, Some(false)).len(), 3);
+        assert!(accel_warnings("off", "vnc", &bare, None).is_empty());
+    }
+}
@@ -0,0 +1,200 @@
+//! QEMU guest agent client
+//!
+//! VMs created with the guest agent enabled expose a virtio-serial channel
+//! (`org.qemu.guest_agent.0`) backed by a UNIX socket in the VM directory.
+//! When `qemu-guest-agent` is installed in the guest, this module uses that
+//! socket to read the guest's IP addresses and OS name, and to request a
+//! clean shutdown.
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::local_socket::LocalStream;
+
+/// Name of the virtio-serial port the guest agent listens on
+pub const AGENT_CHANNEL: &str = "org.qemu.guest_agent.0";
+
+/// File name of the agent socket inside the VM directory
+const SOCKET_NAME: &str = "qga.sock";
+
+/// How long to wait for the guest to answer before giving up
+const AGENT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Information reported by the guest agent
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GuestInfo {
+    /// Guest OS name (e.g., "Debian GNU/Linux 12 (bookworm)")
+    pub os_name: Option<String>,
+    /// Non-loopback addresses as "interface: address"
+    pub ip_addresses: Vec<String>,
+}
+
+/// QEMU arguments that add the guest agent channel (for generated scripts)
+pub fn qemu_args() -> Vec<String> {
+    vec![
+        format!(
+            "-chardev socket,path=\"$VM_DIR/{}\",server=on,wait=off,id=qga0",
+            SOCKET_NAME
+        ),
+        "-device virtio-serial".to_string(),
+        format!("-device virtserialport,chardev=qga0,name={}", AGENT_CHANNEL),
+    ]
+}
+
+/// Whether a launch script wires up the guest agent channel
+pub fn is_configured(content: &str) -> bool {
+    content.contains(AGENT_CHANNEL)
+}
+
+/// Path of the agent socket for a VM
+pub fn socket_path(vm_dir: &Path) -> PathBuf {
+    vm_dir.join(SOCKET_NAME)
+}
+
+/// A connection to a running guest agent
+struct AgentConnection {
+    writer: LocalStream,
+    reader: BufReader<LocalStream>,
+}
+
+impl AgentConnection {
+    /// Connect and synchronize, so stale replies from earlier clients are skipped
+    fn open(vm_dir: &Path) -> Result<Self> {
+        let path = socket_path(vm_dir);
+        let stream = LocalStream::connect(&path)
+            .with_context(|| format!("Guest agent socket not available: {}", path.display()))?;
+        stream.set_read_timeout(Some(AGENT_TIMEOUT))?;
+        stream.set_write_timeout(Some(AGENT_TIMEOUT))?;
+
+        let reader = BufReader::new(stream.try_clone()?);
+        let mut conn = Self { writer: stream, reader };
+
+        let sync_id = std::process::id() as u64 * 1000 + chrono::Utc::now().timestamp_subsec_millis() as u64;
+        conn.send(&json!({"execute": "guest-sync", "arguments": {"id": sync_id}}))?;
+        loop {
+            let reply = conn.read_reply().context("Guest agent is not responding")?;
+            if reply.get("return").and_then(Value::as_u64) == Some(sync_id) {
+                break;
+            }
+        }
+
+        Ok(conn)
+    }
+
+    fn send(&mut self, command: &Value) -> Result<()> {
+        let mut line = command.to_string();
+        line.push('\n');
+        self.writer.write_all(line.as_bytes())?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn read_reply(&mut self) -> Result<Value> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            bail!("Guest agent closed the connection");
+        }
+        serde_json::from_str(line.trim()).context("Invalid reply from guest agent")
+    }
+
+    /// Run a command and return its `return` value
+    fn execute(&mut self, command: &str) -> Result<Value> {
+        self.send(&json!({"execute": command}))?;
+        let reply = self.read_reply()?;
+        if let Some(error) = reply.get("error") {
+            let desc = error.get("desc").and_then(Value::as_str).unwrap_or("unknown error");
+            bail!("{} failed: {}", command, desc);
+        }
+        Ok(reply.get("return").cloned().unwrap_or(Value::Null))
+    }
+}
+
+/// Query the guest agent for OS name and IP addresses
+pub fn query_guest_info(vm_dir: &Path) -> Result<GuestInfo> {
+    let mut conn = AgentConnection::open(vm_dir)?;
+
+    // Older agents (and Windows builds without WMI access) may lack osinfo
+    let os_name = conn
+        .execute("guest-get-osinfo")
+        .ok()
+        .and_then(|v| parse_os_name(&v));
+    let ip_addresses = conn
+        .execute("guest-network-get-interfaces")
+        .map(|v| parse_ip_addresses(&v))
+        .unwrap_or_default();
+
+    Ok(GuestInfo { os_name, ip_addresses })
+}
+
+/// Ask the guest OS to power off cleanly
+pub fn shutdown_guest(vm_dir: &Path) -> Result<()> {
+    let mut conn = AgentConnection::open(vm_dir)?;
+    // guest-shutdown does not reply on success; the guest just goes away
+    conn.send(&json!({"execute": "guest-shutdown", "arguments": {"mode": "powerdown"}}))
+}
+
+fn parse_os_name(value: &Value) -> Option<String> {
+    if let Some(pretty) = value.get("pretty-name").and_then(Value::as_str) {
+        return Some(pretty.to_string());
+    }
+    let name = value.get("name").and_then(Value::as_str)?;
+    match value.get("version").and_then(Value::as_str) {
+        Some(version) => Some(format!("{} {}", name, version)),
+        None => Some(name.to_string()),
+    }
+}
+
+fn parse_ip_addresses(value: &Value) -> Vec<String> {
+    let mut addresses = Vec::new();
+    for iface in value.as_array().into_iter().flatten() {
+        let name = iface.get("name").and_then(Value::as_str).unwrap_or("?");
+        for addr in iface
+            .get("ip-addresses")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let Some(ip) = addr.get("ip-address").and_then(Value::as_str) else {
+                continue;
+            };
+            // Loopback and link-local addresses aren't reachable from the host
+            if ip.starts_with("127.") || ip == "::1" || ip.starts_with("fe80:") || ip.starts_with("169.254.") {
+                continue;
+            }
+            addresses.push(format!("{}: {}", name, ip));
+        }
+    }
+    addresses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ip_addresses_skips_loopback_and_link_local() {
+        let reply = json!([
+            {"name": "lo", "ip-addresses": [
+                {"ip-address-type": "ipv4", "ip-address": "127.0.0.1", "prefix": 8},
+                {"ip-address-type": "ipv6", "ip-address": "::1", "prefix": 128}
+            ]},
+            {"name": "enp0s2", "ip-addresses": [
+                {"ip-address-type": "ipv4", "ip-address": "10.0.2.15", "prefix": 24},
+                {"ip-address-type": "ipv6", "ip-address": "fe80::5054:ff:fe12:3456", "prefix": 64}
+            ]}
+        ]);
+        assert_eq!(parse_ip_addresses(&reply), vec!["enp0s2: 10.0.2.15".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_os_name() {
+        let linux = json!({"id": "debian", "name": "Debian GNU/Linux", "pretty-name": "Debian GNU/Linux 12 (bookworm)"});
+        assert_eq!(parse_os_name(&linux), Some("Debian GNU/Linux 12 (bookworm)".to_string()));
+
+        let windows = json!({"name": "Microsoft Windows", "version": "10"});
+        assert_eq!(parse_os_name(&windows), Some("Microsoft Windows 10".to_string()));
+    }
+}
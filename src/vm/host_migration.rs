@@ -0,0 +1,278 @@
+//! Moving VMs between library hosts
+//!
+//! A stopped VM's folder is copied to another host's library with rsync,
+//! which resumes an interrupted transfer (partial files are kept and a
+//! marker file tells an unfinished copy from a different VM), and is then
+//! removed from the source unless kept. A running VM is live-migrated:
+//! QEMU is started from the destination's copy of the folder waiting for
+//! incoming state, and the source streams the VM through SSH tunnels, so
+//! the two hosts never need to reach each other. Live migration needs the
+//! VM's folder in both libraries with its disk images on storage both
+//! hosts share.
+
+use anyhow::{bail, Context, Result};
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use super::discovery::DiscoveredVm;
+use super::lifecycle::insert_args_section;
+use super::{qmp, remote_host};
+use crate::commands::ssh;
+use crate::config::RemoteHost;
+use crate::vm::create::shell_escape;
+
+// Incoming migration section markers
+const INCOMING_MARKER_START: &str = "# >>> INCOMING MIGRATION (managed by vm-curator) >>>";
+const INCOMING_MARKER_END: &str = "# <<< INCOMING MIGRATION <<<";
+
+/// Present in a destination folder until its transfer has finished
+const TRANSFER_MARKER: &str = ".vm-curator-transfer";
+
+/// Name of a host for messages (None is this machine)
+pub fn host_name(host: Option<&RemoteHost>) -> &str {
+    host.map_or("this computer", |h| h.name.as_str())
+}
+
+/// Run a bash script on a host, or on this machine for None
+fn run_on(host: Option<&RemoteHost>, script: &str) -> Result<String> {
+    if let Some(host) = host {
+        return ssh::run_script(host, script);
+    }
+    let output = Command::new("bash")
+        .args(["-c", script])
+        .stdin(Stdio::null())
+        .output()
+        .context("Failed to run bash")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("{}", stderr.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("command failed").trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Where the VM's folder goes in the destination library
+fn destination_dir(vm: &DiscoveredVm, dest: Option<&RemoteHost>, local_library: &Path) -> Result<String> {
+    let source = vm.remote.as_ref();
+    if source.map(|h| &h.name) == dest.map(|h| &h.name) {
+        bail!("{} is already on {}", vm.display_name(), host_name(dest));
+    }
+    let name = vm.path.file_name().and_then(|n| n.to_str()).context("Invalid VM folder name")?;
+    let library = match dest {
+        Some(host) => remote_host::library_dir(host)?,
+        None => local_library.to_string_lossy().to_string(),
+    };
+    Ok(format!("{}/{}", library.trim_end_matches('/'), name))
+}
+
+/// rsync options shared by every transfer
+fn rsync_args() -> Vec<&'static str> {
+    // -s passes paths to the remote side unparsed; sockets are left behind
+    vec!["-a", "-s", "--partial", "--sparse", "--no-D", "--exclude", TRANSFER_MARKER]
+}
+
+/// Copy a stopped VM's folder to another host's library
+///
+/// Reports progress (0.0 - 1.0) when one side is this machine; a transfer
+/// between two remote hosts runs rsync on the source, which must be able
+/// to log in to the destination itself.
+pub fn transfer<F>(vm: &DiscoveredVm, dest: Option<&RemoteHost>, local_library: &Path, keep_source: bool, on_progress: F) -> Result<()>
+where
+    F: FnMut(f32),
+{
+    let source = vm.remote.as_ref();
+    let dest_dir = destination_dir(vm, dest, local_library)?;
+    let marker = format!("{}/{}", dest_dir, TRANSFER_MARKER);
+    let source_dir = vm.path.to_string_lossy().to_string();
+
+    // A folder without the marker is another VM, not an unfinished copy
+    let prepare = format!(
+        "if [ -e {dir} ] && [ ! -e {marker} ]; then echo 'A VM folder with this name already exists' >&2; exit 1; fi\n\
+         mkdir -p {dir} && touch {marker}\n",
+        dir = shell_escape(&dest_dir),
+        marker = shell_escape(&marker),
+    );
+    run_on(dest, &prepare).with_context(|| format!("Cannot copy to {}", host_name(dest)))?;
+
+    match (source, dest) {
+        (Some(src), Some(dst)) => {
+            let args: Vec<String> = rsync_args().into_iter().map(shell_escape).collect();
+            let script = format!(
+                "rsync {} -e 'ssh -o BatchMode=yes' {} {}\n",
+                args.join(" "),
+                shell_escape(&format!("{}/", source_dir)),
+                shell_escape(&format!("{}:{}/", dst.ssh, dest_dir)),
+            );
+            ssh::run_script(src, &script)?;
+        }
+        (Some(src), None) => rsync(&format!("{}:{}/", src.ssh, source_dir), &format!("{}/", dest_dir), on_progress)?,
+        (None, Some(dst)) => rsync(&format!("{}/", source_dir), &format!("{}:{}/", dst.ssh, dest_dir), on_progress)?,
+        (None, None) => unreachable!("destination_dir refuses a copy to the same host"),
+    }
+
+    run_on(dest, &format!("rm -f {}\n", shell_escape(&marker)))?;
+    if !keep_source {
+        run_on(source, &format!("rm -rf -- {}\n", shell_escape(&source_dir)))
+            .context("Copied, but the source folder could not be removed")?;
+    }
+    Ok(())
+}
+
+/// Run rsync on this machine, reporting its overall progress
+fn rsync<F>(source: &str, dest: &str, mut on_progress: F) -> Result<()>
+where
+    F: FnMut(f32),
+{
+    let mut child = Command::new("rsync")
+        .args(rsync_args())
+        .args(["--info=progress2", "-e", &ssh::rsync_shell(), source, dest])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run rsync (is it installed?)")?;
+
+    // --info=progress2 redraws "  1,234,567  42%  10.00MB/s  0:00:12" with carriage returns
+    if let Some(mut stdout) = child.stdout.take() {
+        let mut line = Vec::new();
+        let mut byte = [0u8; 1];
+        while stdout.read(&mut byte).unwrap_or(0) == 1 {
+            if byte[0] == b'\r' || byte[0] == b'\n' {
+                if let Some(progress) = parse_rsync_progress(&String::from_utf8_lossy(&line)) {
+                    on_progress(progress);
+                }
+                line.clear();
+            } else {
+                line.push(byte[0]);
+            }
+        }
+    }
+
+    let output = child.wait_with_output().context("Failed to run rsync")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("rsync failed: {}", stderr.lines().find(|l| !l.trim().is_empty()).unwrap_or("unknown error"));
+    }
+    Ok(())
+}
+
+fn parse_rsync_progress(line: &str) -> Option<f32> {
+    line.split_whitespace()
+        .find_map(|word| word.strip_suffix('%')?.parse::<f32>().ok())
+        .map(|percent| percent / 100.0)
+}
+
+/// Move a running VM to another host without stopping it
+///
+/// Reports the share of guest RAM sent (0.0 - 1.0).
+pub fn live_migrate<F>(vm: &DiscoveredVm, dest: Option<&RemoteHost>, local_library: &Path, on_progress: F) -> Result<()>
+where
+    F: FnMut(f32),
+{
+    if !qmp::is_configured(&vm.config.raw_script) {
+        bail!("The VM has no QMP socket to control the migration");
+    }
+    let source = vm.remote.as_ref();
+    let dest_dir = destination_dir(vm, dest, local_library)?;
+
+    // The destination starts from its own copy of launch.sh
+    let script_path = format!("{}/launch.sh", dest_dir);
+    let content = run_on(dest, &format!("cat {}\n", shell_escape(&script_path))).with_context(|| {
+        format!("{} has no copy of this VM: live migration needs its folder on storage both hosts share", host_name(dest))
+    })?;
+    if !content.contains(INCOMING_MARKER_START) {
+        let content = add_incoming_section(&content);
+        match dest {
+            Some(host) => ssh::write_file(host, &script_path, &content)?,
+            None => std::fs::write(&script_path, content).context("Failed to write launch.sh")?,
+        }
+    }
+
+    let qmp_dir = match source {
+        Some(host) => remote_host::forward_qmp(host, vm)?,
+        None => vm.path.clone(),
+    };
+    let tunnel_dir = remote_host::tunnel_dir(vm)?;
+
+    let start_destination = || -> Result<String> {
+        let port = pick_port();
+        let env = format!("VM_CURATOR_INCOMING=tcp:127.0.0.1:{}", port);
+        run_on(dest, &remote_host::start_script(Path::new(&dest_dir), &env, &[]))
+            .with_context(|| format!("QEMU did not start on {}", host_name(dest)))?;
+
+        // Reach the waiting QEMU from this machine...
+        let local_target = match dest {
+            Some(host) => {
+                let socket = tunnel_dir.join("migrate.sock");
+                let _ = std::fs::remove_file(&socket);
+                ssh::forward(host, &format!("{}:127.0.0.1:{}", socket.display(), port), || socket.exists())?;
+                socket.to_string_lossy().to_string()
+            }
+            None => format!("127.0.0.1:{}", port),
+        };
+        let local_uri = if dest.is_some() {
+            format!("unix:{}", local_target)
+        } else {
+            format!("tcp:{}", local_target)
+        };
+
+        // ...and from the source host through this machine
+        match source {
+            Some(host) => {
+                let remote_port = pick_port();
+                ssh::reverse_forward(host, &format!("127.0.0.1:{}:{}", remote_port, local_target))?;
+                Ok(format!("tcp:127.0.0.1:{}", remote_port))
+            }
+            None => Ok(local_uri),
+        }
+    };
+
+    qmp::migrate(&qmp_dir, start_destination, on_progress)
+}
+
+/// A port in the dynamic range for a migration stream
+fn pick_port() -> u16 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    49152 + (nanos % 16000) as u16
+}
+
+/// Add the section that makes launch.sh wait for a migration when asked
+/// (no-op if present)
+pub(crate) fn add_incoming_section(content: &str) -> String {
+    if content.contains(INCOMING_MARKER_START) {
+        return content.to_string();
+    }
+    let mut section = String::new();
+    section.push_str(INCOMING_MARKER_START);
+    section.push('\n');
+    section.push_str("INCOMING_ARGS=\"\"\n");
+    section.push_str("if [[ -n \"$VM_CURATOR_INCOMING\" ]]; then\n");
+    section.push_str("    INCOMING_ARGS=\"-incoming $VM_CURATOR_INCOMING\"\n");
+    section.push_str("fi\n");
+    section.push_str(INCOMING_MARKER_END);
+    section.push('\n');
+    insert_args_section(content, &section, "$INCOMING_ARGS")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rsync_progress() {
+        assert_eq!(parse_rsync_progress("  1,234,567  42%   10.00MB/s    0:00:12 (xfr#1, to-chk=0/3)"), Some(0.42));
+        assert_eq!(parse_rsync_progress("sending incremental file list"), None);
+    }
+
+    #[test]
+    fn test_add_incoming_section() {
+        let script = "VM_DIR=\"/vms/t\"\ncase \"$1\" in\n    \"\")\n        qemu-system-x86_64 \\\n            -m 512M\n        ;;\nesac\n";
+        let with_incoming = add_incoming_section(script);
+        assert!(with_incoming.contains("INCOMING_ARGS=\"-incoming $VM_CURATOR_INCOMING\""));
+        assert!(with_incoming.contains("-m 512M $INCOMING_ARGS"));
+        assert_eq!(add_incoming_section(&with_incoming), with_incoming);
+    }
+}
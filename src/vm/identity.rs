@@ -0,0 +1,258 @@
+//! VM identity (UUID and MAC address)
+//!
+//! Without `-uuid` every VM reports the same all-zero system UUID, and
+//! every NIC gets QEMU's default 52:54:00:12:34:56. Windows ties activation
+//! to the UUID and DHCP servers hand out leases by MAC, so each VM gets its
+//! own pair at creation. The pair is recorded in vm-curator.toml and written
+//! to a managed section of launch.sh, which passes `-uuid` and gives the
+//! first NIC `mac=$VM_MAC`. A copied VM folder keeps the pair until it is
+//! regenerated.
+
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::vm::create::generate_uuid;
+use crate::vm::lifecycle::{insert_args_section, remove_args_section};
+use crate::vm::DiscoveredVm;
+
+// Identity section markers
+const IDENTITY_MARKER_START: &str = "# >>> Identity (managed by vm-curator) >>>";
+const IDENTITY_MARKER_END: &str = "# <<< Identity <<<";
+
+/// Locally administered prefix QEMU uses for guest NICs
+const QEMU_MAC_PREFIX: &str = "52:54:00";
+
+static RE_NIC: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"-device\s+[^\s\\]*\bnetdev=[^\s\\]+|-nic\s+[^\s\\]+").expect("Invalid regex: RE_NIC")
+});
+
+static RE_SMBIOS_UUID: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"type=1,[^\s"]*\buuid=([0-9a-fA-F-]{36})"#).expect("Invalid regex: RE_SMBIOS_UUID")
+});
+
+/// A VM's system UUID and NIC MAC address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VmIdentity {
+    pub uuid: String,
+    pub mac: String,
+}
+
+impl VmIdentity {
+    /// A fresh random identity
+    pub fn generate() -> Self {
+        Self {
+            uuid: generate_uuid(),
+            mac: generate_mac(),
+        }
+    }
+}
+
+/// A random MAC in QEMU's 52:54:00 range
+pub fn generate_mac() -> String {
+    let hex: Vec<char> = generate_uuid().chars().filter(|c| *c != '-').take(6).collect();
+    format!(
+        "{}:{}{}:{}{}:{}{}",
+        QEMU_MAC_PREFIX, hex[0], hex[1], hex[2], hex[3], hex[4], hex[5]
+    )
+}
+
+/// Read the identity from a launch script's managed section
+pub fn parse_identity(content: &str) -> Option<VmIdentity> {
+    let mut in_section = false;
+    let mut uuid = None;
+    let mut mac = None;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed == IDENTITY_MARKER_START {
+            in_section = true;
+        } else if trimmed == IDENTITY_MARKER_END {
+            break;
+        } else if in_section {
+            if let Some(value) = trimmed.strip_prefix("VM_UUID=") {
+                uuid = Some(value.trim_matches('"').to_string());
+            } else if let Some(value) = trimmed.strip_prefix("VM_MAC=") {
+                mac = Some(value.trim_matches('"').to_string());
+            }
+        }
+    }
+    Some(VmIdentity { uuid: uuid?, mac: mac? })
+}
+
+/// Add (or replace) the identity section and point the first NIC at `$VM_MAC`
+pub fn apply_identity(content: &str, identity: &VmIdentity) -> String {
+    let content = remove_identity_section(content);
+    let content = insert_args_section(&content, &generate_identity_section(identity), "$IDENTITY_ARGS");
+    add_nic_mac(&content)
+}
+
+/// Give the first NIC `mac=$VM_MAC` if the script has an identity section
+///
+/// Used after network settings regenerate the NIC arguments.
+pub fn add_nic_mac(content: &str) -> String {
+    if !content.contains(IDENTITY_MARKER_START) || content.contains("mac=$VM_MAC") {
+        return content.to_string();
+    }
+    RE_NIC
+        .replacen(content, 1, |caps: &Captures| {
+            let nic = &caps[0];
+            if nic.contains("mac=") {
+                nic.to_string()
+            } else {
+                format!("{},mac=$VM_MAC", nic)
+            }
+        })
+        .into_owned()
+}
+
+/// A new identity for a generated script, keeping the UUID its SMBIOS
+/// options already report
+pub fn identity_for_script(content: &str) -> VmIdentity {
+    VmIdentity {
+        uuid: smbios_uuid(content).unwrap_or_else(generate_uuid),
+        mac: generate_mac(),
+    }
+}
+
+/// Give a VM without an identity section one, keeping the UUID it already
+/// reports (SMBIOS) or was given (vm-curator.toml) so Windows stays activated
+pub fn assign_identity(vm: &DiscoveredVm) -> Result<VmIdentity> {
+    let content = std::fs::read_to_string(&vm.launch_script).context("Failed to read launch.sh")?;
+    let uuid = smbios_uuid(&content)
+        .or_else(|| read_manifest_uuid(&vm.path))
+        .unwrap_or_else(generate_uuid);
+    let identity = VmIdentity { uuid, mac: generate_mac() };
+    write_identity(vm, &content, &identity)?;
+    Ok(identity)
+}
+
+/// Replace a VM's UUID and MAC with new ones (for copied VMs)
+pub fn regenerate_identity(vm: &DiscoveredVm) -> Result<VmIdentity> {
+    let content = std::fs::read_to_string(&vm.launch_script).context("Failed to read launch.sh")?;
+    let identity = VmIdentity::generate();
+
+    // SMBIOS options carry the UUID too
+    let content = match parse_identity(&content) {
+        Some(old) => content.replace(&old.uuid, &identity.uuid),
+        None => content,
+    };
+    write_identity(vm, &content, &identity)?;
+    Ok(identity)
+}
+
+/// Other VMs in the library with the same UUID or MAC (copies of this one)
+pub fn shared_with<'a>(vm: &DiscoveredVm, vms: &'a [DiscoveredVm]) -> Vec<&'a DiscoveredVm> {
+    let Some(ref identity) = vm.config.identity else {
+        return Vec::new();
+    };
+    vms.iter()
+        .filter(|other| other.id != vm.id)
+        .filter(|other| {
+            other
+                .config
+                .identity
+                .as_ref()
+                .is_some_and(|o| o.uuid == identity.uuid || o.mac == identity.mac)
+        })
+        .collect()
+}
+
+/// Write `uuid` and `mac` to a VM's vm-curator.toml, keeping other keys
+pub fn write_manifest_identity(vm_dir: &Path, identity: &VmIdentity) -> Result<()> {
+    let metadata_path = vm_dir.join("vm-curator.toml");
+    let existing = std::fs::read_to_string(&metadata_path).unwrap_or_default();
+
+    let mut content = if existing.is_empty() {
+        "# VM Curator metadata\n\n".to_string()
+    } else {
+        existing
+            .lines()
+            .filter(|l| !is_key(l, "uuid") && !is_key(l, "mac"))
+            .map(|l| format!("{}\n", l))
+            .collect()
+    };
+    content.push_str(&format!("uuid = \"{}\"\n", identity.uuid));
+    content.push_str(&format!("mac = \"{}\"\n", identity.mac));
+
+    std::fs::write(&metadata_path, content)
+        .with_context(|| format!("Failed to write VM metadata: {}", metadata_path.display()))
+}
+
+fn write_identity(vm: &DiscoveredVm, content: &str, identity: &VmIdentity) -> Result<()> {
+    std::fs::write(&vm.launch_script, apply_identity(content, identity)).context("Failed to write launch.sh")?;
+    write_manifest_identity(&vm.path, identity)
+}
+
+fn smbios_uuid(content: &str) -> Option<String> {
+    RE_SMBIOS_UUID.captures(content).map(|caps| caps[1].to_lowercase())
+}
+
+fn read_manifest_uuid(vm_dir: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(vm_dir.join("vm-curator.toml")).ok()?;
+    content
+        .lines()
+        .find(|l| is_key(l, "uuid"))
+        .and_then(|l| l.split_once('='))
+        .map(|(_, value)| value.trim().trim_matches('"').to_string())
+}
+
+fn is_key(line: &str, key: &str) -> bool {
+    line.trim_start()
+        .strip_prefix(key)
+        .is_some_and(|rest| rest.trim_start().starts_with('='))
+}
+
+fn generate_identity_section(identity: &VmIdentity) -> String {
+    let mut section = String::new();
+    section.push_str(IDENTITY_MARKER_START);
+    section.push('\n');
+    section.push_str(&format!("VM_UUID=\"{}\"\n", identity.uuid));
+    section.push_str(&format!("VM_MAC=\"{}\"\n", identity.mac));
+    section.push_str("IDENTITY_ARGS=\"-uuid $VM_UUID\"\n");
+    section.push_str(IDENTITY_MARKER_END);
+    section.push('\n');
+    section
+}
+
+fn remove_identity_section(content: &str) -> String {
+    remove_args_section(content, IDENTITY_MARKER_START, IDENTITY_MARKER_END, "$IDENTITY_ARGS")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = "VM_DIR=\"/vms/xp\"\nqemu-system-i386 \\\n    -netdev user,id=net0 \\\n    -device rtl8139,netdev=net0 \\\n    -m 512M\n";
+
+    #[test]
+    fn test_apply_identity() {
+        let identity = VmIdentity {
+            uuid: "0f8fad5b-d9cb-469f-a165-70867728950e".to_string(),
+            mac: "52:54:00:ab:cd:ef".to_string(),
+        };
+        let applied = apply_identity(SCRIPT, &identity);
+        assert_eq!(parse_identity(&applied), Some(identity.clone()));
+        assert!(applied.contains("-device rtl8139,netdev=net0,mac=$VM_MAC \\"));
+        assert!(applied.contains("-m 512M $IDENTITY_ARGS"));
+
+        // Re-applying replaces the section without doubling the MAC
+        let again = apply_identity(&applied, &VmIdentity { mac: "52:54:00:00:00:01".to_string(), ..identity });
+        assert_eq!(again.matches("mac=$VM_MAC").count(), 1);
+        assert_eq!(again.matches("VM_MAC=").count(), 1);
+        assert!(again.contains("VM_MAC=\"52:54:00:00:00:01\""));
+
+        // Scripts without a section are left alone
+        assert_eq!(add_nic_mac(SCRIPT), SCRIPT);
+    }
+
+    #[test]
+    fn test_generate_identity() {
+        let mac = generate_mac();
+        assert_eq!(mac.len(), 17);
+        assert!(mac.starts_with("52:54:00:"));
+        assert!(is_key("uuid = \"x\"", "uuid"));
+        assert!(!is_key("uuid_old = \"x\"", "uuid"));
+    }
+}
@@ -0,0 +1,258 @@
+//! Evdev input passthrough
+//!
+//! Hands host keyboards, mice and game controllers straight to QEMU from
+//! `/dev/input`, skipping the display window's input path, which adds
+//! latency and loses relative mouse motion. A managed section of launch.sh
+//! builds `INPUT_ARGS` in one of two modes:
+//!
+//! - `input-linux` feeds the events into QEMU's emulated PS/2 or USB
+//!   devices, so DOS and Windows 9x need no drivers. A hotkey on the
+//!   keyboard grabs and releases every passed device. Those devices are
+//!   only a keyboard and a mouse, so game controllers still go through
+//!   virtio-input.
+//! - `virtio-input-host` gives the guest the devices themselves (every
+//!   button and axis of a controller), but needs the virtio-input driver
+//!   and holds the devices until the VM exits.
+//!
+//! Devices that aren't plugged in when the VM starts are skipped.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::hardware::input::{parse_link_name, InputKind};
+use crate::vm::lifecycle::{insert_args_section, remove_args_section};
+
+// Input passthrough section markers
+const INPUT_MARKER_START: &str = "# >>> Input Passthrough (managed by vm-curator) >>>";
+const INPUT_MARKER_END: &str = "# <<< Input Passthrough <<<";
+
+/// Grab/release hotkeys QEMU's input-linux understands: (value, label)
+pub const GRAB_TOGGLES: &[(&str, &str)] = &[
+    ("ctrl-ctrl", "Left Ctrl + Right Ctrl"),
+    ("alt-alt", "Left Alt + Right Alt"),
+    ("shift-shift", "Left Shift + Right Shift"),
+    ("meta-meta", "Left Super + Right Super"),
+    ("scrolllock", "Scroll Lock"),
+    ("ctrl-scrolllock", "Ctrl + Scroll Lock"),
+];
+
+/// How passed devices reach the guest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InputMode {
+    /// Events drive QEMU's emulated keyboard and mouse
+    InputLinux,
+    /// The guest sees the device through virtio-input
+    VirtioInputHost,
+}
+
+impl InputMode {
+    /// Identifier stored in launch.sh
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::InputLinux => "input-linux",
+            Self::VirtioInputHost => "virtio-input-host",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id {
+            "input-linux" => Some(Self::InputLinux),
+            "virtio-input-host" => Some(Self::VirtioInputHost),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::InputLinux => "Emulated PS/2 + USB (input-linux)",
+            Self::VirtioInputHost => "virtio-input-host",
+        }
+    }
+
+    pub fn toggle(&self) -> Self {
+        match self {
+            Self::InputLinux => Self::VirtioInputHost,
+            Self::VirtioInputHost => Self::InputLinux,
+        }
+    }
+}
+
+/// Input passthrough settings stored in launch.sh
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputPassthrough {
+    pub mode: InputMode,
+    /// Grab/release hotkey (input-linux only), one of `GRAB_TOGGLES`
+    pub grab_toggle: String,
+    /// `/dev/input/by-id` links
+    pub devices: Vec<PathBuf>,
+}
+
+impl Default for InputPassthrough {
+    fn default() -> Self {
+        Self {
+            mode: InputMode::InputLinux,
+            grab_toggle: GRAB_TOGGLES[0].0.to_string(),
+            devices: Vec::new(),
+        }
+    }
+}
+
+impl InputPassthrough {
+    /// Step the hotkey through `GRAB_TOGGLES`
+    pub fn cycle_grab_toggle(&mut self, delta: i32) {
+        let current_idx = GRAB_TOGGLES.iter().position(|(id, _)| *id == self.grab_toggle).unwrap_or(0);
+        let new_idx = (current_idx as i32 + delta).rem_euclid(GRAB_TOGGLES.len() as i32) as usize;
+        self.grab_toggle = GRAB_TOGGLES[new_idx].0.to_string();
+    }
+}
+
+/// Label for a grab hotkey value
+pub fn grab_toggle_label(value: &str) -> &str {
+    GRAB_TOGGLES
+        .iter()
+        .find(|(id, _)| *id == value)
+        .map(|(_, label)| *label)
+        .unwrap_or(value)
+}
+
+/// Write the input passthrough section of a launch script; no devices
+/// removes it
+pub fn set_input_passthrough(script_path: &Path, settings: &InputPassthrough) -> Result<()> {
+    let content = std::fs::read_to_string(script_path).context("Failed to read launch.sh")?;
+    let content = remove_input_section(&content);
+
+    let new_content = if settings.devices.is_empty() {
+        content
+    } else {
+        insert_args_section(&content, &generate_input_section(settings), "$INPUT_ARGS")
+    };
+
+    std::fs::write(script_path, new_content).context("Failed to write launch.sh")?;
+    Ok(())
+}
+
+/// Read the input passthrough settings from a launch script
+pub fn parse_input_passthrough(content: &str) -> Option<InputPassthrough> {
+    let mut settings = None::<InputPassthrough>;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed == INPUT_MARKER_START {
+            settings = Some(InputPassthrough::default());
+        } else if trimmed == INPUT_MARKER_END {
+            break;
+        } else if let Some(ref mut settings) = settings {
+            if let Some(value) = trimmed.strip_prefix("INPUT_MODE=") {
+                if let Some(mode) = InputMode::from_id(value.trim_matches('"')) {
+                    settings.mode = mode;
+                }
+            } else if let Some(value) = trimmed.strip_prefix("INPUT_GRAB_TOGGLE=") {
+                settings.grab_toggle = value.trim_matches('"').to_string();
+            } else if let Some(path) = trimmed
+                .strip_prefix("if [[ -e \"")
+                .and_then(|rest| rest.strip_suffix("\" ]]; then"))
+            {
+                settings.devices.push(PathBuf::from(path));
+            }
+        }
+    }
+    settings
+}
+
+/// QEMU arguments for one device; `first_keyboard` carries the grab hotkey
+fn device_args(settings: &InputPassthrough, index: usize, path: &Path, first_keyboard: bool) -> String {
+    let kind = path_kind(path);
+    match settings.mode {
+        InputMode::InputLinux if kind != InputKind::Joystick => {
+            let mut args = format!("-object input-linux,id=input{},evdev={}", index, path.display());
+            if first_keyboard {
+                args.push_str(&format!(",grab_all=on,grab-toggle={}", settings.grab_toggle));
+            }
+            if kind == InputKind::Keyboard {
+                args.push_str(",repeat=on");
+            }
+            args
+        }
+        _ => format!("-device virtio-input-host-pci,id=input{},evdev={}", index, path.display()),
+    }
+}
+
+fn path_kind(path: &Path) -> InputKind {
+    path.file_name()
+        .and_then(|name| parse_link_name(&name.to_string_lossy()))
+        .map_or(InputKind::Other, |(_, kind)| kind)
+}
+
+fn generate_input_section(settings: &InputPassthrough) -> String {
+    let first_keyboard = settings.devices.iter().position(|path| path_kind(path) == InputKind::Keyboard);
+
+    let mut section = String::new();
+    section.push_str(INPUT_MARKER_START);
+    section.push('\n');
+    section.push_str(&format!("INPUT_MODE=\"{}\"\n", settings.mode.id()));
+    // Kept in virtio mode too, for switching back
+    section.push_str(&format!("INPUT_GRAB_TOGGLE=\"{}\"\n", settings.grab_toggle));
+    section.push_str("INPUT_ARGS=\"\"\n");
+    for (i, path) in settings.devices.iter().enumerate() {
+        // A missing device would stop QEMU from starting
+        section.push_str(&format!("if [[ -e \"{}\" ]]; then\n", path.display()));
+        section.push_str(&format!(
+            "    INPUT_ARGS=\"$INPUT_ARGS {}\"\n",
+            device_args(settings, i, path, first_keyboard == Some(i))
+        ));
+        section.push_str("else\n");
+        section.push_str(&format!(
+            "    echo \"Input passthrough: {} not found, skipped\" >&2\n",
+            path.display()
+        ));
+        section.push_str("fi\n");
+    }
+    section.push_str(INPUT_MARKER_END);
+    section.push('\n');
+    section
+}
+
+fn remove_input_section(content: &str) -> String {
+    remove_args_section(content, INPUT_MARKER_START, INPUT_MARKER_END, "$INPUT_ARGS")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_section_round_trip() {
+        let script = "VM_DIR=\"/vms/dos\"\nqemu-system-i386 \\\n    -m 32M\n";
+        let settings = InputPassthrough {
+            mode: InputMode::InputLinux,
+            grab_toggle: "scrolllock".to_string(),
+            devices: vec![
+                PathBuf::from("/dev/input/by-id/usb-Logitech_USB_Receiver-if02-event-mouse"),
+                PathBuf::from("/dev/input/by-id/usb-Corsair_K70_RGB-event-kbd"),
+            ],
+        };
+        let with_input = insert_args_section(script, &generate_input_section(&settings), "$INPUT_ARGS");
+
+        assert_eq!(parse_input_passthrough(&with_input), Some(settings.clone()));
+        assert!(with_input.contains("-object input-linux,id=input0,evdev=/dev/input/by-id/usb-Logitech_USB_Receiver-if02-event-mouse\""));
+        assert!(with_input.contains("evdev=/dev/input/by-id/usb-Corsair_K70_RGB-event-kbd,grab_all=on,grab-toggle=scrolllock,repeat=on\""));
+        assert!(with_input.contains("-m 32M $INPUT_ARGS"));
+
+        // QEMU's emulated keyboard and mouse can't carry a controller
+        let pad = Path::new("/dev/input/by-id/usb-Microsoft_Controller_0-event-joystick");
+        assert_eq!(
+            device_args(&settings, 2, pad, false),
+            "-device virtio-input-host-pci,id=input2,evdev=/dev/input/by-id/usb-Microsoft_Controller_0-event-joystick"
+        );
+
+        let virtio = InputPassthrough { mode: InputMode::VirtioInputHost, ..settings };
+        let section = generate_input_section(&virtio);
+        assert!(section.contains("-device virtio-input-host-pci,id=input1,evdev=/dev/input/by-id/usb-Corsair_K70_RGB-event-kbd\""));
+        assert!(!section.contains("grab_all"));
+        assert_eq!(parse_input_passthrough(&section), Some(virtio));
+
+        let restored = remove_input_section(&with_input);
+        assert_eq!(parse_input_passthrough(&restored), None);
+        assert!(!restored.contains("INPUT"));
+    }
+}
@@ -0,0 +1,369 @@
+//! Per-VM advisory locks
+//!
+//! Two QEMU processes writing the same qcow2 image corrupt it, and two
+//! vm-curator instances sharing a library (on NFS, say) can overwrite each
+//! other's changes. Each VM directory can hold a lock file naming its
+//! owner: the host, user and process, and whether the VM is running or
+//! being edited from a management menu.
+//!
+//! A launch is refused while someone else holds the lock or while the VM's
+//! QMP socket answers (an instance started some other way). Locks held on
+//! this host are checked against their process, so one left by a crashed
+//! VM is removed by the next launch. A lock from another host can't be
+//! checked that way: the vm-curator instance holding it refreshes its
+//! heartbeat, an editing lock whose heartbeat stops is recovered, and a
+//! running VM's lock is only reported as probably stale and can be taken
+//! over by hand.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use super::local_socket::LocalStream;
+use super::qmp;
+
+/// File name of the lock inside the VM directory
+const LOCK_NAME: &str = "vm-curator.lock";
+
+/// A lock from another host whose heartbeat is this old (seconds) is stale
+const STALE_AFTER: i64 = 10 * 60;
+
+/// How often a holder refreshes its heartbeat (seconds)
+const HEARTBEAT_EVERY: i64 = 60;
+
+/// What a lock's holder is doing with the VM
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LockPurpose {
+    /// The VM is running (the lock belongs to its launch script)
+    Running,
+    /// A vm-curator instance has its management menu open
+    Editing,
+}
+
+/// Who holds a VM's lock, as written in the lock file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockOwner {
+    pub host: String,
+    pub user: String,
+    pub pid: u32,
+    pub purpose: LockPurpose,
+    /// When the lock was taken (Unix seconds)
+    pub since: i64,
+    /// When its host last confirmed it (Unix seconds)
+    pub heartbeat: i64,
+}
+
+impl LockOwner {
+    fn new(pid: u32, purpose: LockPurpose) -> Self {
+        let now = chrono::Utc::now().timestamp();
+        Self {
+            host: host_name(),
+            user: std::env::var("USER").unwrap_or_else(|_| "unknown".to_string()),
+            pid,
+            purpose,
+            since: now,
+            heartbeat: now,
+        }
+    }
+
+    /// Whether the lock was taken on this host
+    pub fn is_local(&self) -> bool {
+        self.host == host_name()
+    }
+
+    /// Whether this process holds the lock
+    fn is_ours(&self) -> bool {
+        self.is_local() && self.pid == std::process::id()
+    }
+
+    /// Whether another host's lock has stopped being refreshed
+    pub fn is_stale(&self) -> bool {
+        !self.is_local() && chrono::Utc::now().timestamp() - self.heartbeat > STALE_AFTER
+    }
+
+    /// "running on desktop (alice) since 2026-10-16 14:02"
+    pub fn describe(&self) -> String {
+        let doing = match self.purpose {
+            LockPurpose::Running => "running",
+            LockPurpose::Editing => "being edited",
+        };
+        let since = chrono::DateTime::from_timestamp(self.since, 0)
+            .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        let mut text = format!("{} on {} ({}) since {}", doing, self.host, self.user, since);
+        if self.is_stale() {
+            text.push_str(", probably stale");
+        }
+        text
+    }
+
+    fn write(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, toml::to_string(self)?).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// This machine's host name
+fn host_name() -> String {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|name| name.trim().to_string())
+        .unwrap_or_else(|_| "localhost".to_string())
+}
+
+/// A copy of the VM that is already running
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunningInstance {
+    /// Process holding the lock (None when only the QMP socket answered)
+    pub pid: Option<u32>,
+    /// Host it runs on, when that is not this one
+    pub host: Option<String>,
+}
+
+/// Path of the lock file for a VM
+pub fn lock_path(vm_dir: &Path) -> PathBuf {
+    vm_dir.join(LOCK_NAME)
+}
+
+fn read_owner(path: &Path) -> Option<LockOwner> {
+    let content = std::fs::read_to_string(path).ok()?;
+    toml::from_str(&content).ok().or_else(|| {
+        // Locks from before owners were recorded hold just a PID
+        let pid = content.trim().parse().ok()?;
+        Some(LockOwner::new(pid, LockPurpose::Running))
+    })
+}
+
+/// The current holder of a VM's lock, removing a lock that is no longer held
+pub fn owner(vm_dir: &Path) -> Option<LockOwner> {
+    let path = lock_path(vm_dir);
+    let owner = read_owner(&path)?;
+    let held = if owner.is_local() {
+        holds_lock(owner.pid, vm_dir)
+    } else {
+        // A running VM may outlive the vm-curator that refreshed its lock
+        owner.purpose == LockPurpose::Running || !owner.is_stale()
+    };
+    if !held {
+        let _ = std::fs::remove_file(&path);
+        return None;
+    }
+    Some(owner)
+}
+
+/// Find a running copy of the VM, removing a stale lock
+pub fn find_instance(vm_dir: &Path) -> Option<RunningInstance> {
+    if let Some(owner) = owner(vm_dir).filter(|o| o.purpose == LockPurpose::Running) {
+        let host = (!owner.is_local()).then_some(owner.host);
+        return Some(RunningInstance { pid: Some(owner.pid), host });
+    }
+
+    // A socket left by a crashed QEMU refuses connections
+    let socket = qmp::socket_path(vm_dir);
+    if socket.exists() && LocalStream::connect(&socket).is_ok() {
+        return Some(RunningInstance { pid: None, host: None });
+    }
+    None
+}
+
+/// Whether a process on this host still holds a VM's lock
+///
+/// The launch script (and QEMU) run in the VM directory; between taking the
+/// lock and starting the script, and while editing, the holder is a
+/// vm-curator process.
+#[cfg(not(windows))]
+fn holds_lock(pid: u32, vm_dir: &Path) -> bool {
+    let proc_dir = PathBuf::from(format!("/proc/{}", pid));
+    match std::fs::read_link(proc_dir.join("cwd")) {
+        Ok(cwd) => {
+            let vm_dir = vm_dir.canonicalize().unwrap_or_else(|_| vm_dir.to_path_buf());
+            let exe = std::fs::read_link(proc_dir.join("exe")).ok();
+            cwd == vm_dir || (exe.is_some() && exe == std::fs::read_link("/proc/self/exe").ok())
+        }
+        // Another user's process: assume it is still the VM
+        Err(e) if e.kind() == ErrorKind::PermissionDenied => true,
+        Err(_) => false,
+    }
+}
+
+/// Whether a process on this host still holds a VM's lock
+///
+/// Windows has no /proc to tell where a process runs, so any live process
+/// with the PID counts.
+#[cfg(windows)]
+fn holds_lock(pid: u32, _vm_dir: &Path) -> bool {
+    std::process::Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/NH", "/FO", "CSV"])
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains(&format!("\"{}\"", pid)))
+}
+
+/// A lock held by this process
+///
+/// Dropping it releases the lock, so a launch that fails before the
+/// process starts, or a closed management menu, leaves nothing behind.
+#[derive(Debug)]
+pub struct VmLock {
+    vm_dir: PathBuf,
+    held: bool,
+}
+
+impl VmLock {
+    /// Pass the lock to the launched process, which holds it until it exits
+    pub fn hand_over(mut self, pid: u32) {
+        let owner = LockOwner::new(pid, LockPurpose::Running);
+        let _ = owner.write(&lock_path(&self.vm_dir));
+        self.held = false;
+    }
+}
+
+impl Drop for VmLock {
+    fn drop(&mut self) {
+        let path = lock_path(&self.vm_dir);
+        // The lock may have been handed over or taken over since
+        if self.held && read_owner(&path).is_some_and(|o| o.is_ours()) {
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+}
+
+/// Create the lock file, failing if another instance got there first
+fn create(vm_dir: &Path, purpose: LockPurpose) -> Result<VmLock> {
+    let path = lock_path(vm_dir);
+    let mut file = match OpenOptions::new().write(true).create_new(true).open(&path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => bail!("The VM is being locked by another instance"),
+        Err(e) => return Err(e).with_context(|| format!("Failed to create {}", path.display())),
+    };
+    let owner = LockOwner::new(std::process::id(), purpose);
+    file.write_all(toml::to_string(&owner)?.as_bytes())
+        .context("Failed to write the lock file")?;
+    Ok(VmLock { vm_dir: vm_dir.to_path_buf(), held: true })
+}
+
+/// Take a VM's lock before launching it
+pub fn acquire(vm_dir: &Path) -> Result<VmLock> {
+    match owner(vm_dir) {
+        // Our own management menu gives way to the launch
+        Some(owner) if owner.is_ours() => {
+            let _ = std::fs::remove_file(lock_path(vm_dir));
+        }
+        Some(owner) if owner.purpose == LockPurpose::Running && owner.is_local() => {
+            bail!("The VM is already running (PID {}); a second copy would corrupt its disks", owner.pid)
+        }
+        Some(owner) => bail!("The VM is {}", owner.describe()),
+        None => {}
+    }
+    if find_instance(vm_dir).is_some() {
+        bail!("The VM is already running (its QMP socket answers); a second copy would corrupt its disks");
+    }
+    create(vm_dir, LockPurpose::Editing)
+}
+
+/// Take a VM's lock while changing its settings
+///
+/// Returns the lock's holder when someone else is using the VM. A VM
+/// running on this host needs no lock: the TUI already knows it is running.
+/// Libraries that can't be written to are not locked.
+pub fn acquire_for_editing(vm_dir: &Path) -> Result<Option<VmLock>, LockOwner> {
+    match owner(vm_dir) {
+        Some(owner) if owner.is_ours() => Ok(None),
+        Some(owner) if owner.is_local() && owner.purpose == LockPurpose::Running => Ok(None),
+        Some(owner) => Err(owner),
+        None => Ok(create(vm_dir, LockPurpose::Editing).ok()),
+    }
+}
+
+/// Remove someone else's lock (stale-lock recovery, confirmed by the user)
+pub fn break_lock(vm_dir: &Path) -> Result<()> {
+    let path = lock_path(vm_dir);
+    match std::fs::remove_file(&path) {
+        Err(e) if e.kind() != ErrorKind::NotFound => {
+            Err(e).with_context(|| format!("Failed to remove {}", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Refresh the heartbeat of a lock held on this host
+pub fn refresh(vm_dir: &Path) {
+    let path = lock_path(vm_dir);
+    let Some(mut owner) = read_owner(&path) else {
+        return;
+    };
+    let now = chrono::Utc::now().timestamp();
+    if owner.is_local() && now - owner.heartbeat >= HEARTBEAT_EVERY && holds_lock(owner.pid, vm_dir) {
+        owner.heartbeat = now;
+        let _ = owner.write(&path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("vm-curator-lock-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_launch_lock() {
+        let dir = test_dir("launch");
+
+        let lock = acquire(&dir).unwrap();
+        let owner = owner(&dir).unwrap();
+        assert!(owner.is_ours());
+        assert_eq!(owner.purpose, LockPurpose::Editing);
+        drop(lock);
+        assert!(!lock_path(&dir).exists());
+
+        // The launched process has exited: the lock is stale
+        acquire(&dir).unwrap().hand_over(u32::MAX);
+        assert!(lock_path(&dir).exists());
+        assert_eq!(find_instance(&dir), None);
+        assert!(!lock_path(&dir).exists());
+
+        // Locks written before owners were recorded
+        std::fs::write(lock_path(&dir), format!("{}\n", u32::MAX)).unwrap();
+        assert_eq!(find_instance(&dir), None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_foreign_lock() {
+        let dir = test_dir("foreign");
+        let now = chrono::Utc::now().timestamp();
+        let mut other = LockOwner {
+            host: "another-host.invalid".to_string(),
+            user: "alice".to_string(),
+            pid: 1,
+            purpose: LockPurpose::Running,
+            since: now,
+            heartbeat: now,
+        };
+        other.write(&lock_path(&dir)).unwrap();
+
+        let instance = find_instance(&dir).unwrap();
+        assert_eq!(instance.host.as_deref(), Some("another-host.invalid"));
+        assert!(acquire(&dir).is_err());
+        assert_eq!(acquire_for_editing(&dir).unwrap_err(), other);
+
+        // A stale editing lock is recovered; a stale running one is kept
+        other.heartbeat = now - STALE_AFTER - 1;
+        other.write(&lock_path(&dir)).unwrap();
+        assert!(owner(&dir).unwrap().describe().ends_with("probably stale"));
+        other.purpose = LockPurpose::Editing;
+        other.write(&lock_path(&dir)).unwrap();
+        let lock = acquire_for_editing(&dir).unwrap();
+        assert!(lock.is_some());
+        assert!(owner(&dir).unwrap().is_ours());
+        drop(lock);
+        assert!(!lock_path(&dir).exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,107 @@
+//! Install image downloads
+//!
+//! Fetches one of a profile's ISO sources for the creation wizard, trying
+//! its URL, then each mirror, then its Internet Archive copy until one
+//! works. A file is downloaded under a `.part` name and only renamed once
+//! complete and, when the source has a checksum, verified.
+
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::commands::{curl, iso};
+use crate::metadata::qemu_profiles::IsoSource;
+
+/// Host name of a URL, to say where a download comes from
+pub fn host(url: &str) -> &str {
+    url.split_once("://")
+        .map(|(_, rest)| rest.split('/').next().unwrap_or(rest))
+        .unwrap_or(url)
+}
+
+fn verify(path: &Path, sha256: Option<&str>) -> Result<bool> {
+    match sha256 {
+        Some(expected) => Ok(iso::sha256(path)?.eq_ignore_ascii_case(expected.trim())),
+        None => Ok(true),
+    }
+}
+
+/// Download an image into `dir`, calling `progress` with where it is coming
+/// from, the bytes so far and the total if known; returns the image's path
+///
+/// An earlier complete download of the same file is used as it is.
+pub fn download(
+    source: &IsoSource,
+    dir: &Path,
+    cancel: &AtomicBool,
+    mut progress: impl FnMut(&str, u64, Option<u64>),
+) -> Result<PathBuf> {
+    let urls = source.urls();
+    let Some(file_name) = source.file_name() else {
+        bail!("{} has no download URL", source.name);
+    };
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let dest = dir.join(&file_name);
+    if dest.exists() && verify(&dest, source.sha256.as_deref())? {
+        return Ok(dest);
+    }
+
+    let part = dir.join(format!("{}.part", file_name));
+    let mut errors = Vec::new();
+    for (i, url) in urls.iter().enumerate() {
+        let from = if urls.len() == 1 {
+            host(url).to_string()
+        } else {
+            format!("{} (source {} of {})", host(url), i + 1, urls.len())
+        };
+        match curl::download(url, &part, cancel, |done, total| progress(&from, done, total)) {
+            Ok(()) if verify(&part, source.sha256.as_deref())? => {
+                std::fs::rename(&part, &dest).with_context(|| format!("Failed to write {}", dest.display()))?;
+                return Ok(dest);
+            }
+            Ok(()) => {
+                let _ = std::fs::remove_file(&part);
+                errors.push(format!("{}: checksum mismatch", host(url)));
+            }
+            Err(e) if cancel.load(Ordering::Relaxed) => return Err(e),
+            Err(e) => errors.push(format!("{}: {:#}", host(url), e)),
+        }
+    }
+    bail!("No source of {} worked ({})", file_name, errors.join("; "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mirror_fallback() {
+        let dir = std::env::temp_dir().join(format!("vm-curator-iso-download-{}", std::process::id()));
+        let (origin, downloads) = (dir.join("origin"), dir.join("downloads"));
+        std::fs::create_dir_all(&origin).unwrap();
+        std::fs::write(origin.join("install.iso"), b"CD001 install image").unwrap();
+
+        // The primary is down; the mirror has the file
+        let mut source = IsoSource {
+            name: "Install CD".to_string(),
+            url: Some(format!("file://{}/missing/install.iso", dir.display())),
+            mirrors: vec![format!("file://{}/install.iso", origin.display())],
+            archive_org: None,
+            sha256: Some(iso::sha256(&origin.join("install.iso")).unwrap()),
+        };
+        assert_eq!(host("https://cdn.netbsd.org/pub/NetBSD/"), "cdn.netbsd.org");
+
+        let cancel = AtomicBool::new(false);
+        let path = download(&source, &downloads, &cancel, |_, _, _| {}).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"CD001 install image");
+        assert!(!downloads.join("install.iso.part").exists());
+
+        // A file that doesn't match the checksum is not kept
+        std::fs::remove_file(&path).unwrap();
+        source.sha256 = Some("0".repeat(64));
+        let error = download(&source, &downloads, &cancel, |_, _, _| {}).unwrap_err();
+        assert!(format!("{:#}", error).contains("checksum mismatch"));
+        assert!(!path.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
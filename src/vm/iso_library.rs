@@ -0,0 +1,160 @@
+//! ISO library
+//!
+//! A folder of install images, `isos` in the VM library unless configured
+//! otherwise. Each image is checksummed and looked up among the known
+//! images to tag it with the OS profile it installs. Checksumming a DVD
+//! image takes a while, so only images whose size (or, for known images
+//! without a size, file name) could match a known one are read, and their sums are cached in the folder, keyed by file name,
+//! size and modification time.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::commands::iso;
+use crate::metadata::iso_checksums::{IsoChecksums, KnownImage};
+
+/// Checksum cache, kept in the library folder
+pub const CACHE_FILE: &str = ".vm-curator-checksums.json";
+
+/// An image in the library
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibraryIso {
+    pub path: PathBuf,
+    pub size: u64,
+    /// None when the image wasn't checksummed (no known image has its size or name)
+    pub sha256: Option<String>,
+    /// The known image it is, if any
+    pub known: Option<KnownImage>,
+}
+
+impl LibraryIso {
+    pub fn file_name(&self) -> String {
+        self.path.file_name().unwrap_or_default().to_string_lossy().to_string()
+    }
+}
+
+/// A checksum as of the file's size and modification time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CachedSum {
+    size: u64,
+    modified: u64,
+    sha256: String,
+}
+
+/// Size and modification time (seconds) of a file
+fn stamp(path: &Path) -> Option<(u64, u64)> {
+    let meta = std::fs::metadata(path).ok().filter(|m| m.is_file())?;
+    let modified = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    Some((meta.len(), modified))
+}
+
+fn load_cache(dir: &Path) -> BTreeMap<String, CachedSum> {
+    std::fs::read_to_string(dir.join(CACHE_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// ISO images directly in `dir`, by name
+fn list_images(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut images: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("iso")))
+        .collect();
+    images.sort();
+    images
+}
+
+/// Checksum (where worthwhile) and identify every image in `dir`
+pub fn scan(dir: &Path, known: &IsoChecksums) -> Vec<LibraryIso> {
+    let cache = load_cache(dir);
+    let mut updated = BTreeMap::new();
+    let mut isos = Vec::new();
+
+    for path in list_images(dir) {
+        let Some((size, modified)) = stamp(&path) else {
+            continue;
+        };
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let sha256 = match cache.get(&name) {
+            Some(cached) if cached.size == size && cached.modified == modified => Some(cached.sha256.clone()),
+            _ if known.could_match(&name, size) => match iso::sha256(&path) {
+                Ok(sum) => Some(sum),
+                Err(e) => {
+                    tracing::warn!(iso = %path.display(), "checksum failed: {:#}", e);
+                    None
+                }
+            },
+            _ => None,
+        };
+        if let Some(ref sha256) = sha256 {
+            updated.insert(name, CachedSum { size, modified, sha256: sha256.clone() });
+        }
+        let known = sha256.as_deref().and_then(|sum| known.identify(sum)).cloned();
+        isos.push(LibraryIso { path, size, sha256, known });
+    }
+
+    // Removed and changed images drop out of the cache
+    if updated != cache {
+        if let Ok(json) = serde_json::to_string_pretty(&updated) {
+            let _ = std::fs::write(dir.join(CACHE_FILE), json);
+        }
+    }
+    isos
+}
+
+/// The known image `path` is, going only by an earlier scan of its folder
+/// (nothing is read from an image that hasn't been checksummed)
+pub fn identify_cached(path: &Path, known: &IsoChecksums) -> Option<KnownImage> {
+    let (size, modified) = stamp(path)?;
+    let name = path.file_name()?.to_string_lossy().to_string();
+    let cache = load_cache(path.parent()?);
+    let cached = cache.get(&name).filter(|c| c.size == size && c.modified == modified)?;
+    known.identify(&cached.sha256).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::QemuProfileStore;
+
+    #[test]
+    fn test_scan_identifies_and_caches() {
+        let dir = std::env::temp_dir().join(format!("vm-curator-iso-library-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("fd13-live.iso"), b"FreeDOS").unwrap();
+        std::fs::write(dir.join("mystery.iso"), b"Unknown").unwrap();
+        std::fs::write(dir.join("odd-size.iso"), b"Too long to be known").unwrap();
+        std::fs::write(dir.join("notes.txt"), b"FreeDOS").unwrap();
+
+        let sha256 = iso::sha256(&dir.join("fd13-live.iso")).unwrap();
+        let user_file = dir.join("iso_checksums.toml");
+        std::fs::write(
+            &user_file,
+            format!("[[image]]\nname = \"FreeDOS 1.3 LiveCD\"\nsha256 = \"{}\"\nprofile = \"freedos\"\nsize = 7\n", sha256),
+        )
+        .unwrap();
+        let known = IsoChecksums::load(&user_file, &QemuProfileStore::new());
+
+        let isos = scan(&dir, &known);
+        let names: Vec<String> = isos.iter().map(|i| i.file_name()).collect();
+        assert_eq!(names, ["fd13-live.iso", "mystery.iso", "odd-size.iso"]);
+        assert_eq!(isos[0].known.as_ref().map(|k| k.profile.as_str()), Some("freedos"));
+        // Same size as a known image: checksummed, but not a match
+        assert!(isos[1].sha256.is_some() && isos[1].known.is_none());
+        // No known image is this size, so it isn't read at all
+        assert_eq!(isos[2].sha256, None);
+
+        assert_eq!(identify_cached(&dir.join("fd13-live.iso"), &known).map(|k| k.name), Some("FreeDOS 1.3 LiveCD".to_string()));
+        assert_eq!(identify_cached(&dir.join("odd-size.iso"), &known), None);
+        assert_eq!(load_cache(&dir).len(), 2);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
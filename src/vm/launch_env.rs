@@ -0,0 +1,237 @@
+//! Environment variables, wrapper command and QEMU build for launches
+//!
+//! Some setups need environment variables (`SDL_VIDEODRIVER=x11`) or a
+//! command in front of the emulator (`nice -n 10`, `gamemoderun`,
+//! `prime-run`), and old OSes sometimes run better on a particular QEMU
+//! release. All three are kept in the VM's vm-curator.toml as
+//! `launch_env = ["NAME=value", ...]`, `launch_wrapper = "..."` and
+//! `qemu_binary = "/path/to/qemu-system-..."`, and applied whenever
+//! vm-curator starts the launch script. The wrapper runs the script, so
+//! QEMU inherits whatever it sets up; the chosen QEMU's directory goes
+//! first on PATH, so the script's emulator command finds it. A plain
+//! `./launch.sh` goes without them.
+
+use anyhow::{bail, Context, Result};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::commands::qemu_system;
+
+const ENV_KEY: &str = "launch_env";
+const WRAPPER_KEY: &str = "launch_wrapper";
+const BINARY_KEY: &str = "qemu_binary";
+
+/// A VM's launch environment
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LaunchEnvironment {
+    /// Variables set for the launch, as `NAME=value`
+    pub env: Vec<String>,
+    /// Command the launch script is run through (e.g. "nice -n 10")
+    pub wrapper: String,
+    /// QEMU binary to use instead of the one on PATH
+    pub qemu_binary: Option<PathBuf>,
+}
+
+impl LaunchEnvironment {
+    pub fn is_empty(&self) -> bool {
+        self.env.is_empty() && self.wrapper.trim().is_empty() && self.qemu_binary.is_none()
+    }
+
+    /// PATH with the chosen QEMU's directory first (None without a choice)
+    pub fn path_var(&self) -> Result<Option<OsString>> {
+        let Some(ref binary) = self.qemu_binary else {
+            return Ok(None);
+        };
+        if !binary.exists() {
+            bail!("The QEMU chosen for this VM ({}) is gone; pick another under QEMU Version", binary.display());
+        }
+        let mut dirs = vec![qemu_system::binary_dir(binary)?];
+        if let Some(path) = std::env::var_os("PATH") {
+            dirs.extend(std::env::split_paths(&path));
+        }
+        Ok(Some(std::env::join_paths(dirs)?))
+    }
+
+    /// The variables as (name, value) pairs
+    pub fn vars(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.env.iter().filter_map(|var| var.split_once('='))
+    }
+
+    /// A command running `program` through the wrapper, with the variables
+    /// set and the chosen QEMU first on PATH
+    pub fn command(&self, program: &str) -> Result<Command> {
+        let mut words = self.wrapper.split_whitespace();
+        let mut cmd = match words.next() {
+            Some(wrapper) => {
+                let mut cmd = Command::new(wrapper);
+                cmd.args(words).arg(program);
+                cmd
+            }
+            None => Command::new(program),
+        };
+        cmd.envs(self.vars());
+        if let Some(path) = self.path_var()? {
+            cmd.env("PATH", path);
+        }
+        Ok(cmd)
+    }
+
+    /// One line describing the settings, for logs
+    pub fn describe(&self) -> String {
+        let mut parts = self.env.clone();
+        if !self.wrapper.trim().is_empty() {
+            parts.push(format!("through {}", self.wrapper.trim()));
+        }
+        if let Some(ref binary) = self.qemu_binary {
+            parts.push(format!("QEMU {}", binary.display()));
+        }
+        parts.join(" ")
+    }
+}
+
+/// Check a `NAME=value` entry
+pub fn validate_var(var: &str) -> Result<()> {
+    let Some((name, _)) = var.split_once('=') else {
+        bail!("\"{}\" needs the form NAME=value", var);
+    };
+    let valid_name = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid_name {
+        bail!("\"{}\" isn't a valid variable name", name);
+    }
+    Ok(())
+}
+
+/// The launch environment kept for the VM in `vm_dir`
+pub fn load(vm_dir: &Path) -> LaunchEnvironment {
+    std::fs::read_to_string(vm_dir.join("vm-curator.toml"))
+        .map(|content| parse(&content))
+        .unwrap_or_default()
+}
+
+fn parse(content: &str) -> LaunchEnvironment {
+    let Ok(table) = content.parse::<toml::Table>() else {
+        return LaunchEnvironment::default();
+    };
+    let env = table
+        .get(ENV_KEY)
+        .and_then(|value| value.as_array())
+        .map(|vars| {
+            vars.iter()
+                .filter_map(|var| var.as_str())
+                .filter(|var| validate_var(var).is_ok())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default();
+    let wrapper = table
+        .get(WRAPPER_KEY)
+        .and_then(|value| value.as_str())
+        .unwrap_or_default()
+        .to_string();
+    let qemu_binary = table.get(BINARY_KEY).and_then(|value| value.as_str()).map(PathBuf::from);
+    LaunchEnvironment { env, wrapper, qemu_binary }
+}
+
+/// Save the launch environment to the VM's vm-curator.toml, keeping other keys
+pub fn save(vm_dir: &Path, environment: &LaunchEnvironment) -> Result<()> {
+    for var in &environment.env {
+        validate_var(var)?;
+    }
+    let metadata_path = vm_dir.join("vm-curator.toml");
+    let existing = std::fs::read_to_string(&metadata_path).unwrap_or_default();
+    std::fs::write(&metadata_path, update(&existing, environment))
+        .with_context(|| format!("Failed to write VM metadata: {}", metadata_path.display()))
+}
+
+fn update(existing: &str, environment: &LaunchEnvironment) -> String {
+    let mut content = if existing.is_empty() {
+        "# VM Curator metadata\n\n".to_string()
+    } else {
+        existing
+            .lines()
+            .filter(|l| ![ENV_KEY, WRAPPER_KEY, BINARY_KEY].iter().any(|key| is_key(l, key)))
+            .map(|l| format!("{}\n", l))
+            .collect()
+    };
+    if !environment.env.is_empty() {
+        let vars: Vec<toml::Value> = environment.env.iter().cloned().map(toml::Value::String).collect();
+        content.push_str(&format!("{} = {}\n", ENV_KEY, toml::Value::Array(vars)));
+    }
+    let wrapper = environment.wrapper.trim();
+    if !wrapper.is_empty() {
+        content.push_str(&format!("{} = {}\n", WRAPPER_KEY, toml::Value::String(wrapper.to_string())));
+    }
+    if let Some(ref binary) = environment.qemu_binary {
+        let binary = toml::Value::String(binary.to_string_lossy().to_string());
+        content.push_str(&format!("{} = {}\n", BINARY_KEY, binary));
+    }
+    content
+}
+
+fn is_key(line: &str, key: &str) -> bool {
+    line.trim_start()
+        .strip_prefix(key)
+        .is_some_and(|rest| rest.trim_start().starts_with('='))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let existing = "# VM Curator metadata\n\ndisplay_name = \"Quake box\"\nlaunch_wrapper = \"nice\"\nuuid = \"1234\"\n";
+        let environment = LaunchEnvironment {
+            env: vec!["SDL_VIDEODRIVER=x11".to_string(), "PULSE_LATENCY_MSEC=60".to_string()],
+            wrapper: "gamemoderun".to_string(),
+            qemu_binary: Some(PathBuf::from("/opt/qemu-2.12/bin/qemu-system-i386")),
+        };
+        let content = update(existing, &environment);
+        assert!(content.contains("display_name = \"Quake box\"\n"));
+        assert!(content.contains("uuid = \"1234\"\n"));
+        assert!(!content.contains("\"nice\""));
+        assert_eq!(parse(&content), environment);
+
+        let cleared = update(&content, &LaunchEnvironment::default());
+        assert!(!cleared.contains("launch_"));
+        assert!(parse(&cleared).is_empty());
+    }
+
+    #[test]
+    fn test_validate_var() {
+        assert!(validate_var("SDL_VIDEODRIVER=x11").is_ok());
+        assert!(validate_var("EMPTY=").is_ok());
+        assert!(validate_var("SDL_VIDEODRIVER").is_err());
+        assert!(validate_var("1ST=x").is_err());
+        assert!(validate_var("MY-VAR=x").is_err());
+    }
+
+    #[test]
+    fn test_command() {
+        let environment = LaunchEnvironment {
+            env: vec!["SDL_VIDEODRIVER=x11".to_string()],
+            wrapper: "nice -n 10".to_string(),
+            qemu_binary: None,
+        };
+        let cmd = environment.command("bash").unwrap();
+        assert_eq!(cmd.get_program(), "nice");
+        assert_eq!(cmd.get_args().collect::<Vec<_>>(), ["-n", "10", "bash"]);
+        assert_eq!(cmd.get_envs().collect::<Vec<_>>(), [("SDL_VIDEODRIVER".as_ref(), Some("x11".as_ref()))]);
+        assert_eq!(LaunchEnvironment::default().command("bash").unwrap().get_program(), "bash");
+
+        // The chosen QEMU's directory comes first on PATH
+        let environment = LaunchEnvironment {
+            qemu_binary: Some(PathBuf::from("/bin/sh")),
+            ..Default::default()
+        };
+        let path = environment.path_var().unwrap().unwrap();
+        assert_eq!(std::env::split_paths(&path).next(), Some(PathBuf::from("/bin")));
+        let gone = LaunchEnvironment {
+            qemu_binary: Some(PathBuf::from("/nonexistent/qemu-system-i386")),
+            ..Default::default()
+        };
+        assert!(gone.command("bash").is_err());
+    }
+}
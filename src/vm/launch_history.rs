@@ -0,0 +1,173 @@
+//! Launch history and usage statistics
+//!
+//! Each launch of a local VM is recorded in `launch-history.json` in its
+//! folder, whichever front end started it. A session's end is recorded by
+//! the TUI when it sees the VM stop; a VM launched while no TUI was running
+//! keeps a session with an unknown end, which counts as a launch but adds
+//! nothing to the total time.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// History file in each VM folder
+const HISTORY_FILE: &str = "launch-history.json";
+
+/// A launch within this many seconds of an open session's start is that
+/// session (the TUI can see QEMU running before the launch is recorded)
+const SAME_LAUNCH_SECS: i64 = 60;
+
+/// A run of the VM (Unix seconds)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    pub start: i64,
+    /// None while running, or if the VM was never seen to stop
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<i64>,
+}
+
+/// Every recorded session of a VM, oldest first
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LaunchHistory {
+    #[serde(default)]
+    pub sessions: Vec<Session>,
+}
+
+impl LaunchHistory {
+    pub fn launches(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Start of the latest session
+    pub fn last_launched(&self) -> Option<i64> {
+        self.sessions.last().map(|s| s.start)
+    }
+
+    /// Total time of the sessions with a known end (seconds)
+    pub fn total_secs(&self) -> i64 {
+        self.sessions
+            .iter()
+            .filter_map(|s| s.end.map(|end| (end - s.start).max(0)))
+            .sum()
+    }
+
+    /// Whether the latest session has not been seen to end
+    fn is_open(&self) -> bool {
+        self.sessions.last().is_some_and(|s| s.end.is_none())
+    }
+}
+
+fn history_path(vm_dir: &Path) -> PathBuf {
+    vm_dir.join(HISTORY_FILE)
+}
+
+/// A VM's launch history (empty if it has never been launched)
+pub fn load(vm_dir: &Path) -> LaunchHistory {
+    std::fs::read_to_string(history_path(vm_dir))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save(vm_dir: &Path, history: &LaunchHistory) -> Result<()> {
+    let path = history_path(vm_dir);
+    std::fs::write(&path, serde_json::to_string_pretty(history)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Record a launch; an earlier session still open is left with an unknown end
+pub fn record_launch(vm_dir: &Path, at: i64) -> Result<()> {
+    let mut history = load(vm_dir);
+    if history.is_open() && history.last_launched().is_some_and(|start| (at - start).abs() < SAME_LAUNCH_SECS) {
+        return Ok(());
+    }
+    history.sessions.push(Session { start: at, end: None });
+    save(vm_dir, &history)
+}
+
+/// Record a VM seen running, for VMs started outside vm-curator
+pub fn record_running(vm_dir: &Path, at: i64) -> Result<()> {
+    let mut history = load(vm_dir);
+    if history.is_open() {
+        return Ok(());
+    }
+    history.sessions.push(Session { start: at, end: None });
+    save(vm_dir, &history)
+}
+
+/// Record the end of the running session
+pub fn record_stop(vm_dir: &Path, at: i64) -> Result<()> {
+    let mut history = load(vm_dir);
+    let Some(session) = history.sessions.last_mut().filter(|s| s.end.is_none()) else {
+        return Ok(());
+    };
+    session.end = Some(at);
+    save(vm_dir, &history)
+}
+
+/// "3h 25m", "12m" or "40s"
+pub fn format_duration(secs: i64) -> String {
+    let (hours, minutes) = (secs / 3600, secs % 3600 / 60);
+    match (hours, minutes) {
+        (0, 0) => format!("{}s", secs),
+        (0, m) => format!("{}m", m),
+        (h, m) => format!("{}h {}m", h, m),
+    }
+}
+
+/// Local date and time of a timestamp, to the minute
+pub fn format_time(at: i64) -> String {
+    chrono::DateTime::from_timestamp(at, 0)
+        .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+/// "today", "yesterday" or "5 days ago"
+pub fn format_ago(at: i64, now: i64) -> String {
+    match (now - at) / 86400 {
+        ..=0 => "today".to_string(),
+        1 => "yesterday".to_string(),
+        days => format!("{} days ago", days),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sessions() {
+        let dir = std::env::temp_dir().join(format!("vm-curator-history-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert_eq!(load(&dir).launches(), 0);
+
+        // Seen running before the launch was recorded
+        record_running(&dir, 1000).unwrap();
+        record_launch(&dir, 1001).unwrap();
+        record_running(&dir, 1100).unwrap();
+        record_stop(&dir, 4600).unwrap();
+        // Launched without a TUI to see it stop
+        record_launch(&dir, 10_000).unwrap();
+        record_launch(&dir, 20_000).unwrap();
+        record_stop(&dir, 20_900).unwrap();
+        record_stop(&dir, 30_000).unwrap();
+        // Found running after starting outside vm-curator
+        record_running(&dir, 40_000).unwrap();
+
+        let history = load(&dir);
+        assert_eq!(history.launches(), 4);
+        assert_eq!(history.last_launched(), Some(40_000));
+        assert_eq!(history.total_secs(), 3600 + 900);
+        assert_eq!(history.sessions[1], Session { start: 10_000, end: None });
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(format_duration(40), "40s");
+        assert_eq!(format_duration(12 * 60 + 5), "12m");
+        assert_eq!(format_duration(3 * 3600 + 25 * 60), "3h 25m");
+        assert_eq!(format_ago(1000, 1000 + 3600), "today");
+        assert_eq!(format_ago(1000, 1000 + 86400 * 5), "5 days ago");
+    }
+}
@@ -4,6 +4,7 @@ use std::path::{Path, PathBuf};
 
 use super::qemu_config::*;
 use crate::commands::qemu_img;
+use super::remote_disk::{parse_remote, RemoteDisk};
 
 /// Parse a launch.sh script and extract QEMU configuration
 pub fn parse_launch_script(script_path: &Path, content: &str) -> Result<QemuConfig> {
@@ -38,14 +39,29 @@ pub fn parse_launch_script(script_path: &Path, content: &str) -> Result<QemuConf
         config.vga = vga;
     }
 
+    // virtio-gpu 3D (virtio-vga-gl, gl=on or egl-headless)
+    config.gpu_accel = crate::vm::gpu_accel::parse_gpu_accel(content);
+
+    // Extract resolution hint (xres/yres on the display device)
+    config.resolution = extract_resolution(content);
+
     // Extract audio devices
     config.audio_devices = extract_audio_devices(content);
+    config.audio_backend = extract_audio_backend(content);
+    config.sb16 = crate::vm::sound_blaster::Sb16Settings::parse(content);
 
-    // Check for KVM
-    config.enable_kvm = content.contains("-enable-kvm") || content.contains("-accel kvm");
+    // Check for KVM (or WHPX on Windows hosts)
+    config.enable_kvm = content.contains("-enable-kvm")
+        || content.contains("-accel kvm")
+        || content.contains("accel=whpx")
+        || content.contains("-accel whpx");
 
     // Check for UEFI
     config.uefi = content.contains("OVMF") || content.contains("-bios") && content.contains("efi");
+    config.secure_boot = config.uefi && content.contains("cfi.pflash01,property=secure,value=on");
+
+    // USB controller (only the explicit `id=usb` controller vm-curator generates)
+    config.usb_controller = extract_usb_controller(content);
 
     // Check for TPM
     config.tpm = content.contains("-tpmdev") || content.contains("swtpm");
@@ -59,6 +75,37 @@ pub fn parse_launch_script(script_path: &Path, content: &str) -> Result<QemuConf
     // Extract extra arguments we don't specifically parse
     config.extra_args = extract_extra_args(content);
 
+    // Archival mode (managed section)
+    config.archival_date = crate::vm::archival::parse_archival_date(content);
+
+    // Guest agent channel
+    config.guest_agent = crate::vm::guest_agent::is_configured(content);
+
+    // CPU era masking (managed section)
+    config.cpu_era = crate::vm::cpu_era::parse_cpu_era(content);
+
+    // Looking Glass shared memory (managed section)
+    config.looking_glass = crate::vm::looking_glass::parse_looking_glass(content);
+
+    // MIDI output routing (managed section)
+    config.midi_synth = crate::vm::midi::parse_midi_synth(content);
+
+    // Evdev input passthrough (managed section)
+    config.input_passthrough = crate::vm::input_passthrough::parse_input_passthrough(content);
+
+    // Display scaling and full screen (managed section)
+    config.display_preset = crate::vm::display_preset::parse_display_preset(content);
+    config.identity = crate::vm::identity::parse_identity(content);
+
+    // SPICE channels (managed section)
+    config.spice = crate::vm::spice::parse_spice(content);
+
+    // VNC display number (managed section)
+    config.vnc_display = crate::vm::vnc::parse_vnc_display(content);
+
+    // Install mode until the OS is installed (managed section)
+    config.install_pending = crate::vm::first_boot::is_pending(content);
+
     Ok(config)
 }
 
@@ -71,6 +118,7 @@ fn extract_emulator(content: &str) -> Option<QemuEmulator> {
         "qemu-system-m68k",
         "qemu-system-arm",
         "qemu-system-aarch64",
+        "qemu-system-riscv64",
     ];
 
     for emulator in emulators {
@@ -131,7 +179,8 @@ fn extract_cpu_cores(content: &str) -> Option<u32> {
 /// Extract CPU model
 fn extract_cpu_model(content: &str) -> Option<String> {
     for line in content.lines() {
-        if line.trim_start().starts_with('#') {
+        // Managed sections (e.g. CPU era) carry their own -cpu in a variable
+        if line.trim_start().starts_with('#') || line.contains("_ARGS=") {
             continue;
         }
 
@@ -160,7 +209,7 @@ fn extract_machine(content: &str) -> Option<String> {
             let rest = &line[idx + 3..];
             let machine: String = rest
                 .chars()
-                .take_while(|c| !c.is_whitespace() && *c != '\\')
+                .take_while(|c| !c.is_whitespace() && *c != ',' && *c != '\\')
                 .collect();
             if !machine.is_empty() {
                 return Some(machine);
@@ -198,10 +247,87 @@ fn extract_vga(content: &str) -> Option<VgaType> {
                 return Some(VgaType::from_str(&vga));
             }
         }
+
+        // Display device given explicitly (used when passing a resolution)
+        if line.contains("-device VGA") {
+            return Some(VgaType::Std);
+        }
+        if line.contains("-device qxl-vga") {
+            return Some(VgaType::Qxl);
+        }
     }
     None
 }
 
+/// Extract resolution from xres=/yres= device properties
+fn extract_resolution(content: &str) -> Option<String> {
+    for line in content.lines() {
+        if line.trim_start().starts_with('#') || !line.contains("xres=") {
+            continue;
+        }
+
+        let prop = |key: &str| -> Option<u32> {
+            let start = line.find(key)? + key.len();
+            line[start..]
+                .chars()
+                .take_while(|c| c.is_ascii_digit())
+                .collect::<String>()
+                .parse()
+                .ok()
+        };
+
+        if let (Some(w), Some(h)) = (prop("xres="), prop("yres=")) {
+            return Some(format!("{}x{}", w, h));
+        }
+    }
+    None
+}
+
+/// Find the host audio driver from `-audiodev pa,id=...` (or the `-audio pa,...` shorthand)
+pub fn extract_audio_backend(content: &str) -> Option<String> {
+    content
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .find_map(|line| {
+            let mut args = line.split_whitespace();
+            while let Some(arg) = args.next() {
+                if arg == "-audiodev" || arg == "-audio" {
+                    let spec = args.next()?.trim_matches('"');
+                    let driver = spec.split(',').next()?;
+                    let driver = driver.strip_prefix("driver=").unwrap_or(driver);
+                    return Some(driver.to_string()).filter(|d| !d.contains('='));
+                }
+            }
+            None
+        })
+}
+
+/// Find the VM's USB controller (uhci, ehci, xhci)
+///
+/// Scripts that rely on `-usb` (the machine's default controller) or have
+/// no USB at all yield None.
+pub fn extract_usb_controller(content: &str) -> Option<String> {
+    const CONTROLLERS: &[(&str, &str)] = &[
+        ("qemu-xhci,id=usb", "xhci"),
+        ("nec-usb-xhci,id=usb", "xhci"),
+        ("ich9-usb-ehci1,id=usb", "ehci"),
+        ("usb-ehci,id=usb", "ehci"),
+        ("piix3-usb-uhci,id=usb", "uhci"),
+        ("piix4-usb-uhci,id=usb", "uhci"),
+    ];
+
+    content
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .find_map(|line| {
+            CONTROLLERS.iter().find_map(|(device, name)| {
+                line.split_whitespace()
+                    .any(|arg| arg.trim_matches('"') == *device || arg.starts_with(&format!("{},", device)))
+                    .then(|| name.to_string())
+            })
+        })
+}
+
 /// Extract audio devices
 fn extract_audio_devices(content: &str) -> Vec<AudioDevice> {
     let mut devices = Vec::new();
@@ -226,11 +352,19 @@ fn extract_audio_devices(content: &str) -> Vec<AudioDevice> {
         devices.push(AudioDevice::Es1370);
     }
 
+    // Check for AdLib (OPL2 FM) and Gravis Ultrasound
+    if content.contains("adlib") {
+        devices.push(AudioDevice::AdLib);
+    }
+    if content.contains("-device gus") || content.contains("-soundhw gus") {
+        devices.push(AudioDevice::Gus);
+    }
+
     devices
 }
 
 /// Extract shell variable assignments from the script
-fn extract_shell_variables(content: &str, vm_dir: &Path) -> HashMap<String, String> {
+pub(crate) fn extract_shell_variables(content: &str, vm_dir: &Path) -> HashMap<String, String> {
     let mut vars = HashMap::new();
 
     // Pre-populate with common directory variables
@@ -371,7 +505,8 @@ fn extract_disks(content: &str, vm_dir: &Path) -> Vec<DiskConfig> {
     let vars = extract_shell_variables(content, vm_dir);
 
     for line in content.lines() {
-        if line.trim_start().starts_with('#') {
+        // Physical disks are host devices, listed on their own screen
+        if line.trim_start().starts_with('#') || line.trim_start().starts_with("PHYSICAL_DISK_ARGS=") {
             continue;
         }
 
@@ -382,12 +517,17 @@ fn extract_disks(content: &str, vm_dir: &Path) -> Vec<DiskConfig> {
                 let rest = &line[idx + pattern.len()..];
                 if let Some(path) = extract_path_from_arg(rest) {
                     let expanded = expand_variables(&path, &vars, vm_dir);
+                    if let Some(remote) = parse_remote(&expanded) {
+                        disks.push(remote_disk_config(remote, line, "ide"));
+                        continue;
+                    }
                     let full_path = resolve_path(&expanded, vm_dir);
                     let format = guess_disk_format(&full_path);
                     disks.push(DiskConfig {
                         path: full_path,
                         format,
                         interface: "ide".to_string(),
+                        remote: None,
                     });
                 }
             }
@@ -397,8 +537,6 @@ fn extract_disks(content: &str, vm_dir: &Path) -> Vec<DiskConfig> {
         if line.contains("-drive") && line.contains("file=") {
             if let Some(path) = extract_drive_file(line) {
                 let expanded = expand_variables(&path, &vars, vm_dir);
-                let full_path = resolve_path(&expanded, vm_dir);
-                let format = guess_disk_format(&full_path);
                 let interface = if line.contains("if=virtio") {
                     "virtio"
                 } else if line.contains("if=scsi") {
@@ -406,10 +544,21 @@ fn extract_disks(content: &str, vm_dir: &Path) -> Vec<DiskConfig> {
                 } else {
                     "ide"
                 };
+                if let Some(remote) = parse_remote(&expanded) {
+                    disks.push(remote_disk_config(remote, line, interface));
+                    continue;
+                }
+                let full_path = resolve_path(&expanded, vm_dir);
+                // The image's header wins; a missing image is taken at its word
+                let format = match declared_format(line) {
+                    Some(declared) if !full_path.exists() => declared,
+                    _ => guess_disk_format(&full_path),
+                };
                 disks.push(DiskConfig {
                     path: full_path,
                     format,
                     interface: interface.to_string(),
+                    remote: None,
                 });
             }
         }
@@ -418,6 +567,18 @@ fn extract_disks(content: &str, vm_dir: &Path) -> Vec<DiskConfig> {
     disks
 }
 
+/// A disk on an NBD/iSCSI server (never probed with qemu-img: it would
+/// block on the network, and the format is whatever the drive line says)
+fn remote_disk_config(remote: RemoteDisk, line: &str, interface: &str) -> DiskConfig {
+    let format = declared_format(line).unwrap_or(DiskFormat::Raw);
+    DiskConfig {
+        path: PathBuf::from(&remote.url),
+        format,
+        interface: interface.to_string(),
+        remote: Some(remote),
+    }
+}
+
 /// Extract file path from -drive file= argument
 fn extract_drive_file(line: &str) -> Option<String> {
     if let Some(idx) = line.find("file=") {
@@ -475,18 +636,19 @@ fn resolve_path(path: &str, vm_dir: &Path) -> PathBuf {
     }
 }
 
+/// The `format=` of a drive line, if it has one
+fn declared_format(line: &str) -> Option<DiskFormat> {
+    let idx = line.find("format=")?;
+    let name: String = line[idx + 7..].chars().take_while(|c| c.is_alphanumeric()).collect();
+    Some(if name.is_empty() { DiskFormat::Raw } else { DiskFormat::from_qemu_name(&name) })
+}
+
 /// Detect disk format using qemu-img info, falling back to extension-based guessing
 fn guess_disk_format(path: &PathBuf) -> DiskFormat {
     // First, try to detect the actual format using qemu-img info
     if path.exists() {
         if let Some(format_str) = qemu_img::detect_disk_format(path) {
-            return match format_str.to_lowercase().as_str() {
-                "qcow2" => DiskFormat::Qcow2,
-                "raw" => DiskFormat::Raw,
-                "vmdk" => DiskFormat::Vmdk,
-                "vdi" => DiskFormat::Vdi,
-                other => DiskFormat::Other(other.to_string()),
-            };
+            return DiskFormat::from_qemu_name(&format_str);
         }
     }
 
@@ -503,7 +665,8 @@ fn extract_network(content: &str) -> Option<NetworkConfig> {
     let mut has_network = false;
 
     for line in content.lines() {
-        if line.trim_start().starts_with('#') {
+        // Older scripts exported SMB shared folders through a second NIC
+        if line.trim_start().starts_with('#') || line.trim_start().starts_with("SHARED_FOLDERS_ARGS=") {
             continue;
         }
 
@@ -542,6 +705,18 @@ fn extract_network(content: &str) -> Option<NetworkConfig> {
             if line.contains("passt") {
                 config.backend = NetworkBackend::Passt;
                 config.user_net = false;
+            } else if line.contains("-netdev tap") {
+                config.user_net = false;
+                let tap = line
+                    .find("ifname=")
+                    .map(|idx| {
+                        line[idx + 7..]
+                            .chars()
+                            .take_while(|c| c.is_alphanumeric() || *c == '-' || *c == '_' || *c == '.')
+                            .collect::<String>()
+                    })
+                    .unwrap_or_default();
+                config.backend = NetworkBackend::Tap(tap);
             } else if line.contains("bridge") {
                 config.user_net = false;
                 // Extract bridge name
@@ -560,6 +735,7 @@ fn extract_network(content: &str) -> Option<NetworkConfig> {
             } else if line.contains("user") {
                 config.user_net = true;
                 config.backend = NetworkBackend::User;
+                config.smb_share = line.contains(",smb=");
 
                 // Extract port forwards from hostfwd
                 config.port_forwards = extract_port_forwards(line);
@@ -721,6 +897,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_extract_audio_backend() {
+        let script = "qemu-system-x86_64 \\\n    -audiodev pipewire,id=audio0 \\\n    -device intel-hda -device hda-duplex,audiodev=audio0\n";
+        assert_eq!(extract_audio_backend(script).as_deref(), Some("pipewire"));
+        assert_eq!(extract_audio_backend("qemu-system-i386 -audio driver=alsa,model=sb16\n").as_deref(), Some("alsa"));
+        assert_eq!(extract_audio_backend("qemu-system-i386 -soundhw sb16\n"), None);
+    }
+
+    #[test]
+    fn test_extract_usb_controller() {
+        let script = "USB_PASSTHROUGH_ARGS=\"-device qemu-xhci,id=xhci\"\n\
+            qemu-system-x86_64 -device ich9-usb-ehci1,id=usb \\\n\
+                -device ich9-usb-uhci1,masterbus=usb.0,firstport=0 -device usb-tablet\n";
+        assert_eq!(extract_usb_controller(script).as_deref(), Some("ehci"));
+
+        // The passthrough section's own xHCI and the legacy -usb flag don't count
+        let legacy = "USB_PASSTHROUGH_ARGS=\"-usb -device qemu-xhci,id=xhci\"\nqemu-system-x86_64 -usb\n";
+        assert_eq!(extract_usb_controller(legacy), None);
+    }
+
     #[test]
     fn test_extract_vga() {
         assert_eq!(
@@ -733,6 +929,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_extract_resolution() {
+        let content = "qemu-system-i386 \\\n    -device VGA,edid=on,xres=800,yres=600 \\\n    -m 128M";
+        assert_eq!(extract_vga(content), Some(VgaType::Std));
+        assert_eq!(extract_resolution(content), Some("800x600".to_string()));
+        assert_eq!(extract_resolution("-vga std -m 512"), None);
+    }
+
     #[test]
     fn test_parse_hostfwd_segment() {
         let pf = parse_hostfwd_segment("tcp::2222-:22").unwrap();
@@ -778,6 +982,51 @@ mod tests {
         assert_eq!(config.bridge, Some("virbr0".to_string()));
     }
 
+    #[test]
+    fn test_extract_remote_disks() {
+        let content = "DISK=\"nbd://storage.lan:10809/xp\"\nqemu-system-i386 \\\n  -drive file=\"$DISK\",format=raw,if=ide,index=0,media=disk \\\n  -drive file=iscsi://san/iqn.2024-01.lan.san:vms/1,if=virtio";
+        let disks = extract_disks(content, Path::new("/vms/xp"));
+        assert_eq!(disks.len(), 2);
+        assert_eq!(disks[0].path, PathBuf::from("nbd://storage.lan:10809/xp"));
+        assert_eq!(disks[0].format, DiskFormat::Raw);
+        assert_eq!(disks[0].remote.as_ref().map(|r| r.protocol), Some(crate::vm::remote_disk::RemoteProtocol::Nbd));
+        assert_eq!(disks[1].interface, "virtio");
+        assert!(disks[1].remote.is_some());
+    }
+
+    #[test]
+    fn test_extract_disk_formats() {
+        let content = "qemu-system-x86_64 \\\n  -hda /vms/nt/nt4.vhd \\\n  -drive file=server.img,format=vhdx,if=ide \\\n  -drive file=old.qed,if=virtio";
+        let disks = extract_disks(content, Path::new("/vms/nt"));
+        let formats: Vec<&DiskFormat> = disks.iter().map(|d| &d.format).collect();
+        assert_eq!(formats, [&DiskFormat::Vpc, &DiskFormat::Vhdx, &DiskFormat::Qed]);
+        assert_eq!(disks[0].format.qemu_name(), "vpc");
+        assert!(!disks[1].format.supports_snapshots());
+        assert!(disks[2].format.supports_backing_file());
+        assert!(disks[1].format.limitation().is_some());
+        assert_eq!(DiskFormat::Qcow2.limitation(), None);
+    }
+
+    #[test]
+    fn test_extract_network_smb_share() {
+        let content = "qemu-system-i386 \\\n  -netdev user,id=net0,smb=\"$VM_DIR/share\" \\\n  -device rtl8139,netdev=net0";
+        let config = extract_network(content).unwrap();
+        assert!(config.smb_share);
+
+        // The shared folders SMB export doesn't count
+        let content = "SHARED_FOLDERS_ARGS=\"-netdev user,id=smbnet,smb=/srv -device rtl8139,netdev=smbnet\"\nqemu-system-i386 \\\n  -netdev bridge,id=net0,br=br0 \\\n  -device rtl8139,netdev=net0";
+        let config = extract_network(content).unwrap();
+        assert!(!config.smb_share);
+        assert_eq!(config.backend, NetworkBackend::Bridge("br0".to_string()));
+    }
+
+    #[test]
+    fn test_extract_network_tap() {
+        let content = "qemu-system-x86_64 \\\n  -netdev tap,id=net0,ifname=vmtap0,script=no,downscript=no \\\n  -device virtio-net-pci,netdev=net0";
+        let config = extract_network(content).unwrap();
+        assert_eq!(config.backend, NetworkBackend::Tap("vmtap0".to_string()));
+    }
+
     #[test]
     fn test_extract_network_user_with_portfwd() {
         let content = "qemu-system-x86_64 \\\n  -netdev user,id=net0,hostfwd=tcp::2222-:22 \\\n  -device e1000,netdev=net0";
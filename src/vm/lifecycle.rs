@@ -1,14 +1,24 @@
 use anyhow::{bail, Context, Result};
-use std::io::{BufRead, BufReader};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashMap;
 use std::path::Path;
 use std::process::{Command, Stdio};
-use std::sync::mpsc;
 use std::thread;
 use std::time::Duration;
 
 use super::discovery::DiscoveredVm;
 use super::qemu_config::BootMode;
-use crate::hardware::UsbVersion;
+use crate::hardware::{UsbDevice, UsbVersion};
+
+/// Start of a QEMU command line (the binary, optionally after `exec`)
+static RE_EMULATOR: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"^\s*(?:exec\s+)?(qemu-system-[\w-]+|"\$QEMU"|\$QEMU)"#).expect("Invalid regex: RE_EMULATOR")
+});
+
+static RE_USER_NETDEV: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"-netdev\s+user,id=[\w.-]+").expect("Invalid regex: RE_USER_NETDEV")
+});
 
 /// Result of a VM launch attempt
 #[derive(Debug)]
@@ -32,33 +42,126 @@ fn path_to_str(path: &Path) -> Result<&str> {
 pub struct LaunchOptions {
     pub boot_mode: BootMode,
     pub extra_args: Vec<String>,
-    pub usb_devices: Vec<UsbPassthrough>,
+    /// QEMU settings changed for this launch only
+    pub overrides: LaunchOverrides,
 }
 
-/// USB device for passthrough
-#[derive(Debug, Clone)]
+/// Settings changed for one launch, leaving launch.sh as it is
+///
+/// The arguments are added after the script's own, and QEMU goes by the
+/// last of a repeated option. They reach QEMU through a shell function
+/// named after the emulator, so the script must run the emulator by name
+/// (as generated scripts do) rather than through `exec` or a variable.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LaunchOverrides {
+    pub memory_mb: Option<u32>,
+    pub cpu_cores: Option<u32>,
+    /// QEMU `-display` backend
+    pub display: Option<String>,
+    pub extra_args: Vec<String>,
+}
+
+impl LaunchOverrides {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// The QEMU arguments making the changes
+    pub fn qemu_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(memory) = self.memory_mb {
+            args.extend(["-m".to_string(), format!("{}M", memory)]);
+        }
+        // -smp options merge, so the whole topology is given
+        if let Some(cores) = self.cpu_cores {
+            args.extend(["-smp".to_string(), format!("{0},sockets=1,cores={0},threads=1", cores)]);
+        }
+        if let Some(ref display) = self.display {
+            args.extend(["-display".to_string(), display.clone()]);
+        }
+        args.extend(self.extra_args.iter().cloned());
+        args
+    }
+}
+
+/// Whether a launch script starts `emulator` as a command of its own
+fn runs_emulator_by_name(script: &str, emulator: &str) -> bool {
+    script.lines().any(|line| {
+        line.trim_start()
+            .strip_prefix(emulator)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+    })
+}
+
+/// `bash -c` code running the launch script (`$0`) with `qemu_args` added
+/// to every start of `emulator`
+fn override_wrapper(emulator: &str, qemu_args: &[String]) -> String {
+    let args: Vec<String> = qemu_args.iter().map(|arg| shell_escape(arg)).collect();
+    format!("{0}() {{ command {0} \"$@\" {1}; }}; source \"$0\" \"$@\"", emulator, args.join(" "))
+}
+
+/// A per-VM USB passthrough rule, kept in the launch script
+///
+/// Rules match by vendor:product, optionally pinned to one physical port.
+/// Auto-attach rules are declared on the QEMU command line, so QEMU grabs
+/// the device at boot and again whenever it is plugged in. Other rules are
+/// hot-plugged once by vm-curator at launch, if the device is connected.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct UsbPassthrough {
     pub vendor_id: u16,
     pub product_id: u16,
     pub usb_version: UsbVersion,
+    /// Host bus and port path (e.g. 1 and "1.2") the device must be on
+    pub host_bus: Option<u8>,
+    pub host_port: Option<String>,
+    /// Attach whenever plugged in, not just at launch
+    pub auto_attach: bool,
+}
+
+impl From<&UsbDevice> for UsbPassthrough {
+    fn from(device: &UsbDevice) -> Self {
+        Self {
+            vendor_id: device.vendor_id,
+            product_id: device.product_id,
+            usb_version: device.usb_version,
+            host_bus: None,
+            host_port: None,
+            auto_attach: true,
+        }
+    }
 }
 
 impl UsbPassthrough {
-    /// Generate QEMU device arguments for this USB device
+    /// Whether a connected host device satisfies this rule
+    pub fn matches(&self, device: &UsbDevice) -> bool {
+        self.vendor_id == device.vendor_id
+            && self.product_id == device.product_id
+            && self.host_bus.is_none_or(|bus| bus == device.bus_num)
+            && self.host_port.as_deref().is_none_or(|port| port == device.port_path)
+    }
+
+    /// Pin the rule to the port a device is plugged into, or unpin it
+    pub fn set_pinned(&mut self, device: Option<&UsbDevice>) {
+        self.host_bus = device.map(|d| d.bus_num);
+        self.host_port = device.map(|d| d.port_path.clone());
+    }
+
+    pub fn is_pinned(&self) -> bool {
+        self.host_port.is_some()
+    }
+
+    /// `usb-host` properties for this rule
     /// If `bus` is provided, attach to that specific bus (e.g., "xhci.0" for USB 3.0)
-    pub fn to_qemu_args(&self, bus: Option<&str>) -> Vec<String> {
-        let device_spec = if let Some(bus_name) = bus {
-            format!(
-                "usb-host,bus={},vendorid=0x{:04x},productid=0x{:04x}",
-                bus_name, self.vendor_id, self.product_id
-            )
-        } else {
-            format!(
-                "usb-host,vendorid=0x{:04x},productid=0x{:04x}",
-                self.vendor_id, self.product_id
-            )
-        };
-        vec!["-device".to_string(), device_spec]
+    fn device_spec(&self, bus: Option<&str>) -> String {
+        let mut spec = String::from("usb-host");
+        if let Some(bus_name) = bus {
+            spec.push_str(&format!(",bus={}", bus_name));
+        }
+        spec.push_str(&format!(",vendorid=0x{:04x},productid=0x{:04x}", self.vendor_id, self.product_id));
+        if let (Some(host_bus), Some(port)) = (self.host_bus, &self.host_port) {
+            spec.push_str(&format!(",hostbus={},hostport={}", host_bus, port));
+        }
+        spec
     }
 
     /// Check if this device is USB 3.0 or higher
@@ -67,24 +170,108 @@ impl UsbPassthrough {
     }
 }
 
+/// Hot-plug the connected devices of a running VM's launch-only USB rules
+///
+/// Waits briefly for QEMU to open the QMP socket. Returns how many devices
+/// were attached.
+pub fn attach_launch_usb(vm: &DiscoveredVm) -> Result<usize> {
+    // This machine's USB devices cannot reach a VM on a remote host
+    if vm.remote.is_some() {
+        return Ok(0);
+    }
+    let rules: Vec<UsbPassthrough> = load_usb_passthrough(vm)
+        .into_iter()
+        .filter(|r| !r.auto_attach)
+        .collect();
+    if rules.is_empty() {
+        return Ok(0);
+    }
+
+    let socket = super::qmp::socket_path(&vm.path);
+    for _ in 0..20 {
+        if socket.exists() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    let mut attached = 0;
+    for device in crate::hardware::enumerate_usb_devices()? {
+        if rules.iter().any(|r| r.matches(&device)) {
+            super::qmp::attach_usb(&vm.path, device.bus_num, device.dev_num)?;
+            attached += 1;
+        }
+    }
+    Ok(attached)
+}
+
 /// Launch a VM and monitor for immediate errors
 ///
-/// This function spawns the VM process and monitors stderr for a brief period
-/// to catch any immediate startup errors (like missing files, invalid arguments, etc.)
-/// If the process exits with an error within the monitoring window, we capture it.
+/// This function spawns the VM process, with its output going to the VM's
+/// log, and watches it for a brief period to catch any immediate startup
+/// errors (like missing files, invalid arguments, etc.). If the process
+/// exits within the monitoring window, the errors are read from the log.
 pub fn launch_vm_with_error_check(vm: &DiscoveredVm, options: &LaunchOptions) -> LaunchResult {
+    if let Some(ref host) = vm.remote {
+        return super::remote_host::launch(host, vm, options);
+    }
     let vm_name = vm.display_name();
 
-    let mut cmd = Command::new("bash");
-    cmd.current_dir(&vm.path);
+    // Held until the launch script starts, then handed to it
+    let lock = match super::instance_lock::acquire(&vm.path) {
+        Ok(lock) => lock,
+        Err(e) => {
+            return LaunchResult {
+                success: false,
+                error: Some(format!("{:#}", e)),
+                vm_name,
+            };
+        }
+    };
 
     let mut args = vec![vm.launch_script.to_string_lossy().to_string()];
 
-    match &options.boot_mode {
-        BootMode::Normal => {}
-        BootMode::Install => {
-            args.push("--install".to_string());
+    // A suspended VM carries on where it was; until the OS is installed,
+    // a normal boot is an install boot
+    let saved_state = super::suspend::has_saved_state(&vm.path);
+    let boot_mode = match options.boot_mode {
+        BootMode::Normal if saved_state => &BootMode::Resume,
+        BootMode::Normal if vm.config.install_pending => &BootMode::Install,
+        ref boot_mode => boot_mode,
+    };
+    let mut incoming = None;
+    match boot_mode {
+        BootMode::Resume => {
+            if !saved_state {
+                return LaunchResult {
+                    success: false,
+                    error: Some("The VM has no saved state to resume".to_string()),
+                    vm_name,
+                };
+            }
+            if super::direct_launch::is_needed() {
+                return LaunchResult {
+                    success: false,
+                    error: Some("Resuming a saved state needs bash".to_string()),
+                    vm_name,
+                };
+            }
+            incoming = Some(super::suspend::incoming_uri());
         }
+        // The disks move on from a saved state, so any other boot would
+        // lose it; that takes discarding it first
+        _ if saved_state => {
+            return LaunchResult {
+                success: false,
+                error: Some(format!("{} is suspended; resume it or discard its saved state first", vm_name)),
+                vm_name,
+            };
+        }
+        _ => {}
+    }
+    match boot_mode {
+        BootMode::Normal | BootMode::Resume => {}
+        BootMode::Install => args.push("--install".to_string()),
         BootMode::Cdrom(iso_path) => {
             // Validate ISO path exists before attempting to launch
             if !iso_path.exists() {
@@ -111,75 +298,102 @@ pub fn launch_vm_with_error_check(vm: &DiscoveredVm, options: &LaunchOptions) ->
 
     args.extend(options.extra_args.clone());
 
-    // Add USB passthrough arguments
-    if !options.usb_devices.is_empty() {
-        args.push("-usb".to_string());
-
-        // Check if any USB 3.0 devices are present
-        let has_usb3 = options.usb_devices.iter().any(|d| d.is_usb3());
-
-        // Add xHCI controller if USB 3.0 devices are present
-        if has_usb3 {
-            args.push("-device".to_string());
-            args.push("qemu-xhci,id=xhci".to_string());
-        }
-
-        // Add each USB device, attaching USB 3.0 devices to xHCI controller
-        for usb in &options.usb_devices {
-            let bus = if usb.is_usb3() { Some("xhci.0") } else { None };
-            args.extend(usb.to_qemu_args(bus));
+    // Without bash, QEMU is started with the command read out of the script
+    let direct = super::direct_launch::is_needed();
+    if direct {
+        let command = super::direct_launch::qemu_command(&vm.config.raw_script, &vm.path, boot_mode)
+            .and_then(|command| {
+                if !options.extra_args.is_empty() {
+                    bail!("launch.sh options ({}) need bash", options.extra_args.join(" "));
+                }
+                Ok(command)
+            });
+        match command {
+            Ok(command) => args = command,
+            Err(e) => {
+                return LaunchResult {
+                    success: false,
+                    error: Some(format!("{:#}", e)),
+                    vm_name,
+                };
+            }
         }
     }
 
-    cmd.args(&args);
-
-    // Capture stderr to detect errors, but let stdout go to null
-    cmd.stdin(Stdio::null())
-        .stdout(Stdio::null())
-        .stderr(Stdio::piped());
-
-    let mut child = match cmd.spawn() {
-        Ok(c) => c,
+    // The VM's own variables and wrapper command, if it has any
+    let environment = super::launch_env::load(&vm.path);
+    let program = if direct { args.remove(0) } else { "bash".to_string() };
+    let mut cmd = match environment.command(&program) {
+        Ok(cmd) => cmd,
         Err(e) => {
             return LaunchResult {
                 success: false,
-                error: Some(format!("Failed to start VM process: {}", e)),
+                error: Some(format!("{:#}", e)),
                 vm_name,
             };
         }
     };
+    cmd.current_dir(&vm.path);
+    if let Some(ref uri) = incoming {
+        cmd.env("VM_CURATOR_INCOMING", uri);
+    }
+    // The launch's -m, for sections sized by guest RAM (virtiofs)
+    if let Some(memory) = options.overrides.memory_mb.or(Some(vm.config.memory_mb)).filter(|&mb| mb > 0) {
+        cmd.env("VM_CURATOR_MEMORY", format!("{}M", memory));
+    }
 
-    // Take stderr handle for monitoring
-    let stderr = match child.stderr.take() {
-        Some(s) => s,
-        None => {
+    let mut logged = args.clone();
+    if direct {
+        // QEMU takes the last of a repeated option
+        args.extend(options.overrides.qemu_args());
+        logged = std::iter::once(program).chain(args.iter().cloned()).collect();
+    } else if !options.overrides.is_empty() {
+        let emulator = vm.config.emulator.command();
+        if !runs_emulator_by_name(&vm.config.raw_script, emulator) {
             return LaunchResult {
-                success: true,
-                error: None,
+                success: false,
+                error: Some(format!("launch.sh doesn't run {} by name, so the launch options can't be added", emulator)),
                 vm_name,
             };
         }
-    };
-
-    // Create a channel to receive error output
-    let (tx, rx) = mpsc::channel();
+        let qemu_args = options.overrides.qemu_args();
+        cmd.arg("-c").arg(override_wrapper(emulator, &qemu_args));
+        logged.push(format!("(this launch only: {})", qemu_args.join(" ")));
+    }
+    if !environment.is_empty() {
+        logged.push(format!("(with {})", environment.describe()));
+    }
+    cmd.args(&args);
 
-    // Spawn a thread to read ALL stderr output
-    thread::spawn(move || {
-        let reader = BufReader::new(stderr);
-        let mut all_lines = Vec::new();
+    tracing::info!(vm = %vm.id, args = ?logged, "launching");
 
-        for line in reader.lines() {
-            if let Ok(line) = line {
-                // Capture all stderr output - we'll filter later if needed
-                if !line.trim().is_empty() {
-                    all_lines.push(line);
-                }
-            }
+    // Output goes to the VM's log, which is also where startup errors are read from
+    let log = super::qemu_log::start(&vm.path, &logged).ok();
+    cmd.stdin(Stdio::null());
+    match log.as_ref().map(|log| (log.try_clone(), log.try_clone())) {
+        Some((Ok(stdout), Ok(stderr))) => {
+            cmd.stdout(stdout).stderr(stderr);
+        }
+        _ => {
+            cmd.stdout(Stdio::null()).stderr(Stdio::null());
         }
+    }
+    drop(log);
 
-        let _ = tx.send(all_lines);
-    });
+    let mut child = match cmd.spawn() {
+        Ok(c) => c,
+        Err(e) => {
+            return LaunchResult {
+                success: false,
+                error: Some(match environment.wrapper.split_whitespace().next() {
+                    Some(wrapper) => format!("Failed to start VM process through {}: {}", wrapper, e),
+                    None => format!("Failed to start VM process: {}", e),
+                }),
+                vm_name,
+            };
+        }
+    };
+    lock.hand_over(child.id());
 
     // Wait for QEMU to either start successfully or fail
     // QEMU typically fails fast if there's a configuration error
@@ -190,40 +404,14 @@ pub fn launch_vm_with_error_check(vm: &DiscoveredVm, options: &LaunchOptions) ->
         Ok(Some(status)) => {
             // Process exited - this usually means an error for QEMU
             // (successful QEMU keeps running until the VM shuts down)
-
-            // Wait a bit more for stderr to be fully captured
-            thread::sleep(Duration::from_millis(300));
-
-            // Try to get error output
-            let stderr_lines = rx.recv_timeout(Duration::from_millis(500))
-                .unwrap_or_default();
-
-            // Filter for error-related lines for display
-            let error_lines: Vec<&String> = stderr_lines.iter()
-                .filter(|line| {
-                    let lower = line.to_lowercase();
-                    lower.contains("error")
-                        || lower.contains("failed")
-                        || lower.contains("cannot")
-                        || lower.contains("unable")
-                        || lower.contains("not found")
-                        || lower.contains("no such")
-                        || lower.contains("invalid")
-                        || lower.contains("is not a valid")
-                        || lower.contains("could not")
-                        || lower.contains("qemu-system")
-                        || lower.contains("permission denied")
-                })
-                .collect();
-
-            let error_msg = if !error_lines.is_empty() {
-                error_lines.iter().map(|s| s.as_str()).collect::<Vec<_>>().join("\n")
-            } else if !stderr_lines.is_empty() {
-                // Show all stderr if no specific errors found
-                stderr_lines.join("\n")
-            } else {
+            let lines = super::qemu_log::read_lines(&super::qemu_log::log_path(&vm.path));
+            let error_lines = super::qemu_log::error_lines(&lines);
+            let error_msg = if error_lines.is_empty() {
                 format!("VM process exited with code: {}", status)
+            } else {
+                error_lines.join("\n")
             };
+            tracing::warn!(vm = %vm.id, %status, error = %error_msg, "launch failed");
 
             return LaunchResult {
                 success: false,
@@ -233,6 +421,11 @@ pub fn launch_vm_with_error_check(vm: &DiscoveredVm, options: &LaunchOptions) ->
         }
         Ok(None) => {
             // Process still running - this is the expected success case
+            // (a history that can't be written shouldn't fail the launch)
+            let _ = super::launch_history::record_launch(&vm.path, chrono::Utc::now().timestamp());
+            if incoming.is_some() {
+                super::suspend::finish_resume(vm.path.clone());
+            }
         }
         Err(e) => {
             return LaunchResult {
@@ -323,6 +516,7 @@ pub fn reset_vm(vm: &DiscoveredVm) -> Result<()> {
 
 /// Delete a VM (move to trash or permanently delete)
 pub fn delete_vm(vm: &DiscoveredVm, permanent: bool) -> Result<()> {
+    tracing::info!(vm = %vm.id, permanent, "deleting VM");
     if permanent {
         std::fs::remove_dir_all(&vm.path)
             .context("Failed to delete VM directory")?;
@@ -339,20 +533,7 @@ pub fn delete_vm(vm: &DiscoveredVm, permanent: bool) -> Result<()> {
                 let trash_dir = vm.path.parent()
                     .unwrap_or(Path::new("."))
                     .join(".trash");
-                std::fs::create_dir_all(&trash_dir)
-                    .context("Failed to create trash directory")?;
-
-                // Find a unique name in trash (append timestamp if needed)
-                let mut trash_path = trash_dir.join(&vm.id);
-                if trash_path.exists() {
-                    let timestamp = std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .map(|d| d.as_secs())
-                        .unwrap_or(0);
-                    trash_path = trash_dir.join(format!("{}-{}", vm.id, timestamp));
-                }
-
-                std::fs::rename(&vm.path, &trash_path)
+                move_to_trash_dir(&vm.path, &trash_dir, &vm.id)
                     .context("Failed to move VM to trash")?;
             }
         }
@@ -361,46 +542,60 @@ pub fn delete_vm(vm: &DiscoveredVm, permanent: bool) -> Result<()> {
     Ok(())
 }
 
+/// Move a file or folder into a `.trash` directory as `name`
+pub fn move_to_trash_dir(path: &Path, trash_dir: &Path, name: &str) -> Result<()> {
+    std::fs::create_dir_all(trash_dir)
+        .context("Failed to create trash directory")?;
+
+    // Find a unique name in trash (append timestamp if needed)
+    let mut trash_path = trash_dir.join(name);
+    if trash_path.exists() {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        trash_path = trash_dir.join(format!("{}-{}", name, timestamp));
+    }
+
+    std::fs::rename(path, &trash_path)?;
+    Ok(())
+}
+
 /// Rename a VM by updating its display name in vm-curator.toml
+///
+/// Other keys (os_profile, uuid, mac) are kept as they are.
 pub fn rename_vm(vm: &DiscoveredVm, new_name: &str) -> Result<()> {
     let metadata_path = vm.path.join("vm-curator.toml");
+    let name_line = format!("display_name = \"{}\"", new_name.replace('"', "\\\""));
 
-    // Read existing metadata or create new
-    let os_profile = if metadata_path.exists() {
-        // Parse existing file to preserve os_profile
-        let content = std::fs::read_to_string(&metadata_path)
+    let content = if metadata_path.exists() {
+        let existing = std::fs::read_to_string(&metadata_path)
             .context("Failed to read VM metadata")?;
 
-        // Simple extraction of os_profile
-        content.lines()
-            .find(|line| line.trim().starts_with("os_profile"))
-            .and_then(|line| {
-                let parts: Vec<&str> = line.splitn(2, '=').collect();
-                if parts.len() == 2 {
-                    let value = parts[1].trim();
-                    if value.starts_with('"') && value.ends_with('"') && value.len() >= 2 {
-                        Some(value[1..value.len()-1].to_string())
-                    } else {
-                        None
-                    }
-                } else {
-                    None
+        let mut replaced = false;
+        let mut content = String::new();
+        for line in existing.lines() {
+            if line.trim_start().starts_with("display_name") {
+                if !replaced {
+                    content.push_str(&name_line);
+                    content.push('\n');
+                    replaced = true;
                 }
-            })
+            } else {
+                content.push_str(line);
+                content.push('\n');
+            }
+        }
+        if !replaced {
+            content.push_str(&name_line);
+            content.push('\n');
+        }
+        content
     } else {
         // No existing file, use VM's id as fallback profile
-        Some(vm.id.clone())
+        format!("# VM Curator metadata\n\n{}\nos_profile = \"{}\"\n", name_line, vm.id)
     };
 
-    // Write updated metadata
-    let mut content = String::new();
-    content.push_str("# VM Curator metadata\n\n");
-    content.push_str(&format!("display_name = \"{}\"\n", new_name.replace('"', "\\\"")));
-
-    if let Some(profile) = os_profile {
-        content.push_str(&format!("os_profile = \"{}\"\n", profile));
-    }
-
     std::fs::write(&metadata_path, content)
         .context("Failed to write VM metadata")?;
 
@@ -413,13 +608,15 @@ pub struct QemuProcess {
     pub cmdline: String,
     /// The working directory of the process (from /proc/<pid>/cwd)
     pub cwd: Option<std::path::PathBuf>,
+    /// Remote host the process runs on (None for this machine)
+    pub host: Option<String>,
 }
 
-/// Detect all running QEMU processes.
+/// Detect all running QEMU (and 86Box/DOSBox-X) processes.
 /// Returns process info including the working directory read from /proc.
 pub fn detect_qemu_processes() -> Vec<QemuProcess> {
     let output = match Command::new("pgrep")
-        .args(["-a", "qemu-system"])
+        .args(["-a", &super::backend::process_pattern()])
         .output()
     {
         Ok(o) => o,
@@ -443,15 +640,48 @@ pub fn detect_qemu_processes() -> Vec<QemuProcess> {
                 let cmdline = line[space_pos + 1..].to_string();
                 // Read the process working directory from /proc
                 let cwd = std::fs::read_link(format!("/proc/{}/cwd", pid)).ok();
-                processes.push(QemuProcess { pid, cmdline, cwd });
+                processes.push(QemuProcess { pid, cmdline, cwd, host: None });
             }
         }
     }
     processes
 }
 
+/// Match QEMU processes against known VMs using the process working directory.
+///
+/// Launch scripts run QEMU from the VM's directory, so /proc/<pid>/cwd
+/// reliably identifies which VM a process belongs to — unlike disk filenames
+/// which are often generic (e.g., "disk.qcow2"). Returns vm_id -> PID.
+pub fn match_running_vms(vms: &[DiscoveredVm], processes: &[QemuProcess]) -> HashMap<String, u32> {
+    let mut result = HashMap::new();
+    for vm in vms {
+        let host = vm.remote.as_ref().map(|h| h.name.as_str());
+        for proc in processes.iter().filter(|p| p.host.as_deref() == host) {
+            if let Some(ref cwd) = proc.cwd {
+                // cwd is available — use it as the authoritative match
+                if cwd == &vm.path {
+                    result.insert(vm.id.clone(), proc.pid);
+                    break;
+                }
+            } else {
+                // No cwd available (permissions?) — fall back to full disk path in cmdline
+                if let Some(disk) = vm.config.primary_disk() {
+                    if let Some(disk_path_str) = disk.path.to_str() {
+                        if !disk_path_str.is_empty() && proc.cmdline.contains(disk_path_str) {
+                            result.insert(vm.id.clone(), proc.pid);
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    result
+}
+
 /// Send SIGTERM to a QEMU process (triggers ACPI shutdown in modern QEMU).
 pub fn stop_vm_by_pid(pid: u32) -> Result<()> {
+    tracing::info!(pid, "stopping VM");
     let status = Command::new("kill")
         .arg(pid.to_string())
         .status()
@@ -464,6 +694,7 @@ pub fn stop_vm_by_pid(pid: u32) -> Result<()> {
 
 /// Force-kill a QEMU process with SIGKILL.
 pub fn force_stop_vm(pid: u32) -> Result<()> {
+    tracing::warn!(pid, "force stopping VM");
     let status = Command::new("kill")
         .args(["-9", &pid.to_string()])
         .status()
@@ -479,6 +710,8 @@ pub fn force_stop_vm(pid: u32) -> Result<()> {
 // USB Passthrough configuration markers
 const USB_MARKER_START: &str = "# >>> USB Passthrough (managed by vm-curator) >>>";
 const USB_MARKER_END: &str = "# <<< USB Passthrough <<<";
+/// Comment line holding a launch-only rule inside the USB section
+const USB_LAUNCH_ONLY_PREFIX: &str = "# launch-only: ";
 
 /// Save USB passthrough configuration to the VM's launch.sh
 pub fn save_usb_passthrough(vm: &DiscoveredVm, devices: &[UsbPassthrough]) -> Result<()> {
@@ -489,8 +722,9 @@ pub fn save_usb_passthrough(vm: &DiscoveredVm, devices: &[UsbPassthrough]) -> Re
     // Remove existing USB passthrough section if present
     let content = remove_usb_section(&content);
 
-    // Generate new USB passthrough section
-    let usb_section = generate_usb_section(devices);
+    // Generate new USB passthrough section for the VM's USB controller
+    let controller = super::launch_parser::extract_usb_controller(&content);
+    let usb_section = generate_usb_section(devices, controller.as_deref());
 
     // Find where to insert the USB section
     // We want to add it before the final qemu-system command or at the end
@@ -515,42 +749,10 @@ pub fn load_usb_passthrough(vm: &DiscoveredVm) -> Vec<UsbPassthrough> {
 }
 
 fn remove_usb_section(content: &str) -> String {
-    let mut result = String::new();
-    let mut in_usb_section = false;
-
-    for line in content.lines() {
-        if line.trim() == USB_MARKER_START {
-            in_usb_section = true;
-            continue;
-        }
-        if line.trim() == USB_MARKER_END {
-            in_usb_section = false;
-            continue;
-        }
-        if !in_usb_section {
-            // Also remove any $USB_PASSTHROUGH_ARGS references from qemu command lines
-            let cleaned_line = remove_usb_args_from_line(line);
-            result.push_str(&cleaned_line);
-            result.push('\n');
-        }
-    }
-
-    // Remove trailing empty lines that may have accumulated
-    while result.ends_with("\n\n") {
-        result.pop();
-    }
-
-    result
-}
-
-fn remove_usb_args_from_line(line: &str) -> String {
-    // Remove $USB_PASSTHROUGH_ARGS from a line (with various formats)
-    line.replace(" $USB_PASSTHROUGH_ARGS", "")
-        .replace("$USB_PASSTHROUGH_ARGS ", "")
-        .replace("$USB_PASSTHROUGH_ARGS", "")
+    remove_args_section(content, USB_MARKER_START, USB_MARKER_END, "$USB_PASSTHROUGH_ARGS")
 }
 
-fn generate_usb_section(devices: &[UsbPassthrough]) -> String {
+fn generate_usb_section(devices: &[UsbPassthrough], controller: Option<&str>) -> String {
     if devices.is_empty() {
         return String::new();
     }
@@ -558,42 +760,138 @@ fn generate_usb_section(devices: &[UsbPassthrough]) -> String {
     let mut section = String::new();
     section.push_str(USB_MARKER_START);
     section.push('\n');
-    section.push_str("USB_PASSTHROUGH_ARGS=\"-usb");
 
-    // Check if any USB 3.0 devices are present
-    let has_usb3 = devices.iter().any(|d| d.is_usb3());
+    // Launch-only rules are hot-plugged by vm-curator, so QEMU never sees them
+    let usb3_bus = usb3_bus(controller);
+    for device in devices.iter().filter(|d| !d.auto_attach) {
+        let bus = if device.is_usb3() { Some(usb3_bus) } else { None };
+        section.push_str(&format!("{}{}\n", USB_LAUNCH_ONLY_PREFIX, device.device_spec(bus)));
+    }
+
+    section.push_str(&format!(
+        "USB_PASSTHROUGH_ARGS=\"{}\"\n",
+        usb_passthrough_args(devices, controller, false).join(" ")
+    ));
+    section.push_str(USB_MARKER_END);
+    section.push('\n');
+
+    section
+}
+
+/// Bus USB 3.0 devices attach to: the VM's own XHCI controller, or the
+/// `xhci` controller the passthrough arguments add
+fn usb3_bus(controller: Option<&str>) -> &'static str {
+    if controller == Some("xhci") { "usb.0" } else { "xhci.0" }
+}
+
+/// QEMU arguments passing USB devices through, respecting the VM's controller
+///
+/// VMs without an explicit controller (see `create::usb_controller_args`)
+/// get the machine default via `-usb`. USB 3.0 devices go on XHCI, which is
+/// added unless the VM already has it. Launch-only rules are left out unless
+/// `include_launch_only` is set.
+pub fn usb_passthrough_args(devices: &[UsbPassthrough], controller: Option<&str>, include_launch_only: bool) -> Vec<String> {
+    let mut args = Vec::new();
+    let has_controller = matches!(controller, Some("uhci" | "ehci" | "xhci"));
+    if !has_controller {
+        args.push("-usb".to_string());
+    }
 
     // Add xHCI controller if USB 3.0 devices are present
-    if has_usb3 {
-        section.push_str(" -device qemu-xhci,id=xhci");
+    if devices.iter().any(|d| d.is_usb3()) && controller != Some("xhci") {
+        args.push("-device qemu-xhci,id=xhci".to_string());
     }
 
     // Add each USB device, attaching USB 3.0 devices to xHCI controller
-    for device in devices {
-        if device.is_usb3() {
-            section.push_str(&format!(
-                " -device usb-host,bus=xhci.0,vendorid=0x{:04x},productid=0x{:04x}",
-                device.vendor_id, device.product_id
-            ));
-        } else {
-            section.push_str(&format!(
-                " -device usb-host,vendorid=0x{:04x},productid=0x{:04x}",
-                device.vendor_id, device.product_id
-            ));
-        }
+    for device in devices.iter().filter(|d| d.auto_attach || include_launch_only) {
+        let bus = if device.is_usb3() { Some(usb3_bus(controller)) } else { None };
+        args.push(format!("-device {}", device.device_spec(bus)));
     }
 
-    section.push_str("\"\n");
-    section.push_str(USB_MARKER_END);
-    section.push('\n');
-
-    section
+    args
 }
 
 fn insert_usb_section(content: &str, usb_section: &str) -> String {
     insert_args_section(content, usb_section, "$USB_PASSTHROUGH_ARGS")
 }
 
+/// Insert an argument right after the QEMU binary of every command in a script
+///
+/// Multi-line commands get the argument as its own continuation line, indented
+/// like the line after it. Comments and variable assignments are skipped.
+pub fn insert_base_arg(content: &str, arg: &str) -> Result<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut result = String::new();
+    let mut inserted = false;
+
+    for (i, line) in lines.iter().enumerate() {
+        let Some(m) = RE_EMULATOR.captures(line).and_then(|caps| caps.get(1)) else {
+            result.push_str(line);
+            result.push('\n');
+            continue;
+        };
+        inserted = true;
+
+        if line.trim_end().ends_with('\\') {
+            let indent: String = lines
+                .get(i + 1)
+                .map(|next| next.chars().take_while(|c| c.is_whitespace()).collect())
+                .unwrap_or_else(|| "    ".to_string());
+            result.push_str(line);
+            result.push('\n');
+            result.push_str(&format!("{}{} \\\n", indent, arg));
+        } else {
+            result.push_str(&line[..m.end()]);
+            result.push(' ');
+            result.push_str(arg);
+            result.push_str(&line[m.end()..]);
+            result.push('\n');
+        }
+    }
+
+    if !inserted {
+        bail!("No QEMU command found in launch.sh");
+    }
+    Ok(result)
+}
+
+/// Remove a section added by `insert_args_section`: drop the lines between the
+/// markers and every `$VAR_NAME` reference to it (none if `var_ref` is empty).
+pub fn remove_args_section(content: &str, marker_start: &str, marker_end: &str, var_ref: &str) -> String {
+    let mut result = String::new();
+    let mut in_section = false;
+
+    for line in content.lines() {
+        if line.trim() == marker_start {
+            in_section = true;
+            continue;
+        }
+        if line.trim() == marker_end {
+            in_section = false;
+            continue;
+        }
+        if !in_section {
+            if var_ref.is_empty() {
+                result.push_str(line);
+            } else {
+                let cleaned_line = line
+                    .replace(&format!(" {}", var_ref), "")
+                    .replace(&format!("{} ", var_ref), "")
+                    .replace(var_ref, "");
+                result.push_str(&cleaned_line);
+            }
+            result.push('\n');
+        }
+    }
+
+    // Remove trailing empty lines that may have accumulated
+    while result.ends_with("\n\n") {
+        result.pop();
+    }
+
+    result
+}
+
 /// Generic function to insert a variable-definition section into a launch script
 /// and append `$VAR_NAME` to all QEMU command endings.
 ///
@@ -711,30 +1009,17 @@ fn parse_usb_section(content: &str) -> Vec<UsbPassthrough> {
             in_usb_section = false;
             continue;
         }
-        if in_usb_section && line.contains("USB_PASSTHROUGH_ARGS=") {
+        if !in_usb_section {
+            continue;
+        }
+        if let Some(spec) = line.trim().strip_prefix(USB_LAUNCH_ONLY_PREFIX) {
+            devices.extend(parse_usb_host_spec(spec, false));
+        } else if line.contains("USB_PASSTHROUGH_ARGS=") {
             // Parse the USB args line
             // Format: USB_PASSTHROUGH_ARGS="-usb -device usb-host,vendorid=0x1234,productid=0x5678 ..."
             // Or with xHCI: USB_PASSTHROUGH_ARGS="-usb -device qemu-xhci,id=xhci -device usb-host,bus=xhci.0,vendorid=0x1234,productid=0x5678 ..."
             for part in line.split("-device usb-host,") {
-                if part.contains("vendorid=") && part.contains("productid=") {
-                    if let (Some(vid), Some(pid)) = (
-                        extract_hex_value(part, "vendorid="),
-                        extract_hex_value(part, "productid="),
-                    ) {
-                        // Detect USB version from bus assignment
-                        // If attached to xhci.0, it's USB 3.0; otherwise default to USB 2.0
-                        let usb_version = if part.contains("bus=xhci") {
-                            UsbVersion::Usb3
-                        } else {
-                            UsbVersion::Usb2
-                        };
-                        devices.push(UsbPassthrough {
-                            vendor_id: vid,
-                            product_id: pid,
-                            usb_version,
-                        });
-                    }
-                }
+                devices.extend(parse_usb_host_spec(part, true));
             }
         }
     }
@@ -742,6 +1027,35 @@ fn parse_usb_section(content: &str) -> Vec<UsbPassthrough> {
     devices
 }
 
+/// Parse the properties of one `usb-host` device into a rule
+fn parse_usb_host_spec(spec: &str, auto_attach: bool) -> Option<UsbPassthrough> {
+    let vendor_id = extract_hex_value(spec, "vendorid=")?;
+    let product_id = extract_hex_value(spec, "productid=")?;
+
+    let property = |name: &str| -> Option<&str> {
+        spec.split([',', ' ', '"'])
+            .find_map(|p| p.strip_prefix(name))
+            .filter(|v| !v.is_empty())
+    };
+
+    // Detect USB version from bus assignment
+    // Only USB 3.0 devices are put on a bus (xhci.0 or the VM's own usb.0)
+    let usb_version = if property("bus=").is_some() {
+        UsbVersion::Usb3
+    } else {
+        UsbVersion::Usb2
+    };
+
+    Some(UsbPassthrough {
+        vendor_id,
+        product_id,
+        usb_version,
+        host_bus: property("hostbus=").and_then(|v| v.parse().ok()),
+        host_port: property("hostport=").map(String::from),
+        auto_attach,
+    })
+}
+
 fn extract_hex_value(s: &str, prefix: &str) -> Option<u16> {
     let start = s.find(prefix)? + prefix.len();
     let rest = &s[start..];
@@ -762,13 +1076,65 @@ fn extract_hex_value(s: &str, prefix: &str) -> Option<u16> {
 const SHARED_FOLDERS_MARKER_START: &str = "# >>> Shared Folders (managed by vm-curator) >>>";
 const SHARED_FOLDERS_MARKER_END: &str = "# <<< Shared Folders <<<";
 
-/// A shared folder configuration for virtio-9p host-to-guest file sharing
+/// Added to the VM's user netdev to reach the SMB shared folders server
+pub const SMB_SHARES_NETDEV: &str = "\"$SHARED_FOLDERS_NETDEV\"";
+
+/// A shared folder configuration for host-to-guest file sharing
 #[derive(Debug, Clone)]
 pub struct SharedFolder {
     pub host_path: String,
     pub mount_tag: String,
 }
 
+/// How shared folders are exported to the guest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SharedFolderMethod {
+    /// virtio-9p (Linux, BSD, most Unix guests)
+    #[default]
+    NineP,
+    /// virtiofs via virtiofsd (modern Linux, Windows with WinFSP)
+    Virtiofs,
+    /// An SMB server on the VM's user network, like SLIRP's `smb=` (retro Windows)
+    Smb,
+}
+
+impl SharedFolderMethod {
+    /// Identifier stored in launch.sh
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NineP => "9p",
+            Self::Virtiofs => "virtiofs",
+            Self::Smb => "smb",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "virtiofs" => Self::Virtiofs,
+            "smb" => Self::Smb,
+            _ => Self::NineP,
+        }
+    }
+
+    /// Display label
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::NineP => "virtio-9p",
+            Self::Virtiofs => "virtiofs",
+            Self::Smb => "SMB (SLIRP)",
+        }
+    }
+
+    /// Cycle to the next method
+    pub fn next(&self) -> Self {
+        match self {
+            Self::NineP => Self::Virtiofs,
+            Self::Virtiofs => Self::Smb,
+            Self::Smb => Self::NineP,
+        }
+    }
+}
+
 /// Escape a string for safe use in shell scripts
 fn shell_escape(s: &str) -> String {
     if s.chars()
@@ -781,76 +1147,68 @@ fn shell_escape(s: &str) -> String {
 }
 
 /// Save shared folders configuration to the VM's launch.sh
-pub fn save_shared_folders(vm: &DiscoveredVm, folders: &[SharedFolder]) -> Result<()> {
+pub fn save_shared_folders(
+    vm: &DiscoveredVm,
+    folders: &[SharedFolder],
+    method: SharedFolderMethod,
+) -> Result<()> {
     let script_path = &vm.launch_script;
     let content =
         std::fs::read_to_string(script_path).context("Failed to read launch.sh")?;
 
     // Remove existing shared folders section if present
     let content = remove_shared_folders_section(&content);
+    let smb = method == SharedFolderMethod::Smb && !folders.is_empty();
+    if smb && content.lines().any(|line| line.contains("-netdev user") && line.contains(",smb=")) {
+        bail!("The SMB share from Network settings is on; turn it off and add its folder here instead");
+    }
 
-    // Determine device name based on architecture
-    let device_name = if vm
-        .config
-        .emulator
-        .command()
-        .contains("aarch64")
-        || vm.config.emulator.command().contains("arm")
-    {
-        "virtio-9p-device"
-    } else {
-        "virtio-9p-pci"
-    };
+    // Determine device type based on architecture
+    let is_arm = vm.config.emulator.command().contains("aarch64")
+        || vm.config.emulator.command().contains("arm");
 
     // Generate new shared folders section
-    let section = generate_shared_folders_section(folders, device_name);
+    let section = match method {
+        SharedFolderMethod::NineP => {
+            let device_name = if is_arm { "virtio-9p-device" } else { "virtio-9p-pci" };
+            generate_shared_folders_section(folders, device_name)
+        }
+        SharedFolderMethod::Virtiofs => {
+            let device_name = if is_arm { "vhost-user-fs-device" } else { "vhost-user-fs-pci" };
+            generate_virtiofs_section(folders, device_name, vm.config.memory_mb)
+        }
+        SharedFolderMethod::Smb => generate_smb_section(folders),
+    };
 
     // Insert into script
-    let new_content = insert_shared_folders_section(&content, &section);
+    let mut new_content = insert_shared_folders_section(&content, &section);
+    if smb {
+        new_content = attach_smb_shares(&new_content)?;
+    }
 
     std::fs::write(script_path, new_content).context("Failed to write launch.sh")?;
 
     Ok(())
 }
 
-/// Load shared folders configuration from the VM's launch.sh
-pub fn load_shared_folders(vm: &DiscoveredVm) -> Vec<SharedFolder> {
+/// Load shared folders configuration (and export method) from the VM's launch.sh
+pub fn load_shared_folders(vm: &DiscoveredVm) -> (Vec<SharedFolder>, SharedFolderMethod) {
     let content = match std::fs::read_to_string(&vm.launch_script) {
         Ok(c) => c,
-        Err(_) => return Vec::new(),
+        Err(_) => return (Vec::new(), SharedFolderMethod::default()),
     };
-    parse_shared_folders_section(&content)
+    let method = parse_shared_folders_method(&content);
+    let folders = match method {
+        SharedFolderMethod::NineP => parse_shared_folders_section(&content),
+        SharedFolderMethod::Virtiofs => parse_virtiofs_section(&content),
+        SharedFolderMethod::Smb => parse_smb_section(&content),
+    };
+    (folders, method)
 }
 
 fn remove_shared_folders_section(content: &str) -> String {
-    let mut result = String::new();
-    let mut in_section = false;
-
-    for line in content.lines() {
-        if line.trim() == SHARED_FOLDERS_MARKER_START {
-            in_section = true;
-            continue;
-        }
-        if line.trim() == SHARED_FOLDERS_MARKER_END {
-            in_section = false;
-            continue;
-        }
-        if !in_section {
-            let cleaned_line = line
-                .replace(" $SHARED_FOLDERS_ARGS", "")
-                .replace("$SHARED_FOLDERS_ARGS ", "")
-                .replace("$SHARED_FOLDERS_ARGS", "");
-            result.push_str(&cleaned_line);
-            result.push('\n');
-        }
-    }
-
-    // Remove trailing empty lines that may have accumulated
-    while result.ends_with("\n\n") {
-        result.pop();
-    }
-
-    result
+    let content = content.replace(SMB_SHARES_NETDEV, "");
+    remove_args_section(&content, SHARED_FOLDERS_MARKER_START, SHARED_FOLDERS_MARKER_END, "$SHARED_FOLDERS_ARGS")
 }
 
 fn generate_shared_folders_section(folders: &[SharedFolder], device_name: &str) -> String {
@@ -883,6 +1241,145 @@ fn generate_shared_folders_section(folders: &[SharedFolder], device_name: &str)
     section
 }
 
+/// virtiofs: one virtiofsd per folder plus shared guest memory
+fn generate_virtiofs_section(folders: &[SharedFolder], device_name: &str, memory_mb: u32) -> String {
+    if folders.is_empty() {
+        return String::new();
+    }
+
+    let mut section = String::new();
+    section.push_str(SHARED_FOLDERS_MARKER_START);
+    section.push('\n');
+    section.push_str(&format!(
+        "SHARED_FOLDERS_METHOD=\"{}\"\n",
+        SharedFolderMethod::Virtiofs.as_str()
+    ));
+    section.push_str("VIRTIOFSD=\"$(command -v virtiofsd || echo /usr/libexec/virtiofsd)\"\n");
+    section.push_str("[[ -x \"$VIRTIOFSD\" ]] || VIRTIOFSD=\"/usr/lib/qemu/virtiofsd\"\n");
+    // virtiofsd must be listening before QEMU starts; it exits when QEMU disconnects
+    section.push_str("if [[ \"$1\" != \"--help\" && \"$1\" != \"-h\" ]]; then\n");
+    for (i, folder) in folders.iter().enumerate() {
+        section.push_str(&format!(
+            "    \"$VIRTIOFSD\" --shared-dir={} --socket-path=\"$VM_DIR/virtiofs{}.sock\" &\n",
+            shell_escape(&folder.host_path),
+            i
+        ));
+    }
+    section.push_str("    sleep 1\n");
+    section.push_str("fi\n");
+
+    // vhost-user needs all of guest RAM in a shareable memory backend, so
+    // it is sized by the launch's -m, which vm-curator passes in
+    section.push_str(&format!(
+        "SHARED_FOLDERS_ARGS=\"-object memory-backend-memfd,id=mem,size=${{VM_CURATOR_MEMORY:-{}M}},share=on -numa node,memdev=mem",
+        memory_mb
+    ));
+    for (i, folder) in folders.iter().enumerate() {
+        section.push_str(&format!(
+            " -chardev socket,id=vfs{},path=$VM_DIR/virtiofs{}.sock -device {},chardev=vfs{},tag={}",
+            i, i, device_name, i, folder.mount_tag
+        ));
+    }
+    section.push_str("\"\n");
+    section.push_str(SHARED_FOLDERS_MARKER_END);
+    section.push('\n');
+
+    section
+}
+
+/// SMB: an smbd exporting each folder as a share named by its tag
+///
+/// SLIRP's `smb=` exports one directory, so the server is started the way
+/// QEMU starts its own, once per guest connection to \\10.0.2.4, but with
+/// a configuration of ours. It is forwarded on the VM's user netdev (see
+/// `attach_smb_shares`) rather than on a NIC of its own.
+fn generate_smb_section(folders: &[SharedFolder]) -> String {
+    if folders.is_empty() {
+        return String::new();
+    }
+
+    let mut section = String::new();
+    section.push_str(SHARED_FOLDERS_MARKER_START);
+    section.push('\n');
+    section.push_str(&format!(
+        "SHARED_FOLDERS_METHOD=\"{}\"\n",
+        SharedFolderMethod::Smb.as_str()
+    ));
+    section.push_str("if [[ \"$1\" != \"--help\" && \"$1\" != \"-h\" ]]; then\n");
+    // QEMU splits the command on spaces, so the server's directory has none
+    section.push_str("    SMB_DIR=\"/tmp/vm-curator-smb-$(id -u)-$(basename \"$VM_DIR\" | tr -c 'A-Za-z0-9_-' _)\"\n");
+    section.push_str("    mkdir -p -m 700 \"$SMB_DIR\"\n");
+    section.push_str("    cat > \"$SMB_DIR/smb.conf\" <<SMBCONF\n");
+    section.push_str("[global]\n");
+    for setting in ["private dir", "pid directory", "lock directory", "state directory", "cache directory"] {
+        section.push_str(&format!("{}=$SMB_DIR\n", setting));
+    }
+    section.push_str("ncalrpc dir=$SMB_DIR/ncalrpc\n");
+    section.push_str("log file=$SMB_DIR/log.smbd\n");
+    section.push_str("smb passwd file=$SMB_DIR/smbpasswd\n");
+    section.push_str("interfaces=127.0.0.1\n");
+    section.push_str("bind interfaces only=yes\n");
+    section.push_str("security = user\n");
+    section.push_str("map to guest = Bad User\n");
+    // The guests this is for speak SMB1 only
+    section.push_str("server min protocol = NT1\n");
+    section.push_str("load printers = no\n");
+    section.push_str("printing = bsd\n");
+    section.push_str("disable spoolss = yes\n");
+    section.push_str("usershare max shares = 0\n");
+    for folder in folders {
+        section.push_str(&format!("[{}]\n", folder.mount_tag));
+        section.push_str(&format!("path={}\n", heredoc_escape(&folder.host_path)));
+        section.push_str("read only=no\n");
+        section.push_str("guest ok=yes\n");
+        section.push_str("force user=$(id -un)\n");
+    }
+    section.push_str("SMBCONF\n");
+    section.push_str("    SMBD=\"$(command -v smbd || echo /usr/sbin/smbd) -l $SMB_DIR -s $SMB_DIR/smb.conf\"\n");
+    section.push_str("    SHARED_FOLDERS_NETDEV=\",guestfwd=tcp:10.0.2.4:139-cmd:$SMBD,guestfwd=tcp:10.0.2.4:445-cmd:$SMBD\"\n");
+    section.push_str("fi\n");
+    // Everything is on the netdev; nothing is added to the command line
+    section.push_str("SHARED_FOLDERS_ARGS=\"\"\n");
+    section.push_str(SHARED_FOLDERS_MARKER_END);
+    section.push('\n');
+
+    section
+}
+
+/// Escape a string for an unquoted here-document
+fn heredoc_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('$', "\\$").replace('`', "\\`")
+}
+
+/// Reverse `heredoc_escape`
+fn heredoc_unescape(s: &str) -> String {
+    let mut result = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some(next @ ('\\' | '$' | '`'))) => {
+                result.push(next);
+                chars.next();
+            }
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+/// Forward the SMB shared folders server on the VM's user netdevs
+pub(crate) fn attach_smb_shares(content: &str) -> Result<String> {
+    if content.contains(SMB_SHARES_NETDEV) {
+        return Ok(content.to_string());
+    }
+    if !RE_USER_NETDEV.is_match(content) {
+        bail!("Sharing folders over SMB needs the user (SLIRP) network backend; set it in Network settings");
+    }
+    Ok(RE_USER_NETDEV
+        .replace_all(content, |caps: &regex::Captures| format!("{}{}", &caps[0], SMB_SHARES_NETDEV))
+        .into_owned())
+}
+
 fn insert_shared_folders_section(content: &str, section: &str) -> String {
     insert_args_section(content, section, "$SHARED_FOLDERS_ARGS")
 }
@@ -923,9 +1420,81 @@ fn parse_shared_folders_section(content: &str) -> Vec<SharedFolder> {
     folders
 }
 
+/// Read the export method from a shared folders section (9p when absent)
+fn parse_shared_folders_method(content: &str) -> SharedFolderMethod {
+    content
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("SHARED_FOLDERS_METHOD="))
+        .map(|value| SharedFolderMethod::from_str(value.trim_matches('"')))
+        .unwrap_or_default()
+}
+
+fn parse_virtiofs_section(content: &str) -> Vec<SharedFolder> {
+    let mut paths = Vec::new();
+    let mut tags = Vec::new();
+    let mut in_section = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed == SHARED_FOLDERS_MARKER_START {
+            in_section = true;
+        } else if trimmed == SHARED_FOLDERS_MARKER_END {
+            in_section = false;
+        } else if in_section && trimmed.contains("--shared-dir=") {
+            if let Some(path) = extract_quoted_value(trimmed, "--shared-dir=") {
+                paths.push(path);
+            }
+        } else if in_section && trimmed.starts_with("SHARED_FOLDERS_ARGS=") {
+            for part in trimmed.split(" -device ").skip(1) {
+                if let Some(tag) = extract_simple_value(part, "tag=") {
+                    tags.push(tag);
+                }
+            }
+        }
+    }
+
+    paths
+        .into_iter()
+        .zip(tags)
+        .map(|(host_path, mount_tag)| SharedFolder { host_path, mount_tag })
+        .collect()
+}
+
+fn parse_smb_section(content: &str) -> Vec<SharedFolder> {
+    let mut folders = Vec::new();
+    let mut share: Option<String> = None;
+    let mut in_section = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed == SHARED_FOLDERS_MARKER_START {
+            in_section = true;
+        } else if trimmed == SHARED_FOLDERS_MARKER_END {
+            in_section = false;
+        } else if !in_section {
+            continue;
+        } else if let Some(name) = trimmed.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            share = (name != "global").then(|| name.to_string());
+        } else if let (Some(path), Some(name)) = (line.strip_prefix("path="), &share) {
+            folders.push(SharedFolder { host_path: heredoc_unescape(path), mount_tag: name.clone() });
+        } else if trimmed.starts_with("SHARED_FOLDERS_ARGS=") {
+            // Older scripts exported the first folder through a second NIC's smb=
+            if let Some(host_path) = extract_quoted_value(trimmed, "smb=") {
+                // SLIRP always exports the share as \\10.0.2.4\qemu
+                folders.push(SharedFolder { host_path, mount_tag: "qemu".to_string() });
+            }
+        }
+    }
+    folders
+}
+
 /// Extract a path value from a -fsdev argument, handling shell quoting
 fn extract_path_value(s: &str) -> Option<String> {
-    let start = s.find("path=")? + 5;
+    extract_quoted_value(s, "path=")
+}
+
+/// Extract a `prefix=value` where the value may be single-quoted
+fn extract_quoted_value(s: &str, prefix: &str) -> Option<String> {
+    let start = s.find(prefix)? + prefix.len();
     let rest = &s[start..];
 
     if rest.starts_with('\'') {
@@ -1139,6 +1708,19 @@ mod tests {
         assert!(result.contains("qemu-system-x86_64"));
     }
 
+    #[test]
+    fn test_remove_args_section_round_trip() {
+        let script = "#!/bin/bash\n\ncase \"$1\" in\n    --install)\n        qemu-system-x86_64 -m 2048 -cdrom a.iso\n        ;;\n    \"\")\n        qemu-system-x86_64 -m 2048 \\\n            -hda disk.qcow2\n        ;;\nesac\n";
+        let section = "# >>> Test (managed by vm-curator) >>>\nTEST_ARGS=\"-no-hpet\"\n# <<< Test <<<\n";
+        let added = insert_args_section(script, section, "$TEST_ARGS");
+        assert_eq!(added.matches("$TEST_ARGS").count(), 2);
+        let removed = remove_args_section(&added, "# >>> Test (managed by vm-curator) >>>", "# <<< Test <<<", "$TEST_ARGS");
+        assert!(!removed.contains("TEST_ARGS"));
+        assert!(!removed.contains(">>> Test"));
+        assert!(removed.contains("-cdrom a.iso\n"));
+        assert!(removed.contains("-hda disk.qcow2\n"));
+    }
+
     #[test]
     fn test_insert_shared_folders_section_simple() {
         let content = "#!/bin/bash\nqemu-system-x86_64 -m 2048\n";
@@ -1191,6 +1773,91 @@ mod tests {
         assert_eq!(parsed[1].mount_tag, "host_my_pictures");
     }
 
+    #[test]
+    fn test_virtiofs_section_roundtrip() {
+        let folders = vec![
+            SharedFolder {
+                host_path: "/home/user/Documents".to_string(),
+                mount_tag: "host_documents".to_string(),
+            },
+            SharedFolder {
+                host_path: "/home/user/My Pictures".to_string(),
+                mount_tag: "host_my_pictures".to_string(),
+            },
+        ];
+        let section = generate_virtiofs_section(&folders, "vhost-user-fs-pci", 4096);
+        assert!(section.contains("memory-backend-memfd,id=mem,size=${VM_CURATOR_MEMORY:-4096M},share=on"));
+        assert!(section.contains("--socket-path=\"$VM_DIR/virtiofs1.sock\""));
+        assert!(section.contains("vhost-user-fs-pci,chardev=vfs0,tag=host_documents"));
+        assert_eq!(parse_shared_folders_method(&section), SharedFolderMethod::Virtiofs);
+
+        let parsed = parse_virtiofs_section(&section);
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[1].host_path, "/home/user/My Pictures");
+        assert_eq!(parsed[1].mount_tag, "host_my_pictures");
+    }
+
+    #[test]
+    fn test_smb_section_exports_every_folder() {
+        let folders = vec![
+            SharedFolder {
+                host_path: "/srv/retro".to_string(),
+                mount_tag: "host_retro".to_string(),
+            },
+            SharedFolder {
+                host_path: "/srv/$HOME's `games`".to_string(),
+                mount_tag: "host_games".to_string(),
+            },
+        ];
+        let section = generate_smb_section(&folders);
+        assert!(section.contains("SHARED_FOLDERS_ARGS=\"\"\n"));
+        assert!(!section.contains("-netdev"));
+        assert_eq!(parse_shared_folders_method(&section), SharedFolderMethod::Smb);
+
+        let parsed = parse_smb_section(&section);
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].host_path, "/srv/retro");
+        assert_eq!(parsed[1].host_path, "/srv/$HOME's `games`");
+        assert_eq!(parsed[1].mount_tag, "host_games");
+
+        // The script writes one share per folder and forwards smbd on the netdev
+        let dir = std::env::temp_dir().join(format!("vm-curator-smb-section-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let output = Command::new("bash")
+            .arg("-c")
+            .arg(format!("{}echo \"$SHARED_FOLDERS_NETDEV\"; cat \"$SMB_DIR/smb.conf\"; rm -r \"$SMB_DIR\"", section))
+            .env("VM_DIR", &dir)
+            .output()
+            .unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.starts_with(",guestfwd=tcp:10.0.2.4:139-cmd:"));
+        assert!(stdout.contains(",guestfwd=tcp:10.0.2.4:445-cmd:"));
+        assert!(stdout.contains("[host_retro]\npath=/srv/retro\n"));
+        assert!(stdout.contains("[host_games]\npath=/srv/$HOME's `games`\n"));
+
+        // Scripts from before exported the first folder over a second NIC
+        let old = format!(
+            "{}\nSHARED_FOLDERS_METHOD=\"smb\"\nSHARED_FOLDERS_ARGS=\"-netdev user,id=smbnet,smb=/srv/retro -device ne2k_pci,netdev=smbnet\"\n{}\n",
+            SHARED_FOLDERS_MARKER_START, SHARED_FOLDERS_MARKER_END
+        );
+        let parsed = parse_smb_section(&old);
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].host_path, "/srv/retro");
+        assert_eq!(parsed[0].mount_tag, "qemu");
+    }
+
+    #[test]
+    fn test_attach_smb_shares() {
+        let script = "qemu-system-i386 \\\n    -netdev user,id=net0,hostfwd=tcp::2222-:22 \\\n    -device ne2k_pci,netdev=net0\n";
+        let attached = attach_smb_shares(script).unwrap();
+        assert!(attached.contains("-netdev user,id=net0\"$SHARED_FOLDERS_NETDEV\",hostfwd=tcp::2222-:22 \\"));
+        assert_eq!(attach_smb_shares(&attached).unwrap(), attached);
+        assert_eq!(remove_shared_folders_section(&attached), script);
+
+        assert!(attach_smb_shares("qemu-system-i386 -netdev bridge,id=net0,br=br0\n").is_err());
+    }
+
     #[test]
     fn test_shell_escape_safe() {
         assert_eq!(shell_escape("/home/user/docs"), "/home/user/docs");
@@ -1226,4 +1893,83 @@ mod tests {
         assert_eq!(pids[1], 67890);
         assert!(cmdlines[1].contains("test.img"));
     }
+
+    #[test]
+    fn test_usb_rules_round_trip() {
+        let rules = vec![
+            UsbPassthrough {
+                vendor_id: 0x046d,
+                product_id: 0xc52b,
+                usb_version: UsbVersion::Usb2,
+                host_bus: None,
+                host_port: None,
+                auto_attach: true,
+            },
+            UsbPassthrough {
+                vendor_id: 0x0781,
+                product_id: 0x5581,
+                usb_version: UsbVersion::Usb3,
+                host_bus: Some(2),
+                host_port: Some("1.4".to_string()),
+                auto_attach: false,
+            },
+        ];
+
+        let section = generate_usb_section(&rules, None);
+        assert!(section.contains(
+            "# launch-only: usb-host,bus=xhci.0,vendorid=0x0781,productid=0x5581,hostbus=2,hostport=1.4"
+        ));
+        assert!(section.contains(
+            "USB_PASSTHROUGH_ARGS=\"-usb -device qemu-xhci,id=xhci -device usb-host,vendorid=0x046d,productid=0xc52b\""
+        ));
+
+        let mut parsed = parse_usb_section(&section);
+        parsed.sort_by_key(|r| r.vendor_id);
+        assert_eq!(parsed, rules);
+
+        // A VM with its own XHCI controller needs neither -usb nor a second XHCI
+        let section = generate_usb_section(&rules, Some("xhci"));
+        assert!(section.contains("USB_PASSTHROUGH_ARGS=\"-device usb-host,vendorid=0x046d,productid=0xc52b\""));
+        assert!(section.contains("# launch-only: usb-host,bus=usb.0,vendorid=0x0781"));
+        let mut parsed = parse_usb_section(&section);
+        parsed.sort_by_key(|r| r.vendor_id);
+        assert_eq!(parsed, rules);
+    }
+
+    #[test]
+    fn test_launch_overrides() {
+        let overrides = LaunchOverrides {
+            memory_mb: Some(2048),
+            cpu_cores: Some(4),
+            display: None,
+            extra_args: vec!["-name".to_string(), "Test Run".to_string()],
+        };
+        assert_eq!(
+            overrides.qemu_args(),
+            ["-m", "2048M", "-smp", "4,sockets=1,cores=4,threads=1", "-name", "Test Run"]
+        );
+        assert!(LaunchOverrides::default().is_empty());
+
+        assert!(runs_emulator_by_name("case \"$1\" in\n    \"\")\n        qemu-system-i386 -m 64M\n", "qemu-system-i386"));
+        assert!(!runs_emulator_by_name("exec qemu-system-i386 -m 64M\n", "qemu-system-i386"));
+        assert!(!runs_emulator_by_name("qemu-system-i386-wrapper -m 64M\n", "qemu-system-i386"));
+
+        // The script's own command gets the arguments, with the script's arguments passed through
+        let dir = std::env::temp_dir().join(format!("vm-curator-overrides-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("launch.sh");
+        std::fs::write(&script, "echo started \"$1\"\n").unwrap();
+        let output = Command::new("bash")
+            .arg("-c")
+            .arg(override_wrapper("echo", &overrides.qemu_args()))
+            .arg(&script)
+            .arg("--install")
+            .output()
+            .unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "started --install -m 2048M -smp 4,sockets=1,cores=4,threads=1 -name Test Run\n"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
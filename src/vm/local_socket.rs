@@ -0,0 +1,61 @@
+//! Sockets to a running QEMU
+//!
+//! QMP, the guest agent and the single-instance check talk to QEMU over
+//! Unix sockets in the VM directory. Rust's standard library has no Unix
+//! sockets on Windows, so there connecting fails with an error saying so,
+//! and the features built on these sockets report it instead of working.
+
+#[cfg(unix)]
+pub use std::os::unix::net::UnixStream as LocalStream;
+
+#[cfg(not(unix))]
+pub use self::unsupported::LocalStream;
+
+#[cfg(not(unix))]
+mod unsupported {
+    use std::convert::Infallible;
+    use std::io::{self, Read, Write};
+    use std::path::Path;
+    use std::time::Duration;
+
+    /// Stands in for a Unix socket stream; never connects
+    #[derive(Debug)]
+    pub struct LocalStream(Infallible);
+
+    impl LocalStream {
+        pub fn connect<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("{}: Unix sockets are not supported on this host", path.as_ref().display()),
+            ))
+        }
+
+        pub fn try_clone(&self) -> io::Result<Self> {
+            match self.0 {}
+        }
+
+        pub fn set_read_timeout(&self, _timeout: Option<Duration>) -> io::Result<()> {
+            match self.0 {}
+        }
+
+        pub fn set_write_timeout(&self, _timeout: Option<Duration>) -> io::Result<()> {
+            match self.0 {}
+        }
+    }
+
+    impl Read for LocalStream {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            match self.0 {}
+        }
+    }
+
+    impl Write for LocalStream {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            match self.0 {}
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            match self.0 {}
+        }
+    }
+}
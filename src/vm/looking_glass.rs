@@ -0,0 +1,179 @@
+//! Looking Glass shared memory
+//!
+//! Looking Glass copies frames from a passed-through GPU to a window on the
+//! host through an IVSHMEM device. A managed section of launch.sh declares
+//! the shared memory, backed either by a file in /dev/shm or by the KVMFR
+//! kernel module (`/dev/kvmfr0`), which avoids an extra copy. The size must
+//! hold two frames at the guest's resolution.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::vm::lifecycle::{insert_args_section, remove_args_section};
+
+// Looking Glass section markers
+const LG_MARKER_START: &str = "# >>> Looking Glass (managed by vm-curator) >>>";
+const LG_MARKER_END: &str = "# <<< Looking Glass <<<";
+
+/// Shared memory sizes offered (IVSHMEM needs a power of two)
+pub const IVSHMEM_SIZES_MB: &[u32] = &[16, 32, 64, 128, 256, 512];
+
+/// Shared memory file used without KVMFR
+pub const SHM_PATH: &str = "/dev/shm/looking-glass";
+
+/// Device created by the KVMFR module
+pub const KVMFR_PATH: &str = "/dev/kvmfr0";
+
+/// IVSHMEM settings stored in launch.sh
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LookingGlassSettings {
+    pub size_mb: u32,
+    /// Back the memory with /dev/kvmfr0 instead of /dev/shm
+    pub kvmfr: bool,
+}
+
+impl LookingGlassSettings {
+    /// Path the client opens with `-f`
+    pub fn mem_path(&self) -> &'static str {
+        if self.kvmfr { KVMFR_PATH } else { SHM_PATH }
+    }
+
+    /// Step the size through `IVSHMEM_SIZES_MB`
+    pub fn cycle_size(&mut self, delta: i32) {
+        let current_idx = IVSHMEM_SIZES_MB.iter().position(|&s| s == self.size_mb).unwrap_or(2);
+        let new_idx = (current_idx as i32 + delta).rem_euclid(IVSHMEM_SIZES_MB.len() as i32) as usize;
+        self.size_mb = IVSHMEM_SIZES_MB[new_idx];
+    }
+}
+
+/// Smallest size that fits two 32-bit frames plus 10 MB of headroom,
+/// rounded up to a power of two (e.g. 1920x1080 -> 32, 2560x1440 -> 64)
+pub fn recommended_size_mb(width: u32, height: u32) -> u32 {
+    let frames_mb = (width as u64 * height as u64 * 4 * 2).div_ceil(1024 * 1024) as u32;
+    (frames_mb + 10).next_power_of_two()
+}
+
+/// Write (or with None, remove) the Looking Glass section of a launch script
+pub fn set_looking_glass(script_path: &Path, settings: Option<&LookingGlassSettings>) -> Result<()> {
+    let content = std::fs::read_to_string(script_path).context("Failed to read launch.sh")?;
+    let content = remove_lg_section(&content);
+
+    let new_content = match settings {
+        Some(settings) => insert_args_section(&content, &generate_lg_section(settings), "$LOOKING_GLASS_ARGS"),
+        None => content,
+    };
+
+    std::fs::write(script_path, new_content).context("Failed to write launch.sh")?;
+    Ok(())
+}
+
+/// Read the Looking Glass settings from a launch script
+pub fn parse_looking_glass(content: &str) -> Option<LookingGlassSettings> {
+    let mut in_section = false;
+    let mut size_mb = None;
+    let mut kvmfr = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed == LG_MARKER_START {
+            in_section = true;
+        } else if trimmed == LG_MARKER_END {
+            break;
+        } else if in_section {
+            if let Some(value) = trimmed.strip_prefix("LG_SIZE_MB=") {
+                size_mb = value.trim_matches('"').parse().ok();
+            } else if let Some(value) = trimmed.strip_prefix("LG_BACKEND=") {
+                kvmfr = value.trim_matches('"') == "kvmfr";
+            }
+        }
+    }
+    size_mb.map(|size_mb| LookingGlassSettings { size_mb, kvmfr })
+}
+
+/// Host and guest steps for getting a picture, one line each
+pub fn setup_instructions(settings: &LookingGlassSettings) -> Vec<String> {
+    let mut steps = vec!["Host:".to_string()];
+    if settings.kvmfr {
+        steps.push("  Install the kvmfr module (looking-glass-module-dkms), then:".to_string());
+        steps.push(format!("  sudo modprobe kvmfr static_size_mb={}", settings.size_mb));
+        steps.push(format!(
+            "  echo 'options kvmfr static_size_mb={}' | sudo tee /etc/modprobe.d/kvmfr.conf",
+            settings.size_mb
+        ));
+        steps.push(
+            "  echo 'SUBSYSTEM==\"kvmfr\", OWNER=\"'$USER'\", MODE=\"0600\"' | sudo tee /etc/udev/rules.d/99-kvmfr.rules"
+                .to_string(),
+        );
+    } else {
+        steps.push(format!("  QEMU creates {} when the VM starts", SHM_PATH));
+    }
+    steps.push(String::new());
+    steps.push("Guest (Windows):".to_string());
+    steps.push("  Install the IVSHMEM driver (virtio-win, \"PCI standard RAM Controller\")".to_string());
+    steps.push("  Install the Looking Glass host application (same version as the client)".to_string());
+    steps.push("  Keep a monitor or dummy plug on the passed-through GPU".to_string());
+    steps.push(String::new());
+    steps.push("Client:".to_string());
+    steps.push(format!("  looking-glass-client -f {}", settings.mem_path()));
+    steps.push("  Keyboard/mouse go over SPICE; add -s to disable it if you pass USB input".to_string());
+    steps
+}
+
+fn generate_lg_section(settings: &LookingGlassSettings) -> String {
+    let mut section = String::new();
+    section.push_str(LG_MARKER_START);
+    section.push('\n');
+    section.push_str(&format!("LG_SIZE_MB=\"{}\"\n", settings.size_mb));
+    section.push_str(&format!("LG_BACKEND=\"{}\"\n", if settings.kvmfr { "kvmfr" } else { "shm" }));
+    section.push_str(&format!("LG_MEM_PATH=\"{}\"\n", settings.mem_path()));
+    if settings.kvmfr {
+        // Fall back to /dev/shm rather than failing to start
+        section.push_str(&format!("if [[ ! -e \"{}\" ]]; then\n", KVMFR_PATH));
+        section.push_str(&format!(
+            "    echo \"Looking Glass: {} missing (sudo modprobe kvmfr static_size_mb=$LG_SIZE_MB), using {}\" >&2\n",
+            KVMFR_PATH, SHM_PATH
+        ));
+        section.push_str(&format!("    LG_MEM_PATH=\"{}\"\n", SHM_PATH));
+        section.push_str("fi\n");
+    }
+    section.push_str("LOOKING_GLASS_ARGS=\"-object memory-backend-file,id=looking-glass,share=on,mem-path=$LG_MEM_PATH,size=${LG_SIZE_MB}M -device ivshmem-plain,memdev=looking-glass\"\n");
+    section.push_str(LG_MARKER_END);
+    section.push('\n');
+    section
+}
+
+fn remove_lg_section(content: &str) -> String {
+    remove_args_section(content, LG_MARKER_START, LG_MARKER_END, "$LOOKING_GLASS_ARGS")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_looking_glass_section_round_trip() {
+        let script = "VM_DIR=\"/vms/w\"\nqemu-system-x86_64 \\\n    -m 16G\n";
+        let settings = LookingGlassSettings { size_mb: 64, kvmfr: true };
+        let with_lg = insert_args_section(script, &generate_lg_section(&settings), "$LOOKING_GLASS_ARGS");
+
+        assert_eq!(parse_looking_glass(&with_lg), Some(settings));
+        assert!(with_lg.contains("-device ivshmem-plain,memdev=looking-glass"));
+        assert!(with_lg.contains("-m 16G $LOOKING_GLASS_ARGS"));
+        assert!(setup_instructions(&settings).iter().any(|l| l.contains("static_size_mb=64")));
+
+        let restored = remove_lg_section(&with_lg);
+        assert_eq!(parse_looking_glass(&restored), None);
+        assert!(!restored.contains("LOOKING_GLASS"));
+    }
+
+    #[test]
+    fn test_recommended_size_mb() {
+        assert_eq!(recommended_size_mb(1920, 1080), 32);
+        assert_eq!(recommended_size_mb(2560, 1440), 64);
+        assert_eq!(recommended_size_mb(3840, 2160), 128);
+
+        let mut settings = LookingGlassSettings { size_mb: 512, kvmfr: false };
+        settings.cycle_size(1);
+        assert_eq!(settings.size_mb, 16);
+    }
+}
@@ -0,0 +1,282 @@
+//! Classic Mac firmware: ROM files and OpenBIOS settings
+//!
+//! The Quadra 800 (`q800`) board has no free firmware and boots from a dump
+//! of the real Mac ROM. Dumps are kept in one ROM folder and identified by
+//! the checksum Apple stored in their first four bytes. PowerPC boards
+//! (`mac99`, `g3beige`) boot QEMU's built-in OpenBIOS, configured with
+//! `-prom-env` variables, unless another firmware file is chosen.
+//!
+//! A managed section of launch.sh holds the ROM path, the `-prom-env`
+//! settings and, on the Quadra, a PRAM file that keeps the startup disk and
+//! control panel settings between boots.
+
+use anyhow::{bail, Context, Result};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::vm::lifecycle::{insert_args_section, remove_args_section};
+
+// Mac firmware section markers
+const MAC_MARKER_START: &str = "# >>> MAC FIRMWARE (managed by vm-curator) >>>";
+const MAC_MARKER_END: &str = "# <<< MAC FIRMWARE <<<";
+
+/// ROM file name the section points at when no Quadra ROM has been added yet
+pub const DEFAULT_QUADRA_ROM: &str = "Quadra800.rom";
+
+/// Known Mac ROMs: checksum, models, and the QEMU machine that can boot it
+const KNOWN_ROMS: &[(u32, &str, Option<&str>)] = &[
+    (0x28BA61CE, "Macintosh 128K/512K", None),
+    (0x4D1F8172, "Macintosh Plus", None),
+    (0xB2E362A8, "Macintosh SE", None),
+    (0x97221136, "Macintosh IIx/IIcx/SE/30", None),
+    (0x368CADFE, "Macintosh IIci", None),
+    (0x420DBFF3, "Quadra 700/900, PowerBook 140/170", None),
+    (0xF1ACAD13, "Quadra 610/650/800, Centris 610/650", Some("q800")),
+    (0x3DC27823, "Quadra/LC 630", None),
+    (0x9FEB69B3, "Power Macintosh 6100/7100/8100", None),
+    (0x960E4BE9, "Power Macintosh 7500/8500/9500", None),
+    (0x79D68D63, "Power Macintosh G3 (Beige)", None),
+];
+
+/// OpenBIOS variables offered for PowerPC Macs: value and description
+pub const PROM_ENV_OPTIONS: &[(&str, &str)] = &[
+    ("auto-boot?=true", "Boot straight away instead of stopping at the OpenBIOS prompt"),
+    ("vga-ndrv?=true", "Load QEMU's video driver in Mac OS 9 (more resolutions and depths)"),
+    ("boot-args=-v", "Verbose Mac OS X startup (shows where a boot hangs)"),
+];
+
+/// A ROM file in the ROM folder
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MacRom {
+    pub path: PathBuf,
+    /// Checksum from the ROM header
+    pub checksum: u32,
+    /// Models that shipped with this ROM, if known
+    pub model: Option<&'static str>,
+    /// QEMU machine type that boots it
+    pub machine: Option<&'static str>,
+}
+
+impl MacRom {
+    pub fn file_name(&self) -> String {
+        self.path.file_name().unwrap_or_default().to_string_lossy().to_string()
+    }
+}
+
+/// Firmware settings for one VM
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MacFirmware {
+    /// ROM or firmware image passed with `-bios` (None for OpenBIOS)
+    pub rom: Option<PathBuf>,
+    /// OpenBIOS `-prom-env` variables
+    pub prom_env: Vec<String>,
+    /// Persist the Quadra's PRAM in the VM folder
+    pub pram: bool,
+}
+
+impl MacFirmware {
+    fn is_empty(&self) -> bool {
+        self.rom.is_none() && self.prom_env.is_empty() && !self.pram
+    }
+}
+
+/// The folder ROM dumps are kept in (~/.config/vm-curator/roms)
+pub fn rom_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from(".config"))
+        .join("vm-curator")
+        .join("roms")
+}
+
+/// Whether a machine type cannot boot without a ROM dump
+pub fn needs_rom(machine: &str) -> bool {
+    machine == "q800"
+}
+
+/// Identify a ROM dump by its header checksum
+pub fn identify_rom(path: &Path) -> Result<MacRom> {
+    let mut header = [0u8; 4];
+    std::fs::File::open(path)
+        .and_then(|mut f| f.read_exact(&mut header))
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let checksum = u32::from_be_bytes(header);
+    let known = KNOWN_ROMS.iter().find(|(sum, _, _)| *sum == checksum);
+
+    Ok(MacRom {
+        path: path.to_path_buf(),
+        checksum,
+        model: known.map(|(_, model, _)| *model),
+        machine: known.and_then(|(_, _, machine)| *machine),
+    })
+}
+
+/// ROM files in the ROM folder, those QEMU can boot first
+pub fn list_roms() -> Vec<MacRom> {
+    let Ok(entries) = std::fs::read_dir(rom_dir()) else {
+        return Vec::new();
+    };
+    let mut roms: Vec<MacRom> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .filter_map(|p| identify_rom(&p).ok())
+        .collect();
+    roms.sort_by_key(|r| (r.machine.is_none(), r.file_name().to_lowercase()));
+    roms
+}
+
+/// A ROM in the ROM folder that boots the given machine type
+pub fn find_rom(machine: &str) -> Option<MacRom> {
+    list_roms().into_iter().find(|r| r.machine == Some(machine))
+}
+
+/// Default firmware for a new VM on a classic Mac board
+///
+/// The Quadra gets the ROM from the ROM folder (or the path it is expected
+/// at, so launch.sh explains what is missing) and persistent PRAM.
+pub fn default_firmware(machine: &str, prom_env: &[String]) -> Option<MacFirmware> {
+    if needs_rom(machine) {
+        let rom = find_rom(machine)
+            .map(|r| r.path)
+            .unwrap_or_else(|| rom_dir().join(DEFAULT_QUADRA_ROM));
+        return Some(MacFirmware {
+            rom: Some(rom),
+            prom_env: Vec::new(),
+            pram: true,
+        });
+    }
+    (!prom_env.is_empty()).then(|| MacFirmware {
+        rom: None,
+        prom_env: prom_env.to_vec(),
+        pram: false,
+    })
+}
+
+/// Write (or with None, remove) the Mac firmware section of a launch script
+pub fn set_mac_firmware(script_path: &Path, firmware: Option<&MacFirmware>) -> Result<()> {
+    let content = std::fs::read_to_string(script_path).context("Failed to read launch.sh")?;
+    let content = remove_mac_section(&content);
+
+    let new_content = match firmware.filter(|fw| !fw.is_empty()) {
+        Some(firmware) => insert_args_section(&content, &generate_mac_section(firmware)?, "$MAC_FIRMWARE_ARGS"),
+        None => content,
+    };
+
+    std::fs::write(script_path, new_content).context("Failed to write launch.sh")?;
+    Ok(())
+}
+
+/// Read the Mac firmware settings from a launch script
+pub fn parse_mac_firmware(content: &str) -> MacFirmware {
+    let mut firmware = MacFirmware::default();
+    let mut in_section = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed == MAC_MARKER_START {
+            in_section = true;
+        } else if trimmed == MAC_MARKER_END {
+            break;
+        } else if in_section {
+            if let Some(rom) = trimmed.strip_prefix("MAC_ROM=") {
+                firmware.rom = Some(PathBuf::from(rom.trim_matches('"')));
+            } else if let Some((_, value)) = trimmed.split_once("-prom-env ") {
+                firmware.prom_env.push(value.trim_end_matches('"').to_string());
+            } else if trimmed.contains("if=mtd") {
+                firmware.pram = true;
+            }
+        }
+    }
+    firmware
+}
+
+fn generate_mac_section(firmware: &MacFirmware) -> Result<String> {
+    let unsafe_char = |c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '$' | '`' | '\\');
+    if let Some(ref rom) = firmware.rom {
+        if rom.to_string_lossy().contains(unsafe_char) {
+            bail!("Unsupported characters in ROM path: {}", rom.display());
+        }
+    }
+    if let Some(value) = firmware.prom_env.iter().find(|v| v.contains(unsafe_char)) {
+        bail!("Unsupported characters in PROM variable: {}", value);
+    }
+
+    let mut section = String::new();
+    section.push_str(MAC_MARKER_START);
+    section.push('\n');
+    section.push_str("MAC_FIRMWARE_ARGS=\"\"\n");
+    if let Some(ref rom) = firmware.rom {
+        section.push_str(&format!("MAC_ROM=\"{}\"\n", rom.display()));
+        section.push_str("if [[ ! -f \"$MAC_ROM\" ]]; then\n");
+        section.push_str("    echo \"Mac ROM not found: $MAC_ROM (add a ROM dump in vm-curator's Mac Firmware screen)\" >&2\n");
+        section.push_str("    exit 1\n");
+        section.push_str("fi\n");
+        section.push_str("MAC_FIRMWARE_ARGS=\"-bios $MAC_ROM\"\n");
+    }
+    if firmware.pram {
+        // 256 bytes of PRAM: startup disk, sound and display settings
+        section.push_str("if [[ ! -f \"$VM_DIR/pram.img\" ]]; then\n");
+        section.push_str("    head -c 256 /dev/zero > \"$VM_DIR/pram.img\"\n");
+        section.push_str("fi\n");
+        section.push_str("MAC_FIRMWARE_ARGS=\"$MAC_FIRMWARE_ARGS -drive file=$VM_DIR/pram.img,format=raw,if=mtd\"\n");
+    }
+    for value in &firmware.prom_env {
+        section.push_str(&format!("MAC_FIRMWARE_ARGS=\"$MAC_FIRMWARE_ARGS -prom-env {}\"\n", value));
+    }
+    section.push_str(MAC_MARKER_END);
+    section.push('\n');
+    Ok(section)
+}
+
+fn remove_mac_section(content: &str) -> String {
+    remove_args_section(content, MAC_MARKER_START, MAC_MARKER_END, "$MAC_FIRMWARE_ARGS")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = "VM_DIR=\"/vms/mac\"\ncase \"$1\" in\n    \"\")\n        qemu-system-m68k \\\n            -M q800 \\\n            -m 64M\n        ;;\nesac\n";
+
+    #[test]
+    fn test_mac_section_round_trip() {
+        let firmware = MacFirmware {
+            rom: Some(PathBuf::from("/home/me/.config/vm-curator/roms/Quadra800.rom")),
+            prom_env: vec!["auto-boot?=true".to_string()],
+            pram: true,
+        };
+        let section = generate_mac_section(&firmware).unwrap();
+        assert!(section.contains("MAC_FIRMWARE_ARGS=\"-bios $MAC_ROM\"\n"));
+        assert!(section.contains("-drive file=$VM_DIR/pram.img,format=raw,if=mtd"));
+
+        let with_firmware = insert_args_section(SCRIPT, &section, "$MAC_FIRMWARE_ARGS");
+        assert_eq!(parse_mac_firmware(&with_firmware), firmware);
+        assert!(with_firmware.contains("-m 64M $MAC_FIRMWARE_ARGS"));
+
+        let restored = remove_mac_section(&with_firmware);
+        assert_eq!(parse_mac_firmware(&restored), MacFirmware::default());
+        assert!(!restored.contains("MAC_"));
+
+        let spaced = MacFirmware {
+            rom: Some(PathBuf::from("/roms/Quadra 800.rom")),
+            ..Default::default()
+        };
+        assert!(generate_mac_section(&spaced).is_err());
+    }
+
+    #[test]
+    fn test_identify_rom() {
+        let dir = std::env::temp_dir().join(format!("vm-curator-rom-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("q800.rom");
+        std::fs::write(&path, [0xF1, 0xAC, 0xAD, 0x13, 0x00, 0x2A]).unwrap();
+
+        let rom = identify_rom(&path).unwrap();
+        assert_eq!(rom.checksum, 0xF1ACAD13);
+        assert_eq!(rom.machine, Some("q800"));
+
+        std::fs::write(&path, [0x12, 0x34, 0x56, 0x78]).unwrap();
+        assert_eq!(identify_rom(&path).unwrap().model, None);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,254 @@
+//! Machine types
+//!
+//! `-machine` picks the emulated board: chipset, buses and built-in devices.
+//! Old operating systems are picky about it. Windows 9x and NT have no
+//! drivers for the Q35 chipset's AHCI and ICH9 devices, and DOS-era software
+//! expects an ISA-only PC. This module keeps a curated list per emulator
+//! with compatibility notes, and swaps the machine in a launch script
+//! while keeping its options (accel=kvm, smm=on, ...).
+
+use anyhow::{bail, Context, Result};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::path::Path;
+
+use crate::vm::lifecycle::insert_base_arg;
+use crate::vm::qemu_config::QemuConfig;
+
+/// A machine type offered in the pickers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MachineType {
+    /// Value passed to `-machine`
+    pub id: &'static str,
+    /// Display name
+    pub name: &'static str,
+    /// Which guests it suits
+    pub notes: &'static str,
+}
+
+const PC_MACHINES: &[MachineType] = &[
+    MachineType {
+        id: "q35",
+        name: "Q35 + ICH9 (PCIe)",
+        notes: "Modern guests: Windows Vista and later, Linux, BSD. Needed for Secure Boot.",
+    },
+    MachineType {
+        id: "pc",
+        name: "i440FX + PIIX (PCI)",
+        notes: "Windows 9x/NT/2000/XP, OS/2, BeOS, old Linux: their IDE and ACPI drivers predate ICH9.",
+    },
+    MachineType {
+        id: "isapc",
+        name: "ISA-only PC",
+        notes: "DOS and Windows 3.x on 286/386-class hardware. No PCI: use sb16, ne2k_isa, cirrus/std VGA.",
+    },
+    MachineType {
+        id: "microvm",
+        name: "microvm (virtio only)",
+        notes: "Direct-kernel Linux boot with virtio-mmio devices; no BIOS disk boot or legacy devices.",
+    },
+];
+
+const PPC_MACHINES: &[MachineType] = &[
+    MachineType {
+        id: "mac99",
+        name: "PowerMac G4 (Mac99)",
+        notes: "Mac OS 9.x and Mac OS X 10.0-10.5 (add via-cuda for OS 9 mouse support).",
+    },
+    MachineType {
+        id: "g3beige",
+        name: "PowerMac G3 Beige",
+        notes: "Mac OS 8.x-9.x, and early Linux/PPC releases.",
+    },
+];
+
+const M68K_MACHINES: &[MachineType] = &[
+    MachineType {
+        id: "q800",
+        name: "Macintosh Quadra 800",
+        notes: "System 7.1 to Mac OS 8.1 and A/UX (needs a Quadra 800 ROM).",
+    },
+    MachineType {
+        id: "next-cube",
+        name: "NeXTcube",
+        notes: "NeXTSTEP (needs the NeXT ROM; emulation is incomplete).",
+    },
+];
+
+const ARM_MACHINES: &[MachineType] = &[
+    MachineType {
+        id: "virt",
+        name: "Generic virt board",
+        notes: "Modern ARM Linux and BSD with virtio devices and UEFI.",
+    },
+    MachineType {
+        id: "raspi2b",
+        name: "Raspberry Pi 2B",
+        notes: "Raspberry Pi OS images built for the Pi 2.",
+    },
+    MachineType {
+        id: "versatilepb",
+        name: "ARM Versatile PB",
+        notes: "Old ARM Linux kernels and RISC OS-era experiments.",
+    },
+];
+
+const AARCH64_MACHINES: &[MachineType] = &[
+    MachineType {
+        id: "virt",
+        name: "Generic virt board",
+        notes: "ARM64 Linux, BSD and Windows on ARM with UEFI.",
+    },
+    MachineType {
+        id: "raspi3b",
+        name: "Raspberry Pi 3B",
+        notes: "64-bit Raspberry Pi OS images for the Pi 3.",
+    },
+];
+
+const RISCV64_MACHINES: &[MachineType] = &[
+    MachineType {
+        id: "virt",
+        name: "Generic virt board",
+        notes: "RISC-V Linux and BSD with virtio devices, booted through OpenSBI and U-Boot or EDK2.",
+    },
+    MachineType {
+        id: "sifive_u",
+        name: "SiFive HiFive Unleashed",
+        notes: "Images built for the HiFive Unleashed board (SD card, Cadence Ethernet).",
+    },
+];
+
+static RE_MACHINE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(-machine|-M)\s+(type=)?([\w.-]+)").expect("Invalid regex: RE_MACHINE")
+});
+
+/// PCI-only devices that an ISA machine can't host
+const PCI_DEVICES: &[&str] = &[
+    "virtio", "e1000", "rtl8139", "pcnet", "ne2k_pci", "intel-hda", "AC97", "es1370", "qxl", "usb", "ahci",
+];
+
+/// Curated machine types for an emulator command
+pub fn machines_for(emulator: &str) -> &'static [MachineType] {
+    match emulator {
+        "qemu-system-x86_64" | "qemu-system-i386" => PC_MACHINES,
+        "qemu-system-ppc" => PPC_MACHINES,
+        "qemu-system-m68k" => M68K_MACHINES,
+        "qemu-system-arm" => ARM_MACHINES,
+        "qemu-system-aarch64" => AARCH64_MACHINES,
+        "qemu-system-riscv64" => RISCV64_MACHINES,
+        _ => &[],
+    }
+}
+
+/// Machine ids to cycle through: the curated list, plus `current` if it isn't in it
+pub fn machine_options(emulator: &str, current: Option<&str>) -> Vec<String> {
+    let mut options: Vec<String> = machines_for(emulator).iter().map(|m| m.id.to_string()).collect();
+    if let Some(current) = current {
+        if !options.iter().any(|o| o == current) {
+            options.push(current.to_string());
+        }
+    }
+    options
+}
+
+/// Look up a curated machine (versioned ids like pc-i440fx-8.2 match their family)
+pub fn find_machine(emulator: &str, id: &str) -> Option<&'static MachineType> {
+    let family = if id.starts_with("pc-q35") {
+        "q35"
+    } else if id.starts_with("pc-i440fx") {
+        "pc"
+    } else {
+        id
+    };
+    machines_for(emulator).iter().find(|m| m.id == family)
+}
+
+/// Problems switching a VM to `machine` would cause
+pub fn machine_warnings(machine: &str, config: &QemuConfig) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let script = &config.raw_script;
+
+    match machine {
+        "isapc" => {
+            let pci: Vec<&str> = PCI_DEVICES.iter().copied().filter(|d| script.contains(d)).collect();
+            if !pci.is_empty() {
+                warnings.push(format!("PCI devices won't appear on an ISA machine: {}", pci.join(", ")));
+            }
+            if config.uefi {
+                warnings.push("UEFI firmware needs a PCI machine (pc or q35)".to_string());
+            }
+        }
+        "pc" => {
+            if config.secure_boot {
+                warnings.push("Secure Boot needs q35 (SMM-capable firmware)".to_string());
+            }
+            if script.contains("ich9") || script.contains("pcie-root-port") {
+                warnings.push("Q35-only devices (ICH9, PCIe root ports) must be removed".to_string());
+            }
+        }
+        "q35" if script.contains("piix") => {
+            warnings.push("PIIX devices belong to the pc machine".to_string());
+        }
+        _ => {}
+    }
+
+    warnings
+}
+
+/// Set a VM's machine type, keeping any machine options
+pub fn set_machine(script_path: &Path, machine: &str) -> Result<()> {
+    let content = std::fs::read_to_string(script_path).context("Failed to read launch.sh")?;
+    let new_content = rewrite_machine(&content, machine)?;
+    std::fs::write(script_path, new_content).context("Failed to write launch.sh")?;
+    Ok(())
+}
+
+fn rewrite_machine(content: &str, machine: &str) -> Result<String> {
+    if !machine.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.' || c == '_') {
+        bail!("Invalid machine type: {}", machine);
+    }
+
+    if RE_MACHINE.is_match(content) {
+        let rewritten = RE_MACHINE.replace_all(content, |caps: &Captures| {
+            format!("{} {}{}", &caps[1], caps.get(2).map_or("", |m| m.as_str()), machine)
+        });
+        return Ok(rewritten.into_owned());
+    }
+
+    insert_base_arg(content, &format!("-machine {}", machine))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_machine_keeps_options() {
+        let script = "qemu-system-x86_64 \\\n    -machine q35,accel=kvm,smm=on \\\n    -m 2G\n";
+        let rewritten = rewrite_machine(script, "pc").unwrap();
+        assert!(rewritten.contains("-machine pc,accel=kvm,smm=on \\"));
+
+        let inserted = rewrite_machine("qemu-system-i386 -m 16M\n", "isapc").unwrap();
+        assert_eq!(inserted, "qemu-system-i386 -machine isapc -m 16M\n");
+        assert!(rewrite_machine(script, "pc; rm -rf").is_err());
+    }
+
+    #[test]
+    fn test_machine_lookup_and_warnings() {
+        assert_eq!(find_machine("qemu-system-x86_64", "pc-i440fx-8.2").map(|m| m.id), Some("pc"));
+        assert_eq!(
+            machine_options("qemu-system-ppc", Some("sam460ex")),
+            vec!["mac99", "g3beige", "sam460ex"]
+        );
+
+        let config = QemuConfig {
+            raw_script: "qemu-system-i386 -device e1000 -device sb16".to_string(),
+            ..Default::default()
+        };
+        let warnings = machine_warnings("isapc", &config);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("e1000"));
+        assert!(machine_warnings("pc", &config).is_empty());
+    }
+}
@@ -0,0 +1,138 @@
+//! MIDI output for retro guests
+//!
+//! QEMU has no MPU-401, so General MIDI and MT-32 music leaves the guest
+//! through its first serial port (SoftMPU `/OUTPUT:COM1` under DOS, or a
+//! serial MIDI driver under Windows 3.x/9x). A managed section of launch.sh
+//! writes that port into an `snd-virmidi` raw MIDI device, starts a host
+//! synth on the ALSA sequencer, and connects the two.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use crate::vm::lifecycle::{insert_args_section, remove_args_section};
+
+// MIDI section markers
+const MIDI_MARKER_START: &str = "# >>> MIDI (managed by vm-curator) >>>";
+const MIDI_MARKER_END: &str = "# <<< MIDI <<<";
+
+/// A host synthesizer that plays the guest's MIDI stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MidiSynth {
+    /// Identifier stored in launch.sh
+    pub id: &'static str,
+    /// Display name
+    pub name: &'static str,
+    /// Short description for the picker
+    pub description: &'static str,
+    /// Shell command that starts the synth on the ALSA sequencer
+    pub command: &'static str,
+    /// ALSA sequencer client name (prefix) to connect to
+    pub client: &'static str,
+}
+
+/// Available synths
+pub const MIDI_SYNTHS: &[MidiSynth] = &[
+    MidiSynth {
+        id: "fluidsynth",
+        name: "FluidSynth",
+        description: "General MIDI via the first SoundFont found",
+        command: "fluidsynth -q -is -m alsa_seq \"$(find /usr/share/soundfonts /usr/share/sounds/sf2 -name '*.sf2' 2>/dev/null | head -n 1)\"",
+        client: "FLUID Synth",
+    },
+    MidiSynth {
+        id: "mt32",
+        name: "Roland MT-32 (munt)",
+        description: "LA synthesis for Sierra/LucasArts era games (needs ROMs)",
+        command: "mt32d",
+        client: "Munt MT-32",
+    },
+];
+
+/// Look up a synth by id
+pub fn find_synth(id: &str) -> Option<&'static MidiSynth> {
+    MIDI_SYNTHS.iter().find(|s| s.id == id)
+}
+
+/// Write (or with None, remove) the MIDI section of a launch script
+pub fn set_midi(script_path: &Path, synth: Option<&MidiSynth>) -> Result<()> {
+    let content = std::fs::read_to_string(script_path).context("Failed to read launch.sh")?;
+    let content = remove_midi_section(&content);
+
+    let new_content = match synth {
+        Some(synth) => insert_args_section(&content, &generate_midi_section(synth), "$MIDI_ARGS"),
+        None => content,
+    };
+
+    std::fs::write(script_path, new_content).context("Failed to write launch.sh")?;
+    Ok(())
+}
+
+/// Read the MIDI synth id from a launch script
+pub fn parse_midi_synth(content: &str) -> Option<String> {
+    let mut in_section = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed == MIDI_MARKER_START {
+            in_section = true;
+        } else if trimmed == MIDI_MARKER_END {
+            return None;
+        } else if in_section {
+            if let Some(value) = trimmed.strip_prefix("MIDI_SYNTH=") {
+                return Some(value.trim_matches('"').to_string());
+            }
+        }
+    }
+    None
+}
+
+fn generate_midi_section(synth: &MidiSynth) -> String {
+    let mut section = String::new();
+    section.push_str(MIDI_MARKER_START);
+    section.push('\n');
+    section.push_str(&format!("MIDI_SYNTH=\"{}\"\n", synth.id));
+    section.push_str("MIDI_ARGS=\"\"\n");
+    // Guest COM1 -> snd-virmidi raw MIDI -> ALSA sequencer -> synth
+    section.push_str("MIDI_CARD=\"$(awk '/VirMIDI/ {print $1; exit}' /proc/asound/cards 2>/dev/null)\"\n");
+    section.push_str("if [[ -z \"$MIDI_CARD\" ]]; then\n");
+    section.push_str("    echo \"MIDI disabled: load snd-virmidi first (sudo modprobe snd-virmidi)\" >&2\n");
+    section.push_str("elif [[ \"$1\" != \"--help\" && \"$1\" != \"-h\" ]]; then\n");
+    section.push_str(&format!("    {} >/dev/null 2>&1 &\n", synth.command));
+    section.push_str("    MIDI_PID=$!\n");
+    // Stop the synth once this script (and so QEMU) exits, without touching other traps
+    section.push_str("    ( while kill -0 $$ 2>/dev/null; do sleep 2; done; kill \"$MIDI_PID\" 2>/dev/null ) &\n");
+    section.push_str("    sleep 1\n");
+    section.push_str(&format!(
+        "    aconnect \"Virtual Raw MIDI $MIDI_CARD-0\" \"{}\" 2>/dev/null\n",
+        synth.client
+    ));
+    section.push_str("    MIDI_ARGS=\"-chardev file,id=midi0,path=/dev/snd/midiC${MIDI_CARD}D0 -serial chardev:midi0\"\n");
+    section.push_str("fi\n");
+    section.push_str(MIDI_MARKER_END);
+    section.push('\n');
+    section
+}
+
+fn remove_midi_section(content: &str) -> String {
+    remove_args_section(content, MIDI_MARKER_START, MIDI_MARKER_END, "$MIDI_ARGS")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_midi_section_round_trip() {
+        let script = "VM_DIR=\"/vms/t\"\ncase \"$1\" in\n    \"\")\n        qemu-system-i386 \\\n            -m 64M\n        ;;\nesac\n";
+        let synth = find_synth("mt32").unwrap();
+        let with_midi = insert_args_section(script, &generate_midi_section(synth), "$MIDI_ARGS");
+
+        assert_eq!(parse_midi_synth(&with_midi), Some("mt32".to_string()));
+        assert!(with_midi.contains("aconnect \"Virtual Raw MIDI $MIDI_CARD-0\" \"Munt MT-32\""));
+        assert!(with_midi.contains("-serial chardev:midi0"));
+        assert!(with_midi.contains("-m 64M $MIDI_ARGS"));
+
+        let restored = remove_midi_section(&with_midi);
+        assert_eq!(parse_midi_synth(&restored), None);
+        assert!(!restored.contains("MIDI"));
+    }
+}
@@ -0,0 +1,281 @@
+//! Library layout migrations
+//!
+//! The on-disk layout of a VM library changes as vm-curator grows new
+//! per-VM files. The library records its layout version in
+//! `.vm-curator-library.toml`; libraries without one predate versioning.
+//! On startup, an older library is offered an upgrade: the small per-VM
+//! files (launch scripts, metadata, sidecars; never disk images) are backed
+//! up, then each migration step runs in order and the version is recorded
+//! after every step, so an interrupted upgrade resumes where it stopped.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File at the library root that records the schema version
+const SCHEMA_FILE: &str = ".vm-curator-library.toml";
+
+/// Directory at the library root holding pre-migration backups
+const BACKUP_DIR: &str = ".vm-curator-backups";
+
+/// Schema version of libraries created before versioning existed
+const LEGACY_SCHEMA_VERSION: u32 = 1;
+
+/// Schema version this build writes
+pub const CURRENT_SCHEMA_VERSION: u32 = 4;
+
+/// Per-VM files that make up a VM's configuration (disk images excluded)
+const BACKUP_EXTENSIONS: &[&str] = &["sh", "toml", "json", "conf", "cfg"];
+
+/// One step from `to - 1` to `to`
+struct Migration {
+    to: u32,
+    description: &'static str,
+    apply: fn(&Path) -> Result<()>,
+}
+
+/// All migration steps, in order
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        to: 2,
+        description: "Give every VM a vm-curator.toml with a stable UUID",
+        apply: migrate_v2_vm_metadata,
+    },
+    Migration {
+        to: 3,
+        description: "Open a QMP control socket in every VM's launch script",
+        apply: migrate_qmp_section,
+    },
+    Migration {
+        to: 4,
+        description: "Open a second QMP socket for following VM events",
+        apply: migrate_qmp_section,
+    },
+];
+
+/// A library that needs upgrading
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingMigration {
+    pub from: u32,
+    pub to: u32,
+    /// Descriptions of the steps that will run
+    pub steps: Vec<&'static str>,
+}
+
+/// Result of a completed upgrade
+#[derive(Debug, Clone)]
+pub struct MigrationReport {
+    pub from: u32,
+    pub to: u32,
+    /// Where the pre-migration files were copied
+    pub backup_dir: PathBuf,
+}
+
+/// Read the library's schema version (legacy libraries have no schema file)
+pub fn schema_version(library: &Path) -> u32 {
+    fs::read_to_string(library.join(SCHEMA_FILE))
+        .ok()
+        .and_then(|content| {
+            content.lines().find_map(|line| {
+                let value = line.trim().strip_prefix("schema_version")?;
+                value.trim().strip_prefix('=')?.trim().parse().ok()
+            })
+        })
+        .unwrap_or(LEGACY_SCHEMA_VERSION)
+}
+
+/// Check whether a library needs upgrading
+///
+/// A library with no VMs has nothing to migrate; it is stamped with the
+/// current version so it's never asked again.
+pub fn check_library(library: &Path) -> Option<PendingMigration> {
+    if !library.is_dir() {
+        return None;
+    }
+
+    let from = schema_version(library);
+    if from >= CURRENT_SCHEMA_VERSION {
+        return None;
+    }
+
+    if vm_dirs(library).is_empty() {
+        let _ = write_schema_version(library, CURRENT_SCHEMA_VERSION);
+        return None;
+    }
+
+    Some(PendingMigration {
+        from,
+        to: CURRENT_SCHEMA_VERSION,
+        steps: MIGRATIONS
+            .iter()
+            .filter(|m| m.to > from)
+            .map(|m| m.description)
+            .collect(),
+    })
+}
+
+/// Back up the library's configuration files, then run all pending steps
+pub fn migrate_library(library: &Path) -> Result<MigrationReport> {
+    let from = schema_version(library);
+    let backup_dir = backup_library(library, from)?;
+
+    for migration in MIGRATIONS.iter().filter(|m| m.to > from) {
+        (migration.apply)(library)
+            .with_context(|| format!("Migration to schema {} failed", migration.to))?;
+        write_schema_version(library, migration.to)?;
+    }
+
+    Ok(MigrationReport {
+        from,
+        to: CURRENT_SCHEMA_VERSION,
+        backup_dir,
+    })
+}
+
+/// Record the schema version of a newly created or upgraded library
+pub fn write_schema_version(library: &Path, version: u32) -> Result<()> {
+    let content = format!(
+        "# VM Curator library layout version (do not edit)\nschema_version = {}\n",
+        version
+    );
+    fs::write(library.join(SCHEMA_FILE), content).context("Failed to write library schema version")
+}
+
+/// VM directories in a library (those with a launch.sh)
+fn vm_dirs(library: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(library) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_dir() && p.join("launch.sh").exists())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// Copy every VM's configuration files into a timestamped backup directory
+fn backup_library(library: &Path, from: u32) -> Result<PathBuf> {
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    let backup_dir = library
+        .join(BACKUP_DIR)
+        .join(format!("schema-v{}-{}", from, stamp));
+
+    for vm_dir in vm_dirs(library) {
+        let Some(name) = vm_dir.file_name() else {
+            continue;
+        };
+        let dest = backup_dir.join(name);
+        fs::create_dir_all(&dest)
+            .with_context(|| format!("Failed to create backup directory {}", dest.display()))?;
+
+        for entry in fs::read_dir(&vm_dir)?.flatten() {
+            let path = entry.path();
+            let is_config = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| BACKUP_EXTENSIONS.contains(&e));
+            if path.is_file() && is_config {
+                fs::copy(&path, dest.join(entry.file_name()))
+                    .with_context(|| format!("Failed to back up {}", path.display()))?;
+            }
+        }
+    }
+
+    Ok(backup_dir)
+}
+
+/// Schema 2: every VM has a vm-curator.toml carrying a `uuid` that stays
+/// the same when the VM's folder is renamed
+fn migrate_v2_vm_metadata(library: &Path) -> Result<()> {
+    for vm_dir in vm_dirs(library) {
+        let metadata_path = vm_dir.join("vm-curator.toml");
+        let existing = fs::read_to_string(&metadata_path).unwrap_or_default();
+        if existing.lines().any(|l| l.trim_start().starts_with("uuid")) {
+            continue;
+        }
+
+        let mut content = if existing.is_empty() {
+            "# VM Curator metadata\n\n".to_string()
+        } else {
+            existing
+        };
+        if !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(&format!("uuid = \"{}\"\n", crate::vm::create::generate_uuid()));
+
+        fs::write(&metadata_path, content)
+            .with_context(|| format!("Failed to write {}", metadata_path.display()))?;
+    }
+    Ok(())
+}
+
+/// v3: add the managed QMP section, used for USB hot-plug; v4: add its
+/// event socket (both bring a script to the current section)
+fn migrate_qmp_section(library: &Path) -> Result<()> {
+    for vm_dir in vm_dirs(library) {
+        let script_path = vm_dir.join("launch.sh");
+        let Ok(content) = fs::read_to_string(&script_path) else {
+            continue;
+        };
+        let updated = crate::vm::qmp::add_qmp_section(&content);
+        if updated != content {
+            fs::write(&script_path, updated)
+                .with_context(|| format!("Failed to write {}", script_path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_library(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("vm-curator-migration-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_migrate_legacy_library() {
+        let library = temp_library("legacy");
+        let vm = library.join("dos-622");
+        fs::create_dir_all(&vm).unwrap();
+        fs::write(vm.join("launch.sh"), "qemu-system-i386 -m 16M\n").unwrap();
+        fs::write(vm.join("vm-curator.toml"), "display_name = \"DOS\"\n").unwrap();
+        fs::write(vm.join("disk.qcow2"), b"not backed up").unwrap();
+
+        let pending = check_library(&library).unwrap();
+        assert_eq!((pending.from, pending.to), (LEGACY_SCHEMA_VERSION, CURRENT_SCHEMA_VERSION));
+
+        let report = migrate_library(&library).unwrap();
+        assert_eq!(schema_version(&library), CURRENT_SCHEMA_VERSION);
+        assert!(check_library(&library).is_none());
+
+        let metadata = fs::read_to_string(vm.join("vm-curator.toml")).unwrap();
+        assert!(metadata.contains("display_name = \"DOS\""));
+        assert!(metadata.contains("uuid = \""));
+        let script = fs::read_to_string(vm.join("launch.sh")).unwrap();
+        assert!(script.contains("-m 16M $QMP_ARGS"));
+        assert!(crate::vm::qmp::has_event_socket(&script));
+
+        let backup = report.backup_dir.join("dos-622");
+        assert!(backup.join("launch.sh").exists());
+        assert_eq!(fs::read_to_string(backup.join("vm-curator.toml")).unwrap(), "display_name = \"DOS\"\n");
+        assert!(!backup.join("disk.qcow2").exists());
+
+        fs::remove_dir_all(&library).unwrap();
+    }
+
+    #[test]
+    fn test_empty_library_is_stamped() {
+        let library = temp_library("empty");
+        assert!(check_library(&library).is_none());
+        assert_eq!(schema_version(&library), CURRENT_SCHEMA_VERSION);
+        fs::remove_dir_all(&library).unwrap();
+    }
+}
@@ -1,14 +1,68 @@
+pub mod archival;
+pub mod audio;
+pub mod backing_chain;
+pub mod backend;
+pub mod batch;
+pub mod checklist;
+pub mod cloud_init;
+pub mod cpu_era;
+pub mod cpu_model;
 pub mod create;
+pub mod data_disk;
+pub mod direct_launch;
 pub mod discovery;
+pub mod disk_io;
+pub mod doctor;
+pub mod display_preset;
+pub mod disk_ops;
+pub mod firmware;
+pub mod game_controller;
+pub mod first_boot;
+pub mod gpu_accel;
+pub mod guest_agent;
+pub mod host_migration;
+pub mod identity;
+pub mod input_passthrough;
+pub mod instance_lock;
+pub mod iso_download;
+pub mod iso_library;
+pub mod launch_env;
+pub mod launch_history;
 pub mod launch_parser;
 pub mod lifecycle;
+pub mod local_socket;
+pub mod mac_firmware;
+pub mod machine;
+pub mod looking_glass;
+pub mod midi;
+pub mod migration;
+pub mod monitor;
+pub mod notes;
+pub mod orphans;
+pub mod os_fingerprint;
+pub mod physical_disk;
+pub mod profile_check;
+pub mod qemu_log;
+pub mod qmp;
 pub mod qemu_config;
+pub mod remote_disk;
+pub mod remote_host;
+pub mod screenshots;
 pub mod single_gpu_scripts;
 pub mod snapshot;
+pub mod snapshot_meta;
+pub mod sound_blaster;
+pub mod spice;
+pub mod storage;
+pub mod suspend;
+pub mod systemd;
+pub mod unattended;
+pub mod validation;
+pub mod vnc;
 
 pub use create::create_vm;
 pub use discovery::{discover_vms, group_vms_by_category, DiscoveredVm};
-pub use lifecycle::{detect_qemu_processes, force_stop_vm, launch_vm_sync, launch_vm_with_error_check, load_pci_passthrough, load_shared_folders, load_usb_passthrough, save_shared_folders, save_usb_passthrough, stop_vm_by_pid, LaunchOptions, QemuProcess, SharedFolder, UsbPassthrough};
+pub use lifecycle::{detect_qemu_processes, force_stop_vm, launch_vm_sync, launch_vm_with_error_check, load_pci_passthrough, load_shared_folders, load_usb_passthrough, save_shared_folders, save_usb_passthrough, stop_vm_by_pid, LaunchOptions, LaunchOverrides, QemuProcess, SharedFolder, SharedFolderMethod, UsbPassthrough};
 pub use qemu_config::{BootMode, QemuConfig};
 pub use single_gpu_scripts::generate_single_gpu_scripts;
 pub use snapshot::{create_snapshot, delete_snapshot, list_snapshots, restore_snapshot, Snapshot};
@@ -0,0 +1,216 @@
+//! Live resource figures for running VMs
+//!
+//! CPU time and resident memory come from the QEMU process in /proc.
+//! Disk traffic comes from QMP (`query-blockstats`) and network traffic
+//! from the counters of the tap devices the process holds open, found
+//! through /proc/<pid>/fdinfo; VMs on user-mode networking have none.
+//! Counters are sampled periodically and turned into rates for the
+//! Monitor screen's sparklines.
+
+use std::path::Path;
+use std::time::Instant;
+
+use super::qmp;
+
+/// Clock ticks per second in /proc/<pid>/stat (USER_HZ, 100 on Linux)
+const CLOCK_TICKS: f64 = 100.0;
+
+/// Samples kept for each graph
+pub const HISTORY_LEN: usize = 120;
+
+/// Figures for a process from /proc
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProcessStats {
+    pub cpu_seconds: f64,
+    pub rss_bytes: u64,
+}
+
+pub fn process_stats(pid: u32) -> Option<ProcessStats> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    Some(ProcessStats {
+        cpu_seconds: parse_cpu_ticks(&stat)? as f64 / CLOCK_TICKS,
+        rss_bytes: parse_rss_bytes(&status)?,
+    })
+}
+
+/// utime + stime from /proc/<pid>/stat
+fn parse_cpu_ticks(stat: &str) -> Option<u64> {
+    // The command name may contain spaces; fields after it start at state (3)
+    let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    Some(utime + stime)
+}
+
+/// Seconds since a process started
+pub fn process_age_secs(pid: u32) -> Option<f64> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    let uptime = std::fs::read_to_string("/proc/uptime").ok()?;
+    let uptime: f64 = uptime.split_whitespace().next()?.parse().ok()?;
+    Some(uptime - parse_start_ticks(&stat)? as f64 / CLOCK_TICKS)
+}
+
+/// starttime (ticks after boot) from /proc/<pid>/stat
+fn parse_start_ticks(stat: &str) -> Option<u64> {
+    let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+    fields.get(19)?.parse().ok()
+}
+
+/// VmRSS from /proc/<pid>/status
+fn parse_rss_bytes(status: &str) -> Option<u64> {
+    let line = status.lines().find_map(|line| line.strip_prefix("VmRSS:"))?;
+    let kib: u64 = line.split_whitespace().next()?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+/// Tap interfaces a process holds open
+pub fn tap_interfaces(pid: u32) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(format!("/proc/{}/fdinfo", pid)) else {
+        return Vec::new();
+    };
+    let mut interfaces: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| std::fs::read_to_string(entry.path()).ok())
+        .filter_map(|info| parse_fdinfo_interface(&info))
+        .collect();
+    interfaces.sort();
+    interfaces.dedup();
+    interfaces
+}
+
+/// The interface of a tun/tap file descriptor (`iff:` line of its fdinfo)
+fn parse_fdinfo_interface(info: &str) -> Option<String> {
+    info.lines()
+        .find_map(|line| line.strip_prefix("iff:"))
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+/// Bytes the guest received and sent through the given tap interfaces
+///
+/// The host's receive counter of a tap device is what the guest sent.
+fn tap_bytes(interfaces: &[String]) -> Option<(u64, u64)> {
+    let read = |interface: &str, counter: &str| -> Option<u64> {
+        std::fs::read_to_string(format!("/sys/class/net/{}/statistics/{}", interface, counter))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    };
+    let mut totals = (0, 0);
+    for interface in interfaces {
+        totals.0 += read(interface, "tx_bytes")?;
+        totals.1 += read(interface, "rx_bytes")?;
+    }
+    Some(totals)
+}
+
+/// Counters of a running VM at one moment
+#[derive(Debug, Clone, Copy)]
+pub struct Sample {
+    pub at: Instant,
+    pub process: ProcessStats,
+    /// Bytes read and written by the guest's disks (needs QMP)
+    pub disk: Option<(u64, u64)>,
+    /// Bytes received and sent by the guest (needs tap networking)
+    pub net: Option<(u64, u64)>,
+}
+
+/// Take a sample of a running VM; None once its process has gone
+pub fn sample(pid: u32, vm_dir: &Path, qmp_configured: bool, taps: &[String]) -> Option<Sample> {
+    let process = process_stats(pid)?;
+    let disk = if qmp_configured { qmp::block_stats(vm_dir).ok() } else { None };
+    let net = if taps.is_empty() { None } else { tap_bytes(taps) };
+    Some(Sample { at: Instant::now(), process, disk, net })
+}
+
+/// Recent figures for the graphs, oldest first
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    /// Percent of one host CPU (over 100 with several busy vCPUs)
+    pub cpu_percent: Vec<u64>,
+    pub memory_bytes: Vec<u64>,
+    /// Bytes per second read plus written
+    pub disk_rate: Vec<u64>,
+    /// Bytes per second received plus sent
+    pub net_rate: Vec<u64>,
+}
+
+impl History {
+    /// Add the rates between two samples
+    pub fn push(&mut self, previous: &Sample, current: &Sample) {
+        let secs = current.at.duration_since(previous.at).as_secs_f64();
+        if secs <= 0.0 {
+            return;
+        }
+        let cpu = (current.process.cpu_seconds - previous.process.cpu_seconds).max(0.0) / secs * 100.0;
+        let rate = |before: Option<(u64, u64)>, after: Option<(u64, u64)>| -> u64 {
+            match (before, after) {
+                (Some(before), Some(after)) => {
+                    let bytes = after.0.saturating_sub(before.0) + after.1.saturating_sub(before.1);
+                    (bytes as f64 / secs) as u64
+                }
+                _ => 0,
+            }
+        };
+        push_capped(&mut self.cpu_percent, cpu.round() as u64);
+        push_capped(&mut self.memory_bytes, current.process.rss_bytes);
+        push_capped(&mut self.disk_rate, rate(previous.disk, current.disk));
+        push_capped(&mut self.net_rate, rate(previous.net, current.net));
+    }
+}
+
+fn push_capped(values: &mut Vec<u64>, value: u64) {
+    values.push(value);
+    if values.len() > HISTORY_LEN {
+        values.remove(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_parse_proc() {
+        let stat = "4242 (qemu-system-x86) S 1 4242 4242 0 -1 4194560 180 0 0 0 1500 250 0 0 20 0 9 0 12345 4294967296 262144";
+        assert_eq!(parse_cpu_ticks(stat), Some(1750));
+        assert_eq!(parse_start_ticks(stat), Some(12345));
+        let status = "Name:\tqemu-system-x86\nVmPeak:\t 4300000 kB\nVmRSS:\t  1048576 kB\nThreads:\t9\n";
+        assert_eq!(parse_rss_bytes(status), Some(1024 * 1024 * 1024));
+
+        assert_eq!(parse_fdinfo_interface("pos:\t0\nflags:\t0104002\nmnt_id:\t24\niff:\ttap0\n"), Some("tap0".to_string()));
+        assert_eq!(parse_fdinfo_interface("pos:\t0\nflags:\t02\n"), None);
+    }
+
+    #[test]
+    fn test_history_rates() {
+        let start = Instant::now();
+        let previous = Sample {
+            at: start,
+            process: ProcessStats { cpu_seconds: 10.0, rss_bytes: 100 },
+            disk: Some((1000, 0)),
+            net: None,
+        };
+        let current = Sample {
+            at: start + Duration::from_secs(2),
+            process: ProcessStats { cpu_seconds: 13.0, rss_bytes: 200 },
+            disk: Some((3000, 2000)),
+            net: Some((50, 50)),
+        };
+        let mut history = History::default();
+        history.push(&previous, &current);
+        assert_eq!(history.cpu_percent, vec![150]);
+        assert_eq!(history.memory_bytes, vec![200]);
+        assert_eq!(history.disk_rate, vec![2000]);
+        // No rate until two samples have the counter
+        assert_eq!(history.net_rate, vec![0]);
+
+        for _ in 0..HISTORY_LEN {
+            history.push(&previous, &current);
+        }
+        assert_eq!(history.cpu_percent.len(), HISTORY_LEN);
+    }
+}
@@ -0,0 +1,126 @@
+//! Per-VM notes and journal
+//!
+//! Each VM can keep a markdown document, `notes.md`, in its folder: free-form
+//! notes followed by a `## Journal` section of timestamped entries recording
+//! what was done to the guest (drivers installed, patches applied). The file
+//! is the only record, so it can be read or edited outside vm-curator; the
+//! journal is simply its list items that start with a timestamp.
+
+use anyhow::{Context, Result};
+use chrono::NaiveDateTime;
+use std::path::{Path, PathBuf};
+
+/// Notes document in each VM folder
+pub const NOTES_FILE: &str = "notes.md";
+
+/// Heading the journal entries follow
+const JOURNAL_HEADING: &str = "## Journal";
+
+/// Timestamp format of journal entries
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// A journal entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry {
+    pub at: NaiveDateTime,
+    pub text: String,
+}
+
+/// A VM's notes, split into the free-form part and the journal
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Notes {
+    /// Everything before the journal
+    pub text: String,
+    /// Entries in the order written (oldest first)
+    pub journal: Vec<JournalEntry>,
+}
+
+pub fn notes_path(vm_dir: &Path) -> PathBuf {
+    vm_dir.join(NOTES_FILE)
+}
+
+/// The notes document, empty if the VM has none
+pub fn read(vm_dir: &Path) -> String {
+    std::fs::read_to_string(notes_path(vm_dir)).unwrap_or_default()
+}
+
+/// Replace the notes document (an empty document removes the file)
+pub fn write(vm_dir: &Path, content: &str) -> Result<()> {
+    let path = notes_path(vm_dir);
+    if content.trim().is_empty() {
+        if path.exists() {
+            std::fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+        }
+        return Ok(());
+    }
+    let content = if content.ends_with('\n') { content.to_string() } else { format!("{}\n", content) };
+    std::fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// A VM's notes and journal
+pub fn load(vm_dir: &Path) -> Notes {
+    parse(&read(vm_dir))
+}
+
+/// Split a notes document into its text and journal entries
+pub fn parse(content: &str) -> Notes {
+    let Some(start) = content.lines().position(|line| line.trim() == JOURNAL_HEADING) else {
+        return Notes { text: content.trim_end().to_string(), journal: Vec::new() };
+    };
+    let lines: Vec<&str> = content.lines().collect();
+    let journal = lines[start + 1..].iter().filter_map(|line| parse_entry(line)).collect();
+    Notes {
+        text: lines[..start].join("\n").trim_end().to_string(),
+        journal,
+    }
+}
+
+/// `- 2026-01-31 14:05 Installed the VirtIO drivers`
+fn parse_entry(line: &str) -> Option<JournalEntry> {
+    let rest = line.trim().strip_prefix("- ")?;
+    let stamp = rest.get(..16)?;
+    let at = NaiveDateTime::parse_from_str(stamp, TIMESTAMP_FORMAT).ok()?;
+    Some(JournalEntry { at, text: rest[16..].trim().to_string() })
+}
+
+/// Append an entry to the VM's journal, starting the journal if needed
+pub fn add_journal_entry(vm_dir: &Path, at: NaiveDateTime, text: &str) -> Result<()> {
+    let mut content = read(vm_dir).trim_end().to_string();
+    if !content.lines().any(|line| line.trim() == JOURNAL_HEADING) {
+        if !content.is_empty() {
+            content.push_str("\n\n");
+        }
+        content.push_str(JOURNAL_HEADING);
+        content.push('\n');
+    }
+    content.push_str(&format!("\n- {} {}", at.format(TIMESTAMP_FORMAT), text.trim()));
+    write(vm_dir, &content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notes_and_journal() {
+        let dir = std::env::temp_dir().join(format!("vm-curator-notes-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert_eq!(load(&dir), Notes::default());
+
+        write(&dir, "# Win98 Games\n\nUse the *SB16* driver from the Creative CD.").unwrap();
+        let at = NaiveDateTime::parse_from_str("2026-01-31 14:05", TIMESTAMP_FORMAT).unwrap();
+        add_journal_entry(&dir, at, "Installed DirectX 7 ").unwrap();
+        add_journal_entry(&dir, at, "Applied the Y2K patch").unwrap();
+
+        let content = read(&dir);
+        assert!(content.contains("\n\n## Journal\n\n- 2026-01-31 14:05 Installed DirectX 7\n- 2026-01-31 14:05 Applied the Y2K patch\n"));
+        let notes = parse(&format!("{}Not an entry\n", content));
+        assert_eq!(notes.text, "# Win98 Games\n\nUse the *SB16* driver from the Creative CD.");
+        assert_eq!(notes.journal.len(), 2);
+        assert_eq!(notes.journal[1], JournalEntry { at, text: "Applied the Y2K patch".to_string() });
+
+        write(&dir, "  \n").unwrap();
+        assert!(!notes_path(&dir).exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,246 @@
+//! Unused files in VM folders
+//!
+//! Over time VM folders collect files nothing points at any more: install
+//! ISOs from finished installs, overlays and images left by experiments,
+//! half-written downloads and temp files from interrupted conversions. A
+//! file counts as used when its name appears in the launch script, the
+//! manifest or the emulator config, when it is in the backing chain of a
+//! used qcow2 image, or when it is one of vm-curator's own files.
+
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::DiscoveredVm;
+
+/// Files vm-curator keeps in VM folders, never referenced by the script
+const MANAGED_FILES: &[&str] = &[
+    "launch.sh",
+    "vm-curator.toml",
+    "vm-curator.lock",
+    "notes.md",
+    "launch-history.json",
+    "checklist.json",
+    "archival-network.json",
+    "single-gpu-config.toml",
+    ".vm-curator-launch.log",
+    "qemu.log",
+    "qemu.log.1",
+    "86box.cfg",
+    "dosbox-x.conf",
+    "OVMF_VARS.fd",
+    "suspended.state",
+];
+
+/// Extensions of managed files (sockets, helper scripts, disk operation journals)
+const MANAGED_EXTENSIONS: &[&str] = &["sock", "sh", "journal"];
+
+const DISK_EXTENSIONS: &[&str] = &["qcow2", "img", "raw", "vmdk", "vdi", "vhd", "vhdx"];
+
+/// What an unused file looks like
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrphanKind {
+    Iso,
+    DiskImage,
+    /// Partial download or leftover of an interrupted disk operation
+    Temp,
+    Other,
+}
+
+impl OrphanKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            OrphanKind::Iso => "ISO",
+            OrphanKind::DiskImage => "disk image",
+            OrphanKind::Temp => "leftover",
+            OrphanKind::Other => "other",
+        }
+    }
+
+    /// Whether the file is selected for cleanup without asking
+    pub fn preselected(&self) -> bool {
+        *self == OrphanKind::Temp
+    }
+
+    fn of(name: &str) -> Self {
+        let ext = name.rsplit_once('.').map(|(_, ext)| ext.to_lowercase()).unwrap_or_default();
+        match ext.as_str() {
+            "tmp" | "part" => OrphanKind::Temp,
+            "iso" => OrphanKind::Iso,
+            ext if DISK_EXTENSIONS.contains(&ext) => OrphanKind::DiskImage,
+            _ => OrphanKind::Other,
+        }
+    }
+}
+
+/// A file in a VM folder that nothing uses
+#[derive(Debug, Clone)]
+pub struct OrphanFile {
+    pub vm_id: String,
+    pub vm_name: String,
+    pub path: PathBuf,
+    pub size: u64,
+    pub kind: OrphanKind,
+}
+
+impl OrphanFile {
+    pub fn file_name(&self) -> String {
+        self.path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+    }
+}
+
+/// Unused files in the folders of these VMs, largest first
+pub fn scan(vms: &[&DiscoveredVm]) -> Vec<OrphanFile> {
+    let mut files: Vec<OrphanFile> = vms
+        .iter()
+        .flat_map(|vm| {
+            let mut texts = vec![vm.config.raw_script.clone(), read(&vm.path.join("vm-curator.toml"))];
+            if let Some(config) = vm.backend.config_file() {
+                texts.push(read(&vm.path.join(config)));
+            }
+            scan_folder(&vm.path, &texts.join("\n"))
+                .into_iter()
+                .map(|(path, size, kind)| OrphanFile {
+                    vm_id: vm.id.clone(),
+                    vm_name: vm.display_name(),
+                    path,
+                    size,
+                    kind,
+                })
+        })
+        .collect();
+    files.sort_by(|a, b| b.size.cmp(&a.size).then(a.path.cmp(&b.path)));
+    files
+}
+
+fn read(path: &Path) -> String {
+    std::fs::read_to_string(path).unwrap_or_default()
+}
+
+/// Unused files directly in a VM folder, given the text that refers to files
+fn scan_folder(vm_dir: &Path, references: &str) -> Vec<(PathBuf, u64, OrphanKind)> {
+    let Ok(entries) = std::fs::read_dir(vm_dir) else {
+        return Vec::new();
+    };
+    let files: Vec<(String, PathBuf, u64)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok().filter(|m| m.is_file())?;
+            Some((entry.file_name().to_string_lossy().to_string(), entry.path(), metadata.len()))
+        })
+        .collect();
+
+    let mut used: HashSet<String> = files
+        .iter()
+        .filter(|(name, _, _)| references.contains(name.as_str()))
+        .map(|(name, _, _)| name.clone())
+        .collect();
+    // Images a used overlay is built on
+    let mut pending: Vec<String> = used.iter().cloned().collect();
+    while let Some(name) = pending.pop() {
+        if let Some(backing) = qcow2_backing_file(&vm_dir.join(&name)) {
+            let backing = backing.rsplit('/').next().unwrap_or_default().to_string();
+            if used.insert(backing.clone()) {
+                pending.push(backing);
+            }
+        }
+    }
+
+    files
+        .into_iter()
+        .filter(|(name, _, _)| !used.contains(name) && !is_managed(name))
+        // Temp files of a journaled operation belong to the startup recovery
+        .filter(|(name, _, _)| {
+            let journal = name.strip_suffix(".tmp").map(|base| format!("{}.journal", base));
+            !journal.is_some_and(|journal| vm_dir.join(journal).exists())
+        })
+        .map(|(name, path, size)| (path, size, OrphanKind::of(&name)))
+        .collect()
+}
+
+fn is_managed(name: &str) -> bool {
+    MANAGED_FILES.contains(&name)
+        || name.ends_with(super::snapshot_meta::SIDECAR_SUFFIX)
+        || name.rsplit_once('.').is_some_and(|(_, ext)| MANAGED_EXTENSIONS.contains(&ext))
+}
+
+/// Backing file named in a qcow2 header
+fn qcow2_backing_file(path: &Path) -> Option<String> {
+    let mut file = File::open(path).ok()?;
+    let mut header = [0u8; 20];
+    file.read_exact(&mut header).ok()?;
+    if header[..4] != *b"QFI\xfb" {
+        return None;
+    }
+    let offset = u64::from_be_bytes(header[8..16].try_into().ok()?);
+    let size = u32::from_be_bytes(header[16..20].try_into().ok()?) as usize;
+    if offset == 0 || size == 0 || size > 1023 {
+        return None;
+    }
+    let mut name = vec![0u8; size];
+    file.seek(SeekFrom::Start(offset)).ok()?;
+    file.read_exact(&mut name).ok()?;
+    String::from_utf8(name).ok()
+}
+
+/// Delete an unused file, or move it to the trash (trash-cli when
+/// installed, else the library's `.trash` folder)
+pub fn remove(file: &OrphanFile, library_path: &Path, permanent: bool) -> Result<()> {
+    if permanent {
+        return std::fs::remove_file(&file.path).with_context(|| format!("Failed to delete {}", file.path.display()));
+    }
+    match Command::new("trash-put").arg(&file.path).output() {
+        Ok(output) if output.status.success() => Ok(()),
+        _ => super::lifecycle::move_to_trash_dir(
+            &file.path,
+            &library_path.join(".trash"),
+            &format!("{}-{}", file.vm_id, file.file_name()),
+        )
+        .with_context(|| format!("Failed to move {} to the trash", file.path.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_folder() {
+        let dir = std::env::temp_dir().join(format!("vm-curator-orphans-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("share")).unwrap();
+
+        // overlay.qcow2 (used) is built on base.qcow2
+        let mut overlay = vec![0u8; 0x200];
+        overlay[..4].copy_from_slice(b"QFI\xfb");
+        overlay[8..16].copy_from_slice(&0x100u64.to_be_bytes());
+        overlay[16..20].copy_from_slice(&10u32.to_be_bytes());
+        overlay[0x100..0x10a].copy_from_slice(b"base.qcow2");
+        std::fs::write(dir.join("overlay.qcow2"), &overlay).unwrap();
+        for name in ["base.qcow2", "old-try.qcow2", "win98.iso", "launch.sh", "notes.md", "qmp.sock", "readme.txt"] {
+            std::fs::write(dir.join(name), "x").unwrap();
+        }
+        std::fs::write(dir.join("boot.iso.part"), "xx").unwrap();
+        std::fs::write(dir.join(".overlay.qcow2.1-2.tmp"), "").unwrap();
+        std::fs::write(dir.join(".overlay.qcow2.1-2.journal"), "{}").unwrap();
+
+        let script = "DISK=\"$VM_DIR/overlay.qcow2\"\nqemu-system-i386 -hda \"$DISK\"\n";
+        let mut found: Vec<(String, OrphanKind)> = scan_folder(&dir, script)
+            .into_iter()
+            .map(|(path, _, kind)| (path.file_name().unwrap().to_string_lossy().to_string(), kind))
+            .collect();
+        found.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            found,
+            vec![
+                ("boot.iso.part".to_string(), OrphanKind::Temp),
+                ("old-try.qcow2".to_string(), OrphanKind::DiskImage),
+                ("readme.txt".to_string(), OrphanKind::Other),
+                ("win98.iso".to_string(), OrphanKind::Iso),
+            ]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,219 @@
+//! Guest OS fingerprinting
+//!
+//! Identifies the operating system installed on a disk image by running
+//! libguestfs' virt-inspector, which reads the partition table, mounts the
+//! filesystems read-only and checks the registry or release files. The
+//! result is mapped onto a QEMU profile ID so imported disks pick up the
+//! right hardware defaults and OS metadata.
+
+use anyhow::{anyhow, bail, Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::path::Path;
+use std::process::Command;
+
+use super::qemu_config::DiskFormat;
+
+static RE_OPERATING_SYSTEM: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)<operatingsystem>(.*?)</operatingsystem>").expect("Invalid regex: RE_OPERATING_SYSTEM")
+});
+
+/// What virt-inspector found on a disk
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InspectedOs {
+    /// OS type: windows, linux, dos, freebsd, netbsd, openbsd, hurd...
+    pub name: String,
+    /// Distribution for Linux and DOS (debian, fedora, freedos...)
+    pub distro: String,
+    pub product_name: String,
+    pub major_version: u32,
+    pub minor_version: u32,
+    /// libosinfo short ID (e.g. "winxp", "win11"), when known
+    pub osinfo: String,
+}
+
+impl InspectedOs {
+    /// Product name, or type and version when the guest has none
+    pub fn label(&self) -> String {
+        if !self.product_name.is_empty() && self.product_name != "unknown" {
+            return self.product_name.clone();
+        }
+        let base = if self.distro.is_empty() || self.distro == "unknown" {
+            &self.name
+        } else {
+            &self.distro
+        };
+        format!("{} {}.{}", base, self.major_version, self.minor_version)
+    }
+}
+
+/// Inspect a local disk image (takes a few seconds: libguestfs boots an appliance)
+pub fn inspect_disk(path: &Path) -> Result<InspectedOs> {
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("raw");
+    let format = match DiskFormat::from_extension(ext) {
+        DiskFormat::Other(_) => "raw".to_string(),
+        format => format.qemu_name().to_string(),
+    };
+
+    let output = Command::new("virt-inspector")
+        .args(["--no-applications", "--no-icon"])
+        .arg(format!("--format={}", format))
+        .arg("-a")
+        .arg(path)
+        .output()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => {
+                anyhow!("virt-inspector not found; install guestfs-tools (or libguestfs-tools)")
+            }
+            _ => anyhow!(e),
+        })
+        .context("Failed to run virt-inspector")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("{}", stderr.trim().lines().last().unwrap_or("virt-inspector failed"));
+    }
+
+    parse_inspector_xml(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| anyhow!("No operating system found on the disk"))
+}
+
+/// Read the first operating system from virt-inspector's XML
+pub fn parse_inspector_xml(xml: &str) -> Option<InspectedOs> {
+    let block = RE_OPERATING_SYSTEM.captures(xml)?.get(1)?.as_str();
+    let tag = |name: &str| -> String {
+        let open = format!("<{}>", name);
+        let close = format!("</{}>", name);
+        block
+            .split_once(&open)
+            .and_then(|(_, rest)| rest.split_once(&close))
+            .map(|(value, _)| unescape(value.trim()))
+            .unwrap_or_default()
+    };
+
+    let os = InspectedOs {
+        name: tag("name"),
+        distro: tag("distro"),
+        product_name: tag("product_name"),
+        major_version: tag("major_version").parse().unwrap_or(0),
+        minor_version: tag("minor_version").parse().unwrap_or(0),
+        osinfo: tag("osinfo"),
+    };
+    (!os.name.is_empty()).then_some(os)
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// The QEMU profile ID that best fits the detected OS
+pub fn match_profile(os: &InspectedOs) -> &'static str {
+    match os.name.as_str() {
+        "windows" => match (os.major_version, os.minor_version) {
+            (3, _) | (4, _) => "windows-nt",
+            (5, 0) => "windows-2000",
+            (5, _) => "windows-xp",
+            (6, 0) => "windows-vista",
+            (6, 1) => "windows-7",
+            (6, 2) => "windows-8",
+            (6, 3) => "windows-81",
+            // Windows 11 still reports itself as 10.0
+            (10, _) if os.osinfo == "win11" || os.product_name.contains("Windows 11") => "windows-11",
+            (10, _) => "windows-10",
+            _ => "generic-windows",
+        },
+        "dos" => match os.distro.as_str() {
+            "freedos" => "freedos",
+            _ if os.product_name.contains("DR-DOS") => "drdos",
+            _ => "ms-dos",
+        },
+        "linux" => match os.distro.as_str() {
+            "archlinux" => "linux-arch",
+            "manjaro" => "linux-manjaro",
+            "debian" => "linux-debian",
+            "ubuntu" => "linux-ubuntu",
+            "linuxmint" => "linux-mint",
+            "kalilinux" => "linux-kali",
+            "fedora" => "linux-fedora",
+            "centos" => "linux-centos",
+            "rocky" => "linux-rocky",
+            "alma" | "almalinux" => "linux-alma",
+            "sles" | "suse-based" => "linux-suse",
+            "opensuse" => "linux-opensuse-leap",
+            "gentoo" => "linux-gentoo",
+            "voidlinux" => "linux-void",
+            "nixos" => "linux-nixos",
+            "slackware" => "linux-slackware",
+            "alpinelinux" => "linux-alpine",
+            "clear-linux-os" => "linux-clear",
+            "mageia" => "linux-mageia",
+            "pclinuxos" => "linux-pclinuxos",
+            "mandriva" | "mandrake" => "linux-mandrake-8",
+            // Classic Red Hat Linux, before RHEL split off
+            "redhat-based" | "rhel" if os.major_version <= 9 && os.product_name.starts_with("Red Hat Linux") => "linux-redhat-7",
+            _ => "generic-linux",
+        },
+        "freebsd" => "freebsd",
+        "netbsd" => "netbsd",
+        "openbsd" => "openbsd",
+        _ => "generic-other",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_inspector_xml() {
+        let xml = r#"<?xml version="1.0"?>
+<operatingsystems>
+  <operatingsystem>
+    <root>/dev/sda1</root>
+    <name>windows</name>
+    <arch>i386</arch>
+    <distro>windows</distro>
+    <product_name>Microsoft Windows XP</product_name>
+    <product_variant>Client</product_variant>
+    <major_version>5</major_version>
+    <minor_version>1</minor_version>
+    <windows_systemroot>/WINDOWS</windows_systemroot>
+    <osinfo>winxp</osinfo>
+  </operatingsystem>
+</operatingsystems>"#;
+        let os = parse_inspector_xml(xml).unwrap();
+        assert_eq!(os.name, "windows");
+        assert_eq!(os.major_version, 5);
+        assert_eq!(os.minor_version, 1);
+        assert_eq!(os.label(), "Microsoft Windows XP");
+        assert_eq!(match_profile(&os), "windows-xp");
+
+        assert!(parse_inspector_xml("<operatingsystems/>").is_none());
+    }
+
+    #[test]
+    fn test_match_profile() {
+        let os = |name: &str, distro: &str, product: &str, major: u32, osinfo: &str| InspectedOs {
+            name: name.to_string(),
+            distro: distro.to_string(),
+            product_name: product.to_string(),
+            major_version: major,
+            minor_version: 0,
+            osinfo: osinfo.to_string(),
+        };
+        assert_eq!(match_profile(&os("windows", "windows", "Windows 10 Pro", 10, "win11")), "windows-11");
+        assert_eq!(match_profile(&os("windows", "windows", "Windows 10 Pro", 10, "win10")), "windows-10");
+        assert_eq!(match_profile(&os("windows", "windows", "", 5, "")), "windows-2000");
+        assert_eq!(match_profile(&os("dos", "freedos", "", 1, "")), "freedos");
+        assert_eq!(match_profile(&os("dos", "msdos", "", 6, "")), "ms-dos");
+        assert_eq!(match_profile(&os("linux", "debian", "Debian 12", 12, "debian12")), "linux-debian");
+        assert_eq!(match_profile(&os("linux", "redhat-based", "Red Hat Linux release 7.3", 7, "")), "linux-redhat-7");
+        assert_eq!(match_profile(&os("linux", "unknown", "", 0, "")), "generic-linux");
+        assert_eq!(match_profile(&os("hurd", "", "", 0, "")), "generic-other");
+    }
+}
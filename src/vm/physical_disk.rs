@@ -0,0 +1,212 @@
+//! Physical disk passthrough
+//!
+//! Hands a whole host block device to the guest as an extra hard disk, so
+//! a vintage drive on a USB-IDE adapter can be booted as it is. Each disk
+//! becomes an `if=none` drive plus an explicit `ide-hd` (or
+//! `virtio-blk-pci`) device, which lets one of them carry `bootindex=0`
+//! and boot ahead of the VM's own image. Disks default to read-only; the
+//! managed section refuses to start QEMU if a device is missing.
+
+use anyhow::{bail, Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::path::Path;
+
+use crate::vm::lifecycle::{insert_args_section, remove_args_section};
+
+// Physical disk section markers
+const PHYSICAL_MARKER_START: &str = "# >>> PHYSICAL DISKS (managed by vm-curator) >>>";
+const PHYSICAL_MARKER_END: &str = "# <<< PHYSICAL DISKS <<<";
+
+static RE_PHYSICAL_DRIVE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"-drive file=([^,"]+),format=raw,if=none,id=physdisk\d+(,readonly=on)?"#)
+        .expect("Invalid regex: RE_PHYSICAL_DRIVE")
+});
+static RE_DRIVE_INDEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\bindex=(\d)").expect("Invalid regex: RE_DRIVE_INDEX"));
+
+/// A host block device attached to a VM
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhysicalDisk {
+    /// Device path, preferably under /dev/disk/by-id
+    pub path: String,
+    pub read_only: bool,
+    /// Boot from this disk instead of the VM's image
+    pub boot: bool,
+}
+
+/// Read the attached physical disks from a launch script
+pub fn parse_physical_disks(content: &str) -> Vec<PhysicalDisk> {
+    let mut disks = Vec::new();
+    let mut in_section = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed == PHYSICAL_MARKER_START {
+            in_section = true;
+        } else if trimmed == PHYSICAL_MARKER_END {
+            break;
+        } else if in_section {
+            if let Some(caps) = RE_PHYSICAL_DRIVE.captures(trimmed) {
+                disks.push(PhysicalDisk {
+                    path: caps[1].to_string(),
+                    read_only: caps.get(2).is_some(),
+                    boot: trimmed.contains("bootindex=0"),
+                });
+            }
+        }
+    }
+    disks
+}
+
+/// Write (or with an empty list, remove) the physical disk section
+///
+/// `virtio` follows the VM's own disk interface; otherwise the disks go on
+/// IDE slots the script leaves free (4 on pc, 6 AHCI ports on q35).
+pub fn set_physical_disks(script_path: &Path, disks: &[PhysicalDisk], virtio: bool, q35: bool) -> Result<()> {
+    let content = std::fs::read_to_string(script_path).context("Failed to read launch.sh")?;
+    let content = remove_physical_section(&content);
+
+    let new_content = if disks.is_empty() {
+        content
+    } else {
+        let section = generate_physical_section(&content, disks, virtio, q35)?;
+        insert_args_section(&content, &section, "$PHYSICAL_DISK_ARGS")
+    };
+
+    std::fs::write(script_path, new_content).context("Failed to write launch.sh")?;
+    Ok(())
+}
+
+/// IDE indexes already taken by the script's drives and -hdX/-cdrom options
+pub(crate) fn used_ide_indexes(content: &str) -> Vec<u8> {
+    let mut used = Vec::new();
+    for line in content.lines().filter(|l| !l.trim_start().starts_with('#')) {
+        // -cdrom is shorthand for index 2, like -hdc
+        for (opt, index) in [("-hda ", 0), ("-hdb ", 1), ("-hdc ", 2), ("-cdrom ", 2), ("-hdd ", 3)] {
+            if line.contains(opt) {
+                used.push(index);
+            }
+        }
+        let other_bus = ["if=virtio", "if=scsi", "if=pflash", "if=none", "if=floppy", "if=sd", "if=mtd"]
+            .iter()
+            .any(|b| line.contains(b));
+        if line.contains("-drive") && !other_bus {
+            if let Some(caps) = RE_DRIVE_INDEX.captures(line) {
+                used.push(caps[1].parse().unwrap_or(0));
+            }
+        }
+    }
+    used
+}
+
+fn generate_physical_section(content: &str, disks: &[PhysicalDisk], virtio: bool, q35: bool) -> Result<String> {
+    for disk in disks {
+        if disk.path.contains(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '$' | '`' | '\\' | ',')) {
+            bail!("Unsupported characters in device path: {}", disk.path);
+        }
+    }
+
+    let used = used_ide_indexes(content);
+    let slot_count = if q35 { 6 } else { 4 };
+    let mut free = (0..slot_count).filter(|i| !used.contains(i));
+
+    // One line per disk: the drive and the device that carries its boot order
+    let mut args = Vec::new();
+    for (n, disk) in disks.iter().enumerate() {
+        let readonly = if disk.read_only { ",readonly=on" } else { "" };
+        let bootindex = if disk.boot { ",bootindex=0" } else { "" };
+        let device = if virtio {
+            format!("-device virtio-blk-pci,drive=physdisk{}{}", n, bootindex)
+        } else {
+            let Some(index) = free.next() else {
+                bail!("No free IDE slot for {} (pc has 4, q35 has 6)", disk.path);
+            };
+            let (bus, unit) = if q35 { (index, 0) } else { (index / 2, index % 2) };
+            format!("-device ide-hd,drive=physdisk{},bus=ide.{},unit={}{}", n, bus, unit, bootindex)
+        };
+        args.push(format!(
+            "-drive file={},format=raw,if=none,id=physdisk{}{},cache=none {}",
+            disk.path, n, readonly, device
+        ));
+    }
+
+    let paths: Vec<String> = disks.iter().map(|d| format!("\"{}\"", d.path)).collect();
+
+    let mut section = String::new();
+    section.push_str(PHYSICAL_MARKER_START);
+    section.push('\n');
+    section.push_str(&format!("for PHYSICAL_DISK in {}; do\n", paths.join(" ")));
+    section.push_str("    if [[ ! -r \"$PHYSICAL_DISK\" ]]; then\n");
+    section.push_str("        echo \"Physical disk $PHYSICAL_DISK is not connected or not readable (are you in the disk group?)\" >&2\n");
+    section.push_str("        exit 1\n");
+    section.push_str("    fi\n");
+    section.push_str("done\n");
+    for (i, arg) in args.iter().enumerate() {
+        if i == 0 {
+            section.push_str(&format!("PHYSICAL_DISK_ARGS=\"{}\"\n", arg));
+        } else {
+            section.push_str(&format!("PHYSICAL_DISK_ARGS=\"$PHYSICAL_DISK_ARGS {}\"\n", arg));
+        }
+    }
+    section.push_str(PHYSICAL_MARKER_END);
+    section.push('\n');
+    Ok(section)
+}
+
+fn remove_physical_section(content: &str) -> String {
+    remove_args_section(content, PHYSICAL_MARKER_START, PHYSICAL_MARKER_END, "$PHYSICAL_DISK_ARGS")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = "VM_DIR=\"/vms/dos\"\nqemu-system-i386 \\\n    -drive file=\"$DISK\",format=qcow2,if=ide,index=0,media=disk \\\n    -drive file=\"$ISO\",media=cdrom,index=1 \\\n    -m 16M\n";
+
+    #[test]
+    fn test_physical_section_round_trip() {
+        let disks = vec![
+            PhysicalDisk {
+                path: "/dev/disk/by-id/usb-JMicron_IDE_Bridge-0:0".to_string(),
+                read_only: true,
+                boot: true,
+            },
+            PhysicalDisk {
+                path: "/dev/sdc".to_string(),
+                read_only: false,
+                boot: false,
+            },
+        ];
+        let section = generate_physical_section(SCRIPT, &disks, false, false).unwrap();
+        // Indexes 0 and 1 are taken, so ide.1 unit 0 then unit 1
+        assert!(section.contains("-device ide-hd,drive=physdisk0,bus=ide.1,unit=0,bootindex=0"));
+        assert!(section.contains("-device ide-hd,drive=physdisk1,bus=ide.1,unit=1\""));
+
+        let with_disks = insert_args_section(SCRIPT, &section, "$PHYSICAL_DISK_ARGS");
+        assert_eq!(parse_physical_disks(&with_disks), disks);
+        assert!(with_disks.contains("-m 16M $PHYSICAL_DISK_ARGS"));
+
+        let restored = remove_physical_section(&with_disks);
+        assert!(parse_physical_disks(&restored).is_empty());
+        assert!(!restored.contains("PHYSICAL_DISK"));
+    }
+
+    #[test]
+    fn test_physical_section_slots() {
+        let disk = |path: &str| PhysicalDisk {
+            path: path.to_string(),
+            read_only: true,
+            boot: false,
+        };
+        let three = [disk("/dev/sdb"), disk("/dev/sdc"), disk("/dev/sdd")];
+        assert!(generate_physical_section(SCRIPT, &three, false, false).is_err());
+
+        let q35 = generate_physical_section(SCRIPT, &three, false, true).unwrap();
+        assert!(q35.contains("drive=physdisk2,bus=ide.4,unit=0"));
+
+        let virtio = generate_physical_section(SCRIPT, &three[..1], true, false).unwrap();
+        assert!(virtio.contains("-device virtio-blk-pci,drive=physdisk0\""));
+
+        assert!(generate_physical_section(SCRIPT, &[disk("/dev/sdb,if=ide")], false, false).is_err());
+    }
+}
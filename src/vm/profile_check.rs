@@ -0,0 +1,90 @@
+//! Wizard choices checked against the OS profile
+//!
+//! A profile's defaults are what the OS is known to install and run with.
+//! Settings changed away from them in ways the OS can't cope with (more RAM
+//! than Windows 95 boots with, UEFI for DOS, a virtio disk for an OS
+//! without the driver) are reported before the VM is created. Nothing is
+//! blocked; the user may have a driver disk ready.
+
+use crate::app::WizardQemuConfig;
+use crate::metadata::QemuProfile;
+
+/// What the settings in `config` are likely to break for the profile's OS
+pub fn check(profile: &QemuProfile, config: &WizardQemuConfig) -> Vec<String> {
+    let os = profile.display_name.as_str();
+    let mut warnings = Vec::new();
+
+    if let Some(max) = profile.max_memory_mb.filter(|max| config.memory_mb > *max) {
+        warnings.push(format!("{} may not boot with more than {} MB of RAM", os, max));
+    }
+    if config.memory_mb < profile.memory_mb / 2 {
+        warnings.push(format!("{} MB of RAM is little for {} (it is set up with {} MB)", config.memory_mb, os, profile.memory_mb));
+    }
+    if let Some(max) = profile.max_cpu_cores.filter(|max| config.cpu_cores > *max) {
+        warnings.push(format!("{} uses only {} CPU{}", os, max, if max == 1 { "" } else { "s" }));
+    }
+
+    // 32-bit x86 OSes predate PC UEFI firmware
+    if config.uefi && !profile.uefi && profile.emulator == "qemu-system-i386" {
+        warnings.push(format!("{} can't boot from UEFI firmware; turn UEFI off", os));
+    }
+    if !config.uefi && profile.uefi {
+        warnings.push(format!("{} expects UEFI firmware; its installer may not boot with BIOS", os));
+    }
+    if !config.tpm && profile.tpm {
+        warnings.push(format!("{} requires a TPM to install", os));
+    }
+
+    // virtio devices need drivers the profile's OS doesn't ship with
+    let virtio_missing = |chosen: &str, default: &str| chosen == "virtio" && default != "virtio";
+    if virtio_missing(&config.disk_interface, &profile.disk_interface) {
+        warnings.push(format!("{} has no virtio disk driver; the installer won't find the disk without a driver disk", os));
+    }
+    if virtio_missing(&config.network_model, &profile.network_model) {
+        warnings.push(format!("{} has no virtio network driver; use {} for networking out of the box", os, profile.network_model));
+    }
+    if virtio_missing(&config.vga, &profile.vga) {
+        warnings.push(format!("{} has no virtio display driver; expect a low-resolution screen", os));
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::QemuProfileStore;
+
+    fn config_for(profile: &QemuProfile) -> WizardQemuConfig {
+        let mut state = crate::app::CreateWizardState::default();
+        state.apply_profile(profile);
+        state.qemu_config
+    }
+
+    #[test]
+    fn test_profile_defaults_pass() {
+        let store = QemuProfileStore::load_embedded();
+        for id in ["windows-95", "windows-11", "ms-dos", "linux-debian"] {
+            let profile = store.get(id).unwrap();
+            assert_eq!(check(profile, &config_for(profile)), Vec::<String>::new(), "{}", id);
+        }
+    }
+
+    #[test]
+    fn test_win95_warnings() {
+        let store = QemuProfileStore::load_embedded();
+        let win95 = store.get("windows-95").unwrap();
+        let mut config = config_for(win95);
+        config.memory_mb = 1024;
+        config.uefi = true;
+        config.disk_interface = "virtio".to_string();
+        config.network_model = "virtio".to_string();
+
+        let warnings = check(win95, &config);
+        assert_eq!(warnings.len(), 4);
+        assert!(warnings[0].contains("more than 480 MB"));
+        assert!(warnings[1].contains("UEFI"));
+        assert!(warnings[2].contains("virtio disk"));
+        assert!(warnings[3].contains("use ne2k_pci"));
+    }
+}
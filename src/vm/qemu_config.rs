@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::path::PathBuf;
 
+use crate::vm::remote_disk::RemoteDisk;
+
 /// QEMU emulator type
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum QemuEmulator {
@@ -11,6 +13,7 @@ pub enum QemuEmulator {
     M68k,
     Arm,
     Aarch64,
+    Riscv64,
     Other(String),
 }
 
@@ -23,6 +26,7 @@ impl QemuEmulator {
             "qemu-system-m68k" => Self::M68k,
             "qemu-system-arm" => Self::Arm,
             "qemu-system-aarch64" => Self::Aarch64,
+            "qemu-system-riscv64" => Self::Riscv64,
             other => Self::Other(other.to_string()),
         }
     }
@@ -35,6 +39,7 @@ impl QemuEmulator {
             Self::M68k => "qemu-system-m68k",
             Self::Arm => "qemu-system-arm",
             Self::Aarch64 => "qemu-system-aarch64",
+            Self::Riscv64 => "qemu-system-riscv64",
             Self::Other(cmd) => cmd,
         }
     }
@@ -47,6 +52,7 @@ impl QemuEmulator {
             Self::M68k => "Motorola 68k",
             Self::Arm => "ARM",
             Self::Aarch64 => "ARM64",
+            Self::Riscv64 => "RISC-V 64",
             Self::Other(_) => "Unknown",
         }
     }
@@ -79,6 +85,17 @@ impl VgaType {
     }
 }
 
+/// Parse a "WIDTHxHEIGHT" resolution string
+pub fn parse_resolution(s: &str) -> Option<(u32, u32)> {
+    let (w, h) = s.trim().split_once(['x', 'X'])?;
+    let w: u32 = w.trim().parse().ok()?;
+    let h: u32 = h.trim().parse().ok()?;
+    if w == 0 || h == 0 {
+        return None;
+    }
+    Some((w, h))
+}
+
 /// Audio device type
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AudioDevice {
@@ -87,6 +104,8 @@ pub enum AudioDevice {
     Es1370,
     Hda,
     PcSpk,
+    AdLib,
+    Gus,
     Other(String),
 }
 
@@ -99,6 +118,8 @@ impl AudioDevice {
             "es1370" => Self::Es1370,
             "hda" | "intel-hda" => Self::Hda,
             "pcspk" => Self::PcSpk,
+            "adlib" => Self::AdLib,
+            "gus" => Self::Gus,
             other => Self::Other(other.to_string()),
         }
     }
@@ -113,6 +134,8 @@ pub enum NetworkBackend {
     Passt,
     /// Bridge networking via qemu-bridge-helper
     Bridge(String),
+    /// Persistent tap device opened directly (no helper)
+    Tap(String),
     /// No networking
     None,
 }
@@ -129,6 +152,7 @@ impl fmt::Display for NetworkBackend {
             Self::User => write!(f, "user"),
             Self::Passt => write!(f, "passt"),
             Self::Bridge(name) => write!(f, "bridge:{}", name),
+            Self::Tap(name) => write!(f, "tap:{}", name),
             Self::None => write!(f, "none"),
         }
     }
@@ -174,6 +198,9 @@ pub struct NetworkConfig {
     #[serde(default = "default_true")]
     pub user_net: bool,
     pub bridge: Option<String>,
+    /// SLIRP's built-in SMB server exports the VM's share folder (user backend)
+    #[serde(default)]
+    pub smb_share: bool,
 }
 
 fn default_true() -> bool {
@@ -188,6 +215,7 @@ impl Default for NetworkConfig {
             port_forwards: Vec::new(),
             user_net: true,
             bridge: None,
+            smb_share: false,
         }
     }
 }
@@ -197,8 +225,16 @@ impl Default for NetworkConfig {
 pub enum DiskFormat {
     Qcow2,
     Raw,
+    /// VMware
     Vmdk,
+    /// VirtualBox
     Vdi,
+    /// Hyper-V
+    Vhdx,
+    /// Virtual PC / Hyper-V (.vhd)
+    Vpc,
+    /// QEMU Enhanced Disk, qcow2's short-lived successor
+    Qed,
     Other(String),
 }
 
@@ -209,21 +245,90 @@ impl DiskFormat {
             "raw" | "img" => Self::Raw,
             "vmdk" => Self::Vmdk,
             "vdi" => Self::Vdi,
+            "vhdx" => Self::Vhdx,
+            "vhd" => Self::Vpc,
+            "qed" => Self::Qed,
+            other => Self::Other(other.to_string()),
+        }
+    }
+
+    /// From a QEMU format name, as in `format=` or `qemu-img info`
+    pub fn from_qemu_name(name: &str) -> Self {
+        match name.to_lowercase().as_str() {
+            "qcow2" => Self::Qcow2,
+            "raw" => Self::Raw,
+            "vmdk" => Self::Vmdk,
+            "vdi" => Self::Vdi,
+            "vhdx" => Self::Vhdx,
+            "vpc" => Self::Vpc,
+            "qed" => Self::Qed,
             other => Self::Other(other.to_string()),
         }
     }
 
+    /// QEMU format name
+    pub fn qemu_name(&self) -> &str {
+        match self {
+            Self::Qcow2 => "qcow2",
+            Self::Raw => "raw",
+            Self::Vmdk => "vmdk",
+            Self::Vdi => "vdi",
+            Self::Vhdx => "vhdx",
+            Self::Vpc => "vpc",
+            Self::Qed => "qed",
+            Self::Other(other) => other.as_str(),
+        }
+    }
+
+    /// Name for people
+    pub fn label(&self) -> &str {
+        match self {
+            Self::Qcow2 => "qcow2",
+            Self::Raw => "raw",
+            Self::Vmdk => "VMDK",
+            Self::Vdi => "VDI",
+            Self::Vhdx => "VHDX",
+            Self::Vpc => "VHD",
+            Self::Qed => "QED",
+            Self::Other(other) => other.as_str(),
+        }
+    }
+
+    /// Internal snapshots (`qemu-img snapshot`) only exist in qcow2
     pub fn supports_snapshots(&self) -> bool {
         matches!(self, Self::Qcow2)
     }
+
+    /// Whether the image can be an overlay on a backing image
+    pub fn supports_backing_file(&self) -> bool {
+        matches!(self, Self::Qcow2 | Self::Qed | Self::Vmdk)
+    }
+
+    /// What the format lacks next to qcow2, as a reason to convert
+    pub fn limitation(&self) -> Option<String> {
+        let reason = match self {
+            Self::Qcow2 => return None,
+            Self::Raw => "Raw images have no snapshots or compression",
+            Self::Vmdk => "QEMU can't take snapshots of VMDK (VMware) images",
+            Self::Vdi => "QEMU can't take snapshots of VDI (VirtualBox) images",
+            Self::Vhdx | Self::Vpc => "QEMU can't take snapshots of Hyper-V and Virtual PC images",
+            Self::Qed => "QED was dropped in favour of qcow2 and has no snapshots",
+            Self::Other(other) => return Some(format!("QEMU can't take snapshots of {} images", other)),
+        };
+        Some(reason.to_string())
+    }
 }
 
 /// Disk configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DiskConfig {
+    /// Local image, or the URL itself for remote disks
     pub path: PathBuf,
     pub format: DiskFormat,
     pub interface: String,
+    /// NBD/iSCSI source when the disk lives on a storage server
+    #[serde(default)]
+    pub remote: Option<RemoteDisk>,
 }
 
 /// Boot mode
@@ -234,6 +339,8 @@ pub enum BootMode {
     Install,
     Cdrom(PathBuf),
     Network,
+    /// Load the state saved when the VM was suspended
+    Resume,
 }
 
 /// QEMU configuration extracted from launch.sh
@@ -245,14 +352,48 @@ pub struct QemuConfig {
     pub cpu_model: Option<String>,
     pub machine: Option<String>,
     pub vga: VgaType,
+    /// virtio-gpu 3D mode ("virgl" or "egl-headless"); None when off
+    pub gpu_accel: Option<String>,
+    /// Guest resolution hint from xres/yres (e.g., "800x600")
+    pub resolution: Option<String>,
     pub audio_devices: Vec<AudioDevice>,
+    /// Host audio driver from `-audiodev` (pa, pipewire, alsa, ...)
+    pub audio_backend: Option<String>,
+    /// Sound Blaster 16 port/IRQ/DMA, when the VM has one
+    pub sb16: Option<crate::vm::sound_blaster::Sb16Settings>,
     pub network: Option<NetworkConfig>,
     pub disks: Vec<DiskConfig>,
     pub boot_mode: BootMode,
     pub enable_kvm: bool,
     pub uefi: bool,
+    /// UEFI firmware runs with Secure Boot (secure pflash)
+    pub secure_boot: bool,
     pub tpm: bool,
+    /// Explicit USB controller (uhci, ehci, xhci); None for the machine default
+    pub usb_controller: Option<String>,
     pub extra_args: Vec<String>,
+    /// Frozen RTC date when the VM is locked in archival mode
+    pub archival_date: Option<String>,
+    /// Whether the script exposes a QEMU guest agent channel
+    pub guest_agent: bool,
+    /// Active CPU era preset id (masks modern CPU features)
+    pub cpu_era: Option<String>,
+    /// Looking Glass IVSHMEM shared memory (managed section)
+    pub looking_glass: Option<crate::vm::looking_glass::LookingGlassSettings>,
+    /// Host synth receiving the guest's serial MIDI (managed section)
+    pub midi_synth: Option<String>,
+    /// Host evdev devices handed to the guest (managed section)
+    pub input_passthrough: Option<crate::vm::input_passthrough::InputPassthrough>,
+    /// Window scaling and full screen (managed section)
+    pub display_preset: Option<crate::vm::display_preset::DisplayPreset>,
+    /// System UUID and NIC MAC (managed section)
+    pub identity: Option<crate::vm::identity::VmIdentity>,
+    /// SPICE channels and viewer mode
+    pub spice: Option<crate::vm::spice::SpiceConfig>,
+    /// VNC display number (port 5900 + N) when using the VNC display
+    pub vnc_display: Option<u16>,
+    /// Boots the install ISO by default until the OS is installed (managed section)
+    pub install_pending: bool,
     pub raw_script: String,
 }
 
@@ -265,27 +406,44 @@ impl Default for QemuConfig {
             cpu_model: None,
             machine: None,
             vga: VgaType::default(),
+            gpu_accel: None,
+            resolution: None,
             audio_devices: Vec::new(),
+            audio_backend: None,
+            sb16: None,
             network: Some(NetworkConfig::default()),
             disks: Vec::new(),
             boot_mode: BootMode::default(),
             enable_kvm: false,
             uefi: false,
+            secure_boot: false,
             tpm: false,
+            usb_controller: None,
             extra_args: Vec::new(),
+            archival_date: None,
+            guest_agent: false,
+            cpu_era: None,
+            looking_glass: None,
+            midi_synth: None,
+            input_passthrough: None,
+            display_preset: None,
+            identity: None,
+            spice: None,
+            vnc_display: None,
+            install_pending: false,
             raw_script: String::new(),
         }
     }
 }
 
 impl QemuConfig {
-    /// Check if this VM supports snapshots (qcow2 disks)
+    /// Check if this VM supports snapshots (local qcow2 disks)
     pub fn supports_snapshots(&self) -> bool {
-        self.disks.iter().any(|d| d.format.supports_snapshots())
+        self.disks.iter().any(|d| d.remote.is_none() && d.format.supports_snapshots())
     }
 
-    /// Get the primary disk for snapshot operations
+    /// Get the primary disk for snapshot operations (remote disks are skipped)
     pub fn primary_disk(&self) -> Option<&DiskConfig> {
-        self.disks.first()
+        self.disks.iter().find(|d| d.remote.is_none())
     }
 }
@@ -0,0 +1,140 @@
+//! Output of each launch
+//!
+//! Everything the launch script and QEMU print goes to `qemu.log` in the
+//! VM folder, so messages like "could not load PC BIOS" are still there
+//! once the window is gone. Each launch starts a fresh log; the one before
+//! it is kept as `qemu.log.1`.
+
+use anyhow::{Context, Result};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Log of the latest launch, in the VM folder
+pub const LOG_FILE: &str = "qemu.log";
+
+/// Log of the launch before it
+pub const PREVIOUS_LOG_FILE: &str = "qemu.log.1";
+
+/// Logs longer than this are only read from the end
+const MAX_READ_BYTES: u64 = 1024 * 1024;
+
+pub fn log_path(vm_dir: &Path) -> PathBuf {
+    vm_dir.join(LOG_FILE)
+}
+
+pub fn previous_log_path(vm_dir: &Path) -> PathBuf {
+    vm_dir.join(PREVIOUS_LOG_FILE)
+}
+
+/// Start the log of a new launch, keeping the previous one, and return it
+/// opened for appending (stdout and stderr each get a handle)
+pub fn start(vm_dir: &Path, args: &[String]) -> Result<File> {
+    let path = log_path(vm_dir);
+    if path.exists() {
+        let _ = std::fs::rename(&path, previous_log_path(vm_dir));
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    writeln!(
+        file,
+        "=== {} bash {} ===",
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+        args.join(" ")
+    )?;
+    Ok(file)
+}
+
+/// Lines of a log, without the launch header (the last megabyte of very
+/// long logs)
+pub fn read_lines(path: &Path) -> Vec<String> {
+    let Ok(bytes) = read_tail(path) else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&bytes)
+        .lines()
+        .filter(|line| !(line.starts_with("=== ") && line.ends_with(" ===")))
+        .map(|line| line.trim_end_matches('\r').to_string())
+        .collect()
+}
+
+fn read_tail(path: &Path) -> std::io::Result<Vec<u8>> {
+    use std::io::{Read, Seek, SeekFrom};
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let start = len.saturating_sub(MAX_READ_BYTES);
+    file.seek(SeekFrom::Start(start))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    if start > 0 {
+        // Drop the partial first line
+        let cut = bytes.iter().position(|b| *b == b'\n').map_or(0, |i| i + 1);
+        bytes.drain(..cut);
+    }
+    Ok(bytes)
+}
+
+/// Words that mark a line as an error
+const ERROR_MARKERS: &[&str] = &[
+    "error",
+    "failed",
+    "cannot",
+    "unable",
+    "not found",
+    "no such",
+    "invalid",
+    "is not a valid",
+    "could not",
+    "qemu-system",
+    "permission denied",
+];
+
+pub fn is_error_line(line: &str) -> bool {
+    let lower = line.to_lowercase();
+    ERROR_MARKERS.iter().any(|marker| lower.contains(marker))
+}
+
+/// Lines worth showing when a launch fails: errors if there are any,
+/// otherwise everything that was printed
+pub fn error_lines(lines: &[String]) -> Vec<String> {
+    let lines: Vec<&String> = lines.iter().filter(|line| !line.trim().is_empty()).collect();
+    let errors: Vec<String> = lines
+        .iter()
+        .filter(|line| is_error_line(line))
+        .map(|line| line.to_string())
+        .collect();
+    if errors.is_empty() {
+        lines.into_iter().cloned().collect()
+    } else {
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_rotation_and_errors() {
+        let dir = std::env::temp_dir().join(format!("vm-curator-qemu-log-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut first = start(&dir, &["launch.sh".to_string()]).unwrap();
+        writeln!(first, "first boot").unwrap();
+        let mut second = start(&dir, &["launch.sh".to_string(), "--install".to_string()]).unwrap();
+        writeln!(second, "\nqemu-system-i386: -bios foo.bin: could not load PC BIOS 'foo.bin'\nVNC server running").unwrap();
+
+        assert_eq!(read_lines(&previous_log_path(&dir)), vec!["first boot"]);
+        let lines = read_lines(&log_path(&dir));
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            error_lines(&lines),
+            vec!["qemu-system-i386: -bios foo.bin: could not load PC BIOS 'foo.bin'"]
+        );
+        assert_eq!(error_lines(&["VNC server running".to_string()]), vec!["VNC server running"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,518 @@
+//! QEMU Machine Protocol (QMP) client
+//!
+//! Every VM's launch script opens a QMP control socket in the VM directory
+//! (a managed section added at creation, or by the schema 3 library
+//! migration). vm-curator uses it to hot-plug host USB devices into a
+//! running VM, to list the USB devices a VM currently holds, to shut
+//! down and live-migrate VMs on remote hosts through an SSH-forwarded
+//! socket, and to save a suspended VM's state to disk.
+//!
+//! QEMU serves one client per QMP socket, so the section opens a second
+//! socket that vm-curator holds for as long as the VM runs, following its
+//! events (the guest powering off, resets, disk errors, panics).
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::vm::lifecycle::insert_args_section;
+use crate::vm::local_socket::LocalStream;
+
+// QMP section markers
+const QMP_MARKER_START: &str = "# >>> QMP (managed by vm-curator) >>>";
+const QMP_MARKER_END: &str = "# <<< QMP <<<";
+
+/// File name of the QMP socket inside the VM directory
+const SOCKET_NAME: &str = "qmp.sock";
+
+/// File name of the QMP socket held open for events
+const EVENTS_SOCKET_NAME: &str = "qmp-events.sock";
+
+/// How long to wait for QEMU to answer
+const QMP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// QOM containers holding user-created devices (with and without an id)
+const PERIPHERAL_CONTAINERS: &[&str] = &["/machine/peripheral", "/machine/peripheral-anon"];
+
+/// A host USB device attached to a running VM
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttachedUsb {
+    /// QOM path, accepted by `device_del`
+    pub qom_path: String,
+    pub vendor_id: u16,
+    pub product_id: u16,
+    /// Host bus and address, when the device was attached by port
+    pub host_bus: u32,
+    pub host_addr: u32,
+}
+
+impl AttachedUsb {
+    /// Whether this attachment refers to the given host device
+    pub fn matches(&self, vendor_id: u16, product_id: u16, bus: u8, addr: u8) -> bool {
+        if self.host_bus != 0 && self.host_addr != 0 {
+            return self.host_bus == u32::from(bus) && self.host_addr == u32::from(addr);
+        }
+        self.vendor_id == vendor_id && self.product_id == product_id
+    }
+}
+
+/// Path of the QMP socket for a VM
+pub fn socket_path(vm_dir: &Path) -> PathBuf {
+    vm_dir.join(SOCKET_NAME)
+}
+
+/// Path of the event socket for a VM
+pub fn events_socket_path(vm_dir: &Path) -> PathBuf {
+    vm_dir.join(EVENTS_SOCKET_NAME)
+}
+
+/// Remove the sockets of a QEMU that exited without closing them
+///
+/// Sockets that still accept connections belong to a running QEMU and stay.
+pub fn remove_stale_sockets(vm_dir: &Path) {
+    for socket in [socket_path(vm_dir), events_socket_path(vm_dir)] {
+        if socket.exists() && LocalStream::connect(&socket).is_err() {
+            let _ = std::fs::remove_file(&socket);
+        }
+    }
+}
+
+/// Whether a launch script opens the QMP socket
+pub fn is_configured(content: &str) -> bool {
+    content.contains(QMP_MARKER_START)
+}
+
+/// Whether a launch script also opens the event socket
+pub fn has_event_socket(content: &str) -> bool {
+    is_configured(content) && content.contains(EVENTS_SOCKET_NAME)
+}
+
+/// Add the QMP section to a launch script's content, or the event socket
+/// to a section written before it existed (no-op if both are present)
+pub fn add_qmp_section(content: &str) -> String {
+    if has_event_socket(content) {
+        return content.to_string();
+    }
+    if is_configured(content) {
+        let mut result = String::new();
+        for line in content.lines() {
+            if line.trim_start().starts_with("QMP_ARGS=") {
+                result.push_str(&qmp_args_line());
+            } else {
+                result.push_str(line);
+            }
+            result.push('\n');
+        }
+        return result;
+    }
+    insert_args_section(content, &generate_qmp_section(), "$QMP_ARGS")
+}
+
+/// An event from a running VM
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QmpEvent {
+    /// QEMU is shutting down; `guest` when the guest powered itself off
+    Shutdown { guest: bool, reason: String },
+    /// The machine was reset; `guest` when the guest rebooted itself
+    Reset { guest: bool },
+    /// A disk read or write failed
+    BlockIoError { device: String, operation: String, nospace: bool },
+    /// The guest OS crashed (reported through a pvpanic device)
+    GuestPanicked,
+}
+
+/// The event in a QMP message, if it is one we follow
+fn parse_event(message: &Value) -> Option<QmpEvent> {
+    let data = message.get("data").cloned().unwrap_or(Value::Null);
+    let text = |key: &str| data.get(key).and_then(Value::as_str).unwrap_or_default().to_string();
+    let guest = data.get("guest").and_then(Value::as_bool).unwrap_or(false);
+    match message.get("event")?.as_str()? {
+        "SHUTDOWN" => Some(QmpEvent::Shutdown { guest, reason: text("reason") }),
+        "RESET" => Some(QmpEvent::Reset { guest }),
+        "BLOCK_IO_ERROR" => {
+            // Older QEMU names the drive, newer the node, depending on setup
+            let device = [text("device"), text("qom-path"), text("node-name")]
+                .into_iter()
+                .find(|name| !name.is_empty())
+                .unwrap_or_else(|| "a disk".to_string());
+            Some(QmpEvent::BlockIoError {
+                device,
+                operation: text("operation"),
+                nospace: data.get("nospace").and_then(Value::as_bool).unwrap_or(false),
+            })
+        }
+        "GUEST_PANICKED" => Some(QmpEvent::GuestPanicked),
+        _ => None,
+    }
+}
+
+/// Follow a running VM's events until QEMU exits
+///
+/// Blocks; fails only when the event socket can't be opened.
+pub fn watch_events<F>(vm_dir: &Path, mut on_event: F) -> Result<()>
+where
+    F: FnMut(QmpEvent),
+{
+    let mut conn = QmpConnection::open_socket(&events_socket_path(vm_dir))?;
+    conn.writer.set_read_timeout(None)?;
+    // Closed by QEMU when the VM exits
+    while let Ok(message) = conn.read_message() {
+        if let Some(event) = parse_event(&message) {
+            on_event(event);
+        }
+    }
+    Ok(())
+}
+
+/// A connection to a running VM's QMP socket
+struct QmpConnection {
+    writer: LocalStream,
+    reader: BufReader<LocalStream>,
+}
+
+impl QmpConnection {
+    /// Connect, read the greeting and leave capabilities negotiation mode
+    fn open(vm_dir: &Path) -> Result<Self> {
+        Self::open_socket(&socket_path(vm_dir))
+    }
+
+    fn open_socket(path: &Path) -> Result<Self> {
+        let stream = LocalStream::connect(path).with_context(|| {
+            format!("QMP socket not available: {} (restart the VM to enable it)", path.display())
+        })?;
+        stream.set_read_timeout(Some(QMP_TIMEOUT))?;
+        stream.set_write_timeout(Some(QMP_TIMEOUT))?;
+
+        let reader = BufReader::new(stream.try_clone()?);
+        let mut conn = Self { writer: stream, reader };
+
+        let greeting = conn.read_message().context("QEMU did not send a QMP greeting")?;
+        if greeting.get("QMP").is_none() {
+            bail!("Unexpected QMP greeting");
+        }
+        conn.execute("qmp_capabilities", json!({}))?;
+
+        Ok(conn)
+    }
+
+    fn read_message(&mut self) -> Result<Value> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            bail!("QEMU closed the QMP connection");
+        }
+        serde_json::from_str(line.trim()).context("Invalid QMP message")
+    }
+
+    /// Run a command and return its `return` value, skipping async events
+    fn execute(&mut self, command: &str, arguments: Value) -> Result<Value> {
+        let mut line = json!({"execute": command, "arguments": arguments}).to_string();
+        line.push('\n');
+        self.writer.write_all(line.as_bytes())?;
+        self.writer.flush()?;
+
+        loop {
+            let reply = self.read_message()?;
+            if reply.get("event").is_some() {
+                continue;
+            }
+            if let Some(error) = reply.get("error") {
+                let desc = error.get("desc").and_then(Value::as_str).unwrap_or("unknown error");
+                bail!("{}", desc);
+            }
+            return Ok(reply.get("return").cloned().unwrap_or(Value::Null));
+        }
+    }
+}
+
+/// Hot-plug a host USB device (by bus and address) into a running VM
+///
+/// Returns the id given to the new device.
+pub fn attach_usb(vm_dir: &Path, bus: u8, addr: u8) -> Result<String> {
+    let mut conn = QmpConnection::open(vm_dir)?;
+    let id = format!("vmc-usb-{}-{}", bus, addr);
+    conn.execute(
+        "device_add",
+        json!({"driver": "usb-host", "id": id, "hostbus": bus, "hostaddr": addr}),
+    )
+    .context("Failed to attach USB device")?;
+    Ok(id)
+}
+
+/// Unplug a USB device from a running VM
+pub fn detach_usb(vm_dir: &Path, device: &AttachedUsb) -> Result<()> {
+    let mut conn = QmpConnection::open(vm_dir)?;
+    conn.execute("device_del", json!({"id": device.qom_path}))
+        .context("Failed to detach USB device")?;
+    Ok(())
+}
+
+/// Press the VM's power button (ACPI shutdown request)
+pub fn system_powerdown(vm_dir: &Path) -> Result<()> {
+    let mut conn = QmpConnection::open(vm_dir)?;
+    conn.execute("system_powerdown", json!({}))
+        .context("Failed to send the shutdown request")?;
+    Ok(())
+}
+
+/// End QEMU at once, as if its window were closed
+pub fn quit(vm_dir: &Path) -> Result<()> {
+    let mut conn = QmpConnection::open(vm_dir)?;
+    // QEMU may exit before it answers
+    let _ = conn.execute("quit", json!({}));
+    Ok(())
+}
+
+/// Save the VM's screen to a PPM file
+pub fn screendump(vm_dir: &Path, path: &Path) -> Result<()> {
+    let mut conn = QmpConnection::open(vm_dir)?;
+    conn.execute("screendump", json!({ "filename": path.to_string_lossy() }))
+        .context("Failed to capture the screen")?;
+    Ok(())
+}
+
+/// Live-migrate a running VM to a QEMU waiting with `-incoming`
+///
+/// `start_destination` starts that QEMU and returns the URI to send the
+/// VM to. It runs after connecting, since a destination sharing the VM
+/// directory replaces the socket file. Reports progress (0.0 - 1.0) from
+/// the RAM sent so far, and once the destination has taken over, quits
+/// the source QEMU.
+pub fn migrate<S, F>(vm_dir: &Path, start_destination: S, mut on_progress: F) -> Result<()>
+where
+    S: FnOnce() -> Result<String>,
+    F: FnMut(f32),
+{
+    let mut conn = QmpConnection::open(vm_dir)?;
+    let uri = start_destination()?;
+    conn.execute("migrate", json!({"uri": uri}))
+        .context("Failed to start the migration")?;
+    wait_for_migration(&mut conn, &mut on_progress)?;
+
+    // The source is left paused; the guest now runs on the destination
+    let _ = conn.execute("quit", json!({}));
+    Ok(())
+}
+
+/// Save a running VM's whole state by migrating it to `uri` (an `exec:`
+/// command writing a file), then quit it
+///
+/// The guest keeps running while most of its RAM is written and is
+/// paused for the last pass. If saving fails it carries on running.
+pub fn save_state<F>(vm_dir: &Path, uri: &str, mut on_progress: F) -> Result<()>
+where
+    F: FnMut(f32),
+{
+    let mut conn = QmpConnection::open(vm_dir)?;
+    conn.execute("migrate", json!({"uri": uri}))
+        .context("Failed to start saving the VM state")?;
+    wait_for_migration(&mut conn, &mut on_progress)?;
+
+    let _ = conn.execute("quit", json!({}));
+    Ok(())
+}
+
+/// The VM's run state (`running`, `paused`, `inmigrate`, ...)
+pub fn query_status(vm_dir: &Path) -> Result<String> {
+    let mut conn = QmpConnection::open(vm_dir)?;
+    let status = conn.execute("query-status", json!({}))?;
+    Ok(status.get("status").and_then(Value::as_str).unwrap_or("unknown").to_string())
+}
+
+/// Bytes read and written by a running VM's disks since it started
+pub fn block_stats(vm_dir: &Path) -> Result<(u64, u64)> {
+    let mut conn = QmpConnection::open(vm_dir)?;
+    let reply = conn.execute("query-blockstats", json!({}))?;
+    Ok(sum_block_stats(&reply))
+}
+
+/// Total `rd_bytes` and `wr_bytes` of the devices in a `query-blockstats`
+/// reply (the nested image layers repeat the same traffic)
+fn sum_block_stats(reply: &Value) -> (u64, u64) {
+    reply
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|device| device.get("stats"))
+        .fold((0, 0), |(read, written), stats| {
+            let get = |key: &str| stats.get(key).and_then(Value::as_u64).unwrap_or(0);
+            (read + get("rd_bytes"), written + get("wr_bytes"))
+        })
+}
+
+/// Poll an outgoing migration until it completes
+fn wait_for_migration<F>(conn: &mut QmpConnection, on_progress: &mut F) -> Result<()>
+where
+    F: FnMut(f32),
+{
+    loop {
+        std::thread::sleep(Duration::from_millis(500));
+        let info = conn.execute("query-migrate", json!({}))?;
+        match info.get("status").and_then(Value::as_str).unwrap_or("") {
+            "completed" => break,
+            "failed" | "cancelled" => {
+                let reason = info.get("error-desc").and_then(Value::as_str).unwrap_or("migration failed");
+                bail!("{}", reason);
+            }
+            _ => {
+                if let Some(progress) = ram_progress(&info) {
+                    on_progress(progress);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Fraction of the guest's RAM sent, from a `query-migrate` reply
+fn ram_progress(info: &Value) -> Option<f32> {
+    let ram = info.get("ram")?;
+    let total = ram.get("total")?.as_u64()?;
+    let remaining = ram.get("remaining")?.as_u64()?;
+    (total > 0).then(|| 1.0 - remaining as f32 / total as f32)
+}
+
+/// List the host USB devices a running VM currently holds
+pub fn attached_usb(vm_dir: &Path) -> Result<Vec<AttachedUsb>> {
+    let mut conn = QmpConnection::open(vm_dir)?;
+    let mut devices = Vec::new();
+
+    for container in PERIPHERAL_CONTAINERS {
+        let Ok(children) = conn.execute("qom-list", json!({"path": container})) else {
+            continue;
+        };
+        for name in usb_host_children(&children) {
+            let qom_path = format!("{}/{}", container, name);
+            let mut get = |property: &str| -> u64 {
+                conn.execute("qom-get", json!({"path": qom_path, "property": property}))
+                    .ok()
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(0)
+            };
+            let vendor_id = get("vendorid") as u16;
+            let product_id = get("productid") as u16;
+            let host_bus = get("hostbus") as u32;
+            let host_addr = get("hostaddr") as u32;
+            devices.push(AttachedUsb {
+                qom_path,
+                vendor_id,
+                product_id,
+                host_bus,
+                host_addr,
+            });
+        }
+    }
+
+    Ok(devices)
+}
+
+/// Names of `usb-host` devices in a `qom-list` reply
+fn usb_host_children(reply: &Value) -> Vec<String> {
+    reply
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|p| p.get("type").and_then(Value::as_str) == Some("child<usb-host>"))
+        .filter_map(|p| p.get("name").and_then(Value::as_str).map(String::from))
+        .collect()
+}
+
+fn qmp_args_line() -> String {
+    format!(
+        "QMP_ARGS=\"-qmp unix:$VM_DIR/{},server=on,wait=off -qmp unix:$VM_DIR/{},server=on,wait=off\"",
+        SOCKET_NAME, EVENTS_SOCKET_NAME
+    )
+}
+
+fn generate_qmp_section() -> String {
+    let mut section = String::new();
+    section.push_str(QMP_MARKER_START);
+    section.push('\n');
+    section.push_str(&qmp_args_line());
+    section.push('\n');
+    section.push_str(QMP_MARKER_END);
+    section.push('\n');
+    section
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_qmp_section_once() {
+        let script = "VM_DIR=\"/vms/t\"\ncase \"$1\" in\n    \"\")\n        qemu-system-x86_64 \\\n            -m 512M\n        ;;\nesac\n";
+        let with_qmp = add_qmp_section(script);
+
+        assert!(is_configured(&with_qmp));
+        assert!(has_event_socket(&with_qmp));
+        assert!(with_qmp.contains(
+            "QMP_ARGS=\"-qmp unix:$VM_DIR/qmp.sock,server=on,wait=off -qmp unix:$VM_DIR/qmp-events.sock,server=on,wait=off\""
+        ));
+        assert!(with_qmp.contains("-m 512M $QMP_ARGS"));
+        assert_eq!(add_qmp_section(&with_qmp), with_qmp);
+
+        // A section from before the event socket gains it
+        let old = with_qmp.replace(" -qmp unix:$VM_DIR/qmp-events.sock,server=on,wait=off", "");
+        assert!(is_configured(&old) && !has_event_socket(&old));
+        assert_eq!(add_qmp_section(&old), with_qmp);
+    }
+
+    #[test]
+    fn test_parse_event() {
+        let shutdown = json!({"event": "SHUTDOWN", "data": {"guest": true, "reason": "guest-shutdown"},
+                              "timestamp": {"seconds": 1, "microseconds": 0}});
+        assert_eq!(parse_event(&shutdown), Some(QmpEvent::Shutdown { guest: true, reason: "guest-shutdown".to_string() }));
+        let error = json!({"event": "BLOCK_IO_ERROR",
+                           "data": {"device": "", "node-name": "#block152", "operation": "write", "action": "stop", "nospace": true}});
+        assert_eq!(
+            parse_event(&error),
+            Some(QmpEvent::BlockIoError { device: "#block152".to_string(), operation: "write".to_string(), nospace: true })
+        );
+        assert_eq!(parse_event(&json!({"event": "GUEST_PANICKED", "data": {"action": "pause"}})), Some(QmpEvent::GuestPanicked));
+        assert_eq!(parse_event(&json!({"event": "RTC_CHANGE", "data": {"offset": 1}})), None);
+        assert_eq!(parse_event(&json!({"return": {}})), None);
+    }
+
+    #[test]
+    fn test_usb_host_children_and_matching() {
+        let reply = json!([
+            {"name": "type", "type": "string"},
+            {"name": "vmc-usb-3-7", "type": "child<usb-host>"},
+            {"name": "qga0", "type": "child<virtserialport>"}
+        ]);
+        assert_eq!(usb_host_children(&reply), vec!["vmc-usb-3-7".to_string()]);
+
+        let by_port = AttachedUsb {
+            qom_path: "/machine/peripheral/vmc-usb-3-7".to_string(),
+            vendor_id: 0,
+            product_id: 0,
+            host_bus: 3,
+            host_addr: 7,
+        };
+        assert!(by_port.matches(0x046d, 0xc52b, 3, 7));
+        assert!(!by_port.matches(0x046d, 0xc52b, 3, 8));
+
+        let by_id = AttachedUsb { host_bus: 0, host_addr: 0, vendor_id: 0x046d, product_id: 0xc52b, ..by_port };
+        assert!(by_id.matches(0x046d, 0xc52b, 1, 2));
+    }
+
+    #[test]
+    fn test_ram_progress() {
+        let info = json!({"status": "active", "ram": {"total": 1000, "remaining": 250, "transferred": 800}});
+        assert_eq!(ram_progress(&info), Some(0.75));
+        assert_eq!(ram_progress(&json!({"status": "setup"})), None);
+    }
+
+    #[test]
+    fn test_sum_block_stats() {
+        let reply = json!([
+            {"device": "", "qdev": "ide0", "stats": {"rd_bytes": 4096, "wr_bytes": 512},
+             "parent": {"stats": {"rd_bytes": 4096, "wr_bytes": 512}}},
+            {"device": "", "qdev": "cd0", "stats": {"rd_bytes": 2048, "wr_bytes": 0}}
+        ]);
+        assert_eq!(sum_block_stats(&reply), (6144, 512));
+        assert_eq!(sum_block_stats(&json!([])), (0, 0));
+    }
+}
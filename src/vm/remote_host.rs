@@ -0,0 +1,330 @@
+//! VMs on remote hosts
+//!
+//! A remote host keeps its own VM library, laid out like the local one.
+//! Everything runs there over SSH: discovery reads the launch scripts,
+//! launches start `launch.sh` detached in the VM directory, and process
+//! status comes from the remote `/proc`. QMP and SPICE sockets and VNC
+//! ports are forwarded into a local runtime directory, so shutting down
+//! and viewing work as they do for local VMs. Only QEMU VMs are listed.
+
+use anyhow::{bail, Context, Result};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+
+use super::backend::Backend;
+use super::discovery::{parse_vm_metadata, DiscoveredVm};
+use super::launch_parser::parse_launch_script;
+use super::lifecycle::{LaunchOptions, LaunchResult, QemuProcess};
+use super::qemu_config::{BootMode, QemuConfig};
+use super::spice::SpiceMode;
+use crate::commands::ssh;
+use crate::config::RemoteHost;
+use crate::vm::create::shell_escape;
+
+/// Separates VM records in the discovery listing
+const RECORD_SEPARATOR: char = '\x1e';
+/// Separates a VM's launch script from its vm-curator.toml
+const FIELD_SEPARATOR: char = '\x1f';
+
+/// Log of the last launch, kept in the remote VM directory
+const LAUNCH_LOG: &str = ".vm-curator-launch.log";
+
+/// The library path as a shell word (relative paths start at the remote home)
+fn library_arg(host: &RemoteHost) -> String {
+    let path = host.library_path.trim();
+    let path = path.strip_prefix("~/").unwrap_or(path);
+    if path.is_empty() || path == "~" {
+        ".".to_string()
+    } else {
+        shell_escape(path)
+    }
+}
+
+/// List the VMs in a host's library
+pub fn discover(host: &RemoteHost) -> Result<Vec<DiscoveredVm>> {
+    let script = format!(
+        "cd {} 2>/dev/null || exit 0\n\
+         for dir in */; do\n\
+         \x20   [ -f \"$dir/launch.sh\" ] || continue\n\
+         \x20   printf '\\036%s\\n' \"$PWD/${{dir%/}}\"\n\
+         \x20   cat \"$dir/launch.sh\"\n\
+         \x20   printf '\\037'\n\
+         \x20   cat \"$dir/vm-curator.toml\" 2>/dev/null\n\
+         done\n",
+        library_arg(host)
+    );
+    let listing = ssh::run_script(host, &script)?;
+    Ok(parse_listing(host, &listing))
+}
+
+/// List the VMs of every host, with an error message for each unreachable one
+pub fn discover_all(hosts: &[RemoteHost]) -> (Vec<DiscoveredVm>, Vec<String>) {
+    let mut vms = Vec::new();
+    let mut errors = Vec::new();
+    for host in hosts {
+        match discover(host) {
+            Ok(found) => vms.extend(found),
+            Err(e) => errors.push(e.to_string()),
+        }
+    }
+    (vms, errors)
+}
+
+fn parse_listing(host: &RemoteHost, listing: &str) -> Vec<DiscoveredVm> {
+    listing
+        .split(RECORD_SEPARATOR)
+        .skip(1)
+        .filter_map(|record| {
+            let (dir, rest) = record.split_once('\n')?;
+            let (script, metadata) = rest.split_once(FIELD_SEPARATOR)?;
+            if Backend::detect(script) != Backend::Qemu {
+                return None;
+            }
+
+            let path = PathBuf::from(dir);
+            let name = path.file_name()?.to_str()?.to_string();
+            let launch_script = path.join("launch.sh");
+            let config = parse_launch_script(&launch_script, script).unwrap_or_else(|_| QemuConfig {
+                raw_script: script.to_string(),
+                ..QemuConfig::default()
+            });
+            let (custom_name, os_profile) = parse_vm_metadata(metadata);
+
+            Some(DiscoveredVm {
+                id: format!("{}@{}", name, host.name),
+                path,
+                launch_script,
+                config,
+                backend: Backend::Qemu,
+                custom_name,
+                os_profile,
+                remote: Some(host.clone()),
+            })
+        })
+        .collect()
+}
+
+/// QEMU processes running on a host
+pub fn running_processes(host: &RemoteHost) -> Result<Vec<QemuProcess>> {
+    let script = "pgrep -a qemu-system | while read -r pid cmdline; do\n\
+                  \x20   printf '%s\\t%s\\t%s\\n' \"$pid\" \"$(readlink /proc/$pid/cwd)\" \"$cmdline\"\n\
+                  done\n";
+    let output = ssh::run_script(host, script)?;
+    Ok(parse_processes(host, &output))
+}
+
+fn parse_processes(host: &RemoteHost, output: &str) -> Vec<QemuProcess> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let pid = fields.next()?.trim().parse().ok()?;
+            let cwd = fields.next()?;
+            let cmdline = fields.next()?.to_string();
+            Some(QemuProcess {
+                pid,
+                cmdline,
+                cwd: (!cwd.is_empty()).then(|| PathBuf::from(cwd)),
+                host: Some(host.name.clone()),
+            })
+        })
+        .collect()
+}
+
+/// Start a VM's launch script on its host, detached from the SSH session
+///
+/// An ISO for a CD-ROM boot must be a path on the remote host.
+pub fn launch(host: &RemoteHost, vm: &DiscoveredVm, options: &LaunchOptions) -> LaunchResult {
+    let vm_name = vm.display_name();
+    let mut args = Vec::new();
+    match &options.boot_mode {
+        BootMode::Normal => {}
+        BootMode::Install => args.push("--install".to_string()),
+        BootMode::Cdrom(iso_path) => {
+            args.push("--cdrom".to_string());
+            args.push(shell_escape(&iso_path.to_string_lossy()));
+        }
+        BootMode::Network => args.push("--netboot".to_string()),
+        BootMode::Resume => {
+            return LaunchResult {
+                success: false,
+                error: Some("Only VMs on this computer can be suspended and resumed".to_string()),
+                vm_name,
+            };
+        }
+    }
+    args.extend(options.extra_args.iter().map(|a| shell_escape(a)));
+
+    match ssh::run_script(host, &start_script(&vm.path, "", &args)) {
+        Ok(_) => LaunchResult {
+            success: true,
+            error: None,
+            vm_name,
+        },
+        Err(e) => LaunchResult {
+            success: false,
+            error: Some(e.to_string()),
+            vm_name,
+        },
+    }
+}
+
+/// A script that starts `launch.sh` detached, with shell-quoted `args`
+///
+/// `env` is prepended to the command (e.g. `NAME=value`). The script
+/// waits a moment so a launch that fails right away can be reported.
+pub(crate) fn start_script(vm_dir: &Path, env: &str, args: &[String]) -> String {
+    format!(
+        "cd {dir} || exit 1\n\
+         {env} nohup setsid bash launch.sh {args} </dev/null >/dev/null 2>{log} &\n\
+         pid=$!\n\
+         sleep 2\n\
+         if ! kill -0 \"$pid\" 2>/dev/null && ! wait \"$pid\"; then\n\
+         \x20   tail -n 5 {log} >&2\n\
+         \x20   exit 1\n\
+         fi\n",
+        dir = shell_escape(&vm_dir.to_string_lossy()),
+        env = env,
+        args = args.join(" "),
+        log = LAUNCH_LOG,
+    )
+}
+
+/// Absolute path of a host's VM library
+pub fn library_dir(host: &RemoteHost) -> Result<String> {
+    let output = ssh::run_script(host, &format!("mkdir -p {lib} && cd {lib} && pwd\n", lib = library_arg(host)))?;
+    let dir = output.trim();
+    if dir.is_empty() {
+        bail!("{}: cannot open the VM library", host.name);
+    }
+    Ok(dir.to_string())
+}
+
+/// Local directory holding a remote VM's forwarded sockets
+pub(crate) fn tunnel_dir(vm: &DiscoveredVm) -> Result<PathBuf> {
+    let dir = dirs::runtime_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("vm-curator-remote")
+        .join(&vm.id);
+    std::fs::create_dir_all(&dir).context("Failed to create the tunnel directory")?;
+    Ok(dir)
+}
+
+/// Forward a socket in the remote VM directory to the same name locally
+fn forward_socket(host: &RemoteHost, vm: &DiscoveredVm, local: &Path) -> Result<()> {
+    let name = local.file_name().context("Invalid socket path")?;
+    let remote = vm.path.join(name);
+    let _ = std::fs::remove_file(local);
+    let spec = format!("{}:{}", local.display(), remote.display());
+    ssh::forward(host, &spec, || local.exists())?;
+    Ok(())
+}
+
+/// Forward a remote VM's QMP socket, returning the directory to pass to `qmp`
+pub fn forward_qmp(host: &RemoteHost, vm: &DiscoveredVm) -> Result<PathBuf> {
+    let dir = tunnel_dir(vm)?;
+    forward_socket(host, vm, &super::qmp::socket_path(&dir))?;
+    Ok(dir)
+}
+
+/// Ask a remote VM to shut down
+///
+/// Presses the ACPI power button over the forwarded QMP socket, or sends
+/// SIGTERM on the host when the VM has no QMP socket.
+pub fn stop(host: &RemoteHost, vm: &DiscoveredVm, pid: u32) -> Result<()> {
+    if super::qmp::is_configured(&vm.config.raw_script) {
+        let powered_down = forward_qmp(host, vm).and_then(|dir| super::qmp::system_powerdown(&dir));
+        if powered_down.is_ok() {
+            return Ok(());
+        }
+    }
+    ssh::run_script(host, &format!("kill {}\n", pid))?;
+    Ok(())
+}
+
+/// Kill a remote VM's QEMU process
+pub fn force_stop(host: &RemoteHost, pid: u32) -> Result<()> {
+    ssh::run_script(host, &format!("kill -9 {}\n", pid))?;
+    Ok(())
+}
+
+/// Open a viewer on a remote VM's VNC or SPICE server through a tunnel
+///
+/// Returns the name of the viewer that was started.
+pub fn open_display(host: &RemoteHost, vm: &DiscoveredVm, vnc_client: Option<&str>) -> Result<String> {
+    if let Some(display) = vm.config.vnc_display {
+        let local = super::vnc::allocate_display(&[])?;
+        let spec = format!(
+            "127.0.0.1:{}:127.0.0.1:{}",
+            super::vnc::port(local),
+            super::vnc::port(display)
+        );
+        ssh::forward(host, &spec, || {
+            TcpStream::connect(("127.0.0.1", super::vnc::port(local))).is_ok()
+        })?;
+        return super::vnc::launch_client(vnc_client, local);
+    }
+
+    if vm.config.spice.as_ref().is_some_and(|s| s.mode == SpiceMode::Server) {
+        let dir = tunnel_dir(vm)?;
+        forward_socket(host, vm, &super::spice::socket_path(&dir))?;
+        return super::spice::launch_viewer(&dir, &vm.display_name()).map(String::from);
+    }
+
+    bail!("Set the display to VNC or SPICE (server) to view a VM on a remote host")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host() -> RemoteHost {
+        RemoteHost {
+            name: "attic".to_string(),
+            ssh: "me@attic".to_string(),
+            library_path: "vm-space".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_listing() {
+        let listing = "\x1e/home/me/vm-space/windows-98\n\
+                       qemu-system-i386 -m 256M -vnc 127.0.0.1:3\n\
+                       \x1fdisplay_name = \"Game PC\"\n\
+                       \x1e/home/me/vm-space/dos\n\
+                       # vm-curator backend: dosbox-x\n\
+                       dosbox-x -conf dosbox-x.conf\n\
+                       \x1f";
+        let vms = parse_listing(&host(), listing);
+        assert_eq!(vms.len(), 1);
+
+        let vm = &vms[0];
+        assert_eq!(vm.id, "windows-98@attic");
+        assert_eq!(vm.path, PathBuf::from("/home/me/vm-space/windows-98"));
+        assert_eq!(vm.display_name(), "Game PC (attic)");
+        assert_eq!(vm.remote.as_ref().map(|h| h.ssh.as_str()), Some("me@attic"));
+    }
+
+    #[test]
+    fn test_parse_processes() {
+        let output = "4242\t/home/me/vm-space/windows-98\tqemu-system-i386 -m 256M\n\
+                      4343\t\tqemu-system-x86_64 -m 4G\n";
+        let processes = parse_processes(&host(), output);
+        assert_eq!(processes.len(), 2);
+        assert_eq!(processes[0].pid, 4242);
+        assert_eq!(processes[0].cwd, Some(PathBuf::from("/home/me/vm-space/windows-98")));
+        assert_eq!(processes[0].cmdline, "qemu-system-i386 -m 256M");
+        assert_eq!(processes[1].cwd, None);
+        assert_eq!(processes[1].host.as_deref(), Some("attic"));
+    }
+
+    #[test]
+    fn test_library_arg() {
+        let mut host = host();
+        assert_eq!(library_arg(&host), "vm-space");
+        host.library_path = "~/My VMs".to_string();
+        assert_eq!(library_arg(&host), "'My VMs'");
+        host.library_path = "~".to_string();
+        assert_eq!(library_arg(&host), ".");
+    }
+}
@@ -0,0 +1,244 @@
+//! Boot screenshots
+//!
+//! A little after a VM starts, its screen is captured through QMP
+//! (`screendump`) into a `screenshots` folder in the VM directory, so the
+//! info panel can show what the machine actually looks like. QEMU writes
+//! PPM; the capture is shrunk to thumbnail size and kept as PPM, which
+//! needs no image library to read back. Only the newest few are kept.
+
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDateTime;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::qmp;
+
+/// Folder inside the VM directory
+const DIR_NAME: &str = "screenshots";
+
+/// File the raw capture is written to before shrinking
+const RAW_NAME: &str = ".capture.ppm";
+
+/// File name prefix, followed by the capture time
+const PREFIX: &str = "boot-";
+
+const TIME_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// Screenshots kept per VM
+pub const KEEP: usize = 8;
+
+/// How long after start the screen is captured
+pub const CAPTURE_DELAY: Duration = Duration::from_secs(20);
+
+/// Largest stored screenshot (pixels)
+const MAX_WIDTH: usize = 320;
+const MAX_HEIGHT: usize = 240;
+
+/// An RGB image
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    /// Rows of RGB triplets
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn pixel(&self, x: usize, y: usize) -> (u8, u8, u8) {
+        let i = (y * self.width + x) * 3;
+        (self.pixels[i], self.pixels[i + 1], self.pixels[i + 2])
+    }
+
+    /// The image resized to `width` x `height`, each pixel averaging the
+    /// area it covers
+    pub fn scaled(&self, width: usize, height: usize) -> Image {
+        let width = width.max(1);
+        let height = height.max(1);
+        let mut pixels = Vec::with_capacity(width * height * 3);
+        for y in 0..height {
+            let (y0, y1) = span(y, height, self.height);
+            for x in 0..width {
+                let (x0, x1) = span(x, width, self.width);
+                let mut sum = [0u32; 3];
+                for sy in y0..y1 {
+                    for sx in x0..x1 {
+                        let (r, g, b) = self.pixel(sx, sy);
+                        sum[0] += u32::from(r);
+                        sum[1] += u32::from(g);
+                        sum[2] += u32::from(b);
+                    }
+                }
+                let count = ((y1 - y0) * (x1 - x0)) as u32;
+                pixels.extend(sum.iter().map(|s| (s / count) as u8));
+            }
+        }
+        Image { width, height, pixels }
+    }
+
+    /// Binary PPM (P6)
+    fn to_ppm(&self) -> Vec<u8> {
+        let mut data = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        data.extend_from_slice(&self.pixels);
+        data
+    }
+}
+
+/// Source pixels [start, end) covered by destination pixel `i` of `len`
+fn span(i: usize, len: usize, source_len: usize) -> (usize, usize) {
+    let start = i * source_len / len;
+    let end = ((i + 1) * source_len / len).max(start + 1).min(source_len);
+    (start.min(end - 1), end)
+}
+
+/// Read a binary PPM (P6) with 8-bit samples
+pub fn parse_ppm(data: &[u8]) -> Result<Image> {
+    let mut pos = 0;
+    let mut fields = Vec::new();
+    while fields.len() < 4 {
+        match data.get(pos) {
+            None => bail!("Truncated PPM header"),
+            Some(b'#') => {
+                while data.get(pos).is_some_and(|&c| c != b'\n') {
+                    pos += 1;
+                }
+            }
+            Some(c) if c.is_ascii_whitespace() => pos += 1,
+            Some(_) => {
+                let start = pos;
+                while data.get(pos).is_some_and(|c| !c.is_ascii_whitespace()) {
+                    pos += 1;
+                }
+                fields.push(String::from_utf8_lossy(&data[start..pos]).to_string());
+            }
+        }
+    }
+    // One whitespace byte separates the header from the samples
+    pos += 1;
+
+    if fields[0] != "P6" {
+        bail!("Not a binary PPM image");
+    }
+    let number = |s: &str| s.parse::<usize>().with_context(|| format!("Bad PPM header value {}", s));
+    let (width, height, max) = (number(&fields[1])?, number(&fields[2])?, number(&fields[3])?);
+    if width == 0 || height == 0 || max == 0 || max > 255 {
+        bail!("Unsupported PPM image ({}x{}, max {})", width, height, max);
+    }
+    let len = width * height * 3;
+    let Some(samples) = data.get(pos..pos + len) else {
+        bail!("Truncated PPM image");
+    };
+    let pixels = if max == 255 {
+        samples.to_vec()
+    } else {
+        samples.iter().map(|&s| (u32::from(s) * 255 / max as u32) as u8).collect()
+    };
+    Ok(Image { width, height, pixels })
+}
+
+/// Load a stored screenshot
+pub fn load(path: &Path) -> Result<Image> {
+    let data = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    parse_ppm(&data)
+}
+
+/// A stored screenshot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Screenshot {
+    pub path: PathBuf,
+    /// Local capture time
+    pub taken: NaiveDateTime,
+}
+
+fn dir(vm_dir: &Path) -> PathBuf {
+    vm_dir.join(DIR_NAME)
+}
+
+/// Capture time from a screenshot's file name
+fn parse_name(name: &str) -> Option<NaiveDateTime> {
+    let stamp = name.strip_prefix(PREFIX)?.strip_suffix(".ppm")?;
+    NaiveDateTime::parse_from_str(stamp, TIME_FORMAT).ok()
+}
+
+/// A VM's screenshots, newest first
+pub fn list(vm_dir: &Path) -> Vec<Screenshot> {
+    let Ok(entries) = std::fs::read_dir(dir(vm_dir)) else {
+        return Vec::new();
+    };
+    let mut shots: Vec<Screenshot> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let taken = parse_name(&entry.file_name().to_string_lossy())?;
+            Some(Screenshot { path: entry.path(), taken })
+        })
+        .collect();
+    shots.sort_by_key(|shot| std::cmp::Reverse(shot.taken));
+    shots
+}
+
+/// A VM's newest screenshot
+pub fn latest(vm_dir: &Path) -> Option<Screenshot> {
+    list(vm_dir).into_iter().next()
+}
+
+/// Capture a running VM's screen and drop all but the newest `KEEP`
+pub fn capture(vm_dir: &Path) -> Result<PathBuf> {
+    let dir = dir(vm_dir);
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    // QEMU resolves relative paths against its own working directory
+    let dir = std::fs::canonicalize(&dir).unwrap_or(dir);
+
+    let raw = dir.join(RAW_NAME);
+    qmp::screendump(vm_dir, &raw)?;
+    let image = load(&raw);
+    let _ = std::fs::remove_file(&raw);
+    let image = image?;
+
+    let scale = f64::min(MAX_WIDTH as f64 / image.width as f64, MAX_HEIGHT as f64 / image.height as f64);
+    let image = if scale < 1.0 {
+        image.scaled((image.width as f64 * scale).round() as usize, (image.height as f64 * scale).round() as usize)
+    } else {
+        image
+    };
+
+    let name = format!("{}{}.ppm", PREFIX, chrono::Local::now().format(TIME_FORMAT));
+    let path = dir.join(name);
+    std::fs::write(&path, image.to_ppm()).with_context(|| format!("Failed to write {}", path.display()))?;
+
+    for old in list(vm_dir).into_iter().skip(KEEP) {
+        let _ = std::fs::remove_file(old.path);
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ppm_round_trip_and_scaling() {
+        // 4x2: left half black, right half white
+        let mut data = b"P6\n# CREATOR: QEMU\n4 2\n255\n".to_vec();
+        for _ in 0..2 {
+            data.extend_from_slice(&[0, 0, 0, 0, 0, 0, 255, 255, 255, 255, 255, 255]);
+        }
+        let image = parse_ppm(&data).unwrap();
+        assert_eq!((image.width, image.height), (4, 2));
+        assert_eq!(image.pixel(3, 1), (255, 255, 255));
+        assert_eq!(parse_ppm(&image.to_ppm()).unwrap(), image);
+
+        let small = image.scaled(2, 1);
+        assert_eq!(small.pixel(0, 0), (0, 0, 0));
+        assert_eq!(small.pixel(1, 0), (255, 255, 255));
+        assert_eq!(image.scaled(1, 1).pixel(0, 0), (127, 127, 127));
+        assert_eq!(image.scaled(8, 4).pixel(7, 3), (255, 255, 255));
+
+        assert!(parse_ppm(b"P6\n4 2\n255\n\0\0\0").is_err());
+        assert!(parse_ppm(b"P3\n1 1\n255\n0 0 0").is_err());
+
+        assert_eq!(
+            parse_name("boot-20240115-093000.ppm"),
+            NaiveDateTime::parse_from_str("2024-01-15 09:30:00", "%Y-%m-%d %H:%M:%S").ok()
+        );
+        assert_eq!(parse_name(RAW_NAME), None);
+    }
+}
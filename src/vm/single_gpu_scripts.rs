@@ -10,7 +10,6 @@ use anyhow::{Context, Result};
 use once_cell::sync::Lazy;
 use regex::Regex;
 use std::fs;
-use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
 
 // Lazy-compiled regexes for QEMU command extraction/modification
@@ -239,9 +238,7 @@ fn write_executable_script(path: &Path, content: &str) -> Result<()> {
     fs::write(path, content)
         .with_context(|| format!("Failed to write script: {:?}", path))?;
 
-    let mut perms = fs::metadata(path)?.permissions();
-    perms.set_mode(0o755);
-    fs::set_permissions(path, perms)?;
+    crate::fs::set_mode(path, 0o755)?;
 
     Ok(())
 }
@@ -274,7 +271,7 @@ fn generate_start_script(vm: &DiscoveredVm, config: &SingleGpuConfig) -> Result<
 
     // Load USB passthrough from launch.sh
     let usb_devices = load_usb_passthrough(vm);
-    let usb_passthrough_args = generate_usb_passthrough_args(&usb_devices);
+    let usb_passthrough_args = generate_usb_passthrough_args(&usb_devices, vm.config.usb_controller.as_deref());
 
     // Load PCI passthrough from launch.sh (network cards, USB controllers, etc.)
     let pci_passthrough_args = load_pci_passthrough(vm);
@@ -663,36 +660,14 @@ fn extract_pci_addresses(pci_args: &[String]) -> Vec<String> {
 }
 
 /// Generate USB passthrough arguments
-fn generate_usb_passthrough_args(devices: &[crate::vm::UsbPassthrough]) -> String {
+///
+/// Launch-only rules are included too: nothing hot-plugs them without a session.
+fn generate_usb_passthrough_args(devices: &[crate::vm::UsbPassthrough], controller: Option<&str>) -> String {
     if devices.is_empty() {
         return String::new();
     }
 
-    let mut args = vec!["-usb".to_string()];
-
-    // Check if any USB 3.0 devices are present
-    let has_usb3 = devices.iter().any(|d| d.is_usb3());
-
-    // Add xHCI controller if USB 3.0 devices are present
-    if has_usb3 {
-        args.push("-device qemu-xhci,id=xhci".to_string());
-    }
-
-    // Add each USB device, attaching USB 3.0 devices to xHCI controller
-    for dev in devices {
-        if dev.is_usb3() {
-            args.push(format!(
-                "-device usb-host,bus=xhci.0,vendorid=0x{:04x},productid=0x{:04x}",
-                dev.vendor_id, dev.product_id
-            ));
-        } else {
-            args.push(format!(
-                "-device usb-host,vendorid=0x{:04x},productid=0x{:04x}",
-                dev.vendor_id, dev.product_id
-            ));
-        }
-    }
-    args.join(" \\\n    ")
+    crate::vm::lifecycle::usb_passthrough_args(devices, controller, true).join(" \\\n    ")
 }
 
 /// Generate the emergency restore script
@@ -875,10 +850,7 @@ pub fn run_system_setup(gpu_driver: &str) -> SystemSetupResult {
     }
 
     // Make it executable
-    if let Err(e) = std::fs::set_permissions(
-        script_path,
-        std::os::unix::fs::PermissionsExt::from_mode(0o755),
-    ) {
+    if let Err(e) = crate::fs::set_mode(Path::new(script_path), 0o755) {
         return SystemSetupResult::Error(format!("Failed to make script executable: {}", e));
     }
 
@@ -1264,13 +1236,7 @@ fn generate_basic_qemu_command(
 
     // Add disk
     if let Some(disk) = vm.config.disks.first() {
-        let format_str = match &disk.format {
-            crate::vm::qemu_config::DiskFormat::Qcow2 => "qcow2",
-            crate::vm::qemu_config::DiskFormat::Raw => "raw",
-            crate::vm::qemu_config::DiskFormat::Vmdk => "vmdk",
-            crate::vm::qemu_config::DiskFormat::Vdi => "vdi",
-            crate::vm::qemu_config::DiskFormat::Other(s) => s.as_str(),
-        };
+        let format_str = disk.format.qemu_name();
         cmd.push_str(&format!(
             r#" \
     -drive file="$DISK",format={},if=virtio"#,
@@ -1,17 +1,21 @@
 use anyhow::{bail, Context, Result};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 use std::process::Command;
 
+use super::snapshot_meta::SnapshotMeta;
+
 /// A snapshot of a VM disk
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Snapshot {
     /// Snapshot ID from qemu-img - reserved for future use
     #[allow(dead_code)]
     pub id: String,
     pub name: String,
-    pub size: String,
-    pub date: String,
+    /// Size of the saved VM state in bytes
+    pub size_bytes: u64,
+    /// Creation time (Unix seconds)
+    pub timestamp: i64,
     /// VM clock time - reserved for display in future UI
     #[allow(dead_code)]
     pub vm_clock: String,
@@ -35,16 +39,19 @@ struct QemuImgInfo {
 }
 
 /// Snapshot info from qemu-img JSON output
+///
+/// Fields other than the tag have come and gone across QEMU releases
+/// (`icount` since 5.2, `date-nsec` missing from some builds), so they all
+/// default and unknown ones are ignored.
 #[derive(Debug, Deserialize)]
 struct QemuSnapshot {
+    #[serde(default)]
     id: String,
     name: String,
     #[serde(rename = "vm-state-size", default)]
     vm_state_size: u64,
     #[serde(rename = "date-sec", default)]
     date_sec: i64,
-    #[serde(rename = "date-nsec", default)]
-    date_nsec: i64,
     #[serde(rename = "vm-clock-sec", default)]
     vm_clock_sec: i64,
     #[serde(rename = "vm-clock-nsec", default)]
@@ -95,10 +102,14 @@ pub fn validate_snapshot_name(name: &str) -> Result<String> {
 }
 
 /// List snapshots for a qcow2 disk image using JSON output
+///
+/// Reads with `-U` (force-share), so the disk of a running VM can be listed.
+/// The JSON form is used rather than the `snapshot -l` table, whose columns
+/// differ between QEMU versions and can't hold tags with spaces.
 pub fn list_snapshots(disk_path: &Path) -> Result<Vec<Snapshot>> {
     let disk_str = path_to_str(disk_path)?;
     let output = Command::new("qemu-img")
-        .args(["info", "--output=json", disk_str])
+        .args(["info", "-U", "--output=json", disk_str])
         .output()
         .context("Failed to run qemu-img info")?;
 
@@ -107,28 +118,26 @@ pub fn list_snapshots(disk_path: &Path) -> Result<Vec<Snapshot>> {
         bail!("qemu-img info failed: {}", stderr);
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let info: QemuImgInfo = serde_json::from_str(&stdout)
+    parse_snapshot_list(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Snapshots from `qemu-img info --output=json`
+fn parse_snapshot_list(json: &str) -> Result<Vec<Snapshot>> {
+    let info: QemuImgInfo = serde_json::from_str(json)
         .context("Failed to parse qemu-img JSON output")?;
 
     let snapshots = info
         .snapshots
         .into_iter()
         .map(|s| {
-            // Convert size to human-readable format
-            let size = format_size(s.vm_state_size);
-
-            // Convert timestamp to date string
-            let date = format_timestamp(s.date_sec, s.date_nsec);
-
             // Convert VM clock to readable format
             let vm_clock = format_vm_clock(s.vm_clock_sec, s.vm_clock_nsec);
 
             Snapshot {
                 id: s.id,
                 name: s.name,
-                size,
-                date,
+                size_bytes: s.vm_state_size,
+                timestamp: s.date_sec,
                 vm_clock,
             }
         })
@@ -137,33 +146,6 @@ pub fn list_snapshots(disk_path: &Path) -> Result<Vec<Snapshot>> {
     Ok(snapshots)
 }
 
-/// Format bytes to human-readable size
-fn format_size(bytes: u64) -> String {
-    const KB: u64 = 1024;
-    const MB: u64 = KB * 1024;
-    const GB: u64 = MB * 1024;
-
-    if bytes >= GB {
-        format!("{:.1}G", bytes as f64 / GB as f64)
-    } else if bytes >= MB {
-        format!("{:.1}M", bytes as f64 / MB as f64)
-    } else if bytes >= KB {
-        format!("{:.1}K", bytes as f64 / KB as f64)
-    } else {
-        format!("{}B", bytes)
-    }
-}
-
-/// Format Unix timestamp to date string
-fn format_timestamp(secs: i64, _nsecs: i64) -> String {
-    use chrono::{Local, TimeZone};
-    if let Some(dt) = Local.timestamp_opt(secs, 0).single() {
-        dt.format("%Y-%m-%d %H:%M:%S").to_string()
-    } else {
-        "unknown".to_string()
-    }
-}
-
 /// Format VM clock seconds/nanoseconds to readable string
 fn format_vm_clock(secs: i64, nsecs: i64) -> String {
     let total_secs = secs as f64 + (nsecs as f64 / 1_000_000_000.0);
@@ -177,6 +159,7 @@ fn format_vm_clock(secs: i64, nsecs: i64) -> String {
 pub fn create_snapshot(disk_path: &Path, name: &str) -> Result<()> {
     let disk_str = path_to_str(disk_path)?;
     let sanitized_name = validate_snapshot_name(name)?;
+    tracing::info!(disk = disk_str, snapshot = %sanitized_name, "creating snapshot");
     let output = Command::new("qemu-img")
         .args(["snapshot", "-c", &sanitized_name, disk_str])
         .output()
@@ -194,6 +177,7 @@ pub fn create_snapshot(disk_path: &Path, name: &str) -> Result<()> {
 pub fn restore_snapshot(disk_path: &Path, name: &str) -> Result<()> {
     let disk_str = path_to_str(disk_path)?;
     let sanitized_name = validate_snapshot_name(name)?;
+    tracing::info!(disk = disk_str, snapshot = %sanitized_name, "restoring snapshot");
     let output = Command::new("qemu-img")
         .args(["snapshot", "-a", &sanitized_name, disk_str])
         .output()
@@ -211,6 +195,7 @@ pub fn restore_snapshot(disk_path: &Path, name: &str) -> Result<()> {
 pub fn delete_snapshot(disk_path: &Path, name: &str) -> Result<()> {
     let disk_str = path_to_str(disk_path)?;
     let sanitized_name = validate_snapshot_name(name)?;
+    tracing::info!(disk = disk_str, snapshot = %sanitized_name, "deleting snapshot");
     let output = Command::new("qemu-img")
         .args(["snapshot", "-d", &sanitized_name, disk_str])
         .output()
@@ -221,15 +206,36 @@ pub fn delete_snapshot(disk_path: &Path, name: &str) -> Result<()> {
         bail!("Failed to delete snapshot: {}", stderr);
     }
 
-    Ok(())
+    super::snapshot_meta::forget(disk_path, &sanitized_name)
+}
+
+/// Name prefix of the snapshots taken when a VM shuts down
+pub const SESSION_PREFIX: &str = "session-";
+
+/// Snapshot a disk at the end of a session and delete all but the newest
+/// `keep` session snapshots; returns the new snapshot's name
+pub fn take_session_snapshot(disk_path: &Path, keep: usize, meta: SnapshotMeta) -> Result<String> {
+    let name = format!("{}{}", SESSION_PREFIX, chrono::Local::now().format("%Y%m%d-%H%M%S"));
+    create_snapshot(disk_path, &name)?;
+    super::snapshot_meta::record(disk_path, &name, meta)?;
+    for old in expired_sessions(&list_snapshots(disk_path)?, keep) {
+        delete_snapshot(disk_path, &old)?;
+    }
+    Ok(name)
+}
+
+/// Session snapshots beyond the newest `keep`, oldest last
+fn expired_sessions(snapshots: &[Snapshot], keep: usize) -> Vec<String> {
+    let mut sessions: Vec<&Snapshot> = snapshots.iter().filter(|s| s.name.starts_with(SESSION_PREFIX)).collect();
+    sessions.sort_by(|a, b| (b.timestamp, &b.name).cmp(&(a.timestamp, &a.name)));
+    sessions.into_iter().skip(keep).map(|s| s.name.clone()).collect()
 }
 
 /// Get information about a disk image using JSON output
-#[allow(dead_code)]
 pub fn get_disk_info(disk_path: &Path) -> Result<DiskInfo> {
     let disk_str = path_to_str(disk_path)?;
     let output = Command::new("qemu-img")
-        .args(["info", "--output=json", disk_str])
+        .args(["info", "-U", "--output=json", disk_str])
         .output()
         .context("Failed to run qemu-img info")?;
 
@@ -244,21 +250,21 @@ pub fn get_disk_info(disk_path: &Path) -> Result<DiskInfo> {
 
     Ok(DiskInfo {
         format: info.format,
-        virtual_size: format_size(info.virtual_size),
-        disk_size: info.actual_size.map(format_size).unwrap_or_else(|| "unknown".to_string()),
-        cluster_size: info.cluster_size.map(|s| format_size(s)),
+        virtual_size: info.virtual_size,
+        disk_size: info.actual_size,
+        cluster_size: info.cluster_size,
         backing_file: info.backing_filename,
     })
 }
 
-/// Disk image information - reserved for future disk info display
-#[derive(Debug, Clone)]
-#[allow(dead_code)]
+/// Disk image information from qemu-img (sizes in bytes)
+#[derive(Debug, Clone, Serialize)]
 pub struct DiskInfo {
     pub format: String,
-    pub virtual_size: String,
-    pub disk_size: String,
-    pub cluster_size: Option<String>,
+    pub virtual_size: u64,
+    /// Space used on the host, when qemu-img can tell
+    pub disk_size: Option<u64>,
+    pub cluster_size: Option<u64>,
     pub backing_file: Option<String>,
 }
 
@@ -302,13 +308,101 @@ mod tests {
         assert_eq!(info.format, "qcow2");
     }
 
+    /// `qemu-img info --output=json` as printed by a few QEMU releases
+    const QEMU_2_11_INFO: &str = r#"{
+    "snapshots": [
+        {
+            "vm-clock-nsec": 0,
+            "name": "fresh-install",
+            "date-sec": 1514800000,
+            "date-nsec": 0,
+            "vm-clock-sec": 0,
+            "id": "1",
+            "vm-state-size": 0
+        }
+    ],
+    "virtual-size": 2147483648,
+    "filename": "dos.qcow2",
+    "cluster-size": 65536,
+    "format": "qcow2",
+    "actual-size": 1052672,
+    "format-specific": {"type": "qcow2", "data": {"compat": "1.1", "lazy-refcounts": false, "refcount-bits": 16, "corrupt": false}},
+    "dirty-flag": false
+}"#;
+
+    const QEMU_6_2_INFO: &str = r#"{
+    "snapshots": [
+        {
+            "icount": 0,
+            "vm-clock-nsec": 220411950,
+            "name": "before update",
+            "date-sec": 1650000000,
+            "date-nsec": 512000000,
+            "vm-clock-sec": 812,
+            "id": "1",
+            "vm-state-size": 268435456
+        },
+        {
+            "vm-clock-nsec": 0,
+            "name": "Win98 SE (drivers)",
+            "date-sec": 1650003600,
+            "date-nsec": 0,
+            "vm-clock-sec": 0,
+            "id": "2",
+            "vm-state-size": 0
+        }
+    ],
+    "virtual-size": 8589934592,
+    "filename": "win98.qcow2",
+    "cluster-size": 65536,
+    "format": "qcow2",
+    "actual-size": 1417216000,
+    "format-specific": {"type": "qcow2", "data": {"compat": "1.1", "compression-type": "zlib", "lazy-refcounts": false, "refcount-bits": 16, "corrupt": false, "extended-l2": false}},
+    "dirty-flag": false
+}"#;
+
+    const QEMU_9_0_INFO: &str = r#"{
+    "children": [
+        {"name": "file", "info": {"children": [], "virtual-size": 197120, "filename": "os2.qcow2", "format": "file", "actual-size": 200704, "format-specific": {"type": "file", "data": {}}, "dirty-flag": false}}
+    ],
+    "snapshots": [
+        {
+            "icount": 1048576,
+            "vm-clock-nsec": 5000000,
+            "name": "warp4",
+            "date-sec": 1717000000,
+            "vm-clock-sec": 3661,
+            "id": "3",
+            "vm-state-size": 134217728
+        }
+    ],
+    "virtual-size": 4294967296,
+    "filename": "os2.qcow2",
+    "cluster-size": 65536,
+    "format": "qcow2",
+    "actual-size": 200704,
+    "format-specific": {"type": "qcow2", "data": {"compat": "1.1", "compression-type": "zstd", "lazy-refcounts": false, "refcount-bits": 16, "corrupt": false, "extended-l2": false}},
+    "dirty-flag": false
+}"#;
+
     #[test]
-    fn test_format_size() {
-        assert_eq!(format_size(512), "512B");
-        assert_eq!(format_size(1024), "1.0K");
-        assert_eq!(format_size(1536), "1.5K");
-        assert_eq!(format_size(1048576), "1.0M");
-        assert_eq!(format_size(1073741824), "1.0G");
+    fn test_parse_snapshot_list_across_qemu_versions() {
+        let old = parse_snapshot_list(QEMU_2_11_INFO).unwrap();
+        assert_eq!(old.len(), 1);
+        assert_eq!((old[0].name.as_str(), old[0].timestamp, old[0].size_bytes), ("fresh-install", 1514800000, 0));
+
+        // Tags with spaces and brackets survive intact
+        let names: Vec<_> = parse_snapshot_list(QEMU_6_2_INFO).unwrap().into_iter().map(|s| s.name).collect();
+        assert_eq!(names, vec!["before update", "Win98 SE (drivers)"]);
+
+        // Newer output adds `children` and `icount` and may drop `date-nsec`
+        let new = parse_snapshot_list(QEMU_9_0_INFO).unwrap();
+        assert_eq!((new[0].id.as_str(), new[0].size_bytes), ("3", 134217728));
+        assert_eq!(new[0].vm_clock, "01:01:01.005");
+
+        // An image without snapshots has no `snapshots` key at all
+        assert!(parse_snapshot_list(r#"{"format": "qcow2", "virtual-size": 1024}"#).unwrap().is_empty());
+        assert!(parse_snapshot_list("qemu-img: Could not open 'x.qcow2'").is_err());
     }
 
     #[test]
@@ -337,4 +431,26 @@ mod tests {
         let result = validate_snapshot_name("-test").unwrap();
         assert_eq!(result, "_-test");
     }
+
+    #[test]
+    fn test_expired_sessions() {
+        let snapshot = |name: &str, timestamp| Snapshot {
+            id: String::new(),
+            name: name.to_string(),
+            size_bytes: 0,
+            timestamp,
+            vm_clock: String::new(),
+        };
+        let snapshots = vec![
+            snapshot("session-20240102-090000", 200),
+            snapshot("fresh-install", 50),
+            snapshot("session-20240101-090000", 100),
+            snapshot("session-20240103-090000", 300),
+        ];
+        assert_eq!(
+            expired_sessions(&snapshots, 1),
+            vec!["session-20240102-090000", "session-20240101-090000"]
+        );
+        assert!(expired_sessions(&snapshots, 3).is_empty());
+    }
 }
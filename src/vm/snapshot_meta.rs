@@ -0,0 +1,167 @@
+//! Snapshot descriptions
+//!
+//! qcow2 snapshots carry nothing but a tag and a date. A JSON sidecar next
+//! to the disk (`<disk>.snapshots.json`) adds, per tag, a description, why
+//! the snapshot was taken, and the launch script as it was then, so the
+//! Snapshots screen can show what has changed in the VM's configuration
+//! since.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use super::DiscoveredVm;
+
+/// Appended to the disk's file name
+pub const SIDECAR_SUFFIX: &str = ".snapshots.json";
+
+/// Why a snapshot was taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SnapshotContext {
+    #[default]
+    Manual,
+    CleanInstall,
+    PreUpdate,
+    PreSoftwareInstall,
+    /// Taken when the VM shut down (session snapshots)
+    Session,
+    /// Part of a batch snapshot of the library
+    Batch,
+}
+
+impl SnapshotContext {
+    /// Contexts offered when creating a snapshot by hand
+    pub const CHOICES: &'static [SnapshotContext] =
+        &[Self::Manual, Self::CleanInstall, Self::PreUpdate, Self::PreSoftwareInstall];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Manual => "Manual",
+            Self::CleanInstall => "Clean install",
+            Self::PreUpdate => "Before an update",
+            Self::PreSoftwareInstall => "Before installing software",
+            Self::Session => "End of session",
+            Self::Batch => "Batch snapshot",
+        }
+    }
+}
+
+/// What the sidecar records about one snapshot
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnapshotMeta {
+    pub description: String,
+    pub context: SnapshotContext,
+    /// launch.sh when the snapshot was taken
+    pub launch_script: Option<String>,
+}
+
+impl SnapshotMeta {
+    /// Metadata for a snapshot of `vm` taken now
+    pub fn for_vm(vm: &DiscoveredVm, description: &str, context: SnapshotContext) -> Self {
+        Self {
+            description: description.trim().to_string(),
+            context,
+            launch_script: std::fs::read_to_string(&vm.launch_script).ok(),
+        }
+    }
+}
+
+pub fn sidecar_path(disk_path: &Path) -> PathBuf {
+    let mut name = disk_path.file_name().unwrap_or_default().to_os_string();
+    name.push(SIDECAR_SUFFIX);
+    disk_path.with_file_name(name)
+}
+
+/// Metadata of a disk's snapshots, by tag
+pub fn load(disk_path: &Path) -> BTreeMap<String, SnapshotMeta> {
+    std::fs::read_to_string(sidecar_path(disk_path))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save(disk_path: &Path, entries: &BTreeMap<String, SnapshotMeta>) -> Result<()> {
+    let path = sidecar_path(disk_path);
+    if entries.is_empty() {
+        return match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).with_context(|| format!("Failed to remove {}", path.display()))
+            }
+            _ => Ok(()),
+        };
+    }
+    let content = serde_json::to_string_pretty(entries)?;
+    std::fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Record the metadata of a snapshot just taken, under the name
+/// `create_snapshot` gave it
+pub fn record(disk_path: &Path, name: &str, meta: SnapshotMeta) -> Result<()> {
+    let name = super::snapshot::validate_snapshot_name(name)?;
+    let mut entries = load(disk_path);
+    entries.insert(name, meta);
+    save(disk_path, &entries)
+}
+
+/// Drop a deleted snapshot's metadata
+pub fn forget(disk_path: &Path, name: &str) -> Result<()> {
+    let mut entries = load(disk_path);
+    if entries.remove(name).is_some() {
+        save(disk_path, &entries)?;
+    }
+    Ok(())
+}
+
+/// Settings lines of launch.sh that differ between the snapshot's copy and
+/// the current one: `- ` only in the snapshot's, `+ ` only in the current
+pub fn script_changes(saved: &str, current: &str) -> Vec<String> {
+    let significant = |content: &str| -> Vec<String> {
+        content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(String::from)
+            .collect()
+    };
+    let saved = significant(saved);
+    let current = significant(current);
+    let removed = saved.iter().filter(|l| !current.contains(l)).map(|l| format!("- {}", l));
+    let added = current.iter().filter(|l| !saved.contains(l)).map(|l| format!("+ {}", l));
+    removed.chain(added).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sidecar_round_trip() {
+        let dir = std::env::temp_dir().join(format!("vm-curator-snapmeta-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let disk = dir.join("win98.qcow2");
+        assert_eq!(sidecar_path(&disk), dir.join("win98.qcow2.snapshots.json"));
+
+        let meta = SnapshotMeta {
+            description: "Drivers installed, before DirectX 9".to_string(),
+            context: SnapshotContext::PreUpdate,
+            launch_script: Some("MEMORY=256\n".to_string()),
+        };
+        record(&disk, "drivers", meta.clone()).unwrap();
+        record(&disk, "fresh", SnapshotMeta::default()).unwrap();
+        assert_eq!(load(&disk).get("drivers"), Some(&meta));
+        assert!(std::fs::read_to_string(sidecar_path(&disk)).unwrap().contains("\"pre-update\""));
+
+        forget(&disk, "drivers").unwrap();
+        forget(&disk, "fresh").unwrap();
+        assert!(!sidecar_path(&disk).exists());
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(
+            script_changes("# header\nMEMORY=256\nqemu-system-i386 \\\n", "MEMORY=512\n\nqemu-system-i386 \\\n"),
+            vec!["- MEMORY=256", "+ MEMORY=512"]
+        );
+    }
+}
@@ -0,0 +1,180 @@
+//! Sound Blaster 16 resources
+//!
+//! DOS games talk to the SB16 directly and only find it at the I/O port,
+//! IRQ and DMA channels they were set up for (usually from the BLASTER
+//! environment variable). These settings become properties on the QEMU
+//! `sb16` device; only values that differ from QEMU's defaults are written.
+
+use anyhow::{bail, Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// I/O base addresses the SB16 can be jumpered to
+pub const SB16_PORTS: &[u16] = &[0x220, 0x240, 0x260, 0x280];
+
+/// IRQ lines offered (7 is shared with LPT1, and the GUS default)
+pub const SB16_IRQS: &[u8] = &[5, 7, 9, 10];
+
+/// 8-bit DMA channels
+pub const SB16_DMAS: &[u8] = &[0, 1, 3];
+
+/// 16-bit ("high") DMA channels
+pub const SB16_HDMAS: &[u8] = &[5, 6, 7];
+
+/// Editable fields, in display order
+pub const SB16_FIELDS: &[&str] = &["SB Port", "SB IRQ", "SB DMA", "SB HDMA"];
+
+static RE_SB16_DEVICE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"-device\s+sb16([^\s\\]*)").expect("Invalid regex: RE_SB16_DEVICE")
+});
+
+/// Port, IRQ and DMA channels of an emulated SB16
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sb16Settings {
+    pub port: u16,
+    pub irq: u8,
+    pub dma: u8,
+    pub hdma: u8,
+}
+
+impl Default for Sb16Settings {
+    /// QEMU's defaults, which are also the Creative factory settings
+    fn default() -> Self {
+        Self {
+            port: 0x220,
+            irq: 5,
+            dma: 1,
+            hdma: 5,
+        }
+    }
+}
+
+impl Sb16Settings {
+    /// Read the settings from the `-device sb16,...` argument of a launch script
+    pub fn parse(content: &str) -> Option<Self> {
+        let caps = RE_SB16_DEVICE.captures(content)?;
+        let mut settings = Self::default();
+        for prop in caps[1].split(',') {
+            let Some((key, value)) = prop.split_once('=') else {
+                continue;
+            };
+            match key {
+                "iobase" => {
+                    let hex = value.trim_start_matches("0x");
+                    if let Ok(port) = u16::from_str_radix(hex, 16) {
+                        settings.port = port;
+                    }
+                }
+                "irq" => settings.irq = value.parse().unwrap_or(settings.irq),
+                "dma" => settings.dma = value.parse().unwrap_or(settings.dma),
+                "dma16" => settings.hdma = value.parse().unwrap_or(settings.hdma),
+                _ => {}
+            }
+        }
+        Some(settings)
+    }
+
+    /// Device properties for non-default values (e.g. ",iobase=0x240,irq=7")
+    pub fn device_properties(&self) -> String {
+        let defaults = Self::default();
+        let mut props = String::new();
+        if self.port != defaults.port {
+            props.push_str(&format!(",iobase=0x{:x}", self.port));
+        }
+        if self.irq != defaults.irq {
+            props.push_str(&format!(",irq={}", self.irq));
+        }
+        if self.dma != defaults.dma {
+            props.push_str(&format!(",dma={}", self.dma));
+        }
+        if self.hdma != defaults.hdma {
+            props.push_str(&format!(",dma16={}", self.hdma));
+        }
+        props
+    }
+
+    /// The matching BLASTER variable value (T6 = Sound Blaster 16)
+    pub fn blaster(&self) -> String {
+        format!("A{:x} I{} D{} H{} T6", self.port, self.irq, self.dma, self.hdma)
+    }
+
+    /// Display value of a field (index into `SB16_FIELDS`)
+    pub fn field_value(&self, field: usize) -> String {
+        match field {
+            0 => format!("{:x}h", self.port),
+            1 => self.irq.to_string(),
+            2 => self.dma.to_string(),
+            _ => self.hdma.to_string(),
+        }
+    }
+
+    /// Step a field (index into `SB16_FIELDS`) through its allowed values
+    pub fn cycle(&mut self, field: usize, delta: i32) {
+        match field {
+            0 => cycle_value(&mut self.port, SB16_PORTS, delta),
+            1 => cycle_value(&mut self.irq, SB16_IRQS, delta),
+            2 => cycle_value(&mut self.dma, SB16_DMAS, delta),
+            _ => cycle_value(&mut self.hdma, SB16_HDMAS, delta),
+        }
+    }
+}
+
+fn cycle_value<T: Copy + PartialEq>(current: &mut T, options: &[T], delta: i32) {
+    let current_idx = options.iter().position(|o| o == current).unwrap_or(0);
+    let new_idx = (current_idx as i32 + delta).rem_euclid(options.len() as i32) as usize;
+    *current = options[new_idx];
+}
+
+/// Rewrite the SB16 device of a launch script with new settings
+pub fn set_sb16(script_path: &Path, settings: &Sb16Settings) -> Result<()> {
+    let content = std::fs::read_to_string(script_path).context("Failed to read launch.sh")?;
+    let new_content = rewrite_sb16(&content, settings)?;
+    std::fs::write(script_path, new_content).context("Failed to write launch.sh")?;
+    Ok(())
+}
+
+fn rewrite_sb16(content: &str, settings: &Sb16Settings) -> Result<String> {
+    if !RE_SB16_DEVICE.is_match(content) {
+        bail!("This VM has no Sound Blaster 16 device");
+    }
+
+    let rewritten = RE_SB16_DEVICE.replace_all(content, |caps: &regex::Captures| {
+        // Keep unrelated properties such as audiodev=
+        let others: String = caps[1]
+            .split(',')
+            .filter(|p| !p.is_empty())
+            .filter(|p| !matches!(p.split('=').next(), Some("iobase" | "irq" | "dma" | "dma16")))
+            .map(|p| format!(",{}", p))
+            .collect();
+        format!("-device sb16{}{}", settings.device_properties(), others)
+    });
+    Ok(rewritten.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sb16_settings_round_trip() {
+        let script = "qemu-system-i386 -audiodev pa,id=audio0 -device sb16,audiodev=audio0 -m 64M\n";
+        assert_eq!(Sb16Settings::parse(script), Some(Sb16Settings::default()));
+        assert_eq!(Sb16Settings::default().blaster(), "A220 I5 D1 H5 T6");
+
+        let mut settings = Sb16Settings::default();
+        settings.cycle(0, 1);
+        settings.cycle(1, 1);
+        settings.cycle(3, -1);
+        assert_eq!(settings.blaster(), "A240 I7 D1 H7 T6");
+
+        let rewritten = rewrite_sb16(script, &settings).unwrap();
+        assert!(rewritten.contains("-device sb16,iobase=0x240,irq=7,dma16=7,audiodev=audio0 -m 64M"));
+        assert_eq!(Sb16Settings::parse(&rewritten), Some(settings));
+
+        let restored = rewrite_sb16(&rewritten, &Sb16Settings::default()).unwrap();
+        assert_eq!(restored, script);
+        assert!(rewrite_sb16("qemu-system-i386 -device AC97\n", &settings).is_err());
+    }
+}
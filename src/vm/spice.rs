@@ -0,0 +1,283 @@
+//! SPICE display integration
+//!
+//! VMs using a SPICE display get a managed section in launch.sh with the
+//! guest channels that make SPICE worthwhile: the vdagent port (clipboard
+//! sharing and automatic resolution) and USB redirection. There are two
+//! modes:
+//!
+//! - `spice-app`: QEMU runs the SPICE server and opens a viewer itself, so
+//!   the section only adds the channels.
+//! - `spice`: QEMU runs headless with a SPICE server on a socket in the VM
+//!   directory, and vm-curator starts `remote-viewer` or `spicy` after launch.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::vm::lifecycle::{insert_args_section, remove_args_section};
+
+// SPICE section markers
+const SPICE_MARKER_START: &str = "# >>> SPICE (managed by vm-curator) >>>";
+const SPICE_MARKER_END: &str = "# <<< SPICE <<<";
+
+/// File name of the SPICE server socket inside the VM directory
+const SOCKET_NAME: &str = "spice.sock";
+
+/// How long a just-launched VM gets to start its SPICE server
+const SERVER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Number of USB devices that can be redirected at once
+const USB_REDIR_SLOTS: usize = 2;
+
+/// Who runs the SPICE viewer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpiceMode {
+    /// `-display spice-app`: QEMU starts the server and the viewer
+    App,
+    /// `-display none` plus a SPICE server; vm-curator starts the viewer
+    Server,
+}
+
+impl SpiceMode {
+    /// Display name shown in the display options list
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::App => "spice-app",
+            Self::Server => "spice",
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::App => "app",
+            Self::Server => "server",
+        }
+    }
+}
+
+/// SPICE settings stored in launch.sh
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpiceConfig {
+    pub mode: SpiceMode,
+    /// Add the vdagent channel (clipboard sharing, guest resolution)
+    pub clipboard: bool,
+    /// Add USB redirection channels
+    pub usb_redir: bool,
+}
+
+impl SpiceConfig {
+    /// Defaults for a newly selected SPICE display
+    pub fn new(mode: SpiceMode) -> Self {
+        Self {
+            mode,
+            clipboard: true,
+            usb_redir: true,
+        }
+    }
+}
+
+/// Path of the SPICE server socket for a VM
+pub fn socket_path(vm_dir: &Path) -> PathBuf {
+    vm_dir.join(SOCKET_NAME)
+}
+
+/// Write (or with None, remove) the SPICE section of a launch script
+pub fn set_spice(script_path: &Path, config: Option<&SpiceConfig>) -> Result<()> {
+    let content = std::fs::read_to_string(script_path).context("Failed to read launch.sh")?;
+    let content = remove_spice_section(&content);
+
+    let new_content = match config {
+        Some(config) => insert_args_section(&content, &generate_spice_section(config), "$SPICE_ARGS"),
+        None => content,
+    };
+
+    std::fs::write(script_path, new_content).context("Failed to write launch.sh")?;
+    Ok(())
+}
+
+/// Read SPICE settings from a launch script
+pub fn parse_spice(content: &str) -> Option<SpiceConfig> {
+    let mut in_section = false;
+    let mut mode = None;
+    let mut clipboard = false;
+    let mut usb_redir = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed == SPICE_MARKER_START {
+            in_section = true;
+        } else if trimmed == SPICE_MARKER_END {
+            break;
+        } else if in_section {
+            if let Some((key, value)) = trimmed.split_once('=') {
+                let value = value.trim_matches('"');
+                match key {
+                    "SPICE_MODE" => {
+                        mode = match value {
+                            "app" => Some(SpiceMode::App),
+                            "server" => Some(SpiceMode::Server),
+                            _ => None,
+                        }
+                    }
+                    "SPICE_CLIPBOARD" => clipboard = value == "on",
+                    "SPICE_USBREDIR" => usb_redir = value == "on",
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    mode.map(|mode| SpiceConfig { mode, clipboard, usb_redir })
+}
+
+/// Wait for a just-launched VM's SPICE server to accept connections
+///
+/// A socket left by an earlier run may still be there, so the socket has
+/// to take a connection rather than just exist.
+pub fn wait_for_server(vm_dir: &Path) -> Result<()> {
+    let socket = socket_path(vm_dir);
+    let started = Instant::now();
+    while crate::vm::local_socket::LocalStream::connect(&socket).is_err() {
+        if started.elapsed() > SERVER_TIMEOUT {
+            bail!("the SPICE server didn't start within {}s", SERVER_TIMEOUT.as_secs());
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    Ok(())
+}
+
+/// Open a SPICE viewer on a server-mode VM's socket
+///
+/// Returns the name of the viewer that was started.
+pub fn launch_viewer(vm_dir: &Path, title: &str) -> Result<&'static str> {
+    let Some(viewer) = crate::commands::qemu_system::find_spice_viewer() else {
+        bail!("No SPICE viewer found (install virt-viewer or spice-gtk)");
+    };
+
+    let uri = format!("spice+unix://{}", socket_path(vm_dir).display());
+    let mut cmd = Command::new(viewer);
+    match viewer {
+        "spicy" => cmd.arg(format!("--uri={}", uri)),
+        _ => cmd.arg(format!("--title={}", title)).arg(&uri),
+    };
+
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to start {}", viewer))?;
+
+    Ok(viewer)
+}
+
+fn spice_args(config: &SpiceConfig) -> Vec<String> {
+    let mut args = Vec::new();
+
+    if config.mode == SpiceMode::Server {
+        args.push(format!(
+            "-spice unix=on,addr=$VM_DIR/{},disable-ticketing=on",
+            SOCKET_NAME
+        ));
+    }
+
+    if config.clipboard {
+        args.push("-device virtio-serial-pci,id=spice-serial".to_string());
+        args.push("-chardev spicevmc,id=vdagent,name=vdagent".to_string());
+        args.push(
+            "-device virtserialport,bus=spice-serial.0,chardev=vdagent,name=com.redhat.spice.0"
+                .to_string(),
+        );
+    }
+
+    if config.usb_redir {
+        args.push("-device qemu-xhci,id=spice-xhci".to_string());
+        for slot in 1..=USB_REDIR_SLOTS {
+            args.push(format!("-chardev spicevmc,name=usbredir,id=usbredir{}", slot));
+            args.push(format!(
+                "-device usb-redir,chardev=usbredir{},bus=spice-xhci.0",
+                slot
+            ));
+        }
+    }
+
+    args
+}
+
+fn generate_spice_section(config: &SpiceConfig) -> String {
+    let on_off = |enabled: bool| if enabled { "on" } else { "off" };
+
+    let mut section = String::new();
+    section.push_str(SPICE_MARKER_START);
+    section.push('\n');
+    section.push_str(&format!("SPICE_MODE=\"{}\"\n", config.mode.as_str()));
+    section.push_str(&format!("SPICE_CLIPBOARD=\"{}\"\n", on_off(config.clipboard)));
+    section.push_str(&format!("SPICE_USBREDIR=\"{}\"\n", on_off(config.usb_redir)));
+    section.push_str(&format!("SPICE_ARGS=\"{}\"\n", spice_args(config).join(" ")));
+    section.push_str(SPICE_MARKER_END);
+    section.push('\n');
+    section
+}
+
+fn remove_spice_section(content: &str) -> String {
+    remove_args_section(content, SPICE_MARKER_START, SPICE_MARKER_END, "$SPICE_ARGS")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spice_section_round_trip() {
+        let script = "VM_DIR=\"/vms/t\"\ncase \"$1\" in\n    \"\")\n        qemu-system-x86_64 \\\n            -display none\n        ;;\nesac\n";
+        let config = SpiceConfig {
+            mode: SpiceMode::Server,
+            clipboard: true,
+            usb_redir: false,
+        };
+        let with_spice = insert_args_section(script, &generate_spice_section(&config), "$SPICE_ARGS");
+
+        assert_eq!(parse_spice(&with_spice), Some(config));
+        assert!(with_spice.contains("-spice unix=on,addr=$VM_DIR/spice.sock"));
+        assert!(with_spice.contains("name=com.redhat.spice.0"));
+        assert!(!with_spice.contains("usb-redir"));
+        assert!(with_spice.contains("-display none $SPICE_ARGS"));
+
+        let restored = remove_spice_section(&with_spice);
+        assert_eq!(parse_spice(&restored), None);
+        assert!(!restored.contains("SPICE"));
+    }
+
+    #[test]
+    fn test_app_mode_leaves_server_to_qemu() {
+        let args = spice_args(&SpiceConfig::new(SpiceMode::App));
+        assert!(!args.iter().any(|a| a.starts_with("-spice")));
+        assert_eq!(args.iter().filter(|a| a.starts_with("-device usb-redir")).count(), USB_REDIR_SLOTS);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_wait_for_server_once_listening() {
+        let dir = std::env::temp_dir().join(format!("vm-curator-spice-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let socket = socket_path(&dir);
+        let _ = std::fs::remove_file(&socket);
+
+        // The server comes up a little after the launch returns
+        let listener = {
+            let socket = socket.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(300));
+                std::os::unix::net::UnixListener::bind(socket).unwrap()
+            })
+        };
+        let started = Instant::now();
+        wait_for_server(&dir).unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(300));
+
+        drop(listener.join().unwrap());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
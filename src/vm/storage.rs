@@ -0,0 +1,223 @@
+//! Disk usage of the library
+//!
+//! For each VM: the size its disks claim (virtual) against what they take
+//! on the host (actual), the saved machine state in internal snapshots,
+//! the install ISOs kept in its folder, and the space the whole folder
+//! takes. Sizes come from `qemu-img info`, and from the file system for
+//! everything qemu-img doesn't know about.
+//!
+//! qcow2 images never shrink on their own: clusters the guest freed, and
+//! clusters kept only by deleted snapshots, stay allocated. Comparing an
+//! image's allocation with what `qemu-img measure` says a fresh copy needs
+//! tells how much compacting it would give back.
+
+use std::path::Path;
+
+use super::DiscoveredVm;
+use crate::commands::qemu_img;
+use crate::fs::allocated_size;
+
+/// Compaction is suggested once it would reclaim this much...
+const MIN_RECLAIM: u64 = 256 * 1024 * 1024;
+
+/// ...and the image takes this many times the space its data needs
+const MIN_ALLOCATED_RATIO: f64 = 1.2;
+
+/// Disk usage of one VM
+#[derive(Debug, Clone, Default)]
+pub struct VmStorage {
+    pub vm_id: String,
+    pub vm_name: String,
+    /// Sum of the disks' guest-visible sizes
+    pub virtual_size: u64,
+    /// Space the disks take on the host
+    pub actual_size: u64,
+    pub snapshots: usize,
+    /// Saved machine state held by internal snapshots
+    pub snapshot_size: u64,
+    /// ISO images in the VM folder
+    pub iso_size: u64,
+    /// Space the whole VM folder takes
+    pub total_size: u64,
+    /// What compacting its qcow2 disks would give back, when worth it
+    pub reclaimable: Option<u64>,
+}
+
+impl VmStorage {
+    /// Guest-visible space not allocated on the host yet
+    pub fn sparse_savings(&self) -> u64 {
+        self.virtual_size.saturating_sub(self.actual_size)
+    }
+}
+
+/// Column the dashboard is sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StorageSort {
+    #[default]
+    Total,
+    Actual,
+    Virtual,
+    Snapshots,
+    Iso,
+    Reclaimable,
+    Name,
+}
+
+impl StorageSort {
+    pub fn label(&self) -> &'static str {
+        match self {
+            StorageSort::Total => "total",
+            StorageSort::Actual => "actual size",
+            StorageSort::Virtual => "virtual size",
+            StorageSort::Snapshots => "snapshots",
+            StorageSort::Iso => "ISOs",
+            StorageSort::Reclaimable => "reclaimable",
+            StorageSort::Name => "name",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            StorageSort::Total => StorageSort::Actual,
+            StorageSort::Actual => StorageSort::Virtual,
+            StorageSort::Virtual => StorageSort::Snapshots,
+            StorageSort::Snapshots => StorageSort::Iso,
+            StorageSort::Iso => StorageSort::Reclaimable,
+            StorageSort::Reclaimable => StorageSort::Name,
+            StorageSort::Name => StorageSort::Total,
+        }
+    }
+}
+
+/// Sort VMs by a column: sizes largest first, names alphabetically
+pub fn sort(rows: &mut [VmStorage], by: StorageSort) {
+    rows.sort_by(|a, b| {
+        let key = |s: &VmStorage| match by {
+            StorageSort::Total => s.total_size,
+            StorageSort::Actual => s.actual_size,
+            StorageSort::Virtual => s.virtual_size,
+            StorageSort::Snapshots => s.snapshot_size,
+            StorageSort::Iso => s.iso_size,
+            StorageSort::Reclaimable => s.reclaimable.unwrap_or(0),
+            StorageSort::Name => 0,
+        };
+        key(b)
+            .cmp(&key(a))
+            .then_with(|| a.vm_name.to_lowercase().cmp(&b.vm_name.to_lowercase()))
+    });
+}
+
+/// Measure a local VM's disk usage (runs `qemu-img info` per disk)
+pub fn measure(vm: &DiscoveredVm) -> VmStorage {
+    let mut storage = VmStorage {
+        vm_id: vm.id.clone(),
+        vm_name: vm.display_name(),
+        total_size: allocated_bytes(&vm.path),
+        iso_size: iso_bytes(&vm.path),
+        ..Default::default()
+    };
+    for disk in vm.config.disks.iter().filter(|d| d.remote.is_none() && d.path.exists()) {
+        match qemu_img::image_info(&disk.path) {
+            Some(info) => {
+                storage.virtual_size += info.virtual_size;
+                storage.actual_size += info.actual_size;
+            }
+            None => {
+                let size = std::fs::metadata(&disk.path).map(|m| m.len()).unwrap_or(0);
+                storage.virtual_size += size;
+                storage.actual_size += size;
+            }
+        }
+        if disk.format.supports_snapshots() {
+            let allocated = std::fs::metadata(&disk.path).map(|m| allocated_size(&m)).unwrap_or(0);
+            let suggestion = qemu_img::measure_required(&disk.path)
+                .and_then(|required| compaction_suggestion(allocated, required));
+            if let Some(bytes) = suggestion {
+                storage.reclaimable = Some(storage.reclaimable.unwrap_or(0) + bytes);
+            }
+            let snapshots = super::snapshot::list_snapshots(&disk.path).unwrap_or_default();
+            storage.snapshots += snapshots.len();
+            storage.snapshot_size += snapshots.iter().map(|s| s.size_bytes).sum::<u64>();
+        }
+    }
+    storage
+}
+
+/// Bytes compacting an image would reclaim, if enough to suggest it
+pub fn compaction_suggestion(allocated: u64, required: u64) -> Option<u64> {
+    let reclaim = allocated.saturating_sub(required);
+    let ratio = allocated as f64 / required.max(1) as f64;
+    (reclaim >= MIN_RECLAIM && ratio >= MIN_ALLOCATED_RATIO).then_some(reclaim)
+}
+
+/// Space allocated to a folder's files (sparse images count what they use)
+pub fn allocated_bytes(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(meta) if meta.is_dir() => allocated_bytes(&entry.path()),
+            Ok(meta) => allocated_size(&meta),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Space the ISO images directly in a folder take
+fn iso_bytes(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .filter(|entry| {
+            entry
+                .path()
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("iso"))
+        })
+        .filter_map(|entry| entry.metadata().ok().filter(|m| m.is_file()))
+        .map(|meta| allocated_size(&meta))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(name: &str, total: u64, snapshots: u64) -> VmStorage {
+        VmStorage {
+            vm_name: name.to_string(),
+            total_size: total,
+            snapshot_size: snapshots,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut rows = vec![row("dos", 10, 0), row("Win98", 30, 5), row("beos", 30, 0), row("os2", 20, 9)];
+        sort(&mut rows, StorageSort::Total);
+        let names: Vec<&str> = rows.iter().map(|r| r.vm_name.as_str()).collect();
+        assert_eq!(names, ["beos", "Win98", "os2", "dos"]);
+
+        sort(&mut rows, StorageSort::Snapshots);
+        assert_eq!(rows[0].vm_name, "os2");
+        sort(&mut rows, StorageSort::Name);
+        let names: Vec<&str> = rows.iter().map(|r| r.vm_name.as_str()).collect();
+        assert_eq!(names, ["beos", "dos", "os2", "Win98"]);
+    }
+
+    #[test]
+    fn test_compaction_suggestion() {
+        const GIB: u64 = 1024 * 1024 * 1024;
+        // 10 GiB allocated for 5.8 GiB of data
+        assert_eq!(compaction_suggestion(10 * GIB, 58 * GIB / 10), Some(10 * GIB - 58 * GIB / 10));
+        // A few percent of slack isn't worth a rewrite
+        assert_eq!(compaction_suggestion(40 * GIB, 39 * GIB), None);
+        // Neither is a small image, however bloated
+        assert_eq!(compaction_suggestion(200 * 1024 * 1024, 10 * 1024 * 1024), None);
+    }
+}
@@ -0,0 +1,155 @@
+//! Suspend to disk
+//!
+//! Parks a running QEMU VM like closing a laptop lid: its whole state
+//! (RAM, devices, CPU) is migrated into a file in the VM directory and
+//! QEMU quits. The next normal launch starts QEMU waiting for incoming
+//! state (the managed incoming migration section of launch.sh) and feeds
+//! it the file, so the guest carries on where it was. Once the guest is
+//! running again the file is removed, since the disks move on from it.
+//!
+//! Booting any other way throws the saved state away.
+
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::discovery::DiscoveredVm;
+use super::host_migration::add_incoming_section;
+use super::qmp;
+use crate::vm::create::shell_escape;
+
+/// File holding a suspended VM's state, in the VM directory
+const STATE_FILE: &str = "suspended.state";
+
+/// Written while suspending, renamed to `STATE_FILE` once complete
+const PARTIAL_FILE: &str = "suspended.state.part";
+
+/// How long a resumed VM may take to load its state
+const RESUME_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Path of a VM's saved state
+pub fn state_path(vm_dir: &Path) -> PathBuf {
+    vm_dir.join(STATE_FILE)
+}
+
+/// Whether a VM was suspended and can be resumed
+pub fn has_saved_state(vm_dir: &Path) -> bool {
+    state_path(vm_dir).is_file()
+}
+
+/// Refuse to change a suspended VM's disks
+///
+/// Resuming loads the saved RAM over the disks as they were when it was
+/// suspended; a guest finding different disks under its file system
+/// caches corrupts them.
+pub fn ensure_not_suspended(vm_dir: &Path) -> Result<()> {
+    if has_saved_state(vm_dir) {
+        bail!("The VM is suspended; resume and shut it down, or discard its saved state, before changing its disk");
+    }
+    Ok(())
+}
+
+/// Size of a VM's saved state in bytes, if it has one
+pub fn saved_state_size(vm_dir: &Path) -> Option<u64> {
+    std::fs::metadata(state_path(vm_dir)).ok().filter(|m| m.is_file()).map(|m| m.len())
+}
+
+/// Throw a VM's saved state away
+pub fn discard(vm_dir: &Path) -> Result<()> {
+    match std::fs::remove_file(state_path(vm_dir)) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e).context("Failed to remove the saved state"),
+    }
+}
+
+/// `-incoming` URI that loads the saved state
+///
+/// Relative to the VM directory QEMU runs in, and without spaces, since
+/// launch.sh word-splits `$INCOMING_ARGS`.
+pub fn incoming_uri() -> String {
+    format!("exec:cat<{}", STATE_FILE)
+}
+
+/// Save a running VM's state to disk and stop it
+///
+/// Reports the share of guest RAM written (0.0 - 1.0) and returns the
+/// size of the saved state.
+pub fn suspend<F>(vm: &DiscoveredVm, on_progress: F) -> Result<u64>
+where
+    F: FnMut(f32),
+{
+    if vm.remote.is_some() {
+        bail!("Only VMs on this computer can be suspended");
+    }
+    if !qmp::is_configured(&vm.config.raw_script) {
+        bail!("The VM has no QMP socket to save its state through");
+    }
+
+    // bash is still running launch.sh, reading it as it goes, so the new
+    // script goes in as a new file rather than over the one it reads
+    let content = std::fs::read_to_string(&vm.launch_script).context("Failed to read launch.sh")?;
+    let updated = add_incoming_section(&content);
+    if updated != content {
+        crate::fs::replace_file(&vm.launch_script, updated)?;
+    }
+
+    let partial = vm.path.join(PARTIAL_FILE);
+    let _ = std::fs::remove_file(&partial);
+    let uri = format!("exec:cat > {}", shell_escape(&partial.to_string_lossy()));
+    if let Err(e) = qmp::save_state(&vm.path, &uri, on_progress) {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e);
+    }
+
+    let state = state_path(&vm.path);
+    std::fs::rename(&partial, &state).context("Failed to keep the saved state")?;
+    Ok(std::fs::metadata(&state).map(|m| m.len()).unwrap_or(0))
+}
+
+/// Remove the saved state once a resumed VM is running again
+///
+/// Runs in the background. A VM that fails to load its state exits
+/// without reaching `running`, so the file is kept for another try.
+pub fn finish_resume(vm_dir: PathBuf) {
+    std::thread::spawn(move || {
+        let start = std::time::Instant::now();
+        while start.elapsed() < RESUME_TIMEOUT {
+            std::thread::sleep(Duration::from_secs(1));
+            if qmp::query_status(&vm_dir).is_ok_and(|status| status == "running") {
+                if let Err(e) = discard(&vm_dir) {
+                    tracing::warn!(dir = %vm_dir.display(), "{:#}", e);
+                }
+                return;
+            }
+        }
+        tracing::warn!(dir = %vm_dir.display(), "resumed VM never reached the running state; saved state kept");
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_saved_state_files() {
+        let dir = std::env::temp_dir().join(format!("vm-curator-suspend-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        assert!(!has_saved_state(&dir));
+        assert!(discard(&dir).is_ok());
+        std::fs::write(state_path(&dir), b"QEVM").unwrap();
+        assert!(has_saved_state(&dir));
+        assert!(ensure_not_suspended(&dir).is_err());
+        assert_eq!(saved_state_size(&dir), Some(4));
+        discard(&dir).unwrap();
+        assert!(!has_saved_state(&dir));
+        assert!(ensure_not_suspended(&dir).is_ok());
+
+        // launch.sh splits the URI on whitespace
+        assert_eq!(incoming_uri(), "exec:cat<suspended.state");
+        assert!(!incoming_uri().contains(char::is_whitespace));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,273 @@
+//! systemd user services for VMs
+//!
+//! A VM can get a `systemd --user` service that runs its launch.sh, so it
+//! starts with the desktop session or at boot. The service runs QEMU from
+//! the VM's folder like any other launch, so the TUI sees it as running;
+//! stopping it from the TUI goes through systemctl so the service does
+//! not end up marked as failed. Starting at boot needs lingering
+//! (`loginctl enable-linger`) and a VNC or SPICE display, since there is
+//! no desktop to open a window on.
+
+use anyhow::{bail, Context, Result};
+use std::path::PathBuf;
+use std::process::Command;
+
+use super::discovery::DiscoveredVm;
+use super::launch_env::LaunchEnvironment;
+
+/// When an installed service starts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartAt {
+    /// Only when started by hand (systemctl or the TUI)
+    Manual,
+    /// With the graphical session
+    Login,
+    /// With the user's service manager, at boot when lingering
+    Boot,
+}
+
+impl StartAt {
+    pub const ALL: [StartAt; 3] = [StartAt::Manual, StartAt::Login, StartAt::Boot];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Manual => "Manually (systemctl --user start)",
+            Self::Login => "At login (with the desktop session)",
+            Self::Boot => "At boot (headless, VNC or SPICE display)",
+        }
+    }
+
+    /// Target the service is wanted by
+    fn target(&self) -> Option<&'static str> {
+        match self {
+            Self::Manual => None,
+            Self::Login => Some("graphical-session.target"),
+            Self::Boot => Some("default.target"),
+        }
+    }
+
+    fn from_target(target: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|s| s.target() == Some(target))
+            .unwrap_or(Self::Manual)
+    }
+}
+
+/// What is installed for a VM
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnitStatus {
+    pub installed: bool,
+    pub start_at: StartAt,
+    pub active: bool,
+}
+
+/// Service name for a VM (characters systemd does not allow become '-')
+pub fn unit_name(vm_id: &str) -> String {
+    let id: String = vm_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || "_.-".contains(c) { c } else { '-' })
+        .collect();
+    format!("vm-curator-{}.service", id)
+}
+
+/// Where the VM's service file goes
+pub fn unit_path(vm_id: &str) -> Result<PathBuf> {
+    let config = dirs::config_dir().context("Cannot find the config directory")?;
+    Ok(config.join("systemd/user").join(unit_name(vm_id)))
+}
+
+/// Escape a value for a unit file (specifiers and variables are literal)
+fn escape_value(value: &str) -> String {
+    value.replace('%', "%%").replace('$', "$$")
+}
+
+/// Quote a path as one ExecStart argument
+fn quote_arg(value: &str) -> String {
+    format!("\"{}\"", escape_value(value).replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Contents of the service file for a VM, with its launch environment
+pub fn generate_unit(vm: &DiscoveredVm, start_at: StartAt, environment: &LaunchEnvironment) -> String {
+    let dir = vm.path.to_string_lossy();
+    let script = vm.launch_script.to_string_lossy();
+
+    let mut unit = String::new();
+    unit.push_str("# Generated by vm-curator; change it from the VM's Autostart menu\n");
+    unit.push_str("[Unit]\n");
+    unit.push_str(&format!("Description=vm-curator VM: {}\n", escape_value(&vm.display_name())));
+    if start_at == StartAt::Login {
+        // Stops with the session, and has its display variables
+        unit.push_str("PartOf=graphical-session.target\n");
+        unit.push_str("After=graphical-session.target\n");
+    } else {
+        unit.push_str("After=network-online.target\n");
+    }
+    unit.push('\n');
+    unit.push_str("[Service]\n");
+    unit.push_str("Type=simple\n");
+    unit.push_str(&format!("WorkingDirectory={}\n", escape_value(&dir)));
+    for var in &environment.env {
+        unit.push_str(&format!("Environment={}\n", quote_arg(var)));
+    }
+    if let Ok(Some(path)) = environment.path_var() {
+        unit.push_str(&format!("Environment={}\n", quote_arg(&format!("PATH={}", path.to_string_lossy()))));
+    }
+    let wrapper: Vec<String> = environment.wrapper.split_whitespace().map(quote_arg).collect();
+    let exec = if wrapper.is_empty() { String::new() } else { format!("{} ", wrapper.join(" ")) };
+    unit.push_str(&format!("ExecStart={}/bin/bash {}\n", exec, quote_arg(&script)));
+    // QEMU shuts the guest down on SIGTERM; give it time before SIGKILL
+    unit.push_str("TimeoutStopSec=90\n");
+    if let Some(target) = start_at.target() {
+        unit.push('\n');
+        unit.push_str("[Install]\n");
+        unit.push_str(&format!("WantedBy={}\n", target));
+    }
+    unit
+}
+
+/// Start setting read back from an installed service file
+fn parse_start_at(content: &str) -> StartAt {
+    content
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("WantedBy="))
+        .map(|target| StartAt::from_target(target.trim()))
+        .unwrap_or(StartAt::Manual)
+}
+
+fn systemctl(args: &[&str]) -> Result<String> {
+    let output = Command::new("systemctl")
+        .arg("--user")
+        .args(args)
+        .output()
+        .context("Failed to run systemctl")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("systemctl {}: {}", args.join(" "), stderr.trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Whether the VM's service is running
+pub fn is_active(vm_id: &str) -> bool {
+    systemctl(&["is-active", "--quiet", &unit_name(vm_id)]).is_ok()
+}
+
+/// What is installed for a VM, and whether its service is running
+pub fn status(vm_id: &str) -> UnitStatus {
+    let content = unit_path(vm_id).ok().and_then(|path| std::fs::read_to_string(path).ok());
+    UnitStatus {
+        installed: content.is_some(),
+        start_at: content.as_deref().map(parse_start_at).unwrap_or(StartAt::Manual),
+        active: content.is_some() && is_active(vm_id),
+    }
+}
+
+/// Write (or rewrite) the VM's service and enable it as asked
+///
+/// Returns a warning when the service is installed but will not start at
+/// boot because lingering could not be turned on.
+pub fn install(vm: &DiscoveredVm, start_at: StartAt) -> Result<Option<String>> {
+    let path = unit_path(&vm.id)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let name = unit_name(&vm.id);
+    // Drop links to a previous target before the file changes
+    if path.exists() {
+        let _ = systemctl(&["disable", &name]);
+    }
+    std::fs::write(&path, generate_unit(vm, start_at, &super::launch_env::load(&vm.path)))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    systemctl(&["daemon-reload"])?;
+
+    if start_at != StartAt::Manual {
+        systemctl(&["enable", &name])?;
+    }
+    if start_at == StartAt::Boot {
+        let lingering = Command::new("loginctl")
+            .arg("enable-linger")
+            .status()
+            .map(|s| s.success())
+            .unwrap_or(false);
+        if !lingering {
+            return Ok(Some("Run `loginctl enable-linger` for the VM to start before you log in".to_string()));
+        }
+    }
+    Ok(None)
+}
+
+/// Disable and delete the VM's service (a running VM keeps running)
+pub fn remove(vm_id: &str) -> Result<()> {
+    let path = unit_path(vm_id)?;
+    let _ = systemctl(&["disable", &unit_name(vm_id)]);
+    if path.exists() {
+        std::fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+    }
+    systemctl(&["daemon-reload"])?;
+    Ok(())
+}
+
+/// Start the VM's service now
+pub fn start(vm_id: &str) -> Result<()> {
+    systemctl(&["start", &unit_name(vm_id)])?;
+    Ok(())
+}
+
+/// Stop the VM's service (QEMU gets SIGTERM)
+pub fn stop(vm_id: &str) -> Result<()> {
+    // --no-block: the guest may take a while to shut down
+    systemctl(&["stop", "--no-block", &unit_name(vm_id)])?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::qemu_config::QemuConfig;
+    use std::path::Path;
+
+    fn test_vm(dir: &str) -> DiscoveredVm {
+        DiscoveredVm {
+            id: "windows-98".to_string(),
+            path: PathBuf::from(dir),
+            launch_script: Path::new(dir).join("launch.sh"),
+            config: QemuConfig::default(),
+            backend: Default::default(),
+            custom_name: Some("Win 98 (100%)".to_string()),
+            os_profile: None,
+            remote: None,
+        }
+    }
+
+    #[test]
+    fn test_generate_unit() {
+        let unit = generate_unit(&test_vm("/vms/my \"98\""), StartAt::Login, &LaunchEnvironment::default());
+        assert!(unit.contains("Description=vm-curator VM: Win 98 (100%%)\n"));
+        assert!(unit.contains("WorkingDirectory=/vms/my \"98\"\n"));
+        assert!(unit.contains("ExecStart=/bin/bash \"/vms/my \\\"98\\\"/launch.sh\"\n"));
+        assert!(unit.contains("WantedBy=graphical-session.target\n"));
+        assert_eq!(parse_start_at(&unit), StartAt::Login);
+
+        let unit = generate_unit(&test_vm("/vms/98"), StartAt::Boot, &LaunchEnvironment::default());
+        assert_eq!(parse_start_at(&unit), StartAt::Boot);
+        let unit = generate_unit(&test_vm("/vms/98"), StartAt::Manual, &LaunchEnvironment::default());
+        assert!(!unit.contains("[Install]"));
+        assert_eq!(parse_start_at(&unit), StartAt::Manual);
+
+        let environment = LaunchEnvironment {
+            env: vec!["SDL_VIDEODRIVER=x11".to_string()],
+            wrapper: "nice -n 10".to_string(),
+            qemu_binary: None,
+        };
+        let unit = generate_unit(&test_vm("/vms/98"), StartAt::Manual, &environment);
+        assert!(unit.contains("Environment=\"SDL_VIDEODRIVER=x11\"\n"));
+        assert!(unit.contains("ExecStart=\"nice\" \"-n\" \"10\" /bin/bash \"/vms/98/launch.sh\"\n"));
+    }
+
+    #[test]
+    fn test_unit_name() {
+        assert_eq!(unit_name("windows-98"), "vm-curator-windows-98.service");
+        assert_eq!(unit_name("my vm@attic"), "vm-curator-my-vm-attic.service");
+    }
+}
@@ -0,0 +1,463 @@
+//! Unattended Linux installs (kickstart and preseed)
+//!
+//! Red Hat-family installers read a kickstart file from a disc labelled
+//! `OEMDRV`; Debian-family installers read `/preseed.cfg` from their
+//! initrd, so the preseed file is appended to it as a second archive.
+//! Either way the installer kernel and initrd are copied out of the ISO so
+//! the `--install` boot can pass the matching kernel arguments directly.
+
+use anyhow::{bail, Context, Result};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use crate::commands::iso;
+use crate::vm::cloud_init::{crypt_password, crypt_password_md5};
+
+/// Directory inside the VM folder holding generated install files
+pub const UNATTENDED_DIR: &str = "unattended";
+
+// Unattended section markers
+const UNATTENDED_MARKER_START: &str = "# >>> Unattended Install (managed by vm-curator) >>>";
+const UNATTENDED_MARKER_END: &str = "# <<< Unattended Install <<<";
+
+/// Answer-file flavour used by an installer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnattendedKind {
+    /// Anaconda kickstart (Fedora, RHEL, CentOS, Red Hat Linux)
+    Kickstart,
+    /// debian-installer preseed (Debian, older Ubuntu)
+    Preseed,
+}
+
+impl UnattendedKind {
+    /// Answer-file flavour for an OS profile, if its installer supports one
+    pub fn for_profile(os_profile: &str) -> Option<Self> {
+        match os_profile {
+            "linux-fedora" | "linux-centos" | "linux-rocky" | "linux-alma" | "linux-redhat-7" => {
+                Some(Self::Kickstart)
+            }
+            "linux-debian" | "linux-ubuntu" => Some(Self::Preseed),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Kickstart => "Kickstart",
+            Self::Preseed => "Preseed",
+        }
+    }
+
+    fn file_name(&self) -> &'static str {
+        match self {
+            Self::Kickstart => "ks.cfg",
+            Self::Preseed => "preseed.cfg",
+        }
+    }
+
+    /// Known (kernel, initrd) locations inside installation ISOs
+    fn kernel_candidates(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::Kickstart => &[
+                ("/images/pxeboot/vmlinuz", "/images/pxeboot/initrd.img"),
+                ("/isolinux/vmlinuz", "/isolinux/initrd.img"),
+            ],
+            Self::Preseed => &[
+                ("/install.amd/vmlinuz", "/install.amd/initrd.gz"),
+                ("/install.386/vmlinuz", "/install.386/initrd.gz"),
+                ("/install/vmlinuz", "/install/initrd.gz"),
+            ],
+        }
+    }
+}
+
+/// User-facing unattended install settings collected by the wizard
+#[derive(Debug, Clone)]
+pub struct UnattendedConfig {
+    pub kind: UnattendedKind,
+    /// Pre-2005 Red Hat syntax (no `%end`, no `autopart`)
+    pub legacy: bool,
+    /// Archive a preseeded install fetches packages from (host, directory)
+    pub mirror: (&'static str, &'static str),
+    pub hostname: String,
+    pub username: String,
+    pub password: String,
+    pub timezone: String,
+}
+
+impl UnattendedConfig {
+    /// Create a config for an OS profile, or None if it has no unattended support
+    pub fn for_profile(os_profile: &str, hostname: &str) -> Option<Self> {
+        UnattendedKind::for_profile(os_profile).map(|kind| Self {
+            kind,
+            legacy: os_profile == "linux-redhat-7",
+            mirror: match os_profile {
+                "linux-ubuntu" => ("archive.ubuntu.com", "/ubuntu"),
+                _ => ("deb.debian.org", "/debian"),
+            },
+            hostname: hostname.to_string(),
+            username: "user".to_string(),
+            password: String::new(),
+            timezone: "UTC".to_string(),
+        })
+    }
+
+    /// Validate the settings before generating install files
+    pub fn validate(&self) -> Result<(), String> {
+        if self.hostname.trim().is_empty() {
+            return Err("Unattended install hostname cannot be empty".to_string());
+        }
+        validate_hostname(&self.hostname)?;
+        if self.username.trim().is_empty() {
+            return Err("Unattended install username cannot be empty".to_string());
+        }
+        validate_username(&self.username)?;
+        if self.password.is_empty() {
+            return Err("Unattended install needs a password".to_string());
+        }
+        if iso::find_extractor().is_none() {
+            return Err("Unattended install requires bsdtar, xorriso, or isoinfo".to_string());
+        }
+        if self.kind == UnattendedKind::Kickstart && iso::find_builder().is_none() {
+            return Err("Kickstart media requires genisoimage, mkisofs, or xorriso".to_string());
+        }
+        Ok(())
+    }
+
+    /// Kernel command line for the installer
+    fn kernel_args(&self) -> String {
+        match (self.kind, self.legacy) {
+            // The OEMDRV disc is IDE index 2 (hdc) on old kernels
+            (UnattendedKind::Kickstart, true) => "ks=hd:hdc/ks.cfg text".to_string(),
+            (UnattendedKind::Kickstart, false) => "inst.ks=hd:LABEL=OEMDRV:/ks.cfg inst.text".to_string(),
+            // The preseed file itself is in the initrd
+            (UnattendedKind::Preseed, _) => format!(
+                "priority=critical netcfg/get_hostname={} netcfg/get_domain=localdomain",
+                self.hostname
+            ),
+        }
+    }
+
+    /// The password as a crypt hash the installer understands: MD5 for
+    /// pre-2005 Red Hat, SHA-512 otherwise
+    fn password_hash(&self) -> Result<String> {
+        let hash = if self.legacy { crypt_password_md5(&self.password) } else { crypt_password(&self.password) };
+        hash.context("Failed to hash the unattended install password (is openssl installed?)")
+    }
+}
+
+/// Check a hostname is a single DNS label (RFC 1123)
+pub fn validate_hostname(hostname: &str) -> Result<(), String> {
+    let valid = (1..=63).contains(&hostname.len())
+        && hostname.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        && !hostname.starts_with('-')
+        && !hostname.ends_with('-');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Hostname '{}' must be 1-63 letters, digits or hyphens, not starting or ending with a hyphen", hostname))
+    }
+}
+
+/// Check a login name is one every installer accepts
+pub fn validate_username(username: &str) -> Result<(), String> {
+    let valid = (1..=32).contains(&username.len())
+        && username.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+        && username.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Username '{}' must be up to 32 lowercase letters, digits, '_' or '-', starting with a letter", username))
+    }
+}
+
+/// Generate a kickstart file, with the password as `password_hash`
+pub fn generate_kickstart(config: &UnattendedConfig, password_hash: &str) -> String {
+    let mut ks = String::from("# Kickstart generated by vm-curator\n");
+
+    if config.legacy {
+        ks.push_str("install\ncdrom\ntext\nlang en_US\nkeyboard us\nmouse none\nskipx\n");
+        ks.push_str(&format!("network --bootproto dhcp --hostname {}\n", config.hostname));
+        ks.push_str(&format!("timezone --utc {}\n", config.timezone));
+        ks.push_str(&format!("rootpw --iscrypted {}\n", password_hash));
+        ks.push_str("auth --useshadow --enablemd5\n");
+        ks.push_str("zerombr yes\nclearpart --all\n");
+        ks.push_str("part swap --size 256\npart / --size 1 --grow\n");
+        ks.push_str("lilo --location mbr\nreboot\n\n");
+        ks.push_str("%packages\n@ Base\n\n");
+        ks.push_str("%post\n");
+        ks.push_str(&format!("/usr/sbin/useradd -p '{}' {}\n", password_hash, config.username));
+        return ks;
+    }
+
+    ks.push_str("text\nlang en_US.UTF-8\nkeyboard us\n");
+    ks.push_str(&format!("network --bootproto=dhcp --hostname={}\n", config.hostname));
+    ks.push_str(&format!("timezone {} --utc\n", config.timezone));
+    ks.push_str("rootpw --lock\n");
+    ks.push_str(&format!(
+        "user --name={} --groups=wheel --iscrypted --password={}\n",
+        config.username, password_hash
+    ));
+    ks.push_str("zerombr\nclearpart --all --initlabel\nautopart\nbootloader\nreboot\n\n");
+    ks.push_str("%packages\n@core\n%end\n");
+    ks
+}
+
+/// Generate a debian-installer preseed file, with the password as `password_hash`
+pub fn generate_preseed(config: &UnattendedConfig, password_hash: &str) -> String {
+    let mut lines = vec![
+        "# Preseed generated by vm-curator".to_string(),
+        "d-i debian-installer/locale string en_US.UTF-8".to_string(),
+        "d-i keyboard-configuration/xkb-keymap select us".to_string(),
+        "d-i netcfg/choose_interface select auto".to_string(),
+        format!("d-i netcfg/get_hostname string {}", config.hostname),
+        "d-i netcfg/get_domain string localdomain".to_string(),
+        "d-i mirror/country string manual".to_string(),
+        format!("d-i mirror/http/hostname string {}", config.mirror.0),
+        format!("d-i mirror/http/directory string {}", config.mirror.1),
+        "d-i mirror/http/proxy string".to_string(),
+        "d-i passwd/root-login boolean false".to_string(),
+        format!("d-i passwd/user-fullname string {}", config.username),
+        format!("d-i passwd/username string {}", config.username),
+        format!("d-i passwd/user-password-crypted password {}", password_hash),
+        "d-i clock-setup/utc boolean true".to_string(),
+        format!("d-i time/zone string {}", config.timezone),
+        "d-i partman-auto/method string regular".to_string(),
+        "d-i partman-auto/choose_recipe select atomic".to_string(),
+        "d-i partman-partitioning/confirm_write_new_label boolean true".to_string(),
+        "d-i partman/choose_partition select finish".to_string(),
+        "d-i partman/confirm boolean true".to_string(),
+        "d-i partman/confirm_nooverwrite boolean true".to_string(),
+        // Ubuntu has no ssh-server task, so the package is named instead
+        "tasksel tasksel/first multiselect standard".to_string(),
+        "d-i pkgsel/include string openssh-server".to_string(),
+        "popularity-contest popularity-contest/participate boolean false".to_string(),
+        "d-i grub-installer/only_debian boolean true".to_string(),
+        "d-i grub-installer/bootdev string default".to_string(),
+        "d-i finish-install/reboot_in_progress note".to_string(),
+    ];
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// Append `data` to an initrd as the file `/name`
+///
+/// The kernel unpacks every archive in the initrd in turn, compressed or
+/// not, so an uncompressed newc cpio can follow the distribution's
+/// compressed one. It has to start on a 4-byte boundary; the zero bytes
+/// padding up to it are skipped.
+fn append_to_initrd(initrd: &Path, name: &str, data: &[u8]) -> Result<()> {
+    let len = fs::metadata(initrd).with_context(|| format!("Failed to read {}", initrd.display()))?.len();
+    let mut archive = vec![0u8; ((4 - len % 4) % 4) as usize];
+    for (name, mode, data) in [(name, 0o100600, data), ("TRAILER!!!", 0, &[][..])] {
+        archive.extend_from_slice(
+            format!(
+                "070701{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}{:08x}",
+                0, mode, 0, 0, 1, 0, data.len(), 0, 0, 0, 0, name.len() + 1, 0
+            )
+            .as_bytes(),
+        );
+        archive.extend_from_slice(name.as_bytes());
+        archive.push(0);
+        archive.resize(archive.len().next_multiple_of(4), 0);
+        archive.extend_from_slice(data);
+        archive.resize(archive.len().next_multiple_of(4), 0);
+    }
+    OpenOptions::new()
+        .append(true)
+        .open(initrd)
+        .and_then(|mut file| file.write_all(&archive))
+        .with_context(|| format!("Failed to add {} to {}", name, initrd.display()))
+}
+
+/// Write the answer file, install media, and installer kernel into the VM directory.
+/// Returns the launch script section to insert with [`add_unattended_to_script`].
+pub fn prepare_unattended(vm_dir: &Path, install_iso: &Path, config: &UnattendedConfig) -> Result<String> {
+    let dir = vm_dir.join(UNATTENDED_DIR);
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    // The answer file has the password hash, so only the owner reads it
+    let password_hash = config.password_hash()?;
+    let answer_path = dir.join(config.kind.file_name());
+    let answer = match config.kind {
+        UnattendedKind::Kickstart => generate_kickstart(config, &password_hash),
+        UnattendedKind::Preseed => generate_preseed(config, &password_hash),
+    };
+    fs::write(&answer_path, &answer)
+        .with_context(|| format!("Failed to write {}", answer_path.display()))?;
+    crate::fs::set_mode(&answer_path, 0o600)
+        .with_context(|| format!("Failed to set permissions on {}", answer_path.display()))?;
+
+    // Copy the installer kernel and initrd out of the ISO
+    let kernel = dir.join("vmlinuz");
+    let initrd = dir.join("initrd");
+    let extracted = config.kind.kernel_candidates().iter().any(|(k, i)| {
+        iso::extract_file(install_iso, k, &kernel).is_ok()
+            && iso::extract_file(install_iso, i, &initrd).is_ok()
+    });
+    if !extracted {
+        bail!(
+            "Could not find the installer kernel in {} (is this a {} installer ISO?)",
+            install_iso.display(),
+            config.kind.label()
+        );
+    }
+
+    let mut args = vec![
+        "-kernel \"$VM_DIR/unattended/vmlinuz\"".to_string(),
+        "-initrd \"$VM_DIR/unattended/initrd\"".to_string(),
+        format!("-append \"{}\"", config.kernel_args()),
+    ];
+    let mut section = format!("{}\n", UNATTENDED_MARKER_START);
+
+    let media = match config.kind {
+        UnattendedKind::Kickstart => {
+            let media = dir.join("oemdrv.iso");
+            iso::build_data_iso(&media, "OEMDRV", &[&answer_path])?;
+            args.push("-drive file=\"$VM_DIR/unattended/oemdrv.iso\",media=cdrom,index=2,readonly=on".to_string());
+            media
+        }
+        UnattendedKind::Preseed => {
+            append_to_initrd(&initrd, "preseed.cfg", answer.as_bytes())?;
+            initrd
+        }
+    };
+    crate::fs::set_mode(&media, 0o600)
+        .with_context(|| format!("Failed to set permissions on {}", media.display()))?;
+
+    section.push_str(&format!("UNATTENDED_ARGS=({})\n", args.join(" ")));
+    section.push_str(UNATTENDED_MARKER_END);
+    section.push('\n');
+    Ok(section)
+}
+
+/// Insert the unattended section and add it to the `--install` QEMU command only
+pub fn add_unattended_to_script(content: &str, section: &str) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let case_line = lines.iter().position(|l| l.trim().starts_with("case "));
+    let install_line = lines.iter().position(|l| l.trim() == "--install)");
+
+    // End of the first QEMU command inside the --install branch
+    let install_cmd_end = install_line.and_then(|start| {
+        let qemu_start = (start..lines.len()).find(|&i| lines[i].trim().starts_with("qemu-system-"))?;
+        (qemu_start..lines.len()).find(|&i| !lines[i].trim_end().ends_with('\\'))
+    });
+
+    let mut result = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        if Some(i) == case_line {
+            result.push_str(section);
+            result.push('\n');
+        }
+        if Some(i) == install_cmd_end {
+            result.push_str(line.trim_end());
+            result.push_str(" \"${UNATTENDED_ARGS[@]}\"");
+        } else {
+            result.push_str(line);
+        }
+        result.push('\n');
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(os: &str) -> UnattendedConfig {
+        let mut cfg = UnattendedConfig::for_profile(os, "retro").unwrap();
+        cfg.username = "alice".to_string();
+        cfg.password = "secret".to_string();
+        cfg
+    }
+
+    #[test]
+    fn test_kind_for_profile() {
+        assert_eq!(UnattendedKind::for_profile("linux-fedora"), Some(UnattendedKind::Kickstart));
+        assert_eq!(UnattendedKind::for_profile("linux-debian"), Some(UnattendedKind::Preseed));
+        assert_eq!(UnattendedKind::for_profile("linux-arch"), None);
+        assert!(UnattendedConfig::for_profile("linux-redhat-7", "h").unwrap().legacy);
+    }
+
+    #[test]
+    fn test_generate_answer_files() {
+        let ks = generate_kickstart(&config("linux-fedora"), "$6$salt$hash");
+        assert!(ks.contains("user --name=alice --groups=wheel --iscrypted --password=$6$salt$hash"));
+        assert!(!ks.contains("secret"));
+        assert!(ks.contains("%end"));
+
+        let legacy = generate_kickstart(&config("linux-redhat-7"), "$1$salt$hash");
+        assert!(legacy.contains("rootpw --iscrypted $1$salt$hash"));
+        assert!(legacy.contains("/usr/sbin/useradd -p '$1$salt$hash' alice"));
+        assert!(legacy.contains("lilo --location mbr"));
+        assert!(!legacy.contains("%end"));
+
+        let preseed = generate_preseed(&config("linux-debian"), "$6$salt$hash");
+        assert!(preseed.contains("d-i passwd/user-password-crypted password $6$salt$hash"));
+        assert!(!preseed.contains("secret"));
+        assert!(preseed.contains("d-i passwd/username string alice"));
+        assert!(preseed.contains("d-i netcfg/get_hostname string retro"));
+        assert!(preseed.contains("d-i mirror/http/hostname string deb.debian.org"));
+
+        let ubuntu = generate_preseed(&config("linux-ubuntu"), "$6$salt$hash");
+        assert!(ubuntu.contains("d-i mirror/http/hostname string archive.ubuntu.com"));
+        assert!(ubuntu.contains("d-i mirror/http/directory string /ubuntu"));
+    }
+
+    #[test]
+    fn test_add_unattended_to_script_only_touches_install() {
+        let script = "VM_DIR=\"/vms/t\"\ncase \"$1\" in\n    --install)\n        qemu-system-x86_64 \\\n            -m 512M\n        ;;\n    \"\")\n        qemu-system-x86_64 \\\n            -m 512M\n        ;;\nesac\n";
+        let section = format!("{}\nUNATTENDED_ARGS=(-kernel k)\n{}\n", UNATTENDED_MARKER_START, UNATTENDED_MARKER_END);
+        let result = add_unattended_to_script(script, &section);
+
+        assert!(result.find(UNATTENDED_MARKER_START).unwrap() < result.find("case ").unwrap());
+        assert_eq!(result.matches("\"${UNATTENDED_ARGS[@]}\"").count(), 1);
+        assert!(result.contains("-m 512M \"${UNATTENDED_ARGS[@]}\"\n        ;;\n    \"\")"));
+    }
+
+    #[test]
+    fn test_validate_names() {
+        assert!(validate_hostname("retro-pc2").is_ok());
+        assert!(validate_hostname(&"a".repeat(63)).is_ok());
+        for bad in ["", "-retro", "retro-", "retro pc", "re\"tro", "$(reboot)", "retro.local", &"a".repeat(64)] {
+            assert!(validate_hostname(bad).is_err(), "{:?}", bad);
+        }
+        assert!(validate_username("alice").is_ok());
+        assert!(validate_username("_svc-1").is_ok());
+        for bad in ["", "Alice", "1alice", "al ice", "a:b", "a#b", &"a".repeat(33)] {
+            assert!(validate_username(bad).is_err(), "{:?}", bad);
+        }
+
+        let mut cfg = config("linux-debian");
+        cfg.hostname = "my \"pc\"".to_string();
+        assert!(cfg.validate().unwrap_err().contains("Hostname"));
+    }
+
+    #[test]
+    fn test_append_to_initrd() {
+        let dir = std::env::temp_dir().join(format!("vm-curator-initrd-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let initrd = dir.join("initrd");
+        fs::write(&initrd, b"gzip").unwrap();
+        append_to_initrd(&initrd, "preseed.cfg", b"d-i x\n").unwrap();
+        let odd = dir.join("odd");
+        fs::write(&odd, b"12345").unwrap();
+        append_to_initrd(&odd, "preseed.cfg", b"d-i x\n").unwrap();
+
+        let data = fs::read(&initrd).unwrap();
+        assert!(data.starts_with(b"gzip070701"));
+        assert_eq!(data.len() % 4, 0);
+        assert!(data.windows(12).any(|w| w == b"preseed.cfg\0"));
+        assert!(data.windows(6).any(|w| w == b"d-i x\n"));
+        assert!(data.windows(10).any(|w| w == b"TRAILER!!!"));
+        // The appended archive starts on a 4-byte boundary
+        assert!(fs::read(&odd).unwrap().starts_with(b"12345\x00\x00\x00070701"));
+
+        // newc header fields: file size, then name size with its NUL
+        let header = String::from_utf8_lossy(&data[4..114]).into_owned();
+        assert_eq!(&header[54..62], "00000006");
+        assert_eq!(&header[94..102], "0000000c");
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
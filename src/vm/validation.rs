@@ -0,0 +1,277 @@
+//! Device and machine validation against the installed QEMU
+//!
+//! Profiles and launch scripts name devices (`sb16`, `AC97`, `qxl-vga`) and
+//! machine types (`pc-q35-9.2`) that a given QEMU build may not have:
+//! distributions compile out legacy ISA devices, and versioned machine types
+//! only exist from the release that introduced them. Rather than let QEMU
+//! fail at launch, the script is checked against the emulator's own
+//! `-device help` and `-machine help` output, and each unsupported option is
+//! reported with the closest alternative the emulator does have.
+
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use crate::commands::qemu_system;
+
+/// Substitutes for devices that are often missing, in order of preference
+const DEVICE_ALTERNATIVES: &[(&str, &[&str])] = &[
+    // Audio
+    ("sb16", &["AC97", "es1370", "intel-hda"]),
+    ("adlib", &["sb16", "AC97"]),
+    ("gus", &["sb16", "AC97"]),
+    ("cs4231a", &["sb16", "AC97"]),
+    ("AC97", &["es1370", "intel-hda"]),
+    ("es1370", &["AC97", "intel-hda"]),
+    ("intel-hda", &["ich9-intel-hda", "AC97"]),
+    ("ich9-intel-hda", &["intel-hda", "AC97"]),
+    // Graphics
+    ("qxl-vga", &["virtio-vga", "VGA"]),
+    ("virtio-vga-gl", &["virtio-vga", "VGA"]),
+    ("virtio-vga", &["VGA"]),
+    ("vmware-svga", &["VGA"]),
+    ("cirrus-vga", &["VGA"]),
+    // Network
+    ("e1000e", &["e1000", "rtl8139"]),
+    ("e1000", &["rtl8139", "virtio-net-pci"]),
+    ("rtl8139", &["e1000", "ne2k_pci"]),
+    ("pcnet", &["rtl8139", "e1000"]),
+    ("ne2k_isa", &["ne2k_pci", "rtl8139"]),
+    ("virtio-net-pci", &["e1000"]),
+    // USB
+    ("qemu-xhci", &["nec-usb-xhci", "ich9-usb-ehci1"]),
+    ("usb-tablet", &["usb-mouse"]),
+];
+
+/// `-vga` types and the device each one instantiates on x86
+const VGA_DEVICES: &[(&str, &str)] = &[
+    ("std", "VGA"),
+    ("cirrus", "cirrus-vga"),
+    ("vmware", "vmware-svga"),
+    ("qxl", "qxl-vga"),
+    ("virtio", "virtio-vga"),
+];
+
+/// Devices and machine types one emulator supports
+#[derive(Debug, Default)]
+pub struct Capabilities {
+    devices: HashSet<String>,
+    machines: HashSet<String>,
+}
+
+/// Capabilities per emulator, queried once per session
+static CAPABILITIES: Lazy<Mutex<HashMap<String, Arc<Capabilities>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// What kind of option was rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueKind {
+    Device,
+    Machine,
+}
+
+/// An option the emulator doesn't support
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+    pub kind: IssueKind,
+    pub name: String,
+    pub emulator: String,
+    /// Closest supported alternative, if there is one
+    pub suggestion: Option<String>,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let what = match self.kind {
+            IssueKind::Device => "device",
+            IssueKind::Machine => "machine type",
+        };
+        write!(f, "{} {} not supported by {}", what, self.name, self.emulator)?;
+        if let Some(ref suggestion) = self.suggestion {
+            write!(f, " (try {})", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+/// Query (or reuse) the capabilities of an emulator
+///
+/// Returns None if the emulator isn't installed or can't be queried.
+pub fn capabilities(emulator: &str) -> Option<Arc<Capabilities>> {
+    let mut cache = CAPABILITIES.lock().ok()?;
+    if let Some(caps) = cache.get(emulator) {
+        return Some(Arc::clone(caps));
+    }
+
+    let caps = Arc::new(Capabilities {
+        devices: qemu_system::get_supported_devices(emulator)?.into_iter().collect(),
+        machines: qemu_system::get_supported_machines(emulator)?.into_iter().collect(),
+    });
+    cache.insert(emulator.to_string(), Arc::clone(&caps));
+    Some(caps)
+}
+
+/// Check a launch script's devices and machine type against an emulator
+///
+/// Options set through shell variables can't be checked and are skipped.
+/// An emulator that can't be queried yields no issues; launching reports
+/// a missing emulator on its own.
+pub fn validate_script(emulator: &str, content: &str) -> Vec<ValidationIssue> {
+    match capabilities(emulator) {
+        Some(caps) => check_script(&caps, emulator, content),
+        None => Vec::new(),
+    }
+}
+
+/// Summarize issues on one line, for status messages and dialogs
+pub fn summarize(issues: &[ValidationIssue]) -> Option<String> {
+    let first = issues.first()?;
+    Some(match issues.len() {
+        1 => first.to_string(),
+        n => format!("{} (+{} more)", first, n - 1),
+    })
+}
+
+fn check_script(caps: &Capabilities, emulator: &str, content: &str) -> Vec<ValidationIssue> {
+    let mut issues: Vec<ValidationIssue> = Vec::new();
+    let is_x86 = emulator.ends_with("x86_64") || emulator.ends_with("i386");
+
+    let mut push = |kind: IssueKind, name: &str, suggestion: Option<String>| {
+        if !issues.iter().any(|i| i.kind == kind && i.name == name) {
+            issues.push(ValidationIssue {
+                kind,
+                name: name.to_string(),
+                emulator: emulator.to_string(),
+                suggestion,
+            });
+        }
+    };
+
+    for (flag, value) in script_options(content) {
+        match flag.as_str() {
+            "-device" if !caps.devices.contains(&value) => {
+                push(IssueKind::Device, &value, suggest_device(caps, &value));
+            }
+            "-vga" if is_x86 => {
+                let missing = VGA_DEVICES
+                    .iter()
+                    .find(|(vga, _)| *vga == value)
+                    .map(|(_, device)| *device)
+                    .filter(|device| !caps.devices.contains(*device));
+                if let Some(device) = missing {
+                    push(IssueKind::Device, device, suggest_device(caps, device));
+                }
+            }
+            "-machine" | "-M" if !caps.machines.contains(&value) => {
+                push(IssueKind::Machine, &value, suggest_machine(caps, &value));
+            }
+            _ => {}
+        }
+    }
+
+    issues
+}
+
+/// `-device`, `-vga` and `-machine` values from a script's QEMU commands
+fn script_options(content: &str) -> Vec<(String, String)> {
+    let mut options = Vec::new();
+
+    for line in content.lines() {
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        let mut tokens = line.split_whitespace().map(|t| t.trim_matches(['"', '\'']));
+        while let Some(token) = tokens.next() {
+            if !matches!(token, "-device" | "-vga" | "-machine" | "-M") {
+                continue;
+            }
+            let Some(value) = tokens.next() else {
+                break;
+            };
+            let value = value.split(',').next().unwrap_or_default();
+            let value = value.strip_prefix("type=").unwrap_or(value);
+            if value.is_empty() || value.contains('$') || value == "help" || value == "none" {
+                continue;
+            }
+            options.push((token.to_string(), value.to_string()));
+        }
+    }
+
+    options
+}
+
+fn suggest_device(caps: &Capabilities, name: &str) -> Option<String> {
+    // Device names are case-sensitive (`-device ac97` fails, `AC97` works)
+    if let Some(exact) = caps.devices.iter().find(|d| d.eq_ignore_ascii_case(name)) {
+        return Some(exact.clone());
+    }
+
+    DEVICE_ALTERNATIVES
+        .iter()
+        .find(|(device, _)| device.eq_ignore_ascii_case(name))
+        .and_then(|(_, alternatives)| alternatives.iter().find(|a| caps.devices.contains(**a)))
+        .map(|a| a.to_string())
+}
+
+fn suggest_machine(caps: &Capabilities, name: &str) -> Option<String> {
+    // Versioned machine types fall back to the unversioned alias
+    // (pc-q35-9.2 -> q35, pc-i440fx-9.2 -> pc, virt-9.2 -> virt)
+    let family = if name.starts_with("pc-q35") {
+        Some("q35")
+    } else if name.starts_with("pc-i440fx") || name == "isapc" {
+        Some("pc")
+    } else {
+        name.rsplit_once('-').map(|(base, _)| base)
+    };
+
+    family
+        .filter(|f| caps.machines.contains(*f))
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn caps(devices: &[&str], machines: &[&str]) -> Capabilities {
+        Capabilities {
+            devices: devices.iter().map(|d| d.to_string()).collect(),
+            machines: machines.iter().map(|m| m.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_check_script_suggests_alternatives() {
+        let caps = caps(&["AC97", "VGA", "e1000", "usb-tablet"], &["pc", "q35", "pc-q35-8.2"]);
+        let script = "# -device gus is only a comment\n\
+            qemu-system-x86_64 -machine pc-q35-9.2,accel=kvm -vga cirrus \\\n\
+                -device sb16,audiodev=audio0 -device ac97 -device e1000,netdev=net0 \\\n\
+                -device \"$EXTRA_DEVICE\" -device usb-tablet\n";
+
+        let issues = check_script(&caps, "qemu-system-x86_64", script);
+        let found: Vec<(&str, Option<&str>)> = issues
+            .iter()
+            .map(|i| (i.name.as_str(), i.suggestion.as_deref()))
+            .collect();
+
+        assert_eq!(
+            found,
+            vec![
+                ("pc-q35-9.2", Some("q35")),
+                ("cirrus-vga", Some("VGA")),
+                ("sb16", Some("AC97")),
+                ("ac97", Some("AC97")),
+            ]
+        );
+        assert_eq!(
+            issues[2].to_string(),
+            "device sb16 not supported by qemu-system-x86_64 (try AC97)"
+        );
+        assert_eq!(
+            summarize(&issues).unwrap(),
+            "machine type pc-q35-9.2 not supported by qemu-system-x86_64 (try q35) (+3 more)"
+        );
+    }
+}
@@ -0,0 +1,170 @@
+//! VNC display support
+//!
+//! A VM set to the VNC display runs headless with QEMU's VNC server bound
+//! to localhost. Each VM gets its own VNC display number (port 5900 + N),
+//! recorded in a managed section of launch.sh so the address stays stable
+//! between launches and never collides with another VM in the library.
+
+use anyhow::{bail, Context, Result};
+use std::net::TcpListener;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::vm::lifecycle::{insert_args_section, remove_args_section};
+
+// VNC section markers
+const VNC_MARKER_START: &str = "# >>> VNC (managed by vm-curator) >>>";
+const VNC_MARKER_END: &str = "# <<< VNC <<<";
+
+/// Address the VNC server listens on
+const VNC_HOST: &str = "127.0.0.1";
+
+/// TCP port of VNC display :0
+const VNC_BASE_PORT: u16 = 5900;
+
+/// Highest display number handed out
+const MAX_VNC_DISPLAY: u16 = 99;
+
+/// Clients tried, in order, when no client is configured
+const KNOWN_CLIENTS: &[&str] = &["vncviewer", "gvncviewer", "remmina", "krdc", "vinagre"];
+
+/// TCP port for a VNC display number
+pub fn port(display: u16) -> u16 {
+    VNC_BASE_PORT + display
+}
+
+/// "host:port" address for a VNC display number
+pub fn address(display: u16) -> String {
+    format!("{}:{}", VNC_HOST, port(display))
+}
+
+/// Pick the lowest display number not used by another VM and not bound on the host
+pub fn allocate_display(used: &[u16]) -> Result<u16> {
+    (1..=MAX_VNC_DISPLAY)
+        .find(|n| !used.contains(n) && TcpListener::bind((VNC_HOST, port(*n))).is_ok())
+        .context("No free VNC display available")
+}
+
+/// Write (or with None, remove) the VNC section of a launch script
+pub fn set_vnc(script_path: &Path, display: Option<u16>) -> Result<()> {
+    let content = std::fs::read_to_string(script_path).context("Failed to read launch.sh")?;
+    let content = remove_vnc_section(&content);
+
+    let new_content = match display {
+        Some(display) => insert_args_section(&content, &generate_vnc_section(display), "$VNC_ARGS"),
+        None => content,
+    };
+
+    std::fs::write(script_path, new_content).context("Failed to write launch.sh")?;
+    Ok(())
+}
+
+/// Read the VNC display number from a launch script
+pub fn parse_vnc_display(content: &str) -> Option<u16> {
+    let mut in_section = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed == VNC_MARKER_START {
+            in_section = true;
+        } else if trimmed == VNC_MARKER_END {
+            return None;
+        } else if in_section {
+            if let Some(value) = trimmed.strip_prefix("VNC_DISPLAY=") {
+                return value.trim_matches('"').parse().ok();
+            }
+        }
+    }
+    None
+}
+
+/// Start a VNC client connected to a display
+///
+/// `client` is a command line from the settings; `{addr}` in it is replaced
+/// with the server address, otherwise the address is appended. With no
+/// client configured, the first known client found in PATH is used.
+/// Returns the name of the program that was started.
+pub fn launch_client(client: Option<&str>, display: u16) -> Result<String> {
+    let addr = address(display);
+
+    let (program, args) = match client.map(str::trim).filter(|c| !c.is_empty()) {
+        Some(command) => {
+            let mut parts = command.split_whitespace();
+            let program = parts.next().unwrap_or_default().to_string();
+            let mut args: Vec<String> = parts.map(|p| p.replace("{addr}", &addr)).collect();
+            if !command.contains("{addr}") {
+                args.push(addr.clone());
+            }
+            (program, args)
+        }
+        None => {
+            let Some(program) = KNOWN_CLIENTS.iter().find(|c| is_in_path(c)) else {
+                bail!("No VNC client found (install TigerVNC, Remmina, or set one in Settings)");
+            };
+            let arg = match *program {
+                // These take a URI rather than host:port
+                "remmina" | "krdc" | "vinagre" => format!("vnc://{}", addr),
+                _ => addr.clone(),
+            };
+            (program.to_string(), vec![arg])
+        }
+    };
+
+    Command::new(&program)
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to start {}", program))?;
+
+    Ok(program)
+}
+
+fn is_in_path(program: &str) -> bool {
+    Command::new("which")
+        .arg(program)
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+fn generate_vnc_section(display: u16) -> String {
+    let mut section = String::new();
+    section.push_str(VNC_MARKER_START);
+    section.push('\n');
+    section.push_str(&format!("VNC_DISPLAY=\"{}\"\n", display));
+    section.push_str(&format!("VNC_ARGS=\"-vnc {}:${{VNC_DISPLAY}}\"\n", VNC_HOST));
+    section.push_str(VNC_MARKER_END);
+    section.push('\n');
+    section
+}
+
+fn remove_vnc_section(content: &str) -> String {
+    remove_args_section(content, VNC_MARKER_START, VNC_MARKER_END, "$VNC_ARGS")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vnc_section_round_trip() {
+        let script = "VM_DIR=\"/vms/t\"\ncase \"$1\" in\n    \"\")\n        qemu-system-x86_64 \\\n            -display none\n        ;;\nesac\n";
+        let with_vnc = insert_args_section(script, &generate_vnc_section(3), "$VNC_ARGS");
+
+        assert_eq!(parse_vnc_display(&with_vnc), Some(3));
+        assert!(with_vnc.contains("VNC_ARGS=\"-vnc 127.0.0.1:${VNC_DISPLAY}\""));
+        assert!(with_vnc.contains("-display none $VNC_ARGS"));
+        assert_eq!(address(3), "127.0.0.1:5903");
+
+        let restored = remove_vnc_section(&with_vnc);
+        assert_eq!(parse_vnc_display(&restored), None);
+        assert!(!restored.contains("VNC"));
+    }
+
+    #[test]
+    fn test_allocate_display_skips_used() {
+        let display = allocate_display(&[1, 2]).unwrap();
+        assert!(display >= 3);
+    }
+}